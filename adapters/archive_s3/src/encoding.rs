@@ -0,0 +1,224 @@
+//! Parquet encoding of archived nodes and edge versions
+//!
+//! IDs and labels are strings, properties are JSON text, and times are UTC
+//! timestamps in microseconds, so the files can be read by any Parquet tool.
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::TimestampMicrosecondType;
+use arrow_array::{Array, ArrayRef, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+
+fn timestamp(name: &str, nullable: bool) -> Field {
+    Field::new(name, DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), nullable)
+}
+
+fn node_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("label", DataType::Utf8, false),
+        Field::new("id_alias", DataType::Utf8, true),
+        Field::new("alias_namespace", DataType::Utf8, true),
+        Field::new("props", DataType::Utf8, false),
+        timestamp("deleted_at", false),
+    ]))
+}
+
+fn edge_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("from_node_id", DataType::Utf8, false),
+        Field::new("to_node_id", DataType::Utf8, false),
+        Field::new("kind", DataType::Utf8, false),
+        timestamp("valid_from", false),
+        timestamp("valid_to", true),
+        timestamp("transaction_start_time", false),
+        timestamp("transaction_end_time", true),
+        Field::new("props", DataType::Utf8, false),
+    ]))
+}
+
+fn strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+fn times(values: impl Iterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+    let micros = values.map(|t| t.map(|t| t.timestamp_micros())).collect::<TimestampMicrosecondArray>();
+    Arc::new(micros.with_timezone("UTC"))
+}
+
+fn json(value: &serde_json::Value) -> Result<String, ArchiveError> {
+    serde_json::to_string(value).map_err(|e| ArchiveError::Encoding(format!("Failed to encode properties: {}", e)))
+}
+
+/// Encode deleted nodes as a Parquet file
+pub fn encode_nodes(nodes: &[ArchivedNode]) -> Result<Bytes, ArchiveError> {
+    let ids: Vec<String> = nodes.iter().map(|n| n.id.to_string()).collect();
+    let props = nodes.iter().map(|n| json(&n.node.props)).collect::<Result<Vec<_>, _>>()?;
+
+    write(node_schema(), vec![
+        strings(ids.iter().map(|id| Some(id.as_str()))),
+        strings(nodes.iter().map(|n| Some(n.node.label.as_str()))),
+        strings(nodes.iter().map(|n| n.node.id_alias.as_deref())),
+        strings(nodes.iter().map(|n| n.node.alias_namespace.as_deref())),
+        strings(props.iter().map(|p| Some(p.as_str()))),
+        times(nodes.iter().map(|n| Some(n.deleted_at))),
+    ])
+}
+
+/// Encode closed edge versions as a Parquet file
+pub fn encode_edges(edges: &[EdgeRecord]) -> Result<Bytes, ArchiveError> {
+    let ids: Vec<[String; 3]> = edges.iter()
+        .map(|e| [e.id.to_string(), e.edge.from_node_id.to_string(), e.edge.to_node_id.to_string()])
+        .collect();
+    let props = edges.iter().map(|e| json(&e.edge.props)).collect::<Result<Vec<_>, _>>()?;
+
+    write(edge_schema(), vec![
+        strings(ids.iter().map(|ids| Some(ids[0].as_str()))),
+        strings(ids.iter().map(|ids| Some(ids[1].as_str()))),
+        strings(ids.iter().map(|ids| Some(ids[2].as_str()))),
+        strings(edges.iter().map(|e| Some(e.edge.kind.as_str()))),
+        times(edges.iter().map(|e| Some(e.edge.valid_from))),
+        times(edges.iter().map(|e| e.edge.valid_to)),
+        times(edges.iter().map(|e| Some(e.edge.transaction_start_time))),
+        times(edges.iter().map(|e| e.edge.transaction_end_time)),
+        strings(props.iter().map(|p| Some(p.as_str()))),
+    ])
+}
+
+fn write(schema: SchemaRef, columns: Vec<ArrayRef>) -> Result<Bytes, ArchiveError> {
+    let batch = RecordBatch::try_new(schema.clone(), columns)
+        .map_err(|e| ArchiveError::Encoding(format!("Failed to build record batch: {}", e)))?;
+
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut buffer = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buffer, schema, Some(properties))
+        .map_err(|e| ArchiveError::Encoding(format!("Failed to create Parquet writer: {}", e)))?;
+    writer.write(&batch)
+        .map_err(|e| ArchiveError::Encoding(format!("Failed to write Parquet: {}", e)))?;
+    writer.close()
+        .map_err(|e| ArchiveError::Encoding(format!("Failed to finish Parquet file: {}", e)))?;
+
+    Ok(Bytes::from(buffer))
+}
+
+/// Decode deleted nodes from a Parquet file
+pub fn decode_nodes(data: Bytes) -> Result<Vec<ArchivedNode>, ArchiveError> {
+    let mut nodes = Vec::new();
+    for batch in read(data)? {
+        let columns = Columns(&batch);
+        let (ids, labels, aliases, namespaces, props) = (
+            columns.strings("id")?,
+            columns.strings("label")?,
+            columns.strings("id_alias")?,
+            columns.strings("alias_namespace")?,
+            columns.strings("props")?,
+        );
+        let deleted_at = columns.times("deleted_at")?;
+
+        for row in 0..batch.num_rows() {
+            nodes.push(ArchivedNode {
+                id: uuid(ids.value(row))?,
+                node: Node {
+                    id_alias: aliases.is_valid(row).then(|| aliases.value(row).to_string()),
+                    alias_namespace: namespaces.is_valid(row).then(|| namespaces.value(row).to_string()),
+                    label: labels.value(row).to_string(),
+                    props: parse_json(props.value(row))?,
+                },
+                deleted_at: time(deleted_at.value(row))?,
+            });
+        }
+    }
+    Ok(nodes)
+}
+
+/// Decode closed edge versions from a Parquet file
+pub fn decode_edges(data: Bytes) -> Result<Vec<EdgeRecord>, ArchiveError> {
+    let mut edges = Vec::new();
+    for batch in read(data)? {
+        let columns = Columns(&batch);
+        let (ids, from, to, kinds, props) = (
+            columns.strings("id")?,
+            columns.strings("from_node_id")?,
+            columns.strings("to_node_id")?,
+            columns.strings("kind")?,
+            columns.strings("props")?,
+        );
+        let (valid_from, valid_to, started, ended) = (
+            columns.times("valid_from")?,
+            columns.times("valid_to")?,
+            columns.times("transaction_start_time")?,
+            columns.times("transaction_end_time")?,
+        );
+        let optional = |times: &TimestampMicrosecondArray, row: usize| {
+            times.is_valid(row).then(|| time(times.value(row))).transpose()
+        };
+
+        for row in 0..batch.num_rows() {
+            edges.push(EdgeRecord {
+                id: uuid(ids.value(row))?,
+                edge: TimeEdge {
+                    from_node_id: uuid(from.value(row))?,
+                    to_node_id: uuid(to.value(row))?,
+                    kind: kinds.value(row).to_string(),
+                    valid_from: time(valid_from.value(row))?,
+                    valid_to: optional(valid_to, row)?,
+                    transaction_start_time: time(started.value(row))?,
+                    transaction_end_time: optional(ended, row)?,
+                    // Archived versions are closed, so their expiry no longer matters
+                    expires_at: None,
+                    props: parse_json(props.value(row))?,
+                },
+            });
+        }
+    }
+    Ok(edges)
+}
+
+fn read(data: Bytes) -> Result<Vec<RecordBatch>, ArchiveError> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(data)
+        .and_then(|builder| builder.build())
+        .map_err(|e| ArchiveError::Corrupt(format!("Failed to open Parquet file: {}", e)))?;
+    reader.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ArchiveError::Corrupt(format!("Failed to read Parquet file: {}", e)))
+}
+
+/// Typed access to the columns of a record batch
+struct Columns<'a>(&'a RecordBatch);
+
+impl<'a> Columns<'a> {
+    fn column(&self, name: &str) -> Result<&'a ArrayRef, ArchiveError> {
+        self.0.column_by_name(name)
+            .ok_or_else(|| ArchiveError::Corrupt(format!("Missing column '{}'", name)))
+    }
+
+    fn strings(&self, name: &str) -> Result<&'a StringArray, ArchiveError> {
+        self.column(name)?.as_string_opt::<i32>()
+            .ok_or_else(|| ArchiveError::Corrupt(format!("Column '{}' is not a string column", name)))
+    }
+
+    fn times(&self, name: &str) -> Result<&'a TimestampMicrosecondArray, ArchiveError> {
+        self.column(name)?.as_primitive_opt::<TimestampMicrosecondType>()
+            .ok_or_else(|| ArchiveError::Corrupt(format!("Column '{}' is not a timestamp column", name)))
+    }
+}
+
+fn uuid(value: &str) -> Result<Uuid, ArchiveError> {
+    Uuid::parse_str(value).map_err(|e| ArchiveError::Corrupt(format!("Invalid UUID '{}': {}", value, e)))
+}
+
+fn time(micros: i64) -> Result<DateTime<Utc>, ArchiveError> {
+    DateTime::from_timestamp_micros(micros)
+        .ok_or_else(|| ArchiveError::Corrupt(format!("Timestamp {} is out of range", micros)))
+}
+
+fn parse_json(value: &str) -> Result<serde_json::Value, ArchiveError> {
+    serde_json::from_str(value).map_err(|e| ArchiveError::Corrupt(format!("Invalid properties: {}", e)))
+}
@@ -0,0 +1,259 @@
+//! S3 archive sink for TelaMentis closed graph history
+//!
+//! Each segment is written as Parquet files under the tenant's prefix, and
+//! the tenant's segments are cataloged in a JSON manifest next to them:
+//!
+//! ```text
+//! {prefix}/{tenant}/manifest.json
+//! {prefix}/{tenant}/segments/{segment_id}/nodes.parquet
+//! {prefix}/{tenant}/segments/{segment_id}/edges.parquet
+//! ```
+
+use async_trait::async_trait;
+use chrono::Utc;
+use object_store::aws::AmazonS3Builder;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+mod encoding;
+
+/// Object holding a segment's deleted nodes
+const NODES_OBJECT: &str = "nodes.parquet";
+
+/// Object holding a segment's edge versions
+const EDGES_OBJECT: &str = "edges.parquet";
+
+/// Configuration for the S3 archive sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3ArchiveConfig {
+    /// Bucket the archive is written to
+    pub bucket: String,
+    /// AWS region; taken from the environment when unset
+    pub region: Option<String>,
+    /// Endpoint of an S3-compatible service such as MinIO
+    pub endpoint: Option<String>,
+    /// Key prefix under which each tenant's archive is kept
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// Whether plain HTTP endpoints are allowed
+    #[serde(default)]
+    pub allow_http: bool,
+}
+
+fn default_prefix() -> String {
+    "telamentis/archive".to_string()
+}
+
+impl S3ArchiveConfig {
+    /// Create a configuration for a bucket, with other settings from the environment
+    pub fn new(bucket: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            region: None,
+            endpoint: None,
+            prefix: default_prefix(),
+            allow_http: false,
+        }
+    }
+}
+
+/// Archive sink writing Parquet segments to S3 or any other object store
+pub struct S3ArchiveSink {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    /// Serializes manifest updates made through this sink; segments of a
+    /// tenant should be written by one process at a time
+    manifest_lock: Mutex<()>,
+}
+
+impl S3ArchiveSink {
+    /// Create a sink for an S3 bucket. Credentials are read from the
+    /// standard `AWS_*` environment variables.
+    pub fn new(config: S3ArchiveConfig) -> Result<Self, ArchiveError> {
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(&config.bucket)
+            .with_allow_http(config.allow_http);
+        if let Some(region) = &config.region {
+            builder = builder.with_region(region);
+        }
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+
+        let store = builder.build()
+            .map_err(|e| ArchiveError::Storage(format!("Failed to configure S3 bucket '{}': {}", config.bucket, e)))?;
+
+        info!("Archiving to s3://{}/{}", config.bucket, config.prefix);
+        Ok(Self::with_store(Arc::new(store), &config.prefix))
+    }
+
+    /// Create a sink over an existing object store
+    pub fn with_store(store: Arc<dyn ObjectStore>, prefix: &str) -> Self {
+        Self {
+            store,
+            prefix: Path::from(prefix),
+            manifest_lock: Mutex::new(()),
+        }
+    }
+
+    fn tenant_path(&self, tenant: &TenantId) -> Path {
+        self.prefix.child(tenant.as_str())
+    }
+
+    /// Path of an object named in a manifest, relative to the tenant's archive
+    fn object_path(&self, tenant: &TenantId, object: &str) -> Path {
+        object.split('/').fold(self.tenant_path(tenant), |path, part| path.child(part))
+    }
+
+    async fn put(&self, path: &Path, data: bytes::Bytes) -> Result<(), ArchiveError> {
+        self.store.put(path, PutPayload::from(data)).await
+            .map_err(|e| ArchiveError::Storage(format!("Failed to write {}: {}", path, e)))?;
+        Ok(())
+    }
+
+    async fn get(&self, path: &Path) -> Result<bytes::Bytes, ArchiveError> {
+        let result = self.store.get(path).await
+            .map_err(|e| ArchiveError::Storage(format!("Failed to read {}: {}", path, e)))?;
+        result.bytes().await
+            .map_err(|e| ArchiveError::Storage(format!("Failed to read {}: {}", path, e)))
+    }
+
+    async fn read_manifest(&self, tenant: &TenantId) -> Result<ArchiveManifest, ArchiveError> {
+        let path = self.tenant_path(tenant).child("manifest.json");
+        match self.store.get(&path).await {
+            Ok(result) => {
+                let data = result.bytes().await
+                    .map_err(|e| ArchiveError::Storage(format!("Failed to read {}: {}", path, e)))?;
+                serde_json::from_slice(&data)
+                    .map_err(|e| ArchiveError::Corrupt(format!("Invalid manifest {}: {}", path, e)))
+            }
+            Err(object_store::Error::NotFound { .. }) => Ok(ArchiveManifest::default()),
+            Err(e) => Err(ArchiveError::Storage(format!("Failed to read {}: {}", path, e))),
+        }
+    }
+}
+
+#[async_trait]
+impl ArchiveSink for S3ArchiveSink {
+    async fn write_segment(&self, tenant: &TenantId, batch: &ArchiveBatch) -> Result<ArchiveSegment, ArchiveError> {
+        let (from, to) = batch.time_range()
+            .ok_or_else(|| ArchiveError::Encoding("Cannot archive an empty batch".to_string()))?;
+        let id = Uuid::new_v4();
+
+        // Data objects go first, so a manifest never lists a missing object
+        let mut objects = Vec::new();
+        if !batch.nodes.is_empty() {
+            objects.push((format!("segments/{}/{}", id, NODES_OBJECT), encoding::encode_nodes(&batch.nodes)?));
+        }
+        if !batch.edges.is_empty() {
+            objects.push((format!("segments/{}/{}", id, EDGES_OBJECT), encoding::encode_edges(&batch.edges)?));
+        }
+        for (object, data) in &objects {
+            self.put(&self.object_path(tenant, object), data.clone()).await?;
+        }
+
+        let segment = ArchiveSegment {
+            id,
+            from,
+            to,
+            node_count: batch.nodes.len() as u64,
+            edge_count: batch.edges.len() as u64,
+            objects: objects.into_iter().map(|(object, _)| object).collect(),
+            created_at: Utc::now(),
+        };
+
+        let _guard = self.manifest_lock.lock().await;
+        let mut manifest = self.read_manifest(tenant).await?;
+        manifest.segments.push(segment.clone());
+        let data = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| ArchiveError::Encoding(format!("Failed to encode manifest: {}", e)))?;
+        self.put(&self.tenant_path(tenant).child("manifest.json"), data.into()).await?;
+
+        debug!("Wrote archive segment {} for tenant {}", id, tenant);
+        Ok(segment)
+    }
+
+    async fn manifest(&self, tenant: &TenantId) -> Result<ArchiveManifest, ArchiveError> {
+        self.read_manifest(tenant).await
+    }
+
+    async fn read_segment(&self, tenant: &TenantId, segment: &ArchiveSegment) -> Result<ArchiveBatch, ArchiveError> {
+        let mut batch = ArchiveBatch::default();
+        for object in &segment.objects {
+            let data = self.get(&self.object_path(tenant, object)).await?;
+            if object.ends_with(NODES_OBJECT) {
+                batch.nodes.extend(encoding::decode_nodes(data)?);
+            } else if object.ends_with(EDGES_OBJECT) {
+                batch.edges.extend(encoding::decode_edges(data)?);
+            } else {
+                return Err(ArchiveError::Corrupt(format!("Unknown object '{}' in segment {}", object, segment.id)));
+            }
+        }
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+    use serde_json::json;
+
+    fn sink() -> S3ArchiveSink {
+        S3ArchiveSink::with_store(Arc::new(InMemory::new()), "archive")
+    }
+
+    fn history() -> ArchiveBatch {
+        let ended: DateTime<Utc> = "2024-06-01T12:00:00.123456Z".parse().unwrap();
+        let mut edge = TimeEdge::new(Uuid::new_v4(), Uuid::new_v4(), "WORKS_FOR", "2020-01-01T00:00:00Z".parse().unwrap(), json!({"role": "Engineer"}));
+        edge.transaction_start_time = "2024-01-01T00:00:00Z".parse().unwrap();
+        edge.transaction_end_time = Some(ended);
+
+        ArchiveBatch {
+            nodes: vec![ArchivedNode {
+                id: Uuid::new_v4(),
+                node: Node::new("Person").with_id_alias("alice").with_property("age", json!(30)),
+                deleted_at: "2024-05-01T00:00:00Z".parse().unwrap(),
+            }],
+            edges: vec![EdgeRecord { id: Uuid::new_v4(), edge }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_segment_round_trip() {
+        let sink = sink();
+        let tenant = TenantId::new("tenant/one");
+        let batch = history();
+
+        let segment = sink.write_segment(&tenant, &batch).await.unwrap();
+        assert_eq!((segment.node_count, segment.edge_count), (1, 1));
+        assert_eq!(segment.from, batch.nodes[0].deleted_at);
+        assert_eq!(segment.objects.len(), 2);
+
+        // Every field, including microsecond times, survives the round trip
+        let read = sink.read_segment(&tenant, &segment).await.unwrap();
+        assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value(&batch).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_manifest() {
+        let sink = sink();
+        let tenant = TenantId::new("tenant");
+        assert!(sink.manifest(&tenant).await.unwrap().segments.is_empty());
+
+        let first = sink.write_segment(&tenant, &history()).await.unwrap();
+        let edges_only = ArchiveBatch { nodes: Vec::new(), ..history() };
+        let second = sink.write_segment(&tenant, &edges_only).await.unwrap();
+        assert_eq!(second.objects, vec![format!("segments/{}/edges.parquet", second.id)]);
+
+        let manifest = sink.manifest(&tenant).await.unwrap();
+        assert_eq!(manifest.segments, vec![first, second]);
+        assert!(sink.manifest(&TenantId::new("other")).await.unwrap().segments.is_empty());
+        assert!(sink.write_segment(&tenant, &ArchiveBatch::default()).await.is_err());
+    }
+}
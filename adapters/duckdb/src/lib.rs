@@ -0,0 +1,359 @@
+//! DuckDB analytics engine for TelaMentis
+//!
+//! Each tenant gets its own DuckDB database, in memory or in
+//! `{dir}/{tenant}.duckdb`, holding `nodes` and `edges` tables loaded from a
+//! snapshot. Queries cannot reach another tenant's data or the file system:
+//! external access is disabled and the configuration locked when a database
+//! is opened, and every query runs in a transaction that is rolled back.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use duckdb::types::{TimeUnit, Value};
+use duckdb::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use telamentis_core::analytics::AnalyticsResult;
+use telamentis_core::prelude::*;
+use tracing::{debug, info};
+
+const CREATE_TABLES: &str = "
+    CREATE OR REPLACE TABLE nodes (
+        id VARCHAR NOT NULL,
+        label VARCHAR NOT NULL,
+        id_alias VARCHAR,
+        alias_namespace VARCHAR,
+        props VARCHAR NOT NULL
+    );
+    CREATE OR REPLACE TABLE edges (
+        id VARCHAR NOT NULL,
+        from_node_id VARCHAR NOT NULL,
+        to_node_id VARCHAR NOT NULL,
+        kind VARCHAR NOT NULL,
+        valid_from TIMESTAMPTZ NOT NULL,
+        valid_to TIMESTAMPTZ,
+        transaction_start_time TIMESTAMPTZ NOT NULL,
+        transaction_end_time TIMESTAMPTZ,
+        props VARCHAR NOT NULL
+    );
+";
+
+/// Configuration for the DuckDB analytics engine
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuckDbConfig {
+    /// Directory holding one database file per tenant; in memory when unset
+    pub dir: Option<PathBuf>,
+    /// Threads each database may use; DuckDB's default when unset
+    pub threads: Option<usize>,
+    /// Memory limit of each database, such as "2GB"; DuckDB's default when unset
+    pub memory_limit: Option<String>,
+}
+
+struct TenantDatabase {
+    connection: Arc<Mutex<Connection>>,
+    loaded_at: Option<DateTime<Utc>>,
+}
+
+/// Analytics engine keeping each tenant's graph in its own DuckDB database
+pub struct DuckDbAnalytics {
+    config: DuckDbConfig,
+    tenants: Mutex<HashMap<TenantId, TenantDatabase>>,
+}
+
+impl DuckDbAnalytics {
+    /// Create an engine; databases are opened as tenants are loaded
+    pub fn new(config: DuckDbConfig) -> Result<Self, AnalyticsError> {
+        if let Some(dir) = &config.dir {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| AnalyticsError::Engine(format!("Failed to create {}: {}", dir.display(), e)))?;
+            info!("Keeping analytics databases in {}", dir.display());
+        }
+        Ok(Self {
+            config,
+            tenants: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Open a database for a tenant, with settings locked against queries
+    fn open(&self, tenant: &TenantId) -> Result<Connection, AnalyticsError> {
+        let connection = match &self.config.dir {
+            Some(dir) => Connection::open(dir.join(format!("{}.duckdb", file_name(tenant)))),
+            None => Connection::open_in_memory(),
+        }.map_err(engine_error)?;
+
+        let mut settings = String::new();
+        if let Some(threads) = self.config.threads {
+            settings.push_str(&format!("SET threads = {};", threads));
+        }
+        if let Some(memory_limit) = &self.config.memory_limit {
+            settings.push_str(&format!("SET memory_limit = '{}';", memory_limit.replace('\'', "''")));
+        }
+        settings.push_str("SET enable_external_access = false; SET lock_configuration = true;");
+        connection.execute_batch(&settings).map_err(engine_error)?;
+        Ok(connection)
+    }
+
+    /// Connection of a tenant's database, opening it if `create` is set
+    fn connection(&self, tenant: &TenantId, create: bool) -> Result<Option<Arc<Mutex<Connection>>>, AnalyticsError> {
+        let mut tenants = self.tenants.lock().unwrap();
+        if let Some(database) = tenants.get(tenant) {
+            return Ok(Some(database.connection.clone()));
+        }
+        if !create {
+            return Ok(None);
+        }
+        tenant.validate().map_err(|e| AnalyticsError::InvalidQuery(format!("Invalid tenant ID '{}': {}", tenant, e)))?;
+
+        let connection = Arc::new(Mutex::new(self.open(tenant)?));
+        tenants.insert(tenant.clone(), TenantDatabase { connection: connection.clone(), loaded_at: None });
+        Ok(Some(connection))
+    }
+}
+
+/// Run blocking DuckDB work off the async runtime
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, AnalyticsError> + Send + 'static,
+) -> Result<T, AnalyticsError> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| AnalyticsError::Engine(format!("Analytics task failed: {}", e)))?
+}
+
+fn engine_error(error: duckdb::Error) -> AnalyticsError {
+    AnalyticsError::Engine(error.to_string())
+}
+
+/// Tenant ID made safe for a file name; distinct IDs give distinct names
+fn file_name(tenant: &TenantId) -> String {
+    tenant.as_str().bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn json(value: &serde_json::Value) -> Result<String, AnalyticsError> {
+    serde_json::to_string(value).map_err(|e| AnalyticsError::Engine(format!("Failed to encode properties: {}", e)))
+}
+
+fn load_snapshot(connection: &mut Connection, snapshot: &GraphSnapshot) -> Result<(), AnalyticsError> {
+    let transaction = connection.transaction().map_err(engine_error)?;
+    transaction.execute_batch(CREATE_TABLES).map_err(engine_error)?;
+
+    {
+        let mut nodes = transaction.appender("nodes").map_err(engine_error)?;
+        for record in &snapshot.nodes {
+            let node = &record.node;
+            nodes.append_row(params![
+                record.id.to_string(),
+                node.label,
+                node.id_alias,
+                node.alias_namespace,
+                json(&node.props)?,
+            ]).map_err(engine_error)?;
+        }
+
+        let mut edges = transaction.appender("edges").map_err(engine_error)?;
+        for record in &snapshot.edges {
+            let edge = &record.edge;
+            edges.append_row(params![
+                record.id.to_string(),
+                edge.from_node_id.to_string(),
+                edge.to_node_id.to_string(),
+                edge.kind,
+                edge.valid_from,
+                edge.valid_to,
+                edge.transaction_start_time,
+                edge.transaction_end_time,
+                json(&edge.props)?,
+            ]).map_err(engine_error)?;
+        }
+    }
+
+    transaction.commit().map_err(engine_error)
+}
+
+fn run_query(connection: &mut Connection, sql: &str, max_rows: usize) -> Result<AnalyticsResult, AnalyticsError> {
+    // Rolled back when dropped, so a query that gets past the read-only
+    // check still cannot change the tables
+    let transaction = connection.transaction().map_err(engine_error)?;
+    let mut statement = transaction.prepare(sql)
+        .map_err(|e| AnalyticsError::InvalidQuery(e.to_string()))?;
+
+    let mut result = AnalyticsResult::default();
+    {
+        let mut rows = statement.query([]).map_err(|e| AnalyticsError::InvalidQuery(e.to_string()))?;
+        while let Some(row) = rows.next().map_err(engine_error)? {
+            if result.rows.len() == max_rows {
+                result.truncated = true;
+                break;
+            }
+            let values = (0..row.as_ref().column_count())
+                .map(|i| row.get::<_, Value>(i).map(to_json))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(engine_error)?;
+            result.rows.push(values);
+        }
+    }
+    result.columns = statement.column_names();
+    Ok(result)
+}
+
+fn micros(unit: TimeUnit, value: i64) -> i64 {
+    match unit {
+        TimeUnit::Second => value * 1_000_000,
+        TimeUnit::Millisecond => value * 1_000,
+        TimeUnit::Microsecond => value,
+        TimeUnit::Nanosecond => value / 1_000,
+    }
+}
+
+/// JSON form of a DuckDB value; times are RFC 3339 strings
+fn to_json(value: Value) -> serde_json::Value {
+    use serde_json::json;
+
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Boolean(v) => json!(v),
+        Value::TinyInt(v) => json!(v),
+        Value::SmallInt(v) => json!(v),
+        Value::Int(v) => json!(v),
+        Value::BigInt(v) => json!(v),
+        Value::UTinyInt(v) => json!(v),
+        Value::USmallInt(v) => json!(v),
+        Value::UInt(v) => json!(v),
+        Value::UBigInt(v) => json!(v),
+        // Beyond what JSON numbers hold exactly
+        Value::HugeInt(v) => json!(v.to_string()),
+        Value::Float(v) => json!(v),
+        Value::Double(v) => json!(v),
+        Value::Decimal(v) => json!(v.to_string()),
+        Value::Text(v) | Value::Enum(v) => json!(v),
+        Value::Timestamp(unit, v) => DateTime::<Utc>::from_timestamp_micros(micros(unit, v))
+            .map(|t| json!(t.to_rfc3339()))
+            .unwrap_or(serde_json::Value::Null),
+        Value::Date32(days) => chrono::NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(days.into())))
+            .map(|date| json!(date.to_string()))
+            .unwrap_or(serde_json::Value::Null),
+        Value::List(values) | Value::Array(values) => values.into_iter().map(to_json).collect(),
+        other => json!(format!("{:?}", other)),
+    }
+}
+
+#[async_trait]
+impl AnalyticsEngine for DuckDbAnalytics {
+    async fn load(&self, tenant: &TenantId, snapshot: &GraphSnapshot) -> Result<(), AnalyticsError> {
+        let connection = self.connection(tenant, true)?.expect("created on demand");
+        let snapshot_at = snapshot.snapshot_at;
+        let snapshot = snapshot.clone();
+
+        blocking(move || load_snapshot(&mut connection.lock().unwrap(), &snapshot)).await?;
+
+        if let Some(database) = self.tenants.lock().unwrap().get_mut(tenant) {
+            database.loaded_at = Some(snapshot_at);
+        }
+        debug!("Loaded analytics tables of tenant {} as of {}", tenant, snapshot_at);
+        Ok(())
+    }
+
+    async fn loaded_at(&self, tenant: &TenantId) -> Result<Option<DateTime<Utc>>, AnalyticsError> {
+        Ok(self.tenants.lock().unwrap().get(tenant).and_then(|database| database.loaded_at))
+    }
+
+    async fn query(&self, tenant: &TenantId, sql: &str, max_rows: usize) -> Result<AnalyticsResult, AnalyticsError> {
+        let connection = self.connection(tenant, false)?
+            .ok_or_else(|| AnalyticsError::Engine(format!("No analytics tables are loaded for tenant {}", tenant)))?;
+        let sql = sql.to_string();
+
+        let mut result = blocking(move || run_query(&mut connection.lock().unwrap(), &sql, max_rows)).await?;
+        result.snapshot_at = self.loaded_at(tenant).await?;
+        Ok(result)
+    }
+
+    async fn drop_tenant(&self, tenant: &TenantId) -> Result<bool, AnalyticsError> {
+        let Some(database) = self.tenants.lock().unwrap().remove(tenant) else {
+            return Ok(false);
+        };
+        drop(database);
+
+        if let Some(dir) = &self.config.dir {
+            let path = dir.join(format!("{}.duckdb", file_name(tenant)));
+            for path in [path.clone(), path.with_extension("duckdb.wal")] {
+                if path.exists() {
+                    std::fs::remove_file(&path)
+                        .map_err(|e| AnalyticsError::Engine(format!("Failed to remove {}: {}", path.display(), e)))?;
+                }
+            }
+        }
+        info!("Dropped analytics tables of tenant {}", tenant);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot() -> GraphSnapshot {
+        let alice = NodeRecord { id: Uuid::new_v4(), node: Node::new("Person").with_id_alias("alice") };
+        let bob = NodeRecord { id: Uuid::new_v4(), node: Node::new("Person").with_id_alias("bob") };
+        let acme = NodeRecord { id: Uuid::new_v4(), node: Node::new("Company").with_property("name", json!("Acme")) };
+        let edge = |from: &NodeRecord, to: &NodeRecord, kind: &str| EdgeRecord {
+            id: Uuid::new_v4(),
+            edge: TimeEdge::new(from.id, to.id, kind, "2024-01-01T00:00:00Z".parse().unwrap(), json!({})),
+        };
+
+        GraphSnapshot {
+            snapshot_at: Utc::now(),
+            valid_at: None,
+            edges: vec![edge(&alice, &acme, "WORKS_FOR"), edge(&bob, &acme, "WORKS_FOR"), edge(&alice, &bob, "KNOWS")],
+            nodes: vec![alice, bob, acme],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_and_aggregate() {
+        let engine = DuckDbAnalytics::new(DuckDbConfig::default()).unwrap();
+        let tenant = TenantId::new("acme");
+        assert!(engine.query(&tenant, "SELECT 1", 10).await.is_err());
+
+        let snapshot = snapshot();
+        engine.load(&tenant, &snapshot).await.unwrap();
+        assert_eq!(engine.loaded_at(&tenant).await.unwrap(), Some(snapshot.snapshot_at));
+
+        let result = engine.query(&tenant, "SELECT kind, count(*) AS edges FROM edges GROUP BY kind ORDER BY edges DESC", 10).await.unwrap();
+        assert_eq!(result.columns, vec!["kind", "edges"]);
+        assert_eq!(result.rows, vec![vec![json!("WORKS_FOR"), json!(2)], vec![json!("KNOWS"), json!(1)]]);
+
+        let result = engine.query(&tenant, "SELECT id FROM nodes", 2).await.unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert!(result.truncated);
+
+        // Reloading replaces the tables
+        engine.load(&tenant, &GraphSnapshot { nodes: vec![], edges: vec![], ..snapshot }).await.unwrap();
+        let result = engine.query(&tenant, "SELECT count(*) FROM nodes", 10).await.unwrap();
+        assert_eq!(result.rows, vec![vec![json!(0)]]);
+    }
+
+    #[tokio::test]
+    async fn test_queries_cannot_write_or_read_files() {
+        let engine = DuckDbAnalytics::new(DuckDbConfig::default()).unwrap();
+        let tenant = TenantId::new("acme");
+        engine.load(&tenant, &snapshot()).await.unwrap();
+
+        engine.query(&tenant, "DELETE FROM nodes", 10).await.unwrap();
+        let result = engine.query(&tenant, "SELECT count(*) FROM nodes", 10).await.unwrap();
+        assert_eq!(result.rows, vec![vec![json!(3)]]);
+
+        assert!(engine.query(&tenant, "SELECT * FROM read_csv('/etc/passwd')", 10).await.is_err());
+        assert!(engine.query(&tenant, "SET enable_external_access = true", 10).await.is_err());
+
+        assert!(engine.drop_tenant(&tenant).await.unwrap());
+        assert!(!engine.drop_tenant(&tenant).await.unwrap());
+    }
+}
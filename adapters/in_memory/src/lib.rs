@@ -2,9 +2,14 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use futures::StreamExt;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use telamentis_core::prelude::*;
+use telamentis_core::property_history;
+use telamentis_core::timeline;
+use telamentis_core::traversal::{self, Hop};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
@@ -18,6 +23,17 @@ pub struct InMemoryConfig {
     pub max_edges: Option<usize>,
     /// Whether to enable verbose logging
     pub verbose: bool,
+    /// Reserved system property handling for client-supplied props
+    pub system_properties: SystemProperties,
+    /// Temporal invariants enforced on edge writes
+    pub temporal_validation: TemporalValidation,
+    /// How system IDs of new nodes and edges are generated
+    pub ids: IdStrategy,
+    /// Maintain a view of the currently valid edges, which reads without a
+    /// `valid_at` use instead of filtering every edge version
+    pub current_view: bool,
+    /// Relationship constraints of tenants that have not set their own
+    pub edge_constraints: EdgeConstraints,
 }
 
 impl Default for InMemoryConfig {
@@ -26,6 +42,11 @@ impl Default for InMemoryConfig {
             max_nodes: Some(100_000),
             max_edges: Some(500_000),
             verbose: false,
+            system_properties: SystemProperties::default(),
+            temporal_validation: TemporalValidation::default(),
+            ids: IdStrategy::default(),
+            current_view: false,
+            edge_constraints: EdgeConstraints::default(),
         }
     }
 }
@@ -37,6 +58,17 @@ struct StoredNode {
     pub node: Node,
     pub tenant_id: TenantId,
     pub created_at: DateTime<Utc>,
+    /// When the current version was written
+    pub updated_at: DateTime<Utc>,
+    /// Versions the node had before, oldest first
+    pub versions: Vec<NodeVersion>,
+}
+
+impl StoredNode {
+    /// Earlier versions followed by the current one
+    fn all_versions(&self) -> impl Iterator<Item = NodeVersion> + '_ {
+        self.versions.iter().cloned().chain(std::iter::once(NodeVersion::written(self.node.clone(), self.updated_at)))
+    }
 }
 
 /// Internal storage for an edge
@@ -47,6 +79,129 @@ struct StoredEdge {
     pub tenant_id: TenantId,
 }
 
+/// Number of nodes with a label, or edges of a kind, and of their property keys
+#[derive(Debug, Default)]
+struct CatalogCounts {
+    count: u64,
+    property_keys: HashMap<String, u64>,
+}
+
+impl CatalogCounts {
+    fn add_keys(&mut self, props: &serde_json::Value) {
+        for key in props.as_object().into_iter().flat_map(|props| props.keys()) {
+            *self.property_keys.entry(key.clone()).or_default() += 1;
+        }
+    }
+
+    fn remove_keys(&mut self, props: &serde_json::Value) {
+        for key in props.as_object().into_iter().flat_map(|props| props.keys()) {
+            decrement(&mut self.property_keys, key);
+        }
+    }
+
+    fn to_entry(&self, name: &str) -> CatalogEntry {
+        CatalogEntry {
+            name: name.to_string(),
+            count: self.count,
+            property_keys: self.property_keys.clone(),
+        }
+    }
+}
+
+/// Running totals of a tenant's graph, kept up to date on every write
+#[derive(Debug, Default)]
+struct TenantStats {
+    labels: HashMap<String, CatalogCounts>,
+    kinds: HashMap<String, CatalogCounts>,
+    /// Serialized size of all node and edge properties
+    props_bytes: u64,
+    last_write_at: Option<DateTime<Utc>>,
+}
+
+impl TenantStats {
+    fn node_added(&mut self, node: &Node) {
+        Self::added(&mut self.labels, &node.label, &node.props);
+        self.props_bytes += props_size(&node.props);
+        self.last_write_at = Some(Utc::now());
+    }
+
+    fn node_updated(&mut self, old: &Node, new: &Node) {
+        let counts = self.labels.entry(new.label.clone()).or_default();
+        counts.remove_keys(&old.props);
+        counts.add_keys(&new.props);
+        self.props_bytes = (self.props_bytes + props_size(&new.props)).saturating_sub(props_size(&old.props));
+        self.last_write_at = Some(Utc::now());
+    }
+
+    fn node_removed(&mut self, node: &Node) {
+        Self::removed(&mut self.labels, &node.label, &node.props);
+        self.props_bytes = self.props_bytes.saturating_sub(props_size(&node.props));
+        self.last_write_at = Some(Utc::now());
+    }
+
+    fn edge_added(&mut self, edge: &TimeEdge) {
+        Self::added(&mut self.kinds, &edge.kind, &edge.props);
+        self.props_bytes += props_size(&edge.props);
+        self.last_write_at = Some(Utc::now());
+    }
+
+    fn edge_removed(&mut self, edge: &TimeEdge) {
+        Self::removed(&mut self.kinds, &edge.kind, &edge.props);
+        self.props_bytes = self.props_bytes.saturating_sub(props_size(&edge.props));
+        self.last_write_at = Some(Utc::now());
+    }
+
+    fn added(catalog: &mut HashMap<String, CatalogCounts>, name: &str, props: &serde_json::Value) {
+        let counts = catalog.entry(name.to_string()).or_default();
+        counts.count += 1;
+        counts.add_keys(props);
+    }
+
+    fn removed(catalog: &mut HashMap<String, CatalogCounts>, name: &str, props: &serde_json::Value) {
+        if let Some(counts) = catalog.get_mut(name) {
+            counts.count = counts.count.saturating_sub(1);
+            counts.remove_keys(props);
+            if counts.count == 0 {
+                catalog.remove(name);
+            }
+        }
+    }
+
+    /// Catalog entries sorted by name
+    fn entries(catalog: &HashMap<String, CatalogCounts>) -> Vec<CatalogEntry> {
+        let mut entries: Vec<_> = catalog.iter().map(|(name, counts)| counts.to_entry(name)).collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+}
+
+/// Serialized size of a property map
+fn props_size(props: &serde_json::Value) -> u64 {
+    serde_json::to_vec(props).map_or(0, |bytes| bytes.len() as u64)
+}
+
+/// Decrement a count, dropping it when it reaches zero
+fn decrement(counts: &mut HashMap<String, u64>, key: &str) {
+    if let Some(count) = counts.get_mut(key) {
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(key);
+        }
+    }
+}
+
+/// Deleted nodes and edges of a tenant, kept until purged
+#[derive(Debug, Default)]
+struct History {
+    nodes: Vec<ArchivedNode>,
+    edges: Vec<EdgeRecord>,
+}
+
+/// When a closed or deleted edge version ended
+fn edge_ended_at(edge: &TimeEdge) -> DateTime<Utc> {
+    edge.transaction_end_time.unwrap_or(edge.transaction_start_time)
+}
+
 /// In-memory data store
 #[derive(Debug)]
 struct MemoryStore {
@@ -58,18 +213,27 @@ struct MemoryStore {
     nodes_by_tenant: HashMap<TenantId, Vec<Uuid>>,
     /// Index: tenant_id -> edge_ids
     edges_by_tenant: HashMap<TenantId, Vec<Uuid>>,
-    /// Index: (tenant_id, id_alias) -> node_id
-    nodes_by_alias: HashMap<(TenantId, String), Uuid>,
+    /// Index: (tenant_id, alias namespace + id_alias) -> node_id
+    nodes_by_alias: HashMap<(TenantId, AliasKey), Uuid>,
     /// Index: (tenant_id, label) -> node_ids
     nodes_by_label: HashMap<(TenantId, String), Vec<Uuid>>,
     /// Index: from_node_id -> edge_ids
     edges_from_node: HashMap<Uuid, Vec<Uuid>>,
     /// Index: to_node_id -> edge_ids
     edges_to_node: HashMap<Uuid, Vec<Uuid>>,
+    /// Running totals per tenant, for summaries
+    stats_by_tenant: HashMap<TenantId, TenantStats>,
+    /// Deleted nodes and edges per tenant
+    history_by_tenant: HashMap<TenantId, History>,
+    /// View: tenant_id -> IDs of edges with neither valid_to nor
+    /// transaction_end_time, in creation order; maintained if configured
+    current_edges: Option<HashMap<TenantId, Vec<Uuid>>>,
+    /// Relationship constraints set per tenant
+    constraints_by_tenant: HashMap<TenantId, EdgeConstraints>,
 }
 
 impl MemoryStore {
-    fn new() -> Self {
+    fn new(current_view: bool) -> Self {
         Self {
             nodes: HashMap::new(),
             edges: HashMap::new(),
@@ -79,15 +243,24 @@ impl MemoryStore {
             nodes_by_label: HashMap::new(),
             edges_from_node: HashMap::new(),
             edges_to_node: HashMap::new(),
+            stats_by_tenant: HashMap::new(),
+            history_by_tenant: HashMap::new(),
+            current_edges: current_view.then(HashMap::new),
+            constraints_by_tenant: HashMap::new(),
         }
     }
 
     fn insert_node(&mut self, id: Uuid, node: Node, tenant_id: &TenantId) {
+        self.stats_by_tenant.entry(tenant_id.clone()).or_default().node_added(&node);
+
+        let now = Utc::now();
         let stored_node = StoredNode {
             id,
             node: node.clone(),
             tenant_id: tenant_id.clone(),
-            created_at: Utc::now(),
+            created_at: now,
+            updated_at: now,
+            versions: Vec::new(),
         };
 
         // Store the node
@@ -100,9 +273,9 @@ impl MemoryStore {
             .push(id);
 
         // Update alias index
-        if let Some(ref alias) = node.id_alias {
+        if let Some(alias_key) = node.alias_key() {
             self.nodes_by_alias
-                .insert((tenant_id.clone(), alias.clone()), id);
+                .insert((tenant_id.clone(), alias_key), id);
         }
 
         // Update label index
@@ -113,6 +286,8 @@ impl MemoryStore {
     }
 
     fn insert_edge(&mut self, id: Uuid, edge: TimeEdge, tenant_id: &TenantId) {
+        self.stats_by_tenant.entry(tenant_id.clone()).or_default().edge_added(&edge);
+
         let stored_edge = StoredEdge {
             id,
             edge: edge.clone(),
@@ -138,18 +313,63 @@ impl MemoryStore {
             .entry(edge.to_node_id)
             .or_insert_with(Vec::new)
             .push(id);
+
+        if let Some(current_edges) = &mut self.current_edges {
+            if edge.valid_to.is_none() && edge.is_current_version() {
+                current_edges.entry(tenant_id.clone()).or_default().push(id);
+            }
+        }
+    }
+
+    /// End the current version of an edge in transaction time
+    fn end_edge_version(&mut self, id: Uuid, tenant_id: &TenantId, at: DateTime<Utc>) {
+        if let Some(stored_edge) = self.edges.get_mut(&id) {
+            stored_edge.edge.transaction_end_time = Some(at);
+            self.leave_current_view(id, tenant_id);
+        }
+    }
+
+    /// Edges to close before `edge` is written under the tenant's
+    /// constraints, leaving out the version it replaces
+    fn plan_exclusivity(&self, tenant_id: &TenantId, edge: &TimeEdge, replacing: Option<Uuid>, default: &EdgeConstraints) -> Result<Vec<EdgeClosure>, GraphError> {
+        let constraints = self.constraints_by_tenant.get(tenant_id).unwrap_or(default);
+        let existing = self.edges_from_node.get(&edge.from_node_id).into_iter().flatten()
+            .filter(|&&id| Some(id) != replacing)
+            .filter_map(|id| self.edges.get(id))
+            .filter(|stored| stored.tenant_id == *tenant_id)
+            .map(|stored| (stored.id, &stored.edge));
+        constraints.plan(edge, existing)
+    }
+
+    fn leave_current_view(&mut self, id: Uuid, tenant_id: &TenantId) {
+        if let Some(edge_ids) = self.current_edges.as_mut().and_then(|view| view.get_mut(tenant_id)) {
+            edge_ids.retain(|&edge_id| edge_id != id);
+        }
+    }
+
+    /// Whether a read sees an edge version: current versions valid at
+    /// `valid_at`, or without it, all current versions, or only the
+    /// currently valid ones if the current view is maintained. Expired edges
+    /// are never seen.
+    fn is_visible(&self, edge: &TimeEdge, valid_at: Option<DateTime<Utc>>) -> bool {
+        edge.is_current_version() && !edge.is_expired_at(Utc::now()) && match valid_at {
+            Some(valid_at) => edge.was_valid_at(valid_at),
+            None => self.current_edges.is_none() || edge.valid_to.is_none(),
+        }
     }
 
     fn remove_node(&mut self, id: Uuid, tenant_id: &TenantId) -> bool {
         if let Some(stored_node) = self.nodes.remove(&id) {
+            self.stats_by_tenant.entry(tenant_id.clone()).or_default().node_removed(&stored_node.node);
+
             // Remove from tenant index
             if let Some(node_ids) = self.nodes_by_tenant.get_mut(tenant_id) {
                 node_ids.retain(|&node_id| node_id != id);
             }
 
             // Remove from alias index
-            if let Some(ref alias) = stored_node.node.id_alias {
-                self.nodes_by_alias.remove(&(tenant_id.clone(), alias.clone()));
+            if let Some(alias_key) = stored_node.node.alias_key() {
+                self.nodes_by_alias.remove(&(tenant_id.clone(), alias_key));
             }
 
             // Remove from label index
@@ -175,6 +395,12 @@ impl MemoryStore {
             self.edges_from_node.remove(&id);
             self.edges_to_node.remove(&id);
 
+            self.history_by_tenant.entry(tenant_id.clone()).or_default().nodes.push(ArchivedNode {
+                id,
+                node: stored_node.node,
+                deleted_at: Utc::now(),
+            });
+
             true
         } else {
             false
@@ -182,20 +408,11 @@ impl MemoryStore {
     }
 
     fn remove_edge(&mut self, id: Uuid, tenant_id: &TenantId) -> bool {
-        if let Some(stored_edge) = self.edges.remove(&id) {
-            // Remove from tenant index
-            if let Some(edge_ids) = self.edges_by_tenant.get_mut(tenant_id) {
-                edge_ids.retain(|&edge_id| edge_id != id);
-            }
-
-            // Remove from node relationship indices
-            if let Some(edge_ids) = self.edges_from_node.get_mut(&stored_edge.edge.from_node_id) {
-                edge_ids.retain(|&edge_id| edge_id != id);
-            }
-
-            if let Some(edge_ids) = self.edges_to_node.get_mut(&stored_edge.edge.to_node_id) {
-                edge_ids.retain(|&edge_id| edge_id != id);
-            }
+        if let Some(stored_edge) = self.unlink_edge(id, tenant_id) {
+            // A version that was already closed keeps the time it ended
+            let mut edge = stored_edge.edge;
+            edge.transaction_end_time.get_or_insert_with(Utc::now);
+            self.history_by_tenant.entry(tenant_id.clone()).or_default().edges.push(EdgeRecord { id, edge });
 
             true
         } else {
@@ -203,15 +420,368 @@ impl MemoryStore {
         }
     }
 
+    /// Remove an edge from the edge table and its indexes, without keeping it as history
+    fn unlink_edge(&mut self, id: Uuid, tenant_id: &TenantId) -> Option<StoredEdge> {
+        let stored_edge = self.edges.remove(&id)?;
+        self.stats_by_tenant.entry(tenant_id.clone()).or_default().edge_removed(&stored_edge.edge);
+
+        // Remove from tenant index
+        if let Some(edge_ids) = self.edges_by_tenant.get_mut(tenant_id) {
+            edge_ids.retain(|&edge_id| edge_id != id);
+        }
+
+        // Remove from node relationship indices
+        if let Some(edge_ids) = self.edges_from_node.get_mut(&stored_edge.edge.from_node_id) {
+            edge_ids.retain(|&edge_id| edge_id != id);
+        }
+
+        if let Some(edge_ids) = self.edges_to_node.get_mut(&stored_edge.edge.to_node_id) {
+            edge_ids.retain(|&edge_id| edge_id != id);
+        }
+
+        self.leave_current_view(id, tenant_id);
+        Some(stored_edge)
+    }
+
+    /// IDs of a tenant's edge versions that were closed before `before` but are still in the edge table
+    fn closed_edges(&self, tenant_id: &TenantId, before: DateTime<Utc>) -> Vec<Uuid> {
+        self.edges_by_tenant.get(tenant_id)
+            .into_iter()
+            .flatten()
+            .filter(|id| self.edges.get(id)
+                .and_then(|stored| stored.edge.transaction_end_time)
+                .is_some_and(|ended| ended < before))
+            .copied()
+            .collect()
+    }
+
     fn stats(&self) -> (usize, usize) {
         (self.nodes.len(), self.edges.len())
     }
+
+    /// Node and edge counts of one tenant
+    fn tenant_stats(&self, tenant_id: &TenantId) -> (usize, usize) {
+        (
+            self.nodes_by_tenant.get(tenant_id).map_or(0, Vec::len),
+            self.edges_by_tenant.get(tenant_id).map_or(0, Vec::len),
+        )
+    }
+
+    /// Tenants with nodes, edges or history, sorted
+    fn tenants(&self) -> Vec<TenantId> {
+        let with_nodes = self.nodes_by_tenant.iter().filter(|(_, ids)| !ids.is_empty()).map(|(tenant, _)| tenant);
+        let with_edges = self.edges_by_tenant.iter().filter(|(_, ids)| !ids.is_empty()).map(|(tenant, _)| tenant);
+        let with_history = self.history_by_tenant.iter()
+            .filter(|(_, history)| !history.nodes.is_empty() || !history.edges.is_empty())
+            .map(|(tenant, _)| tenant);
+
+        let tenants: HashSet<&TenantId> = with_nodes.chain(with_edges).chain(with_history).collect();
+        let mut tenants: Vec<TenantId> = tenants.into_iter().cloned().collect();
+        tenants.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        tenants
+    }
+
+    /// Remove a tenant's nodes, edges and history without recording them as
+    /// history; returns the number of records removed
+    fn clear_tenant(&mut self, tenant_id: &TenantId) -> u64 {
+        let node_ids = self.nodes_by_tenant.remove(tenant_id).unwrap_or_default();
+        let edge_ids = self.edges_by_tenant.remove(tenant_id).unwrap_or_default();
+
+        for id in &edge_ids {
+            if let Some(stored_edge) = self.edges.remove(id) {
+                if let Some(edge_ids) = self.edges_from_node.get_mut(&stored_edge.edge.from_node_id) {
+                    edge_ids.retain(|edge_id| edge_id != id);
+                }
+                if let Some(edge_ids) = self.edges_to_node.get_mut(&stored_edge.edge.to_node_id) {
+                    edge_ids.retain(|edge_id| edge_id != id);
+                }
+            }
+        }
+        for id in &node_ids {
+            self.nodes.remove(id);
+            self.edges_from_node.remove(id);
+            self.edges_to_node.remove(id);
+        }
+
+        self.nodes_by_alias.retain(|(tenant, _), _| tenant != tenant_id);
+        self.nodes_by_label.retain(|(tenant, _), _| tenant != tenant_id);
+        if let Some(current_edges) = &mut self.current_edges {
+            current_edges.remove(tenant_id);
+        }
+        self.stats_by_tenant.remove(tenant_id);
+        let history = self.history_by_tenant.remove(tenant_id)
+            .map_or(0, |history| history.nodes.len() + history.edges.len());
+
+        (node_ids.len() + edge_ids.len() + history) as u64
+    }
+
+    /// Summarize a tenant from its indices and running totals
+    fn summary(&self, tenant_id: &TenantId) -> GraphSummary {
+        let counts = |catalog: &HashMap<String, CatalogCounts>| {
+            catalog.iter().map(|(name, counts)| (name.clone(), counts.count)).collect()
+        };
+        let node_count = self.nodes_by_tenant.get(tenant_id).map_or(0, Vec::len);
+        let edge_count = self.edges_by_tenant.get(tenant_id).map_or(0, Vec::len);
+        let stats = self.stats_by_tenant.get(tenant_id);
+        let props_bytes = stats.map_or(0, |stats| stats.props_bytes);
+
+        GraphSummary {
+            node_count: node_count as u64,
+            edge_count: edge_count as u64,
+            nodes_by_label: stats.map(|stats| counts(&stats.labels)).unwrap_or_default(),
+            edges_by_kind: stats.map(|stats| counts(&stats.kinds)).unwrap_or_default(),
+            last_write_at: stats.and_then(|stats| stats.last_write_at),
+            storage_bytes_estimate: (node_count * std::mem::size_of::<StoredNode>()
+                + edge_count * std::mem::size_of::<StoredEdge>()) as u64
+                + props_bytes,
+        }
+    }
+
+    /// Catalog of a tenant from its running totals
+    fn catalog(&self, tenant_id: &TenantId) -> GraphCatalog {
+        self.stats_by_tenant.get(tenant_id)
+            .map(|stats| GraphCatalog {
+                labels: TenantStats::entries(&stats.labels),
+                kinds: TenantStats::entries(&stats.kinds),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Nodes of a tenant matching a `FindNodes` filter, in creation order
+    /// within each label
+    fn matching_nodes<'a>(
+        &'a self,
+        tenant_id: &TenantId,
+        labels: &[String],
+        properties: &'a HashMap<String, serde_json::Value>,
+    ) -> impl DoubleEndedIterator<Item = &'a StoredNode> + 'a {
+        // Each index is in creation order
+        let candidate_ids = if labels.is_empty() {
+            self.nodes_by_tenant.get(tenant_id).cloned().unwrap_or_default()
+        } else {
+            let mut ids = Vec::new();
+            for label in labels {
+                if let Some(label_nodes) = self.nodes_by_label.get(&(tenant_id.clone(), label.clone())) {
+                    ids.extend(label_nodes);
+                }
+            }
+            ids
+        };
+
+        candidate_ids.into_iter()
+            .filter_map(move |node_id| self.nodes.get(&node_id))
+            .filter(move |stored_node| {
+                properties.iter().all(|(key, expected_value)| stored_node.node.props.get(key) == Some(expected_value))
+            })
+    }
+
+    /// Current edges of a tenant matching a `FindRelationships` filter, in
+    /// creation order
+    fn matching_edges<'a>(
+        &'a self,
+        tenant_id: &'a TenantId,
+        from_node_id: Option<Uuid>,
+        to_node_id: Option<Uuid>,
+        relationship_types: &'a [String],
+        valid_at: Option<DateTime<Utc>>,
+    ) -> impl Iterator<Item = &'a StoredEdge> + 'a {
+        // Reads of the present use the current view rather than every version
+        let tenant_edges = match (&self.current_edges, valid_at) {
+            (Some(current_edges), None) => current_edges.get(tenant_id),
+            _ => self.edges_by_tenant.get(tenant_id),
+        };
+        let candidate_ids = if let Some(from_id) = from_node_id {
+            self.edges_from_node.get(&from_id).cloned().unwrap_or_default()
+        } else if let Some(to_id) = to_node_id {
+            self.edges_to_node.get(&to_id).cloned().unwrap_or_default()
+        } else {
+            tenant_edges.cloned().unwrap_or_default()
+        };
+
+        candidate_ids.into_iter()
+            .filter_map(move |edge_id| self.edges.get(&edge_id))
+            .filter(move |stored_edge| {
+                let edge = &stored_edge.edge;
+
+                stored_edge.tenant_id == *tenant_id
+                    // Filter by from_node_id and to_node_id
+                    && from_node_id.is_none_or(|from_id| edge.from_node_id == from_id)
+                    && to_node_id.is_none_or(|to_id| edge.to_node_id == to_id)
+                    // Filter by relationship type
+                    && (relationship_types.is_empty() || relationship_types.contains(&edge.kind))
+                    // Closed, superseded and retracted versions are history
+                    && self.is_visible(edge, valid_at)
+                    // Both end nodes must still exist
+                    && self.nodes.contains_key(&edge.from_node_id)
+                    && self.nodes.contains_key(&edge.to_node_id)
+            })
+    }
+
+    /// Path node of one of a tenant's nodes
+    fn path_node(&self, tenant_id: &TenantId, id: Uuid) -> Result<PathNode, GraphError> {
+        self.nodes.get(&id)
+            .filter(|stored_node| stored_node.tenant_id == *tenant_id)
+            .map(|stored_node| PathNode {
+                id: stored_node.id,
+                labels: vec![stored_node.node.label.clone()],
+                properties: stored_node.node.props.clone(),
+            })
+            .ok_or_else(|| GraphError::NodeNotFound(format!("Node {} not found in tenant {}", id, tenant_id)))
+    }
+
+    /// Current edges a traversal may follow from a node, in creation order
+    fn hops(
+        &self,
+        tenant_id: &TenantId,
+        node_id: Uuid,
+        direction: TraversalDirection,
+        relationship_types: &[String],
+        valid_at: Option<DateTime<Utc>>,
+        weight_property: &str,
+    ) -> Vec<Hop> {
+        let outgoing = match direction {
+            TraversalDirection::Incoming => None,
+            _ => self.edges_from_node.get(&node_id),
+        };
+        let incoming = match direction {
+            TraversalDirection::Outgoing => None,
+            _ => self.edges_to_node.get(&node_id),
+        };
+
+        outgoing.into_iter().flatten()
+            .chain(incoming.into_iter().flatten())
+            .filter_map(|edge_id| self.edges.get(edge_id))
+            .filter(|stored_edge| {
+                let edge = &stored_edge.edge;
+                stored_edge.tenant_id == *tenant_id
+                    && self.is_visible(edge, valid_at)
+                    && (relationship_types.is_empty() || relationship_types.contains(&edge.kind))
+            })
+            .filter_map(|stored_edge| {
+                let edge = &stored_edge.edge;
+                let other = if edge.from_node_id == node_id { edge.to_node_id } else { edge.from_node_id };
+                let node = self.path_node(tenant_id, other).ok()?;
+                Some(Hop {
+                    relationship: PathRelationship {
+                        id: stored_edge.id,
+                        rel_type: edge.kind.clone(),
+                        start_node_id: edge.from_node_id,
+                        end_node_id: edge.to_node_id,
+                        properties: edge.props.clone(),
+                        collapsed: None,
+                    },
+                    node,
+                    weight: traversal::edge_weight(&edge.props, weight_property),
+                })
+            })
+            .collect()
+    }
+
+    /// Count the matches of a structured query, up to `at_most`, without
+    /// building paths
+    fn count_matches(&self, tenant_id: &TenantId, query: GraphQuery, at_most: usize) -> Result<usize, GraphError> {
+        match query {
+            GraphQuery::FindNodes { labels, properties, .. } => {
+                Ok(self.matching_nodes(tenant_id, &labels, &properties).take(at_most).count())
+            }
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, collapse, .. } => {
+                let matching = self.matching_edges(tenant_id, from_node_id, to_node_id, &relationship_types, valid_at);
+                Ok(count_groups(matching, collapse, at_most))
+            }
+            GraphQuery::Raw { .. } => {
+                Err(GraphError::QueryFailed("Raw queries not supported by in-memory adapter".to_string()))
+            }
+            GraphQuery::AsOfQuery { base_query, as_of_time } => match *base_query {
+                GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, collapse, .. } => {
+                    let matching = self.matching_edges(tenant_id, from_node_id, to_node_id, &relationship_types, Some(as_of_time));
+                    Ok(count_groups(matching, collapse, at_most))
+                }
+                // As in `query`, other as-of queries match nothing
+                _ => Ok(0),
+            },
+        }
+    }
+}
+
+/// Stored item that structured queries can sort
+trait Sortable {
+    fn id(&self) -> Uuid;
+    fn sort_key(&self, field: &SortField) -> SortKey<'_>;
+}
+
+impl Sortable for StoredNode {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn sort_key(&self, field: &SortField) -> SortKey<'_> {
+        match field {
+            SortField::Property(name) => SortKey::Property(self.node.props.get(name)),
+            SortField::CreatedAt => SortKey::Time(self.created_at),
+            SortField::Label => SortKey::Text(&self.node.label),
+        }
+    }
+}
+
+impl Sortable for StoredEdge {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn sort_key(&self, field: &SortField) -> SortKey<'_> {
+        match field {
+            SortField::Property(name) => SortKey::Property(self.edge.props.get(name)),
+            SortField::CreatedAt => SortKey::Time(self.edge.transaction_start_time),
+            SortField::Label => SortKey::Text(&self.edge.kind),
+        }
+    }
+}
+
+/// Sort query results by `order_by`, breaking ties by system ID
+fn sort_results<T: Sortable>(items: &mut [&T], order_by: &[OrderBy]) {
+    items.sort_by(|a, b| {
+        order_by.iter()
+            .map(|key| {
+                let ordering = a.sort_key(&key.field).compare(&b.sort_key(&key.field));
+                match key.direction {
+                    SortDirection::Asc => ordering,
+                    SortDirection::Desc => ordering.reverse(),
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.id().cmp(&b.id()))
+    });
+}
+
+/// Apply `offset` and `limit` to sorted query results
+fn page<T>(items: impl Iterator<Item = T>, offset: Option<u32>, limit: Option<u32>) -> Vec<T> {
+    items
+        .skip(offset.unwrap_or(0) as usize)
+        .take(limit.map_or(usize::MAX, |limit| limit as usize))
+        .collect()
+}
+
+/// Count edges, or their groups when collapsed, up to `at_most`
+fn count_groups<'a>(edges: impl Iterator<Item = &'a StoredEdge>, collapse: Option<Collapse>, at_most: usize) -> usize {
+    let Some(collapse) = collapse else {
+        return edges.take(at_most).count();
+    };
+    let mut groups = HashSet::new();
+    for stored_edge in edges {
+        if groups.len() >= at_most {
+            break;
+        }
+        groups.insert(collapse.group_key(&stored_edge.edge));
+    }
+    groups.len()
 }
 
 /// In-memory GraphStore implementation
 pub struct InMemoryStore {
     store: Arc<RwLock<MemoryStore>>,
     config: InMemoryConfig,
+    snapshots: SnapshotRegistry,
+    ids: Arc<dyn IdGenerator>,
 }
 
 impl InMemoryStore {
@@ -224,40 +794,49 @@ impl InMemoryStore {
     pub fn new_with_config(config: InMemoryConfig) -> Self {
         info!("Creating in-memory store with config: {:?}", config);
         Self {
-            store: Arc::new(RwLock::new(MemoryStore::new())),
+            store: Arc::new(RwLock::new(MemoryStore::new(config.current_view))),
+            ids: config.ids.build(),
             config,
+            snapshots: SnapshotRegistry::default(),
         }
     }
 
+    /// Generate system IDs with a custom generator instead of the configured strategy
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
     /// Get statistics about the store
     pub async fn stats(&self) -> (usize, usize) {
         let store = self.store.read().await;
         store.stats()
     }
 
-    /// Clear all data from the store
+    /// Node and edge counts of one tenant
+    pub async fn tenant_stats(&self, tenant: &TenantId) -> (usize, usize) {
+        let store = self.store.read().await;
+        store.tenant_stats(tenant)
+    }
+
+    /// Clear all data from the store, across all tenants. Prefer
+    /// [`GraphStore::clear_tenant`] in stores shared between tests.
     pub async fn clear(&self) {
         let mut store = self.store.write().await;
-        *store = MemoryStore::new();
+        *store = MemoryStore::new(self.config.current_view);
         info!("Cleared in-memory store");
     }
-}
-
-impl Default for InMemoryStore {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-#[async_trait]
-impl GraphStore for InMemoryStore {
-    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
-        let mut store = self.store.write().await;
 
+    /// Upsert a node while holding the store's write lock
+    fn upsert_node_locked(&self, store: &mut MemoryStore, tenant: &TenantId, mut node: Node) -> Result<Uuid, GraphError> {
+        // Nothing is stored under an invalid tenant ID, so reads of one find nothing
+        tenant.validate()?;
         if self.config.verbose {
             debug!("Upserting node for tenant {}: {:?}", tenant, node.label);
         }
 
+        self.config.system_properties.sanitize(&mut node.props)?;
+
         // Check limits
         if let Some(max_nodes) = self.config.max_nodes {
             if store.nodes.len() >= max_nodes {
@@ -265,27 +844,45 @@ impl GraphStore for InMemoryStore {
                     format!("Maximum node limit ({}) reached", max_nodes)
                 ));
             }
+            warn_near_limit("node", store.nodes.len() + 1, max_nodes);
         }
 
-        // Check if node exists by alias
-        let node_id = if let Some(ref alias) = node.id_alias {
-            if let Some(&existing_id) = store.nodes_by_alias.get(&(tenant.clone(), alias.clone())) {
+        // Check if node exists by (namespace, alias)
+        let node_id = if let Some(alias_key) = node.alias_key() {
+            if let Some(&existing_id) = store.nodes_by_alias.get(&(tenant.clone(), alias_key.clone())) {
                 // Update existing node
                 if let Some(stored_node) = store.nodes.get_mut(&existing_id) {
-                    stored_node.node = node;
+                    if stored_node.node.label != node.label {
+                        return Err(GraphError::ConstraintViolation(format!(
+                            "Alias '{}' is already used by a {} node in tenant {}",
+                            alias_key, stored_node.node.label, tenant
+                        )));
+                    }
+                    store.stats_by_tenant.entry(tenant.clone()).or_default().node_updated(&stored_node.node, &node);
+                    // Keep the replaced version for the node's history
+                    let now = Utc::now();
+                    let previous = std::mem::replace(&mut stored_node.node, node);
+                    stored_node.versions.push(NodeVersion {
+                        node: previous,
+                        valid_from: stored_node.updated_at,
+                        valid_to: Some(now),
+                        transaction_start_time: stored_node.updated_at,
+                        transaction_end_time: None,
+                    });
+                    stored_node.updated_at = now;
                     existing_id
                 } else {
                     return Err(GraphError::DatabaseError("Inconsistent alias index".to_string()));
                 }
             } else {
                 // Create new node
-                let new_id = Uuid::new_v4();
+                let new_id = self.ids.next_id();
                 store.insert_node(new_id, node, tenant);
                 new_id
             }
         } else {
             // Always create new node when no alias
-            let new_id = Uuid::new_v4();
+            let new_id = self.ids.next_id();
             store.insert_node(new_id, node, tenant);
             new_id
         };
@@ -297,13 +894,22 @@ impl GraphStore for InMemoryStore {
         Ok(node_id)
     }
 
-    async fn upsert_edge(&self, tenant: &TenantId, mut edge: TimeEdge) -> Result<Uuid, GraphError> {
-        let mut store = self.store.write().await;
+    /// Insert an edge while holding the store's write lock
+    fn upsert_edge_locked(&self, store: &mut MemoryStore, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.insert_edge_locked(store, tenant, edge, None)
+    }
 
+    /// Insert an edge, which replaces the current version `replacing` if
+    /// given, closing the edges it overlaps if the tenant's constraints say so
+    fn insert_edge_locked(&self, store: &mut MemoryStore, tenant: &TenantId, mut edge: TimeEdge, replacing: Option<Uuid>) -> Result<Uuid, GraphError> {
+        tenant.validate()?;
         if self.config.verbose {
             debug!("Upserting edge for tenant {}: {} -> {}", tenant, edge.from_node_id, edge.to_node_id);
         }
 
+        self.config.system_properties.sanitize(&mut edge.props)?;
+        self.config.temporal_validation.validate_edge(&mut edge)?;
+
         // Check limits
         if let Some(max_edges) = self.config.max_edges {
             if store.edges.len() >= max_edges {
@@ -311,6 +917,7 @@ impl GraphStore for InMemoryStore {
                     format!("Maximum edge limit ({}) reached", max_edges)
                 ));
             }
+            warn_near_limit("edge", store.edges.len() + 1, max_edges);
         }
 
         // Verify that both nodes exist and belong to the same tenant
@@ -327,14 +934,22 @@ impl GraphStore for InMemoryStore {
             return Err(GraphError::NodeNotFound(format!("To node {} not found in tenant {}", edge.to_node_id, tenant)));
         }
 
-        // Set transaction time if not already set
-        if edge.transaction_start_time == edge.transaction_start_time {
-            edge.transaction_start_time = Utc::now();
-        }
+        let closures = store.plan_exclusivity(tenant, &edge, replacing, &self.config.edge_constraints)?;
 
-        let edge_id = Uuid::new_v4();
+        let edge_id = self.ids.next_id();
         store.insert_edge(edge_id, edge, tenant);
 
+        let now = Utc::now();
+        for closure in closures {
+            let Some(stored_edge) = store.edges.get(&closure.id) else { continue };
+            let mut closed = stored_edge.edge.clone().with_valid_to(closure.valid_to);
+            closed.transaction_start_time = now;
+            store.end_edge_version(closure.id, tenant, now);
+            let closed_id = self.ids.next_id();
+            store.insert_edge(closed_id, closed, tenant);
+            debug!("Closed exclusive edge {} as {} for tenant {}", closure.id, closed_id, tenant);
+        }
+
         if self.config.verbose {
             debug!("Upserted edge {} for tenant {}", edge_id, tenant);
         }
@@ -342,6 +957,168 @@ impl GraphStore for InMemoryStore {
         Ok(edge_id)
     }
 
+    /// Current version of an edge, for closing or superseding it
+    fn current_edge_locked(store: &MemoryStore, tenant: &TenantId, id: Uuid) -> Result<TimeEdge, GraphError> {
+        let stored_edge = store.edges.get(&id)
+            .filter(|stored| stored.tenant_id == *tenant && !stored.edge.is_expired_at(Utc::now()))
+            .ok_or_else(|| GraphError::EdgeNotFound(format!("Edge {} not found in tenant {}", id, tenant)))?;
+
+        if !stored_edge.edge.is_current_version() {
+            return Err(GraphError::ConstraintViolation(
+                format!("Edge {} is not the current version", id)
+            ));
+        }
+
+        Ok(stored_edge.edge.clone())
+    }
+
+    /// Replace the current version of an edge with `edge`, whose transaction
+    /// time starts when the old version's ends
+    fn replace_edge_locked(&self, store: &mut MemoryStore, tenant: &TenantId, id: Uuid, mut edge: TimeEdge, now: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        edge.transaction_start_time = now;
+        edge.transaction_end_time = None;
+
+        // Insert first so that a rejected edge leaves the old version current
+        let new_id = self.insert_edge_locked(store, tenant, edge, Some(id))?;
+        store.end_edge_version(id, tenant, now);
+
+        if self.config.verbose {
+            debug!("Replaced edge {} with {} for tenant {}", id, new_id, tenant);
+        }
+
+        Ok(new_id)
+    }
+}
+
+/// Share of a record limit after which writes carry a warning
+const LIMIT_WARNING_RATIO: f64 = 0.9;
+
+/// Items a bulk load writes per acquisition of the write lock, so that
+/// readers are not held back for a whole load
+const BULK_CHUNK_SIZE: usize = 10_000;
+
+/// Tell the client when the store is close to a record limit
+fn warn_near_limit(kind: &str, count: usize, max: usize) {
+    if count as f64 >= max as f64 * LIMIT_WARNING_RATIO {
+        telamentis_core::warnings::report(format!("Store holds {} of at most {} {}s", count, max, kind));
+    }
+}
+
+impl Default for InMemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl GraphStore for InMemoryStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        let mut store = self.store.write().await;
+        self.upsert_node_locked(&mut store, tenant, node)
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        let mut store = self.store.write().await;
+        self.upsert_edge_locked(&mut store, tenant, edge)
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        let mut chunks = nodes.chunks(BULK_CHUNK_SIZE);
+        let mut loaded = 0;
+        while let Some(chunk) = chunks.next().await {
+            let mut store = self.store.write().await;
+            for node in chunk {
+                self.upsert_node_locked(&mut store, tenant, node)?;
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        let mut chunks = edges.chunks(BULK_CHUNK_SIZE);
+        let mut loaded = 0;
+        while let Some(chunk) = chunks.next().await {
+            let mut store = self.store.write().await;
+            for edge in chunk {
+                self.upsert_edge_locked(&mut store, tenant, edge)?;
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, mut node: Node, mut edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        // Hold the write lock for the whole operation so it is applied atomically
+        let mut store = self.store.write().await;
+
+        if self.config.verbose {
+            debug!("Upserting node with {} edges for tenant {}: {:?}", edges.len(), tenant, node.label);
+        }
+
+        // Validate properties and resolve every edge target before writing anything
+        self.config.system_properties.sanitize(&mut node.props)?;
+        for spec in edges.iter_mut() {
+            self.config.system_properties.sanitize(&mut spec.props)?;
+            self.config.temporal_validation.validate_spec(spec)?;
+        }
+
+        let mut target_ids = Vec::with_capacity(edges.len());
+        for spec in &edges {
+            let target_id = match &spec.target {
+                NodeRef::Id(id) => store.nodes.get(id)
+                    .filter(|n| n.tenant_id == *tenant)
+                    .map(|n| n.id),
+                NodeRef::Alias(alias_key) => store.nodes_by_alias
+                    .get(&(tenant.clone(), alias_key.clone()))
+                    .copied(),
+            };
+
+            match target_id {
+                Some(id) => target_ids.push(id),
+                None => {
+                    return Err(GraphError::NodeNotFound(format!(
+                        "Edge target {:?} not found in tenant {}", spec.target, tenant
+                    )));
+                }
+            }
+        }
+
+        if let Some(max_edges) = self.config.max_edges {
+            if store.edges.len() + edges.len() > max_edges {
+                return Err(GraphError::ConstraintViolation(
+                    format!("Maximum edge limit ({}) reached", max_edges)
+                ));
+            }
+        }
+
+        // Check exclusive kinds against the current edges of the source
+        // nodes, and against each other; a new node has no edges yet
+        let node_id = node.alias_key()
+            .and_then(|alias_key| store.nodes_by_alias.get(&(tenant.clone(), alias_key)).copied())
+            .unwrap_or_else(Uuid::nil);
+        let batch: Vec<TimeEdge> = edges.iter().zip(&target_ids).map(|(spec, &target_id)| spec.to_time_edge(node_id, target_id)).collect();
+        let sources: HashSet<Uuid> = batch.iter().map(|edge| edge.from_node_id).collect();
+        let existing = sources.iter()
+            .filter_map(|id| store.edges_from_node.get(id))
+            .flatten()
+            .filter_map(|id| store.edges.get(id))
+            .filter(|stored| stored.tenant_id == *tenant && stored.edge.is_current_version())
+            .map(|stored| (stored.id, stored.edge.clone()))
+            .collect();
+        store.constraints_by_tenant.get(tenant).unwrap_or(&self.config.edge_constraints).check_batch(&batch, existing)?;
+
+        let node_id = self.upsert_node_locked(&mut store, tenant, node)?;
+
+        let mut edge_ids = Vec::with_capacity(edges.len());
+        for (spec, target_id) in edges.iter().zip(target_ids) {
+            let edge = spec.to_time_edge(node_id, target_id);
+            edge_ids.push(self.upsert_edge_locked(&mut store, tenant, edge)?);
+        }
+
+        Ok(NodeWithEdges { node_id, edge_ids })
+    }
+
     async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
         let store = self.store.read().await;
 
@@ -350,146 +1127,99 @@ impl GraphStore for InMemoryStore {
         }
 
         match query {
-            GraphQuery::FindNodes { labels, properties, limit } => {
-                let mut matching_nodes = Vec::new();
-
-                // Get candidate nodes by label
-                let candidate_ids = if labels.is_empty() {
-                    // Get all nodes for tenant
-                    store.nodes_by_tenant.get(tenant).cloned().unwrap_or_default()
-                } else {
-                    let mut ids = Vec::new();
-                    for label in &labels {
-                        if let Some(label_nodes) = store.nodes_by_label.get(&(tenant.clone(), label.clone())) {
-                            ids.extend(label_nodes);
-                        }
-                    }
-                    ids
+            GraphQuery::FindNodes { labels, properties, order_by, offset, limit } => {
+                let matching = store.matching_nodes(tenant, &labels, &properties);
+
+                // A single index already holds the nodes in creation order, so
+                // such pages are read off the index without sorting
+                let index_order = match order_by.as_slice() {
+                    [] => Some(SortDirection::Asc),
+                    [OrderBy { field: SortField::CreatedAt, direction }] if labels.len() <= 1 => Some(*direction),
+                    _ => None,
                 };
 
-                // Filter by properties
-                for &node_id in &candidate_ids {
-                    if let Some(stored_node) = store.nodes.get(&node_id) {
-                        let mut matches = true;
-
-                        for (key, expected_value) in &properties {
-                            if let Some(actual_value) = stored_node.node.props.get(key) {
-                                if actual_value != expected_value {
-                                    matches = false;
-                                    break;
-                                }
-                            } else {
-                                matches = false;
-                                break;
-                            }
-                        }
-
-                        if matches {
-                            let path_node = PathNode {
-                                id: stored_node.id,
-                                labels: vec![stored_node.node.label.clone()],
-                                properties: stored_node.node.props.clone(),
-                            };
-
-                            matching_nodes.push(Path {
-                                nodes: vec![path_node],
-                                relationships: Vec::new(),
-                            });
-
-                            if let Some(limit) = limit {
-                                if matching_nodes.len() >= limit as usize {
-                                    break;
-                                }
-                            }
-                        }
+                let nodes = match index_order {
+                    Some(SortDirection::Asc) => page(matching, offset, limit),
+                    Some(SortDirection::Desc) => page(matching.rev(), offset, limit),
+                    None => {
+                        let mut nodes: Vec<_> = matching.collect();
+                        sort_results(&mut nodes, &order_by);
+                        page(nodes.into_iter(), offset, limit)
                     }
-                }
+                };
 
-                Ok(matching_nodes)
+                Ok(nodes.into_iter()
+                    .map(|stored_node| Path {
+                        nodes: vec![PathNode {
+                            id: stored_node.id,
+                            labels: vec![stored_node.node.label.clone()],
+                            properties: stored_node.node.props.clone(),
+                        }],
+                        relationships: Vec::new(),
+                    })
+                    .collect())
             }
 
-            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, limit } => {
-                let mut matching_paths = Vec::new();
-
-                // Get candidate edges
-                let candidate_ids = if let Some(from_id) = from_node_id {
-                    store.edges_from_node.get(&from_id).cloned().unwrap_or_default()
-                } else if let Some(to_id) = to_node_id {
-                    store.edges_to_node.get(&to_id).cloned().unwrap_or_default()
-                } else {
-                    store.edges_by_tenant.get(tenant).cloned().unwrap_or_default()
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse } => {
+                let matching = store.matching_edges(tenant, from_node_id, to_node_id, &relationship_types, valid_at);
+
+                // Transaction times may be backdated, so only the unordered
+                // case can be read off the index
+                let edges: Vec<(Uuid, Cow<'_, TimeEdge>, Option<EdgeAggregate>)> = match collapse {
+                    None if order_by.is_empty() => page(matching, offset, limit).into_iter()
+                        .map(|stored_edge| (stored_edge.id, Cow::Borrowed(&stored_edge.edge), None))
+                        .collect(),
+                    None => {
+                        let mut edges: Vec<_> = matching.collect();
+                        sort_results(&mut edges, &order_by);
+                        page(edges.into_iter(), offset, limit).into_iter()
+                            .map(|stored_edge| (stored_edge.id, Cow::Borrowed(&stored_edge.edge), None))
+                            .collect()
+                    }
+                    // Groups are paged, so every matching edge is read
+                    Some(collapse) => {
+                        let mut edges: Vec<_> = matching.collect();
+                        sort_results(&mut edges, &order_by);
+                        let groups = collapse_edges(edges.iter().map(|stored_edge| (stored_edge.id, &stored_edge.edge)), collapse);
+                        page(groups.into_iter(), offset, limit).into_iter()
+                            .map(|group| (group.id, Cow::Owned(group.edge), Some(group.aggregate)))
+                            .collect()
+                    }
                 };
 
-                for &edge_id in &candidate_ids {
-                    if let Some(stored_edge) = store.edges.get(&edge_id) {
-                        if stored_edge.tenant_id != *tenant {
-                            continue;
-                        }
-
-                        let edge = &stored_edge.edge;
-
-                        // Filter by from_node_id
-                        if let Some(from_id) = from_node_id {
-                            if edge.from_node_id != from_id {
-                                continue;
-                            }
-                        }
-
-                        // Filter by to_node_id
-                        if let Some(to_id) = to_node_id {
-                            if edge.to_node_id != to_id {
-                                continue;
-                            }
-                        }
-
-                        // Filter by relationship type
-                        if !relationship_types.is_empty() && !relationship_types.contains(&edge.kind) {
-                            continue;
-                        }
-
-                        // Filter by temporal validity
-                        if let Some(valid_at) = valid_at {
-                            if !edge.was_valid_at(valid_at) {
-                                continue;
-                            }
-                        }
-
-                        // Get the start and end nodes
-                        let start_node = store.nodes.get(&edge.from_node_id);
-                        let end_node = store.nodes.get(&edge.to_node_id);
-
-                        if let (Some(start), Some(end)) = (start_node, end_node) {
-                            let path_start = PathNode {
-                                id: start.id,
-                                labels: vec![start.node.label.clone()],
-                                properties: start.node.props.clone(),
-                            };
-
-                            let path_end = PathNode {
-                                id: end.id,
-                                labels: vec![end.node.label.clone()],
-                                properties: end.node.props.clone(),
-                            };
-
-                            let path_rel = PathRelationship {
-                                id: stored_edge.id,
-                                rel_type: edge.kind.clone(),
-                                start_node_id: edge.from_node_id,
-                                end_node_id: edge.to_node_id,
-                                properties: edge.props.clone(),
-                            };
-
-                            matching_paths.push(Path {
-                                nodes: vec![path_start, path_end],
-                                relationships: vec![path_rel],
-                            });
-
-                            if let Some(limit) = limit {
-                                if matching_paths.len() >= limit as usize {
-                                    break;
-                                }
-                            }
-                        }
+                let mut matching_paths = Vec::with_capacity(edges.len());
+                for (edge_id, edge, aggregate) in edges {
+
+                    // Get the start and end nodes
+                    let start_node = store.nodes.get(&edge.from_node_id);
+                    let end_node = store.nodes.get(&edge.to_node_id);
+
+                    if let (Some(start), Some(end)) = (start_node, end_node) {
+                        let path_start = PathNode {
+                            id: start.id,
+                            labels: vec![start.node.label.clone()],
+                            properties: start.node.props.clone(),
+                        };
+
+                        let path_end = PathNode {
+                            id: end.id,
+                            labels: vec![end.node.label.clone()],
+                            properties: end.node.props.clone(),
+                        };
+
+                        let path_rel = PathRelationship {
+                            id: edge_id,
+                            rel_type: edge.kind.clone(),
+                            start_node_id: edge.from_node_id,
+                            end_node_id: edge.to_node_id,
+                            properties: edge.props.clone(),
+                            collapsed: aggregate,
+                        };
+
+                        matching_paths.push(Path {
+                            nodes: vec![path_start, path_end],
+                            relationships: vec![path_rel],
+                        });
                     }
                 }
 
@@ -504,13 +1234,16 @@ impl GraphStore for InMemoryStore {
             GraphQuery::AsOfQuery { base_query, as_of_time } => {
                 // Recursively execute with temporal constraint
                 match *base_query {
-                    GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at: _, limit } => {
+                    GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at: _, order_by, offset, limit, collapse } => {
                         self.query(tenant, GraphQuery::FindRelationships {
                             from_node_id,
                             to_node_id,
                             relationship_types,
                             valid_at: Some(as_of_time),
+                            order_by,
+                            offset,
                             limit,
+                            collapse,
                         }).await
                     }
                     _ => {
@@ -522,11 +1255,56 @@ impl GraphStore for InMemoryStore {
         }
     }
 
-    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
         let store = self.store.read().await;
+        Ok(store.count_matches(tenant, query, usize::MAX)? as u64)
+    }
 
-        if let Some(stored_node) = store.nodes.get(&id) {
-            if stored_node.tenant_id == *tenant {
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        let store = self.store.read().await;
+        Ok(store.count_matches(tenant, query, 1)? > 0)
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        let store = self.store.read().await;
+        let start = store.path_node(tenant, request.start)?;
+        traversal::traverse(&request, start, |node_id| {
+            store.hops(tenant, node_id, request.direction, &request.relationship_types, request.valid_at, request.weight_property())
+        })
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        let store = self.store.read().await;
+        let start = store.path_node(tenant, request.from)?;
+        traversal::shortest_path(&request, start, |node_id| {
+            store.hops(tenant, node_id, request.direction, &request.relationship_types, request.valid_at, request.weight_property())
+        })
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        let range = request.range()?;
+        let store = self.store.read().await;
+        store.path_node(tenant, request.node)?;
+
+        // The node's edges are read off the endpoint indexes; self-loops are in both
+        let edge_ids: HashSet<Uuid> = store.edges_from_node.get(&request.node).into_iter().flatten()
+            .chain(store.edges_to_node.get(&request.node).into_iter().flatten())
+            .copied()
+            .collect();
+        let now = Utc::now();
+        let edges = edge_ids.into_iter()
+            .filter_map(|id| store.edges.get(&id))
+            .filter(|stored_edge| stored_edge.tenant_id == *tenant && stored_edge.edge.is_current_version())
+            .filter(|stored_edge| !stored_edge.edge.is_expired_at(now))
+            .map(|stored_edge| EdgeRecord { id: stored_edge.id, edge: stored_edge.edge.clone() });
+        timeline::build_timeline(&request, range, edges)
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        let store = self.store.read().await;
+
+        if let Some(stored_node) = store.nodes.get(&id) {
+            if stored_node.tenant_id == *tenant {
                 Ok(Some(stored_node.node.clone()))
             } else {
                 Ok(None) // Node exists but not in this tenant
@@ -539,7 +1317,7 @@ impl GraphStore for InMemoryStore {
     async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
         let store = self.store.read().await;
 
-        if let Some(&node_id) = store.nodes_by_alias.get(&(tenant.clone(), id_alias.to_string())) {
+        if let Some(&node_id) = store.nodes_by_alias.get(&(tenant.clone(), AliasKey::new(id_alias))) {
             if let Some(stored_node) = store.nodes.get(&node_id) {
                 Ok(Some((node_id, stored_node.node.clone())))
             } else {
@@ -550,6 +1328,22 @@ impl GraphStore for InMemoryStore {
         }
     }
 
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        let store = self.store.read().await;
+
+        let resolved = aliases
+            .iter()
+            .filter_map(|alias_key| {
+                store
+                    .nodes_by_alias
+                    .get(&(tenant.clone(), alias_key.clone()))
+                    .map(|&node_id| (alias_key.clone(), node_id))
+            })
+            .collect();
+
+        Ok(resolved)
+    }
+
     async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
         let mut store = self.store.write().await;
 
@@ -600,13 +1394,261 @@ impl GraphStore for InMemoryStore {
         Ok(deleted)
     }
 
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        let mut store = self.store.write().await;
+        let now = Utc::now();
+
+        let closed = Self::current_edge_locked(&store, tenant, id)?.with_valid_to(valid_to);
+        self.replace_edge_locked(&mut store, tenant, id, closed, now)
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        let mut store = self.store.write().await;
+        let now = Utc::now();
+
+        Self::current_edge_locked(&store, tenant, id)?;
+        self.replace_edge_locked(&mut store, tenant, id, edge, now)
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        let mut store = self.store.write().await;
+
+        if self.config.verbose {
+            debug!("Retracting edge {} for tenant {}", id, tenant);
+        }
+
+        // The edge is kept, so that it is still visible as of earlier
+        // transaction times
+        match store.edges.get(&id) {
+            Some(stored_edge) if stored_edge.tenant_id == *tenant && stored_edge.edge.is_current_version() => {
+                store.end_edge_version(id, tenant, Utc::now());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
     async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
-        // In-memory store doesn't maintain version history in this implementation
-        if let Some(node) = self.get_node(tenant, id).await? {
-            Ok(vec![node])
+        let store = self.store.read().await;
+        Ok(store.nodes.get(&id)
+            .filter(|stored| stored.tenant_id == *tenant)
+            .map(|stored| stored.all_versions().map(|version| version.node).collect())
+            .unwrap_or_default())
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        let store = self.store.read().await;
+        let stored = store.nodes.get(&node_id)
+            .filter(|stored| stored.tenant_id == *tenant)
+            .ok_or_else(|| GraphError::NodeNotFound(format!("Node {} not found in tenant {}", node_id, tenant)))?;
+        Ok(property_history::build_property_history(node_id, key, stored.all_versions()))
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, mut node: Node) -> Result<bool, GraphError> {
+        self.config.system_properties.sanitize(&mut node.props)?;
+        let mut guard = self.store.write().await;
+        let store = &mut *guard;
+        let Some(stored_node) = store.nodes.get_mut(&id).filter(|stored| stored.tenant_id == *tenant) else {
+            return Ok(false);
+        };
+
+        // The alias stays, and with it the alias index
+        node.id_alias = stored_node.node.id_alias.clone();
+        node.alias_namespace = stored_node.node.alias_namespace.clone();
+        let stats = store.stats_by_tenant.entry(tenant.clone()).or_default();
+        if stored_node.node.label == node.label {
+            stats.node_updated(&stored_node.node, &node);
         } else {
-            Ok(Vec::new())
+            stats.node_removed(&stored_node.node);
+            stats.node_added(&node);
+            if let Some(ids) = store.nodes_by_label.get_mut(&(tenant.clone(), stored_node.node.label.clone())) {
+                ids.retain(|node_id| *node_id != id);
+            }
+            store.nodes_by_label.entry((tenant.clone(), node.label.clone())).or_default().push(id);
         }
+        let now = Utc::now();
+        let previous = std::mem::replace(&mut stored_node.node, node);
+        stored_node.versions.push(NodeVersion {
+            node: previous,
+            valid_from: stored_node.updated_at,
+            valid_to: Some(now),
+            transaction_start_time: stored_node.updated_at,
+            transaction_end_time: None,
+        });
+        stored_node.updated_at = now;
+        Ok(true)
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        // The read lock keeps writers out while the snapshot is copied
+        let store = self.store.read().await;
+        let snapshot_at = Utc::now();
+
+        let nodes = store.nodes_by_tenant.get(tenant)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| store.nodes.get(id))
+            .filter(|stored| stored.created_at <= snapshot_at)
+            .map(|stored| NodeRecord { id: stored.id, node: stored.node.clone() })
+            .collect();
+
+        let edges = store.edges_by_tenant.get(tenant)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| store.edges.get(id))
+            .filter(|stored| stored.edge.existed_at_transaction_time(snapshot_at) && !stored.edge.is_expired_at(snapshot_at))
+            .filter(|stored| valid_at.map_or(true, |t| stored.edge.was_valid_at(t)))
+            .map(|stored| EdgeRecord { id: stored.id, edge: stored.edge.clone() })
+            .collect();
+
+        Ok(GraphSnapshot { snapshot_at, valid_at, nodes, edges })
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.snapshots.materialize(self, tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        Ok(self.snapshots.list(tenant))
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        Ok(self.snapshots.remove(tenant, name))
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.snapshots.query(tenant, name, query)
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        let store = self.store.read().await;
+        let mut batch = ArchiveBatch::default();
+
+        if let Some(history) = store.history_by_tenant.get(tenant) {
+            batch.nodes.extend(history.nodes.iter().filter(|n| n.deleted_at < before).cloned());
+            batch.edges.extend(history.edges.iter().filter(|e| edge_ended_at(&e.edge) < before).cloned());
+        }
+        batch.edges.extend(store.closed_edges(tenant, before).into_iter()
+            .filter_map(|id| store.edges.get(&id))
+            .map(|stored| EdgeRecord { id: stored.id, edge: stored.edge.clone() }));
+
+        Ok(batch)
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        let mut store = self.store.write().await;
+
+        // Closed versions move to the history first, keeping their end times
+        for id in store.closed_edges(tenant, before) {
+            store.remove_edge(id, tenant);
+        }
+
+        let Some(history) = store.history_by_tenant.get_mut(tenant) else {
+            return Ok(0);
+        };
+        let held = history.nodes.len() + history.edges.len();
+        history.nodes.retain(|n| n.deleted_at >= before);
+        history.edges.retain(|e| edge_ended_at(&e.edge) >= before);
+        let purged = (held - history.nodes.len() - history.edges.len()) as u64;
+
+        if self.config.verbose {
+            debug!("Purged {} history records for tenant {}", purged, tenant);
+        }
+
+        Ok(purged)
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        tenant.validate()?;
+        let mut store = self.store.write().await;
+        let store = &mut *store;
+        let mut restored = 0;
+
+        for archived in batch.nodes {
+            let history = store.history_by_tenant.entry(tenant.clone()).or_default();
+            if store.nodes.contains_key(&archived.id) || history.nodes.iter().any(|n| n.id == archived.id) {
+                continue;
+            }
+            history.nodes.push(archived);
+            restored += 1;
+        }
+
+        for record in batch.edges {
+            let history = store.history_by_tenant.entry(tenant.clone()).or_default();
+            if store.edges.contains_key(&record.id) || history.edges.iter().any(|e| e.id == record.id) {
+                continue;
+            }
+
+            // Versions between live nodes go back in the edge table, where
+            // as-at queries see them; the rest are kept as deleted edges
+            let is_live = |id: &Uuid| store.nodes.get(id).is_some_and(|stored| stored.tenant_id == *tenant);
+            if record.edge.transaction_end_time.is_some() && is_live(&record.edge.from_node_id) && is_live(&record.edge.to_node_id) {
+                store.insert_edge(record.id, record.edge, tenant);
+            } else {
+                history.edges.push(record);
+            }
+            restored += 1;
+        }
+
+        if self.config.verbose {
+            debug!("Restored {} history records for tenant {}", restored, tenant);
+        }
+
+        Ok(restored)
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        let mut store = self.store.write().await;
+
+        let expired: Vec<Uuid> = store.edges_by_tenant.get(tenant)
+            .into_iter()
+            .flatten()
+            .filter(|id| store.edges.get(id).is_some_and(|stored| stored.edge.is_expired_at(now)))
+            .copied()
+            .collect();
+        for &id in &expired {
+            store.unlink_edge(id, tenant);
+        }
+
+        if self.config.verbose {
+            debug!("Removed {} expired edges for tenant {}", expired.len(), tenant);
+        }
+
+        Ok(expired.len() as u64)
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        let store = self.store.read().await;
+        Ok(store.summary(tenant))
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        let store = self.store.read().await;
+        Ok(store.catalog(tenant))
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        let store = self.store.read().await;
+        Ok(store.tenants())
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        let mut store = self.store.write().await;
+        let removed = store.clear_tenant(tenant) + self.snapshots.clear_tenant(tenant) as u64;
+        info!("Cleared {} records of tenant {} from in-memory store", removed, tenant);
+        Ok(removed)
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        let store = self.store.read().await;
+        Ok(store.constraints_by_tenant.get(tenant).unwrap_or(&self.config.edge_constraints).clone())
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        let mut store = self.store.write().await;
+        info!("Setting {} exclusive relationship kinds for tenant {}", constraints.exclusive.len(), tenant);
+        store.constraints_by_tenant.insert(tenant.clone(), constraints);
+        Ok(())
     }
 
     async fn health_check(&self) -> Result<(), GraphError> {
@@ -620,6 +1662,7 @@ impl GraphStore for InMemoryStore {
 mod tests {
     use super::*;
     use serde_json::json;
+    use telamentis_core::query::Query;
 
     #[tokio::test]
     async fn test_node_upsert() {
@@ -663,7 +1706,10 @@ mod tests {
             to_node_id: None,
             relationship_types: vec!["KNOWS".to_string()],
             valid_at: None,
+            order_by: vec![],
+            offset: None,
             limit: None,
+            collapse: None,
         };
 
         let results = store.query(&tenant, query).await.unwrap();
@@ -694,6 +1740,1592 @@ mod tests {
         assert!(store.get_node(&tenant_b, id_a).await.unwrap().is_none());
     }
 
+    #[tokio::test]
+    async fn test_clear_tenant() {
+        let store = InMemoryStore::new();
+        let tenant_a = TenantId::new("tenant_a");
+        let tenant_b = TenantId::new("tenant_b");
+        assert!(store.list_tenants().await.unwrap().is_empty());
+
+        let mut ids = Vec::new();
+        for tenant in [&tenant_a, &tenant_b] {
+            let alice = store.upsert_node(tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+            let bob = store.upsert_node(tenant, Node::new("Person").with_id_alias("bob")).await.unwrap();
+            let edge = store.upsert_edge(tenant, TimeEdge::new(alice, bob, "KNOWS", Utc::now(), json!({}))).await.unwrap();
+            store.delete_edge(tenant, edge).await.unwrap();
+            store.materialize_snapshot(tenant, "now", Utc::now()).await.unwrap();
+            ids.push(alice);
+        }
+        assert_eq!(store.list_tenants().await.unwrap(), vec![tenant_a.clone(), tenant_b.clone()]);
+        assert_eq!(store.tenant_stats(&tenant_a).await, (2, 0));
+
+        // Two nodes, one edge in history and one snapshot
+        assert_eq!(store.clear_tenant(&tenant_a).await.unwrap(), 4);
+        assert_eq!(store.list_tenants().await.unwrap(), vec![tenant_b.clone()]);
+        assert_eq!(store.tenant_stats(&tenant_a).await, (0, 0));
+        assert_eq!(store.summary(&tenant_a).await.unwrap().node_count, 0);
+        assert!(store.get_node_by_alias(&tenant_a, "alice").await.unwrap().is_none());
+        assert!(store.list_snapshots(&tenant_a).await.unwrap().is_empty());
+        assert!(store.read_history(&tenant_a, Utc::now()).await.unwrap().edges.is_empty());
+
+        // The other tenant is untouched, and the cleared one can be reused
+        assert_eq!(store.tenant_stats(&tenant_b).await, (2, 0));
+        assert_eq!(store.get_node_by_alias(&tenant_b, "alice").await.unwrap().map(|(id, _)| id), Some(ids[1]));
+        assert_eq!(store.list_snapshots(&tenant_b).await.unwrap().len(), 1);
+        store.upsert_node(&tenant_a, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        assert_eq!(store.stats().await, (3, 0));
+    }
+
+    #[tokio::test]
+    async fn test_session_graphs() {
+        let store = Arc::new(InMemoryStore::new());
+        let tenant = TenantId::new("test_tenant");
+        let sessions = SessionGraphs::start(store.clone(), SessionGraphConfig::default());
+        assert!(matches!(sessions.create(&tenant, Some(0)), Err(SessionError::InvalidTtl(_))));
+
+        let session = sessions.create(&tenant, Some(60)).unwrap();
+        store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice").with_property("age", json!(30))).await.unwrap();
+        let alice = store.upsert_node(&session.graph, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme = store.upsert_node(&session.graph, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let scratch = store.upsert_node(&session.graph, Node::new("Note")).await.unwrap();
+        let works_for = store.upsert_edge(&session.graph, TimeEdge::new(alice, acme, "WORKS_FOR", Utc::now(), json!({}))).await.unwrap();
+        assert_eq!(store.tenant_stats(&tenant).await, (1, 0));
+
+        // Promoting an edge brings its endpoints, merged by alias
+        let request = PromoteRequest { nodes: Vec::new(), edges: vec![works_for] };
+        let report = sessions.promote(&tenant, session.id, &request).await.unwrap();
+        assert_eq!((report.nodes.len(), report.edges.len()), (2, 1));
+        let (durable_alice, _) = store.get_node_by_alias(&tenant, "alice").await.unwrap().unwrap();
+        assert_eq!(report.nodes[&alice], durable_alice);
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 1));
+        assert!(store.get_node(&tenant, scratch).await.unwrap().is_none());
+
+        // Unknown IDs are rejected
+        let request = PromoteRequest { nodes: vec![Uuid::new_v4()], edges: Vec::new() };
+        assert!(matches!(sessions.promote(&tenant, session.id, &request).await, Err(CoreError::Session(SessionError::InvalidPromotion(_)))));
+
+        // Expiry removes the session and its graph, leaving promoted data
+        assert_eq!(sessions.purge_expired(Utc::now()).await, 0);
+        assert_eq!(sessions.purge_expired(Utc::now() + chrono::Duration::seconds(61)).await, 1);
+        assert!(matches!(sessions.get(&tenant, session.id), Err(SessionError::NotFound(_))));
+        assert_eq!(store.tenant_stats(&session.graph).await, (0, 0));
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 1));
+
+        let other = sessions.create(&tenant, None).unwrap();
+        assert_eq!(sessions.list(&tenant), vec![other.clone()]);
+        sessions.end(&tenant, other.id).await.unwrap();
+        assert!(sessions.list(&tenant).is_empty());
+        sessions.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_retry() {
+        let store = Arc::new(InMemoryStore::new());
+        let service = CoreGraphService::new(store.clone());
+        let tenant = TenantId::new("test_tenant");
+        let queue = DeadLetterQueue::default();
+
+        // An edge whose endpoints did not exist yet when it was ingested
+        let edge = TimeEdge::new(Uuid::nil(), Uuid::nil(), "WORKS_FOR", Utc::now(), json!({"_from_id_alias": "alice", "_to_id_alias": "acme"}));
+        let id = queue.record(&tenant, DeadLetterRecord::Edge(edge), "Unknown node alias", "csv-import", Default::default());
+
+        let report = queue.retry(&tenant, None, &service).await;
+        assert_eq!((report.succeeded.len(), report.failed, queue.get(&tenant, id).unwrap().attempts), (0, vec![id], 2));
+
+        store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let report = queue.retry(&tenant, Some(&[id]), &service).await;
+        assert_eq!(report.succeeded, vec![id]);
+        assert!(queue.list(&tenant).is_empty());
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_mutation_stream() {
+        let store = Arc::new(InMemoryStore::new());
+        let config = MutationApplierConfig { ack_every: 2, ..Default::default() };
+        let applier = MutationApplier::new(Arc::new(CoreGraphService::new(store.clone())), config);
+        let tenant = TenantId::new("test_tenant");
+
+        let (sink, mut acks) = applier.start();
+        sink.send(1, tenant.clone(), GraphMutation::UpsertNode(Node::new("Person").with_id_alias("alice"))).await.unwrap();
+        sink.reject(2, "Invalid JSON for props").await.unwrap();
+        sink.send(3, tenant.clone(), GraphMutation::UpsertNode(Node::new("Company").with_id_alias("acme"))).await.unwrap();
+        drop(sink);
+
+        let first = acks.recv().await.unwrap();
+        assert_eq!(first.results.iter().map(|r| r.sequence).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(matches!(first.results[0].status, MutationStatus::Applied { .. }));
+        assert_eq!(first.results[1].status, MutationStatus::Failed { error: "Invalid JSON for props".to_string() });
+
+        // The rest is acked when the stream ends
+        let last = acks.recv().await.unwrap();
+        assert_eq!((last.results.len(), last.applied, last.failed), (1, 2, 1));
+        assert!(acks.recv().await.is_none());
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 0));
+    }
+
+    #[tokio::test]
+    async fn test_sync_between_instances() {
+        let tenant = TenantId::new("test_tenant");
+        let instance = |id: &str| {
+            let engine = Arc::new(SyncEngine::new(SyncConfig { instance_id: id.to_string(), ..Default::default() }));
+            let store = Arc::new(InMemoryStore::new());
+            let service = CoreGraphService::new(Arc::new(SyncGraphStore::new(store.clone(), engine.clone())));
+            (engine, store, service)
+        };
+        let (edge_engine, _, edge) = instance("edge-1");
+        let (central_engine, central_store, central) = instance("central");
+
+        let alice = edge.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme = edge.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        edge.upsert_edge(&tenant, TimeEdge::new(alice, acme, "WORKS_FOR", Utc::now(), json!({}))).await.unwrap();
+
+        let batch = edge_engine.changes(&tenant, 0, None, None);
+        assert_eq!(batch.changes.len(), 3);
+        let report = central_engine.apply(&central, &tenant, batch).await;
+        assert_eq!((report.applied, report.cursor), (3, 3));
+        assert!(report.failed.is_none());
+        assert_eq!(central_store.tenant_stats(&tenant).await, (2, 1));
+        assert_eq!(central_engine.status(&tenant).peers.get("edge-1"), Some(&3));
+
+        // Applied changes are not sent back to where they came from
+        assert!(central_engine.changes(&tenant, 0, None, Some("edge-1")).changes.is_empty());
+
+        // A stale write from the edge loses to a newer one made centrally
+        let mut stale = edge_engine.changes(&tenant, 0, Some(1), None);
+        central.upsert_node(&tenant, Node::new("Person").with_id_alias("alice").with_props(json!({"role": "cto"}))).await.unwrap();
+        stale.changes[0].cursor = 4;
+        stale.next_cursor = 4;
+        let report = central_engine.apply(&central, &tenant, stale).await;
+        assert_eq!((report.applied, report.skipped.clone()), (0, vec![4]));
+    }
+
+    #[tokio::test]
+    async fn test_alias_namespaces() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let crm = Node::new("Customer").with_id_alias("123").with_alias_namespace("crm");
+        let tickets = Node::new("Ticket").with_id_alias("123").with_alias_namespace("tickets");
+        let plain = Node::new("Customer").with_id_alias("123");
+
+        let crm_id = store.upsert_node(&tenant, crm.clone()).await.unwrap();
+        let tickets_id = store.upsert_node(&tenant, tickets).await.unwrap();
+        let plain_id = store.upsert_node(&tenant, plain).await.unwrap();
+
+        // Same alias in different namespaces must not collide
+        assert_ne!(crm_id, tickets_id);
+        assert_ne!(crm_id, plain_id);
+        assert_eq!(store.upsert_node(&tenant, crm).await.unwrap(), crm_id);
+
+        // Reusing a namespaced alias for a different label is rejected
+        let conflicting = Node::new("Ticket").with_id_alias("123").with_alias_namespace("crm");
+        assert!(matches!(
+            store.upsert_node(&tenant, conflicting).await,
+            Err(GraphError::ConstraintViolation(_))
+        ));
+
+        let resolved = store
+            .resolve_aliases(&tenant, &[
+                AliasKey::namespaced("crm", "123"),
+                AliasKey::namespaced("tickets", "123"),
+                AliasKey::new("123"),
+                AliasKey::namespaced("crm", "missing"),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.len(), 3);
+        assert_eq!(resolved[&AliasKey::namespaced("crm", "123")], crm_id);
+        assert_eq!(resolved[&AliasKey::namespaced("tickets", "123")], tickets_id);
+        assert_eq!(resolved[&AliasKey::new("123")], plain_id);
+
+        // Resolution is tenant scoped
+        let other = TenantId::new("other_tenant");
+        let resolved = store.resolve_aliases(&other, &[AliasKey::new("123")]).await.unwrap();
+        assert!(resolved.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upsert_node_with_edges() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let bob_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("bob")).await.unwrap();
+
+        let alice = Node::new("Person").with_id_alias("alice");
+        let edges = vec![
+            EdgeSpec::new("WORKS_FOR", NodeRef::Alias(AliasKey::new("acme"))),
+            EdgeSpec::new("KNOWS", NodeRef::Id(bob_id)).incoming(),
+        ];
+
+        let result = store.upsert_node_with_edges(&tenant, alice, edges).await.unwrap();
+        assert_eq!(result.edge_ids.len(), 2);
+
+        let works_for = store.query(&tenant, GraphQuery::FindRelationships {
+            from_node_id: Some(result.node_id),
+            to_node_id: Some(acme_id),
+            relationship_types: vec!["WORKS_FOR".to_string()],
+            valid_at: None,
+            order_by: vec![],
+            offset: None,
+            limit: None,
+            collapse: None,
+        }).await.unwrap();
+        assert_eq!(works_for.len(), 1);
+
+        let knows = store.query(&tenant, GraphQuery::FindRelationships {
+            from_node_id: Some(bob_id),
+            to_node_id: Some(result.node_id),
+            relationship_types: vec!["KNOWS".to_string()],
+            valid_at: None,
+            order_by: vec![],
+            offset: None,
+            limit: None,
+            collapse: None,
+        }).await.unwrap();
+        assert_eq!(knows.len(), 1);
+
+        // An unresolvable target fails the whole operation without writing the node
+        let (nodes_before, edges_before) = store.stats().await;
+        let carol = Node::new("Person").with_id_alias("carol");
+        let edges = vec![
+            EdgeSpec::new("WORKS_FOR", NodeRef::Alias(AliasKey::new("acme"))),
+            EdgeSpec::new("KNOWS", NodeRef::Alias(AliasKey::new("nobody"))),
+        ];
+        let result = store.upsert_node_with_edges(&tenant, carol, edges).await;
+        assert!(matches!(result, Err(GraphError::NodeNotFound(_))));
+        assert_eq!(store.stats().await, (nodes_before, edges_before));
+        assert!(store.get_node_by_alias(&tenant, "carol").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_alias_addressed_operations() {
+        let store = Arc::new(InMemoryStore::new());
+        let service = CoreGraphService::new(store.clone());
+        let tenant = TenantId::new("test_tenant");
+        let crm_alice = AliasKey::namespaced("crm", "alice");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice").with_alias_namespace("crm")
+            .with_props(json!({"name": "Alice", "title": "Engineer"}))).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+
+        let record = service.get_node_by_alias(&tenant, &crm_alice).await.unwrap().unwrap();
+        assert_eq!((record.id, record.node.label.as_str()), (alice_id, "Person"));
+        assert!(service.get_node_by_alias(&tenant, &AliasKey::new("alice")).await.unwrap().is_none());
+
+        // Null removes a property, the node keeps its ID
+        let patch = json!({"title": null, "age": 36}).as_object().unwrap().clone();
+        let record = service.patch_node_by_alias(&tenant, &crm_alice, patch).await.unwrap().unwrap();
+        assert_eq!(record.id, alice_id);
+        assert_eq!(store.get_node(&tenant, alice_id).await.unwrap().unwrap().props, json!({"name": "Alice", "age": 36}));
+
+        let edge = EdgeByRef::new(NodeRef::Alias(crm_alice.clone()), NodeRef::Id(acme_id), "WORKS_FOR");
+        service.upsert_edge_by_ref(&tenant, edge).await.unwrap();
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 1));
+        let dangling = EdgeByRef::new(NodeRef::Alias(AliasKey::new("nobody")), NodeRef::Id(acme_id), "WORKS_FOR");
+        assert!(matches!(service.upsert_edge_by_ref(&tenant, dangling).await, Err(GraphError::NodeNotFound(_))));
+
+        assert_eq!(service.delete_node_by_alias(&tenant, &crm_alice).await.unwrap(), Some(alice_id));
+        assert_eq!(service.delete_node_by_alias(&tenant, &crm_alice).await.unwrap(), None);
+        assert_eq!(store.tenant_stats(&tenant).await.0, 1);
+    }
+
+    #[tokio::test]
+    async fn test_apply_envelope() {
+        let store = Arc::new(InMemoryStore::new());
+        let applier = EnvelopeApplier::new(Arc::new(CoreGraphService::new(store.clone())), EnvelopePolicies::default());
+        let tenant = TenantId::new("test_tenant");
+        let joined_globex = Utc::now() - chrono::Duration::days(365);
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice").with_props(json!({"name": "Alice"}))).await.unwrap();
+        let globex_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("globex")).await.unwrap();
+        let globex_edge = store.upsert_edge(&tenant, TimeEdge::new(alice_id, globex_id, "CURRENT_EMPLOYER", joined_globex, json!({}))).await.unwrap();
+        store.set_edge_constraints(&tenant, EdgeConstraints::default().with_exclusive("CURRENT_EMPLOYER", ExclusivityPolicy::Reject)).await.unwrap();
+
+        let node = |alias: &str, label: &str, props: serde_json::Value, confidence: f32| ExtractionNode {
+            id_alias: alias.to_string(), label: label.to_string(), props, confidence: Some(confidence),
+        };
+        let relation = |from: &str, kind: &str, to: &str| ExtractionRelation {
+            from_id_alias: from.to_string(), to_id_alias: to.to_string(), type_label: kind.to_string(),
+            props: json!({}), valid_from: None, valid_to: None, confidence: Some(0.9),
+        };
+        let envelope = ExtractionEnvelope {
+            nodes: vec![
+                node("alice", "Person", json!({"title": "CTO"}), 0.9),
+                node("acme", "Company", json!({}), 0.95),
+                node("ghost", "Person", json!({}), 0.2),
+            ],
+            relations: vec![relation("alice", "CURRENT_EMPLOYER", "acme"), relation("ghost", "KNOWS", "alice")],
+            metadata: None,
+        };
+        let request = |contradictions| ApplyEnvelopeRequest {
+            origin: Some("agent".to_string()),
+            min_confidence: Some(0.5),
+            contradictions: Some(contradictions),
+            ..ApplyEnvelopeRequest::new(envelope.clone())
+        };
+
+        // Contradicting the current employer fails before anything is written
+        let result = applier.apply(&tenant, request(ContradictionPolicy::Reject)).await;
+        assert!(matches!(result, Err(GraphError::ConstraintViolation(_))));
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 1));
+
+        let report = applier.apply(&tenant, request(ContradictionPolicy::Skip)).await.unwrap();
+        assert_eq!(report.nodes.keys().collect::<Vec<_>>(), vec!["acme", "alice"]);
+        assert_eq!(report.nodes["alice"], alice_id);
+        assert!(report.edges.is_empty());
+        assert_eq!(report.skipped.iter().map(|skipped| skipped.item.as_str()).collect::<Vec<_>>(),
+            vec!["ghost", "alice -CURRENT_EMPLOYER-> acme", "ghost -KNOWS-> alice"]);
+        let alice = store.get_node(&tenant, alice_id).await.unwrap().unwrap();
+        assert_eq!((alice.props["name"].clone(), alice.props["title"].clone()), (json!("Alice"), json!("CTO")));
+        assert_eq!(alice.props["provenance"]["origin"], json!("agent"));
+
+        let report = applier.apply(&tenant, request(ContradictionPolicy::Supersede)).await.unwrap();
+        assert_eq!((report.edges.len(), report.superseded.clone()), (1, vec![globex_edge]));
+
+        // A write that fails undoes the writes before it
+        let failing = ExtractionEnvelope {
+            nodes: vec![node("carol", "Person", json!({}), 0.9), node("dave", "Person", json!({"_tenant_id": "other"}), 0.9)],
+            relations: vec![],
+            metadata: None,
+        };
+        assert!(applier.apply(&tenant, ApplyEnvelopeRequest::new(failing)).await.is_err());
+        assert!(store.get_node_by_alias(&tenant, "carol").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_extraction_lineage() {
+        let store = Arc::new(InMemoryStore::new());
+        let service: Arc<dyn GraphService> = Arc::new(CoreGraphService::new(store.clone()));
+        let lineage = Arc::new(ExtractionLineage::new(service.clone()));
+        let applier = EnvelopeApplier::new(service, EnvelopePolicies::default()).with_lineage(lineage.clone());
+        let tenant = TenantId::new("test_tenant");
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+
+        let envelope = |model: &str, company: &str| ExtractionEnvelope {
+            nodes: vec![
+                ExtractionNode { id_alias: "alice".to_string(), label: "Person".to_string(), props: json!({"title": "CTO"}), confidence: None },
+                ExtractionNode { id_alias: company.to_string(), label: "Company".to_string(), props: json!({}), confidence: None },
+            ],
+            relations: vec![ExtractionRelation {
+                from_id_alias: "alice".to_string(), to_id_alias: company.to_string(), type_label: "WORKS_FOR".to_string(),
+                props: json!({}), valid_from: None, valid_to: None, confidence: None,
+            }],
+            metadata: Some(ExtractionMetadata {
+                provider: "openai".to_string(),
+                model_name: model.to_string(),
+                cost_usd: Some(0.002),
+                warnings: vec!["truncated input".to_string()],
+                ..Default::default()
+            }),
+        };
+        let good = applier.apply(&tenant, ApplyEnvelopeRequest {
+            prompt_version: Some("v1".to_string()),
+            ..ApplyEnvelopeRequest::new(envelope("gpt-4o", "acme"))
+        }).await.unwrap();
+        let bad = applier.apply(&tenant, ApplyEnvelopeRequest {
+            prompt_version: Some("v2".to_string()),
+            ..ApplyEnvelopeRequest::new(envelope("gpt-4o-mini", "globex"))
+        }).await.unwrap();
+        let (good_run, bad_run) = (good.run_id.unwrap(), bad.run_id.unwrap());
+        let alice = store.get_node(&tenant, alice_id).await.unwrap().unwrap();
+        assert_eq!(alice.props["provenance"]["run_id"], json!(bad_run.to_string()));
+
+        // Which model and prompt produced a fact
+        let producers = lineage.producers(&tenant, good.edges[0]).await.unwrap();
+        assert_eq!(producers.len(), 1);
+        assert_eq!((producers[0].model.as_deref(), producers[0].prompt_version.as_deref()), (Some("gpt-4o"), Some("v1")));
+        assert_eq!(producers[0].warnings, vec!["truncated input"]);
+        let producers = lineage.producers(&tenant, alice_id).await.unwrap();
+        assert_eq!(producers.iter().map(|run| run.id).collect::<Vec<_>>(), vec![bad_run, good_run]);
+
+        let filter = RunFilter { prompt_version: Some("v2".to_string()), ..Default::default() };
+        let runs = lineage.runs(&tenant, &filter).await.unwrap();
+        assert_eq!((runs.len(), runs[0].id, runs[0].nodes, runs[0].edges), (1, bad_run, 2, 1));
+        assert!(lineage.runs(&TenantId::new("other"), &RunFilter::default()).await.unwrap().is_empty());
+
+        // Purging the bad run removes what it created and keeps what it only updated
+        let report = lineage.purge(&tenant, bad_run).await.unwrap();
+        assert_eq!(report.deleted_nodes, vec![bad.nodes["globex"]]);
+        assert_eq!(report.deleted_edges, bad.edges);
+        assert_eq!(report.kept_nodes, vec![alice_id]);
+        assert!(store.get_node_by_alias(&tenant, "globex").await.unwrap().is_none());
+        assert!(store.get_node_by_alias(&tenant, "acme").await.unwrap().is_some());
+        assert!(lineage.run(&tenant, bad_run).await.unwrap().unwrap().purged_at.is_some());
+        assert!(matches!(lineage.purge(&tenant, Uuid::new_v4()).await, Err(GraphError::NodeNotFound(_))));
+    }
+
+    /// Embeds a text as its length and number of lines
+    struct ShapeEmbedder;
+
+    #[async_trait]
+    impl Embedder for ShapeEmbedder {
+        fn provider(&self) -> &str {
+            "test"
+        }
+
+        fn max_batch_size(&self) -> usize {
+            8
+        }
+
+        async fn embed(&self, _tenant: &TenantId, texts: &[String]) -> Result<Vec<Result<Vec<f32>, LlmError>>, LlmError> {
+            Ok(texts.iter().map(|text| Ok(vec![text.len() as f32, text.lines().count() as f32])).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embeddings() {
+        use telamentis_core::vector_index::{DiskVectorIndex, DiskVectorIndexConfig};
+
+        let store = Arc::new(InMemoryStore::new());
+        let service: Arc<dyn GraphService> = Arc::new(CoreGraphService::new(store.clone()));
+        let vectors = Arc::new(DiskVectorIndex::open(DiskVectorIndexConfig {
+            dir: std::env::temp_dir().join(format!("telamentis-embeddings-{}", Uuid::new_v4())),
+            sync_writes: false,
+            ..DiskVectorIndexConfig::default()
+        }).unwrap());
+        let config = EmbeddingConfig { text_properties: vec!["name".to_string()], backfill_page_size: 2, ..Default::default() };
+        let embeddings = Arc::new(EmbeddingOrchestrator::new(Arc::new(ShapeEmbedder), vectors.clone(), config));
+        let tenant = TenantId::new("test_tenant");
+
+        // Applied envelopes are embedded as they are written
+        let applier = EnvelopeApplier::new(service.clone(), EnvelopePolicies::default()).with_embeddings(embeddings.clone());
+        let envelope = ExtractionEnvelope {
+            nodes: vec![ExtractionNode { id_alias: "acme".to_string(), label: "Company".to_string(), props: json!({"name": "Acme"}), confidence: None }],
+            relations: vec![],
+            metadata: None,
+        };
+        let report = applier.apply(&tenant, ApplyEnvelopeRequest::new(envelope)).await.unwrap();
+        let matches = vectors.search(&tenant, &["Company\nname: Acme".len() as f32, 2.0], 1).await.unwrap();
+        assert_eq!(matches[0].id, report.nodes["acme"]);
+
+        // The backfill embeds the nodes written before, a page at a time
+        for name in ["Alice", "Bob", "Carol"] {
+            store.upsert_node(&tenant, Node::new("Person").with_props(json!({"name": name}))).await.unwrap();
+        }
+        store.upsert_node(&tenant, Node::new("Person")).await.unwrap();
+        let backfill = EmbeddingBackfill::new(service, embeddings.clone());
+        assert_eq!(backfill.run(&tenant).await.unwrap(), 4);
+        assert_eq!(vectors.count(&tenant).await.unwrap(), 4);
+
+        // Acme's text is unchanged, so it comes from the cache
+        let stats = embeddings.stats();
+        assert_eq!((stats.embedded, stats.cache_hits), (4, 1));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_resumes_from_checkpoint() {
+        let store = Arc::new(InMemoryStore::new());
+        let service: Arc<dyn GraphService> = Arc::new(CoreGraphService::new(store.clone()));
+        let tenant = TenantId::new("test_tenant");
+        let mut ids = Vec::new();
+        for name in ["alice", "bob", "carol"] {
+            ids.push(store.upsert_node(&tenant, Node::new("person").with_id_alias(name)).await.unwrap());
+        }
+        for _ in 0..2 {
+            ids.push(store.upsert_node(&tenant, Node::new("person")).await.unwrap());
+        }
+        store.upsert_edge(&tenant, TimeEdge::new(ids[0], ids[1], "knows", Utc::now(), json!({}))).await.unwrap();
+
+        let relabel = Relabel::new().with_label("person", "Person").with_kind("knows", "KNOWS");
+        let job = BackfillJob::new(service, Arc::new(relabel))
+            .with_config(BackfillConfig { page_size: 2, max_records_per_run: Some(3), ..Default::default() });
+
+        // The first run stops after three nodes, the next picks up after them
+        assert_eq!(job.run(&tenant).await.unwrap(), 3);
+        let progress = job.progress();
+        assert_eq!((progress[0].processed, progress[0].total, progress[0].finished), (3, Some(6), false));
+        assert!(!job.checkpoint(&tenant).unwrap().edges);
+        assert_eq!(job.run(&tenant).await.unwrap(), 3);
+        let progress = job.progress();
+        assert_eq!((progress[0].processed, progress[0].changed, progress[0].finished), (6, 6, true));
+        assert!(job.checkpoint(&tenant).is_none());
+
+        let summary = store.summary(&tenant).await.unwrap();
+        assert_eq!(summary.nodes_by_label, HashMap::from([("Person".to_string(), 5)]));
+        let (alice, node) = store.get_node_by_alias(&tenant, "alice").await.unwrap().unwrap();
+        assert_eq!((alice, node.label.as_str()), (ids[0], "Person"));
+        assert_eq!(store.get_node_history(&tenant, alice).await.unwrap().len(), 2);
+        let knows = |kind: &str| GraphQuery::FindRelationships {
+            from_node_id: Some(ids[0]),
+            to_node_id: None,
+            relationship_types: vec![kind.to_string()],
+            valid_at: None,
+            order_by: vec![],
+            offset: None,
+            limit: None,
+            collapse: None,
+        };
+        assert_eq!(store.query(&tenant, knows("KNOWS")).await.unwrap().len(), 1);
+        assert!(store.query(&tenant, knows("knows")).await.unwrap().is_empty());
+
+        // A finished backfill starts over, with nothing left to change
+        assert_eq!(job.run(&tenant).await.unwrap(), 0);
+        assert_eq!(job.progress()[0].processed, 3);
+    }
+
+    /// Relabels nodes, failing the first page it gets that holds `fail_on`
+    struct FlakyRelabel {
+        relabel: Relabel,
+        fail_on: Uuid,
+        failed: std::sync::atomic::AtomicBool,
+        visited: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl BackfillTransform for FlakyRelabel {
+        fn target(&self) -> BackfillTarget {
+            BackfillTarget::Nodes
+        }
+
+        async fn transform_nodes(&self, tenant: &TenantId, nodes: Vec<NodeRecord>) -> Result<Transformed<NodeRecord>, CoreError> {
+            use std::sync::atomic::Ordering;
+            if nodes.iter().any(|record| record.id == self.fail_on) && !self.failed.swap(true, Ordering::SeqCst) {
+                return Err(CoreError::Internal("model unavailable".to_string()));
+            }
+            self.visited.fetch_add(nodes.len(), Ordering::SeqCst);
+            self.relabel.transform_nodes(tenant, nodes).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backfill_interrupted_run_resumes() {
+        use std::sync::atomic::Ordering;
+        let store = Arc::new(InMemoryStore::new());
+        let service: Arc<dyn GraphService> = Arc::new(CoreGraphService::new(store.clone()));
+        let tenant = TenantId::new("test_tenant");
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            ids.push(store.upsert_node(&tenant, Node::new("person")).await.unwrap());
+        }
+        // Nodes are visited in order of ID
+        ids.sort();
+
+        let transform = Arc::new(FlakyRelabel {
+            relabel: Relabel::new().with_label("person", "Person"),
+            fail_on: ids[2],
+            failed: Default::default(),
+            visited: Default::default(),
+        });
+        let job = BackfillJob::new(service, transform.clone())
+            .with_config(BackfillConfig { page_size: 2, pause_ms: 20, ..Default::default() });
+
+        // The second page fails the run, which keeps the first
+        assert!(job.run(&tenant).await.is_err());
+        assert_eq!(job.checkpoint(&tenant), Some(BackfillCheckpoint { edges: false, after: Some(ids[1]) }));
+        let progress = job.progress();
+        assert_eq!((progress[0].processed, progress[0].changed, progress[0].total, progress[0].finished), (2, 2, Some(5), false));
+        assert_eq!(progress[0].last_error.as_deref(), Some("Internal error: model unavailable"));
+        async fn labels(store: &InMemoryStore, tenant: &TenantId, ids: &[Uuid]) -> Vec<String> {
+            let mut labels = Vec::new();
+            for id in ids {
+                labels.push(store.get_node(tenant, *id).await.unwrap().unwrap().label);
+            }
+            labels
+        }
+        assert_eq!(labels(&store, &tenant, &ids).await, ["Person", "Person", "person", "person", "person"]);
+
+        // The next run resumes after the checkpoint, pausing between its two pages
+        let started = std::time::Instant::now();
+        assert_eq!(job.run(&tenant).await.unwrap(), 3);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(40));
+        assert_eq!(transform.visited.load(Ordering::SeqCst), 5);
+        let progress = job.progress();
+        assert_eq!((progress[0].processed, progress[0].changed, progress[0].finished), (5, 5, true));
+        assert!(job.checkpoint(&tenant).is_none());
+        assert_eq!(labels(&store, &tenant, &ids).await, ["Person"; 5]);
+    }
+
+    #[tokio::test]
+    async fn test_seed_fixture() {
+        let store = Arc::new(InMemoryStore::new());
+        let loader = FixtureLoader::new(Arc::new(CoreGraphService::new(store.clone())));
+        let tenant = TenantId::new("demo");
+        let acme = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+
+        let fixture = Fixture::from_json(r#"{
+            "valid_from": "2024-01-01T00:00:00Z",
+            "tenants": {
+                "demo": {
+                    "nodes": [{"id_alias": "alice", "label": "Person"}, {"id_alias": "bob", "label": "Person"}],
+                    "edges": [
+                        {"from": "alice", "to": "bob", "kind": "KNOWS"},
+                        {"from": "alice", "to": "acme", "kind": "WORKS_FOR", "valid_to": "2024-06-01T00:00:00Z"}
+                    ]
+                }
+            }
+        }"#).unwrap();
+
+        let report = loader.apply(&fixture, false).await.unwrap();
+        assert_eq!(report.tenants["demo"], TenantSeedReport { nodes: 2, edges: 2, removed: 0 });
+        let (alice, _) = store.get_node_by_alias(&tenant, "alice").await.unwrap().unwrap();
+        let works_for = Query::relationships().from(alice).rel_type("WORKS_FOR").build();
+        let paths = store.query(&tenant, works_for).await.unwrap();
+        assert_eq!(paths[0].relationships[0].end_node_id, acme);
+
+        // Reseeding with reset replaces the tenant's graph instead of adding to it
+        let mut fixture = fixture;
+        fixture.tenants.get_mut("demo").unwrap().edges.truncate(1);
+        let report = loader.apply(&fixture, true).await.unwrap();
+        assert_eq!(report.tenants["demo"].removed, 5);
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 1));
+
+        let mut dangling = fixture.clone();
+        dangling.tenants.get_mut("demo").unwrap().edges[0].to = "nobody".to_string();
+        assert!(loader.apply(&dangling, false).await.is_err());
+
+        loader.teardown(&fixture).await.unwrap();
+        assert_eq!(store.tenant_stats(&tenant).await, (0, 0));
+    }
+
+    /// Sets `full_name` on Person nodes from their first and last names
+    struct FullName;
+
+    #[async_trait]
+    impl OnNodeUpsert for FullName {
+        fn name(&self) -> &'static str {
+            "full_name"
+        }
+
+        fn order(&self) -> i32 {
+            10
+        }
+
+        async fn on_node_upsert(&self, _ctx: &HookContext<'_>, node: &mut Node) -> Result<(), GraphError> {
+            if node.label == "Person" {
+                let full_name = format!("{} {}", node.props["first"].as_str().unwrap_or(""), node.props["last"].as_str().unwrap_or(""));
+                node.props["full_name"] = json!(full_name.trim());
+            }
+            Ok(())
+        }
+    }
+
+    /// Fails after tagging the node, with its failures skipped
+    struct Flaky;
+
+    #[async_trait]
+    impl OnNodeUpsert for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn on_error(&self) -> HookErrorPolicy {
+            HookErrorPolicy::Skip
+        }
+
+        async fn on_node_upsert(&self, _ctx: &HookContext<'_>, node: &mut Node) -> Result<(), GraphError> {
+            node.props["flaky"] = json!(true);
+            Err(GraphError::QueryFailed("flaky hook".to_string()))
+        }
+    }
+
+    /// Copies the employer's name onto WORKS_FOR edges, refusing edges to
+    /// unknown nodes
+    struct EmployerName;
+
+    #[async_trait]
+    impl OnEdgeUpsert for EmployerName {
+        fn name(&self) -> &'static str {
+            "employer_name"
+        }
+
+        async fn on_edge_upsert(&self, ctx: &HookContext<'_>, edge: &mut TimeEdge) -> Result<(), GraphError> {
+            if edge.kind == "WORKS_FOR" {
+                let employer = ctx.store.get_node(ctx.tenant, edge.to_node_id).await?
+                    .ok_or_else(|| GraphError::NodeNotFound(edge.to_node_id.to_string()))?;
+                edge.props["employer"] = employer.props["name"].clone();
+            }
+            Ok(())
+        }
+    }
+
+    /// Removes `ssn` from nodes in query results
+    struct RedactSsn;
+
+    #[async_trait]
+    impl OnQueryResult for RedactSsn {
+        fn name(&self) -> &'static str {
+            "redact_ssn"
+        }
+
+        async fn on_query_result(&self, _ctx: &HookContext<'_>, _query: &GraphQuery, paths: &mut Vec<Path>) -> Result<(), GraphError> {
+            for node in paths.iter_mut().flat_map(|path| path.nodes.iter_mut()) {
+                if let Some(props) = node.properties.as_object_mut() {
+                    props.remove("ssn");
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks() {
+        let store = Arc::new(InMemoryStore::new());
+        let hooks = Hooks::new()
+            .with_node_upsert(Arc::new(FullName))
+            .with_node_upsert(Arc::new(Flaky))
+            .with_edge_upsert(Arc::new(EmployerName))
+            .with_query_result(Arc::new(RedactSsn));
+        let service = CoreGraphService::new(store.clone()).with_hooks(hooks);
+        let tenant = TenantId::new("test_tenant");
+
+        let person = Node::new("Person").with_id_alias("alice").with_props(json!({"first": "Alice", "last": "Smith", "ssn": "123"}));
+        let alice = service.upsert_node(&tenant, person).await.unwrap();
+        let acme = service.upsert_node(&tenant, Node::new("Company").with_props(json!({"name": "Acme"}))).await.unwrap();
+
+        let stored = store.get_node(&tenant, alice).await.unwrap().unwrap();
+        assert_eq!(stored.props["full_name"], "Alice Smith");
+        assert!(stored.props.get("flaky").is_none());
+        assert_eq!(stored.props["ssn"], "123");
+
+        service.upsert_edge(&tenant, TimeEdge::new(alice, acme, "WORKS_FOR", Utc::now(), json!({}))).await.unwrap();
+        let works_for = Query::relationships().from(alice).rel_type("WORKS_FOR").build();
+        let paths = service.query(&tenant, works_for).await.unwrap();
+        assert_eq!(paths[0].relationships[0].properties["employer"], "Acme");
+        assert!(!paths[0].nodes.is_empty() && paths[0].nodes.iter().all(|node| node.properties.get("ssn").is_none()));
+
+        // A failing hook that does not skip its failures aborts the write
+        let dangling = TimeEdge::new(alice, Uuid::new_v4(), "WORKS_FOR", Utc::now(), json!({}));
+        assert!(matches!(service.upsert_edge(&tenant, dangling).await, Err(GraphError::NodeNotFound(_))));
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+        let other = TenantId::new("other_tenant");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        store.upsert_node(&other, Node::new("Person").with_id_alias("mallory")).await.unwrap();
+
+        let past: DateTime<Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+        let ended: DateTime<Utc> = "2021-01-01T00:00:00Z".parse().unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", past, json!({}))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "INTERNED_AT", past, json!({})).with_valid_to(ended)).await.unwrap();
+
+        let snapshot = store.snapshot(&tenant, None).await.unwrap();
+        assert_eq!(snapshot.nodes.len(), 2);
+        assert_eq!(snapshot.edges.len(), 2);
+        assert!(snapshot.nodes.iter().all(|record| record.node.id_alias.as_deref() != Some("mallory")));
+
+        // Valid-time filter only keeps edges valid at that instant
+        let snapshot = store.snapshot(&tenant, Some(Utc::now())).await.unwrap();
+        assert_eq!(snapshot.edges.len(), 1);
+        assert_eq!(snapshot.edges[0].edge.kind, "WORKS_FOR");
+        assert!(snapshot.valid_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_expiring_edges() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let page_id = store.upsert_node(&tenant, Node::new("Page").with_id_alias("home")).await.unwrap();
+
+        let now = Utc::now();
+        let lapsed = store.upsert_edge(&tenant, TimeEdge::new(alice_id, page_id, "VIEWING", now, json!({}))
+            .with_expires_at(now - chrono::Duration::seconds(1))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, page_id, "VIEWING", now, json!({}))
+            .with_expires_at(now + chrono::Duration::hours(1))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, page_id, "BOOKMARKED", now, json!({}))).await.unwrap();
+
+        // Expired edges are gone from reads before they are removed
+        let viewing = GraphQuery::FindRelationships {
+            from_node_id: Some(alice_id),
+            to_node_id: None,
+            relationship_types: vec!["VIEWING".to_string()],
+            valid_at: None,
+            order_by: vec![],
+            offset: None,
+            limit: None,
+            collapse: None,
+        };
+        assert_eq!(store.query_count(&tenant, viewing.clone()).await.unwrap(), 1);
+        assert_eq!(store.snapshot(&tenant, None).await.unwrap().edges.len(), 2);
+        assert!(matches!(store.close_edge(&tenant, lapsed, now).await, Err(GraphError::EdgeNotFound(_))));
+
+        assert_eq!(store.purge_expired_edges(&tenant, Utc::now()).await.unwrap(), 1);
+        assert_eq!(store.tenant_stats(&tenant).await, (2, 2));
+        assert_eq!(store.purge_expired_edges(&tenant, Utc::now()).await.unwrap(), 0);
+
+        // Unlike closed or deleted edges, expired ones are not kept as history
+        assert!(store.read_history(&tenant, Utc::now() + chrono::Duration::days(1)).await.unwrap().edges.is_empty());
+
+        // An hour on, the other viewing edge has expired as well
+        assert_eq!(store.purge_expired_edges(&tenant, now + chrono::Duration::hours(2)).await.unwrap(), 1);
+        assert_eq!(store.query_count(&tenant, viewing).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_edge_expiry_sweeper() {
+        let store = Arc::new(InMemoryStore::new());
+        let tenant = TenantId::new("test_tenant");
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let now = Utc::now();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, alice_id, "ACTIVE_SESSION", now, json!({}))
+            .with_expires_at(now - chrono::Duration::seconds(1))).await.unwrap();
+        assert_eq!(store.tenant_stats(&tenant).await, (1, 1));
+
+        let sweeper = EdgeExpirySweeper::start(store.clone(), EdgeExpiryConfig { sweep_interval_ms: 10 });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        sweeper.shutdown().await;
+        assert_eq!(store.tenant_stats(&tenant).await, (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_edge_corrections() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let globex_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("globex")).await.unwrap();
+
+        let started: DateTime<Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+        let left: DateTime<Utc> = "2022-01-01T00:00:00Z".parse().unwrap();
+        let works_for = store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", started, json!({}))).await.unwrap();
+        let before = Utc::now();
+
+        // Closing keeps the open-ended version as history
+        let closed = store.close_edge(&tenant, works_for, left).await.unwrap();
+        assert!(matches!(store.close_edge(&tenant, works_for, left).await, Err(GraphError::ConstraintViolation(_))));
+
+        let current = |types: Vec<&str>| GraphQuery::FindRelationships {
+            from_node_id: Some(alice_id),
+            to_node_id: None,
+            relationship_types: types.into_iter().map(String::from).collect(),
+            valid_at: None,
+            order_by: Vec::new(),
+            offset: None,
+            limit: None,
+            collapse: None,
+        };
+        let paths = store.query(&tenant, current(vec!["WORKS_FOR"])).await.unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].relationships[0].id, closed);
+
+        let snapshot = store.snapshot(&tenant, None).await.unwrap();
+        assert_eq!(snapshot.edges.len(), 1);
+        assert_eq!(snapshot.edges[0].id, closed);
+        let history: Vec<_> = store.store.read().await.edges.values()
+            .filter(|stored| stored.edge.existed_at_transaction_time(before))
+            .map(|stored| stored.id)
+            .collect();
+        assert_eq!(history, vec![works_for]);
+
+        // Superseding replaces the fact with a corrected one
+        let corrected = TimeEdge::new(alice_id, globex_id, "WORKS_FOR", started, json!({})).with_valid_to(left);
+        let superseded = store.supersede_edge(&tenant, closed, corrected).await.unwrap();
+        let paths = store.query(&tenant, current(vec!["WORKS_FOR"])).await.unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].nodes[1].id, globex_id);
+
+        // Retracting leaves no current version
+        assert!(store.retract_edge(&tenant, superseded).await.unwrap());
+        assert!(!store.retract_edge(&tenant, superseded).await.unwrap());
+        assert!(store.query(&tenant, current(vec![])).await.unwrap().is_empty());
+        assert!(matches!(store.close_edge(&tenant, Uuid::new_v4(), left).await, Err(GraphError::EdgeNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_timeline() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let bob_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("bob")).await.unwrap();
+
+        let day = |d: u32| -> DateTime<Utc> { format!("2024-05-{:02}T09:00:00Z", d).parse().unwrap() };
+        let works_for = store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", day(2), json!({}))).await.unwrap();
+        let closed = store.close_edge(&tenant, works_for, day(16)).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(bob_id, alice_id, "KNOWS", day(8), json!({}))).await.unwrap();
+        let retracted = store.upsert_edge(&tenant, TimeEdge::new(alice_id, bob_id, "MANAGES", day(9), json!({}))).await.unwrap();
+        store.retract_edge(&tenant, retracted).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(bob_id, acme_id, "WORKS_FOR", day(3), json!({}))).await.unwrap();
+
+        // 2024-04-29 and the following Mondays start the weeks
+        let mut request = TimelineRequest::new(alice_id, TimelineInterval::Week);
+        request.from = Some(day(1));
+        request.to = Some(day(20));
+        let timeline = store.timeline(&tenant, request.clone()).await.unwrap();
+        let counts: Vec<_> = timeline.buckets.iter().map(|bucket| (bucket.created, bucket.closed)).collect();
+        assert_eq!(counts, vec![(1, 0), (1, 0), (0, 1), (0, 0)]);
+        assert_eq!(timeline.buckets[2].events[0].edge_id, closed);
+
+        request.relationship_types = vec!["KNOWS".to_string()];
+        let timeline = store.timeline(&tenant, request.clone()).await.unwrap();
+        assert_eq!(timeline.buckets.iter().map(|bucket| bucket.created).sum::<u64>(), 1);
+
+        request.node = Uuid::new_v4();
+        assert!(matches!(store.timeline(&tenant, request).await, Err(GraphError::NodeNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_property_history() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let deal = |props: serde_json::Value| Node::new("Deal").with_id_alias("deal-7").with_props(props);
+        let deal_id = store.upsert_node(&tenant, deal(json!({"stage": "lead"}))).await.unwrap();
+        store.upsert_node(&tenant, deal(json!({"stage": "negotiation"}))).await.unwrap();
+        store.upsert_node(&tenant, deal(json!({"stage": "negotiation", "amount": 5000}))).await.unwrap();
+        store.upsert_node(&tenant, deal(json!({"stage": "won", "amount": 5000}))).await.unwrap();
+
+        assert_eq!(store.get_node_history(&tenant, deal_id).await.unwrap().len(), 4);
+
+        let history = store.property_history(&tenant, deal_id, "stage").await.unwrap();
+        let stages: Vec<_> = history.values.iter().map(|value| value.value.clone()).collect();
+        assert_eq!(stages, vec![json!("lead"), json!("negotiation"), json!("won")]);
+        // Each value holds until the next one
+        assert_eq!(history.values[0].valid_to, Some(history.values[1].valid_from));
+        assert_eq!(history.values[2].valid_to, None);
+        assert_eq!(history.current(), Some(&json!("won")));
+
+        let amounts = store.property_history(&tenant, deal_id, "amount").await.unwrap();
+        assert_eq!(amounts.values.len(), 1);
+        assert!(amounts.values[0].valid_from > history.values[1].valid_from);
+        assert_eq!(amounts.values[0].valid_to, None);
+
+        let other_tenant = TenantId::new("other_tenant");
+        assert!(matches!(store.property_history(&other_tenant, deal_id, "stage").await, Err(GraphError::NodeNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_edges() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let globex_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("globex")).await.unwrap();
+
+        let joined_acme: DateTime<Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+        let joined_globex: DateTime<Utc> = "2022-01-01T00:00:00Z".parse().unwrap();
+        let employer = |to, from| TimeEdge::new(alice_id, to, "CURRENT_EMPLOYER", from, json!({}));
+        let acme = store.upsert_edge(&tenant, employer(acme_id, joined_acme)).await.unwrap();
+
+        let constraints = EdgeConstraints::default().with_exclusive("CURRENT_EMPLOYER", ExclusivityPolicy::Reject);
+        store.set_edge_constraints(&tenant, constraints).await.unwrap();
+        assert!(matches!(
+            store.upsert_edge(&tenant, employer(globex_id, joined_globex)).await,
+            Err(GraphError::ConstraintViolation(_))
+        ));
+        // Other tenants and closing the edge are not affected
+        let other = TenantId::new("other_tenant");
+        assert!(store.edge_constraints(&other).await.unwrap().exclusive.is_empty());
+        store.close_edge(&tenant, acme, joined_globex).await.unwrap();
+        let globex = store.upsert_edge(&tenant, employer(globex_id, joined_globex)).await.unwrap();
+
+        // Auto-closing ends the overlapped edge when the new one starts
+        let constraints = EdgeConstraints::default().with_exclusive("CURRENT_EMPLOYER", ExclusivityPolicy::AutoClose);
+        store.set_edge_constraints(&tenant, constraints).await.unwrap();
+        let rejoined: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        store.upsert_edge(&tenant, employer(acme_id, rejoined)).await.unwrap();
+        let paths = store.query(&tenant, Query::relationships().from(alice_id).valid_at(rejoined).build()).await.unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].nodes[1].id, acme_id);
+        let paths = store.query(&tenant, Query::relationships().from(alice_id).build()).await.unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(paths.iter().all(|path| path.relationships[0].id != globex));
+
+        // An edge starting before the current one cannot close it
+        let edges = vec![EdgeSpec::new("CURRENT_EMPLOYER", NodeRef::Id(globex_id)).with_valid_from(joined_acme)];
+        assert!(matches!(
+            store.upsert_node_with_edges(&tenant, Node::new("Person").with_id_alias("alice"), edges).await,
+            Err(GraphError::ConstraintViolation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_materialized_snapshots() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+
+        let started: DateTime<Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+        let quarter_end: DateTime<Utc> = "2021-03-31T23:59:59Z".parse().unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", started, json!({}))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "ADVISES", "2022-01-01T00:00:00Z".parse().unwrap(), json!({}))).await.unwrap();
+
+        let info = store.materialize_snapshot(&tenant, "end-of-q1", quarter_end).await.unwrap();
+        assert_eq!((info.node_count, info.edge_count), (2, 1));
+
+        // Later writes do not change the snapshot
+        store.upsert_edge(&tenant, TimeEdge::new(acme_id, alice_id, "EMPLOYS", started, json!({}))).await.unwrap();
+        let relationships = GraphQuery::FindRelationships {
+            from_node_id: None,
+            to_node_id: None,
+            relationship_types: Vec::new(),
+            valid_at: None,
+            order_by: Vec::new(),
+            offset: None,
+            limit: None,
+            collapse: None,
+        };
+        let paths = store.query_snapshot(&tenant, "end-of-q1", relationships.clone()).await.unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].relationships[0].rel_type, "WORKS_FOR");
+        assert_eq!(store.query(&tenant, relationships.clone()).await.unwrap().len(), 3);
+
+        assert_eq!(store.list_snapshots(&tenant).await.unwrap(), vec![info]);
+        assert!(store.list_snapshots(&TenantId::new("other_tenant")).await.unwrap().is_empty());
+
+        assert!(store.drop_snapshot(&tenant, "end-of-q1").await.unwrap());
+        assert!(matches!(
+            store.query_snapshot(&tenant, "end-of-q1", relationships).await,
+            Err(GraphError::SnapshotNotFound(_))
+        ));
+    }
+
+    /// Sink that keeps segments in memory
+    #[derive(Default)]
+    struct MemorySink {
+        segments: std::sync::Mutex<Vec<(ArchiveSegment, ArchiveBatch)>>,
+    }
+
+    #[async_trait]
+    impl ArchiveSink for MemorySink {
+        async fn write_segment(&self, _tenant: &TenantId, batch: &ArchiveBatch) -> Result<ArchiveSegment, ArchiveError> {
+            let (from, to) = batch.time_range().unwrap();
+            let segment = ArchiveSegment {
+                id: Uuid::new_v4(),
+                from,
+                to,
+                node_count: batch.nodes.len() as u64,
+                edge_count: batch.edges.len() as u64,
+                objects: Vec::new(),
+                created_at: Utc::now(),
+            };
+            self.segments.lock().unwrap().push((segment.clone(), batch.clone()));
+            Ok(segment)
+        }
+
+        async fn manifest(&self, _tenant: &TenantId) -> Result<ArchiveManifest, ArchiveError> {
+            let segments = self.segments.lock().unwrap().iter().map(|(segment, _)| segment.clone()).collect();
+            Ok(ArchiveManifest { segments })
+        }
+
+        async fn read_segment(&self, _tenant: &TenantId, segment: &ArchiveSegment) -> Result<ArchiveBatch, ArchiveError> {
+            self.segments.lock().unwrap().iter()
+                .find(|(written, _)| written.id == segment.id)
+                .map(|(_, batch)| batch.clone())
+                .ok_or_else(|| ArchiveError::Corrupt(format!("Segment {} not found", segment.id)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_restore() {
+        let store = Arc::new(InMemoryStore::new());
+        let tenant = TenantId::new("test_tenant");
+        let job = ArchiveJob::new(store.clone(), Arc::new(MemorySink::default()));
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let bob_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("bob")).await.unwrap();
+
+        let started: DateTime<Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+        let works_for = store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", started, json!({}))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(bob_id, acme_id, "WORKS_FOR", started, json!({}))).await.unwrap();
+        let current = store.close_edge(&tenant, works_for, "2022-01-01T00:00:00Z".parse().unwrap()).await.unwrap();
+        assert!(store.delete_node(&tenant, bob_id).await.unwrap());
+        let archived_at = Utc::now();
+
+        // Exporting copies the closed version, the deleted node and its edge
+        let segment = job.export(&tenant, archived_at).await.unwrap().unwrap();
+        assert_eq!((segment.node_count, segment.edge_count), (1, 2));
+        assert_eq!(store.read_history(&tenant, archived_at).await.unwrap().len(), 3);
+
+        // Retaining purges what was archived, leaving current data alone
+        job.retain(&tenant, archived_at).await.unwrap().unwrap();
+        assert!(store.read_history(&tenant, archived_at).await.unwrap().is_empty());
+        assert!(job.retain(&tenant, archived_at).await.unwrap().is_none());
+        assert!(store.store.read().await.edges.contains_key(&current));
+        assert!(!store.store.read().await.edges.contains_key(&works_for));
+
+        let report = job.restore(&tenant, started, Utc::now()).await.unwrap();
+        assert_eq!((report.segments, report.records, report.restored), (2, 6, 3));
+        assert!(store.store.read().await.edges.contains_key(&works_for));
+        assert_eq!(store.read_history(&tenant, archived_at).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_summary() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+        let other = TenantId::new("other_tenant");
+        assert_eq!(store.summary(&tenant).await.unwrap(), GraphSummary::default());
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let bob_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("bob")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        store.upsert_node(&other, Node::new("Person").with_id_alias("mallory")).await.unwrap();
+        let past = "2020-01-01T00:00:00Z".parse().unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", past, json!({}))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(bob_id, acme_id, "WORKS_FOR", past, json!({}))).await.unwrap();
+
+        let summary = store.summary(&tenant).await.unwrap();
+        assert_eq!((summary.node_count, summary.edge_count), (3, 2));
+        assert_eq!(summary.nodes_by_label, HashMap::from([("Person".to_string(), 2), ("Company".to_string(), 1)]));
+        assert_eq!(summary.edges_by_kind, HashMap::from([("WORKS_FOR".to_string(), 2)]));
+        assert!(summary.last_write_at.is_some());
+        assert!(summary.storage_bytes_estimate > 0);
+
+        // Updating a node keeps the counts; deleting it removes its edges too
+        let bigger = Node::new("Person").with_id_alias("bob").with_props(json!({"bio": "x".repeat(100)}));
+        store.upsert_node(&tenant, bigger).await.unwrap();
+        assert!(store.summary(&tenant).await.unwrap().storage_bytes_estimate >= summary.storage_bytes_estimate + 100);
+
+        store.delete_node(&tenant, bob_id).await.unwrap();
+        let summary = store.summary(&tenant).await.unwrap();
+        assert_eq!((summary.node_count, summary.edge_count), (2, 1));
+        assert_eq!(summary.nodes_by_label["Person"], 1);
+        assert_eq!(summary.edges_by_kind["WORKS_FOR"], 1);
+        assert_eq!(store.summary(&other).await.unwrap().node_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_catalog() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let alice = Node::new("Person").with_id_alias("alice").with_props(json!({"name": "Alice", "age": 30}));
+        let alice_id = store.upsert_node(&tenant, alice).await.unwrap();
+        let bob_id = store.upsert_node(&tenant, Node::new("Person").with_props(json!({"name": "Bob"}))).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_props(json!({"name": "Acme"}))).await.unwrap();
+        let past = "2020-01-01T00:00:00Z".parse().unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", past, json!({"role": "CEO"}))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, bob_id, "KNOWS", past, json!({}))).await.unwrap();
+
+        let catalog = store.catalog(&tenant).await.unwrap();
+        let labels: Vec<_> = catalog.labels.iter().map(|entry| (entry.name.as_str(), entry.count)).collect();
+        assert_eq!(labels, [("Company", 1), ("Person", 2)]);
+        assert_eq!(catalog.labels[1].property_keys, HashMap::from([("name".to_string(), 2), ("age".to_string(), 1)]));
+        let kinds: Vec<_> = catalog.kinds.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(kinds, ["KNOWS", "WORKS_FOR"]);
+        assert_eq!(catalog.kinds[1].property_keys["role"], 1);
+
+        // Updates replace a node's keys, deletes drop empty labels and kinds
+        let alice = Node::new("Person").with_id_alias("alice").with_props(json!({"name": "Alice", "email": "a@example.com"}));
+        store.upsert_node(&tenant, alice).await.unwrap();
+        store.delete_node(&tenant, acme_id).await.unwrap();
+
+        let catalog = store.catalog(&tenant).await.unwrap();
+        assert_eq!(catalog.labels.len(), 1);
+        assert_eq!(
+            catalog.labels[0].property_keys,
+            HashMap::from([("name".to_string(), 2), ("email".to_string(), 1)])
+        );
+        let kinds: Vec<_> = catalog.kinds.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(kinds, ["KNOWS"]);
+    }
+
+    #[tokio::test]
+    async fn test_reserved_properties() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        // Spoofing a system key is rejected by default
+        let spoofed = Node::new("Person").with_props(json!({"name": "Mallory", "_tenant_id": "other"}));
+        let result = store.upsert_node(&tenant, spoofed).await;
+        assert!(matches!(result, Err(GraphError::ReservedProperty(_))));
+        assert_eq!(store.stats().await, (0, 0));
+
+        // With the strip policy the key is dropped and the write goes through
+        let config = InMemoryConfig {
+            system_properties: SystemProperties::default().with_policy(ReservedKeyPolicy::Strip),
+            ..Default::default()
+        };
+        let store = InMemoryStore::new_with_config(config);
+        let spoofed = Node::new("Person")
+            .with_id_alias("mallory")
+            .with_props(json!({"name": "Mallory", "_tenant_id": "other"}));
+        store.upsert_node(&tenant, spoofed).await.unwrap();
+
+        let stored = store.get_node_by_alias(&tenant, "mallory").await.unwrap().unwrap();
+        assert_eq!(stored.1.props, json!({"name": "Mallory"}));
+    }
+
+    #[tokio::test]
+    async fn test_limit_warnings() {
+        let tenant = TenantId::new("test_tenant");
+        let store = InMemoryStore::new_with_config(InMemoryConfig { max_nodes: Some(10), ..Default::default() });
+
+        let (_, warnings) = collect_warnings(async {
+            for i in 0..8 {
+                store.upsert_node(&tenant, Node::new("Person").with_id_alias(format!("p{}", i))).await.unwrap();
+            }
+        }).await;
+        assert!(warnings.is_empty());
+
+        let (_, warnings) = collect_warnings(store.upsert_node(&tenant, Node::new("Person"))).await;
+        assert_eq!(warnings, vec!["Store holds 9 of at most 10 nodes"]);
+    }
+
+    #[tokio::test]
+    async fn test_seeded_ids() {
+        let tenant = TenantId::new("test_tenant");
+        let config = InMemoryConfig { ids: IdStrategy::Seeded { seed: 7 }, ..Default::default() };
+
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let store = InMemoryStore::new_with_config(config.clone());
+            let alice = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+            let bob = store.upsert_node(&tenant, Node::new("Person").with_id_alias("bob")).await.unwrap();
+            let knows = store.upsert_edge(&tenant, TimeEdge::new(alice, bob, "KNOWS", Utc::now(), json!({}))).await.unwrap();
+            runs.push(vec![alice, bob, knows]);
+        }
+        assert_eq!(runs[0], runs[1]);
+        assert_eq!(runs[0][0], IdStrategy::Seeded { seed: 7 }.build().next_id());
+    }
+
+    #[tokio::test]
+    async fn test_temporal_validation() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+
+        let started = "2024-01-01T00:00:00Z".parse().unwrap();
+        let ended = "2023-01-01T00:00:00Z".parse().unwrap();
+        let inverted = TimeEdge::new(alice_id, acme_id, "WORKS_FOR", started, json!({})).with_valid_to(ended);
+
+        // Inverted intervals are rejected by default, including in atomic writes
+        let result = store.upsert_edge(&tenant, inverted.clone()).await;
+        assert!(matches!(result, Err(GraphError::Temporal(TemporalError::ValidToBeforeValidFrom { .. }))));
+        let spec = EdgeSpec::new("KNOWS", NodeRef::Id(alice_id)).with_valid_from(started).with_valid_to(ended);
+        let result = store.upsert_node_with_edges(&tenant, Node::new("Person").with_id_alias("bob"), vec![spec]).await;
+        assert!(matches!(result, Err(GraphError::Temporal(_))));
+        assert_eq!(store.stats().await, (2, 0));
+
+        // The repair policy clamps valid_to and keeps the client's transaction time
+        let config = InMemoryConfig {
+            temporal_validation: TemporalValidation::new(TemporalPolicy::Repair),
+            ..Default::default()
+        };
+        let store = InMemoryStore::new_with_config(config);
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let recorded = "2024-02-01T00:00:00Z".parse().unwrap();
+        let edge = TimeEdge::new(alice_id, acme_id, "WORKS_FOR", started, json!({}))
+            .with_valid_to(ended)
+            .with_transaction_start_time(recorded);
+        store.upsert_edge(&tenant, edge).await.unwrap();
+
+        let snapshot = store.snapshot(&tenant, None).await.unwrap();
+        assert_eq!(snapshot.edges[0].edge.valid_to, Some(started));
+        assert_eq!(snapshot.edges[0].edge.transaction_start_time, recorded);
+    }
+
+    #[tokio::test]
+    async fn test_sorted_pagination() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let mut ids = Vec::new();
+        for (name, age) in [("carol", json!(41)), ("alice", json!(30)), ("dave", json!(null)), ("bob", json!(30))] {
+            let node = Node::new("Person").with_id_alias(name).with_props(json!({"name": name, "age": age}));
+            ids.push(store.upsert_node(&tenant, node).await.unwrap());
+        }
+        store.upsert_node(&tenant, Node::new("Company").with_props(json!({"name": "acme"}))).await.unwrap();
+
+        let find = |order_by: Vec<OrderBy>, offset: Option<u32>, limit: Option<u32>| GraphQuery::FindNodes {
+            labels: vec!["Person".to_string()],
+            properties: HashMap::new(),
+            order_by,
+            offset,
+            limit,
+        };
+        let names = |paths: Vec<Path>| -> Vec<String> {
+            paths.iter().map(|path| path.nodes[0].properties["name"].as_str().unwrap().to_string()).collect()
+        };
+
+        // Ascending by age puts missing values last; ties are stable across pages
+        let by_age = vec![OrderBy::asc(SortField::Property("age".to_string()))];
+        let first = names(store.query(&tenant, find(by_age.clone(), None, Some(2))).await.unwrap());
+        let second = names(store.query(&tenant, find(by_age.clone(), Some(2), Some(2))).await.unwrap());
+        assert_eq!(first.len(), 2);
+        assert!(first.contains(&"alice".to_string()) && first.contains(&"bob".to_string()));
+        assert_eq!(second, ["carol", "dave"]);
+
+        let by_name_desc = vec![OrderBy::desc(SortField::Property("name".to_string()))];
+        let result = names(store.query(&tenant, find(by_name_desc, Some(1), None)).await.unwrap());
+        assert_eq!(result, ["carol", "bob", "alice"]);
+
+        // Creation order is read off the label index
+        let newest = vec![OrderBy::desc(SortField::CreatedAt)];
+        let result = store.query(&tenant, find(newest, None, Some(1))).await.unwrap();
+        assert_eq!(result[0].nodes[0].id, ids[3]);
+
+        // Label then name across all nodes
+        let query = GraphQuery::FindNodes {
+            labels: vec![],
+            properties: HashMap::new(),
+            order_by: vec![OrderBy::asc(SortField::Label), OrderBy::asc(SortField::Property("name".to_string()))],
+            offset: None,
+            limit: Some(2),
+        };
+        assert_eq!(names(store.query(&tenant, query).await.unwrap()), ["acme", "alice"]);
+
+        // Relationships sort by transaction time for created_at
+        for (i, &from) in ids.iter().enumerate() {
+            let recorded = format!("2024-0{}-01T00:00:00Z", 4 - i).parse().unwrap();
+            let edge = TimeEdge::new(from, ids[0], "KNOWS", "2023-01-01T00:00:00Z".parse().unwrap(), json!({}))
+                .with_transaction_start_time(recorded);
+            store.upsert_edge(&tenant, edge).await.unwrap();
+        }
+        let query = GraphQuery::FindRelationships {
+            from_node_id: None,
+            to_node_id: Some(ids[0]),
+            relationship_types: vec![],
+            valid_at: None,
+            order_by: vec![OrderBy::asc(SortField::CreatedAt)],
+            offset: Some(1),
+            limit: Some(2),
+            collapse: None,
+        };
+        let results = store.query(&tenant, query).await.unwrap();
+        let from: Vec<Uuid> = results.iter().map(|path| path.relationships[0].start_node_id).collect();
+        assert_eq!(from, [ids[2], ids[1]]);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_traversal() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+        let since = "2024-01-01T00:00:00Z".parse().unwrap();
+
+        let mut ids = Vec::new();
+        for name in ["home", "park", "shop", "work"] {
+            ids.push(store.upsert_node(&tenant, Node::new("Place").with_props(json!({"name": name}))).await.unwrap());
+        }
+        let [home, park, shop, work] = ids[..] else { unreachable!() };
+        for (from, to, weight) in [(home, work, 9.0), (home, park, 2.0), (park, shop, 2.0), (shop, work, 2.0)] {
+            store.upsert_edge(&tenant, TimeEdge::new(from, to, "ROAD", since, json!({})).with_weight(weight)).await.unwrap();
+        }
+        // Closed roads are not followed
+        let closed = store.upsert_edge(&tenant, TimeEdge::new(park, work, "ROAD", since, json!({}))).await.unwrap();
+        store.retract_edge(&tenant, closed).await.unwrap();
+
+        let fewest_hops = store.shortest_path(&tenant, ShortestPathRequest::new(home, work)).await.unwrap().unwrap();
+        assert_eq!((fewest_hops.path.relationships.len(), fewest_hops.weight), (1, 9.0));
+
+        let request = ShortestPathRequest { weighted: true, ..ShortestPathRequest::new(home, work) };
+        let lightest = store.shortest_path(&tenant, request).await.unwrap().unwrap();
+        let route: Vec<Uuid> = lightest.path.nodes.iter().map(|node| node.id).collect();
+        assert_eq!((route, lightest.weight), (vec![home, park, shop, work], 6.0));
+
+        // Ranked expansion reaches the nearest places first
+        let request = TraversalRequest { order: TraversalOrder::Weight, ..TraversalRequest::new(home) };
+        let reached: Vec<Uuid> = store.traverse(&tenant, request).await.unwrap()
+            .iter()
+            .map(|path| path.path.nodes.last().unwrap().id)
+            .collect();
+        assert_eq!(reached, [park, shop, work]);
+
+        let request = TraversalRequest { direction: TraversalDirection::Incoming, ..TraversalRequest::new(work) };
+        assert_eq!(store.traverse(&tenant, request).await.unwrap().len(), 3);
+        assert!(store.traverse(&TenantId::new("other"), TraversalRequest::new(home)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_current_view() {
+        let store = InMemoryStore::new_with_config(InMemoryConfig { current_view: true, ..Default::default() });
+        let tenant = TenantId::new("test_tenant");
+        let since: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+
+        let alice = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let globex = store.upsert_node(&tenant, Node::new("Company").with_id_alias("globex")).await.unwrap();
+        let worked = store.upsert_edge(&tenant, TimeEdge::new(alice, acme, "WORKS_FOR", since, json!({}))).await.unwrap();
+        let works = store.upsert_edge(&tenant, TimeEdge::new(alice, globex, "WORKS_FOR", since, json!({}))).await.unwrap();
+        let knows = store.upsert_edge(&tenant, TimeEdge::new(acme, globex, "PARTNERS", since, json!({}))).await.unwrap();
+        store.close_edge(&tenant, worked, "2024-06-01T00:00:00Z".parse().unwrap()).await.unwrap();
+        store.retract_edge(&tenant, knows).await.unwrap();
+
+        // Without a valid time, reads see only the currently valid edges
+        let current = store.query(&tenant, Query::relationships().build()).await.unwrap();
+        let ids: Vec<Uuid> = current.iter().map(|path| path.relationships[0].id).collect();
+        assert_eq!(ids, [works]);
+        assert_eq!(store.query_count(&tenant, Query::relationships().from(alice).build()).await.unwrap(), 1);
+
+        // The closed version is still found at times it was valid
+        let earlier = Query::relationships().valid_at("2024-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap()).build();
+        assert_eq!(store.query_count(&tenant, earlier).await.unwrap(), 2);
+
+        store.delete_node(&tenant, globex).await.unwrap();
+        assert!(!store.query_exists(&tenant, Query::relationships().build()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_count_and_exists() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let mut ids = Vec::new();
+        for name in ["alice", "bob", "carol"] {
+            ids.push(store.upsert_node(&tenant, Node::new("Person").with_props(json!({"name": name}))).await.unwrap());
+        }
+        store.upsert_node(&TenantId::new("other"), Node::new("Person")).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(ids[0], ids[1], "KNOWS", Utc::now(), json!({}))).await.unwrap();
+
+        // Pagination is ignored
+        let people = Query::nodes().label("Person").offset(1).limit(1).build();
+        assert_eq!(store.query_count(&tenant, people.clone()).await.unwrap(), 3);
+        assert!(store.query_exists(&tenant, people).await.unwrap());
+
+        let dave = Query::nodes().label("Person").prop_eq("name", "dave").build();
+        assert_eq!(store.query_count(&tenant, dave.clone()).await.unwrap(), 0);
+        assert!(!store.query_exists(&tenant, dave).await.unwrap());
+
+        let knows = Query::relationships().from(ids[0]).rel_type("KNOWS").build();
+        assert_eq!(store.query_count(&tenant, knows).await.unwrap(), 1);
+        let before = Query::relationships().rel_type("KNOWS").build();
+        let before = GraphQuery::AsOfQuery { base_query: Box::new(before), as_of_time: "2000-01-01T00:00:00Z".parse().unwrap() };
+        assert!(!store.query_exists(&tenant, before).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tenant_rejected() {
+        let store = InMemoryStore::new();
+        for tenant in ["", "Acme", "acme/../other", "ａｃｍｅ"] {
+            let result = store.upsert_node(&TenantId::new(tenant), Node::new("Person")).await;
+            assert!(matches!(result, Err(GraphError::InvalidTenant(_))), "{:?} was accepted", tenant);
+        }
+        let node = TenantId::new("acme");
+        let id = store.upsert_node(&node, Node::new("Person")).await.unwrap();
+        let edge = TimeEdge::new(id, id, "KNOWS", Utc::now(), json!({}));
+        assert!(matches!(store.upsert_edge(&TenantId::new("ACME"), edge).await, Err(GraphError::InvalidTenant(_))));
+        assert!(store.query(&TenantId::new("ACME"), Query::nodes().build()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_load() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("acme");
+        // More than a chunk, with every alias loaded twice
+        let nodes = (0..BULK_CHUNK_SIZE + 5).map(|i| Node::new("Person").with_id_alias(format!("p{}", i % 6_000)));
+        let loaded = store.bulk_load_nodes(&tenant, futures::stream::iter(nodes).boxed()).await.unwrap();
+        assert_eq!(loaded, BULK_CHUNK_SIZE as u64 + 5);
+        assert_eq!(store.summary(&tenant).await.unwrap().node_count, 6_000);
+
+        let ids = store.resolve_aliases(&tenant, &[AliasKey::new("p0"), AliasKey::new("p1")]).await.unwrap();
+        let (p0, p1) = (ids[&AliasKey::new("p0")], ids[&AliasKey::new("p1")]);
+        let edges = vec![
+            TimeEdge::new(p0, p1, "KNOWS", Utc::now(), json!({})),
+            TimeEdge::new(p1, p0, "KNOWS", Utc::now(), json!({})),
+        ];
+        assert_eq!(store.bulk_load_edges(&tenant, futures::stream::iter(edges).boxed()).await.unwrap(), 2);
+        assert_eq!(store.summary(&tenant).await.unwrap().edge_count, 2);
+
+        // Items before a failure stay loaded
+        let nodes = vec![Node::new("Person").with_id_alias("late"), Node::new("Person").with_property("_tenant_id", json!("other"))];
+        let result = store.bulk_load_nodes(&tenant, futures::stream::iter(nodes).boxed()).await;
+        assert!(matches!(result, Err(GraphError::ReservedProperty(_))));
+        assert!(store.get_node_by_alias(&tenant, "late").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_schema_doc() {
+        let store = Arc::new(InMemoryStore::new());
+        let service = CoreGraphService::new(store.clone());
+        let tenant = TenantId::new("test_tenant");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice").with_property("name", json!("Alice"))).await.unwrap();
+        let bob_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("bob").with_property("age", json!(41))).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+
+        let at = |time: &str| -> DateTime<Utc> { time.parse().unwrap() };
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", at("2020-01-01T00:00:00Z"), json!({"title": "Engineer"}))
+            .with_valid_to(at("2022-01-01T00:00:00Z"))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", at("2022-01-01T00:00:00Z"), json!({"title": "CTO"}))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(bob_id, acme_id, "WORKS_FOR", at("2021-01-01T00:00:00Z"), json!({}))).await.unwrap();
+
+        let mut schema = IngestSchema::default();
+        schema.labels.entry("Person".to_string()).or_default().insert("age".to_string(), Coercion::Integer);
+
+        let doc = generate_schema_doc(&service, &tenant, Some(&schema), 10).await.unwrap();
+        assert_eq!(doc.labels.iter().map(|label| label.name.as_str()).collect::<Vec<_>>(), vec!["Company", "Person"]);
+        let person = &doc.labels[1];
+        assert_eq!(person.count, 2);
+        let age = person.properties.iter().find(|property| property.name == "age").unwrap();
+        assert_eq!((age.count, age.types.clone(), age.declared, age.example.clone()), (1, vec!["integer".to_string()], Some(Coercion::Integer), Some(json!(41))));
+
+        let works_for = &doc.kinds[0];
+        assert_eq!(works_for.endpoints, vec![("Person".to_string(), "Company".to_string())]);
+        assert_eq!(works_for.cardinality, Some(Cardinality::ManyToOne));
+        assert_eq!(works_for.temporal.pairs, 2);
+        assert_eq!(works_for.temporal.versions_per_pair, 1.5);
+        assert_eq!(works_for.temporal.open_pairs, 2);
+        assert_eq!(works_for.temporal.earliest_valid_from, Some(at("2020-01-01T00:00:00Z")));
+
+        let markdown = doc.render(SchemaDocFormat::Markdown);
+        assert!(markdown.contains("### `WORKS_FOR`"));
+        assert!(markdown.contains("| age | 50% | integer | integer | 41 |"));
+    }
+
+    #[tokio::test]
+    async fn test_collapsed_relationships() {
+        let store = InMemoryStore::new();
+        let tenant = TenantId::new("test_tenant");
+
+        let alice_id = store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        let acme_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await.unwrap();
+        let globex_id = store.upsert_node(&tenant, Node::new("Company").with_id_alias("globex")).await.unwrap();
+
+        let at = |time: &str| -> DateTime<Utc> { time.parse().unwrap() };
+        for (from, to, title) in [("2020-01-01T00:00:00Z", "2021-01-01T00:00:00Z", "Engineer"), ("2021-01-01T00:00:00Z", "2022-01-01T00:00:00Z", "Lead")] {
+            let edge = TimeEdge::new(alice_id, acme_id, "WORKS_FOR", at(from), json!({"title": title, "badge": title.len()}))
+                .with_valid_to(at(to));
+            store.upsert_edge(&tenant, edge).await.unwrap();
+        }
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "WORKS_FOR", at("2022-01-01T00:00:00Z"), json!({"title": "CTO"}))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, acme_id, "ADVISES", at("2023-01-01T00:00:00Z"), json!({}))).await.unwrap();
+        store.upsert_edge(&tenant, TimeEdge::new(alice_id, globex_id, "ADVISES", at("2023-01-01T00:00:00Z"), json!({}))).await.unwrap();
+
+        let by_kind = Query::relationships().from(alice_id).rel_type("WORKS_FOR").collapse(Collapse::ByKind);
+        let results = store.query(&tenant, by_kind.clone().build()).await.unwrap();
+        assert_eq!(results.len(), 1);
+        let works_for = &results[0].relationships[0];
+        // Properties of later versions override those of earlier ones
+        assert_eq!(works_for.properties, json!({"title": "CTO", "badge": 4}));
+        assert_eq!(works_for.collapsed, Some(EdgeAggregate {
+            count: 3,
+            valid_from: at("2020-01-01T00:00:00Z"),
+            valid_to: None,
+            kinds: vec!["WORKS_FOR".to_string()],
+        }));
+        assert_eq!(store.query_count(&tenant, by_kind.build()).await.unwrap(), 1);
+
+        let by_pair = Query::relationships().from(alice_id).collapse(Collapse::ByPair).order_by(OrderBy::asc(SortField::CreatedAt));
+        let results = store.query(&tenant, by_pair.clone().build()).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].nodes[1].id, acme_id);
+        let acme = results[0].relationships[0].collapsed.as_ref().unwrap();
+        assert_eq!(acme.count, 4);
+        assert_eq!(acme.kinds, vec!["ADVISES", "WORKS_FOR"]);
+        assert_eq!(results[1].relationships[0].collapsed.as_ref().unwrap().count, 1);
+        assert_eq!(store.query_count(&tenant, by_pair.clone().build()).await.unwrap(), 2);
+
+        // Pages are of groups, not of edges
+        let second = store.query(&tenant, by_pair.offset(1).limit(1).build()).await.unwrap();
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].nodes[1].id, globex_id);
+
+        // Without collapsing, every version is returned
+        let uncollapsed = store.query(&tenant, Query::relationships().from(alice_id).build()).await.unwrap();
+        assert_eq!(uncollapsed.len(), 5);
+        assert!(uncollapsed.iter().all(|path| path.relationships[0].collapsed.is_none()));
+    }
+
     #[tokio::test]
     async fn test_temporal_queries() {
         let store = InMemoryStore::new();
@@ -721,7 +3353,10 @@ mod tests {
             to_node_id: None,
             relationship_types: vec!["WORKS_FOR".to_string()],
             valid_at: Some(current_time),
+            order_by: vec![],
+            offset: None,
             limit: None,
+            collapse: None,
         };
 
         let results = store.query(&tenant, query).await.unwrap();
@@ -734,7 +3369,10 @@ mod tests {
             to_node_id: None,
             relationship_types: vec!["WORKS_FOR".to_string()],
             valid_at: Some(before_time),
+            order_by: vec![],
+            offset: None,
             limit: None,
+            collapse: None,
         };
 
         let results = store.query(&tenant, query).await.unwrap();
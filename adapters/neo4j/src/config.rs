@@ -1,12 +1,46 @@
 //! Configuration types for Neo4j adapter
 
 use serde::{Deserialize, Serialize};
+use telamentis_core::constraints::EdgeConstraints;
+use telamentis_core::ids::IdStrategy;
+use telamentis_core::migrations::SchemaCheck;
+use telamentis_core::properties::SystemProperties;
+use telamentis_core::temporal_validation::TemporalValidation;
+
+/// Default time a tenant's reads stay on the primary after it writes
+const DEFAULT_READ_AFTER_WRITE_MS: u64 = 2000;
+
+/// Default time a failed read replica is skipped before being retried
+const DEFAULT_REPLICA_RETRY_MS: u64 = 30_000;
+
+/// Default time a `replicated` write waits for the read replicas
+const DEFAULT_REPLICATION_TIMEOUT_MS: u64 = 5000;
+
+/// Default time a tenant's label and kind catalog is served from cache
+const DEFAULT_CATALOG_CACHE_TTL_MS: u64 = 60_000;
+
+/// Default time a replica waits for another one to finish migrating
+const DEFAULT_MIGRATION_LOCK_TIMEOUT_MS: u64 = 120_000;
 
 /// Configuration for Neo4j connection
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Neo4jConfig {
-    /// Neo4j connection URI (e.g., bolt://localhost:7687)
+    /// Neo4j connection URI of the primary (e.g., bolt://localhost:7687).
+    /// A `neo4j://` URI lets the driver route within a causal cluster itself.
     pub uri: String,
+    /// URIs of read replicas; read-only operations are routed here when set
+    #[serde(default)]
+    pub read_replicas: Vec<String>,
+    /// How long a tenant's reads are pinned to the primary after it writes, in milliseconds
+    #[serde(default = "default_read_after_write_ms")]
+    pub read_after_write_ms: u64,
+    /// How long a failed read replica is taken out of rotation, in milliseconds
+    #[serde(default = "default_replica_retry_ms")]
+    pub replica_retry_ms: u64,
+    /// How long a write with the `replicated` write concern waits for the
+    /// read replicas to catch up before failing, in milliseconds
+    #[serde(default = "default_replication_timeout_ms")]
+    pub replication_timeout_ms: u64,
     /// Username for authentication
     pub user: Option<String>,
     /// Password for authentication  
@@ -15,16 +49,59 @@ pub struct Neo4jConfig {
     pub max_connections: usize,
     /// Connection timeout in milliseconds
     pub connection_timeout_ms: u64,
+    /// Naming of system properties (e.g. `_tenant_id`) and reserved-key handling
+    #[serde(default)]
+    pub system_properties: SystemProperties,
+    /// Temporal invariants enforced on edge writes
+    #[serde(default)]
+    pub temporal_validation: TemporalValidation,
+    /// How long a computed catalog is reused before it is recomputed, in milliseconds
+    #[serde(default = "default_catalog_cache_ttl_ms")]
+    pub catalog_cache_ttl_ms: u64,
+    /// What to do on startup when the database has pending migrations
+    #[serde(default)]
+    pub schema_check: SchemaCheck,
+    /// Never create indexes on startup, e.g. for a locked-down database whose
+    /// indexes an administrator manages; they are only verified
+    #[serde(default)]
+    pub skip_index_creation: bool,
+    /// How long startup waits for another replica that is migrating the
+    /// schema, in milliseconds
+    #[serde(default = "default_migration_lock_timeout_ms")]
+    pub migration_lock_timeout_ms: u64,
+    /// How system IDs of new nodes and edges are generated
+    #[serde(default)]
+    pub ids: IdStrategy,
+    /// Read current edges from the maintained current-state view when a
+    /// query gives no time; the view is kept up to date either way
+    #[serde(default)]
+    pub current_view: bool,
+    /// Relationship constraints of tenants that have not set their own
+    #[serde(default)]
+    pub edge_constraints: EdgeConstraints,
 }
 
 impl Default for Neo4jConfig {
     fn default() -> Self {
         Self {
             uri: "bolt://localhost:7687".to_string(),
+            read_replicas: Vec::new(),
+            read_after_write_ms: DEFAULT_READ_AFTER_WRITE_MS,
+            replica_retry_ms: DEFAULT_REPLICA_RETRY_MS,
+            replication_timeout_ms: DEFAULT_REPLICATION_TIMEOUT_MS,
             user: Some("neo4j".to_string()),
             password: Some("neo4j".to_string()),
             max_connections: 10,
             connection_timeout_ms: 5000,
+            system_properties: SystemProperties::default(),
+            temporal_validation: TemporalValidation::default(),
+            catalog_cache_ttl_ms: DEFAULT_CATALOG_CACHE_TTL_MS,
+            schema_check: SchemaCheck::default(),
+            skip_index_creation: false,
+            migration_lock_timeout_ms: DEFAULT_MIGRATION_LOCK_TIMEOUT_MS,
+            ids: IdStrategy::default(),
+            current_view: false,
+            edge_constraints: EdgeConstraints::default(),
         }
     }
 }
@@ -45,6 +122,34 @@ impl Neo4jConfig {
         self
     }
     
+    /// Route read-only operations to the given replica URIs
+    pub fn with_read_replicas<I, S>(mut self, uris: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.read_replicas = uris.into_iter().map(Into::into).collect();
+        self
+    }
+    
+    /// Set how long reads stay on the primary after a tenant writes (0 disables pinning)
+    pub fn with_read_after_write(mut self, window_ms: u64) -> Self {
+        self.read_after_write_ms = window_ms;
+        self
+    }
+    
+    /// Set how long a failed read replica is skipped before it is retried
+    pub fn with_replica_retry(mut self, retry_ms: u64) -> Self {
+        self.replica_retry_ms = retry_ms;
+        self
+    }
+    
+    /// Set how long `replicated` writes wait for the read replicas
+    pub fn with_replication_timeout(mut self, timeout_ms: u64) -> Self {
+        self.replication_timeout_ms = timeout_ms;
+        self
+    }
+    
     /// Set the connection pool size
     pub fn with_max_connections(mut self, max_connections: usize) -> Self {
         self.max_connections = max_connections;
@@ -56,4 +161,63 @@ impl Neo4jConfig {
         self.connection_timeout_ms = timeout_ms;
         self
     }
-}
\ No newline at end of file
+    
+    /// Set the system property naming, e.g. a custom prefix to avoid clashing
+    /// with properties already present in an existing database
+    pub fn with_system_properties(mut self, system_properties: SystemProperties) -> Self {
+        self.system_properties = system_properties;
+        self
+    }
+    
+    /// Set the temporal validation rules, e.g. to repair rather than reject
+    /// inconsistent timestamps
+    pub fn with_temporal_validation(mut self, temporal_validation: TemporalValidation) -> Self {
+        self.temporal_validation = temporal_validation;
+        self
+    }
+    
+    /// Set how long a computed catalog is reused (0 recomputes it on every request)
+    pub fn with_catalog_cache_ttl(mut self, ttl_ms: u64) -> Self {
+        self.catalog_cache_ttl_ms = ttl_ms;
+        self
+    }
+    
+    /// Set what happens on startup when the database has pending migrations,
+    /// e.g. to start against an unmigrated schema during a rollout
+    pub fn with_schema_check(mut self, schema_check: SchemaCheck) -> Self {
+        self.schema_check = schema_check;
+        self
+    }
+    
+    /// Only verify the indexes on startup instead of creating missing ones
+    pub fn with_skip_index_creation(mut self, skip: bool) -> Self {
+        self.skip_index_creation = skip;
+        self
+    }
+    
+    /// Set how system IDs are generated, e.g. time-ordered for index locality
+    pub fn with_ids(mut self, ids: IdStrategy) -> Self {
+        self.ids = ids;
+        self
+    }
+}
+
+fn default_read_after_write_ms() -> u64 {
+    DEFAULT_READ_AFTER_WRITE_MS
+}
+
+fn default_replica_retry_ms() -> u64 {
+    DEFAULT_REPLICA_RETRY_MS
+}
+
+fn default_replication_timeout_ms() -> u64 {
+    DEFAULT_REPLICATION_TIMEOUT_MS
+}
+
+fn default_catalog_cache_ttl_ms() -> u64 {
+    DEFAULT_CATALOG_CACHE_TTL_MS
+}
+
+fn default_migration_lock_timeout_ms() -> u64 {
+    DEFAULT_MIGRATION_LOCK_TIMEOUT_MS
+}
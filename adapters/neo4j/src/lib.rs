@@ -2,95 +2,452 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use neo4j::{Graph, Query, Result as Neo4jResult};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use telamentis_core::prelude::*;
+use telamentis_core::timeline;
+use telamentis_core::traversal::{self, Hop};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 mod config;
+mod migrations;
 mod queries;
+mod routing;
+mod scoping;
 mod utils;
 
 pub use config::Neo4jConfig;
+pub use migrations::{IndexEntity, IndexMismatch, IndexReport, IndexSpec, Neo4jMigrator};
+
+use routing::{Bookmarks, ReplicaSet};
+
+/// Stored value of `_alias_namespace` for aliases in the default namespace.
+/// Neo4j cannot MERGE on null properties, so the default namespace is an empty string.
+const DEFAULT_ALIAS_NAMESPACE: &str = "";
+
+/// Neo4j store record sizes, used to estimate a tenant's storage
+const NODE_RECORD_BYTES: u64 = 15;
+const RELATIONSHIP_RECORD_BYTES: u64 = 34;
+const PROPERTY_RECORD_BYTES: u64 = 41;
+
+/// How often a `replicated` write polls the replicas for its records
+const REPLICATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Items a bulk load writes per transaction
+const BULK_CHUNK_SIZE: usize = 1_000;
+
+/// One group of a summary count query
+struct CountRow {
+    key: String,
+    count: u64,
+    property_count: u64,
+    last_write_at: Option<DateTime<Utc>>,
+}
 
 /// Neo4j implementation of GraphStore
 pub struct Neo4jStore {
     graph: Graph,
+    replicas: ReplicaSet,
+    bookmarks: Bookmarks,
     config: Neo4jConfig,
+    /// Catalogs by tenant with the time they were computed
+    catalogs: Mutex<HashMap<TenantId, (Instant, GraphCatalog)>>,
+    /// Materialized snapshots, held in memory
+    snapshots: SnapshotRegistry,
+    /// Source of system IDs for new nodes and edges
+    ids: Arc<dyn IdGenerator>,
 }
 
 impl Neo4jStore {
     /// Create a new Neo4j store instance
+    ///
+    /// Fails with `GraphError::SchemaOutdated` if the database has pending
+    /// migrations or its indexes differ from those the migrations create,
+    /// unless the config's `schema_check` says otherwise.
     pub async fn new(config: Neo4jConfig) -> Result<Self, GraphError> {
-        info!("Connecting to Neo4j at {}", config.uri);
-        
-        let graph = Graph::new(
-            &config.uri,
-            config.user.as_deref().unwrap_or("neo4j"),
-            config.password.as_deref().unwrap_or("neo4j")
-        )
-        .await
-        .map_err(|e| GraphError::ConnectionFailed(format!("Neo4j connection failed: {}", e)))?;
+        let graph = connect(&config).await?;
+
+        let replicas = ReplicaSet::connect(&config).await;
+        let bookmarks = Bookmarks::new(Duration::from_millis(config.read_after_write_ms));
 
         // Test the connection
-        let store = Self { graph, config };
+        let ids = config.ids.build();
+        let store = Self { graph, replicas, bookmarks, config, catalogs: Mutex::new(HashMap::new()), snapshots: SnapshotRegistry::default(), ids };
         store.health_check().await?;
         
-        // Create indices for performance
-        store.create_indices().await?;
+        // Make sure the indices are those this version expects
+        store.migrator().check(store.config.schema_check, store.config.skip_index_creation).await?;
         
         Ok(store)
     }
 
-    /// Create necessary indices for optimal performance
-    async fn create_indices(&self) -> Result<(), GraphError> {
-        let indices = vec![
-            // Tenant isolation index
-            "CREATE INDEX tenant_node_idx IF NOT EXISTS FOR (n) ON (n._tenant_id)",
-            "CREATE INDEX tenant_rel_idx IF NOT EXISTS FOR ()-[r]-() ON (r._tenant_id)",
-            // Node alias index
-            "CREATE INDEX node_alias_idx IF NOT EXISTS FOR (n) ON (n.id_alias)",
-            // Temporal indices
-            "CREATE INDEX valid_from_idx IF NOT EXISTS FOR ()-[r]-() ON (r.valid_from)",
-            "CREATE INDEX valid_to_idx IF NOT EXISTS FOR ()-[r]-() ON (r.valid_to)",
-            // Transaction time indices
-            "CREATE INDEX transaction_start_idx IF NOT EXISTS FOR ()-[r]-() ON (r.transaction_start_time)",
-            "CREATE INDEX transaction_end_idx IF NOT EXISTS FOR ()-[r]-() ON (r.transaction_end_time)",
-            // System ID index
-            "CREATE INDEX system_id_idx IF NOT EXISTS FOR (n) ON (n.system_id)",
-        ];
+    /// Generate system IDs with a custom generator instead of the configured strategy
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
 
-        for index_query in indices {
-            debug!("Creating index: {}", index_query);
-            let query = Query::new(index_query.to_string());
-            self.graph.execute(query).await
-                .map_err(|e| GraphError::DatabaseError(format!("Failed to create index: {}", e)))?;
+    /// Migrator for the primary's schema
+    fn migrator(&self) -> Neo4jMigrator {
+        Neo4jMigrator::new(self.graph.clone(), &self.config)
+    }
+
+    /// Rewrite a query template to use the configured system property names
+    fn cypher(&self, template: &str) -> String {
+        rewrite_system_keys(&self.config.system_properties, template)
+    }
+
+    /// MATCH and WHERE clauses of a `FindNodes` query, binding `n`
+    fn match_nodes(tenant: &TenantId, labels: &[String], properties: HashMap<String, Value>) -> (Vec<String>, HashMap<String, Value>) {
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        
+        let mut query_parts = vec!["MATCH (n)".to_string()];
+        query_parts.push("WHERE n._tenant_id = $tenant_id".to_string());
+        
+        // Add label filters
+        if !labels.is_empty() {
+            let label_filter = labels.iter()
+                .map(|l| format!("n:{}", l))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            query_parts.push(format!("AND ({})", label_filter));
+        }
+        
+        // Add property filters
+        for (key, value) in properties {
+            let param_name = format!("prop_{}", key);
+            params.insert(param_name.clone(), value);
+            query_parts.push(format!("AND n.{} = ${}", key, param_name));
         }
+        
+        (query_parts, params)
+    }
 
-        info!("Neo4j indices created successfully");
-        Ok(())
+    /// MATCH and WHERE clauses of a `FindRelationships` query, binding
+    /// `a`, `r` and `b`
+    fn match_relationships(
+        &self,
+        tenant: &TenantId,
+        from_node_id: Option<Uuid>,
+        to_node_id: Option<Uuid>,
+        relationship_types: &[String],
+        valid_at: Option<DateTime<Utc>>,
+    ) -> (Vec<String>, HashMap<String, Value>) {
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        
+        let mut query_parts = vec!["MATCH (a)-[r]->(b)".to_string()];
+        query_parts.push("WHERE r._tenant_id = $tenant_id".to_string());
+        // Closed, superseded and retracted versions are history; without a
+        // time, only current edges match, which the view marks
+        if valid_at.is_none() && self.config.current_view {
+            query_parts.push("AND r._current = true".to_string());
+        } else {
+            query_parts.push("AND r.transaction_end_time IS NULL".to_string());
+        }
+        // Expired edges are gone before they are purged
+        query_parts.push("AND (r.expires_at IS NULL OR datetime() < r.expires_at)".to_string());
+        
+        if let Some(from_id) = from_node_id {
+            params.insert("from_id".to_string(), Value::String(from_id.to_string()));
+            query_parts.push("AND a.system_id = $from_id".to_string());
+        }
+        
+        if let Some(to_id) = to_node_id {
+            params.insert("to_id".to_string(), Value::String(to_id.to_string()));
+            query_parts.push("AND b.system_id = $to_id".to_string());
+        }
+        
+        if !relationship_types.is_empty() {
+            let type_filter = relationship_types.iter()
+                .map(|t| format!("type(r) = '{}'", t))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            query_parts.push(format!("AND ({})", type_filter));
+        }
+        
+        if let Some(valid_at) = valid_at {
+            params.insert("valid_at".to_string(), Value::String(valid_at.to_rfc3339()));
+            query_parts.push("AND r.valid_from <= datetime($valid_at)".to_string());
+            query_parts.push("AND (r.valid_to IS NULL OR datetime($valid_at) < r.valid_to)".to_string());
+        }
+        
+        (query_parts, params)
+    }
+
+    /// MATCH and WHERE clauses of a structured query and the variable bound
+    /// to each match; `None` for queries that match nothing or are raw
+    fn match_structured(&self, tenant: &TenantId, query: GraphQuery) -> Option<(Vec<String>, HashMap<String, Value>, &'static str)> {
+        match query {
+            GraphQuery::FindNodes { labels, properties, .. } => {
+                let (query_parts, params) = Self::match_nodes(tenant, &labels, properties);
+                Some((query_parts, params, "n"))
+            }
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, collapse, .. } => {
+                let (query_parts, params) = self.match_relationships(tenant, from_node_id, to_node_id, &relationship_types, valid_at);
+                Some((query_parts, params, Self::counted_relationships(collapse)))
+            }
+            GraphQuery::AsOfQuery { base_query, as_of_time } => match *base_query {
+                GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, collapse, .. } => {
+                    let (query_parts, params) = self.match_relationships(tenant, from_node_id, to_node_id, &relationship_types, Some(as_of_time));
+                    Some((query_parts, params, Self::counted_relationships(collapse)))
+                }
+                // As in `query`, other as-of queries match nothing
+                _ => None,
+            },
+            GraphQuery::Raw { .. } => None,
+        }
     }
 
-    /// Ensure tenant isolation by adding tenant filter to node queries
-    fn add_tenant_filter_node(&self, query: &str, tenant: &TenantId) -> String {
-        if query.contains("WHERE") {
-            format!("{} AND n._tenant_id = $tenant_id", query)
+    /// What counting the matches of a relationship query counts: edges, or
+    /// the groups they collapse into
+    fn counted_relationships(collapse: Option<Collapse>) -> &'static str {
+        match collapse {
+            None => "r",
+            Some(Collapse::ByKind) => "DISTINCT [a.system_id, b.system_id, type(r)]",
+            Some(Collapse::ByPair) => "DISTINCT [a.system_id, b.system_id]",
+        }
+    }
+
+    /// Clauses grouping matched relationships `r` into one row per group,
+    /// with `r` bound to the latest edge and the aggregate computed
+    /// store-side, so that versions are not transferred
+    fn collapse_relationships(collapse: Collapse) -> Vec<String> {
+        let group = match collapse {
+            Collapse::ByKind => "a, b, type(r) AS kind",
+            Collapse::ByPair => "a, b",
+        };
+        vec![
+            "WITH a, r, b ORDER BY r.valid_from, r.transaction_start_time, r.system_id".to_string(),
+            format!("WITH {}, collect(r) AS versions", group),
+            "WITH a, b, versions, last(versions) AS r, \
+             reduce(ks = [], v IN versions | ks + [k IN keys(v) WHERE NOT k IN ks]) AS prop_keys".to_string(),
+            // Later versions override the properties of earlier ones
+            "RETURN a, r, b, size(versions) AS version_count, \
+             head(versions).valid_from AS first_valid_from, \
+             CASE WHEN any(v IN versions WHERE v.valid_to IS NULL) THEN null \
+             ELSE reduce(hi = head(versions).valid_to, v IN versions | CASE WHEN v.valid_to > hi THEN v.valid_to ELSE hi END) END AS last_valid_to, \
+             reduce(ts = [], v IN versions | CASE WHEN type(v) IN ts THEN ts ELSE ts + type(v) END) AS kinds, \
+             [k IN prop_keys | [k, last([v IN versions WHERE v[k] IS NOT NULL | v[k]])]] AS merged_props".to_string(),
+        ]
+    }
+
+    /// Aggregate of a row returned by the clauses of `collapse_relationships`,
+    /// and the merged properties of its edges
+    fn collapsed_relationship(row: &neo4j::Row) -> Result<(EdgeAggregate, Value), GraphError> {
+        fn field<T: serde::de::DeserializeOwned>(row: &neo4j::Row, name: &str) -> Result<T, GraphError> {
+            row.get::<T>(name).map_err(|e| GraphError::QueryFailed(format!("Failed to read {}: {}", name, e)))
+        }
+        fn time(value: &str) -> Result<DateTime<Utc>, GraphError> {
+            DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| GraphError::DatabaseError(format!("Invalid datetime format: {}", e)))
+        }
+
+        let mut kinds: Vec<String> = field(row, "kinds")?;
+        kinds.sort();
+        let aggregate = EdgeAggregate {
+            count: field::<i64>(row, "version_count")? as u64,
+            valid_from: time(&field::<String>(row, "first_valid_from")?)?,
+            valid_to: field::<Option<String>>(row, "last_valid_to")?.as_deref().map(time).transpose()?,
+            kinds,
+        };
+        let props: Vec<(String, Value)> = field(row, "merged_props")?;
+        Ok((aggregate, Value::Object(props.into_iter().collect())))
+    }
+
+    /// Start node and the current edges within `depth` hops of it that a
+    /// traversal may follow, by the node they are followed from
+    #[allow(clippy::too_many_arguments)]
+    async fn traversal_graph(
+        &self,
+        tenant: &TenantId,
+        start: Uuid,
+        direction: TraversalDirection,
+        relationship_types: &[String],
+        valid_at: Option<DateTime<Utc>>,
+        depth: u32,
+        weight_property: &str,
+    ) -> Result<(PathNode, HashMap<Uuid, Vec<Hop>>), GraphError> {
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("start".to_string(), Value::String(start.to_string()));
+        
+        let start_query = Query::new(self.cypher("MATCH (s) WHERE s._tenant_id = $tenant_id AND s.system_id = $start RETURN s"))
+            .params(params.clone());
+        
+        let pattern = match direction {
+            TraversalDirection::Outgoing => format!("(s)-[rels*1..{}]->()", depth),
+            TraversalDirection::Incoming => format!("(s)<-[rels*1..{}]-()", depth),
+            TraversalDirection::Both => format!("(s)-[rels*1..{}]-()", depth),
+        };
+        let current = if valid_at.is_none() && self.config.current_view {
+            "r._current = true AND (r.expires_at IS NULL OR datetime() < r.expires_at)"
         } else {
-            format!("{} WHERE n._tenant_id = $tenant_id", query)
+            "r.transaction_end_time IS NULL AND (r.expires_at IS NULL OR datetime() < r.expires_at)"
+        };
+        let mut query_parts = vec![
+            format!("MATCH {}", pattern),
+            "WHERE s._tenant_id = $tenant_id AND s.system_id = $start".to_string(),
+            // Closed, superseded and retracted versions are history
+            format!("AND all(r IN rels WHERE r._tenant_id = $tenant_id AND {}", current),
+        ];
+        if !relationship_types.is_empty() {
+            params.insert("types".to_string(), Value::from(relationship_types.to_vec()));
+            query_parts.push("AND type(r) IN $types".to_string());
+        }
+        if let Some(valid_at) = valid_at {
+            params.insert("valid_at".to_string(), Value::String(valid_at.to_rfc3339()));
+            query_parts.push("AND r.valid_from <= datetime($valid_at)".to_string());
+            query_parts.push("AND (r.valid_to IS NULL OR datetime($valid_at) < r.valid_to)".to_string());
+        }
+        query_parts.push(")".to_string());
+        query_parts.push("UNWIND rels AS r WITH DISTINCT r".to_string());
+        query_parts.push("RETURN startNode(r) AS a, r, endNode(r) AS b ORDER BY r.transaction_start_time, r.system_id".to_string());
+        let edges_query = Query::new(self.cypher(&query_parts.join(" "))).params(params);
+        
+        debug!("Reading traversal graph for tenant {} from node {}", tenant, start);
+        
+        self.read(tenant, |graph| {
+            let start_query = start_query.clone();
+            let edges_query = edges_query.clone();
+            async move {
+                let mut result = graph.execute(start_query).await
+                    .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
+                let start_node = match result.next().await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                    Some(row) => row.get::<neo4j::Node>("s")
+                        .map(|node| Self::path_node(&node))
+                        .map_err(|e| GraphError::QueryFailed(format!("Missing node in result: {}", e)))?,
+                    None => return Err(GraphError::NodeNotFound(format!("Node {} not found in tenant {}", start, tenant))),
+                };
+                
+                let mut result = graph.execute(edges_query).await
+                    .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
+                let mut hops: HashMap<Uuid, Vec<Hop>> = HashMap::new();
+                while let Some(row) = result.next().await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                    if let (Ok(from), Ok(relationship), Ok(to)) = (
+                        row.get::<neo4j::Node>("a"),
+                        row.get::<neo4j::Relationship>("r"),
+                        row.get::<neo4j::Node>("b")
+                    ) {
+                        let relationship = Self::path_relationship(&relationship);
+                        let weight = traversal::edge_weight(&relationship.properties, weight_property);
+                        let (from, to) = (Self::path_node(&from), Self::path_node(&to));
+                        if direction != TraversalDirection::Incoming {
+                            hops.entry(from.id).or_default().push(Hop { relationship: relationship.clone(), node: to.clone(), weight });
+                        }
+                        if direction != TraversalDirection::Outgoing {
+                            hops.entry(to.id).or_default().push(Hop { relationship, node: from, weight });
+                        }
+                    }
+                }
+                
+                Ok((start_node, hops))
+            }
+        }).await
+    }
+
+    /// Path node of a node read from Neo4j
+    fn path_node(node: &neo4j::Node) -> PathNode {
+        PathNode {
+            id: *node.node_identity(),
+            labels: node.labels().clone(),
+            properties: serde_json::to_value(node.properties().clone())
+                .unwrap_or(Value::Null),
         }
     }
 
+    /// Path relationship of a relationship read from Neo4j
+    fn path_relationship(relationship: &neo4j::Relationship) -> PathRelationship {
+        PathRelationship {
+            id: *relationship.rel_identity(),
+            rel_type: relationship.rel_type().clone(),
+            start_node_id: *relationship.start_node_identity(),
+            end_node_id: *relationship.end_node_identity(),
+            properties: serde_json::to_value(relationship.properties().clone())
+                .unwrap_or(Value::Null),
+            collapsed: None,
+        }
+    }
+
+    /// Run a read query returning a single `count` column
+    async fn read_count(&self, tenant: &TenantId, query_str: String, params: HashMap<String, Value>) -> Result<u64, GraphError> {
+        let query = Query::new(self.cypher(&query_str)).params(params);
+        
+        self.read(tenant, |graph| {
+            let query = query.clone();
+            async move {
+                let mut result = graph.execute(query).await
+                    .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
+                
+                let row = result.next().await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))?
+                    .ok_or_else(|| GraphError::QueryFailed("No count returned".to_string()))?;
+                let count: i64 = row.get("count")
+                    .map_err(|e| GraphError::QueryFailed(format!("Missing count in result: {}", e)))?;
+                Ok(count as u64)
+            }
+        }).await
+    }
+
+    /// Run a read-only operation on a healthy replica, falling back to the primary.
+    ///
+    /// Reads for a tenant with a recent write bookmark go straight to the primary.
+    /// A replica is only marked unhealthy once the same read succeeds elsewhere,
+    /// so a malformed query does not take replicas out of rotation.
+    async fn read<T, F, Fut>(&self, tenant: &TenantId, op: F) -> Result<T, GraphError>
+    where
+        F: Fn(Graph) -> Fut,
+        Fut: Future<Output = Result<T, GraphError>>,
+    {
+        let mut failed = Vec::new();
+
+        if !self.bookmarks.requires_primary(tenant) {
+            for replica in self.replicas.candidates() {
+                match op(replica.graph.clone()).await {
+                    Ok(value) => {
+                        failed.iter().for_each(|r| self.replicas.mark_failed(r));
+                        return Ok(value);
+                    }
+                    Err(e) => {
+                        warn!("Read on replica {} failed, trying next target: {}", replica.uri, e);
+                        failed.push(replica);
+                    }
+                }
+            }
+        }
+
+        let result = op(self.graph.clone()).await;
+        if result.is_ok() {
+            failed.iter().for_each(|r| self.replicas.mark_failed(r));
+        }
+        result
+    }
+
     /// Convert Neo4j node to TelaMentis Node
     fn convert_neo4j_node(&self, node: &neo4j::Node) -> Result<Node, GraphError> {
         let mut props = node.properties().clone();
         
         // Remove system properties
+        let system = &self.config.system_properties;
         props.remove("system_id");
-        props.remove("_tenant_id");
+        props.remove(&system.tenant_key());
+        props.remove("created_at");
+        props.remove("updated_at");
         let id_alias = props.remove("id_alias")
             .and_then(|v| v.as_str().map(|s| s.to_string()));
+        let alias_namespace = props.remove(&system.alias_namespace_key())
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .filter(|ns| ns != DEFAULT_ALIAS_NAMESPACE);
         
         let labels = node.labels();
         let label = labels.first()
@@ -98,6 +455,7 @@ impl Neo4jStore {
 
         Ok(Node {
             id_alias,
+            alias_namespace,
             label: label.clone(),
             props: serde_json::to_value(props)
                 .map_err(|e| GraphError::DatabaseError(format!("Failed to serialize props: {}", e)))?,
@@ -126,9 +484,14 @@ impl Neo4jStore {
             .map(|v| self.parse_datetime(&v))
             .transpose()?;
 
+        let expires_at = props.remove("expires_at")
+            .map(|v| self.parse_datetime(&v))
+            .transpose()?;
+
         // Remove system properties
         props.remove("system_id");
-        props.remove("_tenant_id");
+        props.remove(&self.config.system_properties.tenant_key());
+        props.remove(&self.config.system_properties.current_key());
         props.remove("created_at");
 
         Ok(TimeEdge {
@@ -139,11 +502,494 @@ impl Neo4jStore {
             valid_to,
             transaction_start_time,
             transaction_end_time,
+            expires_at,
             props: serde_json::to_value(props)
                 .map_err(|e| GraphError::DatabaseError(format!("Failed to serialize props: {}", e)))?,
         })
     }
 
+    /// Build the node upsert query (MERGE on alias, CREATE otherwise)
+    fn build_upsert_node_query(&self, tenant: &TenantId, node: &Node) -> Query {
+        let system_id = self.ids.next_id();
+        
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("system_id".to_string(), Value::String(system_id.to_string()));
+        params.insert("label".to_string(), Value::String(node.label.clone()));
+        params.insert("props".to_string(), node.props.clone());
+        
+        if let Some(id_alias) = &node.id_alias {
+            let alias_namespace = node.alias_namespace.as_deref().unwrap_or(DEFAULT_ALIAS_NAMESPACE);
+            params.insert("id_alias".to_string(), Value::String(id_alias.clone()));
+            params.insert("alias_namespace".to_string(), Value::String(alias_namespace.to_string()));
+            Query::new(self.cypher(queries::UPSERT_NODE_WITH_ALIAS)).params(params)
+        } else {
+            Query::new(self.cypher(queries::CREATE_NODE_WITHOUT_ALIAS)).params(params)
+        }
+    }
+
+    /// Build the temporal edge creation query
+    fn build_upsert_edge_query(&self, tenant: &TenantId, edge: &TimeEdge) -> Query {
+        let system_id = self.ids.next_id();
+        
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("system_id".to_string(), Value::String(system_id.to_string()));
+        params.insert("from_id".to_string(), Value::String(edge.from_node_id.to_string()));
+        params.insert("to_id".to_string(), Value::String(edge.to_node_id.to_string()));
+        params.insert("rel_type".to_string(), Value::String(edge.kind.clone()));
+        params.insert("valid_from".to_string(), Value::String(edge.valid_from.to_rfc3339()));
+        params.insert("transaction_start_time".to_string(), Value::String(edge.transaction_start_time.to_rfc3339()));
+        params.insert("expires_at".to_string(), edge.expires_at.map_or(Value::Null, |t| Value::String(t.to_rfc3339())));
+        params.insert("props".to_string(), edge.props.clone());
+        
+        if let Some(valid_to) = edge.valid_to {
+            params.insert("valid_to".to_string(), Value::String(valid_to.to_rfc3339()));
+        }
+
+        Query::new(self.cypher(queries::UPSERT_EDGE)).params(params)
+    }
+
+    /// Run a write query inside a transaction and read back the returned system_id
+    async fn execute_returning_id(txn: &mut neo4j::Txn, query: Query) -> Result<Option<Uuid>, GraphError> {
+        let mut result = txn.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
+
+        if let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to get result: {}", e)))? {
+            let returned_id: String = row.get("system_id")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing system_id in result: {}", e)))?;
+            let id = Uuid::parse_str(&returned_id)
+                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID format: {}", e)))?;
+            Ok(Some(id))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Fail if the (namespace, alias) pair is already held by a node with a different label
+    async fn check_alias_conflict(&self, tenant: &TenantId, node: &Node, alias_namespace: &str) -> Result<(), GraphError> {
+        let Some(id_alias) = &node.id_alias else {
+            return Ok(());
+        };
+
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("id_alias".to_string(), Value::String(id_alias.clone()));
+        params.insert("alias_namespace".to_string(), Value::String(alias_namespace.to_string()));
+        params.insert("label".to_string(), Value::String(node.label.clone()));
+
+        let query = Query::new(self.cypher(queries::FIND_ALIAS_CONFLICT)).params(params);
+
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to check alias uniqueness: {}", e)))?;
+
+        if result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))?
+            .is_some() {
+            return Err(GraphError::ConstraintViolation(format!(
+                "Alias '{}' is already used by a node with a different label in tenant {}",
+                node.alias_key().map(|k| k.to_string()).unwrap_or_default(),
+                tenant
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Resolve aliases against a specific server
+    async fn resolve_aliases_on(&self, graph: &Graph, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        if aliases.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let alias_params: Vec<Value> = aliases.iter()
+            .map(|key| serde_json::json!({
+                "namespace": key.namespace.as_deref().unwrap_or(DEFAULT_ALIAS_NAMESPACE),
+                "alias": key.alias,
+            }))
+            .collect();
+
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("aliases".to_string(), Value::Array(alias_params));
+
+        let query = Query::new(self.cypher(queries::RESOLVE_ALIASES)).params(params);
+
+        debug!("Resolving {} aliases for tenant {}", aliases.len(), tenant);
+
+        let mut result = graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to resolve aliases: {}", e)))?;
+
+        let mut resolved = HashMap::new();
+        while let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+            let namespace: String = row.get("namespace")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing namespace: {}", e)))?;
+            let alias: String = row.get("alias")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing alias: {}", e)))?;
+            let system_id_str: String = row.get("system_id")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing system_id: {}", e)))?;
+            let system_id = Uuid::parse_str(&system_id_str)
+                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?;
+
+            let key = AliasKey {
+                namespace: Some(namespace).filter(|ns| ns != DEFAULT_ALIAS_NAMESPACE),
+                alias,
+            };
+            resolved.insert(key, system_id);
+        }
+
+        Ok(resolved)
+    }
+
+    /// Refresh the health of every read replica. Replica failures only take the
+    /// replica out of rotation; they never fail the store's health check.
+    async fn check_replicas(&self) {
+        for replica in self.replicas.all() {
+            let query = Query::new("RETURN 1 as test".to_string());
+            match replica.graph.execute(query).await {
+                Ok(_) => replica.health.mark_ok(),
+                Err(e) => {
+                    warn!("Health check failed for read replica {}: {}", replica.uri, e);
+                    self.replicas.mark_failed(replica);
+                }
+            }
+        }
+    }
+
+    /// Read the nodes and edges of a snapshot inside an open transaction
+    async fn read_snapshot(&self, txn: &mut neo4j::Txn, params: HashMap<String, Value>) -> Result<(Vec<NodeRecord>, Vec<EdgeRecord>), GraphError> {
+        let query = Query::new(self.cypher(queries::SNAPSHOT_NODES)).params(params.clone());
+        let mut result = txn.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to read snapshot nodes: {}", e)))?;
+
+        let mut nodes = Vec::new();
+        while let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+            let node: neo4j::Node = row.get("n")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing node: {}", e)))?;
+            let system_id_str: String = row.get("system_id")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing system_id: {}", e)))?;
+            let id = Uuid::parse_str(&system_id_str)
+                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?;
+            nodes.push(NodeRecord { id, node: self.convert_neo4j_node(&node)? });
+        }
+
+        let query = Query::new(self.cypher(queries::SNAPSHOT_RELATIONSHIPS)).params(params);
+        let mut result = txn.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to read snapshot edges: {}", e)))?;
+
+        let mut edges = Vec::new();
+        while let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+            let rel: neo4j::Relationship = row.get("r")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing relationship: {}", e)))?;
+            let system_id_str: String = row.get("system_id")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing system_id: {}", e)))?;
+            let id = Uuid::parse_str(&system_id_str)
+                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?;
+            edges.push(EdgeRecord { id, edge: self.convert_neo4j_relationship(&rel)? });
+        }
+
+        Ok((nodes, edges))
+    }
+
+    /// Read the current version of an edge inside an open transaction
+    async fn read_current_edge(&self, txn: &mut neo4j::Txn, tenant: &TenantId, id: Uuid) -> Result<TimeEdge, GraphError> {
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("system_id".to_string(), Value::String(id.to_string()));
+
+        let query = Query::new(self.cypher(queries::GET_EDGE_BY_ID)).params(params);
+        let mut result = txn.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to read edge: {}", e)))?;
+
+        let row = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))?
+            .ok_or_else(|| GraphError::EdgeNotFound(format!("Edge {} not found in tenant {}", id, tenant)))?;
+
+        let rel: neo4j::Relationship = row.get("r")
+            .map_err(|e| GraphError::QueryFailed(format!("Missing relationship: {}", e)))?;
+        let mut edge = self.convert_neo4j_relationship(&rel)?;
+        if edge.is_expired_at(Utc::now()) {
+            return Err(GraphError::EdgeNotFound(format!("Edge {} not found in tenant {}", id, tenant)));
+        }
+        if !edge.is_current_version() {
+            return Err(GraphError::ConstraintViolation(format!("Edge {} is not the current version", id)));
+        }
+
+        for (column, node_id) in [("from_id", &mut edge.from_node_id), ("to_id", &mut edge.to_node_id)] {
+            let system_id: String = row.get(column)
+                .map_err(|e| GraphError::QueryFailed(format!("Missing {}: {}", column, e)))?;
+            *node_id = Uuid::parse_str(&system_id)
+                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?;
+        }
+
+        Ok(edge)
+    }
+
+    /// End the current version of an edge and write its replacement in one
+    /// transaction. The replacement's transaction time starts when the old
+    /// version's ends.
+    async fn replace_edge(
+        &self,
+        tenant: &TenantId,
+        id: Uuid,
+        replacement: impl FnOnce(TimeEdge) -> TimeEdge,
+    ) -> Result<Uuid, GraphError> {
+        let now = Utc::now();
+        let constraints = self.edge_constraints(tenant).await?;
+
+        let mut txn = self.graph.start_txn().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to start transaction: {}", e)))?;
+
+        let result = self.replace_edge_in(&mut txn, tenant, id, now, &constraints, replacement).await;
+        let new_id = match result {
+            Ok(new_id) => new_id,
+            Err(e) => {
+                let _ = txn.rollback().await;
+                return Err(e);
+            }
+        };
+
+        txn.commit().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to commit transaction: {}", e)))?;
+        self.bookmarks.record_write(tenant);
+
+        debug!("Replaced edge {} with {} for tenant {}", id, new_id, tenant);
+        self.await_replication(tenant, &[new_id]).await?;
+        Ok(new_id)
+    }
+
+    /// Body of [`Self::replace_edge`], run inside an open transaction
+    async fn replace_edge_in(
+        &self,
+        txn: &mut neo4j::Txn,
+        tenant: &TenantId,
+        id: Uuid,
+        now: DateTime<Utc>,
+        constraints: &EdgeConstraints,
+        replacement: impl FnOnce(TimeEdge) -> TimeEdge,
+    ) -> Result<Uuid, GraphError> {
+        let current = self.read_current_edge(txn, tenant, id).await?;
+
+        let mut edge = replacement(current);
+        edge.transaction_start_time = now;
+        edge.transaction_end_time = None;
+        self.config.system_properties.sanitize(&mut edge.props)?;
+        self.config.temporal_validation.validate_edge(&mut edge)?;
+
+        // Ended first, so the old version is not checked against its replacement
+        self.end_edge_version_in(txn, tenant, id, now).await?;
+        self.insert_edge_in(txn, tenant, &edge, now, constraints).await?
+            .ok_or_else(|| GraphError::NodeNotFound(format!(
+                "End nodes of edge {} not found in tenant {}", id, tenant
+            )))
+    }
+
+    /// End the current version of an edge in transaction time, inside an open transaction
+    async fn end_edge_version_in(&self, txn: &mut neo4j::Txn, tenant: &TenantId, id: Uuid, now: DateTime<Utc>) -> Result<(), GraphError> {
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("system_id".to_string(), Value::String(id.to_string()));
+        params.insert("transaction_end_time".to_string(), Value::String(now.to_rfc3339()));
+        txn.run(Query::new(self.cypher(queries::END_EDGE_TRANSACTION)).params(params)).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to end edge version: {}", e)))
+    }
+
+    /// Write a new edge inside an open transaction, after closing the edges
+    /// it overlaps if its kind is exclusive and the constraints say so.
+    /// `None` if an end node does not exist.
+    async fn insert_edge_in(
+        &self,
+        txn: &mut neo4j::Txn,
+        tenant: &TenantId,
+        edge: &TimeEdge,
+        now: DateTime<Utc>,
+        constraints: &EdgeConstraints,
+    ) -> Result<Option<Uuid>, GraphError> {
+        if constraints.exclusivity(&edge.kind).is_some() {
+            let existing = self.read_current_edges_of_kind(txn, tenant, edge.from_node_id, &edge.kind).await?;
+            for closure in constraints.plan(edge, existing.iter().map(|(id, edge)| (*id, edge)))? {
+                let mut closed = self.read_current_edge(txn, tenant, closure.id).await?.with_valid_to(closure.valid_to);
+                closed.transaction_start_time = now;
+                self.end_edge_version_in(txn, tenant, closure.id, now).await?;
+                Self::execute_returning_id(txn, self.build_upsert_edge_query(tenant, &closed)).await?;
+                debug!("Closed exclusive edge {} for tenant {}", closure.id, tenant);
+            }
+        }
+
+        Self::execute_returning_id(txn, self.build_upsert_edge_query(tenant, edge)).await
+    }
+
+    /// Current versions of a node's outgoing edges of one kind, inside an open transaction
+    async fn read_current_edges_of_kind(&self, txn: &mut neo4j::Txn, tenant: &TenantId, from: Uuid, kind: &str) -> Result<Vec<(Uuid, TimeEdge)>, GraphError> {
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("from_id".to_string(), Value::String(from.to_string()));
+        params.insert("rel_type".to_string(), Value::String(kind.to_string()));
+
+        let query = Query::new(self.cypher(queries::CURRENT_EDGES_OF_KIND)).params(params);
+        let mut result = txn.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to read edges: {}", e)))?;
+
+        let mut edges = Vec::new();
+        while let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+            let rel: neo4j::Relationship = row.get("r")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing relationship: {}", e)))?;
+            let system_id_str: String = row.get("system_id")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing system_id: {}", e)))?;
+            let id = Uuid::parse_str(&system_id_str)
+                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?;
+            let mut edge = self.convert_neo4j_relationship(&rel)?;
+            edge.from_node_id = from;
+            edges.push((id, edge));
+        }
+
+        Ok(edges)
+    }
+
+    /// Honor a `replicated` write concern for records just written on the
+    /// primary: wait until every replica in rotation has caught up with them.
+    /// Other write concerns return at once, as do stores without replicas.
+    async fn await_replication(&self, tenant: &TenantId, ids: &[Uuid]) -> Result<(), GraphError> {
+        let replicas = self.replicas.candidates();
+        if current_write_concern() != WriteConcern::Replicated || replicas.is_empty() {
+            return Ok(());
+        }
+
+        let timeout = Duration::from_millis(self.config.replication_timeout_ms);
+        let deadline = Instant::now() + timeout;
+        for id in ids {
+            let Some(written_at) = self.record_written_at(tenant, *id).await? else {
+                continue;
+            };
+
+            for replica in &replicas {
+                while !self.record_caught_up(&replica.graph, tenant, *id, &written_at).await {
+                    if Instant::now() >= deadline {
+                        return Err(GraphError::Timeout(format!(
+                            "Write {} was committed but not replicated to {} within {:?}", id, replica.uri, timeout
+                        )));
+                    }
+                    tokio::time::sleep(REPLICATION_POLL_INTERVAL).await;
+                }
+            }
+        }
+
+        debug!("Writes {:?} of tenant {} replicated to {} replica(s)", ids, tenant, replicas.len());
+        Ok(())
+    }
+
+    /// When a node or edge version was last written, read on the primary
+    async fn record_written_at(&self, tenant: &TenantId, id: Uuid) -> Result<Option<String>, GraphError> {
+        let query = Query::new(self.cypher(queries::RECORD_WRITTEN_AT)).params(record_params(tenant, id));
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to read write time: {}", e)))?;
+
+        match result.next().await.map_err(|e| GraphError::QueryFailed(format!("Failed to fetch result: {}", e)))? {
+            Some(row) => Ok(row.get::<String>("written_at").ok()),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether a replica has the version of a record written at `written_at`.
+    /// A failed check counts as not caught up, so it is retried until the deadline.
+    async fn record_caught_up(&self, graph: &Graph, tenant: &TenantId, id: Uuid, written_at: &str) -> bool {
+        let mut params = record_params(tenant, id);
+        params.insert("written_at".to_string(), Value::String(written_at.to_string()));
+        let query = Query::new(self.cypher(queries::RECORD_CAUGHT_UP)).params(params);
+
+        let caught_up: Neo4jResult<bool> = async {
+            let mut result = graph.execute(query).await?;
+            Ok(match result.next().await? {
+                Some(row) => row.get::<i64>("caught_up").unwrap_or(0) > 0,
+                None => false,
+            })
+        }.await;
+
+        caught_up.unwrap_or_else(|e| {
+            debug!("Replication check for {} failed: {}", id, e);
+            false
+        })
+    }
+
+    /// Run a summary count query, grouped by label or relationship type
+    async fn read_counts(&self, tenant: &TenantId, template: &str) -> Result<Vec<CountRow>, GraphError> {
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        let query = Query::new(self.cypher(template)).params(params);
+
+        self.read(tenant, |graph| {
+            let query = query.clone();
+            async move {
+                let mut result = graph.execute(query).await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to count graph: {}", e)))?;
+
+                let mut rows = Vec::new();
+                while let Some(row) = result.next().await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                    let key: String = row.get("key")
+                        .map_err(|e| GraphError::QueryFailed(format!("Missing key: {}", e)))?;
+                    let count: i64 = row.get("count")
+                        .map_err(|e| GraphError::QueryFailed(format!("Missing count: {}", e)))?;
+                    let property_count: i64 = row.get("property_count").unwrap_or(0);
+                    let last_write_at = row.get::<String>("last_write_at").ok()
+                        .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                        .map(|time| time.with_timezone(&Utc));
+
+                    rows.push(CountRow {
+                        key,
+                        count: count as u64,
+                        property_count: property_count as u64,
+                        last_write_at,
+                    });
+                }
+
+                Ok(rows)
+            }
+        }).await
+    }
+
+    /// Run a catalog query, returning entries sorted by name
+    async fn read_catalog_entries(&self, tenant: &TenantId, template: &str, system_keys: &[String]) -> Result<Vec<CatalogEntry>, GraphError> {
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("system_keys".to_string(), Value::from(system_keys.to_vec()));
+        let query = Query::new(self.cypher(template)).params(params);
+
+        let mut entries = self.read(tenant, |graph| {
+            let query = query.clone();
+            async move {
+                let mut result = graph.execute(query).await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to read catalog: {}", e)))?;
+
+                let mut entries: HashMap<String, CatalogEntry> = HashMap::new();
+                while let Some(row) = result.next().await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                    let name: String = row.get("name")
+                        .map_err(|e| GraphError::QueryFailed(format!("Missing name: {}", e)))?;
+                    let count: i64 = row.get("count")
+                        .map_err(|e| GraphError::QueryFailed(format!("Missing count: {}", e)))?;
+
+                    let entry = entries.entry(name.clone())
+                        .or_insert_with(|| CatalogEntry { name, ..Default::default() });
+                    match row.get::<String>("key").ok() {
+                        Some(key) => { entry.property_keys.insert(key, count as u64); }
+                        None => entry.count = count as u64,
+                    }
+                }
+
+                Ok(entries.into_values().collect::<Vec<_>>())
+            }
+        }).await?;
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
     /// Parse datetime from Neo4j value
     fn parse_datetime(&self, value: &Value) -> Result<DateTime<Utc>, GraphError> {
         match value {
@@ -159,77 +1005,242 @@ impl Neo4jStore {
 
 #[async_trait]
 impl GraphStore for Neo4jStore {
-    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
-        let system_id = Uuid::new_v4();
-        
-        let mut params = HashMap::new();
-        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
-        params.insert("system_id".to_string(), Value::String(system_id.to_string()));
-        params.insert("label".to_string(), Value::String(node.label.clone()));
-        params.insert("props".to_string(), node.props.clone());
-        
-        let query = if let Some(id_alias) = &node.id_alias {
-            params.insert("id_alias".to_string(), Value::String(id_alias.clone()));
-            Query::new(queries::UPSERT_NODE_WITH_ALIAS.to_string()).params(params)
-        } else {
-            Query::new(queries::CREATE_NODE_WITHOUT_ALIAS.to_string()).params(params)
-        };
+    async fn upsert_node(&self, tenant: &TenantId, mut node: Node) -> Result<Uuid, GraphError> {
+        tenant.validate()?;
+        self.config.system_properties.sanitize(&mut node.props)?;
+
+        if node.id_alias.is_some() {
+            let alias_namespace = node.alias_namespace.as_deref().unwrap_or(DEFAULT_ALIAS_NAMESPACE);
+            self.check_alias_conflict(tenant, &node, alias_namespace).await?;
+        }
+
+        let query = self.build_upsert_node_query(tenant, &node);
 
         debug!("Upserting node for tenant {}: {:?}", tenant, node.label);
         
         let mut result = self.graph.execute(query).await
             .map_err(|e| GraphError::QueryFailed(format!("Failed to upsert node: {}", e)))?;
+        self.bookmarks.record_write(tenant);
 
-        if let Some(row) = result.next().await
+        let id = if let Some(row) = result.next().await
             .map_err(|e| GraphError::QueryFailed(format!("Failed to get result: {}", e)))? {
             let returned_id: String = row.get("system_id")
                 .map_err(|e| GraphError::QueryFailed(format!("Missing system_id in result: {}", e)))?;
             Uuid::parse_str(&returned_id)
-                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID format: {}", e)))
+                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID format: {}", e)))?
         } else {
-            Err(GraphError::QueryFailed("No result returned from upsert".to_string()))
+            return Err(GraphError::QueryFailed("No result returned from upsert".to_string()));
+        };
+
+        self.await_replication(tenant, &[id]).await?;
+        Ok(id)
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, mut edge: TimeEdge) -> Result<Uuid, GraphError> {
+        tenant.validate()?;
+        self.config.system_properties.sanitize(&mut edge.props)?;
+        self.config.temporal_validation.validate_edge(&mut edge)?;
+        let constraints = self.edge_constraints(tenant).await?;
+
+        debug!("Upserting edge for tenant {}: {} -> {}", tenant, edge.from_node_id, edge.to_node_id);
+
+        // A transaction, so that closing the edges an exclusive kind
+        // overlaps and writing the new one happen together
+        let mut txn = self.graph.start_txn().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to start transaction: {}", e)))?;
+
+        let id = match self.insert_edge_in(&mut txn, tenant, &edge, Utc::now(), &constraints).await {
+            Ok(Some(id)) => id,
+            Ok(None) => {
+                let _ = txn.rollback().await;
+                return Err(GraphError::QueryFailed("No result returned from upsert".to_string()));
+            }
+            Err(e) => {
+                let _ = txn.rollback().await;
+                return Err(e);
+            }
+        };
+
+        txn.commit().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to commit transaction: {}", e)))?;
+        self.bookmarks.record_write(tenant);
+
+        self.await_replication(tenant, &[id]).await?;
+        Ok(id)
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        tenant.validate()?;
+        let mut chunks = nodes.chunks(BULK_CHUNK_SIZE);
+        let mut loaded = 0;
+        while let Some(mut chunk) = chunks.next().await {
+            for node in chunk.iter_mut() {
+                self.config.system_properties.sanitize(&mut node.props)?;
+                if node.id_alias.is_some() {
+                    let alias_namespace = node.alias_namespace.as_deref().unwrap_or(DEFAULT_ALIAS_NAMESPACE);
+                    self.check_alias_conflict(tenant, node, alias_namespace).await?;
+                }
+            }
+
+            // One transaction, and one wait for replicas, per chunk
+            let mut txn = self.graph.start_txn().await
+                .map_err(|e| GraphError::TransactionFailed(format!("Failed to start transaction: {}", e)))?;
+            let mut ids = Vec::with_capacity(chunk.len());
+            for node in &chunk {
+                match Self::execute_returning_id(&mut txn, self.build_upsert_node_query(tenant, node)).await {
+                    Ok(Some(id)) => ids.push(id),
+                    Ok(None) => {
+                        let _ = txn.rollback().await;
+                        return Err(GraphError::QueryFailed("No result returned from upsert".to_string()));
+                    }
+                    Err(e) => {
+                        let _ = txn.rollback().await;
+                        return Err(e);
+                    }
+                }
+            }
+            txn.commit().await
+                .map_err(|e| GraphError::TransactionFailed(format!("Failed to commit transaction: {}", e)))?;
+            self.bookmarks.record_write(tenant);
+
+            self.await_replication(tenant, &ids).await?;
+            loaded += ids.len() as u64;
+            debug!("Bulk loaded {} nodes for tenant {}", ids.len(), tenant);
         }
+        Ok(loaded)
     }
 
-    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
-        let system_id = Uuid::new_v4();
-        
-        let mut params = HashMap::new();
-        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
-        params.insert("system_id".to_string(), Value::String(system_id.to_string()));
-        params.insert("from_id".to_string(), Value::String(edge.from_node_id.to_string()));
-        params.insert("to_id".to_string(), Value::String(edge.to_node_id.to_string()));
-        params.insert("rel_type".to_string(), Value::String(edge.kind.clone()));
-        params.insert("valid_from".to_string(), Value::String(edge.valid_from.to_rfc3339()));
-        params.insert("transaction_start_time".to_string(), Value::String(edge.transaction_start_time.to_rfc3339()));
-        params.insert("props".to_string(), edge.props.clone());
-        
-        if let Some(valid_to) = edge.valid_to {
-            params.insert("valid_to".to_string(), Value::String(valid_to.to_rfc3339()));
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        tenant.validate()?;
+        let constraints = self.edge_constraints(tenant).await?;
+        let mut chunks = edges.chunks(BULK_CHUNK_SIZE);
+        let mut loaded = 0;
+        while let Some(mut chunk) = chunks.next().await {
+            for edge in chunk.iter_mut() {
+                self.config.system_properties.sanitize(&mut edge.props)?;
+                self.config.temporal_validation.validate_edge(edge)?;
+            }
+
+            let now = Utc::now();
+            let mut txn = self.graph.start_txn().await
+                .map_err(|e| GraphError::TransactionFailed(format!("Failed to start transaction: {}", e)))?;
+            let mut ids = Vec::with_capacity(chunk.len());
+            for edge in &chunk {
+                // Each edge is checked against those written before it
+                match self.insert_edge_in(&mut txn, tenant, edge, now, &constraints).await {
+                    Ok(Some(id)) => ids.push(id),
+                    Ok(None) => {
+                        let _ = txn.rollback().await;
+                        return Err(GraphError::QueryFailed("No result returned from upsert".to_string()));
+                    }
+                    Err(e) => {
+                        let _ = txn.rollback().await;
+                        return Err(e);
+                    }
+                }
+            }
+            txn.commit().await
+                .map_err(|e| GraphError::TransactionFailed(format!("Failed to commit transaction: {}", e)))?;
+            self.bookmarks.record_write(tenant);
+
+            self.await_replication(tenant, &ids).await?;
+            loaded += ids.len() as u64;
+            debug!("Bulk loaded {} edges for tenant {}", ids.len(), tenant);
+        }
+        Ok(loaded)
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, mut node: Node, mut edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        tenant.validate()?;
+        // Validate all client properties before any write
+        self.config.system_properties.sanitize(&mut node.props)?;
+        for spec in edges.iter_mut() {
+            self.config.system_properties.sanitize(&mut spec.props)?;
+            self.config.temporal_validation.validate_spec(spec)?;
+        }
+
+        if node.id_alias.is_some() {
+            let alias_namespace = node.alias_namespace.as_deref().unwrap_or(DEFAULT_ALIAS_NAMESPACE);
+            self.check_alias_conflict(tenant, &node, alias_namespace).await?;
+        }
+
+        // Resolve alias targets up front; ID targets are checked by the edge MATCH
+        let aliases: Vec<AliasKey> = edges.iter()
+            .filter_map(|spec| match &spec.target {
+                NodeRef::Alias(key) => Some(key.clone()),
+                NodeRef::Id(_) => None,
+            })
+            .collect();
+        // Resolved on the primary so targets created just before are visible
+        let resolved = self.resolve_aliases_on(&self.graph, tenant, &aliases).await?;
+
+        let mut target_ids = Vec::with_capacity(edges.len());
+        for spec in &edges {
+            let target_id = match &spec.target {
+                NodeRef::Id(id) => *id,
+                NodeRef::Alias(key) => *resolved.get(key).ok_or_else(|| {
+                    GraphError::NodeNotFound(format!("Edge target alias '{}' not found in tenant {}", key, tenant))
+                })?,
+            };
+            target_ids.push(target_id);
         }
 
-        let query = Query::new(queries::UPSERT_EDGE.to_string()).params(params);
+        debug!("Upserting node with {} edges for tenant {}: {:?}", edges.len(), tenant, node.label);
+        let constraints = self.edge_constraints(tenant).await?;
+        let now = Utc::now();
+
+        let mut txn = self.graph.start_txn().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to start transaction: {}", e)))?;
+
+        let node_query = self.build_upsert_node_query(tenant, &node);
+        let node_id = match Self::execute_returning_id(&mut txn, node_query).await {
+            Ok(Some(id)) => id,
+            Ok(None) => {
+                let _ = txn.rollback().await;
+                return Err(GraphError::QueryFailed("No result returned from upsert".to_string()));
+            }
+            Err(e) => {
+                let _ = txn.rollback().await;
+                return Err(e);
+            }
+        };
 
-        debug!("Upserting edge for tenant {}: {} -> {}", tenant, edge.from_node_id, edge.to_node_id);
-        
-        let mut result = self.graph.execute(query).await
-            .map_err(|e| GraphError::QueryFailed(format!("Failed to upsert edge: {}", e)))?;
+        let mut edge_ids = Vec::with_capacity(edges.len());
+        for (spec, target_id) in edges.iter().zip(target_ids) {
+            // Each edge is checked against those written before it
+            let edge = spec.to_time_edge(node_id, target_id);
 
-        if let Some(row) = result.next().await
-            .map_err(|e| GraphError::QueryFailed(format!("Failed to get result: {}", e)))? {
-            let returned_id: String = row.get("system_id")
-                .map_err(|e| GraphError::QueryFailed(format!("Missing system_id in result: {}", e)))?;
-            Uuid::parse_str(&returned_id)
-                .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID format: {}", e)))
-        } else {
-            Err(GraphError::QueryFailed("No result returned from upsert".to_string()))
+            match self.insert_edge_in(&mut txn, tenant, &edge, now, &constraints).await {
+                Ok(Some(id)) => edge_ids.push(id),
+                Ok(None) => {
+                    let _ = txn.rollback().await;
+                    return Err(GraphError::NodeNotFound(format!(
+                        "Edge target {} not found in tenant {}", target_id, tenant
+                    )));
+                }
+                Err(e) => {
+                    let _ = txn.rollback().await;
+                    return Err(e);
+                }
+            }
         }
+
+        txn.commit().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to commit transaction: {}", e)))?;
+        self.bookmarks.record_write(tenant);
+
+        let written: Vec<Uuid> = std::iter::once(node_id).chain(edge_ids.iter().copied()).collect();
+        self.await_replication(tenant, &written).await?;
+        Ok(NodeWithEdges { node_id, edge_ids })
     }
 
     async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        tenant.validate()?;
         match query {
             GraphQuery::Raw { query, params } => {
-                let tenant_scoped_query = self.add_tenant_filter_node(&query, tenant);
+                // Raw queries may write
+                tenant.validate()?;
+                let tenant_scoped_query = scoping::scope_query(&query, &self.config.system_properties.tenant_key())?;
                 let mut neo4j_params = params;
                 neo4j_params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
                 
@@ -237,8 +1248,10 @@ impl GraphStore for Neo4jStore {
                 
                 debug!("Executing raw query for tenant {}", tenant);
                 
+                // Raw queries may write, so they always run on the primary
                 let mut result = self.graph.execute(neo4j_query).await
                     .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
+                self.bookmarks.record_write(tenant);
                 
                 let mut paths = Vec::new();
                 while let Some(_row) = result.next().await
@@ -253,158 +1266,118 @@ impl GraphStore for Neo4jStore {
                 
                 Ok(paths)
             }
-            GraphQuery::FindNodes { labels, properties, limit } => {
-                let mut params = HashMap::new();
-                params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
-                
-                let mut query_parts = vec!["MATCH (n)".to_string()];
-                query_parts.push(format!("WHERE n._tenant_id = $tenant_id"));
-                
-                // Add label filters
-                if !labels.is_empty() {
-                    let label_filter = labels.iter()
-                        .map(|l| format!("n:{}", l))
-                        .collect::<Vec<_>>()
-                        .join(" OR ");
-                    query_parts.push(format!("AND ({})", label_filter));
-                }
-                
-                // Add property filters
-                for (key, value) in properties {
-                    let param_name = format!("prop_{}", key);
-                    params.insert(param_name.clone(), value);
-                    query_parts.push(format!("AND n.{} = ${}", key, param_name));
-                }
-                
-                if let Some(limit) = limit {
-                    query_parts.push(format!("LIMIT {}", limit));
-                }
+            GraphQuery::FindNodes { labels, properties, order_by, offset, limit } => {
+                let (mut query_parts, params) = Self::match_nodes(tenant, &labels, properties);
                 
                 query_parts.push("RETURN n".to_string());
-                let query_str = query_parts.join(" ");
+                query_parts.push(utils::build_order_clause("n", "n.created_at", "labels(n)[0]", &order_by, offset, limit));
+                let query_str = self.cypher(&query_parts.join(" "));
                 
                 let neo4j_query = Query::new(query_str).params(params);
                 
                 debug!("Finding nodes for tenant {} with labels: {:?}", tenant, labels);
                 
-                let mut result = self.graph.execute(neo4j_query).await
-                    .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
+                self.read(tenant, |graph| {
+                    let neo4j_query = neo4j_query.clone();
+                    async move {
+                        let mut result = graph.execute(neo4j_query).await
+                            .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
                 
-                let mut paths = Vec::new();
-                while let Some(row) = result.next().await
-                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
-                    if let Ok(node) = row.get::<neo4j::Node>("n") {
-                        let path_node = PathNode {
-                            id: *node.node_identity(),
-                            labels: node.labels().clone(),
-                            properties: serde_json::to_value(node.properties().clone())
-                                .unwrap_or(Value::Null),
-                        };
-                        paths.push(Path {
-                            nodes: vec![path_node],
-                            relationships: Vec::new(),
-                        });
-                    }
-                }
+                        let mut paths = Vec::new();
+                        while let Some(row) = result.next().await
+                            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                            if let Ok(node) = row.get::<neo4j::Node>("n") {
+                                let path_node = PathNode {
+                                    id: *node.node_identity(),
+                                    labels: node.labels().clone(),
+                                    properties: serde_json::to_value(node.properties().clone())
+                                        .unwrap_or(Value::Null),
+                                };
+                                paths.push(Path {
+                                    nodes: vec![path_node],
+                                    relationships: Vec::new(),
+                                });
+                            }
+                        }
                 
-                Ok(paths)
+                        Ok(paths)
+                    }
+                }).await
             }
-            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, limit } => {
-                let mut params = HashMap::new();
-                params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
-                
-                let mut query_parts = vec!["MATCH (a)-[r]->(b)".to_string()];
-                query_parts.push("WHERE r._tenant_id = $tenant_id".to_string());
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse } => {
+                let (mut query_parts, params) = self.match_relationships(tenant, from_node_id, to_node_id, &relationship_types, valid_at);
                 
-                if let Some(from_id) = from_node_id {
-                    params.insert("from_id".to_string(), Value::String(from_id.to_string()));
-                    query_parts.push("AND a.system_id = $from_id".to_string());
+                match collapse {
+                    Some(collapse) => query_parts.extend(Self::collapse_relationships(collapse)),
+                    None => query_parts.push("RETURN a, r, b".to_string()),
                 }
-                
-                if let Some(to_id) = to_node_id {
-                    params.insert("to_id".to_string(), Value::String(to_id.to_string()));
-                    query_parts.push("AND b.system_id = $to_id".to_string());
-                }
-                
-                if !relationship_types.is_empty() {
-                    let type_filter = relationship_types.iter()
-                        .map(|t| format!("type(r) = '{}'", t))
-                        .collect::<Vec<_>>()
-                        .join(" OR ");
-                    query_parts.push(format!("AND ({})", type_filter));
-                }
-                
-                if let Some(valid_at) = valid_at {
-                    params.insert("valid_at".to_string(), Value::String(valid_at.to_rfc3339()));
-                    query_parts.push("AND r.valid_from <= datetime($valid_at)".to_string());
-                    query_parts.push("AND (r.valid_to IS NULL OR datetime($valid_at) < r.valid_to)".to_string());
-                }
-                
-                if let Some(limit) = limit {
-                    query_parts.push(format!("LIMIT {}", limit));
-                }
-                
-                query_parts.push("RETURN a, r, b".to_string());
-                let query_str = query_parts.join(" ");
+                query_parts.push(utils::build_order_clause("r", "r.transaction_start_time", "type(r)", &order_by, offset, limit));
+                let query_str = self.cypher(&query_parts.join(" "));
                 
                 let neo4j_query = Query::new(query_str).params(params);
                 
                 debug!("Finding relationships for tenant {}", tenant);
                 
-                let mut result = self.graph.execute(neo4j_query).await
-                    .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
+                self.read(tenant, |graph| {
+                    let neo4j_query = neo4j_query.clone();
+                    async move {
+                        let mut result = graph.execute(neo4j_query).await
+                            .map_err(|e| GraphError::QueryFailed(format!("Query execution failed: {}", e)))?;
                 
-                let mut paths = Vec::new();
-                while let Some(row) = result.next().await
-                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                        let mut paths = Vec::new();
+                        while let Some(row) = result.next().await
+                            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
                     
-                    if let (Ok(start_node), Ok(relationship), Ok(end_node)) = (
-                        row.get::<neo4j::Node>("a"),
-                        row.get::<neo4j::Relationship>("r"),
-                        row.get::<neo4j::Node>("b")
-                    ) {
-                        let path_start = PathNode {
-                            id: *start_node.node_identity(),
-                            labels: start_node.labels().clone(),
-                            properties: serde_json::to_value(start_node.properties().clone())
-                                .unwrap_or(Value::Null),
-                        };
+                            if let (Ok(start_node), Ok(relationship), Ok(end_node)) = (
+                                row.get::<neo4j::Node>("a"),
+                                row.get::<neo4j::Relationship>("r"),
+                                row.get::<neo4j::Node>("b")
+                            ) {
+                                let path_start = PathNode {
+                                    id: *start_node.node_identity(),
+                                    labels: start_node.labels().clone(),
+                                    properties: serde_json::to_value(start_node.properties().clone())
+                                        .unwrap_or(Value::Null),
+                                };
                         
-                        let path_end = PathNode {
-                            id: *end_node.node_identity(),
-                            labels: end_node.labels().clone(),
-                            properties: serde_json::to_value(end_node.properties().clone())
-                                .unwrap_or(Value::Null),
-                        };
+                                let path_end = PathNode {
+                                    id: *end_node.node_identity(),
+                                    labels: end_node.labels().clone(),
+                                    properties: serde_json::to_value(end_node.properties().clone())
+                                        .unwrap_or(Value::Null),
+                                };
                         
-                        let path_rel = PathRelationship {
-                            id: *relationship.rel_identity(),
-                            rel_type: relationship.rel_type().clone(),
-                            start_node_id: *relationship.start_node_identity(),
-                            end_node_id: *relationship.end_node_identity(),
-                            properties: serde_json::to_value(relationship.properties().clone())
-                                .unwrap_or(Value::Null),
-                        };
+                                let mut path_rel = Self::path_relationship(&relationship);
+                                if collapse.is_some() {
+                                    let (aggregate, properties) = Self::collapsed_relationship(&row)?;
+                                    path_rel.properties = properties;
+                                    path_rel.collapsed = Some(aggregate);
+                                }
                         
-                        paths.push(Path {
-                            nodes: vec![path_start, path_end],
-                            relationships: vec![path_rel],
-                        });
-                    }
-                }
+                                paths.push(Path {
+                                    nodes: vec![path_start, path_end],
+                                    relationships: vec![path_rel],
+                                });
+                            }
+                        }
                 
-                Ok(paths)
+                        Ok(paths)
+                    }
+                }).await
             }
             GraphQuery::AsOfQuery { base_query, as_of_time } => {
                 // Recursively execute the base query with temporal constraints
                 match *base_query {
-                    GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at: _, limit } => {
+                    GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at: _, order_by, offset, limit, collapse } => {
                         self.query(tenant, GraphQuery::FindRelationships {
                             from_node_id,
                             to_node_id,
                             relationship_types,
                             valid_at: Some(as_of_time),
+                            order_by,
+                            offset,
                             limit,
+                            collapse,
                         }).await
                     }
                     _ => {
@@ -416,59 +1389,190 @@ impl GraphStore for Neo4jStore {
         }
     }
 
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        tenant.validate()?;
+        if matches!(query, GraphQuery::Raw { .. }) {
+            return Ok(self.query(tenant, query).await?.len() as u64);
+        }
+        let Some((mut query_parts, params, var)) = self.match_structured(tenant, query) else {
+            return Ok(0);
+        };
+        
+        query_parts.push(format!("RETURN count({}) AS count", var));
+        self.read_count(tenant, query_parts.join(" "), params).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        tenant.validate()?;
+        if matches!(query, GraphQuery::Raw { .. }) {
+            return Ok(!self.query(tenant, query).await?.is_empty());
+        }
+        let Some((mut query_parts, params, _)) = self.match_structured(tenant, query) else {
+            return Ok(false);
+        };
+        
+        // Stop at the first match instead of counting them all
+        query_parts.push("WITH 1 AS found LIMIT 1 RETURN count(found) AS count".to_string());
+        Ok(self.read_count(tenant, query_parts.join(" "), params).await? > 0)
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        tenant.validate()?;
+        let (start, hops) = self.traversal_graph(
+            tenant,
+            request.start,
+            request.direction,
+            &request.relationship_types,
+            request.valid_at,
+            request.depth(),
+            request.weight_property(),
+        ).await?;
+        traversal::traverse(&request, start, |node_id| hops.get(&node_id).cloned().unwrap_or_default())
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        tenant.validate()?;
+        let (start, hops) = self.traversal_graph(
+            tenant,
+            request.from,
+            request.direction,
+            &request.relationship_types,
+            request.valid_at,
+            request.depth(),
+            request.weight_property(),
+        ).await?;
+        traversal::shortest_path(&request, start, |node_id| hops.get(&node_id).cloned().unwrap_or_default())
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        tenant.validate()?;
+        let range = request.range()?;
+        if self.get_node(tenant, request.node).await?.is_none() {
+            return Err(GraphError::NodeNotFound(format!("Node {} not found in tenant {}", request.node, tenant)));
+        }
+
+        // The valid time indexes narrow the node's edges to those changing in the range
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("node_id".to_string(), Value::String(request.node.to_string()));
+        params.insert("from".to_string(), Value::String(range.0.to_rfc3339()));
+        params.insert("to".to_string(), Value::String(range.1.to_rfc3339()));
+        let rel_types = if request.relationship_types.is_empty() {
+            Value::Null
+        } else {
+            Value::from(request.relationship_types.clone())
+        };
+        params.insert("rel_types".to_string(), rel_types);
+
+        let query = Query::new(self.cypher(queries::NODE_TIMELINE)).params(params);
+
+        let edges = self.read(tenant, |graph| {
+            let query = query.clone();
+            async move {
+                let mut result = graph.execute(query).await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to read timeline edges: {}", e)))?;
+
+                let mut edges = Vec::new();
+                while let Some(row) = result.next().await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                    let rel: neo4j::Relationship = row.get("r")
+                        .map_err(|e| GraphError::QueryFailed(format!("Missing relationship: {}", e)))?;
+                    let mut edge = self.convert_neo4j_relationship(&rel)?;
+
+                    let mut ids = Vec::with_capacity(3);
+                    for column in ["system_id", "from_id", "to_id"] {
+                        let system_id: String = row.get(column)
+                            .map_err(|e| GraphError::QueryFailed(format!("Missing {}: {}", column, e)))?;
+                        ids.push(Uuid::parse_str(&system_id)
+                            .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?);
+                    }
+                    edge.from_node_id = ids[1];
+                    edge.to_node_id = ids[2];
+                    edges.push(EdgeRecord { id: ids[0], edge });
+                }
+                Ok(edges)
+            }
+        }).await?;
+
+        debug!("Read {} edges changing in the timeline of node {} of tenant {}", edges.len(), request.node, tenant);
+        timeline::build_timeline(&request, range, edges)
+    }
+
     async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        tenant.validate()?;
         let mut params = HashMap::new();
         params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
         params.insert("system_id".to_string(), Value::String(id.to_string()));
         
-        let query = Query::new(queries::GET_NODE_BY_ID.to_string()).params(params);
+        let query = Query::new(self.cypher(queries::GET_NODE_BY_ID)).params(params);
         
-        let mut result = self.graph.execute(query).await
-            .map_err(|e| GraphError::QueryFailed(format!("Failed to get node: {}", e)))?;
+        self.read(tenant, |graph| {
+            let query = query.clone();
+            async move {
+                let mut result = graph.execute(query).await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to get node: {}", e)))?;
         
-        if let Some(row) = result.next().await
-            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
-            if let Ok(node) = row.get::<neo4j::Node>("n") {
-                return Ok(Some(self.convert_neo4j_node(&node)?));
-            }
-        }
+                if let Some(row) = result.next().await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                    if let Ok(node) = row.get::<neo4j::Node>("n") {
+                        return Ok(Some(self.convert_neo4j_node(&node)?));
+                    }
+                }
         
-        Ok(None)
+                Ok(None)
+            }
+        }).await
     }
 
     async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        tenant.validate()?;
         let mut params = HashMap::new();
         params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
         params.insert("id_alias".to_string(), Value::String(id_alias.to_string()));
+        params.insert("alias_namespace".to_string(), Value::String(DEFAULT_ALIAS_NAMESPACE.to_string()));
         
-        let query = Query::new(queries::GET_NODE_BY_ALIAS.to_string()).params(params);
+        let query = Query::new(self.cypher(queries::GET_NODE_BY_ALIAS)).params(params);
         
-        let mut result = self.graph.execute(query).await
-            .map_err(|e| GraphError::QueryFailed(format!("Failed to get node by alias: {}", e)))?;
+        self.read(tenant, |graph| {
+            let query = query.clone();
+            async move {
+                let mut result = graph.execute(query).await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to get node by alias: {}", e)))?;
         
-        if let Some(row) = result.next().await
-            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
-            if let Ok(node) = row.get::<neo4j::Node>("n") {
-                let system_id_str: String = row.get("system_id")
-                    .map_err(|e| GraphError::QueryFailed(format!("Missing system_id: {}", e)))?;
-                let system_id = Uuid::parse_str(&system_id_str)
-                    .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?;
-                return Ok(Some((system_id, self.convert_neo4j_node(&node)?)));
-            }
-        }
+                if let Some(row) = result.next().await
+                    .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+                    if let Ok(node) = row.get::<neo4j::Node>("n") {
+                        let system_id_str: String = row.get("system_id")
+                            .map_err(|e| GraphError::QueryFailed(format!("Missing system_id: {}", e)))?;
+                        let system_id = Uuid::parse_str(&system_id_str)
+                            .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?;
+                        return Ok(Some((system_id, self.convert_neo4j_node(&node)?)));
+                    }
+                }
         
-        Ok(None)
+                Ok(None)
+            }
+        }).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        tenant.validate()?;
+        self.read(tenant, |graph| async move {
+            self.resolve_aliases_on(&graph, tenant, aliases).await
+        }).await
     }
 
     async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        tenant.validate()?;
         let mut params = HashMap::new();
         params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
         params.insert("system_id".to_string(), Value::String(id.to_string()));
         
-        let query = Query::new(queries::DELETE_NODE.to_string()).params(params);
+        let query = Query::new(self.cypher(queries::DELETE_NODE)).params(params);
         
         let mut result = self.graph.execute(query).await
             .map_err(|e| GraphError::QueryFailed(format!("Failed to delete node: {}", e)))?;
+        self.bookmarks.record_write(tenant);
         
         if let Some(row) = result.next().await
             .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch result: {}", e)))? {
@@ -481,14 +1585,16 @@ impl GraphStore for Neo4jStore {
     }
 
     async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        tenant.validate()?;
         let mut params = HashMap::new();
         params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
         params.insert("system_id".to_string(), Value::String(id.to_string()));
         
-        let query = Query::new(queries::DELETE_EDGE.to_string()).params(params);
+        let query = Query::new(self.cypher(queries::DELETE_EDGE)).params(params);
         
         let mut result = self.graph.execute(query).await
             .map_err(|e| GraphError::QueryFailed(format!("Failed to delete edge: {}", e)))?;
+        self.bookmarks.record_write(tenant);
         
         if let Some(row) = result.next().await
             .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch result: {}", e)))? {
@@ -500,7 +1606,266 @@ impl GraphStore for Neo4jStore {
         }
     }
 
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        tenant.validate()?;
+        debug!("Closing edge {} for tenant {} at {}", id, tenant, valid_to);
+        self.replace_edge(tenant, id, |edge| edge.with_valid_to(valid_to)).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        tenant.validate()?;
+        debug!("Superseding edge {} for tenant {}", id, tenant);
+        self.replace_edge(tenant, id, |_| edge).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        tenant.validate()?;
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("system_id".to_string(), Value::String(id.to_string()));
+        params.insert("transaction_end_time".to_string(), Value::String(Utc::now().to_rfc3339()));
+
+        // Ending the version in transaction time keeps it for audit
+        let query = Query::new(self.cypher(queries::END_EDGE_TRANSACTION)).params(params);
+        
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to retract edge: {}", e)))?;
+        self.bookmarks.record_write(tenant);
+        
+        if let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch result: {}", e)))? {
+            let updated_count: i64 = row.get("updated_count")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing updated_count: {}", e)))?;
+            Ok(updated_count > 0)
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        tenant.validate()?;
+        let snapshot_at = Utc::now();
+
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("snapshot_at".to_string(), Value::String(snapshot_at.to_rfc3339()));
+        params.insert("valid_at".to_string(), valid_at.map_or(Value::Null, |t| Value::String(t.to_rfc3339())));
+
+        debug!("Reading snapshot for tenant {} as of {}", tenant, snapshot_at);
+
+        // Both reads share one transaction on the primary, and the transaction-time
+        // filter excludes anything written after the snapshot started
+        let mut txn = self.graph.start_txn().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to start transaction: {}", e)))?;
+
+        let result = self.read_snapshot(&mut txn, params).await;
+        let _ = txn.rollback().await;
+        let (nodes, edges) = result?;
+
+        Ok(GraphSnapshot { snapshot_at, valid_at, nodes, edges })
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        tenant.validate()?;
+        let ttl = Duration::from_millis(self.config.catalog_cache_ttl_ms);
+        if let Some((computed_at, catalog)) = self.catalogs.lock().unwrap().get(tenant) {
+            if computed_at.elapsed() < ttl {
+                return Ok(catalog.clone());
+            }
+        }
+
+        debug!("Computing catalog of tenant {}", tenant);
+
+        // Keys the adapter stores alongside client properties
+        let system = &self.config.system_properties;
+        let node_keys = ["system_id", "created_at", "updated_at", "id_alias"].map(String::from).into_iter()
+            .chain([system.tenant_key(), system.alias_namespace_key()])
+            .collect::<Vec<_>>();
+        let relationship_keys = ["system_id", "created_at", "valid_from", "valid_to", "transaction_start_time", "transaction_end_time", "expires_at"]
+            .map(String::from).into_iter()
+            .chain([system.tenant_key(), system.current_key()])
+            .collect::<Vec<_>>();
+
+        let catalog = GraphCatalog {
+            labels: self.read_catalog_entries(tenant, queries::CATALOG_NODES, &node_keys).await?,
+            kinds: self.read_catalog_entries(tenant, queries::CATALOG_RELATIONSHIPS, &relationship_keys).await?,
+        };
+
+        self.catalogs.lock().unwrap().insert(tenant.clone(), (Instant::now(), catalog.clone()));
+        Ok(catalog)
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        tenant.validate()?;
+        // Reads one consistent snapshot on the primary, then answers queries
+        // from memory
+        self.snapshots.materialize(self, tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        tenant.validate()?;
+        Ok(self.snapshots.list(tenant))
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        tenant.validate()?;
+        Ok(self.snapshots.remove(tenant, name))
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        tenant.validate()?;
+        debug!("Querying snapshot '{}' of tenant {}", name, tenant);
+        self.snapshots.query(tenant, name, query)
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        tenant.validate()?;
+        // Deleted nodes are removed with DETACH DELETE, so only closed edge
+        // versions are kept as history
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("before".to_string(), Value::String(before.to_rfc3339()));
+
+        let query = Query::new(self.cypher(queries::HISTORY_RELATIONSHIPS)).params(params);
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to read edge history: {}", e)))?;
+
+        let mut edges = Vec::new();
+        while let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+            let rel: neo4j::Relationship = row.get("r")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing relationship: {}", e)))?;
+            let mut edge = self.convert_neo4j_relationship(&rel)?;
+
+            let mut ids = Vec::with_capacity(3);
+            for column in ["system_id", "from_id", "to_id"] {
+                let system_id: String = row.get(column)
+                    .map_err(|e| GraphError::QueryFailed(format!("Missing {}: {}", column, e)))?;
+                ids.push(Uuid::parse_str(&system_id)
+                    .map_err(|e| GraphError::DatabaseError(format!("Invalid UUID: {}", e)))?);
+            }
+            edge.from_node_id = ids[1];
+            edge.to_node_id = ids[2];
+            edges.push(EdgeRecord { id: ids[0], edge });
+        }
+
+        debug!("Read {} closed edge versions of tenant {} before {}", edges.len(), tenant, before);
+        Ok(ArchiveBatch { nodes: Vec::new(), edges })
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        tenant.validate()?;
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("before".to_string(), Value::String(before.to_rfc3339()));
+
+        let query = Query::new(self.cypher(queries::PURGE_HISTORY_RELATIONSHIPS)).params(params);
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to purge edge history: {}", e)))?;
+        self.bookmarks.record_write(tenant);
+
+        if let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch result: {}", e)))? {
+            let deleted_count: i64 = row.get("deletedRelationships")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing deletedRelationships count: {}", e)))?;
+            Ok(deleted_count as u64)
+        } else {
+            Ok(0)
+        }
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        tenant.validate()?;
+        if !batch.nodes.is_empty() {
+            warn!("Skipping {} archived nodes for tenant {}: deleted nodes cannot be restored", batch.nodes.len(), tenant);
+        }
+
+        let mut txn = self.graph.start_txn().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to start transaction: {}", e)))?;
+
+        let mut restored = 0;
+        for record in &batch.edges {
+            let edge = &record.edge;
+            let mut params = HashMap::new();
+            params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+            params.insert("system_id".to_string(), Value::String(record.id.to_string()));
+            params.insert("from_id".to_string(), Value::String(edge.from_node_id.to_string()));
+            params.insert("to_id".to_string(), Value::String(edge.to_node_id.to_string()));
+            params.insert("rel_type".to_string(), Value::String(edge.kind.clone()));
+            params.insert("valid_from".to_string(), Value::String(edge.valid_from.to_rfc3339()));
+            params.insert("valid_to".to_string(), edge.valid_to.map_or(Value::Null, |t| Value::String(t.to_rfc3339())));
+            params.insert("transaction_start_time".to_string(), Value::String(edge.transaction_start_time.to_rfc3339()));
+            params.insert("transaction_end_time".to_string(), edge.transaction_end_time.map_or(Value::Null, |t| Value::String(t.to_rfc3339())));
+            params.insert("props".to_string(), edge.props.clone());
+
+            // Versions whose end nodes are gone, or that already exist, are skipped
+            let query = Query::new(self.cypher(queries::RESTORE_EDGE)).params(params);
+            match Self::execute_returning_id(&mut txn, query).await {
+                Ok(Some(_)) => restored += 1,
+                Ok(None) => {}
+                Err(e) => {
+                    let _ = txn.rollback().await;
+                    return Err(e);
+                }
+            }
+        }
+
+        txn.commit().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to commit transaction: {}", e)))?;
+        self.bookmarks.record_write(tenant);
+
+        debug!("Restored {} of {} archived edge versions for tenant {}", restored, batch.edges.len(), tenant);
+        Ok(restored)
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        tenant.validate()?;
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("now".to_string(), Value::String(now.to_rfc3339()));
+
+        let query = Query::new(self.cypher(queries::PURGE_EXPIRED_RELATIONSHIPS)).params(params);
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to purge expired edges: {}", e)))?;
+        self.bookmarks.record_write(tenant);
+
+        if let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch result: {}", e)))? {
+            let deleted_count: i64 = row.get("deletedRelationships")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing deletedRelationships count: {}", e)))?;
+            debug!("Removed {} expired edges of tenant {}", deleted_count, tenant);
+            Ok(deleted_count as u64)
+        } else {
+            Ok(0)
+        }
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        tenant.validate()?;
+        debug!("Summarizing graph of tenant {}", tenant);
+
+        // Both counts aggregate over the tenant indices without returning entities
+        let nodes = self.read_counts(tenant, queries::SUMMARY_NODES).await?;
+        let edges = self.read_counts(tenant, queries::SUMMARY_RELATIONSHIPS).await?;
+
+        let node_count: u64 = nodes.iter().map(|row| row.count).sum();
+        let edge_count: u64 = edges.iter().map(|row| row.count).sum();
+        let property_count: u64 = nodes.iter().chain(&edges).map(|row| row.property_count).sum();
+
+        Ok(GraphSummary {
+            node_count,
+            edge_count,
+            last_write_at: nodes.iter().chain(&edges).filter_map(|row| row.last_write_at).max(),
+            storage_bytes_estimate: node_count * NODE_RECORD_BYTES
+                + edge_count * RELATIONSHIP_RECORD_BYTES
+                + property_count * PROPERTY_RECORD_BYTES,
+            nodes_by_label: nodes.into_iter().map(|row| (row.key, row.count)).collect(),
+            edges_by_kind: edges.into_iter().map(|row| (row.key, row.count)).collect(),
+        })
+    }
+
     async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        tenant.validate()?;
         // This is a simplified implementation - in a full bitemporal system,
         // we would track transaction time as well
         warn!("get_node_history not fully implemented - returning current state only");
@@ -511,6 +1876,78 @@ impl GraphStore for Neo4jStore {
         }
     }
 
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, mut node: Node) -> Result<bool, GraphError> {
+        tenant.validate()?;
+        self.config.system_properties.sanitize(&mut node.props)?;
+        let label = utils::sanitize_label(&node.label)?;
+        let Some(current) = self.get_node(tenant, id).await? else {
+            return Ok(false);
+        };
+        let current_label = utils::sanitize_label(&current.label)?;
+
+        let mut params = record_params(tenant, id);
+        params.insert("props".to_string(), node.props);
+        let cypher = queries::UPDATE_NODE
+            .replace("${current_label}", &current_label)
+            .replace("${label}", &label);
+        let query = Query::new(self.cypher(&cypher)).params(params);
+
+        debug!("Updating node {} of tenant {}: {} -> {}", id, tenant, current_label, label);
+        let mut txn = self.graph.start_txn().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to start transaction: {}", e)))?;
+        let updated = match Self::execute_returning_id(&mut txn, query).await {
+            Ok(updated) => updated.is_some(),
+            Err(e) => {
+                let _ = txn.rollback().await;
+                return Err(e);
+            }
+        };
+        txn.commit().await
+            .map_err(|e| GraphError::TransactionFailed(format!("Failed to commit transaction: {}", e)))?;
+        self.bookmarks.record_write(tenant);
+
+        // No match if the node was deleted or relabeled since it was read
+        if updated {
+            self.await_replication(tenant, &[id]).await?;
+        }
+        Ok(updated)
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        tenant.validate()?;
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        let query = Query::new(queries::GET_EDGE_CONSTRAINTS.to_string()).params(params);
+
+        // Read on the primary, so that writes right after setting them are checked
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to read edge constraints: {}", e)))?;
+        let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? else {
+            return Ok(self.config.edge_constraints.clone());
+        };
+
+        let constraints: String = row.get("constraints")
+            .map_err(|e| GraphError::QueryFailed(format!("Missing constraints: {}", e)))?;
+        serde_json::from_str(&constraints)
+            .map_err(|e| GraphError::DatabaseError(format!("Invalid edge constraints: {}", e)))
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        tenant.validate()?;
+        let json = serde_json::to_string(&constraints)
+            .map_err(|e| GraphError::DatabaseError(format!("Failed to serialize edge constraints: {}", e)))?;
+
+        let mut params = HashMap::new();
+        params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+        params.insert("constraints".to_string(), Value::String(json));
+        self.graph.execute(Query::new(queries::SET_EDGE_CONSTRAINTS.to_string()).params(params)).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to set edge constraints: {}", e)))?;
+
+        info!("Set {} exclusive relationship kinds for tenant {}", constraints.exclusive.len(), tenant);
+        Ok(())
+    }
+
     async fn health_check(&self) -> Result<(), GraphError> {
         debug!("Performing Neo4j health check");
         
@@ -522,6 +1959,7 @@ impl GraphStore for Neo4jStore {
             .map_err(|e| GraphError::ConnectionFailed(format!("Health check result failed: {}", e)))?
             .is_some() {
             debug!("Neo4j health check passed");
+            self.check_replicas().await;
             Ok(())
         } else {
             Err(GraphError::ConnectionFailed("Health check returned no results".to_string()))
@@ -529,6 +1967,49 @@ impl GraphStore for Neo4jStore {
     }
 }
 
+#[async_trait]
+impl SchemaMigrator for Neo4jStore {
+    async fn schema_status(&self) -> Result<SchemaStatus, GraphError> {
+        self.migrator().schema_status().await
+    }
+
+    async fn migrate(&self, target: Option<u32>) -> Result<SchemaStatus, GraphError> {
+        self.migrator().migrate(target).await
+    }
+}
+
+/// Connect to the primary
+async fn connect(config: &Neo4jConfig) -> Result<Graph, GraphError> {
+    info!("Connecting to Neo4j at {}", config.uri);
+    
+    Graph::new(
+        &config.uri,
+        config.user.as_deref().unwrap_or("neo4j"),
+        config.password.as_deref().unwrap_or("neo4j")
+    )
+    .await
+    .map_err(|e| GraphError::ConnectionFailed(format!("Neo4j connection failed: {}", e)))
+}
+
+/// Rewrite a query template to use the given system property names
+fn rewrite_system_keys(system: &SystemProperties, template: &str) -> String {
+    if system.prefix == DEFAULT_SYSTEM_PREFIX {
+        return template.to_string();
+    }
+
+    template
+        .replace("_tenant_id", &system.tenant_key())
+        .replace("_alias_namespace", &system.alias_namespace_key())
+        .replace("_current", &system.current_key())
+}
+
+fn record_params(tenant: &TenantId, id: Uuid) -> HashMap<String, Value> {
+    let mut params = HashMap::new();
+    params.insert("tenant_id".to_string(), Value::String(tenant.to_string()));
+    params.insert("system_id".to_string(), Value::String(id.to_string()));
+    params
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -542,9 +2023,78 @@ mod tests {
             password: Some("password".to_string()),
             max_connections: 10,
             connection_timeout_ms: 5000,
+            system_properties: SystemProperties::default(),
+            temporal_validation: TemporalValidation::default(),
+            read_replicas: Vec::new(),
+            read_after_write_ms: 2000,
+            replica_retry_ms: 30_000,
+            replication_timeout_ms: 5000,
+            catalog_cache_ttl_ms: 60_000,
+            schema_check: SchemaCheck::Require,
+            skip_index_creation: false,
+            migration_lock_timeout_ms: 120_000,
+            ids: IdStrategy::UuidV7,
+            current_view: false,
+            edge_constraints: EdgeConstraints::default(),
         };
         
         assert_eq!(config.uri, "bolt://localhost:7687");
         assert_eq!(config.max_connections, 10);
     }
+
+    /// Store whose primary is never contacted: the driver only connects on
+    /// first use
+    async fn unconnected_store() -> Neo4jStore {
+        let config = Neo4jConfig::new("bolt://localhost:7687");
+        Neo4jStore {
+            graph: connect(&config).await.unwrap(),
+            replicas: ReplicaSet::connect(&config).await,
+            bookmarks: Bookmarks::new(Duration::from_millis(config.read_after_write_ms)),
+            catalogs: Mutex::new(HashMap::new()),
+            snapshots: SnapshotRegistry::default(),
+            ids: config.ids.build(),
+            config,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_malformed_tenant_before_writing() {
+        let store = unconnected_store().await;
+        let tenant = TenantId::new("Bad Tenant!");
+        let id = Uuid::new_v4();
+        let edge = TimeEdge::new(Uuid::new_v4(), Uuid::new_v4(), "KNOWS", Utc::now(), serde_json::json!({}));
+        let invalid = |result: Result<(), GraphError>| matches!(result, Err(GraphError::InvalidTenant(_)));
+
+        assert!(invalid(store.delete_node(&tenant, id).await.map(drop)));
+        assert!(invalid(store.delete_edge(&tenant, id).await.map(drop)));
+        assert!(invalid(store.close_edge(&tenant, id, Utc::now()).await.map(drop)));
+        assert!(invalid(store.supersede_edge(&tenant, id, edge).await.map(drop)));
+        assert!(invalid(store.retract_edge(&tenant, id).await.map(drop)));
+        assert!(invalid(store.update_node(&tenant, id, Node::new("Person")).await.map(drop)));
+        assert!(invalid(store.purge_history(&tenant, Utc::now()).await.map(drop)));
+        assert!(invalid(store.set_edge_constraints(&tenant, EdgeConstraints::default()).await));
+        assert!(invalid(store.get_node(&tenant, id).await.map(drop)));
+    }
+
+    #[test]
+    fn test_neo4j_replica_config() {
+        let config = Neo4jConfig::new("bolt://primary:7687")
+            .with_read_replicas(["bolt://replica-1:7687", "bolt://replica-2:7687"])
+            .with_read_after_write(500)
+            .with_replication_timeout(1000);
+
+        assert_eq!(config.read_replicas.len(), 2);
+        assert_eq!(config.read_after_write_ms, 500);
+        assert_eq!(config.replication_timeout_ms, 1000);
+
+        let parsed: Neo4jConfig = serde_json::from_value(serde_json::json!({
+            "uri": "bolt://primary:7687",
+            "user": null,
+            "password": null,
+            "max_connections": 10,
+            "connection_timeout_ms": 5000
+        })).unwrap();
+        assert!(parsed.read_replicas.is_empty());
+        assert_eq!(parsed.replica_retry_ms, 30_000);
+    }
 }
\ No newline at end of file
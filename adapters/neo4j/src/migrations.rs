@@ -0,0 +1,486 @@
+//! Versioned migrations of the Neo4j indexes
+//!
+//! Each applied migration is recorded as a `_TelaMentisMigration` node. A
+//! database with TelaMentis data but no records predates the migrations and
+//! is taken to be at version 1, whose indexes earlier releases created on
+//! startup.
+//!
+//! Replicas starting together take turns through a lock node, so only one
+//! applies migrations at a time. `CREATE INDEX ... IF NOT EXISTS` silently
+//! keeps an index of the same name that differs, so after migrating, the
+//! indexes the migrations create are verified against those in the database.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use neo4j::{Graph, Query};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use telamentis_core::prelude::*;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::{connect, queries, rewrite_system_keys, Neo4jConfig};
+
+/// How long the migration lock is held without being renewed; a replica
+/// that dies while migrating blocks the others at most this long
+const MIGRATION_LOCK_LEASE: Duration = Duration::from_secs(60);
+
+/// How often a replica waiting for the migration lock tries to take it
+const MIGRATION_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A versioned change to the indexes
+struct Migration {
+    version: u32,
+    description: &'static str,
+    statements: &'static [&'static str],
+}
+
+/// All migrations, oldest first; append new ones with the next version
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Tenant, alias, temporal and system ID indexes",
+        statements: &[
+            // Tenant isolation index
+            "CREATE INDEX tenant_node_idx IF NOT EXISTS FOR (n) ON (n._tenant_id)",
+            "CREATE INDEX tenant_rel_idx IF NOT EXISTS FOR ()-[r]-() ON (r._tenant_id)",
+            // Node alias index, unique per (tenant, namespace, alias)
+            "CREATE INDEX node_alias_idx IF NOT EXISTS FOR (n) ON (n._tenant_id, n._alias_namespace, n.id_alias)",
+            // Temporal indices
+            "CREATE INDEX valid_from_idx IF NOT EXISTS FOR ()-[r]-() ON (r.valid_from)",
+            "CREATE INDEX valid_to_idx IF NOT EXISTS FOR ()-[r]-() ON (r.valid_to)",
+            // Transaction time indices
+            "CREATE INDEX transaction_start_idx IF NOT EXISTS FOR ()-[r]-() ON (r.transaction_start_time)",
+            "CREATE INDEX transaction_end_idx IF NOT EXISTS FOR ()-[r]-() ON (r.transaction_end_time)",
+            // System ID index
+            "CREATE INDEX system_id_idx IF NOT EXISTS FOR (n) ON (n.system_id)",
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "Tenant-scoped transaction and valid time indexes",
+        statements: &[
+            "CREATE INDEX tenant_transaction_end_idx IF NOT EXISTS FOR ()-[r]-() ON (r._tenant_id, r.transaction_end_time)",
+            "CREATE INDEX tenant_valid_time_idx IF NOT EXISTS FOR ()-[r]-() ON (r._tenant_id, r.valid_from, r.valid_to)",
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "Current-state view of edges",
+        statements: &[
+            "CREATE INDEX current_edge_idx IF NOT EXISTS FOR ()-[r]-() ON (r._tenant_id, r._current)",
+            // Mark the edges written before the view was maintained
+            "MATCH ()-[r]->() WHERE r._tenant_id IS NOT NULL AND r.transaction_end_time IS NULL AND r.valid_to IS NULL SET r._current = true",
+        ],
+    },
+];
+
+/// Whether an index covers nodes or relationships
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexEntity {
+    Node,
+    Relationship,
+}
+
+/// An index as created by a migration or found in the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexSpec {
+    pub name: String,
+    pub entity: IndexEntity,
+    pub properties: Vec<String>,
+}
+
+impl std::fmt::Display for IndexSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entity = match self.entity {
+            IndexEntity::Node => "nodes",
+            IndexEntity::Relationship => "relationships",
+        };
+        write!(f, "{} on {} ({})", self.name, entity, self.properties.join(", "))
+    }
+}
+
+/// An expected index whose namesake in the database differs from it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexMismatch {
+    pub expected: IndexSpec,
+    pub found: IndexSpec,
+    /// State of the index found, e.g. `ONLINE` or `FAILED`
+    pub state: String,
+}
+
+/// Expected indexes that the database lacks or has in another form
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IndexReport {
+    /// Number of indexes the migrations create
+    pub expected: usize,
+    pub missing: Vec<IndexSpec>,
+    pub mismatched: Vec<IndexMismatch>,
+}
+
+impl IndexReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.mismatched.is_empty()
+    }
+
+    /// One-line summary of the problems, for logs and errors
+    pub fn summary(&self) -> String {
+        let missing = self.missing.iter().map(|index| format!("missing {}", index.name));
+        let mismatched = self.mismatched.iter().map(|m| format!("mismatched {} (found {}, {})", m.expected.name, m.found, m.state));
+        missing.chain(mismatched).collect::<Vec<_>>().join("; ")
+    }
+}
+
+/// The index a `CREATE INDEX <name> IF NOT EXISTS FOR <pattern> ON (<properties>)`
+/// statement creates; `None` for any other statement
+fn index_spec(statement: &str) -> Option<IndexSpec> {
+    let rest = statement.strip_prefix("CREATE INDEX ")?;
+    let (name, rest) = rest.split_once(' ')?;
+    let (pattern, properties) = rest.split_once(" ON (")?;
+    let entity = if pattern.contains("-[") { IndexEntity::Relationship } else { IndexEntity::Node };
+    let properties = properties.strip_suffix(')')?
+        .split(',')
+        .map(|property| property.trim().split_once('.').map_or(property.trim(), |(_, key)| key).to_string())
+        .collect();
+    Some(IndexSpec { name: name.to_string(), entity, properties })
+}
+
+/// Compare the expected indexes with those found. An index found under
+/// another name that covers the same properties counts as present, since
+/// `IF NOT EXISTS` does not create an equivalent one.
+fn compare_indexes(expected: &[IndexSpec], found: &[(IndexSpec, String)]) -> IndexReport {
+    let mut report = IndexReport { expected: expected.len(), ..IndexReport::default() };
+    for index in expected {
+        let named = found.iter().find(|(candidate, _)| candidate.name == index.name);
+        let equivalent = |candidate: &IndexSpec| candidate.entity == index.entity && candidate.properties == index.properties;
+        match named {
+            Some((candidate, state)) if !equivalent(candidate) || state == "FAILED" => report.mismatched.push(IndexMismatch {
+                expected: index.clone(),
+                found: candidate.clone(),
+                state: state.clone(),
+            }),
+            Some(_) => {}
+            None if found.iter().any(|(candidate, _)| equivalent(candidate)) => {}
+            None => report.missing.push(index.clone()),
+        }
+    }
+    report
+}
+
+/// Reads and applies the schema migrations of a Neo4j database
+pub struct Neo4jMigrator {
+    graph: Graph,
+    system_properties: SystemProperties,
+    /// How long to wait for another replica's migration
+    lock_timeout: Duration,
+    /// Identifies this migrator as holder of the migration lock
+    holder: Uuid,
+}
+
+impl Neo4jMigrator {
+    /// Connect to the primary without checking its schema, e.g. to migrate
+    /// it before a store is started against it
+    pub async fn connect(config: &Neo4jConfig) -> Result<Self, GraphError> {
+        Ok(Self::new(connect(config).await?, config))
+    }
+
+    pub(crate) fn new(graph: Graph, config: &Neo4jConfig) -> Self {
+        Self {
+            graph,
+            system_properties: config.system_properties.clone(),
+            lock_timeout: Duration::from_millis(config.migration_lock_timeout_ms),
+            holder: Uuid::new_v4(),
+        }
+    }
+
+    /// Startup check: an empty database is migrated in any case, one that is
+    /// behind is handled as `check` says, and the indexes are verified once
+    /// the schema is current. With `skip_index_creation` nothing is created
+    /// and only the indexes are verified.
+    pub(crate) async fn check(&self, check: SchemaCheck, skip_index_creation: bool) -> Result<(), GraphError> {
+        if skip_index_creation {
+            return self.check_indexes(check).await;
+        }
+
+        let status = self.schema_status().await?;
+        if status.is_current() {
+            debug!("Neo4j schema is at version {}", status.current_version);
+            return self.check_indexes(check).await;
+        }
+
+        if status.current_version == 0 || check == SchemaCheck::Migrate {
+            self.migrate(None).await?;
+            return self.check_indexes(check).await;
+        }
+
+        let pending = status.pending()
+            .map(|m| format!("{} ({})", m.version, m.description))
+            .collect::<Vec<_>>()
+            .join(", ");
+        match check {
+            SchemaCheck::Warn => {
+                warn!("Neo4j schema is at version {} of {}; pending migrations: {}", status.current_version, status.latest_version, pending);
+                Ok(())
+            }
+            _ => Err(GraphError::SchemaOutdated(format!(
+                "Neo4j schema is at version {} of {}; run `kgctl migrate apply` or set schema_check to warn. Pending migrations: {}",
+                status.current_version, status.latest_version, pending
+            ))),
+        }
+    }
+
+    /// Fail, or warn if `check` says so, when the indexes differ from those
+    /// the migrations create
+    async fn check_indexes(&self, check: SchemaCheck) -> Result<(), GraphError> {
+        let report = self.verify_indexes().await?;
+        if report.is_ok() {
+            debug!("All {} expected Neo4j indexes are present", report.expected);
+            return Ok(());
+        }
+
+        match check {
+            SchemaCheck::Warn => {
+                warn!("Neo4j indexes differ from the schema: {}", report.summary());
+                Ok(())
+            }
+            _ => Err(GraphError::SchemaOutdated(format!(
+                "Neo4j indexes differ from the schema: {}; run `kgctl migrate verify` for details or set schema_check to warn",
+                report.summary()
+            ))),
+        }
+    }
+
+    /// Compare the indexes in the database with those the migrations create
+    pub async fn verify_indexes(&self) -> Result<IndexReport, GraphError> {
+        let expected: Vec<IndexSpec> = MIGRATIONS.iter()
+            .flat_map(|m| m.statements)
+            .filter_map(|statement| index_spec(&self.cypher(statement)))
+            .collect();
+
+        let mut result = self.graph.execute(Query::new(queries::SHOW_INDEXES.to_string())).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to list indexes: {}", e)))?;
+        let mut found = Vec::new();
+        while let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+            let name: String = row.get("name")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing index name: {}", e)))?;
+            let entity = match row.get::<String>("entity_type").unwrap_or_default().as_str() {
+                "RELATIONSHIP" => IndexEntity::Relationship,
+                _ => IndexEntity::Node,
+            };
+            let properties: Vec<String> = row.get("properties").unwrap_or_default();
+            let state: String = row.get("state").unwrap_or_default();
+            found.push((IndexSpec { name, entity, properties }, state));
+        }
+        Ok(compare_indexes(&expected, &found))
+    }
+
+    /// Take the migration lock, or renew it if this migrator holds it;
+    /// returns the holder, which is another replica's if it is taken
+    async fn try_lock(&self) -> Result<String, GraphError> {
+        let mut params = HashMap::new();
+        params.insert("holder".to_string(), Value::String(self.holder.to_string()));
+        params.insert("lease_ms".to_string(), Value::from(MIGRATION_LOCK_LEASE.as_millis() as u64));
+        let query = Query::new(queries::ACQUIRE_MIGRATION_LOCK.to_string()).params(params);
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::DatabaseError(format!("Failed to take the migration lock: {}", e)))?;
+
+        let row = result.next().await
+            .map_err(|e| GraphError::DatabaseError(format!("Failed to fetch row: {}", e)))?
+            .ok_or_else(|| GraphError::DatabaseError("Migration lock query returned no row".to_string()))?;
+        row.get("holder").map_err(|e| GraphError::DatabaseError(format!("Missing lock holder: {}", e)))
+    }
+
+    /// Wait for the migration lock until the lock timeout
+    async fn lock(&self) -> Result<(), GraphError> {
+        self.graph.execute(Query::new(queries::MIGRATION_LOCK_CONSTRAINT.to_string())).await
+            .map_err(|e| GraphError::DatabaseError(format!("Failed to create the migration lock constraint: {}", e)))?;
+
+        let deadline = Instant::now() + self.lock_timeout;
+        loop {
+            let holder = self.try_lock().await?;
+            if holder == self.holder.to_string() {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(GraphError::Timeout(format!(
+                    "Replica {} was still migrating the schema after {}ms", holder, self.lock_timeout.as_millis()
+                )));
+            }
+            debug!("Waiting for replica {} to finish migrating the schema", holder);
+            tokio::time::sleep(MIGRATION_LOCK_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn unlock(&self) {
+        let mut params = HashMap::new();
+        params.insert("holder".to_string(), Value::String(self.holder.to_string()));
+        let query = Query::new(queries::RELEASE_MIGRATION_LOCK.to_string()).params(params);
+        if let Err(e) = self.graph.execute(query).await {
+            warn!("Failed to release the migration lock; it expires within {}s: {}", MIGRATION_LOCK_LEASE.as_secs(), e);
+        }
+    }
+
+    /// Apply the migrations up to `target` while holding the lock
+    async fn migrate_locked(&self, target: u32) -> Result<SchemaStatus, GraphError> {
+        // Another replica may have migrated while this one waited
+        let current = self.schema_status().await?.current_version;
+        for migration in MIGRATIONS.iter().filter(|m| m.version > current && m.version <= target) {
+            if self.try_lock().await? != self.holder.to_string() {
+                return Err(GraphError::DatabaseError("Lost the migration lock to another replica".to_string()));
+            }
+            self.apply(migration).await?;
+        }
+        self.schema_status().await
+    }
+
+    fn cypher(&self, template: &str) -> String {
+        rewrite_system_keys(&self.system_properties, template)
+    }
+
+    /// Recorded migrations with the time each was applied
+    async fn applied(&self) -> Result<BTreeMap<u32, Option<DateTime<Utc>>>, GraphError> {
+        let mut result = self.graph.execute(Query::new(queries::APPLIED_MIGRATIONS.to_string())).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to read migrations: {}", e)))?;
+
+        let mut applied = BTreeMap::new();
+        while let Some(row) = result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+            let version: i64 = row.get("version")
+                .map_err(|e| GraphError::QueryFailed(format!("Missing version: {}", e)))?;
+            let applied_at = row.get::<String>("applied_at").ok()
+                .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                .map(|time| time.with_timezone(&Utc));
+            applied.insert(version as u32, applied_at);
+        }
+        Ok(applied)
+    }
+
+    /// Whether any tenant has written to the database
+    async fn has_tenant_data(&self) -> Result<bool, GraphError> {
+        let query = Query::new(self.cypher(queries::HAS_TENANT_DATA));
+        let mut result = self.graph.execute(query).await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to inspect database: {}", e)))?;
+
+        Ok(match result.next().await
+            .map_err(|e| GraphError::QueryFailed(format!("Failed to fetch row: {}", e)))? {
+            Some(row) => row.get::<i64>("count").unwrap_or(0) > 0,
+            None => false,
+        })
+    }
+
+    async fn apply(&self, migration: &Migration) -> Result<(), GraphError> {
+        info!("Applying Neo4j migration {}: {}", migration.version, migration.description);
+        for statement in migration.statements {
+            let statement = self.cypher(statement);
+            debug!("Running migration statement: {}", statement);
+            self.graph.execute(Query::new(statement)).await
+                .map_err(|e| GraphError::DatabaseError(format!("Migration {} failed: {}", migration.version, e)))?;
+        }
+
+        let mut params = HashMap::new();
+        params.insert("version".to_string(), Value::from(migration.version));
+        params.insert("description".to_string(), Value::String(migration.description.to_string()));
+        let query = Query::new(queries::RECORD_MIGRATION.to_string()).params(params);
+        self.graph.execute(query).await
+            .map_err(|e| GraphError::DatabaseError(format!("Failed to record migration {}: {}", migration.version, e)))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SchemaMigrator for Neo4jMigrator {
+    async fn schema_status(&self) -> Result<SchemaStatus, GraphError> {
+        let applied = self.applied().await?;
+        let current_version = match applied.keys().next_back() {
+            Some(version) => *version,
+            None if self.has_tenant_data().await? => 1,
+            None => 0,
+        };
+
+        Ok(SchemaStatus {
+            current_version,
+            latest_version: latest_version(),
+            migrations: MIGRATIONS.iter().map(|m| MigrationInfo {
+                version: m.version,
+                description: m.description.to_string(),
+                applied_at: applied.get(&m.version).copied().flatten(),
+            }).collect(),
+        })
+    }
+
+    async fn migrate(&self, target: Option<u32>) -> Result<SchemaStatus, GraphError> {
+        let target = target.unwrap_or_else(latest_version);
+        if target > latest_version() {
+            return Err(GraphError::Unsupported(format!(
+                "Schema version {} is unknown; the latest is {}", target, latest_version()
+            )));
+        }
+
+        let status = self.schema_status().await?;
+        if status.current_version >= target {
+            return Ok(status);
+        }
+
+        self.lock().await?;
+        let status = self.migrate_locked(target).await;
+        self.unlock().await;
+        status
+    }
+}
+
+fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let expected: Vec<u32> = (1..=MIGRATIONS.len() as u32).collect();
+        assert_eq!(versions, expected);
+        assert!(MIGRATIONS.iter().all(|m| !m.statements.is_empty()));
+    }
+
+    fn spec(name: &str, entity: IndexEntity, properties: &[&str]) -> IndexSpec {
+        IndexSpec { name: name.to_string(), entity, properties: properties.iter().map(|p| p.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_index_spec() {
+        let alias = index_spec("CREATE INDEX node_alias_idx IF NOT EXISTS FOR (n) ON (n._tenant_id, n._alias_namespace, n.id_alias)");
+        assert_eq!(alias, Some(spec("node_alias_idx", IndexEntity::Node, &["_tenant_id", "_alias_namespace", "id_alias"])));
+        let current = index_spec("CREATE INDEX current_edge_idx IF NOT EXISTS FOR ()-[r]-() ON (r._tenant_id, r._current)");
+        assert_eq!(current, Some(spec("current_edge_idx", IndexEntity::Relationship, &["_tenant_id", "_current"])));
+        assert_eq!(index_spec("MATCH ()-[r]->() SET r._current = true"), None);
+    }
+
+    #[test]
+    fn test_compare_indexes() {
+        let expected = vec![
+            spec("tenant_node_idx", IndexEntity::Node, &["_tenant_id"]),
+            spec("valid_from_idx", IndexEntity::Relationship, &["valid_from"]),
+            spec("system_id_idx", IndexEntity::Node, &["system_id"]),
+            spec("current_edge_idx", IndexEntity::Relationship, &["_tenant_id", "_current"]),
+        ];
+        let found = vec![
+            (spec("tenant_node_idx", IndexEntity::Node, &["_tenant_id"]), "ONLINE".to_string()),
+            // Created by an administrator under another name
+            (spec("dba_valid_from", IndexEntity::Relationship, &["valid_from"]), "ONLINE".to_string()),
+            (spec("current_edge_idx", IndexEntity::Relationship, &["_current"]), "ONLINE".to_string()),
+        ];
+
+        let report = compare_indexes(&expected, &found);
+        assert!(!report.is_ok());
+        assert_eq!(report.missing, vec![expected[2].clone()]);
+        assert_eq!(report.mismatched.len(), 1);
+        assert_eq!(report.mismatched[0].expected.name, "current_edge_idx");
+        assert!(compare_indexes(&expected[..2], &found).is_ok());
+    }
+}
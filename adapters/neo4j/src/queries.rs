@@ -2,7 +2,7 @@
 
 /// Upsert a node with id_alias (MERGE operation)
 pub const UPSERT_NODE_WITH_ALIAS: &str = r#"
-MERGE (n:${label} {id_alias: $id_alias, _tenant_id: $tenant_id})
+MERGE (n:${label} {id_alias: $id_alias, _alias_namespace: $alias_namespace, _tenant_id: $tenant_id})
 ON CREATE SET 
   n.system_id = $system_id,
   n += $props,
@@ -37,20 +37,57 @@ CREATE (from)-[r:${rel_type} {
   valid_to: CASE WHEN $valid_to IS NOT NULL THEN datetime($valid_to) ELSE null END,
   transaction_start_time: datetime($transaction_start_time),
   transaction_end_time: null,
+  expires_at: CASE WHEN $expires_at IS NOT NULL THEN datetime($expires_at) ELSE null END,
+  _current: CASE WHEN $valid_to IS NULL THEN true ELSE null END,
   created_at: datetime()
 }]->(to)
 SET r += $props
 RETURN r.system_id as system_id
 "#;
 
+/// Time a node or edge version was last written, for replication checks
+pub const RECORD_WRITTEN_AT: &str = r#"
+CALL {
+  MATCH (n {system_id: $system_id, _tenant_id: $tenant_id}) RETURN n.updated_at as written_at
+  UNION ALL
+  MATCH ()-[r {system_id: $system_id, _tenant_id: $tenant_id}]->() RETURN r.created_at as written_at
+}
+RETURN toString(written_at) as written_at
+"#;
+
+/// Whether a node or edge version written at $written_at is visible
+pub const RECORD_CAUGHT_UP: &str = r#"
+CALL {
+  MATCH (n {system_id: $system_id, _tenant_id: $tenant_id}) RETURN n.updated_at as written_at
+  UNION ALL
+  MATCH ()-[r {system_id: $system_id, _tenant_id: $tenant_id}]->() RETURN r.created_at as written_at
+}
+WITH written_at WHERE written_at >= datetime($written_at)
+RETURN count(*) as caught_up
+"#;
+
 /// Update existing edge to set transaction_end_time (for versioning)
 pub const END_EDGE_TRANSACTION: &str = r#"
 MATCH ()-[r {system_id: $system_id, _tenant_id: $tenant_id}]->()
 WHERE r.transaction_end_time IS NULL
 SET r.transaction_end_time = datetime($transaction_end_time)
+REMOVE r._current
 RETURN count(r) as updated_count
 "#;
 
+/// Get an edge version with the system IDs of its end nodes
+pub const GET_EDGE_BY_ID: &str = r#"
+MATCH (from)-[r {system_id: $system_id, _tenant_id: $tenant_id}]->(to)
+RETURN r, from.system_id as from_id, to.system_id as to_id
+"#;
+
+/// Current versions of a node's outgoing edges of one kind
+pub const CURRENT_EDGES_OF_KIND: &str = r#"
+MATCH (from {system_id: $from_id, _tenant_id: $tenant_id})-[r {_tenant_id: $tenant_id}]->(to)
+WHERE type(r) = $rel_type AND r.transaction_end_time IS NULL
+RETURN r, r.system_id as system_id
+"#;
+
 /// Get a node by system ID
 pub const GET_NODE_BY_ID: &str = r#"
 MATCH (n {system_id: $system_id, _tenant_id: $tenant_id})
@@ -60,9 +97,45 @@ RETURN n, n.system_id as system_id
 /// Get a node by id_alias
 pub const GET_NODE_BY_ALIAS: &str = r#"
 MATCH (n {id_alias: $id_alias, _tenant_id: $tenant_id})
+WHERE coalesce(n._alias_namespace, '') = $alias_namespace
 RETURN n, n.system_id as system_id
 "#;
 
+/// Find nodes holding an alias under a different label (uniqueness check before upsert)
+pub const FIND_ALIAS_CONFLICT: &str = r#"
+MATCH (n {id_alias: $id_alias, _tenant_id: $tenant_id})
+WHERE coalesce(n._alias_namespace, '') = $alias_namespace
+  AND NOT $label IN labels(n)
+RETURN n.system_id as system_id, labels(n) as labels
+LIMIT 1
+"#;
+
+/// Resolve a batch of (namespace, alias) pairs to system IDs
+pub const RESOLVE_ALIASES: &str = r#"
+UNWIND $aliases AS a
+MATCH (n {id_alias: a.alias, _tenant_id: $tenant_id})
+WHERE coalesce(n._alias_namespace, '') = a.namespace
+RETURN a.namespace as namespace, a.alias as alias, n.system_id as system_id
+"#;
+
+/// Replace the label and properties of a node, keeping its system
+/// properties and alias; labels cannot be parameters, so `${current_label}`
+/// and `${label}` are filled in before the query runs
+pub const UPDATE_NODE: &str = r#"
+MATCH (n:${current_label} {system_id: $system_id, _tenant_id: $tenant_id})
+WITH n, n.id_alias AS id_alias, n._alias_namespace AS alias_namespace, n.created_at AS created_at
+SET n = $props
+SET n.system_id = $system_id,
+  n._tenant_id = $tenant_id,
+  n.id_alias = id_alias,
+  n._alias_namespace = alias_namespace,
+  n.created_at = created_at,
+  n.updated_at = datetime()
+REMOVE n:${current_label}
+SET n:${label}
+RETURN n.system_id as system_id
+"#;
+
 /// Delete a node and all its relationships
 pub const DELETE_NODE: &str = r#"
 MATCH (n {system_id: $system_id, _tenant_id: $tenant_id})
@@ -116,6 +189,21 @@ ORDER BY r.valid_from DESC
 RETURN a, r, b
 "#;
 
+/// Current edge versions of a node whose valid time starts or ends within a range
+pub const NODE_TIMELINE: &str = r#"
+MATCH (n)-[r]-()
+WHERE n._tenant_id = $tenant_id
+  AND n.system_id = $node_id
+  AND r._tenant_id = $tenant_id
+  AND r.transaction_end_time IS NULL
+  AND (r.expires_at IS NULL OR datetime() < r.expires_at)
+  AND ($rel_types IS NULL OR type(r) IN $rel_types)
+  AND ((datetime($from) <= r.valid_from AND r.valid_from < datetime($to))
+    OR (datetime($from) <= r.valid_to AND r.valid_to < datetime($to)))
+WITH DISTINCT r
+RETURN r, r.system_id as system_id, startNode(r).system_id as from_id, endNode(r).system_id as to_id
+"#;
+
 /// Count nodes for a tenant
 pub const COUNT_NODES: &str = r#"
 MATCH (n {_tenant_id: $tenant_id})
@@ -126,4 +214,174 @@ RETURN count(n) as node_count
 pub const COUNT_RELATIONSHIPS: &str = r#"
 MATCH ()-[r {_tenant_id: $tenant_id}]->()
 RETURN count(r) as relationship_count
-"#;
\ No newline at end of file
+"#;
+/// Node counts of a tenant by label, for summaries
+pub const SUMMARY_NODES: &str = r#"
+MATCH (n)
+WHERE n._tenant_id = $tenant_id
+RETURN labels(n)[0] as key,
+  count(n) as count,
+  sum(size(keys(n))) as property_count,
+  toString(max(n.updated_at)) as last_write_at
+"#;
+
+/// Relationship counts of a tenant by type, for summaries
+pub const SUMMARY_RELATIONSHIPS: &str = r#"
+MATCH ()-[r]->()
+WHERE r._tenant_id = $tenant_id
+RETURN type(r) as key,
+  count(r) as count,
+  sum(size(keys(r))) as property_count,
+  toString(max(r.created_at)) as last_write_at
+"#;
+
+/// Node and property key counts of a tenant by label, for the catalog.
+/// The row with a null key carries the number of nodes with the label.
+pub const CATALOG_NODES: &str = r#"
+MATCH (n)
+WHERE n._tenant_id = $tenant_id
+WITH labels(n)[0] as name, n
+UNWIND [null] + [key IN keys(n) WHERE NOT key IN $system_keys] as key
+RETURN name, key, count(*) as count
+"#;
+
+/// Relationship and property key counts of a tenant by type, for the catalog
+pub const CATALOG_RELATIONSHIPS: &str = r#"
+MATCH ()-[r]->()
+WHERE r._tenant_id = $tenant_id
+WITH type(r) as name, r
+UNWIND [null] + [key IN keys(r) WHERE NOT key IN $system_keys] as key
+RETURN name, key, count(*) as count
+"#;
+
+/// Nodes of a tenant created up to the snapshot time
+pub const SNAPSHOT_NODES: &str = r#"
+MATCH (n)
+WHERE n._tenant_id = $tenant_id
+  AND (n.created_at IS NULL OR n.created_at <= datetime($snapshot_at))
+RETURN n, n.system_id as system_id
+"#;
+
+/// Edge versions of a tenant current at the snapshot time
+pub const SNAPSHOT_RELATIONSHIPS: &str = r#"
+MATCH ()-[r]->()
+WHERE r._tenant_id = $tenant_id
+  AND r.transaction_start_time <= datetime($snapshot_at)
+  AND (r.transaction_end_time IS NULL OR datetime($snapshot_at) < r.transaction_end_time)
+  AND (r.expires_at IS NULL OR datetime($snapshot_at) < r.expires_at)
+  AND ($valid_at IS NULL OR (
+    r.valid_from <= datetime($valid_at) AND 
+    (r.valid_to IS NULL OR datetime($valid_at) < r.valid_to)
+  ))
+RETURN r, r.system_id as system_id
+"#;
+
+/// Edge versions of a tenant whose transaction time ended before a cutoff
+pub const HISTORY_RELATIONSHIPS: &str = r#"
+MATCH (from)-[r]->(to)
+WHERE r._tenant_id = $tenant_id
+  AND r.transaction_end_time IS NOT NULL
+  AND r.transaction_end_time < datetime($before)
+RETURN r, r.system_id as system_id, from.system_id as from_id, to.system_id as to_id
+"#;
+
+/// Delete edge versions of a tenant whose transaction time ended before a cutoff
+pub const PURGE_HISTORY_RELATIONSHIPS: &str = r#"
+MATCH ()-[r]->()
+WHERE r._tenant_id = $tenant_id
+  AND r.transaction_end_time IS NOT NULL
+  AND r.transaction_end_time < datetime($before)
+DELETE r
+RETURN count(r) as deletedRelationships
+"#;
+
+/// Delete edges of a tenant that expired by a cutoff
+pub const PURGE_EXPIRED_RELATIONSHIPS: &str = r#"
+MATCH ()-[r]->()
+WHERE r._tenant_id = $tenant_id
+  AND r.expires_at IS NOT NULL
+  AND r.expires_at <= datetime($now)
+DELETE r
+RETURN count(r) as deletedRelationships
+"#;
+
+/// Write back an archived edge version with its original ID and times,
+/// unless a version with that ID already exists
+pub const RESTORE_EDGE: &str = r#"
+MATCH (from {system_id: $from_id, _tenant_id: $tenant_id})
+MATCH (to {system_id: $to_id, _tenant_id: $tenant_id})
+WHERE NOT EXISTS { MATCH ()-[{system_id: $system_id, _tenant_id: $tenant_id}]->() }
+CREATE (from)-[r:${rel_type} {
+  system_id: $system_id,
+  _tenant_id: $tenant_id,
+  valid_from: datetime($valid_from),
+  valid_to: CASE WHEN $valid_to IS NOT NULL THEN datetime($valid_to) ELSE null END,
+  transaction_start_time: datetime($transaction_start_time),
+  transaction_end_time: datetime($transaction_end_time),
+  created_at: datetime()
+}]->(to)
+SET r += $props
+RETURN r.system_id as system_id
+"#;
+
+/// Schema migrations recorded as applied
+pub const APPLIED_MIGRATIONS: &str = r#"
+MATCH (m:_TelaMentisMigration)
+RETURN m.version as version, toString(m.applied_at) as applied_at
+ORDER BY version
+"#;
+
+/// Record a schema migration as applied
+pub const RECORD_MIGRATION: &str = r#"
+MERGE (m:_TelaMentisMigration {version: $version})
+SET m.description = $description, m.applied_at = datetime()
+"#;
+
+/// Indexes present in the database
+pub const SHOW_INDEXES: &str = r#"
+SHOW INDEXES YIELD name, entityType, properties, state
+RETURN name, entityType as entity_type, properties, state
+"#;
+
+/// Keep two replicas from creating the migration lock node at once
+pub const MIGRATION_LOCK_CONSTRAINT: &str =
+    "CREATE CONSTRAINT migration_lock_idx IF NOT EXISTS FOR (l:_TelaMentisMigrationLock) REQUIRE l.name IS UNIQUE";
+
+/// Take the migration lock if it is free or its holder's lease ran out;
+/// returns the holder afterwards
+pub const ACQUIRE_MIGRATION_LOCK: &str = r#"
+MERGE (l:_TelaMentisMigrationLock {name: 'schema'})
+WITH l
+CALL {
+  WITH l
+  WITH l WHERE l.holder IS NULL OR l.holder = $holder OR l.expires_at < datetime()
+  SET l.holder = $holder, l.expires_at = datetime() + duration({milliseconds: $lease_ms})
+}
+RETURN l.holder as holder
+"#;
+
+/// Release the migration lock if this replica holds it
+pub const RELEASE_MIGRATION_LOCK: &str = r#"
+MATCH (l:_TelaMentisMigrationLock {name: 'schema', holder: $holder})
+REMOVE l.holder, l.expires_at
+"#;
+
+/// A tenant's relationship constraints, as JSON
+pub const GET_EDGE_CONSTRAINTS: &str = r#"
+MATCH (c:_TelaMentisEdgeConstraints {tenant: $tenant_id})
+RETURN c.constraints as constraints
+"#;
+
+/// Replace a tenant's relationship constraints
+pub const SET_EDGE_CONSTRAINTS: &str = r#"
+MERGE (c:_TelaMentisEdgeConstraints {tenant: $tenant_id})
+SET c.constraints = $constraints, c.updated_at = datetime()
+"#;
+
+/// Whether the database holds any tenant's data
+pub const HAS_TENANT_DATA: &str = r#"
+CALL {
+  MATCH (n) WHERE n._tenant_id IS NOT NULL RETURN n LIMIT 1
+}
+RETURN count(n) as count
+"#;
@@ -0,0 +1,185 @@
+//! Read/write routing between the primary and read replicas
+
+use neo4j::Graph;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use telamentis_core::prelude::*;
+use tracing::{info, warn};
+
+use crate::config::Neo4jConfig;
+
+/// Health state of a single read target
+#[derive(Debug, Default)]
+pub(crate) struct ReplicaHealth {
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl ReplicaHealth {
+    /// Whether the target may receive reads at `now`
+    pub fn is_available(&self, now: Instant) -> bool {
+        match *self.unhealthy_until.lock().unwrap() {
+            Some(until) => now >= until,
+            None => true,
+        }
+    }
+
+    /// Take the target out of rotation for `retry_after`
+    pub fn mark_failed(&self, retry_after: Duration) {
+        *self.unhealthy_until.lock().unwrap() = Some(Instant::now() + retry_after);
+    }
+
+    /// Put the target back into rotation
+    pub fn mark_ok(&self) {
+        *self.unhealthy_until.lock().unwrap() = None;
+    }
+}
+
+/// A connected read replica
+pub(crate) struct Replica {
+    pub uri: String,
+    pub graph: Graph,
+    pub health: ReplicaHealth,
+}
+
+/// Read replicas with round-robin selection and health-based failover
+pub(crate) struct ReplicaSet {
+    replicas: Vec<Replica>,
+    next: AtomicUsize,
+    retry_after: Duration,
+}
+
+impl ReplicaSet {
+    /// Connect to every configured replica. Unreachable replicas are skipped so
+    /// that the store can still start against the primary alone.
+    pub async fn connect(config: &Neo4jConfig) -> Self {
+        let mut replicas = Vec::with_capacity(config.read_replicas.len());
+
+        for uri in &config.read_replicas {
+            match Graph::new(
+                uri,
+                config.user.as_deref().unwrap_or("neo4j"),
+                config.password.as_deref().unwrap_or("neo4j")
+            ).await {
+                Ok(graph) => {
+                    info!("Connected to Neo4j read replica at {}", uri);
+                    replicas.push(Replica {
+                        uri: uri.clone(),
+                        graph,
+                        health: ReplicaHealth::default(),
+                    });
+                }
+                Err(e) => warn!("Skipping unreachable Neo4j read replica {}: {}", uri, e),
+            }
+        }
+
+        Self {
+            replicas,
+            next: AtomicUsize::new(0),
+            retry_after: Duration::from_millis(config.replica_retry_ms),
+        }
+    }
+
+    /// All connected replicas, regardless of health
+    pub fn all(&self) -> &[Replica] {
+        &self.replicas
+    }
+
+    /// Healthy replicas, starting from the next one in round-robin order
+    pub fn candidates(&self) -> Vec<&Replica> {
+        if self.replicas.is_empty() {
+            return Vec::new();
+        }
+
+        let now = Instant::now();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+
+        (0..self.replicas.len())
+            .map(|offset| &self.replicas[(start + offset) % self.replicas.len()])
+            .filter(|replica| replica.health.is_available(now))
+            .collect()
+    }
+
+    /// Take a replica out of rotation after a failed read
+    pub fn mark_failed(&self, replica: &Replica) {
+        warn!("Marking Neo4j read replica {} unhealthy for {:?}", replica.uri, self.retry_after);
+        replica.health.mark_failed(self.retry_after);
+    }
+}
+
+/// Per-tenant write bookmarks used for read-after-write consistency.
+///
+/// A bookmark records when a tenant last committed a write on the primary.
+/// Until replicas are expected to have caught up (`window`), that tenant's
+/// reads are pinned to the primary so they observe their own writes.
+#[derive(Debug)]
+pub(crate) struct Bookmarks {
+    last_write: Mutex<HashMap<TenantId, Instant>>,
+    window: Duration,
+}
+
+impl Bookmarks {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            last_write: Mutex::new(HashMap::new()),
+            window,
+        }
+    }
+
+    /// Record a committed write for the tenant
+    pub fn record_write(&self, tenant: &TenantId) {
+        if self.window.is_zero() {
+            return;
+        }
+        self.last_write.lock().unwrap().insert(tenant.clone(), Instant::now());
+    }
+
+    /// Whether reads for the tenant must go to the primary
+    pub fn requires_primary(&self, tenant: &TenantId) -> bool {
+        let mut last_write = self.last_write.lock().unwrap();
+        match last_write.get(tenant) {
+            Some(written_at) if written_at.elapsed() < self.window => true,
+            Some(_) => {
+                // Replicas have caught up; drop the bookmark
+                last_write.remove(tenant);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replica_health() {
+        let health = ReplicaHealth::default();
+        assert!(health.is_available(Instant::now()));
+
+        health.mark_failed(Duration::from_secs(60));
+        assert!(!health.is_available(Instant::now()));
+        assert!(health.is_available(Instant::now() + Duration::from_secs(61)));
+
+        health.mark_ok();
+        assert!(health.is_available(Instant::now()));
+    }
+
+    #[test]
+    fn test_bookmarks_pin_reads_after_write() {
+        let bookmarks = Bookmarks::new(Duration::from_secs(60));
+        let tenant = TenantId::new("tenant_a");
+        let other = TenantId::new("tenant_b");
+
+        assert!(!bookmarks.requires_primary(&tenant));
+        bookmarks.record_write(&tenant);
+        assert!(bookmarks.requires_primary(&tenant));
+        assert!(!bookmarks.requires_primary(&other));
+
+        let disabled = Bookmarks::new(Duration::ZERO);
+        disabled.record_write(&tenant);
+        assert!(!disabled.requires_primary(&tenant));
+    }
+}
@@ -0,0 +1,699 @@
+//! Tenant scoping of raw Cypher queries
+//!
+//! Raw queries are rewritten before they run so that they only see and
+//! create records of the calling tenant: each `MATCH` gets a `$tenant_id`
+//! predicate on every node and relationship of its pattern (anonymous ones
+//! are given a variable first), and each node and relationship created by
+//! `CREATE` or `MERGE` gets the tenant key in its property map. Constructs
+//! the rewriter cannot scope are rejected rather than run: procedure calls
+//! and subqueries, patterns inside expressions, namespaced functions other
+//! than the temporal and spatial built-ins, writes to the tenant key,
+//! properties named dynamically in `SET` and `REMOVE`, and replacing an
+//! entity's whole property map.
+
+use std::collections::HashSet;
+use telamentis_core::errors::GraphError;
+
+/// Namespaces of built-in functions. Others, such as APOC's, may run
+/// queries of their own.
+const BUILTIN_NAMESPACES: &[&str] = &["date", "datetime", "localdatetime", "localtime", "time", "duration", "point"];
+
+/// Prefix of the variables given to anonymous pattern elements
+const SCOPE_VARIABLE_PREFIX: &str = "__scoped";
+
+/// Rewrite a raw query so that it only reads and writes records of the
+/// tenant passed as `$tenant_id`
+pub fn scope_query(query: &str, tenant_key: &str) -> Result<String, GraphError> {
+    let tokens = tokenize(query)?;
+    let mut scoper = Scoper {
+        query,
+        tokens,
+        tenant_key,
+        inserts: Vec::new(),
+        bound: HashSet::new(),
+        next_variable: 0,
+    };
+    scoper.scope()?;
+
+    let mut inserts = scoper.inserts;
+    inserts.sort_by_key(|(offset, _)| *offset);
+    let mut scoped = String::with_capacity(query.len() + inserts.iter().map(|(_, text)| text.len()).sum::<usize>());
+    let mut copied = 0;
+    for (offset, text) in inserts {
+        scoped.push_str(&query[copied..offset]);
+        scoped.push_str(&text);
+        copied = offset;
+    }
+    scoped.push_str(&query[copied..]);
+    Ok(scoped)
+}
+
+fn unscopable(reason: impl std::fmt::Display) -> GraphError {
+    GraphError::TenantIsolationViolation(format!("Raw query cannot be scoped to the tenant: {}", reason))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Identifier,
+    /// Backtick-quoted identifier
+    Quoted,
+    Parameter,
+    Literal,
+    Punct(char),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, GraphError> {
+    let chars: Vec<(usize, char)> = query.char_indices().collect();
+    let offset = |i: usize| chars.get(i).map_or(query.len(), |(offset, _)| *offset);
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i].1;
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let kind = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '/' if next == Some('/') => {
+                while i < chars.len() && chars[i].1 != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i].1 == '*' && chars.get(i + 1).map(|(_, c)| *c) == Some('/')) {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(unscopable("unterminated comment"));
+                }
+                i += 2;
+                continue;
+            }
+            '\'' | '"' => {
+                i += 1;
+                while i < chars.len() && chars[i].1 != c {
+                    i += if chars[i].1 == '\\' { 2 } else { 1 };
+                }
+                if i >= chars.len() {
+                    return Err(unscopable("unterminated string"));
+                }
+                i += 1;
+                TokenKind::Literal
+            }
+            '`' => {
+                i += 1;
+                loop {
+                    match chars.get(i).map(|(_, c)| *c) {
+                        None => return Err(unscopable("unterminated quoted identifier")),
+                        Some('`') if chars.get(i + 1).map(|(_, c)| *c) == Some('`') => i += 2,
+                        Some('`') => break,
+                        Some(_) => i += 1,
+                    }
+                }
+                i += 1;
+                TokenKind::Quoted
+            }
+            '$' => {
+                i += 1;
+                while i < chars.len() && is_word(chars[i].1) {
+                    i += 1;
+                }
+                TokenKind::Parameter
+            }
+            _ if c.is_ascii_digit() => {
+                while i < chars.len() && is_word(chars[i].1) {
+                    i += 1;
+                }
+                TokenKind::Literal
+            }
+            _ if is_word(c) => {
+                while i < chars.len() && is_word(chars[i].1) {
+                    i += 1;
+                }
+                TokenKind::Identifier
+            }
+            _ => {
+                i += 1;
+                TokenKind::Punct(c)
+            }
+        };
+        tokens.push(Token { kind, start: offset(start), end: offset(i) });
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClauseKind {
+    Match,
+    Create,
+    Merge,
+    Where,
+    With,
+    Unwind,
+    Set,
+    Remove,
+    Union,
+    Other,
+}
+
+/// A top-level clause: its first keyword and the tokens after its keywords
+#[derive(Debug, Clone, Copy)]
+struct Clause {
+    kind: ClauseKind,
+    keyword: usize,
+    start: usize,
+    end: usize,
+}
+
+/// How a pattern's elements are scoped
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PatternMode {
+    /// Filter matched elements with a predicate
+    Match,
+    /// Stamp created elements with the tenant key
+    Write,
+}
+
+struct Scoper<'q> {
+    query: &'q str,
+    tokens: Vec<Token>,
+    tenant_key: &'q str,
+    /// Text to insert at byte offsets of the query
+    inserts: Vec<(usize, String)>,
+    /// Variables bound by earlier clauses of the current query part
+    bound: HashSet<String>,
+    next_variable: usize,
+}
+
+impl Scoper<'_> {
+    fn text(&self, i: usize) -> &str {
+        self.tokens.get(i).map_or("", |t| &self.query[t.start..t.end])
+    }
+
+    fn is_punct(&self, i: usize, c: char) -> bool {
+        self.tokens.get(i).is_some_and(|t| t.kind == TokenKind::Punct(c))
+    }
+
+    fn is_keyword(&self, i: usize, keyword: &str) -> bool {
+        self.tokens.get(i).is_some_and(|t| t.kind == TokenKind::Identifier) && self.text(i).eq_ignore_ascii_case(keyword)
+    }
+
+    fn is_variable(&self, i: usize) -> bool {
+        self.tokens.get(i).is_some_and(|t| matches!(t.kind, TokenKind::Identifier | TokenKind::Quoted))
+            && !self.is_keyword(i, "WHERE")
+    }
+
+    fn is_tenant_key(&self, i: usize) -> bool {
+        self.is_variable(i) && self.text(i).trim_matches('`') == self.tenant_key
+    }
+
+    /// Index of the bracket closing the one at `open`
+    fn closing(&self, open: usize) -> Result<usize, GraphError> {
+        let mut depth = 0usize;
+        for i in open..self.tokens.len() {
+            match self.tokens[i].kind {
+                TokenKind::Punct('(' | '[' | '{') => depth += 1,
+                TokenKind::Punct(')' | ']' | '}') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(unscopable("unbalanced brackets"))
+    }
+
+    /// Split `start..end` at commas outside of brackets
+    fn items(&self, start: usize, end: usize) -> Vec<(usize, usize)> {
+        let mut items = Vec::new();
+        let (mut depth, mut item_start) = (0usize, start);
+        for i in start..end {
+            match self.tokens[i].kind {
+                TokenKind::Punct('(' | '[' | '{') => depth += 1,
+                TokenKind::Punct(')' | ']' | '}') => depth = depth.saturating_sub(1),
+                TokenKind::Punct(',') if depth == 0 => {
+                    items.push((item_start, i));
+                    item_start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if item_start < end {
+            items.push((item_start, end));
+        }
+        items
+    }
+
+    fn fresh_variable(&mut self) -> String {
+        let name = format!("{}{}", SCOPE_VARIABLE_PREFIX, self.next_variable);
+        self.next_variable += 1;
+        name
+    }
+
+    fn scope(&mut self) -> Result<(), GraphError> {
+        let clauses = self.clauses()?;
+        for (index, clause) in clauses.iter().enumerate() {
+            match clause.kind {
+                ClauseKind::Match => {
+                    let predicates = self.pattern(clause.start, clause.end, PatternMode::Match)?;
+                    if predicates.is_empty() {
+                        return Err(unscopable("MATCH without a pattern"));
+                    }
+                    let predicates = predicates.join(" AND ");
+                    match clauses.get(index + 1) {
+                        Some(next) if next.kind == ClauseKind::Where && next.start < next.end => {
+                            self.inserts.push((self.tokens[next.start].start, format!("{} AND (", predicates)));
+                            self.inserts.push((self.tokens[next.end - 1].end, ")".to_string()));
+                        }
+                        _ => {
+                            let end = self.tokens[clause.end - 1].end;
+                            self.inserts.push((end, format!(" WHERE {}", predicates)));
+                        }
+                    }
+                }
+                ClauseKind::Create | ClauseKind::Merge => {
+                    self.reject_tenant_key(clause)?;
+                    self.pattern(clause.start, clause.end, PatternMode::Write)?;
+                }
+                ClauseKind::With => {
+                    self.check_expression(clause.start, clause.end)?;
+                    self.rebind(clause.start, clause.end);
+                }
+                ClauseKind::Unwind => {
+                    self.check_expression(clause.start, clause.end)?;
+                    if clause.end >= clause.start + 2 && self.is_keyword(clause.end - 2, "AS") {
+                        self.bound.insert(self.text(clause.end - 1).to_string());
+                    }
+                }
+                ClauseKind::Set => {
+                    self.reject_tenant_key(clause)?;
+                    self.check_set(clause.start, clause.end)?;
+                }
+                ClauseKind::Remove => {
+                    self.reject_tenant_key(clause)?;
+                    self.reject_dynamic_properties(clause.start, clause.end)?;
+                }
+                ClauseKind::Union => self.bound.clear(),
+                ClauseKind::Where | ClauseKind::Other => self.check_expression(clause.start, clause.end)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Split the query into top-level clauses, rejecting the clauses that
+    /// cannot be scoped
+    fn clauses(&self) -> Result<Vec<Clause>, GraphError> {
+        let mut clauses: Vec<Clause> = Vec::new();
+        let mut depth = 0usize;
+        let mut i = 0;
+        while i < self.tokens.len() {
+            match self.tokens[i].kind {
+                TokenKind::Punct('(' | '[' | '{') => depth += 1,
+                TokenKind::Punct(')' | ']' | '}') => depth = depth.saturating_sub(1),
+                TokenKind::Punct(';') if i + 1 < self.tokens.len() => {
+                    return Err(unscopable("only one statement may be run at a time"));
+                }
+                TokenKind::Identifier if !self.is_punct(i.wrapping_sub(1), '.')
+                    && !self.is_punct(i.wrapping_sub(1), ':')
+                    && !self.is_punct(i + 1, ':') => {
+                    let keyword = self.text(i).to_ascii_uppercase();
+                    if matches!(keyword.as_str(), "CALL" | "FOREACH" | "LOAD" | "USE") {
+                        return Err(unscopable(format!("{} is not supported", keyword)));
+                    }
+                    if depth > 0 {
+                        if matches!(keyword.as_str(), "MATCH" | "CREATE" | "MERGE" | "SET" | "REMOVE" | "DELETE") {
+                            return Err(unscopable("subqueries are not supported"));
+                        }
+                    } else if let Some((kind, keywords)) = self.clause_keyword(i, &keyword) {
+                        if let Some(last) = clauses.last_mut() {
+                            last.end = i;
+                        }
+                        clauses.push(Clause { kind, keyword: i, start: i + keywords, end: self.tokens.len() });
+                        i += keywords;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        if let Some(last) = clauses.last_mut() {
+            if self.is_punct(last.end.wrapping_sub(1), ';') {
+                last.end -= 1;
+            }
+        }
+        match clauses.first() {
+            Some(first) if first.keyword == 0 => Ok(clauses),
+            _ => Err(unscopable("expected a clause at the start of the query")),
+        }
+    }
+
+    /// Kind of the clause starting at `i` and the number of keywords it starts with
+    fn clause_keyword(&self, i: usize, keyword: &str) -> Option<(ClauseKind, usize)> {
+        Some(match keyword {
+            "MATCH" => (ClauseKind::Match, 1),
+            "OPTIONAL" if self.is_keyword(i + 1, "MATCH") => (ClauseKind::Match, 2),
+            "CREATE" => (ClauseKind::Create, 1),
+            "MERGE" => (ClauseKind::Merge, 1),
+            "ON" if self.is_keyword(i + 2, "SET") => (ClauseKind::Set, 3),
+            "SET" => (ClauseKind::Set, 1),
+            "REMOVE" => (ClauseKind::Remove, 1),
+            "WHERE" => (ClauseKind::Where, 1),
+            "WITH" => (ClauseKind::With, 1),
+            "UNWIND" => (ClauseKind::Unwind, 1),
+            "UNION" => (ClauseKind::Union, 1),
+            "DETACH" | "DELETE" | "RETURN" | "ORDER" | "SKIP" | "LIMIT" | "OFFSET" => (ClauseKind::Other, 1),
+            _ => return None,
+        })
+    }
+
+    /// Scope the comma-separated path patterns in `start..end`, returning the
+    /// predicates of a match
+    fn pattern(&mut self, start: usize, end: usize, mode: PatternMode) -> Result<Vec<String>, GraphError> {
+        let mut predicates = Vec::new();
+        let mut i = start;
+        while i < end {
+            // Path variable
+            if self.is_variable(i) && self.is_punct(i + 1, '=') {
+                self.bound.insert(self.text(i).to_string());
+                i += 2;
+            }
+            // shortestPath(...) and allShortestPaths(...)
+            let function = self.is_variable(i) && self.is_punct(i + 1, '(');
+            if function {
+                i += 2;
+            }
+            i = self.node(i, mode, &mut predicates)?;
+            while self.is_punct(i, '-') || (self.is_punct(i, '<') && self.is_punct(i + 1, '-')) {
+                i = self.relationship(i, mode, &mut predicates)?;
+                i = self.node(i, mode, &mut predicates)?;
+            }
+            if function {
+                if !self.is_punct(i, ')') {
+                    return Err(unscopable(format!("unexpected `{}` in pattern", self.text(i))));
+                }
+                i += 1;
+            }
+            if !self.is_punct(i, ',') {
+                break;
+            }
+            i += 1;
+        }
+        // Index hints
+        self.check_expression(i, end)?;
+        Ok(predicates)
+    }
+
+    /// Scope the node pattern at `open`, returning the index after it
+    fn node(&mut self, open: usize, mode: PatternMode, predicates: &mut Vec<String>) -> Result<usize, GraphError> {
+        if !self.is_punct(open, '(') {
+            return Err(unscopable(format!("unexpected `{}` in pattern", self.text(open))));
+        }
+        if self.is_punct(open + 1, '(') {
+            return Err(unscopable("quantified path patterns are not supported"));
+        }
+        let close = self.closing(open)?;
+        let variable = self.is_variable(open + 1).then(|| self.text(open + 1).to_string());
+        self.element(open, close, variable, mode, predicates, false)?;
+        Ok(close + 1)
+    }
+
+    /// Scope the relationship pattern starting at `start`, returning the index after it
+    fn relationship(&mut self, start: usize, mode: PatternMode, predicates: &mut Vec<String>) -> Result<usize, GraphError> {
+        let mut i = start;
+        if self.is_punct(i, '<') {
+            i += 1;
+        }
+        // The leading dash
+        i += 1;
+        if self.is_punct(i, '[') {
+            let close = self.closing(i)?;
+            let variable = self.is_variable(i + 1).then(|| self.text(i + 1).to_string());
+            let variable_length = (i + 1..close).any(|j| self.is_punct(j, '*'));
+            self.element(i, close, variable, mode, predicates, variable_length)?;
+            i = close + 1;
+        } else if mode == PatternMode::Match && self.is_punct(i, '-') {
+            // `--` or `-->`: name it to filter it
+            let name = self.fresh_variable();
+            self.inserts.push((self.tokens[i - 1].end, format!("[{}]", name)));
+            predicates.push(format!("{}.{} = $tenant_id", name, self.tenant_key));
+        }
+        if !self.is_punct(i, '-') {
+            return Err(unscopable(format!("unexpected `{}` in pattern", self.text(i))));
+        }
+        i += 1;
+        if self.is_punct(i, '>') {
+            i += 1;
+        }
+        Ok(i)
+    }
+
+    /// Scope the node or relationship pattern between the brackets at `open` and `close`
+    fn element(
+        &mut self,
+        open: usize,
+        close: usize,
+        variable: Option<String>,
+        mode: PatternMode,
+        predicates: &mut Vec<String>,
+        variable_length: bool,
+    ) -> Result<(), GraphError> {
+        // Property map and inline predicate
+        let mut map = None;
+        let mut parameter_map = false;
+        let mut i = open + 1;
+        while i < close {
+            if self.is_punct(i, '{') {
+                let map_close = self.closing(i)?;
+                self.check_expression(i + 1, map_close)?;
+                map.get_or_insert((i, map_close));
+                i = map_close + 1;
+            } else if self.tokens[i].kind == TokenKind::Parameter {
+                parameter_map = true;
+                i += 1;
+            } else if self.is_keyword(i, "WHERE") {
+                self.check_expression(i + 1, close)?;
+                break;
+            } else {
+                i += 1;
+            }
+        }
+
+        match mode {
+            PatternMode::Match => {
+                let name = match variable {
+                    Some(variable) => variable,
+                    None => {
+                        let name = self.fresh_variable();
+                        self.inserts.push((self.tokens[open].end, name.clone()));
+                        name
+                    }
+                };
+                predicates.push(if variable_length {
+                    let each = self.fresh_variable();
+                    format!("all({} IN {} WHERE {}.{} = $tenant_id)", each, name, each, self.tenant_key)
+                } else {
+                    format!("{}.{} = $tenant_id", name, self.tenant_key)
+                });
+                self.bound.insert(name);
+            }
+            PatternMode::Write => {
+                if let Some(variable) = &variable {
+                    if self.bound.contains(variable) {
+                        return Ok(());
+                    }
+                }
+                if parameter_map {
+                    return Err(unscopable("CREATE and MERGE need property maps as literals, not parameters"));
+                }
+                let stamp = format!("{}: $tenant_id", self.tenant_key);
+                match map {
+                    Some((map_open, map_close)) if map_close == map_open + 1 => {
+                        self.inserts.push((self.tokens[map_open].end, stamp));
+                    }
+                    Some((map_open, _)) => self.inserts.push((self.tokens[map_open].end, format!("{}, ", stamp))),
+                    None => self.inserts.push((self.tokens[close].start, format!(" {{{}}}", stamp))),
+                }
+                if let Some(variable) = variable {
+                    self.bound.insert(variable);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject expressions in `start..end` that could reach other tenants' records
+    fn check_expression(&self, start: usize, end: usize) -> Result<(), GraphError> {
+        for i in start..end {
+            // Subquery expressions, which may hold bare node patterns: `COUNT { (m:Secret) }`
+            let subquery = ["COUNT", "EXISTS", "COLLECT"].iter().any(|keyword| self.is_keyword(i, keyword));
+            if subquery && self.is_punct(i + 1, '{') {
+                return Err(unscopable("subqueries are not supported"));
+            }
+            // Pattern predicates and comprehensions: `(a)-->(b)`, `(a)<-[r]-(b)`
+            if self.is_punct(i, ')') {
+                let dash = if self.is_punct(i + 1, '<') { i + 2 } else { i + 1 };
+                let arrow = self.is_punct(dash, '-')
+                    && (self.is_punct(dash + 1, '-') || self.is_punct(dash + 1, '[') || (dash == i + 1 && self.is_punct(dash + 1, '>')));
+                if arrow {
+                    return Err(unscopable("patterns in expressions are not supported; use OPTIONAL MATCH"));
+                }
+            }
+            // Namespaced functions, with their names quoted or not:
+            // `apoc.cypher.run(...)`, `` `apoc`.cypher.run(...) ``
+            if self.is_variable(i) && !self.is_punct(i.wrapping_sub(1), '.') {
+                let mut j = i + 1;
+                while self.is_punct(j, '.') && self.is_variable(j + 1) {
+                    j += 2;
+                }
+                if self.is_punct(j, '(') {
+                    let name = (i..j).step_by(2).map(|k| self.text(k).trim_matches('`')).collect::<Vec<_>>().join(".");
+                    let namespace = name.split_once('.').map(|(namespace, _)| namespace.to_ascii_lowercase());
+                    if namespace.is_some_and(|namespace| !BUILTIN_NAMESPACES.contains(&namespace.as_str())) {
+                        return Err(unscopable(format!("function `{}` is not supported", name)));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn reject_tenant_key(&self, clause: &Clause) -> Result<(), GraphError> {
+        if (clause.start..clause.end).any(|i| self.is_tenant_key(i)) {
+            return Err(unscopable(format!("`{}` is managed by the store", self.tenant_key)));
+        }
+        Ok(())
+    }
+
+    /// Reject SET items that would replace the tenant key with the rest of a property map
+    fn check_set(&self, start: usize, end: usize) -> Result<(), GraphError> {
+        self.check_expression(start, end)?;
+        self.reject_dynamic_properties(start, end)?;
+        for (item, _) in self.items(start, end) {
+            let replaces = self.is_punct(item + 1, '=');
+            let merges_unknown = self.is_punct(item + 1, '+') && self.is_punct(item + 2, '=') && !self.is_punct(item + 3, '{');
+            if self.is_variable(item) && (replaces || merges_unknown) {
+                return Err(unscopable(format!(
+                    "SET {} would overwrite `{}`; set single properties or use += with a map literal",
+                    self.text(item), self.tenant_key
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject SET and REMOVE items naming their property with an expression,
+    /// `n[$key]`, which may evaluate to the tenant key
+    fn reject_dynamic_properties(&self, start: usize, end: usize) -> Result<(), GraphError> {
+        for (item, _) in self.items(start, end) {
+            if self.is_variable(item) && self.is_punct(item + 1, '[') {
+                return Err(unscopable(format!("dynamic properties such as `{}[...]` cannot be set or removed", self.text(item))));
+            }
+        }
+        Ok(())
+    }
+
+    /// Bind the variables projected by a WITH clause in `start..end`
+    fn rebind(&mut self, start: usize, end: usize) {
+        let mut bound = HashSet::new();
+        for (item_start, item_end) in self.items(start, end) {
+            let item_start = if self.is_keyword(item_start, "DISTINCT") { item_start + 1 } else { item_start };
+            if item_end == item_start + 1 && self.is_punct(item_start, '*') {
+                bound.extend(self.bound.iter().cloned());
+            } else if item_end == item_start + 1 {
+                bound.insert(self.text(item_start).to_string());
+            } else if item_end >= item_start + 2 && self.is_keyword(item_end - 2, "AS") {
+                bound.insert(self.text(item_end - 1).to_string());
+            }
+        }
+        self.bound = bound;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(query: &str) -> Result<String, GraphError> {
+        scope_query(query, "_tenant_id")
+    }
+
+    #[test]
+    fn test_scopes_matches() {
+        assert_eq!(
+            scope("MATCH (n:Person) RETURN n").unwrap(),
+            "MATCH (n:Person) WHERE n._tenant_id = $tenant_id RETURN n"
+        );
+        assert_eq!(
+            scope("MATCH (:Person)-[r:KNOWS]->(b) WHERE b.age > 30 OR b.age < 18 RETURN r").unwrap(),
+            "MATCH (__scoped0:Person)-[r:KNOWS]->(b) WHERE __scoped0._tenant_id = $tenant_id AND r._tenant_id = $tenant_id \
+             AND b._tenant_id = $tenant_id AND (b.age > 30 OR b.age < 18) RETURN r"
+        );
+        assert_eq!(
+            scope("MATCH (a)-->(b) OPTIONAL MATCH p = (b)-[*1..3]-(c) RETURN p").unwrap(),
+            "MATCH (a)-[__scoped0]->(b) WHERE a._tenant_id = $tenant_id AND __scoped0._tenant_id = $tenant_id \
+             AND b._tenant_id = $tenant_id OPTIONAL MATCH p = (b)-[__scoped1*1..3]-(c) WHERE b._tenant_id = $tenant_id \
+             AND all(__scoped2 IN __scoped1 WHERE __scoped2._tenant_id = $tenant_id) AND c._tenant_id = $tenant_id RETURN p"
+        );
+    }
+
+    #[test]
+    fn test_stamps_writes() {
+        assert_eq!(
+            scope("MATCH (a {id_alias: $a}) CREATE (a)-[:KNOWS {since: 2020}]->(b:Person {name: 'Bo'}) RETURN b").unwrap(),
+            "MATCH (a {id_alias: $a}) WHERE a._tenant_id = $tenant_id CREATE (a)-[:KNOWS {_tenant_id: $tenant_id, since: 2020}]->\
+             (b:Person {_tenant_id: $tenant_id, name: 'Bo'}) RETURN b"
+        );
+        // `a` is no longer bound after the WITH, so this MERGE creates or matches a new node
+        assert_eq!(
+            scope("MATCH (a) WITH count(a) AS total MERGE (a:Stats) SET a.total = total").unwrap(),
+            "MATCH (a) WHERE a._tenant_id = $tenant_id WITH count(a) AS total MERGE (a:Stats {_tenant_id: $tenant_id}) SET a.total = total"
+        );
+    }
+
+    #[test]
+    fn test_rejects_unscopable_queries() {
+        let rejected = [
+            "CALL db.labels()",
+            "MATCH (n) WHERE EXISTS { MATCH (n)-->(m) } RETURN n",
+            "MATCH (n) RETURN COUNT { (m:Secret) }",
+            "MATCH (n) WHERE EXISTS { (m {ssn: 'x'}) } RETURN n",
+            "MATCH (n) WITH n, collect { (m) } AS others RETURN others",
+            "MATCH (n {score: count { (m) }}) RETURN n",
+            "MATCH (n) RETURN size((n)-->())",
+            "MATCH (n) RETURN [(n)<-[:KNOWS]-(m) | m.name]",
+            "RETURN apoc.cypher.runFirstColumnSingle('MATCH (n) RETURN n', {})",
+            "RETURN `apoc`.cypher.runFirstColumnSingle('MATCH (n) RETURN count(n)', {})",
+            "RETURN `apoc.cypher.runFirstColumnSingle`('MATCH (n) RETURN count(n)', {})",
+            "MATCH (n) SET n._tenant_id = 'other'",
+            "MATCH (n) SET n = $properties",
+            "MATCH (n) SET n[$key] = 'x'",
+            "MATCH (n) SET n.name = 'x', n['_tenant' + '_id'] = 'other'",
+            "MATCH (n) REMOVE n[$key]",
+            "CREATE (n $properties)",
+            "MERGE (n {`_tenant_id`: 'other'})",
+            "MATCH (n) RETURN n; MATCH (m) DETACH DELETE m",
+        ];
+        for query in rejected {
+            assert!(matches!(scope(query), Err(GraphError::TenantIsolationViolation(_))), "{} was not rejected", query);
+        }
+        assert!(scope("MATCH (n) RETURN date.truncate('day', n.created) - duration('P1D'), count(n) - 1").is_ok());
+        assert!(scope("MATCH (n) WHERE exists(n.email) RETURN count(*)").is_ok());
+        assert!(scope("MATCH (n) SET n.tags = n.tags[0..2] REMOVE n.draft RETURN `datetime`.fromepoch(0)").is_ok());
+    }
+}
@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use serde_json::Value;
 use std::collections::HashMap;
 use telamentis_core::errors::GraphError;
+use telamentis_core::types::{OrderBy, SortDirection, SortField};
 
 /// Convert Neo4j properties to JSON Value
 pub fn neo4j_props_to_json(props: &HashMap<String, Value>) -> Result<Value, GraphError> {
@@ -77,6 +78,49 @@ pub fn build_property_filters(
     (where_clause, params)
 }
 
+/// Build the ORDER BY, SKIP and LIMIT clauses of a structured query.
+///
+/// `created_at` and `label` are the Cypher expressions for those sort fields
+/// of `var`. Ties are broken by `system_id`, so pages are stable.
+pub fn build_order_clause(
+    var: &str,
+    created_at: &str,
+    label: &str,
+    order_by: &[OrderBy],
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> String {
+    let mut clauses = Vec::new();
+
+    if !order_by.is_empty() {
+        let mut keys: Vec<String> = order_by.iter()
+            .map(|key| {
+                let expr = match &key.field {
+                    SortField::Property(name) => format!("{}.`{}`", var, name.replace('`', "``")),
+                    SortField::CreatedAt => created_at.to_string(),
+                    SortField::Label => label.to_string(),
+                };
+                match key.direction {
+                    SortDirection::Asc => expr,
+                    SortDirection::Desc => format!("{} DESC", expr),
+                }
+            })
+            .collect();
+        keys.push(format!("{}.system_id", var));
+        clauses.push(format!("ORDER BY {}", keys.join(", ")));
+    }
+
+    if let Some(offset) = offset {
+        clauses.push(format!("SKIP {}", offset));
+    }
+
+    if let Some(limit) = limit {
+        clauses.push(format!("LIMIT {}", limit));
+    }
+
+    clauses.join(" ")
+}
+
 /// Generate a unique parameter name
 pub fn generate_param_name(base: &str, index: usize) -> String {
     format!("{}_{}", base, index)
@@ -126,6 +170,22 @@ mod tests {
         assert_eq!(params.get("prop_age").unwrap(), &json!(30));
     }
 
+    #[test]
+    fn test_build_order_clause() {
+        let order_by = vec![
+            OrderBy::asc(SortField::Label),
+            OrderBy::desc(SortField::Property("last`name".to_string())),
+            OrderBy::desc(SortField::CreatedAt),
+        ];
+        let clause = build_order_clause("n", "n.created_at", "labels(n)[0]", &order_by, Some(20), Some(10));
+        assert_eq!(
+            clause,
+            "ORDER BY labels(n)[0], n.`last``name` DESC, n.created_at DESC, n.system_id SKIP 20 LIMIT 10"
+        );
+
+        assert_eq!(build_order_clause("r", "r.transaction_start_time", "type(r)", &[], None, Some(5)), "LIMIT 5");
+    }
+
     #[test]
     fn test_is_valid_identifier() {
         assert!(is_valid_identifier("validName"));
@@ -0,0 +1,100 @@
+//! Criterion benchmarks for the ingestion hot paths against the in-memory
+//! store, parameterized by payload size. Run with `cargo bench -p telamentis-bench`.
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::json;
+use std::sync::Arc;
+use telamentis_adapter_in_memory::InMemoryStore;
+use telamentis_bench::{bench_node, extraction_context, find_nodes_query, ingest_envelope, payload, SyntheticConnector};
+use telamentis_core::traits::{GraphStore, LlmConnector};
+use telamentis_core::types::{TenantId, TimeEdge};
+use tokio::runtime::Runtime;
+
+const PAYLOAD_SIZES: &[usize] = &[64, 1_024, 16_384];
+
+/// Nodes written before the edge and query benchmarks run
+const SEED_NODES: usize = 1_000;
+
+fn upserts(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let tenant = TenantId::new("bench");
+    let mut group = c.benchmark_group("upsert");
+    group.throughput(Throughput::Elements(1));
+
+    for &size in PAYLOAD_SIZES {
+        let store = Arc::new(InMemoryStore::new());
+        let payload = payload(size);
+        let mut n = 0;
+        group.bench_with_input(BenchmarkId::new("node", size), &payload, |b, payload| {
+            b.to_async(&runtime).iter(|| {
+                n += 1;
+                let (store, tenant, node) = (store.clone(), tenant.clone(), bench_node(n, payload));
+                async move { store.upsert_node(&tenant, node).await.unwrap() }
+            })
+        });
+
+        let ids = runtime.block_on(seed(&store, &tenant, &payload));
+        let mut n = 0;
+        group.bench_with_input(BenchmarkId::new("edge", size), &payload, |b, payload| {
+            b.to_async(&runtime).iter(|| {
+                n += 1;
+                let edge = TimeEdge::new(ids[n % ids.len()], ids[(n + 1) % ids.len()], "RELATED_TO", Utc::now(), json!({ "payload": payload }));
+                let (store, tenant) = (store.clone(), tenant.clone());
+                async move { store.upsert_edge(&tenant, edge).await.unwrap() }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn queries(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let tenant = TenantId::new("bench");
+    let store = Arc::new(InMemoryStore::new());
+    runtime.block_on(seed(&store, &tenant, &payload(256)));
+
+    let mut n = 0;
+    c.bench_function("query/find_nodes", |b| {
+        b.to_async(&runtime).iter(|| {
+            n += 1;
+            let (store, tenant) = (store.clone(), tenant.clone());
+            async move { store.query(&tenant, find_nodes_query(n % SEED_NODES)).await.unwrap() }
+        })
+    });
+}
+
+fn extraction_ingest(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let tenant = TenantId::new("bench");
+    let store = Arc::new(InMemoryStore::new());
+    let connector = Arc::new(SyntheticConnector::new(0));
+    let mut group = c.benchmark_group("extraction_ingest");
+
+    for entities in [10, 100] {
+        let mut n = 0;
+        group.throughput(Throughput::Elements(entities as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(entities), &entities, |b, &entities| {
+            b.to_async(&runtime).iter(|| {
+                n += 1;
+                let (store, connector, tenant) = (store.clone(), connector.clone(), tenant.clone());
+                async move {
+                    let envelope = connector.extract(&tenant, extraction_context(n, entities)).await.unwrap();
+                    ingest_envelope(store.as_ref(), &tenant, envelope).await.unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+async fn seed(store: &InMemoryStore, tenant: &TenantId, payload: &str) -> Vec<uuid::Uuid> {
+    let mut ids = Vec::with_capacity(SEED_NODES);
+    for n in 0..SEED_NODES {
+        ids.push(store.upsert_node(tenant, bench_node(n, payload)).await.unwrap());
+    }
+    ids
+}
+
+criterion_group!(benches, upserts, queries, extraction_ingest);
+criterion_main!(benches);
@@ -0,0 +1,504 @@
+//! Soft real-time ingestion benchmarks
+//!
+//! Measures how a `GraphStore` holds up under a synthetic multi-tenant
+//! workload: node and edge upsert throughput, query latency distributions,
+//! and the end-to-end latency of extracting an envelope from text and writing
+//! it to the graph. Tenants run concurrently, each issuing its operations one
+//! after another, so latencies reflect contention between tenants.
+//!
+//! The `telamentis-bench` binary runs a [`Workload`] and writes a
+//! [`BenchReport`] as JSON; [`compare`] checks a report against an earlier
+//! one so CI can flag regressions. The criterion suite in `benches/` covers
+//! the same operations against the in-memory store for local profiling.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use telamentis_core::errors::{CoreError, LlmError};
+use telamentis_core::traits::{
+    ExtractionContext, ExtractionEnvelope, ExtractionMetadata, ExtractionNode, ExtractionRelation,
+    GraphStore, LlmConnector, LlmMessage,
+};
+use telamentis_core::types::{GraphQuery, Node, TenantId, TimeEdge};
+use uuid::Uuid;
+
+/// Label of the nodes written by the workload
+const NODE_LABEL: &str = "BenchEntity";
+
+/// Kind of the edges written by the workload
+const EDGE_KIND: &str = "RELATED_TO";
+
+/// Shape and size of a benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workload {
+    /// Tenants written to concurrently
+    pub tenants: usize,
+    /// Nodes upserted per tenant
+    pub nodes_per_tenant: usize,
+    /// Edges upserted from each node
+    pub edges_per_node: usize,
+    /// Size of the string property carried by every node and edge, in bytes
+    pub payload_bytes: usize,
+    /// Queries of each kind issued per tenant
+    pub queries_per_tenant: usize,
+    /// Texts extracted and ingested per tenant
+    pub extractions_per_tenant: usize,
+    /// Entities mentioned in each extracted text
+    pub entities_per_extraction: usize,
+    /// Simulated model latency of each extraction, in milliseconds
+    pub extraction_latency_ms: u64,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            tenants: 4,
+            nodes_per_tenant: 1_000,
+            edges_per_node: 2,
+            payload_bytes: 256,
+            queries_per_tenant: 200,
+            extractions_per_tenant: 50,
+            entities_per_extraction: 10,
+            extraction_latency_ms: 0,
+        }
+    }
+}
+
+/// Distribution of operation latencies, in microseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    /// Fastest operation
+    pub min_us: u64,
+    /// Mean over all operations
+    pub mean_us: u64,
+    /// Median
+    pub p50_us: u64,
+    /// 90th percentile
+    pub p90_us: u64,
+    /// 99th percentile
+    pub p99_us: u64,
+    /// Slowest operation
+    pub max_us: u64,
+}
+
+impl LatencySummary {
+    /// Summarize a set of latencies; empty sets summarize to zeros
+    pub fn from_durations(durations: &[Duration]) -> Self {
+        if durations.is_empty() {
+            return Self::default();
+        }
+        let mut micros: Vec<u64> = durations.iter().map(|d| d.as_micros() as u64).collect();
+        micros.sort_unstable();
+        let mean = micros.iter().sum::<u64>() / micros.len() as u64;
+
+        Self {
+            min_us: micros[0],
+            mean_us: mean,
+            p50_us: percentile(&micros, 50.0),
+            p90_us: percentile(&micros, 90.0),
+            p99_us: percentile(&micros, 99.0),
+            max_us: micros[micros.len() - 1],
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Measurements of one kind of operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageResult {
+    /// Operation measured, e.g. `node_upsert`
+    pub name: String,
+    /// Operations completed across all tenants
+    pub operations: usize,
+    /// Wall-clock time of the stage, in milliseconds
+    pub elapsed_ms: u64,
+    /// Operations per second across all tenants
+    pub throughput_per_sec: f64,
+    /// Latency of individual operations
+    pub latency: LatencySummary,
+}
+
+/// Results of a benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// Adapter the workload ran against
+    pub adapter: String,
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+    /// The workload that was run
+    pub workload: Workload,
+    /// Results per stage, in the order they ran
+    pub stages: Vec<StageResult>,
+}
+
+impl BenchReport {
+    /// Look up a stage by name
+    pub fn stage(&self, name: &str) -> Option<&StageResult> {
+        self.stages.iter().find(|stage| stage.name == name)
+    }
+}
+
+/// A stage whose results got worse than allowed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Regression {
+    /// Stage that regressed
+    pub stage: String,
+    /// Metric that regressed, `p99_us` or `throughput_per_sec`
+    pub metric: String,
+    /// Value in the baseline report
+    pub baseline: f64,
+    /// Value in the current report
+    pub current: f64,
+}
+
+/// Compare `current` against `baseline`, reporting stages whose p99 latency
+/// grew or whose throughput fell by more than `tolerance` (0.1 = 10%).
+/// Stages missing from either report are ignored.
+pub fn compare(baseline: &BenchReport, current: &BenchReport, tolerance: f64) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for stage in &current.stages {
+        let Some(base) = baseline.stage(&stage.name) else {
+            continue;
+        };
+
+        let (base_p99, p99) = (base.latency.p99_us as f64, stage.latency.p99_us as f64);
+        if p99 > base_p99 * (1.0 + tolerance) {
+            regressions.push(Regression {
+                stage: stage.name.clone(),
+                metric: "p99_us".to_string(),
+                baseline: base_p99,
+                current: p99,
+            });
+        }
+        if stage.throughput_per_sec < base.throughput_per_sec * (1.0 - tolerance) {
+            regressions.push(Regression {
+                stage: stage.name.clone(),
+                metric: "throughput_per_sec".to_string(),
+                baseline: base.throughput_per_sec,
+                current: stage.throughput_per_sec,
+            });
+        }
+    }
+    regressions
+}
+
+/// Run `workload` against `store` and collect the results.
+///
+/// Tenants are named `bench-<run>-<n>` with a fresh run ID, so runs against
+/// a shared database never see each other's data.
+pub async fn run(store: Arc<dyn GraphStore>, adapter: &str, workload: Workload) -> Result<BenchReport, CoreError> {
+    let started_at = Utc::now();
+    let run_id = Uuid::new_v4().simple().to_string();
+    let tenants: Vec<TenantId> = (0..workload.tenants)
+        .map(|n| TenantId::new(format!("bench-{}-{}", &run_id[..8], n)))
+        .collect();
+    let connector: Arc<dyn LlmConnector> = Arc::new(SyntheticConnector::new(workload.extraction_latency_ms));
+    let payload = payload(workload.payload_bytes);
+
+    let mut stages = Vec::new();
+
+    // IDs of each tenant's nodes, for the edge and relationship stages
+    let created = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+    stages.push(run_stage("node_upsert", &tenants, |tenant| {
+        let (store, created, payload, workload) = (store.clone(), created.clone(), payload.clone(), workload.clone());
+        async move {
+            let mut latencies = Vec::with_capacity(workload.nodes_per_tenant);
+            let mut ids = Vec::with_capacity(workload.nodes_per_tenant);
+            for n in 0..workload.nodes_per_tenant {
+                let start = Instant::now();
+                ids.push(store.upsert_node(&tenant, bench_node(n, &payload)).await?);
+                latencies.push(start.elapsed());
+            }
+            created.lock().await.insert(tenant, ids);
+            Ok(latencies)
+        }
+    }).await?);
+    let node_ids: Arc<HashMap<TenantId, Vec<Uuid>>> = Arc::new(std::mem::take(&mut *created.lock().await));
+
+    stages.push(run_stage("edge_upsert", &tenants, |tenant| {
+        let (store, node_ids, payload, workload) = (store.clone(), node_ids.clone(), payload.clone(), workload.clone());
+        async move {
+            let ids = &node_ids[&tenant];
+            let mut latencies = Vec::with_capacity(ids.len() * workload.edges_per_node);
+            for (n, from) in ids.iter().enumerate() {
+                for k in 1..=workload.edges_per_node {
+                    let to = ids[(n + k) % ids.len()];
+                    let edge = TimeEdge::new(*from, to, EDGE_KIND, Utc::now(), json!({ "payload": payload }));
+                    let start = Instant::now();
+                    store.upsert_edge(&tenant, edge).await?;
+                    latencies.push(start.elapsed());
+                }
+            }
+            Ok(latencies)
+        }
+    }).await?);
+
+    stages.push(run_stage("query_find_nodes", &tenants, |tenant| {
+        let (store, workload) = (store.clone(), workload.clone());
+        async move {
+            let mut latencies = Vec::with_capacity(workload.queries_per_tenant);
+            for n in 0..workload.queries_per_tenant {
+                let start = Instant::now();
+                store.query(&tenant, find_nodes_query(n % workload.nodes_per_tenant.max(1))).await?;
+                latencies.push(start.elapsed());
+            }
+            Ok(latencies)
+        }
+    }).await?);
+
+    stages.push(run_stage("query_find_relationships", &tenants, |tenant| {
+        let (store, node_ids, workload) = (store.clone(), node_ids.clone(), workload.clone());
+        async move {
+            let ids = &node_ids[&tenant];
+            let mut latencies = Vec::with_capacity(workload.queries_per_tenant);
+            for n in 0..workload.queries_per_tenant {
+                let query = GraphQuery::FindRelationships {
+                    from_node_id: ids.get(n % ids.len().max(1)).copied(),
+                    to_node_id: None,
+                    relationship_types: vec![EDGE_KIND.to_string()],
+                    valid_at: Some(Utc::now()),
+                    order_by: Vec::new(),
+                    offset: None,
+                    limit: Some(100),
+                    collapse: None,
+                };
+                let start = Instant::now();
+                store.query(&tenant, query).await?;
+                latencies.push(start.elapsed());
+            }
+            Ok(latencies)
+        }
+    }).await?);
+
+    stages.push(run_stage("extraction_ingest", &tenants, |tenant| {
+        let (store, connector, workload) = (store.clone(), connector.clone(), workload.clone());
+        async move {
+            let mut latencies = Vec::with_capacity(workload.extractions_per_tenant);
+            for n in 0..workload.extractions_per_tenant {
+                let context = extraction_context(n, workload.entities_per_extraction);
+                let start = Instant::now();
+                let envelope = connector.extract(&tenant, context).await?;
+                ingest_envelope(store.as_ref(), &tenant, envelope).await?;
+                latencies.push(start.elapsed());
+            }
+            Ok(latencies)
+        }
+    }).await?);
+
+    Ok(BenchReport {
+        adapter: adapter.to_string(),
+        started_at,
+        workload,
+        stages,
+    })
+}
+
+/// Run one task per tenant and combine their latencies into a stage result
+async fn run_stage<F, Fut>(name: &str, tenants: &[TenantId], task: F) -> Result<StageResult, CoreError>
+where
+    F: Fn(TenantId) -> Fut,
+    Fut: Future<Output = Result<Vec<Duration>, CoreError>> + Send + 'static,
+{
+    let start = Instant::now();
+    let handles: Vec<_> = tenants.iter().cloned().map(|tenant| tokio::spawn(task(tenant))).collect();
+
+    let mut latencies = Vec::new();
+    for handle in handles {
+        let tenant_latencies = handle.await
+            .map_err(|e| CoreError::Internal(format!("Benchmark task failed: {}", e)))??;
+        latencies.extend(tenant_latencies);
+    }
+    let elapsed = start.elapsed();
+
+    Ok(StageResult {
+        name: name.to_string(),
+        operations: latencies.len(),
+        elapsed_ms: elapsed.as_millis() as u64,
+        throughput_per_sec: if elapsed.is_zero() { 0.0 } else { latencies.len() as f64 / elapsed.as_secs_f64() },
+        latency: LatencySummary::from_durations(&latencies),
+    })
+}
+
+/// A string property of the given size
+pub fn payload(bytes: usize) -> String {
+    "x".repeat(bytes)
+}
+
+/// The `n`th node of a tenant's workload
+pub fn bench_node(n: usize, payload: &str) -> Node {
+    Node::new(NODE_LABEL)
+        .with_id_alias(format!("entity-{}", n))
+        .with_property("seq", json!(n))
+        .with_property("payload", json!(payload))
+}
+
+/// A lookup of one workload node by property
+pub fn find_nodes_query(n: usize) -> GraphQuery {
+    GraphQuery::FindNodes {
+        labels: vec![NODE_LABEL.to_string()],
+        properties: HashMap::from([("seq".to_string(), json!(n))]),
+        order_by: Vec::new(),
+        offset: None,
+        limit: Some(10),
+    }
+}
+
+/// Text for the `n`th extraction, mentioning `entities` entities that the
+/// [`SyntheticConnector`] picks out again
+pub fn extraction_context(n: usize, entities: usize) -> ExtractionContext {
+    let text = (0..entities)
+        .map(|e| format!("Entity {}-{} was mentioned in document {}.", n, e, n))
+        .collect::<Vec<_>>()
+        .join("\n");
+    ExtractionContext {
+        messages: vec![LlmMessage::new("user", text)],
+        system_prompt: None,
+        desired_schema: None,
+        max_tokens: None,
+        temperature: None,
+        examples: None,
+        model: None,
+        provider: None,
+        source: None,
+        seed: None,
+        graph_context: None,
+    }
+}
+
+/// Write an extracted envelope to the graph: nodes first, keyed by alias,
+/// then the relations between them. Relations to aliases that were not
+/// extracted are skipped.
+pub async fn ingest_envelope(store: &dyn GraphStore, tenant: &TenantId, envelope: ExtractionEnvelope) -> Result<(), CoreError> {
+    let mut ids = HashMap::with_capacity(envelope.nodes.len());
+    for node in envelope.nodes {
+        let id = store.upsert_node(tenant, Node::new(node.label).with_id_alias(&node.id_alias).with_props(node.props)).await?;
+        ids.insert(node.id_alias, id);
+    }
+
+    for relation in envelope.relations {
+        let (Some(&from), Some(&to)) = (ids.get(&relation.from_id_alias), ids.get(&relation.to_id_alias)) else {
+            continue;
+        };
+        let mut edge = TimeEdge::new(from, to, relation.type_label, relation.valid_from.unwrap_or_else(Utc::now), relation.props);
+        if let Some(valid_to) = relation.valid_to {
+            edge = edge.with_valid_to(valid_to);
+        }
+        store.upsert_edge(tenant, edge).await?;
+    }
+    Ok(())
+}
+
+/// Connector that "extracts" one node per line of input and links
+/// consecutive nodes, after an optional simulated model latency. It stands
+/// in for a real model so runs measure the graph store, not the provider.
+pub struct SyntheticConnector {
+    latency: Duration,
+}
+
+impl SyntheticConnector {
+    /// Create a connector that takes `latency_ms` per extraction
+    pub fn new(latency_ms: u64) -> Self {
+        Self { latency: Duration::from_millis(latency_ms) }
+    }
+}
+
+#[async_trait]
+impl LlmConnector for SyntheticConnector {
+    async fn extract(&self, _tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+
+        let nodes: Vec<ExtractionNode> = context.messages.iter()
+            .flat_map(|message| message.content.lines())
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| ExtractionNode {
+                id_alias: line.split_whitespace().take(2).collect::<Vec<_>>().join("-").to_lowercase(),
+                label: NODE_LABEL.to_string(),
+                props: json!({ "mention": line }),
+                confidence: Some(1.0),
+            })
+            .collect();
+        let relations = nodes.windows(2)
+            .map(|pair| ExtractionRelation {
+                from_id_alias: pair[0].id_alias.clone(),
+                to_id_alias: pair[1].id_alias.clone(),
+                type_label: EDGE_KIND.to_string(),
+                props: json!({}),
+                valid_from: None,
+                valid_to: None,
+                confidence: Some(1.0),
+            })
+            .collect();
+
+        Ok(ExtractionEnvelope {
+            nodes,
+            relations,
+            metadata: Some(ExtractionMetadata {
+                provider: "synthetic".to_string(),
+                model_name: "synthetic".to_string(),
+                latency_ms: Some(self.latency.as_millis() as u64),
+                ..Default::default()
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use telamentis_adapter_in_memory::InMemoryStore;
+
+    #[test]
+    fn test_latency_summary() {
+        let durations: Vec<Duration> = (1..=100).map(Duration::from_micros).collect();
+        let summary = LatencySummary::from_durations(&durations);
+        assert_eq!(summary.min_us, 1);
+        assert_eq!(summary.mean_us, 50);
+        assert_eq!(summary.p50_us, 50);
+        assert_eq!(summary.p90_us, 90);
+        assert_eq!(summary.p99_us, 99);
+        assert_eq!(summary.max_us, 100);
+        assert_eq!(LatencySummary::from_durations(&[]), LatencySummary::default());
+    }
+
+    #[tokio::test]
+    async fn test_run_and_compare() {
+        let workload = Workload {
+            tenants: 2,
+            nodes_per_tenant: 20,
+            edges_per_node: 2,
+            payload_bytes: 16,
+            queries_per_tenant: 5,
+            extractions_per_tenant: 3,
+            entities_per_extraction: 4,
+            extraction_latency_ms: 0,
+        };
+        let report = run(Arc::new(InMemoryStore::new()), "in-memory", workload).await.unwrap();
+
+        assert_eq!(report.stage("node_upsert").unwrap().operations, 40);
+        assert_eq!(report.stage("edge_upsert").unwrap().operations, 80);
+        assert_eq!(report.stage("query_find_nodes").unwrap().operations, 10);
+        assert_eq!(report.stage("extraction_ingest").unwrap().operations, 6);
+        assert!(compare(&report, &report, 0.0).is_empty());
+
+        let mut slower = report.clone();
+        for stage in &mut slower.stages {
+            stage.latency.p99_us = stage.latency.p99_us * 2 + 1;
+        }
+        let regressions = compare(&report, &slower, 0.1);
+        assert_eq!(regressions.len(), report.stages.len());
+        assert!(regressions.iter().all(|r| r.metric == "p99_us"));
+    }
+}
@@ -0,0 +1,185 @@
+//! `telamentis-bench`: run the ingestion benchmark and publish the results
+//!
+//! ```text
+//! telamentis-bench --adapter in-memory --tenants 8 --payload-bytes 1024 --output bench.json
+//! telamentis-bench --baseline main.json --tolerance 0.15
+//! ```
+//!
+//! The report is written as JSON to `--output`, or stdout. With
+//! `--baseline`, the run exits non-zero when any stage regressed beyond
+//! `--tolerance` relative to the baseline report.
+
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+use std::sync::Arc;
+use telamentis_adapter_in_memory::InMemoryStore;
+use telamentis_bench::{compare, run, BenchReport, Workload};
+use telamentis_core::errors::CoreError;
+use telamentis_core::traits::GraphStore;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Adapter {
+    /// In-process store, for measuring the core without I/O
+    InMemory,
+    /// Neo4j, when built with the `neo4j` feature
+    Neo4j,
+}
+
+impl Adapter {
+    fn name(self) -> &'static str {
+        match self {
+            Adapter::InMemory => "in-memory",
+            Adapter::Neo4j => "neo4j",
+        }
+    }
+}
+
+/// Connection settings used with `--adapter neo4j`
+#[derive(Debug, clap::Args)]
+#[cfg_attr(not(feature = "neo4j"), allow(dead_code))]
+struct Neo4jArgs {
+    /// Neo4j connection URI
+    #[arg(long, default_value = "bolt://localhost:7687")]
+    neo4j_uri: String,
+
+    /// Neo4j user
+    #[arg(long, default_value = "neo4j")]
+    neo4j_user: String,
+
+    /// Neo4j password
+    #[arg(long, default_value = "password")]
+    neo4j_password: String,
+
+    /// Only verify the indexes instead of creating them, for a database
+    /// whose user may not create indexes
+    #[arg(long)]
+    skip_index_creation: bool,
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "telamentis-bench", about = "Measure TelaMentis ingestion throughput and query latency")]
+struct Args {
+    /// Graph store to benchmark
+    #[arg(long, value_enum, default_value = "in-memory")]
+    adapter: Adapter,
+
+    #[command(flatten)]
+    neo4j: Neo4jArgs,
+
+    /// Tenants written to concurrently
+    #[arg(long, default_value_t = Workload::default().tenants)]
+    tenants: usize,
+
+    /// Nodes upserted per tenant
+    #[arg(long, default_value_t = Workload::default().nodes_per_tenant)]
+    nodes: usize,
+
+    /// Edges upserted from each node
+    #[arg(long, default_value_t = Workload::default().edges_per_node)]
+    edges_per_node: usize,
+
+    /// Size of the payload property on every node and edge, in bytes
+    #[arg(long, default_value_t = Workload::default().payload_bytes)]
+    payload_bytes: usize,
+
+    /// Queries of each kind per tenant
+    #[arg(long, default_value_t = Workload::default().queries_per_tenant)]
+    queries: usize,
+
+    /// Texts extracted and ingested per tenant
+    #[arg(long, default_value_t = Workload::default().extractions_per_tenant)]
+    extractions: usize,
+
+    /// Entities mentioned in each extracted text
+    #[arg(long, default_value_t = Workload::default().entities_per_extraction)]
+    entities: usize,
+
+    /// Simulated model latency per extraction, in milliseconds
+    #[arg(long, default_value_t = Workload::default().extraction_latency_ms)]
+    extraction_latency_ms: u64,
+
+    /// File to write the JSON report to (default: stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Earlier report to check this run against
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Allowed regression relative to the baseline (0.1 = 10%)
+    #[arg(long, default_value_t = 0.1)]
+    tolerance: f64,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run_bench(Args::parse()).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run_bench(args: Args) -> Result<(), CoreError> {
+    let workload = Workload {
+        tenants: args.tenants,
+        nodes_per_tenant: args.nodes,
+        edges_per_node: args.edges_per_node,
+        payload_bytes: args.payload_bytes,
+        queries_per_tenant: args.queries,
+        extractions_per_tenant: args.extractions,
+        entities_per_extraction: args.entities,
+        extraction_latency_ms: args.extraction_latency_ms,
+    };
+
+    let store = connect(&args).await?;
+    let report = run(store, args.adapter.name(), workload).await?;
+
+    let json = serde_json::to_string_pretty(&report)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, json)
+            .map_err(|e| CoreError::Internal(format!("Failed to write {}: {}", path.display(), e)))?,
+        None => println!("{}", json),
+    }
+
+    for stage in &report.stages {
+        eprintln!(
+            "{:<26} {:>8} ops {:>10.0} ops/s  p50 {:>7}us  p99 {:>7}us",
+            stage.name, stage.operations, stage.throughput_per_sec, stage.latency.p50_us, stage.latency.p99_us
+        );
+    }
+
+    if let Some(path) = &args.baseline {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| CoreError::Configuration(format!("Failed to read baseline {}: {}", path.display(), e)))?;
+        let baseline: BenchReport = serde_json::from_str(&content)?;
+        let regressions = compare(&baseline, &report, args.tolerance);
+        for regression in &regressions {
+            eprintln!(
+                "Regression in {} {}: {:.0} -> {:.0}",
+                regression.stage, regression.metric, regression.baseline, regression.current
+            );
+        }
+        if !regressions.is_empty() {
+            return Err(CoreError::Internal(format!("{} metrics regressed beyond {:.0}%", regressions.len(), args.tolerance * 100.0)));
+        }
+    }
+    Ok(())
+}
+
+async fn connect(args: &Args) -> Result<Arc<dyn GraphStore>, CoreError> {
+    match args.adapter {
+        Adapter::InMemory => Ok(Arc::new(InMemoryStore::new())),
+        #[cfg(feature = "neo4j")]
+        Adapter::Neo4j => {
+            use telamentis_adapter_neo4j::{Neo4jConfig, Neo4jStore};
+            let config = Neo4jConfig::new(&args.neo4j.neo4j_uri)
+                .with_auth(&args.neo4j.neo4j_user, &args.neo4j.neo4j_password)
+                .with_skip_index_creation(args.neo4j.skip_index_creation);
+            Ok(Arc::new(Neo4jStore::new(config).await?))
+        }
+        #[cfg(not(feature = "neo4j"))]
+        Adapter::Neo4j => Err(CoreError::Configuration(
+            "telamentis-bench was built without Neo4j support; rebuild with --features neo4j".to_string()
+        )),
+    }
+}
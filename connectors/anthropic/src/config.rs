@@ -1,6 +1,8 @@
 //! Configuration for Anthropic connector
 
 use serde::{Deserialize, Serialize};
+use telamentis_core::http::{HttpClientConfig, ProxyConfig};
+use telamentis_core::sandbox::{SANDBOX_API_KEY, SANDBOX_TIMEOUT_MS};
 
 /// Anthropic API configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,8 +19,15 @@ pub struct AnthropicConfig {
     pub temperature: Option<f32>,
     /// Request timeout in milliseconds
     pub timeout_ms: u64,
-    /// Maximum retries for failed requests
+    /// Maximum retries for failed requests, including extractions where the
+    /// model does not call the extraction tool
     pub max_retries: u32,
+    /// Use tool use for extraction; `None` detects support from the model
+    #[serde(default)]
+    pub structured_output: Option<bool>,
+    /// Proxy, root certificate and connection pool settings
+    #[serde(default)]
+    pub http: HttpClientConfig,
 }
 
 impl AnthropicConfig {
@@ -32,6 +41,8 @@ impl AnthropicConfig {
             temperature: Some(0.1),
             timeout_ms: 30_000,
             max_retries: 3,
+            structured_output: None,
+            http: HttpClientConfig::default(),
         }
     }
 
@@ -47,6 +58,17 @@ impl AnthropicConfig {
         self
     }
 
+    /// Route requests to a sandbox server at `base_url` instead of the real
+    /// API, with a short timeout and a placeholder API key if none is set
+    pub fn with_sandbox(mut self, base_url: impl Into<String>) -> Self {
+        self.api_base = base_url.into().trim_end_matches('/').to_string();
+        self.timeout_ms = SANDBOX_TIMEOUT_MS;
+        if self.api_key.is_empty() {
+            self.api_key = SANDBOX_API_KEY.to_string();
+        }
+        self
+    }
+
     /// Set maximum tokens
     pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
         self.max_tokens = Some(max_tokens);
@@ -70,6 +92,39 @@ impl AnthropicConfig {
         self.max_retries = max_retries;
         self
     }
+
+    /// Set the proxy, root certificate and connection pool settings
+    pub fn with_http_client(mut self, http: HttpClientConfig) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Send requests through an outbound proxy
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.http.proxy = Some(proxy);
+        self
+    }
+
+    /// Force native structured output on or off instead of detecting it
+    pub fn with_structured_output(mut self, enabled: bool) -> Self {
+        self.structured_output = Some(enabled);
+        self
+    }
+
+    /// Whether extraction should use tool use rather than JSON prompting
+    pub fn uses_structured_output(&self) -> bool {
+        self.uses_structured_output_for(&self.model)
+    }
+
+    /// Like [`Self::uses_structured_output`], for a model chosen per request
+    pub fn uses_structured_output_for(&self, model: &str) -> bool {
+        self.structured_output.unwrap_or_else(|| supports_tool_use(model))
+    }
+}
+
+/// Whether a model is known to support forced tool use (Claude 3 and later)
+fn supports_tool_use(model: &str) -> bool {
+    model.starts_with("claude-") && !model.starts_with("claude-2") && !model.starts_with("claude-instant")
 }
 
 impl Default for AnthropicConfig {
@@ -3,31 +3,50 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Instant;
 use telamentis_core::prelude::*;
 use tracing::{debug, error, info, warn};
 
 mod config;
 mod models;
+#[cfg(feature = "sandbox")]
+mod sandbox;
 
 pub use config::AnthropicConfig;
+#[cfg(feature = "sandbox")]
+pub use sandbox::AnthropicSandbox;
 use models::*;
 
 /// Anthropic implementation of LlmConnector
 pub struct AnthropicConnector {
     client: Client,
     config: AnthropicConfig,
+    exchange_log: Option<Arc<ExchangeLog>>,
 }
 
 impl AnthropicConnector {
     /// Create a new Anthropic connector
     pub fn new(config: AnthropicConfig) -> Result<Self, LlmError> {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_millis(config.timeout_ms))
+        let builder = Client::builder()
+            .timeout(std::time::Duration::from_millis(config.timeout_ms));
+        let client = config.http.apply(builder)
+            .map_err(LlmError::ConfigError)?
             .build()
             .map_err(|e| LlmError::ConfigError(format!("Failed to create HTTP client: {}", e)))?;
 
-        Ok(Self { client, config })
+        Ok(Self { client, config, exchange_log: None })
+    }
+
+    /// Record sampled requests and responses of tenants with logging enabled
+    pub fn with_exchange_log(mut self, exchange_log: Arc<ExchangeLog>) -> Self {
+        self.exchange_log = Some(exchange_log);
+        self
+    }
+
+    /// Model for an extraction: the request's model or the configured default
+    fn model<'a>(&'a self, context: &'a ExtractionContext) -> &'a str {
+        context.model.as_deref().unwrap_or(&self.config.model)
     }
 
     /// Build the system prompt for extraction
@@ -36,28 +55,69 @@ impl AnthropicConnector {
             "You are an expert knowledge graph extraction engine. Analyze the provided text/conversation and identify relevant entities (as nodes) and relationships (as relations) between them."
         );
 
+        let examples = render_examples(
+            context.examples.as_deref().unwrap_or_default(),
+            TokenEstimator::for_provider("anthropic"),
+            example_token_budget(self.model(context)),
+        );
+
         format!(
-            "{}\n\nReturn your findings strictly as a JSON object matching the following schema:\n{}\n\nInstructions:\n- `id_alias` should be a descriptive, unique identifier for nodes within this extraction (e.g., \"user_john_doe\", \"acme_corp_hq\")\n- If a date or time for `valid_from` or `valid_to` is mentioned, use ISO8601 format\n- If a relation is ongoing, `valid_to` can be omitted or null\n- Only extract explicitly mentioned information. Do not infer or hallucinate\n- If unsure about a piece of information, omit it or assign a low confidence score",
+            "{}\n\nReturn your findings strictly as a JSON object matching the following schema:\n{}\n\nInstructions:\n- `id_alias` should be a descriptive, unique identifier for nodes within this extraction (e.g., \"user_john_doe\", \"acme_corp_hq\")\n- If a date or time for `valid_from` or `valid_to` is mentioned, use ISO8601 format, only as precise as the text: \"2020\" for a year, \"2020-03\" for a month, \"2020-03-15\" for a day, a full timestamp only for an exact time\n- If a relation is ongoing, `valid_to` can be omitted or null\n- Only extract explicitly mentioned information. Do not infer or hallucinate\n- If unsure about a piece of information, omit it or assign a low confidence score{}{}",
             base_prompt,
-            ExtractionEnvelope::json_schema_example()
+            ExtractionEnvelope::json_schema_example(),
+            graph_context_section(context),
+            examples
         )
     }
 
     /// Convert TelaMentis messages to Anthropic format
     fn convert_messages(&self, context: &ExtractionContext) -> (Option<String>, Vec<Message>) {
-        let system_prompt = self.build_extraction_prompt(context);
-        
-        let messages: Vec<Message> = context.messages.iter()
-            .map(|msg| {
-                match msg.role.as_str() {
-                    "user" => Message::new_user(&msg.content),
-                    "assistant" => Message::new_assistant(&msg.content),
-                    _ => Message::new_user(&msg.content), // Default to user for other roles
-                }
-            })
-            .collect();
-        
-        (Some(system_prompt), messages)
+        split_system(Some(self.build_extraction_prompt(context)), &context.messages)
+    }
+
+    /// Build the message request for an extraction, using tool use when the
+    /// model supports it and JSON prompting otherwise
+    fn build_extraction_request(&self, context: &ExtractionContext) -> MessageRequest {
+        let model = self.model(context);
+        let structured = self.config.uses_structured_output_for(model);
+        let (system, messages) = self.convert_messages(context);
+
+        MessageRequest {
+            model: model.to_string(),
+            messages,
+            system,
+            max_tokens: context.max_tokens.or(self.config.max_tokens),
+            temperature: context.temperature.or(self.config.temperature),
+            tools: structured.then(|| vec![Tool {
+                name: ExtractionEnvelope::TOOL_NAME.to_string(),
+                description: "Record the entities and relationships extracted from the conversation".to_string(),
+                input_schema: ExtractionEnvelope::json_schema(),
+            }]),
+            tool_choice: structured.then(|| ToolChoice {
+                choice_type: "tool".to_string(),
+                name: ExtractionEnvelope::TOOL_NAME.to_string(),
+            }),
+        }
+    }
+
+    /// Get the extraction JSON from a response: the extraction tool's input
+    /// with tool use, the text otherwise. `None` when a tool-use response
+    /// does not call the tool.
+    fn extraction_content(&self, response: &MessageResponse, structured: bool) -> Result<Option<String>, LlmError> {
+        if structured {
+            let tool_input = response.content
+                .iter()
+                .filter(|c| c.content_type == "tool_use" && c.name.as_deref() == Some(ExtractionEnvelope::TOOL_NAME))
+                .find_map(|c| c.input.as_ref());
+            return Ok(tool_input.map(|input| input.to_string()));
+        }
+
+        let text = response_text(response);
+        if text.is_empty() {
+            return Err(LlmError::ResponseParseError("No content in response".to_string()));
+        }
+
+        Ok(Some(text))
     }
 
     /// Parse and validate the Anthropic response
@@ -119,9 +179,9 @@ impl AnthropicConnector {
     }
 
     /// Calculate estimated cost based on token usage
-    fn calculate_cost(&self, usage: &Usage) -> Option<f64> {
+    fn calculate_cost(&self, model: &str, usage: &Usage) -> Option<f64> {
         // Anthropic pricing (approximate, as of 2024)
-        let (input_cost_per_1k, output_cost_per_1k) = match self.config.model.as_str() {
+        let (input_cost_per_1k, output_cost_per_1k) = match model {
             "claude-3-opus" => (0.015, 0.075),
             "claude-3-sonnet" => (0.003, 0.015),
             "claude-3-haiku" => (0.00025, 0.00125),
@@ -133,61 +193,102 @@ impl AnthropicConnector {
         
         Some(input_cost + output_cost)
     }
-}
 
-#[async_trait]
-impl LlmConnector for AnthropicConnector {
-    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
-        debug!("Starting Anthropic extraction for tenant: {}", tenant);
-        let start_time = Instant::now();
+    /// Tokens left for conversation messages after the extraction prompt,
+    /// tool schema and reserved output
+    fn input_budget(&self, context: &ExtractionContext, estimator: TokenEstimator) -> usize {
+        let mut overhead = estimator.count(&self.build_extraction_prompt(context));
+        if self.config.uses_structured_output_for(self.model(context)) {
+            overhead += estimator.count(&ExtractionEnvelope::json_schema().to_string());
+        }
 
-        // Build the request
-        let (system, messages) = self.convert_messages(&context);
-        
-        let request = MessageRequest {
-            model: self.config.model.clone(),
-            messages,
-            system,
-            max_tokens: context.max_tokens.or(self.config.max_tokens),
-            temperature: context.temperature.or(self.config.temperature),
-            response_format: Some(ResponseFormat {
-                format_type: "json_object".to_string(),
-            }),
-        };
+        model_limits(self.model(context)).input_budget(context.max_tokens.or(self.config.max_tokens), overhead)
+    }
 
-        // Make the API call
+    /// Send a request to the API and return the response body, recording the
+    /// exchange if the tenant's exchanges are logged
+    async fn send<T: Serialize>(&self, tenant: &TenantId, model: &str, request: &T) -> Result<String, LlmError> {
+        let pending = self.exchange_log.as_ref().and_then(|log| log.begin(tenant, "anthropic", model, request));
+        let result = self.post(request).await;
+        if let (Some(log), Some(pending)) = (&self.exchange_log, pending) {
+            log.finish(pending, result.as_deref()).await;
+        }
+        result
+    }
+
+    async fn post<T: Serialize>(&self, request: &T) -> Result<String, LlmError> {
         let response = self.client
             .post(&format!("{}/v1/messages", self.config.api_base))
             .header("x-api-key", &self.config.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(request)
             .send()
             .await
-            .map_err(|e| LlmError::NetworkError(format!("HTTP request failed: {}", e)))?;
+            .map_err(request_error)?;
 
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(LlmError::ApiError(format!("Anthropic API error {}: {}", status, error_text)));
+            return Err(api_error(status, error_text));
         }
 
-        let message_response: MessageResponse = response.json().await
-            .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse response: {}", e)))?;
+        response.text().await
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to read response: {}", e)))
+    }
 
-        // Extract the content
-        let content_text = message_response.content
-            .iter()
-            .filter(|c| c.content_type == "text")
-            .map(|c| c.text.as_str())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        if content_text.is_empty() {
-            return Err(LlmError::ResponseParseError("No content in response".to_string()));
+    async fn get(&self, endpoint: &str) -> Result<String, LlmError> {
+        let response = self.client
+            .get(format!("{}/v1/{}", self.config.api_base, endpoint))
+            .header("x-api-key", &self.config.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(api_error(status, error_text));
         }
 
+        response.text().await
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to read response: {}", e)))
+    }
+
+    /// Extract from a context that fits the model's context window
+    async fn extract_single(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let model = self.model(&context).to_string();
+        debug!("Starting Anthropic extraction for tenant: {}", tenant);
+        let start_time = Instant::now();
+
+        // Build the request
+        let request = self.build_extraction_request(&context);
+        let structured = self.config.uses_structured_output_for(&model);
+
+        // Make the API call, again while the model answers without calling the tool
+        let mut refusals = 0;
+        let (message_response, content_text) = loop {
+            let body = self.send(tenant, &model, &request).await?;
+            let message_response: MessageResponse = serde_json::from_str(&body)
+                .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse response: {}", e)))?;
+
+            if let Some(content) = self.extraction_content(&message_response, structured)? {
+                break (message_response, content);
+            }
+            if refusals == self.config.max_retries {
+                return Err(tool_refusal_error(&message_response, refusals + 1));
+            }
+            refusals += 1;
+            warn!(
+                "Anthropic model did not call the {} tool (stop reason {:?}); retrying",
+                ExtractionEnvelope::TOOL_NAME,
+                message_response.stop_reason
+            );
+        };
+
         // Parse the extraction
         let mut envelope = self.parse_extraction_response(&content_text)?;
 
@@ -195,12 +296,17 @@ impl LlmConnector for AnthropicConnector {
         let latency = start_time.elapsed();
         envelope.metadata = Some(ExtractionMetadata {
             provider: "anthropic".to_string(),
-            model_name: self.config.model.clone(),
+            model_name: model.clone(),
             latency_ms: Some(latency.as_millis() as u64),
             input_tokens: message_response.usage.as_ref().map(|u| u.input_tokens),
             output_tokens: message_response.usage.as_ref().map(|u| u.output_tokens),
-            cost_usd: message_response.usage.as_ref().and_then(|u| self.calculate_cost(u)),
-            warnings: Vec::new(),
+            cost_usd: message_response.usage.as_ref().and_then(|u| self.calculate_cost(&model, u)),
+            warnings: (refusals > 0)
+                .then(|| format!("Retried {} time(s) after the model did not call the extraction tool", refusals))
+                .into_iter()
+                .collect(),
+            model_selection: None,
+            citations: Vec::new(),
         });
 
         info!(
@@ -213,51 +319,41 @@ impl LlmConnector for AnthropicConnector {
 
         Ok(envelope)
     }
+}
+
+#[async_trait]
+impl LlmConnector for AnthropicConnector {
+    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let estimator = TokenEstimator::for_provider("anthropic");
+        let budget = self.input_budget(&context, estimator);
+
+        extract_chunked(context, estimator, budget, |chunk| self.extract_single(tenant, chunk)).await
+    }
 
     async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
         debug!("Starting Anthropic completion for tenant: {}", tenant);
         let start_time = Instant::now();
 
         // Build the request
-        let messages = vec![Message::new_user(&request.prompt)];
+        let (system, messages) = split_system(request.system.clone(), &request.conversation());
 
         let message_request = MessageRequest {
             model: self.config.model.clone(),
             messages,
-            system: None,
+            system,
             max_tokens: request.max_tokens.or(self.config.max_tokens),
             temperature: request.temperature.or(self.config.temperature),
-            response_format: None, // No JSON formatting for regular completion
+            tools: None,
+            tool_choice: None,
         };
 
         // Make the API call
-        let response = self.client
-            .post(&format!("{}/v1/messages", self.config.api_base))
-            .header("x-api-key", &self.config.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&message_request)
-            .send()
-            .await
-            .map_err(|e| LlmError::NetworkError(format!("HTTP request failed: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(LlmError::ApiError(format!("Anthropic API error {}: {}", status, error_text)));
-        }
-
-        let message_response: MessageResponse = response.json().await
+        let body = self.send(tenant, &self.config.model, &message_request).await?;
+        let message_response: MessageResponse = serde_json::from_str(&body)
             .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse response: {}", e)))?;
 
         // Extract the content
-        let text = message_response.content
-            .iter()
-            .filter(|c| c.content_type == "text")
-            .map(|c| c.text.as_str())
-            .collect::<Vec<_>>()
-            .join("\n");
+        let text = response_text(&message_response);
 
         if text.is_empty() {
             return Err(LlmError::ResponseParseError("No content in response".to_string()));
@@ -271,8 +367,10 @@ impl LlmConnector for AnthropicConnector {
             latency_ms: Some(latency.as_millis() as u64),
             input_tokens: message_response.usage.as_ref().map(|u| u.input_tokens),
             output_tokens: message_response.usage.as_ref().map(|u| u.output_tokens),
-            cost_usd: message_response.usage.as_ref().and_then(|u| self.calculate_cost(u)),
+            cost_usd: message_response.usage.as_ref().and_then(|u| self.calculate_cost(&self.config.model, u)),
             warnings: Vec::new(),
+            model_selection: None,
+            citations: Vec::new(),
         });
 
         info!(
@@ -283,6 +381,148 @@ impl LlmConnector for AnthropicConnector {
 
         Ok(CompletionResponse { text, metadata })
     }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        let body = self.get("models?limit=1000").await?;
+        let list: ModelList = serde_json::from_str(&body)
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse model list: {}", e)))?;
+
+        Ok(list.data.into_iter().map(|model| ModelInfo { id: model.id, display_name: model.display_name }).collect())
+    }
+}
+
+/// Text blocks of a response, joined
+fn response_text(response: &MessageResponse) -> String {
+    response.content
+        .iter()
+        .filter(|c| c.content_type == "text")
+        .map(|c| c.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Error for a model that did not call the extraction tool in any attempt
+fn tool_refusal_error(response: &MessageResponse, attempts: u32) -> LlmError {
+    let reply: String = response_text(response).chars().take(200).collect();
+    let message = format!(
+        "Model did not call the {} tool in {} attempt(s); last stop reason {:?}: '{}'",
+        ExtractionEnvelope::TOOL_NAME, attempts, response.stop_reason, reply
+    );
+    if response.stop_reason.as_deref() == Some("refusal") {
+        LlmError::ContentBlocked(message)
+    } else {
+        LlmError::SchemaValidationError(message)
+    }
+}
+
+/// Map a failed HTTP request to an `LlmError`
+fn request_error(e: reqwest::Error) -> LlmError {
+    if e.is_timeout() {
+        LlmError::Timeout
+    } else if e.is_connect() {
+        LlmError::CapabilityUnavailable(format!("Anthropic API is unreachable: {}", e))
+    } else {
+        LlmError::NetworkError(format!("HTTP request failed: {}", e))
+    }
+}
+
+/// Map an unsuccessful API response to an `LlmError`
+fn api_error(status: reqwest::StatusCode, error_text: String) -> LlmError {
+    let message = format!("Anthropic API error {}: {}", status, error_text);
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        LlmError::RateLimited(message)
+    } else if provider_unavailable(status) {
+        LlmError::CapabilityUnavailable(message)
+    } else {
+        LlmError::ApiError(message)
+    }
+}
+
+/// Statuses returned while the provider is down or overloaded; Anthropic
+/// reports overload with 529
+fn provider_unavailable(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        reqwest::StatusCode::BAD_GATEWAY | reqwest::StatusCode::SERVICE_UNAVAILABLE | reqwest::StatusCode::GATEWAY_TIMEOUT
+    ) || status.as_u16() == 529
+}
+
+/// Map messages to a system prompt and Anthropic messages; the API takes
+/// system text only as the system prompt, so system messages are appended to it
+fn split_system(system: Option<String>, messages: &[LlmMessage]) -> (Option<String>, Vec<Message>) {
+    let mut system_prompt = system;
+
+    for msg in messages.iter().filter(|msg| msg.role == "system") {
+        match &mut system_prompt {
+            Some(prompt) => {
+                prompt.push_str("\n\n");
+                prompt.push_str(&msg.content);
+            }
+            None => system_prompt = Some(msg.content.clone()),
+        }
+        if !msg.parts.is_empty() {
+            skip("content parts of a system message were skipped".to_string());
+        }
+    }
+
+    let messages = messages.iter()
+        .filter(|msg| msg.role != "system")
+        .map(convert_message)
+        .collect();
+
+    (system_prompt, messages)
+}
+
+/// Map a non-system message to an Anthropic message; tool results are sent
+/// as `tool_result` blocks of a user message
+fn convert_message(msg: &LlmMessage) -> Message {
+    let role = match msg.role.as_str() {
+        "assistant" => "assistant",
+        "user" | "tool" => "user",
+        other => {
+            skip(format!("role '{}' is not supported and was sent as 'user'", other));
+            "user"
+        }
+    };
+
+    let mut content = Vec::new();
+    if !msg.content.is_empty() {
+        content.push(Content::Text { text: msg.content.clone() });
+    }
+    for part in &msg.parts {
+        match part {
+            ContentPart::Text { text } => content.push(Content::Text { text: text.clone() }),
+            ContentPart::Image { .. } | ContentPart::ToolResult { .. } if role == "assistant" => {
+                skip("image or tool result in an assistant message was skipped; only user messages take them".to_string())
+            }
+            ContentPart::Image { source } => content.push(Content::Image {
+                source: match source {
+                    ImageSource::Url { url } => ImageBlockSource::Url { url: url.clone() },
+                    ImageSource::Base64 { media_type, data } => ImageBlockSource::Base64 {
+                        media_type: media_type.clone(),
+                        data: data.clone(),
+                    },
+                },
+            }),
+            ContentPart::ToolResult { tool_call_id, content: output, is_error, .. } => content.push(Content::ToolResult {
+                tool_use_id: tool_call_id.clone(),
+                content: output.clone(),
+                is_error: *is_error,
+            }),
+        }
+    }
+    if content.is_empty() {
+        content.push(Content::Text { text: String::new() });
+    }
+
+    Message { role: role.to_string(), content }
+}
+
+/// Report content that Anthropic does not accept to the client and the log
+fn skip(reason: String) {
+    let message = format!("Anthropic: {}", reason);
+    warn!("{}", message);
+    telamentis_core::warnings::report(message);
 }
 
 #[cfg(test)]
@@ -308,11 +548,18 @@ mod tests {
             messages: vec![LlmMessage {
                 role: "user".to_string(),
                 content: "Alice works at Acme Corp".to_string(),
+                parts: Vec::new(),
             }],
             system_prompt: Some("Custom prompt".to_string()),
             desired_schema: None,
             max_tokens: None,
             temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
         };
 
         let prompt = connector.build_extraction_prompt(&context);
@@ -345,6 +592,207 @@ mod tests {
         assert_eq!(envelope.nodes[0].id_alias, "alice");
     }
 
+    #[tokio::test]
+    async fn test_structured_output_detection() {
+        assert!(AnthropicConfig::new("test-key").uses_structured_output());
+        assert!(!AnthropicConfig::new("test-key").with_model("claude-2.1").uses_structured_output());
+        assert!(!AnthropicConfig::new("test-key").with_structured_output(false).uses_structured_output());
+    }
+
+    #[tokio::test]
+    async fn test_multimodal_messages() {
+        let connector = AnthropicConnector::new(AnthropicConfig::new("test-key")).unwrap();
+        let context = ExtractionContext {
+            messages: vec![
+                LlmMessage::new("system", "The user is Alice."),
+                LlmMessage::new("user", "Who is this?")
+                    .with_part(ContentPart::Image { source: ImageSource::Url { url: "https://example.com/a.png".to_string() } }),
+                LlmMessage::new("assistant", "Looking it up")
+                    .with_part(ContentPart::Image { source: ImageSource::Url { url: "https://example.com/b.png".to_string() } }),
+                LlmMessage::new("tool", "").with_part(ContentPart::ToolResult {
+                    tool_call_id: "toolu_1".to_string(),
+                    name: None,
+                    content: "Not found".to_string(),
+                    is_error: true,
+                }),
+            ],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        };
+
+        let (request, warnings) = collect_warnings(async { serde_json::to_value(connector.build_extraction_request(&context)).unwrap() }).await;
+        assert!(request["system"].as_str().unwrap().ends_with("The user is Alice."));
+        assert_eq!(request["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(request["messages"][0]["content"][1], json!({"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}}));
+        assert_eq!(request["messages"][1]["content"], json!([{"type": "text", "text": "Looking it up"}]));
+        assert_eq!(request["messages"][2], json!({
+            "role": "user",
+            "content": [{"type": "tool_result", "tool_use_id": "toolu_1", "content": "Not found", "is_error": true}]
+        }));
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_extraction_request_modes() {
+        let context = ExtractionContext {
+            messages: vec![LlmMessage {
+                role: "user".to_string(),
+                content: "Alice works at Acme Corp".to_string(),
+                parts: Vec::new(),
+            }],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        };
+
+        let connector = AnthropicConnector::new(AnthropicConfig::new("test-key")).unwrap();
+        let request = serde_json::to_value(connector.build_extraction_request(&context)).unwrap();
+        assert_eq!(request["tools"][0]["name"], ExtractionEnvelope::TOOL_NAME);
+        assert_eq!(request["tool_choice"]["type"], "tool");
+        assert!(request.get("response_format").is_none());
+
+        let connector = AnthropicConnector::new(AnthropicConfig::new("test-key").with_model("claude-2.1")).unwrap();
+        let request = serde_json::to_value(connector.build_extraction_request(&context)).unwrap();
+        assert!(request.get("tools").is_none());
+        assert!(request.get("response_format").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_extraction_content_from_tool_use() {
+        let connector = AnthropicConnector::new(AnthropicConfig::new("test-key")).unwrap();
+        let response: MessageResponse = serde_json::from_value(json!({
+            "id": "msg_1",
+            "model": "claude-3-sonnet",
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "record_extraction",
+                "input": {
+                    "nodes": [{"id_alias": "alice", "label": "Person", "props": {}}],
+                    "relations": []
+                }
+            }]
+        })).unwrap();
+
+        let content = connector.extraction_content(&response, true).unwrap().unwrap();
+        let envelope = connector.parse_extraction_response(&content).unwrap();
+        assert_eq!(envelope.nodes[0].id_alias, "alice");
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_tool_refusal() {
+        let server = MockServer::start().await;
+        let refusal = json!({
+            "id": "msg_1",
+            "model": "claude-3-sonnet",
+            "content": [{"type": "text", "text": "Here are the entities I found: Alice."}],
+            "stop_reason": "end_turn"
+        });
+        let tool_use = json!({
+            "id": "msg_2",
+            "model": "claude-3-sonnet",
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "record_extraction",
+                "input": {"nodes": [{"id_alias": "alice", "label": "Person", "props": {}}], "relations": []}
+            }],
+            "stop_reason": "tool_use"
+        });
+        Mock::given(method("POST")).and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&refusal))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST")).and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&tool_use))
+            .mount(&server)
+            .await;
+
+        let tenant = TenantId::new("acme");
+        let context = ExtractionContext {
+            messages: vec![LlmMessage::new("user", "Alice works at Acme Corp")],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        };
+
+        let connector = AnthropicConnector::new(AnthropicConfig::new("test-key").with_sandbox(server.uri())).unwrap();
+        let envelope = connector.extract(&tenant, context.clone()).await.unwrap();
+        assert_eq!(envelope.nodes[0].id_alias, "alice");
+        assert!(envelope.metadata.unwrap().warnings[0].starts_with("Retried 1 time(s)"));
+
+        // Without retries the refusal fails the extraction
+        server.reset().await;
+        Mock::given(method("POST")).and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&refusal))
+            .mount(&server)
+            .await;
+        let connector = AnthropicConnector::new(AnthropicConfig::new("test-key").with_sandbox(server.uri()).with_max_retries(0)).unwrap();
+        let error = connector.extract(&tenant, context).await.unwrap_err();
+        assert!(matches!(error, LlmError::SchemaValidationError(msg) if msg.contains("in 1 attempt(s)")));
+    }
+
+    #[tokio::test]
+    async fn test_multi_turn_completion() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "msg_1",
+                "model": "claude-3-sonnet",
+                "content": [{"type": "text", "text": "Acme Corp."}],
+                "stop_reason": "end_turn"
+            })))
+            .mount(&server)
+            .await;
+
+        let request = CompletionRequest {
+            prompt: "Where does she work?".to_string(),
+            messages: vec![
+                LlmMessage::new("system", "Answer briefly."),
+                LlmMessage::new("user", "Alice joined Acme Corp in 2023."),
+                LlmMessage::new("assistant", "Noted."),
+            ],
+            system: Some("You answer questions about people.".to_string()),
+            max_tokens: Some(50),
+            temperature: None,
+            params: json!({}),
+            seed: None,
+        };
+
+        let connector = AnthropicConnector::new(AnthropicConfig::new("test-key").with_sandbox(server.uri())).unwrap();
+        let response = connector.complete(&TenantId::new("acme"), request).await.unwrap();
+        assert_eq!(response.text, "Acme Corp.");
+
+        let sent: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert_eq!(sent["system"], "You answer questions about people.\n\nAnswer briefly.");
+        let roles: Vec<_> = sent["messages"].as_array().unwrap().iter().map(|m| m["role"].as_str().unwrap()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "user"]);
+        assert_eq!(sent["messages"][2]["content"][0]["text"], "Where does she work?");
+    }
+
     #[tokio::test]
     async fn test_validation_duplicate_nodes() {
         let config = AnthropicConfig::new("test-key");
@@ -12,8 +12,10 @@ pub struct MessageRequest {
     pub max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
-    #[serde(rename = "response_format")]
-    pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
 }
 
 /// Anthropic message format
@@ -25,17 +27,57 @@ pub struct Message {
 
 /// Content part of a message
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Content {
-    #[serde(rename = "type")]
-    pub content_type: String,
-    pub text: String,
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Content {
+    Text {
+        text: String,
+    },
+    Image {
+        source: ImageBlockSource,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        is_error: bool,
+    },
+}
+
+/// Data of an image block
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageBlockSource {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
+}
+
+/// Tool available to the model
+#[derive(Debug, Serialize)]
+pub struct Tool {
+    pub name: String,
+    pub description: String,
+    pub input_schema: serde_json::Value,
 }
 
-/// Response format specification
+/// Forces the model to use a specific tool
 #[derive(Debug, Serialize)]
-pub struct ResponseFormat {
+pub struct ToolChoice {
     #[serde(rename = "type")]
-    pub format_type: String, // "json_object" for JSON mode
+    pub choice_type: String, // "tool"
+    pub name: String,
+}
+
+/// Response of the list-models endpoint
+#[derive(Debug, Deserialize)]
+pub struct ModelList {
+    pub data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModelEntry {
+    pub id: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
 }
 
 /// Anthropic Message API response
@@ -44,6 +86,9 @@ pub struct MessageResponse {
     pub id: String,
     pub model: String,
     pub content: Vec<ContentResponse>,
+    /// Why the model stopped, e.g. `tool_use`, `end_turn` or `refusal`
+    #[serde(default)]
+    pub stop_reason: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<Usage>,
 }
@@ -52,8 +97,15 @@ pub struct MessageResponse {
 #[derive(Debug, Deserialize)]
 pub struct ContentResponse {
     #[serde(rename = "type")]
-    pub content_type: String,
+    pub content_type: String, // "text" or "tool_use"
+    #[serde(default)]
     pub text: String,
+    /// Tool name for `tool_use` blocks
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Tool input for `tool_use` blocks
+    #[serde(default)]
+    pub input: Option<serde_json::Value>,
 }
 
 /// Token usage information
@@ -75,28 +127,4 @@ pub struct ErrorDetails {
     #[serde(rename = "type")]
     pub error_type: String,
     pub message: String,
-}
-
-impl Message {
-    /// Create a new user message with text content
-    pub fn new_user(text: impl Into<String>) -> Self {
-        Self {
-            role: "user".to_string(),
-            content: vec![Content {
-                content_type: "text".to_string(),
-                text: text.into(),
-            }],
-        }
-    }
-
-    /// Create a new assistant message with text content
-    pub fn new_assistant(text: impl Into<String>) -> Self {
-        Self {
-            role: "assistant".to_string(),
-            content: vec![Content {
-                content_type: "text".to_string(),
-                text: text.into(),
-            }],
-        }
-    }
 }
\ No newline at end of file
@@ -1,6 +1,10 @@
 //! Configuration for Gemini connector
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use telamentis_core::http::{HttpClientConfig, ProxyConfig};
+use telamentis_core::sandbox::{SANDBOX_API_KEY, SANDBOX_TIMEOUT_MS};
+use telamentis_core::types::TenantId;
 
 /// Gemini API configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +25,32 @@ pub struct GeminiConfig {
     pub timeout_ms: u64,
     /// Maximum retries for failed requests
     pub max_retries: u32,
+    /// Use a response schema for extraction; `None` detects support from the model
+    #[serde(default)]
+    pub structured_output: Option<bool>,
+    /// Proxy, root certificate and connection pool settings
+    #[serde(default)]
+    pub http: HttpClientConfig,
+    /// Safety settings sent with every request; empty uses the API's defaults
+    #[serde(default)]
+    pub safety_settings: Vec<SafetySetting>,
+    /// Safety settings of tenants, replacing `safety_settings` for them
+    #[serde(default)]
+    pub tenant_safety_settings: HashMap<TenantId, Vec<SafetySetting>>,
+}
+
+/// Blocking threshold of a harm category, e.g. `BLOCK_ONLY_HIGH` for
+/// `HARM_CATEGORY_HARASSMENT`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+impl SafetySetting {
+    pub fn new(category: impl Into<String>, threshold: impl Into<String>) -> Self {
+        Self { category: category.into(), threshold: threshold.into() }
+    }
 }
 
 impl GeminiConfig {
@@ -35,6 +65,10 @@ impl GeminiConfig {
             temperature: Some(0.1),
             timeout_ms: 30_000,
             max_retries: 3,
+            structured_output: None,
+            http: HttpClientConfig::default(),
+            safety_settings: Vec::new(),
+            tenant_safety_settings: HashMap::new(),
         }
     }
 
@@ -56,6 +90,17 @@ impl GeminiConfig {
         self
     }
 
+    /// Route requests to a sandbox server at `base_url` instead of the real
+    /// API, with a short timeout and a placeholder API key if none is set
+    pub fn with_sandbox(mut self, base_url: impl Into<String>) -> Self {
+        self.api_base = format!("{}/v1", base_url.into().trim_end_matches('/'));
+        self.timeout_ms = SANDBOX_TIMEOUT_MS;
+        if self.api_key.is_empty() {
+            self.api_key = SANDBOX_API_KEY.to_string();
+        }
+        self
+    }
+
     /// Set maximum tokens
     pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
         self.max_tokens = Some(max_tokens);
@@ -79,6 +124,59 @@ impl GeminiConfig {
         self.max_retries = max_retries;
         self
     }
+
+    /// Set the proxy, root certificate and connection pool settings
+    pub fn with_http_client(mut self, http: HttpClientConfig) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Send requests through an outbound proxy
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.http.proxy = Some(proxy);
+        self
+    }
+
+    /// Force native structured output on or off instead of detecting it
+    pub fn with_structured_output(mut self, enabled: bool) -> Self {
+        self.structured_output = Some(enabled);
+        self
+    }
+
+    /// Set the safety settings of tenants without their own
+    pub fn with_safety_settings(mut self, settings: Vec<SafetySetting>) -> Self {
+        self.safety_settings = settings;
+        self
+    }
+
+    /// Set a tenant's safety settings
+    pub fn with_tenant_safety_settings(mut self, tenant: TenantId, settings: Vec<SafetySetting>) -> Self {
+        self.tenant_safety_settings.insert(tenant, settings);
+        self
+    }
+
+    /// Safety settings of a tenant's requests
+    pub fn safety_settings_for(&self, tenant: &TenantId) -> &[SafetySetting] {
+        self.tenant_safety_settings.get(tenant).unwrap_or(&self.safety_settings)
+    }
+
+    /// Whether extraction should use `responseSchema` rather than JSON prompting
+    pub fn uses_structured_output(&self) -> bool {
+        self.uses_structured_output_for(&self.model)
+    }
+
+    /// Like [`Self::uses_structured_output`], for a model chosen per request
+    pub fn uses_structured_output_for(&self, model: &str) -> bool {
+        self.structured_output.unwrap_or_else(|| supports_response_schema(model))
+    }
+}
+
+/// Whether a model is known to support `responseSchema` (Gemini 1.5 and later)
+fn supports_response_schema(model: &str) -> bool {
+    model.starts_with("gemini-")
+        && !model.starts_with("gemini-pro")
+        && !model.starts_with("gemini-ultra")
+        && !model.starts_with("gemini-1.0")
 }
 
 impl Default for GeminiConfig {
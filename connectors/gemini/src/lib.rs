@@ -3,31 +3,50 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Instant;
 use telamentis_core::prelude::*;
 use tracing::{debug, error, info, warn};
 
 mod config;
 mod models;
+#[cfg(feature = "sandbox")]
+mod sandbox;
 
-pub use config::GeminiConfig;
+pub use config::{GeminiConfig, SafetySetting};
+#[cfg(feature = "sandbox")]
+pub use sandbox::GeminiSandbox;
 use models::*;
 
 /// Gemini implementation of LlmConnector
 pub struct GeminiConnector {
     client: Client,
     config: GeminiConfig,
+    exchange_log: Option<Arc<ExchangeLog>>,
 }
 
 impl GeminiConnector {
     /// Create a new Gemini connector
     pub fn new(config: GeminiConfig) -> Result<Self, LlmError> {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_millis(config.timeout_ms))
+        let builder = Client::builder()
+            .timeout(std::time::Duration::from_millis(config.timeout_ms));
+        let client = config.http.apply(builder)
+            .map_err(LlmError::ConfigError)?
             .build()
             .map_err(|e| LlmError::ConfigError(format!("Failed to create HTTP client: {}", e)))?;
 
-        Ok(Self { client, config })
+        Ok(Self { client, config, exchange_log: None })
+    }
+
+    /// Record sampled requests and responses of tenants with logging enabled
+    pub fn with_exchange_log(mut self, exchange_log: Arc<ExchangeLog>) -> Self {
+        self.exchange_log = Some(exchange_log);
+        self
+    }
+
+    /// Model for an extraction: the request's model or the configured default
+    fn model<'a>(&'a self, context: &'a ExtractionContext) -> &'a str {
+        context.model.as_deref().unwrap_or(&self.config.model)
     }
 
     /// Build the system prompt for extraction
@@ -36,10 +55,18 @@ impl GeminiConnector {
             "You are an expert knowledge graph extraction engine. Analyze the provided text/conversation and identify relevant entities (as nodes) and relationships (as relations) between them."
         );
 
+        let examples = render_examples(
+            context.examples.as_deref().unwrap_or_default(),
+            TokenEstimator::for_provider("gemini"),
+            example_token_budget(self.model(context)),
+        );
+
         format!(
-            "{}\n\nReturn your findings strictly as a JSON object matching the following schema:\n{}\n\nInstructions:\n- `id_alias` should be a descriptive, unique identifier for nodes within this extraction (e.g., \"user_john_doe\", \"acme_corp_hq\")\n- If a date or time for `valid_from` or `valid_to` is mentioned, use ISO8601 format\n- If a relation is ongoing, `valid_to` can be omitted or null\n- Only extract explicitly mentioned information. Do not infer or hallucinate\n- If unsure about a piece of information, omit it or assign a low confidence score",
+            "{}\n\nReturn your findings strictly as a JSON object matching the following schema:\n{}\n\nInstructions:\n- `id_alias` should be a descriptive, unique identifier for nodes within this extraction (e.g., \"user_john_doe\", \"acme_corp_hq\")\n- If a date or time for `valid_from` or `valid_to` is mentioned, use ISO8601 format, only as precise as the text: \"2020\" for a year, \"2020-03\" for a month, \"2020-03-15\" for a day, a full timestamp only for an exact time\n- If a relation is ongoing, `valid_to` can be omitted or null\n- Only extract explicitly mentioned information. Do not infer or hallucinate\n- If unsure about a piece of information, omit it or assign a low confidence score{}{}",
             base_prompt,
-            ExtractionEnvelope::json_schema_example()
+            ExtractionEnvelope::json_schema_example(),
+            graph_context_section(context),
+            examples
         )
     }
 
@@ -49,17 +76,36 @@ impl GeminiConnector {
         
         let mut contents = vec![Content::new_user(system_prompt)];
         
-        for msg in &context.messages {
-            match msg.role.as_str() {
-                "user" => contents.push(Content::new_user(&msg.content)),
-                "assistant" => contents.push(Content::new_model(&msg.content)),
-                _ => contents.push(Content::new_user(&msg.content)),
-            }
-        }
+        contents.extend(context.messages.iter().map(convert_message));
         
         contents
     }
 
+    /// Build the content request for an extraction, constraining the output
+    /// with a response schema when the model supports it
+    fn build_extraction_request(&self, tenant: &TenantId, context: &ExtractionContext) -> ContentRequest {
+        let structured = self.config.uses_structured_output_for(self.model(context));
+
+        let generation_config = GenerationConfig {
+            temperature: context.temperature.or(self.config.temperature),
+            max_output_tokens: context.max_tokens.or(self.config.max_tokens),
+            response_mime_type: structured.then(|| "application/json".to_string()),
+            response_schema: structured.then(|| to_gemini_schema(ExtractionEnvelope::json_schema())),
+        };
+
+        ContentRequest {
+            contents: self.convert_messages(context),
+            generation_config: Some(generation_config),
+            safety_settings: self.safety_settings(tenant),
+        }
+    }
+
+    /// Safety settings of a tenant's requests; `None` uses the API's defaults
+    fn safety_settings(&self, tenant: &TenantId) -> Option<Vec<SafetySetting>> {
+        let settings = self.config.safety_settings_for(tenant);
+        (!settings.is_empty()).then(|| settings.to_vec())
+    }
+
     /// Parse and validate the Gemini response
     fn parse_extraction_response(&self, content: &str) -> Result<ExtractionEnvelope, LlmError> {
         // Clean up potential markdown code block fences
@@ -119,9 +165,9 @@ impl GeminiConnector {
     }
 
     /// Calculate estimated cost based on token usage
-    fn calculate_cost(&self, usage: &UsageMetadata) -> Option<f64> {
+    fn calculate_cost(&self, model: &str, usage: &UsageMetadata) -> Option<f64> {
         // Gemini pricing (approximate, as of 2024)
-        let (input_cost_per_1k, output_cost_per_1k) = match self.config.model.as_str() {
+        let (input_cost_per_1k, output_cost_per_1k) = match model {
             "gemini-pro" => (0.00125, 0.00375),
             "gemini-ultra" => (0.00875, 0.02625), // Approximate
             _ => (0.00125, 0.00375), // Default to Gemini Pro pricing
@@ -134,9 +180,8 @@ impl GeminiConnector {
     }
     
     /// Get the API URL based on configuration
-    fn get_api_url(&self) -> String {
+    fn get_api_url(&self, model: &str) -> String {
         let base_url = &self.config.api_base;
-        let model = &self.config.model;
         
         // Handle different URL formats (direct API key vs. project-based)
         if let Some(project_id) = &self.config.project_id {
@@ -145,66 +190,98 @@ impl GeminiConnector {
             format!("{}/models/{}:generateContent", base_url, model)
         }
     }
-}
 
-#[async_trait]
-impl LlmConnector for GeminiConnector {
-    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
-        debug!("Starting Gemini extraction for tenant: {}", tenant);
-        let start_time = Instant::now();
+    fn get_models_url(&self) -> String {
+        match &self.config.project_id {
+            Some(project_id) => format!("{}/projects/{}/models", self.config.api_base, project_id),
+            None => format!("{}/models", self.config.api_base),
+        }
+    }
 
-        // Build the request
-        let contents = self.convert_messages(&context);
-        
-        let generation_config = GenerationConfig {
-            temperature: context.temperature.or(self.config.temperature),
-            max_output_tokens: context.max_tokens.or(self.config.max_tokens),
-            response_mime_type: Some("application/json".to_string()),
-        };
-        
-        let request = ContentRequest {
-            contents,
-            generation_config: Some(generation_config),
-            safety_settings: None,
-        };
+    /// Tokens left for conversation messages after the extraction prompt,
+    /// response schema and reserved output
+    fn input_budget(&self, context: &ExtractionContext, estimator: TokenEstimator) -> usize {
+        let mut overhead = estimator.count(&self.build_extraction_prompt(context));
+        if self.config.uses_structured_output_for(self.model(context)) {
+            overhead += estimator.count(&ExtractionEnvelope::json_schema().to_string());
+        }
 
-        // Make the API call
-        let api_url = self.get_api_url();
+        model_limits(self.model(context)).input_budget(context.max_tokens.or(self.config.max_tokens), overhead)
+    }
+
+    /// Send a request to the API and return the response body, recording the
+    /// exchange if the tenant's exchanges are logged
+    async fn send<T: Serialize>(&self, tenant: &TenantId, model: &str, request: &T) -> Result<String, LlmError> {
+        let pending = self.exchange_log.as_ref().and_then(|log| log.begin(tenant, "gemini", model, request));
+        let result = self.post(model, request).await;
+        if let (Some(log), Some(pending)) = (&self.exchange_log, pending) {
+            log.finish(pending, result.as_deref()).await;
+        }
+        result
+    }
+
+    async fn post<T: Serialize>(&self, model: &str, request: &T) -> Result<String, LlmError> {
+        let api_url = self.get_api_url(model);
         let url = if api_url.contains('?') {
             format!("{}&key={}", api_url, self.config.api_key)
         } else {
             format!("{}?key={}", api_url, self.config.api_key)
         };
-        
+
         let response = self.client
             .post(&url)
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(request)
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(api_error(status, error_text));
+        }
+
+        response.text().await
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to read response: {}", e)))
+    }
+
+    async fn get(&self, url: &str) -> Result<String, LlmError> {
+        let response = self.client
+            .get(url)
+            .query(&[("key", &self.config.api_key)])
             .send()
             .await
-            .map_err(|e| LlmError::NetworkError(format!("HTTP request failed: {}", e)))?;
+            .map_err(request_error)?;
 
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(LlmError::ApiError(format!("Gemini API error {}: {}", status, error_text)));
+            return Err(api_error(status, error_text));
         }
 
-        let content_response: ContentResponse = response.json().await
+        response.text().await
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to read response: {}", e)))
+    }
+
+    /// Extract from a context that fits the model's context window
+    async fn extract_single(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let model = self.model(&context).to_string();
+        debug!("Starting Gemini extraction for tenant: {}", tenant);
+        let start_time = Instant::now();
+
+        // Build the request
+        let request = self.build_extraction_request(tenant, &context);
+
+        // Make the API call
+        let body = self.send(tenant, &model, &request).await?;
+        let content_response: ContentResponse = serde_json::from_str(&body)
             .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse response: {}", e)))?;
 
         // Extract the content
-        let content_text = content_response.candidates
-            .iter()
-            .flat_map(|c| &c.content.parts)
-            .map(|p| p.text.as_str())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        if content_text.is_empty() {
-            return Err(LlmError::ResponseParseError("No content in response".to_string()));
-        }
+        let content_text = response_text(&content_response)?;
 
         // Parse the extraction
         let mut envelope = self.parse_extraction_response(&content_text)?;
@@ -213,12 +290,14 @@ impl LlmConnector for GeminiConnector {
         let latency = start_time.elapsed();
         envelope.metadata = Some(ExtractionMetadata {
             provider: "gemini".to_string(),
-            model_name: self.config.model.clone(),
+            model_name: model.clone(),
             latency_ms: Some(latency.as_millis() as u64),
             input_tokens: content_response.usage_metadata.as_ref().map(|u| u.prompt_token_count),
             output_tokens: content_response.usage_metadata.as_ref().map(|u| u.candidates_token_count),
-            cost_usd: content_response.usage_metadata.as_ref().and_then(|u| self.calculate_cost(u)),
+            cost_usd: content_response.usage_metadata.as_ref().and_then(|u| self.calculate_cost(&model, u)),
             warnings: Vec::new(),
+            model_selection: None,
+            citations: citations(&content_response),
         });
 
         info!(
@@ -231,63 +310,68 @@ impl LlmConnector for GeminiConnector {
 
         Ok(envelope)
     }
+}
+
+/// Convert a JSON Schema into Gemini's OpenAPI-style schema, which uses
+/// upper-case type names
+fn to_gemini_schema(mut schema: serde_json::Value) -> serde_json::Value {
+    if let Some(object) = schema.as_object_mut() {
+        for (key, value) in object.iter_mut() {
+            match (key.as_str(), value) {
+                ("type", serde_json::Value::String(type_name)) => *type_name = type_name.to_uppercase(),
+                ("properties", serde_json::Value::Object(properties)) => {
+                    for property in properties.values_mut() {
+                        *property = to_gemini_schema(property.take());
+                    }
+                }
+                ("items", items) => *items = to_gemini_schema(items.take()),
+                _ => {}
+            }
+        }
+    }
+    schema
+}
+
+#[async_trait]
+impl LlmConnector for GeminiConnector {
+    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let estimator = TokenEstimator::for_provider("gemini");
+        let budget = self.input_budget(&context, estimator);
+
+        extract_chunked(context, estimator, budget, |chunk| self.extract_single(tenant, chunk)).await
+    }
 
     async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
         debug!("Starting Gemini completion for tenant: {}", tenant);
         let start_time = Instant::now();
 
         // Build the request
-        let contents = vec![Content::new_user(&request.prompt)];
+        // Sent as the first user turn, as for extraction
+        let contents = request.system.iter()
+            .map(Content::new_user)
+            .chain(request.conversation().iter().map(convert_message))
+            .collect();
         
         let generation_config = GenerationConfig {
             temperature: request.temperature.or(self.config.temperature),
             max_output_tokens: request.max_tokens.or(self.config.max_tokens),
             response_mime_type: None,
+            response_schema: None,
         };
         
         let content_request = ContentRequest {
             contents,
             generation_config: Some(generation_config),
-            safety_settings: None,
+            safety_settings: self.safety_settings(tenant),
         };
 
         // Make the API call
-        let api_url = self.get_api_url();
-        let url = if api_url.contains('?') {
-            format!("{}&key={}", api_url, self.config.api_key)
-        } else {
-            format!("{}?key={}", api_url, self.config.api_key)
-        };
-        
-        let response = self.client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&content_request)
-            .send()
-            .await
-            .map_err(|e| LlmError::NetworkError(format!("HTTP request failed: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(LlmError::ApiError(format!("Gemini API error {}: {}", status, error_text)));
-        }
-
-        let content_response: ContentResponse = response.json().await
+        let body = self.send(tenant, &self.config.model, &content_request).await?;
+        let content_response: ContentResponse = serde_json::from_str(&body)
             .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse response: {}", e)))?;
 
         // Extract the content
-        let text = content_response.candidates
-            .iter()
-            .flat_map(|c| &c.content.parts)
-            .map(|p| p.text.as_str())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        if text.is_empty() {
-            return Err(LlmError::ResponseParseError("No content in response".to_string()));
-        }
+        let text = response_text(&content_response)?;
 
         // Build metadata
         let latency = start_time.elapsed();
@@ -297,8 +381,10 @@ impl LlmConnector for GeminiConnector {
             latency_ms: Some(latency.as_millis() as u64),
             input_tokens: content_response.usage_metadata.as_ref().map(|u| u.prompt_token_count),
             output_tokens: content_response.usage_metadata.as_ref().map(|u| u.candidates_token_count),
-            cost_usd: content_response.usage_metadata.as_ref().and_then(|u| self.calculate_cost(u)),
+            cost_usd: content_response.usage_metadata.as_ref().and_then(|u| self.calculate_cost(&self.config.model, u)),
             warnings: Vec::new(),
+            model_selection: None,
+            citations: citations(&content_response),
         });
 
         info!(
@@ -309,6 +395,168 @@ impl LlmConnector for GeminiConnector {
 
         Ok(CompletionResponse { text, metadata })
     }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        let body = self.get(&format!("{}?pageSize=1000", self.get_models_url())).await?;
+        let list: ModelList = serde_json::from_str(&body)
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse model list: {}", e)))?;
+
+        Ok(list.models.into_iter()
+            .map(|model| ModelInfo {
+                id: model.name.strip_prefix("models/").map(str::to_string).unwrap_or(model.name),
+                display_name: model.display_name,
+            })
+            .collect())
+    }
+}
+
+/// Finish reasons of candidates withheld for their content
+const BLOCKED_FINISH_REASONS: &[&str] = &["SAFETY", "RECITATION", "BLOCKLIST", "PROHIBITED_CONTENT", "SPII"];
+
+/// Text of a response's candidates, or `ContentBlocked` when the prompt or
+/// every candidate was blocked
+fn response_text(response: &ContentResponse) -> Result<String, LlmError> {
+    if let Some(feedback) = &response.prompt_feedback {
+        if let Some(reason) = &feedback.block_reason {
+            return Err(LlmError::ContentBlocked(blocked_message("prompt", reason, &feedback.safety_ratings)));
+        }
+    }
+
+    let text = response.candidates
+        .iter()
+        .flat_map(|c| &c.content.parts)
+        .map(|p| p.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if text.is_empty() {
+        let blocked = response.candidates.iter().find_map(|c| {
+            c.finish_reason.as_deref().filter(|reason| BLOCKED_FINISH_REASONS.contains(reason)).map(|reason| (reason, c))
+        });
+        if let Some((reason, candidate)) = blocked {
+            let ratings = candidate.safety_ratings.as_deref().unwrap_or_default();
+            return Err(LlmError::ContentBlocked(blocked_message("response", reason, ratings)));
+        }
+        return Err(LlmError::ResponseParseError("No content in response".to_string()));
+    }
+
+    Ok(text)
+}
+
+/// Describe a block with the harm categories that caused it
+fn blocked_message(what: &str, reason: &str, ratings: &[SafetyRating]) -> String {
+    let categories = ratings.iter().filter(|r| r.blocked).map(|r| r.category.as_str()).collect::<Vec<_>>();
+    if categories.is_empty() {
+        format!("Gemini blocked the {} ({})", what, reason)
+    } else {
+        format!("Gemini blocked the {} ({}: {})", what, reason, categories.join(", "))
+    }
+}
+
+/// Sources the candidates cite or are grounded in
+fn citations(response: &ContentResponse) -> Vec<Citation> {
+    let mut citations = Vec::new();
+    for candidate in &response.candidates {
+        let sources = candidate.citation_metadata.iter().flat_map(|m| &m.citation_sources);
+        citations.extend(sources.map(|source| Citation {
+            uri: source.uri.clone(),
+            title: source.title.clone(),
+            start_index: source.start_index,
+            end_index: source.end_index,
+        }));
+
+        let chunks = candidate.grounding_metadata.iter().flat_map(|m| &m.grounding_chunks);
+        citations.extend(chunks.filter_map(|chunk| chunk.web.as_ref()).map(|web| Citation {
+            uri: web.uri.clone(),
+            title: web.title.clone(),
+            start_index: None,
+            end_index: None,
+        }));
+    }
+    citations
+}
+
+/// Map a failed HTTP request to an `LlmError`
+fn request_error(e: reqwest::Error) -> LlmError {
+    if e.is_timeout() {
+        LlmError::Timeout
+    } else if e.is_connect() {
+        LlmError::CapabilityUnavailable(format!("Gemini API is unreachable: {}", e))
+    } else {
+        LlmError::NetworkError(format!("HTTP request failed: {}", e))
+    }
+}
+
+/// Map an unsuccessful API response to an `LlmError`
+fn api_error(status: reqwest::StatusCode, error_text: String) -> LlmError {
+    let message = format!("Gemini API error {}: {}", status, error_text);
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        LlmError::RateLimited(message)
+    } else if provider_unavailable(status) {
+        LlmError::CapabilityUnavailable(message)
+    } else {
+        LlmError::ApiError(message)
+    }
+}
+
+/// Statuses returned while the provider is down or overloaded
+fn provider_unavailable(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        reqwest::StatusCode::BAD_GATEWAY | reqwest::StatusCode::SERVICE_UNAVAILABLE | reqwest::StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Map a message to a Gemini content; system and tool messages are sent as
+/// user content, and tool results as function responses
+fn convert_message(msg: &LlmMessage) -> Content {
+    let role = match msg.role.as_str() {
+        "assistant" => "model",
+        "user" | "system" | "tool" => "user",
+        other => {
+            skip(format!("role '{}' is not supported and was sent as 'user'", other));
+            "user"
+        }
+    };
+
+    let mut parts = Vec::new();
+    if !msg.content.is_empty() {
+        parts.push(Part::Text(msg.content.clone()));
+    }
+    for part in &msg.parts {
+        match part {
+            ContentPart::Text { text } => parts.push(Part::Text(text.clone())),
+            ContentPart::Image { .. } | ContentPart::ToolResult { .. } if role == "model" => {
+                skip("image or tool result in an assistant message was skipped; only user messages take them".to_string())
+            }
+            ContentPart::Image { source: ImageSource::Base64 { media_type, data } } => parts.push(Part::InlineData(InlineData {
+                mime_type: media_type.clone(),
+                data: data.clone(),
+            })),
+            ContentPart::Image { source: ImageSource::Url { .. } } => {
+                skip("image URL was skipped; Gemini takes images only as base64 data".to_string())
+            }
+            ContentPart::ToolResult { name: Some(name), content, is_error, .. } => parts.push(Part::FunctionResponse(FunctionResponse {
+                name: name.clone(),
+                response: if *is_error { serde_json::json!({ "error": content }) } else { serde_json::json!({ "content": content }) },
+            })),
+            ContentPart::ToolResult { tool_call_id, name: None, .. } => {
+                skip(format!("result of tool call '{}' was skipped; Gemini needs the tool's name", tool_call_id))
+            }
+        }
+    }
+    if parts.is_empty() {
+        parts.push(Part::Text(String::new()));
+    }
+
+    Content { parts, role: Some(role.to_string()) }
+}
+
+/// Report content that Gemini does not accept to the client and the log
+fn skip(reason: String) {
+    let message = format!("Gemini: {}", reason);
+    warn!("{}", message);
+    telamentis_core::warnings::report(message);
 }
 
 #[cfg(test)]
@@ -334,11 +582,18 @@ mod tests {
             messages: vec![LlmMessage {
                 role: "user".to_string(),
                 content: "Alice works at Acme Corp".to_string(),
+                parts: Vec::new(),
             }],
             system_prompt: Some("Custom prompt".to_string()),
             desired_schema: None,
             max_tokens: None,
             temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
         };
 
         let prompt = connector.build_extraction_prompt(&context);
@@ -370,4 +625,135 @@ mod tests {
         assert_eq!(envelope.nodes.len(), 1);
         assert_eq!(envelope.nodes[0].id_alias, "alice");
     }
+
+    #[tokio::test]
+    async fn test_structured_output_detection() {
+        assert!(!GeminiConfig::new("test-key").uses_structured_output());
+        assert!(GeminiConfig::new("test-key").with_model("gemini-1.5-pro").uses_structured_output());
+        assert!(GeminiConfig::new("test-key").with_structured_output(true).uses_structured_output());
+    }
+
+    #[tokio::test]
+    async fn test_multimodal_messages() {
+        let messages = [
+            LlmMessage::new("user", "")
+                .with_part(ContentPart::Image { source: ImageSource::Base64 { media_type: "image/jpeg".to_string(), data: "/9j/4AAQ".to_string() } })
+                .with_part(ContentPart::Image { source: ImageSource::Url { url: "https://example.com/a.png".to_string() } }),
+            LlmMessage::new("tool", "")
+                .with_part(ContentPart::ToolResult {
+                    tool_call_id: "call_1".to_string(),
+                    name: Some("lookup".to_string()),
+                    content: "Alice works at Acme Corp".to_string(),
+                    is_error: false,
+                })
+                .with_part(ContentPart::ToolResult {
+                    tool_call_id: "call_2".to_string(),
+                    name: None,
+                    content: "ignored".to_string(),
+                    is_error: false,
+                }),
+            LlmMessage::new("assistant", "Alice works at Acme"),
+        ];
+
+        let (converted, warnings) = collect_warnings(async {
+            messages.iter().map(convert_message).map(|c| serde_json::to_value(c).unwrap()).collect::<Vec<_>>()
+        }).await;
+        assert_eq!(converted[0], json!({"role": "user", "parts": [{"inline_data": {"mime_type": "image/jpeg", "data": "/9j/4AAQ"}}]}));
+        assert_eq!(converted[1], json!({
+            "role": "user",
+            "parts": [{"function_response": {"name": "lookup", "response": {"content": "Alice works at Acme Corp"}}}]
+        }));
+        assert_eq!(converted[2], json!({"role": "model", "parts": [{"text": "Alice works at Acme"}]}));
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_extraction_request_modes() {
+        let context = ExtractionContext {
+            messages: vec![LlmMessage {
+                role: "user".to_string(),
+                content: "Alice works at Acme Corp".to_string(),
+                parts: Vec::new(),
+            }],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        };
+
+        let connector = GeminiConnector::new(GeminiConfig::new("test-key").with_model("gemini-1.5-flash")).unwrap();
+        let request = serde_json::to_value(connector.build_extraction_request(&TenantId::new("acme"), &context)).unwrap();
+        let generation_config = &request["generation_config"];
+        assert_eq!(generation_config["response_mime_type"], "application/json");
+        assert_eq!(generation_config["response_schema"]["type"], "OBJECT");
+        assert_eq!(generation_config["response_schema"]["properties"]["nodes"]["items"]["type"], "OBJECT");
+
+        let connector = GeminiConnector::new(GeminiConfig::new("test-key")).unwrap();
+        let request = serde_json::to_value(connector.build_extraction_request(&TenantId::new("acme"), &context)).unwrap();
+        assert!(request["generation_config"].get("response_schema").is_none());
+        assert!(request.get("safety_settings").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_safety_settings() {
+        let config = GeminiConfig::new("test-key")
+            .with_safety_settings(vec![SafetySetting::new("HARM_CATEGORY_HARASSMENT", "BLOCK_MEDIUM_AND_ABOVE")])
+            .with_tenant_safety_settings(TenantId::new("clinic"), vec![SafetySetting::new("HARM_CATEGORY_DANGEROUS_CONTENT", "BLOCK_ONLY_HIGH")]);
+        let connector = GeminiConnector::new(config).unwrap();
+        let context = ExtractionContext {
+            messages: vec![LlmMessage::new("user", "Alice works at Acme Corp")],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        };
+
+        let request = serde_json::to_value(connector.build_extraction_request(&TenantId::new("acme"), &context)).unwrap();
+        assert_eq!(request["safety_settings"], json!([{"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_MEDIUM_AND_ABOVE"}]));
+        let request = serde_json::to_value(connector.build_extraction_request(&TenantId::new("clinic"), &context)).unwrap();
+        assert_eq!(request["safety_settings"], json!([{"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "threshold": "BLOCK_ONLY_HIGH"}]));
+    }
+
+    #[test]
+    fn test_blocked_responses_and_citations() {
+        let blocked_prompt: ContentResponse = serde_json::from_value(json!({
+            "prompt_feedback": {
+                "block_reason": "SAFETY",
+                "safety_ratings": [{"category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH", "blocked": true}]
+            }
+        })).unwrap();
+        let error = response_text(&blocked_prompt).unwrap_err();
+        assert!(matches!(&error, LlmError::ContentBlocked(msg) if msg.contains("prompt (SAFETY: HARM_CATEGORY_HARASSMENT)")));
+
+        let blocked_candidate: ContentResponse = serde_json::from_value(json!({
+            "candidates": [{"finish_reason": "RECITATION"}]
+        })).unwrap();
+        assert!(matches!(response_text(&blocked_candidate), Err(LlmError::ContentBlocked(_))));
+
+        let cited: ContentResponse = serde_json::from_value(json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "Alice works at Acme Corp"}], "role": "model"},
+                "finish_reason": "STOP",
+                "citation_metadata": {"citation_sources": [{"start_index": 0, "end_index": 24, "uri": "https://example.com/acme"}]},
+                "grounding_metadata": {"grounding_chunks": [{"web": {"uri": "https://example.com/team", "title": "Team"}}]}
+            }]
+        })).unwrap();
+        assert_eq!(response_text(&cited).unwrap(), "Alice works at Acme Corp");
+        let citations = citations(&cited);
+        assert_eq!(citations.len(), 2);
+        assert_eq!((citations[0].start_index, citations[0].end_index), (Some(0), Some(24)));
+        assert_eq!(citations[1].title.as_deref(), Some("Team"));
+    }
 }
\ No newline at end of file
@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::config::SafetySetting;
+
 /// Gemini Content API request
 #[derive(Debug, Serialize)]
 pub struct ContentRequest {
@@ -22,8 +24,25 @@ pub struct Content {
 
 /// Part of a content
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Part {
-    pub text: String,
+#[serde(rename_all = "snake_case")]
+pub enum Part {
+    Text(String),
+    InlineData(InlineData),
+    FunctionResponse(FunctionResponse),
+}
+
+/// Base64-encoded data sent inline, e.g. an image
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InlineData {
+    pub mime_type: String,
+    pub data: String,
+}
+
+/// Result of a function call, sent back to the model
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FunctionResponse {
+    pub name: String,
+    pub response: serde_json::Value,
 }
 
 /// Generation configuration
@@ -35,36 +54,66 @@ pub struct GenerationConfig {
     pub max_output_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
 }
 
-/// Safety setting
-#[derive(Debug, Serialize)]
-pub struct SafetySetting {
-    pub category: String,
-    pub threshold: String,
+/// Response of the list-models endpoint
+#[derive(Debug, Deserialize)]
+pub struct ModelList {
+    #[serde(default)]
+    pub models: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModelEntry {
+    /// Resource name, e.g. `models/gemini-1.5-pro`
+    pub name: String,
+    #[serde(default, alias = "displayName")]
+    pub display_name: Option<String>,
 }
 
 /// Gemini Content API response
 #[derive(Debug, Deserialize)]
 pub struct ContentResponse {
+    /// Empty when the prompt was blocked
+    #[serde(default)]
     pub candidates: Vec<Candidate>,
+    #[serde(default)]
+    pub prompt_feedback: Option<PromptFeedback>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage_metadata: Option<UsageMetadata>,
 }
 
+/// Why a prompt was blocked
+#[derive(Debug, Deserialize)]
+pub struct PromptFeedback {
+    #[serde(default)]
+    pub block_reason: Option<String>,
+    #[serde(default)]
+    pub safety_ratings: Vec<SafetyRating>,
+}
+
 /// Candidate in the response
 #[derive(Debug, Deserialize)]
 pub struct Candidate {
+    /// Missing when the candidate was blocked
+    #[serde(default)]
     pub content: ContentResult,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub finish_reason: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub safety_ratings: Option<Vec<SafetyRating>>,
+    #[serde(default)]
+    pub citation_metadata: Option<CitationMetadata>,
+    #[serde(default)]
+    pub grounding_metadata: Option<GroundingMetadata>,
 }
 
 /// Content in the response
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct ContentResult {
+    #[serde(default)]
     pub parts: Vec<PartResult>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub role: Option<String>,
@@ -73,6 +122,7 @@ pub struct ContentResult {
 /// Part in the response
 #[derive(Debug, Deserialize)]
 pub struct PartResult {
+    #[serde(default)]
     pub text: String,
 }
 
@@ -81,6 +131,44 @@ pub struct PartResult {
 pub struct SafetyRating {
     pub category: String,
     pub probability: String,
+    #[serde(default)]
+    pub blocked: bool,
+}
+
+/// Sources the candidate recites
+#[derive(Debug, Deserialize)]
+pub struct CitationMetadata {
+    #[serde(default)]
+    pub citation_sources: Vec<CitationSource>,
+}
+
+/// A recited source and the span of the candidate reciting it
+#[derive(Debug, Deserialize)]
+pub struct CitationSource {
+    pub start_index: Option<u32>,
+    pub end_index: Option<u32>,
+    pub uri: Option<String>,
+    pub title: Option<String>,
+}
+
+/// Sources a grounded candidate is based on
+#[derive(Debug, Deserialize)]
+pub struct GroundingMetadata {
+    #[serde(default)]
+    pub grounding_chunks: Vec<GroundingChunk>,
+}
+
+/// A grounding source; only web sources are read
+#[derive(Debug, Deserialize)]
+pub struct GroundingChunk {
+    pub web: Option<WebSource>,
+}
+
+/// A web page a candidate is grounded in
+#[derive(Debug, Deserialize)]
+pub struct WebSource {
+    pub uri: Option<String>,
+    pub title: Option<String>,
 }
 
 /// Usage metadata
@@ -109,16 +197,8 @@ impl Content {
     /// Create a new user content
     pub fn new_user(text: impl Into<String>) -> Self {
         Self {
-            parts: vec![Part { text: text.into() }],
+            parts: vec![Part::Text(text.into())],
             role: Some("user".to_string()),
         }
     }
-
-    /// Create a new model content
-    pub fn new_model(text: impl Into<String>) -> Self {
-        Self {
-            parts: vec![Part { text: text.into() }],
-            role: Some("model".to_string()),
-        }
-    }
 }
\ No newline at end of file
@@ -0,0 +1,80 @@
+//! Sandbox mode: a local mock of the Gemini API
+//!
+//! Requires the `sandbox` feature. The mock server answers every request with
+//! the fixture for its [`SandboxScenario`], taken from the crate's `fixtures`
+//! directory, so extraction can run end-to-end without an API key. Model
+//! listings, and so health probes, always succeed.
+
+use crate::{GeminiConfig, GeminiConnector};
+use std::time::Duration;
+use telamentis_core::prelude::*;
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const EXTRACTION: &str = include_str!("../fixtures/extraction.json");
+const MALFORMED_JSON: &str = include_str!("../fixtures/malformed_json.json");
+const RATE_LIMITED: &str = include_str!("../fixtures/rate_limited.json");
+const MODELS: &str = include_str!("../fixtures/models.json");
+
+/// Local mock of the Gemini API serving one scenario
+pub struct GeminiSandbox {
+    server: MockServer,
+    scenario: SandboxScenario,
+}
+
+impl GeminiSandbox {
+    /// Start a sandbox server on a free local port
+    pub async fn start(scenario: SandboxScenario) -> Self {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/v1/models/[^/]+:generateContent$"))
+            .respond_with(response(scenario))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(MODELS, "application/json"))
+            .mount(&server)
+            .await;
+
+        Self { server, scenario }
+    }
+
+    /// Scenario the sandbox serves
+    pub fn scenario(&self) -> SandboxScenario {
+        self.scenario
+    }
+
+    /// Base URL of the sandbox server
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Connector configuration routed to the sandbox
+    pub fn config(&self) -> GeminiConfig {
+        GeminiConfig::default().with_sandbox(self.uri())
+    }
+
+    /// Create a connector routed to the sandbox
+    pub fn connector(&self) -> Result<GeminiConnector, LlmError> {
+        GeminiConnector::new(self.config())
+    }
+
+    /// Number of requests the sandbox has received
+    pub async fn request_count(&self) -> usize {
+        self.server.received_requests().await.map_or(0, |requests| requests.len())
+    }
+}
+
+/// Provider response for a scenario
+fn response(scenario: SandboxScenario) -> ResponseTemplate {
+    let fixture = |status: u16, body: &str| ResponseTemplate::new(status).set_body_raw(body, "application/json");
+
+    match scenario {
+        SandboxScenario::Success => fixture(200, EXTRACTION),
+        SandboxScenario::RateLimited => fixture(429, RATE_LIMITED).insert_header("retry-after", "20"),
+        SandboxScenario::MalformedJson => fixture(200, MALFORMED_JSON),
+        SandboxScenario::Timeout => fixture(200, EXTRACTION)
+            .set_delay(Duration::from_millis(SANDBOX_RESPONSE_DELAY_MS)),
+    }
+}
@@ -1,6 +1,8 @@
 //! Configuration for OpenAI connector
 
 use serde::{Deserialize, Serialize};
+use telamentis_core::http::{HttpClientConfig, ProxyConfig};
+use telamentis_core::sandbox::{SANDBOX_API_KEY, SANDBOX_TIMEOUT_MS};
 
 /// OpenAI API configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +21,16 @@ pub struct OpenAiConfig {
     pub timeout_ms: u64,
     /// Maximum retries for failed requests
     pub max_retries: u32,
+    /// Use function calling for extraction; `None` detects support from the model
+    #[serde(default)]
+    pub structured_output: Option<bool>,
+    /// Proxy, root certificate and connection pool settings
+    #[serde(default)]
+    pub http: HttpClientConfig,
+    /// Shape requests for a reasoning model, which takes
+    /// `max_completion_tokens` and no temperature; `None` detects it from the model
+    #[serde(default)]
+    pub reasoning_model: Option<bool>,
 }
 
 impl OpenAiConfig {
@@ -32,6 +44,9 @@ impl OpenAiConfig {
             temperature: Some(0.1),
             timeout_ms: 30_000,
             max_retries: 3,
+            structured_output: None,
+            http: HttpClientConfig::default(),
+            reasoning_model: None,
         }
     }
 
@@ -47,6 +62,17 @@ impl OpenAiConfig {
         self
     }
 
+    /// Route requests to a sandbox server at `base_url` instead of the real
+    /// API, with a short timeout and a placeholder API key if none is set
+    pub fn with_sandbox(mut self, base_url: impl Into<String>) -> Self {
+        self.api_base = format!("{}/v1", base_url.into().trim_end_matches('/'));
+        self.timeout_ms = SANDBOX_TIMEOUT_MS;
+        if self.api_key.is_empty() {
+            self.api_key = SANDBOX_API_KEY.to_string();
+        }
+        self
+    }
+
     /// Set maximum tokens
     pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
         self.max_tokens = Some(max_tokens);
@@ -70,6 +96,61 @@ impl OpenAiConfig {
         self.max_retries = max_retries;
         self
     }
+
+    /// Set the proxy, root certificate and connection pool settings
+    pub fn with_http_client(mut self, http: HttpClientConfig) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Send requests through an outbound proxy
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.http.proxy = Some(proxy);
+        self
+    }
+
+    /// Force native structured output on or off instead of detecting it
+    pub fn with_structured_output(mut self, enabled: bool) -> Self {
+        self.structured_output = Some(enabled);
+        self
+    }
+
+    /// Force reasoning-model request shaping on or off instead of detecting it
+    pub fn with_reasoning_model(mut self, enabled: bool) -> Self {
+        self.reasoning_model = Some(enabled);
+        self
+    }
+
+    /// Whether requests to a model take `max_completion_tokens` and no temperature
+    pub fn is_reasoning_model_for(&self, model: &str) -> bool {
+        self.reasoning_model.unwrap_or_else(|| is_reasoning_model(model))
+    }
+
+    /// Whether extraction should use function calling rather than JSON prompting
+    pub fn uses_structured_output(&self) -> bool {
+        self.uses_structured_output_for(&self.model)
+    }
+
+    /// Like [`Self::uses_structured_output`], for a model chosen per request
+    pub fn uses_structured_output_for(&self, model: &str) -> bool {
+        self.structured_output.unwrap_or_else(|| supports_function_calling(model))
+    }
+}
+
+/// Whether a model is known to support forced function calling
+fn supports_function_calling(model: &str) -> bool {
+    const PREFIXES: &[&str] = &["gpt-4", "gpt-3.5-turbo", "gpt-5", "o1", "o3", "o4"];
+    const UNSUPPORTED: &[&str] = &["o1-preview", "o1-mini"];
+
+    PREFIXES.iter().any(|prefix| model.starts_with(prefix))
+        && !UNSUPPORTED.iter().any(|prefix| model.starts_with(prefix))
+}
+
+/// Whether a model is known to be a reasoning model
+fn is_reasoning_model(model: &str) -> bool {
+    const PREFIXES: &[&str] = &["o1", "o3", "o4", "gpt-5"];
+
+    PREFIXES.iter().any(|prefix| model.starts_with(prefix))
 }
 
 impl Default for OpenAiConfig {
@@ -3,31 +3,50 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Instant;
 use telamentis_core::prelude::*;
 use tracing::{debug, error, info, warn};
 
 mod config;
 mod models;
+#[cfg(feature = "sandbox")]
+mod sandbox;
 
 pub use config::OpenAiConfig;
+#[cfg(feature = "sandbox")]
+pub use sandbox::OpenAiSandbox;
 use models::*;
 
 /// OpenAI implementation of LlmConnector
 pub struct OpenAiConnector {
     client: Client,
     config: OpenAiConfig,
+    exchange_log: Option<Arc<ExchangeLog>>,
 }
 
 impl OpenAiConnector {
     /// Create a new OpenAI connector
     pub fn new(config: OpenAiConfig) -> Result<Self, LlmError> {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_millis(config.timeout_ms))
+        let builder = Client::builder()
+            .timeout(std::time::Duration::from_millis(config.timeout_ms));
+        let client = config.http.apply(builder)
+            .map_err(LlmError::ConfigError)?
             .build()
             .map_err(|e| LlmError::ConfigError(format!("Failed to create HTTP client: {}", e)))?;
 
-        Ok(Self { client, config })
+        Ok(Self { client, config, exchange_log: None })
+    }
+
+    /// Record sampled requests and responses of tenants with logging enabled
+    pub fn with_exchange_log(mut self, exchange_log: Arc<ExchangeLog>) -> Self {
+        self.exchange_log = Some(exchange_log);
+        self
+    }
+
+    /// Model for an extraction: the request's model or the configured default
+    fn model<'a>(&'a self, context: &'a ExtractionContext) -> &'a str {
+        context.model.as_deref().unwrap_or(&self.config.model)
     }
 
     /// Build the system prompt for extraction
@@ -36,30 +55,101 @@ impl OpenAiConnector {
             "You are an expert knowledge graph extraction engine. Analyze the provided text/conversation and identify relevant entities (as nodes) and relationships (as relations) between them."
         );
 
+        let examples = render_examples(
+            context.examples.as_deref().unwrap_or_default(),
+            TokenEstimator::for_provider("openai"),
+            example_token_budget(self.model(context)),
+        );
+
         format!(
-            "{}\n\nReturn your findings strictly as a JSON object matching the following schema:\n{}\n\nInstructions:\n- `id_alias` should be a descriptive, unique identifier for nodes within this extraction (e.g., \"user_john_doe\", \"acme_corp_hq\")\n- If a date or time for `valid_from` or `valid_to` is mentioned, use ISO8601 format\n- If a relation is ongoing, `valid_to` can be omitted or null\n- Only extract explicitly mentioned information. Do not infer or hallucinate\n- If unsure about a piece of information, omit it or assign a low confidence score",
+            "{}\n\nReturn your findings strictly as a JSON object matching the following schema:\n{}\n\nInstructions:\n- `id_alias` should be a descriptive, unique identifier for nodes within this extraction (e.g., \"user_john_doe\", \"acme_corp_hq\")\n- If a date or time for `valid_from` or `valid_to` is mentioned, use ISO8601 format, only as precise as the text: \"2020\" for a year, \"2020-03\" for a month, \"2020-03-15\" for a day, a full timestamp only for an exact time\n- If a relation is ongoing, `valid_to` can be omitted or null\n- Only extract explicitly mentioned information. Do not infer or hallucinate\n- If unsure about a piece of information, omit it or assign a low confidence score{}{}",
             base_prompt,
-            ExtractionEnvelope::json_schema_example()
+            ExtractionEnvelope::json_schema_example(),
+            graph_context_section(context),
+            examples
         )
     }
 
     /// Convert TelaMentis messages to OpenAI format
     fn convert_messages(&self, context: &ExtractionContext) -> Vec<OpenAiMessage> {
-        let mut messages = vec![OpenAiMessage {
-            role: "system".to_string(),
-            content: self.build_extraction_prompt(context),
-        }];
+        let mut messages = vec![OpenAiMessage::text("system", self.build_extraction_prompt(context))];
 
         for msg in &context.messages {
-            messages.push(OpenAiMessage {
-                role: msg.role.clone(),
-                content: msg.content.clone(),
-            });
+            messages.extend(convert_message(msg));
         }
 
         messages
     }
 
+    /// Build the chat request for an extraction, using function calling when
+    /// the model supports it and JSON mode otherwise
+    fn build_extraction_request(&self, context: &ExtractionContext) -> ChatCompletionRequest {
+        let model = self.model(context);
+        let structured = self.config.uses_structured_output_for(model);
+
+        let request = ChatCompletionRequest {
+            model: model.to_string(),
+            messages: self.convert_messages(context),
+            max_tokens: context.max_tokens.or(self.config.max_tokens),
+            max_completion_tokens: None,
+            temperature: context.temperature.or(self.config.temperature),
+            seed: context.seed,
+            response_format: (!structured).then(|| ResponseFormat {
+                r#type: "json_object".to_string(),
+            }),
+            tools: structured.then(|| vec![Tool {
+                r#type: "function".to_string(),
+                function: FunctionDefinition {
+                    name: ExtractionEnvelope::TOOL_NAME.to_string(),
+                    description: "Record the entities and relationships extracted from the conversation".to_string(),
+                    parameters: ExtractionEnvelope::json_schema(),
+                },
+            }]),
+            tool_choice: structured.then(|| ToolChoice {
+                r#type: "function".to_string(),
+                function: ToolChoiceFunction {
+                    name: ExtractionEnvelope::TOOL_NAME.to_string(),
+                },
+            }),
+            extra: serde_json::Map::new(),
+        };
+        self.shape_for_model(request, context.temperature)
+    }
+
+    /// Adapt a request to its model: reasoning models take the token limit as
+    /// `max_completion_tokens` and reject any temperature but their default
+    fn shape_for_model(&self, mut request: ChatCompletionRequest, requested_temperature: Option<f32>) -> ChatCompletionRequest {
+        if !self.config.is_reasoning_model_for(&request.model) {
+            return request;
+        }
+
+        request.max_completion_tokens = request.max_tokens.take();
+        request.temperature = None;
+        if requested_temperature.is_some() {
+            skip(format!("temperature was not sent; reasoning model '{}' only uses its default", request.model));
+        }
+        request
+    }
+
+    /// Get the extraction JSON from a response, preferring the tool call arguments
+    fn extraction_content<'a>(&self, response: &'a ChatCompletionResponse) -> Result<&'a str, LlmError> {
+        let message = &response.choices
+            .first()
+            .ok_or_else(|| LlmError::ResponseParseError("No choices in response".to_string()))?
+            .message;
+
+        let tool_call = message.tool_calls
+            .iter()
+            .find(|call| call.r#type == "function" && call.function.name == ExtractionEnvelope::TOOL_NAME);
+        if let Some(call) = tool_call {
+            return Ok(&call.function.arguments);
+        }
+
+        message.content
+            .as_deref()
+            .ok_or_else(|| LlmError::ResponseParseError("No content in response".to_string()))
+    }
+
     /// Parse and validate the OpenAI response
     fn parse_extraction_response(&self, content: &str) -> Result<ExtractionEnvelope, LlmError> {
         // Clean up potential markdown code block fences
@@ -119,9 +209,9 @@ impl OpenAiConnector {
     }
 
     /// Calculate estimated cost based on token usage
-    fn calculate_cost(&self, usage: &Usage) -> Option<f64> {
+    fn calculate_cost(&self, model: &str, usage: &Usage) -> Option<f64> {
         // OpenAI pricing (approximate, as of 2024)
-        let (input_cost_per_1k, output_cost_per_1k) = match self.config.model.as_str() {
+        let (input_cost_per_1k, output_cost_per_1k) = match model {
             "gpt-4" => (0.03, 0.06),
             "gpt-4-turbo" => (0.01, 0.03),
             "gpt-3.5-turbo" => (0.001, 0.002),
@@ -133,51 +223,85 @@ impl OpenAiConnector {
         
         Some(input_cost + output_cost)
     }
-}
 
-#[async_trait]
-impl LlmConnector for OpenAiConnector {
-    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
-        debug!("Starting OpenAI extraction for tenant: {}", tenant);
-        let start_time = Instant::now();
+    /// Tokens left for conversation messages after the extraction prompt,
+    /// tool schema and reserved output
+    fn input_budget(&self, context: &ExtractionContext, estimator: TokenEstimator) -> usize {
+        let mut overhead = estimator.count(&self.build_extraction_prompt(context));
+        if self.config.uses_structured_output_for(self.model(context)) {
+            overhead += estimator.count(&ExtractionEnvelope::json_schema().to_string());
+        }
 
-        // Build the request
-        let messages = self.convert_messages(&context);
-        let request = ChatCompletionRequest {
-            model: self.config.model.clone(),
-            messages,
-            max_tokens: context.max_tokens.or(self.config.max_tokens),
-            temperature: context.temperature.or(self.config.temperature),
-            response_format: Some(ResponseFormat {
-                r#type: "json_object".to_string(),
-            }),
-        };
+        model_limits(self.model(context)).input_budget(context.max_tokens.or(self.config.max_tokens), overhead)
+    }
 
-        // Make the API call
+    /// Send a request to the API and return the response body, recording the
+    /// exchange if the tenant's exchanges are logged
+    async fn send<T: Serialize>(&self, tenant: &TenantId, model: &str, request: &T) -> Result<String, LlmError> {
+        let pending = self.exchange_log.as_ref().and_then(|log| log.begin(tenant, "openai", model, request));
+        let result = self.post(request).await;
+        if let (Some(log), Some(pending)) = (&self.exchange_log, pending) {
+            log.finish(pending, result.as_deref()).await;
+        }
+        result
+    }
+
+    async fn post<T: Serialize>(&self, request: &T) -> Result<String, LlmError> {
         let response = self.client
             .post(&format!("{}/chat/completions", self.config.api_base))
             .header("Authorization", format!("Bearer {}", self.config.api_key))
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(request)
             .send()
             .await
-            .map_err(|e| LlmError::NetworkError(format!("HTTP request failed: {}", e)))?;
+            .map_err(request_error)?;
 
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(LlmError::ApiError(format!("OpenAI API error {}: {}", status, error_text)));
+            return Err(api_error(status, error_text));
         }
 
-        let chat_response: ChatCompletionResponse = response.json().await
+        response.text().await
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to read response: {}", e)))
+    }
+
+    async fn get(&self, endpoint: &str) -> Result<String, LlmError> {
+        let response = self.client
+            .get(format!("{}/{}", self.config.api_base, endpoint))
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(api_error(status, error_text));
+        }
+
+        response.text().await
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to read response: {}", e)))
+    }
+
+    /// Extract from a context that fits the model's context window
+    async fn extract_single(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let model = self.model(&context).to_string();
+        debug!("Starting OpenAI extraction for tenant: {}", tenant);
+        let start_time = Instant::now();
+
+        // Build the request
+        let request = self.build_extraction_request(&context);
+
+        // Make the API call
+        let body = self.send(tenant, &model, &request).await?;
+        let chat_response: ChatCompletionResponse = serde_json::from_str(&body)
             .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse response: {}", e)))?;
 
         // Extract the content
-        let content = chat_response.choices
-            .first()
-            .and_then(|choice| choice.message.content.as_ref())
-            .ok_or_else(|| LlmError::ResponseParseError("No content in response".to_string()))?;
+        let content = self.extraction_content(&chat_response)?;
 
         // Parse the extraction
         let mut envelope = self.parse_extraction_response(content)?;
@@ -186,12 +310,14 @@ impl LlmConnector for OpenAiConnector {
         let latency = start_time.elapsed();
         envelope.metadata = Some(ExtractionMetadata {
             provider: "openai".to_string(),
-            model_name: self.config.model.clone(),
+            model_name: model.clone(),
             latency_ms: Some(latency.as_millis() as u64),
             input_tokens: chat_response.usage.as_ref().map(|u| u.prompt_tokens),
             output_tokens: chat_response.usage.as_ref().map(|u| u.completion_tokens),
-            cost_usd: chat_response.usage.as_ref().and_then(|u| self.calculate_cost(u)),
+            cost_usd: chat_response.usage.as_ref().and_then(|u| self.calculate_cost(&model, u)),
             warnings: Vec::new(),
+            model_selection: None,
+            citations: Vec::new(),
         });
 
         info!(
@@ -204,43 +330,44 @@ impl LlmConnector for OpenAiConnector {
 
         Ok(envelope)
     }
+}
+
+#[async_trait]
+impl LlmConnector for OpenAiConnector {
+    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let estimator = TokenEstimator::for_provider("openai");
+        let budget = self.input_budget(&context, estimator);
+
+        extract_chunked(context, estimator, budget, |chunk| self.extract_single(tenant, chunk)).await
+    }
 
     async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
         debug!("Starting OpenAI completion for tenant: {}", tenant);
         let start_time = Instant::now();
 
         // Build the request
-        let messages = vec![OpenAiMessage {
-            role: "user".to_string(),
-            content: request.prompt,
-        }];
+        let mut messages: Vec<_> = request.system.iter()
+            .map(|system| OpenAiMessage::text("system", system.clone()))
+            .collect();
+        messages.extend(request.conversation().iter().flat_map(convert_message));
 
         let chat_request = ChatCompletionRequest {
             model: self.config.model.clone(),
             messages,
             max_tokens: request.max_tokens.or(self.config.max_tokens),
+            max_completion_tokens: None,
             temperature: request.temperature.or(self.config.temperature),
+            seed: request.seed,
             response_format: None, // No JSON formatting for regular completion
+            tools: None,
+            tool_choice: None,
+            extra: passthrough_params(request.params),
         };
+        let chat_request = self.shape_for_model(chat_request, request.temperature);
 
         // Make the API call
-        let response = self.client
-            .post(&format!("{}/chat/completions", self.config.api_base))
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&chat_request)
-            .send()
-            .await
-            .map_err(|e| LlmError::NetworkError(format!("HTTP request failed: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(LlmError::ApiError(format!("OpenAI API error {}: {}", status, error_text)));
-        }
-
-        let chat_response: ChatCompletionResponse = response.json().await
+        let body = self.send(tenant, &self.config.model, &chat_request).await?;
+        let chat_response: ChatCompletionResponse = serde_json::from_str(&body)
             .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse response: {}", e)))?;
 
         // Extract the content
@@ -258,8 +385,10 @@ impl LlmConnector for OpenAiConnector {
             latency_ms: Some(latency.as_millis() as u64),
             input_tokens: chat_response.usage.as_ref().map(|u| u.prompt_tokens),
             output_tokens: chat_response.usage.as_ref().map(|u| u.completion_tokens),
-            cost_usd: chat_response.usage.as_ref().and_then(|u| self.calculate_cost(u)),
+            cost_usd: chat_response.usage.as_ref().and_then(|u| self.calculate_cost(&self.config.model, u)),
             warnings: Vec::new(),
+            model_selection: None,
+            citations: Vec::new(),
         });
 
         info!(
@@ -270,6 +399,117 @@ impl LlmConnector for OpenAiConnector {
 
         Ok(CompletionResponse { text, metadata })
     }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        let body = self.get("models").await?;
+        let list: ModelList = serde_json::from_str(&body)
+            .map_err(|e| LlmError::ResponseParseError(format!("Failed to parse model list: {}", e)))?;
+
+        Ok(list.data.into_iter().map(|model| ModelInfo { id: model.id, display_name: None }).collect())
+    }
+}
+
+/// Map a failed HTTP request to an `LlmError`
+fn request_error(e: reqwest::Error) -> LlmError {
+    if e.is_timeout() {
+        LlmError::Timeout
+    } else if e.is_connect() {
+        LlmError::CapabilityUnavailable(format!("OpenAI API is unreachable: {}", e))
+    } else {
+        LlmError::NetworkError(format!("HTTP request failed: {}", e))
+    }
+}
+
+/// Map an unsuccessful API response to an `LlmError`
+fn api_error(status: reqwest::StatusCode, error_text: String) -> LlmError {
+    let message = format!("OpenAI API error {}: {}", status, error_text);
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        LlmError::RateLimited(message)
+    } else if provider_unavailable(status) {
+        LlmError::CapabilityUnavailable(message)
+    } else {
+        LlmError::ApiError(message)
+    }
+}
+
+/// Statuses returned while the provider is down or overloaded
+fn provider_unavailable(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        reqwest::StatusCode::BAD_GATEWAY | reqwest::StatusCode::SERVICE_UNAVAILABLE | reqwest::StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Parameters of a completion request sent to the API as they are, except
+/// those with fields of their own
+fn passthrough_params(params: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    let serde_json::Value::Object(params) = params else {
+        return serde_json::Map::new();
+    };
+
+    params.into_iter()
+        .filter(|(key, _)| {
+            let modeled = MODELED_PARAMS.contains(&key.as_str());
+            if modeled {
+                skip(format!("param '{}' was ignored; set it with its request field", key));
+            }
+            !modeled
+        })
+        .collect()
+}
+
+/// Map a message to OpenAI messages: each tool result as a `tool` message,
+/// then the text and images as a message of the same role
+fn convert_message(msg: &LlmMessage) -> Vec<OpenAiMessage> {
+    let role = match msg.role.as_str() {
+        role @ ("system" | "user" | "assistant") => role,
+        "tool" => "user",
+        other => {
+            skip(format!("role '{}' is not supported and was sent as 'user'", other));
+            "user"
+        }
+    };
+
+    let mut messages = Vec::new();
+    let mut parts = Vec::new();
+    if !msg.content.is_empty() {
+        parts.push(OpenAiContentPart::Text { text: msg.content.clone() });
+    }
+    for part in &msg.parts {
+        match part {
+            ContentPart::Text { text } => parts.push(OpenAiContentPart::Text { text: text.clone() }),
+            ContentPart::Image { source } if role == "user" => {
+                let url = match source {
+                    ImageSource::Url { url } => url.clone(),
+                    ImageSource::Base64 { media_type, data } => format!("data:{};base64,{}", media_type, data),
+                };
+                parts.push(OpenAiContentPart::ImageUrl { image_url: ImageUrl { url } });
+            }
+            ContentPart::Image { .. } => skip(format!("image in a '{}' message was skipped; only user messages take images", role)),
+            ContentPart::ToolResult { tool_call_id, content, is_error, .. } => messages.push(OpenAiMessage {
+                role: "tool".to_string(),
+                content: OpenAiContent::Text(if *is_error { format!("Error: {}", content) } else { content.clone() }),
+                tool_call_id: Some(tool_call_id.clone()),
+            }),
+        }
+    }
+
+    // A single text part is sent as plain content, as before parts existed
+    let content = match parts.as_slice() {
+        [] if !messages.is_empty() => return messages,
+        [] => OpenAiContent::Text(String::new()),
+        [OpenAiContentPart::Text { text }] => OpenAiContent::Text(text.clone()),
+        _ => OpenAiContent::Parts(parts),
+    };
+    messages.push(OpenAiMessage { role: role.to_string(), content, tool_call_id: None });
+    messages
+}
+
+/// Report content that OpenAI does not accept to the client and the log
+fn skip(reason: String) {
+    let message = format!("OpenAI: {}", reason);
+    warn!("{}", message);
+    telamentis_core::warnings::report(message);
 }
 
 #[cfg(test)]
@@ -295,16 +535,45 @@ mod tests {
             messages: vec![LlmMessage {
                 role: "user".to_string(),
                 content: "Alice works at Acme Corp".to_string(),
+                parts: Vec::new(),
             }],
             system_prompt: Some("Custom prompt".to_string()),
             desired_schema: None,
             max_tokens: None,
             temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
         };
 
         let prompt = connector.build_extraction_prompt(&context);
         assert!(prompt.contains("Custom prompt"));
         assert!(prompt.contains("JSON object"));
+        assert!(!prompt.contains("Examples of correct extractions"));
+
+        let example = ExtractionExample::new("Bob joined Initech", ExtractionEnvelope {
+            nodes: vec![],
+            relations: vec![],
+            metadata: None,
+        });
+        let context = ExtractionContext {
+            examples: Some(vec![example]),
+            ..context
+        };
+        let prompt = connector.build_extraction_prompt(&context);
+        assert!(prompt.contains("Example 1:\nInput:\nBob joined Initech"));
+        assert!(!prompt.contains("The knowledge graph already contains"));
+
+        let context = ExtractionContext {
+            graph_context: Some("Node labels:\n- Organization (3)".to_string()),
+            ..context
+        };
+        let prompt = connector.build_extraction_prompt(&context);
+        let grounding = prompt.find("- Organization (3)").unwrap();
+        assert!(grounding < prompt.find("Example 1:").unwrap());
     }
 
     #[tokio::test]
@@ -332,6 +601,199 @@ mod tests {
         assert_eq!(envelope.nodes[0].id_alias, "alice");
     }
 
+    #[tokio::test]
+    async fn test_structured_output_detection() {
+        assert!(OpenAiConfig::new("test-key").uses_structured_output());
+        assert!(!OpenAiConfig::new("test-key").with_model("o1-mini").uses_structured_output());
+        assert!(!OpenAiConfig::new("test-key").with_model("local-llama").uses_structured_output());
+        assert!(OpenAiConfig::new("test-key").with_model("local-llama").with_structured_output(true).uses_structured_output());
+    }
+
+    #[tokio::test]
+    async fn test_extraction_request_modes() {
+        let context = ExtractionContext {
+            messages: vec![LlmMessage {
+                role: "user".to_string(),
+                content: "Alice works at Acme Corp".to_string(),
+                parts: Vec::new(),
+            }],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        };
+
+        let connector = OpenAiConnector::new(OpenAiConfig::new("test-key")).unwrap();
+        let request = serde_json::to_value(connector.build_extraction_request(&context)).unwrap();
+        assert_eq!(request["tools"][0]["function"]["name"], ExtractionEnvelope::TOOL_NAME);
+        assert_eq!(request["tool_choice"]["function"]["name"], ExtractionEnvelope::TOOL_NAME);
+        assert!(request.get("response_format").is_none());
+
+        let connector = OpenAiConnector::new(OpenAiConfig::new("test-key").with_structured_output(false)).unwrap();
+        let request = serde_json::to_value(connector.build_extraction_request(&context)).unwrap();
+        assert!(request.get("tools").is_none());
+        assert_eq!(request["response_format"]["type"], "json_object");
+
+        // A per-request model replaces the configured one, including detection
+        let connector = OpenAiConnector::new(OpenAiConfig::new("test-key")).unwrap();
+        let context = ExtractionContext { model: Some("o1-mini".to_string()), ..context };
+        let request = serde_json::to_value(connector.build_extraction_request(&context)).unwrap();
+        assert_eq!(request["model"], "o1-mini");
+        assert!(request.get("tools").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reasoning_model_shaping() {
+        let context = ExtractionContext {
+            messages: vec![LlmMessage::new("user", "Alice works at Acme Corp")],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: Some(500),
+            temperature: Some(0.2),
+            examples: None,
+            model: Some("o3-mini".to_string()),
+            provider: None,
+            source: None,
+            seed: Some(42),
+            graph_context: None,
+        };
+
+        let connector = OpenAiConnector::new(OpenAiConfig::new("test-key")).unwrap();
+        let (request, warnings) = collect_warnings(async {
+            serde_json::to_value(connector.build_extraction_request(&context)).unwrap()
+        }).await;
+        assert_eq!(request["max_completion_tokens"], 500);
+        assert!(request.get("max_tokens").is_none());
+        assert!(request.get("temperature").is_none());
+        assert_eq!(request["seed"], 42);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("temperature was not sent"));
+
+        let context = ExtractionContext { model: Some("gpt-4o".to_string()), ..context };
+        let request = serde_json::to_value(connector.build_extraction_request(&context)).unwrap();
+        assert_eq!(request["max_tokens"], 500);
+        assert!(request.get("max_completion_tokens").is_none());
+
+        // The config overrides detection either way
+        let connector = OpenAiConnector::new(OpenAiConfig::new("test-key").with_reasoning_model(true)).unwrap();
+        let request = serde_json::to_value(connector.build_extraction_request(&context)).unwrap();
+        assert_eq!(request["max_completion_tokens"], 500);
+    }
+
+    #[tokio::test]
+    async fn test_multi_turn_completion() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/v1/chat/completions")).and(header("Authorization", "Bearer test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4o",
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Acme Corp."}, "finish_reason": "stop"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let request = CompletionRequest {
+            prompt: String::new(),
+            messages: vec![
+                LlmMessage::new("user", "Alice joined Acme Corp in 2023."),
+                LlmMessage::new("assistant", "Noted."),
+                LlmMessage::new("user", "Where does she work?"),
+            ],
+            system: Some("Answer briefly.".to_string()),
+            max_tokens: None,
+            temperature: None,
+            params: json!({}),
+            seed: None,
+        };
+
+        let connector = OpenAiConnector::new(OpenAiConfig::new("test-key").with_sandbox(server.uri())).unwrap();
+        let response = connector.complete(&TenantId::new("acme"), request).await.unwrap();
+        assert_eq!(response.text, "Acme Corp.");
+
+        let sent: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        let roles: Vec<_> = sent["messages"].as_array().unwrap().iter().map(|m| m["role"].as_str().unwrap()).collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+        assert_eq!(sent["messages"][3]["content"], "Where does she work?");
+    }
+
+    #[tokio::test]
+    async fn test_passthrough_params() {
+        let (params, warnings) = collect_warnings(async {
+            passthrough_params(json!({"top_p": 0.5, "logit_bias": {"50256": -100}, "model": "gpt-4"}))
+        }).await;
+        assert_eq!(params.len(), 2);
+        assert_eq!(params["top_p"], 0.5);
+        assert!(!params.contains_key("model"));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("param 'model' was ignored"));
+
+        assert!(passthrough_params(serde_json::Value::Null).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_multimodal_messages() {
+        let messages = [
+            LlmMessage::new("user", "What is in the picture?")
+                .with_part(ContentPart::Image { source: ImageSource::Base64 { media_type: "image/png".to_string(), data: "iVBORw0K".to_string() } }),
+            LlmMessage::new("tool", "").with_part(ContentPart::ToolResult {
+                tool_call_id: "call_1".to_string(),
+                name: Some("lookup".to_string()),
+                content: "Alice works at Acme Corp".to_string(),
+                is_error: false,
+            }),
+            LlmMessage::new("assistant", "Noted")
+                .with_part(ContentPart::Image { source: ImageSource::Url { url: "https://example.com/a.png".to_string() } }),
+        ];
+
+        let (converted, warnings) = collect_warnings(async {
+            messages.iter().flat_map(convert_message).map(|m| serde_json::to_value(m).unwrap()).collect::<Vec<_>>()
+        }).await;
+        assert_eq!(converted[0]["content"][1], json!({"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0K"}}));
+        assert_eq!(converted[1], json!({"role": "tool", "content": "Alice works at Acme Corp", "tool_call_id": "call_1"}));
+        assert_eq!(converted[2], json!({"role": "assistant", "content": "Noted"}));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("only user messages take images"));
+    }
+
+    #[tokio::test]
+    async fn test_extraction_content_from_tool_call() {
+        let connector = OpenAiConnector::new(OpenAiConfig::new("test-key")).unwrap();
+        let response: ChatCompletionResponse = serde_json::from_value(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {
+                            "name": "record_extraction",
+                            "arguments": "{\"nodes\":[{\"id_alias\":\"alice\",\"label\":\"Person\",\"props\":{}}],\"relations\":[]}"
+                        }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        })).unwrap();
+
+        let content = connector.extraction_content(&response).unwrap();
+        let envelope = connector.parse_extraction_response(content).unwrap();
+        assert_eq!(envelope.nodes[0].id_alias, "alice");
+    }
+
     #[tokio::test]
     async fn test_validation_duplicate_nodes() {
         let config = OpenAiConfig::new("test-key");
@@ -360,4 +822,13 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Duplicate node id_alias"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_api_error_classification() {
+        use reqwest::StatusCode;
+
+        assert!(matches!(api_error(StatusCode::TOO_MANY_REQUESTS, String::new()), LlmError::RateLimited(_)));
+        assert!(matches!(api_error(StatusCode::SERVICE_UNAVAILABLE, String::new()), LlmError::CapabilityUnavailable(_)));
+        assert!(matches!(api_error(StatusCode::BAD_REQUEST, String::new()), LlmError::ApiError(_)));
+    }
+}
@@ -9,17 +9,61 @@ pub struct ChatCompletionRequest {
     pub messages: Vec<OpenAiMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
+    /// Replaces `max_tokens` for reasoning models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    /// Parameters passed through from the caller as they are
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
+/// Parameters set from their own fields, which passed-through parameters
+/// may not replace
+pub const MODELED_PARAMS: &[&str] = &[
+    "model", "messages", "max_tokens", "max_completion_tokens", "temperature",
+    "seed", "response_format", "tools", "tool_choice",
+];
+
 /// OpenAI message format
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenAiMessage {
     pub role: String,
-    pub content: String,
+    pub content: OpenAiContent,
+    /// Tool call answered by a `tool` message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+/// Message content: plain text, or typed parts when it has images
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OpenAiContent {
+    Text(String),
+    Parts(Vec<OpenAiContentPart>),
+}
+
+/// Typed part of a message's content
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpenAiContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+/// Image of an `image_url` part, by URL or as a `data:` URL
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
 }
 
 /// Response format specification for JSON mode
@@ -28,6 +72,45 @@ pub struct ResponseFormat {
     pub r#type: String, // "json_object" for JSON mode
 }
 
+/// Tool available to the model
+#[derive(Debug, Serialize)]
+pub struct Tool {
+    pub r#type: String, // "function"
+    pub function: FunctionDefinition,
+}
+
+/// Function definition with a JSON Schema for its arguments
+#[derive(Debug, Serialize)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+/// Forces the model to call a specific function
+#[derive(Debug, Serialize)]
+pub struct ToolChoice {
+    pub r#type: String, // "function"
+    pub function: ToolChoiceFunction,
+}
+
+/// Function named in a tool choice
+#[derive(Debug, Serialize)]
+pub struct ToolChoiceFunction {
+    pub name: String,
+}
+
+/// Response of the list-models endpoint
+#[derive(Debug, Deserialize)]
+pub struct ModelList {
+    pub data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModelEntry {
+    pub id: String,
+}
+
 /// OpenAI Chat Completion Response
 #[derive(Debug, Deserialize)]
 pub struct ChatCompletionResponse {
@@ -53,6 +136,22 @@ pub struct Choice {
 pub struct ChoiceMessage {
     pub role: String,
     pub content: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Tool call made by the model
+#[derive(Debug, Deserialize)]
+pub struct ToolCall {
+    pub r#type: String, // "function"
+    pub function: FunctionCall,
+}
+
+/// Function name and JSON-encoded arguments of a tool call
+#[derive(Debug, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
 }
 
 /// Token usage information
@@ -76,4 +175,15 @@ pub struct ErrorDetails {
     pub r#type: String,
     pub param: Option<String>,
     pub code: Option<String>,
-}
\ No newline at end of file
+}
+
+impl OpenAiMessage {
+    /// Create a message with text content
+    pub fn text(role: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: role.into(),
+            content: OpenAiContent::Text(content.into()),
+            tool_call_id: None,
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! End-to-end extraction through `GraphService` against the OpenAI sandbox
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use telamentis_connector_openai::{OpenAiConnector, OpenAiSandbox};
+use telamentis_core::prelude::*;
+
+/// Graph service that only forwards extraction to its LLM connector
+struct ExtractionService {
+    llm: Arc<dyn LlmConnector>,
+}
+
+#[async_trait]
+impl GraphService for ExtractionService {
+    async fn upsert_node(&self, _tenant: &TenantId, _node: Node) -> Result<Uuid, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn upsert_edge(&self, _tenant: &TenantId, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn close_edge(&self, _tenant: &TenantId, _id: Uuid, _valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn supersede_edge(&self, _tenant: &TenantId, _id: Uuid, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn retract_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn upsert_node_with_edges(&self, _tenant: &TenantId, _node: Node, _edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn query(&self, _tenant: &TenantId, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn resolve_aliases(&self, _tenant: &TenantId, _aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn snapshot(&self, _tenant: &TenantId, _valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn materialize_snapshot(&self, _tenant: &TenantId, _name: &str, _valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn list_snapshots(&self, _tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn drop_snapshot(&self, _tenant: &TenantId, _name: &str) -> Result<bool, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn query_snapshot(&self, _tenant: &TenantId, _name: &str, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn summary(&self, _tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn catalog(&self, _tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        Err(unsupported())
+    }
+
+    async fn extract_knowledge(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        self.llm.extract(tenant, context).await
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        Ok(())
+    }
+}
+
+fn unsupported() -> GraphError {
+    GraphError::QueryFailed("Not supported by the extraction service".to_string())
+}
+
+fn extraction_context() -> ExtractionContext {
+    ExtractionContext {
+        messages: vec![LlmMessage {
+            role: "user".to_string(),
+            content: "Alice joined Acme Corp as an engineer in January 2023.".to_string(),
+            parts: Vec::new(),
+        }],
+        system_prompt: None,
+        desired_schema: None,
+        max_tokens: None,
+        temperature: None,
+        examples: None,
+        model: None,
+        provider: None,
+        source: None,
+        seed: None,
+        graph_context: None,
+    }
+}
+
+async fn extract(scenario: SandboxScenario) -> (Result<ExtractionEnvelope, LlmError>, usize) {
+    let sandbox = OpenAiSandbox::start(scenario).await;
+    let service = ExtractionService {
+        llm: Arc::new(sandbox.connector().unwrap()),
+    };
+
+    let result = service.extract_knowledge(&TenantId::new("sandbox"), extraction_context()).await;
+    (result, sandbox.request_count().await)
+}
+
+#[tokio::test]
+async fn test_sandbox_success() {
+    let (result, requests) = extract(SandboxScenario::Success).await;
+    let envelope = result.unwrap();
+
+    assert_eq!(requests, 1);
+    assert_eq!(envelope.nodes.len(), 2);
+    assert_eq!(envelope.relations[0].type_label, "WORKS_FOR");
+    let metadata = envelope.metadata.unwrap();
+    assert_eq!(metadata.provider, "openai");
+    assert_eq!(metadata.input_tokens, Some(412));
+    assert!(metadata.cost_usd.unwrap() > 0.0);
+}
+
+#[tokio::test]
+async fn test_sandbox_rate_limited() {
+    let (result, _) = extract(SandboxScenario::RateLimited).await;
+    assert!(matches!(result, Err(LlmError::RateLimited(_))));
+}
+
+#[tokio::test]
+async fn test_sandbox_malformed_json() {
+    let (result, _) = extract(SandboxScenario::MalformedJson).await;
+    assert!(matches!(result, Err(LlmError::SchemaValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_sandbox_timeout() {
+    let (result, _) = extract(SandboxScenario::Timeout).await;
+    assert!(matches!(result, Err(LlmError::Timeout)));
+}
+
+#[tokio::test]
+async fn test_extraction_through_proxy() {
+    // The sandbox stands in for the proxy; the API host itself does not resolve
+    let sandbox = OpenAiSandbox::start(SandboxScenario::Success).await;
+    let config = sandbox.config()
+        .with_api_base("http://api.openai.invalid/v1")
+        .with_proxy(ProxyConfig::new(sandbox.uri()));
+    let connector = OpenAiConnector::new(config).unwrap();
+
+    let envelope = connector.extract(&TenantId::new("sandbox"), extraction_context()).await.unwrap();
+    assert_eq!(envelope.nodes.len(), 2);
+    assert_eq!(sandbox.request_count().await, 1);
+
+    let bypassed = sandbox.config()
+        .with_api_base("http://api.openai.invalid/v1")
+        .with_proxy(ProxyConfig::new(sandbox.uri()).with_no_proxy(["api.openai.invalid"]));
+    let connector = OpenAiConnector::new(bypassed).unwrap();
+    assert!(connector.extract(&TenantId::new("sandbox"), extraction_context()).await.is_err());
+    assert_eq!(sandbox.request_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_exchange_log() {
+    let sandbox = OpenAiSandbox::start(SandboxScenario::Success).await;
+    let dir = std::env::temp_dir().join(format!("telamentis-openai-exchanges-{}", Uuid::new_v4()));
+    let log = Arc::new(ExchangeLog::new(ExchangeLogConfig {
+        dir,
+        redact_fields: vec!["content".to_string()],
+        ..Default::default()
+    }));
+    let tenant = TenantId::new("sandbox");
+    log.enable(&tenant, TenantExchangeLogging { sample_rate: 1.0, redact_fields: None }).unwrap();
+    let connector = sandbox.connector().unwrap().with_exchange_log(log.clone());
+
+    connector.extract(&tenant, extraction_context()).await.unwrap();
+    connector.extract(&TenantId::new("other"), extraction_context()).await.unwrap();
+
+    let exchanges = log.list(&tenant).await.unwrap();
+    assert_eq!(exchanges.len(), 1);
+    assert_eq!(exchanges[0].provider, "openai");
+    assert_eq!(exchanges[0].request["messages"][1]["content"], "[REDACTED]");
+    assert!(exchanges[0].response.as_ref().unwrap()["choices"].is_array());
+    assert!(log.list(&TenantId::new("other")).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_sandbox_models() {
+    let sandbox = OpenAiSandbox::start(SandboxScenario::RateLimited).await;
+    let connector = sandbox.connector().unwrap();
+
+    let models = connector.list_models().await.unwrap();
+    let ids: Vec<_> = models.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, ["gpt-4o", "gpt-4o-mini", "o3-mini"]);
+    assert!(connector.health().await.is_available());
+
+    let providers = connector.provider_status(None).await;
+    assert_eq!(providers.len(), 1);
+    assert_eq!(providers[0].models, models);
+}
@@ -0,0 +1,295 @@
+//! Access logs of the presentation adapters
+//!
+//! Every adapter records one [`AccessLogEntry`] per request in the same
+//! shape, so per-tenant latency and error rates can be charted from one
+//! stream of JSON lines whichever protocol served the request. Lines go to
+//! the [`ACCESS_LOG_TARGET`] tracing target, or to a file of their own.
+//!
+//! Healthy requests are sampled by request ID; failed and slow ones are kept
+//! regardless, and each line carries the rate it was kept at so dashboards
+//! can weight their counts. Writes and denied requests are also recorded in
+//! the audit trail, unsampled.
+//!
+//! The tenant and principal are often only known deep inside a request, so
+//! adapters run requests inside [`attributed`] and code that resolves them
+//! calls [`attribute_tenant`] or [`attribute_principal`].
+
+use crate::errors::CoreError;
+use crate::types::TenantId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::{LineWriter, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Tracing target access log lines are emitted under
+pub const ACCESS_LOG_TARGET: &str = "telamentis::access";
+
+/// Configuration for the access log of a presentation adapter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessLogConfig {
+    pub enabled: bool,
+    /// Share of successful requests logged
+    pub sample_rate: f64,
+    /// Share of successful requests logged for these tenants instead of `sample_rate`
+    pub tenant_sample_rates: HashMap<TenantId, f64>,
+    /// Log every request that failed, whatever the sample rate
+    pub always_log_errors: bool,
+    /// Log every request that took at least this long, in milliseconds
+    pub slow_request_ms: Option<u64>,
+    /// Append lines to this file instead of emitting them under [`ACCESS_LOG_TARGET`]
+    pub file: Option<PathBuf>,
+    /// Record writes and denied requests in the audit trail
+    pub audit: bool,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_rate: 1.0,
+            tenant_sample_rates: HashMap::new(),
+            always_log_errors: true,
+            slow_request_ms: None,
+            file: None,
+            audit: true,
+        }
+    }
+}
+
+/// One request served by a presentation adapter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub request_id: Uuid,
+    /// Adapter that served the request: `http`, `grpc` or `uds`
+    pub adapter: String,
+    /// HTTP method, or the RPC or request name for gRPC and UDS
+    pub method: String,
+    /// Request path, or the full method path for gRPC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<TenantId>,
+    /// Token or caller the request was authorized as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    /// HTTP status, or its equivalent for gRPC and UDS
+    pub status: u16,
+    pub latency_ms: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_in: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_out: Option<u64>,
+    /// Whether the request changed the graph or server state
+    #[serde(default)]
+    pub write: bool,
+    /// Share of such requests logged; 1 for requests logged regardless
+    pub sample_rate: f64,
+}
+
+impl AccessLogEntry {
+    /// An entry for a request that just completed; tenant, principal and
+    /// byte counts are left unset
+    pub fn new(adapter: &str, method: impl Into<String>, status: u16, latency: std::time::Duration) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            request_id: Uuid::new_v4(),
+            adapter: adapter.to_string(),
+            method: method.into(),
+            path: None,
+            tenant: None,
+            principal: None,
+            status,
+            latency_ms: latency.as_secs_f64() * 1000.0,
+            bytes_in: None,
+            bytes_out: None,
+            write: false,
+            sample_rate: 1.0,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.status >= 400
+    }
+
+    pub fn is_denied(&self) -> bool {
+        matches!(self.status, 401 | 403)
+    }
+}
+
+/// Writes the access log of one adapter
+#[derive(Debug, Default)]
+pub struct AccessLog {
+    config: AccessLogConfig,
+    file: Option<Mutex<LineWriter<File>>>,
+}
+
+impl AccessLog {
+    /// Open the access log, creating its file if it has one
+    pub fn new(config: AccessLogConfig) -> Result<Self, CoreError> {
+        let mut rates = std::iter::once(&config.sample_rate).chain(config.tenant_sample_rates.values());
+        if let Some(rate) = rates.find(|rate| !(0.0..=1.0).contains(*rate)) {
+            return Err(CoreError::Configuration(format!("Access log sample rate {} is not between 0 and 1", rate)));
+        }
+
+        let file = match &config.file {
+            Some(path) if config.enabled => {
+                let file = OpenOptions::new().create(true).append(true).open(path)
+                    .map_err(|e| CoreError::Configuration(format!("Failed to open access log {}: {}", path.display(), e)))?;
+                Some(Mutex::new(LineWriter::new(file)))
+            }
+            _ => None,
+        };
+        Ok(Self { config, file })
+    }
+
+    /// An access log that records nothing
+    pub fn disabled() -> Self {
+        Self { config: AccessLogConfig { enabled: false, audit: false, ..AccessLogConfig::default() }, file: None }
+    }
+
+    /// Share of the tenant's successful requests that are logged
+    pub fn sample_rate(&self, tenant: Option<&TenantId>) -> f64 {
+        tenant.and_then(|tenant| self.config.tenant_sample_rates.get(tenant))
+            .copied()
+            .unwrap_or(self.config.sample_rate)
+    }
+
+    /// The rate a request is logged at, or `None` if it is sampled out.
+    /// Failed and slow requests are logged at a rate of 1.
+    pub fn keep_rate(&self, entry: &AccessLogEntry) -> Option<f64> {
+        if !self.config.enabled {
+            return None;
+        }
+        let slow = self.config.slow_request_ms.is_some_and(|slow_ms| entry.latency_ms >= slow_ms as f64);
+        if slow || (self.config.always_log_errors && entry.is_error()) {
+            return Some(1.0);
+        }
+
+        // Sampling by request ID keeps the decision stable across adapters and retries
+        let rate = self.sample_rate(entry.tenant.as_ref());
+        let sampled = (entry.request_id.as_u128() as u64) as f64 / u64::MAX as f64;
+        (sampled < rate).then_some(rate)
+    }
+
+    /// Record a completed request. Failures to write are logged, never
+    /// returned, so logging cannot fail a request.
+    pub fn record(&self, mut entry: AccessLogEntry) {
+        if self.config.audit && (entry.write || entry.is_denied()) {
+            info!(
+                "Audit: {} {} by tenant {} as {}: {} (request_id: {})",
+                entry.method,
+                entry.path.as_deref().unwrap_or(&entry.adapter),
+                entry.tenant.as_ref().map(TenantId::as_str).unwrap_or("-"),
+                entry.principal.as_deref().unwrap_or("-"),
+                entry.status,
+                entry.request_id
+            );
+        }
+
+        let Some(rate) = self.keep_rate(&entry) else {
+            return;
+        };
+        entry.sample_rate = rate;
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to encode access log entry: {}", e);
+                return;
+            }
+        };
+        match &self.file {
+            Some(file) => {
+                if let Err(e) = writeln!(file.lock().unwrap(), "{}", line) {
+                    warn!("Failed to write access log: {}", e);
+                }
+            }
+            None => info!(target: ACCESS_LOG_TARGET, "{}", line),
+        }
+    }
+}
+
+tokio::task_local! {
+    static ATTRIBUTION: RefCell<Attribution>;
+}
+
+/// Who a request was made by, as learned while serving it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attribution {
+    pub tenant: Option<TenantId>,
+    pub principal: Option<String>,
+}
+
+/// Run `future`, returning its output with the tenant and principal it attributed
+pub async fn attributed<F: Future>(future: F) -> (F::Output, Attribution) {
+    ATTRIBUTION.scope(RefCell::new(Attribution::default()), async {
+        let output = future.await;
+        (output, ATTRIBUTION.with(|attribution| attribution.take()))
+    }).await
+}
+
+/// Attribute the current request to a tenant; does nothing outside of [`attributed`]
+pub fn attribute_tenant(tenant: &TenantId) {
+    let _ = ATTRIBUTION.try_with(|attribution| attribution.borrow_mut().tenant = Some(tenant.clone()));
+}
+
+/// Attribute the current request to a token or caller; does nothing outside of [`attributed`]
+pub fn attribute_principal(principal: impl Into<String>) {
+    let principal = principal.into();
+    let _ = ATTRIBUTION.try_with(|attribution| attribution.borrow_mut().principal = Some(principal));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(tenant: &str, status: u16, latency_ms: u64) -> AccessLogEntry {
+        AccessLogEntry {
+            tenant: Some(TenantId::new(tenant)),
+            ..AccessLogEntry::new("http", "GET", status, Duration::from_millis(latency_ms))
+        }
+    }
+
+    #[test]
+    fn test_keep_rate() {
+        let config = AccessLogConfig {
+            sample_rate: 0.0,
+            tenant_sample_rates: HashMap::from([(TenantId::new("acme"), 1.0)]),
+            slow_request_ms: Some(500),
+            ..AccessLogConfig::default()
+        };
+        let log = AccessLog::new(config).unwrap();
+
+        assert_eq!(log.keep_rate(&entry("acme", 200, 10)), Some(1.0));
+        assert_eq!(log.keep_rate(&entry("globex", 200, 10)), None);
+        // Failed and slow requests are kept whatever the rate
+        assert_eq!(log.keep_rate(&entry("globex", 503, 10)), Some(1.0));
+        assert_eq!(log.keep_rate(&entry("globex", 200, 800)), Some(1.0));
+        assert_eq!(AccessLog::disabled().keep_rate(&entry("acme", 500, 10)), None);
+
+        let invalid = AccessLogConfig { sample_rate: 1.5, ..AccessLogConfig::default() };
+        assert!(AccessLog::new(invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_attributed() {
+        let ((), attribution) = attributed(async {
+            attribute_tenant(&TenantId::new("acme"));
+            attribute_principal("token-1");
+        }).await;
+        assert_eq!(attribution.tenant, Some(TenantId::new("acme")));
+        assert_eq!(attribution.principal.as_deref(), Some("token-1"));
+
+        // Outside of a request nothing is attributed
+        attribute_tenant(&TenantId::new("ignored"));
+    }
+}
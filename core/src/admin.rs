@@ -0,0 +1,415 @@
+//! Operator controls of a running server
+//!
+//! [`AdminControls`] backs the admin API of the presentation adapters, which
+//! is separate from the tenant API: its requests carry a deployment-wide
+//! admin secret instead of a tenant's token. Through it operators switch a
+//! tenant's default LLM provider, flush query caches, run maintenance jobs
+//! such as retention, and drain the server before shutting it down.
+//! Pipeline plugins are enabled and disabled on the adapter's
+//! `PipelineRunner`. The status includes the lanes of the LLM request
+//! queue and the store queries queued by tenant, when a queue or query
+//! scheduler is attached.
+//!
+//! While the server drains, new requests are refused and the drain waits for
+//! those in flight to finish. Admin requests are not counted, so the drain
+//! can be watched and undone.
+
+use crate::archive::ArchiveJob;
+use crate::auth::hash_secret;
+use crate::connector_registry::ConnectorRegistry;
+use crate::embeddings::{EmbeddingOrchestrator, EmbeddingStats};
+use crate::errors::{AuthError, CoreError, LlmError};
+use crate::events::{MutationEventBus, MutationKind};
+use crate::llm_queue::{LaneStats, LlmRequestQueue};
+use crate::query_scheduler::{QueryScheduler, TenantQueryStats};
+use crate::traits::MaintenanceJob;
+use crate::types::TenantId;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+/// Outcome of a maintenance job run over one tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobReport {
+    pub job: String,
+    pub tenant: TenantId,
+    /// Records the job changed
+    pub affected: u64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Progress of a maintenance job over one tenant, as reported by jobs that
+/// run long enough to be watched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub tenant: TenantId,
+    /// Records visited so far
+    pub processed: u64,
+    /// Records to visit in all, if known
+    pub total: Option<u64>,
+    /// Records the job changed
+    pub changed: u64,
+    /// Records the job could not process
+    pub failed: u64,
+    /// Error of the last record or run that failed
+    pub last_error: Option<String>,
+    /// Whether the job got through every record; a run that stopped early
+    /// resumes where it left off
+    pub finished: bool,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Outcome of draining the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrainReport {
+    /// Whether every request in flight finished before the timeout
+    pub drained: bool,
+    /// Requests still in flight
+    pub in_flight: usize,
+}
+
+/// What the admin API reports about the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdminStatus {
+    pub draining: bool,
+    pub in_flight: usize,
+    /// Names of the maintenance jobs that can be run
+    pub jobs: Vec<String>,
+    /// Registered LLM providers
+    pub providers: Vec<String>,
+    /// LLM request queue lanes by provider
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub llm_queues: BTreeMap<String, LaneStats>,
+    /// Store queries running and waiting by tenant
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub query_queues: BTreeMap<String, TenantQueryStats>,
+    /// Requests, cache hits and throughput of the embedding orchestrator
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embeddings: Option<EmbeddingStats>,
+}
+
+/// Count of requests in flight, refusing new ones while draining
+#[derive(Debug, Default)]
+pub struct DrainState {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// A request in flight, counted until dropped
+pub struct InFlight {
+    state: Arc<DrainState>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.state.leave();
+    }
+}
+
+impl DrainState {
+    /// Count a new request; `None` if the server is draining
+    pub fn enter(self: &Arc<Self>) -> Option<InFlight> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        // Checked after counting, so a drain either sees the request or refuses it
+        if self.is_draining() {
+            self.leave();
+            return None;
+        }
+        Some(InFlight { state: self.clone() })
+    }
+
+    fn leave(&self) {
+        if self.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Refuse new requests and wait up to `timeout` for those in flight
+    pub async fn drain(&self, timeout: Duration) -> DrainReport {
+        if !self.draining.swap(true, Ordering::SeqCst) {
+            info!("Draining: refusing new requests, {} in flight", self.in_flight());
+        }
+
+        let drained = tokio::time::timeout(timeout, async {
+            loop {
+                let idle = self.idle.notified();
+                if self.in_flight() == 0 {
+                    break;
+                }
+                idle.await;
+            }
+        }).await.is_ok();
+
+        DrainReport { drained, in_flight: self.in_flight() }
+    }
+
+    /// Accept requests again
+    pub fn resume(&self) {
+        if self.draining.swap(false, Ordering::SeqCst) {
+            info!("Drain ended: accepting requests again");
+        }
+    }
+}
+
+/// Operator controls shared by the admin APIs of the presentation adapters
+pub struct AdminControls {
+    /// SHA-256 of the admin secret
+    secret_hash: String,
+    connectors: Option<Arc<ConnectorRegistry>>,
+    cache_events: Option<MutationEventBus>,
+    llm_queue: Option<Arc<LlmRequestQueue>>,
+    query_scheduler: Option<Arc<QueryScheduler>>,
+    embeddings: Option<Arc<EmbeddingOrchestrator>>,
+    jobs: BTreeMap<String, Arc<dyn MaintenanceJob>>,
+    drain: Arc<DrainState>,
+}
+
+impl AdminControls {
+    /// Controls that admin requests reach with `secret` as bearer token
+    pub fn new(secret: &str) -> Self {
+        Self {
+            secret_hash: hash_secret(secret),
+            connectors: None,
+            cache_events: None,
+            llm_queue: None,
+            query_scheduler: None,
+            embeddings: None,
+            jobs: BTreeMap::new(),
+            drain: Arc::new(DrainState::default()),
+        }
+    }
+
+    /// Switch tenants' default providers in this registry
+    pub fn with_connectors(mut self, connectors: Arc<ConnectorRegistry>) -> Self {
+        self.connectors = Some(connectors);
+        self
+    }
+
+    /// Flush the query caches listening on this bus, see `CacheLayer`
+    pub fn with_cache_events(mut self, events: MutationEventBus) -> Self {
+        self.cache_events = Some(events);
+        self
+    }
+
+    /// Report the lanes of this LLM request queue in the status
+    pub fn with_llm_queue(mut self, queue: Arc<LlmRequestQueue>) -> Self {
+        self.llm_queue = Some(queue);
+        self
+    }
+
+    /// Report the queries of this scheduler in the status
+    pub fn with_query_scheduler(mut self, scheduler: Arc<QueryScheduler>) -> Self {
+        self.query_scheduler = Some(scheduler);
+        self
+    }
+
+    /// Report the counters of this embedding orchestrator in the status
+    pub fn with_embeddings(mut self, embeddings: Arc<EmbeddingOrchestrator>) -> Self {
+        self.embeddings = Some(embeddings);
+        self
+    }
+
+    /// Let operators run a maintenance job under a name
+    pub fn with_job(mut self, name: impl Into<String>, job: Arc<dyn MaintenanceJob>) -> Self {
+        self.jobs.insert(name.into(), job);
+        self
+    }
+
+    /// Check the secret of an admin request
+    pub fn authorize(&self, secret: &str) -> Result<(), AuthError> {
+        if secret.is_empty() || hash_secret(secret) != self.secret_hash {
+            return Err(AuthError::InvalidToken);
+        }
+        Ok(())
+    }
+
+    /// Requests in flight and whether the server is draining
+    pub fn drain_state(&self) -> Arc<DrainState> {
+        self.drain.clone()
+    }
+
+    pub fn status(&self) -> AdminStatus {
+        AdminStatus {
+            draining: self.drain.is_draining(),
+            in_flight: self.drain.in_flight(),
+            jobs: self.jobs.keys().cloned().collect(),
+            providers: self.connectors.as_ref()
+                .map(|connectors| connectors.providers().into_iter().map(str::to_string).collect())
+                .unwrap_or_default(),
+            llm_queues: self.llm_queue.as_ref().map(|queue| queue.stats()).unwrap_or_default(),
+            query_queues: self.query_scheduler.as_ref().map(|scheduler| scheduler.stats()).unwrap_or_default(),
+            embeddings: self.embeddings.as_ref().map(|embeddings| embeddings.stats()),
+        }
+    }
+
+    /// Make `provider` the tenant's default LLM provider, or with `None`
+    /// return it to the registry's default
+    pub fn set_default_provider(&self, tenant: &TenantId, provider: Option<&str>) -> Result<(), CoreError> {
+        let connectors = self.connectors.as_ref()
+            .ok_or_else(|| LlmError::CapabilityUnavailable("No connector registry is configured".to_string()))?;
+        connectors.set_tenant_provider(tenant, provider)?;
+        info!("Default LLM provider of tenant {} switched to {}", tenant, provider.unwrap_or("the registry default"));
+        Ok(())
+    }
+
+    /// Drop the cached query results of tenants; `false` if no cache is configured
+    pub fn flush_caches(&self, tenants: &[TenantId]) -> bool {
+        let Some(events) = &self.cache_events else {
+            return false;
+        };
+        for tenant in tenants {
+            events.publish(tenant, MutationKind::CacheFlush);
+        }
+        info!("Flushed query caches of {} tenant(s)", tenants.len());
+        true
+    }
+
+    /// Maintenance job registered under a name
+    pub fn job(&self, name: &str) -> Option<Arc<dyn MaintenanceJob>> {
+        self.jobs.get(name).cloned()
+    }
+
+    /// Progress of a maintenance job over the tenants it ran for; empty for
+    /// jobs that do not report progress
+    pub fn job_progress(&self, name: &str) -> Result<Vec<JobProgress>, CoreError> {
+        self.job(name)
+            .map(|job| job.progress())
+            .ok_or_else(|| CoreError::Configuration(format!("Unknown maintenance job '{}'", name)))
+    }
+
+    /// Run a maintenance job over each tenant in turn, stopping at the first failure
+    pub async fn run_job(&self, name: &str, tenants: &[TenantId]) -> Result<Vec<JobReport>, CoreError> {
+        let job = self.job(name)
+            .ok_or_else(|| CoreError::Configuration(format!("Unknown maintenance job '{}'", name)))?;
+
+        let mut reports = Vec::new();
+        for tenant in tenants {
+            let started_at = Utc::now();
+            let affected = job.run(tenant).await.inspect_err(|e| {
+                warn!("Maintenance job {} failed for tenant {}: {}", name, tenant, e);
+            })?;
+            info!("Maintenance job {} changed {} record(s) of tenant {}", name, affected, tenant);
+            reports.push(JobReport {
+                job: name.to_string(),
+                tenant: tenant.clone(),
+                affected,
+                started_at,
+                finished_at: Utc::now(),
+            });
+        }
+        Ok(reports)
+    }
+}
+
+/// Maintenance job archiving and purging history that ended longer ago than
+/// a retention period, see [`ArchiveJob::retain`]
+pub struct RetentionJob {
+    archive: Arc<ArchiveJob>,
+    keep: ChronoDuration,
+}
+
+impl RetentionJob {
+    /// Keep the history of the last `keep` in the store
+    pub fn new(archive: Arc<ArchiveJob>, keep: ChronoDuration) -> Self {
+        Self { archive, keep }
+    }
+}
+
+#[async_trait]
+impl MaintenanceJob for RetentionJob {
+    async fn run(&self, tenant: &TenantId) -> Result<u64, CoreError> {
+        let segment = self.archive.retain(tenant, Utc::now() - self.keep).await?;
+        Ok(segment.map_or(0, |segment| segment.node_count + segment.edge_count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::GraphError;
+    use crate::events::MutationEvent;
+    use std::sync::atomic::AtomicU64;
+
+    /// Job counting the tenants it ran for, failing for one
+    #[derive(Default)]
+    struct CountingJob {
+        runs: AtomicU64,
+    }
+
+    #[async_trait]
+    impl MaintenanceJob for CountingJob {
+        async fn run(&self, tenant: &TenantId) -> Result<u64, CoreError> {
+            if tenant.as_str() == "broken" {
+                return Err(GraphError::DatabaseError("unreachable".to_string()).into());
+            }
+            Ok(self.runs.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admin_controls() {
+        let events = MutationEventBus::default();
+        let mut flushed = events.subscribe();
+        let job = Arc::new(CountingJob::default());
+        let admin = AdminControls::new("s3cret").with_cache_events(events).with_job("dedup", job.clone());
+
+        assert!(admin.authorize("s3cret").is_ok());
+        assert!(matches!(admin.authorize("guess"), Err(AuthError::InvalidToken)));
+        assert!(admin.authorize("").is_err());
+
+        let (acme, globex) = (TenantId::new("acme"), TenantId::new("globex"));
+        let only_acme = [acme.clone()];
+        assert!(admin.flush_caches(&only_acme));
+        assert_eq!(flushed.recv().await.unwrap(), MutationEvent { tenant: acme.clone(), kind: MutationKind::CacheFlush });
+        assert!(!AdminControls::new("s3cret").flush_caches(&only_acme));
+
+        let reports = admin.run_job("dedup", &[acme.clone(), globex]).await.unwrap();
+        assert_eq!(reports.iter().map(|report| report.affected).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(admin.run_job("dedup", &[TenantId::new("broken"), acme.clone()]).await.is_err());
+        assert!(matches!(admin.run_job("missing", &only_acme).await, Err(CoreError::Configuration(_))));
+        assert_eq!(admin.status().jobs, vec!["dedup"]);
+
+        assert!(matches!(admin.set_default_provider(&acme, Some("openai")), Err(CoreError::Llm(LlmError::CapabilityUnavailable(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_drain() {
+        let drain = Arc::new(DrainState::default());
+        let request = drain.enter().unwrap();
+
+        // Times out while a request is in flight, and refuses new ones
+        let report = drain.drain(Duration::from_millis(10)).await;
+        assert_eq!(report, DrainReport { drained: false, in_flight: 1 });
+        assert!(drain.enter().is_none());
+        assert_eq!(drain.in_flight(), 1);
+
+        let waiting = tokio::spawn({
+            let drain = drain.clone();
+            async move { drain.drain(Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(request);
+        assert_eq!(waiting.await.unwrap(), DrainReport { drained: true, in_flight: 0 });
+
+        drain.resume();
+        assert!(!drain.is_draining());
+        assert!(drain.enter().is_some());
+    }
+}
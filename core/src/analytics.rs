@@ -0,0 +1,197 @@
+//! Read-only SQL analytics over copies of tenants' graphs
+//!
+//! Aggregate questions ("edges per kind per month") are awkward as graph
+//! queries, so [`AnalyticsJob`] copies a tenant's snapshot into an
+//! [`AnalyticsEngine`] such as DuckDB and runs SQL over it there. Writes
+//! always go to the `GraphStore`; the copy is loaded when a tenant is first
+//! queried and reloaded once it is older than `max_staleness_ms`, or on
+//! request with [`AnalyticsJob::sync`].
+//!
+//! Engines expose two tables per tenant:
+//!
+//! ```text
+//! nodes(id, label, id_alias, alias_namespace, props)
+//! edges(id, from_node_id, to_node_id, kind, valid_from, valid_to,
+//!       transaction_start_time, transaction_end_time, props)
+//! ```
+
+use crate::errors::{AnalyticsError, CoreError};
+use crate::traits::{AnalyticsEngine, GraphStore};
+use crate::types::TenantId;
+use crate::views::current_view;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+/// Statements an analytics query may start with
+const READ_ONLY_STATEMENTS: &[&str] = &["select", "with", "from", "values", "summarize", "describe", "show"];
+
+/// Configuration for `AnalyticsJob`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalyticsConfig {
+    /// Age after which a tenant's tables are reloaded before a query, in milliseconds
+    pub max_staleness_ms: u64,
+    /// Most rows a query returns
+    pub max_rows: usize,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            max_staleness_ms: 60_000,
+            max_rows: 10_000,
+        }
+    }
+}
+
+/// Rows returned by an analytics query
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnalyticsResult {
+    /// Column names, in order
+    pub columns: Vec<String>,
+    /// Row values, in column order
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Whether rows beyond the row limit were left out
+    pub truncated: bool,
+    /// Transaction time of the snapshot the query ran against
+    pub snapshot_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of loading a tenant's snapshot into the engine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyticsSync {
+    /// Transaction time of the loaded snapshot
+    pub snapshot_at: DateTime<Utc>,
+    /// Nodes loaded
+    pub node_count: usize,
+    /// Edges loaded
+    pub edge_count: usize,
+}
+
+/// Keeps an analytics engine loaded from a store and queries it
+pub struct AnalyticsJob {
+    store: Arc<dyn GraphStore>,
+    engine: Arc<dyn AnalyticsEngine>,
+    config: AnalyticsConfig,
+    /// Serializes loads so concurrent stale queries reload once
+    loading: Mutex<()>,
+}
+
+impl AnalyticsJob {
+    /// Create a job copying `store`'s graphs into `engine`
+    pub fn new(store: Arc<dyn GraphStore>, engine: Arc<dyn AnalyticsEngine>, config: AnalyticsConfig) -> Self {
+        Self {
+            store,
+            engine,
+            config,
+            loading: Mutex::new(()),
+        }
+    }
+
+    /// Load the tenant's current graph into the engine
+    pub async fn sync(&self, tenant: &TenantId) -> Result<AnalyticsSync, CoreError> {
+        refuse_in_view()?;
+        let _loading = self.loading.lock().await;
+        self.load(tenant).await
+    }
+
+    async fn load(&self, tenant: &TenantId) -> Result<AnalyticsSync, CoreError> {
+        let snapshot = self.store.snapshot(tenant, None).await?;
+        self.engine.load(tenant, &snapshot).await?;
+
+        info!(
+            "Loaded {} nodes and {} edges of tenant {} for analytics",
+            snapshot.nodes.len(), snapshot.edges.len(), tenant
+        );
+        Ok(AnalyticsSync {
+            snapshot_at: snapshot.snapshot_at,
+            node_count: snapshot.nodes.len(),
+            edge_count: snapshot.edges.len(),
+        })
+    }
+
+    /// Whether tables loaded at `loaded_at` should be reloaded before a query
+    fn is_stale(&self, loaded_at: Option<DateTime<Utc>>) -> bool {
+        match loaded_at {
+            Some(loaded_at) => (Utc::now() - loaded_at).num_milliseconds() > self.config.max_staleness_ms as i64,
+            None => true,
+        }
+    }
+
+    /// Run a read-only SQL query over the tenant's tables, loading them first
+    /// if they are missing or stale. `limit` is capped at `max_rows`.
+    pub async fn query(&self, tenant: &TenantId, sql: &str, limit: Option<usize>) -> Result<AnalyticsResult, CoreError> {
+        refuse_in_view()?;
+        ensure_read_only(sql)?;
+
+        if self.is_stale(self.engine.loaded_at(tenant).await?) {
+            let _loading = self.loading.lock().await;
+            // Another query may have reloaded while this one waited
+            if self.is_stale(self.engine.loaded_at(tenant).await?) {
+                debug!("Analytics tables of tenant {} are stale, reloading", tenant);
+                self.load(tenant).await?;
+            }
+        }
+
+        let max_rows = limit.unwrap_or(self.config.max_rows).min(self.config.max_rows);
+        Ok(self.engine.query(tenant, sql, max_rows).await?)
+    }
+
+    /// Remove the tenant's tables from the engine
+    pub async fn drop_tenant(&self, tenant: &TenantId) -> Result<bool, CoreError> {
+        Ok(self.engine.drop_tenant(tenant).await?)
+    }
+}
+
+/// Refuse analytics to tasks confined to a view: the tables hold the whole
+/// tenant graph, which SQL could read past the view
+fn refuse_in_view() -> Result<(), CoreError> {
+    match current_view() {
+        Some(view) => Err(view.refuse("Analytics queries").into()),
+        None => Ok(()),
+    }
+}
+
+/// Reject anything but a single read-only statement. Engines also run
+/// queries in a transaction that is rolled back, so this is a first line of
+/// defence rather than the only one.
+pub fn ensure_read_only(sql: &str) -> Result<(), AnalyticsError> {
+    let statement = sql.trim().trim_end_matches(';').trim_end();
+    if statement.is_empty() {
+        return Err(AnalyticsError::InvalidQuery("Query is empty".to_string()));
+    }
+    if statement.contains(';') {
+        return Err(AnalyticsError::InvalidQuery("Only a single statement may be run".to_string()));
+    }
+
+    let keyword = statement
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if !READ_ONLY_STATEMENTS.contains(&keyword.as_str()) {
+        return Err(AnalyticsError::InvalidQuery(format!(
+            "Only read-only queries are allowed, not '{}'", keyword
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_read_only() {
+        assert!(ensure_read_only("SELECT kind, count(*) FROM edges GROUP BY kind;").is_ok());
+        assert!(ensure_read_only("  with t AS (SELECT 1) SELECT * FROM t").is_ok());
+        assert!(ensure_read_only("SUMMARIZE nodes").is_ok());
+
+        for sql in ["", ";", "DELETE FROM nodes", "SELECT 1; DROP TABLE edges", "ATTACH 'other.db'", "COPY nodes TO 'x.csv'"] {
+            assert!(matches!(ensure_read_only(sql), Err(AnalyticsError::InvalidQuery(_))), "{}", sql);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Archival of closed graph history to cold storage
+//!
+//! Stores keep closed edge versions, and the nodes and edges that were
+//! deleted, so "as-at" queries can see what was believed in the past.
+//! [`ArchiveJob`] copies or moves that history into an [`ArchiveSink`] such
+//! as object storage, and rehydrates a time range of it back into the store.
+//! Each run writes one [`ArchiveSegment`], and a tenant's segments are listed
+//! in its [`ArchiveManifest`].
+
+use crate::errors::CoreError;
+use crate::traits::{ArchiveSink, GraphStore};
+use crate::types::{EdgeRecord, Node, TenantId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// A node as it was when it was deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedNode {
+    /// System ID of the node
+    pub id: Uuid,
+    /// The node itself
+    pub node: Node,
+    /// When the node was deleted
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Closed history of a tenant's graph
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArchiveBatch {
+    /// Deleted nodes
+    pub nodes: Vec<ArchivedNode>,
+    /// Closed and deleted edge versions; `transaction_end_time` is when each ended
+    pub edges: Vec<EdgeRecord>,
+}
+
+impl ArchiveBatch {
+    /// Whether the batch holds no records
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty()
+    }
+
+    /// Number of records in the batch
+    pub fn len(&self) -> usize {
+        self.nodes.len() + self.edges.len()
+    }
+
+    /// Earliest and latest time a record in the batch ended
+    pub fn time_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        self.nodes.iter().map(|n| n.deleted_at)
+            .chain(self.edges.iter().map(edge_ended_at))
+            .fold(None, |range, t| match range {
+                None => Some((t, t)),
+                Some((from, to)) => Some((from.min(t), to.max(t))),
+            })
+    }
+
+    /// Only the records that ended within `[from, to)`
+    pub fn within(self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        let in_range = |t: DateTime<Utc>| from <= t && t < to;
+        Self {
+            nodes: self.nodes.into_iter().filter(|n| in_range(n.deleted_at)).collect(),
+            edges: self.edges.into_iter().filter(|e| in_range(edge_ended_at(e))).collect(),
+        }
+    }
+}
+
+/// When an archived edge version ended
+fn edge_ended_at(record: &EdgeRecord) -> DateTime<Utc> {
+    record.edge.transaction_end_time.unwrap_or(record.edge.transaction_start_time)
+}
+
+/// One archive run's worth of history, as recorded in the manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveSegment {
+    /// Segment ID
+    pub id: Uuid,
+    /// Earliest time a record in the segment ended
+    pub from: DateTime<Utc>,
+    /// Latest time a record in the segment ended
+    pub to: DateTime<Utc>,
+    /// Number of deleted nodes in the segment
+    pub node_count: u64,
+    /// Number of edge versions in the segment
+    pub edge_count: u64,
+    /// Objects holding the segment's records, relative to the tenant's archive
+    pub objects: Vec<String>,
+    /// When the segment was written
+    pub created_at: DateTime<Utc>,
+}
+
+impl ArchiveSegment {
+    /// Whether the segment may hold records that ended within `[from, to)`
+    pub fn overlaps(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> bool {
+        self.from < to && from <= self.to
+    }
+}
+
+/// Catalog of a tenant's archive segments
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Segments in the order they were written
+    pub segments: Vec<ArchiveSegment>,
+}
+
+impl ArchiveManifest {
+    /// Segments that may hold records that ended within `[from, to)`
+    pub fn overlapping(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> impl Iterator<Item = &ArchiveSegment> {
+        self.segments.iter().filter(move |s| s.overlaps(from, to))
+    }
+}
+
+/// Outcome of rehydrating archived history
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RestoreReport {
+    /// Segments read from the archive
+    pub segments: usize,
+    /// Records in the requested time range
+    pub records: u64,
+    /// Records written back to the store; the rest were already there
+    pub restored: u64,
+}
+
+/// Moves closed history between a store and an archive
+pub struct ArchiveJob {
+    store: Arc<dyn GraphStore>,
+    sink: Arc<dyn ArchiveSink>,
+}
+
+impl ArchiveJob {
+    /// Create a job archiving `store`'s history into `sink`
+    pub fn new(store: Arc<dyn GraphStore>, sink: Arc<dyn ArchiveSink>) -> Self {
+        Self { store, sink }
+    }
+
+    /// Copy history that ended before `before` into a new segment, leaving
+    /// the store unchanged. Returns `None` if there was nothing to copy.
+    pub async fn export(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<Option<ArchiveSegment>, CoreError> {
+        let batch = self.store.read_history(tenant, before).await?;
+        if batch.is_empty() {
+            debug!("No history before {} to archive for tenant {}", before, tenant);
+            return Ok(None);
+        }
+
+        let segment = self.sink.write_segment(tenant, &batch).await?;
+        info!("Archived {} history records of tenant {} in segment {}", batch.len(), tenant, segment.id);
+        Ok(Some(segment))
+    }
+
+    /// Move history that ended before `before` into a new segment, removing
+    /// it from the store once the segment is written
+    pub async fn retain(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<Option<ArchiveSegment>, CoreError> {
+        // History ending from now on is not in this read, so must not be purged
+        let before = before.min(Utc::now());
+
+        let segment = self.export(tenant, before).await?;
+        if segment.is_some() {
+            let purged = self.store.purge_history(tenant, before).await?;
+            info!("Purged {} archived history records of tenant {}", purged, tenant);
+        }
+        Ok(segment)
+    }
+
+    /// The catalog of a tenant's archive segments
+    pub async fn manifest(&self, tenant: &TenantId) -> Result<ArchiveManifest, CoreError> {
+        Ok(self.sink.manifest(tenant).await?)
+    }
+
+    /// Write archived history that ended within `[from, to)` back into the store
+    pub async fn restore(&self, tenant: &TenantId, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<RestoreReport, CoreError> {
+        let manifest = self.sink.manifest(tenant).await?;
+        let mut report = RestoreReport::default();
+
+        for segment in manifest.overlapping(from, to) {
+            let batch = self.sink.read_segment(tenant, segment).await?.within(from, to);
+            report.segments += 1;
+            report.records += batch.len() as u64;
+            if !batch.is_empty() {
+                report.restored += self.store.restore_history(tenant, batch).await?;
+            }
+        }
+
+        info!(
+            "Restored {} of {} archived records of tenant {} from {} segments",
+            report.restored, report.records, tenant, report.segments
+        );
+        Ok(report)
+    }
+}
@@ -0,0 +1,353 @@
+//! Tenant-scoped API tokens
+//!
+//! A token grants one scope on one tenant. Its secret is returned once, when
+//! the token is created or rotated; the [`SecretStore`] keeps only a SHA-256
+//! hash of it next to the token's record. Rotation issues a successor with
+//! the same name and scope and lets the old secret keep working for an
+//! overlap period, so clients can switch without downtime.
+
+use crate::errors::AuthError;
+use crate::traits::SecretStore;
+use crate::types::TenantId;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::info;
+use uuid::Uuid;
+
+/// Prefix of every token secret, so leaked secrets are easy to recognize
+pub const SECRET_PREFIX: &str = "tm_";
+
+/// Characters of a secret kept in its record to tell tokens apart
+const DISPLAY_PREFIX_LEN: usize = SECRET_PREFIX.len() + 8;
+
+/// What a token may do within its tenant. Each scope includes the ones
+/// before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// Read the tenant's graph
+    Read,
+    /// Read and write the tenant's graph
+    Write,
+    /// Everything, including managing the tenant and its tokens
+    Admin,
+}
+
+impl TokenScope {
+    /// Whether this scope includes `required`
+    pub fn allows(self, required: TokenScope) -> bool {
+        self >= required
+    }
+}
+
+impl std::fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Admin => "admin",
+        })
+    }
+}
+
+impl std::str::FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            "admin" => Ok(Self::Admin),
+            _ => Err(format!("Unknown token scope '{}'", s)),
+        }
+    }
+}
+
+/// An API token, without its secret
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub tenant: TenantId,
+    pub name: String,
+    pub scope: TokenScope,
+    /// Start of the secret, to tell tokens apart
+    pub prefix: String,
+    pub created_at: DateTime<Utc>,
+    /// When the token stops working; `None` if it does not expire
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Token issued when this one was rotated
+    pub replaced_by: Option<Uuid>,
+}
+
+impl ApiToken {
+    /// Whether the token is accepted at `time`
+    pub fn is_active_at(&self, time: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| expires_at > time)
+    }
+
+    /// Why the token is not accepted at `time`, if it is not
+    fn check_active_at(&self, time: DateTime<Utc>) -> Result<(), AuthError> {
+        if self.revoked_at.is_some() {
+            Err(AuthError::TokenRevoked)
+        } else if self.expires_at.is_some_and(|expires_at| expires_at <= time) {
+            Err(AuthError::TokenExpired)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A token record as held by a [`SecretStore`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub token: ApiToken,
+    /// Hex-encoded SHA-256 of the secret
+    pub secret_hash: String,
+    /// Lifetime given at creation, reused for successors on rotation
+    pub ttl_secs: Option<i64>,
+}
+
+/// A newly created or rotated token with its secret. The secret cannot be
+/// read back later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+/// Creates, rotates, revokes and checks tenants' API tokens
+pub struct ApiTokenManager {
+    store: Arc<dyn SecretStore>,
+}
+
+impl ApiTokenManager {
+    /// Create a manager over the given secret store
+    pub fn new(store: Arc<dyn SecretStore>) -> Self {
+        Self { store }
+    }
+
+    /// Issue a token for the tenant, expiring after `ttl` if one is given
+    pub async fn create(&self, tenant: &TenantId, name: &str, scope: TokenScope, ttl: Option<Duration>) -> Result<IssuedToken, AuthError> {
+        let issued = self.issue(tenant, name, scope, ttl.map(|ttl| ttl.num_seconds())).await?;
+        info!("Created {} token '{}' ({}) for tenant {}", scope, name, issued.token.id, tenant);
+        Ok(issued)
+    }
+
+    /// The tenant's tokens, oldest first
+    pub async fn list(&self, tenant: &TenantId) -> Result<Vec<ApiToken>, AuthError> {
+        let mut tokens: Vec<ApiToken> = self.store.list(tenant).await?
+            .into_iter()
+            .map(|record| record.token)
+            .collect();
+        tokens.sort_by_key(|token| token.created_at);
+        Ok(tokens)
+    }
+
+    /// Revoke a token immediately. Revoking a revoked token is a no-op.
+    pub async fn revoke(&self, tenant: &TenantId, id: Uuid) -> Result<ApiToken, AuthError> {
+        let mut record = self.tenant_record(tenant, id).await?;
+        if record.token.revoked_at.is_none() {
+            record.token.revoked_at = Some(Utc::now());
+            self.store.put(record.clone()).await?;
+            info!("Revoked token {} of tenant {}", id, tenant);
+        }
+        Ok(record.token)
+    }
+
+    /// Issue a successor of an active token with the same name and scope.
+    /// The old token keeps working for `overlap`, or until it would have
+    /// expired anyway.
+    pub async fn rotate(&self, tenant: &TenantId, id: Uuid, overlap: Duration) -> Result<IssuedToken, AuthError> {
+        let mut record = self.tenant_record(tenant, id).await?;
+        let now = Utc::now();
+        record.token.check_active_at(now)?;
+
+        let successor = self.issue(tenant, &record.token.name, record.token.scope, record.ttl_secs).await?;
+
+        let overlap_end = now + overlap;
+        record.token.expires_at = Some(record.token.expires_at.map_or(overlap_end, |expires_at| expires_at.min(overlap_end)));
+        record.token.replaced_by = Some(successor.token.id);
+        self.store.put(record).await?;
+
+        info!("Rotated token {} of tenant {} to {}", id, tenant, successor.token.id);
+        Ok(successor)
+    }
+
+    /// The active token with the given secret
+    pub async fn authenticate(&self, secret: &str) -> Result<ApiToken, AuthError> {
+        let record = self.store.find_by_hash(&hash_secret(secret)).await?
+            .ok_or(AuthError::InvalidToken)?;
+        record.token.check_active_at(Utc::now())?;
+        Ok(record.token)
+    }
+
+    /// The active token with the given secret, if it grants `required` on
+    /// `tenant`
+    pub async fn authorize(&self, secret: &str, tenant: &TenantId, required: TokenScope) -> Result<ApiToken, AuthError> {
+        let token = self.authenticate(secret).await?;
+        if token.tenant != *tenant {
+            return Err(AuthError::WrongTenant(tenant.to_string()));
+        }
+        if !token.scope.allows(required) {
+            return Err(AuthError::InsufficientScope { required, granted: token.scope });
+        }
+        Ok(token)
+    }
+
+    async fn issue(&self, tenant: &TenantId, name: &str, scope: TokenScope, ttl_secs: Option<i64>) -> Result<IssuedToken, AuthError> {
+        let secret = generate_secret();
+        let now = Utc::now();
+        let token = ApiToken {
+            id: Uuid::new_v4(),
+            tenant: tenant.clone(),
+            name: name.to_string(),
+            scope,
+            prefix: secret[..DISPLAY_PREFIX_LEN].to_string(),
+            created_at: now,
+            expires_at: ttl_secs.map(|secs| now + Duration::seconds(secs)),
+            revoked_at: None,
+            replaced_by: None,
+        };
+        self.store.put(StoredToken {
+            token: token.clone(),
+            secret_hash: hash_secret(&secret),
+            ttl_secs,
+        }).await?;
+        Ok(IssuedToken { token, secret })
+    }
+
+    /// A token's record, if it belongs to the tenant
+    async fn tenant_record(&self, tenant: &TenantId, id: Uuid) -> Result<StoredToken, AuthError> {
+        self.store.get(id).await?
+            .filter(|record| record.token.tenant == *tenant)
+            .ok_or_else(|| AuthError::TokenNotFound(id.to_string()))
+    }
+}
+
+/// 256 random bits from two v4 UUIDs, hex-encoded after the secret prefix
+fn generate_secret() -> String {
+    format!("{}{}{}", SECRET_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Hex-encoded SHA-256 of a secret
+pub fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Secret store that keeps records in memory; tokens are lost on restart
+#[derive(Default)]
+pub struct InMemorySecretStore {
+    records: RwLock<HashMap<Uuid, StoredToken>>,
+}
+
+impl InMemorySecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SecretStore for InMemorySecretStore {
+    async fn put(&self, record: StoredToken) -> Result<(), AuthError> {
+        self.records.write().unwrap().insert(record.token.id, record);
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<StoredToken>, AuthError> {
+        Ok(self.records.read().unwrap().get(&id).cloned())
+    }
+
+    async fn find_by_hash(&self, secret_hash: &str) -> Result<Option<StoredToken>, AuthError> {
+        Ok(self.records.read().unwrap().values()
+            .find(|record| record.secret_hash == secret_hash)
+            .cloned())
+    }
+
+    async fn list(&self, tenant: &TenantId) -> Result<Vec<StoredToken>, AuthError> {
+        Ok(self.records.read().unwrap().values()
+            .filter(|record| record.token.tenant == *tenant)
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> ApiTokenManager {
+        ApiTokenManager::new(Arc::new(InMemorySecretStore::new()))
+    }
+
+    #[tokio::test]
+    async fn test_create_and_authorize() {
+        let tokens = manager();
+        let tenant = TenantId::new("acme");
+        let issued = tokens.create(&tenant, "ci", TokenScope::Write, None).await.unwrap();
+
+        assert!(issued.secret.starts_with(SECRET_PREFIX));
+        assert!(issued.secret.starts_with(&issued.token.prefix));
+        assert_eq!(tokens.authenticate(&issued.secret).await.unwrap().id, issued.token.id);
+        assert!(tokens.authorize(&issued.secret, &tenant, TokenScope::Read).await.is_ok());
+        assert!(matches!(
+            tokens.authorize(&issued.secret, &tenant, TokenScope::Admin).await,
+            Err(AuthError::InsufficientScope { required: TokenScope::Admin, granted: TokenScope::Write })
+        ));
+        assert!(matches!(
+            tokens.authorize(&issued.secret, &TenantId::new("globex"), TokenScope::Read).await,
+            Err(AuthError::WrongTenant(_))
+        ));
+        assert!(matches!(tokens.authenticate("tm_unknown").await, Err(AuthError::InvalidToken)));
+    }
+
+    #[tokio::test]
+    async fn test_revoke() {
+        let tokens = manager();
+        let tenant = TenantId::new("acme");
+        let issued = tokens.create(&tenant, "ci", TokenScope::Read, None).await.unwrap();
+
+        assert!(matches!(
+            tokens.revoke(&TenantId::new("globex"), issued.token.id).await,
+            Err(AuthError::TokenNotFound(_))
+        ));
+        let revoked = tokens.revoke(&tenant, issued.token.id).await.unwrap();
+        assert!(revoked.revoked_at.is_some());
+        assert!(matches!(tokens.authenticate(&issued.secret).await, Err(AuthError::TokenRevoked)));
+        assert_eq!(tokens.list(&tenant).await.unwrap(), vec![revoked]);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_with_overlap() {
+        let tokens = manager();
+        let tenant = TenantId::new("acme");
+        let old = tokens.create(&tenant, "ci", TokenScope::Admin, Some(Duration::days(30))).await.unwrap();
+
+        let new = tokens.rotate(&tenant, old.token.id, Duration::hours(1)).await.unwrap();
+        assert_eq!((new.token.name.as_str(), new.token.scope), ("ci", TokenScope::Admin));
+        assert!(new.token.expires_at.unwrap() > Utc::now() + Duration::days(29));
+
+        let listed = tokens.list(&tenant).await.unwrap();
+        assert_eq!(listed.len(), 2);
+        let previous = listed.iter().find(|token| token.id == old.token.id).unwrap();
+        assert_eq!(previous.replaced_by, Some(new.token.id));
+        assert!(previous.expires_at.unwrap() <= Utc::now() + Duration::hours(1));
+
+        // Both secrets work during the overlap
+        assert!(tokens.authenticate(&old.secret).await.is_ok());
+        assert!(tokens.authenticate(&new.secret).await.is_ok());
+
+        // Without overlap the old secret stops working at once
+        let newest = tokens.rotate(&tenant, new.token.id, Duration::zero()).await.unwrap();
+        assert!(matches!(tokens.authenticate(&new.secret).await, Err(AuthError::TokenExpired)));
+        assert!(tokens.authenticate(&newest.secret).await.is_ok());
+        assert!(matches!(tokens.rotate(&tenant, new.token.id, Duration::zero()).await, Err(AuthError::TokenExpired)));
+    }
+}
@@ -0,0 +1,275 @@
+//! Graceful degradation when the LLM connector is unavailable
+//!
+//! `GuardedConnector` sits in front of the LLM connector, or stands in for
+//! one when none is configured. After `failure_threshold` consecutive
+//! failures that indicate the provider is down, extraction fails fast with
+//! `LlmError::CapabilityUnavailable` for `cooldown_ms` instead of waiting on the
+//! provider, then one request is let through to probe it. Graph operations
+//! never pass through the guard, so they keep working while it is open.
+
+use crate::errors::LlmError;
+use crate::traits::{CompletionRequest, CompletionResponse, ExtractionContext, ExtractionEnvelope, LlmConnector, ModelInfo, ProviderStatus};
+use crate::types::TenantId;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Whether an optional capability of the service can be used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CapabilityStatus {
+    Available,
+    Unavailable { reason: String },
+}
+
+impl CapabilityStatus {
+    pub fn is_available(&self) -> bool {
+        matches!(self, CapabilityStatus::Available)
+    }
+}
+
+/// Configuration for `GuardedConnector`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AvailabilityConfig {
+    /// Consecutive provider failures after which calls fail fast
+    pub failure_threshold: u32,
+    /// How long calls fail fast before the provider is probed again, in milliseconds
+    pub cooldown_ms: u64,
+}
+
+impl Default for AvailabilityConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown_ms: 30_000,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// LLM connector wrapper that fails fast while the provider is unavailable
+pub struct GuardedConnector {
+    inner: Option<Arc<dyn LlmConnector>>,
+    config: AvailabilityConfig,
+    breaker: Mutex<Breaker>,
+}
+
+impl GuardedConnector {
+    /// Guard a connector
+    pub fn new(inner: Arc<dyn LlmConnector>, config: AvailabilityConfig) -> Self {
+        Self {
+            inner: Some(inner),
+            config,
+            breaker: Mutex::new(Breaker::default()),
+        }
+    }
+
+    /// Stand-in for a service without an LLM connector; every call fails
+    /// with `LlmError::CapabilityUnavailable`
+    pub fn unconfigured() -> Self {
+        Self {
+            inner: None,
+            config: AvailabilityConfig::default(),
+            breaker: Mutex::new(Breaker::default()),
+        }
+    }
+
+    /// Guard a connector if there is one
+    pub fn from_option(inner: Option<Arc<dyn LlmConnector>>, config: AvailabilityConfig) -> Self {
+        match inner {
+            Some(inner) => Self::new(inner, config),
+            None => Self::unconfigured(),
+        }
+    }
+
+    /// Current availability, for health reporting
+    pub fn status(&self) -> CapabilityStatus {
+        if self.inner.is_none() {
+            return CapabilityStatus::Unavailable { reason: "No LLM connector is configured".to_string() };
+        }
+        let breaker = self.breaker.lock().unwrap();
+        match breaker.open_until {
+            Some(until) if Instant::now() < until => CapabilityStatus::Unavailable {
+                reason: breaker.last_error.clone().unwrap_or_default(),
+            },
+            _ => CapabilityStatus::Available,
+        }
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_millis(self.config.cooldown_ms)
+    }
+
+    /// The connector to call, or the error to fail fast with
+    fn admit(&self) -> Result<&Arc<dyn LlmConnector>, LlmError> {
+        let Some(inner) = &self.inner else {
+            return Err(LlmError::CapabilityUnavailable("No LLM connector is configured".to_string()));
+        };
+
+        let mut breaker = self.breaker.lock().unwrap();
+        if let Some(until) = breaker.open_until {
+            if Instant::now() < until {
+                return Err(LlmError::CapabilityUnavailable(format!(
+                    "LLM provider is unavailable: {}",
+                    breaker.last_error.as_deref().unwrap_or("repeated failures")
+                )));
+            }
+            // Let this request probe the provider; further ones fail fast
+            // until it completes
+            breaker.open_until = Some(Instant::now() + self.cooldown());
+        }
+        Ok(inner)
+    }
+
+    fn record<T>(&self, result: &Result<T, LlmError>) {
+        let mut breaker = self.breaker.lock().unwrap();
+        match result {
+            Err(e) if indicates_outage(e) => {
+                breaker.consecutive_failures += 1;
+                breaker.last_error = Some(e.to_string());
+                if breaker.consecutive_failures >= self.config.failure_threshold {
+                    if breaker.open_until.is_none() {
+                        warn!("LLM provider marked unavailable after {} failures: {}", breaker.consecutive_failures, e);
+                    }
+                    breaker.open_until = Some(Instant::now() + self.cooldown());
+                }
+            }
+            _ => {
+                if breaker.open_until.is_some() {
+                    info!("LLM provider is available again");
+                }
+                *breaker = Breaker::default();
+            }
+        }
+    }
+}
+
+/// Errors that mean the provider could not be reached or could not serve
+/// the request, as opposed to a problem with the request itself
+fn indicates_outage(error: &LlmError) -> bool {
+    matches!(
+        error,
+        LlmError::CapabilityUnavailable(_) | LlmError::NetworkError(_) | LlmError::Timeout
+    )
+}
+
+#[async_trait]
+impl LlmConnector for GuardedConnector {
+    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let result = self.admit()?.extract(tenant, context).await;
+        self.record(&result);
+        result
+    }
+
+    async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let result = self.admit()?.complete(tenant, request).await;
+        self.record(&result);
+        result
+    }
+
+    // Probes go to the provider even while calls fail fast, so that they
+    // show when it is back
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        match &self.inner {
+            Some(inner) => inner.list_models().await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn health(&self) -> CapabilityStatus {
+        match &self.inner {
+            Some(inner) => inner.health().await,
+            None => self.status(),
+        }
+    }
+
+    async fn provider_status(&self, tenant: Option<&TenantId>) -> Vec<ProviderStatus> {
+        match &self.inner {
+            Some(inner) => inner.provider_status(tenant).await,
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Connector that fails with a network error while `down` is set
+    #[derive(Default)]
+    struct FlakyProvider {
+        down: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmConnector for FlakyProvider {
+        async fn extract(&self, _tenant: &TenantId, _context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                return Err(LlmError::NetworkError("connection refused".to_string()));
+            }
+            Ok(ExtractionEnvelope { nodes: vec![], relations: vec![], metadata: None })
+        }
+    }
+
+    fn context() -> ExtractionContext {
+        ExtractionContext {
+            messages: vec![],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured_fails_fast() {
+        let connector = GuardedConnector::from_option(None, AvailabilityConfig::default());
+        let tenant = TenantId::new("tenant");
+
+        assert!(matches!(connector.extract(&tenant, context()).await, Err(LlmError::CapabilityUnavailable(_))));
+        assert!(!connector.status().is_available());
+    }
+
+    #[tokio::test]
+    async fn test_outage_opens_and_recovers() {
+        let provider = Arc::new(FlakyProvider::default());
+        let config = AvailabilityConfig { failure_threshold: 2, cooldown_ms: 50 };
+        let connector = GuardedConnector::new(provider.clone(), config);
+        let tenant = TenantId::new("tenant");
+
+        provider.down.store(true, Ordering::SeqCst);
+        for _ in 0..2 {
+            assert!(matches!(connector.extract(&tenant, context()).await, Err(LlmError::NetworkError(_))));
+        }
+        assert!(!connector.status().is_available());
+
+        // Open: the provider is not called
+        assert!(matches!(connector.extract(&tenant, context()).await, Err(LlmError::CapabilityUnavailable(_))));
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+
+        // After the cooldown, a successful probe closes the guard
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        provider.down.store(false, Ordering::SeqCst);
+        assert!(connector.extract(&tenant, context()).await.is_ok());
+        assert!(connector.status().is_available());
+    }
+}
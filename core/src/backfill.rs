@@ -0,0 +1,481 @@
+//! Backfill jobs reprocessing a tenant's existing graph
+//!
+//! When prompts, schemas or embedding models change, records written before
+//! no longer look like those written after. A [`BackfillJob`] is a
+//! maintenance job handing a tenant's nodes, then edges, page by page to a
+//! [`BackfillTransform`] and writing back what it changed: nodes with
+//! `update_node`, edges as corrections with `supersede_edge`.
+//!
+//! Records are read from a snapshot of the tenant and visited in order of
+//! ID. After every page the job checkpoints the last ID it got through, so
+//! a run that fails or stops at `max_records_per_run` resumes there on the
+//! next run; checkpoints are kept by the job for the life of the process.
+//! Runs pause `pause_ms` between pages and go to the LLM request queue at
+//! background priority. Progress is reported through
+//! [`MaintenanceJob::progress`].
+
+use crate::admin::JobProgress;
+use crate::embeddings::EmbeddingOrchestrator;
+use crate::errors::CoreError;
+use crate::llm_queue::{with_priority, RequestPriority};
+use crate::traits::{GraphService, MaintenanceJob};
+use crate::types::{EdgeRecord, Node, NodeRecord, TenantId};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Records a transform visits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackfillTarget {
+    Nodes,
+    Edges,
+    All,
+}
+
+impl BackfillTarget {
+    fn nodes(self) -> bool {
+        matches!(self, BackfillTarget::Nodes | BackfillTarget::All)
+    }
+
+    fn edges(self) -> bool {
+        matches!(self, BackfillTarget::Edges | BackfillTarget::All)
+    }
+}
+
+/// Configuration of a backfill job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackfillConfig {
+    /// Records handed to the transform at a time
+    pub page_size: usize,
+    /// Wait between pages, in milliseconds
+    pub pause_ms: u64,
+    /// Records visited by one run before it stops, to resume on the next;
+    /// no limit if `None`
+    pub max_records_per_run: Option<u64>,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self { page_size: 200, pause_ms: 0, max_records_per_run: None }
+    }
+}
+
+/// What a transform made of a page of records
+#[derive(Debug, Clone)]
+pub struct Transformed<T> {
+    /// Records to write back
+    pub writes: Vec<T>,
+    /// Records changed outside the graph, e.g. re-embedded
+    pub changed: u64,
+    /// Errors of records that could not be transformed, by ID
+    pub failed: BTreeMap<Uuid, String>,
+}
+
+impl<T> Default for Transformed<T> {
+    fn default() -> Self {
+        Self { writes: Vec::new(), changed: 0, failed: BTreeMap::new() }
+    }
+}
+
+/// Trait for transformations a backfill job applies to existing records
+#[async_trait]
+pub trait BackfillTransform: Send + Sync {
+    /// Records the transform visits
+    fn target(&self) -> BackfillTarget;
+
+    /// Transform a page of nodes; returning an error fails the run at the
+    /// page, to be retried by the next one
+    async fn transform_nodes(&self, _tenant: &TenantId, _nodes: Vec<NodeRecord>) -> Result<Transformed<NodeRecord>, CoreError> {
+        Ok(Transformed::default())
+    }
+
+    /// Transform a page of edges, like `transform_nodes`
+    async fn transform_edges(&self, _tenant: &TenantId, _edges: Vec<EdgeRecord>) -> Result<Transformed<EdgeRecord>, CoreError> {
+        Ok(Transformed::default())
+    }
+}
+
+/// Embed nodes again, e.g. after switching embedding models
+pub struct Reembed {
+    embeddings: Arc<EmbeddingOrchestrator>,
+}
+
+impl Reembed {
+    pub fn new(embeddings: Arc<EmbeddingOrchestrator>) -> Self {
+        Self { embeddings }
+    }
+}
+
+#[async_trait]
+impl BackfillTransform for Reembed {
+    fn target(&self) -> BackfillTarget {
+        BackfillTarget::Nodes
+    }
+
+    async fn transform_nodes(&self, tenant: &TenantId, nodes: Vec<NodeRecord>) -> Result<Transformed<NodeRecord>, CoreError> {
+        let nodes = nodes.into_iter().map(|record| (record.id, record.node)).collect();
+        let report = self.embeddings.embed_nodes(tenant, nodes).await?;
+        Ok(Transformed { writes: Vec::new(), changed: report.embedded as u64, failed: report.failed })
+    }
+}
+
+/// Rename node labels and edge kinds, e.g. after the extraction schema
+/// settled on other names
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Relabel {
+    /// New label by old label
+    pub labels: HashMap<String, String>,
+    /// New kind by old kind
+    pub kinds: HashMap<String, String>,
+}
+
+impl Relabel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rename nodes labeled `from` to `to`
+    pub fn with_label(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.labels.insert(from.into(), to.into());
+        self
+    }
+
+    /// Rename edges of kind `from` to `to`
+    pub fn with_kind(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.kinds.insert(from.into(), to.into());
+        self
+    }
+}
+
+#[async_trait]
+impl BackfillTransform for Relabel {
+    fn target(&self) -> BackfillTarget {
+        match (self.labels.is_empty(), self.kinds.is_empty()) {
+            (false, true) => BackfillTarget::Nodes,
+            (true, false) => BackfillTarget::Edges,
+            _ => BackfillTarget::All,
+        }
+    }
+
+    async fn transform_nodes(&self, _tenant: &TenantId, nodes: Vec<NodeRecord>) -> Result<Transformed<NodeRecord>, CoreError> {
+        let writes = nodes.into_iter()
+            .filter_map(|mut record| {
+                let label = self.labels.get(&record.node.label).filter(|label| **label != record.node.label)?;
+                record.node.label = label.clone();
+                Some(record)
+            })
+            .collect();
+        Ok(Transformed { writes, ..Default::default() })
+    }
+
+    async fn transform_edges(&self, _tenant: &TenantId, edges: Vec<EdgeRecord>) -> Result<Transformed<EdgeRecord>, CoreError> {
+        let writes = edges.into_iter()
+            .filter_map(|mut record| {
+                let kind = self.kinds.get(&record.edge.kind).filter(|kind| **kind != record.edge.kind)?;
+                record.edge.kind = kind.clone();
+                Some(record)
+            })
+            .collect();
+        Ok(Transformed { writes, ..Default::default() })
+    }
+}
+
+type DeriveFn = dyn Fn(&Node) -> Option<serde_json::Map<String, Value>> + Send + Sync;
+
+/// Recompute properties derived from others, e.g. after the derivation
+/// changed. Nodes are written back only if a derived value differs from
+/// the stored one.
+pub struct DeriveProperties {
+    labels: Vec<String>,
+    derive: Box<DeriveFn>,
+}
+
+impl DeriveProperties {
+    /// Derive properties of nodes with one of `labels`, or of every node if
+    /// empty; `derive` returns the properties to set, or `None` to leave
+    /// the node as it is
+    pub fn new<F>(labels: Vec<String>, derive: F) -> Self
+    where
+        F: Fn(&Node) -> Option<serde_json::Map<String, Value>> + Send + Sync + 'static,
+    {
+        Self { labels, derive: Box::new(derive) }
+    }
+}
+
+#[async_trait]
+impl BackfillTransform for DeriveProperties {
+    fn target(&self) -> BackfillTarget {
+        BackfillTarget::Nodes
+    }
+
+    async fn transform_nodes(&self, _tenant: &TenantId, nodes: Vec<NodeRecord>) -> Result<Transformed<NodeRecord>, CoreError> {
+        let writes = nodes.into_iter()
+            .filter(|record| self.labels.is_empty() || self.labels.contains(&record.node.label))
+            .filter_map(|mut record| {
+                let derived = (self.derive)(&record.node)?;
+                let props = record.node.props.as_object_mut()?;
+                let mut changed = false;
+                for (key, value) in derived {
+                    if props.get(&key) != Some(&value) {
+                        props.insert(key, value);
+                        changed = true;
+                    }
+                }
+                changed.then_some(record)
+            })
+            .collect();
+        Ok(Transformed { writes, ..Default::default() })
+    }
+}
+
+/// Where a tenant's backfill has got to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackfillCheckpoint {
+    /// Whether the job is through with the nodes and visits edges
+    pub edges: bool,
+    /// Last record ID the job got through, in the current phase
+    pub after: Option<Uuid>,
+}
+
+struct TenantBackfill {
+    checkpoint: BackfillCheckpoint,
+    progress: JobProgress,
+}
+
+/// Maintenance job applying a [`BackfillTransform`] to a tenant's existing
+/// records, resuming from its checkpoint
+pub struct BackfillJob {
+    service: Arc<dyn GraphService>,
+    transform: Arc<dyn BackfillTransform>,
+    config: BackfillConfig,
+    tenants: Mutex<HashMap<TenantId, TenantBackfill>>,
+}
+
+impl BackfillJob {
+    pub fn new(service: Arc<dyn GraphService>, transform: Arc<dyn BackfillTransform>) -> Self {
+        Self { service, transform, config: BackfillConfig::default(), tenants: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn with_config(mut self, config: BackfillConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Where the next run over the tenant starts, `None` if it starts over
+    pub fn checkpoint(&self, tenant: &TenantId) -> Option<BackfillCheckpoint> {
+        self.tenants.lock().unwrap().get(tenant)
+            .filter(|state| !state.progress.finished)
+            .map(|state| state.checkpoint)
+    }
+
+    /// Forget the tenant's checkpoint, so the next run starts over
+    pub fn reset(&self, tenant: &TenantId) {
+        self.tenants.lock().unwrap().remove(tenant);
+    }
+
+    /// Checkpoint of the run starting now, with the progress it resumes
+    fn resume(&self, tenant: &TenantId, total: u64) -> BackfillCheckpoint {
+        let mut tenants = self.tenants.lock().unwrap();
+        let now = Utc::now();
+        let state = tenants.entry(tenant.clone())
+            .and_modify(|state| {
+                // A finished backfill starts over
+                if state.progress.finished {
+                    state.checkpoint = BackfillCheckpoint { edges: false, after: None };
+                    state.progress = empty_progress(tenant);
+                }
+            })
+            .or_insert_with(|| TenantBackfill {
+                checkpoint: BackfillCheckpoint { edges: false, after: None },
+                progress: empty_progress(tenant),
+            });
+        state.progress.total = Some(total);
+        state.progress.updated_at = now;
+        state.checkpoint
+    }
+
+    fn record<T>(&self, tenant: &TenantId, checkpoint: BackfillCheckpoint, visited: usize, outcome: &Transformed<T>, written: u64, errors: &[String]) {
+        let mut tenants = self.tenants.lock().unwrap();
+        if let Some(state) = tenants.get_mut(tenant) {
+            state.checkpoint = checkpoint;
+            let progress = &mut state.progress;
+            progress.processed += visited as u64;
+            progress.changed += outcome.changed + written;
+            progress.failed += (outcome.failed.len() + errors.len()) as u64;
+            if let Some(error) = errors.last().or_else(|| outcome.failed.values().last()) {
+                progress.last_error = Some(error.clone());
+            }
+            progress.updated_at = Utc::now();
+        }
+    }
+
+    fn advance(&self, tenant: &TenantId, checkpoint: BackfillCheckpoint) {
+        if let Some(state) = self.tenants.lock().unwrap().get_mut(tenant) {
+            state.checkpoint = checkpoint;
+        }
+    }
+
+    fn update(&self, tenant: &TenantId, update: impl FnOnce(&mut JobProgress)) {
+        if let Some(state) = self.tenants.lock().unwrap().get_mut(tenant) {
+            update(&mut state.progress);
+            state.progress.updated_at = Utc::now();
+        }
+    }
+
+    async fn backfill(&self, tenant: &TenantId) -> Result<u64, CoreError> {
+        let target = self.transform.target();
+        let snapshot = self.service.snapshot(tenant, None).await?;
+        let mut nodes = if target.nodes() { snapshot.nodes } else { Vec::new() };
+        let mut edges = if target.edges() { snapshot.edges } else { Vec::new() };
+        nodes.sort_by_key(|record| record.id);
+        edges.sort_by_key(|record| record.id);
+
+        let mut checkpoint = self.resume(tenant, (nodes.len() + edges.len()) as u64);
+        let page_size = self.config.page_size.max(1);
+        let mut budget = self.config.max_records_per_run.unwrap_or(u64::MAX);
+        let mut changed = 0;
+
+        if !checkpoint.edges {
+            let pending: Vec<NodeRecord> = nodes.into_iter().filter(|record| Some(record.id) > checkpoint.after).collect();
+            for page in pending.chunks(page_size) {
+                if budget == 0 {
+                    return Ok(changed);
+                }
+                let page = &page[..page.len().min(budget as usize)];
+                let outcome = self.transform.transform_nodes(tenant, page.to_vec()).await?;
+                let mut written = 0;
+                let mut errors = Vec::new();
+                for record in &outcome.writes {
+                    match self.service.update_node(tenant, record.id, record.node.clone()).await {
+                        Ok(true) => written += 1,
+                        // Deleted since the snapshot
+                        Ok(false) => {}
+                        Err(e) => errors.push(format!("Node {}: {}", record.id, e)),
+                    }
+                }
+                checkpoint.after = page.last().map(|record| record.id);
+                self.record(tenant, checkpoint, page.len(), &outcome, written, &errors);
+                changed += outcome.changed + written;
+                budget -= page.len() as u64;
+                self.pause().await;
+            }
+            checkpoint = BackfillCheckpoint { edges: true, after: None };
+            self.advance(tenant, checkpoint);
+        }
+
+        let pending: Vec<EdgeRecord> = edges.into_iter().filter(|record| Some(record.id) > checkpoint.after).collect();
+        for page in pending.chunks(page_size) {
+            if budget == 0 {
+                return Ok(changed);
+            }
+            let page = &page[..page.len().min(budget as usize)];
+            let outcome = self.transform.transform_edges(tenant, page.to_vec()).await?;
+            let mut written = 0;
+            let mut errors = Vec::new();
+            for record in &outcome.writes {
+                match self.service.supersede_edge(tenant, record.id, record.edge.clone()).await {
+                    Ok(_) => written += 1,
+                    Err(e) => errors.push(format!("Edge {}: {}", record.id, e)),
+                }
+            }
+            checkpoint.after = page.last().map(|record| record.id);
+            self.record(tenant, checkpoint, page.len(), &outcome, written, &errors);
+            changed += outcome.changed + written;
+            budget -= page.len() as u64;
+            self.pause().await;
+        }
+
+        self.update(tenant, |progress| progress.finished = true);
+        Ok(changed)
+    }
+
+    async fn pause(&self) {
+        if self.config.pause_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.config.pause_ms)).await;
+        }
+    }
+}
+
+fn empty_progress(tenant: &TenantId) -> JobProgress {
+    let now = Utc::now();
+    JobProgress {
+        tenant: tenant.clone(),
+        processed: 0,
+        total: None,
+        changed: 0,
+        failed: 0,
+        last_error: None,
+        finished: false,
+        started_at: now,
+        updated_at: now,
+    }
+}
+
+#[async_trait]
+impl MaintenanceJob for BackfillJob {
+    async fn run(&self, tenant: &TenantId) -> Result<u64, CoreError> {
+        let result = with_priority(RequestPriority::Background, self.backfill(tenant)).await;
+        match &result {
+            Ok(changed) => info!("Backfill changed {} record(s) of tenant {}", changed, tenant),
+            Err(e) => {
+                warn!("Backfill of tenant {} stopped at its checkpoint: {}", tenant, e);
+                self.update(tenant, |progress| progress.last_error = Some(e.to_string()));
+            }
+        }
+        result
+    }
+
+    fn progress(&self) -> Vec<JobProgress> {
+        let mut progress: Vec<JobProgress> = self.tenants.lock().unwrap().values().map(|state| state.progress.clone()).collect();
+        progress.sort_by(|a, b| a.tenant.as_str().cmp(b.tenant.as_str()));
+        progress
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(label: &str, props: Value) -> NodeRecord {
+        NodeRecord { id: Uuid::new_v4(), node: Node::new(label).with_props(props) }
+    }
+
+    #[tokio::test]
+    async fn test_transforms_write_only_changed_records() {
+        let tenant = TenantId::new("acme");
+
+        let relabel = Relabel::new().with_label("person", "Person").with_label("Person", "Person");
+        assert_eq!(relabel.target(), BackfillTarget::Nodes);
+        let nodes = vec![record("person", json!({})), record("Person", json!({})), record("Company", json!({}))];
+        let relabeled = relabel.transform_nodes(&tenant, nodes.clone()).await.unwrap();
+        assert_eq!(relabeled.writes.len(), 1);
+        assert_eq!(relabeled.writes[0].id, nodes[0].id);
+        assert_eq!(relabeled.writes[0].node.label, "Person");
+        assert_eq!(Relabel::new().with_kind("works_for", "WORKS_FOR").target(), BackfillTarget::Edges);
+
+        let derive = DeriveProperties::new(vec!["Person".to_string()], |node| {
+            let name = node.props.get("name")?.as_str()?;
+            Some(serde_json::Map::from_iter([("name_lower".to_string(), json!(name.to_lowercase()))]))
+        });
+        let nodes = vec![
+            record("Person", json!({"name": "Alice"})),
+            record("Person", json!({"name": "Bob", "name_lower": "bob"})),
+            record("Person", json!({})),
+            record("Company", json!({"name": "Acme"})),
+        ];
+        let derived = derive.transform_nodes(&tenant, nodes.clone()).await.unwrap();
+        assert_eq!(derived.writes.len(), 1);
+        assert_eq!(derived.writes[0].id, nodes[0].id);
+        assert_eq!(derived.writes[0].node.props, json!({"name": "Alice", "name_lower": "alice"}));
+    }
+}
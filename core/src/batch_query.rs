@@ -0,0 +1,262 @@
+//! Batch queries
+//!
+//! Dashboards issue several queries per page load. A batch sends them in one
+//! request and runs them concurrently: each query succeeds or fails on its
+//! own, the whole batch shares one deadline after which unfinished queries
+//! fail with a timeout, and results come back in the order of the queries.
+
+use crate::errors::GraphError;
+use crate::query_cache::{cache_bypassed, without_cache};
+use crate::traits::GraphService;
+use crate::types::{GraphQuery, Path, QueryMode, TenantId};
+use crate::views::in_current_view;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tracing::debug;
+
+/// Most queries accepted in one batch
+pub const MAX_BATCH_QUERIES: usize = 32;
+
+/// Deadline for a batch that does not set one, in milliseconds
+pub const DEFAULT_BATCH_TIMEOUT_MS: u64 = 30_000;
+
+/// One query of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchQuery {
+    pub query: GraphQuery,
+    /// Return the matching paths, only their number, or only whether there are any
+    #[serde(default)]
+    pub mode: QueryMode,
+}
+
+impl BatchQuery {
+    pub fn new(query: GraphQuery) -> Self {
+        Self { query, mode: QueryMode::Full }
+    }
+
+    pub fn with_mode(mut self, mode: QueryMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+/// Queries to run together
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchQueryRequest {
+    pub queries: Vec<BatchQuery>,
+    /// Deadline for the whole batch in milliseconds, [`DEFAULT_BATCH_TIMEOUT_MS`] if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Outcome of one query of a batch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchQueryResult {
+    /// Matching paths; empty unless the mode is `full`
+    #[serde(default)]
+    pub paths: Vec<Path>,
+    /// Number of matches, in `count` mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    /// Whether anything matched, in `exists` mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    /// Why the query failed; the other fields are empty if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub execution_time_ms: u64,
+}
+
+impl BatchQueryResult {
+    fn failed(error: &GraphError, execution_time_ms: u64) -> Self {
+        Self { error: Some(error.to_string()), execution_time_ms, ..Self::default() }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Results of a batch, in the order of its queries
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchQueryResponse {
+    pub results: Vec<BatchQueryResult>,
+    pub execution_time_ms: u64,
+}
+
+/// Run one query of a batch
+async fn run_one(service: &dyn GraphService, tenant: &TenantId, query: BatchQuery) -> Result<BatchQueryResult, GraphError> {
+    let mut result = BatchQueryResult::default();
+    match query.mode {
+        QueryMode::Full => result.paths = service.query(tenant, query.query).await?,
+        QueryMode::Count => result.count = Some(service.query_count(tenant, query.query).await?),
+        QueryMode::Exists => result.exists = Some(service.query_exists(tenant, query.query).await?),
+    }
+    Ok(result)
+}
+
+/// Run a batch of queries concurrently.
+///
+/// Fails as a whole only if the batch is empty or larger than
+/// [`MAX_BATCH_QUERIES`]; a failing query is reported in its own result.
+/// Queries still running at the deadline are cancelled. Runs without the
+/// query cache if the caller runs inside [`without_cache`], and confined to
+/// the caller's view if it has one.
+pub async fn run_batch(service: Arc<dyn GraphService>, tenant: &TenantId, request: BatchQueryRequest) -> Result<BatchQueryResponse, GraphError> {
+    if request.queries.is_empty() {
+        return Err(GraphError::QueryFailed("A batch needs at least one query".to_string()));
+    }
+    if request.queries.len() > MAX_BATCH_QUERIES {
+        return Err(GraphError::QueryFailed(format!(
+            "A batch holds at most {} queries, got {}", MAX_BATCH_QUERIES, request.queries.len()
+        )));
+    }
+
+    let start_time = Instant::now();
+    let timeout = Duration::from_millis(request.timeout_ms.unwrap_or(DEFAULT_BATCH_TIMEOUT_MS));
+    // Spawned tasks do not inherit task-locals, so they are set again in each
+    let bypass_cache = cache_bypassed();
+    let mut results: Vec<Option<BatchQueryResult>> = vec![None; request.queries.len()];
+
+    let mut running = JoinSet::new();
+    for (index, query) in request.queries.into_iter().enumerate() {
+        let service = service.clone();
+        let tenant = tenant.clone();
+        running.spawn(in_current_view(async move {
+            let query_start = Instant::now();
+            let run = run_one(service.as_ref(), &tenant, query);
+            let outcome = if bypass_cache { without_cache(run).await } else { run.await };
+            let execution_time_ms = query_start.elapsed().as_millis() as u64;
+            let result = match outcome {
+                Ok(result) => BatchQueryResult { execution_time_ms, ..result },
+                Err(e) => BatchQueryResult::failed(&e, execution_time_ms),
+            };
+            (index, result)
+        }));
+    }
+
+    let collect = async {
+        while let Some(joined) = running.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(e) => debug!("Batch query task failed: {}", e),
+            }
+        }
+    };
+    let timed_out = tokio::time::timeout(timeout, collect).await.is_err();
+    if timed_out {
+        debug!("Batch for tenant {} hit its {}ms deadline", tenant, timeout.as_millis());
+        running.abort_all();
+    }
+
+    let execution_time_ms = start_time.elapsed().as_millis() as u64;
+    let unfinished = if timed_out {
+        GraphError::Timeout(format!("Query did not finish within the batch deadline of {}ms", timeout.as_millis()))
+    } else {
+        GraphError::QueryFailed("Query did not complete".to_string())
+    };
+    let results = results.into_iter()
+        .map(|result| result.unwrap_or_else(|| BatchQueryResult::failed(&unfinished, execution_time_ms)))
+        .collect();
+    Ok(BatchQueryResponse { results, execution_time_ms })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::LlmError;
+    use crate::materialized::SnapshotInfo;
+    use crate::traits::{ExtractionContext, ExtractionEnvelope};
+    use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphSnapshot, GraphSummary, Node, NodeWithEdges, TimeEdge};
+    use crate::views::{current_view, with_view, GraphView};
+    use async_trait::async_trait;
+    use chrono::{DateTime, Utc};
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    /// Answers queries by the label they ask for: `Slow` never finishes,
+    /// `Broken` fails and any other label matches one node per character,
+    /// or none if it is outside the current view
+    struct LabelService;
+
+    #[async_trait]
+    impl GraphService for LabelService {
+        async fn upsert_node(&self, _tenant: &TenantId, _node: Node) -> Result<Uuid, GraphError> { unimplemented!() }
+        async fn upsert_edge(&self, _tenant: &TenantId, _edge: TimeEdge) -> Result<Uuid, GraphError> { unimplemented!() }
+        async fn close_edge(&self, _tenant: &TenantId, _id: Uuid, _valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> { unimplemented!() }
+        async fn supersede_edge(&self, _tenant: &TenantId, _id: Uuid, _edge: TimeEdge) -> Result<Uuid, GraphError> { unimplemented!() }
+        async fn retract_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> { unimplemented!() }
+        async fn upsert_node_with_edges(&self, _tenant: &TenantId, _node: Node, _edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> { unimplemented!() }
+
+        async fn query(&self, _tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            let GraphQuery::FindNodes { labels, .. } = query else {
+                return Err(GraphError::Unsupported("Only node queries".to_string()));
+            };
+            match labels[0].as_str() {
+                "Slow" => std::future::pending().await,
+                "Broken" => Err(GraphError::QueryFailed("Broken label".to_string())),
+                label if current_view().is_some_and(|view| !view.labels.iter().any(|l| l == label)) => Ok(Vec::new()),
+                label => Ok(vec![Path { nodes: Vec::new(), relationships: Vec::new() }; label.len()]),
+            }
+        }
+
+        async fn resolve_aliases(&self, _tenant: &TenantId, _aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> { unimplemented!() }
+        async fn snapshot(&self, _tenant: &TenantId, _valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> { unimplemented!() }
+        async fn materialize_snapshot(&self, _tenant: &TenantId, _name: &str, _valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> { unimplemented!() }
+        async fn list_snapshots(&self, _tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> { unimplemented!() }
+        async fn drop_snapshot(&self, _tenant: &TenantId, _name: &str) -> Result<bool, GraphError> { unimplemented!() }
+        async fn query_snapshot(&self, _tenant: &TenantId, _name: &str, _query: GraphQuery) -> Result<Vec<Path>, GraphError> { unimplemented!() }
+        async fn summary(&self, _tenant: &TenantId) -> Result<GraphSummary, GraphError> { unimplemented!() }
+        async fn catalog(&self, _tenant: &TenantId) -> Result<GraphCatalog, GraphError> { unimplemented!() }
+        async fn extract_knowledge(&self, _tenant: &TenantId, _context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> { unimplemented!() }
+        async fn health_check(&self) -> Result<(), GraphError> { Ok(()) }
+    }
+
+    fn find(label: &str) -> BatchQuery {
+        BatchQuery::new(GraphQuery::FindNodes {
+            labels: vec![label.to_string()],
+            properties: HashMap::new(),
+            order_by: Vec::new(),
+            offset: None,
+            limit: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_run_batch() {
+        let service: Arc<dyn GraphService> = Arc::new(LabelService);
+        let tenant = TenantId::new("acme");
+        let request = BatchQueryRequest {
+            queries: vec![find("Person"), find("Broken"), find("Slow"), find("Org").with_mode(QueryMode::Count), find("Org").with_mode(QueryMode::Exists)],
+            timeout_ms: Some(50),
+        };
+
+        let response = run_batch(service.clone(), &tenant, request).await.unwrap();
+        let results = &response.results;
+        assert_eq!(results.len(), 5);
+        assert_eq!((results[0].paths.len(), results[0].is_ok()), (6, true));
+        assert!(results[1].error.as_deref().unwrap().contains("Broken label"));
+        assert!(results[2].error.as_deref().unwrap().contains("deadline"));
+        assert_eq!((results[3].count, results[3].paths.len()), (Some(3), 0));
+        assert_eq!(results[4].exists, Some(true));
+
+        let empty = BatchQueryRequest::default();
+        assert!(run_batch(service.clone(), &tenant, empty).await.is_err());
+        let too_many = BatchQueryRequest { queries: vec![find("Person"); MAX_BATCH_QUERIES + 1], timeout_ms: None };
+        assert!(run_batch(service, &tenant, too_many).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_keeps_view() {
+        let service: Arc<dyn GraphService> = Arc::new(LabelService);
+        let tenant = TenantId::new("acme");
+        let request = BatchQueryRequest { queries: vec![find("Person"), find("Org")], timeout_ms: None };
+        let view = GraphView { name: "people".to_string(), labels: vec!["Person".to_string()], kinds: Vec::new(), properties: HashMap::new() };
+
+        let response = with_view(Some(view), run_batch(service, &tenant, request)).await.unwrap();
+        assert_eq!(response.results[0].paths.len(), 6);
+        assert!(response.results[1].paths.is_empty());
+    }
+}
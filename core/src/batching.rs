@@ -0,0 +1,758 @@
+//! Write-behind batching layer for high-throughput ingestion
+//!
+//! `BatchingGraphStore` wraps any `GraphStore` and buffers mutations in an
+//! in-process queue. Repeated upserts of the same aliased node are coalesced
+//! into a single write, and the queue is flushed when it reaches
+//! `max_batch_size` or every `flush_interval_ms`, whichever comes first.
+//!
+//! Writes honor the request's [`WriteConcern`](crate::write_concern::WriteConcern):
+//! `buffered` writes return as soon as they are queued, `committed` writes
+//! wait for their flush and `replicated` writes flush the queue and go to the
+//! underlying store directly, so that it can wait for its replicas.
+
+use crate::archive::ArchiveBatch;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{EdgeStream, NodeStream};
+use crate::errors::GraphError;
+use crate::materialized::SnapshotInfo;
+use crate::traits::GraphStore;
+use crate::write_concern::{current_write_concern, WriteConcern};
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::types::{AliasKey, EdgeSpec, GraphMutation, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeWithEdges, Path, TenantId, TimeEdge};
+use chrono::{DateTime, Utc};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex, Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Configuration for the write-behind buffer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchingConfig {
+    /// Number of buffered mutations that triggers an immediate flush
+    pub max_batch_size: usize,
+    /// Maximum time a mutation waits in the buffer, in milliseconds
+    pub flush_interval_ms: u64,
+    /// Maximum number of buffered mutations before writers are held back
+    pub max_pending: usize,
+    /// Whether upserts of the same aliased node are merged before flushing
+    pub coalesce: bool,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 500,
+            flush_interval_ms: 100,
+            max_pending: 10_000,
+            coalesce: true,
+        }
+    }
+}
+
+/// Result of a buffered mutation once it has been flushed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// Node or edge was written with the given ID
+    Upserted(Uuid),
+    /// Delete was applied; `false` if nothing matched
+    Deleted(bool),
+}
+
+/// Handle to a buffered mutation. Await it for the outcome, or drop it for
+/// fire-and-forget writes.
+pub struct PendingWrite(oneshot::Receiver<Result<WriteOutcome, GraphError>>);
+
+impl PendingWrite {
+    /// Wait until the mutation has been flushed to the underlying store
+    pub async fn wait(self) -> Result<WriteOutcome, GraphError> {
+        self.0.await.map_err(|_| {
+            GraphError::TransactionFailed("Write buffer was dropped before flushing".to_string())
+        })?
+    }
+}
+
+/// A buffered mutation with everyone waiting on it
+struct PendingEntry {
+    tenant: TenantId,
+    mutation: GraphMutation,
+    waiters: Vec<oneshot::Sender<Result<WriteOutcome, GraphError>>>,
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Key under which mutations are coalesced
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum CoalesceKey {
+    Node(TenantId, AliasKey),
+    DeleteNode(TenantId, Uuid),
+    DeleteEdge(TenantId, Uuid),
+}
+
+#[derive(Default)]
+struct BufferState {
+    entries: Vec<PendingEntry>,
+    slots: HashMap<CoalesceKey, usize>,
+}
+
+struct Shared {
+    inner: Arc<dyn GraphStore>,
+    config: BatchingConfig,
+    state: Mutex<BufferState>,
+    /// Serializes flushes so mutations reach the store in enqueue order
+    flush_lock: Mutex<()>,
+    capacity: Arc<Semaphore>,
+}
+
+impl Shared {
+    async fn flush(&self) -> usize {
+        let _flushing = self.flush_lock.lock().await;
+
+        let entries = {
+            let mut state = self.state.lock().await;
+            state.slots.clear();
+            std::mem::take(&mut state.entries)
+        };
+
+        if entries.is_empty() {
+            return 0;
+        }
+
+        debug!("Flushing {} buffered mutations", entries.len());
+
+        let count = entries.len();
+        for entry in entries {
+            let result = self.apply(&entry.tenant, entry.mutation).await;
+
+            if let Err(e) = &result {
+                warn!("Buffered write for tenant {} failed: {}", entry.tenant, e);
+            }
+            for waiter in entry.waiters {
+                let _ = waiter.send(result.clone());
+            }
+        }
+
+        count
+    }
+
+    /// Write a mutation to the underlying store
+    async fn apply(&self, tenant: &TenantId, mutation: GraphMutation) -> Result<WriteOutcome, GraphError> {
+        match mutation {
+            GraphMutation::UpsertNode(node) => self.inner.upsert_node(tenant, node).await.map(WriteOutcome::Upserted),
+            GraphMutation::UpsertEdge(edge) => self.inner.upsert_edge(tenant, edge).await.map(WriteOutcome::Upserted),
+            GraphMutation::DeleteNode { id } => self.inner.delete_node(tenant, id).await.map(WriteOutcome::Deleted),
+            GraphMutation::DeleteEdge { id } => self.inner.delete_edge(tenant, id).await.map(WriteOutcome::Deleted),
+        }
+    }
+}
+
+/// `GraphStore` decorator that batches and coalesces writes.
+///
+/// Trait write methods wait for their mutation to be flushed, unless the
+/// request's write concern is `buffered`, so they keep their usual semantics
+/// while concurrent callers share a flush. Source
+/// adapters that do not need the result can use [`BatchingGraphStore::enqueue`]
+/// and drop the returned handle. Reads are passed straight through.
+///
+/// Must be created inside a Tokio runtime. Call [`BatchingGraphStore::shutdown`]
+/// before dropping it so buffered mutations are not lost.
+pub struct BatchingGraphStore {
+    shared: Arc<Shared>,
+    shutdown: Arc<Notify>,
+    ticker: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl BatchingGraphStore {
+    /// Wrap a store with the given batching configuration
+    pub fn new(inner: Arc<dyn GraphStore>, config: BatchingConfig) -> Self {
+        let shared = Arc::new(Shared {
+            inner,
+            capacity: Arc::new(Semaphore::new(config.max_pending.max(1))),
+            config,
+            state: Mutex::new(BufferState::default()),
+            flush_lock: Mutex::new(()),
+        });
+        let shutdown = Arc::new(Notify::new());
+        let ticker = tokio::spawn(Self::run_ticker(Arc::downgrade(&shared), shutdown.clone()));
+
+        Self {
+            shared,
+            shutdown,
+            ticker: std::sync::Mutex::new(Some(ticker)),
+        }
+    }
+
+    async fn run_ticker(shared: Weak<Shared>, shutdown: Arc<Notify>) {
+        let Some(period) = shared.upgrade().map(|s| Duration::from_millis(s.config.flush_interval_ms.max(1))) else {
+            return;
+        };
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match shared.upgrade() {
+                        Some(shared) => { shared.flush().await; }
+                        None => break,
+                    }
+                }
+                _ = shutdown.notified() => break,
+            }
+        }
+    }
+
+    /// Buffer a mutation. Waits while the buffer is full (backpressure) and
+    /// flushes inline once `max_batch_size` is reached.
+    pub async fn enqueue(&self, tenant: &TenantId, mutation: GraphMutation) -> Result<PendingWrite, GraphError> {
+        let permit = self.shared.capacity.clone().acquire_owned().await
+            .map_err(|_| GraphError::ConnectionFailed("Write buffer has been shut down".to_string()))?;
+
+        let (tx, rx) = oneshot::channel();
+        let should_flush = {
+            let mut state = self.shared.state.lock().await;
+            let key = if self.shared.config.coalesce { coalesce_key(tenant, &mutation) } else { None };
+            let existing = key.as_ref().and_then(|k| state.slots.get(k).copied());
+
+            match existing {
+                Some(slot) if merge_into(&mut state.entries[slot].mutation, &mutation) => {
+                    // Merged into an existing entry; the new permit is released on drop
+                    state.entries[slot].waiters.push(tx);
+                }
+                _ => {
+                    let slot = state.entries.len();
+                    state.entries.push(PendingEntry {
+                        tenant: tenant.clone(),
+                        mutation,
+                        waiters: vec![tx],
+                        _permit: permit,
+                    });
+                    if let Some(key) = key {
+                        state.slots.insert(key, slot);
+                    }
+                }
+            }
+
+            state.entries.len() >= self.shared.config.max_batch_size
+        };
+
+        if should_flush {
+            self.shared.flush().await;
+        }
+
+        Ok(PendingWrite(rx))
+    }
+
+    /// Flush all buffered mutations now, returning how many were written
+    pub async fn flush(&self) -> usize {
+        self.shared.flush().await
+    }
+
+    /// Number of mutations currently buffered
+    pub async fn pending(&self) -> usize {
+        self.shared.state.lock().await.entries.len()
+    }
+
+    /// Stop the background flusher, refuse new writes and flush what is buffered
+    pub async fn shutdown(&self) -> usize {
+        self.shared.capacity.close();
+        self.shutdown.notify_one();
+
+        let ticker = self.ticker.lock().unwrap().take();
+        if let Some(ticker) = ticker {
+            let _ = ticker.await;
+        }
+
+        self.shared.flush().await
+    }
+
+    /// Write a mutation with the request's write concern. Buffered upserts
+    /// are acknowledged with the nil UUID and buffered deletes as applied.
+    async fn write(&self, tenant: &TenantId, mutation: GraphMutation) -> Result<WriteOutcome, GraphError> {
+        match current_write_concern() {
+            WriteConcern::Buffered => {
+                let accepted = match &mutation {
+                    GraphMutation::UpsertNode(_) | GraphMutation::UpsertEdge(_) => WriteOutcome::Upserted(Uuid::nil()),
+                    GraphMutation::DeleteNode { .. } | GraphMutation::DeleteEdge { .. } => WriteOutcome::Deleted(true),
+                };
+                self.enqueue(tenant, mutation).await?;
+                Ok(accepted)
+            }
+            WriteConcern::Committed => self.enqueue(tenant, mutation).await?.wait().await,
+            WriteConcern::Replicated => {
+                // Written from this task, where the inner store sees the write concern
+                self.flush().await;
+                self.shared.apply(tenant, mutation).await
+            }
+        }
+    }
+}
+
+impl Drop for BatchingGraphStore {
+    fn drop(&mut self) {
+        if let Ok(state) = self.shared.state.try_lock() {
+            if !state.entries.is_empty() {
+                warn!("BatchingGraphStore dropped with {} unflushed mutations", state.entries.len());
+            }
+        }
+        if let Some(ticker) = self.ticker.lock().unwrap().take() {
+            ticker.abort();
+        }
+    }
+}
+
+/// Coalescing key of a mutation, if it can be merged with others
+fn coalesce_key(tenant: &TenantId, mutation: &GraphMutation) -> Option<CoalesceKey> {
+    match mutation {
+        GraphMutation::UpsertNode(node) => node.alias_key().map(|key| CoalesceKey::Node(tenant.clone(), key)),
+        GraphMutation::DeleteNode { id } => Some(CoalesceKey::DeleteNode(tenant.clone(), *id)),
+        GraphMutation::DeleteEdge { id } => Some(CoalesceKey::DeleteEdge(tenant.clone(), *id)),
+        GraphMutation::UpsertEdge(_) => None,
+    }
+}
+
+/// Merge a later mutation into a buffered one with the same key.
+/// Returns false if they cannot be merged (e.g. a node changed label).
+fn merge_into(existing: &mut GraphMutation, update: &GraphMutation) -> bool {
+    match (existing, update) {
+        (GraphMutation::UpsertNode(existing), GraphMutation::UpsertNode(update)) => {
+            if existing.label != update.label {
+                return false;
+            }
+            match (existing.props.as_object_mut(), update.props.as_object()) {
+                (Some(existing_props), Some(update_props)) => {
+                    for (key, value) in update_props {
+                        existing_props.insert(key.clone(), value.clone());
+                    }
+                }
+                _ => existing.props = update.props.clone(),
+            }
+            true
+        }
+        (GraphMutation::DeleteNode { .. }, GraphMutation::DeleteNode { .. })
+        | (GraphMutation::DeleteEdge { .. }, GraphMutation::DeleteEdge { .. }) => true,
+        _ => false,
+    }
+}
+
+fn expect_upserted(outcome: WriteOutcome) -> Result<Uuid, GraphError> {
+    match outcome {
+        WriteOutcome::Upserted(id) => Ok(id),
+        other => Err(GraphError::DatabaseError(format!("Unexpected write outcome: {:?}", other))),
+    }
+}
+
+fn expect_deleted(outcome: WriteOutcome) -> Result<bool, GraphError> {
+    match outcome {
+        WriteOutcome::Deleted(deleted) => Ok(deleted),
+        other => Err(GraphError::DatabaseError(format!("Unexpected write outcome: {:?}", other))),
+    }
+}
+
+#[async_trait]
+impl GraphStore for BatchingGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        expect_upserted(self.write(tenant, GraphMutation::UpsertNode(node)).await?)
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        expect_upserted(self.write(tenant, GraphMutation::UpsertEdge(edge)).await?)
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        // Edge targets may still be buffered
+        self.flush().await;
+        self.shared.inner.upsert_node_with_edges(tenant, node, edges).await
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        // Bulk loads are chunked by the store already; buffered writes go first
+        self.flush().await;
+        self.shared.inner.bulk_load_nodes(tenant, nodes).await
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        // Edge endpoints may still be buffered
+        self.flush().await;
+        self.shared.inner.bulk_load_edges(tenant, edges).await
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.shared.inner.query(tenant, query).await
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.shared.inner.query_count(tenant, query).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.shared.inner.query_exists(tenant, query).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.shared.inner.traverse(tenant, request).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.shared.inner.shortest_path(tenant, request).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.shared.inner.timeline(tenant, request).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.shared.inner.property_history(tenant, node_id, key).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        // Apply after writes to the node that are still buffered
+        self.flush().await;
+        self.shared.inner.update_node(tenant, id, node).await
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.shared.inner.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.shared.inner.set_edge_constraints(tenant, constraints).await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.shared.inner.get_node(tenant, id).await
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        self.shared.inner.get_node_by_alias(tenant, id_alias).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.shared.inner.resolve_aliases(tenant, aliases).await
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        expect_deleted(self.write(tenant, GraphMutation::DeleteNode { id }).await?)
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        expect_deleted(self.write(tenant, GraphMutation::DeleteEdge { id }).await?)
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        // Apply after writes to the edge that are still buffered
+        self.flush().await;
+        self.shared.inner.close_edge(tenant, id, valid_to).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.flush().await;
+        self.shared.inner.supersede_edge(tenant, id, edge).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.flush().await;
+        self.shared.inner.retract_edge(tenant, id).await
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        self.shared.inner.get_node_history(tenant, id).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        // Include writes that were accepted before the snapshot was requested
+        self.flush().await;
+        self.shared.inner.snapshot(tenant, valid_at).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.flush().await;
+        self.shared.inner.materialize_snapshot(tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.shared.inner.list_snapshots(tenant).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.shared.inner.drop_snapshot(tenant, name).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.shared.inner.query_snapshot(tenant, name, query).await
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        self.flush().await;
+        self.shared.inner.read_history(tenant, before).await
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.flush().await;
+        self.shared.inner.purge_history(tenant, before).await
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        self.flush().await;
+        self.shared.inner.restore_history(tenant, batch).await
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.flush().await;
+        self.shared.inner.purge_expired_edges(tenant, now).await
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        // Count writes that were accepted before the summary was requested
+        self.flush().await;
+        self.shared.inner.summary(tenant).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.flush().await;
+        self.shared.inner.catalog(tenant).await
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        // Include tenants whose first writes are still buffered
+        self.flush().await;
+        self.shared.inner.list_tenants().await
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        // Buffered writes to the tenant are cleared with the rest of it
+        self.flush().await;
+        self.shared.inner.clear_tenant(tenant).await
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.shared.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write_concern::with_write_concern;
+    use serde_json::json;
+
+    /// Store that records every node write it receives
+    #[derive(Default)]
+    struct RecordingStore {
+        nodes: std::sync::Mutex<Vec<Node>>,
+    }
+
+    #[async_trait]
+    impl GraphStore for RecordingStore {
+        async fn upsert_node(&self, _tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+            self.nodes.lock().unwrap().push(node);
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_edge(&self, _tenant: &TenantId, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_node_with_edges(&self, _tenant: &TenantId, _node: Node, _edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+            Ok(NodeWithEdges { node_id: Uuid::new_v4(), edge_ids: Vec::new() })
+        }
+
+        async fn query(&self, _tenant: &TenantId, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_node(&self, _tenant: &TenantId, _id: Uuid) -> Result<Option<Node>, GraphError> {
+            Ok(None)
+        }
+
+        async fn get_node_by_alias(&self, _tenant: &TenantId, _id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+            Ok(None)
+        }
+
+        async fn resolve_aliases(&self, _tenant: &TenantId, _aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+            Ok(HashMap::new())
+        }
+
+        async fn delete_node(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn delete_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn close_edge(&self, _tenant: &TenantId, _id: Uuid, _valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn supersede_edge(&self, _tenant: &TenantId, _id: Uuid, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn retract_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn get_node_history(&self, _tenant: &TenantId, _id: Uuid) -> Result<Vec<Node>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn snapshot(&self, _tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+            Ok(GraphSnapshot { snapshot_at: Utc::now(), valid_at, nodes: Vec::new(), edges: Vec::new() })
+        }
+
+        async fn materialize_snapshot(&self, _tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+            Ok(SnapshotInfo {
+                name: name.to_string(),
+                valid_at,
+                snapshot_at: Utc::now(),
+                node_count: 0,
+                edge_count: 0,
+            })
+        }
+
+        async fn list_snapshots(&self, _tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn drop_snapshot(&self, _tenant: &TenantId, _name: &str) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn query_snapshot(&self, _tenant: &TenantId, _name: &str, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn read_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+            Ok(ArchiveBatch::default())
+        }
+
+        async fn purge_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn restore_history(&self, _tenant: &TenantId, _batch: ArchiveBatch) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn purge_expired_edges(&self, _tenant: &TenantId, _now: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn summary(&self, _tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+            Ok(GraphSummary::default())
+        }
+
+        async fn catalog(&self, _tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+            Ok(GraphCatalog::default())
+        }
+
+        async fn clear_tenant(&self, _tenant: &TenantId) -> Result<u64, GraphError> {
+            Ok(self.nodes.lock().unwrap().drain(..).count() as u64)
+        }
+
+        async fn health_check(&self) -> Result<(), GraphError> {
+            Ok(())
+        }
+    }
+
+    fn batching_config() -> BatchingConfig {
+        BatchingConfig {
+            max_batch_size: 100,
+            flush_interval_ms: 60_000,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coalesces_node_updates() {
+        let inner = Arc::new(RecordingStore::default());
+        let store = BatchingGraphStore::new(inner.clone(), batching_config());
+        let tenant = TenantId::new("tenant");
+
+        let first = store.enqueue(&tenant, GraphMutation::UpsertNode(
+            Node::new("Person").with_id_alias("alice").with_props(json!({"name": "Alice", "age": 30}))
+        )).await.unwrap();
+        let second = store.enqueue(&tenant, GraphMutation::UpsertNode(
+            Node::new("Person").with_id_alias("alice").with_props(json!({"age": 31}))
+        )).await.unwrap();
+        store.enqueue(&tenant, GraphMutation::UpsertNode(Node::new("Person").with_id_alias("bob"))).await.unwrap();
+
+        assert_eq!(store.pending().await, 2);
+        assert_eq!(store.flush().await, 2);
+
+        let nodes = inner.nodes.lock().unwrap().clone();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].props, json!({"name": "Alice", "age": 31}));
+
+        // Both callers see the same merged write
+        assert_eq!(first.wait().await.unwrap(), second.wait().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_flushes_on_batch_size_and_shutdown() {
+        let inner = Arc::new(RecordingStore::default());
+        let config = BatchingConfig { max_batch_size: 2, ..batching_config() };
+        let store = BatchingGraphStore::new(inner.clone(), config);
+        let tenant = TenantId::new("tenant");
+
+        for alias in ["a", "b", "c"] {
+            store.enqueue(&tenant, GraphMutation::UpsertNode(Node::new("Item").with_id_alias(alias))).await.unwrap();
+        }
+        assert_eq!(inner.nodes.lock().unwrap().len(), 2);
+        assert_eq!(store.pending().await, 1);
+
+        assert_eq!(store.shutdown().await, 1);
+        assert_eq!(inner.nodes.lock().unwrap().len(), 3);
+        assert!(store.enqueue(&tenant, GraphMutation::DeleteNode { id: Uuid::new_v4() }).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_clear_tenant_includes_buffered_writes() {
+        let inner = Arc::new(RecordingStore::default());
+        let store = BatchingGraphStore::new(inner.clone(), batching_config());
+        let tenant = TenantId::new("tenant");
+
+        store.enqueue(&tenant, GraphMutation::UpsertNode(Node::new("Item"))).await.unwrap();
+        assert_eq!(store.clear_tenant(&tenant).await.unwrap(), 1);
+        assert_eq!(store.pending().await, 0);
+        assert!(inner.nodes.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trait_writes_wait_for_flush() {
+        let inner = Arc::new(RecordingStore::default());
+        let config = BatchingConfig { flush_interval_ms: 10, ..batching_config() };
+        let store = BatchingGraphStore::new(inner.clone(), config);
+        let tenant = TenantId::new("tenant");
+
+        store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice")).await.unwrap();
+        assert_eq!(inner.nodes.lock().unwrap().len(), 1);
+        assert!(store.delete_node(&tenant, Uuid::new_v4()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_write_concern() {
+        let inner = Arc::new(RecordingStore::default());
+        let store = BatchingGraphStore::new(inner.clone(), batching_config());
+        let tenant = TenantId::new("tenant");
+
+        // Buffered writes are acknowledged before they are flushed
+        let id = with_write_concern(WriteConcern::Buffered, store.upsert_node(&tenant, Node::new("Person").with_id_alias("alice"))).await.unwrap();
+        assert!(id.is_nil());
+        assert_eq!(store.pending().await, 1);
+        assert!(inner.nodes.lock().unwrap().is_empty());
+
+        // Replicated writes flush what is buffered, then write through
+        let id = with_write_concern(WriteConcern::Replicated, store.upsert_node(&tenant, Node::new("Person").with_id_alias("bob"))).await.unwrap();
+        assert!(!id.is_nil());
+        assert_eq!(store.pending().await, 0);
+        let aliases: Vec<_> = inner.nodes.lock().unwrap().iter().map(|node| node.id_alias.clone().unwrap()).collect();
+        assert_eq!(aliases, ["alice", "bob"]);
+    }
+}
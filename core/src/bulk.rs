@@ -0,0 +1,91 @@
+//! Streaming bulk loads
+//!
+//! Upserting items one at a time costs a round trip, and for most stores a
+//! commit, per item, which is far too slow for ingests of millions of rows.
+//! `GraphStore::bulk_load_nodes` and `GraphStore::bulk_load_edges` take a
+//! stream of items instead, so a load is never held in memory at once, and
+//! stores commit them in chunks of a size that suits them. Chunks committed
+//! before a failure stay committed.
+//!
+//! Stores without a faster path, and decorators that act on every item,
+//! upsert the items one at a time with [`upsert_nodes_each`] and
+//! [`upsert_edges_each`]. Streams of fallible items, such as parsed request
+//! bodies, are loaded up to their first error with [`until_error`].
+
+use crate::errors::GraphError;
+use crate::traits::GraphStore;
+use crate::types::{Node, TenantId, TimeEdge};
+use futures::stream::{BoxStream, Stream, StreamExt};
+use std::sync::{Arc, Mutex};
+
+/// Nodes to bulk load
+pub type NodeStream<'a> = BoxStream<'a, Node>;
+
+/// Edges to bulk load
+pub type EdgeStream<'a> = BoxStream<'a, TimeEdge>;
+
+/// Upsert streamed nodes one at a time; returns the number upserted
+pub async fn upsert_nodes_each<S: GraphStore + ?Sized>(store: &S, tenant: &TenantId, mut nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+    let mut loaded = 0;
+    while let Some(node) = nodes.next().await {
+        store.upsert_node(tenant, node).await?;
+        loaded += 1;
+    }
+    Ok(loaded)
+}
+
+/// Upsert streamed edges one at a time; returns the number upserted
+pub async fn upsert_edges_each<S: GraphStore + ?Sized>(store: &S, tenant: &TenantId, mut edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+    let mut loaded = 0;
+    while let Some(edge) = edges.next().await {
+        store.upsert_edge(tenant, edge).await?;
+        loaded += 1;
+    }
+    Ok(loaded)
+}
+
+/// The first error of a stream passed to [`until_error`], once it ended
+pub struct FirstError<E>(Arc<Mutex<Option<E>>>);
+
+impl<E> FirstError<E> {
+    /// Take the error, if the stream ended on one
+    pub fn take(&self) -> Option<E> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// The items of a stream of results up to its first error, which is kept
+/// for after the stream was consumed
+pub fn until_error<'a, T, E>(items: impl Stream<Item = Result<T, E>> + Send + 'a) -> (BoxStream<'a, T>, FirstError<E>)
+where
+    T: Send + 'a,
+    E: Send + 'a,
+{
+    let slot = Arc::new(Mutex::new(None));
+    let first_error = FirstError(slot.clone());
+    let items = items
+        .map(move |item| item.map_err(|e| *slot.lock().unwrap() = Some(e)))
+        .take_while(|item| futures::future::ready(item.is_ok()))
+        .filter_map(|item| futures::future::ready(item.ok()))
+        .boxed();
+    (items, first_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    #[tokio::test]
+    async fn test_until_error_stops_at_first_error() {
+        let items = stream::iter(vec![Ok(1), Ok(2), Err("bad row"), Ok(3), Err("later")]);
+        let (items, first_error) = until_error(items);
+        assert!(first_error.take().is_none());
+        assert_eq!(items.collect::<Vec<_>>().await, vec![1, 2]);
+        assert_eq!(first_error.take(), Some("bad row"));
+
+        let (items, first_error) = until_error(stream::iter(vec![Ok::<_, ()>(1)]));
+        assert_eq!(items.count().await, 1);
+        assert!(first_error.take().is_none());
+    }
+}
@@ -0,0 +1,292 @@
+//! Opt-in capture of requests for replay
+//!
+//! When capture is enabled for a tenant, a sample of its requests is written
+//! to disk with the operation's input and output, so a request a user reports
+//! by ID can be re-executed against another environment. Each capture is one
+//! JSON file under `<dir>/<tenant>/`, named so that listing the directory
+//! orders captures by time, and the oldest are removed once a tenant has more
+//! than `max_per_tenant`.
+
+use crate::errors::CoreError;
+use crate::traits::RequestContext;
+use crate::types::TenantId;
+use crate::vector_index::partition_dir;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Context attribute holding the HTTP status of the captured response
+pub const STATUS_ATTRIBUTE: &str = "http_status";
+
+/// Configuration for request capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureConfig {
+    /// Directory holding one subdirectory of captures per tenant
+    pub dir: PathBuf,
+    /// Share of requests captured for each tenant that has capture enabled
+    #[serde(default)]
+    pub tenants: HashMap<TenantId, f64>,
+    /// Largest request or response body captured, in bytes
+    pub max_body_bytes: usize,
+    /// Captures kept per tenant; older ones are removed
+    pub max_per_tenant: usize,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("data/captures"),
+            tenants: HashMap::new(),
+            max_body_bytes: 1024 * 1024,
+            max_per_tenant: 1000,
+        }
+    }
+}
+
+/// A request as captured for replay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedRequest {
+    pub request_id: Uuid,
+    pub tenant: TenantId,
+    pub method: String,
+    /// Path and query of the request, including the API version prefix
+    pub path: String,
+    /// Request body, if it was JSON
+    pub input: Option<serde_json::Value>,
+    /// Response body, if it was JSON
+    pub output: Option<serde_json::Value>,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub captured_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+impl CapturedRequest {
+    /// Build a capture from a completed request context; `None` if the
+    /// request had no tenant
+    pub fn from_context(ctx: &RequestContext) -> Option<Self> {
+        Some(Self {
+            request_id: ctx.request_id,
+            tenant: ctx.tenant_id.clone()?,
+            method: ctx.method.clone(),
+            path: ctx.path.clone(),
+            input: ctx.core_operation_input.clone(),
+            output: ctx.core_operation_output.clone(),
+            status: ctx.get_attribute(STATUS_ATTRIBUTE)
+                .and_then(|status| status.as_u64())
+                .map(|status| status as u16),
+            error: ctx.error.clone(),
+            captured_at: Utc::now(),
+            duration_ms: ctx.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+/// Per-tenant request capture, persisted to disk
+pub struct RequestCapture {
+    config: CaptureConfig,
+    sample_rates: RwLock<HashMap<TenantId, f64>>,
+}
+
+impl RequestCapture {
+    /// Create a capture store, enabling the tenants listed in the configuration
+    pub fn new(config: CaptureConfig) -> Self {
+        let sample_rates = config.tenants.iter()
+            .map(|(tenant, rate)| (tenant.clone(), rate.clamp(0.0, 1.0)))
+            .collect();
+        Self {
+            config,
+            sample_rates: RwLock::new(sample_rates),
+        }
+    }
+
+    /// Largest request or response body captured, in bytes
+    pub fn max_body_bytes(&self) -> usize {
+        self.config.max_body_bytes
+    }
+
+    /// Capture `sample_rate` of the tenant's requests from now on
+    pub fn enable(&self, tenant: &TenantId, sample_rate: f64) -> Result<(), CoreError> {
+        if !(0.0..=1.0).contains(&sample_rate) {
+            return Err(CoreError::Configuration(format!("Sample rate {} is not between 0 and 1", sample_rate)));
+        }
+        info!("Capturing {:.0}% of requests for tenant {}", sample_rate * 100.0, tenant);
+        self.sample_rates.write().unwrap().insert(tenant.clone(), sample_rate);
+        Ok(())
+    }
+
+    /// Stop capturing the tenant's requests; existing captures are kept
+    pub fn disable(&self, tenant: &TenantId) -> bool {
+        self.sample_rates.write().unwrap().remove(tenant).is_some()
+    }
+
+    /// The tenant's sample rate, if capture is enabled for it
+    pub fn sample_rate(&self, tenant: &TenantId) -> Option<f64> {
+        self.sample_rates.read().unwrap().get(tenant).copied()
+    }
+
+    /// Whether the request should be captured. Sampling is decided by the
+    /// request ID, so the decision is the same wherever it is made.
+    pub fn should_capture(&self, tenant: &TenantId, request_id: Uuid) -> bool {
+        let Some(rate) = self.sample_rate(tenant) else {
+            return false;
+        };
+        let sample = (request_id.as_u128() as u64) as f64 / u64::MAX as f64;
+        sample < rate
+    }
+
+    /// Persist a completed request if its tenant is capturing and it is
+    /// sampled; returns whether it was captured
+    pub async fn record(&self, ctx: &RequestContext) -> Result<bool, CoreError> {
+        let Some(captured) = CapturedRequest::from_context(ctx) else {
+            return Ok(false);
+        };
+        if !self.should_capture(&captured.tenant, captured.request_id) {
+            return Ok(false);
+        }
+
+        let dir = partition_dir(&self.config.dir, &captured.tenant);
+        tokio::fs::create_dir_all(&dir).await.map_err(|e| io_error(&dir, e))?;
+
+        let path = dir.join(file_name(&captured));
+        let data = serde_json::to_vec_pretty(&captured)?;
+        tokio::fs::write(&path, data).await.map_err(|e| io_error(&path, e))?;
+        debug!("Captured request {} for tenant {}", captured.request_id, captured.tenant);
+
+        self.prune(&dir).await?;
+        Ok(true)
+    }
+
+    /// Read a captured request
+    pub async fn get(&self, tenant: &TenantId, request_id: Uuid) -> Result<Option<CapturedRequest>, CoreError> {
+        let suffix = format!("-{}.json", request_id);
+        let Some(path) = self.files(tenant).await?.into_iter()
+            .find(|path| path.to_string_lossy().ends_with(&suffix)) else {
+            return Ok(None);
+        };
+
+        let data = tokio::fs::read(&path).await.map_err(|e| io_error(&path, e))?;
+        Ok(Some(serde_json::from_slice(&data)?))
+    }
+
+    /// The tenant's captured requests, oldest first
+    pub async fn list(&self, tenant: &TenantId) -> Result<Vec<CapturedRequest>, CoreError> {
+        let mut captures = Vec::new();
+        for path in self.files(tenant).await? {
+            let data = tokio::fs::read(&path).await.map_err(|e| io_error(&path, e))?;
+            captures.push(serde_json::from_slice(&data)?);
+        }
+        Ok(captures)
+    }
+
+    /// Capture files of a tenant, oldest first
+    async fn files(&self, tenant: &TenantId) -> Result<Vec<PathBuf>, CoreError> {
+        files_in(&partition_dir(&self.config.dir, tenant)).await
+    }
+
+    /// Remove the oldest captures beyond the per-tenant limit
+    async fn prune(&self, dir: &Path) -> Result<(), CoreError> {
+        let files = files_in(dir).await?;
+        let excess = files.len().saturating_sub(self.config.max_per_tenant);
+        for path in &files[..excess] {
+            tokio::fs::remove_file(path).await.map_err(|e| io_error(path, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Name of a capture file; the zero-padded time prefix orders files by time
+fn file_name(captured: &CapturedRequest) -> String {
+    format!("{:016}-{}.json", captured.captured_at.timestamp_micros(), captured.request_id)
+}
+
+/// JSON files in a directory, sorted by name; empty if it does not exist
+pub(crate) async fn files_in(dir: &Path) -> Result<Vec<PathBuf>, CoreError> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_error(dir, e)),
+    };
+
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(|e| io_error(dir, e))? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> CoreError {
+    CoreError::Internal(format!("Request capture {}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(max_per_tenant: usize) -> RequestCapture {
+        let dir = std::env::temp_dir().join(format!("telamentis-capture-{}", Uuid::new_v4()));
+        RequestCapture::new(CaptureConfig { dir, max_per_tenant, ..CaptureConfig::default() })
+    }
+
+    fn request(tenant: &TenantId) -> RequestContext {
+        let mut ctx = RequestContext::new("POST".to_string(), format!("/v1/graph/{}/nodes", tenant));
+        ctx.tenant_id = Some(tenant.clone());
+        ctx.core_operation_input = Some(serde_json::json!({"node": {"label": "Person"}}));
+        ctx.core_operation_output = Some(serde_json::json!({"success": true}));
+        ctx.set_attribute(STATUS_ATTRIBUTE, serde_json::json!(200));
+        ctx
+    }
+
+    #[tokio::test]
+    async fn test_capture_is_opt_in_per_tenant() {
+        let capture = capture(10);
+        let tenant = TenantId::new("tenant/one");
+        let ctx = request(&tenant);
+
+        assert!(!capture.record(&ctx).await.unwrap());
+        capture.enable(&tenant, 1.0).unwrap();
+        assert!(capture.record(&ctx).await.unwrap());
+        assert!(!capture.record(&request(&TenantId::new("other"))).await.unwrap());
+
+        let captured = capture.get(&tenant, ctx.request_id).await.unwrap().unwrap();
+        assert_eq!(captured.path, ctx.path);
+        assert_eq!(captured.input, ctx.core_operation_input);
+        assert_eq!(captured.status, Some(200));
+        assert!(capture.get(&tenant, Uuid::new_v4()).await.unwrap().is_none());
+
+        assert!(capture.enable(&tenant, 1.5).is_err());
+        assert!(capture.disable(&tenant));
+        assert!(!capture.record(&request(&tenant)).await.unwrap());
+        assert_eq!(capture.list(&tenant).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sampling_and_retention() {
+        let capture = capture(3);
+        let tenant = TenantId::new("tenant");
+
+        capture.enable(&tenant, 0.0).unwrap();
+        assert!(!capture.record(&request(&tenant)).await.unwrap());
+
+        capture.enable(&tenant, 1.0).unwrap();
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            let ctx = request(&tenant);
+            capture.record(&ctx).await.unwrap();
+            ids.push(ctx.request_id);
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+
+        let kept: Vec<Uuid> = capture.list(&tenant).await.unwrap().iter().map(|c| c.request_id).collect();
+        assert_eq!(kept, ids[2..]);
+    }
+}
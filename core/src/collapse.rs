@@ -0,0 +1,140 @@
+//! Collapsing edge versions into aggregate relationships
+//!
+//! Relationship queries with a [`Collapse`] return one relationship per
+//! group of matching edges instead of every version: per start node, end
+//! node and kind, or per start and end node. Stores that read matching
+//! edges themselves group them with [`collapse_edges`]; the aggregate of a
+//! group travels in [`PathRelationship::collapsed`](crate::types::PathRelationship).
+
+use crate::types::{Collapse, EdgeAggregate, TimeEdge};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A group of edges collapsed into one
+#[derive(Debug, Clone)]
+pub struct CollapsedEdge {
+    /// ID of the latest edge of the group
+    pub id: Uuid,
+    /// The latest edge, with the properties of all edges merged into it
+    pub edge: TimeEdge,
+    pub aggregate: EdgeAggregate,
+}
+
+impl Collapse {
+    /// Name of the collapse, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Collapse::ByKind => "by_kind",
+            Collapse::ByPair => "by_pair",
+        }
+    }
+
+    /// Key of the group an edge falls in
+    pub fn group_key<'a>(&self, edge: &'a TimeEdge) -> (Uuid, Uuid, Option<&'a str>) {
+        let kind = match self {
+            Collapse::ByKind => Some(edge.kind.as_str()),
+            Collapse::ByPair => None,
+        };
+        (edge.from_node_id, edge.to_node_id, kind)
+    }
+}
+
+/// Group edges as `collapse` says. Within a group, edges are merged in order
+/// of `valid_from`, then of transaction time: later edges override the
+/// properties of earlier ones. Groups are in the order of their latest edge,
+/// so sorting the edges first sorts the groups by it.
+pub fn collapse_edges<'a>(edges: impl IntoIterator<Item = (Uuid, &'a TimeEdge)>, collapse: Collapse) -> Vec<CollapsedEdge> {
+    let mut groups: Vec<Vec<(usize, Uuid, &TimeEdge)>> = Vec::new();
+    let mut index: HashMap<(Uuid, Uuid, Option<&str>), usize> = HashMap::new();
+    for (position, (id, edge)) in edges.into_iter().enumerate() {
+        let group = *index.entry(collapse.group_key(edge)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push((position, id, edge));
+    }
+
+    let mut merged: Vec<(usize, CollapsedEdge)> = groups.into_iter().map(merge).collect();
+    merged.sort_by_key(|(position, _)| *position);
+    merged.into_iter().map(|(_, collapsed)| collapsed).collect()
+}
+
+/// Merge the edges of a group, with the position of the latest one
+fn merge(mut versions: Vec<(usize, Uuid, &TimeEdge)>) -> (usize, CollapsedEdge) {
+    versions.sort_by_key(|(_, id, edge)| (edge.valid_from, edge.transaction_start_time, *id));
+
+    let mut props = serde_json::Map::new();
+    let mut kinds: Vec<String> = Vec::new();
+    for (_, _, edge) in &versions {
+        if let Some(edge_props) = edge.props.as_object() {
+            props.extend(edge_props.clone());
+        }
+        if !kinds.contains(&edge.kind) {
+            kinds.push(edge.kind.clone());
+        }
+    }
+    kinds.sort();
+
+    let aggregate = EdgeAggregate {
+        count: versions.len() as u64,
+        valid_from: versions.iter().map(|(_, _, edge)| edge.valid_from).min().expect("groups are not empty"),
+        valid_to: if versions.iter().any(|(_, _, edge)| edge.valid_to.is_none()) {
+            None
+        } else {
+            versions.iter().filter_map(|(_, _, edge)| edge.valid_to).max()
+        },
+        kinds,
+    };
+    let (position, id, latest) = versions.pop().expect("groups are not empty");
+    let mut edge = latest.clone();
+    edge.props = serde_json::Value::Object(props);
+    (position, CollapsedEdge { id, edge, aggregate })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_collapse_edges() {
+        let (alice, acme, globex) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let edges = [
+            (Uuid::new_v4(), TimeEdge::new(alice, acme, "WORKS_FOR", at("2021-01-01T00:00:00Z"), json!({"title": "Engineer", "team": "core"}))
+                .with_valid_to(at("2022-01-01T00:00:00Z"))),
+            (Uuid::new_v4(), TimeEdge::new(alice, globex, "WORKS_FOR", at("2023-01-01T00:00:00Z"), json!({}))),
+            (Uuid::new_v4(), TimeEdge::new(alice, acme, "WORKS_FOR", at("2022-01-01T00:00:00Z"), json!({"title": "CTO"}))
+                .with_valid_to(at("2023-01-01T00:00:00Z"))),
+            (Uuid::new_v4(), TimeEdge::new(alice, acme, "ADVISES", at("2024-01-01T00:00:00Z"), json!({}))),
+        ];
+        let refs = || edges.iter().map(|(id, edge)| (*id, edge));
+
+        let by_kind = collapse_edges(refs(), Collapse::ByKind);
+        assert_eq!(by_kind.len(), 3);
+        // Ordered by their latest edge
+        assert_eq!(by_kind[0].edge.to_node_id, globex);
+        let works_for_acme = &by_kind[1];
+        assert_eq!(works_for_acme.id, edges[2].0);
+        assert_eq!(works_for_acme.edge.props, json!({"title": "CTO", "team": "core"}));
+        assert_eq!(works_for_acme.aggregate, EdgeAggregate {
+            count: 2,
+            valid_from: at("2021-01-01T00:00:00Z"),
+            valid_to: Some(at("2023-01-01T00:00:00Z")),
+            kinds: vec!["WORKS_FOR".to_string()],
+        });
+        assert_eq!(by_kind[2].edge.kind, "ADVISES");
+
+        let by_pair = collapse_edges(refs(), Collapse::ByPair);
+        assert_eq!(by_pair.len(), 2);
+        assert_eq!(by_pair[1].aggregate.count, 3);
+        assert_eq!(by_pair[1].aggregate.kinds, vec!["ADVISES", "WORKS_FOR"]);
+        // An open-ended edge leaves the group open-ended
+        assert_eq!(by_pair[1].aggregate.valid_to, None);
+        assert_eq!(by_pair[1].edge.kind, "ADVISES");
+    }
+}
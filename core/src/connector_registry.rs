@@ -0,0 +1,381 @@
+//! Registry of LLM connectors with per-tenant provider selection
+//!
+//! `ConnectorRegistry` holds connectors by provider name and is itself an
+//! `LlmConnector`, so a `GraphService` serves every provider through one
+//! connector. An extraction goes to the provider named in
+//! `ExtractionContext::provider`, else the tenant's default provider, else the
+//! registry's default; the model is chosen the same way, falling back to the
+//! connector's configured model. Naming a provider or model in a request
+//! requires the tenant's `provider_override` or `model_override` feature flag.
+//! Operators can switch a tenant's default provider while serving, with
+//! [`ConnectorRegistry::set_tenant_provider`].
+
+use crate::errors::LlmError;
+use crate::availability::CapabilityStatus;
+use crate::traits::{CompletionRequest, CompletionResponse, ExtractionContext, ExtractionEnvelope, LlmConnector, ProviderStatus};
+use crate::types::TenantId;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+/// Feature flag letting a tenant's requests choose the provider
+pub const PROVIDER_OVERRIDE: &str = "provider_override";
+
+/// Feature flag letting a tenant's requests choose the model
+pub const MODEL_OVERRIDE: &str = "model_override";
+
+/// Provider settings of a tenant
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderPolicy {
+    /// Provider used when the request names none
+    pub provider: Option<String>,
+    /// Model used when the request names none
+    pub model: Option<String>,
+    /// Providers the tenant may use; empty allows every registered provider
+    pub allowed_providers: Vec<String>,
+    /// Feature flags enabled for the tenant
+    pub features: HashSet<String>,
+}
+
+impl ProviderPolicy {
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    fn allows(&self, provider: &str) -> bool {
+        self.allowed_providers.is_empty() || self.allowed_providers.iter().any(|p| p == provider)
+    }
+}
+
+/// Provider policies by tenant
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderPolicies {
+    /// Policy of tenants without their own
+    pub default: ProviderPolicy,
+    /// Policies by tenant ID
+    pub tenants: HashMap<String, ProviderPolicy>,
+}
+
+impl ProviderPolicies {
+    /// Policy of a tenant
+    pub fn for_tenant(&self, tenant: &TenantId) -> &ProviderPolicy {
+        self.tenants.get(tenant.as_str()).unwrap_or(&self.default)
+    }
+}
+
+/// Connector chosen for a request
+pub struct Selection {
+    pub provider: String,
+    pub connector: Arc<dyn LlmConnector>,
+    /// Model to request, or `None` for the connector's configured model
+    pub model: Option<String>,
+}
+
+/// LLM connectors by provider name
+pub struct ConnectorRegistry {
+    connectors: BTreeMap<String, Arc<dyn LlmConnector>>,
+    default_provider: Option<String>,
+    policies: RwLock<ProviderPolicies>,
+}
+
+impl ConnectorRegistry {
+    /// Create an empty registry with the given tenant policies
+    pub fn new(policies: ProviderPolicies) -> Self {
+        Self {
+            connectors: BTreeMap::new(),
+            default_provider: None,
+            policies: RwLock::new(policies),
+        }
+    }
+
+    /// Register a connector; the first one registered is the default provider
+    pub fn with_connector(mut self, provider: impl Into<String>, connector: Arc<dyn LlmConnector>) -> Self {
+        let provider = provider.into();
+        self.default_provider.get_or_insert_with(|| provider.clone());
+        self.connectors.insert(provider, connector);
+        self
+    }
+
+    /// Use a provider for tenants whose policy names none
+    pub fn with_default_provider(mut self, provider: impl Into<String>) -> Self {
+        self.default_provider = Some(provider.into());
+        self
+    }
+
+    /// Names of the registered providers
+    pub fn providers(&self) -> Vec<&str> {
+        self.connectors.keys().map(String::as_str).collect()
+    }
+
+    /// Connector registered under a provider name
+    pub fn get(&self, provider: &str) -> Option<Arc<dyn LlmConnector>> {
+        self.connectors.get(provider).cloned()
+    }
+
+    /// Current provider policy of a tenant
+    pub fn policy(&self, tenant: &TenantId) -> ProviderPolicy {
+        self.policies.read().unwrap().for_tenant(tenant).clone()
+    }
+
+    /// Make `provider` the tenant's default, or with `None` fall back to the
+    /// registry's default. The provider must be registered and allowed for
+    /// the tenant.
+    pub fn set_tenant_provider(&self, tenant: &TenantId, provider: Option<&str>) -> Result<(), LlmError> {
+        let mut policies = self.policies.write().unwrap();
+        let mut policy = policies.for_tenant(tenant).clone();
+        if let Some(provider) = provider {
+            if !self.connectors.contains_key(provider) {
+                return Err(LlmError::ProviderNotAllowed(format!("Unknown provider '{}'", provider)));
+            }
+            if !policy.allows(provider) {
+                return Err(LlmError::ProviderNotAllowed(format!(
+                    "Provider '{}' is not enabled for tenant {}", provider, tenant
+                )));
+            }
+        }
+
+        policy.provider = provider.map(str::to_string);
+        policies.tenants.insert(tenant.as_str().to_string(), policy);
+        debug!("Default provider of tenant {} set to {:?}", tenant, provider);
+        Ok(())
+    }
+
+    /// Provider and model for a tenant's request, checked against its policy
+    pub fn select(
+        &self,
+        tenant: &TenantId,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<Selection, LlmError> {
+        let policy = self.policy(tenant);
+        let default_provider = policy.provider.as_deref().or(self.default_provider.as_deref());
+
+        if provider.is_some_and(|p| Some(p) != default_provider) && !policy.has_feature(PROVIDER_OVERRIDE) {
+            return Err(LlmError::ProviderNotAllowed(format!(
+                "Tenant {} may not choose the provider per request", tenant
+            )));
+        }
+        if model.is_some_and(|m| Some(m) != policy.model.as_deref()) && !policy.has_feature(MODEL_OVERRIDE) {
+            return Err(LlmError::ProviderNotAllowed(format!(
+                "Tenant {} may not choose the model per request", tenant
+            )));
+        }
+
+        let Some(provider) = provider.or(default_provider) else {
+            return Err(LlmError::CapabilityUnavailable("No LLM connector is configured".to_string()));
+        };
+        if !policy.allows(provider) {
+            return Err(LlmError::ProviderNotAllowed(format!(
+                "Provider '{}' is not enabled for tenant {}", provider, tenant
+            )));
+        }
+        let connector = self.get(provider).ok_or_else(|| {
+            LlmError::ProviderNotAllowed(format!("Unknown provider '{}'", provider))
+        })?;
+
+        Ok(Selection {
+            provider: provider.to_string(),
+            connector,
+            model: model.or(policy.model.as_deref()).map(str::to_string),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmConnector for ConnectorRegistry {
+    async fn extract(&self, tenant: &TenantId, mut context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let selection = self.select(tenant, context.provider.as_deref(), context.model.as_deref())?;
+        debug!("Extracting for tenant {} with provider '{}' (model: {:?})", tenant, selection.provider, selection.model);
+
+        context.provider = Some(selection.provider);
+        context.model = selection.model;
+        selection.connector.extract(tenant, context).await
+    }
+
+    async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let selection = self.select(tenant, None, None)?;
+        debug!("Completing for tenant {} with provider '{}'", tenant, selection.provider);
+        selection.connector.complete(tenant, request).await
+    }
+
+    /// Available while any provider is
+    async fn health(&self) -> CapabilityStatus {
+        let mut reasons = Vec::new();
+        for (provider, connector) in &self.connectors {
+            match connector.health().await {
+                CapabilityStatus::Available => return CapabilityStatus::Available,
+                CapabilityStatus::Unavailable { reason } => reasons.push(format!("{}: {}", provider, reason)),
+            }
+        }
+        CapabilityStatus::Unavailable {
+            reason: if reasons.is_empty() { "No LLM connector is configured".to_string() } else { reasons.join("; ") },
+        }
+    }
+
+    async fn provider_status(&self, tenant: Option<&TenantId>) -> Vec<ProviderStatus> {
+        let policy = tenant.map(|tenant| self.policy(tenant));
+        let default_provider = policy.as_ref().and_then(|p| p.provider.as_deref()).or(self.default_provider.as_deref());
+
+        let mut statuses = Vec::new();
+        for (provider, connector) in &self.connectors {
+            if policy.as_ref().is_some_and(|policy| !policy.allows(provider)) {
+                continue;
+            }
+            let status = connector.health().await;
+            let models = if status.is_available() { connector.list_models().await.unwrap_or_default() } else { Vec::new() };
+            statuses.push(ProviderStatus {
+                provider: provider.clone(),
+                status,
+                models,
+                is_default: Some(provider.as_str()) == default_provider,
+            });
+        }
+        statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{ExtractionMetadata, ModelInfo};
+
+    /// Connector that reports its name and the model it was asked for
+    struct Named(&'static str);
+
+    #[async_trait]
+    impl LlmConnector for Named {
+        async fn extract(&self, _tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+            Ok(ExtractionEnvelope {
+                nodes: vec![],
+                relations: vec![],
+                metadata: Some(ExtractionMetadata {
+                    provider: self.0.to_string(),
+                    model_name: context.model.unwrap_or_default(),
+                    ..ExtractionMetadata::default()
+                }),
+            })
+        }
+
+        async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmError> {
+            match self.0 {
+                "gemini" => Err(LlmError::NetworkError("connection refused".to_string())),
+                name => Ok(vec![ModelInfo { id: format!("{}-model", name), display_name: None }]),
+            }
+        }
+    }
+
+    fn context(provider: Option<&str>, model: Option<&str>) -> ExtractionContext {
+        ExtractionContext {
+            messages: vec![],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: model.map(str::to_string),
+            provider: provider.map(str::to_string),
+            source: None,
+            seed: None,
+            graph_context: None,
+        }
+    }
+
+    fn registry() -> ConnectorRegistry {
+        let mut policies = ProviderPolicies::default();
+        policies.tenants.insert("acme".to_string(), ProviderPolicy {
+            provider: Some("anthropic".to_string()),
+            model: Some("claude-3-haiku".to_string()),
+            allowed_providers: vec!["anthropic".to_string(), "openai".to_string()],
+            features: [PROVIDER_OVERRIDE.to_string()].into(),
+        });
+        ConnectorRegistry::new(policies)
+            .with_connector("openai", Arc::new(Named("openai")))
+            .with_connector("anthropic", Arc::new(Named("anthropic")))
+            .with_connector("gemini", Arc::new(Named("gemini")))
+    }
+
+    async fn extract(registry: &ConnectorRegistry, tenant: &str, context: ExtractionContext) -> Result<(String, String), LlmError> {
+        let envelope = registry.extract(&TenantId::new(tenant), context).await?;
+        let metadata = envelope.metadata.unwrap();
+        Ok((metadata.provider, metadata.model_name))
+    }
+
+    #[tokio::test]
+    async fn test_tenant_and_registry_defaults() {
+        let registry = registry();
+        assert_eq!(registry.providers(), vec!["anthropic", "gemini", "openai"]);
+
+        let acme = extract(&registry, "acme", context(None, None)).await.unwrap();
+        assert_eq!(acme, ("anthropic".to_string(), "claude-3-haiku".to_string()));
+
+        let other = extract(&registry, "other", context(None, None)).await.unwrap();
+        assert_eq!(other, ("openai".to_string(), String::new()));
+    }
+
+    #[tokio::test]
+    async fn test_overrides_follow_feature_flags() {
+        let registry = registry();
+
+        let acme = extract(&registry, "acme", context(Some("openai"), None)).await.unwrap();
+        assert_eq!(acme.0, "openai");
+        assert!(matches!(
+            extract(&registry, "acme", context(Some("gemini"), None)).await,
+            Err(LlmError::ProviderNotAllowed(_))
+        ));
+        assert!(matches!(
+            extract(&registry, "acme", context(None, Some("claude-3-opus"))).await,
+            Err(LlmError::ProviderNotAllowed(_))
+        ));
+
+        // Naming the default is not an override
+        assert!(extract(&registry, "other", context(Some("openai"), None)).await.is_ok());
+        assert!(matches!(
+            extract(&registry, "other", context(Some("anthropic"), None)).await,
+            Err(LlmError::ProviderNotAllowed(_))
+        ));
+        assert!(matches!(
+            ConnectorRegistry::new(ProviderPolicies::default()).extract(&TenantId::new("acme"), context(None, None)).await,
+            Err(LlmError::CapabilityUnavailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_switch_tenant_provider() {
+        let registry = registry();
+        let acme = TenantId::new("acme");
+
+        registry.set_tenant_provider(&acme, Some("openai")).unwrap();
+        assert_eq!(extract(&registry, "acme", context(None, None)).await.unwrap().0, "openai");
+        // The rest of the tenant's policy is kept
+        assert_eq!(registry.policy(&acme).model.as_deref(), Some("claude-3-haiku"));
+
+        assert!(matches!(registry.set_tenant_provider(&acme, Some("gemini")), Err(LlmError::ProviderNotAllowed(_))));
+        assert!(matches!(registry.set_tenant_provider(&acme, Some("mistral")), Err(LlmError::ProviderNotAllowed(_))));
+
+        // Tenants without a policy get one based on the default
+        registry.set_tenant_provider(&TenantId::new("other"), Some("gemini")).unwrap();
+        assert_eq!(extract(&registry, "other", context(None, None)).await.unwrap().0, "gemini");
+        registry.set_tenant_provider(&TenantId::new("other"), None).unwrap();
+        assert_eq!(extract(&registry, "other", context(None, None)).await.unwrap().0, "openai");
+    }
+
+    #[tokio::test]
+    async fn test_provider_status() {
+        let registry = registry();
+
+        let all = registry.provider_status(None).await;
+        let summary: Vec<_> = all.iter().map(|p| (p.provider.as_str(), p.status.is_available(), p.models.len(), p.is_default)).collect();
+        assert_eq!(summary, vec![("anthropic", true, 1, false), ("gemini", false, 0, false), ("openai", true, 1, true)]);
+        assert!(registry.health().await.is_available());
+
+        // Only the tenant's providers, with its default
+        let acme = registry.provider_status(Some(&TenantId::new("acme"))).await;
+        let summary: Vec<_> = acme.iter().map(|p| (p.provider.as_str(), p.is_default)).collect();
+        assert_eq!(summary, vec![("anthropic", true), ("openai", false)]);
+        assert_eq!(acme[0].models[0].id, "anthropic-model");
+    }
+}
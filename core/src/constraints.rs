@@ -0,0 +1,158 @@
+//! Temporal exclusivity of relationship kinds
+//!
+//! A kind declared exclusive, e.g. `MARRIED_TO` or `CURRENT_EMPLOYER`, lets a
+//! node have at most one outgoing edge of that kind valid at any time. Stores
+//! check every new edge of an exclusive kind against the current versions of
+//! the source node's edges of the same kind, with [`EdgeConstraints::plan`]:
+//! under `ExclusivityPolicy::Reject` an overlapping write fails with
+//! `GraphError::ConstraintViolation`; under `ExclusivityPolicy::AutoClose` the
+//! edges it overlaps are closed when the new edge becomes valid, as
+//! `close_edge` would. An edge that started at or after the new one cannot
+//! be closed that way, so overlapping it is always rejected.
+//!
+//! Constraints are set per tenant through `GraphStore::set_edge_constraints`.
+
+use crate::errors::GraphError;
+use crate::types::TimeEdge;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// How a write overlapping an edge of an exclusive kind is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusivityPolicy {
+    /// Fail the write with `GraphError::ConstraintViolation`
+    #[default]
+    Reject,
+    /// Close the overlapped edges when the new edge becomes valid
+    AutoClose,
+}
+
+/// Relationship constraints of a tenant
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EdgeConstraints {
+    /// Kinds of which a node may have one valid outgoing edge at a time
+    pub exclusive: BTreeMap<String, ExclusivityPolicy>,
+}
+
+/// Edge to close for a new edge to be written, and its new `valid_to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeClosure {
+    pub id: Uuid,
+    pub valid_to: DateTime<Utc>,
+}
+
+impl EdgeConstraints {
+    /// Declare a kind exclusive
+    pub fn with_exclusive(mut self, kind: impl Into<String>, policy: ExclusivityPolicy) -> Self {
+        self.exclusive.insert(kind.into(), policy);
+        self
+    }
+
+    /// Policy of an exclusive kind; `None` if the kind is not exclusive
+    pub fn exclusivity(&self, kind: &str) -> Option<ExclusivityPolicy> {
+        self.exclusive.get(kind).copied()
+    }
+
+    /// Edges to close before `edge` is written, given the current versions
+    /// of its source node's edges of the same kind. Fails if `edge`
+    /// overlaps one that may not be closed. Expired edges never conflict.
+    pub fn plan<'a>(
+        &self,
+        edge: &TimeEdge,
+        existing: impl IntoIterator<Item = (Uuid, &'a TimeEdge)>,
+    ) -> Result<Vec<EdgeClosure>, GraphError> {
+        let Some(policy) = self.exclusivity(&edge.kind) else {
+            return Ok(Vec::new());
+        };
+
+        let mut closures = Vec::new();
+        for (id, other) in existing {
+            if other.kind != edge.kind || other.from_node_id != edge.from_node_id
+                || !other.is_current_version() || other.is_expired_at(Utc::now()) || !overlaps(edge, other) {
+                continue;
+            }
+            if policy == ExclusivityPolicy::Reject || other.valid_from >= edge.valid_from {
+                return Err(GraphError::ConstraintViolation(format!(
+                    "{} is exclusive, and node {} already has edge {} valid from {}",
+                    edge.kind, edge.from_node_id, id, other.valid_from
+                )));
+            }
+            closures.push(EdgeClosure { id, valid_to: edge.valid_from });
+        }
+        Ok(closures)
+    }
+
+    /// Check edges written together, in order, as `plan` checks each of them
+    /// once those before it are written; `existing` are the current
+    /// versions of their source node's edges
+    pub fn check_batch(&self, edges: &[TimeEdge], existing: Vec<(Uuid, TimeEdge)>) -> Result<(), GraphError> {
+        let mut written = existing;
+        for edge in edges {
+            for closure in self.plan(edge, written.iter().map(|(id, edge)| (*id, edge)))? {
+                if let Some((_, closed)) = written.iter_mut().find(|(id, _)| *id == closure.id) {
+                    closed.valid_to = Some(closure.valid_to);
+                }
+            }
+            written.push((Uuid::new_v4(), edge.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the valid-time intervals of two edges intersect
+fn overlaps(a: &TimeEdge, b: &TimeEdge) -> bool {
+    let before_end = |time: DateTime<Utc>, end: Option<DateTime<Utc>>| end.is_none_or(|end| time < end);
+    before_end(a.valid_from, b.valid_to) && before_end(b.valid_from, a.valid_to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn edge(from: Uuid, kind: &str, valid_from: DateTime<Utc>, valid_to: Option<DateTime<Utc>>) -> TimeEdge {
+        let mut edge = TimeEdge::new(from, Uuid::new_v4(), kind, valid_from, serde_json::json!({}));
+        edge.valid_to = valid_to;
+        edge
+    }
+
+    #[test]
+    fn test_exclusivity_plan() {
+        let alice = Uuid::new_v4();
+        let t0 = Utc::now() - Duration::days(30);
+        let t1 = t0 + Duration::days(10);
+        let t2 = t0 + Duration::days(20);
+        let married = (Uuid::new_v4(), edge(alice, "MARRIED_TO", t0, None));
+        let existing = [(married.0, &married.1)];
+
+        // Kinds that are not exclusive are not checked
+        let constraints = EdgeConstraints::default();
+        assert!(constraints.plan(&edge(alice, "MARRIED_TO", t1, None), existing).unwrap().is_empty());
+
+        let constraints = EdgeConstraints::default().with_exclusive("MARRIED_TO", ExclusivityPolicy::Reject);
+        assert!(matches!(
+            constraints.plan(&edge(alice, "MARRIED_TO", t1, None), existing),
+            Err(GraphError::ConstraintViolation(_))
+        ));
+        // Intervals that only touch, or other source nodes, do not overlap
+        let closed = (married.0, married.1.clone().with_valid_to(t1));
+        assert!(constraints.plan(&edge(alice, "MARRIED_TO", t1, None), [(closed.0, &closed.1)]).unwrap().is_empty());
+        assert!(constraints.plan(&edge(Uuid::new_v4(), "MARRIED_TO", t1, None), existing).unwrap().is_empty());
+
+        let constraints = EdgeConstraints::default().with_exclusive("MARRIED_TO", ExclusivityPolicy::AutoClose);
+        let closures = constraints.plan(&edge(alice, "MARRIED_TO", t2, None), existing).unwrap();
+        assert_eq!(closures, vec![EdgeClosure { id: married.0, valid_to: t2 }]);
+        // An edge valid before the existing one cannot close it
+        assert!(constraints.plan(&edge(alice, "MARRIED_TO", t0 - Duration::days(1), Some(t1)), existing).is_err());
+
+        // Within a batch, later edges close earlier ones
+        let batch = [edge(alice, "MARRIED_TO", t1, None), edge(alice, "MARRIED_TO", t2, None)];
+        assert!(constraints.check_batch(&batch, vec![married.clone()]).is_ok());
+        let constraints = EdgeConstraints::default().with_exclusive("MARRIED_TO", ExclusivityPolicy::Reject);
+        assert!(constraints.check_batch(&batch, Vec::new()).is_err());
+    }
+}
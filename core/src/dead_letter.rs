@@ -0,0 +1,229 @@
+//! Dead-letter queue for failed ingestion records
+//!
+//! Batch and streaming ingestion keep going when a single record fails, so
+//! a failed node or edge would otherwise only show up in the logs. The
+//! ingestion paths record each such record here, per tenant, together with
+//! the error, where it came from and any context the caller has (the import
+//! row, the batch). Operators list the entries, retry them once the cause is
+//! fixed (a successful retry removes the entry) or purge them.
+//!
+//! Edges are kept as submitted, alias references included, so that an edge
+//! that failed because an endpoint did not exist yet succeeds on retry once
+//! the node has been ingested. The queue lives in memory and holds at most
+//! `max_per_tenant` entries per tenant, dropping the oldest.
+
+use crate::errors::GraphError;
+use crate::ingest_template::resolve_edge_aliases;
+use crate::traits::GraphService;
+use crate::types::{Node, TenantId, TimeEdge};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Configuration for [`DeadLetterQueue`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeadLetterConfig {
+    /// Entries kept per tenant; the oldest are dropped beyond this
+    pub max_per_tenant: usize,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self { max_per_tenant: 10_000 }
+    }
+}
+
+/// The record that failed to be written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum DeadLetterRecord {
+    Node(Node),
+    Edge(TimeEdge),
+}
+
+/// A failed ingestion record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub tenant: TenantId,
+    pub record: DeadLetterRecord,
+    /// Error of the last attempt
+    pub error: String,
+    /// Ingestion path the record came through, e.g. `http`, `uds` or `csv-import`
+    pub source: String,
+    /// Caller-supplied details, such as the import row or template
+    #[serde(default)]
+    pub context: BTreeMap<String, String>,
+    /// Failed attempts, counting the original one
+    pub attempts: u32,
+    pub first_failed_at: DateTime<Utc>,
+    pub last_failed_at: DateTime<Utc>,
+}
+
+/// Outcome of retrying dead letters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryReport {
+    /// Entries written and removed from the queue
+    pub succeeded: Vec<Uuid>,
+    /// Entries that failed again and stay queued
+    pub failed: Vec<Uuid>,
+}
+
+/// In-memory per-tenant queue of failed ingestion records
+#[derive(Debug, Default)]
+pub struct DeadLetterQueue {
+    config: DeadLetterConfig,
+    entries: RwLock<HashMap<TenantId, VecDeque<DeadLetter>>>,
+}
+
+impl DeadLetterQueue {
+    pub fn new(config: DeadLetterConfig) -> Self {
+        Self { config, entries: RwLock::new(HashMap::new()) }
+    }
+
+    /// Queue a record that failed with `error`; returns the entry ID
+    pub fn record(
+        &self,
+        tenant: &TenantId,
+        record: DeadLetterRecord,
+        error: impl ToString,
+        source: &str,
+        context: BTreeMap<String, String>,
+    ) -> Uuid {
+        let now = Utc::now();
+        let entry = DeadLetter {
+            id: Uuid::new_v4(),
+            tenant: tenant.clone(),
+            record,
+            error: error.to_string(),
+            source: source.to_string(),
+            context,
+            attempts: 1,
+            first_failed_at: now,
+            last_failed_at: now,
+        };
+        let id = entry.id;
+
+        let mut entries = self.entries.write().unwrap();
+        let queue = entries.entry(tenant.clone()).or_default();
+        queue.push_back(entry);
+        if queue.len() > self.config.max_per_tenant {
+            queue.pop_front();
+            warn!("Dead-letter queue of tenant {} is full, dropped its oldest entry", tenant);
+        }
+        id
+    }
+
+    /// A tenant's entries, oldest first
+    pub fn list(&self, tenant: &TenantId) -> Vec<DeadLetter> {
+        self.entries.read().unwrap()
+            .get(tenant)
+            .map(|queue| queue.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, tenant: &TenantId, id: Uuid) -> Option<DeadLetter> {
+        self.entries.read().unwrap()
+            .get(tenant)
+            .and_then(|queue| queue.iter().find(|entry| entry.id == id).cloned())
+    }
+
+    /// Remove an entry; returns whether it existed
+    pub fn remove(&self, tenant: &TenantId, id: Uuid) -> bool {
+        let mut entries = self.entries.write().unwrap();
+        let Some(queue) = entries.get_mut(tenant) else {
+            return false;
+        };
+        let before = queue.len();
+        queue.retain(|entry| entry.id != id);
+        queue.len() < before
+    }
+
+    /// Remove all of a tenant's entries; returns how many there were
+    pub fn purge(&self, tenant: &TenantId) -> usize {
+        let removed = self.entries.write().unwrap().remove(tenant).map_or(0, |queue| queue.len());
+        if removed > 0 {
+            info!("Purged {} dead letters of tenant {}", removed, tenant);
+        }
+        removed
+    }
+
+    /// Write the given entries, or all of the tenant's, again. Entries that
+    /// succeed are removed; the others keep their place with the new error.
+    pub async fn retry(&self, tenant: &TenantId, ids: Option<&[Uuid]>, service: &dyn GraphService) -> RetryReport {
+        let pending: Vec<DeadLetter> = self.list(tenant).into_iter()
+            .filter(|entry| ids.is_none_or(|ids| ids.contains(&entry.id)))
+            .collect();
+
+        let mut report = RetryReport::default();
+        for entry in pending {
+            match write_record(service, tenant, entry.record.clone()).await {
+                Ok(_) => {
+                    self.remove(tenant, entry.id);
+                    report.succeeded.push(entry.id);
+                }
+                Err(e) => {
+                    self.mark_failed(tenant, entry.id, e.to_string());
+                    report.failed.push(entry.id);
+                }
+            }
+        }
+
+        info!("Retried {} dead letters of tenant {}: {} succeeded", report.succeeded.len() + report.failed.len(), tenant, report.succeeded.len());
+        report
+    }
+
+    fn mark_failed(&self, tenant: &TenantId, id: Uuid, error: String) {
+        let mut entries = self.entries.write().unwrap();
+        if let Some(entry) = entries.get_mut(tenant).and_then(|queue| queue.iter_mut().find(|entry| entry.id == id)) {
+            entry.error = error;
+            entry.attempts += 1;
+            entry.last_failed_at = Utc::now();
+        }
+    }
+}
+
+async fn write_record(service: &dyn GraphService, tenant: &TenantId, record: DeadLetterRecord) -> Result<Uuid, GraphError> {
+    match record {
+        DeadLetterRecord::Node(node) => service.upsert_node(tenant, node).await,
+        DeadLetterRecord::Edge(edge) => {
+            let mut edges = [edge];
+            resolve_edge_aliases(service, tenant, &mut edges).await?;
+            let [edge] = edges;
+            if edge.from_node_id.is_nil() || edge.to_node_id.is_nil() {
+                return Err(GraphError::NodeNotFound("edge endpoint alias did not resolve".to_string()));
+            }
+            service.upsert_edge(tenant, edge).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_letter_queue_bounds_and_purge() {
+        let queue = DeadLetterQueue::new(DeadLetterConfig { max_per_tenant: 2 });
+        let tenant = TenantId::new("acme");
+        let ids: Vec<Uuid> = (0..3)
+            .map(|i| queue.record(&tenant, DeadLetterRecord::Node(Node::new("Person")), format!("error {}", i), "http", BTreeMap::new()))
+            .collect();
+
+        // The oldest entry was dropped
+        let entries = queue.list(&tenant);
+        assert_eq!(entries.iter().map(|e| e.id).collect::<Vec<_>>(), ids[1..]);
+        assert!(queue.get(&tenant, ids[0]).is_none());
+        assert_eq!(queue.get(&tenant, ids[2]).unwrap().error, "error 2");
+        assert!(queue.list(&TenantId::new("other")).is_empty());
+
+        assert!(queue.remove(&tenant, ids[1]));
+        assert!(!queue.remove(&tenant, ids[1]));
+        assert_eq!(queue.purge(&tenant), 1);
+        assert!(queue.list(&tenant).is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! Self-test of a deployment's configuration and dependencies
+//!
+//! A `Doctor` runs named checks one after another, each under a timeout, and
+//! collects a pass/fail report. Servers run it before serving (or instead of
+//! serving, in doctor mode) so that a wrong URI or a missing API key shows up
+//! at startup rather than on the first request that needs it.
+
+use crate::errors::LlmError;
+use crate::traits::{CompletionRequest, GraphStore, LlmConnector, SchemaMigrator};
+use crate::types::TenantId;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Tenant that connector checks are made as
+pub const DOCTOR_TENANT: &str = "_doctor";
+
+/// Result of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    /// Works, but something needs attention
+    Warn,
+    Fail,
+    /// Not configured, so not checked
+    Skip,
+}
+
+/// Status and explanation returned by a check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckOutcome {
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl CheckOutcome {
+    pub fn pass(message: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Pass, message: message.into() }
+    }
+
+    pub fn warn(message: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Warn, message: message.into() }
+    }
+
+    pub fn fail(message: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Fail, message: message.into() }
+    }
+
+    pub fn skip(message: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Skip, message: message.into() }
+    }
+}
+
+/// A check as reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    pub elapsed_ms: u64,
+}
+
+/// All checks of a run, in the order they ran
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DoctorReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl DoctorReport {
+    /// Whether no check failed; warnings and skipped checks still pass
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|c| c.status == CheckStatus::Fail)
+    }
+
+    /// Append the checks of another report
+    pub fn extend(&mut self, other: DoctorReport) {
+        self.checks.extend(other.checks);
+    }
+}
+
+type Check = Box<dyn Fn() -> Pin<Box<dyn Future<Output = CheckOutcome> + Send>> + Send + Sync>;
+
+/// Named checks of a deployment's dependencies
+pub struct Doctor {
+    checks: Vec<(String, Check)>,
+    timeout: Duration,
+}
+
+impl Default for Doctor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Doctor {
+    /// Create a doctor without checks; each check gets 10 seconds
+    pub fn new() -> Self {
+        Self { checks: Vec::new(), timeout: Duration::from_secs(10) }
+    }
+
+    /// Set how long a check may take before it fails
+    pub fn with_timeout(mut self, timeout_ms: u64) -> Self {
+        self.timeout = Duration::from_millis(timeout_ms);
+        self
+    }
+
+    /// Add a check; checks run in the order they were added
+    pub fn with_check<F, Fut>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = CheckOutcome> + Send + 'static,
+    {
+        self.checks.push((name.into(), Box::new(move || Box::pin(check()))));
+        self
+    }
+
+    /// Check that the graph store answers
+    pub fn with_store(self, store: Arc<dyn GraphStore>) -> Self {
+        self.with_check("graph_store", move || {
+            let store = store.clone();
+            async move {
+                match store.health_check().await {
+                    Ok(()) => CheckOutcome::pass("Store is reachable"),
+                    Err(e) => CheckOutcome::fail(e.to_string()),
+                }
+            }
+        })
+    }
+
+    /// Check that the store's indexes are at the version this release expects
+    pub fn with_schema(self, migrator: Arc<dyn SchemaMigrator>) -> Self {
+        self.with_check("schema", move || {
+            let migrator = migrator.clone();
+            async move {
+                match migrator.schema_status().await {
+                    Ok(status) if status.is_current() => {
+                        CheckOutcome::pass(format!("Schema is at version {}", status.current_version))
+                    }
+                    Ok(status) => CheckOutcome::fail(format!(
+                        "Schema is at version {} of {}; run `kgctl migrate apply`",
+                        status.current_version, status.latest_version
+                    )),
+                    Err(e) => CheckOutcome::fail(e.to_string()),
+                }
+            }
+        })
+    }
+
+    /// Check an LLM connector's credentials with a one-token completion
+    pub fn with_llm(self, name: &str, connector: Arc<dyn LlmConnector>) -> Self {
+        self.with_check(format!("llm:{}", name), move || {
+            let connector = connector.clone();
+            async move {
+                let request = CompletionRequest {
+                    prompt: "ping".to_string(),
+                    messages: Vec::new(),
+                    system: None,
+                    max_tokens: Some(1),
+                    temperature: Some(0.0),
+                    params: serde_json::json!({}),
+                    seed: None,
+                };
+                match connector.complete(&TenantId::new(DOCTOR_TENANT), request).await {
+                    Ok(_) => CheckOutcome::pass("Credentials accepted"),
+                    Err(LlmError::RateLimited(e)) => CheckOutcome::warn(format!("Credentials accepted, but rate limited: {}", e)),
+                    Err(e) => CheckOutcome::fail(e.to_string()),
+                }
+            }
+        })
+    }
+
+    /// Run all checks
+    pub async fn run(&self) -> DoctorReport {
+        let mut report = DoctorReport::default();
+        for (name, check) in &self.checks {
+            let started = Instant::now();
+            let outcome = tokio::time::timeout(self.timeout, check()).await
+                .unwrap_or_else(|_| CheckOutcome::fail(format!("Timed out after {:?}", self.timeout)));
+            report.checks.push(CheckResult {
+                name: name.clone(),
+                status: outcome.status,
+                message: outcome.message,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_doctor_report() {
+        let doctor = Doctor::new()
+            .with_timeout(50)
+            .with_check("config", || async { CheckOutcome::pass("ok") })
+            .with_check("cache", || async { CheckOutcome::warn("cold") })
+            .with_check("slow", || async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                CheckOutcome::pass("never")
+            });
+
+        let report = doctor.run().await;
+        let statuses: Vec<_> = report.checks.iter().map(|c| (c.name.as_str(), c.status)).collect();
+        assert_eq!(statuses, vec![("config", CheckStatus::Pass), ("cache", CheckStatus::Warn), ("slow", CheckStatus::Fail)]);
+        assert!(!report.passed());
+        assert_eq!(report.failures().count(), 1);
+
+        let passing = Doctor::new().with_check("config", || async { CheckOutcome::skip("unset") }).run().await;
+        assert!(passing.passed());
+    }
+}
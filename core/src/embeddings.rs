@@ -0,0 +1,581 @@
+//! Batched, rate-controlled embedding of node texts
+//!
+//! Embedding providers take a limited number of texts per request and limit
+//! requests per minute like any LLM API. An [`EmbeddingOrchestrator`] wraps
+//! an [`Embedder`] of any provider: it splits texts into batches of at most
+//! `max_batch_size` (and the embedder's own limit), sends up to
+//! `max_concurrent` batches at a time and, given an [`LlmRequestQueue`],
+//! waits for a turn in the provider's lane like the LLM connectors do.
+//! Texts that fail with a retryable error are sent again, up to
+//! `max_retries` times with backoff, without the rest of their batch.
+//!
+//! Embeddings are cached per tenant by the SHA-256 of their text, so a node
+//! whose text did not change is not sent again. [`EmbeddingOrchestrator::stats`]
+//! reports requests, texts, cache hits, retries and throughput.
+//!
+//! Nodes are embedded from their label and text properties, see
+//! [`node_text`]. [`EnvelopeApplier`](crate::envelope::EnvelopeApplier)
+//! embeds the nodes of each envelope it applies, and [`EmbeddingBackfill`]
+//! is a maintenance job embedding a tenant's existing nodes.
+
+use crate::errors::{CoreError, ErrorInfo, LlmError, VectorError};
+use crate::llm_queue::{current_priority, with_priority, LlmRequestQueue, RequestPriority};
+use crate::tokens::TokenEstimator;
+use crate::traits::{Embedder, GraphService, MaintenanceJob, VectorIndex};
+use crate::types::{GraphQuery, Node, OrderBy, SortField, TenantId};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Configuration of an embedding orchestrator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingConfig {
+    /// Texts per request, at most the embedder's own limit
+    pub max_batch_size: usize,
+    /// Requests sent at once
+    pub max_concurrent: usize,
+    /// Times a text failing with a retryable error is sent again
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each further one, in
+    /// milliseconds; the error's own retry hint takes precedence
+    pub retry_backoff_ms: u64,
+    /// Embeddings kept per tenant by content hash; none if 0
+    pub cache_per_tenant: usize,
+    /// Node properties embedded, in order; all text properties if empty
+    pub text_properties: Vec<String>,
+    /// Nodes read at a time by the backfill job
+    pub backfill_page_size: u32,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 96,
+            max_concurrent: 4,
+            max_retries: 3,
+            retry_backoff_ms: 500,
+            cache_per_tenant: 10_000,
+            text_properties: Vec::new(),
+            backfill_page_size: 500,
+        }
+    }
+}
+
+/// Counters of an embedding orchestrator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingStats {
+    /// Requests sent to the embedder, retries included
+    pub requests: u64,
+    /// Texts the embedder embedded
+    pub embedded: u64,
+    /// Texts answered from the cache
+    pub cache_hits: u64,
+    /// Texts sent again after a retryable failure
+    pub retries: u64,
+    /// Texts that could not be embedded
+    pub failed: u64,
+    /// Time spent in requests to the embedder, in milliseconds
+    pub busy_ms: u64,
+    /// Texts embedded per minute of `busy_ms`
+    pub texts_per_minute: u64,
+}
+
+/// What embedding a set of nodes did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingReport {
+    /// Nodes whose embedding was stored
+    pub embedded: usize,
+    /// Nodes without text to embed
+    pub skipped: usize,
+    /// Errors of nodes that could not be embedded, by node ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failed: BTreeMap<Uuid, String>,
+}
+
+/// Text a node is embedded from: its label, then `key: value` lines of the
+/// `properties` given, or of all its properties holding strings, numbers or
+/// booleans if none are given. `None` if no such property is set.
+pub fn node_text(node: &Node, properties: &[String]) -> Option<String> {
+    let Value::Object(props) = &node.props else {
+        return None;
+    };
+    let scalar = |value: &Value| match value {
+        Value::String(text) if !text.trim().is_empty() => Some(text.clone()),
+        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+        _ => None,
+    };
+    let lines: Vec<String> = if properties.is_empty() {
+        props.iter()
+            .filter_map(|(key, value)| scalar(value).map(|text| format!("{}: {}", key, text)))
+            .collect()
+    } else {
+        properties.iter()
+            .filter_map(|key| props.get(key).and_then(scalar).map(|text| format!("{}: {}", key, text)))
+            .collect()
+    };
+    if lines.is_empty() {
+        return None;
+    }
+    Some(format!("{}\n{}", node.label, lines.join("\n")))
+}
+
+fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Embeddings of a tenant by content hash, oldest evicted first
+#[derive(Default)]
+struct TenantCache {
+    vectors: HashMap<String, Vec<f32>>,
+    order: VecDeque<String>,
+}
+
+impl TenantCache {
+    fn insert(&mut self, hash: String, vector: Vec<f32>, capacity: usize) {
+        if self.vectors.insert(hash.clone(), vector).is_none() {
+            self.order.push_back(hash);
+        }
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.vectors.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Sends batches to the embedder, shared by the tasks sending them
+#[derive(Clone)]
+struct BatchSender {
+    embedder: Arc<dyn Embedder>,
+    queue: Option<Arc<LlmRequestQueue>>,
+    stats: Arc<Mutex<EmbeddingStats>>,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+type Embedded = Result<Vec<f32>, LlmError>;
+
+impl BatchSender {
+    /// One request, waiting for a turn in the provider's lane first
+    async fn request(&self, tenant: &TenantId, priority: RequestPriority, texts: &[String]) -> Result<Vec<Embedded>, LlmError> {
+        let permit = match &self.queue {
+            Some(queue) => {
+                let provider = self.embedder.provider();
+                let estimator = TokenEstimator::for_provider(provider);
+                let tokens = texts.iter().map(|text| estimator.count(text) as u64).sum();
+                Some(queue.acquire(provider, priority, tokens).await?)
+            }
+            None => None,
+        };
+
+        let started = Instant::now();
+        let result = self.embedder.embed(tenant, texts).await;
+        if let (Some(permit), Err(LlmError::RateLimited(_))) = (&permit, &result) {
+            permit.rate_limited();
+        }
+        let mut stats = self.stats.lock().unwrap();
+        stats.requests += 1;
+        stats.busy_ms += started.elapsed().as_millis() as u64;
+        drop(stats);
+
+        let results = result?;
+        if results.len() != texts.len() {
+            return Err(LlmError::ResponseParseError(format!(
+                "Embedder returned {} embeddings for {} texts", results.len(), texts.len()
+            )));
+        }
+        Ok(results)
+    }
+
+    /// Embed a batch, sending texts that failed with a retryable error again
+    async fn send(&self, tenant: &TenantId, priority: RequestPriority, mut pending: Vec<(usize, String)>) -> Vec<(usize, Embedded)> {
+        let mut done = Vec::with_capacity(pending.len());
+        let mut attempt = 0;
+        while !pending.is_empty() {
+            let texts: Vec<String> = pending.iter().map(|(_, text)| text.clone()).collect();
+            let results = match self.request(tenant, priority, &texts).await {
+                Ok(results) => results,
+                Err(e) => texts.iter().map(|_| Err(e.clone())).collect(),
+            };
+
+            let mut retry = Vec::new();
+            let mut wait = self.retry_backoff * 2u32.saturating_pow(attempt);
+            for ((index, text), result) in pending.drain(..).zip(results) {
+                match result {
+                    Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                        wait = wait.max(e.retry_after().unwrap_or_default());
+                        retry.push((index, text));
+                    }
+                    result => done.push((index, result)),
+                }
+            }
+            if retry.is_empty() {
+                break;
+            }
+
+            self.stats.lock().unwrap().retries += retry.len() as u64;
+            debug!("Retrying {} embeddings for tenant {} in {:?}", retry.len(), tenant, wait);
+            tokio::time::sleep(wait).await;
+            pending = retry;
+            attempt += 1;
+        }
+        done
+    }
+}
+
+/// Embeds texts in batches with an embedder, caching the embeddings and
+/// storing those of nodes in a vector index
+pub struct EmbeddingOrchestrator {
+    sender: BatchSender,
+    vectors: Arc<dyn VectorIndex>,
+    config: EmbeddingConfig,
+    cache: Mutex<HashMap<TenantId, TenantCache>>,
+}
+
+impl EmbeddingOrchestrator {
+    /// Embed with `embedder` and store node embeddings in `vectors`
+    pub fn new(embedder: Arc<dyn Embedder>, vectors: Arc<dyn VectorIndex>, config: EmbeddingConfig) -> Self {
+        Self {
+            sender: BatchSender {
+                embedder,
+                queue: None,
+                stats: Arc::new(Mutex::new(EmbeddingStats::default())),
+                max_retries: config.max_retries,
+                retry_backoff: Duration::from_millis(config.retry_backoff_ms),
+            },
+            vectors,
+            config,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Send requests through the embedder's provider lane of this queue
+    pub fn with_queue(mut self, queue: Arc<LlmRequestQueue>) -> Self {
+        self.sender.queue = Some(queue);
+        self
+    }
+
+    pub fn config(&self) -> &EmbeddingConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> EmbeddingStats {
+        let mut stats = *self.sender.stats.lock().unwrap();
+        stats.texts_per_minute = (stats.embedded * 60_000).checked_div(stats.busy_ms).unwrap_or(0);
+        stats
+    }
+
+    fn batch_size(&self) -> usize {
+        self.config.max_batch_size.min(self.sender.embedder.max_batch_size()).max(1)
+    }
+
+    /// Embed texts, returning one result per text in order
+    pub async fn embed(&self, tenant: &TenantId, texts: Vec<String>) -> Vec<Result<Vec<f32>, LlmError>> {
+        let hashes: Vec<String> = texts.iter().map(|text| content_hash(text)).collect();
+        let mut results: Vec<Option<Embedded>> = Vec::with_capacity(texts.len());
+        let mut pending = Vec::new();
+        {
+            let cache = self.cache.lock().unwrap();
+            let cached = cache.get(tenant);
+            for (index, (text, hash)) in texts.into_iter().zip(&hashes).enumerate() {
+                match cached.and_then(|cached| cached.vectors.get(hash)) {
+                    Some(vector) => results.push(Some(Ok(vector.clone()))),
+                    None => {
+                        results.push(None);
+                        pending.push((index, text));
+                    }
+                }
+            }
+        }
+        let hits = (results.len() - pending.len()) as u64;
+        self.sender.stats.lock().unwrap().cache_hits += hits;
+        if pending.is_empty() {
+            return results.into_iter().flatten().collect();
+        }
+
+        // Spawned tasks do not inherit the caller's priority
+        let priority = current_priority();
+        let batch_size = self.batch_size();
+        let batches = pending.len().div_ceil(batch_size);
+        debug!("Embedding {} texts for tenant {} in {} batches ({} cached)", pending.len(), tenant, batches, hits);
+
+        let permits = Arc::new(tokio::sync::Semaphore::new(self.config.max_concurrent.max(1)));
+        let mut tasks = JoinSet::new();
+        let mut pending = pending.into_iter().peekable();
+        while pending.peek().is_some() {
+            let batch: Vec<(usize, String)> = pending.by_ref().take(batch_size).collect();
+            let sender = self.sender.clone();
+            let tenant = tenant.clone();
+            let permits = permits.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await.expect("semaphore is never closed");
+                sender.send(&tenant, priority, batch).await
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(embedded) => {
+                    for (index, result) in embedded {
+                        results[index] = Some(result);
+                    }
+                }
+                Err(e) => warn!("Embedding task failed: {}", e),
+            }
+        }
+
+        let mut stats = self.sender.stats.lock().unwrap();
+        let mut cache = self.cache.lock().unwrap();
+        let results: Vec<Embedded> = results.into_iter()
+            .zip(hashes)
+            .map(|(result, hash)| match result {
+                Some(Ok(vector)) => {
+                    if self.config.cache_per_tenant > 0 {
+                        cache.entry(tenant.clone()).or_default().insert(hash, vector.clone(), self.config.cache_per_tenant);
+                    }
+                    Ok(vector)
+                }
+                // A slot is only left empty when its task panicked or was cancelled
+                result => {
+                    stats.failed += 1;
+                    result.unwrap_or_else(|| Err(LlmError::InternalError("Embedding task failed".to_string())))
+                }
+            })
+            .collect();
+        stats.embedded += results.iter().filter(|result| result.is_ok()).count() as u64 - hits;
+        results
+    }
+
+    /// Embed nodes from their text and store the embeddings under their IDs.
+    /// Nodes that could not be embedded are reported; failing to store an
+    /// embedding fails the call.
+    pub async fn embed_nodes(&self, tenant: &TenantId, nodes: Vec<(Uuid, Node)>) -> Result<EmbeddingReport, VectorError> {
+        let mut report = EmbeddingReport::default();
+        let (ids, texts): (Vec<Uuid>, Vec<String>) = nodes.iter()
+            .filter_map(|(id, node)| node_text(node, &self.config.text_properties).map(|text| (*id, text)))
+            .unzip();
+        report.skipped = nodes.len() - ids.len();
+
+        for (id, result) in ids.into_iter().zip(self.embed(tenant, texts).await) {
+            match result {
+                Ok(vector) => {
+                    self.vectors.upsert_vector(tenant, id, vector).await?;
+                    report.embedded += 1;
+                }
+                Err(e) => {
+                    report.failed.insert(id, e.to_string());
+                }
+            }
+        }
+        if !report.failed.is_empty() {
+            warn!("Could not embed {} of {} nodes of tenant {}", report.failed.len(), nodes.len(), tenant);
+        }
+        Ok(report)
+    }
+}
+
+/// Maintenance job embedding all nodes of a tenant, a page at a time and
+/// behind interactive requests. Nodes whose text is unchanged since they
+/// were last embedded come from the cache.
+pub struct EmbeddingBackfill {
+    service: Arc<dyn GraphService>,
+    embeddings: Arc<EmbeddingOrchestrator>,
+}
+
+impl EmbeddingBackfill {
+    pub fn new(service: Arc<dyn GraphService>, embeddings: Arc<EmbeddingOrchestrator>) -> Self {
+        Self { service, embeddings }
+    }
+
+    async fn backfill(&self, tenant: &TenantId) -> Result<EmbeddingReport, CoreError> {
+        let page_size = self.embeddings.config().backfill_page_size.max(1);
+        let mut report = EmbeddingReport::default();
+        let mut offset = 0;
+        loop {
+            let paths = self.service.query(tenant, GraphQuery::FindNodes {
+                labels: Vec::new(),
+                properties: HashMap::new(),
+                order_by: vec![OrderBy::asc(SortField::CreatedAt)],
+                offset: Some(offset),
+                limit: Some(page_size),
+            }).await?;
+            let nodes: Vec<(Uuid, Node)> = paths.into_iter()
+                .flat_map(|path| path.nodes)
+                .map(|node| {
+                    let label = node.labels.into_iter().next().unwrap_or_default();
+                    (node.id, Node::new(label).with_props(node.properties))
+                })
+                .collect();
+            let read = nodes.len() as u32;
+
+            let page = self.embeddings.embed_nodes(tenant, nodes).await?;
+            report.embedded += page.embedded;
+            report.skipped += page.skipped;
+            report.failed.extend(page.failed);
+            if read < page_size {
+                break;
+            }
+            offset += read;
+        }
+        Ok(report)
+    }
+}
+
+#[async_trait]
+impl MaintenanceJob for EmbeddingBackfill {
+    async fn run(&self, tenant: &TenantId) -> Result<u64, CoreError> {
+        let report = with_priority(RequestPriority::Background, self.backfill(tenant)).await?;
+        info!(
+            "Embedded {} nodes of tenant {} ({} without text, {} failed)",
+            report.embedded, tenant, report.skipped, report.failed.len()
+        );
+        Ok(report.embedded as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_queue::LlmQueueConfig;
+    use crate::types::VectorMatch;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Embeds a text as its length; texts containing "flaky" fail once,
+    /// texts containing "broken" always
+    #[derive(Default)]
+    struct LengthEmbedder {
+        requests: AtomicUsize,
+        batches: Mutex<Vec<usize>>,
+        flaked: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Embedder for LengthEmbedder {
+        fn provider(&self) -> &str {
+            "test"
+        }
+
+        fn max_batch_size(&self) -> usize {
+            2
+        }
+
+        async fn embed(&self, _tenant: &TenantId, texts: &[String]) -> Result<Vec<Embedded>, LlmError> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            self.batches.lock().unwrap().push(texts.len());
+            let mut flaked = self.flaked.lock().unwrap();
+            Ok(texts.iter()
+                .map(|text| {
+                    if text.contains("broken") {
+                        Err(LlmError::ContextLengthExceeded(text.clone()))
+                    } else if text.contains("flaky") && !flaked.contains(text) {
+                        flaked.push(text.clone());
+                        Err(LlmError::NetworkError("connection reset".to_string()))
+                    } else {
+                        Ok(vec![text.len() as f32])
+                    }
+                })
+                .collect())
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryIndex {
+        vectors: Mutex<HashMap<Uuid, Vec<f32>>>,
+    }
+
+    #[async_trait]
+    impl VectorIndex for MemoryIndex {
+        async fn upsert_vector(&self, _tenant: &TenantId, id: Uuid, vector: Vec<f32>) -> Result<(), VectorError> {
+            self.vectors.lock().unwrap().insert(id, vector);
+            Ok(())
+        }
+
+        async fn remove_vector(&self, _tenant: &TenantId, id: Uuid) -> Result<bool, VectorError> {
+            Ok(self.vectors.lock().unwrap().remove(&id).is_some())
+        }
+
+        async fn search(&self, _tenant: &TenantId, _query: &[f32], _k: usize) -> Result<Vec<VectorMatch>, VectorError> {
+            Ok(Vec::new())
+        }
+
+        async fn count(&self, _tenant: &TenantId) -> Result<usize, VectorError> {
+            Ok(self.vectors.lock().unwrap().len())
+        }
+
+        async fn drop_tenant(&self, _tenant: &TenantId) -> Result<bool, VectorError> {
+            Ok(false)
+        }
+    }
+
+    fn orchestrator() -> (Arc<LengthEmbedder>, Arc<MemoryIndex>, EmbeddingOrchestrator) {
+        let embedder = Arc::new(LengthEmbedder::default());
+        let index = Arc::new(MemoryIndex::default());
+        let config = EmbeddingConfig { max_batch_size: 10, retry_backoff_ms: 1, ..Default::default() };
+        let orchestrator = EmbeddingOrchestrator::new(embedder.clone(), index.clone(), config)
+            .with_queue(Arc::new(LlmRequestQueue::new(LlmQueueConfig::default())));
+        (embedder, index, orchestrator)
+    }
+
+    #[test]
+    fn test_node_text() {
+        let node = Node::new("Deal").with_props(json!({"stage": "won", "amount": 12, "provenance": {"run_id": "x"}, "note": " "}));
+        assert_eq!(node_text(&node, &[]).unwrap(), "Deal\namount: 12\nstage: won");
+        assert_eq!(node_text(&node, &["stage".to_string()]).unwrap(), "Deal\nstage: won");
+        assert_eq!(node_text(&node, &["note".to_string()]), None);
+    }
+
+    #[tokio::test]
+    async fn test_embed_batches_retries_and_caches() {
+        let (embedder, _, orchestrator) = orchestrator();
+        let tenant = TenantId::new("tenant");
+        let texts = ["a", "bb", "flaky", "broken", "ccc"].map(str::to_string).to_vec();
+
+        let results = orchestrator.embed(&tenant, texts.clone()).await;
+        assert_eq!(results[1].as_ref().unwrap(), &vec![2.0]);
+        // Sent again on its own after failing once
+        assert_eq!(results[2].as_ref().unwrap(), &vec![5.0]);
+        assert!(matches!(results[3], Err(LlmError::ContextLengthExceeded(_))));
+        // Batches are limited by the embedder, and the retry carries only the flaky text
+        let mut batches = embedder.batches.lock().unwrap().clone();
+        batches.sort();
+        assert_eq!(batches, vec![1, 1, 2, 2]);
+
+        let stats = orchestrator.stats();
+        assert_eq!((stats.requests, stats.embedded, stats.retries, stats.failed), (4, 4, 1, 1));
+
+        // Only the broken text is sent again
+        let results = orchestrator.embed(&tenant, texts).await;
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 4);
+        assert_eq!(embedder.requests.load(Ordering::SeqCst), 5);
+        assert_eq!(orchestrator.stats().cache_hits, 4);
+
+        // Caches are per tenant
+        orchestrator.embed(&TenantId::new("other"), vec!["a".to_string()]).await;
+        assert_eq!(embedder.requests.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_embed_nodes() {
+        let (_, index, orchestrator) = orchestrator();
+        let tenant = TenantId::new("tenant");
+        let (deal, empty, broken) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let nodes = vec![
+            (deal, Node::new("Deal").with_props(json!({"name": "Acme renewal"}))),
+            (empty, Node::new("Deal")),
+            (broken, Node::new("Deal").with_props(json!({"name": "broken"}))),
+        ];
+
+        let report = orchestrator.embed_nodes(&tenant, nodes).await.unwrap();
+        assert_eq!((report.embedded, report.skipped), (1, 1));
+        assert!(report.failed.contains_key(&broken));
+        assert_eq!(index.vectors.lock().unwrap()[&deal], vec!["Deal\nname: Acme renewal".len() as f32]);
+    }
+}
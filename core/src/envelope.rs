@@ -0,0 +1,494 @@
+//! Applying extraction envelopes to the graph
+//!
+//! Agents that call an LLM themselves end up with an `ExtractionEnvelope`
+//! of their own. [`EnvelopeApplier`] writes such an envelope to a tenant's
+//! graph with the same semantics as server-side ingestion:
+//!
+//! - nodes and relations below the policy's minimum confidence are left out;
+//! - relation endpoints are resolved against the envelope's nodes and the
+//!   alias index, in the policy's alias namespace, and extracted properties
+//!   are merged into the existing node of an alias;
+//! - a relation of a kind the tenant declares exclusive (see `constraints`)
+//!   that overlaps a current edge of its source node to another target
+//!   contradicts it, and is handled by the [`ContradictionPolicy`];
+//! - written nodes and edges record their provenance: origin, provider,
+//!   model, confidence and when they were applied;
+//! - with an [`ExtractionLineage`], each application is recorded as an
+//!   extraction run in the lineage graph, and the provenance names the run;
+//! - with an [`EmbeddingOrchestrator`], the written nodes are embedded once
+//!   the envelope is applied. Nodes that fail to embed are only logged, as
+//!   the backfill job embeds them later.
+//!
+//! The whole envelope is checked before anything is written. Should a write
+//! fail anyway, the nodes and edges written before it are undone; edges
+//! closed to supersede contradicted facts stay closed.
+
+use crate::embeddings::EmbeddingOrchestrator;
+use crate::errors::GraphError;
+use crate::extraction::merge_envelopes;
+use crate::lineage::{ExtractionLineage, ExtractionRun, RunFacts};
+use crate::traits::{ExtractionEnvelope, ExtractionMetadata, GraphService};
+use crate::types::{AliasKey, GraphQuery, Node, TenantId, TimeEdge};
+use crate::valid_time::SourceInfo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Property recording where nodes and edges came from, by default
+pub const DEFAULT_PROVENANCE_KEY: &str = "provenance";
+
+/// How a relation contradicting a current edge is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContradictionPolicy {
+    /// Fail the envelope with `GraphError::ConstraintViolation`
+    #[default]
+    Reject,
+    /// Leave the contradicting relation out
+    Skip,
+    /// Close the contradicted edges when the relation becomes valid
+    Supersede,
+}
+
+/// How envelopes of a tenant are applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvelopePolicy {
+    /// Nodes and relations with a lower confidence are left out; those
+    /// without a confidence are kept
+    pub min_confidence: f32,
+    /// Namespace of the envelope's aliases
+    pub alias_namespace: Option<String>,
+    pub contradictions: ContradictionPolicy,
+    /// Property recording provenance; none to not record it
+    pub provenance_key: Option<String>,
+}
+
+impl Default for EnvelopePolicy {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.0,
+            alias_namespace: None,
+            contradictions: ContradictionPolicy::default(),
+            provenance_key: Some(DEFAULT_PROVENANCE_KEY.to_string()),
+        }
+    }
+}
+
+/// Envelope policies by tenant
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvelopePolicies {
+    /// Policy of tenants without their own
+    pub default: EnvelopePolicy,
+    /// Policies by tenant ID
+    pub tenants: HashMap<String, EnvelopePolicy>,
+}
+
+impl EnvelopePolicies {
+    /// Policy of a tenant
+    pub fn for_tenant(&self, tenant: &TenantId) -> &EnvelopePolicy {
+        self.tenants.get(tenant.as_str()).unwrap_or(&self.default)
+    }
+}
+
+/// An envelope to apply, with overrides of the tenant's policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyEnvelopeRequest {
+    pub envelope: ExtractionEnvelope,
+    /// Where the envelope was extracted from, e.g. `slack:#sales`, recorded
+    /// in the provenance
+    #[serde(default)]
+    pub origin: Option<String>,
+    /// Message the envelope was extracted from, for valid-time defaults
+    #[serde(default)]
+    pub source: Option<SourceInfo>,
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
+    #[serde(default)]
+    pub contradictions: Option<ContradictionPolicy>,
+    /// Version of the prompt template the envelope was extracted with,
+    /// recorded in the lineage
+    #[serde(default)]
+    pub prompt_version: Option<String>,
+}
+
+impl ApplyEnvelopeRequest {
+    pub fn new(envelope: ExtractionEnvelope) -> Self {
+        Self { envelope, origin: None, source: None, min_confidence: None, contradictions: None, prompt_version: None }
+    }
+}
+
+/// A node or relation of an envelope that was not written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedItem {
+    /// Alias of the node, or the relation as `from -KIND-> to`
+    pub item: String,
+    pub reason: String,
+}
+
+/// What applying an envelope wrote
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeReport {
+    /// IDs of the written nodes by alias
+    pub nodes: BTreeMap<String, Uuid>,
+    /// IDs of the written edges, in the order of the envelope's relations
+    pub edges: Vec<Uuid>,
+    /// Edges closed because a relation superseded them
+    pub superseded: Vec<Uuid>,
+    pub skipped: Vec<SkippedItem>,
+    /// Extraction run the application was recorded as, if lineage is kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<Uuid>,
+}
+
+/// A node to write and what its write replaces
+struct PlannedNode {
+    alias: String,
+    node: Node,
+    /// The node of the alias before the write
+    previous: Option<Node>,
+}
+
+/// A relation to write once its endpoints are written
+struct PlannedEdge {
+    from: String,
+    to: String,
+    edge: TimeEdge,
+    /// Current edges it supersedes
+    closes: Vec<Uuid>,
+}
+
+/// A write to undo if a later one fails
+enum Undo {
+    CreatedNode(Uuid),
+    UpdatedNode(Node),
+    CreatedEdge(Uuid),
+}
+
+/// Applies extraction envelopes to the graph
+pub struct EnvelopeApplier {
+    service: Arc<dyn GraphService>,
+    policies: EnvelopePolicies,
+    lineage: Option<Arc<ExtractionLineage>>,
+    embeddings: Option<Arc<EmbeddingOrchestrator>>,
+}
+
+impl EnvelopeApplier {
+    pub fn new(service: Arc<dyn GraphService>, policies: EnvelopePolicies) -> Self {
+        Self { service, policies, lineage: None, embeddings: None }
+    }
+
+    /// Record every application as an extraction run
+    pub fn with_lineage(mut self, lineage: Arc<ExtractionLineage>) -> Self {
+        self.lineage = Some(lineage);
+        self
+    }
+
+    /// Embed the nodes of every applied envelope
+    pub fn with_embeddings(mut self, embeddings: Arc<EmbeddingOrchestrator>) -> Self {
+        self.embeddings = Some(embeddings);
+        self
+    }
+
+    /// Policy of a tenant, before a request's overrides
+    pub fn policy(&self, tenant: &TenantId) -> &EnvelopePolicy {
+        self.policies.for_tenant(tenant)
+    }
+
+    /// Check an envelope against the tenant's graph and write it
+    pub async fn apply(&self, tenant: &TenantId, request: ApplyEnvelopeRequest) -> Result<EnvelopeReport, GraphError> {
+        let mut policy = self.policy(tenant).clone();
+        if let Some(min_confidence) = request.min_confidence {
+            policy.min_confidence = min_confidence;
+        }
+        if let Some(contradictions) = request.contradictions {
+            policy.contradictions = contradictions;
+        }
+
+        let mut report = EnvelopeReport {
+            run_id: self.lineage.is_some().then(Uuid::new_v4),
+            ..Default::default()
+        };
+        let run = report.run_id.map(|id| new_run(id, tenant, &request));
+        let (nodes, edges) = self.plan(tenant, &policy, request, &mut report).await?;
+        debug!("Applying {} nodes and {} relations to tenant {}", nodes.len(), edges.len(), tenant);
+
+        let written: Vec<(String, Node)> = match &self.embeddings {
+            Some(_) => nodes.iter().map(|planned| (planned.alias.clone(), planned.node.clone())).collect(),
+            None => Vec::new(),
+        };
+        let mut undo = Vec::new();
+        let mut result = self.write(tenant, nodes, edges, &mut report, &mut undo).await;
+        if let (Ok(()), Some(lineage), Some(mut run)) = (&result, &self.lineage, run) {
+            let facts = run_facts(&report, &undo);
+            (run.nodes, run.edges) = (report.nodes.len(), report.edges.len());
+            result = lineage.record(&run, &facts).await;
+        }
+        if let Err(e) = result {
+            warn!("Applying envelope to tenant {} failed, undoing {} writes: {}", tenant, undo.len(), e);
+            self.undo(tenant, undo).await;
+            return Err(e);
+        }
+
+        info!("Applied envelope to tenant {}: {} nodes, {} edges, {} skipped",
+            tenant, report.nodes.len(), report.edges.len(), report.skipped.len());
+        if let Some(embeddings) = &self.embeddings {
+            let nodes = written.into_iter().map(|(alias, node)| (report.nodes[&alias], node)).collect();
+            if let Err(e) = embeddings.embed_nodes(tenant, nodes).await {
+                warn!("Could not store embeddings of the nodes of an envelope applied to tenant {}: {}", tenant, e);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Decide what to write, failing before any write if the envelope
+    /// cannot be applied
+    async fn plan(
+        &self,
+        tenant: &TenantId,
+        policy: &EnvelopePolicy,
+        request: ApplyEnvelopeRequest,
+        report: &mut EnvelopeReport,
+    ) -> Result<(Vec<PlannedNode>, Vec<PlannedEdge>), GraphError> {
+        let envelope = merge_envelopes(vec![request.envelope]);
+        let confident = |confidence: Option<f32>| confidence.is_none_or(|c| c >= policy.min_confidence);
+        let alias_key = |alias: &str| AliasKey { namespace: policy.alias_namespace.clone(), alias: alias.to_string() };
+        let now = Utc::now();
+
+        let aliases: Vec<AliasKey> = envelope.nodes.iter().map(|node| alias_key(&node.id_alias))
+            .chain(envelope.relations.iter().flat_map(|rel| [alias_key(&rel.from_id_alias), alias_key(&rel.to_id_alias)]))
+            .collect();
+        let existing = self.service.resolve_aliases(tenant, &aliases).await?;
+
+        let mut nodes = Vec::new();
+        for extracted in envelope.nodes {
+            if !confident(extracted.confidence) {
+                report.skipped.push(below_confidence(extracted.id_alias, extracted.confidence));
+                continue;
+            }
+
+            let key = alias_key(&extracted.id_alias);
+            let previous = match existing.get(&key) {
+                Some(id) => self.service.get_node(tenant, *id).await?,
+                None => None,
+            };
+            let mut props = match &previous {
+                Some(previous) if previous.label != extracted.label => {
+                    return Err(GraphError::ConstraintViolation(format!(
+                        "Alias '{}' is already used by a {} node, not a {}", key, previous.label, extracted.label
+                    )));
+                }
+                Some(previous) => previous.props.as_object().cloned().unwrap_or_default(),
+                None => Map::new(),
+            };
+            if let Value::Object(extracted_props) = extracted.props {
+                props.extend(extracted_props);
+            }
+            tag(&mut props, policy, &request.origin, envelope.metadata.as_ref(), extracted.confidence, report.run_id, now);
+
+            let mut node = Node::new(extracted.label).with_id_alias(&extracted.id_alias).with_props(Value::Object(props));
+            node.alias_namespace = policy.alias_namespace.clone();
+            nodes.push(PlannedNode { alias: extracted.id_alias, node, previous });
+        }
+
+        let constraints = self.service.edge_constraints(tenant).await?;
+        let mut edges = Vec::new();
+        for relation in envelope.relations {
+            let item = format!("{} -{}-> {}", relation.from_id_alias, relation.type_label, relation.to_id_alias);
+            if !confident(relation.confidence) {
+                report.skipped.push(below_confidence(item, relation.confidence));
+                continue;
+            }
+            let unknown = [&relation.from_id_alias, &relation.to_id_alias].into_iter()
+                .find(|alias| !nodes.iter().any(|node| &node.alias == *alias) && !existing.contains_key(&alias_key(alias)));
+            if let Some(alias) = unknown {
+                report.skipped.push(SkippedItem { item, reason: format!("No node has the alias '{}'", alias) });
+                continue;
+            }
+
+            let mut props = relation.props.as_object().cloned().unwrap_or_default();
+            tag(&mut props, policy, &request.origin, envelope.metadata.as_ref(), relation.confidence, report.run_id, now);
+            let source_id = existing.get(&alias_key(&relation.from_id_alias)).copied();
+            let target_id = existing.get(&alias_key(&relation.to_id_alias)).copied();
+            let mut edge = TimeEdge::new(
+                source_id.unwrap_or_else(Uuid::nil),
+                target_id.unwrap_or_else(Uuid::nil),
+                relation.type_label,
+                relation.valid_from.unwrap_or(now),
+                Value::Object(props),
+            );
+            edge.valid_to = relation.valid_to;
+
+            // Only an existing source node can have edges to contradict
+            let mut closes = Vec::new();
+            if let (Some(source_id), true) = (source_id, constraints.exclusivity(&edge.kind).is_some()) {
+                let current = self.current_edges(tenant, source_id, &edge.kind, edge.valid_from).await?;
+                if current.iter().any(|(_, to)| Some(*to) == target_id) {
+                    report.skipped.push(SkippedItem { item, reason: "Already in the graph".to_string() });
+                    continue;
+                }
+                if let Some((contradicted, _)) = current.first() {
+                    match policy.contradictions {
+                        ContradictionPolicy::Reject => {
+                            return Err(GraphError::ConstraintViolation(format!(
+                                "{} contradicts edge {}, as {} is exclusive", item, contradicted, edge.kind
+                            )));
+                        }
+                        ContradictionPolicy::Skip => {
+                            report.skipped.push(SkippedItem { item, reason: format!("Contradicts edge {}", contradicted) });
+                            continue;
+                        }
+                        ContradictionPolicy::Supersede => closes = current.iter().map(|(id, _)| *id).collect(),
+                    }
+                }
+            }
+
+            edges.push(PlannedEdge { from: relation.from_id_alias, to: relation.to_id_alias, edge, closes });
+        }
+
+        Ok((nodes, edges))
+    }
+
+    /// IDs and targets of a node's edges of a kind valid at a time
+    async fn current_edges(&self, tenant: &TenantId, from: Uuid, kind: &str, valid_at: DateTime<Utc>) -> Result<Vec<(Uuid, Uuid)>, GraphError> {
+        let paths = self.service.query(tenant, GraphQuery::FindRelationships {
+            from_node_id: Some(from),
+            to_node_id: None,
+            relationship_types: vec![kind.to_string()],
+            valid_at: Some(valid_at),
+            order_by: vec![],
+            offset: None,
+            limit: None,
+            collapse: None,
+        }).await?;
+        Ok(paths.iter()
+            .flat_map(|path| &path.relationships)
+            .map(|rel| (rel.id, rel.end_node_id))
+            .collect())
+    }
+
+    async fn write(
+        &self,
+        tenant: &TenantId,
+        nodes: Vec<PlannedNode>,
+        edges: Vec<PlannedEdge>,
+        report: &mut EnvelopeReport,
+        undo: &mut Vec<Undo>,
+    ) -> Result<(), GraphError> {
+        for planned in nodes {
+            let id = self.service.upsert_node(tenant, planned.node).await?;
+            undo.push(match planned.previous {
+                Some(previous) => Undo::UpdatedNode(previous),
+                None => Undo::CreatedNode(id),
+            });
+            report.nodes.insert(planned.alias, id);
+        }
+
+        for mut planned in edges {
+            if planned.edge.from_node_id.is_nil() {
+                planned.edge.from_node_id = report.nodes[&planned.from];
+            }
+            if planned.edge.to_node_id.is_nil() {
+                planned.edge.to_node_id = report.nodes[&planned.to];
+            }
+            for id in planned.closes {
+                self.service.close_edge(tenant, id, planned.edge.valid_from).await?;
+                report.superseded.push(id);
+            }
+            let id = self.service.upsert_edge(tenant, planned.edge).await?;
+            undo.push(Undo::CreatedEdge(id));
+            report.edges.push(id);
+        }
+        Ok(())
+    }
+
+    /// Undo writes, latest first, carrying on past failures
+    async fn undo(&self, tenant: &TenantId, undo: Vec<Undo>) {
+        for step in undo.into_iter().rev() {
+            let result = match step {
+                Undo::CreatedEdge(id) => self.service.delete_edge(tenant, id).await.map(|_| ()),
+                Undo::CreatedNode(id) => self.service.delete_node(tenant, id).await.map(|_| ()),
+                Undo::UpdatedNode(node) => self.service.upsert_node(tenant, node).await.map(|_| ()),
+            };
+            if let Err(e) = result {
+                warn!("Could not undo a write of an envelope to tenant {}: {}", tenant, e);
+            }
+        }
+    }
+}
+
+/// A run for an envelope about to be applied
+fn new_run(id: Uuid, tenant: &TenantId, request: &ApplyEnvelopeRequest) -> ExtractionRun {
+    let metadata = request.envelope.metadata.as_ref();
+    ExtractionRun {
+        id,
+        tenant: tenant.as_str().to_string(),
+        origin: request.origin.clone(),
+        provider: metadata.map(|metadata| metadata.provider.clone()),
+        model: metadata.map(|metadata| metadata.model_name.clone()),
+        prompt_version: request.prompt_version.clone(),
+        input_tokens: metadata.and_then(|metadata| metadata.input_tokens),
+        output_tokens: metadata.and_then(|metadata| metadata.output_tokens),
+        cost_usd: metadata.and_then(|metadata| metadata.cost_usd),
+        warnings: metadata.map(|metadata| metadata.warnings.clone()).unwrap_or_default(),
+        applied_at: Utc::now(),
+        nodes: 0,
+        edges: 0,
+        purged_at: None,
+    }
+}
+
+/// What a successful application wrote, telling created nodes from updated
+/// ones by what undoing it would take
+fn run_facts(report: &EnvelopeReport, undo: &[Undo]) -> RunFacts {
+    let created_nodes: Vec<Uuid> = undo.iter()
+        .filter_map(|step| match step {
+            Undo::CreatedNode(id) => Some(*id),
+            _ => None,
+        })
+        .collect();
+    RunFacts {
+        updated_nodes: report.nodes.values().filter(|id| !created_nodes.contains(id)).copied().collect(),
+        created_nodes,
+        edges: report.edges.clone(),
+        superseded: report.superseded.clone(),
+    }
+}
+
+fn below_confidence(item: String, confidence: Option<f32>) -> SkippedItem {
+    SkippedItem { item, reason: format!("Confidence {:.2} is below the minimum", confidence.unwrap_or_default()) }
+}
+
+/// Record where a node or edge came from under the policy's provenance key
+fn tag(
+    props: &mut Map<String, Value>,
+    policy: &EnvelopePolicy,
+    origin: &Option<String>,
+    metadata: Option<&ExtractionMetadata>,
+    confidence: Option<f32>,
+    run_id: Option<Uuid>,
+    applied_at: DateTime<Utc>,
+) {
+    let Some(key) = &policy.provenance_key else {
+        return;
+    };
+    let mut provenance = json!({ "applied_at": applied_at });
+    let fields = [
+        ("origin", origin.clone().map(Value::from)),
+        ("provider", metadata.map(|metadata| Value::from(metadata.provider.clone()))),
+        ("model", metadata.map(|metadata| Value::from(metadata.model_name.clone()))),
+        ("confidence", confidence.map(Value::from)),
+        ("run_id", run_id.map(|id| Value::from(id.to_string()))),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            provenance[name] = value;
+        }
+    }
+    props.insert(key.clone(), provenance);
+}
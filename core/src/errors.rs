@@ -1,5 +1,9 @@
 //! Error types for TelaMentis core operations
 
+use crate::auth::TokenScope;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use thiserror::Error;
 
 /// Main error type for TelaMentis core operations
@@ -14,9 +18,30 @@ pub enum CoreError {
     #[error("Pipeline error: {0}")]
     Pipeline(#[from] PipelineError),
     
+    #[error("Vector index error: {0}")]
+    Vector(#[from] VectorError),
+    
+    #[error("Archive error: {0}")]
+    Archive(#[from] ArchiveError),
+    
+    #[error("Analytics error: {0}")]
+    Analytics(#[from] AnalyticsError),
+    
+    #[error("Authentication error: {0}")]
+    Auth(#[from] AuthError),
+    
+    #[error("Session error: {0}")]
+    Session(#[from] SessionError),
+    
+    #[error("View error: {0}")]
+    View(#[from] ViewError),
+    
     #[error("Tenant error: {0}")]
     Tenant(String),
     
+    #[error("Operation cancelled: {0}")]
+    Cancelled(String),
+    
     #[error("Temporal query error: {0}")]
     Temporal(String),
     
@@ -53,7 +78,7 @@ pub enum PipelineError {
 }
 
 /// Errors related to graph storage operations
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum GraphError {
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
@@ -76,15 +101,74 @@ pub enum GraphError {
     #[error("Tenant isolation violation: {0}")]
     TenantIsolationViolation(String),
     
+    #[error("Reserved property: {0}")]
+    ReservedProperty(String),
+    
     #[error("Database error: {0}")]
     DatabaseError(String),
     
     #[error("Timeout: {0}")]
     Timeout(String),
+    
+    #[error("Snapshot not found: {0}")]
+    SnapshotNotFound(String),
+    
+    #[error("Not supported by this store: {0}")]
+    Unsupported(String),
+    
+    #[error("Temporal validation failed: {0}")]
+    Temporal(#[from] TemporalError),
+    
+    #[error("Schema outdated: {0}")]
+    SchemaOutdated(String),
+    
+    #[error("Overloaded: {0}")]
+    Overloaded(String),
+    
+    #[error("Invalid tenant ID: {0}")]
+    InvalidTenant(#[from] TenantIdError),
+}
+
+/// Why a string is not a valid tenant ID
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TenantIdError {
+    #[error("tenant ID is empty")]
+    Empty,
+    
+    #[error("tenant ID is {length} characters long, more than {max}")]
+    TooLong { length: usize, max: usize },
+    
+    #[error("'{0}' is not allowed; use lowercase ASCII letters, digits, '_' and '-'")]
+    InvalidCharacter(char),
+    
+    #[error("tenant ID starts with '{0}' instead of a letter or digit")]
+    InvalidStart(char),
+}
+
+/// Violations of the bitemporal invariants of an edge
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TemporalError {
+    #[error("valid_to ({valid_to}) is before valid_from ({valid_from})")]
+    ValidToBeforeValidFrom {
+        valid_from: DateTime<Utc>,
+        valid_to: DateTime<Utc>,
+    },
+    
+    #[error("transaction_end_time ({transaction_end_time}) is before transaction_start_time ({transaction_start_time})")]
+    TransactionEndBeforeStart {
+        transaction_start_time: DateTime<Utc>,
+        transaction_end_time: DateTime<Utc>,
+    },
+    
+    #[error("{field} ({time}) is in the future")]
+    FutureTransactionTime {
+        field: &'static str,
+        time: DateTime<Utc>,
+    },
 }
 
 /// Errors related to LLM connector operations
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum LlmError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
@@ -98,6 +182,9 @@ pub enum LlmError {
     #[error("Timeout during LLM call")]
     Timeout,
     
+    #[error("Rate limited by LLM provider: {0}")]
+    RateLimited(String),
+    
     #[error("Failed to parse LLM response: {0}")]
     ResponseParseError(String),
     
@@ -107,6 +194,21 @@ pub enum LlmError {
     #[error("Extraction budget exceeded")]
     BudgetExceeded,
     
+    #[error("Extraction input rejected by safety checks: {0}")]
+    UnsafeInput(String),
+    
+    #[error("Extraction input exceeds the model context window: {0}")]
+    ContextLengthExceeded(String),
+    
+    #[error("LLM capability unavailable: {0}")]
+    CapabilityUnavailable(String),
+    
+    #[error("LLM provider not allowed: {0}")]
+    ProviderNotAllowed(String),
+    
+    #[error("Content blocked by the LLM provider: {0}")]
+    ContentBlocked(String),
+    
     #[error("Internal connector error: {0}")]
     InternalError(String),
 }
@@ -130,6 +232,112 @@ pub enum PresentationError {
     AuthorizationFailed(String),
 }
 
+/// Errors related to vector indexes
+#[derive(Error, Debug, Clone)]
+pub enum VectorError {
+    #[error("Vector has dimension {actual}, index expects {expected}")]
+    DimensionMismatch { expected: usize, actual: usize },
+    
+    #[error("Invalid vector: {0}")]
+    InvalidVector(String),
+    
+    #[error("Vector index storage error: {0}")]
+    Storage(String),
+    
+    #[error("Corrupt vector index: {0}")]
+    Corrupt(String),
+}
+
+/// Errors related to archive sinks
+#[derive(Error, Debug, Clone)]
+pub enum ArchiveError {
+    #[error("Archive storage error: {0}")]
+    Storage(String),
+    
+    #[error("Archive encoding error: {0}")]
+    Encoding(String),
+    
+    #[error("Corrupt archive: {0}")]
+    Corrupt(String),
+}
+
+/// Errors related to analytics engines
+#[derive(Error, Debug, Clone)]
+pub enum AnalyticsError {
+    #[error("Analytics query rejected: {0}")]
+    InvalidQuery(String),
+    
+    #[error("Analytics engine error: {0}")]
+    Engine(String),
+}
+
+/// Errors related to usage telemetry
+#[derive(Error, Debug, Clone)]
+pub enum TelemetryError {
+    #[error("Invalid telemetry configuration: {0}")]
+    Configuration(String),
+    
+    #[error("Telemetry export failed: {0}")]
+    Export(String),
+}
+
+/// Errors related to API tokens
+#[derive(Error, Debug, Clone)]
+pub enum AuthError {
+    #[error("Missing or unknown API token")]
+    InvalidToken,
+    
+    #[error("API token has expired")]
+    TokenExpired,
+    
+    #[error("API token has been revoked")]
+    TokenRevoked,
+    
+    #[error("API token is not valid for tenant {0}")]
+    WrongTenant(String),
+    
+    #[error("API token has {granted} scope, {required} is required")]
+    InsufficientScope { required: TokenScope, granted: TokenScope },
+    
+    #[error("Invalid request signature: {0}")]
+    InvalidSignature(String),
+    
+    #[error("API token not found: {0}")]
+    TokenNotFound(String),
+    
+    #[error("Secret store error: {0}")]
+    Storage(String),
+}
+
+/// Errors related to ephemeral session graphs
+#[derive(Error, Debug, Clone)]
+pub enum SessionError {
+    #[error("Session not found: {0}")]
+    NotFound(String),
+    
+    #[error("Invalid session TTL: {0}")]
+    InvalidTtl(String),
+    
+    #[error("Session limit reached: {0}")]
+    LimitReached(String),
+    
+    #[error("Invalid promotion: {0}")]
+    InvalidPromotion(String),
+}
+
+/// Errors related to graph views
+#[derive(Error, Debug, Clone)]
+pub enum ViewError {
+    #[error("View not found: {0}")]
+    NotFound(String),
+    
+    #[error("View in use: {0}")]
+    InUse(String),
+    
+    #[error("Invalid view: {0}")]
+    Invalid(String),
+}
+
 /// Errors related to source adapters
 #[derive(Error, Debug)]
 pub enum SourceError {
@@ -146,6 +354,308 @@ pub enum SourceError {
     ConfigError(String),
 }
 
+/// Whether a failed request can succeed if sent again unchanged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Transient: the same request may succeed later
+    Retryable,
+    /// The request has to change before it can succeed
+    Permanent,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Retryable => "retryable",
+            ErrorKind::Permanent => "permanent",
+        }
+    }
+}
+
+/// Classification of errors for clients. Codes are stable, snake_case and
+/// unique across error types, so SDKs can match on them instead of messages.
+pub trait ErrorInfo {
+    /// Code of the error, e.g. `node_not_found`
+    fn code(&self) -> &'static str;
+
+    fn kind(&self) -> ErrorKind;
+
+    /// How long to wait before retrying, for retryable errors with a known
+    /// recovery time
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+
+    fn is_retryable(&self) -> bool {
+        self.kind() == ErrorKind::Retryable
+    }
+
+    fn details(&self) -> ErrorDetails {
+        ErrorDetails {
+            code: self.code().to_string(),
+            kind: self.kind(),
+            retry_after_ms: self.retry_after().map(|after| after.as_millis() as u64),
+        }
+    }
+}
+
+/// Classification of an error as sent to clients
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    pub code: String,
+    pub kind: ErrorKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+use ErrorKind::{Permanent, Retryable};
+
+impl ErrorInfo for CoreError {
+    fn code(&self) -> &'static str {
+        match self {
+            CoreError::Storage(e) => e.code(),
+            CoreError::Llm(e) => e.code(),
+            CoreError::Pipeline(e) => e.code(),
+            CoreError::Vector(e) => e.code(),
+            CoreError::Archive(e) => e.code(),
+            CoreError::Analytics(e) => e.code(),
+            CoreError::Auth(e) => e.code(),
+            CoreError::Session(e) => e.code(),
+            CoreError::View(e) => e.code(),
+            CoreError::Tenant(_) => "invalid_tenant",
+            CoreError::Cancelled(_) => "cancelled",
+            CoreError::Temporal(_) => "invalid_temporal_query",
+            CoreError::Serialization(_) => "invalid_format",
+            CoreError::Configuration(_) => "configuration_error",
+            CoreError::Internal(_) => "internal_error",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self {
+            CoreError::Storage(e) => e.kind(),
+            CoreError::Llm(e) => e.kind(),
+            CoreError::Pipeline(e) => e.kind(),
+            CoreError::Vector(e) => e.kind(),
+            CoreError::Archive(e) => e.kind(),
+            CoreError::Analytics(e) => e.kind(),
+            CoreError::Auth(e) => e.kind(),
+            CoreError::Session(e) => e.kind(),
+            CoreError::View(_) | CoreError::Tenant(_) | CoreError::Cancelled(_) | CoreError::Temporal(_) | CoreError::Serialization(_)
+            | CoreError::Configuration(_) | CoreError::Internal(_) => Permanent,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            CoreError::Storage(e) => e.retry_after(),
+            CoreError::Llm(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+}
+
+impl ErrorInfo for GraphError {
+    fn code(&self) -> &'static str {
+        match self {
+            GraphError::ConnectionFailed(_) => "storage_unavailable",
+            GraphError::QueryFailed(_) => "query_failed",
+            GraphError::NodeNotFound(_) => "node_not_found",
+            GraphError::EdgeNotFound(_) => "edge_not_found",
+            GraphError::ConstraintViolation(_) => "constraint_violation",
+            GraphError::TransactionFailed(_) => "transaction_failed",
+            GraphError::TenantIsolationViolation(_) => "tenant_isolation_violation",
+            GraphError::ReservedProperty(_) => "reserved_property",
+            GraphError::DatabaseError(_) => "database_error",
+            GraphError::Timeout(_) => "storage_timeout",
+            GraphError::SnapshotNotFound(_) => "snapshot_not_found",
+            GraphError::Unsupported(_) => "unsupported",
+            GraphError::Temporal(_) => "invalid_temporal_data",
+            GraphError::SchemaOutdated(_) => "schema_outdated",
+            GraphError::Overloaded(_) => "overloaded",
+            GraphError::InvalidTenant(_) => "invalid_tenant",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self {
+            GraphError::ConnectionFailed(_) | GraphError::TransactionFailed(_) | GraphError::DatabaseError(_)
+            | GraphError::Timeout(_) | GraphError::SchemaOutdated(_) | GraphError::Overloaded(_) => Retryable,
+            _ => Permanent,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            GraphError::Overloaded(_) => Some(Duration::from_secs(1)),
+            GraphError::ConnectionFailed(_) => Some(Duration::from_secs(5)),
+            // Another replica is migrating the schema
+            GraphError::SchemaOutdated(_) => Some(Duration::from_secs(10)),
+            _ => None,
+        }
+    }
+}
+
+impl ErrorInfo for LlmError {
+    fn code(&self) -> &'static str {
+        match self {
+            LlmError::ConfigError(_) => "llm_configuration_error",
+            LlmError::NetworkError(_) => "llm_network_error",
+            LlmError::ApiError(_) => "llm_api_error",
+            LlmError::Timeout => "llm_timeout",
+            LlmError::RateLimited(_) => "llm_rate_limited",
+            LlmError::ResponseParseError(_) => "llm_response_unparsable",
+            LlmError::SchemaValidationError(_) => "llm_response_invalid",
+            LlmError::BudgetExceeded => "budget_exceeded",
+            LlmError::UnsafeInput(_) => "unsafe_input",
+            LlmError::ContextLengthExceeded(_) => "context_length_exceeded",
+            LlmError::CapabilityUnavailable(_) => "llm_unavailable",
+            LlmError::ProviderNotAllowed(_) => "provider_not_allowed",
+            LlmError::ContentBlocked(_) => "content_blocked",
+            LlmError::InternalError(_) => "llm_internal_error",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self {
+            // Models are not deterministic, so a malformed response may not
+            // recur
+            LlmError::NetworkError(_) | LlmError::Timeout | LlmError::RateLimited(_) | LlmError::ResponseParseError(_)
+            | LlmError::SchemaValidationError(_) | LlmError::CapabilityUnavailable(_) => Retryable,
+            _ => Permanent,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LlmError::RateLimited(_) => Some(Duration::from_secs(10)),
+            LlmError::CapabilityUnavailable(_) => Some(Duration::from_secs(30)),
+            _ => None,
+        }
+    }
+}
+
+impl ErrorInfo for PipelineError {
+    fn code(&self) -> &'static str {
+        match self {
+            PipelineError::PluginInitFailed(_) => "plugin_init_failed",
+            PipelineError::PluginExecutionFailed(_) => "plugin_failed",
+            PipelineError::ConfigurationError(_) => "pipeline_configuration_error",
+            PipelineError::StageExecutionFailed(_) => "pipeline_stage_failed",
+            PipelineError::PluginNotFound(_) => "plugin_not_found",
+            PipelineError::PipelineHalted(_) => "request_rejected",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        Permanent
+    }
+}
+
+impl ErrorInfo for VectorError {
+    fn code(&self) -> &'static str {
+        match self {
+            VectorError::DimensionMismatch { .. } => "vector_dimension_mismatch",
+            VectorError::InvalidVector(_) => "invalid_vector",
+            VectorError::Storage(_) => "vector_storage_error",
+            VectorError::Corrupt(_) => "vector_index_corrupt",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self {
+            VectorError::Storage(_) => Retryable,
+            _ => Permanent,
+        }
+    }
+}
+
+impl ErrorInfo for ArchiveError {
+    fn code(&self) -> &'static str {
+        match self {
+            ArchiveError::Storage(_) => "archive_storage_error",
+            ArchiveError::Encoding(_) => "archive_encoding_error",
+            ArchiveError::Corrupt(_) => "archive_corrupt",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ArchiveError::Storage(_) => Retryable,
+            _ => Permanent,
+        }
+    }
+}
+
+impl ErrorInfo for AnalyticsError {
+    fn code(&self) -> &'static str {
+        match self {
+            AnalyticsError::InvalidQuery(_) => "invalid_analytics_query",
+            AnalyticsError::Engine(_) => "analytics_engine_error",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        Permanent
+    }
+}
+
+impl ErrorInfo for AuthError {
+    fn code(&self) -> &'static str {
+        match self {
+            AuthError::InvalidToken => "invalid_token",
+            AuthError::TokenExpired => "token_expired",
+            AuthError::TokenRevoked => "token_revoked",
+            AuthError::WrongTenant(_) => "wrong_tenant",
+            AuthError::InsufficientScope { .. } => "insufficient_scope",
+            AuthError::InvalidSignature(_) => "invalid_signature",
+            AuthError::TokenNotFound(_) => "token_not_found",
+            AuthError::Storage(_) => "secret_store_error",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self {
+            AuthError::Storage(_) => Retryable,
+            _ => Permanent,
+        }
+    }
+}
+
+impl ErrorInfo for SessionError {
+    fn code(&self) -> &'static str {
+        match self {
+            SessionError::NotFound(_) => "session_not_found",
+            SessionError::InvalidTtl(_) => "invalid_session_ttl",
+            SessionError::LimitReached(_) => "session_limit_reached",
+            SessionError::InvalidPromotion(_) => "invalid_promotion",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self {
+            // Frees up as other sessions end or expire
+            SessionError::LimitReached(_) => Retryable,
+            _ => Permanent,
+        }
+    }
+}
+
+impl ErrorInfo for ViewError {
+    fn code(&self) -> &'static str {
+        match self {
+            ViewError::NotFound(_) => "view_not_found",
+            ViewError::InUse(_) => "view_in_use",
+            ViewError::Invalid(_) => "invalid_view",
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        Permanent
+    }
+}
+
 /// Result type alias for core operations
 pub type CoreResult<T> = Result<T, CoreError>;
 
@@ -153,4 +663,23 @@ pub type CoreResult<T> = Result<T, CoreError>;
 pub type GraphResult<T> = Result<T, GraphError>;
 
 /// Result type alias for LLM operations
-pub type LlmResult<T> = Result<T, LlmError>;
\ No newline at end of file
+pub type LlmResult<T> = Result<T, LlmError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_info() {
+        let error = CoreError::from(GraphError::Overloaded("busy".to_string()));
+        assert_eq!((error.code(), error.kind()), ("overloaded", ErrorKind::Retryable));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(1)));
+
+        let error = CoreError::from(LlmError::BudgetExceeded);
+        assert!(!error.is_retryable());
+        assert_eq!(serde_json::to_value(error.details()).unwrap(), serde_json::json!({"code": "budget_exceeded", "kind": "permanent"}));
+
+        assert_eq!(CoreError::Cancelled("client left".to_string()).code(), "cancelled");
+        assert!(CoreError::from(SessionError::LimitReached("10 sessions".to_string())).is_retryable());
+    }
+}
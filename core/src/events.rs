@@ -0,0 +1,78 @@
+//! In-process bus of graph mutation events
+//!
+//! Components that write to a `GraphStore` publish a [`MutationEvent`] after
+//! each successful write; components that hold derived state, such as the
+//! query cache, subscribe to learn when a tenant's graph has changed.
+//! Operators flushing caches publish a `CacheFlush` event the same way.
+
+use crate::types::TenantId;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::trace;
+
+/// Default number of events a lagging subscriber can fall behind by
+pub const DEFAULT_EVENT_CAPACITY: usize = 1_024;
+
+/// Kind of write that changed a tenant's graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationKind {
+    UpsertNode,
+    UpsertEdge,
+    UpsertNodeWithEdges,
+    DeleteNode,
+    DeleteEdge,
+    CloseEdge,
+    SupersedeEdge,
+    RetractEdge,
+    PurgeHistory,
+    PurgeExpiredEdges,
+    RestoreHistory,
+    ClearTenant,
+    /// Not a write: an operator asked for the tenant's cached results to be dropped
+    CacheFlush,
+}
+
+/// A successful write to a tenant's graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutationEvent {
+    /// Tenant whose graph changed
+    pub tenant: TenantId,
+    /// What kind of write it was
+    pub kind: MutationKind,
+}
+
+/// Broadcast channel for mutation events
+#[derive(Debug, Clone)]
+pub struct MutationEventBus {
+    sender: broadcast::Sender<MutationEvent>,
+}
+
+impl Default for MutationEventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+impl MutationEventBus {
+    /// Create a bus that buffers up to `capacity` events per subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Publish an event to all current subscribers
+    pub fn publish(&self, tenant: &TenantId, kind: MutationKind) {
+        trace!("Publishing {:?} mutation for tenant {}", kind, tenant);
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(MutationEvent {
+            tenant: tenant.clone(),
+            kind,
+        });
+    }
+
+    /// Subscribe to events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<MutationEvent> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,221 @@
+//! Few-shot examples for LLM extraction
+//!
+//! Each tenant can keep a set of worked examples (input text and the envelope
+//! it should produce). Connectors render them into the extraction prompt,
+//! within a token budget, to steer extraction towards the tenant's domain. A
+//! request can replace the tenant's examples through `ExtractionContext::examples`.
+
+use crate::tokens::{model_limits, TokenEstimator};
+use crate::traits::{ExtractionContext, ExtractionEnvelope};
+use crate::types::TenantId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Maximum prompt tokens spent on examples
+pub const DEFAULT_EXAMPLE_TOKEN_BUDGET: usize = 2_000;
+
+/// A worked extraction example: input text and the expected envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionExample {
+    /// Identifier used to manage the example
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
+    /// Text the example extracts from
+    pub input: String,
+    /// Envelope the extraction should produce for `input`
+    pub expected: ExtractionEnvelope,
+    /// Optional note on what the example demonstrates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl ExtractionExample {
+    /// Create an example from input text and the expected envelope
+    pub fn new(input: impl Into<String>, expected: ExtractionEnvelope) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            input: input.into(),
+            expected,
+            description: None,
+        }
+    }
+
+    /// Set the description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Render the example as it appears in a prompt
+    fn render(&self) -> String {
+        let expected = serde_json::json!({
+            "nodes": self.expected.nodes,
+            "relations": self.expected.relations,
+        });
+
+        format!("Input:\n{}\nOutput:\n{}", self.input.trim(), expected)
+    }
+}
+
+/// Tokens to spend on examples for a model: the default budget, capped at a
+/// quarter of the model's context window
+pub fn example_token_budget(model: &str) -> usize {
+    DEFAULT_EXAMPLE_TOKEN_BUDGET.min(model_limits(model).context_window / 4)
+}
+
+/// Render examples as a prompt section, in order, skipping any that would
+/// exceed `budget` tokens. Returns an empty string when none fit.
+pub fn render_examples(examples: &[ExtractionExample], estimator: TokenEstimator, budget: usize) -> String {
+    let mut rendered = Vec::new();
+    let mut used = 0;
+
+    for example in examples {
+        let text = example.render();
+        let tokens = estimator.count(&text);
+        if used + tokens > budget {
+            continue;
+        }
+        used += tokens;
+        rendered.push(format!("Example {}:\n{}", rendered.len() + 1, text));
+    }
+
+    if rendered.is_empty() {
+        return String::new();
+    }
+
+    format!("\n\nExamples of correct extractions:\n\n{}", rendered.join("\n\n"))
+}
+
+/// In-memory per-tenant store of few-shot examples
+#[derive(Debug, Default)]
+pub struct FewShotStore {
+    examples: RwLock<HashMap<TenantId, Vec<ExtractionExample>>>,
+}
+
+impl FewShotStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// List a tenant's examples in prompt order
+    pub async fn list(&self, tenant: &TenantId) -> Vec<ExtractionExample> {
+        self.examples.read().await.get(tenant).cloned().unwrap_or_default()
+    }
+
+    /// Append an example, returning its ID
+    pub async fn add(&self, tenant: &TenantId, example: ExtractionExample) -> Uuid {
+        let id = example.id;
+        self.examples.write().await.entry(tenant.clone()).or_default().push(example);
+        id
+    }
+
+    /// Replace all of a tenant's examples
+    pub async fn replace(&self, tenant: &TenantId, examples: Vec<ExtractionExample>) {
+        let mut store = self.examples.write().await;
+        if examples.is_empty() {
+            store.remove(tenant);
+        } else {
+            store.insert(tenant.clone(), examples);
+        }
+    }
+
+    /// Remove an example; returns `false` if it did not exist
+    pub async fn remove(&self, tenant: &TenantId, id: Uuid) -> bool {
+        let mut store = self.examples.write().await;
+        let Some(examples) = store.get_mut(tenant) else {
+            return false;
+        };
+
+        let before = examples.len();
+        examples.retain(|example| example.id != id);
+        let removed = examples.len() != before;
+        if examples.is_empty() {
+            store.remove(tenant);
+        }
+        removed
+    }
+
+    /// Use the tenant's examples for a request that does not bring its own
+    pub async fn apply(&self, tenant: &TenantId, context: &mut ExtractionContext) {
+        if context.examples.is_none() {
+            let examples = self.list(tenant).await;
+            if !examples.is_empty() {
+                context.examples = Some(examples);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::ExtractionNode;
+
+    fn example(input: &str) -> ExtractionExample {
+        ExtractionExample::new(input, ExtractionEnvelope {
+            nodes: vec![ExtractionNode {
+                id_alias: "alice".to_string(),
+                label: "Person".to_string(),
+                props: serde_json::json!({"name": "Alice"}),
+                confidence: None,
+            }],
+            relations: vec![],
+            metadata: None,
+        })
+    }
+
+    fn context() -> ExtractionContext {
+        ExtractionContext {
+            messages: vec![],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        }
+    }
+
+    #[test]
+    fn test_render_examples_within_budget() {
+        let estimator = TokenEstimator::CharRatio(4.0);
+        let short = example("Alice joined.");
+        let long = example(&"Alice joined the company. ".repeat(200));
+
+        let rendered = render_examples(&[long.clone(), short], estimator, 100);
+        assert!(rendered.contains("Example 1:\nInput:\nAlice joined.\nOutput:\n{\"nodes\""));
+        assert!(!rendered.contains("Example 2"));
+
+        assert_eq!(render_examples(&[long], estimator, 100), "");
+        assert_eq!(example_token_budget("gpt-4"), DEFAULT_EXAMPLE_TOKEN_BUDGET);
+    }
+
+    #[tokio::test]
+    async fn test_store_and_request_override() {
+        let store = FewShotStore::new();
+        let tenant = TenantId::new("tenant");
+        let first = store.add(&tenant, example("first")).await;
+        store.add(&tenant, example("second")).await;
+
+        assert!(store.remove(&tenant, first).await);
+        assert!(!store.remove(&tenant, first).await);
+        assert_eq!(store.list(&tenant).await.len(), 1);
+        assert!(store.list(&TenantId::new("other")).await.is_empty());
+
+        let mut ctx = context();
+        store.apply(&tenant, &mut ctx).await;
+        assert_eq!(ctx.examples.as_ref().unwrap()[0].input, "second");
+
+        // An explicit empty list disables the tenant's examples
+        let mut ctx = ExtractionContext { examples: Some(vec![]), ..context() };
+        store.apply(&tenant, &mut ctx).await;
+        assert!(ctx.examples.unwrap().is_empty());
+    }
+}
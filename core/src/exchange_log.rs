@@ -0,0 +1,369 @@
+//! Opt-in logging of raw LLM provider exchanges
+//!
+//! Debugging extraction quality needs the exact prompt a connector sent and
+//! the raw response it got back, but both carry user data. When logging is
+//! enabled for a tenant, connectors record a sample of their provider
+//! requests and responses here, with configured JSON fields redacted. Each
+//! exchange is one JSON file under `<dir>/<tenant>/`, named so that listing
+//! the directory orders exchanges by time; exchanges older than the retention
+//! period or beyond `max_per_tenant` are removed.
+
+use crate::capture::files_in;
+use crate::errors::{CoreError, LlmError};
+use crate::types::TenantId;
+use crate::vector_index::partition_dir;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Instant;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Replacement for the values of redacted fields
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Logging settings of one tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenantExchangeLogging {
+    /// Share of the tenant's exchanges recorded, from 0 to 1
+    pub sample_rate: f64,
+    /// Fields redacted in this tenant's exchanges; `None` uses the configured default
+    #[serde(default)]
+    pub redact_fields: Option<Vec<String>>,
+}
+
+/// Configuration for [`ExchangeLog`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExchangeLogConfig {
+    /// Directory holding one subdirectory of exchanges per tenant
+    pub dir: PathBuf,
+    /// Tenants whose exchanges are logged
+    pub tenants: HashMap<TenantId, TenantExchangeLogging>,
+    /// JSON object keys whose values are redacted at any depth, compared
+    /// case-insensitively (e.g. `content` hides message text)
+    pub redact_fields: Vec<String>,
+    /// Largest request or response body recorded, in bytes; larger bodies
+    /// are replaced by a note of their size
+    pub max_body_bytes: usize,
+    /// Exchanges kept per tenant; older ones are removed
+    pub max_per_tenant: usize,
+    /// How long exchanges are kept, in hours
+    pub retention_hours: Option<u64>,
+}
+
+impl Default for ExchangeLogConfig {
+    fn default() -> Self {
+        Self {
+            dir: "data/llm_exchanges".into(),
+            tenants: HashMap::new(),
+            redact_fields: Vec::new(),
+            max_body_bytes: 256 * 1024,
+            max_per_tenant: 500,
+            retention_hours: Some(72),
+        }
+    }
+}
+
+/// A provider request and its response as recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmExchange {
+    pub id: Uuid,
+    pub tenant: TenantId,
+    pub provider: String,
+    pub model: String,
+    /// Request body sent to the provider
+    pub request: Value,
+    /// Response body; a string if the provider did not return JSON
+    pub response: Option<Value>,
+    pub error: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+/// An exchange whose response has not arrived yet
+pub struct PendingExchange {
+    exchange: LlmExchange,
+    started: Instant,
+}
+
+/// Per-tenant log of LLM exchanges, persisted to disk
+pub struct ExchangeLog {
+    config: ExchangeLogConfig,
+    tenants: RwLock<HashMap<TenantId, TenantExchangeLogging>>,
+}
+
+impl ExchangeLog {
+    /// Create an exchange log, enabling the tenants listed in the configuration
+    pub fn new(config: ExchangeLogConfig) -> Self {
+        let tenants = config.tenants.iter()
+            .map(|(tenant, logging)| {
+                let sample_rate = logging.sample_rate.clamp(0.0, 1.0);
+                (tenant.clone(), TenantExchangeLogging { sample_rate, ..logging.clone() })
+            })
+            .collect();
+        Self {
+            config,
+            tenants: RwLock::new(tenants),
+        }
+    }
+
+    /// Log `logging.sample_rate` of the tenant's exchanges from now on
+    pub fn enable(&self, tenant: &TenantId, logging: TenantExchangeLogging) -> Result<(), CoreError> {
+        if !(0.0..=1.0).contains(&logging.sample_rate) {
+            return Err(CoreError::Configuration(format!("Sample rate {} is not between 0 and 1", logging.sample_rate)));
+        }
+        info!("Logging {:.0}% of LLM exchanges for tenant {}", logging.sample_rate * 100.0, tenant);
+        self.tenants.write().unwrap().insert(tenant.clone(), logging);
+        Ok(())
+    }
+
+    /// Stop logging the tenant's exchanges; recorded exchanges are kept
+    pub fn disable(&self, tenant: &TenantId) -> bool {
+        self.tenants.write().unwrap().remove(tenant).is_some()
+    }
+
+    /// The tenant's settings, if logging is enabled for it
+    pub fn settings(&self, tenant: &TenantId) -> Option<TenantExchangeLogging> {
+        self.tenants.read().unwrap().get(tenant).cloned()
+    }
+
+    /// Start an exchange if the tenant is logging and the exchange is sampled.
+    /// Connectors call this before sending a request and pass the result to
+    /// [`Self::finish`].
+    pub fn begin(&self, tenant: &TenantId, provider: &str, model: &str, request: &impl Serialize) -> Option<PendingExchange> {
+        let rate = self.settings(tenant)?.sample_rate;
+        let id = Uuid::new_v4();
+        if (id.as_u128() as u64) as f64 / u64::MAX as f64 >= rate {
+            return None;
+        }
+
+        let request = match serde_json::to_value(request) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("Failed to serialize LLM request for the exchange log: {}", e);
+                return None;
+            }
+        };
+        Some(PendingExchange {
+            exchange: LlmExchange {
+                id,
+                tenant: tenant.clone(),
+                provider: provider.to_string(),
+                model: model.to_string(),
+                request,
+                response: None,
+                error: None,
+                recorded_at: Utc::now(),
+                duration_ms: 0,
+            },
+            started: Instant::now(),
+        })
+    }
+
+    /// Record the outcome of an exchange: the raw response body or the error.
+    /// Failures to write are logged, never returned, so logging cannot fail
+    /// an extraction.
+    pub async fn finish(&self, pending: PendingExchange, result: Result<&str, &LlmError>) {
+        let PendingExchange { mut exchange, started } = pending;
+        exchange.duration_ms = started.elapsed().as_millis() as u64;
+        match result {
+            Ok(body) => {
+                exchange.response = Some(serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.to_string())));
+            }
+            Err(e) => exchange.error = Some(e.to_string()),
+        }
+
+        let fields = self.settings(&exchange.tenant)
+            .and_then(|logging| logging.redact_fields)
+            .unwrap_or_else(|| self.config.redact_fields.clone());
+        for body in std::iter::once(&mut exchange.request).chain(exchange.response.as_mut()) {
+            redact(body, &fields);
+            self.limit_size(body);
+        }
+
+        if let Err(e) = self.record(&exchange).await {
+            warn!("Failed to record LLM exchange {}: {}", exchange.id, e);
+        }
+    }
+
+    /// Read a recorded exchange
+    pub async fn get(&self, tenant: &TenantId, id: Uuid) -> Result<Option<LlmExchange>, CoreError> {
+        let suffix = format!("-{}.json", id);
+        let dir = partition_dir(&self.config.dir, tenant);
+        let Some(path) = files_in(&dir).await?.into_iter()
+            .find(|path| path.to_string_lossy().ends_with(&suffix)) else {
+            return Ok(None);
+        };
+
+        let data = tokio::fs::read(&path).await.map_err(|e| io_error(&path, e))?;
+        Ok(Some(serde_json::from_slice(&data)?))
+    }
+
+    /// The tenant's recorded exchanges, oldest first
+    pub async fn list(&self, tenant: &TenantId) -> Result<Vec<LlmExchange>, CoreError> {
+        let dir = partition_dir(&self.config.dir, tenant);
+        self.prune(&dir).await?;
+
+        let mut exchanges = Vec::new();
+        for path in files_in(&dir).await? {
+            let data = tokio::fs::read(&path).await.map_err(|e| io_error(&path, e))?;
+            exchanges.push(serde_json::from_slice(&data)?);
+        }
+        Ok(exchanges)
+    }
+
+    async fn record(&self, exchange: &LlmExchange) -> Result<(), CoreError> {
+        let dir = partition_dir(&self.config.dir, &exchange.tenant);
+        tokio::fs::create_dir_all(&dir).await.map_err(|e| io_error(&dir, e))?;
+
+        let name = format!("{:016}-{}.json", exchange.recorded_at.timestamp_micros(), exchange.id);
+        let path = dir.join(name);
+        tokio::fs::write(&path, serde_json::to_vec_pretty(exchange)?).await.map_err(|e| io_error(&path, e))?;
+        debug!("Recorded {} exchange {} for tenant {}", exchange.provider, exchange.id, exchange.tenant);
+
+        self.prune(&dir).await
+    }
+
+    /// Remove exchanges past the retention period or beyond the per-tenant limit
+    async fn prune(&self, dir: &Path) -> Result<(), CoreError> {
+        let files = files_in(dir).await?;
+        let cutoff = self.config.retention_hours
+            .map(|hours| (Utc::now() - chrono::Duration::hours(hours as i64)).timestamp_micros());
+        let expired = files.iter()
+            .take_while(|path| cutoff.is_some_and(|cutoff| recorded_micros(path).is_some_and(|micros| micros < cutoff)))
+            .count();
+        let excess = files.len().saturating_sub(self.config.max_per_tenant).max(expired);
+
+        for path in &files[..excess] {
+            tokio::fs::remove_file(path).await.map_err(|e| io_error(path, e))?;
+        }
+        Ok(())
+    }
+
+    /// Replace a body larger than `max_body_bytes` with a note of its size
+    fn limit_size(&self, body: &mut Value) {
+        let size = body.to_string().len();
+        if size > self.config.max_body_bytes {
+            *body = Value::String(format!("[{} bytes omitted]", size));
+        }
+    }
+}
+
+/// Redact the values of `fields` at any depth. JSON encoded in strings, such
+/// as tool call arguments, is redacted too.
+pub fn redact(value: &mut Value, fields: &[String]) {
+    if fields.is_empty() {
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.iter().any(|field| field.eq_ignore_ascii_case(key)) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact(value, fields);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, fields)),
+        Value::String(text) if text.trim_start().starts_with(['{', '[']) => {
+            if let Ok(mut nested) = serde_json::from_str::<Value>(text) {
+                redact(&mut nested, fields);
+                *text = nested.to_string();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Recording time encoded in an exchange file name
+fn recorded_micros(path: &Path) -> Option<i64> {
+    path.file_name()?.to_str()?.split('-').next()?.parse().ok()
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> CoreError {
+    CoreError::Internal(format!("Exchange log {}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn exchange_log(config: ExchangeLogConfig) -> ExchangeLog {
+        let dir = std::env::temp_dir().join(format!("telamentis-exchanges-{}", Uuid::new_v4()));
+        ExchangeLog::new(ExchangeLogConfig { dir, ..config })
+    }
+
+    fn request() -> Value {
+        json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Alice's phone is 555-0100"}],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_logging_is_opt_in_and_redacted() {
+        let log = exchange_log(ExchangeLogConfig { redact_fields: vec!["content".to_string()], ..Default::default() });
+        let tenant = TenantId::new("acme");
+        assert!(log.begin(&tenant, "openai", "gpt-4o", &request()).is_none());
+
+        log.enable(&tenant, TenantExchangeLogging { sample_rate: 1.0, redact_fields: None }).unwrap();
+        let pending = log.begin(&tenant, "openai", "gpt-4o", &request()).unwrap();
+        let response = r#"{"choices":[{"message":{"content":null,"tool_calls":[{"function":{"arguments":"{\"phone\":\"555-0100\"}"}}]}}]}"#;
+        log.finish(pending, Ok(response)).await;
+
+        let exchanges = log.list(&tenant).await.unwrap();
+        assert_eq!(exchanges.len(), 1);
+        let exchange = &exchanges[0];
+        assert_eq!(exchange.request["model"], "gpt-4o");
+        assert_eq!(exchange.request["messages"][0]["content"], REDACTED);
+        assert_eq!(exchange.response.as_ref().unwrap()["choices"][0]["message"]["content"], REDACTED);
+        assert_eq!(log.get(&tenant, exchange.id).await.unwrap().as_ref(), Some(exchange));
+
+        // A tenant's own fields replace the default, including inside encoded JSON
+        log.enable(&tenant, TenantExchangeLogging { sample_rate: 1.0, redact_fields: Some(vec!["phone".to_string()]) }).unwrap();
+        let pending = log.begin(&tenant, "openai", "gpt-4o", &request()).unwrap();
+        log.finish(pending, Ok(response)).await;
+        let exchange = log.list(&tenant).await.unwrap().pop().unwrap();
+        assert_eq!(exchange.request["messages"][0]["content"], "Alice's phone is 555-0100");
+        assert!(!exchange.response.unwrap().to_string().contains("555-0100"));
+
+        assert!(log.enable(&tenant, TenantExchangeLogging { sample_rate: 2.0, redact_fields: None }).is_err());
+        assert!(log.disable(&tenant));
+        assert!(log.begin(&tenant, "openai", "gpt-4o", &request()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_retention() {
+        let log = exchange_log(ExchangeLogConfig { max_per_tenant: 2, max_body_bytes: 64, ..Default::default() });
+        let tenant = TenantId::new("acme");
+        log.enable(&tenant, TenantExchangeLogging { sample_rate: 1.0, redact_fields: None }).unwrap();
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let pending = log.begin(&tenant, "openai", "gpt-4o", &json!({"prompt": "hi"})).unwrap();
+            ids.push(pending.exchange.id);
+            log.finish(pending, Err(&LlmError::RateLimited("slow down".to_string()))).await;
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+        let pending = log.begin(&tenant, "openai", "gpt-4o", &request()).unwrap();
+        log.finish(pending, Ok(&"x".repeat(100))).await;
+
+        let exchanges = log.list(&tenant).await.unwrap();
+        assert_eq!(exchanges[0].id, ids[2]);
+        assert!(exchanges[0].error.as_deref().unwrap().contains("slow down"));
+        assert_eq!(exchanges[1].response, Some(json!("[102 bytes omitted]")));
+
+        let expired = exchange_log(ExchangeLogConfig { retention_hours: Some(0), ..Default::default() });
+        expired.enable(&tenant, TenantExchangeLogging { sample_rate: 1.0, redact_fields: None }).unwrap();
+        let pending = expired.begin(&tenant, "openai", "gpt-4o", &request()).unwrap();
+        expired.finish(pending, Ok("{}")).await;
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        assert!(expired.list(&tenant).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Expiring edges
+//!
+//! Short-lived facts such as "currently viewing" or "active session" carry an
+//! `expires_at` on their edge. Stores leave expired edges out of reads on
+//! their own; the sweeper here removes them for good in the background, and
+//! [`ExpiredEdgesJob`] lets operators do the same on demand.
+
+use crate::errors::CoreError;
+use crate::traits::{GraphStore, MaintenanceJob};
+use crate::types::TenantId;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Configuration for [`EdgeExpirySweeper`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EdgeExpiryConfig {
+    /// How often expired edges are removed, in milliseconds
+    pub sweep_interval_ms: u64,
+}
+
+impl Default for EdgeExpiryConfig {
+    fn default() -> Self {
+        Self { sweep_interval_ms: 60_000 }
+    }
+}
+
+/// Remove every tenant's expired edges, returning how many were removed
+async fn sweep(store: &dyn GraphStore) -> Result<u64, CoreError> {
+    let now = Utc::now();
+    let mut purged = 0;
+    for tenant in store.list_tenants().await? {
+        match store.purge_expired_edges(&tenant, now).await {
+            Ok(0) => {}
+            Ok(count) => {
+                debug!("Removed {} expired edges of tenant {}", count, tenant);
+                purged += count;
+            }
+            Err(e) => warn!("Failed to remove expired edges of tenant {}: {}", tenant, e),
+        }
+    }
+    Ok(purged)
+}
+
+/// Background task removing expired edges from a store.
+///
+/// Must be started inside a Tokio runtime. Call
+/// [`EdgeExpirySweeper::shutdown`] before dropping it to stop it cleanly.
+pub struct EdgeExpirySweeper {
+    shutdown: Arc<Notify>,
+    sweeper: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl EdgeExpirySweeper {
+    /// Start removing the expired edges of all tenants in `store`
+    pub fn start(store: Arc<dyn GraphStore>, config: EdgeExpiryConfig) -> Self {
+        let shutdown = Arc::new(Notify::new());
+        let sweeper = tokio::spawn(Self::run(store, config, shutdown.clone()));
+
+        Self {
+            shutdown,
+            sweeper: std::sync::Mutex::new(Some(sweeper)),
+        }
+    }
+
+    async fn run(store: Arc<dyn GraphStore>, config: EdgeExpiryConfig, shutdown: Arc<Notify>) {
+        let mut interval = tokio::time::interval(Duration::from_millis(config.sweep_interval_ms.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => match sweep(store.as_ref()).await {
+                    Ok(0) => {}
+                    Ok(purged) => info!("Removed {} expired edges", purged),
+                    Err(e) => warn!("Failed to list tenants for the edge expiry sweep: {}", e),
+                },
+                _ = shutdown.notified() => break,
+            }
+        }
+    }
+
+    /// Stop the sweeper, waiting for a sweep in progress to finish
+    pub async fn shutdown(&self) {
+        self.shutdown.notify_one();
+
+        let sweeper = self.sweeper.lock().unwrap().take();
+        if let Some(sweeper) = sweeper {
+            let _ = sweeper.await;
+        }
+    }
+}
+
+impl Drop for EdgeExpirySweeper {
+    fn drop(&mut self) {
+        if let Some(sweeper) = self.sweeper.lock().unwrap().take() {
+            sweeper.abort();
+        }
+    }
+}
+
+/// Maintenance job removing a tenant's expired edges
+pub struct ExpiredEdgesJob {
+    store: Arc<dyn GraphStore>,
+}
+
+impl ExpiredEdgesJob {
+    pub fn new(store: Arc<dyn GraphStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl MaintenanceJob for ExpiredEdgesJob {
+    async fn run(&self, tenant: &TenantId) -> Result<u64, CoreError> {
+        Ok(self.store.purge_expired_edges(tenant, Utc::now()).await?)
+    }
+}
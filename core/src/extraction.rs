@@ -0,0 +1,655 @@
+//! Chunked extraction for inputs that exceed a model's context window
+//!
+//! Connectors use [`extract_chunked`] to stay within their own context window.
+//! [`ExtractionOrchestrator`] wraps any connector and splits long inputs into
+//! smaller, overlapping chunks that are extracted concurrently, which is both
+//! faster and less lossy than a single pass over a long transcript. Both
+//! report the chunks extracted so far with `progress::report_progress`.
+
+use crate::errors::LlmError;
+use crate::progress::report_progress;
+use crate::tokens::TokenEstimator;
+use crate::traits::{
+    CompletionRequest, CompletionResponse, ExtractionContext, ExtractionEnvelope, ExtractionMetadata,
+    ExtractionNode, ExtractionRelation, LlmConnector, LlmMessage,
+};
+use crate::types::TenantId;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+/// Split an extraction context into contexts whose messages fit `budget` tokens.
+///
+/// Messages are packed greedily in order; a message that is too large on its
+/// own is split on whitespace. Every chunk keeps the system prompt, schema and
+/// generation settings of the original context.
+pub fn chunk_context(context: &ExtractionContext, estimator: TokenEstimator, budget: usize) -> Vec<ExtractionContext> {
+    chunk_context_with_overlap(context, estimator, budget, 0)
+}
+
+/// Like [`chunk_context`], but each chunk starts with the trailing messages of
+/// the previous chunk, up to `overlap` tokens, so facts that span a chunk
+/// boundary are seen together at least once.
+pub fn chunk_context_with_overlap(
+    context: &ExtractionContext,
+    estimator: TokenEstimator,
+    budget: usize,
+    overlap: usize,
+) -> Vec<ExtractionContext> {
+    let mut chunks = Vec::new();
+    let mut current: Vec<LlmMessage> = Vec::new();
+    let mut current_tokens = 0;
+    // Whether `current` holds messages not already sent in the previous chunk
+    let mut has_new_messages = false;
+
+    for message in split_oversized_messages(&context.messages, estimator, budget) {
+        let tokens = estimator.count_message(&message);
+        if has_new_messages && current_tokens + tokens > budget {
+            let carried = overlap_tail(&current, estimator, overlap);
+            chunks.push(with_messages(context, std::mem::replace(&mut current, carried)));
+            current_tokens = estimator.count_messages(&current);
+        }
+
+        // Drop carried messages that would push the new message over budget
+        while !current.is_empty() && current_tokens + tokens > budget {
+            current_tokens -= estimator.count_message(&current.remove(0));
+        }
+
+        current_tokens += tokens;
+        current.push(message);
+        has_new_messages = true;
+    }
+
+    if has_new_messages || chunks.is_empty() {
+        chunks.push(with_messages(context, current));
+    }
+
+    chunks
+}
+
+/// Merge envelopes extracted from chunks of the same input.
+///
+/// Nodes are deduplicated by `id_alias`; properties are combined (first value
+/// wins) and the highest confidence is kept. Relations with the same endpoints
+/// and type are reconciled when their validity periods agree or one of them is
+/// undated, filling in whichever bounds are missing; relations with different
+/// `valid_from` dates are kept as separate facts. Token counts, cost and
+/// latency are summed.
+pub fn merge_envelopes(envelopes: Vec<ExtractionEnvelope>) -> ExtractionEnvelope {
+    let mut nodes: Vec<ExtractionNode> = Vec::new();
+    let mut node_index: HashMap<String, usize> = HashMap::new();
+    let mut relations: Vec<ExtractionRelation> = Vec::new();
+    let mut relation_index: HashMap<(String, String, String), Vec<usize>> = HashMap::new();
+    let mut metadata: Option<ExtractionMetadata> = None;
+
+    for envelope in envelopes {
+        for node in envelope.nodes {
+            match node_index.get(&node.id_alias) {
+                Some(&index) => {
+                    let existing = &mut nodes[index];
+                    merge_props(&mut existing.props, node.props);
+                    existing.confidence = max_confidence(existing.confidence, node.confidence);
+                }
+                None => {
+                    node_index.insert(node.id_alias.clone(), nodes.len());
+                    nodes.push(node);
+                }
+            }
+        }
+
+        for relation in envelope.relations {
+            let key = (
+                relation.from_id_alias.clone(),
+                relation.to_id_alias.clone(),
+                relation.type_label.clone(),
+            );
+            let candidates = relation_index.entry(key).or_default();
+            let matching = candidates.iter().copied().find(|&index| {
+                let existing = &relations[index];
+                existing.valid_from.is_none() || relation.valid_from.is_none() || existing.valid_from == relation.valid_from
+            });
+
+            match matching {
+                Some(index) => {
+                    let existing = &mut relations[index];
+                    merge_props(&mut existing.props, relation.props);
+                    existing.valid_from = existing.valid_from.or(relation.valid_from);
+                    existing.valid_to = existing.valid_to.or(relation.valid_to);
+                    existing.confidence = max_confidence(existing.confidence, relation.confidence);
+                }
+                None => {
+                    candidates.push(relations.len());
+                    relations.push(relation);
+                }
+            }
+        }
+
+        if let Some(chunk_metadata) = envelope.metadata {
+            metadata = Some(match metadata {
+                Some(combined) => combine_metadata(combined, chunk_metadata),
+                None => chunk_metadata,
+            });
+        }
+    }
+
+    ExtractionEnvelope {
+        nodes,
+        relations,
+        metadata,
+    }
+}
+
+/// Extract from a context that may exceed the model's context window.
+///
+/// The input is chunked to `budget` tokens, `extract` is called for each chunk
+/// in order (map) and the resulting envelopes are merged (reduce). Inputs that
+/// fit are passed through unchanged.
+pub async fn extract_chunked<F, Fut>(
+    context: ExtractionContext,
+    estimator: TokenEstimator,
+    budget: usize,
+    mut extract: F,
+) -> Result<ExtractionEnvelope, LlmError>
+where
+    F: FnMut(ExtractionContext) -> Fut,
+    Fut: Future<Output = Result<ExtractionEnvelope, LlmError>>,
+{
+    if budget == 0 {
+        return Err(LlmError::ContextLengthExceeded(
+            "The extraction prompt and reserved output leave no room for input".to_string()
+        ));
+    }
+
+    if estimator.count_messages(&context.messages) <= budget {
+        return extract(context).await;
+    }
+
+    let chunks = chunk_context(&context, estimator, budget);
+    let chunk_count = chunks.len();
+    debug!("Input exceeds {} tokens; extracting in {} chunks", budget, chunk_count);
+
+    report_progress(|progress| progress.total = Some(chunk_count as u64));
+    let mut envelopes = Vec::with_capacity(chunk_count);
+    for chunk in chunks {
+        envelopes.push(extract(chunk).await?);
+        report_progress(|progress| progress.processed += 1);
+    }
+
+    let mut merged = merge_envelopes(envelopes);
+    merged.metadata.get_or_insert_with(ExtractionMetadata::default).warnings.push(format!(
+        "Input exceeded the context window and was extracted in {} chunks",
+        chunk_count
+    ));
+
+    Ok(merged)
+}
+
+/// Configuration for parallel chunked extraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkingConfig {
+    /// Maximum conversation tokens per chunk
+    pub chunk_tokens: usize,
+    /// Tokens of trailing messages repeated at the start of the next chunk
+    pub overlap_tokens: usize,
+    /// Maximum number of chunks extracted concurrently
+    pub max_parallelism: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            chunk_tokens: 4_000,
+            overlap_tokens: 200,
+            max_parallelism: 4,
+        }
+    }
+}
+
+/// Connector wrapper that extracts long inputs as concurrent, overlapping chunks.
+///
+/// Inputs within `chunk_tokens` are passed straight to the inner connector.
+/// Longer inputs are split with [`chunk_context_with_overlap`], up to
+/// `max_parallelism` chunks are extracted at a time and the envelopes are
+/// merged with [`merge_envelopes`]. A failed chunk is reported as a warning
+/// unless every chunk fails, in which case the first error is returned.
+pub struct ExtractionOrchestrator {
+    inner: Arc<dyn LlmConnector>,
+    estimator: TokenEstimator,
+    config: ChunkingConfig,
+}
+
+impl ExtractionOrchestrator {
+    /// Wrap a connector with the given chunking configuration
+    pub fn new(inner: Arc<dyn LlmConnector>, estimator: TokenEstimator, config: ChunkingConfig) -> Self {
+        Self {
+            inner,
+            estimator,
+            config,
+        }
+    }
+
+    /// Extract every chunk, returning the results in chunk order
+    async fn extract_chunks(
+        &self,
+        tenant: &TenantId,
+        chunks: Vec<ExtractionContext>,
+    ) -> Vec<Result<ExtractionEnvelope, LlmError>> {
+        let permits = Arc::new(Semaphore::new(self.config.max_parallelism.max(1)));
+        let mut results: Vec<Option<Result<ExtractionEnvelope, LlmError>>> = chunks.iter().map(|_| None).collect();
+        let mut tasks = JoinSet::new();
+
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            let inner = self.inner.clone();
+            let tenant = tenant.clone();
+            let permits = permits.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await.expect("semaphore is never closed");
+                (index, inner.extract(&tenant, chunk).await)
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            let extracted = match joined {
+                Ok((index, result)) => {
+                    let extracted = result.is_ok();
+                    results[index] = Some(result);
+                    extracted
+                }
+                Err(e) => {
+                    warn!("Chunk extraction task failed: {}", e);
+                    false
+                }
+            };
+            report_progress(|progress| {
+                progress.processed += 1;
+                if !extracted {
+                    progress.failed += 1;
+                }
+            });
+        }
+
+        // A slot is only left empty when its task panicked or was cancelled
+        results
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|| Err(LlmError::InternalError("Chunk extraction task failed".to_string()))))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl LlmConnector for ExtractionOrchestrator {
+    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        if self.estimator.count_messages(&context.messages) <= self.config.chunk_tokens {
+            return self.inner.extract(tenant, context).await;
+        }
+
+        let chunks = chunk_context_with_overlap(
+            &context,
+            self.estimator,
+            self.config.chunk_tokens.max(1),
+            self.config.overlap_tokens,
+        );
+        let chunk_count = chunks.len();
+        report_progress(|progress| progress.total = Some(chunk_count as u64));
+        debug!(
+            "Extracting {} chunks for tenant {} with parallelism {}",
+            chunk_count, tenant, self.config.max_parallelism
+        );
+
+        let mut envelopes = Vec::with_capacity(chunk_count);
+        let mut warnings = Vec::new();
+        let mut first_error = None;
+
+        for (index, result) in self.extract_chunks(tenant, chunks).await.into_iter().enumerate() {
+            match result {
+                Ok(envelope) => envelopes.push(envelope),
+                Err(e) => {
+                    warn!("Chunk {} of {} failed for tenant {}: {}", index + 1, chunk_count, tenant, e);
+                    warnings.push(format!("Chunk {} of {} failed: {}", index + 1, chunk_count, e));
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        if envelopes.is_empty() {
+            return Err(first_error.unwrap_or_else(|| LlmError::InternalError("No chunks were extracted".to_string())));
+        }
+
+        let mut merged = merge_envelopes(envelopes);
+        let metadata = merged.metadata.get_or_insert_with(ExtractionMetadata::default);
+        metadata.warnings.push(format!(
+            "Input was extracted in {} overlapping chunks",
+            chunk_count
+        ));
+        metadata.warnings.extend(warnings);
+
+        Ok(merged)
+    }
+
+    async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        self.inner.complete(tenant, request).await
+    }
+}
+
+/// Trailing messages whose combined size fits within `overlap` tokens
+fn overlap_tail(messages: &[LlmMessage], estimator: TokenEstimator, overlap: usize) -> Vec<LlmMessage> {
+    let mut tokens = 0;
+    let start = messages
+        .iter()
+        .rposition(|message| {
+            tokens += estimator.count_message(message);
+            tokens > overlap
+        })
+        .map_or(0, |index| index + 1);
+
+    messages[start..].to_vec()
+}
+
+/// Split messages that do not fit the budget on their own into smaller messages
+fn split_oversized_messages(messages: &[LlmMessage], estimator: TokenEstimator, budget: usize) -> Vec<LlmMessage> {
+    let mut result = Vec::with_capacity(messages.len());
+
+    for message in messages {
+        if estimator.count_message(message) <= budget {
+            result.push(message.clone());
+            continue;
+        }
+
+        // Parts stay whole, on the last piece of the text
+        let max_content_tokens = budget.saturating_sub(estimator.count_message(&LlmMessage {
+            content: String::new(),
+            ..message.clone()
+        }));
+        let mut piece = String::new();
+        let mut piece_tokens = 0;
+
+        for word in message.content.split_inclusive(char::is_whitespace) {
+            let tokens = estimator.count(word);
+            if !piece.is_empty() && piece_tokens + tokens > max_content_tokens {
+                result.push(LlmMessage::new(message.role.clone(), std::mem::take(&mut piece)));
+                piece_tokens = 0;
+            }
+            piece_tokens += tokens;
+            piece.push_str(word);
+        }
+
+        if !piece.is_empty() || !message.parts.is_empty() {
+            result.push(LlmMessage {
+                content: piece,
+                ..message.clone()
+            });
+        }
+    }
+
+    result
+}
+
+fn with_messages(context: &ExtractionContext, messages: Vec<LlmMessage>) -> ExtractionContext {
+    ExtractionContext {
+        messages,
+        ..context.clone()
+    }
+}
+
+/// Add keys from `other` that `target` does not have yet
+fn merge_props(target: &mut serde_json::Value, other: serde_json::Value) {
+    if let (Some(target), serde_json::Value::Object(other)) = (target.as_object_mut(), other) {
+        for (key, value) in other {
+            target.entry(key).or_insert(value);
+        }
+    }
+}
+
+fn max_confidence(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn sum<T: std::ops::Add<Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+fn combine_metadata(mut combined: ExtractionMetadata, other: ExtractionMetadata) -> ExtractionMetadata {
+    combined.latency_ms = sum(combined.latency_ms, other.latency_ms);
+    combined.input_tokens = sum(combined.input_tokens, other.input_tokens);
+    combined.output_tokens = sum(combined.output_tokens, other.output_tokens);
+    combined.cost_usd = sum(combined.cost_usd, other.cost_usd);
+    combined.warnings.extend(other.warnings);
+    combined.citations.extend(other.citations);
+    combined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::ContentPart;
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn message(content: &str) -> LlmMessage {
+        LlmMessage::new("user", content)
+    }
+
+    fn context(messages: Vec<LlmMessage>) -> ExtractionContext {
+        ExtractionContext {
+            messages,
+            system_prompt: Some("Extract".to_string()),
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        }
+    }
+
+    fn node(alias: &str, props: serde_json::Value, confidence: f32) -> ExtractionNode {
+        ExtractionNode {
+            id_alias: alias.to_string(),
+            label: "Person".to_string(),
+            props,
+            confidence: Some(confidence),
+        }
+    }
+
+    #[test]
+    fn test_chunk_context() {
+        let estimator = TokenEstimator::CharRatio(1.0);
+        let ctx = context(vec![message("aaaa"), message("bbbb"), message(&"c ".repeat(20))]);
+
+        // Each short message costs 8 tokens (4 content + 4 overhead)
+        let chunks = chunk_context(&ctx, estimator, 16);
+        assert!(chunks.len() >= 3);
+        assert_eq!(chunks[0].messages.len(), 2);
+        assert!(chunks.iter().all(|chunk| estimator.count_messages(&chunk.messages) <= 16));
+        assert!(chunks.iter().all(|chunk| chunk.system_prompt.as_deref() == Some("Extract")));
+
+        let rejoined: String = chunks[1..].iter().flat_map(|c| &c.messages).map(|m| m.content.as_str()).collect();
+        assert_eq!(rejoined, "c ".repeat(20));
+    }
+
+    #[test]
+    fn test_split_keeps_parts() {
+        let estimator = TokenEstimator::CharRatio(1.0);
+        let result = ContentPart::ToolResult {
+            tool_call_id: "call_1".to_string(),
+            name: None,
+            content: "ok".to_string(),
+            is_error: false,
+        };
+        let long = message(&"c ".repeat(20)).with_part(result.clone());
+
+        let pieces = split_oversized_messages(&[long], estimator, 24);
+        assert!(pieces.len() > 1);
+        assert!(pieces[..pieces.len() - 1].iter().all(|piece| piece.parts.is_empty()));
+        assert_eq!(pieces.last().unwrap().parts, vec![result]);
+        assert!(pieces.iter().all(|piece| estimator.count_message(piece) <= 24));
+    }
+
+    #[test]
+    fn test_merge_envelopes() {
+        let first = ExtractionEnvelope {
+            nodes: vec![node("alice", json!({"name": "Alice"}), 0.6)],
+            relations: vec![],
+            metadata: Some(ExtractionMetadata {
+                input_tokens: Some(100),
+                cost_usd: Some(0.01),
+                ..Default::default()
+            }),
+        };
+        let second = ExtractionEnvelope {
+            nodes: vec![node("alice", json!({"name": "Other", "age": 30}), 0.9), node("bob", json!({}), 0.5)],
+            relations: vec![],
+            metadata: Some(ExtractionMetadata {
+                input_tokens: Some(50),
+                cost_usd: Some(0.02),
+                ..Default::default()
+            }),
+        };
+
+        let merged = merge_envelopes(vec![first, second]);
+        assert_eq!(merged.nodes.len(), 2);
+        assert_eq!(merged.nodes[0].props, json!({"name": "Alice", "age": 30}));
+        assert_eq!(merged.nodes[0].confidence, Some(0.9));
+
+        let metadata = merged.metadata.unwrap();
+        assert_eq!(metadata.input_tokens, Some(150));
+        assert!((metadata.cost_usd.unwrap() - 0.03).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_chunk_overlap_and_relation_reconciliation() {
+        let estimator = TokenEstimator::CharRatio(1.0);
+        let ctx = context(vec![message("aaaa"), message("bbbb"), message("cccc"), message("dddd")]);
+
+        // Chunks of 16 tokens holding two messages, carrying one message over
+        let chunks = chunk_context_with_overlap(&ctx, estimator, 16, 8);
+        let contents: Vec<Vec<&str>> = chunks
+            .iter()
+            .map(|chunk| chunk.messages.iter().map(|m| m.content.as_str()).collect())
+            .collect();
+        assert_eq!(contents, vec![vec!["aaaa", "bbbb"], vec!["bbbb", "cccc"], vec!["cccc", "dddd"]]);
+
+        let dated = Utc::now();
+        let relation = |valid_from: Option<DateTime<Utc>>| ExtractionRelation {
+            from_id_alias: "alice".to_string(),
+            to_id_alias: "acme".to_string(),
+            type_label: "WORKS_FOR".to_string(),
+            props: json!({}),
+            valid_from,
+            valid_to: None,
+            confidence: None,
+        };
+        let envelope = |relations| ExtractionEnvelope {
+            nodes: vec![],
+            relations,
+            metadata: None,
+        };
+
+        let merged = merge_envelopes(vec![
+            envelope(vec![relation(None)]),
+            envelope(vec![relation(Some(dated))]),
+            envelope(vec![relation(Some(dated - chrono::Duration::days(365)))]),
+        ]);
+        assert_eq!(merged.relations.len(), 2);
+        assert_eq!(merged.relations[0].valid_from, Some(dated));
+    }
+
+    struct CountingConnector {
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmConnector for CountingConnector {
+        async fn extract(&self, _tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+            let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let last = &context.messages.last().unwrap().content;
+            if last == "fail" {
+                return Err(LlmError::Timeout);
+            }
+            Ok(ExtractionEnvelope {
+                nodes: context.messages.iter().map(|m| node(&m.content, json!({}), 1.0)).collect(),
+                relations: vec![],
+                metadata: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_parallel_extraction() {
+        let connector = Arc::new(CountingConnector {
+            in_flight: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        });
+        let config = ChunkingConfig {
+            chunk_tokens: 16,
+            overlap_tokens: 8,
+            max_parallelism: 2,
+        };
+        let orchestrator = ExtractionOrchestrator::new(connector.clone(), TokenEstimator::CharRatio(1.0), config);
+        let tenant = TenantId::new("tenant");
+
+        let messages = ["m001", "m002", "m003", "m004", "m005", "m006"].map(message).to_vec();
+        let envelope = orchestrator.extract(&tenant, context(messages)).await.unwrap();
+
+        // Overlapping chunks see shared messages twice; merging dedups them
+        assert_eq!(envelope.nodes.len(), 6);
+        assert_eq!(connector.peak.load(Ordering::SeqCst), 2);
+
+        let messages = vec![message("m001"), message("m002"), message("fail"), message("m004")];
+        let envelope = orchestrator.extract(&tenant, context(messages)).await.unwrap();
+        let warnings = envelope.metadata.unwrap().warnings;
+        assert!(warnings.iter().any(|w| w.starts_with("Chunk 2 of 3 failed")));
+
+        let messages = vec![message("fail"), message("fail"), message("fail")];
+        assert!(orchestrator.extract(&tenant, context(messages)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_extract_chunked() {
+        let estimator = TokenEstimator::CharRatio(1.0);
+        let ctx = context(vec![message("aaaa"), message("bbbb")]);
+
+        let mut calls = 0;
+        let envelope = extract_chunked(ctx.clone(), estimator, 8, |chunk| {
+            calls += 1;
+            let alias = chunk.messages[0].content.clone();
+            async move {
+                Ok(ExtractionEnvelope {
+                    nodes: vec![node(&alias, json!({}), 1.0)],
+                    relations: vec![],
+                    metadata: None,
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(calls, 2);
+        assert_eq!(envelope.nodes.len(), 2);
+        assert_eq!(envelope.metadata.unwrap().warnings.len(), 1);
+
+        let result = extract_chunked(ctx, estimator, 0, |_| async { unreachable!() }).await;
+        assert!(matches!(result, Err(LlmError::ContextLengthExceeded(_))));
+    }
+}
@@ -0,0 +1,654 @@
+//! Routing tenants to different storage backends
+//!
+//! A [`FederatedGraphStore`] puts several named `GraphStore` backends behind
+//! one store, so a single server can keep high-value tenants in Neo4j and
+//! trial tenants in memory. Each tenant is routed to exactly one backend:
+//! its own route if it has one, the default backend otherwise. Routes come
+//! from [`FederationConfig`], from the `store_backend` key of a tenant's
+//! metadata, or are set at runtime. Every operation of a tenant goes to the
+//! same backend, so a tenant never sees part of its graph in one store and
+//! part in another.
+//!
+//! Backends are health-aware. A call that fails with a connection error or a
+//! timeout takes its backend out of rotation for `retry_after_ms`, as does a
+//! failed health check. While a backend is out, its tenants are served by
+//! its failover backend, if the config names one; failovers are meant to be
+//! standbys that share the backend's data, since writes made during the
+//! outage stay on the failover. The failed call itself is not retried, as a
+//! write that timed out may have been applied.
+
+use crate::archive::ArchiveBatch;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{EdgeStream, NodeStream};
+use crate::errors::{CoreError, GraphError};
+use crate::materialized::SnapshotInfo;
+use crate::tenant::TenantInfo;
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::traits::GraphStore;
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeWithEdges, Path, TenantId, TimeEdge};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Key of a tenant's metadata naming the backend the tenant is stored in
+pub const BACKEND_METADATA_KEY: &str = "store_backend";
+
+/// Which backend stores which tenant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationConfig {
+    /// Backend of tenants without a route of their own
+    pub default_backend: String,
+    /// Backend by tenant ID
+    #[serde(default)]
+    pub tenants: HashMap<String, String>,
+    /// Backend that serves a backend's tenants while it is unhealthy, by
+    /// backend name
+    #[serde(default)]
+    pub failover: HashMap<String, String>,
+    /// How long a failed backend stays out of rotation
+    #[serde(default = "default_retry_after_ms")]
+    pub retry_after_ms: u64,
+}
+
+fn default_retry_after_ms() -> u64 {
+    30_000
+}
+
+impl FederationConfig {
+    /// Route every tenant to one backend
+    pub fn new(default_backend: impl Into<String>) -> Self {
+        Self {
+            default_backend: default_backend.into(),
+            tenants: HashMap::new(),
+            failover: HashMap::new(),
+            retry_after_ms: default_retry_after_ms(),
+        }
+    }
+}
+
+/// Health of one backend, as reported by [`FederatedGraphStore::status`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendStatus {
+    pub name: String,
+    /// Whether the backend is in rotation
+    pub healthy: bool,
+    /// Tenants routed to the backend explicitly; the default backend also
+    /// serves every tenant without a route
+    pub tenants: Vec<TenantId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failover: Option<String>,
+}
+
+struct Backend {
+    name: String,
+    store: Arc<dyn GraphStore>,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl Backend {
+    fn is_available(&self, now: Instant) -> bool {
+        match *self.unhealthy_until.lock().unwrap() {
+            Some(until) => now >= until,
+            None => true,
+        }
+    }
+
+    fn mark_failed(&self, retry_after: Duration) {
+        warn!("Marking storage backend '{}' unhealthy for {:?}", self.name, retry_after);
+        *self.unhealthy_until.lock().unwrap() = Some(Instant::now() + retry_after);
+    }
+
+    fn mark_ok(&self) {
+        if self.unhealthy_until.lock().unwrap().take().is_some() {
+            info!("Storage backend '{}' is back in rotation", self.name);
+        }
+    }
+}
+
+/// Whether an error means the backend could not be reached, rather than that
+/// the request was wrong
+fn is_outage(error: &GraphError) -> bool {
+    matches!(error, GraphError::ConnectionFailed(_) | GraphError::Timeout(_))
+}
+
+/// A `GraphStore` that routes each tenant to one of several backends
+pub struct FederatedGraphStore {
+    backends: BTreeMap<String, Backend>,
+    default_backend: String,
+    failover: HashMap<String, String>,
+    routes: RwLock<HashMap<TenantId, String>>,
+    retry_after: Duration,
+}
+
+impl FederatedGraphStore {
+    /// Federate the given backends, by name. Fails if the config names a
+    /// backend that is not given, or a backend fails over to itself.
+    pub fn new(config: FederationConfig, backends: HashMap<String, Arc<dyn GraphStore>>) -> Result<Self, CoreError> {
+        let check = |name: &str, usage: &str| {
+            if backends.contains_key(name) {
+                Ok(())
+            } else {
+                Err(CoreError::Configuration(format!("Unknown storage backend '{}' for {}", name, usage)))
+            }
+        };
+
+        check(&config.default_backend, "the default route")?;
+        for (tenant, backend) in &config.tenants {
+            check(backend, &format!("tenant {}", tenant))?;
+        }
+        for (backend, failover) in &config.failover {
+            check(backend, "failover")?;
+            check(failover, &format!("failover of backend '{}'", backend))?;
+            if backend == failover {
+                return Err(CoreError::Configuration(format!("Storage backend '{}' cannot fail over to itself", backend)));
+            }
+        }
+
+        let routes = config.tenants.into_iter().map(|(tenant, backend)| (TenantId::new(tenant), backend)).collect();
+        let backends = backends.into_iter()
+            .map(|(name, store)| (name.clone(), Backend { name, store, unhealthy_until: Mutex::new(None) }))
+            .collect();
+
+        Ok(Self {
+            backends,
+            default_backend: config.default_backend,
+            failover: config.failover,
+            routes: RwLock::new(routes),
+            retry_after: Duration::from_millis(config.retry_after_ms),
+        })
+    }
+
+    /// Route a tenant to a backend from now on. Data the tenant already has
+    /// in its previous backend is not moved.
+    pub fn set_route(&self, tenant: &TenantId, backend: &str) -> Result<(), CoreError> {
+        if !self.backends.contains_key(backend) {
+            return Err(CoreError::Configuration(format!("Unknown storage backend '{}' for tenant {}", backend, tenant)));
+        }
+        info!("Routing tenant {} to storage backend '{}'", tenant, backend);
+        self.routes.write().unwrap().insert(tenant.clone(), backend.to_string());
+        Ok(())
+    }
+
+    /// Route a tenant by the `store_backend` key of its metadata; returns
+    /// whether the metadata names a backend
+    pub fn route_from_metadata(&self, tenant: &TenantInfo) -> Result<bool, CoreError> {
+        match tenant.metadata.get(BACKEND_METADATA_KEY).and_then(|backend| backend.as_str()) {
+            Some(backend) => self.set_route(&tenant.id, backend).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Name of the backend the tenant is routed to, ignoring health
+    pub fn backend_of(&self, tenant: &TenantId) -> String {
+        self.routes.read().unwrap().get(tenant).cloned().unwrap_or_else(|| self.default_backend.clone())
+    }
+
+    /// Health and routes of every backend, by name
+    pub fn status(&self) -> Vec<BackendStatus> {
+        let now = Instant::now();
+        let routes = self.routes.read().unwrap();
+        self.backends.values()
+            .map(|backend| {
+                let mut tenants: Vec<_> = routes.iter()
+                    .filter(|(_, name)| **name == backend.name)
+                    .map(|(tenant, _)| tenant.clone())
+                    .collect();
+                tenants.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+                BackendStatus {
+                    name: backend.name.clone(),
+                    healthy: backend.is_available(now),
+                    tenants,
+                    failover: self.failover.get(&backend.name).cloned(),
+                }
+            })
+            .collect()
+    }
+
+    /// Backend that serves the tenant now: its own, or the failover of its
+    /// own while that is out of rotation
+    fn route(&self, tenant: &TenantId) -> &Backend {
+        let name = self.backend_of(tenant);
+        let backend = &self.backends[&name];
+        let now = Instant::now();
+        if backend.is_available(now) {
+            return backend;
+        }
+
+        match self.failover.get(&name).map(|failover| &self.backends[failover]) {
+            Some(failover) if failover.is_available(now) => {
+                debug!("Serving tenant {} from '{}' while '{}' is unhealthy", tenant, failover.name, name);
+                failover
+            }
+            // Nothing better to try than the backend itself
+            _ => backend,
+        }
+    }
+
+    /// Run an operation of the tenant on the backend serving it, keeping
+    /// track of the backend's health
+    async fn call<'a, T, F, Fut>(&'a self, tenant: &TenantId, operation: F) -> Result<T, GraphError>
+    where
+        F: FnOnce(&'a Arc<dyn GraphStore>) -> Fut,
+        Fut: Future<Output = Result<T, GraphError>>,
+    {
+        let backend = self.route(tenant);
+        let result = operation(&backend.store).await;
+        match &result {
+            Err(error) if is_outage(error) => backend.mark_failed(self.retry_after),
+            Err(_) => {}
+            Ok(_) => backend.mark_ok(),
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl GraphStore for FederatedGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        self.call(tenant, |store| store.upsert_node(tenant, node)).await
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.call(tenant, |store| store.upsert_edge(tenant, edge)).await
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        self.call(tenant, |store| store.upsert_node_with_edges(tenant, node, edges)).await
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        self.call(tenant, |store| store.bulk_load_nodes(tenant, nodes)).await
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        self.call(tenant, |store| store.bulk_load_edges(tenant, edges)).await
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.call(tenant, |store| store.query(tenant, query)).await
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.call(tenant, |store| store.query_count(tenant, query)).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.call(tenant, |store| store.query_exists(tenant, query)).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.call(tenant, |store| store.traverse(tenant, request)).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.call(tenant, |store| store.shortest_path(tenant, request)).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.call(tenant, |store| store.timeline(tenant, request)).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.call(tenant, |store| store.property_history(tenant, node_id, key)).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        self.call(tenant, |store| store.update_node(tenant, id, node)).await
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.call(tenant, |store| store.edge_constraints(tenant)).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.call(tenant, |store| store.set_edge_constraints(tenant, constraints)).await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.call(tenant, |store| store.get_node(tenant, id)).await
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        self.call(tenant, |store| store.get_node_by_alias(tenant, id_alias)).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.call(tenant, |store| store.resolve_aliases(tenant, aliases)).await
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.call(tenant, |store| store.delete_node(tenant, id)).await
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.call(tenant, |store| store.delete_edge(tenant, id)).await
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        self.call(tenant, |store| store.close_edge(tenant, id, valid_to)).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.call(tenant, |store| store.supersede_edge(tenant, id, edge)).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.call(tenant, |store| store.retract_edge(tenant, id)).await
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        self.call(tenant, |store| store.get_node_history(tenant, id)).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        self.call(tenant, |store| store.snapshot(tenant, valid_at)).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.call(tenant, |store| store.materialize_snapshot(tenant, name, valid_at)).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.call(tenant, |store| store.list_snapshots(tenant)).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.call(tenant, |store| store.drop_snapshot(tenant, name)).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.call(tenant, |store| store.query_snapshot(tenant, name, query)).await
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        self.call(tenant, |store| store.read_history(tenant, before)).await
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.call(tenant, |store| store.purge_history(tenant, before)).await
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        self.call(tenant, |store| store.restore_history(tenant, batch)).await
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.call(tenant, |store| store.purge_expired_edges(tenant, now)).await
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        self.call(tenant, |store| store.summary(tenant)).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.call(tenant, |store| store.catalog(tenant)).await
+    }
+
+    /// Tenants of every backend that can list them, sorted. A tenant is only
+    /// listed if the backend it is routed to has it, so data a tenant left
+    /// in a previous backend does not show.
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        let mut tenants = BTreeSet::new();
+        let mut listed = false;
+        for backend in self.backends.values() {
+            match backend.store.list_tenants().await {
+                Ok(found) => {
+                    listed = true;
+                    tenants.extend(found.into_iter()
+                        .filter(|tenant| self.backend_of(tenant) == backend.name)
+                        .map(|tenant| tenant.as_str().to_string()));
+                }
+                Err(GraphError::Unsupported(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !listed {
+            return Err(GraphError::Unsupported("Listing tenants".to_string()));
+        }
+        Ok(tenants.into_iter().map(TenantId::new).collect())
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        self.call(tenant, |store| store.clear_tenant(tenant)).await
+    }
+
+    /// Check every backend, taking failed ones out of rotation. Healthy as
+    /// long as every backend is healthy or has a healthy failover.
+    async fn health_check(&self) -> Result<(), GraphError> {
+        let mut failed = BTreeSet::new();
+        for backend in self.backends.values() {
+            match backend.store.health_check().await {
+                Ok(()) => backend.mark_ok(),
+                Err(e) => {
+                    warn!("Health check of storage backend '{}' failed: {}", backend.name, e);
+                    backend.mark_failed(self.retry_after);
+                    failed.insert(backend.name.clone());
+                }
+            }
+        }
+
+        let down: Vec<_> = failed.iter()
+            .filter(|name| self.failover.get(*name).is_none_or(|failover| failed.contains(failover)))
+            .cloned()
+            .collect();
+        if down.is_empty() {
+            Ok(())
+        } else {
+            Err(GraphError::ConnectionFailed(format!("Storage backends without a healthy failover: {}", down.join(", "))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Store that keeps the tenants it was written for and can be taken down
+    #[derive(Default)]
+    struct TenantStore {
+        tenants: Mutex<BTreeSet<String>>,
+        down: AtomicBool,
+    }
+
+    impl TenantStore {
+        fn check(&self) -> Result<(), GraphError> {
+            if self.down.load(Ordering::SeqCst) {
+                Err(GraphError::ConnectionFailed("down".to_string()))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn has(&self, tenant: &str) -> bool {
+            self.tenants.lock().unwrap().contains(tenant)
+        }
+    }
+
+    #[async_trait]
+    impl GraphStore for TenantStore {
+        async fn upsert_node(&self, tenant: &TenantId, _node: Node) -> Result<Uuid, GraphError> {
+            self.check()?;
+            self.tenants.lock().unwrap().insert(tenant.as_str().to_string());
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_edge(&self, _tenant: &TenantId, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_node_with_edges(&self, _tenant: &TenantId, _node: Node, _edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+            Ok(NodeWithEdges { node_id: Uuid::new_v4(), edge_ids: vec![] })
+        }
+
+        async fn query(&self, _tenant: &TenantId, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            Ok(vec![])
+        }
+
+        async fn get_node(&self, _tenant: &TenantId, _id: Uuid) -> Result<Option<Node>, GraphError> {
+            Ok(None)
+        }
+
+        async fn get_node_by_alias(&self, _tenant: &TenantId, _id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+            Ok(None)
+        }
+
+        async fn resolve_aliases(&self, _tenant: &TenantId, _aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+            Ok(HashMap::new())
+        }
+
+        async fn delete_node(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn delete_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn close_edge(&self, _tenant: &TenantId, _id: Uuid, _valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn supersede_edge(&self, _tenant: &TenantId, _id: Uuid, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn retract_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn get_node_history(&self, _tenant: &TenantId, _id: Uuid) -> Result<Vec<Node>, GraphError> {
+            Ok(vec![])
+        }
+
+        async fn snapshot(&self, _tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+            Ok(GraphSnapshot { snapshot_at: Utc::now(), valid_at, nodes: vec![], edges: vec![] })
+        }
+
+        async fn materialize_snapshot(&self, _tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+            Ok(SnapshotInfo { name: name.to_string(), valid_at, snapshot_at: Utc::now(), node_count: 0, edge_count: 0 })
+        }
+
+        async fn list_snapshots(&self, _tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn drop_snapshot(&self, _tenant: &TenantId, _name: &str) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn query_snapshot(&self, _tenant: &TenantId, _name: &str, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn read_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+            Ok(ArchiveBatch::default())
+        }
+
+        async fn purge_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn restore_history(&self, _tenant: &TenantId, _batch: ArchiveBatch) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn purge_expired_edges(&self, _tenant: &TenantId, _now: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn summary(&self, _tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+            Ok(GraphSummary::default())
+        }
+
+        async fn catalog(&self, _tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+            Ok(GraphCatalog::default())
+        }
+
+        async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+            Ok(self.tenants.lock().unwrap().iter().map(TenantId::new).collect())
+        }
+
+        async fn health_check(&self) -> Result<(), GraphError> {
+            self.check()
+        }
+    }
+
+    fn federation(config: FederationConfig) -> (FederatedGraphStore, Arc<TenantStore>, Arc<TenantStore>, Arc<TenantStore>) {
+        let (premium, trial, standby) = (Arc::new(TenantStore::default()), Arc::new(TenantStore::default()), Arc::new(TenantStore::default()));
+        let mut backends: HashMap<String, Arc<dyn GraphStore>> = HashMap::new();
+        backends.insert("premium".to_string(), premium.clone());
+        backends.insert("trial".to_string(), trial.clone());
+        backends.insert("standby".to_string(), standby.clone());
+        (FederatedGraphStore::new(config, backends).unwrap(), premium, trial, standby)
+    }
+
+    #[tokio::test]
+    async fn test_routes_tenants() {
+        let config: FederationConfig = serde_json::from_value(serde_json::json!({
+            "default_backend": "trial",
+            "tenants": {"acme": "premium"}
+        })).unwrap();
+        let (store, premium, trial, _) = federation(config);
+
+        let mut globex = TenantInfo::new(TenantId::new("globex"));
+        globex.metadata = serde_json::json!({ BACKEND_METADATA_KEY: "premium" });
+        assert!(store.route_from_metadata(&globex).unwrap());
+        assert!(store.set_route(&TenantId::new("initech"), "missing").is_err());
+
+        for tenant in ["acme", "globex", "initech"] {
+            store.upsert_node(&TenantId::new(tenant), Node::new("Person")).await.unwrap();
+        }
+        assert!(premium.has("acme") && premium.has("globex") && !premium.has("initech"));
+        assert!(trial.has("initech") && !trial.has("acme"));
+
+        let tenants = store.list_tenants().await.unwrap();
+        assert_eq!(tenants, vec![TenantId::new("acme"), TenantId::new("globex"), TenantId::new("initech")]);
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_while_unhealthy() {
+        let mut config = FederationConfig::new("trial");
+        config.tenants.insert("acme".to_string(), "premium".to_string());
+        config.failover.insert("premium".to_string(), "standby".to_string());
+        config.retry_after_ms = 60_000;
+        let (store, premium, _, standby) = federation(config);
+        let acme = TenantId::new("acme");
+
+        premium.down.store(true, Ordering::SeqCst);
+        assert!(store.upsert_node(&acme, Node::new("Person")).await.is_err());
+        store.upsert_node(&acme, Node::new("Person")).await.unwrap();
+        assert!(standby.has("acme") && !premium.has("acme"));
+
+        // The standby covers the outage, so the federation stays healthy
+        store.health_check().await.unwrap();
+        let status = store.status();
+        let premium_status = status.iter().find(|backend| backend.name == "premium").unwrap();
+        assert!(!premium_status.healthy);
+        assert_eq!(premium_status.tenants, vec![acme.clone()]);
+
+        standby.down.store(true, Ordering::SeqCst);
+        assert!(store.health_check().await.is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_backends() {
+        let mut config = FederationConfig::new("trial");
+        config.failover.insert("premium".to_string(), "premium".to_string());
+        let backends: HashMap<String, Arc<dyn GraphStore>> = [
+            ("trial".to_string(), Arc::new(TenantStore::default()) as Arc<dyn GraphStore>),
+            ("premium".to_string(), Arc::new(TenantStore::default()) as Arc<dyn GraphStore>),
+        ].into_iter().collect();
+        assert!(FederatedGraphStore::new(config, backends.clone()).is_err());
+        assert!(FederatedGraphStore::new(FederationConfig::new("neo4j"), backends).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Declarative fixtures of graph states
+//!
+//! A [`Fixture`] declares the nodes and edges of one or more tenants, with
+//! their valid times, so tests and demos can start from the same graph every
+//! time. Fixtures are plain serde data: the server takes them as JSON and
+//! `kgctl seed` reads them from YAML or JSON files.
+//!
+//! Edges name their endpoints by alias. Aliases of the fixture's own nodes
+//! resolve to the nodes it just wrote, others to nodes already in the graph.
+//! Applying a fixture twice upserts the same nodes again, but adds its edges
+//! a second time, so fixtures are normally applied with `reset`, which
+//! clears the tenants first. Tearing a fixture down clears its tenants.
+
+use crate::errors::{CoreError, GraphError};
+use crate::traits::GraphService;
+use crate::types::{AliasKey, Node, TenantId, TimeEdge};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+/// Graph states of tenants, by tenant ID
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    /// Valid time of edges that do not give their own; the time the fixture
+    /// is applied if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tenants: BTreeMap<String, TenantFixture>,
+}
+
+/// Nodes and edges of one tenant
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TenantFixture {
+    #[serde(default)]
+    pub nodes: Vec<FixtureNode>,
+    #[serde(default)]
+    pub edges: Vec<FixtureEdge>,
+}
+
+/// A node, which edges refer to by its alias
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureNode {
+    pub id_alias: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias_namespace: Option<String>,
+    pub label: String,
+    #[serde(default = "empty_props")]
+    pub props: serde_json::Value,
+}
+
+impl FixtureNode {
+    fn alias(&self) -> AliasKey {
+        alias_key(&self.alias_namespace, &self.id_alias)
+    }
+}
+
+/// An edge between two aliased nodes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureEdge {
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_namespace: Option<String>,
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_namespace: Option<String>,
+    pub kind: String,
+    /// When the edge became valid; the fixture's `valid_from` if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<DateTime<Utc>>,
+    #[serde(default = "empty_props")]
+    pub props: serde_json::Value,
+}
+
+fn empty_props() -> serde_json::Value {
+    serde_json::Value::Object(Default::default())
+}
+
+fn alias_key(namespace: &Option<String>, alias: &str) -> AliasKey {
+    match namespace {
+        Some(namespace) => AliasKey::namespaced(namespace.clone(), alias),
+        None => AliasKey::new(alias),
+    }
+}
+
+impl Fixture {
+    /// Parse a fixture from JSON
+    pub fn from_json(json: &str) -> Result<Self, CoreError> {
+        let fixture: Self = serde_json::from_str(json)?;
+        fixture.validate()?;
+        Ok(fixture)
+    }
+
+    /// Check that tenants are named, nodes have labels, aliases are unique
+    /// within a tenant and no edge ends before it starts
+    pub fn validate(&self) -> Result<(), CoreError> {
+        for (tenant, fixture) in &self.tenants {
+            if tenant.trim().is_empty() {
+                return Err(CoreError::Tenant("Fixture has a tenant without an ID".to_string()));
+            }
+            let mut aliases = HashSet::new();
+            for node in &fixture.nodes {
+                if node.label.trim().is_empty() {
+                    return Err(invalid(tenant, format!("node '{}' has no label", node.id_alias)));
+                }
+                if !aliases.insert(node.alias()) {
+                    return Err(invalid(tenant, format!("alias '{}' is declared twice", node.alias())));
+                }
+            }
+            for edge in &fixture.edges {
+                let valid_from = edge.valid_from.or(self.valid_from);
+                if let (Some(valid_from), Some(valid_to)) = (valid_from, edge.valid_to) {
+                    if valid_to <= valid_from {
+                        return Err(invalid(tenant, format!(
+                            "{} edge from '{}' to '{}' ends at {} before it starts at {}",
+                            edge.kind, edge.from, edge.to, valid_to, valid_from
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid(tenant: &str, message: String) -> CoreError {
+    GraphError::ConstraintViolation(format!("Invalid fixture of tenant {}: {}", tenant, message)).into()
+}
+
+/// What seeding or tearing down a fixture did to one tenant
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantSeedReport {
+    pub nodes: u64,
+    pub edges: u64,
+    /// Records cleared before seeding, or by the teardown
+    pub removed: u64,
+}
+
+/// What seeding or tearing down a fixture did, by tenant ID
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedReport {
+    pub tenants: BTreeMap<String, TenantSeedReport>,
+}
+
+/// Writes fixtures to the graph and removes them again
+pub struct FixtureLoader {
+    service: Arc<dyn GraphService>,
+}
+
+impl FixtureLoader {
+    pub fn new(service: Arc<dyn GraphService>) -> Self {
+        Self { service }
+    }
+
+    /// Write a fixture's nodes, then its edges, tenant by tenant; with
+    /// `reset`, each tenant is cleared first
+    pub async fn apply(&self, fixture: &Fixture, reset: bool) -> Result<SeedReport, CoreError> {
+        fixture.validate()?;
+        let default_valid_from = fixture.valid_from.unwrap_or_else(Utc::now);
+
+        let mut report = SeedReport::default();
+        for (tenant_id, tenant_fixture) in &fixture.tenants {
+            let tenant = TenantId::new(tenant_id);
+            let mut tenant_report = TenantSeedReport::default();
+            if reset {
+                tenant_report.removed = self.service.clear_tenant(&tenant).await?;
+            }
+
+            let mut ids = HashMap::new();
+            for node in &tenant_fixture.nodes {
+                let mut record = Node::new(&node.label).with_id_alias(&node.id_alias).with_props(node.props.clone());
+                record.alias_namespace = node.alias_namespace.clone();
+                ids.insert(node.alias(), self.service.upsert_node(&tenant, record).await?);
+                tenant_report.nodes += 1;
+            }
+
+            let endpoints = self.resolve_endpoints(&tenant, tenant_fixture, &mut ids).await?;
+            for (edge, (from, to)) in tenant_fixture.edges.iter().zip(endpoints) {
+                let mut record = TimeEdge::new(from, to, &edge.kind, edge.valid_from.unwrap_or(default_valid_from), edge.props.clone());
+                if let Some(valid_to) = edge.valid_to {
+                    record = record.with_valid_to(valid_to);
+                }
+                self.service.upsert_edge(&tenant, record).await?;
+                tenant_report.edges += 1;
+            }
+
+            info!("Seeded tenant {} with {} nodes and {} edges", tenant, tenant_report.nodes, tenant_report.edges);
+            report.tenants.insert(tenant_id.clone(), tenant_report);
+        }
+        Ok(report)
+    }
+
+    /// Clear every tenant of a fixture
+    pub async fn teardown(&self, fixture: &Fixture) -> Result<SeedReport, CoreError> {
+        let mut report = SeedReport::default();
+        for tenant_id in fixture.tenants.keys() {
+            let tenant = TenantId::new(tenant_id);
+            let removed = self.service.clear_tenant(&tenant).await?;
+            info!("Tore down fixture of tenant {}: {} records removed", tenant, removed);
+            report.tenants.insert(tenant_id.clone(), TenantSeedReport { removed, ..Default::default() });
+        }
+        Ok(report)
+    }
+
+    /// Node IDs of the endpoints of each edge, looking up aliases the
+    /// fixture does not declare in the graph
+    async fn resolve_endpoints(
+        &self,
+        tenant: &TenantId,
+        fixture: &TenantFixture,
+        ids: &mut HashMap<AliasKey, Uuid>,
+    ) -> Result<Vec<(Uuid, Uuid)>, CoreError> {
+        let keys = |edge: &FixtureEdge| [alias_key(&edge.from_namespace, &edge.from), alias_key(&edge.to_namespace, &edge.to)];
+        let mut unknown: Vec<AliasKey> = fixture.edges.iter().flat_map(keys).filter(|key| !ids.contains_key(key)).collect();
+        unknown.sort_by(|a, b| (&a.namespace, &a.alias).cmp(&(&b.namespace, &b.alias)));
+        unknown.dedup();
+        if !unknown.is_empty() {
+            ids.extend(self.service.resolve_aliases(tenant, &unknown).await?);
+        }
+
+        fixture.edges.iter()
+            .map(|edge| {
+                let [from, to] = keys(edge).map(|key| ids.get(&key).copied()
+                    .ok_or_else(|| GraphError::NodeNotFound(format!("Fixture edge {} refers to unknown alias '{}'", edge.kind, key))));
+                Ok((from?, to?))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fixture() {
+        let fixture = Fixture::from_json(r#"{
+            "valid_from": "2024-01-01T00:00:00Z",
+            "tenants": {
+                "demo": {
+                    "nodes": [
+                        {"id_alias": "alice", "label": "Person", "props": {"name": "Alice"}},
+                        {"id_alias": "acme", "alias_namespace": "crm", "label": "Company"}
+                    ],
+                    "edges": [
+                        {"from": "alice", "to": "acme", "to_namespace": "crm", "kind": "WORKS_FOR", "valid_to": "2024-06-01T00:00:00Z"}
+                    ]
+                }
+            }
+        }"#).unwrap();
+
+        let demo = &fixture.tenants["demo"];
+        assert_eq!(demo.nodes[1].alias(), AliasKey::namespaced("crm", "acme"));
+        assert_eq!(demo.nodes[1].props, serde_json::json!({}));
+        assert_eq!(demo.edges[0].valid_from, None);
+    }
+
+    #[test]
+    fn test_invalid_fixtures() {
+        let node = |alias: &str| FixtureNode { id_alias: alias.to_string(), alias_namespace: None, label: "Person".to_string(), props: empty_props() };
+        let mut fixture = Fixture::default();
+        fixture.tenants.insert("demo".to_string(), TenantFixture { nodes: vec![node("alice"), node("alice")], edges: Vec::new() });
+        assert!(fixture.validate().is_err());
+
+        let edge: FixtureEdge = serde_json::from_str(r#"{
+            "from": "alice", "to": "bob", "kind": "KNOWS",
+            "valid_from": "2024-02-01T00:00:00Z", "valid_to": "2024-01-01T00:00:00Z"
+        }"#).unwrap();
+        fixture.tenants.insert("demo".to_string(), TenantFixture { nodes: vec![node("alice")], edges: vec![edge] });
+        assert!(fixture.validate().is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! Graph context for grounding extraction
+//!
+//! An LLM that does not know how a tenant's graph is shaped invents labels,
+//! relationship kinds and aliases next to the existing ones, e.g. `Company`
+//! where the graph says `Organization`. A [`GraphContextBuilder`] summarizes
+//! the tenant's catalog (labels and relationship kinds, most used first, with
+//! their common property keys) and optionally its most connected entities,
+//! and sets the summary as `ExtractionContext::graph_context`, which
+//! connectors render after their instructions.
+//!
+//! Tenants opt in through [`GraphContextPolicies`]. A summary stays within
+//! the policy's token budget, dropping what does not fit, and is reused for
+//! `ttl_secs` before the graph is read again.
+
+use crate::errors::GraphError;
+use crate::tokens::TokenEstimator;
+use crate::traits::{ExtractionContext, GraphService};
+use crate::types::{CatalogEntry, GraphCatalog, TenantId};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Maximum prompt tokens spent on the graph context
+pub const DEFAULT_GRAPH_CONTEXT_TOKEN_BUDGET: usize = 1_000;
+
+/// Graph context settings of a tenant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphContextPolicy {
+    /// Add the graph context to the tenant's extractions
+    pub enabled: bool,
+    /// Prompt tokens the summary may take
+    pub token_budget: usize,
+    /// Most connected entities to list; none by default, as finding them
+    /// reads the whole graph
+    pub max_entities: usize,
+    /// Property keys listed per label or kind
+    pub max_property_keys: usize,
+    /// How long a summary is reused, in seconds
+    pub ttl_secs: u64,
+}
+
+impl Default for GraphContextPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token_budget: DEFAULT_GRAPH_CONTEXT_TOKEN_BUDGET,
+            max_entities: 0,
+            max_property_keys: 5,
+            ttl_secs: 300,
+        }
+    }
+}
+
+/// Graph context policies by tenant
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphContextPolicies {
+    /// Policy of tenants without their own
+    pub default: GraphContextPolicy,
+    /// Policies by tenant ID
+    pub tenants: HashMap<String, GraphContextPolicy>,
+}
+
+impl GraphContextPolicies {
+    /// Policy of a tenant
+    pub fn for_tenant(&self, tenant: &TenantId) -> &GraphContextPolicy {
+        self.tenants.get(tenant.as_str()).unwrap_or(&self.default)
+    }
+}
+
+/// An entity of the graph, with the number of current edges it has
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SalientEntity {
+    pub id_alias: String,
+    pub label: String,
+    /// The node's `name` or `title` property
+    pub name: Option<String>,
+    pub degree: usize,
+}
+
+/// Render a graph summary as a prompt section, adding lines in order of
+/// usage until `budget` tokens are spent. Returns an empty string when
+/// nothing fits.
+pub fn render_graph_context(
+    catalog: &GraphCatalog,
+    entities: &[SalientEntity],
+    max_property_keys: usize,
+    estimator: TokenEstimator,
+    budget: usize,
+) -> String {
+    let mut sections: Vec<(&str, Vec<String>)> = vec![
+        ("Node labels", by_usage(&catalog.labels).map(|entry| render_entry(entry, max_property_keys)).collect()),
+        ("Relationship kinds", by_usage(&catalog.kinds).map(|entry| render_entry(entry, max_property_keys)).collect()),
+        ("Known entities", entities.iter().map(|entity| match &entity.name {
+            Some(name) => format!("- {} ({}): {}", entity.id_alias, entity.label, name),
+            None => format!("- {} ({})", entity.id_alias, entity.label),
+        }).collect()),
+    ];
+
+    let mut rendered = Vec::new();
+    let mut used = 0;
+    for (heading, lines) in &mut sections {
+        let heading = format!("{}:", heading);
+        let mut fitting = Vec::new();
+        let mut section_tokens = estimator.count(&heading);
+        for line in lines.drain(..) {
+            let tokens = estimator.count(&line);
+            if used + section_tokens + tokens > budget {
+                break;
+            }
+            section_tokens += tokens;
+            fitting.push(line);
+        }
+        if !fitting.is_empty() {
+            used += section_tokens;
+            rendered.push(format!("{}\n{}", heading, fitting.join("\n")));
+        }
+    }
+    rendered.join("\n\n")
+}
+
+/// Catalog entries, most used first
+fn by_usage(entries: &[CatalogEntry]) -> impl Iterator<Item = &CatalogEntry> {
+    let mut entries: Vec<_> = entries.iter().collect();
+    entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    entries.into_iter()
+}
+
+fn render_entry(entry: &CatalogEntry, max_property_keys: usize) -> String {
+    let mut keys: Vec<_> = entry.property_keys.iter().collect();
+    keys.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let keys: Vec<&str> = keys.into_iter().take(max_property_keys).map(|(key, _)| key.as_str()).collect();
+
+    if keys.is_empty() {
+        format!("- {} ({})", entry.name, entry.count)
+    } else {
+        format!("- {} ({}): {}", entry.name, entry.count, keys.join(", "))
+    }
+}
+
+/// Builds and caches the graph context of each tenant
+pub struct GraphContextBuilder {
+    service: Arc<dyn GraphService>,
+    policies: GraphContextPolicies,
+    cache: Mutex<HashMap<TenantId, (Instant, String)>>,
+}
+
+impl GraphContextBuilder {
+    pub fn new(service: Arc<dyn GraphService>, policies: GraphContextPolicies) -> Self {
+        Self { service, policies, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// Summarize a tenant's graph under its policy, without the cache
+    pub async fn build(&self, tenant: &TenantId) -> Result<String, GraphError> {
+        let policy = self.policies.for_tenant(tenant);
+        let catalog = self.service.catalog(tenant).await?;
+        let entities = if policy.max_entities > 0 {
+            self.salient_entities(tenant, policy.max_entities).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(render_graph_context(
+            &catalog,
+            &entities,
+            policy.max_property_keys,
+            TokenEstimator::CharRatio(4.0),
+            policy.token_budget,
+        ))
+    }
+
+    /// Nodes with an alias and the most current edges, most connected first
+    async fn salient_entities(&self, tenant: &TenantId, limit: usize) -> Result<Vec<SalientEntity>, GraphError> {
+        let snapshot = self.service.snapshot(tenant, Some(Utc::now())).await?;
+
+        let mut degrees: HashMap<Uuid, usize> = HashMap::new();
+        for record in snapshot.edges.iter().filter(|record| record.edge.is_current_version()) {
+            *degrees.entry(record.edge.from_node_id).or_default() += 1;
+            *degrees.entry(record.edge.to_node_id).or_default() += 1;
+        }
+
+        let mut entities: Vec<SalientEntity> = snapshot.nodes.into_iter()
+            .filter_map(|record| {
+                let degree = degrees.get(&record.id).copied().unwrap_or_default();
+                let name = ["name", "title"].iter()
+                    .find_map(|key| record.node.props.get(key).and_then(|value| value.as_str()))
+                    .map(str::to_string);
+                Some(SalientEntity { id_alias: record.node.id_alias?, label: record.node.label, name, degree })
+            })
+            .filter(|entity| entity.degree > 0)
+            .collect();
+        entities.sort_by(|a, b| b.degree.cmp(&a.degree).then_with(|| a.id_alias.cmp(&b.id_alias)));
+        entities.truncate(limit);
+        Ok(entities)
+    }
+
+    /// Ground an extraction in the tenant's graph, if its policy enables it
+    /// and the request brings no context of its own. Failing to read the
+    /// graph leaves the request as it is.
+    pub async fn apply(&self, tenant: &TenantId, context: &mut ExtractionContext) {
+        let policy = self.policies.for_tenant(tenant);
+        if !policy.enabled || context.graph_context.is_some() {
+            return;
+        }
+
+        let ttl = Duration::from_secs(policy.ttl_secs);
+        let cached = self.cache.lock().unwrap().get(tenant)
+            .filter(|(built_at, _)| built_at.elapsed() < ttl)
+            .map(|(_, summary)| summary.clone());
+        let summary = match cached {
+            Some(summary) => summary,
+            None => match self.build(tenant).await {
+                Ok(summary) => {
+                    debug!("Built graph context of tenant {} ({} chars)", tenant, summary.len());
+                    self.cache.lock().unwrap().insert(tenant.clone(), (Instant::now(), summary.clone()));
+                    summary
+                }
+                Err(e) => {
+                    warn!("Extracting without graph context for tenant {}: {}", tenant, e);
+                    return;
+                }
+            },
+        };
+
+        if !summary.is_empty() {
+            context.graph_context = Some(summary);
+        }
+    }
+
+    /// Drop a tenant's cached summary, e.g. after a bulk import
+    pub fn invalidate(&self, tenant: &TenantId) {
+        self.cache.lock().unwrap().remove(tenant);
+    }
+}
+
+/// Prompt section for an extraction's graph context; empty without one
+pub fn graph_context_section(context: &ExtractionContext) -> String {
+    match context.graph_context.as_deref() {
+        Some(summary) if !summary.is_empty() => format!(
+            "\n\nThe knowledge graph already contains the following. Reuse these labels, relationship kinds and `id_alias` values where they fit instead of inventing new ones:\n\n{}",
+            summary
+        ),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, count: u64, keys: &[(&str, u64)]) -> CatalogEntry {
+        CatalogEntry {
+            name: name.to_string(),
+            count,
+            property_keys: keys.iter().map(|(key, count)| (key.to_string(), *count)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_render_graph_context() {
+        let catalog = GraphCatalog {
+            labels: vec![entry("Organization", 3, &[]), entry("Person", 10, &[("name", 10), ("email", 4), ("age", 2)])],
+            kinds: vec![entry("WORKS_AT", 8, &[("role", 5)])],
+        };
+        let entities = vec![SalientEntity {
+            id_alias: "alice".to_string(),
+            label: "Person".to_string(),
+            name: Some("Alice".to_string()),
+            degree: 4,
+        }];
+        let estimator = TokenEstimator::CharRatio(4.0);
+
+        let rendered = render_graph_context(&catalog, &entities, 2, estimator, 1_000);
+        assert_eq!(
+            rendered,
+            "Node labels:\n- Person (10): name, email\n- Organization (3)\n\nRelationship kinds:\n- WORKS_AT (8): role\n\nKnown entities:\n- alice (Person): Alice"
+        );
+
+        // Within a small budget the most used labels come first
+        let rendered = render_graph_context(&catalog, &entities, 2, estimator, 12);
+        assert_eq!(rendered, "Node labels:\n- Person (10): name, email");
+        assert_eq!(render_graph_context(&catalog, &entities, 2, estimator, 1), "");
+    }
+
+    #[test]
+    fn test_graph_context_section() {
+        let mut context: ExtractionContext = serde_json::from_value(serde_json::json!({
+            "messages": [], "system_prompt": null, "desired_schema": null, "max_tokens": null, "temperature": null
+        })).unwrap();
+        assert_eq!(graph_context_section(&context), "");
+
+        context.graph_context = Some("Node labels:\n- Person (10)".to_string());
+        assert!(graph_context_section(&context).ends_with("instead of inventing new ones:\n\nNode labels:\n- Person (10)"));
+    }
+}
@@ -0,0 +1,545 @@
+//! Hierarchical navigable small world (HNSW) graph for approximate nearest
+//! neighbour search
+//!
+//! `HnswGraph` holds vectors and their neighbour lists in memory and supports
+//! incremental insertion. Removed vectors are tombstoned: they keep routing
+//! searches but are never returned, and [`HnswGraph::rebuild`] drops them.
+//! The graph serializes to JSON so [`crate::vector_index::DiskVectorIndex`]
+//! can persist it.
+
+use crate::errors::VectorError;
+use crate::types::VectorMatch;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// Highest layer a vector can be assigned to
+const MAX_LEVEL: usize = 16;
+
+/// Distance function between vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// One minus the cosine similarity
+    #[default]
+    Cosine,
+    /// Euclidean distance
+    Euclidean,
+    /// Negated inner product, for vectors that are already normalized
+    DotProduct,
+}
+
+impl Metric {
+    /// Distance between two vectors of the same dimension; smaller is closer
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => {
+                let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+                for (x, y) in a.iter().zip(b) {
+                    dot += x * y;
+                    norm_a += x * x;
+                    norm_b += y * y;
+                }
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    1.0
+                } else {
+                    1.0 - dot / (norm_a.sqrt() * norm_b.sqrt())
+                }
+            }
+            Metric::Euclidean => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f32>().sqrt(),
+            Metric::DotProduct => -a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>(),
+        }
+    }
+}
+
+/// Tuning parameters of an HNSW graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// Neighbours kept per vector on upper layers (twice this on the bottom layer)
+    pub m: usize,
+    /// Candidate list size while inserting; higher builds a better graph, slower
+    pub ef_construction: usize,
+    /// Candidate list size while searching; higher improves recall, slower
+    pub ef_search: usize,
+    /// Distance function
+    pub metric: Metric,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 200,
+            ef_search: 64,
+            metric: Metric::Cosine,
+        }
+    }
+}
+
+/// A stored vector and its neighbours on each layer it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HnswNode {
+    id: Uuid,
+    vector: Vec<f32>,
+    layers: Vec<Vec<u32>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    deleted: bool,
+}
+
+/// Node reached during a search, ordered by distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    node: u32,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.node.cmp(&other.node))
+    }
+}
+
+/// In-memory HNSW graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredGraph")]
+pub struct HnswGraph {
+    config: HnswConfig,
+    dimension: Option<usize>,
+    nodes: Vec<HnswNode>,
+    entry_point: Option<u32>,
+    #[serde(skip_serializing)]
+    ids: HashMap<Uuid, u32>,
+    #[serde(skip_serializing)]
+    deleted: usize,
+}
+
+/// Serialized form of a graph; the ID lookup is rebuilt on load
+#[derive(Deserialize)]
+struct StoredGraph {
+    config: HnswConfig,
+    dimension: Option<usize>,
+    nodes: Vec<HnswNode>,
+    entry_point: Option<u32>,
+}
+
+impl From<StoredGraph> for HnswGraph {
+    fn from(stored: StoredGraph) -> Self {
+        let ids = stored.nodes.iter().enumerate()
+            .filter(|(_, node)| !node.deleted)
+            .map(|(index, node)| (node.id, index as u32))
+            .collect();
+        let deleted = stored.nodes.iter().filter(|node| node.deleted).count();
+
+        Self {
+            config: stored.config,
+            dimension: stored.dimension,
+            nodes: stored.nodes,
+            entry_point: stored.entry_point,
+            ids,
+            deleted,
+        }
+    }
+}
+
+impl Default for HnswGraph {
+    fn default() -> Self {
+        Self::new(HnswConfig::default())
+    }
+}
+
+impl HnswGraph {
+    /// Create an empty graph
+    pub fn new(config: HnswConfig) -> Self {
+        Self {
+            config: HnswConfig { m: config.m.max(2), ..config },
+            dimension: None,
+            nodes: Vec::new(),
+            entry_point: None,
+            ids: HashMap::new(),
+            deleted: 0,
+        }
+    }
+
+    /// Tuning parameters of the graph
+    pub fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    /// Dimension of the stored vectors, set by the first insert
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Whether the graph has no live vectors
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Number of removed vectors still held for routing
+    pub fn tombstones(&self) -> usize {
+        self.deleted
+    }
+
+    /// Whether a live vector is stored for `id`
+    pub fn contains(&self, id: &Uuid) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    /// The live vector stored for `id`
+    pub fn get(&self, id: &Uuid) -> Option<&[f32]> {
+        self.ids.get(id).map(|&index| self.nodes[index as usize].vector.as_slice())
+    }
+
+    /// Live vectors in insertion order
+    pub fn vectors(&self) -> impl Iterator<Item = (Uuid, &[f32])> {
+        self.nodes.iter()
+            .filter(|node| !node.deleted)
+            .map(|node| (node.id, node.vector.as_slice()))
+    }
+
+    /// Check that `vector` could be inserted
+    pub fn validate(&self, vector: &[f32]) -> Result<(), VectorError> {
+        if vector.is_empty() {
+            return Err(VectorError::InvalidVector("Vector is empty".to_string()));
+        }
+        if let Some(position) = vector.iter().position(|x| !x.is_finite()) {
+            return Err(VectorError::InvalidVector(format!("Component {} is not a finite number", position)));
+        }
+        match self.dimension {
+            Some(expected) if expected != vector.len() => Err(VectorError::DimensionMismatch {
+                expected,
+                actual: vector.len(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Insert the vector for `id`, replacing any previous one
+    pub fn insert(&mut self, id: Uuid, vector: Vec<f32>) -> Result<(), VectorError> {
+        self.validate(&vector)?;
+        self.dimension = Some(vector.len());
+        self.remove(&id);
+
+        let level = random_level(&id, 1.0 / (self.config.m as f64).ln());
+        let index = self.nodes.len() as u32;
+        self.nodes.push(HnswNode {
+            id,
+            vector,
+            layers: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.ids.insert(id, index);
+
+        let Some(entry) = self.entry_point else {
+            self.entry_point = Some(index);
+            return Ok(());
+        };
+
+        let query = self.nodes[index as usize].vector.clone();
+        let top = self.top_layer(entry);
+        let mut entry_points = vec![entry];
+
+        for layer in (level + 1..=top).rev() {
+            entry_points = vec![self.search_layer(&query, &entry_points, 1, layer)[0].node];
+        }
+
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, &entry_points, self.config.ef_construction, layer);
+            let neighbours = self.select_neighbours(&found, self.config.m);
+            for &neighbour in &neighbours {
+                self.connect(neighbour, index, layer);
+            }
+            self.nodes[index as usize].layers[layer] = neighbours;
+            entry_points = found.iter().map(|c| c.node).collect();
+        }
+
+        if level > top {
+            self.entry_point = Some(index);
+        }
+        Ok(())
+    }
+
+    /// Remove the vector for `id`; returns false if there was none
+    pub fn remove(&mut self, id: &Uuid) -> bool {
+        match self.ids.remove(id) {
+            Some(index) => {
+                self.nodes[index as usize].deleted = true;
+                self.deleted += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Find the `k` live vectors nearest to `query`, closest first
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<VectorMatch>, VectorError> {
+        let Some(entry) = self.entry_point else {
+            return Ok(Vec::new());
+        };
+        self.validate(query)?;
+        if k == 0 || self.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut entry_points = vec![entry];
+        for layer in (1..=self.top_layer(entry)).rev() {
+            entry_points = vec![self.search_layer(query, &entry_points, 1, layer)[0].node];
+        }
+
+        // Widen the search by the tombstones it may have to skip
+        let ef = self.config.ef_search.max(k).saturating_add(self.deleted).min(self.nodes.len());
+        Ok(self.search_layer(query, &entry_points, ef, 0)
+            .into_iter()
+            .filter(|c| !self.nodes[c.node as usize].deleted)
+            .take(k)
+            .map(|c| VectorMatch {
+                id: self.nodes[c.node as usize].id,
+                distance: c.distance,
+            })
+            .collect())
+    }
+
+    /// A new graph holding only the live vectors
+    pub fn rebuild(&self) -> Self {
+        let mut graph = Self::new(self.config.clone());
+        for node in self.nodes.iter().filter(|node| !node.deleted) {
+            // Vectors were validated when first inserted
+            let _ = graph.insert(node.id, node.vector.clone());
+        }
+        graph
+    }
+
+    fn top_layer(&self, index: u32) -> usize {
+        self.nodes[index as usize].layers.len() - 1
+    }
+
+    fn max_neighbours(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.config.m * 2
+        } else {
+            self.config.m
+        }
+    }
+
+    fn distance(&self, a: u32, b: u32) -> f32 {
+        self.config.metric.distance(&self.nodes[a as usize].vector, &self.nodes[b as usize].vector)
+    }
+
+    /// Best-first search of one layer, returning up to `ef` nodes, closest first
+    fn search_layer(&self, query: &[f32], entry_points: &[u32], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entry_points.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut results = BinaryHeap::new();
+
+        for &node in entry_points {
+            let candidate = Candidate {
+                distance: self.config.metric.distance(query, &self.nodes[node as usize].vector),
+                node,
+            };
+            candidates.push(Reverse(candidate));
+            results.push(candidate);
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|worst: &Candidate| current.distance > worst.distance) {
+                break;
+            }
+
+            let Some(neighbours) = self.nodes[current.node as usize].layers.get(layer) else {
+                continue;
+            };
+            for &neighbour in neighbours {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let distance = self.config.metric.distance(query, &self.nodes[neighbour as usize].vector);
+                if results.len() < ef || results.peek().is_some_and(|worst| distance < worst.distance) {
+                    let candidate = Candidate { distance, node: neighbour };
+                    candidates.push(Reverse(candidate));
+                    results.push(candidate);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    /// Pick up to `m` neighbours from candidates sorted closest first,
+    /// preferring ones that are not already reachable through a closer pick
+    fn select_neighbours(&self, candidates: &[Candidate], m: usize) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(m);
+        let mut skipped = Vec::new();
+
+        for candidate in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            if selected.iter().all(|&s| self.distance(candidate.node, s) > candidate.distance) {
+                selected.push(candidate.node);
+            } else {
+                skipped.push(candidate.node);
+            }
+        }
+
+        // Keep the graph dense when the heuristic is too strict
+        for node in skipped {
+            if selected.len() >= m {
+                break;
+            }
+            selected.push(node);
+        }
+        selected
+    }
+
+    /// Link `from` to `to` on `layer`, pruning `from`'s neighbours if needed
+    fn connect(&mut self, from: u32, to: u32, layer: usize) {
+        let max = self.max_neighbours(layer);
+        let neighbours = &mut self.nodes[from as usize].layers[layer];
+        neighbours.push(to);
+        if neighbours.len() <= max {
+            return;
+        }
+
+        let mut candidates: Vec<Candidate> = self.nodes[from as usize].layers[layer].iter()
+            .map(|&node| Candidate { distance: self.distance(from, node), node })
+            .collect();
+        candidates.sort();
+        self.nodes[from as usize].layers[layer] = self.select_neighbours(&candidates, max);
+    }
+}
+
+/// Layer of a vector, drawn from an exponential distribution seeded by its ID
+/// so that rebuilding a graph gives every vector the same layer
+fn random_level(id: &Uuid, level_multiplier: f64) -> usize {
+    let bits = id.as_u128();
+    let mut z = ((bits as u64) ^ ((bits >> 64) as u64)).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+
+    // Uniform in (0, 1]
+    let uniform = ((z >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+    ((-uniform.ln() * level_multiplier).floor() as usize).min(MAX_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random vectors
+    fn vectors(count: usize, dimension: usize) -> Vec<(Uuid, Vec<f32>)> {
+        let mut state = 42u64;
+        (0..count)
+            .map(|_| {
+                let vector = (0..dimension)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                    })
+                    .collect();
+                (Uuid::new_v4(), vector)
+            })
+            .collect()
+    }
+
+    fn brute_force(data: &[(Uuid, Vec<f32>)], query: &[f32], k: usize, metric: Metric) -> Vec<Uuid> {
+        let mut scored: Vec<_> = data.iter().map(|(id, v)| (metric.distance(query, v), *id)).collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+        scored.into_iter().take(k).map(|(_, id)| id).collect()
+    }
+
+    #[test]
+    fn test_search_recall() {
+        let data = vectors(1_000, 16);
+        let mut graph = HnswGraph::new(HnswConfig { metric: Metric::Euclidean, ..HnswConfig::default() });
+        for (id, vector) in &data {
+            graph.insert(*id, vector.clone()).unwrap();
+        }
+        assert_eq!(graph.len(), 1_000);
+
+        let mut hits = 0;
+        for (_, query) in data.iter().take(50) {
+            let expected = brute_force(&data, query, 10, Metric::Euclidean);
+            let found: Vec<Uuid> = graph.search(query, 10).unwrap().into_iter().map(|m| m.id).collect();
+            hits += found.iter().filter(|id| expected.contains(id)).count();
+        }
+        // Recall@10 over 50 queries
+        assert!(hits as f64 / 500.0 > 0.9, "recall too low: {}", hits);
+
+        // A stored vector is its own nearest neighbour
+        let (id, vector) = &data[7];
+        let top = &graph.search(vector, 1).unwrap()[0];
+        assert_eq!(top.id, *id);
+        assert!(top.distance.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_remove_replace_and_rebuild() {
+        let data = vectors(200, 8);
+        let mut graph = HnswGraph::default();
+        for (id, vector) in &data {
+            graph.insert(*id, vector.clone()).unwrap();
+        }
+
+        let (removed, removed_vector) = &data[0];
+        assert!(graph.remove(removed));
+        assert!(!graph.remove(removed));
+        assert!(graph.search(removed_vector, 200).unwrap().iter().all(|m| m.id != *removed));
+        assert_eq!(graph.search(removed_vector, 500).unwrap().len(), 199);
+
+        // Replacing a vector moves it
+        let (moved, _) = &data[1];
+        let target = data[2].1.iter().map(|x| -x).collect::<Vec<_>>();
+        graph.insert(*moved, target.clone()).unwrap();
+        assert_eq!(graph.search(&target, 1).unwrap()[0].id, *moved);
+        assert_eq!(graph.len(), 199);
+        assert_eq!(graph.tombstones(), 2);
+
+        let rebuilt = graph.rebuild();
+        assert_eq!(rebuilt.len(), 199);
+        assert_eq!(rebuilt.tombstones(), 0);
+        assert_eq!(rebuilt.get(moved), Some(target.as_slice()));
+
+        // The ID lookup survives a round trip through JSON
+        let restored: HnswGraph = serde_json::from_str(&serde_json::to_string(&graph).unwrap()).unwrap();
+        assert_eq!(restored.len(), 199);
+        assert_eq!(restored.tombstones(), 2);
+        assert_eq!(restored.search(&target, 1).unwrap()[0].id, *moved);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut graph = HnswGraph::default();
+        assert!(graph.search(&[1.0, 0.0], 5).unwrap().is_empty());
+        assert!(matches!(graph.insert(Uuid::new_v4(), vec![]), Err(VectorError::InvalidVector(_))));
+        assert!(matches!(graph.insert(Uuid::new_v4(), vec![f32::NAN]), Err(VectorError::InvalidVector(_))));
+
+        graph.insert(Uuid::new_v4(), vec![1.0, 0.0]).unwrap();
+        assert_eq!(graph.dimension(), Some(2));
+        assert!(matches!(
+            graph.insert(Uuid::new_v4(), vec![1.0, 0.0, 0.0]),
+            Err(VectorError::DimensionMismatch { expected: 2, actual: 3 })
+        ));
+        assert!(matches!(graph.search(&[1.0], 1), Err(VectorError::DimensionMismatch { .. })));
+    }
+}
@@ -0,0 +1,404 @@
+//! Custom business logic on graph operations
+//!
+//! Hooks are typed callbacks that run inside a [`HookedGraphStore`]:
+//! [`OnNodeUpsert`] and [`OnEdgeUpsert`] see, and may change, every node and
+//! edge before it is written, and [`OnQueryResult`] sees every query result
+//! before it is returned. They are collected in [`Hooks`] and installed with
+//! `CoreGraphService::with_hooks`, or as a [`HookLayer`] of a
+//! `LayeredGraphStore`:
+//!
+//! ```ignore
+//! let hooks = Hooks::new().with_node_upsert(Arc::new(FullNamePerson));
+//! let service = CoreGraphService::new(store).with_hooks(hooks);
+//! ```
+//!
+//! Hooks of a kind run in ascending `order`, and in registration order among
+//! equals, each seeing the changes of the hooks before it. A failing hook
+//! aborts the operation with its error, so nothing is written, unless its
+//! `on_error` is [`HookErrorPolicy::Skip`]: then its changes are discarded,
+//! a warning is logged and the next hook runs. Hooks get the store below
+//! them through [`HookContext`] for lookups; what they do with it does not
+//! trigger hooks again.
+//!
+//! Edges created by `upsert_node_with_edges` are written by the store from
+//! their `EdgeSpec`s and do not pass edge hooks; the node does pass node
+//! hooks.
+
+use crate::archive::ArchiveBatch;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{until_error, EdgeStream, NodeStream};
+use crate::errors::GraphError;
+use crate::layers::GraphStoreLayer;
+use crate::materialized::SnapshotInfo;
+use crate::traits::GraphStore;
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeWithEdges, Path, TenantId, TimeEdge};
+use async_trait::async_trait;
+use futures::StreamExt;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+/// What a hook failure does to the operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookErrorPolicy {
+    /// Fail the operation with the hook's error
+    #[default]
+    Abort,
+    /// Discard the hook's changes and carry on
+    Skip,
+}
+
+/// What a hook runs with
+pub struct HookContext<'a> {
+    pub tenant: &'a TenantId,
+    /// The store below the hooks, for lookups
+    pub store: &'a Arc<dyn GraphStore>,
+}
+
+/// Runs before a node is written
+#[async_trait]
+pub trait OnNodeUpsert: Send + Sync {
+    /// Name of the hook, for logging
+    fn name(&self) -> &'static str;
+
+    /// Position among node hooks; lower runs first
+    fn order(&self) -> i32 {
+        0
+    }
+
+    fn on_error(&self) -> HookErrorPolicy {
+        HookErrorPolicy::Abort
+    }
+
+    async fn on_node_upsert(&self, ctx: &HookContext<'_>, node: &mut Node) -> Result<(), GraphError>;
+}
+
+/// Runs before an edge is written, including the new version of a
+/// superseded edge
+#[async_trait]
+pub trait OnEdgeUpsert: Send + Sync {
+    /// Name of the hook, for logging
+    fn name(&self) -> &'static str;
+
+    /// Position among edge hooks; lower runs first
+    fn order(&self) -> i32 {
+        0
+    }
+
+    fn on_error(&self) -> HookErrorPolicy {
+        HookErrorPolicy::Abort
+    }
+
+    async fn on_edge_upsert(&self, ctx: &HookContext<'_>, edge: &mut TimeEdge) -> Result<(), GraphError>;
+}
+
+/// Runs on the result of a query, before it is returned
+#[async_trait]
+pub trait OnQueryResult: Send + Sync {
+    /// Name of the hook, for logging
+    fn name(&self) -> &'static str;
+
+    /// Position among query result hooks; lower runs first
+    fn order(&self) -> i32 {
+        0
+    }
+
+    fn on_error(&self) -> HookErrorPolicy {
+        HookErrorPolicy::Abort
+    }
+
+    async fn on_query_result(&self, ctx: &HookContext<'_>, query: &GraphQuery, paths: &mut Vec<Path>) -> Result<(), GraphError>;
+}
+
+/// Registered hooks, by kind, in the order they run
+#[derive(Clone, Default)]
+pub struct Hooks {
+    node_upsert: Vec<Arc<dyn OnNodeUpsert>>,
+    edge_upsert: Vec<Arc<dyn OnEdgeUpsert>>,
+    query_result: Vec<Arc<dyn OnQueryResult>>,
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `hook` before every node upsert
+    pub fn with_node_upsert(mut self, hook: Arc<dyn OnNodeUpsert>) -> Self {
+        self.node_upsert.push(hook);
+        // Stable, so equal orders keep registration order
+        self.node_upsert.sort_by_key(|hook| hook.order());
+        self
+    }
+
+    /// Run `hook` before every edge upsert
+    pub fn with_edge_upsert(mut self, hook: Arc<dyn OnEdgeUpsert>) -> Self {
+        self.edge_upsert.push(hook);
+        self.edge_upsert.sort_by_key(|hook| hook.order());
+        self
+    }
+
+    /// Run `hook` on every query result
+    pub fn with_query_result(mut self, hook: Arc<dyn OnQueryResult>) -> Self {
+        self.query_result.push(hook);
+        self.query_result.sort_by_key(|hook| hook.order());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.node_upsert.is_empty() && self.edge_upsert.is_empty() && self.query_result.is_empty()
+    }
+}
+
+/// Keep what a hook made of a copy of `value` if it succeeded, and apply its
+/// error policy if it failed
+fn settle<T>(name: &str, policy: HookErrorPolicy, value: &mut T, changed: T, result: Result<(), GraphError>) -> Result<(), GraphError> {
+    match result {
+        Ok(()) => {
+            *value = changed;
+            Ok(())
+        }
+        Err(e) if policy == HookErrorPolicy::Skip => {
+            warn!("Skipping failed hook '{}': {}", name, e);
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// A `GraphStore` that runs hooks around another store's operations
+pub struct HookedGraphStore {
+    inner: Arc<dyn GraphStore>,
+    hooks: Hooks,
+}
+
+impl HookedGraphStore {
+    pub fn new(inner: Arc<dyn GraphStore>, hooks: Hooks) -> Self {
+        Self { inner, hooks }
+    }
+
+    async fn node_hooks(&self, tenant: &TenantId, node: &mut Node) -> Result<(), GraphError> {
+        let ctx = HookContext { tenant, store: &self.inner };
+        for hook in &self.hooks.node_upsert {
+            let mut changed = node.clone();
+            let result = hook.on_node_upsert(&ctx, &mut changed).await;
+            settle(hook.name(), hook.on_error(), node, changed, result)?;
+        }
+        Ok(())
+    }
+
+    async fn edge_hooks(&self, tenant: &TenantId, edge: &mut TimeEdge) -> Result<(), GraphError> {
+        let ctx = HookContext { tenant, store: &self.inner };
+        for hook in &self.hooks.edge_upsert {
+            let mut changed = edge.clone();
+            let result = hook.on_edge_upsert(&ctx, &mut changed).await;
+            settle(hook.name(), hook.on_error(), edge, changed, result)?;
+        }
+        Ok(())
+    }
+}
+
+/// Layer that runs hooks, see [`HookedGraphStore`]
+#[derive(Clone)]
+pub struct HookLayer {
+    hooks: Hooks,
+}
+
+impl HookLayer {
+    pub fn new(hooks: Hooks) -> Self {
+        Self { hooks }
+    }
+}
+
+impl GraphStoreLayer for HookLayer {
+    fn name(&self) -> &'static str {
+        "hooks"
+    }
+
+    fn layer(&self, inner: Arc<dyn GraphStore>) -> Arc<dyn GraphStore> {
+        Arc::new(HookedGraphStore::new(inner, self.hooks.clone()))
+    }
+}
+
+#[async_trait]
+impl GraphStore for HookedGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, mut node: Node) -> Result<Uuid, GraphError> {
+        self.node_hooks(tenant, &mut node).await?;
+        self.inner.upsert_node(tenant, node).await
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, mut edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.edge_hooks(tenant, &mut edge).await?;
+        self.inner.upsert_edge(tenant, edge).await
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, mut node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        self.node_hooks(tenant, &mut node).await?;
+        self.inner.upsert_node_with_edges(tenant, node, edges).await
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        // Hooks run on each node as the store reads it; a rejected node ends the load
+        let (nodes, rejected) = until_error(nodes.then(|mut node| async move {
+            self.node_hooks(tenant, &mut node).await.map(|()| node)
+        }));
+        let loaded = self.inner.bulk_load_nodes(tenant, nodes).await?;
+        rejected.take().map_or(Ok(loaded), Err)
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        let (edges, rejected) = until_error(edges.then(|mut edge| async move {
+            self.edge_hooks(tenant, &mut edge).await.map(|()| edge)
+        }));
+        let loaded = self.inner.bulk_load_edges(tenant, edges).await?;
+        rejected.take().map_or(Ok(loaded), Err)
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        let mut paths = self.inner.query(tenant, query.clone()).await?;
+        let ctx = HookContext { tenant, store: &self.inner };
+        for hook in &self.hooks.query_result {
+            let mut changed = paths.clone();
+            let result = hook.on_query_result(&ctx, &query, &mut changed).await;
+            settle(hook.name(), hook.on_error(), &mut paths, changed, result)?;
+        }
+        Ok(paths)
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.inner.query_count(tenant, query).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.inner.query_exists(tenant, query).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.inner.traverse(tenant, request).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.inner.shortest_path(tenant, request).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.inner.timeline(tenant, request).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.inner.property_history(tenant, node_id, key).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, mut node: Node) -> Result<bool, GraphError> {
+        self.node_hooks(tenant, &mut node).await?;
+        self.inner.update_node(tenant, id, node).await
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.inner.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.inner.set_edge_constraints(tenant, constraints).await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.inner.get_node(tenant, id).await
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        self.inner.get_node_by_alias(tenant, id_alias).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.inner.resolve_aliases(tenant, aliases).await
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.inner.delete_node(tenant, id).await
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.inner.delete_edge(tenant, id).await
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        self.inner.close_edge(tenant, id, valid_to).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, mut edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.edge_hooks(tenant, &mut edge).await?;
+        self.inner.supersede_edge(tenant, id, edge).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.inner.retract_edge(tenant, id).await
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        self.inner.get_node_history(tenant, id).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        self.inner.snapshot(tenant, valid_at).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.inner.materialize_snapshot(tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.inner.list_snapshots(tenant).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.inner.drop_snapshot(tenant, name).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.inner.query_snapshot(tenant, name, query).await
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        self.inner.read_history(tenant, before).await
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.inner.purge_history(tenant, before).await
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        self.inner.restore_history(tenant, batch).await
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.inner.purge_expired_edges(tenant, now).await
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        self.inner.summary(tenant).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.inner.catalog(tenant).await
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        self.inner.list_tenants().await
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        self.inner.clear_tenant(tenant).await
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.inner.health_check().await
+    }
+}
@@ -0,0 +1,217 @@
+//! System ID generation for new nodes and edges
+//!
+//! Stores draw the IDs of new records from an `IdGenerator`. Random v4 UUIDs
+//! are the default; time-ordered strategies keep recent writes close together
+//! in B-tree indexes, and `Seeded` yields the same IDs on every run so that
+//! test fixtures can name them.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::{Builder, Uuid};
+
+/// Start of snowflake timestamps: 2020-01-01T00:00:00Z, in Unix milliseconds
+pub const SNOWFLAKE_EPOCH_MS: u64 = 1_577_836_800_000;
+
+const SNOWFLAKE_NODE_BITS: u32 = 10;
+const SNOWFLAKE_SEQUENCE_BITS: u32 = 12;
+const ULID_RANDOM_BITS: u32 = 80;
+
+/// Source of system IDs for new nodes and edges
+pub trait IdGenerator: Send + Sync + fmt::Debug {
+    /// A new ID, distinct from all IDs this generator returned before
+    fn next_id(&self) -> Uuid;
+}
+
+/// How a deployment generates system IDs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum IdStrategy {
+    /// Random v4 UUIDs
+    #[default]
+    UuidV4,
+    /// Time-ordered v7 UUIDs
+    UuidV7,
+    /// ULIDs: a millisecond timestamp and 80 random bits, monotonic within a
+    /// millisecond, stored as UUIDs
+    Ulid,
+    /// 64-bit snowflake IDs (timestamp, node, sequence) in the low half of
+    /// the UUID. Only the lower 10 bits of `node_id` are used, so every
+    /// writer of a database needs its own value below 1024.
+    Snowflake { node_id: u16 },
+    /// Deterministic v4-shaped IDs from a seed, for tests
+    Seeded { seed: u64 },
+}
+
+impl IdStrategy {
+    /// A generator for the strategy, starting from a fresh state
+    pub fn build(&self) -> Arc<dyn IdGenerator> {
+        match self {
+            IdStrategy::UuidV4 => Arc::new(RandomIds),
+            IdStrategy::UuidV7 => Arc::new(TimeOrderedIds),
+            IdStrategy::Ulid => Arc::new(UlidIds::default()),
+            IdStrategy::Snowflake { node_id } => Arc::new(SnowflakeIds::new(*node_id)),
+            IdStrategy::Seeded { seed } => Arc::new(SeededIds::new(*seed)),
+        }
+    }
+}
+
+/// Random v4 UUIDs
+#[derive(Debug, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn next_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Time-ordered v7 UUIDs
+#[derive(Debug, Default)]
+pub struct TimeOrderedIds;
+
+impl IdGenerator for TimeOrderedIds {
+    fn next_id(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// ULIDs stored as UUIDs
+#[derive(Debug, Default)]
+pub struct UlidIds {
+    /// Timestamp and random part of the last ID
+    last: Mutex<(u64, u128)>,
+}
+
+impl IdGenerator for UlidIds {
+    fn next_id(&self) -> Uuid {
+        let mut last = self.last.lock().unwrap();
+        let (mut ms, mut random) = (now_ms(), random_bits(ULID_RANDOM_BITS));
+        if ms <= last.0 {
+            // Same millisecond, or the clock went back: increment the last ID
+            ms = last.0;
+            random = last.1 + 1;
+            if random >> ULID_RANDOM_BITS != 0 {
+                ms += 1;
+                random = 0;
+            }
+        }
+        *last = (ms, random);
+        Uuid::from_u128((u128::from(ms) << ULID_RANDOM_BITS) | random)
+    }
+}
+
+/// Snowflake IDs in the low 64 bits of a UUID
+#[derive(Debug)]
+pub struct SnowflakeIds {
+    node_id: u64,
+    /// Timestamp and sequence number of the last ID
+    last: Mutex<(u64, u64)>,
+}
+
+impl SnowflakeIds {
+    /// Create a generator for a node; only the lower 10 bits of `node_id` are used
+    pub fn new(node_id: u16) -> Self {
+        Self {
+            node_id: u64::from(node_id) & ((1 << SNOWFLAKE_NODE_BITS) - 1),
+            last: Mutex::new((0, 0)),
+        }
+    }
+}
+
+impl IdGenerator for SnowflakeIds {
+    fn next_id(&self) -> Uuid {
+        let mut last = self.last.lock().unwrap();
+        let mut ms = now_ms().saturating_sub(SNOWFLAKE_EPOCH_MS).max(last.0);
+        let mut sequence = 0;
+        if ms == last.0 {
+            sequence = last.1 + 1;
+            if sequence >> SNOWFLAKE_SEQUENCE_BITS != 0 {
+                // Sequence exhausted: borrow the next millisecond
+                ms += 1;
+                sequence = 0;
+            }
+        }
+        *last = (ms, sequence);
+        let id = (ms << (SNOWFLAKE_NODE_BITS + SNOWFLAKE_SEQUENCE_BITS)) | (self.node_id << SNOWFLAKE_SEQUENCE_BITS) | sequence;
+        Uuid::from_u64_pair(0, id)
+    }
+}
+
+/// Deterministic IDs from a seed (SplitMix64)
+#[derive(Debug)]
+pub struct SeededIds {
+    state: AtomicU64,
+}
+
+impl SeededIds {
+    pub fn new(seed: u64) -> Self {
+        Self { state: AtomicU64::new(seed) }
+    }
+
+    fn next_u64(&self) -> u64 {
+        let mut z = self.state.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl IdGenerator for SeededIds {
+    fn next_id(&self) -> Uuid {
+        let bytes = (u128::from(self.next_u64()) << 64 | u128::from(self.next_u64())).to_be_bytes();
+        Builder::from_random_bytes(bytes).into_uuid()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// The lower `bits` bits of a random v4 UUID's payload
+fn random_bits(bits: u32) -> u128 {
+    Uuid::new_v4().as_u128() & ((1 << bits) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(strategy: &IdStrategy, count: usize) -> Vec<Uuid> {
+        let generator = strategy.build();
+        (0..count).map(|_| generator.next_id()).collect()
+    }
+
+    #[test]
+    fn test_time_ordered_strategies_are_monotonic() {
+        for strategy in [IdStrategy::UuidV7, IdStrategy::Ulid, IdStrategy::Snowflake { node_id: 7 }] {
+            let generated = ids(&strategy, 10_000);
+            assert!(generated.windows(2).all(|pair| pair[0] < pair[1]), "{:?} is not monotonic", strategy);
+        }
+    }
+
+    #[test]
+    fn test_snowflake_layout() {
+        let id = SnowflakeIds::new(1024 + 5).next_id();
+        let (high, low) = id.as_u64_pair();
+        assert_eq!(high, 0);
+        assert_eq!((low >> SNOWFLAKE_SEQUENCE_BITS) & 0x3FF, 5);
+    }
+
+    #[test]
+    fn test_seeded_ids_are_deterministic() {
+        let seeded = IdStrategy::Seeded { seed: 42 };
+        assert_eq!(ids(&seeded, 3), ids(&seeded, 3));
+        assert_ne!(ids(&seeded, 3), ids(&IdStrategy::Seeded { seed: 43 }, 3));
+        assert_eq!(ids(&seeded, 1)[0].get_version_num(), 4);
+    }
+
+    #[test]
+    fn test_strategy_config() {
+        let strategy: IdStrategy = serde_json::from_str(r#"{"strategy": "snowflake", "node_id": 3}"#).unwrap();
+        assert_eq!(strategy, IdStrategy::Snowflake { node_id: 3 });
+        assert_eq!(serde_json::from_str::<IdStrategy>(r#"{"strategy": "uuid_v7"}"#).unwrap(), IdStrategy::UuidV7);
+    }
+}
@@ -0,0 +1,795 @@
+//! Server-side ingestion templates
+//!
+//! A template is a named, reusable mapping from the columns of tabular data,
+//! such as CSV rows, to nodes or relationships: which columns hold the
+//! label, alias, endpoints, relationship kind and valid times, which become
+//! properties, how their values are coerced and what to use for empty cells.
+//! Templates are kept per tenant in an [`IngestTemplateStore`] and applied
+//! row by row with [`IngestTemplate::map_row`], by kgctl and by the CSV
+//! import endpoint alike.
+//!
+//! Cells of columns without a coercion of their own are typed by the
+//! tenant's [`IngestSchema`], if it declares the property for the row's
+//! label or relationship kind, and inferred otherwise. Inference turns
+//! `01234` into `1234` and `yes` into `true`; in strict mode a cell only
+//! becomes a number or boolean if it reads back the same, see
+//! [`infer_value`].
+//!
+//! Relationship rows name their endpoints by alias. The mapped edge has nil
+//! node IDs and carries the aliases in `_from_id_alias` and `_to_id_alias`
+//! (plus `_from_alias_namespace` and `_to_alias_namespace`) properties, which
+//! [`resolve_edge_aliases`] replaces with node IDs before the edge is written.
+
+use crate::errors::GraphError;
+use crate::temporal::{IntervalBound, IsoDuration, PartialDate};
+use crate::traits::GraphService;
+use crate::types::{AliasKey, Node, TenantId, TimeEdge};
+use crate::valid_time::{SourceInfo, ValidTimePolicy};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Format of timestamps in templates that do not set one
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// What a template produces from each row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateKind {
+    Node,
+    Relationship,
+}
+
+/// What a column is mapped to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "target", rename_all = "snake_case")]
+pub enum ColumnTarget {
+    /// Node label
+    Label,
+    /// Node `id_alias`
+    Alias,
+    /// Alias of the relationship's source node
+    From,
+    /// Alias of the relationship's target node
+    To,
+    /// Relationship kind
+    Kind,
+    /// Start of the relationship's validity
+    ValidFrom,
+    /// End of the relationship's validity
+    ValidTo,
+    /// A property, named after the column unless `name` is given
+    Property {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    /// Not ingested
+    Ignore,
+}
+
+/// How a property value is converted from its cell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Coercion {
+    /// Integers, floats and `true`/`false`/`yes`/`no` are converted; anything
+    /// else stays a string
+    #[default]
+    Auto,
+    String,
+    Integer,
+    Float,
+    /// `true`/`false`, `yes`/`no` or `1`/`0`
+    Boolean,
+    /// The cell holds JSON
+    Json,
+    /// A timestamp, stored as RFC 3339
+    Timestamp,
+    /// A year, month or day, stored as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`
+    /// without widening it to a timestamp
+    Date,
+    /// An ISO 8601 duration such as `P1Y6M`
+    Duration,
+}
+
+impl Coercion {
+    /// Convert a cell; timestamps without an offset are read with
+    /// `date_format` in the policy's timezone. The error describes why the
+    /// cell does not fit.
+    pub fn apply(self, value: &str, date_format: &str, valid_time: &ValidTimePolicy) -> Result<Value, String> {
+        let invalid = |kind: &str| format!("'{}' is not {}", value, kind);
+        match self {
+            Coercion::Auto => Ok(infer_value(value, false)),
+            Coercion::String => Ok(Value::String(value.to_string())),
+            Coercion::Integer => value.parse::<i64>().map(Value::from).map_err(|_| invalid("an integer")),
+            Coercion::Float => value.parse::<f64>().ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| invalid("a number")),
+            Coercion::Boolean => match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok(Value::Bool(true)),
+                "false" | "no" | "0" => Ok(Value::Bool(false)),
+                _ => Err(invalid("a boolean")),
+            },
+            Coercion::Json => serde_json::from_str(value).map_err(|e| format!("'{}' is not JSON: {}", value, e)),
+            Coercion::Timestamp => parse_timestamp(value, date_format, valid_time, IntervalBound::Start).map(|time| Value::String(time.to_rfc3339())),
+            Coercion::Date => value.parse::<PartialDate>().map(|date| Value::String(date.to_string())),
+            Coercion::Duration => value.parse::<IsoDuration>().map(|duration| Value::String(duration.to_string())),
+        }
+    }
+}
+
+/// Declared property types of a tenant's node labels and relationship kinds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestSchema {
+    /// Property types by node label
+    #[serde(default)]
+    pub labels: BTreeMap<String, BTreeMap<String, Coercion>>,
+    /// Property types by relationship kind
+    #[serde(default)]
+    pub kinds: BTreeMap<String, BTreeMap<String, Coercion>>,
+}
+
+impl IngestSchema {
+    /// Declared property types of a label or relationship kind
+    pub fn properties(&self, kind: TemplateKind, name: &str) -> Option<&BTreeMap<String, Coercion>> {
+        match kind {
+            TemplateKind::Node => self.labels.get(name),
+            TemplateKind::Relationship => self.kinds.get(name),
+        }
+    }
+
+    /// Declared type of a property of a label or relationship kind
+    pub fn property_type(&self, kind: TemplateKind, name: &str, property: &str) -> Option<Coercion> {
+        self.properties(kind, name)?.get(property).copied()
+    }
+
+    /// Check that every declared property has a type
+    pub fn validate(&self) -> Result<(), String> {
+        let declared = self.labels.iter().map(|(label, properties)| ("label", label, properties))
+            .chain(self.kinds.iter().map(|(kind, properties)| ("relationship kind", kind, properties)));
+        for (what, name, properties) in declared {
+            if let Some((property, _)) = properties.iter().find(|(_, coercion)| **coercion == Coercion::Auto) {
+                return Err(format!("Property '{}' of {} '{}' needs a type other than auto", property, what, name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A property cell of a row, not yet typed
+struct PropertyCell {
+    column: String,
+    property: String,
+    value: String,
+    coerce: Coercion,
+}
+
+/// Mapping of one column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    /// Column name, or its zero-based index for files without a header
+    pub column: String,
+    #[serde(flatten)]
+    pub target: ColumnTarget,
+    #[serde(default)]
+    pub coerce: Coercion,
+    /// Value used when the cell is empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+impl ColumnMapping {
+    /// Map `column` to `target`
+    pub fn new(column: impl Into<String>, target: ColumnTarget) -> Self {
+        Self {
+            column: column.into(),
+            target,
+            coerce: Coercion::Auto,
+            default: None,
+        }
+    }
+
+    /// Set the coercion of the column's values
+    pub fn with_coercion(mut self, coerce: Coercion) -> Self {
+        self.coerce = coerce;
+        self
+    }
+
+    /// Set the value used for empty cells
+    pub fn with_default(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+}
+
+/// A named mapping from columns to nodes or relationships
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IngestTemplate {
+    pub name: String,
+    pub kind: TemplateKind,
+    pub columns: Vec<ColumnMapping>,
+    /// Label of nodes whose row has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Kind of relationships whose row has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationship_kind: Option<String>,
+    /// Namespace of the aliases the template reads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias_namespace: Option<String>,
+    /// `strftime` format of timestamps without an offset; RFC 3339 and plain
+    /// dates are always accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// Ingest columns the template does not mention as properties
+    #[serde(default)]
+    pub unmapped_as_properties: bool,
+    /// Infer only lossless types for cells the coercions and schema leave
+    /// untyped, see [`infer_value`]
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// A row mapped by a template
+#[derive(Debug, Clone)]
+pub enum MappedRecord {
+    Node(Node),
+    Edge(TimeEdge),
+}
+
+impl IngestTemplate {
+    /// Create a template with no columns
+    pub fn new(name: impl Into<String>, kind: TemplateKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            columns: Vec::new(),
+            label: None,
+            relationship_kind: None,
+            alias_namespace: None,
+            date_format: None,
+            unmapped_as_properties: false,
+            strict: false,
+        }
+    }
+
+    /// Add a column mapping
+    pub fn with_column(mut self, column: ColumnMapping) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// Check that the template can produce its kind of record
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.contains('/') {
+            return Err(format!("Invalid template name '{}'", self.name));
+        }
+
+        let count = |target: &ColumnTarget| self.columns.iter().filter(|c| &c.target == target).count();
+        let structural = [ColumnTarget::Label, ColumnTarget::Alias, ColumnTarget::From, ColumnTarget::To, ColumnTarget::Kind, ColumnTarget::ValidFrom, ColumnTarget::ValidTo];
+        if let Some(target) = structural.iter().find(|target| count(target) > 1) {
+            return Err(format!("More than one column is mapped to {:?}", target));
+        }
+
+        let (required, allowed): (&[ColumnTarget], &[ColumnTarget]) = match self.kind {
+            TemplateKind::Node => {
+                if count(&ColumnTarget::Label) == 0 && self.label.is_none() {
+                    return Err("Node templates need a label column or a fixed label".to_string());
+                }
+                (&[], &[ColumnTarget::Label, ColumnTarget::Alias])
+            }
+            TemplateKind::Relationship => {
+                if count(&ColumnTarget::Kind) == 0 && self.relationship_kind.is_none() {
+                    return Err("Relationship templates need a kind column or a fixed relationship kind".to_string());
+                }
+                (&[ColumnTarget::From, ColumnTarget::To], &[ColumnTarget::From, ColumnTarget::To, ColumnTarget::Kind, ColumnTarget::ValidFrom, ColumnTarget::ValidTo])
+            }
+        };
+        if let Some(target) = required.iter().find(|target| count(target) == 0) {
+            return Err(format!("No column is mapped to {:?}", target));
+        }
+        if let Some(column) = self.columns.iter().find(|c| structural.contains(&c.target) && !allowed.contains(&c.target)) {
+            return Err(format!("Column '{}' is mapped to {:?}, which {:?} templates do not use", column.column, column.target, self.kind));
+        }
+        Ok(())
+    }
+
+    /// Map a row, given the file's column names, to a node or relationship.
+    /// The error describes why the row cannot be ingested.
+    pub fn map_row(&self, headers: &[String], row: &[&str], valid_time: &ValidTimePolicy) -> Result<MappedRecord, String> {
+        self.map_row_with_schema(headers, row, valid_time, None)
+    }
+
+    /// Map a row like [`map_row`](Self::map_row), typing the properties the
+    /// template does not coerce as `schema` declares them. The error lists
+    /// every cell that does not fit its type.
+    pub fn map_row_with_schema(&self, headers: &[String], row: &[&str], valid_time: &ValidTimePolicy, schema: Option<&IngestSchema>) -> Result<MappedRecord, String> {
+        let mut fields: HashMap<&str, String> = HashMap::new();
+        let mut cells = Vec::new();
+        let mut mapped = vec![false; headers.len()];
+
+        for mapping in &self.columns {
+            let index = column_index(headers, &mapping.column)?;
+            mapped[index] = true;
+            let cell = row.get(index).map(|cell| cell.trim()).unwrap_or_default();
+            let Some(value) = (if cell.is_empty() { mapping.default.clone() } else { Some(cell.to_string()) }) else {
+                continue;
+            };
+
+            match &mapping.target {
+                ColumnTarget::Property { name } => cells.push(PropertyCell {
+                    column: mapping.column.clone(),
+                    property: name.clone().unwrap_or_else(|| headers[index].clone()),
+                    value,
+                    coerce: mapping.coerce,
+                }),
+                ColumnTarget::Ignore => {}
+                target => {
+                    fields.insert(target_key(target), value);
+                }
+            }
+        }
+
+        if self.unmapped_as_properties {
+            for (index, header) in headers.iter().enumerate().filter(|(index, _)| !mapped[*index]) {
+                if let Some(cell) = row.get(index).map(|cell| cell.trim()).filter(|cell| !cell.is_empty()) {
+                    cells.push(PropertyCell { column: header.clone(), property: header.clone(), value: cell.to_string(), coerce: Coercion::Auto });
+                }
+            }
+        }
+
+        match self.kind {
+            TemplateKind::Node => {
+                let label = fields.remove("label").or_else(|| self.label.clone())
+                    .ok_or_else(|| "Row has no label".to_string())?;
+                let declared = schema.and_then(|schema| schema.properties(TemplateKind::Node, &label));
+                let props = self.type_cells(cells, declared, valid_time)?;
+                let mut node = Node::new(label);
+                if let Some(alias) = fields.remove("alias") {
+                    node.id_alias = Some(alias);
+                    node.alias_namespace = self.alias_namespace.clone();
+                }
+                node.props = Value::Object(props);
+                Ok(MappedRecord::Node(node))
+            }
+            TemplateKind::Relationship => {
+                let from = fields.remove("from").ok_or_else(|| "Row has no source alias".to_string())?;
+                let to = fields.remove("to").ok_or_else(|| "Row has no target alias".to_string())?;
+                let kind = fields.remove("kind").or_else(|| self.relationship_kind.clone())
+                    .ok_or_else(|| "Row has no relationship kind".to_string())?;
+                let declared = schema.and_then(|schema| schema.properties(TemplateKind::Relationship, &kind));
+                let mut props = self.type_cells(cells, declared, valid_time)?;
+
+                let valid_from = match fields.remove("valid_from") {
+                    Some(value) => self.parse_time(&value, valid_time, IntervalBound::Start)?,
+                    None => valid_time.default_valid_from(&row_source(headers, row)),
+                };
+                let valid_to = match fields.remove("valid_to") {
+                    Some(value) => Some(self.parse_time(&value, valid_time, IntervalBound::End)?),
+                    None => valid_time.default_valid_to(valid_from),
+                };
+
+                props.insert("_from_id_alias".to_string(), Value::String(from));
+                props.insert("_to_id_alias".to_string(), Value::String(to));
+                if let Some(namespace) = &self.alias_namespace {
+                    props.insert("_from_alias_namespace".to_string(), Value::String(namespace.clone()));
+                    props.insert("_to_alias_namespace".to_string(), Value::String(namespace.clone()));
+                }
+
+                let mut edge = TimeEdge::new(Uuid::nil(), Uuid::nil(), kind, valid_from, Value::Object(props));
+                edge.valid_to = valid_to;
+                Ok(MappedRecord::Edge(edge))
+            }
+        }
+    }
+
+    /// Type property cells by their column's coercion, else by the declared
+    /// type, else by inference
+    fn type_cells(&self, cells: Vec<PropertyCell>, declared: Option<&BTreeMap<String, Coercion>>, valid_time: &ValidTimePolicy) -> Result<Map<String, Value>, String> {
+        let date_format = self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT);
+        let mut props = Map::new();
+        let mut errors = Vec::new();
+        for cell in cells {
+            let coercion = match cell.coerce {
+                Coercion::Auto => declared.and_then(|declared| declared.get(&cell.property)).copied(),
+                pinned => Some(pinned),
+            };
+            match coercion.map(|coercion| coercion.apply(&cell.value, date_format, valid_time)) {
+                Some(Ok(value)) => {
+                    props.insert(cell.property, value);
+                }
+                Some(Err(e)) => errors.push(format!("Column '{}': {}", cell.column, e)),
+                None => {
+                    props.insert(cell.property, infer_value(&cell.value, self.strict));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(props)
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    fn parse_time(&self, value: &str, valid_time: &ValidTimePolicy, bound: IntervalBound) -> Result<DateTime<Utc>, String> {
+        parse_timestamp(value, self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT), valid_time, bound)
+    }
+}
+
+/// Parse an RFC 3339 timestamp, a timestamp in `format`, or a plain date or
+/// date-time. Values without an offset are in the policy's timezone; a year,
+/// month or day stands for the instant `bound` gives it.
+pub fn parse_timestamp(value: &str, format: &str, valid_time: &ValidTimePolicy, bound: IntervalBound) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    NaiveDateTime::parse_from_str(value, format)
+        .map(|time| valid_time.localize(time))
+        .or_else(|e| valid_time.parse_bound(value, bound).ok_or(e))
+        .map_err(|e| format!("Failed to parse datetime '{}' with format '{}': {}", value, format, e))
+}
+
+/// Index of a column given by name or, failing that, by zero-based index
+pub fn column_index(headers: &[String], column: &str) -> Result<usize, String> {
+    headers.iter()
+        .position(|header| header == column)
+        .or_else(|| column.parse::<usize>().ok().filter(|index| *index < headers.len()))
+        .ok_or_else(|| format!("Column not found: {}", column))
+}
+
+/// Resolve the alias references of edges whose node IDs are unset, in a
+/// single lookup. Edges whose aliases do not resolve keep nil node IDs.
+pub async fn resolve_edge_aliases(service: &dyn GraphService, tenant: &TenantId, edges: &mut [TimeEdge]) -> Result<(), GraphError> {
+    let aliases: Vec<AliasKey> = edges.iter()
+        .flat_map(|edge| [edge_alias_ref(edge, "from"), edge_alias_ref(edge, "to")])
+        .flatten()
+        .collect();
+
+    if !aliases.is_empty() {
+        let resolved = service.resolve_aliases(tenant, &aliases).await?;
+        for edge in edges.iter_mut() {
+            apply_resolved_aliases(edge, &resolved);
+        }
+    }
+    Ok(())
+}
+
+/// Build the alias reference for one end ("from" or "to") of an edge whose node ID is unset.
+///
+/// Ingestion clients that only know aliases send `_{end}_id_alias` and, optionally,
+/// `_{end}_alias_namespace` properties with a nil node ID.
+fn edge_alias_ref(edge: &TimeEdge, end: &str) -> Option<AliasKey> {
+    let node_id = if end == "from" { edge.from_node_id } else { edge.to_node_id };
+    if !node_id.is_nil() {
+        return None;
+    }
+
+    let alias = edge.props.get(format!("_{}_id_alias", end))?.as_str()?;
+    let namespace = edge.props.get(format!("_{}_alias_namespace", end))
+        .and_then(|v| v.as_str())
+        .map(|ns| ns.to_string());
+
+    Some(AliasKey {
+        namespace,
+        alias: alias.to_string(),
+    })
+}
+
+/// Replace nil node IDs with resolved alias targets and strip the alias reference properties
+fn apply_resolved_aliases(edge: &mut TimeEdge, resolved: &HashMap<AliasKey, Uuid>) {
+    if let Some(id) = edge_alias_ref(edge, "from").and_then(|key| resolved.get(&key)) {
+        edge.from_node_id = *id;
+    }
+    if let Some(id) = edge_alias_ref(edge, "to").and_then(|key| resolved.get(&key)) {
+        edge.to_node_id = *id;
+    }
+
+    if let Some(props) = edge.props.as_object_mut() {
+        for key in ["_from_id_alias", "_to_id_alias", "_from_alias_namespace", "_to_alias_namespace"] {
+            props.remove(key);
+        }
+    }
+}
+
+fn target_key(target: &ColumnTarget) -> &'static str {
+    match target {
+        ColumnTarget::Label => "label",
+        ColumnTarget::Alias => "alias",
+        ColumnTarget::From => "from",
+        ColumnTarget::To => "to",
+        ColumnTarget::Kind => "kind",
+        ColumnTarget::ValidFrom => "valid_from",
+        ColumnTarget::ValidTo => "valid_to",
+        ColumnTarget::Property { .. } | ColumnTarget::Ignore => "",
+    }
+}
+
+/// Type of a cell without a declared type: an integer, a number, a boolean
+/// (`true`/`false`, or `yes`/`no` unless `strict`) or else a string. In
+/// strict mode, numbers must read back as written, so `01234`, `+5` and
+/// `1.50` stay strings.
+pub fn infer_value(value: &str, strict: bool) -> Value {
+    if let Ok(integer) = value.parse::<i64>() {
+        if !strict || integer.to_string() == value {
+            return Value::from(integer);
+        }
+    }
+    if let Some(number) = value.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+        if !strict || number.to_string() == value {
+            return Value::Number(number);
+        }
+    }
+    match value.to_ascii_lowercase().as_str() {
+        "true" if !strict || value == "true" => Value::Bool(true),
+        "false" if !strict || value == "false" => Value::Bool(false),
+        "yes" if !strict => Value::Bool(true),
+        "no" if !strict => Value::Bool(false),
+        _ => Value::String(value.to_string()),
+    }
+}
+
+/// The row as the source document of its edge, for valid-time policies
+fn row_source(headers: &[String], row: &[&str]) -> SourceInfo {
+    let properties = headers.iter()
+        .zip(row)
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(header, value)| (header.clone(), Value::String(value.to_string())))
+        .collect();
+
+    SourceInfo { timestamp: None, properties }
+}
+
+/// In-memory per-tenant store of ingestion templates and schemas
+#[derive(Debug, Default)]
+pub struct IngestTemplateStore {
+    templates: RwLock<HashMap<TenantId, BTreeMap<String, IngestTemplate>>>,
+    schemas: RwLock<HashMap<TenantId, IngestSchema>>,
+}
+
+impl IngestTemplateStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A tenant's templates, by name
+    pub async fn list(&self, tenant: &TenantId) -> Vec<IngestTemplate> {
+        self.templates.read().await.get(tenant)
+            .map(|templates| templates.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// A tenant's template
+    pub async fn get(&self, tenant: &TenantId, name: &str) -> Option<IngestTemplate> {
+        self.templates.read().await.get(tenant).and_then(|templates| templates.get(name)).cloned()
+    }
+
+    /// Store a template, replacing any of the same name; returns whether one
+    /// was replaced. The error describes why the template is invalid.
+    pub async fn put(&self, tenant: &TenantId, template: IngestTemplate) -> Result<bool, String> {
+        template.validate()?;
+        let mut store = self.templates.write().await;
+        Ok(store.entry(tenant.clone()).or_default().insert(template.name.clone(), template).is_some())
+    }
+
+    /// Remove a template; returns `false` if it did not exist
+    pub async fn remove(&self, tenant: &TenantId, name: &str) -> bool {
+        let mut store = self.templates.write().await;
+        let Some(templates) = store.get_mut(tenant) else {
+            return false;
+        };
+
+        let removed = templates.remove(name).is_some();
+        if templates.is_empty() {
+            store.remove(tenant);
+        }
+        removed
+    }
+
+    /// A tenant's schema, if it has one
+    pub async fn schema(&self, tenant: &TenantId) -> Option<IngestSchema> {
+        self.schemas.read().await.get(tenant).cloned()
+    }
+
+    /// Set a tenant's schema, replacing any it had; returns whether one was
+    /// replaced. The error describes why the schema is invalid.
+    pub async fn put_schema(&self, tenant: &TenantId, schema: IngestSchema) -> Result<bool, String> {
+        schema.validate()?;
+        Ok(self.schemas.write().await.insert(tenant.clone(), schema).is_some())
+    }
+
+    /// Remove a tenant's schema; returns `false` if it had none
+    pub async fn remove_schema(&self, tenant: &TenantId) -> bool {
+        self.schemas.write().await.remove(tenant).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_map_node_rows() {
+        let template = IngestTemplate::new("people", TemplateKind::Node)
+            .with_column(ColumnMapping::new("id", ColumnTarget::Alias))
+            .with_column(ColumnMapping::new("type", ColumnTarget::Label).with_default("Person"))
+            .with_column(ColumnMapping::new("age", ColumnTarget::Property { name: None }).with_coercion(Coercion::Integer))
+            .with_column(ColumnMapping::new("zip", ColumnTarget::Property { name: Some("postal_code".to_string()) }).with_coercion(Coercion::String))
+            .with_column(ColumnMapping::new("active", ColumnTarget::Property { name: None }).with_coercion(Coercion::Boolean).with_default("yes"));
+        template.validate().unwrap();
+        let headers = headers(&["id", "type", "age", "zip", "active", "notes"]);
+        let policy = ValidTimePolicy::default();
+
+        let MappedRecord::Node(node) = template.map_row(&headers, &["alice", "", "30", "01234", "", "vip"], &policy).unwrap() else {
+            panic!("expected a node");
+        };
+        assert_eq!(node.label, "Person");
+        assert_eq!(node.id_alias.as_deref(), Some("alice"));
+        assert_eq!(node.props, serde_json::json!({"age": 30, "postal_code": "01234", "active": true}));
+
+        let error = template.map_row(&headers, &["bob", "Person", "old", "", "", ""], &policy).unwrap_err();
+        assert!(error.contains("'age'") && error.contains("integer"), "{}", error);
+
+        let template = IngestTemplate { unmapped_as_properties: true, ..template };
+        let MappedRecord::Node(node) = template.map_row(&headers, &["alice", "Person", "30", "", "no", "vip"], &policy).unwrap() else {
+            panic!("expected a node");
+        };
+        assert_eq!(node.props, serde_json::json!({"age": 30, "active": false, "notes": "vip"}));
+    }
+
+    #[test]
+    fn test_map_relationship_rows() {
+        let mut template = IngestTemplate::new("employment", TemplateKind::Relationship)
+            .with_column(ColumnMapping::new("person", ColumnTarget::From))
+            .with_column(ColumnMapping::new("company", ColumnTarget::To))
+            .with_column(ColumnMapping::new("start", ColumnTarget::ValidFrom))
+            .with_column(ColumnMapping::new("end", ColumnTarget::ValidTo));
+        assert!(template.validate().is_err());
+        template.relationship_kind = Some("WORKS_FOR".to_string());
+        template.alias_namespace = Some("hr".to_string());
+        template.date_format = Some("%d/%m/%Y %H:%M".to_string());
+        template.validate().unwrap();
+
+        let headers = headers(&["person", "company", "start", "end"]);
+        let MappedRecord::Edge(edge) = template.map_row(&headers, &["alice", "acme", "15/01/2024 09:00", ""], &ValidTimePolicy::default()).unwrap() else {
+            panic!("expected an edge");
+        };
+        assert_eq!(edge.kind, "WORKS_FOR");
+        assert!(edge.from_node_id.is_nil());
+        assert_eq!(edge.valid_from.to_rfc3339(), "2024-01-15T09:00:00+00:00");
+        assert_eq!(edge.valid_to, None);
+        assert_eq!(edge.props["_from_id_alias"], "alice");
+        assert_eq!(edge.props["_to_alias_namespace"], "hr");
+
+        assert!(template.map_row(&headers, &["", "acme", "", ""], &ValidTimePolicy::default()).is_err());
+    }
+
+    #[test]
+    fn test_schema_types() {
+        let template = IngestTemplate { unmapped_as_properties: true, ..IngestTemplate::new("people", TemplateKind::Node) }
+            .with_column(ColumnMapping::new("type", ColumnTarget::Label))
+            .with_column(ColumnMapping::new("code", ColumnTarget::Property { name: None }).with_coercion(Coercion::String));
+        let headers = headers(&["type", "zip", "age", "code", "vip"]);
+        let policy = ValidTimePolicy::default();
+        let schema: IngestSchema = serde_json::from_value(json!({
+            "labels": {"Person": {"zip": "string", "age": "integer", "code": "integer"}}
+        })).unwrap();
+        schema.validate().unwrap();
+
+        // Declared types win over inference, and pinned columns over the schema
+        let row = ["Person", "01234", "42", "007", "0"];
+        let MappedRecord::Node(node) = template.map_row_with_schema(&headers, &row, &policy, Some(&schema)).unwrap() else {
+            panic!("expected a node");
+        };
+        assert_eq!(node.props, json!({"zip": "01234", "age": 42, "code": "007", "vip": 0}));
+        let MappedRecord::Node(node) = template.map_row(&headers, &row, &policy).unwrap() else {
+            panic!("expected a node");
+        };
+        assert_eq!(node.props["zip"], 1234);
+
+        // Every cell that does not fit is reported
+        let error = template.map_row_with_schema(&headers, &["Person", "1", "forty", "", ""], &policy, Some(&schema)).unwrap_err();
+        assert!(error.contains("'age'") && error.contains("integer"), "{}", error);
+        // Labels the schema does not declare are inferred
+        assert!(template.map_row_with_schema(&headers, &["Company", "1", "forty", "", ""], &policy, Some(&schema)).is_ok());
+
+        let auto: IngestSchema = serde_json::from_value(json!({"kinds": {"KNOWS": {"since": "auto"}}})).unwrap();
+        assert!(auto.validate().is_err());
+    }
+
+    #[test]
+    fn test_infer_value() {
+        assert_eq!(infer_value("42", false), json!(42));
+        assert_eq!(infer_value("01234", false), json!(1234));
+        assert_eq!(infer_value("yes", false), json!(true));
+        assert_eq!(infer_value("0", false), json!(0));
+
+        // Strict inference keeps anything that would not read back the same
+        assert_eq!(infer_value("42", true), json!(42));
+        assert_eq!(infer_value("-1.5", true), json!(-1.5));
+        assert_eq!(infer_value("true", true), json!(true));
+        for value in ["01234", "+5", "1.50", "1e3", "yes", "TRUE"] {
+            assert_eq!(infer_value(value, true), json!(value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let node = || IngestTemplate::new("nodes", TemplateKind::Node);
+        assert!(node().validate().is_err());
+        assert!(IngestTemplate { label: Some("Thing".to_string()), ..node() }.validate().is_ok());
+        let with_from = node().with_column(ColumnMapping::new("a", ColumnTarget::Label)).with_column(ColumnMapping::new("b", ColumnTarget::From));
+        assert!(with_from.validate().is_err());
+        let two_aliases = node().with_column(ColumnMapping::new("a", ColumnTarget::Label))
+            .with_column(ColumnMapping::new("b", ColumnTarget::Alias))
+            .with_column(ColumnMapping::new("c", ColumnTarget::Alias));
+        assert!(two_aliases.validate().is_err());
+
+        let parsed: ColumnMapping = serde_json::from_str(r#"{"column": "zip", "target": "property", "name": "postal_code", "coerce": "string"}"#).unwrap();
+        assert_eq!(parsed, ColumnMapping::new("zip", ColumnTarget::Property { name: Some("postal_code".to_string()) }).with_coercion(Coercion::String));
+    }
+
+    #[tokio::test]
+    async fn test_store() {
+        let store = IngestTemplateStore::new();
+        let tenant = TenantId::new("acme");
+        let template = IngestTemplate { label: Some("Thing".to_string()), ..IngestTemplate::new("things", TemplateKind::Node) };
+
+        assert!(!store.put(&tenant, template.clone()).await.unwrap());
+        assert!(store.put(&tenant, template.clone()).await.unwrap());
+        assert!(store.put(&tenant, IngestTemplate::new("broken", TemplateKind::Node)).await.is_err());
+        assert_eq!(store.list(&tenant).await, vec![template.clone()]);
+        assert_eq!(store.get(&tenant, "things").await, Some(template));
+        assert!(store.get(&TenantId::new("other"), "things").await.is_none());
+        assert!(store.remove(&tenant, "things").await);
+        assert!(!store.remove(&tenant, "things").await);
+
+        let schema = IngestSchema { labels: BTreeMap::from([("Thing".to_string(), BTreeMap::from([("size".to_string(), Coercion::Integer)]))]), ..IngestSchema::default() };
+        assert!(!store.put_schema(&tenant, schema.clone()).await.unwrap());
+        assert_eq!(store.schema(&tenant).await, Some(schema));
+        assert!(store.remove_schema(&tenant).await);
+        assert!(store.schema(&tenant).await.is_none());
+    }
+
+    #[test]
+    fn test_apply_resolved_aliases() {
+        let mut edge = TimeEdge::new(
+            Uuid::nil(),
+            Uuid::nil(),
+            "OPENED",
+            Utc::now(),
+            json!({
+                "_from_id_alias": "123",
+                "_from_alias_namespace": "crm",
+                "_to_id_alias": "123",
+                "_to_alias_namespace": "tickets",
+                "channel": "email"
+            }),
+        );
+
+        let customer_id = Uuid::new_v4();
+        let ticket_id = Uuid::new_v4();
+        let mut resolved = HashMap::new();
+        resolved.insert(AliasKey::namespaced("crm", "123"), customer_id);
+        resolved.insert(AliasKey::namespaced("tickets", "123"), ticket_id);
+
+        assert_eq!(edge_alias_ref(&edge, "from"), Some(AliasKey::namespaced("crm", "123")));
+
+        apply_resolved_aliases(&mut edge, &resolved);
+
+        assert_eq!(edge.from_node_id, customer_id);
+        assert_eq!(edge.to_node_id, ticket_id);
+        assert_eq!(edge.props, json!({"channel": "email"}));
+        assert_eq!(edge_alias_ref(&edge, "from"), None);
+    }
+}
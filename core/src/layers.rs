@@ -0,0 +1,559 @@
+//! Composition of `GraphStore` middleware
+//!
+//! Cross-cutting features such as caching and write batching are decorators
+//! around a `GraphStore`. A [`GraphStoreLayer`] knows how to wrap a store in
+//! one such decorator, and [`LayeredGraphStore`] stacks layers on top of a
+//! backend, either in code:
+//!
+//! ```ignore
+//! let store = LayeredGraphStore::new(backend)
+//!     .with(BatchingLayer::new(BatchingConfig::default()))
+//!     .with(CacheLayer::new(QueryCacheConfig::default(), events));
+//! ```
+//!
+//! or from configuration with [`LayeredGraphStore::from_config`]. Each layer
+//! wraps the stack built so far, so the last layer added sees calls first.
+
+use crate::batching::{BatchingConfig, BatchingGraphStore};
+use crate::archive::ArchiveBatch;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{EdgeStream, NodeStream};
+use crate::errors::GraphError;
+use crate::materialized::SnapshotInfo;
+use crate::events::MutationEventBus;
+use crate::query_cache::{QueryCacheConfig, QueryCachingGraphStore};
+use crate::query_scheduler::{QueryScheduler, ScheduledGraphStore};
+use crate::sync::{SyncEngine, SyncGraphStore};
+use crate::telemetry::{Telemetry, TelemetryGraphStore};
+use crate::traits::GraphStore;
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeWithEdges, Path, TenantId, TimeEdge};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Wraps a `GraphStore` in a decorator
+pub trait GraphStoreLayer: Send + Sync {
+    /// Name of the layer, for logging and introspection
+    fn name(&self) -> &'static str;
+
+    /// Wrap `inner`, returning the decorated store
+    fn layer(&self, inner: Arc<dyn GraphStore>) -> Arc<dyn GraphStore>;
+}
+
+/// Layer that caches query results, see [`QueryCachingGraphStore`]
+#[derive(Debug, Clone)]
+pub struct CacheLayer {
+    config: QueryCacheConfig,
+    events: MutationEventBus,
+}
+
+impl CacheLayer {
+    /// Cache with the given settings, invalidated by events on `events`
+    pub fn new(config: QueryCacheConfig, events: MutationEventBus) -> Self {
+        Self { config, events }
+    }
+}
+
+impl GraphStoreLayer for CacheLayer {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+
+    fn layer(&self, inner: Arc<dyn GraphStore>) -> Arc<dyn GraphStore> {
+        Arc::new(QueryCachingGraphStore::new(inner, self.config.clone(), self.events.clone()))
+    }
+}
+
+/// Layer that batches and coalesces writes, see [`BatchingGraphStore`]
+#[derive(Debug, Clone)]
+pub struct BatchingLayer {
+    config: BatchingConfig,
+}
+
+impl BatchingLayer {
+    /// Batch writes with the given settings
+    pub fn new(config: BatchingConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl GraphStoreLayer for BatchingLayer {
+    fn name(&self) -> &'static str {
+        "batching"
+    }
+
+    fn layer(&self, inner: Arc<dyn GraphStore>) -> Arc<dyn GraphStore> {
+        Arc::new(BatchingGraphStore::new(inner, self.config.clone()))
+    }
+}
+
+/// Layer that logs writes for sync with other instances, see [`SyncGraphStore`]
+#[derive(Clone)]
+pub struct SyncLayer {
+    engine: Arc<SyncEngine>,
+}
+
+impl SyncLayer {
+    /// Log writes in `engine`
+    pub fn new(engine: Arc<SyncEngine>) -> Self {
+        Self { engine }
+    }
+}
+
+impl GraphStoreLayer for SyncLayer {
+    fn name(&self) -> &'static str {
+        "sync"
+    }
+
+    fn layer(&self, inner: Arc<dyn GraphStore>) -> Arc<dyn GraphStore> {
+        Arc::new(SyncGraphStore::new(inner, self.engine.clone()))
+    }
+}
+
+/// Layer that records usage for telemetry, see [`TelemetryGraphStore`]
+#[derive(Clone)]
+pub struct TelemetryLayer {
+    telemetry: Arc<Telemetry>,
+}
+
+impl TelemetryLayer {
+    /// Record usage in `telemetry`
+    pub fn new(telemetry: Arc<Telemetry>) -> Self {
+        Self { telemetry }
+    }
+}
+
+impl GraphStoreLayer for TelemetryLayer {
+    fn name(&self) -> &'static str {
+        "telemetry"
+    }
+
+    fn layer(&self, inner: Arc<dyn GraphStore>) -> Arc<dyn GraphStore> {
+        Arc::new(TelemetryGraphStore::new(inner, self.telemetry.clone()))
+    }
+}
+
+/// Layer that schedules reads fairly across tenants, see [`ScheduledGraphStore`]
+#[derive(Clone)]
+pub struct SchedulerLayer {
+    scheduler: Arc<QueryScheduler>,
+}
+
+impl SchedulerLayer {
+    /// Admit reads through `scheduler`
+    pub fn new(scheduler: Arc<QueryScheduler>) -> Self {
+        Self { scheduler }
+    }
+}
+
+impl GraphStoreLayer for SchedulerLayer {
+    fn name(&self) -> &'static str {
+        "scheduler"
+    }
+
+    fn layer(&self, inner: Arc<dyn GraphStore>) -> Arc<dyn GraphStore> {
+        Arc::new(ScheduledGraphStore::new(inner, self.scheduler.clone()))
+    }
+}
+
+/// Declarative description of a layer, e.g. from a deployment's config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LayerConfig {
+    /// Query result cache
+    Cache(QueryCacheConfig),
+    /// Write-behind batching
+    Batching(BatchingConfig),
+}
+
+impl LayerConfig {
+    /// Build the layer; layers that react to writes listen on `events`
+    pub fn build(&self, events: &MutationEventBus) -> Box<dyn GraphStoreLayer> {
+        match self {
+            LayerConfig::Cache(config) => Box::new(CacheLayer::new(config.clone(), events.clone())),
+            LayerConfig::Batching(config) => Box::new(BatchingLayer::new(config.clone())),
+        }
+    }
+}
+
+/// A `GraphStore` backend with a stack of layers on top of it.
+///
+/// Layers are applied as they are added, so they must be added inside a
+/// Tokio runtime if any of them spawns background tasks.
+pub struct LayeredGraphStore {
+    store: Arc<dyn GraphStore>,
+    layers: Vec<&'static str>,
+}
+
+impl LayeredGraphStore {
+    /// Start a stack from a backend store
+    pub fn new(store: Arc<dyn GraphStore>) -> Self {
+        Self {
+            store,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Build a stack from layer configurations, innermost first
+    pub fn from_config(store: Arc<dyn GraphStore>, layers: &[LayerConfig], events: &MutationEventBus) -> Self {
+        layers.iter().fold(Self::new(store), |stack, config| stack.with_boxed(config.build(events)))
+    }
+
+    /// Wrap the stack in another layer
+    pub fn with<L: GraphStoreLayer>(self, layer: L) -> Self {
+        self.apply(&layer)
+    }
+
+    /// Wrap the stack in a layer chosen at runtime
+    pub fn with_boxed(self, layer: Box<dyn GraphStoreLayer>) -> Self {
+        self.apply(layer.as_ref())
+    }
+
+    fn apply(mut self, layer: &dyn GraphStoreLayer) -> Self {
+        debug!("Adding GraphStore layer '{}'", layer.name());
+        self.store = layer.layer(self.store);
+        self.layers.push(layer.name());
+        self
+    }
+
+    /// Names of the applied layers, innermost first
+    pub fn layers(&self) -> &[&'static str] {
+        &self.layers
+    }
+
+    /// The outermost store of the stack
+    pub fn into_inner(self) -> Arc<dyn GraphStore> {
+        self.store
+    }
+}
+
+#[async_trait]
+impl GraphStore for LayeredGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        self.store.upsert_node(tenant, node).await
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.store.upsert_edge(tenant, edge).await
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        self.store.upsert_node_with_edges(tenant, node, edges).await
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        self.store.bulk_load_nodes(tenant, nodes).await
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        self.store.bulk_load_edges(tenant, edges).await
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.store.query(tenant, query).await
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.store.query_count(tenant, query).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.store.query_exists(tenant, query).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.store.traverse(tenant, request).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.store.shortest_path(tenant, request).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.store.timeline(tenant, request).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.store.property_history(tenant, node_id, key).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        self.store.update_node(tenant, id, node).await
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.store.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.store.set_edge_constraints(tenant, constraints).await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.store.get_node(tenant, id).await
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        self.store.get_node_by_alias(tenant, id_alias).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.store.resolve_aliases(tenant, aliases).await
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.store.delete_node(tenant, id).await
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.store.delete_edge(tenant, id).await
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        self.store.close_edge(tenant, id, valid_to).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.store.supersede_edge(tenant, id, edge).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.store.retract_edge(tenant, id).await
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        self.store.get_node_history(tenant, id).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        self.store.snapshot(tenant, valid_at).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.store.materialize_snapshot(tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.store.list_snapshots(tenant).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.store.drop_snapshot(tenant, name).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.store.query_snapshot(tenant, name, query).await
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        self.store.read_history(tenant, before).await
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.store.purge_history(tenant, before).await
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        self.store.restore_history(tenant, batch).await
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.store.purge_expired_edges(tenant, now).await
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        self.store.summary(tenant).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.store.catalog(tenant).await
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        self.store.list_tenants().await
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        self.store.clear_tenant(tenant).await
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.store.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Store that records the operations it receives
+    #[derive(Default)]
+    struct RecordingStore {
+        calls: Mutex<Vec<&'static str>>,
+    }
+
+    impl RecordingStore {
+        fn record(&self, call: &'static str) {
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    #[async_trait]
+    impl GraphStore for RecordingStore {
+        async fn upsert_node(&self, _tenant: &TenantId, _node: Node) -> Result<Uuid, GraphError> {
+            self.record("upsert_node");
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_edge(&self, _tenant: &TenantId, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            self.record("upsert_edge");
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_node_with_edges(&self, _tenant: &TenantId, _node: Node, _edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+            self.record("upsert_node_with_edges");
+            Ok(NodeWithEdges { node_id: Uuid::new_v4(), edge_ids: vec![] })
+        }
+
+        async fn query(&self, _tenant: &TenantId, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            self.record("query");
+            Ok(vec![])
+        }
+
+        async fn get_node(&self, _tenant: &TenantId, _id: Uuid) -> Result<Option<Node>, GraphError> {
+            Ok(None)
+        }
+
+        async fn get_node_by_alias(&self, _tenant: &TenantId, _id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+            Ok(None)
+        }
+
+        async fn resolve_aliases(&self, _tenant: &TenantId, _aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+            Ok(HashMap::new())
+        }
+
+        async fn delete_node(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn delete_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn close_edge(&self, _tenant: &TenantId, _id: Uuid, _valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn supersede_edge(&self, _tenant: &TenantId, _id: Uuid, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn retract_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn get_node_history(&self, _tenant: &TenantId, _id: Uuid) -> Result<Vec<Node>, GraphError> {
+            Ok(vec![])
+        }
+
+        async fn snapshot(&self, _tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+            Ok(GraphSnapshot { snapshot_at: Utc::now(), valid_at, nodes: vec![], edges: vec![] })
+        }
+
+        async fn materialize_snapshot(&self, _tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+            Ok(SnapshotInfo {
+                name: name.to_string(),
+                valid_at,
+                snapshot_at: Utc::now(),
+                node_count: 0,
+                edge_count: 0,
+            })
+        }
+
+        async fn list_snapshots(&self, _tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn drop_snapshot(&self, _tenant: &TenantId, _name: &str) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn query_snapshot(&self, _tenant: &TenantId, _name: &str, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn read_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+            Ok(ArchiveBatch::default())
+        }
+
+        async fn purge_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn restore_history(&self, _tenant: &TenantId, _batch: ArchiveBatch) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn purge_expired_edges(&self, _tenant: &TenantId, _now: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn summary(&self, _tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+            Ok(GraphSummary::default())
+        }
+
+        async fn catalog(&self, _tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+            Ok(GraphCatalog::default())
+        }
+
+        async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+            self.record("list_tenants");
+            Ok(vec![TenantId::new("tenant")])
+        }
+
+        async fn clear_tenant(&self, _tenant: &TenantId) -> Result<u64, GraphError> {
+            self.record("clear_tenant");
+            Ok(1)
+        }
+
+        async fn health_check(&self) -> Result<(), GraphError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layers_from_config() {
+        let config: Vec<LayerConfig> = serde_json::from_value(serde_json::json!([
+            {"type": "batching", "max_batch_size": 10, "flush_interval_ms": 5, "max_pending": 100, "coalesce": true},
+            {"type": "cache", "ttl_ms": 60000},
+        ])).unwrap();
+
+        let backend = Arc::new(RecordingStore::default());
+        let store = LayeredGraphStore::from_config(backend.clone(), &config, &MutationEventBus::default());
+        assert_eq!(store.layers(), ["batching", "cache"]);
+
+        let tenant = TenantId::new("tenant");
+        let query = GraphQuery::FindNodes { labels: vec!["Person".to_string()], properties: HashMap::new(), order_by: vec![], offset: None, limit: None };
+        store.query(&tenant, query.clone()).await.unwrap();
+        store.query(&tenant, query.clone()).await.unwrap();
+
+        // The write goes through the batching layer and invalidates the cache
+        store.upsert_node(&tenant, Node::new("Person")).await.unwrap();
+        store.query(&tenant, query).await.unwrap();
+
+        assert_eq!(*backend.calls.lock().unwrap(), ["query", "upsert_node", "query"]);
+
+        // Tenant operations reach the backend through every layer
+        assert_eq!(store.list_tenants().await.unwrap(), vec![tenant.clone()]);
+        assert_eq!(store.clear_tenant(&tenant).await.unwrap(), 1);
+        assert_eq!(backend.calls.lock().unwrap()[3..], ["list_tenants", "clear_tenant"]);
+    }
+}
@@ -8,10 +8,75 @@ pub mod types;
 pub mod traits;
 pub mod errors;
 pub mod temporal;
+pub mod temporal_validation;
 pub mod tenant;
+pub mod properties;
+pub mod secure_export;
+pub mod batching;
+pub mod bulk;
+pub mod events;
+pub mod query_cache;
+pub mod layers;
+pub mod safety;
+pub mod tokens;
+pub mod extraction;
+pub mod examples;
+pub mod model_selection;
+pub mod availability;
+pub mod connector_registry;
+pub mod valid_time;
+pub mod materialized;
+pub mod hnsw;
+pub mod vector_index;
+pub mod archive;
+pub mod analytics;
+pub mod capture;
+pub mod paging;
+pub mod sandbox;
+pub mod service;
+pub mod auth;
+pub mod signing;
+pub mod http;
+pub mod exchange_log;
+pub mod sessions;
+pub mod ingest_template;
+pub mod dead_letter;
+pub mod write_concern;
+pub mod mutation_applier;
+pub mod sync;
+pub mod rdf;
+pub mod migrations;
+pub mod doctor;
+pub mod ids;
+pub mod warnings;
+pub mod query;
+pub mod traversal;
+pub mod constraints;
+pub mod telemetry;
+pub mod admin;
+pub mod llm_queue;
+pub mod graph_context;
+pub mod envelope;
+pub mod lineage;
+pub mod timeline;
+pub mod operations;
+pub mod fixtures;
+pub mod federation;
+pub mod hooks;
+pub mod expiry;
+pub mod batch_query;
+pub mod access_log;
+pub mod query_scheduler;
+pub mod collapse;
+pub mod schema_docs;
+pub mod property_history;
+pub mod embeddings;
+pub mod backfill;
+pub mod progress;
+pub mod views;
 
 // Re-export commonly used types and traits
-pub use types::{Node, TimeEdge, TenantId};
+pub use types::{AliasKey, Node, TimeEdge, TenantId};
 pub use traits::{GraphStore, LlmConnector, PresentationAdapter, SourceAdapter, PipelinePlugin, RequestContext, PluginOutcome, PluginConfig};
 pub use errors::{CoreError, GraphError, LlmError};
 
@@ -20,9 +85,71 @@ pub mod pipeline;
 /// Prelude module for convenient imports
 pub mod prelude {
     pub use crate::types::*;
+    pub use crate::secure_export::{ExportKey, ExportKeys, ExportManifest};
     pub use crate::traits::*;
     pub use crate::errors::*;
     pub use crate::pipeline::*;
+    pub use crate::properties::*;
+    pub use crate::temporal::{IntervalBound, IsoDuration, PartialDate, TemporalUtils, TemporalValue, TimePrecision};
+    pub use crate::temporal_validation::*;
+    pub use crate::safety::*;
+    pub use crate::tokens::*;
+    pub use crate::extraction::*;
+    pub use crate::examples::*;
+    pub use crate::model_selection::*;
+    pub use crate::availability::{AvailabilityConfig, CapabilityStatus, GuardedConnector};
+    pub use crate::connector_registry::{ConnectorRegistry, ProviderPolicies, ProviderPolicy, Selection};
+    pub use crate::valid_time::*;
+    pub use crate::materialized::{MaterializedSnapshot, SnapshotInfo, SnapshotRegistry};
+    pub use crate::hnsw::{HnswConfig, HnswGraph, Metric};
+    pub use crate::vector_index::{DiskVectorIndex, DiskVectorIndexConfig};
+    pub use crate::capture::{CaptureConfig, CapturedRequest, RequestCapture};
+    pub use crate::paging::PageRequest;
+    pub use crate::analytics::{AnalyticsConfig, AnalyticsJob, AnalyticsResult, AnalyticsSync};
+    pub use crate::archive::{ArchiveBatch, ArchiveJob, ArchiveManifest, ArchiveSegment, ArchivedNode, RestoreReport};
+    pub use crate::sandbox::*;
+    pub use crate::service::CoreGraphService;
+    pub use crate::auth::{ApiToken, ApiTokenManager, InMemorySecretStore, IssuedToken, TokenScope};
+    pub use crate::signing::{RequestSigningPlugin, SigningConfig, TenantSigning};
+    pub use crate::http::{HttpClientConfig, PoolConfig, ProxyConfig};
+    pub use crate::exchange_log::{ExchangeLog, ExchangeLogConfig, LlmExchange, TenantExchangeLogging};
+    pub use crate::ingest_template::{ColumnMapping, ColumnTarget, Coercion, IngestSchema, IngestTemplate, IngestTemplateStore, MappedRecord, TemplateKind};
+    pub use crate::sessions::{EphemeralSession, PromoteRequest, PromotionReport, SessionGraphConfig, SessionGraphs};
+    pub use crate::dead_letter::{DeadLetter, DeadLetterConfig, DeadLetterQueue, DeadLetterRecord, RetryReport};
+    pub use crate::write_concern::{current_write_concern, with_write_concern, WriteConcern};
+    pub use crate::mutation_applier::{MutationAck, MutationApplier, MutationApplierConfig, MutationResult, MutationSink, MutationStatus};
+    pub use crate::sync::{ApplyReport, ChangeBatch, ConflictResolver, LastWriterWins, RecordVersion, SyncChange, SyncConfig, SyncEngine, SyncFailure, SyncGraphStore, SyncOperation, SyncStatus};
+    pub use crate::rdf::{RdfMapping, RdfMappings, RdfSyntax};
+    pub use crate::migrations::{MigrationInfo, SchemaCheck, SchemaStatus};
+    pub use crate::doctor::{CheckOutcome, CheckResult, CheckStatus, Doctor, DoctorReport};
+    pub use crate::ids::{IdGenerator, IdStrategy};
+    pub use crate::warnings::collect_warnings;
+    pub use crate::query::{NodeQuery, Query, RawQuery, RelationshipQuery};
+    pub use crate::admin::{AdminControls, AdminStatus, DrainReport, DrainState, InFlight, JobProgress, JobReport, RetentionJob};
+    pub use crate::telemetry::{ExporterConfig, Telemetry, TelemetryConfig, TelemetryConnector, TelemetryGraphStore, TelemetryReporter, UsageReport};
+    pub use crate::llm_queue::{current_priority, with_priority, LaneStats, LlmQueueConfig, LlmRequestQueue, ProviderLimits, QueuePermit, QueuedConnector, RequestPriority};
+    pub use crate::graph_context::{graph_context_section, render_graph_context, GraphContextBuilder, GraphContextPolicies, GraphContextPolicy, SalientEntity};
+    pub use crate::envelope::{ApplyEnvelopeRequest, ContradictionPolicy, EnvelopeApplier, EnvelopePolicies, EnvelopePolicy, EnvelopeReport, SkippedItem};
+    pub use crate::lineage::{ExtractionLineage, ExtractionRun, PurgeReport, RunFacts, RunFilter, LINEAGE_TENANT};
+    pub use crate::constraints::{EdgeClosure, EdgeConstraints, ExclusivityPolicy};
+    pub use crate::traversal::{ShortestPathRequest, TraversalDirection, TraversalOrder, TraversalRequest, WeightedPath};
+    pub use crate::timeline::{EdgeChange, Timeline, TimelineBucket, TimelineEvent, TimelineInterval, TimelineRequest};
+    pub use crate::property_history::{NodeVersion, PropertyHistory, PropertyValue};
+    pub use crate::embeddings::{EmbeddingBackfill, EmbeddingConfig, EmbeddingOrchestrator, EmbeddingReport, EmbeddingStats};
+    pub use crate::backfill::{BackfillCheckpoint, BackfillConfig, BackfillJob, BackfillTarget, BackfillTransform, DeriveProperties, Reembed, Relabel, Transformed};
+    pub use crate::progress::{report_progress, with_progress, ProgressConfig, ProgressEvent, ProgressHub, ProgressSubscription, ProgressTracker, ProgressUpdate};
+    pub use crate::bulk::{until_error, EdgeStream, FirstError, NodeStream};
+    pub use crate::views::{current_view, in_current_view, with_view, GraphView, GraphViews, ViewGraphStore};
+    pub use crate::operations::{Operation, OperationInfo, OperationKind, OperationRegistry};
+    pub use crate::fixtures::{Fixture, FixtureEdge, FixtureLoader, FixtureNode, SeedReport, TenantFixture, TenantSeedReport};
+    pub use crate::federation::{BackendStatus, FederatedGraphStore, FederationConfig};
+    pub use crate::hooks::{HookContext, HookErrorPolicy, HookLayer, HookedGraphStore, Hooks, OnEdgeUpsert, OnNodeUpsert, OnQueryResult};
+    pub use crate::expiry::{EdgeExpiryConfig, EdgeExpirySweeper, ExpiredEdgesJob};
+    pub use crate::batch_query::{run_batch, BatchQuery, BatchQueryRequest, BatchQueryResponse, BatchQueryResult};
+    pub use crate::access_log::{AccessLog, AccessLogConfig, AccessLogEntry, Attribution};
+    pub use crate::query_scheduler::{QueryPermit, QueryScheduler, QuerySchedulerConfig, ScheduledGraphStore, TenantQueryLimits, TenantQueryStats};
+    pub use crate::collapse::{collapse_edges, CollapsedEdge};
+    pub use crate::schema_docs::{generate_schema_doc, DEFAULT_SCHEMA_DOC_SAMPLE, Cardinality, KindDoc, LabelDoc, PropertyDoc, SchemaDoc, SchemaDocFormat, TemporalUsage};
     pub use async_trait::async_trait;
     pub use uuid::Uuid;
     pub use chrono::{DateTime, Utc};
@@ -0,0 +1,290 @@
+//! Lineage of extracted facts, kept as a graph
+//!
+//! Each applied extraction envelope is an extraction run. Its record lives in
+//! the [`LINEAGE_TENANT`] system tenant, next to the tenant's own graph:
+//!
+//! - an `ExtractionRun` node with the run's origin, provider, model, prompt
+//!   version, token counts, cost and warnings;
+//! - `USED_MODEL` and `USED_PROMPT` edges to `Model` and `PromptVersion`
+//!   nodes, shared by all runs using them;
+//! - a `PRODUCED` edge to a `Fact` node for every node and edge the run
+//!   wrote, recording whether it created or only updated it, and a
+//!   `SUPERSEDED` edge for every edge it closed.
+//!
+//! Run and fact nodes are aliased by ID in the tenant's namespace, so
+//! [`ExtractionLineage::producers`] answers "which model and prompt produced
+//! this fact" with two lookups, and [`ExtractionLineage::purge`] removes
+//! everything a bad run created in one operation.
+
+use crate::errors::GraphError;
+use crate::traits::GraphService;
+use crate::types::{AliasKey, GraphQuery, Node, TenantId, TimeEdge};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Tenant holding the lineage of all tenants' extraction runs
+pub const LINEAGE_TENANT: &str = "_lineage";
+
+const RUN_LABEL: &str = "ExtractionRun";
+const FACT_LABEL: &str = "Fact";
+const PRODUCED: &str = "PRODUCED";
+const SUPERSEDED: &str = "SUPERSEDED";
+
+/// An applied extraction envelope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractionRun {
+    pub id: Uuid,
+    /// Tenant the envelope was applied to
+    pub tenant: String,
+    /// Where the envelope was extracted from, e.g. `slack:#sales`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Version of the prompt template the extraction used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    pub applied_at: DateTime<Utc>,
+    /// Number of nodes and edges the run wrote
+    pub nodes: usize,
+    pub edges: usize,
+    /// When the run's facts were purged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purged_at: Option<DateTime<Utc>>,
+}
+
+/// Nodes and edges a run wrote to its tenant
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunFacts {
+    pub created_nodes: Vec<Uuid>,
+    /// Existing nodes the run merged properties into
+    pub updated_nodes: Vec<Uuid>,
+    pub edges: Vec<Uuid>,
+    /// Existing edges the run closed
+    pub superseded: Vec<Uuid>,
+}
+
+/// Filter of [`ExtractionLineage::runs`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunFilter {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub prompt_version: Option<String>,
+}
+
+/// What purging a run removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeReport {
+    pub run_id: Uuid,
+    pub deleted_nodes: Vec<Uuid>,
+    pub deleted_edges: Vec<Uuid>,
+    /// Nodes the run only updated, which existed before it and are kept
+    pub kept_nodes: Vec<Uuid>,
+}
+
+/// Records extraction runs and answers lineage queries
+pub struct ExtractionLineage {
+    service: Arc<dyn GraphService>,
+}
+
+impl ExtractionLineage {
+    pub fn new(service: Arc<dyn GraphService>) -> Self {
+        Self { service }
+    }
+
+    fn lineage_tenant() -> TenantId {
+        TenantId::new(LINEAGE_TENANT)
+    }
+
+    /// Record a run and the facts it wrote
+    pub async fn record(&self, run: &ExtractionRun, facts: &RunFacts) -> Result<(), GraphError> {
+        let lineage = Self::lineage_tenant();
+        let run_id = self.upsert_run(run).await?;
+
+        let shared = [
+            ("Model", "USED_MODEL", run.provider.as_ref().zip(run.model.as_ref()).map(|(provider, model)| format!("{}/{}", provider, model))),
+            ("PromptVersion", "USED_PROMPT", run.prompt_version.clone()),
+        ];
+        for (label, kind, name) in shared {
+            let Some(name) = name else {
+                continue;
+            };
+            let node = Node::new(label).with_id_alias(&name).with_props(json!({ "name": name }));
+            let id = self.service.upsert_node(&lineage, node).await?;
+            self.service.upsert_edge(&lineage, TimeEdge::new(run_id, id, kind, run.applied_at, json!({}))).await?;
+        }
+
+        let produced = facts.created_nodes.iter().map(|id| (*id, "node", PRODUCED, true))
+            .chain(facts.updated_nodes.iter().map(|id| (*id, "node", PRODUCED, false)))
+            .chain(facts.edges.iter().map(|id| (*id, "edge", PRODUCED, true)))
+            .chain(facts.superseded.iter().map(|id| (*id, "edge", SUPERSEDED, false)));
+        for (fact_id, fact_kind, kind, created) in produced {
+            let mut fact = Node::new(FACT_LABEL)
+                .with_id_alias(fact_id.to_string())
+                .with_props(json!({ "tenant": run.tenant, "fact_id": fact_id, "kind": fact_kind }));
+            fact.alias_namespace = Some(run.tenant.clone());
+            let id = self.service.upsert_node(&lineage, fact).await?;
+            let props = json!({ "fact_id": fact_id, "kind": fact_kind, "created": created });
+            self.service.upsert_edge(&lineage, TimeEdge::new(run_id, id, kind, run.applied_at, props)).await?;
+        }
+
+        info!("Recorded extraction run {} of tenant {}: {} nodes, {} edges", run.id, run.tenant, run.nodes, run.edges);
+        Ok(())
+    }
+
+    async fn upsert_run(&self, run: &ExtractionRun) -> Result<Uuid, GraphError> {
+        let props = serde_json::to_value(run)
+            .map_err(|e| GraphError::QueryFailed(format!("Could not serialize extraction run {}: {}", run.id, e)))?;
+        let mut node = Node::new(RUN_LABEL).with_id_alias(run.id.to_string()).with_props(props);
+        node.alias_namespace = Some(run.tenant.clone());
+        self.service.upsert_node(&Self::lineage_tenant(), node).await
+    }
+
+    /// Lineage node ID of a run or fact of a tenant
+    async fn resolve(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Uuid>, GraphError> {
+        let key = AliasKey { namespace: Some(tenant.as_str().to_string()), alias: id.to_string() };
+        let mut resolved = self.service.resolve_aliases(&Self::lineage_tenant(), std::slice::from_ref(&key)).await?;
+        Ok(resolved.remove(&key))
+    }
+
+    async fn run_node(&self, node_id: Uuid) -> Result<Option<ExtractionRun>, GraphError> {
+        let node = self.service.get_node(&Self::lineage_tenant(), node_id).await?;
+        Ok(node.filter(|node| node.label == RUN_LABEL).and_then(|node| serde_json::from_value(node.props).ok()))
+    }
+
+    /// A run of a tenant
+    pub async fn run(&self, tenant: &TenantId, run_id: Uuid) -> Result<Option<ExtractionRun>, GraphError> {
+        match self.resolve(tenant, run_id).await? {
+            Some(node_id) => self.run_node(node_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Runs of a tenant, latest first
+    pub async fn runs(&self, tenant: &TenantId, filter: &RunFilter) -> Result<Vec<ExtractionRun>, GraphError> {
+        let mut properties = HashMap::from([("tenant".to_string(), Value::from(tenant.as_str()))]);
+        if let Some(model) = &filter.model {
+            properties.insert("model".to_string(), Value::from(model.as_str()));
+        }
+        if let Some(prompt_version) = &filter.prompt_version {
+            properties.insert("prompt_version".to_string(), Value::from(prompt_version.as_str()));
+        }
+        let paths = self.service.query(&Self::lineage_tenant(), GraphQuery::FindNodes {
+            labels: vec![RUN_LABEL.to_string()],
+            properties,
+            order_by: vec![],
+            offset: None,
+            limit: None,
+        }).await?;
+
+        let mut runs: Vec<ExtractionRun> = paths.into_iter()
+            .flat_map(|path| path.nodes)
+            .filter_map(|node| serde_json::from_value(node.properties).ok())
+            .collect();
+        runs.sort_by_key(|run| std::cmp::Reverse(run.applied_at));
+        Ok(runs)
+    }
+
+    /// Runs that wrote or closed a node or edge of a tenant, latest first
+    pub async fn producers(&self, tenant: &TenantId, fact_id: Uuid) -> Result<Vec<ExtractionRun>, GraphError> {
+        let Some(fact) = self.resolve(tenant, fact_id).await? else {
+            return Ok(Vec::new());
+        };
+        let paths = self.service.query(&Self::lineage_tenant(), GraphQuery::FindRelationships {
+            from_node_id: None,
+            to_node_id: Some(fact),
+            relationship_types: vec![PRODUCED.to_string(), SUPERSEDED.to_string()],
+            valid_at: None,
+            order_by: vec![],
+            offset: None,
+            limit: None,
+            collapse: None,
+        }).await?;
+
+        let mut runs = Vec::new();
+        for rel in paths.iter().flat_map(|path| &path.relationships) {
+            if let Some(run) = self.run_node(rel.start_node_id).await? {
+                runs.push(run);
+            }
+        }
+        runs.sort_by_key(|run| std::cmp::Reverse(run.applied_at));
+        Ok(runs)
+    }
+
+    /// Nodes and edges a run wrote
+    pub async fn facts(&self, tenant: &TenantId, run_id: Uuid) -> Result<Option<RunFacts>, GraphError> {
+        let Some(run) = self.resolve(tenant, run_id).await? else {
+            return Ok(None);
+        };
+        let paths = self.service.query(&Self::lineage_tenant(), GraphQuery::FindRelationships {
+            from_node_id: Some(run),
+            to_node_id: None,
+            relationship_types: vec![PRODUCED.to_string(), SUPERSEDED.to_string()],
+            valid_at: None,
+            order_by: vec![],
+            offset: None,
+            limit: None,
+            collapse: None,
+        }).await?;
+
+        let mut facts = RunFacts::default();
+        for rel in paths.iter().flat_map(|path| &path.relationships) {
+            let props = &rel.properties;
+            let Some(fact_id) = props["fact_id"].as_str().and_then(|id| id.parse().ok()) else {
+                continue;
+            };
+            let list = match (rel.rel_type.as_str(), props["kind"].as_str(), props["created"].as_bool()) {
+                (SUPERSEDED, _, _) => &mut facts.superseded,
+                (_, Some("edge"), _) => &mut facts.edges,
+                (_, _, Some(true)) => &mut facts.created_nodes,
+                _ => &mut facts.updated_nodes,
+            };
+            list.push(fact_id);
+        }
+        Ok(Some(facts))
+    }
+
+    /// Delete the edges and nodes a run created from its tenant. Nodes it
+    /// only updated are kept, as are the edges it closed.
+    pub async fn purge(&self, tenant: &TenantId, run_id: Uuid) -> Result<PurgeReport, GraphError> {
+        let (Some(mut run), Some(facts)) = (self.run(tenant, run_id).await?, self.facts(tenant, run_id).await?) else {
+            return Err(GraphError::NodeNotFound(format!("Extraction run {}", run_id)));
+        };
+
+        let mut report = PurgeReport { run_id, kept_nodes: facts.updated_nodes, ..Default::default() };
+        for id in facts.edges {
+            if self.service.delete_edge(tenant, id).await? {
+                report.deleted_edges.push(id);
+            }
+        }
+        for id in facts.created_nodes {
+            if self.service.delete_node(tenant, id).await? {
+                report.deleted_nodes.push(id);
+            }
+        }
+
+        run.purged_at = Some(Utc::now());
+        if let Err(e) = self.upsert_run(&run).await {
+            warn!("Could not mark extraction run {} of tenant {} as purged: {}", run_id, tenant, e);
+        }
+        info!("Purged extraction run {} of tenant {}: {} nodes, {} edges deleted",
+            run_id, tenant, report.deleted_nodes.len(), report.deleted_edges.len());
+        Ok(report)
+    }
+}
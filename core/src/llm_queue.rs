@@ -0,0 +1,495 @@
+//! Rate-limited, prioritized queue of LLM requests
+//!
+//! Providers limit requests and tokens per minute, and a burst of
+//! extractions otherwise runs into their 429s. An [`LlmRequestQueue`] keeps
+//! one lane per provider, admitting a request once the lane has a free slot
+//! under `max_concurrent` and the requests and tokens admitted over the last
+//! minute leave room for it. Tokens are estimated up front from the prompt
+//! and `max_tokens`, then corrected with the usage the provider reports.
+//!
+//! Requests are interactive unless made inside [`with_priority`] with
+//! [`RequestPriority::Background`], e.g. for re-extraction jobs; background
+//! requests wait while interactive ones are queued for the same provider.
+//! A request that waits longer than `max_wait_ms` fails with
+//! `LlmError::RateLimited`, and a 429 from the provider pauses its lane for
+//! `cooldown_ms`.
+//!
+//! One queue is shared by all connectors, each wrapped in a
+//! [`QueuedConnector`] under its provider name; [`LlmRequestQueue::stats`]
+//! reports each lane.
+
+use crate::availability::CapabilityStatus;
+use crate::errors::LlmError;
+use crate::tokens::TokenEstimator;
+use crate::traits::{CompletionRequest, CompletionResponse, ExtractionContext, ExtractionEnvelope, ExtractionMetadata, LlmConnector, ModelInfo, ProviderStatus};
+use crate::types::TenantId;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{debug, warn};
+
+/// Window over which requests and tokens per minute are counted
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Output tokens reserved for a request that does not set `max_tokens`
+const DEFAULT_OUTPUT_TOKENS: u64 = 1_024;
+
+tokio::task_local! {
+    static PRIORITY: RequestPriority;
+}
+
+/// Priority class of an LLM request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    /// A caller is waiting for the answer, e.g. recall or extraction on ingest
+    #[default]
+    Interactive,
+    /// Work nobody waits on, e.g. re-extraction; yields to interactive requests
+    Background,
+}
+
+/// Run `future` with its LLM requests queued at `priority`
+pub async fn with_priority<F: Future>(priority: RequestPriority, future: F) -> F::Output {
+    PRIORITY.scope(priority, future).await
+}
+
+/// Priority of LLM requests made by the current task
+pub fn current_priority() -> RequestPriority {
+    PRIORITY.try_with(|priority| *priority).unwrap_or_default()
+}
+
+/// Limits of one provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderLimits {
+    /// Requests sent to the provider at once
+    pub max_concurrent: usize,
+    /// Requests admitted per minute; unlimited if unset
+    pub requests_per_minute: Option<u32>,
+    /// Input and output tokens admitted per minute; unlimited if unset
+    pub tokens_per_minute: Option<u64>,
+    /// Longest a request waits for its turn, in milliseconds
+    pub max_wait_ms: u64,
+    /// How long the provider gets no requests after answering 429, in milliseconds
+    pub cooldown_ms: u64,
+}
+
+impl Default for ProviderLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 8,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_wait_ms: 60_000,
+            cooldown_ms: 1_000,
+        }
+    }
+}
+
+/// Queue limits by provider
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmQueueConfig {
+    /// Limits of providers without their own
+    pub default: ProviderLimits,
+    /// Limits by provider name
+    pub providers: HashMap<String, ProviderLimits>,
+}
+
+impl LlmQueueConfig {
+    /// Limits of a provider
+    pub fn for_provider(&self, provider: &str) -> &ProviderLimits {
+        self.providers.get(provider).unwrap_or(&self.default)
+    }
+}
+
+/// State of a provider's lane
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaneStats {
+    /// Requests sent and not yet answered
+    pub in_flight: usize,
+    /// Interactive requests waiting for their turn
+    pub waiting_interactive: usize,
+    /// Background requests waiting for their turn
+    pub waiting_background: usize,
+    /// Requests admitted over the last minute
+    pub requests_last_minute: usize,
+    /// Tokens of the requests admitted over the last minute
+    pub tokens_last_minute: u64,
+    /// Requests admitted since the queue was created
+    pub admitted: u64,
+    /// Requests that gave up after `max_wait_ms`
+    pub timed_out: u64,
+    /// 429s the provider answered
+    pub rate_limited: u64,
+    /// Time admitted requests spent waiting, in milliseconds
+    pub total_wait_ms: u64,
+}
+
+/// Outcome of trying to admit a request
+enum Admission {
+    Admitted(u64),
+    /// Try again when notified, or after the given time has passed
+    Wait(Option<Duration>),
+}
+
+#[derive(Default)]
+struct LaneState {
+    in_flight: usize,
+    waiting_interactive: usize,
+    waiting_background: usize,
+    /// Requests admitted within the window: ID, admission time and tokens
+    window: VecDeque<(u64, Instant, u64)>,
+    window_tokens: u64,
+    next_id: u64,
+    paused_until: Option<Instant>,
+    admitted: u64,
+    timed_out: u64,
+    rate_limited: u64,
+    total_wait_ms: u64,
+}
+
+impl LaneState {
+    fn waiting(&mut self, priority: RequestPriority) -> &mut usize {
+        match priority {
+            RequestPriority::Interactive => &mut self.waiting_interactive,
+            RequestPriority::Background => &mut self.waiting_background,
+        }
+    }
+
+    fn try_admit(&mut self, limits: &ProviderLimits, priority: RequestPriority, tokens: u64, now: Instant) -> Admission {
+        while let Some(&(_, admitted_at, admitted_tokens)) = self.window.front() {
+            if now.duration_since(admitted_at) < WINDOW {
+                break;
+            }
+            self.window.pop_front();
+            self.window_tokens -= admitted_tokens;
+        }
+        let window_frees_at = |window: &VecDeque<(u64, Instant, u64)>| {
+            window.front().map(|&(_, admitted_at, _)| (admitted_at + WINDOW).saturating_duration_since(now))
+        };
+
+        if let Some(until) = self.paused_until {
+            if now < until {
+                return Admission::Wait(Some(until - now));
+            }
+            self.paused_until = None;
+        }
+        if priority == RequestPriority::Background && self.waiting_interactive > 0 {
+            return Admission::Wait(None);
+        }
+        if self.in_flight >= limits.max_concurrent.max(1) {
+            return Admission::Wait(None);
+        }
+        if limits.requests_per_minute.is_some_and(|rpm| self.window.len() >= rpm as usize) {
+            return Admission::Wait(window_frees_at(&self.window));
+        }
+        // A request over the whole budget is let through once the window is empty
+        if limits.tokens_per_minute.is_some_and(|tpm| self.window_tokens + tokens > tpm) && !self.window.is_empty() {
+            return Admission::Wait(window_frees_at(&self.window));
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.in_flight += 1;
+        self.admitted += 1;
+        self.window.push_back((id, now, tokens));
+        self.window_tokens += tokens;
+        Admission::Admitted(id)
+    }
+}
+
+/// Requests of one provider
+struct Lane {
+    provider: String,
+    limits: ProviderLimits,
+    state: Mutex<LaneState>,
+    /// Notified whenever a request finishes or stops waiting
+    changed: Notify,
+}
+
+/// A request waiting in a lane, counted until dropped
+struct Waiting<'a> {
+    lane: &'a Lane,
+    priority: RequestPriority,
+    queued: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if self.queued {
+            *self.lane.state.lock().unwrap().waiting(self.priority) -= 1;
+            self.lane.changed.notify_waiters();
+        }
+    }
+}
+
+impl Lane {
+    async fn acquire(self: &Arc<Self>, priority: RequestPriority, tokens: u64) -> Result<QueuePermit, LlmError> {
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(self.limits.max_wait_ms);
+        let mut waiting = Waiting { lane: self, priority, queued: false };
+
+        loop {
+            // Created before checking, so a request finishing in between still wakes us
+            let changed = self.changed.notified();
+            let now = Instant::now();
+            let retry_in = {
+                let mut state = self.state.lock().unwrap();
+                match state.try_admit(&self.limits, priority, tokens, now) {
+                    Admission::Admitted(id) => {
+                        if waiting.queued {
+                            *state.waiting(priority) -= 1;
+                            waiting.queued = false;
+                        }
+                        state.total_wait_ms += now.duration_since(started).as_millis() as u64;
+                        return Ok(QueuePermit { lane: self.clone(), id });
+                    }
+                    Admission::Wait(retry_in) => {
+                        if !waiting.queued {
+                            *state.waiting(priority) += 1;
+                            waiting.queued = true;
+                        }
+                        retry_in
+                    }
+                }
+            };
+
+            if now >= deadline {
+                self.state.lock().unwrap().timed_out += 1;
+                warn!("LLM request to provider '{}' gave up after waiting {:?} in the queue", self.provider, now - started);
+                return Err(LlmError::RateLimited(format!(
+                    "Request to provider '{}' waited more than {}ms in the queue", self.provider, self.limits.max_wait_ms
+                )));
+            }
+            let sleep = retry_in.map_or(deadline - now, |retry_in| retry_in.min(deadline - now));
+            tokio::select! {
+                _ = changed => {}
+                _ = tokio::time::sleep(sleep) => {}
+            }
+        }
+    }
+
+    fn stats(&self) -> LaneStats {
+        let state = self.state.lock().unwrap();
+        LaneStats {
+            in_flight: state.in_flight,
+            waiting_interactive: state.waiting_interactive,
+            waiting_background: state.waiting_background,
+            requests_last_minute: state.window.len(),
+            tokens_last_minute: state.window_tokens,
+            admitted: state.admitted,
+            timed_out: state.timed_out,
+            rate_limited: state.rate_limited,
+            total_wait_ms: state.total_wait_ms,
+        }
+    }
+}
+
+/// A request admitted to its provider, holding a slot until dropped
+pub struct QueuePermit {
+    lane: Arc<Lane>,
+    id: u64,
+}
+
+impl QueuePermit {
+    /// Count the tokens the provider reported instead of the estimate
+    pub fn settle(&self, tokens: u64) {
+        let mut state = self.lane.state.lock().unwrap();
+        let LaneState { window, window_tokens, .. } = &mut *state;
+        if let Some((_, _, counted)) = window.iter_mut().find(|(id, _, _)| *id == self.id) {
+            *window_tokens = *window_tokens - *counted + tokens;
+            *counted = tokens;
+        }
+    }
+
+    /// Pause the lane after the provider answered 429
+    pub fn rate_limited(&self) {
+        let mut state = self.lane.state.lock().unwrap();
+        state.rate_limited += 1;
+        state.paused_until = Some(Instant::now() + Duration::from_millis(self.lane.limits.cooldown_ms));
+        warn!("Provider '{}' is rate limiting; pausing its queue for {}ms", self.lane.provider, self.lane.limits.cooldown_ms);
+    }
+
+    /// Settle the permit with the outcome of its request
+    fn finish(self, outcome: Result<Option<&ExtractionMetadata>, &LlmError>) {
+        match outcome {
+            Ok(Some(ExtractionMetadata { input_tokens: Some(input), output_tokens: Some(output), .. })) => {
+                self.settle(*input as u64 + *output as u64);
+            }
+            Err(LlmError::RateLimited(_)) => self.rate_limited(),
+            _ => {}
+        }
+    }
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        self.lane.state.lock().unwrap().in_flight -= 1;
+        self.lane.changed.notify_waiters();
+    }
+}
+
+/// Lanes of LLM requests by provider, shared by all connectors
+#[derive(Default)]
+pub struct LlmRequestQueue {
+    config: LlmQueueConfig,
+    lanes: Mutex<BTreeMap<String, Arc<Lane>>>,
+}
+
+impl LlmRequestQueue {
+    pub fn new(config: LlmQueueConfig) -> Self {
+        Self { config, lanes: Mutex::new(BTreeMap::new()) }
+    }
+
+    fn lane(&self, provider: &str) -> Arc<Lane> {
+        self.lanes.lock().unwrap()
+            .entry(provider.to_string())
+            .or_insert_with(|| Arc::new(Lane {
+                provider: provider.to_string(),
+                limits: self.config.for_provider(provider).clone(),
+                state: Mutex::new(LaneState::default()),
+                changed: Notify::new(),
+            }))
+            .clone()
+    }
+
+    /// Wait for a turn to send a request of about `tokens` tokens to a provider
+    pub async fn acquire(&self, provider: &str, priority: RequestPriority, tokens: u64) -> Result<QueuePermit, LlmError> {
+        self.lane(provider).acquire(priority, tokens).await
+    }
+
+    /// State of each provider's lane
+    pub fn stats(&self) -> BTreeMap<String, LaneStats> {
+        self.lanes.lock().unwrap()
+            .iter()
+            .map(|(provider, lane)| (provider.clone(), lane.stats()))
+            .collect()
+    }
+}
+
+/// Connector whose requests wait for their turn in a shared queue
+pub struct QueuedConnector {
+    inner: Arc<dyn LlmConnector>,
+    provider: String,
+    queue: Arc<LlmRequestQueue>,
+}
+
+impl QueuedConnector {
+    /// Queue the requests to `inner` in the lane of `provider`
+    pub fn new(inner: Arc<dyn LlmConnector>, provider: impl Into<String>, queue: Arc<LlmRequestQueue>) -> Self {
+        Self { inner, provider: provider.into(), queue }
+    }
+
+    async fn acquire(&self, tokens: usize, max_tokens: Option<u32>) -> Result<QueuePermit, LlmError> {
+        let tokens = tokens as u64 + max_tokens.map_or(DEFAULT_OUTPUT_TOKENS, u64::from);
+        let priority = current_priority();
+        debug!("Queueing {:?} request of ~{} tokens for provider '{}'", priority, tokens, self.provider);
+        self.queue.acquire(&self.provider, priority, tokens).await
+    }
+}
+
+#[async_trait]
+impl LlmConnector for QueuedConnector {
+    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let estimator = TokenEstimator::for_provider(&self.provider);
+        let permit = self.acquire(estimator.count_context(&context), context.max_tokens).await?;
+        let result = self.inner.extract(tenant, context).await;
+        permit.finish(result.as_ref().map(|envelope| envelope.metadata.as_ref()));
+        result
+    }
+
+    async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let estimator = TokenEstimator::for_provider(&self.provider);
+        let prompt = estimator.count(&request.prompt)
+            + estimator.count_messages(&request.messages)
+            + request.system.as_deref().map_or(0, |system| estimator.count(system));
+        let permit = self.acquire(prompt, request.max_tokens).await?;
+        let result = self.inner.complete(tenant, request).await;
+        permit.finish(result.as_ref().map(|response| response.metadata.as_ref()));
+        result
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        self.inner.list_models().await
+    }
+
+    async fn health(&self) -> CapabilityStatus {
+        self.inner.health().await
+    }
+
+    async fn provider_status(&self, tenant: Option<&TenantId>) -> Vec<ProviderStatus> {
+        self.inner.provider_status(tenant).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(limits: ProviderLimits) -> Arc<LlmRequestQueue> {
+        Arc::new(LlmRequestQueue::new(LlmQueueConfig { default: limits, providers: HashMap::new() }))
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_and_priority() {
+        let queue = queue(ProviderLimits { max_concurrent: 1, ..ProviderLimits::default() });
+        let first = queue.acquire("openai", RequestPriority::Interactive, 10).await.unwrap();
+
+        // A background request queued first still goes after an interactive one
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let spawn = |priority| {
+            let (queue, order) = (queue.clone(), order.clone());
+            tokio::spawn(async move {
+                let permit = queue.acquire("openai", priority, 10).await.unwrap();
+                order.lock().unwrap().push(priority);
+                drop(permit);
+            })
+        };
+        let background = spawn(RequestPriority::Background);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let interactive = spawn(RequestPriority::Interactive);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let stats = queue.stats()["openai"];
+        assert_eq!((stats.in_flight, stats.waiting_interactive, stats.waiting_background), (1, 1, 1));
+        // Other providers have lanes of their own
+        assert!(queue.acquire("anthropic", RequestPriority::Background, 10).await.is_ok());
+
+        drop(first);
+        background.await.unwrap();
+        interactive.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), vec![RequestPriority::Interactive, RequestPriority::Background]);
+        assert_eq!(queue.stats()["openai"].admitted, 3);
+    }
+
+    #[tokio::test]
+    async fn test_token_budget() {
+        let queue = queue(ProviderLimits { tokens_per_minute: Some(1_000), max_wait_ms: 20, ..ProviderLimits::default() });
+
+        let permit = queue.acquire("openai", RequestPriority::Interactive, 800).await.unwrap();
+        assert!(matches!(
+            queue.acquire("openai", RequestPriority::Interactive, 300).await,
+            Err(LlmError::RateLimited(_))
+        ));
+        // Reported usage replaces the estimate
+        permit.settle(500);
+        drop(permit);
+        assert!(queue.acquire("openai", RequestPriority::Interactive, 300).await.is_ok());
+
+        let stats = queue.stats()["openai"];
+        assert_eq!((stats.tokens_last_minute, stats.requests_last_minute, stats.timed_out), (800, 2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_priority_scope() {
+        assert_eq!(current_priority(), RequestPriority::Interactive);
+        let priority = with_priority(RequestPriority::Background, async { current_priority() }).await;
+        assert_eq!(priority, RequestPriority::Background);
+    }
+}
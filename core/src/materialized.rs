@@ -0,0 +1,420 @@
+//! Named, materialized snapshots for fast as-of reads
+//!
+//! Dashboards that read the graph as it was at a fixed time, such as the end
+//! of a quarter, would otherwise recompute the temporal filters on every
+//! request. A [`MaterializedSnapshot`] reads the graph valid at that time once,
+//! through `GraphStore::snapshot`, and answers structured queries from memory.
+//!
+//! Snapshots are frozen: later writes do not change them, and materializing a
+//! name again replaces the snapshot. Stores keep them in a [`SnapshotRegistry`].
+
+use crate::collapse::collapse_edges;
+use crate::errors::GraphError;
+use crate::traits::GraphStore;
+use crate::types::{
+    EdgeAggregate, EdgeRecord, GraphQuery, GraphSnapshot, Node, NodeRecord, OrderBy, Path, PathNode, PathRelationship,
+    SortDirection, SortField, SortKey, TenantId, TimeEdge,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Default number of snapshots a tenant can hold
+pub const DEFAULT_MAX_SNAPSHOTS_PER_TENANT: usize = 16;
+
+/// Longest allowed snapshot name
+pub const MAX_SNAPSHOT_NAME_LEN: usize = 64;
+
+/// Description of a materialized snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    /// Name of the snapshot, unique per tenant
+    pub name: String,
+    /// Valid time the snapshot holds the graph at
+    pub valid_at: DateTime<Utc>,
+    /// Transaction time the snapshot was read at
+    pub snapshot_at: DateTime<Utc>,
+    /// Number of nodes in the snapshot
+    pub node_count: u64,
+    /// Number of edges in the snapshot
+    pub edge_count: u64,
+}
+
+/// A tenant's graph valid at one time, held in memory
+#[derive(Debug, Clone)]
+pub struct MaterializedSnapshot {
+    info: SnapshotInfo,
+    /// Nodes in the order the store returned them
+    nodes: Vec<NodeRecord>,
+    edges: Vec<EdgeRecord>,
+    /// Position of each node in `nodes`
+    node_index: HashMap<Uuid, usize>,
+}
+
+impl MaterializedSnapshot {
+    /// Materialize a snapshot read with `valid_at`
+    pub fn new(name: impl Into<String>, valid_at: DateTime<Utc>, snapshot: GraphSnapshot) -> Self {
+        let node_index = snapshot.nodes.iter()
+            .enumerate()
+            .map(|(position, record)| (record.id, position))
+            .collect();
+
+        Self {
+            info: SnapshotInfo {
+                name: name.into(),
+                valid_at,
+                snapshot_at: snapshot.snapshot_at,
+                node_count: snapshot.nodes.len() as u64,
+                edge_count: snapshot.edges.len() as u64,
+            },
+            nodes: snapshot.nodes,
+            edges: snapshot.edges,
+            node_index,
+        }
+    }
+
+    /// Description of the snapshot
+    pub fn info(&self) -> &SnapshotInfo {
+        &self.info
+    }
+
+    /// Answer a structured query. Raw and as-of queries are not supported,
+    /// since the snapshot already fixes the valid time.
+    pub fn query(&self, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        match query {
+            GraphQuery::FindNodes { labels, properties, order_by, offset, limit } => {
+                let mut nodes: Vec<(usize, &NodeRecord)> = self.nodes.iter()
+                    .enumerate()
+                    .filter(|(_, record)| labels.is_empty() || labels.contains(&record.node.label))
+                    .filter(|(_, record)| {
+                        properties.iter().all(|(key, expected)| record.node.props.get(key) == Some(expected))
+                    })
+                    .collect();
+
+                // Nodes carry no creation time, so the store's order stands in for it
+                sort_by_keys(&mut nodes, &order_by, |(position, record), field| match field {
+                    SortField::Property(name) => SortKey::Property(record.node.props.get(name)),
+                    SortField::CreatedAt => SortKey::Position(*position),
+                    SortField::Label => SortKey::Text(&record.node.label),
+                }, |(_, record)| record.id);
+
+                Ok(page(nodes, offset, limit).into_iter()
+                    .map(|(_, record)| Path {
+                        nodes: vec![path_node(record.id, &record.node)],
+                        relationships: Vec::new(),
+                    })
+                    .collect())
+            }
+
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse } => {
+                let mut edges: Vec<&EdgeRecord> = self.edges.iter()
+                    .filter(|record| {
+                        let edge = &record.edge;
+
+                        from_node_id.is_none_or(|from_id| edge.from_node_id == from_id)
+                            && to_node_id.is_none_or(|to_id| edge.to_node_id == to_id)
+                            && (relationship_types.is_empty() || relationship_types.contains(&edge.kind))
+                            // Narrows the snapshot's own valid time further, if given
+                            && valid_at.is_none_or(|valid_at| edge.was_valid_at(valid_at))
+                            && self.node_index.contains_key(&edge.from_node_id)
+                            && self.node_index.contains_key(&edge.to_node_id)
+                    })
+                    .collect();
+
+                sort_by_keys(&mut edges, &order_by, |record, field| match field {
+                    SortField::Property(name) => SortKey::Property(record.edge.props.get(name)),
+                    SortField::CreatedAt => SortKey::Time(record.edge.transaction_start_time),
+                    SortField::Label => SortKey::Text(&record.edge.kind),
+                }, |record| record.id);
+
+                let edges: Vec<(Uuid, TimeEdge, Option<EdgeAggregate>)> = match collapse {
+                    Some(collapse) => collapse_edges(edges.iter().map(|record| (record.id, &record.edge)), collapse)
+                        .into_iter()
+                        .map(|collapsed| (collapsed.id, collapsed.edge, Some(collapsed.aggregate)))
+                        .collect(),
+                    None => edges.into_iter().map(|record| (record.id, record.edge.clone(), None)).collect(),
+                };
+
+                Ok(page(edges, offset, limit).into_iter()
+                    .map(|(id, edge, aggregate)| {
+                        let start = &self.nodes[self.node_index[&edge.from_node_id]];
+                        let end = &self.nodes[self.node_index[&edge.to_node_id]];
+
+                        Path {
+                            nodes: vec![path_node(start.id, &start.node), path_node(end.id, &end.node)],
+                            relationships: vec![PathRelationship {
+                                id,
+                                rel_type: edge.kind,
+                                start_node_id: edge.from_node_id,
+                                end_node_id: edge.to_node_id,
+                                properties: edge.props,
+                                collapsed: aggregate,
+                            }],
+                        }
+                    })
+                    .collect())
+            }
+
+            GraphQuery::Raw { .. } | GraphQuery::AsOfQuery { .. } => Err(GraphError::QueryFailed(
+                "Only FindNodes and FindRelationships queries are supported on materialized snapshots".to_string(),
+            )),
+        }
+    }
+}
+
+fn path_node(id: Uuid, node: &Node) -> PathNode {
+    PathNode {
+        id,
+        labels: vec![node.label.clone()],
+        properties: node.props.clone(),
+    }
+}
+
+/// Sort query results by `order_by`, breaking ties by system ID
+fn sort_by_keys<T>(
+    items: &mut [T],
+    order_by: &[OrderBy],
+    key: impl for<'a> Fn(&'a T, &SortField) -> SortKey<'a>,
+    id: impl Fn(&T) -> Uuid,
+) {
+    if order_by.is_empty() {
+        return;
+    }
+
+    items.sort_by(|a, b| {
+        order_by.iter()
+            .map(|order| {
+                let ordering = key(a, &order.field).compare(&key(b, &order.field));
+                match order.direction {
+                    SortDirection::Asc => ordering,
+                    SortDirection::Desc => ordering.reverse(),
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| id(a).cmp(&id(b)))
+    });
+}
+
+/// Apply `offset` and `limit` to sorted query results
+fn page<T>(items: Vec<T>, offset: Option<u32>, limit: Option<u32>) -> Vec<T> {
+    items.into_iter()
+        .skip(offset.unwrap_or(0) as usize)
+        .take(limit.map_or(usize::MAX, |limit| limit as usize))
+        .collect()
+}
+
+/// Materialized snapshots by tenant and name
+#[derive(Debug)]
+pub struct SnapshotRegistry {
+    max_per_tenant: usize,
+    snapshots: RwLock<HashMap<TenantId, BTreeMap<String, Arc<MaterializedSnapshot>>>>,
+}
+
+impl Default for SnapshotRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SNAPSHOTS_PER_TENANT)
+    }
+}
+
+impl SnapshotRegistry {
+    /// Create a registry holding up to `max_per_tenant` snapshots per tenant
+    pub fn new(max_per_tenant: usize) -> Self {
+        Self {
+            max_per_tenant,
+            snapshots: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Read the graph valid at `valid_at` from `store` and keep it as `name`,
+    /// replacing any snapshot of that name
+    pub async fn materialize(
+        &self,
+        store: &dyn GraphStore,
+        tenant: &TenantId,
+        name: &str,
+        valid_at: DateTime<Utc>,
+    ) -> Result<SnapshotInfo, GraphError> {
+        validate_name(name)?;
+        // Fail before the read rather than after it
+        self.check_capacity(tenant, name)?;
+
+        debug!("Materializing snapshot '{}' of tenant {} at {}", name, tenant, valid_at);
+        let snapshot = store.snapshot(tenant, Some(valid_at)).await?;
+        self.insert(tenant, MaterializedSnapshot::new(name, valid_at, snapshot))
+    }
+
+    /// Keep a snapshot, replacing any snapshot of the same name
+    pub fn insert(&self, tenant: &TenantId, snapshot: MaterializedSnapshot) -> Result<SnapshotInfo, GraphError> {
+        validate_name(&snapshot.info.name)?;
+        self.check_capacity(tenant, &snapshot.info.name)?;
+
+        let info = snapshot.info.clone();
+        self.snapshots.write().unwrap()
+            .entry(tenant.clone())
+            .or_default()
+            .insert(info.name.clone(), Arc::new(snapshot));
+        Ok(info)
+    }
+
+    /// Snapshots of a tenant, sorted by name
+    pub fn list(&self, tenant: &TenantId) -> Vec<SnapshotInfo> {
+        self.snapshots.read().unwrap()
+            .get(tenant)
+            .map(|snapshots| snapshots.values().map(|snapshot| snapshot.info.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Drop a snapshot; returns false if there was none of that name
+    pub fn remove(&self, tenant: &TenantId, name: &str) -> bool {
+        let mut snapshots = self.snapshots.write().unwrap();
+        let Some(tenant_snapshots) = snapshots.get_mut(tenant) else {
+            return false;
+        };
+
+        let removed = tenant_snapshots.remove(name).is_some();
+        if tenant_snapshots.is_empty() {
+            snapshots.remove(tenant);
+        }
+        removed
+    }
+
+    /// Drop all of a tenant's snapshots; returns how many there were
+    pub fn clear_tenant(&self, tenant: &TenantId) -> usize {
+        self.snapshots.write().unwrap().remove(tenant).map_or(0, |snapshots| snapshots.len())
+    }
+
+    /// Snapshot of a tenant by name
+    pub fn get(&self, tenant: &TenantId, name: &str) -> Result<Arc<MaterializedSnapshot>, GraphError> {
+        self.snapshots.read().unwrap()
+            .get(tenant)
+            .and_then(|snapshots| snapshots.get(name))
+            .cloned()
+            .ok_or_else(|| GraphError::SnapshotNotFound(format!("Snapshot '{}' not found in tenant {}", name, tenant)))
+    }
+
+    /// Answer a structured query from a snapshot
+    pub fn query(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.get(tenant, name)?.query(query)
+    }
+
+    /// Fail if a new snapshot called `name` would exceed the tenant's limit
+    fn check_capacity(&self, tenant: &TenantId, name: &str) -> Result<(), GraphError> {
+        let snapshots = self.snapshots.read().unwrap();
+        let Some(tenant_snapshots) = snapshots.get(tenant) else {
+            return Ok(());
+        };
+
+        if !tenant_snapshots.contains_key(name) && tenant_snapshots.len() >= self.max_per_tenant {
+            return Err(GraphError::ConstraintViolation(format!(
+                "Maximum snapshot limit ({}) reached for tenant {}", self.max_per_tenant, tenant
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Names are 1 to 64 ASCII letters, digits, '-', '_' or '.'
+fn validate_name(name: &str) -> Result<(), GraphError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SNAPSHOT_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(GraphError::ConstraintViolation(format!(
+            "Invalid snapshot name '{}': use up to {} letters, digits, '-', '_' or '.'", name, MAX_SNAPSHOT_NAME_LEN
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Query;
+    use crate::types::Collapse;
+    use serde_json::json;
+
+    fn snapshot() -> (GraphSnapshot, Uuid, Uuid) {
+        let alice = NodeRecord { id: Uuid::new_v4(), node: Node::new("Person").with_property("name", json!("Alice")) };
+        let acme = NodeRecord { id: Uuid::new_v4(), node: Node::new("Company").with_property("name", json!("Acme")) };
+        let since: DateTime<Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+        let edges = ["WORKS_FOR", "OWNS"].iter()
+            .map(|kind| EdgeRecord {
+                id: Uuid::new_v4(),
+                edge: TimeEdge::new(alice.id, acme.id, *kind, since, json!({})),
+            })
+            .collect();
+
+        let (alice_id, acme_id) = (alice.id, acme.id);
+        (GraphSnapshot { snapshot_at: Utc::now(), valid_at: Some(since), nodes: vec![alice, acme], edges }, alice_id, acme_id)
+    }
+
+    #[test]
+    fn test_query_snapshot() {
+        let (graph, alice_id, acme_id) = snapshot();
+        let snapshot = MaterializedSnapshot::new("q1", graph.valid_at.unwrap(), graph);
+        assert_eq!(snapshot.info().node_count, 2);
+
+        let people = snapshot.query(GraphQuery::FindNodes {
+            labels: vec!["Person".to_string()],
+            properties: HashMap::new(),
+            order_by: Vec::new(),
+            offset: None,
+            limit: None,
+        }).unwrap();
+        assert_eq!(people.len(), 1);
+        assert_eq!(people[0].nodes[0].id, alice_id);
+
+        let relationships = snapshot.query(GraphQuery::FindRelationships {
+            from_node_id: Some(alice_id),
+            to_node_id: None,
+            relationship_types: Vec::new(),
+            valid_at: None,
+            order_by: vec![OrderBy::asc(SortField::Label)],
+            offset: Some(1),
+            limit: Some(1),
+            collapse: None,
+        }).unwrap();
+        assert_eq!(relationships.len(), 1);
+        assert_eq!(relationships[0].relationships[0].rel_type, "WORKS_FOR");
+        assert_eq!(relationships[0].nodes[1].id, acme_id);
+
+        let collapsed = snapshot.query(Query::relationships().from(alice_id).collapse(Collapse::ByPair).build()).unwrap();
+        assert_eq!(collapsed.len(), 1);
+        let aggregate = collapsed[0].relationships[0].collapsed.as_ref().unwrap();
+        assert_eq!(aggregate.count, 2);
+        assert_eq!(aggregate.kinds, vec!["OWNS", "WORKS_FOR"]);
+
+        let raw = GraphQuery::Raw { query: "MATCH (n) RETURN n".to_string(), params: HashMap::new() };
+        assert!(matches!(snapshot.query(raw), Err(GraphError::QueryFailed(_))));
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = SnapshotRegistry::new(1);
+        let tenant = TenantId::new("test_tenant");
+        let (graph, _, _) = snapshot();
+        let valid_at = graph.valid_at.unwrap();
+
+        registry.insert(&tenant, MaterializedSnapshot::new("q1", valid_at, graph.clone())).unwrap();
+        // Replacing a snapshot does not count against the limit
+        registry.insert(&tenant, MaterializedSnapshot::new("q1", valid_at, graph.clone())).unwrap();
+        assert!(matches!(
+            registry.insert(&tenant, MaterializedSnapshot::new("q2", valid_at, graph.clone())),
+            Err(GraphError::ConstraintViolation(_))
+        ));
+        assert!(registry.insert(&tenant, MaterializedSnapshot::new("end of q1", valid_at, graph)).is_err());
+
+        assert_eq!(registry.list(&tenant).len(), 1);
+        assert!(registry.list(&TenantId::new("other_tenant")).is_empty());
+        assert!(matches!(registry.get(&TenantId::new("other_tenant"), "q1"), Err(GraphError::SnapshotNotFound(_))));
+
+        assert!(registry.remove(&tenant, "q1"));
+        assert!(!registry.remove(&tenant, "q1"));
+        assert!(registry.list(&tenant).is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+//! Versioned schema migrations of graph stores
+//!
+//! Stores with indexes and constraints of their own (e.g. Neo4j) describe
+//! layout changes as numbered migrations and record which ones a database
+//! has applied. A store refuses to start against a database that is behind
+//! unless its [`SchemaCheck`] says otherwise; `kgctl migrate` applies them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One versioned step of a store's schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationInfo {
+    /// Version the schema is at once this step is applied
+    pub version: u32,
+    /// What the step changes
+    pub description: String,
+    /// When the step was applied; None while it is pending
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+/// Schema version of a database against the migrations the store knows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaStatus {
+    /// Highest version applied to the database; 0 for an empty database
+    pub current_version: u32,
+    /// Version of the newest known migration
+    pub latest_version: u32,
+    /// All known migrations, oldest first
+    pub migrations: Vec<MigrationInfo>,
+}
+
+impl SchemaStatus {
+    /// Whether every known migration has been applied
+    pub fn is_current(&self) -> bool {
+        self.current_version >= self.latest_version
+    }
+
+    /// Migrations not yet applied, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &MigrationInfo> {
+        self.migrations.iter().filter(move |m| m.version > self.current_version)
+    }
+}
+
+/// What a store does on startup when the database's schema is behind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaCheck {
+    /// Refuse to start until the pending migrations are applied
+    #[default]
+    Require,
+    /// Log the pending migrations and start anyway
+    Warn,
+    /// Apply the pending migrations before starting
+    Migrate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(version: u32, applied: bool) -> MigrationInfo {
+        MigrationInfo {
+            version,
+            description: format!("step {}", version),
+            applied_at: applied.then(Utc::now),
+        }
+    }
+
+    #[test]
+    fn test_pending_migrations() {
+        let status = SchemaStatus {
+            current_version: 1,
+            latest_version: 3,
+            migrations: vec![migration(1, true), migration(2, false), migration(3, false)],
+        };
+        assert!(!status.is_current());
+        assert_eq!(status.pending().map(|m| m.version).collect::<Vec<_>>(), vec![2, 3]);
+
+        let current = SchemaStatus { current_version: 3, ..status };
+        assert!(current.is_current());
+        assert_eq!(current.pending().count(), 0);
+    }
+}
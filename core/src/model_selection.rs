@@ -0,0 +1,306 @@
+//! Cost-aware model selection for LLM extraction
+//!
+//! `ModelSelectingConnector` wraps a connector and picks the model for each
+//! extraction from configurable rules over input size, tenant tier and the
+//! tenant's remaining daily budget, e.g. a small model for short texts and a
+//! larger one for long or complex inputs. The decision is recorded in
+//! `ExtractionMetadata::model_selection`.
+
+use crate::errors::LlmError;
+use crate::tokens::TokenEstimator;
+use crate::traits::{CompletionRequest, CompletionResponse, ExtractionContext, ExtractionEnvelope, ExtractionMetadata, LlmConnector};
+use crate::types::TenantId;
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// A model selection rule. All conditions that are set must hold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelRule {
+    /// Name recorded in the decision
+    pub name: String,
+    /// Model to use when the rule matches
+    pub model: String,
+    /// Tenant tiers the rule applies to; empty matches every tier
+    #[serde(default)]
+    pub tiers: Vec<String>,
+    /// Minimum estimated input tokens
+    #[serde(default)]
+    pub min_input_tokens: Option<usize>,
+    /// Maximum estimated input tokens
+    #[serde(default)]
+    pub max_input_tokens: Option<usize>,
+    /// Match only when the remaining daily budget is at most this amount
+    #[serde(default)]
+    pub max_remaining_budget_usd: Option<f64>,
+}
+
+impl ModelRule {
+    fn matches(&self, tier: &str, input_tokens: usize, remaining_budget_usd: Option<f64>) -> bool {
+        (self.tiers.is_empty() || self.tiers.iter().any(|t| t == tier))
+            && self.min_input_tokens.is_none_or(|min| input_tokens >= min)
+            && self.max_input_tokens.is_none_or(|max| input_tokens <= max)
+            && self.max_remaining_budget_usd.is_none_or(|max| {
+                remaining_budget_usd.is_some_and(|remaining| remaining <= max)
+            })
+    }
+}
+
+/// Configuration for cost-aware model selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSelectionConfig {
+    /// Rules evaluated in order; the first match wins
+    pub rules: Vec<ModelRule>,
+    /// Tier of each tenant by tenant ID
+    pub tenant_tiers: HashMap<String, String>,
+    /// Tier of tenants missing from `tenant_tiers`
+    pub default_tier: String,
+    /// Daily spending limit per tier in USD; tiers without one are unlimited
+    pub daily_budget_usd: HashMap<String, f64>,
+}
+
+impl Default for ModelSelectionConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            tenant_tiers: HashMap::new(),
+            default_tier: "standard".to_string(),
+            daily_budget_usd: HashMap::new(),
+        }
+    }
+}
+
+impl ModelSelectionConfig {
+    /// Tier of a tenant
+    pub fn tier_for(&self, tenant: &TenantId) -> &str {
+        self.tenant_tiers.get(tenant.as_str()).unwrap_or(&self.default_tier)
+    }
+
+    /// First rule matching a request, if any
+    pub fn select(&self, tier: &str, input_tokens: usize, remaining_budget_usd: Option<f64>) -> Option<&ModelRule> {
+        self.rules.iter().find(|rule| rule.matches(tier, input_tokens, remaining_budget_usd))
+    }
+}
+
+/// Why a model was used for an extraction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelDecision {
+    /// Model requested from the connector; `None` keeps the connector default
+    pub model: Option<String>,
+    /// Rule that selected the model, or `None` if no rule matched or the
+    /// request named its own model
+    pub rule: Option<String>,
+    /// Tenant tier
+    pub tier: String,
+    /// Estimated input tokens
+    pub input_tokens: usize,
+    /// Remaining daily budget before the request, if the tier has one
+    pub remaining_budget_usd: Option<f64>,
+}
+
+/// Spending of a tenant on the current UTC day
+#[derive(Debug, Clone, Copy)]
+struct DailySpend {
+    day: NaiveDate,
+    spent_usd: f64,
+}
+
+/// Connector wrapper that chooses the model per extraction.
+///
+/// A model set explicitly in `ExtractionContext::model` is kept. Extraction
+/// fails with `LlmError::BudgetExceeded` once a tenant has spent its tier's
+/// daily budget, as reported by `ExtractionMetadata::cost_usd`.
+pub struct ModelSelectingConnector {
+    inner: Arc<dyn LlmConnector>,
+    estimator: TokenEstimator,
+    config: ModelSelectionConfig,
+    spend: Mutex<HashMap<TenantId, DailySpend>>,
+}
+
+impl ModelSelectingConnector {
+    /// Wrap a connector with the given selection rules
+    pub fn new(inner: Arc<dyn LlmConnector>, estimator: TokenEstimator, config: ModelSelectionConfig) -> Self {
+        Self {
+            inner,
+            estimator,
+            config,
+            spend: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remaining daily budget for a tenant, or `None` if its tier is unlimited
+    pub fn remaining_budget(&self, tenant: &TenantId) -> Option<f64> {
+        let budget = *self.config.daily_budget_usd.get(self.config.tier_for(tenant))?;
+        let today = Utc::now().date_naive();
+        let spent = self.spend.lock().unwrap()
+            .get(tenant)
+            .filter(|spend| spend.day == today)
+            .map_or(0.0, |spend| spend.spent_usd);
+
+        Some(budget - spent)
+    }
+
+    fn record_spend(&self, tenant: &TenantId, cost_usd: f64) {
+        let today = Utc::now().date_naive();
+        let mut spend = self.spend.lock().unwrap();
+        let entry = spend.entry(tenant.clone()).or_insert(DailySpend { day: today, spent_usd: 0.0 });
+        if entry.day != today {
+            *entry = DailySpend { day: today, spent_usd: 0.0 };
+        }
+        entry.spent_usd += cost_usd;
+    }
+}
+
+#[async_trait]
+impl LlmConnector for ModelSelectingConnector {
+    async fn extract(&self, tenant: &TenantId, mut context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let tier = self.config.tier_for(tenant).to_string();
+        let remaining_budget_usd = self.remaining_budget(tenant);
+        if remaining_budget_usd.is_some_and(|remaining| remaining <= 0.0) {
+            warn!("Tenant {} has exhausted the daily LLM budget of tier '{}'", tenant, tier);
+            return Err(LlmError::BudgetExceeded);
+        }
+
+        let input_tokens = self.estimator.count_context(&context);
+        let rule = match context.model {
+            Some(_) => None,
+            None => self.config.select(&tier, input_tokens, remaining_budget_usd),
+        };
+        if let Some(rule) = rule {
+            context.model = Some(rule.model.clone());
+        }
+
+        let decision = ModelDecision {
+            model: context.model.clone(),
+            rule: rule.map(|rule| rule.name.clone()),
+            tier,
+            input_tokens,
+            remaining_budget_usd,
+        };
+        debug!("Model selection for tenant {}: {:?}", tenant, decision);
+
+        let mut envelope = self.inner.extract(tenant, context).await?;
+        let metadata = envelope.metadata.get_or_insert_with(ExtractionMetadata::default);
+        if let Some(cost_usd) = metadata.cost_usd {
+            self.record_spend(tenant, cost_usd);
+        }
+        metadata.model_selection = Some(decision);
+
+        Ok(envelope)
+    }
+
+    async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        self.inner.complete(tenant, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::LlmMessage;
+
+    struct EchoModel;
+
+    #[async_trait]
+    impl LlmConnector for EchoModel {
+        async fn extract(&self, _tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+            Ok(ExtractionEnvelope {
+                nodes: vec![],
+                relations: vec![],
+                metadata: Some(ExtractionMetadata {
+                    model_name: context.model.unwrap_or_else(|| "default".to_string()),
+                    cost_usd: Some(0.6),
+                    ..Default::default()
+                }),
+            })
+        }
+    }
+
+    fn rule(name: &str, model: &str) -> ModelRule {
+        ModelRule {
+            name: name.to_string(),
+            model: model.to_string(),
+            tiers: vec![],
+            min_input_tokens: None,
+            max_input_tokens: None,
+            max_remaining_budget_usd: None,
+        }
+    }
+
+    fn context(text: &str) -> ExtractionContext {
+        ExtractionContext {
+            messages: vec![LlmMessage {
+                role: "user".to_string(),
+                content: text.to_string(),
+                parts: Vec::new(),
+            }],
+            system_prompt: None,
+            desired_schema: None,
+            max_tokens: None,
+            temperature: None,
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
+        }
+    }
+
+    fn config() -> ModelSelectionConfig {
+        ModelSelectionConfig {
+            rules: vec![
+                ModelRule { max_remaining_budget_usd: Some(0.5), ..rule("low-budget", "claude-3-haiku") },
+                ModelRule { max_input_tokens: Some(100), ..rule("short", "claude-3-haiku") },
+                ModelRule { tiers: vec!["premium".to_string()], ..rule("premium", "claude-3-opus") },
+                rule("fallback", "claude-3-5-sonnet"),
+            ],
+            tenant_tiers: HashMap::from([("vip".to_string(), "premium".to_string())]),
+            daily_budget_usd: HashMap::from([("standard".to_string(), 1.0)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rule_selection() {
+        let config = config();
+        assert_eq!(config.tier_for(&TenantId::new("vip")), "premium");
+        assert_eq!(config.tier_for(&TenantId::new("other")), "standard");
+
+        assert_eq!(config.select("standard", 10, Some(5.0)).unwrap().name, "short");
+        assert_eq!(config.select("standard", 1_000, Some(5.0)).unwrap().name, "fallback");
+        assert_eq!(config.select("premium", 1_000, None).unwrap().name, "premium");
+        assert_eq!(config.select("standard", 1_000, Some(0.2)).unwrap().name, "low-budget");
+    }
+
+    #[tokio::test]
+    async fn test_selection_is_recorded_and_budget_enforced() {
+        let connector = ModelSelectingConnector::new(Arc::new(EchoModel), TokenEstimator::CharRatio(1.0), config());
+        let tenant = TenantId::new("tenant");
+        let long_text = "x".repeat(500);
+
+        let envelope = connector.extract(&tenant, context(&long_text)).await.unwrap();
+        let metadata = envelope.metadata.unwrap();
+        assert_eq!(metadata.model_name, "claude-3-5-sonnet");
+        let decision = metadata.model_selection.unwrap();
+        assert_eq!(decision.rule.as_deref(), Some("fallback"));
+        assert_eq!(decision.remaining_budget_usd, Some(1.0));
+
+        // 0.4 left: the low-budget rule now applies
+        let envelope = connector.extract(&tenant, context(&long_text)).await.unwrap();
+        assert_eq!(envelope.metadata.unwrap().model_name, "claude-3-haiku");
+
+        // An explicit model is kept, but the exhausted budget still applies
+        let explicit = ExtractionContext { model: Some("gpt-4o".to_string()), ..context("hi") };
+        assert!(matches!(connector.extract(&tenant, explicit).await, Err(LlmError::BudgetExceeded)));
+
+        let explicit = ExtractionContext { model: Some("gpt-4o".to_string()), ..context("hi") };
+        let metadata = connector.extract(&TenantId::new("vip"), explicit).await.unwrap().metadata.unwrap();
+        assert_eq!(metadata.model_name, "gpt-4o");
+        assert_eq!(metadata.model_selection.unwrap().rule, None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Streaming application of graph mutations
+//!
+//! High-throughput producers push a continuous stream of node and edge
+//! mutations instead of one request per record. [`MutationApplier`] applies
+//! them to a `GraphService` in the order they arrive and reports a status per
+//! record, keyed by the producer's sequence number, in acks that are sent
+//! every `ack_every` records or `ack_interval_ms`, whichever comes first.
+//!
+//! Both directions are bounded: [`MutationSink::send`] waits while
+//! `max_in_flight` mutations are queued, and the applier stops taking
+//! mutations while `max_pending_acks` acks are unread. A transport that only
+//! reads from the producer as fast as the sink accepts therefore pushes back
+//! on producers that outrun the store or do not read their acks.
+
+use crate::errors::GraphError;
+use crate::traits::GraphService;
+use crate::types::{GraphMutation, TenantId};
+use crate::views::in_current_view;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Configuration for [`MutationApplier`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MutationApplierConfig {
+    /// Mutations queued before producers are held back
+    pub max_in_flight: usize,
+    /// Records reported per ack at most
+    pub ack_every: usize,
+    /// Maximum time a record's status waits before it is acked, in milliseconds
+    pub ack_interval_ms: u64,
+    /// Unread acks before the applier stops taking mutations
+    pub max_pending_acks: usize,
+}
+
+impl Default for MutationApplierConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 1_000,
+            ack_every: 100,
+            ack_interval_ms: 500,
+            max_pending_acks: 16,
+        }
+    }
+}
+
+/// Outcome of one streamed record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MutationStatus {
+    /// Node or edge was written with the given ID
+    Applied { id: Uuid },
+    /// Delete was applied; `false` if nothing matched
+    Deleted { deleted: bool },
+    /// Record was rejected or failed to be written
+    Failed { error: String },
+}
+
+/// Status of the record with the producer's `sequence`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutationResult {
+    pub sequence: u64,
+    #[serde(flatten)]
+    pub status: MutationStatus,
+}
+
+/// Statuses of the records finished since the previous ack
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutationAck {
+    pub results: Vec<MutationResult>,
+    /// Records applied since the stream started
+    pub applied: u64,
+    /// Records failed since the stream started
+    pub failed: u64,
+}
+
+enum Input {
+    Apply { sequence: u64, tenant: TenantId, mutation: GraphMutation },
+    Reject { sequence: u64, error: String },
+}
+
+/// Producer side of a mutation stream. Dropping every clone ends the
+/// stream; the applier then acks what is left and closes the ack receiver.
+#[derive(Clone)]
+pub struct MutationSink(mpsc::Sender<Input>);
+
+impl MutationSink {
+    /// Queue a mutation, waiting while `max_in_flight` mutations are queued
+    pub async fn send(&self, sequence: u64, tenant: TenantId, mutation: GraphMutation) -> Result<(), GraphError> {
+        self.push(Input::Apply { sequence, tenant, mutation }).await
+    }
+
+    /// Report a record the producer sent but that could not be decoded, in
+    /// order with the others
+    pub async fn reject(&self, sequence: u64, error: impl ToString) -> Result<(), GraphError> {
+        self.push(Input::Reject { sequence, error: error.to_string() }).await
+    }
+
+    async fn push(&self, input: Input) -> Result<(), GraphError> {
+        self.0.send(input).await
+            .map_err(|_| GraphError::ConnectionFailed("Mutation stream has been closed".to_string()))
+    }
+}
+
+/// Applies streams of mutations to a graph service
+#[derive(Clone)]
+pub struct MutationApplier {
+    service: Arc<dyn GraphService>,
+    config: MutationApplierConfig,
+}
+
+impl MutationApplier {
+    pub fn new(service: Arc<dyn GraphService>, config: MutationApplierConfig) -> Self {
+        Self { service, config }
+    }
+
+    /// Start a stream: mutations sent into the sink are applied in order on
+    /// a background task, within the caller's view, and their statuses
+    /// arrive on the receiver. The stream stops early if the receiver is
+    /// dropped.
+    pub fn start(&self) -> (MutationSink, mpsc::Receiver<MutationAck>) {
+        let (input_tx, input_rx) = mpsc::channel(self.config.max_in_flight.max(1));
+        let (ack_tx, ack_rx) = mpsc::channel(self.config.max_pending_acks.max(1));
+        tokio::spawn(in_current_view(run(self.service.clone(), self.config.clone(), input_rx, ack_tx)));
+        (MutationSink(input_tx), ack_rx)
+    }
+}
+
+async fn run(
+    service: Arc<dyn GraphService>,
+    config: MutationApplierConfig,
+    mut inputs: mpsc::Receiver<Input>,
+    acks: mpsc::Sender<MutationAck>,
+) {
+    let ack_every = config.ack_every.max(1);
+    let mut interval = tokio::time::interval(Duration::from_millis(config.ack_interval_ms.max(1)));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut pending = MutationAck::default();
+
+    loop {
+        tokio::select! {
+            input = inputs.recv() => {
+                let Some(input) = input else { break };
+                let result = match input {
+                    Input::Apply { sequence, tenant, mutation } => {
+                        let status = apply(service.as_ref(), &tenant, mutation).await.unwrap_or_else(|e| {
+                            debug!("Streamed mutation {} for tenant {} failed: {}", sequence, tenant, e);
+                            MutationStatus::Failed { error: e.to_string() }
+                        });
+                        MutationResult { sequence, status }
+                    }
+                    Input::Reject { sequence, error } => MutationResult { sequence, status: MutationStatus::Failed { error } },
+                };
+                match result.status {
+                    MutationStatus::Failed { .. } => pending.failed += 1,
+                    _ => pending.applied += 1,
+                }
+                pending.results.push(result);
+
+                if pending.results.len() >= ack_every && !send_ack(&acks, &mut pending).await {
+                    debug!("Ack receiver dropped, stopping mutation stream");
+                    return;
+                }
+            }
+            _ = interval.tick() => {
+                if !pending.results.is_empty() && !send_ack(&acks, &mut pending).await {
+                    debug!("Ack receiver dropped, stopping mutation stream");
+                    return;
+                }
+            }
+        }
+    }
+
+    if !pending.results.is_empty() {
+        send_ack(&acks, &mut pending).await;
+    }
+    info!("Mutation stream finished: {} applied, {} failed", pending.applied, pending.failed);
+}
+
+/// Send the pending statuses, keeping the running totals; `false` once the
+/// receiver is gone
+async fn send_ack(acks: &mpsc::Sender<MutationAck>, pending: &mut MutationAck) -> bool {
+    let ack = MutationAck {
+        results: std::mem::take(&mut pending.results),
+        applied: pending.applied,
+        failed: pending.failed,
+    };
+    acks.send(ack).await.is_ok()
+}
+
+async fn apply(service: &dyn GraphService, tenant: &TenantId, mutation: GraphMutation) -> Result<MutationStatus, GraphError> {
+    match mutation {
+        GraphMutation::UpsertNode(node) => service.upsert_node(tenant, node).await.map(|id| MutationStatus::Applied { id }),
+        GraphMutation::UpsertEdge(edge) => service.upsert_edge(tenant, edge).await.map(|id| MutationStatus::Applied { id }),
+        GraphMutation::DeleteNode { id } => service.delete_node(tenant, id).await.map(|deleted| MutationStatus::Deleted { deleted }),
+        GraphMutation::DeleteEdge { id } => service.delete_edge(tenant, id).await.map(|deleted| MutationStatus::Deleted { deleted }),
+    }
+}
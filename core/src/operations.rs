@@ -0,0 +1,242 @@
+//! Cancellation of long-running operations
+//!
+//! Queries, exports and extractions can run for a long time. Presentation
+//! adapters run each of them as an [`Operation`] of an
+//! [`OperationRegistry`], which gives it an ID the caller learns right away:
+//! from a response header, or by choosing the ID itself and sending it with
+//! the request. Cancelling that ID from another request stops the operation
+//! at its next await: its future is dropped, which abandons the store or LLM
+//! call it was waiting on, and the caller gets `CoreError::Cancelled`.
+//! Writes the operation already made are kept.
+//!
+//! Each cancellation is logged to the audit trail and counted by the
+//! registry and, when attached, by telemetry as `cancelled.<kind>`.
+
+use crate::errors::{CoreError, GraphError};
+use crate::telemetry::Telemetry;
+use crate::types::TenantId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// What an operation does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Query,
+    Export,
+    Extraction,
+}
+
+impl OperationKind {
+    /// Telemetry operation counting cancellations of this kind
+    fn cancelled_metric(self) -> &'static str {
+        match self {
+            Self::Query => "cancelled.query",
+            Self::Export => "cancelled.export",
+            Self::Extraction => "cancelled.extraction",
+        }
+    }
+}
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Query => write!(f, "query"),
+            Self::Export => write!(f, "export"),
+            Self::Extraction => write!(f, "extraction"),
+        }
+    }
+}
+
+/// An operation in flight
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationInfo {
+    pub id: Uuid,
+    pub tenant: TenantId,
+    pub kind: OperationKind,
+    pub started_at: DateTime<Utc>,
+    /// Whether the operation was cancelled and is being stopped
+    pub cancelled: bool,
+}
+
+/// Operations of one kind since the registry was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationCounts {
+    pub started: u64,
+    /// Operations that ran to the end, whether they succeeded or failed
+    pub completed: u64,
+    pub cancelled: u64,
+}
+
+struct Tracked {
+    info: OperationInfo,
+    cancel: watch::Sender<bool>,
+}
+
+/// Operations in flight, by ID
+#[derive(Default)]
+pub struct OperationRegistry {
+    operations: Mutex<HashMap<Uuid, Tracked>>,
+    counts: Mutex<BTreeMap<OperationKind, OperationCounts>>,
+    telemetry: Option<Arc<Telemetry>>,
+}
+
+impl OperationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count cancellations in the given telemetry too
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Start tracking an operation of the tenant, under the ID the caller
+    /// chose or a new one. The operation stays cancellable until the
+    /// returned handle is dropped.
+    pub fn start(self: &Arc<Self>, tenant: &TenantId, kind: OperationKind, id: Option<Uuid>) -> Result<Operation, CoreError> {
+        let id = id.unwrap_or_else(Uuid::new_v4);
+        let (cancel, cancelled) = watch::channel(false);
+        let info = OperationInfo { id, tenant: tenant.clone(), kind, started_at: Utc::now(), cancelled: false };
+
+        let mut operations = self.operations.lock().unwrap();
+        if operations.contains_key(&id) {
+            return Err(GraphError::ConstraintViolation(format!("Operation {} is already running", id)).into());
+        }
+        operations.insert(id, Tracked { info, cancel });
+        drop(operations);
+
+        self.counts.lock().unwrap().entry(kind).or_default().started += 1;
+        debug!("Started {} operation {} for tenant {}", kind, id, tenant);
+        Ok(Operation { registry: self.clone(), id, tenant: tenant.clone(), kind, cancelled })
+    }
+
+    /// An operation of the tenant in flight
+    pub fn get(&self, tenant: &TenantId, id: Uuid) -> Option<OperationInfo> {
+        self.operations.lock().unwrap().get(&id)
+            .filter(|tracked| &tracked.info.tenant == tenant)
+            .map(|tracked| tracked.info.clone())
+    }
+
+    /// Operations of the tenant in flight, oldest first
+    pub fn list(&self, tenant: &TenantId) -> Vec<OperationInfo> {
+        let mut operations: Vec<_> = self.operations.lock().unwrap().values()
+            .filter(|tracked| &tracked.info.tenant == tenant)
+            .map(|tracked| tracked.info.clone())
+            .collect();
+        operations.sort_by_key(|info| info.started_at);
+        operations
+    }
+
+    /// Cancel an operation of the tenant, returning it; `None` if the
+    /// tenant has no such operation in flight. Cancelling twice is harmless.
+    pub fn cancel(&self, tenant: &TenantId, id: Uuid) -> Option<OperationInfo> {
+        let mut operations = self.operations.lock().unwrap();
+        let tracked = operations.get_mut(&id).filter(|tracked| &tracked.info.tenant == tenant)?;
+        if tracked.info.cancelled {
+            return Some(tracked.info.clone());
+        }
+        tracked.info.cancelled = true;
+        tracked.cancel.send_replace(true);
+        let info = tracked.info.clone();
+        drop(operations);
+
+        self.counts.lock().unwrap().entry(info.kind).or_default().cancelled += 1;
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.record_operation(tenant, info.kind.cancelled_metric(), None, true);
+        }
+        info!("Audit: cancelled {} operation {} by tenant {} (started at {})", info.kind, id, tenant, info.started_at);
+        Some(info)
+    }
+
+    /// Operations started, completed and cancelled, by kind
+    pub fn counts(&self) -> BTreeMap<OperationKind, OperationCounts> {
+        self.counts.lock().unwrap().clone()
+    }
+
+    fn finish(&self, id: Uuid, kind: OperationKind) {
+        let removed = self.operations.lock().unwrap().remove(&id);
+        if removed.is_some_and(|tracked| !tracked.info.cancelled) {
+            self.counts.lock().unwrap().entry(kind).or_default().completed += 1;
+        }
+    }
+}
+
+/// An operation being tracked, until dropped
+pub struct Operation {
+    registry: Arc<OperationRegistry>,
+    id: Uuid,
+    tenant: TenantId,
+    kind: OperationKind,
+    cancelled: watch::Receiver<bool>,
+}
+
+impl Operation {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Run the operation's work, dropping it at its next await if the
+    /// operation is cancelled first
+    pub async fn run<F: Future>(mut self, work: F) -> Result<F::Output, CoreError> {
+        tokio::select! {
+            output = work => Ok(output),
+            _ = self.cancelled.wait_for(|cancelled| *cancelled) => {
+                Err(CoreError::Cancelled(format!("{} operation {} of tenant {} was cancelled", self.kind, self.id, self.tenant)))
+            }
+        }
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        self.registry.finish(self.id, self.kind);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_operation() {
+        let registry = Arc::new(OperationRegistry::new());
+        let tenant = TenantId::new("acme");
+        let operation = registry.start(&tenant, OperationKind::Query, None).unwrap();
+        let id = operation.id();
+
+        assert!(registry.start(&tenant, OperationKind::Query, Some(id)).is_err());
+        assert_eq!(registry.list(&tenant).len(), 1);
+        assert!(registry.cancel(&TenantId::new("other"), id).is_none());
+
+        let running = tokio::spawn(operation.run(tokio::time::sleep(Duration::from_secs(60))));
+        let info = registry.cancel(&tenant, id).unwrap();
+        assert!(info.cancelled);
+        assert!(matches!(running.await.unwrap(), Err(CoreError::Cancelled(_))));
+        assert!(registry.get(&tenant, id).is_none());
+
+        let counts = registry.counts()[&OperationKind::Query];
+        assert_eq!(counts, OperationCounts { started: 1, completed: 0, cancelled: 1 });
+    }
+
+    #[tokio::test]
+    async fn test_operation_completes() {
+        let registry = Arc::new(OperationRegistry::new());
+        let tenant = TenantId::new("acme");
+        let operation = registry.start(&tenant, OperationKind::Export, None).unwrap();
+        let id = operation.id();
+
+        assert_eq!(operation.run(async { 42 }).await.unwrap(), 42);
+        assert!(registry.cancel(&tenant, id).is_none());
+        assert_eq!(registry.counts()[&OperationKind::Export].completed, 1);
+    }
+}
@@ -0,0 +1,125 @@
+//! Page-at-a-time reads of structured queries
+//!
+//! Used by API v2 to serve node and relationship listings in pages. A page
+//! is fetched with one extra result, which tells whether another page
+//! follows without counting every match.
+
+use crate::types::{GraphQuery, OrderBy, SortField};
+use serde::{Deserialize, Serialize};
+
+/// Results per page when the request names no limit
+pub const DEFAULT_PAGE_LIMIT: u32 = 100;
+
+/// Largest page served
+pub const MAX_PAGE_LIMIT: u32 = 1000;
+
+/// A page of a query's results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    /// Results skipped before the page
+    pub offset: u32,
+    /// Results on the page
+    pub limit: u32,
+}
+
+impl PageRequest {
+    /// Page `page` (1-based) of `limit` results; an explicit `offset` takes
+    /// precedence over `page`
+    pub fn new(page: Option<u32>, limit: Option<u32>, offset: Option<u32>) -> Self {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+        let page = page.unwrap_or(1).max(1);
+        Self {
+            offset: offset.unwrap_or_else(|| (page - 1).saturating_mul(limit)),
+            limit,
+        }
+    }
+
+    /// 1-based number of the page containing `offset`
+    pub fn page(&self) -> u32 {
+        self.offset / self.limit + 1
+    }
+
+    /// Restrict a structured query to this page, plus one result.
+    ///
+    /// Queries without a sort order are sorted by creation time so that
+    /// consecutive pages neither repeat nor skip results.
+    pub fn apply(&self, query: GraphQuery) -> Result<GraphQuery, String> {
+        let (offset, limit) = (Some(self.offset), Some(self.limit + 1));
+        match query {
+            GraphQuery::FindNodes { labels, properties, order_by, .. } => Ok(GraphQuery::FindNodes {
+                labels,
+                properties,
+                order_by: stable(order_by),
+                offset,
+                limit,
+            }),
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, collapse, .. } => {
+                Ok(GraphQuery::FindRelationships {
+                    from_node_id,
+                    to_node_id,
+                    relationship_types,
+                    valid_at,
+                    order_by: stable(order_by),
+                    offset,
+                    limit,
+                    collapse,
+                })
+            }
+            GraphQuery::AsOfQuery { base_query, as_of_time } => Ok(GraphQuery::AsOfQuery {
+                base_query: Box::new(self.apply(*base_query)?),
+                as_of_time,
+            }),
+            GraphQuery::Raw { .. } => Err("Raw queries cannot be paginated".to_string()),
+        }
+    }
+
+    /// Trim the results of an applied query to the page; returns whether
+    /// another page follows
+    pub fn finish<T>(&self, results: &mut Vec<T>) -> bool {
+        let has_next = results.len() > self.limit as usize;
+        results.truncate(self.limit as usize);
+        has_next
+    }
+}
+
+fn stable(order_by: Vec<OrderBy>) -> Vec<OrderBy> {
+    if order_by.is_empty() {
+        vec![OrderBy::asc(SortField::CreatedAt)]
+    } else {
+        order_by
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_page_request() {
+        let request = PageRequest::new(Some(3), Some(20), None);
+        assert_eq!((request.offset, request.limit, request.page()), (40, 20, 3));
+        assert_eq!(PageRequest::new(None, Some(5000), Some(7)), PageRequest { offset: 7, limit: MAX_PAGE_LIMIT });
+
+        let query = GraphQuery::FindNodes {
+            labels: vec!["Person".to_string()],
+            properties: HashMap::new(),
+            order_by: vec![],
+            offset: None,
+            limit: None,
+        };
+        let GraphQuery::FindNodes { order_by, offset, limit, .. } = request.apply(query).unwrap() else {
+            panic!("expected a node query");
+        };
+        assert_eq!(order_by, vec![OrderBy::asc(SortField::CreatedAt)]);
+        assert_eq!((offset, limit), (Some(40), Some(21)));
+
+        let mut results: Vec<u32> = (0..21).collect();
+        assert!(request.finish(&mut results));
+        assert_eq!(results.len(), 20);
+        assert!(!request.finish(&mut results));
+
+        let raw = GraphQuery::Raw { query: "MATCH (n) RETURN n".to_string(), params: HashMap::new() };
+        assert!(request.apply(raw).is_err());
+    }
+}
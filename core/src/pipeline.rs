@@ -1,14 +1,20 @@
 //! Request processing pipeline implementation
 
 use crate::prelude::*;
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 use tracing::{debug, error, info, warn};
 
 /// Pipeline stages
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum PipelineStage {
+    /// Runs over every request as received, before it is routed
+    Verification,
     PreOperation,
+    /// Runs over extraction inputs before they reach the LLM connector
+    PreExtraction,
     Operation,
     PostOperation,
 }
@@ -16,30 +22,131 @@ pub enum PipelineStage {
 impl std::fmt::Display for PipelineStage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            PipelineStage::Verification => write!(f, "verification"),
             PipelineStage::PreOperation => write!(f, "pre-operation"),
+            PipelineStage::PreExtraction => write!(f, "pre-extraction"),
             PipelineStage::Operation => write!(f, "operation"),
             PipelineStage::PostOperation => write!(f, "post-operation"),
         }
     }
 }
 
-/// Pipeline configuration
-#[derive(Debug, Clone)]
+/// A plugin in a configured pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginSpec {
+    /// Name the plugin is registered under in the `PluginRegistry`
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Plugin-specific parameters, passed to `PipelinePlugin::init`
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Pipeline configuration: ordered plugins by stage
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PipelineConfig {
-    pub stages: HashMap<PipelineStage, Vec<PluginConfig>>,
+    pub stages: HashMap<PipelineStage, Vec<PluginSpec>>,
 }
 
-impl Default for PipelineConfig {
-    fn default() -> Self {
-        Self {
-            stages: HashMap::new(),
+/// Pipelines of tenants that do not use the adapter's default one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantPipelines {
+    /// Pipelines by tenant ID. A stage listed for a tenant replaces the
+    /// default plugins of that stage; other stages keep them.
+    pub tenants: HashMap<String, PipelineConfig>,
+}
+
+/// A plugin of the default pipeline and whether it runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginState {
+    pub stage: PipelineStage,
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Stages in the order a request goes through them
+const STAGE_ORDER: [PipelineStage; 5] = [
+    PipelineStage::Verification,
+    PipelineStage::PreOperation,
+    PipelineStage::PreExtraction,
+    PipelineStage::Operation,
+    PipelineStage::PostOperation,
+];
+
+/// Plugins of a pipeline by stage
+pub type StagePlugins = HashMap<PipelineStage, Vec<Arc<dyn PipelinePlugin>>>;
+
+type PluginFactory = Box<dyn Fn() -> Box<dyn PipelinePlugin> + Send + Sync>;
+
+/// Plugins that pipelines can be composed of, by name
+pub struct PluginRegistry {
+    factories: HashMap<String, PluginFactory>,
+}
+
+impl PluginRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self { factories: HashMap::new() }
+    }
+
+    /// Create a registry of the built-in plugins, each under its plugin name
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("RequestLogging", || Box::new(RequestLoggingPlugin::new()));
+        registry.register("TenantValidation", || Box::new(TenantValidationPlugin::new()));
+        registry.register("AuditTrail", || Box::new(AuditTrailPlugin::new()));
+        registry.register("ExtractionSafety", || Box::new(crate::safety::ExtractionSafetyPlugin::new()));
+        registry
+    }
+
+    /// Register a plugin under a name, replacing any registered before
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn() -> Box<dyn PipelinePlugin> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Box::new(factory));
+    }
+
+    /// Create and initialize the enabled plugins of a configuration
+    pub async fn build(&self, config: &PipelineConfig) -> Result<StagePlugins, PipelineError> {
+        let mut stages = StagePlugins::new();
+        for (stage, specs) in &config.stages {
+            let mut plugins: Vec<Arc<dyn PipelinePlugin>> = Vec::new();
+            for spec in specs.iter().filter(|spec| spec.enabled) {
+                let factory = self.factories.get(&spec.name)
+                    .ok_or_else(|| PipelineError::PluginNotFound(spec.name.clone()))?;
+                let mut plugin = factory();
+                let plugin_config = PluginConfig { enabled: true, config: spec.config.clone() };
+                plugin.init(plugin_config).await
+                    .map_err(|e| PipelineError::PluginInitFailed(format!("{}: {}", spec.name, e)))?;
+                plugins.push(Arc::from(plugin));
+            }
+            stages.insert(stage.clone(), plugins);
         }
+        Ok(stages)
+    }
+}
+
+impl Default for PluginRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
     }
 }
 
 /// The main pipeline runner that executes plugins in stages
 pub struct PipelineRunner {
-    plugins: HashMap<PipelineStage, Vec<Arc<dyn PipelinePlugin>>>,
+    plugins: StagePlugins,
+    /// Stages that tenants run with plugins of their own
+    tenant_plugins: RwLock<HashMap<TenantId, StagePlugins>>,
+    /// Names of plugins skipped in every pipeline
+    disabled: RwLock<HashSet<String>>,
 }
 
 impl PipelineRunner {
@@ -47,6 +154,8 @@ impl PipelineRunner {
     pub fn new() -> Self {
         Self {
             plugins: HashMap::new(),
+            tenant_plugins: RwLock::new(HashMap::new()),
+            disabled: RwLock::new(HashSet::new()),
         }
     }
     
@@ -55,6 +164,73 @@ impl PipelineRunner {
         self.plugins.entry(stage).or_insert_with(Vec::new).push(plugin);
     }
     
+    /// Run a tenant's requests through its own plugins for the given
+    /// stages, replacing any set before; other stages run the default plugins
+    pub fn set_tenant_plugins(&self, tenant: TenantId, plugins: StagePlugins) {
+        self.tenant_plugins.write().unwrap().insert(tenant, plugins);
+    }
+    
+    /// Return a tenant to the default plugins
+    pub fn remove_tenant_plugins(&self, tenant: &TenantId) {
+        self.tenant_plugins.write().unwrap().remove(tenant);
+    }
+    
+    /// Build and set the configured pipelines of tenants
+    pub async fn compose_tenants(&self, pipelines: &TenantPipelines, registry: &PluginRegistry) -> Result<(), PipelineError> {
+        for (tenant, config) in &pipelines.tenants {
+            let plugins = registry.build(config).await?;
+            info!("Composed pipeline of tenant {} with {} stage(s)", tenant, plugins.len());
+            self.set_tenant_plugins(TenantId::new(tenant), plugins);
+        }
+        Ok(())
+    }
+    
+    /// Skip or run again the plugins of a name, in the default pipeline and
+    /// in tenants' own, while serving
+    pub fn set_plugin_enabled(&self, name: &str, enabled: bool) -> Result<(), PipelineError> {
+        let named = |plugins: &StagePlugins| plugins.values().flatten().any(|plugin| plugin.name() == name);
+        let known = named(&self.plugins) || self.tenant_plugins.read().unwrap().values().any(named);
+        if !known {
+            return Err(PipelineError::PluginNotFound(name.to_string()));
+        }
+
+        let mut disabled = self.disabled.write().unwrap();
+        if enabled {
+            disabled.remove(name);
+        } else {
+            disabled.insert(name.to_string());
+        }
+        info!("Plugin {} {}", name, if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
+    /// Plugins of the default pipeline, in the order they run
+    pub fn plugin_states(&self) -> Vec<PluginState> {
+        let disabled = self.disabled.read().unwrap();
+        STAGE_ORDER.iter()
+            .flat_map(|stage| self.plugins.get(stage).into_iter().flatten().map(move |plugin| (stage, plugin)))
+            .map(|(stage, plugin)| PluginState {
+                stage: stage.clone(),
+                name: plugin.name().to_string(),
+                enabled: !disabled.contains(plugin.name()),
+            })
+            .collect()
+    }
+
+    /// Plugins a request of `tenant` runs for a stage
+    fn plugins_for(&self, tenant: Option<&TenantId>, stage: &PipelineStage) -> Vec<Arc<dyn PipelinePlugin>> {
+        let tenant_plugins = tenant.and_then(|tenant| {
+            self.tenant_plugins.read().unwrap().get(tenant).and_then(|stages| stages.get(stage)).cloned()
+        });
+        let plugins = tenant_plugins.or_else(|| self.plugins.get(stage).cloned()).unwrap_or_default();
+
+        let disabled = self.disabled.read().unwrap();
+        if disabled.is_empty() {
+            return plugins;
+        }
+        plugins.into_iter().filter(|plugin| !disabled.contains(plugin.name())).collect()
+    }
+    
     /// Execute the pipeline for a request
     pub async fn execute(&self, mut ctx: RequestContext) -> Result<RequestContext, CoreError> {
         debug!("Starting pipeline execution for request {}", ctx.request_id);
@@ -83,15 +259,73 @@ impl PipelineRunner {
         Ok(ctx)
     }
     
+    /// Run the pre-extraction stage over an extraction request, returning the
+    /// possibly sanitized context and what the plugins did, including any
+    /// warnings they recorded
+    pub async fn prepare_extraction(
+        &self,
+        tenant: &TenantId,
+        context: ExtractionContext,
+    ) -> Result<(ExtractionContext, OperationMetadata), CoreError> {
+        if self.plugins_for(Some(tenant), &PipelineStage::PreExtraction).is_empty() {
+            return Ok((context, OperationMetadata::default()));
+        }
+        
+        let mut ctx = RequestContext::new("EXTRACT".to_string(), format!("/llm/{}/extract", tenant));
+        ctx.tenant_id = Some(tenant.clone());
+        ctx.core_operation_input = Some(serde_json::to_value(&context)?);
+        
+        let mut ctx = self.execute_stage(PipelineStage::PreExtraction, ctx).await?;
+        if let Some(error) = ctx.error {
+            return Err(CoreError::Pipeline(PipelineError::PipelineHalted(error)));
+        }
+        
+        let warnings: Vec<String> = ctx
+            .get_attribute(crate::safety::EXTRACTION_WARNINGS_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default();
+        ctx.operation.warnings.extend(warnings);
+        let context = match ctx.core_operation_input {
+            Some(input) => serde_json::from_value(input)?,
+            None => context,
+        };
+        
+        Ok((context, ctx.operation))
+    }
+    
+    /// Run the verification stage over a request as received. Plugins that
+    /// reject the request fail it with `AuthError::InvalidSignature`.
+    pub async fn verify_request(&self, ctx: RequestContext) -> Result<OperationMetadata, CoreError> {
+        let ctx = self.execute_stage(PipelineStage::Verification, ctx).await?;
+        match ctx.error {
+            Some(error) => Err(CoreError::Auth(AuthError::InvalidSignature(error))),
+            None => Ok(ctx.operation),
+        }
+    }
+    
     /// Execute plugins for a specific stage
     async fn execute_stage(&self, stage: PipelineStage, mut ctx: RequestContext) -> Result<RequestContext, CoreError> {
-        if let Some(plugins) = self.plugins.get(&stage) {
+        let plugins = self.plugins_for(ctx.tenant_id.as_ref(), &stage);
+        if !plugins.is_empty() {
             debug!("Executing {} plugins for stage {}", plugins.len(), stage);
             
             for (index, plugin) in plugins.iter().enumerate() {
                 debug!("Executing plugin {} ({}) for stage {}", plugin.name(), index + 1, stage);
                 
-                match plugin.call(&mut ctx).await {
+                let started = std::time::Instant::now();
+                let outcome = plugin.call(&mut ctx).await;
+                ctx.operation.plugins.push(PluginRun {
+                    plugin: plugin.name().to_string(),
+                    stage: stage.to_string(),
+                    duration_us: started.elapsed().as_micros() as u64,
+                    outcome: match outcome {
+                        PluginOutcome::Continue => "continue",
+                        PluginOutcome::Halt => "halt",
+                        PluginOutcome::HaltWithError(_) => "error",
+                    }.to_string(),
+                });
+                
+                match outcome {
                     PluginOutcome::Continue => {
                         debug!("Plugin {} returned Continue", plugin.name());
                         continue;
@@ -112,7 +346,7 @@ impl PipelineRunner {
         Ok(ctx)
     }
     
-    /// Get the number of plugins registered for a stage
+    /// Get the number of default plugins registered for a stage
     pub fn plugin_count(&self, stage: &PipelineStage) -> usize {
         self.plugins.get(stage).map_or(0, |plugins| plugins.len())
     }
@@ -259,6 +493,16 @@ impl PipelinePlugin for TenantValidationPlugin {
                 return PluginOutcome::Halt;
             }
         }
+
+        // Tenants named by requests must be in normalized form, and not one of
+        // the reserved tenants the server keeps for itself
+        if let Some(tenant) = &ctx.tenant_id {
+            if let Err(e) = tenant.validate().and_then(|()| TenantId::parse(tenant.as_str()).map(drop)) {
+                warn!("Request to {} names invalid tenant ID '{}': {}", ctx.path, tenant, e);
+                ctx.error = Some(format!("Invalid tenant ID '{}': {}", tenant, e));
+                return PluginOutcome::Halt;
+            }
+        }
         
         PluginOutcome::Continue
     }
@@ -355,6 +599,104 @@ mod tests {
         assert_eq!(test_plugin.call_count(), 0);
     }
 
+    #[tokio::test]
+    async fn test_tenant_validation() {
+        let plugin = &TenantValidationPlugin::new();
+        let call = |tenant: Option<&str>| {
+            let mut ctx = RequestContext::new("GET".to_string(), "/v1/graph/x/nodes".to_string());
+            ctx.tenant_id = tenant.map(TenantId::new);
+            async move {
+                let outcome = plugin.call(&mut ctx).await;
+                (matches!(outcome, PluginOutcome::Continue), ctx.error)
+            }
+        };
+
+        assert_eq!(call(Some("acme-eu_1")).await, (true, None));
+        assert_eq!(call(Some("acme~session~6f1c2a7e-8a57-4d43-9b8e-2d9a7c1b3e00")).await, (true, None));
+        let invalid = [
+            None, Some(""), Some("Acme"), Some("../acme"), Some("acme\u{202e}"), Some("-acme"),
+            Some(crate::lineage::LINEAGE_TENANT), Some(crate::doctor::DOCTOR_TENANT),
+        ];
+        for invalid in invalid {
+            let (passed, error) = call(invalid).await;
+            assert!(!passed && error.is_some(), "{:?} passed", invalid);
+        }
+
+        assert_eq!(TenantId::parse(" Acme-EU "), Ok(TenantId::new("acme-eu")));
+        assert_eq!(TenantId::parse(&"a".repeat(65)), Err(TenantIdError::TooLong { length: 65, max: MAX_TENANT_ID_LEN }));
+        assert_eq!("acme/one".parse::<TenantId>(), Err(TenantIdError::InvalidCharacter('/')));
+        assert_eq!(TenantId::parse("_acme"), Err(TenantIdError::InvalidStart('_')));
+        assert_eq!(TenantId::parse("  "), Err(TenantIdError::Empty));
+    }
+
+    #[tokio::test]
+    async fn test_operation_metadata() {
+        let mut runner = PipelineRunner::new();
+        runner.register_plugin(PipelineStage::PreOperation, Arc::new(TestPlugin::new("First")));
+        runner.register_plugin(PipelineStage::PostOperation, Arc::new(TestPlugin::new("Last")));
+        
+        let ctx = runner.execute(RequestContext::new("GET".to_string(), "/test".to_string())).await.unwrap();
+        let runs: Vec<(&str, &str, &str)> = ctx.operation.plugins
+            .iter()
+            .map(|run| (run.plugin.as_str(), run.stage.as_str(), run.outcome.as_str()))
+            .collect();
+        assert_eq!(runs, vec![("First", "pre-operation", "continue"), ("Last", "post-operation", "continue")]);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_pipelines() {
+        let mut runner = PipelineRunner::new();
+        let default_plugin = Arc::new(TestPlugin::new("Default"));
+        runner.register_plugin(PipelineStage::PreOperation, default_plugin.clone());
+
+        let pipelines: TenantPipelines = serde_json::from_value(serde_json::json!({
+            "tenants": {
+                "acme": {"stages": {"pre-operation": [{"name": "TenantValidation"}, {"name": "AuditTrail", "enabled": false}]}},
+                "quiet": {"stages": {"pre-operation": []}}
+            }
+        })).unwrap();
+        runner.compose_tenants(&pipelines, &PluginRegistry::with_builtins()).await.unwrap();
+
+        let run = |tenant: &str| {
+            let mut ctx = RequestContext::new("GET".to_string(), "/graph/test".to_string());
+            ctx.tenant_id = Some(TenantId::new(tenant));
+            runner.execute(ctx)
+        };
+        let plugins = |ctx: RequestContext| ctx.operation.plugins.into_iter().map(|run| run.plugin).collect::<Vec<_>>();
+        assert_eq!(plugins(run("acme").await.unwrap()), vec!["TenantValidation"]);
+        assert!(plugins(run("quiet").await.unwrap()).is_empty());
+        assert_eq!(plugins(run("other").await.unwrap()), vec!["Default"]);
+        assert_eq!(default_plugin.call_count(), 1);
+
+        runner.remove_tenant_plugins(&TenantId::new("acme"));
+        assert_eq!(plugins(run("acme").await.unwrap()), vec!["Default"]);
+
+        let unknown = PipelineConfig {
+            stages: HashMap::from([(PipelineStage::PostOperation, vec![PluginSpec { name: "Missing".to_string(), enabled: true, config: serde_json::Value::Null }])]),
+        };
+        assert!(matches!(PluginRegistry::with_builtins().build(&unknown).await, Err(PipelineError::PluginNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_disable_plugins() {
+        let mut runner = PipelineRunner::new();
+        let first = Arc::new(TestPlugin::new("First"));
+        runner.register_plugin(PipelineStage::PostOperation, Arc::new(TestPlugin::new("Last")));
+        runner.register_plugin(PipelineStage::PreOperation, first.clone());
+
+        runner.set_plugin_enabled("First", false).unwrap();
+        let ctx = runner.execute(RequestContext::new("GET".to_string(), "/test".to_string())).await.unwrap();
+        assert_eq!(ctx.operation.plugins.len(), 1);
+        assert_eq!(first.call_count(), 0);
+        let states: Vec<_> = runner.plugin_states().into_iter().map(|state| (state.name, state.enabled)).collect();
+        assert_eq!(states, vec![("First".to_string(), false), ("Last".to_string(), true)]);
+
+        runner.set_plugin_enabled("First", true).unwrap();
+        runner.execute(RequestContext::new("GET".to_string(), "/test".to_string())).await.unwrap();
+        assert_eq!(first.call_count(), 1);
+        assert!(matches!(runner.set_plugin_enabled("Missing", false), Err(PipelineError::PluginNotFound(_))));
+    }
+
     #[test]
     fn test_request_context() {
         let mut ctx = RequestContext::new("POST".to_string(), "/api/test".to_string());
@@ -0,0 +1,290 @@
+//! Progress of long-running operations
+//!
+//! Long extractions and big queries give no feedback until they complete.
+//! Work run as an operation reports to a [`ProgressTracker`] instead: its
+//! counts, in the [`JobProgress`] that maintenance jobs report, results
+//! available before it completes, and finally its result or error. Code
+//! deep inside the work, such as chunked extraction, reports through the
+//! tracker of the current task, set with [`with_progress`].
+//!
+//! A [`ProgressHub`] numbers each operation's events and keeps the last
+//! `max_events` of them, so a subscriber that reconnects resumes after the
+//! last event it saw. Events of finished operations are kept for
+//! `retain_secs`.
+
+use crate::admin::JobProgress;
+use crate::types::TenantId;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+tokio::task_local! {
+    static TRACKER: ProgressTracker;
+}
+
+/// Run a future with the tracker its progress is reported to
+pub async fn with_progress<F: Future>(tracker: ProgressTracker, future: F) -> F::Output {
+    TRACKER.scope(tracker, future).await
+}
+
+/// Update the counts of the current task's operation; does nothing outside
+/// [`with_progress`]
+pub fn report_progress(update: impl FnOnce(&mut JobProgress)) {
+    let _ = TRACKER.try_with(|tracker| tracker.update(update));
+}
+
+/// Configuration of a progress hub
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressConfig {
+    /// Events kept per operation for subscribers that resume
+    pub max_events: usize,
+    /// How long events of a finished operation are kept, in seconds
+    pub retain_secs: u64,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self { max_events: 256, retain_secs: 300 }
+    }
+}
+
+/// Something that happened in an operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// Counts of the work done so far
+    Progress { progress: JobProgress },
+    /// Results available before the operation completes, e.g. a page of rows
+    Partial { results: Value },
+    Completed { result: Value },
+    Failed { error: String },
+}
+
+impl ProgressEvent {
+    fn is_final(&self) -> bool {
+        matches!(self, ProgressEvent::Completed { .. } | ProgressEvent::Failed { .. })
+    }
+}
+
+/// An event of an operation, numbered from 1 in the order it happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressUpdate {
+    pub operation: Uuid,
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: ProgressEvent,
+}
+
+/// Events of an operation a subscriber missed, followed by those to come
+pub struct ProgressSubscription {
+    /// Kept events after the one the subscriber saw last; the first may not
+    /// follow it if events were dropped meanwhile
+    pub missed: Vec<ProgressUpdate>,
+    /// Events from now on; closed once the operation finished
+    pub live: broadcast::Receiver<ProgressUpdate>,
+    /// Whether the operation already finished, so nothing more will come
+    pub finished: bool,
+}
+
+struct ProgressLog {
+    tenant: TenantId,
+    events: VecDeque<ProgressUpdate>,
+    seq: u64,
+    progress: JobProgress,
+    /// Dropped once the operation finished, closing the receivers
+    sender: Option<broadcast::Sender<ProgressUpdate>>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+/// Progress of operations, by operation ID
+pub struct ProgressHub {
+    config: ProgressConfig,
+    logs: Mutex<HashMap<Uuid, ProgressLog>>,
+}
+
+impl Default for ProgressHub {
+    fn default() -> Self {
+        Self::new(ProgressConfig::default())
+    }
+}
+
+impl ProgressHub {
+    pub fn new(config: ProgressConfig) -> Self {
+        Self { config, logs: Mutex::new(HashMap::new()) }
+    }
+
+    /// Start tracking the progress of an operation of the tenant. Tracking
+    /// an operation again starts its log over.
+    pub fn track(self: &Arc<Self>, tenant: &TenantId, operation: Uuid) -> ProgressTracker {
+        let now = Utc::now();
+        let mut logs = self.logs.lock().unwrap();
+        self.prune(&mut logs, now);
+        let (sender, _) = broadcast::channel(self.config.max_events.max(1));
+        logs.insert(operation, ProgressLog {
+            tenant: tenant.clone(),
+            events: VecDeque::new(),
+            seq: 0,
+            progress: JobProgress {
+                tenant: tenant.clone(),
+                processed: 0,
+                total: None,
+                changed: 0,
+                failed: 0,
+                last_error: None,
+                finished: false,
+                started_at: now,
+                updated_at: now,
+            },
+            sender: Some(sender),
+            finished_at: None,
+        });
+        ProgressTracker { hub: self.clone(), operation }
+    }
+
+    /// Events of an operation of the tenant after `after`, or all kept
+    /// events, and those to come; `None` if its events are not kept
+    pub fn subscribe(&self, tenant: &TenantId, operation: Uuid, after: Option<u64>) -> Option<ProgressSubscription> {
+        let mut logs = self.logs.lock().unwrap();
+        self.prune(&mut logs, Utc::now());
+        let log = logs.get(&operation).filter(|log| &log.tenant == tenant)?;
+        let after = after.unwrap_or(0);
+        Some(ProgressSubscription {
+            missed: log.events.iter().filter(|update| update.seq > after).cloned().collect(),
+            live: match &log.sender {
+                Some(sender) => sender.subscribe(),
+                None => broadcast::channel(1).1,
+            },
+            finished: log.finished_at.is_some(),
+        })
+    }
+
+    /// Counts an operation of the tenant last reported
+    pub fn progress(&self, tenant: &TenantId, operation: Uuid) -> Option<JobProgress> {
+        self.logs.lock().unwrap().get(&operation)
+            .filter(|log| &log.tenant == tenant)
+            .map(|log| log.progress.clone())
+    }
+
+    fn publish(&self, operation: Uuid, event: impl FnOnce(&mut ProgressLog) -> ProgressEvent) {
+        let mut logs = self.logs.lock().unwrap();
+        let Some(log) = logs.get_mut(&operation).filter(|log| log.finished_at.is_none()) else {
+            return;
+        };
+        let event = event(log);
+        let now = Utc::now();
+        if event.is_final() {
+            log.finished_at = Some(now);
+        }
+        log.seq += 1;
+        let update = ProgressUpdate { operation, seq: log.seq, at: now, event };
+        if log.events.len() >= self.config.max_events.max(1) {
+            log.events.pop_front();
+        }
+        log.events.push_back(update.clone());
+        if let Some(sender) = &log.sender {
+            // Nobody may be listening
+            let _ = sender.send(update);
+        }
+        if log.finished_at.is_some() {
+            log.sender = None;
+        }
+    }
+
+    fn prune(&self, logs: &mut HashMap<Uuid, ProgressLog>, now: DateTime<Utc>) {
+        let retain = ChronoDuration::seconds(self.config.retain_secs as i64);
+        logs.retain(|_, log| log.finished_at.is_none_or(|finished_at| now - finished_at < retain));
+    }
+}
+
+/// Reports the progress of one operation
+#[derive(Clone)]
+pub struct ProgressTracker {
+    hub: Arc<ProgressHub>,
+    operation: Uuid,
+}
+
+impl ProgressTracker {
+    pub fn operation(&self) -> Uuid {
+        self.operation
+    }
+
+    /// Update the operation's counts
+    pub fn update(&self, update: impl FnOnce(&mut JobProgress)) {
+        self.hub.publish(self.operation, |log| {
+            update(&mut log.progress);
+            log.progress.updated_at = Utc::now();
+            ProgressEvent::Progress { progress: log.progress.clone() }
+        });
+    }
+
+    /// Report results available before the operation completes
+    pub fn partial(&self, results: Value) {
+        self.hub.publish(self.operation, |_| ProgressEvent::Partial { results });
+    }
+
+    /// Report the operation's result; later reports are ignored
+    pub fn complete(&self, result: Value) {
+        self.hub.publish(self.operation, |log| {
+            log.progress.finished = true;
+            ProgressEvent::Completed { result }
+        });
+    }
+
+    /// Report the error the operation failed with; later reports are ignored
+    pub fn fail(&self, error: impl fmt::Display) {
+        self.hub.publish(self.operation, |log| {
+            log.progress.last_error = Some(error.to_string());
+            ProgressEvent::Failed { error: error.to_string() }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_subscriber_resumes_after_last_seen_event() {
+        let hub = Arc::new(ProgressHub::new(ProgressConfig { max_events: 3, retain_secs: 60 }));
+        let tenant = TenantId::new("acme");
+        let operation = Uuid::new_v4();
+        let tracker = hub.track(&tenant, operation);
+
+        with_progress(tracker.clone(), async {
+            report_progress(|progress| progress.total = Some(2));
+            report_progress(|progress| progress.processed += 1);
+        }).await;
+        tracker.partial(json!([{"id": 1}]));
+        assert!(hub.subscribe(&TenantId::new("other"), operation, None).is_none());
+
+        // Only the last three events are kept
+        let mut subscription = hub.subscribe(&tenant, operation, Some(1)).unwrap();
+        let seqs: Vec<u64> = subscription.missed.iter().map(|update| update.seq).collect();
+        assert_eq!(seqs, vec![2, 3]);
+        assert!(!subscription.finished);
+
+        tracker.update(|progress| progress.processed += 1);
+        tracker.complete(json!({"rows": 2}));
+        tracker.fail("too late");
+        let update = subscription.live.recv().await.unwrap();
+        assert!(matches!(update.event, ProgressEvent::Progress { ref progress } if progress.processed == 2));
+        let update = subscription.live.recv().await.unwrap();
+        assert_eq!((update.seq, update.event), (5, ProgressEvent::Completed { result: json!({"rows": 2}) }));
+        assert!(subscription.live.recv().await.is_err());
+
+        let resumed = hub.subscribe(&tenant, operation, Some(5)).unwrap();
+        assert!(resumed.missed.is_empty() && resumed.finished);
+        assert!(hub.progress(&tenant, operation).unwrap().finished);
+        // Progress reported outside an operation goes nowhere
+        report_progress(|progress| progress.processed += 1);
+    }
+}
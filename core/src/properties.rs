@@ -0,0 +1,159 @@
+//! System property naming and reserved-key protection
+
+use crate::errors::GraphError;
+use serde::{Deserialize, Serialize};
+
+/// Default prefix for system-managed properties (e.g. `_tenant_id`)
+pub const DEFAULT_SYSTEM_PREFIX: &str = "_";
+
+/// Unprefixed property names managed by storage backends on nodes and edges
+const UNPREFIXED_SYSTEM_KEYS: &[&str] = &[
+    "system_id",
+    "id_alias",
+    "created_at",
+    "updated_at",
+    "valid_from",
+    "valid_to",
+    "transaction_start_time",
+    "transaction_end_time",
+];
+
+/// How client-supplied properties that collide with system keys are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReservedKeyPolicy {
+    /// Fail the write with `GraphError::ReservedProperty`
+    #[default]
+    Reject,
+    /// Silently drop the offending keys and continue
+    Strip,
+}
+
+/// Naming of system-managed properties and how collisions are handled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemProperties {
+    /// Prefix for system properties such as `tenant_id`; any client key with
+    /// this prefix is treated as reserved
+    pub prefix: String,
+    /// What to do when a client sends a reserved key
+    pub policy: ReservedKeyPolicy,
+}
+
+impl Default for SystemProperties {
+    fn default() -> Self {
+        Self {
+            prefix: DEFAULT_SYSTEM_PREFIX.to_string(),
+            policy: ReservedKeyPolicy::default(),
+        }
+    }
+}
+
+impl SystemProperties {
+    /// Create a configuration with the given prefix and the default policy
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the reserved key policy
+    pub fn with_policy(mut self, policy: ReservedKeyPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Name of a prefixed system property, e.g. `key("tenant_id")` -> `_tenant_id`
+    pub fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// Property holding the owning tenant
+    pub fn tenant_key(&self) -> String {
+        self.key("tenant_id")
+    }
+
+    /// Property holding the alias namespace of a node
+    pub fn alias_namespace_key(&self) -> String {
+        self.key("alias_namespace")
+    }
+
+    /// Property marking edges in a store's current-state view
+    pub fn current_key(&self) -> String {
+        self.key("current")
+    }
+
+    /// Whether a property name is reserved for system use
+    pub fn is_reserved(&self, key: &str) -> bool {
+        UNPREFIXED_SYSTEM_KEYS.contains(&key)
+            || (!self.prefix.is_empty() && key.starts_with(&self.prefix))
+    }
+
+    /// Validate client-supplied properties against the reserved keys.
+    ///
+    /// With `Reject` the first reserved key fails the call; with `Strip` all
+    /// reserved keys are removed from `props` in place.
+    pub fn sanitize(&self, props: &mut serde_json::Value) -> Result<(), GraphError> {
+        let Some(map) = props.as_object_mut() else {
+            return Ok(());
+        };
+
+        let reserved: Vec<String> = map.keys().filter(|k| self.is_reserved(k)).cloned().collect();
+        if reserved.is_empty() {
+            return Ok(());
+        }
+
+        match self.policy {
+            ReservedKeyPolicy::Reject => Err(GraphError::ReservedProperty(format!(
+                "Properties use reserved system keys: {}",
+                reserved.join(", ")
+            ))),
+            ReservedKeyPolicy::Strip => {
+                crate::warnings::report(format!("Removed properties with reserved system keys: {}", reserved.join(", ")));
+                for key in reserved {
+                    map.remove(&key);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reserved_keys() {
+        let system = SystemProperties::default();
+
+        assert!(system.is_reserved("_tenant_id"));
+        assert!(system.is_reserved("system_id"));
+        assert!(system.is_reserved("valid_from"));
+        assert!(!system.is_reserved("name"));
+        assert_eq!(system.tenant_key(), "_tenant_id");
+    }
+
+    #[test]
+    fn test_reject_policy() {
+        let system = SystemProperties::default();
+
+        let mut props = json!({"name": "Alice", "_tenant_id": "other"});
+        assert!(matches!(system.sanitize(&mut props), Err(GraphError::ReservedProperty(_))));
+
+        let mut props = json!({"name": "Alice"});
+        assert!(system.sanitize(&mut props).is_ok());
+    }
+
+    #[test]
+    fn test_strip_policy_with_custom_prefix() {
+        let system = SystemProperties::new("tm_").with_policy(ReservedKeyPolicy::Strip);
+        assert_eq!(system.tenant_key(), "tm_tenant_id");
+
+        let mut props = json!({"name": "Alice", "tm_tenant_id": "other", "system_id": "x", "_legacy": 1});
+        system.sanitize(&mut props).unwrap();
+
+        assert_eq!(props, json!({"name": "Alice", "_legacy": 1}));
+    }
+}
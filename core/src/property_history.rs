@@ -0,0 +1,151 @@
+//! History of a node property
+//!
+//! Nodes are rewritten in place, so how a property got its current value
+//! ("stage" of a deal going from `lead` to `won`) is only kept by stores that
+//! record a version of the node on every write. Stores hand those versions to
+//! [`build_property_history`], which turns them into the values the property
+//! took, each with the valid time it held and the transaction time the store
+//! believed it.
+
+use crate::types::Node;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A version of a node, as written at `valid_from` until replaced at `valid_to`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeVersion {
+    pub node: Node,
+    pub valid_from: DateTime<Utc>,
+    /// When the version was replaced, `None` while it is current
+    pub valid_to: Option<DateTime<Utc>>,
+    /// When the store recorded the version
+    pub transaction_start_time: DateTime<Utc>,
+    /// When the version was retracted; stores that only record writes as
+    /// they happen never retract one
+    pub transaction_end_time: Option<DateTime<Utc>>,
+}
+
+impl NodeVersion {
+    /// A version written at `at`, current until replaced
+    pub fn written(node: Node, at: DateTime<Utc>) -> Self {
+        Self { node, valid_from: at, valid_to: None, transaction_start_time: at, transaction_end_time: None }
+    }
+}
+
+/// A value a property held over an interval of valid time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyValue {
+    pub value: serde_json::Value,
+    pub valid_from: DateTime<Utc>,
+    /// When the value was replaced or removed, `None` if it still holds
+    pub valid_to: Option<DateTime<Utc>>,
+    pub transaction_start_time: DateTime<Utc>,
+    pub transaction_end_time: Option<DateTime<Utc>>,
+}
+
+/// Values a property of a node took, in order of valid time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyHistory {
+    pub node_id: Uuid,
+    pub key: String,
+    pub values: Vec<PropertyValue>,
+}
+
+impl PropertyHistory {
+    /// The value holding now, if the property is set
+    pub fn current(&self) -> Option<&serde_json::Value> {
+        self.values.iter()
+            .rev()
+            .find(|value| value.valid_to.is_none() && value.transaction_end_time.is_none())
+            .map(|value| &value.value)
+    }
+}
+
+/// Turn versions of a node into the history of its property `key`.
+/// Consecutive versions that leave the value unchanged extend one entry
+/// instead of adding one each; versions without the property leave a gap.
+/// Retracted versions are kept apart, each with its own transaction time.
+pub fn build_property_history(node_id: Uuid, key: &str, versions: impl IntoIterator<Item = NodeVersion>) -> PropertyHistory {
+    let mut versions: Vec<NodeVersion> = versions.into_iter().collect();
+    versions.sort_by_key(|version| (version.transaction_end_time.is_some(), version.valid_from, version.transaction_start_time));
+
+    let mut values: Vec<PropertyValue> = Vec::new();
+    let mut open = false;
+    for version in versions {
+        let Some(value) = version.node.props.get(key) else {
+            open = false;
+            continue;
+        };
+        match values.last_mut() {
+            // Extends the previous entry if it ends where this version starts
+            Some(last) if open
+                && version.transaction_end_time.is_none()
+                && last.value == *value
+                && last.valid_to == Some(version.valid_from) => {
+                last.valid_to = version.valid_to;
+            }
+            _ => values.push(PropertyValue {
+                value: value.clone(),
+                valid_from: version.valid_from,
+                valid_to: version.valid_to,
+                transaction_start_time: version.transaction_start_time,
+                transaction_end_time: version.transaction_end_time,
+            }),
+        }
+        open = version.transaction_end_time.is_none();
+    }
+    values.sort_by_key(|value| (value.valid_from, value.transaction_start_time));
+
+    PropertyHistory { node_id, key: key.to_string(), values }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap()
+    }
+
+    fn version(props: serde_json::Value, from: u32, to: Option<u32>) -> NodeVersion {
+        let mut version = NodeVersion::written(Node::new("Deal").with_props(props), at(from));
+        version.valid_to = to.map(at);
+        version
+    }
+
+    #[test]
+    fn test_build_property_history() {
+        let node = Uuid::new_v4();
+        let mut retracted = version(json!({"stage": "won"}), 3, Some(4));
+        retracted.transaction_end_time = Some(at(4));
+        let versions = vec![
+            version(json!({"stage": "negotiation", "amount": 10}), 2, Some(5)),
+            version(json!({"stage": "lead"}), 1, Some(2)),
+            retracted,
+            // Only the amount changes
+            version(json!({"stage": "negotiation", "amount": 12}), 5, Some(6)),
+            version(json!({"amount": 12}), 6, Some(8)),
+            version(json!({"stage": "won", "amount": 12}), 8, None),
+        ];
+
+        let history = build_property_history(node, "stage", versions.clone());
+        let stages: Vec<_> = history.values.iter()
+            .map(|value| (value.value.as_str().unwrap(), value.valid_from, value.valid_to, value.transaction_end_time))
+            .collect();
+        assert_eq!(stages, vec![
+            ("lead", at(1), Some(at(2)), None),
+            ("negotiation", at(2), Some(at(6)), None),
+            ("won", at(3), Some(at(4)), Some(at(4))),
+            ("won", at(8), None, None),
+        ]);
+        assert_eq!(history.current(), Some(&json!("won")));
+
+        let amounts = build_property_history(node, "amount", versions);
+        assert_eq!(amounts.values.len(), 2);
+        assert_eq!(amounts.values[1].valid_from, at(5));
+        assert!(build_property_history(node, "owner", Vec::new()).values.is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Fluent builder of graph queries
+//!
+//! `Query` builds the same `GraphQuery` values as writing them out by hand,
+//! without maps of JSON values or unused fields to fill in:
+//!
+//! ```
+//! use telamentis_core::query::Query;
+//!
+//! let query = Query::nodes().label("Person").prop_eq("name", "Alice").limit(10).build();
+//! ```
+//!
+//! Setters of optional parts also take an `Option`, so values parsed from
+//! user input can be passed through as they are.
+//!
+//! Every presentation adapter maps `GraphQuery` to its protocol, so a built
+//! query means the same over HTTP, gRPC and UDS.
+
+use crate::types::{Collapse, GraphQuery, OrderBy};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Entry point of the query builders
+pub struct Query;
+
+impl Query {
+    /// Find nodes
+    pub fn nodes() -> NodeQuery {
+        NodeQuery::default()
+    }
+
+    /// Find relationships
+    pub fn relationships() -> RelationshipQuery {
+        RelationshipQuery::default()
+    }
+
+    /// Run a store-specific query, e.g. Cypher
+    pub fn raw(query: impl Into<String>) -> RawQuery {
+        RawQuery { query: query.into(), params: HashMap::new() }
+    }
+}
+
+/// Builder of a `FindNodes` query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeQuery {
+    labels: Vec<String>,
+    properties: HashMap<String, Value>,
+    order_by: Vec<OrderBy>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    valid_at: Option<DateTime<Utc>>,
+}
+
+impl NodeQuery {
+    /// Match nodes with this label; with several labels, nodes with any of them
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        let label = label.into();
+        if !self.labels.contains(&label) {
+            self.labels.push(label);
+        }
+        self
+    }
+
+    /// Match nodes with any of these labels
+    pub fn labels<L: Into<String>>(self, labels: impl IntoIterator<Item = L>) -> Self {
+        labels.into_iter().fold(self, Self::label)
+    }
+
+    /// Match nodes whose property equals the value
+    pub fn prop_eq(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a sort key, less significant than those added before
+    pub fn order_by(mut self, order: OrderBy) -> Self {
+        self.order_by.push(order);
+        self
+    }
+
+    /// Skip results, after sorting
+    pub fn offset(mut self, offset: impl Into<Option<u32>>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    /// Return at most `limit` results
+    pub fn limit(mut self, limit: impl Into<Option<u32>>) -> Self {
+        self.limit = limit.into();
+        self
+    }
+
+    /// Query the graph as of a time, as an `AsOfQuery`
+    pub fn valid_at(mut self, time: impl Into<Option<DateTime<Utc>>>) -> Self {
+        self.valid_at = time.into();
+        self
+    }
+
+    pub fn build(self) -> GraphQuery {
+        let query = GraphQuery::FindNodes {
+            labels: self.labels,
+            properties: self.properties,
+            order_by: self.order_by,
+            offset: self.offset,
+            limit: self.limit,
+        };
+        match self.valid_at {
+            Some(as_of_time) => GraphQuery::AsOfQuery { base_query: Box::new(query), as_of_time },
+            None => query,
+        }
+    }
+}
+
+/// Builder of a `FindRelationships` query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelationshipQuery {
+    from_node_id: Option<Uuid>,
+    to_node_id: Option<Uuid>,
+    relationship_types: Vec<String>,
+    valid_at: Option<DateTime<Utc>>,
+    order_by: Vec<OrderBy>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    collapse: Option<Collapse>,
+}
+
+impl RelationshipQuery {
+    /// Match relationships starting at this node
+    pub fn from(mut self, node_id: impl Into<Option<Uuid>>) -> Self {
+        self.from_node_id = node_id.into();
+        self
+    }
+
+    /// Match relationships ending at this node
+    pub fn to(mut self, node_id: impl Into<Option<Uuid>>) -> Self {
+        self.to_node_id = node_id.into();
+        self
+    }
+
+    /// Match relationships of this type; with several types, of any of them
+    pub fn rel_type(mut self, kind: impl Into<String>) -> Self {
+        let kind = kind.into();
+        if !self.relationship_types.contains(&kind) {
+            self.relationship_types.push(kind);
+        }
+        self
+    }
+
+    /// Match relationships of any of these types
+    pub fn rel_types<T: Into<String>>(self, kinds: impl IntoIterator<Item = T>) -> Self {
+        kinds.into_iter().fold(self, Self::rel_type)
+    }
+
+    /// Match relationships valid at a time
+    pub fn valid_at(mut self, time: impl Into<Option<DateTime<Utc>>>) -> Self {
+        self.valid_at = time.into();
+        self
+    }
+
+    /// Add a sort key, less significant than those added before
+    pub fn order_by(mut self, order: OrderBy) -> Self {
+        self.order_by.push(order);
+        self
+    }
+
+    /// Skip results, after sorting
+    pub fn offset(mut self, offset: impl Into<Option<u32>>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    /// Return at most `limit` results
+    pub fn limit(mut self, limit: impl Into<Option<u32>>) -> Self {
+        self.limit = limit.into();
+        self
+    }
+
+    /// Return one relationship per group of matching edges
+    pub fn collapse(mut self, collapse: impl Into<Option<Collapse>>) -> Self {
+        self.collapse = collapse.into();
+        self
+    }
+
+    pub fn build(self) -> GraphQuery {
+        GraphQuery::FindRelationships {
+            from_node_id: self.from_node_id,
+            to_node_id: self.to_node_id,
+            relationship_types: self.relationship_types,
+            valid_at: self.valid_at,
+            order_by: self.order_by,
+            offset: self.offset,
+            limit: self.limit,
+            collapse: self.collapse,
+        }
+    }
+}
+
+/// Builder of a `Raw` query
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawQuery {
+    query: String,
+    params: HashMap<String, Value>,
+}
+
+impl RawQuery {
+    /// Bind a query parameter
+    pub fn param(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.params.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn build(self) -> GraphQuery {
+        GraphQuery::Raw { query: self.query, params: self.params }
+    }
+}
+
+impl From<NodeQuery> for GraphQuery {
+    fn from(query: NodeQuery) -> Self {
+        query.build()
+    }
+}
+
+impl From<RelationshipQuery> for GraphQuery {
+    fn from(query: RelationshipQuery) -> Self {
+        query.build()
+    }
+}
+
+impl From<RawQuery> for GraphQuery {
+    fn from(query: RawQuery) -> Self {
+        query.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SortField;
+
+    #[test]
+    fn test_node_query() {
+        let at: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let query = Query::nodes()
+            .label("Person")
+            .label("Person")
+            .prop_eq("name", "Alice")
+            .prop_eq("age", 30)
+            .order_by(OrderBy::desc(SortField::CreatedAt))
+            .limit(10)
+            .valid_at(at)
+            .build();
+
+        let GraphQuery::AsOfQuery { base_query, as_of_time } = query else { panic!("expected an as-of query") };
+        assert_eq!(as_of_time, at);
+        let GraphQuery::FindNodes { labels, properties, order_by, offset, limit } = *base_query else { panic!("expected FindNodes") };
+        assert_eq!(labels, vec!["Person"]);
+        assert_eq!(properties.get("name"), Some(&Value::from("Alice")));
+        assert_eq!(properties.get("age"), Some(&Value::from(30)));
+        assert_eq!(order_by, vec![OrderBy::desc(SortField::CreatedAt)]);
+        assert_eq!((offset, limit), (None, Some(10)));
+    }
+
+    #[test]
+    fn test_relationship_and_raw_queries() {
+        let from = Uuid::new_v4();
+        let query: GraphQuery = Query::relationships().from(from).rel_type("KNOWS").offset(5).into();
+        let json = serde_json::to_value(&query).unwrap();
+        assert_eq!(json["FindRelationships"]["from_node_id"], Value::from(from.to_string()));
+        assert_eq!(json["FindRelationships"]["relationship_types"], serde_json::json!(["KNOWS"]));
+        assert_eq!(json["FindRelationships"]["offset"], Value::from(5));
+
+        let GraphQuery::Raw { query, params } = Query::raw("MATCH (n) WHERE n.name = $name RETURN n").param("name", "Bo").build() else {
+            panic!("expected a raw query")
+        };
+        assert!(query.starts_with("MATCH"));
+        assert_eq!(params.get("name"), Some(&Value::from("Bo")));
+    }
+}
@@ -0,0 +1,721 @@
+//! Per-tenant query result caching
+//!
+//! `QueryCachingGraphStore` wraps any `GraphStore` and caches the results of
+//! structured queries per tenant, keyed by a hash of the normalized query.
+//! Entries expire after `ttl_ms` and a tenant's entries are dropped whenever
+//! its graph changes, either through this store or through any other writer
+//! publishing to the same [`MutationEventBus`]. A request can skip the cache
+//! by running inside [`without_cache`].
+
+use crate::archive::ArchiveBatch;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{EdgeStream, NodeStream};
+use crate::errors::GraphError;
+use crate::materialized::SnapshotInfo;
+use crate::events::{MutationEvent, MutationEventBus, MutationKind};
+use crate::traits::GraphStore;
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeWithEdges, Path, TenantId, TimeEdge};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+tokio::task_local! {
+    static BYPASS_CACHE: bool;
+}
+
+/// Run `future` with query caching disabled: queries it makes go to the
+/// underlying store and their results are not cached
+pub async fn without_cache<F: Future>(future: F) -> F::Output {
+    BYPASS_CACHE.scope(true, future).await
+}
+
+pub(crate) fn cache_bypassed() -> bool {
+    BYPASS_CACHE.try_with(|bypass| *bypass).unwrap_or(false)
+}
+
+/// Configuration for the query result cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryCacheConfig {
+    /// How long a cached result may be served, in milliseconds
+    pub ttl_ms: u64,
+    /// Maximum cached results per tenant; the oldest is evicted beyond this
+    pub max_entries_per_tenant: usize,
+}
+
+impl Default for QueryCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_ms: 30_000,
+            max_entries_per_tenant: 1_000,
+        }
+    }
+}
+
+/// Cache counters since the store was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryCacheStats {
+    /// Queries answered from the cache
+    pub hits: u64,
+    /// Cacheable queries that went to the underlying store
+    pub misses: u64,
+    /// Queries that skipped the cache because of [`without_cache`]
+    pub bypassed: u64,
+    /// Times a tenant's entries were dropped after a write
+    pub invalidations: u64,
+    /// Results currently cached
+    pub entries: usize,
+}
+
+/// A cached query result
+struct CacheEntry {
+    paths: Vec<Path>,
+    inserted_at: Instant,
+}
+
+/// Cached results of one tenant
+#[derive(Default)]
+struct TenantCache {
+    entries: HashMap<u64, CacheEntry>,
+    /// Bumped on every invalidation, so results of queries that raced with a
+    /// write are not cached
+    generation: u64,
+}
+
+struct CacheState {
+    config: QueryCacheConfig,
+    tenants: Mutex<HashMap<TenantId, TenantCache>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    bypassed: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl CacheState {
+    fn ttl(&self) -> Duration {
+        Duration::from_millis(self.config.ttl_ms)
+    }
+
+    /// Cached result and the tenant's current generation
+    fn lookup(&self, tenant: &TenantId, key: u64) -> (Option<Vec<Path>>, u64) {
+        let mut tenants = self.tenants.lock().unwrap();
+        let Some(cache) = tenants.get_mut(tenant) else {
+            return (None, 0);
+        };
+
+        match cache.entries.get(&key) {
+            Some(entry) if entry.inserted_at.elapsed() < self.ttl() => (Some(entry.paths.clone()), cache.generation),
+            Some(_) => {
+                cache.entries.remove(&key);
+                (None, cache.generation)
+            }
+            None => (None, cache.generation),
+        }
+    }
+
+    fn insert(&self, tenant: &TenantId, key: u64, generation: u64, paths: Vec<Path>) {
+        let max_entries = self.config.max_entries_per_tenant;
+        if max_entries == 0 {
+            return;
+        }
+
+        let mut tenants = self.tenants.lock().unwrap();
+        let cache = tenants.entry(tenant.clone()).or_default();
+        if cache.generation != generation {
+            return;
+        }
+
+        if cache.entries.len() >= max_entries && !cache.entries.contains_key(&key) {
+            let ttl = self.ttl();
+            cache.entries.retain(|_, entry| entry.inserted_at.elapsed() < ttl);
+            if cache.entries.len() >= max_entries {
+                let oldest = cache.entries.iter()
+                    .min_by_key(|(_, entry)| entry.inserted_at)
+                    .map(|(key, _)| *key);
+                if let Some(oldest) = oldest {
+                    cache.entries.remove(&oldest);
+                }
+            }
+        }
+
+        cache.entries.insert(key, CacheEntry {
+            paths,
+            inserted_at: Instant::now(),
+        });
+    }
+
+    fn invalidate(&self, tenant: &TenantId) {
+        let mut tenants = self.tenants.lock().unwrap();
+        let cache = tenants.entry(tenant.clone()).or_default();
+        cache.entries.clear();
+        cache.generation += 1;
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn invalidate_all(&self) {
+        let mut tenants = self.tenants.lock().unwrap();
+        for cache in tenants.values_mut() {
+            cache.entries.clear();
+            cache.generation += 1;
+        }
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// `GraphStore` decorator that caches query results per tenant.
+///
+/// Only `FindNodes`, `FindRelationships` and `AsOfQuery` results are cached;
+/// raw queries may write and always go to the underlying store. Writes made
+/// through this store are published on the event bus after they succeed.
+///
+/// Must be created inside a Tokio runtime.
+pub struct QueryCachingGraphStore {
+    inner: Arc<dyn GraphStore>,
+    state: Arc<CacheState>,
+    events: MutationEventBus,
+    listener: JoinHandle<()>,
+}
+
+impl QueryCachingGraphStore {
+    /// Wrap a store, invalidating on events published to `events`
+    pub fn new(inner: Arc<dyn GraphStore>, config: QueryCacheConfig, events: MutationEventBus) -> Self {
+        let state = Arc::new(CacheState {
+            config,
+            tenants: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            bypassed: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        });
+        // Subscribe before returning so no write after construction is missed
+        let listener = tokio::spawn(Self::run_listener(Arc::downgrade(&state), events.subscribe()));
+
+        Self { inner, state, events, listener }
+    }
+
+    /// Drop cached results on mutation events until the cache is dropped
+    async fn run_listener(state: Weak<CacheState>, mut receiver: broadcast::Receiver<MutationEvent>) {
+        loop {
+            let event = receiver.recv().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+
+            match event {
+                Ok(event) => state.invalidate(&event.tenant),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Query cache missed {} mutation events; clearing all tenants", skipped);
+                    state.invalidate_all();
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Current hit/miss counters
+    pub fn stats(&self) -> QueryCacheStats {
+        let entries = self.state.tenants.lock().unwrap()
+            .values()
+            .map(|cache| cache.entries.len())
+            .sum();
+
+        QueryCacheStats {
+            hits: self.state.hits.load(Ordering::Relaxed),
+            misses: self.state.misses.load(Ordering::Relaxed),
+            bypassed: self.state.bypassed.load(Ordering::Relaxed),
+            invalidations: self.state.invalidations.load(Ordering::Relaxed),
+            entries,
+        }
+    }
+
+    /// Drop all cached results of a tenant
+    pub fn invalidate(&self, tenant: &TenantId) {
+        self.state.invalidate(tenant);
+    }
+
+    /// Invalidate locally and let other subscribers know about a write
+    fn written(&self, tenant: &TenantId, kind: MutationKind) {
+        self.state.invalidate(tenant);
+        self.events.publish(tenant, kind);
+    }
+}
+
+impl Drop for QueryCachingGraphStore {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+/// Cache key of a query, or `None` if its results must not be cached
+fn cache_key(query: &GraphQuery) -> Option<u64> {
+    let normalized = normalize(query)?;
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(&normalized).ok()?.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Query with order-insensitive lists sorted, so equivalent queries share a key
+fn normalize(query: &GraphQuery) -> Option<GraphQuery> {
+    let sorted = |items: &[String]| {
+        let mut items = items.to_vec();
+        items.sort();
+        items.dedup();
+        items
+    };
+
+    match query {
+        GraphQuery::Raw { .. } => None,
+        GraphQuery::FindNodes { labels, properties, order_by, offset, limit } => Some(GraphQuery::FindNodes {
+            labels: sorted(labels),
+            properties: properties.clone(),
+            order_by: order_by.clone(),
+            offset: *offset,
+            limit: *limit,
+        }),
+        GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse } => {
+            Some(GraphQuery::FindRelationships {
+                from_node_id: *from_node_id,
+                to_node_id: *to_node_id,
+                relationship_types: sorted(relationship_types),
+                valid_at: *valid_at,
+                order_by: order_by.clone(),
+                offset: *offset,
+                limit: *limit,
+                collapse: *collapse,
+            })
+        }
+        GraphQuery::AsOfQuery { base_query, as_of_time } => Some(GraphQuery::AsOfQuery {
+            base_query: Box::new(normalize(base_query)?),
+            as_of_time: *as_of_time,
+        }),
+    }
+}
+
+#[async_trait]
+impl GraphStore for QueryCachingGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        let id = self.inner.upsert_node(tenant, node).await?;
+        self.written(tenant, MutationKind::UpsertNode);
+        Ok(id)
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        let id = self.inner.upsert_edge(tenant, edge).await?;
+        self.written(tenant, MutationKind::UpsertEdge);
+        Ok(id)
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        let result = self.inner.upsert_node_with_edges(tenant, node, edges).await?;
+        self.written(tenant, MutationKind::UpsertNodeWithEdges);
+        Ok(result)
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        let result = self.inner.bulk_load_nodes(tenant, nodes).await;
+        // Chunks committed before a failure are written too
+        self.written(tenant, MutationKind::UpsertNode);
+        result
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        let result = self.inner.bulk_load_edges(tenant, edges).await;
+        self.written(tenant, MutationKind::UpsertEdge);
+        result
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        let Some(key) = cache_key(&query) else {
+            return self.inner.query(tenant, query).await;
+        };
+        if cache_bypassed() {
+            self.state.bypassed.fetch_add(1, Ordering::Relaxed);
+            return self.inner.query(tenant, query).await;
+        }
+
+        let (cached, generation) = self.state.lookup(tenant, key);
+        if let Some(paths) = cached {
+            self.state.hits.fetch_add(1, Ordering::Relaxed);
+            debug!("Query cache hit for tenant {}", tenant);
+            return Ok(paths);
+        }
+
+        self.state.misses.fetch_add(1, Ordering::Relaxed);
+        let paths = self.inner.query(tenant, query).await?;
+        self.state.insert(tenant, key, generation, paths.clone());
+        Ok(paths)
+    }
+
+    // Counts are cheap to compute and not cached
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.inner.query_count(tenant, query).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.inner.query_exists(tenant, query).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.inner.traverse(tenant, request).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.inner.shortest_path(tenant, request).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.inner.timeline(tenant, request).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.inner.property_history(tenant, node_id, key).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        let updated = self.inner.update_node(tenant, id, node).await?;
+        if updated {
+            self.written(tenant, MutationKind::UpsertNode);
+        }
+        Ok(updated)
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.inner.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.inner.set_edge_constraints(tenant, constraints).await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.inner.get_node(tenant, id).await
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        self.inner.get_node_by_alias(tenant, id_alias).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.inner.resolve_aliases(tenant, aliases).await
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        let deleted = self.inner.delete_node(tenant, id).await?;
+        if deleted {
+            self.written(tenant, MutationKind::DeleteNode);
+        }
+        Ok(deleted)
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        let deleted = self.inner.delete_edge(tenant, id).await?;
+        if deleted {
+            self.written(tenant, MutationKind::DeleteEdge);
+        }
+        Ok(deleted)
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        let new_id = self.inner.close_edge(tenant, id, valid_to).await?;
+        self.written(tenant, MutationKind::CloseEdge);
+        Ok(new_id)
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        let new_id = self.inner.supersede_edge(tenant, id, edge).await?;
+        self.written(tenant, MutationKind::SupersedeEdge);
+        Ok(new_id)
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        let retracted = self.inner.retract_edge(tenant, id).await?;
+        if retracted {
+            self.written(tenant, MutationKind::RetractEdge);
+        }
+        Ok(retracted)
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        self.inner.get_node_history(tenant, id).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        self.inner.snapshot(tenant, valid_at).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.inner.materialize_snapshot(tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.inner.list_snapshots(tenant).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.inner.drop_snapshot(tenant, name).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        // Snapshots are already held in memory and never change
+        self.inner.query_snapshot(tenant, name, query).await
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        self.inner.read_history(tenant, before).await
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        let purged = self.inner.purge_history(tenant, before).await?;
+        if purged > 0 {
+            self.written(tenant, MutationKind::PurgeHistory);
+        }
+        Ok(purged)
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        let restored = self.inner.restore_history(tenant, batch).await?;
+        if restored > 0 {
+            self.written(tenant, MutationKind::RestoreHistory);
+        }
+        Ok(restored)
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        let purged = self.inner.purge_expired_edges(tenant, now).await?;
+        if purged > 0 {
+            self.written(tenant, MutationKind::PurgeExpiredEdges);
+        }
+        Ok(purged)
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        self.inner.summary(tenant).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.inner.catalog(tenant).await
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        self.inner.list_tenants().await
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        let cleared = self.inner.clear_tenant(tenant).await?;
+        if cleared > 0 {
+            self.written(tenant, MutationKind::ClearTenant);
+        }
+        Ok(cleared)
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Store that counts queries and answers each with an empty result
+    #[derive(Default)]
+    struct CountingStore {
+        queries: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl GraphStore for CountingStore {
+        async fn upsert_node(&self, _tenant: &TenantId, _node: Node) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_edge(&self, _tenant: &TenantId, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_node_with_edges(&self, _tenant: &TenantId, _node: Node, _edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+            Ok(NodeWithEdges { node_id: Uuid::new_v4(), edge_ids: vec![] })
+        }
+
+        async fn query(&self, _tenant: &TenantId, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            self.queries.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![])
+        }
+
+        async fn get_node(&self, _tenant: &TenantId, _id: Uuid) -> Result<Option<Node>, GraphError> {
+            Ok(None)
+        }
+
+        async fn get_node_by_alias(&self, _tenant: &TenantId, _id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+            Ok(None)
+        }
+
+        async fn resolve_aliases(&self, _tenant: &TenantId, _aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+            Ok(HashMap::new())
+        }
+
+        async fn delete_node(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn delete_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn close_edge(&self, _tenant: &TenantId, _id: Uuid, _valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn supersede_edge(&self, _tenant: &TenantId, _id: Uuid, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn retract_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn get_node_history(&self, _tenant: &TenantId, _id: Uuid) -> Result<Vec<Node>, GraphError> {
+            Ok(vec![])
+        }
+
+        async fn snapshot(&self, _tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+            Ok(GraphSnapshot { snapshot_at: Utc::now(), valid_at, nodes: vec![], edges: vec![] })
+        }
+
+        async fn materialize_snapshot(&self, _tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+            Ok(SnapshotInfo {
+                name: name.to_string(),
+                valid_at,
+                snapshot_at: Utc::now(),
+                node_count: 0,
+                edge_count: 0,
+            })
+        }
+
+        async fn list_snapshots(&self, _tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn drop_snapshot(&self, _tenant: &TenantId, _name: &str) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn query_snapshot(&self, _tenant: &TenantId, _name: &str, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn read_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+            Ok(ArchiveBatch::default())
+        }
+
+        async fn purge_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn restore_history(&self, _tenant: &TenantId, _batch: ArchiveBatch) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn purge_expired_edges(&self, _tenant: &TenantId, _now: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn summary(&self, _tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+            Ok(GraphSummary::default())
+        }
+
+        async fn catalog(&self, _tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+            Ok(GraphCatalog::default())
+        }
+
+        async fn clear_tenant(&self, _tenant: &TenantId) -> Result<u64, GraphError> {
+            Ok(1)
+        }
+
+        async fn health_check(&self) -> Result<(), GraphError> {
+            Ok(())
+        }
+    }
+
+    fn find_people(labels: &[&str]) -> GraphQuery {
+        GraphQuery::FindNodes {
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            properties: HashMap::new(),
+            order_by: vec![],
+            offset: None,
+            limit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hits_misses_and_bypass() {
+        let inner = Arc::new(CountingStore::default());
+        let store = QueryCachingGraphStore::new(inner.clone(), QueryCacheConfig::default(), MutationEventBus::default());
+        let tenant = TenantId::new("tenant");
+
+        store.query(&tenant, find_people(&["Person", "Employee"])).await.unwrap();
+        // Label order does not matter
+        store.query(&tenant, find_people(&["Employee", "Person"])).await.unwrap();
+        // Other tenants have their own entries
+        store.query(&TenantId::new("other"), find_people(&["Person", "Employee"])).await.unwrap();
+        without_cache(store.query(&tenant, find_people(&["Person", "Employee"]))).await.unwrap();
+        // Raw queries are never cached
+        let raw = GraphQuery::Raw { query: "MATCH (n) RETURN n".to_string(), params: HashMap::new() };
+        store.query(&tenant, raw.clone()).await.unwrap();
+        store.query(&tenant, raw).await.unwrap();
+
+        assert_eq!(inner.queries.load(Ordering::SeqCst), 5);
+        let stats = store.stats();
+        assert_eq!((stats.hits, stats.misses, stats.bypassed, stats.entries), (1, 2, 1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_on_writes_and_events() {
+        let inner = Arc::new(CountingStore::default());
+        let events = MutationEventBus::default();
+        let store = QueryCachingGraphStore::new(inner.clone(), QueryCacheConfig::default(), events.clone());
+        let tenant = TenantId::new("tenant");
+        let other = TenantId::new("other");
+
+        store.query(&tenant, find_people(&["Person"])).await.unwrap();
+        store.query(&other, find_people(&["Person"])).await.unwrap();
+
+        // A write through the store invalidates only that tenant
+        store.upsert_node(&tenant, Node::new("Person")).await.unwrap();
+        store.query(&tenant, find_people(&["Person"])).await.unwrap();
+        store.query(&other, find_people(&["Person"])).await.unwrap();
+        assert_eq!(inner.queries.load(Ordering::SeqCst), 3);
+
+        // A write published by another writer invalidates through the bus
+        events.publish(&other, MutationKind::UpsertEdge);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        store.query(&other, find_people(&["Person"])).await.unwrap();
+        assert_eq!(inner.queries.load(Ordering::SeqCst), 4);
+
+        // So does clearing the tenant
+        store.clear_tenant(&other).await.unwrap();
+        store.query(&other, find_people(&["Person"])).await.unwrap();
+        assert_eq!(inner.queries.load(Ordering::SeqCst), 5);
+
+        // Expired entries are not served
+        let store = QueryCachingGraphStore::new(inner.clone(), QueryCacheConfig { ttl_ms: 0, ..Default::default() }, events);
+        store.query(&tenant, find_people(&["Person"])).await.unwrap();
+        store.query(&tenant, find_people(&["Person"])).await.unwrap();
+        assert_eq!(store.stats().hits, 0);
+    }
+}
@@ -0,0 +1,526 @@
+//! Fair scheduling of store queries across tenants
+//!
+//! One tenant running heavy traversals should not starve the others. A
+//! [`QueryScheduler`] admits a query to the store once fewer than
+//! `max_concurrent` queries run overall and fewer than the tenant's own
+//! `max_concurrent` run for that tenant. When tenants compete for a free
+//! slot, it goes to the one that has been admitted least for its `weight`:
+//! under load, a tenant of weight 2 gets twice the slots of a tenant of
+//! weight 1. A tenant that was idle joins at the current share instead of
+//! catching up on what it did not use.
+//!
+//! A query that finds `max_queued` queries of its tenant already waiting
+//! fails at once with `GraphError::Overloaded`, and one that waits longer
+//! than `max_wait_ms` with `GraphError::Timeout`.
+//!
+//! [`ScheduledGraphStore`] runs the reads of a store that scan it through a
+//! scheduler: queries, traversals, shortest paths, timelines, snapshots,
+//! summaries and catalogs. Writes and lookups by ID or alias are not held
+//! back. [`QueryScheduler::stats`] reports running and waiting queries and
+//! their time in the queue by tenant.
+
+use crate::archive::ArchiveBatch;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{EdgeStream, NodeStream};
+use crate::errors::GraphError;
+use crate::materialized::SnapshotInfo;
+use crate::traits::GraphStore;
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeWithEdges, Path, TenantId, TimeEdge};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Limits of one tenant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantQueryLimits {
+    /// Queries of the tenant running at once
+    pub max_concurrent: usize,
+    /// Queries of the tenant waiting at once; further queries are refused
+    pub max_queued: usize,
+    /// Share of the store the tenant gets under load, relative to other tenants
+    pub weight: u32,
+    /// Longest a query waits for its turn, in milliseconds
+    pub max_wait_ms: u64,
+}
+
+impl Default for TenantQueryLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 8,
+            max_queued: 64,
+            weight: 1,
+            max_wait_ms: 30_000,
+        }
+    }
+}
+
+/// Scheduler limits, overall and by tenant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuerySchedulerConfig {
+    /// Queries running against the store at once, across tenants
+    pub max_concurrent: usize,
+    /// Limits of tenants without their own
+    pub default: TenantQueryLimits,
+    /// Limits by tenant
+    pub tenants: HashMap<TenantId, TenantQueryLimits>,
+}
+
+impl Default for QuerySchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 32,
+            default: TenantQueryLimits::default(),
+            tenants: HashMap::new(),
+        }
+    }
+}
+
+impl QuerySchedulerConfig {
+    /// Limits of a tenant
+    pub fn for_tenant(&self, tenant: &TenantId) -> &TenantQueryLimits {
+        self.tenants.get(tenant).unwrap_or(&self.default)
+    }
+}
+
+/// Queries of one tenant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantQueryStats {
+    /// Queries running against the store
+    pub in_flight: usize,
+    /// Queries waiting for their turn
+    pub queued: usize,
+    /// Queries admitted since the scheduler was created
+    pub admitted: u64,
+    /// Queries refused because the tenant's queue was full
+    pub rejected: u64,
+    /// Queries that gave up after `max_wait_ms`
+    pub timed_out: u64,
+    /// Time admitted queries spent waiting, in milliseconds
+    pub total_wait_ms: u64,
+    /// Longest time an admitted query spent waiting, in milliseconds
+    pub max_wait_ms: u64,
+}
+
+#[derive(Default)]
+struct TenantState {
+    stats: TenantQueryStats,
+    /// Admissions so far, each counted as `1 / weight`
+    virtual_time: f64,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    in_flight: usize,
+    /// Virtual time of the tenant admitted last
+    virtual_time: f64,
+    tenants: HashMap<TenantId, TenantState>,
+}
+
+impl SchedulerState {
+    fn tenant(&mut self, tenant: &TenantId) -> &mut TenantState {
+        self.tenants.entry(tenant.clone()).or_default()
+    }
+
+    /// Whether another tenant waiting for a slot it could take is further
+    /// behind its share than `tenant`; ties go to the tenant named first
+    fn others_first(&self, config: &QuerySchedulerConfig, tenant: &TenantId, virtual_time: f64) -> bool {
+        self.tenants.iter().any(|(other, state)| {
+            other != tenant
+                && state.stats.queued > 0
+                && state.stats.in_flight < config.for_tenant(other).max_concurrent.max(1)
+                && (state.virtual_time < virtual_time || (state.virtual_time == virtual_time && other.as_str() < tenant.as_str()))
+        })
+    }
+
+    fn try_admit(&mut self, config: &QuerySchedulerConfig, tenant: &TenantId) -> bool {
+        let limits = config.for_tenant(tenant);
+        let system_time = self.virtual_time;
+        let state = self.tenant(tenant);
+        if state.stats.in_flight == 0 && state.stats.queued == 0 {
+            state.virtual_time = state.virtual_time.max(system_time);
+        }
+        let virtual_time = state.virtual_time;
+        if state.stats.in_flight >= limits.max_concurrent.max(1) || self.in_flight >= config.max_concurrent.max(1) {
+            return false;
+        }
+        if self.others_first(config, tenant, virtual_time) {
+            return false;
+        }
+
+        self.in_flight += 1;
+        self.virtual_time = virtual_time;
+        let state = self.tenant(tenant);
+        state.virtual_time += 1.0 / f64::from(limits.weight.max(1));
+        state.stats.in_flight += 1;
+        state.stats.admitted += 1;
+        true
+    }
+}
+
+/// A query waiting for its turn, counted until dropped
+struct Waiting<'a> {
+    scheduler: &'a QueryScheduler,
+    tenant: &'a TenantId,
+    queued: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if self.queued {
+            self.scheduler.state.lock().unwrap().tenant(self.tenant).stats.queued -= 1;
+            self.scheduler.changed.notify_waiters();
+        }
+    }
+}
+
+/// Admission of queries to the store, shared by all tenants
+#[derive(Default)]
+pub struct QueryScheduler {
+    config: QuerySchedulerConfig,
+    state: Mutex<SchedulerState>,
+    /// Notified whenever a query finishes or stops waiting
+    changed: Notify,
+}
+
+impl QueryScheduler {
+    pub fn new(config: QuerySchedulerConfig) -> Self {
+        Self { config, state: Mutex::new(SchedulerState::default()), changed: Notify::new() }
+    }
+
+    /// Wait for a turn to run a query of `tenant`
+    pub async fn acquire(self: &Arc<Self>, tenant: &TenantId) -> Result<QueryPermit, GraphError> {
+        let limits = self.config.for_tenant(tenant);
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(limits.max_wait_ms);
+        let mut waiting = Waiting { scheduler: self, tenant, queued: false };
+
+        loop {
+            // Created before checking, so a query finishing in between still wakes us
+            let changed = self.changed.notified();
+            let now = Instant::now();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.try_admit(&self.config, tenant) {
+                    let wait_ms = now.duration_since(started).as_millis() as u64;
+                    let stats = &mut state.tenant(tenant).stats;
+                    if waiting.queued {
+                        stats.queued -= 1;
+                        waiting.queued = false;
+                    }
+                    stats.total_wait_ms += wait_ms;
+                    stats.max_wait_ms = stats.max_wait_ms.max(wait_ms);
+                    return Ok(QueryPermit { scheduler: self.clone(), tenant: tenant.clone() });
+                }
+                if !waiting.queued {
+                    let stats = &mut state.tenant(tenant).stats;
+                    if stats.queued >= limits.max_queued {
+                        stats.rejected += 1;
+                        warn!("Refusing query of tenant {}: {} queries already waiting", tenant, stats.queued);
+                        return Err(GraphError::Overloaded(format!(
+                            "Tenant {} has {} queries waiting, the most allowed", tenant, stats.queued
+                        )));
+                    }
+                    stats.queued += 1;
+                    waiting.queued = true;
+                    debug!("Query of tenant {} is waiting for its turn", tenant);
+                }
+            }
+
+            if now >= deadline {
+                self.state.lock().unwrap().tenant(tenant).stats.timed_out += 1;
+                warn!("Query of tenant {} gave up after waiting {:?} in the queue", tenant, now - started);
+                return Err(GraphError::Timeout(format!(
+                    "Query of tenant {} waited more than {}ms in the queue", tenant, limits.max_wait_ms
+                )));
+            }
+            tokio::select! {
+                _ = changed => {}
+                _ = tokio::time::sleep(deadline - now) => {}
+            }
+        }
+    }
+
+    /// Run `operation` once a query of `tenant` may run
+    pub async fn run<T, F>(self: &Arc<Self>, tenant: &TenantId, operation: F) -> Result<T, GraphError>
+    where
+        F: Future<Output = Result<T, GraphError>>,
+    {
+        let _permit = self.acquire(tenant).await?;
+        operation.await
+    }
+
+    /// Queries of each tenant seen so far, by tenant
+    pub fn stats(&self) -> BTreeMap<String, TenantQueryStats> {
+        self.state.lock().unwrap()
+            .tenants
+            .iter()
+            .map(|(tenant, state)| (tenant.to_string(), state.stats))
+            .collect()
+    }
+}
+
+/// A query admitted to the store, holding a slot until dropped
+pub struct QueryPermit {
+    scheduler: Arc<QueryScheduler>,
+    tenant: TenantId,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        {
+            let mut state = self.scheduler.state.lock().unwrap();
+            state.in_flight -= 1;
+            state.tenant(&self.tenant).stats.in_flight -= 1;
+        }
+        self.scheduler.changed.notify_waiters();
+    }
+}
+
+/// `GraphStore` decorator whose reads wait for their turn in a [`QueryScheduler`]
+pub struct ScheduledGraphStore {
+    inner: Arc<dyn GraphStore>,
+    scheduler: Arc<QueryScheduler>,
+}
+
+impl ScheduledGraphStore {
+    /// Schedule the reads of `inner` in `scheduler`
+    pub fn new(inner: Arc<dyn GraphStore>, scheduler: Arc<QueryScheduler>) -> Self {
+        Self { inner, scheduler }
+    }
+}
+
+#[async_trait]
+impl GraphStore for ScheduledGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        self.inner.upsert_node(tenant, node).await
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.inner.upsert_edge(tenant, edge).await
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        self.inner.upsert_node_with_edges(tenant, node, edges).await
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        self.inner.bulk_load_nodes(tenant, nodes).await
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        self.inner.bulk_load_edges(tenant, edges).await
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.scheduler.run(tenant, self.inner.query(tenant, query)).await
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.scheduler.run(tenant, self.inner.query_count(tenant, query)).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.scheduler.run(tenant, self.inner.query_exists(tenant, query)).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.scheduler.run(tenant, self.inner.traverse(tenant, request)).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.scheduler.run(tenant, self.inner.shortest_path(tenant, request)).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.scheduler.run(tenant, self.inner.timeline(tenant, request)).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.scheduler.run(tenant, self.inner.property_history(tenant, node_id, key)).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        self.inner.update_node(tenant, id, node).await
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.inner.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.inner.set_edge_constraints(tenant, constraints).await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.inner.get_node(tenant, id).await
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        self.inner.get_node_by_alias(tenant, id_alias).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.inner.resolve_aliases(tenant, aliases).await
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.inner.delete_node(tenant, id).await
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.inner.delete_edge(tenant, id).await
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        self.inner.close_edge(tenant, id, valid_to).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.inner.supersede_edge(tenant, id, edge).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.inner.retract_edge(tenant, id).await
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        self.inner.get_node_history(tenant, id).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        self.scheduler.run(tenant, self.inner.snapshot(tenant, valid_at)).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.inner.materialize_snapshot(tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.inner.list_snapshots(tenant).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.inner.drop_snapshot(tenant, name).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.scheduler.run(tenant, self.inner.query_snapshot(tenant, name, query)).await
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        self.inner.read_history(tenant, before).await
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.inner.purge_history(tenant, before).await
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        self.inner.restore_history(tenant, batch).await
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.inner.purge_expired_edges(tenant, now).await
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        self.scheduler.run(tenant, self.inner.summary(tenant)).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.scheduler.run(tenant, self.inner.catalog(tenant)).await
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        self.inner.list_tenants().await
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        self.inner.clear_tenant(tenant).await
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(max_concurrent: usize, default: TenantQueryLimits, tenants: &[(&str, TenantQueryLimits)]) -> Arc<QueryScheduler> {
+        Arc::new(QueryScheduler::new(QuerySchedulerConfig {
+            max_concurrent,
+            default,
+            tenants: tenants.iter().map(|(tenant, limits)| (TenantId::new(*tenant), limits.clone())).collect(),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_weighted_fairness() {
+        let heavy = TenantQueryLimits { weight: 2, ..TenantQueryLimits::default() };
+        let scheduler = scheduler(1, TenantQueryLimits::default(), &[("globex", heavy)]);
+        let first = scheduler.acquire(&TenantId::new("acme")).await.unwrap();
+
+        // acme queues first, but globex has had no turn yet and twice the weight
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut waiting = Vec::new();
+        for tenant in ["acme", "acme", "acme", "globex", "globex", "globex"] {
+            let (scheduler, order) = (scheduler.clone(), order.clone());
+            waiting.push(tokio::spawn(async move {
+                let permit = scheduler.acquire(&TenantId::new(tenant)).await.unwrap();
+                order.lock().unwrap().push(tenant);
+                drop(permit);
+            }));
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let stats = scheduler.stats();
+        assert_eq!((stats["acme"].in_flight, stats["acme"].queued, stats["globex"].queued), (1, 3, 3));
+
+        drop(first);
+        for task in waiting {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["globex", "globex", "acme", "globex", "acme", "acme"]);
+        let stats = scheduler.stats();
+        assert_eq!((stats["acme"].admitted, stats["globex"].admitted, stats["acme"].in_flight), (4, 3, 0));
+        assert!(stats["acme"].max_wait_ms > 0);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_limits() {
+        let limits = TenantQueryLimits { max_concurrent: 1, max_queued: 1, max_wait_ms: 20, ..TenantQueryLimits::default() };
+        let scheduler = scheduler(8, limits, &[]);
+        let acme = TenantId::new("acme");
+        let running = scheduler.acquire(&acme).await.unwrap();
+
+        let waiting = tokio::spawn({
+            let (scheduler, acme) = (scheduler.clone(), acme.clone());
+            async move { scheduler.acquire(&acme).await.map(drop) }
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        // The queue is full, but other tenants are not held back
+        assert!(matches!(scheduler.acquire(&acme).await, Err(GraphError::Overloaded(_))));
+        assert!(scheduler.acquire(&TenantId::new("globex")).await.is_ok());
+        assert!(matches!(waiting.await.unwrap(), Err(GraphError::Timeout(_))));
+
+        drop(running);
+        assert!(scheduler.acquire(&acme).await.is_ok());
+        let stats = scheduler.stats()["acme"];
+        assert_eq!((stats.admitted, stats.rejected, stats.timed_out, stats.queued), (2, 1, 1, 0));
+    }
+}
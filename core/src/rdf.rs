@@ -0,0 +1,516 @@
+//! RDF encoding of graph exports
+//!
+//! Some consumers only read RDF, so a [`GraphSnapshot`] can be exported as
+//! Turtle or N-Triples. Nodes become resources `{base_iri}{tenant}/node/{id}`
+//! typed by their label, node properties become literal-valued triples and
+//! each edge becomes a triple from its source to its target node with its
+//! kind as predicate. Labels, kinds and property keys are terms of
+//! `vocab_iri` unless [`RdfMapping`] maps them to other IRIs, e.g. to reuse a
+//! shared vocabulary with `"Person": "schema:Person"` and a `schema` prefix,
+//! so SPARQL queries can be written against familiar terms.
+//!
+//! A plain triple cannot carry an edge's valid and transaction times or its
+//! properties. With `reify_edges`, each edge is also described by an
+//! `rdf:Statement` resource `{base_iri}{tenant}/edge/{id}` that has them.
+//! Without it, edge properties are not exported.
+//!
+//! The same mapping describes nodes and query results as JSON-LD, whose
+//! `@context` maps labels, kinds and property keys to the same IRIs, so
+//! linked-data consumers can read API responses without a custom parser.
+
+use crate::types::{GraphSnapshot, Node, Path, TenantId};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use uuid::Uuid;
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Serialization of an RDF export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RdfSyntax {
+    /// Turtle, with the mapping's prefixes and triples grouped by subject
+    Turtle,
+    /// N-Triples, one triple per line with full IRIs
+    NTriples,
+}
+
+impl RdfSyntax {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            RdfSyntax::Turtle => "text/turtle",
+            RdfSyntax::NTriples => "application/n-triples",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RdfSyntax::Turtle => "ttl",
+            RdfSyntax::NTriples => "nt",
+        }
+    }
+}
+
+/// How graph names map to IRIs in RDF exports and JSON-LD responses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RdfMapping {
+    /// Start of node and edge IRIs, followed by the tenant
+    pub base_iri: String,
+    /// Namespace of labels, kinds, property keys and the export's own terms
+    /// such as `validFrom`; declared as the `tm` prefix
+    pub vocab_iri: String,
+    /// Prefixes usable in the mappings below, declared in Turtle output
+    pub prefixes: BTreeMap<String, String>,
+    /// IRIs or prefixed names of node labels
+    pub labels: BTreeMap<String, String>,
+    /// IRIs or prefixed names of edge kinds
+    pub kinds: BTreeMap<String, String>,
+    /// IRIs or prefixed names of property keys, for nodes and edges alike
+    pub properties: BTreeMap<String, String>,
+    /// Also describe each edge as an `rdf:Statement` with its times and properties
+    pub reify_edges: bool,
+}
+
+impl Default for RdfMapping {
+    fn default() -> Self {
+        Self {
+            base_iri: "urn:telamentis:".to_string(),
+            vocab_iri: "urn:telamentis:vocab:".to_string(),
+            prefixes: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            kinds: BTreeMap::new(),
+            properties: BTreeMap::new(),
+            reify_edges: false,
+        }
+    }
+}
+
+/// RDF mappings by tenant
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RdfMappings {
+    /// Mapping of tenants without their own
+    pub default: RdfMapping,
+    /// Mappings by tenant ID
+    pub tenants: HashMap<String, RdfMapping>,
+}
+
+impl RdfMappings {
+    /// Mapping of a tenant
+    pub fn for_tenant(&self, tenant: &TenantId) -> &RdfMapping {
+        self.tenants.get(tenant.as_str()).unwrap_or(&self.default)
+    }
+}
+
+enum Object {
+    Iri(String),
+    Literal { value: String, datatype: Option<String> },
+}
+
+struct Triple {
+    subject: String,
+    predicate: String,
+    object: Object,
+}
+
+impl RdfMapping {
+    /// Encode a tenant's snapshot
+    pub fn encode(&self, tenant: &TenantId, snapshot: &GraphSnapshot, syntax: RdfSyntax) -> String {
+        let triples = self.triples(tenant, snapshot);
+        match syntax {
+            RdfSyntax::Turtle => self.write_turtle(&triples),
+            RdfSyntax::NTriples => triples.iter().fold(String::new(), |mut out, triple| {
+                let _ = writeln!(out, "<{}> <{}> {} .", escape_iri(&triple.subject), escape_iri(&triple.predicate), ntriples_object(&triple.object));
+                out
+            }),
+        }
+    }
+
+    /// JSON-LD `@context` of the mapping: unmapped names are terms of
+    /// `vocab_iri`, mapped ones expand to their IRIs
+    pub fn json_ld_context(&self) -> Value {
+        let mut context = Map::new();
+        context.insert("@version".to_string(), json!(1.1));
+        context.insert("@vocab".to_string(), json!(self.vocab_iri));
+        for (prefix, namespace) in self.turtle_prefixes() {
+            context.insert(prefix.to_string(), json!(namespace));
+        }
+        for (name, iri) in self.labels.iter().chain(&self.kinds).chain(&self.properties) {
+            context.insert(name.clone(), json!(iri));
+        }
+        Value::Object(context)
+    }
+
+    /// JSON-LD document of a node
+    pub fn node_json_ld(&self, tenant: &TenantId, id: Uuid, node: &Node) -> Value {
+        let mut object = self.node_object(tenant, id, std::slice::from_ref(&node.label), &node.props);
+        if let Some(alias) = &node.id_alias {
+            object.insert("idAlias".to_string(), json!(alias));
+        }
+        if let Some(namespace) = &node.alias_namespace {
+            object.insert("aliasNamespace".to_string(), json!(namespace));
+        }
+        object.insert("@context".to_string(), self.json_ld_context());
+        Value::Object(object)
+    }
+
+    /// JSON-LD document of query results: their nodes, and for each
+    /// relationship its kind linking the source node to the target, as one
+    /// `@graph`
+    pub fn paths_json_ld(&self, tenant: &TenantId, paths: &[Path]) -> Value {
+        let mut graph = Vec::new();
+        let mut seen = HashSet::new();
+        for path in paths {
+            for node in &path.nodes {
+                if seen.insert(node.id) {
+                    graph.push(Value::Object(self.node_object(tenant, node.id, &node.labels, &node.properties)));
+                }
+            }
+            for relationship in &path.relationships {
+                if !seen.insert(relationship.id) {
+                    continue;
+                }
+                let target = json!({ "@id": self.node_iri(tenant, &relationship.end_node_id) });
+                graph.push(json!({
+                    "@id": self.node_iri(tenant, &relationship.start_node_id),
+                    relationship.rel_type.clone(): target.clone(),
+                }));
+                if self.reify_edges {
+                    let mut statement = properties_object(&relationship.properties);
+                    statement.insert("@id".to_string(), json!(self.edge_iri(tenant, &relationship.id)));
+                    statement.insert("@type".to_string(), json!("rdf:Statement"));
+                    statement.insert("rdf:subject".to_string(), json!({ "@id": self.node_iri(tenant, &relationship.start_node_id) }));
+                    statement.insert("rdf:predicate".to_string(), json!({ "@id": self.term(&self.kinds, &relationship.rel_type) }));
+                    statement.insert("rdf:object".to_string(), target);
+                    graph.push(Value::Object(statement));
+                }
+            }
+        }
+        json!({ "@context": self.json_ld_context(), "@graph": graph })
+    }
+
+    fn node_object(&self, tenant: &TenantId, id: Uuid, labels: &[String], props: &Value) -> Map<String, Value> {
+        let mut object = properties_object(props);
+        object.insert("@id".to_string(), json!(self.node_iri(tenant, &id)));
+        object.insert("@type".to_string(), match labels {
+            [label] => json!(label),
+            labels => json!(labels),
+        });
+        object
+    }
+
+    fn node_iri(&self, tenant: &TenantId, id: &Uuid) -> String {
+        format!("{}{}/node/{}", self.base_iri, encode_segment(tenant.as_str()), id)
+    }
+
+    fn edge_iri(&self, tenant: &TenantId, id: &Uuid) -> String {
+        format!("{}{}/edge/{}", self.base_iri, encode_segment(tenant.as_str()), id)
+    }
+
+    fn triples(&self, tenant: &TenantId, snapshot: &GraphSnapshot) -> Vec<Triple> {
+        let rdf_type = format!("{}type", RDF);
+        let node_iri = |id: &Uuid| self.node_iri(tenant, id);
+        let mut triples = Vec::new();
+
+        for record in &snapshot.nodes {
+            let subject = node_iri(&record.id);
+            let mut add = |predicate: String, object: Object| triples.push(Triple { subject: subject.clone(), predicate, object });
+            add(rdf_type.clone(), Object::Iri(self.term(&self.labels, &record.node.label)));
+            if let Some(alias) = &record.node.id_alias {
+                add(self.vocab("idAlias"), string_literal(alias));
+            }
+            if let Some(namespace) = &record.node.alias_namespace {
+                add(self.vocab("aliasNamespace"), string_literal(namespace));
+            }
+            for (key, value) in record.node.props.as_object().into_iter().flatten() {
+                for literal in literals(value) {
+                    add(self.term(&self.properties, key), literal);
+                }
+            }
+        }
+
+        for record in &snapshot.edges {
+            let edge = &record.edge;
+            let predicate = self.term(&self.kinds, &edge.kind);
+            triples.push(Triple {
+                subject: node_iri(&edge.from_node_id),
+                predicate: predicate.clone(),
+                object: Object::Iri(node_iri(&edge.to_node_id)),
+            });
+            if !self.reify_edges {
+                continue;
+            }
+
+            let subject = self.edge_iri(tenant, &record.id);
+            let mut add = |predicate: String, object: Object| triples.push(Triple { subject: subject.clone(), predicate, object });
+            add(rdf_type.clone(), Object::Iri(format!("{}Statement", RDF)));
+            add(format!("{}subject", RDF), Object::Iri(node_iri(&edge.from_node_id)));
+            add(format!("{}predicate", RDF), Object::Iri(predicate));
+            add(format!("{}object", RDF), Object::Iri(node_iri(&edge.to_node_id)));
+            add(self.vocab("validFrom"), date_time_literal(edge.valid_from));
+            if let Some(valid_to) = edge.valid_to {
+                add(self.vocab("validTo"), date_time_literal(valid_to));
+            }
+            add(self.vocab("transactionStartTime"), date_time_literal(edge.transaction_start_time));
+            if let Some(transaction_end_time) = edge.transaction_end_time {
+                add(self.vocab("transactionEndTime"), date_time_literal(transaction_end_time));
+            }
+            for (key, value) in edge.props.as_object().into_iter().flatten() {
+                for literal in literals(value) {
+                    add(self.term(&self.properties, key), literal);
+                }
+            }
+        }
+        triples
+    }
+
+    /// IRI of a label, kind or property key
+    fn term(&self, mapping: &BTreeMap<String, String>, name: &str) -> String {
+        match mapping.get(name) {
+            Some(mapped) => self.expand(mapped),
+            None => self.vocab(&encode_segment(name)),
+        }
+    }
+
+    fn vocab(&self, name: &str) -> String {
+        format!("{}{}", self.vocab_iri, name)
+    }
+
+    /// Expand a prefixed name; anything else is taken as a full IRI
+    fn expand(&self, term: &str) -> String {
+        term.split_once(':')
+            .and_then(|(prefix, local)| self.namespace(prefix).map(|namespace| format!("{}{}", namespace, local)))
+            .unwrap_or_else(|| term.to_string())
+    }
+
+    fn namespace(&self, prefix: &str) -> Option<&str> {
+        match prefix {
+            "rdf" => Some(RDF),
+            "xsd" => Some(XSD),
+            "tm" => Some(&self.vocab_iri),
+            _ => self.prefixes.get(prefix).map(String::as_str),
+        }
+    }
+
+    fn turtle_prefixes(&self) -> Vec<(&str, &str)> {
+        let mut prefixes = vec![("rdf", RDF), ("xsd", XSD), ("tm", self.vocab_iri.as_str())];
+        prefixes.extend(self.prefixes.iter()
+            .filter(|(prefix, _)| !matches!(prefix.as_str(), "rdf" | "xsd" | "tm"))
+            .map(|(prefix, namespace)| (prefix.as_str(), namespace.as_str())));
+        prefixes
+    }
+
+    fn write_turtle(&self, triples: &[Triple]) -> String {
+        let prefixes = self.turtle_prefixes();
+        let mut out = String::new();
+        for (prefix, namespace) in &prefixes {
+            let _ = writeln!(out, "@prefix {}: <{}> .", prefix, escape_iri(namespace));
+        }
+
+        let mut subject: Option<&str> = None;
+        for triple in triples {
+            let predicate = if triple.predicate == format!("{}type", RDF) {
+                "a".to_string()
+            } else {
+                turtle_iri(&prefixes, &triple.predicate)
+            };
+            let object = match &triple.object {
+                Object::Iri(iri) => turtle_iri(&prefixes, iri),
+                Object::Literal { value, datatype: Some(datatype) } => format!("\"{}\"^^{}", escape_literal(value), turtle_iri(&prefixes, datatype)),
+                Object::Literal { value, datatype: None } => format!("\"{}\"", escape_literal(value)),
+            };
+
+            if subject == Some(triple.subject.as_str()) {
+                let _ = write!(out, " ;\n    {} {}", predicate, object);
+            } else {
+                out.push_str(if subject.is_some() { " .\n\n" } else { "\n" });
+                let _ = write!(out, "{} {} {}", turtle_iri(&prefixes, &triple.subject), predicate, object);
+                subject = Some(&triple.subject);
+            }
+        }
+        if subject.is_some() {
+            out.push_str(" .\n");
+        }
+        out
+    }
+}
+
+/// JSON-LD values of properties; JSON objects are JSON literals rather
+/// than nested nodes
+fn properties_object(props: &Value) -> Map<String, Value> {
+    let literal = |value: &Value| match value {
+        Value::Object(_) => json!({ "@type": "@json", "@value": value }),
+        value => value.clone(),
+    };
+    props.as_object().into_iter().flatten()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Array(items) => Value::Array(items.iter().map(literal).collect()),
+                value => literal(value),
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
+
+/// Literals of a property value: one per array element, objects as JSON
+fn literals(value: &Value) -> Vec<Object> {
+    let typed = |value: String, datatype: &str| Object::Literal { value, datatype: Some(format!("{}{}", XSD, datatype)) };
+    match value {
+        Value::Null => Vec::new(),
+        Value::Bool(b) => vec![typed(b.to_string(), "boolean")],
+        Value::Number(n) if n.is_f64() => vec![typed(n.to_string(), "double")],
+        Value::Number(n) => vec![typed(n.to_string(), "integer")],
+        Value::String(s) => vec![string_literal(s)],
+        Value::Array(items) => items.iter().flat_map(literals).collect(),
+        Value::Object(_) => vec![Object::Literal { value: value.to_string(), datatype: Some(format!("{}JSON", RDF)) }],
+    }
+}
+
+fn string_literal(value: &str) -> Object {
+    Object::Literal { value: value.to_string(), datatype: None }
+}
+
+fn date_time_literal(time: DateTime<Utc>) -> Object {
+    Object::Literal {
+        value: time.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        datatype: Some(format!("{}dateTime", XSD)),
+    }
+}
+
+fn ntriples_object(object: &Object) -> String {
+    match object {
+        Object::Iri(iri) => format!("<{}>", escape_iri(iri)),
+        Object::Literal { value, datatype: Some(datatype) } => format!("\"{}\"^^<{}>", escape_literal(value), escape_iri(datatype)),
+        Object::Literal { value, datatype: None } => format!("\"{}\"", escape_literal(value)),
+    }
+}
+
+/// Prefixed name of an IRI if a prefix covers it with a simple local name
+fn turtle_iri(prefixes: &[(&str, &str)], iri: &str) -> String {
+    prefixes.iter()
+        .filter_map(|(prefix, namespace)| iri.strip_prefix(namespace).map(|local| (prefix, local)))
+        .find(|(_, local)| {
+            local.chars().next().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                && local.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+        .map(|(prefix, local)| format!("{}:{}", prefix, local))
+        .unwrap_or_else(|| format!("<{}>", escape_iri(iri)))
+}
+
+/// Percent-encode a name for use as an IRI segment
+fn encode_segment(name: &str) -> String {
+    name.bytes().fold(String::new(), |mut out, byte| {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            out.push(byte as char);
+        } else {
+            let _ = write!(out, "%{:02X}", byte);
+        }
+        out
+    })
+}
+
+fn escape_iri(iri: &str) -> String {
+    iri.chars().fold(String::new(), |mut out, c| {
+        if matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\') || c <= ' ' {
+            let _ = write!(out, "\\u{:04X}", c as u32);
+        } else {
+            out.push(c);
+        }
+        out
+    })
+}
+
+fn escape_literal(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EdgeRecord, Node, NodeRecord, TimeEdge};
+    use chrono::TimeZone;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn snapshot() -> GraphSnapshot {
+        let alice = Uuid::from_u128(1);
+        let acme = Uuid::from_u128(2);
+        let since = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        GraphSnapshot {
+            snapshot_at: since,
+            valid_at: None,
+            nodes: vec![
+                NodeRecord { id: alice, node: Node::new("Person").with_id_alias("alice").with_props(json!({"name": "Alice \"Al\"", "age": 30})) },
+                NodeRecord { id: acme, node: Node::new("Company").with_props(json!({"tags": ["a", "b"], "address": null})) },
+            ],
+            edges: vec![EdgeRecord {
+                id: Uuid::from_u128(3),
+                edge: TimeEdge::new(alice, acme, "WORKS_FOR", since, json!({"role": "cto"})).with_transaction_start_time(since),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_ntriples() {
+        let out = RdfMapping::default().encode(&TenantId::new("acme corp"), &snapshot(), RdfSyntax::NTriples);
+        let alice = "<urn:telamentis:acme%20corp/node/00000000-0000-0000-0000-000000000001>";
+
+        assert!(out.contains(&format!("{} <{}type> <urn:telamentis:vocab:Person> .", alice, RDF)));
+        assert!(out.contains(&format!("{} <urn:telamentis:vocab:name> \"Alice \\\"Al\\\"\" .", alice)));
+        assert!(out.contains(&format!("{} <urn:telamentis:vocab:age> \"30\"^^<{}integer> .", alice, XSD)));
+        assert!(out.contains(&format!("{} <urn:telamentis:vocab:WORKS_FOR> <urn:telamentis:acme%20corp/node/00000000-0000-0000-0000-000000000002> .", alice)));
+        // Arrays become one triple per element, nulls none; edge properties need reification
+        assert_eq!(out.matches("vocab:tags>").count(), 2);
+        assert!(!out.contains("address") && !out.contains("cto"));
+    }
+
+    #[test]
+    fn test_turtle_mapping_and_reification() {
+        let mapping = RdfMapping {
+            prefixes: BTreeMap::from([("schema".to_string(), "https://schema.org/".to_string())]),
+            labels: BTreeMap::from([("Person".to_string(), "schema:Person".to_string())]),
+            kinds: BTreeMap::from([("WORKS_FOR".to_string(), "https://schema.org/worksFor".to_string())]),
+            reify_edges: true,
+            ..Default::default()
+        };
+        let out = mapping.encode(&TenantId::new("acme"), &snapshot(), RdfSyntax::Turtle);
+
+        assert!(out.starts_with(&format!("@prefix rdf: <{}> .", RDF)));
+        assert!(out.contains("@prefix schema: <https://schema.org/> ."));
+        assert!(out.contains("<urn:telamentis:acme/node/00000000-0000-0000-0000-000000000001> a schema:Person ;\n    tm:idAlias \"alice\""));
+        assert!(out.contains("a rdf:Statement ;"));
+        assert!(out.contains("rdf:predicate schema:worksFor"));
+        assert!(out.contains("tm:validFrom \"2024-01-01T00:00:00Z\"^^xsd:dateTime"));
+        assert!(out.contains("tm:role \"cto\" .\n"));
+    }
+
+    #[test]
+    fn test_json_ld() {
+        let mapping = RdfMapping {
+            prefixes: BTreeMap::from([("schema".to_string(), "https://schema.org/".to_string())]),
+            properties: BTreeMap::from([("name".to_string(), "schema:name".to_string())]),
+            ..Default::default()
+        };
+        let tenant = TenantId::new("acme");
+        let node = Node::new("Person").with_id_alias("alice").with_props(json!({"name": "Alice", "address": {"city": "Oslo"}}));
+
+        let document = mapping.node_json_ld(&tenant, Uuid::from_u128(1), &node);
+        assert_eq!(document["@id"], "urn:telamentis:acme/node/00000000-0000-0000-0000-000000000001");
+        assert_eq!(document["@type"], "Person");
+        assert_eq!(document["idAlias"], "alice");
+        assert_eq!(document["address"], json!({"@type": "@json", "@value": {"city": "Oslo"}}));
+        assert_eq!(document["@context"]["@vocab"], "urn:telamentis:vocab:");
+        assert_eq!(document["@context"]["name"], "schema:name");
+        assert_eq!(document["@context"]["schema"], "https://schema.org/");
+    }
+}
@@ -0,0 +1,58 @@
+//! Sandbox scenarios for LLM connectors
+//!
+//! With their `sandbox` feature enabled, the connectors can serve canned
+//! provider responses from a local mock server instead of calling the real
+//! API. This lets CI exercise the whole extraction flow deterministically and
+//! lets users try TelaMentis without API keys. The scenario chooses which
+//! response the mock server returns.
+
+use serde::{Deserialize, Serialize};
+
+/// API key used by sandbox configurations
+pub const SANDBOX_API_KEY: &str = "sandbox";
+
+/// Request timeout of sandbox configurations, in milliseconds
+pub const SANDBOX_TIMEOUT_MS: u64 = 1_000;
+
+/// How long the sandbox delays its response in the [`SandboxScenario::Timeout`]
+/// scenario, in milliseconds
+pub const SANDBOX_RESPONSE_DELAY_MS: u64 = 3 * SANDBOX_TIMEOUT_MS;
+
+/// Provider behaviour simulated by a sandbox server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SandboxScenario {
+    /// The provider returns a valid extraction
+    #[default]
+    Success,
+    /// The provider rejects the request with HTTP 429
+    RateLimited,
+    /// The provider returns an extraction that is not valid JSON
+    MalformedJson,
+    /// The provider does not respond before the client times out
+    Timeout,
+}
+
+impl SandboxScenario {
+    /// All scenarios
+    pub const ALL: [SandboxScenario; 4] = [
+        SandboxScenario::Success,
+        SandboxScenario::RateLimited,
+        SandboxScenario::MalformedJson,
+        SandboxScenario::Timeout,
+    ];
+}
+
+impl std::str::FromStr for SandboxScenario {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "success" => Ok(SandboxScenario::Success),
+            "rate-limited" => Ok(SandboxScenario::RateLimited),
+            "malformed-json" => Ok(SandboxScenario::MalformedJson),
+            "timeout" => Ok(SandboxScenario::Timeout),
+            _ => Err(format!("Unknown sandbox scenario: {}", s)),
+        }
+    }
+}
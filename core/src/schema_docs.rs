@@ -0,0 +1,523 @@
+//! Schema documentation of tenants
+//!
+//! A tenant's schema is whatever its writers made of it. [`generate_schema_doc`]
+//! describes it for people: the labels and relationship kinds of the catalog,
+//! their properties with the types and example values seen in a sample, the
+//! type the tenant's [`IngestSchema`] declares for them, which labels each
+//! kind links and how many nodes on either side share an edge, and how the
+//! kind uses valid time. A [`SchemaDoc`] renders as Markdown or HTML.
+//!
+//! Everything but the counts comes from a sample of up to `sample` nodes per
+//! label and relationships per kind, so large graphs are documented without
+//! being read in full. Cardinality is observed on the edges valid now, and
+//! temporal usage on the collapsed pairs of nodes a kind links.
+
+use crate::errors::GraphError;
+use crate::ingest_template::{Coercion, IngestSchema, TemplateKind};
+use crate::query::Query;
+use crate::traits::GraphService;
+use crate::types::{CatalogEntry, Collapse, Path, TenantId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Nodes per label and relationships per kind sampled by default
+pub const DEFAULT_SCHEMA_DOC_SAMPLE: u32 = 100;
+
+/// Most nodes or relationships sampled per label or kind
+pub const MAX_SCHEMA_DOC_SAMPLE: u32 = 1_000;
+
+/// Longest example value rendered, in characters
+const MAX_EXAMPLE_CHARS: usize = 40;
+
+/// Format of a rendered schema document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaDocFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl SchemaDocFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SchemaDocFormat::Markdown => "text/markdown; charset=utf-8",
+            SchemaDocFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SchemaDocFormat::Markdown => "md",
+            SchemaDocFormat::Html => "html",
+        }
+    }
+}
+
+/// A property of a label or relationship kind
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyDoc {
+    pub name: String,
+    /// Number of nodes or edges holding the property, from the catalog
+    pub count: u64,
+    /// JSON types of the sampled values, sorted
+    pub types: Vec<String>,
+    /// Type declared by the tenant's ingestion schema
+    pub declared: Option<Coercion>,
+    /// A sampled value
+    pub example: Option<Value>,
+}
+
+/// A node label
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelDoc {
+    pub name: String,
+    /// Number of nodes with the label
+    pub count: u64,
+    /// Properties, most common first
+    pub properties: Vec<PropertyDoc>,
+}
+
+/// How many nodes on either side of a relationship kind share an edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cardinality {
+    OneToOne,
+    OneToMany,
+    ManyToOne,
+    ManyToMany,
+}
+
+impl Cardinality {
+    /// Cardinality of edges given by their start and end nodes
+    pub fn observe(edges: impl IntoIterator<Item = (Uuid, Uuid)>) -> Option<Self> {
+        let (mut targets, mut sources) = (HashMap::<Uuid, BTreeSet<Uuid>>::new(), HashMap::<Uuid, BTreeSet<Uuid>>::new());
+        for (from, to) in edges {
+            targets.entry(from).or_default().insert(to);
+            sources.entry(to).or_default().insert(from);
+        }
+        if targets.is_empty() {
+            return None;
+        }
+        let many = |ends: &HashMap<Uuid, BTreeSet<Uuid>>| ends.values().any(|others| others.len() > 1);
+        Some(match (many(&sources), many(&targets)) {
+            (false, false) => Cardinality::OneToOne,
+            (false, true) => Cardinality::OneToMany,
+            (true, false) => Cardinality::ManyToOne,
+            (true, true) => Cardinality::ManyToMany,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cardinality::OneToOne => "one-to-one",
+            Cardinality::OneToMany => "one-to-many",
+            Cardinality::ManyToOne => "many-to-one",
+            Cardinality::ManyToMany => "many-to-many",
+        }
+    }
+}
+
+/// How a relationship kind uses valid time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TemporalUsage {
+    /// Pairs of nodes linked by the kind
+    pub pairs: u64,
+    /// Edge versions per pair
+    pub versions_per_pair: f64,
+    /// Pairs sampled
+    pub sampled_pairs: u64,
+    /// Sampled pairs whose latest edge is still valid
+    pub open_pairs: u64,
+    /// Earliest valid time of a sampled edge
+    pub earliest_valid_from: Option<DateTime<Utc>>,
+    /// Latest end of validity of a sampled pair that is closed
+    pub latest_valid_to: Option<DateTime<Utc>>,
+}
+
+/// A relationship kind
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KindDoc {
+    pub name: String,
+    /// Number of edges of the kind
+    pub count: u64,
+    /// Properties, most common first
+    pub properties: Vec<PropertyDoc>,
+    /// Labels of the start and end nodes seen, sorted
+    pub endpoints: Vec<(String, String)>,
+    /// Observed on the edges valid now; `None` if none is
+    pub cardinality: Option<Cardinality>,
+    pub temporal: TemporalUsage,
+}
+
+/// Human-readable description of a tenant's schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaDoc {
+    pub tenant: String,
+    pub generated_at: DateTime<Utc>,
+    /// Nodes per label and relationships per kind sampled
+    pub sample: u32,
+    /// Labels, sorted by name
+    pub labels: Vec<LabelDoc>,
+    /// Relationship kinds, sorted by name
+    pub kinds: Vec<KindDoc>,
+}
+
+/// Describe a tenant's schema from its catalog, a sample of up to `sample`
+/// nodes per label and relationships per kind, and its ingestion schema
+pub async fn generate_schema_doc(
+    service: &dyn GraphService,
+    tenant: &TenantId,
+    schema: Option<&IngestSchema>,
+    sample: u32,
+) -> Result<SchemaDoc, GraphError> {
+    let sample = sample.clamp(1, MAX_SCHEMA_DOC_SAMPLE);
+    let catalog = service.catalog(tenant).await?;
+
+    let mut labels = Vec::with_capacity(catalog.labels.len());
+    for entry in &catalog.labels {
+        let nodes = service.query(tenant, Query::nodes().label(&entry.name).limit(sample).build()).await?;
+        let values = nodes.iter().filter_map(|path| path.nodes.first()).map(|node| &node.properties);
+        labels.push(LabelDoc {
+            name: entry.name.clone(),
+            count: entry.count,
+            properties: property_docs(entry, values, schema, TemplateKind::Node),
+        });
+    }
+
+    let mut kinds = Vec::with_capacity(catalog.kinds.len());
+    for entry in &catalog.kinds {
+        let all = Query::relationships().rel_type(&entry.name);
+        let pairs = all.clone().collapse(Collapse::ByKind);
+        let sampled = service.query(tenant, pairs.clone().limit(sample).build()).await?;
+        let current = service.query(tenant, all.clone().valid_at(Utc::now()).limit(sample).build()).await?;
+        let versions = service.query_count(tenant, all.build()).await?;
+        let pair_count = service.query_count(tenant, pairs.build()).await?;
+
+        let values = sampled.iter().flat_map(|path| &path.relationships).map(|rel| &rel.properties);
+        kinds.push(KindDoc {
+            name: entry.name.clone(),
+            count: entry.count,
+            properties: property_docs(entry, values, schema, TemplateKind::Relationship),
+            endpoints: endpoints(&sampled),
+            cardinality: Cardinality::observe(current.iter()
+                .flat_map(|path| &path.relationships)
+                .map(|rel| (rel.start_node_id, rel.end_node_id))),
+            temporal: temporal_usage(&sampled, versions, pair_count),
+        });
+    }
+
+    Ok(SchemaDoc {
+        tenant: tenant.to_string(),
+        generated_at: Utc::now(),
+        sample,
+        labels,
+        kinds,
+    })
+}
+
+fn property_docs<'a>(
+    entry: &CatalogEntry,
+    values: impl Iterator<Item = &'a Value>,
+    schema: Option<&IngestSchema>,
+    kind: TemplateKind,
+) -> Vec<PropertyDoc> {
+    let mut observed: HashMap<&str, (BTreeSet<&'static str>, Option<&Value>)> = HashMap::new();
+    for props in values {
+        for (key, value) in props.as_object().into_iter().flatten() {
+            let (types, example) = observed.entry(key.as_str()).or_default();
+            types.insert(json_type(value));
+            if example.is_none() && !value.is_null() {
+                *example = Some(value);
+            }
+        }
+    }
+
+    let mut properties: Vec<PropertyDoc> = entry.property_keys.iter()
+        .map(|(name, count)| {
+            let (types, example) = observed.remove(name.as_str()).unwrap_or_default();
+            PropertyDoc {
+                name: name.clone(),
+                count: *count,
+                types: types.into_iter().map(str::to_string).collect(),
+                declared: schema.and_then(|schema| schema.property_type(kind, &entry.name, name)),
+                example: example.cloned(),
+            }
+        })
+        .collect();
+    properties.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    properties
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn endpoints(paths: &[Path]) -> Vec<(String, String)> {
+    let label = |node: Option<&crate::types::PathNode>| node
+        .and_then(|node| node.labels.first())
+        .cloned()
+        .unwrap_or_default();
+    paths.iter()
+        .map(|path| (label(path.nodes.first()), label(path.nodes.get(1))))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn temporal_usage(pairs: &[Path], versions: u64, pair_count: u64) -> TemporalUsage {
+    let aggregates: Vec<_> = pairs.iter()
+        .flat_map(|path| &path.relationships)
+        .filter_map(|rel| rel.collapsed.as_ref())
+        .collect();
+    TemporalUsage {
+        pairs: pair_count,
+        versions_per_pair: if pair_count == 0 { 0.0 } else { versions as f64 / pair_count as f64 },
+        sampled_pairs: aggregates.len() as u64,
+        open_pairs: aggregates.iter().filter(|aggregate| aggregate.valid_to.is_none()).count() as u64,
+        earliest_valid_from: aggregates.iter().map(|aggregate| aggregate.valid_from).min(),
+        latest_valid_to: aggregates.iter().filter_map(|aggregate| aggregate.valid_to).max(),
+    }
+}
+
+impl SchemaDoc {
+    /// Render the document
+    pub fn render(&self, format: SchemaDocFormat) -> String {
+        match format {
+            SchemaDocFormat::Markdown => self.to_markdown(),
+            SchemaDocFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Schema of tenant `{}`\n", self.tenant);
+        let _ = writeln!(out, "{}\n", self.intro());
+        for (title, sections) in self.sections() {
+            let _ = writeln!(out, "## {}\n", title);
+            if sections.is_empty() {
+                let _ = writeln!(out, "None.\n");
+            }
+            for section in sections {
+                let _ = writeln!(out, "### `{}`\n", section.name);
+                for line in &section.summary {
+                    let _ = writeln!(out, "{}  ", line);
+                }
+                let _ = writeln!(out);
+                if section.rows.is_empty() {
+                    continue;
+                }
+                let _ = writeln!(out, "| {} |", PROPERTY_COLUMNS.join(" | "));
+                let _ = writeln!(out, "|{}", "---|".repeat(PROPERTY_COLUMNS.len()));
+                for row in &section.rows {
+                    let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                    let _ = writeln!(out, "| {} |", cells.join(" | "));
+                }
+                let _ = writeln!(out);
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        let title = format!("Schema of tenant {}", escape_html(&self.tenant));
+        let _ = writeln!(out, "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>", title);
+        let _ = writeln!(out, "<h1>{}</h1>\n<p>{}</p>", title, escape_html(&self.intro()));
+        for (title, sections) in self.sections() {
+            let _ = writeln!(out, "<h2>{}</h2>", title);
+            if sections.is_empty() {
+                let _ = writeln!(out, "<p>None.</p>");
+            }
+            for section in sections {
+                let _ = writeln!(out, "<h3><code>{}</code></h3>", escape_html(&section.name));
+                for line in &section.summary {
+                    let _ = writeln!(out, "<p>{}</p>", escape_html(line));
+                }
+                if section.rows.is_empty() {
+                    continue;
+                }
+                let header: String = PROPERTY_COLUMNS.iter().map(|column| format!("<th>{}</th>", column)).collect();
+                let _ = writeln!(out, "<table>\n<tr>{}</tr>", header);
+                for row in &section.rows {
+                    let cells: String = row.iter().map(|cell| format!("<td>{}</td>", escape_html(cell))).collect();
+                    let _ = writeln!(out, "<tr>{}</tr>", cells);
+                }
+                let _ = writeln!(out, "</table>");
+            }
+        }
+        let _ = writeln!(out, "</body>\n</html>");
+        out
+    }
+
+    fn intro(&self) -> String {
+        format!(
+            "Generated at {} from the catalog and a sample of up to {} nodes per label and relationships per kind.",
+            self.generated_at.format("%Y-%m-%d %H:%M UTC"),
+            self.sample,
+        )
+    }
+
+    /// Labels and kinds as sections of plain text, for either format
+    fn sections(&self) -> [(&'static str, Vec<Section>); 2] {
+        let labels = self.labels.iter()
+            .map(|label| Section {
+                name: label.name.clone(),
+                summary: vec![format!("{} {}.", label.count, plural(label.count, "node", "nodes"))],
+                rows: property_rows(&label.properties, label.count),
+            })
+            .collect();
+        let kinds = self.kinds.iter()
+            .map(|kind| {
+                let mut summary = vec![format!("{} {}.", kind.count, plural(kind.count, "edge", "edges"))];
+                if !kind.endpoints.is_empty() {
+                    let endpoints: Vec<String> = kind.endpoints.iter().map(|(from, to)| format!("{} → {}", from, to)).collect();
+                    summary.push(format!("Links {}.", endpoints.join(", ")));
+                }
+                if let Some(cardinality) = kind.cardinality {
+                    summary.push(format!("Observed {} on the edges valid now.", cardinality.as_str()));
+                }
+                summary.push(temporal_summary(&kind.temporal));
+                Section { name: kind.name.clone(), summary, rows: property_rows(&kind.properties, kind.count) }
+            })
+            .collect();
+        [("Labels", labels), ("Relationship kinds", kinds)]
+    }
+}
+
+const PROPERTY_COLUMNS: [&str; 5] = ["Property", "Present", "Types", "Declared", "Example"];
+
+/// A label or kind, in plain text
+struct Section {
+    name: String,
+    summary: Vec<String>,
+    rows: Vec<[String; 5]>,
+}
+
+fn property_rows(properties: &[PropertyDoc], total: u64) -> Vec<[String; 5]> {
+    properties.iter()
+        .map(|property| [
+            property.name.clone(),
+            format!("{}%", (property.count * 100).checked_div(total).unwrap_or(0)),
+            property.types.join(", "),
+            property.declared
+                .and_then(|declared| serde_json::to_value(declared).ok())
+                .and_then(|declared| declared.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            property.example.as_ref().map(example).unwrap_or_default(),
+        ])
+        .collect()
+}
+
+fn temporal_summary(temporal: &TemporalUsage) -> String {
+    let mut summary = format!(
+        "{} {} of nodes, {:.1} versions per pair",
+        temporal.pairs,
+        plural(temporal.pairs, "pair", "pairs"),
+        temporal.versions_per_pair,
+    );
+    if temporal.sampled_pairs > 0 {
+        let _ = write!(summary, "; {} of {} sampled still valid", temporal.open_pairs, temporal.sampled_pairs);
+    }
+    if let Some(valid_from) = temporal.earliest_valid_from {
+        let _ = write!(summary, "; valid from {}", valid_from.format("%Y-%m-%d"));
+    }
+    if let Some(valid_to) = temporal.latest_valid_to {
+        let _ = write!(summary, "; last closed {}", valid_to.format("%Y-%m-%d"));
+    }
+    summary + "."
+}
+
+fn example(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(MAX_EXAMPLE_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+fn plural(count: u64, one: &'static str, many: &'static str) -> &'static str {
+    if count == 1 { one } else { many }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc() -> SchemaDoc {
+        SchemaDoc {
+            tenant: "acme".to_string(),
+            generated_at: "2024-05-01T12:00:00Z".parse().unwrap(),
+            sample: 100,
+            labels: vec![LabelDoc {
+                name: "Person".to_string(),
+                count: 4,
+                properties: vec![PropertyDoc {
+                    name: "name".to_string(),
+                    count: 2,
+                    types: vec!["string".to_string()],
+                    declared: Some(Coercion::String),
+                    example: Some(json!("Alice <CTO> | Acme")),
+                }],
+            }],
+            kinds: vec![KindDoc {
+                name: "WORKS_FOR".to_string(),
+                count: 3,
+                properties: Vec::new(),
+                endpoints: vec![("Person".to_string(), "Company".to_string())],
+                cardinality: Some(Cardinality::ManyToOne),
+                temporal: TemporalUsage {
+                    pairs: 2,
+                    versions_per_pair: 1.5,
+                    sampled_pairs: 2,
+                    open_pairs: 1,
+                    earliest_valid_from: Some("2020-01-01T00:00:00Z".parse().unwrap()),
+                    latest_valid_to: None,
+                },
+            }],
+        }
+    }
+
+    #[test]
+    fn test_cardinality() {
+        let (alice, bob, acme, globex) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(Cardinality::observe([]), None);
+        assert_eq!(Cardinality::observe([(alice, acme), (bob, globex)]), Some(Cardinality::OneToOne));
+        assert_eq!(Cardinality::observe([(alice, acme), (bob, acme)]), Some(Cardinality::ManyToOne));
+        assert_eq!(Cardinality::observe([(acme, alice), (acme, bob)]), Some(Cardinality::OneToMany));
+        assert_eq!(Cardinality::observe([(alice, acme), (bob, acme), (alice, globex)]), Some(Cardinality::ManyToMany));
+        // Versions of the same pair do not make it many
+        assert_eq!(Cardinality::observe([(alice, acme), (alice, acme)]), Some(Cardinality::OneToOne));
+    }
+
+    #[test]
+    fn test_render_schema_doc() {
+        let markdown = doc().render(SchemaDocFormat::Markdown);
+        assert!(markdown.starts_with("# Schema of tenant `acme`\n"));
+        assert!(markdown.contains("| name | 50% | string | string | \"Alice <CTO> \\| Acme\" |"));
+        assert!(markdown.contains("Links Person → Company."));
+        assert!(markdown.contains("Observed many-to-one on the edges valid now."));
+        assert!(markdown.contains("2 pairs of nodes, 1.5 versions per pair; 1 of 2 sampled still valid; valid from 2020-01-01."));
+
+        let html = doc().render(SchemaDocFormat::Html);
+        assert!(html.contains("<h3><code>WORKS_FOR</code></h3>"));
+        assert!(html.contains("<td>&quot;Alice &lt;CTO&gt; | Acme&quot;</td>"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Encryption and signed manifests for exports that leave the trust boundary
+//!
+//! A per-tenant master key (32 bytes, base64-encoded) is expanded into separate
+//! encryption and signing keys. Export data is encrypted with AES-256-GCM bound
+//! to the tenant ID, and a manifest describing the export is signed with
+//! HMAC-SHA256 so tampering with either file is detected on verification.
+//! The server signs its exports with [`ExportKeys`]; kgctl signs the exports
+//! it writes and checks manifests before loading exports back.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use crate::errors::CoreError;
+use crate::types::TenantId;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Length of a tenant master key in bytes
+pub const KEY_LEN: usize = 32;
+
+/// Length of the AES-GCM nonce prepended to encrypted exports
+const NONCE_LEN: usize = 12;
+
+/// Current manifest format version
+const MANIFEST_VERSION: u32 = 1;
+
+/// Description of an export, written next to the data file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub version: u32,
+    pub tenant_id: String,
+    pub format: String,
+    pub generated_at: String,
+    pub snapshot_at: Option<String>,
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// Whether the data file is encrypted
+    pub encrypted: bool,
+    /// Hex SHA-256 of the data file as written (ciphertext when encrypted)
+    pub sha256: String,
+    /// Hex HMAC-SHA256 over the manifest with an empty signature
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl ExportManifest {
+    /// Describe an export whose data file contains `data`
+    pub fn new(
+        tenant_id: impl Into<String>,
+        format: impl Into<String>,
+        snapshot_at: Option<String>,
+        node_count: usize,
+        edge_count: usize,
+        encrypted: bool,
+        data: &[u8],
+    ) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            tenant_id: tenant_id.into(),
+            format: format.into(),
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            snapshot_at,
+            node_count,
+            edge_count,
+            encrypted,
+            sha256: sha256_hex(data),
+            signature: String::new(),
+        }
+    }
+
+    /// Bytes covered by the signature
+    fn signing_payload(&self) -> Result<Vec<u8>, CoreError> {
+        let unsigned = Self {
+            signature: String::new(),
+            ..self.clone()
+        };
+        serde_json::to_vec(&unsigned)
+            .map_err(|e| CoreError::Internal(format!("Failed to serialize manifest: {}", e)))
+    }
+}
+
+/// Per-tenant key material for export encryption and signing
+pub struct ExportKey {
+    encryption: [u8; KEY_LEN],
+    signing: [u8; KEY_LEN],
+}
+
+impl ExportKey {
+    /// Derive the export keys from a base64-encoded 32-byte master key
+    pub fn from_base64(encoded: &str) -> Result<Self, CoreError> {
+        let master = BASE64.decode(encoded.trim())
+            .map_err(|e| CoreError::Configuration(format!("Export key is not valid base64: {}", e)))?;
+        if master.len() != KEY_LEN {
+            return Err(CoreError::Configuration(format!(
+                "Export key must be {} bytes, got {}", KEY_LEN, master.len()
+            )));
+        }
+
+        Ok(Self {
+            encryption: derive_key(&master, b"telamentis-export-encryption"),
+            signing: derive_key(&master, b"telamentis-export-signing"),
+        })
+    }
+
+    /// Read a base64-encoded master key from a file
+    pub fn from_file(path: &Path) -> Result<Self, CoreError> {
+        let encoded = std::fs::read_to_string(path)
+            .map_err(|e| CoreError::Configuration(format!("Failed to read key file {}: {}", path.display(), e)))?;
+        Self::from_base64(&encoded)
+    }
+
+    /// Encrypt export data for a tenant; the output is `nonce || ciphertext`
+    pub fn encrypt(&self, tenant_id: &str, plaintext: &[u8]) -> Result<Vec<u8>, CoreError> {
+        let cipher = Aes256Gcm::new_from_slice(&self.encryption)
+            .map_err(|e| CoreError::Internal(format!("Invalid encryption key: {}", e)))?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let ciphertext = cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad: tenant_id.as_bytes() })
+            .map_err(|_| CoreError::Internal("Failed to encrypt export".to_string()))?;
+
+        let mut output = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    /// Decrypt export data produced by [`ExportKey::encrypt`] for the same tenant
+    pub fn decrypt(&self, tenant_id: &str, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        if data.len() < NONCE_LEN {
+            return Err(CoreError::Internal("Encrypted export is truncated".to_string()));
+        }
+
+        let cipher = Aes256Gcm::new_from_slice(&self.encryption)
+            .map_err(|e| CoreError::Internal(format!("Invalid encryption key: {}", e)))?;
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+
+        cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: tenant_id.as_bytes() })
+            .map_err(|_| CoreError::Internal(
+                "Failed to decrypt export: wrong key, wrong tenant or corrupted data".to_string()
+            ))
+    }
+
+    /// Sign a manifest in place
+    pub fn sign(&self, manifest: &mut ExportManifest) -> Result<(), CoreError> {
+        let mut mac = self.mac()?;
+        mac.update(&manifest.signing_payload()?);
+        manifest.signature = hex_encode(&mac.finalize().into_bytes());
+        Ok(())
+    }
+
+    /// Check the manifest signature and that `data` matches its checksum
+    pub fn verify(&self, manifest: &ExportManifest, data: &[u8]) -> Result<(), CoreError> {
+        let signature = hex_decode(&manifest.signature)
+            .ok_or_else(|| CoreError::Internal("Manifest signature is malformed".to_string()))?;
+
+        let mut mac = self.mac()?;
+        mac.update(&manifest.signing_payload()?);
+        mac.verify_slice(&signature)
+            .map_err(|_| CoreError::Internal("Manifest signature is invalid".to_string()))?;
+
+        if sha256_hex(data) != manifest.sha256 {
+            return Err(CoreError::Internal("Export data does not match the manifest checksum".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn mac(&self) -> Result<HmacSha256, CoreError> {
+        <HmacSha256 as Mac>::new_from_slice(&self.signing)
+            .map_err(|e| CoreError::Internal(format!("Invalid signing key: {}", e)))
+    }
+}
+
+/// Export keys of the tenants whose exports are signed, and can be encrypted
+#[derive(Default)]
+pub struct ExportKeys {
+    keys: HashMap<TenantId, ExportKey>,
+}
+
+impl ExportKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tenant's key, replacing any it had
+    pub fn with_key(mut self, tenant: TenantId, key: ExportKey) -> Self {
+        self.keys.insert(tenant, key);
+        self
+    }
+
+    /// Keys from base64-encoded master keys by tenant ID, as configured
+    pub fn from_base64(encoded: &HashMap<String, String>) -> Result<Self, CoreError> {
+        encoded.iter().try_fold(Self::new(), |keys, (tenant, key)| {
+            Ok(keys.with_key(TenantId::new(tenant.as_str()), ExportKey::from_base64(key)?))
+        })
+    }
+
+    /// Key of a tenant, if its exports are signed
+    pub fn get(&self, tenant: &TenantId) -> Option<&ExportKey> {
+        self.keys.get(tenant)
+    }
+}
+
+fn derive_key(master: &[u8], label: &[u8]) -> [u8; KEY_LEN] {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(master).expect("HMAC accepts any key length");
+    mac.update(label);
+    mac.finalize().into_bytes().into()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex_encode(&Sha256::digest(data))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [hi, lo] => u8::from_str_radix(std::str::from_utf8(&[*hi, *lo]).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> ExportKey {
+        ExportKey::from_base64(&BASE64.encode([7u8; KEY_LEN])).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_roundtrip_is_bound_to_tenant() {
+        let key = test_key();
+        let encrypted = key.encrypt("tenant_a", b"export data").unwrap();
+
+        assert_ne!(&encrypted[NONCE_LEN..], b"export data");
+        assert_eq!(key.decrypt("tenant_a", &encrypted).unwrap(), b"export data");
+        assert!(key.decrypt("tenant_b", &encrypted).is_err());
+    }
+
+    #[test]
+    fn test_manifest_signature() {
+        let key = test_key();
+        let data = b"{\"nodes\":[]}";
+        let mut manifest = ExportManifest::new("tenant_a", "jsonl", None, 0, 0, false, data);
+        key.sign(&mut manifest).unwrap();
+
+        assert!(key.verify(&manifest, data).is_ok());
+        assert!(key.verify(&manifest, b"tampered").is_err());
+
+        let mut forged = manifest.clone();
+        forged.node_count = 42;
+        assert!(key.verify(&forged, data).is_err());
+    }
+
+    #[test]
+    fn test_export_keys() {
+        let keys = ExportKeys::from_base64(&HashMap::from([("acme".to_string(), BASE64.encode([7u8; KEY_LEN]))])).unwrap();
+        let data = test_key().encrypt("acme", b"export data").unwrap();
+        assert_eq!(keys.get(&TenantId::new("acme")).unwrap().decrypt("acme", &data).unwrap(), b"export data");
+        assert!(keys.get(&TenantId::new("globex")).is_none());
+        assert!(ExportKeys::from_base64(&HashMap::from([("acme".to_string(), "short".to_string())])).is_err());
+    }
+
+    #[test]
+    fn test_key_validation() {
+        assert!(ExportKey::from_base64("not base64!").is_err());
+        assert!(ExportKey::from_base64(&BASE64.encode([1u8; 16])).is_err());
+    }
+}
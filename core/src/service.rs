@@ -0,0 +1,205 @@
+//! The standard `GraphService`
+//!
+//! [`CoreGraphService`] is what presentation adapters are normally started
+//! with: graph operations go straight to a `GraphStore`, and extraction goes
+//! through a [`GuardedConnector`] so that an unavailable or unconfigured LLM
+//! fails fast without affecting the graph. Calls made within a graph view
+//! are confined to it by a [`ViewGraphStore`] around the store.
+
+use crate::bulk::{EdgeStream, NodeStream};
+use crate::availability::{AvailabilityConfig, CapabilityStatus, GuardedConnector};
+use crate::constraints::EdgeConstraints;
+use crate::errors::{GraphError, LlmError};
+use crate::hooks::{HookedGraphStore, Hooks};
+use crate::materialized::SnapshotInfo;
+use crate::traits::{ExtractionContext, ExtractionEnvelope, GraphService, GraphStore, LlmConnector, ProviderStatus};
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::views::ViewGraphStore;
+use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeWithEdges, Path, TenantId, TimeEdge};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// `GraphService` over a graph store and an optional LLM connector
+pub struct CoreGraphService {
+    store: Arc<dyn GraphStore>,
+    llm: GuardedConnector,
+}
+
+impl CoreGraphService {
+    /// Create a service without extraction; extraction requests fail with
+    /// `LlmError::CapabilityUnavailable`
+    pub fn new(store: Arc<dyn GraphStore>) -> Self {
+        Self {
+            store: Arc::new(ViewGraphStore::new(store)),
+            llm: GuardedConnector::unconfigured(),
+        }
+    }
+
+    /// Extract with `connector`, guarded with the given availability settings
+    pub fn with_connector(mut self, connector: Arc<dyn LlmConnector>, availability: AvailabilityConfig) -> Self {
+        self.llm = GuardedConnector::new(connector, availability);
+        self
+    }
+
+    /// Run `hooks` on the store's operations. They run outside the store's
+    /// layers, so cached query results pass query result hooks too.
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        if !hooks.is_empty() {
+            self.store = Arc::new(HookedGraphStore::new(self.store, hooks));
+        }
+        self
+    }
+
+    /// The underlying store
+    pub fn store(&self) -> &Arc<dyn GraphStore> {
+        &self.store
+    }
+}
+
+#[async_trait]
+impl GraphService for CoreGraphService {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        self.store.upsert_node(tenant, node).await
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.store.upsert_edge(tenant, edge).await
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        self.store.close_edge(tenant, id, valid_to).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.store.supersede_edge(tenant, id, edge).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.store.retract_edge(tenant, id).await
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        self.store.upsert_node_with_edges(tenant, node, edges).await
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        self.store.bulk_load_nodes(tenant, nodes).await
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        self.store.bulk_load_edges(tenant, edges).await
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.store.query(tenant, query).await
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.store.query_count(tenant, query).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.store.query_exists(tenant, query).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.store.traverse(tenant, request).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.store.shortest_path(tenant, request).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.store.timeline(tenant, request).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.store.property_history(tenant, node_id, key).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        self.store.update_node(tenant, id, node).await
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.store.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.store.set_edge_constraints(tenant, constraints).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.store.resolve_aliases(tenant, aliases).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        self.store.snapshot(tenant, valid_at).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.store.materialize_snapshot(tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.store.list_snapshots(tenant).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.store.drop_snapshot(tenant, name).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.store.query_snapshot(tenant, name, query).await
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        self.store.summary(tenant).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.store.catalog(tenant).await
+    }
+
+    async fn extract_knowledge(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        self.llm.extract(tenant, context).await
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.store.health_check().await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.store.get_node(tenant, id).await
+    }
+    
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.store.delete_node(tenant, id).await
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.store.delete_edge(tenant, id).await
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        self.store.list_tenants().await
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        self.store.clear_tenant(tenant).await
+    }
+
+    async fn llm_status(&self) -> CapabilityStatus {
+        self.llm.status()
+    }
+
+    async fn llm_providers(&self, tenant: Option<&TenantId>) -> Vec<ProviderStatus> {
+        self.llm.provider_status(tenant).await
+    }
+}
@@ -0,0 +1,329 @@
+//! Ephemeral session graphs
+//!
+//! Agent frameworks often want a scratch graph per conversation that goes
+//! away on its own. A session graph is an ordinary graph in the same store,
+//! kept under the tenant ID `<tenant>~session~<session id>`, so every graph
+//! API works on it unchanged. A session expires once it has been inactive
+//! for its TTL; a background task then removes its graph with
+//! `GraphStore::clear_tenant`. Before that, selected nodes and edges can be
+//! promoted into the durable tenant graph.
+
+use crate::errors::{CoreError, SessionError};
+use crate::traits::GraphStore;
+use crate::types::{TenantId, TimeEdge};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock, Weak};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Separates the owning tenant from the session ID in a session graph's tenant ID
+pub const SESSION_SEPARATOR: &str = "~session~";
+
+/// Configuration for [`SessionGraphs`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionGraphConfig {
+    /// Inactivity after which a session expires, if it is created without a TTL, in seconds
+    pub default_ttl_secs: u64,
+    /// Longest TTL a session may be created with, in seconds
+    pub max_ttl_secs: u64,
+    /// Live sessions allowed per tenant
+    pub max_sessions_per_tenant: usize,
+    /// How often the background task purges expired sessions, in milliseconds
+    pub sweep_interval_ms: u64,
+}
+
+impl Default for SessionGraphConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl_secs: 3600,
+            max_ttl_secs: 7 * 24 * 3600,
+            max_sessions_per_tenant: 100,
+            sweep_interval_ms: 60_000,
+        }
+    }
+}
+
+/// An ephemeral graph owned by a tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EphemeralSession {
+    pub id: Uuid,
+    pub tenant: TenantId,
+    /// Tenant ID the session's graph is stored and queried under
+    pub graph: TenantId,
+    /// Inactivity after which the session expires, in seconds
+    pub ttl_secs: u64,
+    pub created_at: DateTime<Utc>,
+    pub last_active_at: DateTime<Utc>,
+    /// When the session expires unless it is used again
+    pub expires_at: DateTime<Utc>,
+}
+
+impl EphemeralSession {
+    fn touch(&mut self, now: DateTime<Utc>) {
+        self.last_active_at = now;
+        self.expires_at = now + Duration::seconds(self.ttl_secs as i64);
+    }
+}
+
+/// Nodes and edges of a session graph to copy into the durable tenant graph
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromoteRequest {
+    /// Session node IDs
+    pub nodes: Vec<Uuid>,
+    /// Session edge IDs; their endpoints are promoted with them
+    pub edges: Vec<Uuid>,
+}
+
+/// Outcome of a promotion, mapping session IDs to durable IDs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromotionReport {
+    pub nodes: HashMap<Uuid, Uuid>,
+    pub edges: HashMap<Uuid, Uuid>,
+}
+
+/// Tenant ID of a session's graph
+pub fn session_graph(tenant: &TenantId, session: Uuid) -> TenantId {
+    TenantId::new(format!("{}{}{}", tenant, SESSION_SEPARATOR, session))
+}
+
+/// Owning tenant and session ID of a session graph's tenant ID; `None` for
+/// ordinary tenants
+pub fn session_of(graph: &TenantId) -> Option<(TenantId, Uuid)> {
+    let (tenant, session) = graph.as_str().rsplit_once(SESSION_SEPARATOR)?;
+    let session = Uuid::parse_str(session).ok()?;
+    (!tenant.is_empty()).then(|| (TenantId::new(tenant), session))
+}
+
+struct Shared {
+    store: Arc<dyn GraphStore>,
+    config: SessionGraphConfig,
+    sessions: RwLock<HashMap<Uuid, EphemeralSession>>,
+}
+
+impl Shared {
+    /// Remove sessions expired at `now` and their graphs, returning how many were removed
+    async fn purge_expired(&self, now: DateTime<Utc>) -> usize {
+        let expired: Vec<EphemeralSession> = {
+            let mut sessions = self.sessions.write().unwrap();
+            let ids: Vec<Uuid> = sessions.values().filter(|s| s.expires_at <= now).map(|s| s.id).collect();
+            ids.iter().filter_map(|id| sessions.remove(id)).collect()
+        };
+
+        for session in &expired {
+            match self.store.clear_tenant(&session.graph).await {
+                Ok(removed) => info!("Session {} of tenant {} expired; removed {} records", session.id, session.tenant, removed),
+                Err(e) => warn!("Failed to remove graph of expired session {}: {}", session.id, e),
+            }
+        }
+        expired.len()
+    }
+}
+
+/// Creates, tracks and expires tenants' session graphs.
+///
+/// Must be created inside a Tokio runtime. Call [`SessionGraphs::shutdown`]
+/// before dropping it to stop the background sweeper cleanly.
+pub struct SessionGraphs {
+    shared: Arc<Shared>,
+    shutdown: Arc<Notify>,
+    sweeper: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl SessionGraphs {
+    /// Keep session graphs in `store` and start purging expired ones
+    pub fn start(store: Arc<dyn GraphStore>, config: SessionGraphConfig) -> Self {
+        let shared = Arc::new(Shared {
+            store,
+            config,
+            sessions: RwLock::new(HashMap::new()),
+        });
+        let shutdown = Arc::new(Notify::new());
+        let sweeper = tokio::spawn(Self::run_sweeper(Arc::downgrade(&shared), shutdown.clone()));
+
+        Self {
+            shared,
+            shutdown,
+            sweeper: std::sync::Mutex::new(Some(sweeper)),
+        }
+    }
+
+    async fn run_sweeper(shared: Weak<Shared>, shutdown: Arc<Notify>) {
+        let Some(period) = shared.upgrade().map(|s| std::time::Duration::from_millis(s.config.sweep_interval_ms.max(1))) else {
+            return;
+        };
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match shared.upgrade() {
+                        Some(shared) => { shared.purge_expired(Utc::now()).await; }
+                        None => break,
+                    }
+                }
+                _ = shutdown.notified() => break,
+            }
+        }
+    }
+
+    /// Start a session for the tenant, expiring after `ttl_secs` of
+    /// inactivity or the configured default
+    pub fn create(&self, tenant: &TenantId, ttl_secs: Option<u64>) -> Result<EphemeralSession, SessionError> {
+        let config = &self.shared.config;
+        let ttl_secs = ttl_secs.unwrap_or(config.default_ttl_secs);
+        if ttl_secs == 0 || ttl_secs > config.max_ttl_secs {
+            return Err(SessionError::InvalidTtl(format!("TTL must be between 1 and {} seconds", config.max_ttl_secs)));
+        }
+
+        let mut sessions = self.shared.sessions.write().unwrap();
+        let now = Utc::now();
+        let live = sessions.values().filter(|s| &s.tenant == tenant && s.expires_at > now).count();
+        if live >= config.max_sessions_per_tenant {
+            return Err(SessionError::LimitReached(format!("Tenant {} has {} live sessions", tenant, live)));
+        }
+
+        let id = Uuid::new_v4();
+        let session = EphemeralSession {
+            id,
+            tenant: tenant.clone(),
+            graph: session_graph(tenant, id),
+            ttl_secs,
+            created_at: now,
+            last_active_at: now,
+            expires_at: now + Duration::seconds(ttl_secs as i64),
+        };
+        sessions.insert(id, session.clone());
+        info!("Created session {} for tenant {} with a TTL of {}s", id, tenant, ttl_secs);
+        Ok(session)
+    }
+
+    /// A live session of the tenant
+    pub fn get(&self, tenant: &TenantId, id: Uuid) -> Result<EphemeralSession, SessionError> {
+        let sessions = self.shared.sessions.read().unwrap();
+        sessions.get(&id)
+            .filter(|s| &s.tenant == tenant && s.expires_at > Utc::now())
+            .cloned()
+            .ok_or_else(|| SessionError::NotFound(id.to_string()))
+    }
+
+    /// The tenant's live sessions, oldest first
+    pub fn list(&self, tenant: &TenantId) -> Vec<EphemeralSession> {
+        let now = Utc::now();
+        let mut sessions: Vec<EphemeralSession> = self.shared.sessions.read().unwrap()
+            .values()
+            .filter(|s| &s.tenant == tenant && s.expires_at > now)
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|s| s.created_at);
+        sessions
+    }
+
+    /// Record activity on a session, restarting its TTL
+    pub fn touch(&self, tenant: &TenantId, id: Uuid) -> Result<EphemeralSession, SessionError> {
+        let mut sessions = self.shared.sessions.write().unwrap();
+        let now = Utc::now();
+        let session = sessions.get_mut(&id)
+            .filter(|s| &s.tenant == tenant && s.expires_at > now)
+            .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+        session.touch(now);
+        Ok(session.clone())
+    }
+
+    /// End a session now, removing its graph; returns the number of records removed
+    pub async fn end(&self, tenant: &TenantId, id: Uuid) -> Result<u64, CoreError> {
+        let session = {
+            let mut sessions = self.shared.sessions.write().unwrap();
+            match sessions.get(&id) {
+                Some(s) if &s.tenant == tenant => sessions.remove(&id).unwrap(),
+                _ => return Err(SessionError::NotFound(id.to_string()).into()),
+            }
+        };
+        let removed = self.shared.store.clear_tenant(&session.graph).await?;
+        info!("Ended session {} of tenant {}; removed {} records", id, tenant, removed);
+        Ok(removed)
+    }
+
+    /// Copy nodes and edges of a session graph into the tenant's durable
+    /// graph. Nodes with an `id_alias` are merged into the durable node of
+    /// that alias; edges are recorded as new facts with their valid times.
+    pub async fn promote(&self, tenant: &TenantId, id: Uuid, request: &PromoteRequest) -> Result<PromotionReport, CoreError> {
+        let session = self.touch(tenant, id)?;
+        let snapshot = self.shared.store.snapshot(&session.graph, None).await?;
+        let edges: HashMap<Uuid, &TimeEdge> = snapshot.edges.iter().map(|r| (r.id, &r.edge)).collect();
+
+        let mut node_ids: Vec<Uuid> = request.nodes.clone();
+        for edge_id in &request.edges {
+            let edge = edges.get(edge_id).ok_or_else(|| SessionError::InvalidPromotion(format!("Edge {} is not in session {}", edge_id, id)))?;
+            node_ids.extend([edge.from_node_id, edge.to_node_id]);
+        }
+        let mut seen = HashSet::new();
+        node_ids.retain(|node_id| seen.insert(*node_id));
+
+        let nodes: HashMap<Uuid, _> = snapshot.nodes.iter().map(|r| (r.id, &r.node)).collect();
+        let mut report = PromotionReport::default();
+        for node_id in node_ids {
+            let node = nodes.get(&node_id).ok_or_else(|| SessionError::InvalidPromotion(format!("Node {} is not in session {}", node_id, id)))?;
+            let durable_id = self.shared.store.upsert_node(tenant, (*node).clone()).await?;
+            report.nodes.insert(node_id, durable_id);
+        }
+
+        for edge_id in &request.edges {
+            let edge = edges[edge_id];
+            let mut promoted = TimeEdge::new(report.nodes[&edge.from_node_id], report.nodes[&edge.to_node_id], edge.kind.clone(), edge.valid_from, edge.props.clone());
+            promoted.valid_to = edge.valid_to;
+            let durable_id = self.shared.store.upsert_edge(tenant, promoted).await?;
+            report.edges.insert(*edge_id, durable_id);
+        }
+
+        debug!("Promoted {} nodes and {} edges from session {} of tenant {}", report.nodes.len(), report.edges.len(), id, tenant);
+        Ok(report)
+    }
+
+    /// Remove sessions expired at `now` and their graphs, returning how many were removed
+    pub async fn purge_expired(&self, now: DateTime<Utc>) -> usize {
+        self.shared.purge_expired(now).await
+    }
+
+    /// Stop the background sweeper
+    pub async fn shutdown(&self) {
+        self.shutdown.notify_one();
+
+        let sweeper = self.sweeper.lock().unwrap().take();
+        if let Some(sweeper) = sweeper {
+            let _ = sweeper.await;
+        }
+    }
+}
+
+impl Drop for SessionGraphs {
+    fn drop(&mut self) {
+        if let Some(sweeper) = self.sweeper.lock().unwrap().take() {
+            sweeper.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_graph_names() {
+        let tenant = TenantId::new("acme");
+        let id = Uuid::new_v4();
+        let graph = session_graph(&tenant, id);
+        assert_eq!(graph.as_str(), format!("acme~session~{}", id));
+        assert_eq!(session_of(&graph), Some((tenant.clone(), id)));
+
+        assert_eq!(session_of(&tenant), None);
+        assert_eq!(session_of(&TenantId::new("acme~session~not-a-uuid")), None);
+        assert_eq!(session_of(&TenantId::new(format!("~session~{}", id))), None);
+    }
+}
@@ -0,0 +1,284 @@
+//! HMAC request signing for integrators that cannot use TLS client certificates
+//!
+//! A signed request carries three headers:
+//!
+//! * `X-TelaMentis-Timestamp`: Unix time in seconds when the request was signed
+//! * `X-TelaMentis-Content-SHA256`: hex SHA-256 of the request body
+//! * `X-TelaMentis-Signature`: `v1=` followed by the hex HMAC-SHA256, keyed
+//!   with the tenant's shared secret, of the canonical string
+//!   `"{timestamp}\n{METHOD}\n{path and query}\n{body digest}"`
+//!
+//! [`RequestSigningPlugin`] verifies them in `PipelineStage::Verification`.
+//! The presentation adapter supplies the request's headers and the digest of
+//! the body it actually received; requests outside the replay window, or
+//! whose signature was already seen within it, are rejected.
+
+use crate::errors::AuthError;
+use crate::traits::{PipelinePlugin, PluginConfig, PluginOutcome, RequestContext};
+use crate::types::TenantId;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// Header holding the signing time, in Unix seconds
+pub const TIMESTAMP_HEADER: &str = "x-telamentis-timestamp";
+
+/// Header holding the hex SHA-256 of the body
+pub const CONTENT_SHA256_HEADER: &str = "x-telamentis-content-sha256";
+
+/// Header holding the versioned signature
+pub const SIGNATURE_HEADER: &str = "x-telamentis-signature";
+
+/// Request attribute holding the hex SHA-256 of the body as received
+pub const BODY_SHA256_ATTRIBUTE: &str = "body_sha256";
+
+/// Version prefix of signatures in `SIGNATURE_HEADER`
+const SIGNATURE_VERSION: &str = "v1=";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signing settings of one tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenantSigning {
+    /// Shared secret the tenant's requests are signed with
+    pub secret: String,
+    /// Reject unsigned requests. When false, only requests that carry a
+    /// signature are verified, which lets integrators switch over gradually.
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// Configuration for [`RequestSigningPlugin`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SigningConfig {
+    /// Tenants whose requests are verified; other tenants are not checked
+    pub tenants: HashMap<TenantId, TenantSigning>,
+    /// Largest accepted difference between the signing time and now, in seconds
+    pub replay_window_secs: u64,
+}
+
+impl Default for SigningConfig {
+    fn default() -> Self {
+        Self {
+            tenants: HashMap::new(),
+            replay_window_secs: 300,
+        }
+    }
+}
+
+/// Hex SHA-256 of a request body
+pub fn body_digest(body: &[u8]) -> String {
+    to_hex(&Sha256::digest(body))
+}
+
+/// [`body_digest`] of a body read a chunk at a time
+#[derive(Default)]
+pub struct BodyHasher(Sha256);
+
+impl BodyHasher {
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    pub fn finish(self) -> String {
+        to_hex(&self.0.finalize())
+    }
+}
+
+/// The `SIGNATURE_HEADER` value for a request
+pub fn sign(secret: &str, timestamp: i64, method: &str, path: &str, body_digest: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(canonical_request(timestamp, method, path, body_digest).as_bytes());
+    format!("{}{}", SIGNATURE_VERSION, to_hex(&mac.finalize().into_bytes()))
+}
+
+/// The headers that sign a request, as (name, value) pairs
+pub fn signature_headers(secret: &str, timestamp: i64, method: &str, path: &str, body: &[u8]) -> Vec<(&'static str, String)> {
+    let digest = body_digest(body);
+    vec![
+        (TIMESTAMP_HEADER, timestamp.to_string()),
+        (SIGNATURE_HEADER, sign(secret, timestamp, method, path, &digest)),
+        (CONTENT_SHA256_HEADER, digest),
+    ]
+}
+
+fn canonical_request(timestamp: i64, method: &str, path: &str, body_digest: &str) -> String {
+    format!("{}\n{}\n{}\n{}", timestamp, method.to_ascii_uppercase(), path, body_digest)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Verification-stage plugin that checks HMAC request signatures
+pub struct RequestSigningPlugin {
+    name: &'static str,
+    config: SigningConfig,
+    /// Signatures seen within the replay window, with their timestamps
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl RequestSigningPlugin {
+    pub fn new(config: SigningConfig) -> Self {
+        Self {
+            name: "RequestSigning",
+            config,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verify a request's signature at `now` (Unix seconds). `Ok` if the
+    /// request is signed correctly or its tenant does not require signing.
+    pub fn verify(&self, ctx: &RequestContext, now: i64) -> Result<(), AuthError> {
+        let Some(signing) = ctx.tenant_id.as_ref().and_then(|tenant| self.config.tenants.get(tenant)) else {
+            return Ok(());
+        };
+        let header = |name: &str| ctx.headers.get(name).map(String::as_str);
+        let Some(signature) = header(SIGNATURE_HEADER) else {
+            return if signing.required {
+                Err(AuthError::InvalidSignature("Request is not signed".to_string()))
+            } else {
+                Ok(())
+            };
+        };
+
+        let timestamp: i64 = header(TIMESTAMP_HEADER)
+            .and_then(|value| value.trim().parse().ok())
+            .ok_or_else(|| AuthError::InvalidSignature(format!("Missing or invalid {} header", TIMESTAMP_HEADER)))?;
+        let window = self.config.replay_window_secs as i64;
+        if (now - timestamp).abs() > window {
+            return Err(AuthError::InvalidSignature(format!("Timestamp is outside the {}s replay window", window)));
+        }
+
+        let received_digest = ctx.get_attribute(BODY_SHA256_ATTRIBUTE)
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| AuthError::InvalidSignature("Body digest is unavailable".to_string()))?;
+        if header(CONTENT_SHA256_HEADER).is_some_and(|claimed| !claimed.eq_ignore_ascii_case(received_digest)) {
+            return Err(AuthError::InvalidSignature("Body does not match its digest".to_string()));
+        }
+
+        let mac_bytes = signature.strip_prefix(SIGNATURE_VERSION)
+            .and_then(from_hex)
+            .ok_or_else(|| AuthError::InvalidSignature("Malformed signature".to_string()))?;
+        let mut mac = HmacSha256::new_from_slice(signing.secret.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(canonical_request(timestamp, &ctx.method, &ctx.path, received_digest).as_bytes());
+        mac.verify_slice(&mac_bytes)
+            .map_err(|_| AuthError::InvalidSignature("Signature does not match".to_string()))?;
+
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, signed_at| (now - *signed_at).abs() <= window);
+        if seen.insert(signature.to_string(), timestamp).is_some() {
+            return Err(AuthError::InvalidSignature("Signature was already used".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PipelinePlugin for RequestSigningPlugin {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn init(&mut self, config: PluginConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.config = serde_json::from_value(config.config)?;
+        info!("Initialized RequestSigning plugin for {} tenant(s)", self.config.tenants.len());
+        Ok(())
+    }
+
+    async fn call(&self, ctx: &mut RequestContext) -> PluginOutcome {
+        match self.verify(ctx, chrono::Utc::now().timestamp()) {
+            Ok(()) => {
+                debug!("Request {} passed signature verification", ctx.request_id);
+                PluginOutcome::Continue
+            }
+            Err(e) => {
+                warn!("Rejected request {} to {}: {}", ctx.request_id, ctx.path, e);
+                // The runner reports the reason as `AuthError::InvalidSignature`
+                let reason = match e {
+                    AuthError::InvalidSignature(reason) => reason,
+                    other => other.to_string(),
+                };
+                PluginOutcome::HaltWithError(reason.into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn plugin(required: bool) -> RequestSigningPlugin {
+        let mut config = SigningConfig::default();
+        config.tenants.insert(TenantId::new("acme"), TenantSigning { secret: "s3cret".to_string(), required });
+        RequestSigningPlugin::new(config)
+    }
+
+    fn request(tenant: &str, body: &[u8], headers: Vec<(&'static str, String)>) -> RequestContext {
+        let mut ctx = RequestContext::new("POST".to_string(), format!("/v1/graph/{}/nodes", tenant));
+        ctx.tenant_id = Some(TenantId::new(tenant));
+        ctx.headers = headers.into_iter().map(|(name, value)| (name.to_string(), value)).collect();
+        ctx.set_attribute(BODY_SHA256_ATTRIBUTE, serde_json::json!(body_digest(body)));
+        ctx
+    }
+
+    #[test]
+    fn test_valid_signature_and_replay() {
+        let plugin = plugin(true);
+        let body = br#"{"node":{}}"#;
+        let headers = signature_headers("s3cret", NOW, "POST", "/v1/graph/acme/nodes", body);
+        let ctx = request("acme", body, headers);
+
+        assert!(plugin.verify(&ctx, NOW + 10).is_ok());
+        assert!(matches!(plugin.verify(&ctx, NOW + 20), Err(AuthError::InvalidSignature(msg)) if msg.contains("already used")));
+    }
+
+    #[test]
+    fn test_rejected_requests() {
+        let plugin = plugin(true);
+        let body = b"{}";
+        let signed = || signature_headers("s3cret", NOW, "POST", "/v1/graph/acme/nodes", body);
+
+        // Unsigned, stale, tampered body, wrong secret
+        assert!(plugin.verify(&request("acme", body, Vec::new()), NOW).is_err());
+        assert!(plugin.verify(&request("acme", body, signed()), NOW + 301).is_err());
+        let mut tampered = request("acme", b"{\"x\":1}", signed());
+        tampered.headers.remove(CONTENT_SHA256_HEADER);
+        assert!(plugin.verify(&tampered, NOW).is_err());
+        let forged = signature_headers("guess", NOW, "POST", "/v1/graph/acme/nodes", body);
+        assert!(plugin.verify(&request("acme", body, forged), NOW).is_err());
+
+        // Other tenants are not checked
+        assert!(plugin.verify(&request("globex", body, Vec::new()), NOW).is_ok());
+    }
+
+    #[test]
+    fn test_optional_signing() {
+        let plugin = plugin(false);
+        assert!(plugin.verify(&request("acme", b"", Vec::new()), NOW).is_ok());
+        let forged = signature_headers("guess", NOW, "POST", "/v1/graph/acme/nodes", b"");
+        assert!(plugin.verify(&request("acme", b"", forged), NOW).is_err());
+    }
+}
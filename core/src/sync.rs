@@ -0,0 +1,601 @@
+//! Change-based sync of tenant graphs between instances
+//!
+//! Edge sites run their own instance and exchange changes with a central
+//! one. [`SyncGraphStore`] records each successful node and edge write in a
+//! per-tenant change log, numbered by a cursor; a peer pulls the changes
+//! after the cursor it last saw with [`SyncEngine::changes`] and applies
+//! them with [`SyncEngine::apply`], which remembers the sender's cursor so
+//! the next sync resumes from there. Pushing is the same exchange in the
+//! other direction.
+//!
+//! Instances assign their own node IDs, so changes refer to nodes by alias:
+//! nodes without an alias are synced as new nodes, and edges are only synced
+//! when both endpoints have one. Edge deletes and corrections (close,
+//! supersede, retract) are not synced yet.
+//!
+//! Changes applied from a peer are logged under the peer's instance ID, and
+//! [`SyncEngine::changes`] can leave out the requesting peer's own changes,
+//! so they are not sent back. When a peer's change meets a local version of
+//! the same node or edge written by another instance, a [`ConflictResolver`]
+//! decides which one stays; the default, [`LastWriterWins`], keeps the one
+//! with the later transaction time.
+
+use crate::archive::ArchiveBatch;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{self, EdgeStream, NodeStream};
+use crate::errors::GraphError;
+use crate::materialized::SnapshotInfo;
+use crate::traits::{GraphService, GraphStore};
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::types::{AliasKey, EdgeDirection, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeRef, NodeWithEdges, Path, TenantId, TimeEdge};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+tokio::task_local! {
+    /// Origin and transaction time of the peer change being applied
+    static APPLYING: (String, DateTime<Utc>);
+}
+
+/// Configuration for [`SyncEngine`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// ID of this instance, unique among the instances that sync together
+    pub instance_id: String,
+    /// Changes kept per tenant; peers further behind must resync in full
+    pub max_changes_per_tenant: usize,
+    /// Changes returned per pull at most
+    pub max_batch_size: usize,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            instance_id: Uuid::new_v4().to_string(),
+            max_changes_per_tenant: 100_000,
+            max_batch_size: 1_000,
+        }
+    }
+}
+
+/// A synced write
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncOperation {
+    UpsertNode { node: Node },
+    /// Edge between the nodes with the given aliases; its node IDs are the
+    /// sender's and are replaced on apply
+    UpsertEdge { from: AliasKey, to: AliasKey, edge: TimeEdge },
+    DeleteNode { alias: AliasKey },
+}
+
+impl SyncOperation {
+    fn key(&self) -> Option<RecordKey> {
+        match self {
+            SyncOperation::UpsertNode { node } => node.alias_key().map(RecordKey::Node),
+            SyncOperation::DeleteNode { alias } => Some(RecordKey::Node(alias.clone())),
+            SyncOperation::UpsertEdge { from, to, edge } => Some(RecordKey::Edge(from.clone(), to.clone(), edge.kind.clone())),
+        }
+    }
+}
+
+/// An entry of a tenant's change log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncChange {
+    /// Position in the log of the instance that sent it
+    pub cursor: u64,
+    /// Instance the write was first made on
+    pub origin: String,
+    /// When the write was first made
+    pub transaction_time: DateTime<Utc>,
+    pub operation: SyncOperation,
+}
+
+/// Changes after a cursor, as pulled from a peer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangeBatch {
+    /// Instance the batch was read from
+    pub instance_id: String,
+    pub changes: Vec<SyncChange>,
+    /// Cursor to pull from next
+    pub next_cursor: u64,
+    pub has_more: bool,
+    /// Whether changes after the requested cursor were already dropped from
+    /// the log, so the requester has missed some
+    pub truncated: bool,
+}
+
+/// Outcome of applying a [`ChangeBatch`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApplyReport {
+    pub applied: usize,
+    /// Cursors of changes that lost to a local version
+    pub skipped: Vec<u64>,
+    /// The change that failed; applying stops there so it is retried on the
+    /// next sync
+    pub failed: Option<SyncFailure>,
+    /// Cursor of the sender recorded for the next pull
+    pub cursor: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncFailure {
+    pub cursor: u64,
+    pub error: String,
+}
+
+/// Sync position of a tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub instance_id: String,
+    /// Cursor of the latest change
+    pub cursor: u64,
+    /// Cursor of the oldest change still in the log
+    pub oldest_cursor: Option<u64>,
+    /// Cursor of each peer's log that this instance has applied up to
+    pub peers: BTreeMap<String, u64>,
+}
+
+/// Who last wrote a node or edge, and when
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordVersion {
+    pub origin: String,
+    pub transaction_time: DateTime<Utc>,
+}
+
+/// Decides between a peer's change and the local version of the same node
+/// or edge, when another instance wrote the local version
+pub trait ConflictResolver: Send + Sync {
+    /// Whether `remote` replaces `local`
+    fn accept(&self, local: &RecordVersion, remote: &SyncChange) -> bool;
+}
+
+/// Keeps the version with the later transaction time; ties go to the
+/// greater instance ID so that every instance picks the same one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LastWriterWins;
+
+impl ConflictResolver for LastWriterWins {
+    fn accept(&self, local: &RecordVersion, remote: &SyncChange) -> bool {
+        (remote.transaction_time, remote.origin.as_str()) > (local.transaction_time, local.origin.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum RecordKey {
+    Node(AliasKey),
+    Edge(AliasKey, AliasKey, String),
+}
+
+#[derive(Default)]
+struct TenantLog {
+    last_cursor: u64,
+    changes: VecDeque<SyncChange>,
+    versions: HashMap<RecordKey, RecordVersion>,
+    peers: BTreeMap<String, u64>,
+}
+
+/// Per-tenant change logs and the sync operations on them
+pub struct SyncEngine {
+    config: SyncConfig,
+    tenants: RwLock<HashMap<TenantId, TenantLog>>,
+    resolver: Arc<dyn ConflictResolver>,
+}
+
+impl SyncEngine {
+    pub fn new(config: SyncConfig) -> Self {
+        Self {
+            config,
+            tenants: RwLock::new(HashMap::new()),
+            resolver: Arc::new(LastWriterWins),
+        }
+    }
+
+    /// Resolve conflicts with a custom policy instead of last-writer-wins
+    pub fn with_resolver(mut self, resolver: Arc<dyn ConflictResolver>) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.config.instance_id
+    }
+
+    pub fn status(&self, tenant: &TenantId) -> SyncStatus {
+        let tenants = self.tenants.read().unwrap();
+        let log = tenants.get(tenant);
+        SyncStatus {
+            instance_id: self.config.instance_id.clone(),
+            cursor: log.map_or(0, |log| log.last_cursor),
+            oldest_cursor: log.and_then(|log| log.changes.front()).map(|change| change.cursor),
+            peers: log.map(|log| log.peers.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Changes after `since`, at most `limit` (capped at `max_batch_size`),
+    /// leaving out those that originated on `exclude_origin`
+    pub fn changes(&self, tenant: &TenantId, since: u64, limit: Option<usize>, exclude_origin: Option<&str>) -> ChangeBatch {
+        let limit = limit.unwrap_or(self.config.max_batch_size).clamp(1, self.config.max_batch_size.max(1));
+        let tenants = self.tenants.read().unwrap();
+        let mut batch = ChangeBatch {
+            instance_id: self.config.instance_id.clone(),
+            next_cursor: since,
+            ..Default::default()
+        };
+        let Some(log) = tenants.get(tenant) else {
+            return batch;
+        };
+
+        batch.truncated = log.changes.front().is_some_and(|oldest| oldest.cursor > since + 1);
+        for change in log.changes.iter().filter(|change| change.cursor > since) {
+            if batch.changes.len() == limit {
+                batch.has_more = true;
+                break;
+            }
+            batch.next_cursor = change.cursor;
+            if exclude_origin != Some(change.origin.as_str()) {
+                batch.changes.push(change.clone());
+            }
+        }
+        batch
+    }
+
+    /// Apply a batch pulled from a peer through `service`, in order, and
+    /// record the peer's cursor. Stops at the first change that fails.
+    pub async fn apply(&self, service: &dyn GraphService, tenant: &TenantId, batch: ChangeBatch) -> ApplyReport {
+        let mut report = ApplyReport { cursor: self.peer_cursor(tenant, &batch.instance_id), ..Default::default() };
+        if batch.truncated {
+            warn!("Sync from {} for tenant {} missed changes that were dropped from its log", batch.instance_id, tenant);
+        }
+
+        for change in batch.changes {
+            if change.cursor <= report.cursor {
+                continue;
+            }
+            if self.conflicts(tenant, &change) {
+                debug!("Change {} from {} lost to the local version", change.cursor, batch.instance_id);
+                report.skipped.push(change.cursor);
+            } else {
+                let scope = (change.origin.clone(), change.transaction_time);
+                if let Err(e) = APPLYING.scope(scope, apply_operation(service, tenant, change.operation)).await {
+                    report.failed = Some(SyncFailure { cursor: change.cursor, error: e.to_string() });
+                    break;
+                }
+                report.applied += 1;
+            }
+            report.cursor = change.cursor;
+        }
+        if report.failed.is_none() {
+            report.cursor = report.cursor.max(batch.next_cursor);
+        }
+
+        self.tenants.write().unwrap()
+            .entry(tenant.clone()).or_default()
+            .peers.insert(batch.instance_id.clone(), report.cursor);
+        info!("Applied {} changes from {} for tenant {} ({} skipped)", report.applied, batch.instance_id, tenant, report.skipped.len());
+        report
+    }
+
+    fn peer_cursor(&self, tenant: &TenantId, peer: &str) -> u64 {
+        self.tenants.read().unwrap()
+            .get(tenant)
+            .and_then(|log| log.peers.get(peer).copied())
+            .unwrap_or(0)
+    }
+
+    fn conflicts(&self, tenant: &TenantId, change: &SyncChange) -> bool {
+        let Some(key) = change.operation.key() else {
+            return false;
+        };
+        let tenants = self.tenants.read().unwrap();
+        match tenants.get(tenant).and_then(|log| log.versions.get(&key)) {
+            Some(local) if local.origin != change.origin => !self.resolver.accept(local, change),
+            _ => false,
+        }
+    }
+
+    /// Append a successful write to the tenant's log, under the origin of
+    /// the peer change being applied, if any
+    fn record(&self, tenant: &TenantId, operation: SyncOperation) {
+        let (origin, transaction_time) = APPLYING.try_with(|applying| applying.clone())
+            .unwrap_or_else(|_| (self.config.instance_id.clone(), Utc::now()));
+
+        let mut tenants = self.tenants.write().unwrap();
+        let log = tenants.entry(tenant.clone()).or_default();
+        if let Some(key) = operation.key() {
+            log.versions.insert(key, RecordVersion { origin: origin.clone(), transaction_time });
+        }
+        log.last_cursor += 1;
+        log.changes.push_back(SyncChange { cursor: log.last_cursor, origin, transaction_time, operation });
+        if log.changes.len() > self.config.max_changes_per_tenant {
+            log.changes.pop_front();
+        }
+    }
+}
+
+async fn apply_operation(service: &dyn GraphService, tenant: &TenantId, operation: SyncOperation) -> Result<(), GraphError> {
+    match operation {
+        SyncOperation::UpsertNode { node } => service.upsert_node(tenant, node).await.map(|_| ()),
+        SyncOperation::UpsertEdge { from, to, mut edge } => {
+            let resolved = service.resolve_aliases(tenant, &[from.clone(), to.clone()]).await?;
+            let endpoint = |key: &AliasKey| resolved.get(key).copied()
+                .ok_or_else(|| GraphError::NodeNotFound(format!("edge endpoint {:?}", key)));
+            edge.from_node_id = endpoint(&from)?;
+            edge.to_node_id = endpoint(&to)?;
+            service.upsert_edge(tenant, edge).await.map(|_| ())
+        }
+        SyncOperation::DeleteNode { alias } => {
+            let resolved = service.resolve_aliases(tenant, std::slice::from_ref(&alias)).await?;
+            match resolved.get(&alias) {
+                Some(id) => service.delete_node(tenant, *id).await.map(|_| ()),
+                // Already gone here
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+/// `GraphStore` decorator that logs node and edge writes for sync
+pub struct SyncGraphStore {
+    inner: Arc<dyn GraphStore>,
+    engine: Arc<SyncEngine>,
+}
+
+impl SyncGraphStore {
+    pub fn new(inner: Arc<dyn GraphStore>, engine: Arc<SyncEngine>) -> Self {
+        Self { inner, engine }
+    }
+
+    async fn node_alias(&self, tenant: &TenantId, id: Uuid) -> Result<Option<AliasKey>, GraphError> {
+        Ok(self.inner.get_node(tenant, id).await?.and_then(|node| node.alias_key()))
+    }
+
+    fn record_edge(&self, tenant: &TenantId, from: Option<AliasKey>, to: Option<AliasKey>, edge: TimeEdge) {
+        match (from, to) {
+            (Some(from), Some(to)) => self.engine.record(tenant, SyncOperation::UpsertEdge { from, to, edge }),
+            _ => debug!("Not syncing {} edge of tenant {}: an endpoint has no alias", edge.kind, tenant),
+        }
+    }
+}
+
+#[async_trait]
+impl GraphStore for SyncGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        let id = self.inner.upsert_node(tenant, node.clone()).await?;
+        self.engine.record(tenant, SyncOperation::UpsertNode { node });
+        Ok(id)
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        let id = self.inner.upsert_edge(tenant, edge.clone()).await?;
+        let from = self.node_alias(tenant, edge.from_node_id).await?;
+        let to = self.node_alias(tenant, edge.to_node_id).await?;
+        self.record_edge(tenant, from, to, edge);
+        Ok(id)
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        let result = self.inner.upsert_node_with_edges(tenant, node.clone(), edges.clone()).await?;
+        let alias = node.alias_key();
+        self.engine.record(tenant, SyncOperation::UpsertNode { node });
+
+        for spec in edges {
+            let target = match &spec.target {
+                NodeRef::Alias(key) => Some(key.clone()),
+                NodeRef::Id(id) => self.node_alias(tenant, *id).await?,
+            };
+            let (from, to) = match spec.direction {
+                EdgeDirection::Outgoing => (alias.clone(), target),
+                EdgeDirection::Incoming => (target, alias.clone()),
+            };
+            let mut edge = TimeEdge::new(Uuid::nil(), Uuid::nil(), spec.kind, spec.valid_from.unwrap_or_else(Utc::now), spec.props);
+            edge.valid_to = spec.valid_to;
+            self.record_edge(tenant, from, to, edge);
+        }
+        Ok(result)
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        // Each node is recorded once upserted
+        bulk::upsert_nodes_each(self, tenant, nodes).await
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        bulk::upsert_edges_each(self, tenant, edges).await
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.inner.query(tenant, query).await
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.inner.query_count(tenant, query).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.inner.query_exists(tenant, query).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.inner.traverse(tenant, request).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.inner.shortest_path(tenant, request).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.inner.timeline(tenant, request).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.inner.property_history(tenant, node_id, key).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        let updated = self.inner.update_node(tenant, id, node).await?;
+        // Synced as the stored node, which carries the alias peers know it by
+        if updated {
+            if let Some(node) = self.inner.get_node(tenant, id).await?.filter(|node| node.alias_key().is_some()) {
+                self.engine.record(tenant, SyncOperation::UpsertNode { node });
+            }
+        }
+        Ok(updated)
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.inner.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.inner.set_edge_constraints(tenant, constraints).await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.inner.get_node(tenant, id).await
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        self.inner.get_node_by_alias(tenant, id_alias).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.inner.resolve_aliases(tenant, aliases).await
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        let alias = self.node_alias(tenant, id).await?;
+        let deleted = self.inner.delete_node(tenant, id).await?;
+        if let (true, Some(alias)) = (deleted, alias) {
+            self.engine.record(tenant, SyncOperation::DeleteNode { alias });
+        }
+        Ok(deleted)
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.inner.delete_edge(tenant, id).await
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        self.inner.close_edge(tenant, id, valid_to).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.inner.supersede_edge(tenant, id, edge).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.inner.retract_edge(tenant, id).await
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        self.inner.get_node_history(tenant, id).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        self.inner.snapshot(tenant, valid_at).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.inner.materialize_snapshot(tenant, name, valid_at).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.inner.list_snapshots(tenant).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.inner.drop_snapshot(tenant, name).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.inner.query_snapshot(tenant, name, query).await
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        self.inner.read_history(tenant, before).await
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.inner.purge_history(tenant, before).await
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        self.inner.restore_history(tenant, batch).await
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.inner.purge_expired_edges(tenant, now).await
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        self.inner.summary(tenant).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.inner.catalog(tenant).await
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        self.inner.list_tenants().await
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        self.inner.clear_tenant(tenant).await
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(instance_id: &str, max_changes_per_tenant: usize) -> SyncEngine {
+        SyncEngine::new(SyncConfig { instance_id: instance_id.to_string(), max_changes_per_tenant, max_batch_size: 2 })
+    }
+
+    #[test]
+    fn test_change_log_cursors() {
+        let engine = engine("edge-1", 3);
+        let tenant = TenantId::new("acme");
+        for alias in ["a", "b", "c", "d"] {
+            engine.record(&tenant, SyncOperation::UpsertNode { node: Node::new("Person").with_id_alias(alias) });
+        }
+
+        // The first change was dropped, so a peer at cursor 0 missed it
+        let status = engine.status(&tenant);
+        assert_eq!((status.cursor, status.oldest_cursor), (4, Some(2)));
+        let batch = engine.changes(&tenant, 0, None, None);
+        assert!(batch.truncated && batch.has_more);
+        assert_eq!((batch.changes.len(), batch.next_cursor), (2, 3));
+
+        let batch = engine.changes(&tenant, 3, None, Some("edge-1"));
+        assert!(!batch.truncated && !batch.has_more && batch.changes.is_empty());
+        assert_eq!(batch.next_cursor, 4);
+    }
+
+    #[test]
+    fn test_last_writer_wins() {
+        let local = RecordVersion { origin: "central".to_string(), transaction_time: Utc::now() };
+        let mut remote = SyncChange {
+            cursor: 1,
+            origin: "edge-1".to_string(),
+            transaction_time: local.transaction_time - chrono::Duration::seconds(1),
+            operation: SyncOperation::DeleteNode { alias: AliasKey::new("alice") },
+        };
+        assert!(!LastWriterWins.accept(&local, &remote));
+
+        remote.transaction_time = local.transaction_time;
+        assert!(LastWriterWins.accept(&local, &remote));
+    }
+}
@@ -0,0 +1,789 @@
+//! Opt-in, anonymized usage telemetry
+//!
+//! Operators can ship aggregate usage of a deployment to their own telemetry
+//! endpoint: operations per second and store latency by operation, and LLM
+//! requests, tokens and spend by provider. Nothing is collected unless
+//! [`TelemetryConfig::enabled`] is set.
+//!
+//! Only aggregates leave the process. Recorders take operation names and
+//! numbers, never node or edge contents, queries, prompts or responses, and
+//! tenants only show as the number of tenants active in a window. Each
+//! report carries a random instance ID, which changes on restart.
+//!
+//! [`TelemetryGraphStore`] records store operations and [`TelemetryConnector`]
+//! LLM calls into a shared [`Telemetry`]; a [`TelemetryReporter`] hands its
+//! report to a [`TelemetryExporter`] every interval, as JSON over HTTP or as
+//! statsd metrics over UDP.
+
+use crate::archive::ArchiveBatch;
+use crate::availability::CapabilityStatus;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{EdgeStream, NodeStream};
+use crate::errors::{GraphError, LlmError, TelemetryError};
+use crate::http::HttpClientConfig;
+use crate::materialized::SnapshotInfo;
+use crate::traits::{CompletionRequest, CompletionResponse, ExtractionContext, ExtractionEnvelope, ExtractionMetadata, GraphStore, LlmConnector, ModelInfo, ProviderStatus, TelemetryExporter};
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeWithEdges, Path, TenantId, TimeEdge};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Largest statsd datagram, below the usual MTU so that it is not fragmented
+const STATSD_PACKET_BYTES: usize = 1_432;
+
+/// Provider of LLM calls that failed before reporting one
+const UNKNOWN_PROVIDER: &str = "unknown";
+
+/// Configuration for usage telemetry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Collect and report usage; off unless set
+    pub enabled: bool,
+    /// Seconds between reports
+    pub interval_secs: u64,
+    /// Share of operations whose latency is measured, from 0 to 1. Every
+    /// operation is counted.
+    pub sample_rate: f64,
+    /// Where reports are sent
+    pub exporter: Option<ExporterConfig>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 60,
+            sample_rate: 1.0,
+            exporter: None,
+        }
+    }
+}
+
+/// Destination of usage reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExporterConfig {
+    /// POST each report as JSON
+    Http {
+        url: String,
+        /// Extra request headers, e.g. for authentication
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        #[serde(default)]
+        client: HttpClientConfig,
+    },
+    /// Send each report as statsd counters and gauges over UDP
+    Statsd {
+        /// `host:port` of the statsd daemon
+        address: String,
+        /// Prefix of every metric name
+        #[serde(default = "default_statsd_prefix")]
+        prefix: String,
+    },
+}
+
+fn default_statsd_prefix() -> String {
+    "telamentis".to_string()
+}
+
+impl ExporterConfig {
+    /// Build the configured exporter
+    pub fn build(&self) -> Result<Arc<dyn TelemetryExporter>, TelemetryError> {
+        Ok(match self {
+            ExporterConfig::Http { url, headers, client } => Arc::new(HttpJsonExporter::new(url, headers.clone(), client)?),
+            ExporterConfig::Statsd { address, prefix } => Arc::new(StatsdExporter::new(address, prefix)),
+        })
+    }
+}
+
+/// Usage of one operation in a report window
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationStats {
+    pub count: u64,
+    pub errors: u64,
+    pub ops_per_sec: f64,
+    /// Operations whose latency was measured
+    pub latency_samples: u64,
+    pub mean_latency_ms: f64,
+    pub max_latency_ms: f64,
+}
+
+/// LLM usage of one provider in a report window
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LlmUsage {
+    pub requests: u64,
+    pub errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Spend as estimated by the connectors
+    pub cost_usd: f64,
+}
+
+/// Aggregate usage of a deployment over a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Random ID of this process, new on every start
+    pub instance_id: Uuid,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    /// Number of tenants with any operation in the window
+    pub active_tenants: u64,
+    /// Store and LLM operations by name, e.g. `upsert_edge` or `llm.extract`
+    pub operations: BTreeMap<String, OperationStats>,
+    /// LLM usage by provider
+    pub llm: BTreeMap<String, LlmUsage>,
+}
+
+/// Running totals of one operation
+#[derive(Debug, Default)]
+struct OperationTotals {
+    count: u64,
+    errors: u64,
+    latency_samples: u64,
+    latency_total: Duration,
+    latency_max: Duration,
+}
+
+/// Usage since the last report
+#[derive(Debug)]
+struct Window {
+    started_at: DateTime<Utc>,
+    operations: BTreeMap<&'static str, OperationTotals>,
+    llm: BTreeMap<String, LlmUsage>,
+    /// Hashes of the active tenants, so that their IDs are not kept
+    tenants: HashSet<u64>,
+}
+
+impl Window {
+    fn new() -> Self {
+        Self {
+            started_at: Utc::now(),
+            operations: BTreeMap::new(),
+            llm: BTreeMap::new(),
+            tenants: HashSet::new(),
+        }
+    }
+
+    fn tenant_active(&mut self, tenant: &TenantId) {
+        let mut hasher = DefaultHasher::new();
+        tenant.hash(&mut hasher);
+        self.tenants.insert(hasher.finish());
+    }
+}
+
+/// Collector of aggregate usage, shared by the recording decorators
+#[derive(Debug)]
+pub struct Telemetry {
+    instance_id: Uuid,
+    /// Latency is measured for one in this many operations
+    sample_every: u64,
+    operations_seen: AtomicU64,
+    window: Mutex<Window>,
+}
+
+impl Telemetry {
+    /// Collect usage, measuring the latency of `sample_rate` of operations
+    pub fn new(sample_rate: f64) -> Self {
+        let sample_every = if sample_rate > 0.0 { (1.0 / sample_rate.min(1.0)).round() as u64 } else { u64::MAX };
+        Self {
+            instance_id: Uuid::new_v4(),
+            sample_every,
+            operations_seen: AtomicU64::new(0),
+            window: Mutex::new(Window::new()),
+        }
+    }
+
+    /// Whether the latency of the next operation is measured
+    fn sampled(&self) -> bool {
+        self.operations_seen.fetch_add(1, Ordering::Relaxed).is_multiple_of(self.sample_every)
+    }
+
+    /// Count an operation of the tenant, with its latency if it was sampled
+    pub fn record_operation(&self, tenant: &TenantId, operation: &'static str, latency: Option<Duration>, ok: bool) {
+        let mut window = self.window.lock().unwrap();
+        window.tenant_active(tenant);
+        let totals = window.operations.entry(operation).or_default();
+        totals.count += 1;
+        if !ok {
+            totals.errors += 1;
+        }
+        if let Some(latency) = latency {
+            totals.latency_samples += 1;
+            totals.latency_total += latency;
+            totals.latency_max = totals.latency_max.max(latency);
+        }
+    }
+
+    /// Count an LLM call and the tokens and spend its metadata reports
+    pub fn record_llm(&self, tenant: &TenantId, operation: &'static str, latency: Option<Duration>, metadata: Option<&ExtractionMetadata>, ok: bool) {
+        self.record_operation(tenant, operation, latency, ok);
+
+        let provider = metadata.map_or(UNKNOWN_PROVIDER, |metadata| metadata.provider.as_str());
+        let mut window = self.window.lock().unwrap();
+        let usage = window.llm.entry(provider.to_string()).or_default();
+        usage.requests += 1;
+        if !ok {
+            usage.errors += 1;
+        }
+        if let Some(metadata) = metadata {
+            usage.input_tokens += metadata.input_tokens.unwrap_or(0) as u64;
+            usage.output_tokens += metadata.output_tokens.unwrap_or(0) as u64;
+            usage.cost_usd += metadata.cost_usd.unwrap_or(0.0);
+        }
+    }
+
+    /// Report the usage since the last report, and start a new window
+    pub fn take_report(&self) -> UsageReport {
+        let window = std::mem::replace(&mut *self.window.lock().unwrap(), Window::new());
+        let window_end = Utc::now();
+        let seconds = ((window_end - window.started_at).num_milliseconds() as f64 / 1000.0).max(0.001);
+
+        let operations = window.operations.into_iter()
+            .map(|(name, totals)| {
+                let mean = match totals.latency_samples {
+                    0 => 0.0,
+                    samples => totals.latency_total.as_secs_f64() * 1000.0 / samples as f64,
+                };
+                (name.to_string(), OperationStats {
+                    count: totals.count,
+                    errors: totals.errors,
+                    ops_per_sec: totals.count as f64 / seconds,
+                    latency_samples: totals.latency_samples,
+                    mean_latency_ms: mean,
+                    max_latency_ms: totals.latency_max.as_secs_f64() * 1000.0,
+                })
+            })
+            .collect();
+
+        UsageReport {
+            instance_id: self.instance_id,
+            window_start: window.started_at,
+            window_end,
+            active_tenants: window.tenants.len() as u64,
+            operations,
+            llm: window.llm,
+        }
+    }
+
+    /// Run an operation, recording it
+    async fn timed<T, E>(&self, tenant: &TenantId, operation: &'static str, call: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+        let started = self.sampled().then(Instant::now);
+        let result = call.await;
+        self.record_operation(tenant, operation, started.map(|started| started.elapsed()), result.is_ok());
+        result
+    }
+}
+
+/// Exporter that POSTs each report as JSON
+pub struct HttpJsonExporter {
+    client: reqwest::Client,
+    url: String,
+    headers: BTreeMap<String, String>,
+}
+
+impl HttpJsonExporter {
+    /// Export to `url` with extra request headers
+    pub fn new(url: &str, headers: BTreeMap<String, String>, client: &HttpClientConfig) -> Result<Self, TelemetryError> {
+        let client = client.apply(reqwest::Client::builder().timeout(Duration::from_secs(10)))
+            .and_then(|builder| builder.build().map_err(|e| e.to_string()))
+            .map_err(TelemetryError::Configuration)?;
+        Ok(Self { client, url: url.to_string(), headers })
+    }
+}
+
+#[async_trait]
+impl TelemetryExporter for HttpJsonExporter {
+    async fn export(&self, report: &UsageReport) -> Result<(), TelemetryError> {
+        let mut request = self.client.post(&self.url).json(report);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await
+            .map_err(|e| TelemetryError::Export(format!("Failed to send usage report: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(TelemetryError::Export(format!("Telemetry endpoint returned {}", response.status())));
+        }
+        Ok(())
+    }
+}
+
+/// Exporter that sends each report as statsd metrics over UDP
+pub struct StatsdExporter {
+    address: String,
+    prefix: String,
+}
+
+impl StatsdExporter {
+    /// Export to the statsd daemon at `host:port`, under `prefix`
+    pub fn new(address: &str, prefix: &str) -> Self {
+        Self { address: address.to_string(), prefix: prefix.to_string() }
+    }
+}
+
+#[async_trait]
+impl TelemetryExporter for StatsdExporter {
+    async fn export(&self, report: &UsageReport) -> Result<(), TelemetryError> {
+        let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await
+            .map_err(|e| TelemetryError::Export(format!("Failed to open UDP socket: {}", e)))?;
+        socket.connect(&self.address).await
+            .map_err(|e| TelemetryError::Export(format!("Failed to reach statsd at {}: {}", self.address, e)))?;
+
+        for packet in statsd_packets(&statsd_lines(&self.prefix, report)) {
+            socket.send(packet.as_bytes()).await
+                .map_err(|e| TelemetryError::Export(format!("Failed to send to statsd: {}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// A report as statsd lines: counters for counts, gauges for rates and latencies
+pub fn statsd_lines(prefix: &str, report: &UsageReport) -> Vec<String> {
+    let mut lines = vec![format!("{}.active_tenants:{}|g", prefix, report.active_tenants)];
+    for (name, stats) in &report.operations {
+        let name = format!("{}.ops.{}", prefix, statsd_name(name));
+        lines.push(format!("{}.count:{}|c", name, stats.count));
+        lines.push(format!("{}.errors:{}|c", name, stats.errors));
+        lines.push(format!("{}.ops_per_sec:{:.3}|g", name, stats.ops_per_sec));
+        if stats.latency_samples > 0 {
+            lines.push(format!("{}.latency_mean_ms:{:.3}|g", name, stats.mean_latency_ms));
+            lines.push(format!("{}.latency_max_ms:{:.3}|g", name, stats.max_latency_ms));
+        }
+    }
+    for (provider, usage) in &report.llm {
+        let name = format!("{}.llm.{}", prefix, statsd_name(provider));
+        lines.push(format!("{}.requests:{}|c", name, usage.requests));
+        lines.push(format!("{}.errors:{}|c", name, usage.errors));
+        lines.push(format!("{}.input_tokens:{}|c", name, usage.input_tokens));
+        lines.push(format!("{}.output_tokens:{}|c", name, usage.output_tokens));
+        lines.push(format!("{}.cost_usd:{:.6}|g", name, usage.cost_usd));
+    }
+    lines
+}
+
+/// A name segment with the characters statsd gives meaning to replaced
+fn statsd_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' { c } else { '_' }).collect()
+}
+
+/// Join lines into datagrams of at most `STATSD_PACKET_BYTES`
+fn statsd_packets(lines: &[String]) -> Vec<String> {
+    let mut packets: Vec<String> = Vec::new();
+    for line in lines {
+        match packets.last_mut() {
+            Some(packet) if packet.len() + 1 + line.len() <= STATSD_PACKET_BYTES => {
+                packet.push('\n');
+                packet.push_str(line);
+            }
+            _ => packets.push(line.clone()),
+        }
+    }
+    packets
+}
+
+/// Background task sending a report every interval.
+///
+/// Must be started inside a Tokio runtime. Call [`TelemetryReporter::shutdown`]
+/// to send the last window before stopping.
+pub struct TelemetryReporter {
+    shutdown: Arc<Notify>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl TelemetryReporter {
+    /// Report `telemetry` through `exporter` every `interval`
+    pub fn start(telemetry: Arc<Telemetry>, exporter: Arc<dyn TelemetryExporter>, interval: Duration) -> Self {
+        let shutdown = Arc::new(Notify::new());
+        let task = tokio::spawn(Self::run(telemetry, exporter, interval, shutdown.clone()));
+        Self { shutdown, task: Mutex::new(Some(task)) }
+    }
+
+    /// Collect and report usage as configured; `None` if telemetry is disabled
+    pub fn from_config(config: &TelemetryConfig) -> Result<Option<(Arc<Telemetry>, Self)>, TelemetryError> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let exporter = config.exporter.as_ref()
+            .ok_or_else(|| TelemetryError::Configuration("Telemetry is enabled without an exporter".to_string()))?
+            .build()?;
+        if !(0.0..=1.0).contains(&config.sample_rate) {
+            return Err(TelemetryError::Configuration(format!("Sample rate {} is not between 0 and 1", config.sample_rate)));
+        }
+
+        let telemetry = Arc::new(Telemetry::new(config.sample_rate));
+        info!("Reporting usage telemetry every {}s", config.interval_secs);
+        let reporter = Self::start(telemetry.clone(), exporter, Duration::from_secs(config.interval_secs.max(1)));
+        Ok(Some((telemetry, reporter)))
+    }
+
+    async fn run(telemetry: Arc<Telemetry>, exporter: Arc<dyn TelemetryExporter>, period: Duration, shutdown: Arc<Notify>) {
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            let stopping = tokio::select! {
+                _ = interval.tick() => false,
+                _ = shutdown.notified() => true,
+            };
+            let report = telemetry.take_report();
+            match exporter.export(&report).await {
+                Ok(()) => debug!("Exported usage report of {} operations", report.operations.len()),
+                // Reports are aggregates of a window, so a lost one is not retried
+                Err(e) => warn!("Failed to export usage report: {}", e),
+            }
+            if stopping {
+                break;
+            }
+        }
+    }
+
+    /// Send the current window and stop reporting
+    pub async fn shutdown(&self) {
+        self.shutdown.notify_one();
+
+        let task = self.task.lock().unwrap().take();
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for TelemetryReporter {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+/// `GraphStore` decorator that records every tenant operation in a [`Telemetry`]
+pub struct TelemetryGraphStore {
+    inner: Arc<dyn GraphStore>,
+    telemetry: Arc<Telemetry>,
+}
+
+impl TelemetryGraphStore {
+    /// Record the operations on `inner`
+    pub fn new(inner: Arc<dyn GraphStore>, telemetry: Arc<Telemetry>) -> Self {
+        Self { inner, telemetry }
+    }
+}
+
+#[async_trait]
+impl GraphStore for TelemetryGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        self.telemetry.timed(tenant, "upsert_node", self.inner.upsert_node(tenant, node)).await
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.telemetry.timed(tenant, "upsert_edge", self.inner.upsert_edge(tenant, edge)).await
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        self.telemetry.timed(tenant, "upsert_node_with_edges", self.inner.upsert_node_with_edges(tenant, node, edges)).await
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        self.telemetry.timed(tenant, "bulk_load_nodes", self.inner.bulk_load_nodes(tenant, nodes)).await
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        self.telemetry.timed(tenant, "bulk_load_edges", self.inner.bulk_load_edges(tenant, edges)).await
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.telemetry.timed(tenant, "query", self.inner.query(tenant, query)).await
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        self.telemetry.timed(tenant, "query_count", self.inner.query_count(tenant, query)).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        self.telemetry.timed(tenant, "query_exists", self.inner.query_exists(tenant, query)).await
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        self.telemetry.timed(tenant, "traverse", self.inner.traverse(tenant, request)).await
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        self.telemetry.timed(tenant, "shortest_path", self.inner.shortest_path(tenant, request)).await
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        self.telemetry.timed(tenant, "timeline", self.inner.timeline(tenant, request)).await
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        self.telemetry.timed(tenant, "property_history", self.inner.property_history(tenant, node_id, key)).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        self.telemetry.timed(tenant, "update_node", self.inner.update_node(tenant, id, node)).await
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.inner.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        self.inner.set_edge_constraints(tenant, constraints).await
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        self.telemetry.timed(tenant, "get_node", self.inner.get_node(tenant, id)).await
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        self.telemetry.timed(tenant, "get_node_by_alias", self.inner.get_node_by_alias(tenant, id_alias)).await
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        self.telemetry.timed(tenant, "resolve_aliases", self.inner.resolve_aliases(tenant, aliases)).await
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.telemetry.timed(tenant, "delete_node", self.inner.delete_node(tenant, id)).await
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.telemetry.timed(tenant, "delete_edge", self.inner.delete_edge(tenant, id)).await
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        self.telemetry.timed(tenant, "close_edge", self.inner.close_edge(tenant, id, valid_to)).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        self.telemetry.timed(tenant, "supersede_edge", self.inner.supersede_edge(tenant, id, edge)).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        self.telemetry.timed(tenant, "retract_edge", self.inner.retract_edge(tenant, id)).await
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        self.telemetry.timed(tenant, "get_node_history", self.inner.get_node_history(tenant, id)).await
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        self.telemetry.timed(tenant, "snapshot", self.inner.snapshot(tenant, valid_at)).await
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        self.telemetry.timed(tenant, "materialize_snapshot", self.inner.materialize_snapshot(tenant, name, valid_at)).await
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        self.inner.list_snapshots(tenant).await
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        self.inner.drop_snapshot(tenant, name).await
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        self.telemetry.timed(tenant, "query_snapshot", self.inner.query_snapshot(tenant, name, query)).await
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        self.inner.read_history(tenant, before).await
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.inner.purge_history(tenant, before).await
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        self.inner.restore_history(tenant, batch).await
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        self.inner.purge_expired_edges(tenant, now).await
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        self.inner.summary(tenant).await
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        self.inner.catalog(tenant).await
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        self.inner.list_tenants().await
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        self.inner.clear_tenant(tenant).await
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.inner.health_check().await
+    }
+}
+
+/// `LlmConnector` decorator that records calls, tokens and spend in a [`Telemetry`]
+pub struct TelemetryConnector {
+    inner: Arc<dyn LlmConnector>,
+    telemetry: Arc<Telemetry>,
+}
+
+impl TelemetryConnector {
+    /// Record the calls to `inner`
+    pub fn new(inner: Arc<dyn LlmConnector>, telemetry: Arc<Telemetry>) -> Self {
+        Self { inner, telemetry }
+    }
+}
+
+#[async_trait]
+impl LlmConnector for TelemetryConnector {
+    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let started = self.telemetry.sampled().then(Instant::now);
+        let result = self.inner.extract(tenant, context).await;
+        let metadata = result.as_ref().ok().and_then(|envelope| envelope.metadata.as_ref());
+        self.telemetry.record_llm(tenant, "llm.extract", started.map(|started| started.elapsed()), metadata, result.is_ok());
+        result
+    }
+
+    async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let started = self.telemetry.sampled().then(Instant::now);
+        let result = self.inner.complete(tenant, request).await;
+        let metadata = result.as_ref().ok().and_then(|response| response.metadata.as_ref());
+        self.telemetry.record_llm(tenant, "llm.complete", started.map(|started| started.elapsed()), metadata, result.is_ok());
+        result
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        self.inner.list_models().await
+    }
+
+    async fn health(&self) -> CapabilityStatus {
+        self.inner.health().await
+    }
+
+    async fn provider_status(&self, tenant: Option<&TenantId>) -> Vec<ProviderStatus> {
+        self.inner.provider_status(tenant).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exporter that keeps the reports it is given
+    #[derive(Default)]
+    struct RecordingExporter {
+        reports: Mutex<Vec<UsageReport>>,
+    }
+
+    #[async_trait]
+    impl TelemetryExporter for RecordingExporter {
+        async fn export(&self, report: &UsageReport) -> Result<(), TelemetryError> {
+            self.reports.lock().unwrap().push(report.clone());
+            Ok(())
+        }
+    }
+
+    fn metadata(provider: &str, input_tokens: u32, cost_usd: f64) -> ExtractionMetadata {
+        ExtractionMetadata {
+            provider: provider.to_string(),
+            model_name: "model".to_string(),
+            latency_ms: None,
+            input_tokens: Some(input_tokens),
+            output_tokens: Some(10),
+            cost_usd: Some(cost_usd),
+            warnings: Vec::new(),
+            model_selection: None,
+            citations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_usage_report() {
+        let telemetry = Telemetry::new(0.5);
+        let (acme, globex) = (TenantId::new("acme"), TenantId::new("globex"));
+
+        for i in 0..4 {
+            let latency = telemetry.sampled().then_some(Duration::from_millis(10 * (i + 1)));
+            telemetry.record_operation(&acme, "upsert_edge", latency, i != 3);
+        }
+        telemetry.record_llm(&globex, "llm.extract", None, Some(&metadata("openai", 100, 0.01)), true);
+        telemetry.record_llm(&globex, "llm.extract", None, Some(&metadata("openai", 50, 0.02)), true);
+        telemetry.record_llm(&globex, "llm.extract", None, None, false);
+
+        let report = telemetry.take_report();
+        assert_eq!(report.active_tenants, 2);
+        let upserts = &report.operations["upsert_edge"];
+        assert_eq!((upserts.count, upserts.errors, upserts.latency_samples), (4, 1, 2));
+        assert!((upserts.mean_latency_ms - 20.0).abs() < 1e-9);
+        assert!((upserts.max_latency_ms - 30.0).abs() < 1e-9);
+        assert_eq!(report.operations["llm.extract"].count, 3);
+        let openai = &report.llm["openai"];
+        assert_eq!((openai.requests, openai.input_tokens, openai.output_tokens), (2, 150, 20));
+        assert!((openai.cost_usd - 0.03).abs() < 1e-9);
+        assert_eq!(report.llm[UNKNOWN_PROVIDER].errors, 1);
+
+        // Nothing identifying a tenant is reported, and the window restarts
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains("acme") && !json.contains("globex"));
+        let next = telemetry.take_report();
+        assert!(next.operations.is_empty() && next.active_tenants == 0);
+        assert_eq!(next.instance_id, report.instance_id);
+    }
+
+    #[test]
+    fn test_statsd_lines() {
+        let telemetry = Telemetry::new(1.0);
+        telemetry.record_operation(&TenantId::new("acme"), "query", Some(Duration::from_millis(4)), true);
+        telemetry.record_llm(&TenantId::new("acme"), "llm.complete", None, Some(&metadata("open ai", 7, 0.5)), true);
+        let lines = statsd_lines("tm", &telemetry.take_report());
+
+        assert!(lines.contains(&"tm.active_tenants:1|g".to_string()));
+        assert!(lines.contains(&"tm.ops.query.count:1|c".to_string()));
+        assert!(lines.contains(&"tm.ops.query.latency_mean_ms:4.000|g".to_string()));
+        assert!(lines.contains(&"tm.llm.open_ai.input_tokens:7|c".to_string()));
+        assert!(!lines.iter().any(|line| line.starts_with("tm.ops.llm.complete.latency")));
+
+        let packets = statsd_packets(&vec!["x".repeat(1_000); 3]);
+        assert_eq!(packets.len(), 3);
+        assert_eq!(statsd_packets(&lines).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reporter() {
+        let telemetry = Arc::new(Telemetry::new(1.0));
+        let exporter = Arc::new(RecordingExporter::default());
+        let reporter = TelemetryReporter::start(telemetry.clone(), exporter.clone(), Duration::from_millis(20));
+
+        telemetry.record_operation(&TenantId::new("acme"), "query", None, true);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        telemetry.record_operation(&TenantId::new("acme"), "upsert_node", None, true);
+        reporter.shutdown().await;
+
+        let reports = exporter.reports.lock().unwrap();
+        assert!(reports.len() >= 2);
+        assert!(reports[0].operations.contains_key("query"));
+        // The last window is sent on shutdown
+        assert!(reports.last().unwrap().operations.contains_key("upsert_node"));
+
+        assert!(TelemetryReporter::from_config(&TelemetryConfig::default()).unwrap().is_none());
+        let enabled = TelemetryConfig { enabled: true, ..Default::default() };
+        assert!(matches!(TelemetryReporter::from_config(&enabled), Err(TelemetryError::Configuration(_))));
+    }
+}
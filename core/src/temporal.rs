@@ -1,8 +1,381 @@
 //! Temporal utilities and helpers for working with bitemporal data
+//!
+//! Besides instants, facts are often only known to a year, month or day
+//! ("joined in March 2020"). A [`PartialDate`] keeps that precision and
+//! stands for the whole period: as the start of a validity interval it is
+//! the first instant of the period, as the end it is the first instant
+//! after it, so an edge that ended "in June 2021" is still valid on June 15.
+//! [`IsoDuration`] holds ISO 8601 durations such as `P1Y6M`. Both travel as
+//! strings, so they read the same in JSON over HTTP and the Unix socket and
+//! in gRPC's string and JSON fields.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
 use crate::types::TimeEdge;
 
+/// How precisely a time is known
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimePrecision {
+    Year,
+    Month,
+    Day,
+    /// An exact timestamp
+    #[default]
+    Instant,
+}
+
+/// A date known to a year, month or day, written `2020`, `2020-03` or
+/// `2020-03-15`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PartialDate {
+    first_day: NaiveDate,
+    precision: TimePrecision,
+}
+
+impl PartialDate {
+    /// A whole year
+    pub fn year(year: i32) -> Option<Self> {
+        Self::from_date(NaiveDate::from_ymd_opt(year, 1, 1)?, TimePrecision::Year)
+    }
+
+    /// A whole month
+    pub fn month(year: i32, month: u32) -> Option<Self> {
+        Self::from_date(NaiveDate::from_ymd_opt(year, month, 1)?, TimePrecision::Month)
+    }
+
+    /// A single day
+    pub fn day(year: i32, month: u32, day: u32) -> Option<Self> {
+        Self::from_date(NaiveDate::from_ymd_opt(year, month, day)?, TimePrecision::Day)
+    }
+
+    /// The period of the given precision containing `date`; `None` for
+    /// `TimePrecision::Instant`
+    pub fn from_date(date: NaiveDate, precision: TimePrecision) -> Option<Self> {
+        let first_day = match precision {
+            TimePrecision::Year => date.with_ordinal(1)?,
+            TimePrecision::Month => date.with_day(1)?,
+            TimePrecision::Day => date,
+            TimePrecision::Instant => return None,
+        };
+        Some(Self { first_day, precision })
+    }
+
+    /// Precision of the date
+    pub fn precision(&self) -> TimePrecision {
+        self.precision
+    }
+
+    /// First day of the period
+    pub fn first_day(&self) -> NaiveDate {
+        self.first_day
+    }
+
+    /// First day after the period
+    pub fn next_day(&self) -> NaiveDate {
+        let next = match self.precision {
+            TimePrecision::Year => self.first_day.checked_add_months(Months::new(12)),
+            TimePrecision::Month => self.first_day.checked_add_months(Months::new(1)),
+            _ => self.first_day.checked_add_days(Days::new(1)),
+        };
+        next.unwrap_or(NaiveDate::MAX)
+    }
+
+    /// First instant of the period, in UTC
+    pub fn start(&self) -> DateTime<Utc> {
+        midnight(self.first_day)
+    }
+
+    /// First instant after the period, in UTC
+    pub fn end(&self) -> DateTime<Utc> {
+        midnight(self.next_day())
+    }
+
+    /// Last instant of the period, which "as of" a partial date refers to:
+    /// the graph as it stood when the period closed
+    pub fn as_of(&self) -> DateTime<Utc> {
+        self.end() - chrono::Duration::nanoseconds(1)
+    }
+
+    /// Whether `time` falls within the period
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        TemporalUtils::point_in_interval(time, self.start(), Some(self.end()))
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or(NaiveDateTime::MAX).and_utc()
+}
+
+impl fmt::Display for PartialDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let date = self.first_day;
+        match self.precision {
+            TimePrecision::Year => write!(f, "{:04}", date.year()),
+            TimePrecision::Month => write!(f, "{:04}-{:02}", date.year(), date.month()),
+            _ => write!(f, "{}", date.format("%Y-%m-%d")),
+        }
+    }
+}
+
+impl FromStr for PartialDate {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not a date (YYYY, YYYY-MM or YYYY-MM-DD)", value);
+        let parts: Vec<&str> = value.trim().split('-').collect();
+        let widths = [4, 2, 2];
+        if parts.len() > widths.len()
+            || parts.iter().zip(widths).any(|(part, width)| part.len() != width || !part.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(invalid());
+        }
+        let number = |index: usize| parts[index].parse::<u32>().map_err(|_| invalid());
+        let year = number(0)? as i32;
+        match parts.len() {
+            1 => Self::year(year),
+            2 => Self::month(year, number(1)?),
+            _ => Self::day(year, number(1)?, number(2)?),
+        }
+        .ok_or_else(invalid)
+    }
+}
+
+impl Serialize for PartialDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PartialDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// An ISO 8601 duration such as `P1Y6M`, `P2W` or `PT90M`. Years and
+/// months are calendar units, so `P1M` after January 31st ends on the last
+/// day of February.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct IsoDuration {
+    pub years: u32,
+    pub months: u32,
+    pub days: u32,
+    pub seconds: u64,
+}
+
+impl IsoDuration {
+    /// A duration of whole months
+    pub fn months(months: u32) -> Self {
+        Self { months, ..Default::default() }
+    }
+
+    /// A duration of whole days
+    pub fn days(days: u32) -> Self {
+        Self { days, ..Default::default() }
+    }
+
+    /// Precision of the smallest unit the duration uses, e.g. `Month` for
+    /// `P1Y6M`
+    pub fn precision(&self) -> TimePrecision {
+        if self.seconds > 0 {
+            TimePrecision::Instant
+        } else if self.days > 0 {
+            TimePrecision::Day
+        } else if self.months > 0 {
+            TimePrecision::Month
+        } else {
+            TimePrecision::Year
+        }
+    }
+
+    /// `time` plus the duration, or `None` if that is out of range
+    pub fn add_to(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let months = self.years.checked_mul(12)?.checked_add(self.months)?;
+        time.checked_add_months(Months::new(months))?
+            .checked_add_days(Days::new(self.days.into()))?
+            .checked_add_signed(chrono::Duration::seconds(i64::try_from(self.seconds).ok()?))
+    }
+
+    /// The end of a period of this length starting with `date`, as a
+    /// partial date of the coarser of the two precisions
+    pub fn after(&self, date: &PartialDate) -> Option<PartialDate> {
+        let end = self.add_to(date.start())?;
+        PartialDate::from_date(end.date_naive(), date.precision().min(self.precision()))
+    }
+}
+
+impl fmt::Display for IsoDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "P")?;
+        for (value, unit) in [(self.years as u64, 'Y'), (self.months as u64, 'M'), (self.days as u64, 'D')] {
+            if value > 0 {
+                write!(f, "{}{}", value, unit)?;
+            }
+        }
+        if self.seconds > 0 {
+            write!(f, "T")?;
+            for (value, unit) in [(self.seconds / 3600, 'H'), (self.seconds / 60 % 60, 'M'), (self.seconds % 60, 'S')] {
+                if value > 0 {
+                    write!(f, "{}{}", value, unit)?;
+                }
+            }
+        } else if *self == Self::default() {
+            write!(f, "0D")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for IsoDuration {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not an ISO 8601 duration such as P1Y6M or PT2H", value);
+        let rest = value.trim().strip_prefix('P').ok_or_else(invalid)?;
+        let (date, time) = match rest.split_once('T') {
+            Some((date, time)) if !time.is_empty() => (date, Some(time)),
+            Some(_) => return Err(invalid()),
+            None => (rest, None),
+        };
+        if date.is_empty() && time.is_none() {
+            return Err(invalid());
+        }
+
+        let mut duration = Self::default();
+        for (number, unit) in components(date).ok_or_else(invalid)? {
+            let number = u32::try_from(number).map_err(|_| invalid())?;
+            match unit {
+                'Y' => duration.years = number,
+                'M' => duration.months = number,
+                'W' => duration.days = number.checked_mul(7).ok_or_else(invalid)?,
+                'D' => duration.days = duration.days.checked_add(number).ok_or_else(invalid)?,
+                _ => return Err(invalid()),
+            }
+        }
+        for (number, unit) in components(time.unwrap_or_default()).ok_or_else(invalid)? {
+            let unit_secs = match unit {
+                'H' => 3600,
+                'M' => 60,
+                'S' => 1,
+                _ => return Err(invalid()),
+            };
+            duration.seconds = number.checked_mul(unit_secs).and_then(|secs| duration.seconds.checked_add(secs)).ok_or_else(invalid)?;
+        }
+        Ok(duration)
+    }
+}
+
+/// Split `1Y6M` into `[(1, 'Y'), (6, 'M')]`
+fn components(value: &str) -> Option<Vec<(u64, char)>> {
+    let mut components = Vec::new();
+    let mut digits = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+        } else {
+            components.push((digits.parse().ok()?, c));
+            digits.clear();
+        }
+    }
+    digits.is_empty().then_some(components)
+}
+
+impl Serialize for IsoDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IsoDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A temporal property value: an instant, a partial date or a duration,
+/// written as an RFC 3339 timestamp, `YYYY[-MM[-DD]]` or an ISO 8601
+/// duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TemporalValue {
+    Instant(DateTime<Utc>),
+    Date(PartialDate),
+    Duration(IsoDuration),
+}
+
+impl TemporalValue {
+    /// Precision of the value
+    pub fn precision(&self) -> TimePrecision {
+        match self {
+            TemporalValue::Instant(_) => TimePrecision::Instant,
+            TemporalValue::Date(date) => date.precision(),
+            TemporalValue::Duration(duration) => duration.precision(),
+        }
+    }
+}
+
+impl fmt::Display for TemporalValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemporalValue::Instant(time) => write!(f, "{}", time.to_rfc3339()),
+            TemporalValue::Date(date) => write!(f, "{}", date),
+            TemporalValue::Duration(duration) => write!(f, "{}", duration),
+        }
+    }
+}
+
+impl FromStr for TemporalValue {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.starts_with('P') {
+            return value.parse().map(TemporalValue::Duration);
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+            return Ok(TemporalValue::Instant(time.with_timezone(&Utc)));
+        }
+        value.parse().map(TemporalValue::Date)
+            .map_err(|_| format!("'{}' is not a timestamp, date or duration", value))
+    }
+}
+
+impl Serialize for TemporalValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TemporalValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Which end of a validity interval a time is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalBound {
+    /// `valid_from`: a partial date is the start of its period
+    Start,
+    /// `valid_to`: a partial date is the end of its period
+    End,
+    /// An "as of" time: a partial date is the last instant of its period
+    AsOf,
+}
+
+impl IntervalBound {
+    /// The instant a partial date stands for at this bound
+    pub fn of(self, date: &PartialDate) -> DateTime<Utc> {
+        match self {
+            IntervalBound::Start => date.start(),
+            IntervalBound::End => date.end(),
+            IntervalBound::AsOf => date.as_of(),
+        }
+    }
+}
+
 /// Utilities for working with temporal data
 pub struct TemporalUtils;
 
@@ -30,6 +403,24 @@ impl TemporalUtils {
         point >= start && point < end
     }
     
+    /// Whether an interval overlaps any part of `period`; "valid in March
+    /// 2020" holds for an edge that ended on March 10th
+    pub fn valid_during(start: DateTime<Utc>, end: Option<DateTime<Utc>>, period: &PartialDate) -> bool {
+        Self::intervals_overlap(start, end, period.start(), Some(period.end()))
+    }
+
+    /// Parse an RFC 3339 timestamp or a partial date, which stands for the
+    /// instant `bound` gives it
+    pub fn parse_time(value: &str, bound: IntervalBound) -> Result<DateTime<Utc>, String> {
+        let value = value.trim();
+        if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+            return Ok(time.with_timezone(&Utc));
+        }
+        value.parse::<PartialDate>()
+            .map(|date| bound.of(&date))
+            .map_err(|_| format!("'{}' is not an RFC 3339 timestamp or a date", value))
+    }
+
     /// Get the current timestamp
     pub fn now() -> DateTime<Utc> {
         Utc::now()
@@ -46,6 +437,56 @@ impl TemporalUtils {
     }
 }
 
+/// Serde for valid-time bounds that also accept partial dates; they are
+/// still written as RFC 3339
+pub mod valid_bound {
+    use super::{IntervalBound, TemporalUtils};
+    use chrono::{DateTime, Utc};
+    use serde::{de::Error, Deserialize, Deserializer};
+
+    /// LLMs and YAML write a bare year as a number
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Text(String),
+        Year(i32),
+    }
+
+    fn parse<'de, D: Deserializer<'de>>(deserializer: D, bound: IntervalBound) -> Result<Option<DateTime<Utc>>, D::Error> {
+        let value = match Option::<Raw>::deserialize(deserializer)? {
+            Some(Raw::Text(value)) => value,
+            Some(Raw::Year(year)) => format!("{:04}", year),
+            None => return Ok(None),
+        };
+        TemporalUtils::parse_time(&value, bound).map(Some).map_err(D::Error::custom)
+    }
+
+    /// A required start of validity
+    pub fn start<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        parse(deserializer, IntervalBound::Start)?.ok_or_else(|| D::Error::custom("expected a timestamp or date, found null"))
+    }
+
+    /// An optional start of validity
+    pub fn start_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        parse(deserializer, IntervalBound::Start)
+    }
+
+    /// An optional end of validity
+    pub fn end_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        parse(deserializer, IntervalBound::End)
+    }
+
+    /// A required "as of" time
+    pub fn as_of<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        parse(deserializer, IntervalBound::AsOf)?.ok_or_else(|| D::Error::custom("expected a timestamp or date, found null"))
+    }
+
+    /// An optional "as of" time
+    pub fn as_of_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        parse(deserializer, IntervalBound::AsOf)
+    }
+}
+
 /// Allen's Interval Algebra relations
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IntervalRelation {
@@ -137,4 +578,84 @@ mod tests {
         assert!(!TemporalUtils::point_in_interval(end, start, Some(end))); // End is exclusive
         assert!(TemporalUtils::point_in_interval(middle, start, None)); // Open interval
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_partial_date() {
+        let march: PartialDate = "2020-03".parse().unwrap();
+        assert_eq!(march.precision(), TimePrecision::Month);
+        assert_eq!(march.to_string(), "2020-03");
+        assert_eq!(march.start(), Utc.with_ymd_and_hms(2020, 3, 1, 0, 0, 0).unwrap());
+        assert_eq!(march.end(), Utc.with_ymd_and_hms(2020, 4, 1, 0, 0, 0).unwrap());
+        assert!(march.contains(Utc.with_ymd_and_hms(2020, 3, 31, 23, 0, 0).unwrap()));
+        assert!(!march.contains(march.end()));
+        assert!(march.as_of() < march.end() && march.contains(march.as_of()));
+
+        assert_eq!("2020".parse::<PartialDate>().unwrap().end(), Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!("2020-02-29".parse::<PartialDate>().unwrap().precision(), TimePrecision::Day);
+        for invalid in ["2020-13", "2021-02-29", "20", "2020-3", "March 2020", "2020-03-01T00:00:00Z"] {
+            assert!(invalid.parse::<PartialDate>().is_err(), "{}", invalid);
+        }
+        assert_eq!(serde_json::to_value(march).unwrap(), serde_json::json!("2020-03"));
+
+        // An edge that ended on March 10th was valid during March
+        let start = Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap();
+        assert!(TemporalUtils::valid_during(start, Some(Utc.with_ymd_and_hms(2020, 3, 10, 0, 0, 0).unwrap()), &march));
+        assert!(!TemporalUtils::valid_during(start, Some(march.start()), &march));
+    }
+
+    #[test]
+    fn test_iso_duration() {
+        let duration: IsoDuration = "P1Y6M".parse().unwrap();
+        assert_eq!(duration, IsoDuration { years: 1, months: 6, ..Default::default() });
+        assert_eq!(duration.precision(), TimePrecision::Month);
+        assert_eq!("P2W".parse::<IsoDuration>().unwrap(), IsoDuration::days(14));
+        assert_eq!("PT1H30M".parse::<IsoDuration>().unwrap().to_string(), "PT1H30M");
+        assert_eq!("P1DT90M".parse::<IsoDuration>().unwrap().to_string(), "P1DT1H30M");
+        assert_eq!(IsoDuration::default().to_string(), "P0D");
+        for invalid in ["P", "PT", "1Y", "P1H", "PT1D", "P1.5Y"] {
+            assert!(invalid.parse::<IsoDuration>().is_err(), "{}", invalid);
+        }
+
+        // Calendar months clamp to the end of shorter months
+        let january = Utc.with_ymd_and_hms(2021, 1, 31, 12, 0, 0).unwrap();
+        assert_eq!(IsoDuration::months(1).add_to(january), Some(Utc.with_ymd_and_hms(2021, 2, 28, 12, 0, 0).unwrap()));
+        let joined: PartialDate = "2020-03".parse().unwrap();
+        assert_eq!(duration.after(&joined).unwrap().to_string(), "2021-09");
+
+        assert_eq!("P3M".parse::<TemporalValue>().unwrap(), TemporalValue::Duration(IsoDuration::months(3)));
+        assert_eq!("2020".parse::<TemporalValue>().unwrap().precision(), TimePrecision::Year);
+        assert_eq!("2020-03-01T10:00:00Z".parse::<TemporalValue>().unwrap().precision(), TimePrecision::Instant);
+    }
+
+    #[test]
+    fn test_valid_bounds() {
+        let edge: TimeEdge = serde_json::from_value(serde_json::json!({
+            "from_node_id": uuid::Uuid::nil(),
+            "to_node_id": uuid::Uuid::nil(),
+            "kind": "WORKS_FOR",
+            "valid_from": 2020,
+            "valid_to": "2021-06",
+            "transaction_start_time": "2024-01-01T00:00:00Z",
+            "transaction_end_time": null,
+            "props": {}
+        })).unwrap();
+        assert_eq!(edge.valid_from, Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap());
+        // Left "in June 2021": still valid on June 15th
+        assert_eq!(edge.valid_to, Some(Utc.with_ymd_and_hms(2021, 7, 1, 0, 0, 0).unwrap()));
+        assert!(edge.was_valid_at(Utc.with_ymd_and_hms(2021, 6, 15, 0, 0, 0).unwrap()));
+
+        let relation: crate::traits::ExtractionRelation = serde_json::from_value(serde_json::json!({
+            "from_id_alias": "alice",
+            "to_id_alias": "acme",
+            "type_label": "WORKS_FOR",
+            "props": {},
+            "valid_from": "2020-03-15T09:00:00+01:00",
+            "confidence": null
+        })).unwrap();
+        assert_eq!(relation.valid_from, Some(Utc.with_ymd_and_hms(2020, 3, 15, 8, 0, 0).unwrap()));
+        assert_eq!(relation.valid_to, None);
+
+        assert_eq!(TemporalUtils::parse_time("2020-03", IntervalBound::Start).unwrap(), Utc.with_ymd_and_hms(2020, 3, 1, 0, 0, 0).unwrap());
+        assert!(TemporalUtils::parse_time("last spring", IntervalBound::Start).is_err());
+    }
+}
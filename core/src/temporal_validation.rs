@@ -0,0 +1,199 @@
+//! Validation of bitemporal invariants on edge writes
+//!
+//! Every `GraphStore` implementation runs edges through [`TemporalValidation`]
+//! before writing them, so that no backend stores an edge whose valid-time or
+//! transaction-time interval is inverted, or whose transaction time lies in
+//! the future.
+
+use crate::errors::{GraphError, TemporalError};
+use crate::types::{EdgeSpec, TimeEdge};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Default tolerance for transaction times ahead of the store's clock
+pub const DEFAULT_MAX_CLOCK_SKEW_MS: u64 = 5_000;
+
+/// How edges that violate a temporal invariant are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemporalPolicy {
+    /// Fail the write with `GraphError::Temporal`
+    #[default]
+    Reject,
+    /// Clamp offending timestamps to the nearest consistent value and continue
+    Repair,
+}
+
+/// Temporal validation rules applied to edge writes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemporalValidation {
+    /// What to do with an edge that violates a rule
+    pub policy: TemporalPolicy,
+    /// How far a transaction time may be ahead of the store's clock, in milliseconds
+    pub max_clock_skew_ms: u64,
+}
+
+impl Default for TemporalValidation {
+    fn default() -> Self {
+        Self {
+            policy: TemporalPolicy::default(),
+            max_clock_skew_ms: DEFAULT_MAX_CLOCK_SKEW_MS,
+        }
+    }
+}
+
+impl TemporalValidation {
+    /// Create rules with the given policy and the default clock skew
+    pub fn new(policy: TemporalPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Set the tolerated clock skew
+    pub fn with_max_clock_skew_ms(mut self, max_clock_skew_ms: u64) -> Self {
+        self.max_clock_skew_ms = max_clock_skew_ms;
+        self
+    }
+
+    /// Validate an edge before it is written.
+    ///
+    /// The rules are, in order:
+    /// - transaction times may not be later than now plus the clock skew
+    ///   (repaired by clamping to now)
+    /// - `transaction_end_time` may not precede `transaction_start_time`
+    ///   (repaired by clamping it to the start)
+    /// - `valid_to` may not precede `valid_from` (repaired by clamping it to
+    ///   `valid_from`)
+    pub fn validate_edge<P>(&self, edge: &mut TimeEdge<P>) -> Result<(), GraphError> {
+        let now = Utc::now();
+        let latest = now + Duration::milliseconds(self.max_clock_skew_ms as i64);
+
+        if edge.transaction_start_time > latest {
+            self.violation(TemporalError::FutureTransactionTime {
+                field: "transaction_start_time",
+                time: edge.transaction_start_time,
+            })?;
+            edge.transaction_start_time = now;
+        }
+
+        if let Some(end) = edge.transaction_end_time.filter(|end| *end > latest) {
+            self.violation(TemporalError::FutureTransactionTime {
+                field: "transaction_end_time",
+                time: end,
+            })?;
+            edge.transaction_end_time = Some(now);
+        }
+
+        if let Some(end) = edge.transaction_end_time.filter(|end| *end < edge.transaction_start_time) {
+            self.violation(TemporalError::TransactionEndBeforeStart {
+                transaction_start_time: edge.transaction_start_time,
+                transaction_end_time: end,
+            })?;
+            edge.transaction_end_time = Some(edge.transaction_start_time);
+        }
+
+        edge.valid_to = self.validate_valid_time(edge.valid_from, edge.valid_to)?;
+        Ok(())
+    }
+
+    /// Validate the valid-time interval of an edge spec, before any part of
+    /// a node-with-edges write is applied
+    pub fn validate_spec(&self, spec: &mut EdgeSpec) -> Result<(), GraphError> {
+        if let Some(valid_from) = spec.valid_from {
+            spec.valid_to = self.validate_valid_time(valid_from, spec.valid_to)?;
+        }
+        Ok(())
+    }
+
+    /// Check `valid_to` against `valid_from`, returning the value to store
+    fn validate_valid_time(
+        &self,
+        valid_from: DateTime<Utc>,
+        valid_to: Option<DateTime<Utc>>,
+    ) -> Result<Option<DateTime<Utc>>, GraphError> {
+        match valid_to {
+            Some(valid_to) if valid_to < valid_from => {
+                self.violation(TemporalError::ValidToBeforeValidFrom { valid_from, valid_to })?;
+                Ok(Some(valid_from))
+            }
+            _ => Ok(valid_to),
+        }
+    }
+
+    /// Fail with the violation, or log it when repairing
+    fn violation(&self, error: TemporalError) -> Result<(), GraphError> {
+        match self.policy {
+            TemporalPolicy::Reject => Err(GraphError::Temporal(error)),
+            TemporalPolicy::Repair => {
+                warn!("Repairing temporal violation: {}", error);
+                crate::warnings::report(format!("Repaired temporal data: {}", error));
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn edge(valid_from: &str) -> TimeEdge<()> {
+        TimeEdge::new(Uuid::new_v4(), Uuid::new_v4(), "KNOWS", valid_from.parse().unwrap(), ())
+    }
+
+    #[test]
+    fn test_reject_policy() {
+        let rules = TemporalValidation::default();
+
+        let mut ok = edge("2023-01-01T00:00:00Z").with_valid_to("2024-01-01T00:00:00Z".parse().unwrap());
+        assert!(rules.validate_edge(&mut ok).is_ok());
+
+        let mut inverted = edge("2024-01-01T00:00:00Z").with_valid_to("2023-01-01T00:00:00Z".parse().unwrap());
+        assert!(matches!(
+            rules.validate_edge(&mut inverted),
+            Err(GraphError::Temporal(TemporalError::ValidToBeforeValidFrom { .. }))
+        ));
+
+        let mut future = edge("2023-01-01T00:00:00Z").with_transaction_start_time(Utc::now() + Duration::days(1));
+        assert!(matches!(
+            rules.validate_edge(&mut future),
+            Err(GraphError::Temporal(TemporalError::FutureTransactionTime { field: "transaction_start_time", .. }))
+        ));
+
+        let start = "2024-01-01T00:00:00Z".parse().unwrap();
+        let mut superseded = edge("2023-01-01T00:00:00Z")
+            .with_transaction_start_time(start)
+            .with_transaction_end_time("2023-06-01T00:00:00Z".parse().unwrap());
+        assert!(matches!(
+            rules.validate_edge(&mut superseded),
+            Err(GraphError::Temporal(TemporalError::TransactionEndBeforeStart { .. }))
+        ));
+
+        let mut spec = EdgeSpec::new("KNOWS", crate::types::NodeRef::Id(Uuid::new_v4()))
+            .with_valid_from(start)
+            .with_valid_to("2023-01-01T00:00:00Z".parse().unwrap());
+        assert!(rules.validate_spec(&mut spec).is_err());
+    }
+
+    #[test]
+    fn test_repair_policy() {
+        let rules = TemporalValidation::new(TemporalPolicy::Repair);
+
+        let valid_from = "2024-01-01T00:00:00Z".parse().unwrap();
+        let mut inverted = edge("2024-01-01T00:00:00Z").with_valid_to("2023-01-01T00:00:00Z".parse().unwrap());
+        rules.validate_edge(&mut inverted).unwrap();
+        assert_eq!(inverted.valid_to, Some(valid_from));
+
+        let mut future = edge("2023-01-01T00:00:00Z")
+            .with_transaction_start_time(Utc::now() + Duration::days(1))
+            .with_transaction_end_time(Utc::now() + Duration::days(2));
+        rules.validate_edge(&mut future).unwrap();
+        assert!(future.transaction_start_time <= Utc::now());
+        assert!(future.transaction_end_time.unwrap() >= future.transaction_start_time);
+    }
+}
@@ -0,0 +1,288 @@
+//! Activity timelines of entities
+//!
+//! A timeline shows what happened with a node over time: the edges to and
+//! from it that became valid (`created`) or stopped being valid (`closed`),
+//! bucketed by day, week or month over a range of valid time. Only current
+//! edge versions count, so a corrected fact shows up once, at its corrected
+//! times, and a retracted one not at all.
+//!
+//! Stores find the edge versions of the node whose valid time starts or ends
+//! within the request's range and hand them to [`build_timeline`] here, so
+//! every store buckets them the same way.
+
+use crate::errors::GraphError;
+use crate::types::EdgeRecord;
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Buckets covered when a request names no start
+pub const DEFAULT_TIMELINE_BUCKETS: u32 = 30;
+
+/// Most buckets a timeline may have
+pub const MAX_TIMELINE_BUCKETS: usize = 1_000;
+
+/// Width of a timeline's buckets; buckets start at midnight UTC, weeks on
+/// Monday and months on their first day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineInterval {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl TimelineInterval {
+    /// Start of the bucket holding `at`
+    pub fn bucket_start(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let day = at.date_naive();
+        let start = match self {
+            Self::Day => day,
+            Self::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+            Self::Month => day.with_day(1).unwrap_or(day),
+        };
+        start.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+    }
+
+    /// Start of the bucket `buckets` after the one starting at `start`
+    fn advance(self, start: DateTime<Utc>, buckets: u32) -> DateTime<Utc> {
+        match self {
+            Self::Day => start + Duration::days(buckets as i64),
+            Self::Week => start + Duration::weeks(buckets as i64),
+            Self::Month => start.checked_add_months(Months::new(buckets)).unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+
+    /// Start of the bucket `buckets` before the one starting at `start`
+    fn rewind(self, start: DateTime<Utc>, buckets: u32) -> DateTime<Utc> {
+        match self {
+            Self::Day => start - Duration::days(buckets as i64),
+            Self::Week => start - Duration::weeks(buckets as i64),
+            Self::Month => start.checked_sub_months(Months::new(buckets)).unwrap_or(DateTime::<Utc>::MIN_UTC),
+        }
+    }
+}
+
+impl std::fmt::Display for TimelineInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Day => write!(f, "day"),
+            Self::Week => write!(f, "week"),
+            Self::Month => write!(f, "month"),
+        }
+    }
+}
+
+/// Request for the activity timeline of a node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineRequest {
+    pub node: Uuid,
+    #[serde(default)]
+    pub interval: TimelineInterval,
+    /// Start of the range; `DEFAULT_TIMELINE_BUCKETS` buckets before its end
+    /// by default
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range, exclusive; now by default
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+    /// Count only edges of these relationship types; all when empty
+    #[serde(default)]
+    pub relationship_types: Vec<String>,
+}
+
+impl TimelineRequest {
+    pub fn new(node: Uuid, interval: TimelineInterval) -> Self {
+        Self { node, interval, from: None, to: None, relationship_types: Vec::new() }
+    }
+
+    /// Range the timeline covers, from the start of the bucket holding
+    /// `from` up to `to`
+    pub fn range(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), GraphError> {
+        let to = self.to.unwrap_or_else(Utc::now);
+        let from = match self.from {
+            Some(from) => self.interval.bucket_start(from),
+            None => self.interval.rewind(self.interval.bucket_start(to), DEFAULT_TIMELINE_BUCKETS - 1),
+        };
+        if from >= to {
+            return Err(GraphError::QueryFailed(format!("Timeline range starts at {} but ends at {}", from, to)));
+        }
+        Ok((from, to))
+    }
+
+    /// Whether a timeline counts an edge version
+    fn includes(&self, record: &EdgeRecord) -> bool {
+        let edge = &record.edge;
+        edge.is_current_version()
+            && (edge.from_node_id == self.node || edge.to_node_id == self.node)
+            && (self.relationship_types.is_empty() || self.relationship_types.contains(&edge.kind))
+    }
+}
+
+/// What happened to an edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeChange {
+    /// The edge became valid
+    Created,
+    /// The edge stopped being valid
+    Closed,
+}
+
+/// An edge of the node starting or ending
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub at: DateTime<Utc>,
+    pub change: EdgeChange,
+    pub edge_id: Uuid,
+    pub kind: String,
+    pub from_node_id: Uuid,
+    pub to_node_id: Uuid,
+}
+
+/// Events of one bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineBucket {
+    pub start: DateTime<Utc>,
+    /// Start of the next bucket
+    pub end: DateTime<Utc>,
+    pub created: u64,
+    pub closed: u64,
+    /// The bucket's events, earliest first
+    pub events: Vec<TimelineEvent>,
+}
+
+/// Activity timeline of a node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    pub node_id: Uuid,
+    pub interval: TimelineInterval,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Every bucket of the range in order, empty ones included
+    pub buckets: Vec<TimelineBucket>,
+}
+
+/// Bucket the changes of a node's edges within `range`, the request's
+/// [`TimelineRequest::range`]. Edges that are not current versions, do not
+/// touch the node or are of other relationship types are ignored, as are
+/// changes outside the range.
+pub fn build_timeline(
+    request: &TimelineRequest,
+    (from, to): (DateTime<Utc>, DateTime<Utc>),
+    edges: impl IntoIterator<Item = EdgeRecord>,
+) -> Result<Timeline, GraphError> {
+
+    let mut buckets = Vec::new();
+    let mut start = from;
+    while start < to {
+        if buckets.len() == MAX_TIMELINE_BUCKETS {
+            return Err(GraphError::QueryFailed(format!(
+                "Timeline from {} to {} by {} has more than {} buckets",
+                from, to, request.interval, MAX_TIMELINE_BUCKETS
+            )));
+        }
+        let end = request.interval.advance(start, 1);
+        buckets.push(TimelineBucket { start, end, created: 0, closed: 0, events: Vec::new() });
+        start = end;
+    }
+
+    let mut events: Vec<TimelineEvent> = edges.into_iter()
+        .filter(|record| request.includes(record))
+        .flat_map(|record| {
+            let edge = record.edge;
+            [(Some(edge.valid_from), EdgeChange::Created), (edge.valid_to, EdgeChange::Closed)]
+                .into_iter()
+                .filter_map(|(at, change)| at.map(|at| (at, change)))
+                .filter(|(at, _)| from <= *at && *at < to)
+                .map(|(at, change)| TimelineEvent {
+                    at,
+                    change,
+                    edge_id: record.id,
+                    kind: edge.kind.clone(),
+                    from_node_id: edge.from_node_id,
+                    to_node_id: edge.to_node_id,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    events.sort_by(|a, b| a.at.cmp(&b.at).then_with(|| a.edge_id.cmp(&b.edge_id)));
+
+    for event in events {
+        // Buckets are in order, so the event's is the last one starting at or before it
+        let index = buckets.partition_point(|bucket| bucket.start <= event.at) - 1;
+        let bucket = &mut buckets[index];
+        match event.change {
+            EdgeChange::Created => bucket.created += 1,
+            EdgeChange::Closed => bucket.closed += 1,
+        }
+        bucket.events.push(event);
+    }
+
+    Ok(Timeline { node_id: request.node, interval: request.interval, from, to, buckets })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TimeEdge;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        // 2024-03-14 is a Thursday
+        let thursday = at(2024, 3, 14);
+        assert_eq!(TimelineInterval::Day.bucket_start(thursday), Utc.with_ymd_and_hms(2024, 3, 14, 0, 0, 0).unwrap());
+        assert_eq!(TimelineInterval::Week.bucket_start(thursday), Utc.with_ymd_and_hms(2024, 3, 11, 0, 0, 0).unwrap());
+        assert_eq!(TimelineInterval::Month.bucket_start(thursday), Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_build_timeline() {
+        let node = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let mut request = TimelineRequest::new(node, TimelineInterval::Month);
+        request.from = Some(at(2024, 1, 20));
+        request.to = Some(at(2024, 4, 1));
+
+        let closed = TimeEdge::new(node, other, "WORKS_AT", at(2024, 1, 5), serde_json::json!({}))
+            .with_valid_to(at(2024, 3, 2));
+        let opened = TimeEdge::new(other, node, "KNOWS", at(2024, 2, 10), serde_json::json!({}));
+        let retracted = TimeEdge::new(node, other, "KNOWS", at(2024, 2, 11), serde_json::json!({}))
+            .with_transaction_end_time(at(2024, 2, 12));
+        let unrelated = TimeEdge::new(other, other, "KNOWS", at(2024, 2, 13), serde_json::json!({}));
+
+        let edges = [closed, opened, retracted, unrelated].into_iter()
+            .map(|edge| EdgeRecord { id: Uuid::new_v4(), edge });
+        let timeline = build_timeline(&request, request.range().unwrap(), edges).unwrap();
+
+        // The range starts with the bucket holding `from`
+        assert_eq!(timeline.from, Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        let counts: Vec<_> = timeline.buckets.iter().map(|bucket| (bucket.created, bucket.closed)).collect();
+        assert_eq!(counts, vec![(1, 0), (1, 0), (0, 1), (0, 0)]);
+        assert_eq!(timeline.buckets[1].events[0].kind, "KNOWS");
+        assert_eq!(timeline.buckets[3].end, Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap());
+
+        request.relationship_types = vec!["KNOWS".to_string()];
+        let timeline = build_timeline(&request, request.range().unwrap(), Vec::new()).unwrap();
+        assert!(timeline.buckets.iter().all(|bucket| bucket.events.is_empty()));
+
+        request.interval = TimelineInterval::Day;
+        request.from = Some(at(2000, 1, 1));
+        let range = request.range().unwrap();
+        assert!(build_timeline(&request, range, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_default_range() {
+        let mut request = TimelineRequest::new(Uuid::new_v4(), TimelineInterval::Week);
+        request.to = Some(at(2024, 3, 14));
+        let (from, _) = request.range().unwrap();
+        assert_eq!(from, Utc.with_ymd_and_hms(2023, 8, 21, 0, 0, 0).unwrap());
+    }
+}
@@ -0,0 +1,190 @@
+//! Token estimation and per-model context-window limits
+
+use crate::traits::{ContentPart, ExtractionContext, LlmMessage};
+
+/// Tokens added by chat formatting for every message (role markers, separators)
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Tokens counted for an image part, whose real cost depends on its size
+const IMAGE_TOKENS: usize = 1000;
+
+/// Estimates how many tokens a model will see for a piece of text.
+///
+/// Estimates are deliberately conservative: they are used to keep requests
+/// under the context window, where over-counting is cheap and under-counting
+/// fails the request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenEstimator {
+    /// Approximation of tiktoken's BPE pre-tokenization (OpenAI models)
+    Tiktoken,
+    /// Fixed characters-per-token ratio for providers without a public tokenizer
+    CharRatio(f32),
+}
+
+impl TokenEstimator {
+    /// Estimator for a provider name as reported in `ExtractionMetadata`
+    pub fn for_provider(provider: &str) -> Self {
+        match provider {
+            "openai" => Self::Tiktoken,
+            "anthropic" => Self::CharRatio(3.5),
+            _ => Self::CharRatio(4.0),
+        }
+    }
+
+    /// Estimate the tokens in a piece of text
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Tiktoken => estimate_bpe_tokens(text),
+            Self::CharRatio(chars_per_token) => {
+                (text.chars().count() as f32 / chars_per_token.max(1.0)).ceil() as usize
+            }
+        }
+    }
+
+    /// Estimate the tokens of a single chat message including formatting
+    pub fn count_message(&self, message: &LlmMessage) -> usize {
+        let parts: usize = message.parts.iter().map(|part| match part {
+            ContentPart::Text { text } => self.count(text),
+            ContentPart::Image { .. } => IMAGE_TOKENS,
+            ContentPart::ToolResult { content, .. } => self.count(content) + MESSAGE_OVERHEAD_TOKENS,
+        }).sum();
+        self.count(&message.content) + parts + MESSAGE_OVERHEAD_TOKENS
+    }
+
+    /// Estimate the tokens of the conversation in an extraction context
+    pub fn count_messages(&self, messages: &[LlmMessage]) -> usize {
+        messages.iter().map(|message| self.count_message(message)).sum()
+    }
+
+    /// Estimate the tokens of an extraction context, including its system prompt
+    pub fn count_context(&self, context: &ExtractionContext) -> usize {
+        let system = context.system_prompt.as_deref().map_or(0, |prompt| self.count(prompt));
+        system + self.count_messages(&context.messages)
+    }
+}
+
+/// Token limits of a model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelLimits {
+    /// Total tokens (input and output) the model accepts
+    pub context_window: usize,
+    /// Maximum tokens the model can generate
+    pub max_output_tokens: usize,
+}
+
+impl ModelLimits {
+    /// Tokens left for input after reserving the output and a fixed overhead
+    /// (prompt, schema). `max_output_tokens` defaults to the model maximum.
+    pub fn input_budget(&self, max_output_tokens: Option<u32>, overhead: usize) -> usize {
+        let reserved_output = max_output_tokens
+            .map_or(self.max_output_tokens, |tokens| (tokens as usize).min(self.max_output_tokens));
+
+        self.context_window
+            .saturating_sub(reserved_output)
+            .saturating_sub(overhead)
+    }
+}
+
+/// Known limits by model-name prefix; more specific prefixes come first
+const MODEL_LIMITS: &[(&str, usize, usize)] = &[
+    ("gpt-4o", 128_000, 16_384),
+    ("gpt-4-turbo", 128_000, 4_096),
+    ("gpt-4-32k", 32_768, 4_096),
+    ("gpt-4.1", 1_047_576, 32_768),
+    ("gpt-4", 8_192, 4_096),
+    ("gpt-3.5-turbo", 16_385, 4_096),
+    ("o1", 200_000, 100_000),
+    ("o3", 200_000, 100_000),
+    ("o4", 200_000, 100_000),
+    ("claude-3-5", 200_000, 8_192),
+    ("claude-3", 200_000, 4_096),
+    ("claude-2", 100_000, 4_096),
+    ("claude-", 200_000, 8_192),
+    ("gemini-1.5-pro", 2_097_152, 8_192),
+    ("gemini-1.5", 1_048_576, 8_192),
+    ("gemini-2", 1_048_576, 8_192),
+    ("gemini-pro", 32_760, 8_192),
+];
+
+/// Limits used for models missing from the table
+const DEFAULT_LIMITS: ModelLimits = ModelLimits {
+    context_window: 8_192,
+    max_output_tokens: 2_048,
+};
+
+/// Look up the token limits of a model by name
+pub fn model_limits(model: &str) -> ModelLimits {
+    MODEL_LIMITS
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix))
+        .map_or(DEFAULT_LIMITS, |&(_, context_window, max_output_tokens)| ModelLimits {
+            context_window,
+            max_output_tokens,
+        })
+}
+
+/// Approximate cl100k-style tokenization: words of up to ~5 letters, digit
+/// groups of up to 3 and punctuation are one token each, a single space joins
+/// the following word, and non-ASCII characters count one token each.
+fn estimate_bpe_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphabetic() {
+            let mut length: usize = 1;
+            while chars.next_if(|next| next.is_ascii_alphabetic()).is_some() {
+                length += 1;
+            }
+            tokens += length.div_ceil(5);
+        } else if c.is_ascii_digit() {
+            let mut length: usize = 1;
+            while chars.next_if(|next| next.is_ascii_digit()).is_some() {
+                length += 1;
+            }
+            tokens += length.div_ceil(3);
+        } else if c.is_whitespace() {
+            let mut length = 1;
+            while chars.next_if(|next| next.is_whitespace()).is_some() {
+                length += 1;
+            }
+            if length > 1 || c != ' ' {
+                tokens += 1;
+            }
+        } else {
+            tokens += 1;
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimators() {
+        let tiktoken = TokenEstimator::Tiktoken;
+        assert_eq!(tiktoken.count(""), 0);
+        assert_eq!(tiktoken.count("Alice works at Acme."), 5);
+        assert_eq!(tiktoken.count("123456"), 2);
+
+        let heuristic = TokenEstimator::for_provider("gemini");
+        assert_eq!(heuristic.count("abcdefgh"), 2);
+        assert_eq!(TokenEstimator::for_provider("openai"), TokenEstimator::Tiktoken);
+    }
+
+    #[test]
+    fn test_model_limits() {
+        assert_eq!(model_limits("gpt-4o-mini").context_window, 128_000);
+        assert_eq!(model_limits("gpt-4").context_window, 8_192);
+        assert_eq!(model_limits("claude-3-sonnet").max_output_tokens, 4_096);
+        assert_eq!(model_limits("unknown-model"), DEFAULT_LIMITS);
+
+        let limits = model_limits("gpt-4");
+        assert_eq!(limits.input_budget(Some(1_000), 192), 7_000);
+        assert_eq!(limits.input_budget(None, 0), 4_096);
+        assert_eq!(limits.input_budget(Some(1_000), 10_000), 0);
+    }
+}
@@ -1,9 +1,26 @@
 //! Core traits defining the plugin interfaces for TelaMentis
 
-use crate::errors::{GraphError, LlmError, PresentationError, SourceError};
-use crate::types::{GraphMutation, GraphQuery, Node, Path, TenantId, TimeEdge};
+use crate::admin::JobProgress;
+use crate::analytics::AnalyticsResult;
+use crate::archive::{ArchiveBatch, ArchiveManifest, ArchiveSegment};
+use crate::availability::CapabilityStatus;
+use crate::auth::StoredToken;
+use crate::bulk::{self, EdgeStream, NodeStream};
+use crate::constraints::EdgeConstraints;
+use crate::errors::{AnalyticsError, ArchiveError, AuthError, CoreError, GraphError, LlmError, PresentationError, SourceError, TelemetryError, VectorError};
+use crate::examples::ExtractionExample;
+use crate::model_selection::ModelDecision;
+use crate::valid_time::SourceInfo;
+use crate::materialized::SnapshotInfo;
+use crate::migrations::SchemaStatus;
+use crate::telemetry::UsageReport;
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::types::{AliasKey, EdgeByRef, EdgeSpec, GraphMutation, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeRecord, NodeRef, NodeWithEdges, Path, TenantId, TimeEdge, VectorMatch};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -19,28 +36,282 @@ pub trait GraphStore: Send + Sync {
     /// Insert or update a temporal edge for the given tenant
     async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError>;
     
+    /// Upsert a node and create edges to existing nodes in one atomic operation
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError>;
+    
+    /// Upsert a stream of nodes, committed in chunks of a size that suits
+    /// the store; returns the number upserted. Defaults to upserting them
+    /// one at a time.
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        bulk::upsert_nodes_each(self, tenant, nodes).await
+    }
+    
+    /// Upsert a stream of edges, committed in chunks of a size that suits
+    /// the store; returns the number upserted. Defaults to upserting them
+    /// one at a time.
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        bulk::upsert_edges_each(self, tenant, edges).await
+    }
+    
     /// Execute a query against the graph for the given tenant
     async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError>;
     
+    /// Count the results of a query, ignoring its ordering, offset and limit.
+    /// Stores that can count without reading the matches override this.
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        Ok(self.query(tenant, query.with_page(None, None)).await?.len() as u64)
+    }
+    
+    /// Whether a query has any result, ignoring its offset and limit
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        Ok(!self.query(tenant, query.with_page(None, Some(1))).await?.is_empty())
+    }
+    
     /// Get a node by its system ID
     async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError>;
     
     /// Get a node by its id_alias
     async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError>;
     
+    /// Resolve a batch of namespace-qualified aliases to node IDs (unknown aliases are omitted)
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError>;
+    
     /// Delete a node (logical delete)
     async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError>;
     
     /// Delete an edge (logical delete) 
     async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError>;
     
+    /// End the current version of an edge at `valid_to` in valid time. The
+    /// closed fact is recorded as a new version; returns its ID.
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError>;
+    
+    /// Replace the current version of an edge with a corrected edge; returns
+    /// the ID of the new version
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError>;
+    
+    /// Mark the current version of an edge as never true by ending it in
+    /// transaction time. Returns false if there is no current version.
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError>;
+    
     /// Get the history of changes for a node
     async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError>;
     
+    /// Read a consistent snapshot of the tenant's graph as known now, optionally
+    /// restricted to edges valid at `valid_at`
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError>;
+    
+    /// Read the graph valid at `valid_at` once and keep it as a named
+    /// snapshot, replacing any snapshot of that name
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError>;
+    
+    /// List the tenant's materialized snapshots
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError>;
+    
+    /// Drop a materialized snapshot; returns false if there is none of that name
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError>;
+    
+    /// Execute a structured query against a materialized snapshot
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError>;
+    
+    /// Read closed history that ended before `before`: deleted nodes, and
+    /// edge versions that were superseded, retracted or deleted
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError>;
+    
+    /// Remove closed history that ended before `before`; returns the number of records removed
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError>;
+    
+    /// Write archived history back with its original IDs and times, skipping
+    /// records already present; returns the number of records written
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError>;
+
+    /// Remove edges that expired by `now` for good, without keeping them as
+    /// history; returns the number of edges removed
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError>;
+    
+    /// Count the tenant's nodes and edges without reading them
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError>;
+    
+    /// List the labels, relationship kinds and property keys in use by the tenant
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError>;
+    
+    /// List the tenants that have nodes, edges or history in the store, sorted.
+    /// Optional; stores that cannot enumerate tenants return `GraphError::Unsupported`.
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        Err(GraphError::Unsupported("Listing tenants".to_string()))
+    }
+    
+    /// Permanently remove all of a tenant's nodes, edges, history and
+    /// snapshots, leaving other tenants untouched; returns the number of
+    /// records removed. Optional, like `list_tenants`.
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        Err(GraphError::Unsupported(format!("Clearing tenant {}", tenant)))
+    }
+    
+    /// Follow current edges out from a node, reaching each node once, by
+    /// fewest hops or least total weight. Optional, like `list_tenants`.
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        Err(GraphError::Unsupported(format!("Traversing from node {} of tenant {}", request.start, tenant)))
+    }
+    
+    /// Path between two nodes over current edges with the fewest hops, or the
+    /// least total weight; `None` if there is none within the request's
+    /// depth. Optional, like `list_tenants`.
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        Err(GraphError::Unsupported(format!("Shortest paths from node {} of tenant {}", request.from, tenant)))
+    }
+    
+    /// Edges of a node that became valid or stopped being valid over a range
+    /// of valid time, bucketed as `timeline::build_timeline` does. Optional,
+    /// like `list_tenants`.
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        Err(GraphError::Unsupported(format!("Timeline of node {} of tenant {}", request.node, tenant)))
+    }
+    
+    /// Values the property `key` of a node took over time, built from the
+    /// node's versions as `property_history::build_property_history` does.
+    /// Optional, like `list_tenants`; stores that keep no node versions
+    /// cannot support it.
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        Err(GraphError::Unsupported(format!("History of property '{}' of node {} of tenant {}", key, node_id, tenant)))
+    }
+    
+    /// Replace the label and properties of a node by system ID, keeping its
+    /// alias; the replaced version is kept as history where the store keeps
+    /// node versions. Returns false if the tenant has no such node. Unlike
+    /// `upsert_node`, the label may change. Optional, like `list_tenants`.
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, _node: Node) -> Result<bool, GraphError> {
+        Err(GraphError::Unsupported(format!("Updating node {} of tenant {}", id, tenant)))
+    }
+    
+    /// The tenant's relationship constraints, checked at every edge upsert.
+    /// Stores that cannot enforce constraints have none.
+    async fn edge_constraints(&self, _tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        Ok(EdgeConstraints::default())
+    }
+    
+    /// Replace the tenant's relationship constraints; edges already stored
+    /// are not checked. Optional, like `list_tenants`.
+    async fn set_edge_constraints(&self, tenant: &TenantId, _constraints: EdgeConstraints) -> Result<(), GraphError> {
+        Err(GraphError::Unsupported(format!("Edge constraints of tenant {}", tenant)))
+    }
+    
     /// Test the connection to the storage backend
     async fn health_check(&self) -> Result<(), GraphError>;
 }
 
+/// Trait for similarity search over per-tenant embeddings
+#[async_trait]
+pub trait VectorIndex: Send + Sync {
+    /// Store the embedding for `id`, replacing any previous one
+    async fn upsert_vector(&self, tenant: &TenantId, id: Uuid, vector: Vec<f32>) -> Result<(), VectorError>;
+    
+    /// Remove the embedding for `id`; returns false if there was none
+    async fn remove_vector(&self, tenant: &TenantId, id: Uuid) -> Result<bool, VectorError>;
+    
+    /// Find the `k` embeddings nearest to `query`, closest first
+    async fn search(&self, tenant: &TenantId, query: &[f32], k: usize) -> Result<Vec<VectorMatch>, VectorError>;
+    
+    /// Number of embeddings stored for the tenant
+    async fn count(&self, tenant: &TenantId) -> Result<usize, VectorError>;
+    
+    /// Remove all of the tenant's embeddings; returns false if there were none
+    async fn drop_tenant(&self, tenant: &TenantId) -> Result<bool, VectorError>;
+}
+
+/// Trait for models turning texts into embeddings, of any provider
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Name of the provider, e.g. `openai`, whose request lane the embedder
+    /// shares with the provider's LLM connector
+    fn provider(&self) -> &str;
+    
+    /// Most texts the provider takes in one request
+    fn max_batch_size(&self) -> usize;
+    
+    /// Embed texts, returning one result per text in order. An error for
+    /// the whole request fails every text; an error for one text, e.g. one
+    /// over the model's input limit, fails only that text.
+    async fn embed(&self, tenant: &TenantId, texts: &[String]) -> Result<Vec<Result<Vec<f32>, LlmError>>, LlmError>;
+}
+
+/// Trait for maintenance jobs an operator can run on demand, e.g. history
+/// retention or deduplication
+#[async_trait]
+pub trait MaintenanceJob: Send + Sync {
+    /// Run the job over a tenant's graph, returning how many records it changed
+    async fn run(&self, tenant: &TenantId) -> Result<u64, CoreError>;
+
+    /// Progress of the job over each tenant it ran for, for jobs long enough
+    /// to be watched while they run
+    fn progress(&self) -> Vec<JobProgress> {
+        Vec::new()
+    }
+}
+
+/// Trait for destinations of aggregate usage reports
+#[async_trait]
+pub trait TelemetryExporter: Send + Sync {
+    /// Send a report; reports that fail are not retried
+    async fn export(&self, report: &UsageReport) -> Result<(), TelemetryError>;
+}
+
+/// Trait for cold storage of closed graph history
+#[async_trait]
+pub trait ArchiveSink: Send + Sync {
+    /// Write a batch as a new segment and record it in the tenant's manifest
+    async fn write_segment(&self, tenant: &TenantId, batch: &ArchiveBatch) -> Result<ArchiveSegment, ArchiveError>;
+    
+    /// Read the tenant's manifest; empty if nothing has been archived
+    async fn manifest(&self, tenant: &TenantId) -> Result<ArchiveManifest, ArchiveError>;
+    
+    /// Read back the records of a segment
+    async fn read_segment(&self, tenant: &TenantId, segment: &ArchiveSegment) -> Result<ArchiveBatch, ArchiveError>;
+}
+
+/// Trait for stores whose schema is versioned and migrated
+#[async_trait]
+pub trait SchemaMigrator: Send + Sync {
+    /// Applied and pending migrations of the database
+    async fn schema_status(&self) -> Result<SchemaStatus, GraphError>;
+    
+    /// Apply pending migrations in order, up to `target` or all of them
+    async fn migrate(&self, target: Option<u32>) -> Result<SchemaStatus, GraphError>;
+}
+
+/// Trait for storage of API token records and their secret hashes
+#[async_trait]
+pub trait SecretStore: Send + Sync {
+    /// Insert or replace the record with the token's ID
+    async fn put(&self, record: StoredToken) -> Result<(), AuthError>;
+    
+    /// Read a record by token ID
+    async fn get(&self, id: Uuid) -> Result<Option<StoredToken>, AuthError>;
+    
+    /// Read the record whose secret hashes to `secret_hash`
+    async fn find_by_hash(&self, secret_hash: &str) -> Result<Option<StoredToken>, AuthError>;
+    
+    /// All records of a tenant, including revoked and expired tokens
+    async fn list(&self, tenant: &TenantId) -> Result<Vec<StoredToken>, AuthError>;
+}
+
+/// Trait for read-only SQL analytics over copies of tenants' graphs
+#[async_trait]
+pub trait AnalyticsEngine: Send + Sync {
+    /// Replace the tenant's `nodes` and `edges` tables with a snapshot
+    async fn load(&self, tenant: &TenantId, snapshot: &GraphSnapshot) -> Result<(), AnalyticsError>;
+    
+    /// Transaction time of the snapshot last loaded for the tenant, if any
+    async fn loaded_at(&self, tenant: &TenantId) -> Result<Option<DateTime<Utc>>, AnalyticsError>;
+    
+    /// Run a read-only query over the tenant's tables, returning at most
+    /// `max_rows` rows
+    async fn query(&self, tenant: &TenantId, sql: &str, max_rows: usize) -> Result<AnalyticsResult, AnalyticsError>;
+    
+    /// Remove the tenant's tables; returns false if none were loaded
+    async fn drop_tenant(&self, tenant: &TenantId) -> Result<bool, AnalyticsError>;
+}
+
 /// Trait for Large Language Model connectors
 #[async_trait]
 pub trait LlmConnector: Send + Sync {
@@ -52,6 +323,52 @@ pub trait LlmConnector: Send + Sync {
         // Default implementation returns not implemented
         Err(LlmError::InternalError("Complete method not implemented".to_string()))
     }
+
+    /// Models the provider offers; empty for connectors that cannot list them
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmError> {
+        Ok(Vec::new())
+    }
+
+    /// Probe whether the provider can currently be used. The default lists
+    /// models, which needs valid credentials but costs no tokens.
+    async fn health(&self) -> CapabilityStatus {
+        match self.list_models().await {
+            Ok(_) => CapabilityStatus::Available,
+            Err(e) => CapabilityStatus::Unavailable { reason: e.to_string() },
+        }
+    }
+
+    /// Status and models of each provider behind the connector that the
+    /// tenant may use, or of every provider without a tenant
+    async fn provider_status(&self, _tenant: Option<&TenantId>) -> Vec<ProviderStatus> {
+        let status = self.health().await;
+        let models = if status.is_available() { self.list_models().await.unwrap_or_default() } else { Vec::new() };
+        vec![ProviderStatus { provider: DEFAULT_PROVIDER.to_string(), status, models, is_default: true }]
+    }
+}
+
+/// Provider name reported by connectors that do not name their provider
+pub const DEFAULT_PROVIDER: &str = "default";
+
+/// A model a provider offers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// ID to request the model by
+    pub id: String,
+    /// Human-readable name, if the provider has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+/// Whether a provider can be used, and its models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderStatus {
+    pub provider: String,
+    pub status: CapabilityStatus,
+    /// Models the provider offers; empty while it is unavailable
+    pub models: Vec<ModelInfo>,
+    /// Whether requests that name no provider go to this one
+    pub is_default: bool,
 }
 
 /// Context for LLM extraction operations
@@ -67,15 +384,85 @@ pub struct ExtractionContext {
     pub max_tokens: Option<u32>,
     /// Temperature for generation (0.0 to 1.0)
     pub temperature: Option<f32>,
+    /// Few-shot examples for this request; `None` uses the tenant's examples
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub examples: Option<Vec<ExtractionExample>>,
+    /// Model to use instead of the connector's configured model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Provider to use instead of the tenant's default, when the service has
+    /// several connectors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Where the text came from, for valid-time policies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceInfo>,
+    /// Seed for repeatable sampling, for providers that support one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Summary of the tenant's graph grounding the extraction, see
+    /// `GraphContextBuilder`; `None` leaves it out of the prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_context: Option<String>,
 }
 
 /// A message in the LLM conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmMessage {
-    /// Role of the message sender ("user", "assistant", "system")
+    /// Role of the message sender ("user", "assistant", "system", "tool")
     pub role: String,
-    /// Content of the message
+    /// Text content of the message
     pub content: String,
+    /// Further content after the text, e.g. images and tool results;
+    /// connectors skip parts their provider does not accept, with a warning
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
+}
+
+impl LlmMessage {
+    /// Create a text message
+    pub fn new(role: impl Into<String>, content: impl Into<String>) -> Self {
+        Self { role: role.into(), content: content.into(), parts: Vec::new() }
+    }
+
+    /// Add a content part after the message's text
+    pub fn with_part(mut self, part: ContentPart) -> Self {
+        self.parts.push(part);
+        self
+    }
+}
+
+/// A typed part of a message's content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// More text
+    Text { text: String },
+    /// An image, by URL or inline
+    Image { source: ImageSource },
+    /// Output of a tool call the conversation made
+    ToolResult {
+        /// ID of the tool call the result answers
+        tool_call_id: String,
+        /// Name of the tool, which some providers require
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// Output of the tool
+        content: String,
+        /// Whether the tool failed, with `content` the error
+        #[serde(default)]
+        is_error: bool,
+    },
+}
+
+/// Where an image part's data is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ImageSource {
+    /// An image the provider fetches
+    Url { url: String },
+    /// Base64-encoded image data, e.g. of media type `image/png`
+    Base64 { media_type: String, data: String },
 }
 
 /// Result of LLM extraction containing structured knowledge
@@ -113,9 +500,13 @@ pub struct ExtractionRelation {
     pub type_label: String,
     /// Relationship properties
     pub props: serde_json::Value,
-    /// When the relationship became valid
+    /// When the relationship became valid; a partial date such as "2020-03"
+    /// is read as the start of its period
+    #[serde(default, deserialize_with = "crate::temporal::valid_bound::start_opt")]
     pub valid_from: Option<DateTime<Utc>>,
-    /// When the relationship ceased to be valid
+    /// When the relationship ceased to be valid; a partial date is read as
+    /// the end of its period
+    #[serde(default, deserialize_with = "crate::temporal::valid_bound::end_opt")]
     pub valid_to: Option<DateTime<Utc>>,
     /// Confidence score (0.0 to 1.0)
     pub confidence: Option<f32>,
@@ -138,19 +529,64 @@ pub struct ExtractionMetadata {
     pub cost_usd: Option<f64>,
     /// Warnings or issues
     pub warnings: Vec<String>,
+    /// How the model was chosen, when a selection policy was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_selection: Option<ModelDecision>,
+    /// Sources the provider cited, for providers that report them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub citations: Vec<Citation>,
+}
+
+/// A source the provider cited or grounded its response in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    /// URI of the source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    /// Title of the source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Start of the cited span of the response, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_index: Option<u32>,
+    /// End of the cited span of the response, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_index: Option<u32>,
 }
 
 /// Request for text completion
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompletionRequest {
-    /// Input prompt
+    /// Input prompt, sent as a user message after `messages`; may be empty
+    /// when `messages` ends with the turn to answer
+    #[serde(default)]
     pub prompt: String,
+    /// Conversation history
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<LlmMessage>,
+    /// System prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
     /// Maximum tokens to generate
     pub max_tokens: Option<u32>,
     /// Temperature for generation
     pub temperature: Option<f32>,
     /// Additional parameters
     pub params: serde_json::Value,
+    /// Seed for repeatable sampling, for providers that support one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl CompletionRequest {
+    /// Messages to send: the history, then the prompt if there is one
+    pub fn conversation(&self) -> Vec<LlmMessage> {
+        let mut messages = self.messages.clone();
+        if !self.prompt.is_empty() {
+            messages.push(LlmMessage::new("user", self.prompt.clone()));
+        }
+        messages
+    }
 }
 
 /// Response from text completion
@@ -181,14 +617,206 @@ pub trait GraphService: Send + Sync {
     /// Upsert an edge
     async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError>;
     
+    /// Close an edge at `valid_to`, keeping the previous version for audit
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError>;
+    
+    /// Replace an edge with a corrected one, keeping the previous version for audit
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError>;
+    
+    /// Retract an edge that was never true, keeping it for audit
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError>;
+    
+    /// Upsert a node together with edges to existing nodes, atomically
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError>;
+    
     /// Execute a query
     async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError>;
     
+    /// Count the results of a query, as `GraphStore::query_count`
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        Ok(self.query(tenant, query.with_page(None, None)).await?.len() as u64)
+    }
+    
+    /// Whether a query has any result, as `GraphStore::query_exists`
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        Ok(!self.query(tenant, query.with_page(None, Some(1))).await?.is_empty())
+    }
+    
+    /// Resolve a batch of namespace-qualified aliases to node IDs
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError>;
+    
+    /// Read a consistent snapshot of the tenant's graph for export
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError>;
+    
+    /// Materialize a named snapshot of the graph valid at `valid_at`
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError>;
+    
+    /// List a tenant's materialized snapshots
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError>;
+    
+    /// Drop a materialized snapshot
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError>;
+    
+    /// Execute a query against a materialized snapshot
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError>;
+    
+    /// Summarize the size and activity of a tenant's graph
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError>;
+    
+    /// List the labels, relationship kinds and property keys of a tenant's graph
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError>;
+    
     /// Extract knowledge using LLM
     async fn extract_knowledge(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError>;
     
     /// Get service health status
     async fn health_check(&self) -> Result<(), GraphError>;
+    
+    /// Upsert a stream of nodes, as `GraphStore::bulk_load_nodes`
+    async fn bulk_load_nodes(&self, tenant: &TenantId, mut nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        let mut loaded = 0;
+        while let Some(node) = nodes.next().await {
+            self.upsert_node(tenant, node).await?;
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+    
+    /// Upsert a stream of edges, as `GraphStore::bulk_load_edges`
+    async fn bulk_load_edges(&self, tenant: &TenantId, mut edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        let mut loaded = 0;
+        while let Some(edge) = edges.next().await {
+            self.upsert_edge(tenant, edge).await?;
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+    
+    /// Delete a node and its relationships, if the service supports deletes
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        Err(GraphError::Unsupported(format!("Deleting node {} of tenant {}", id, tenant)))
+    }
+    
+    /// Read a node by system ID, if the service supports point reads
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        Err(GraphError::Unsupported(format!("Reading node {} of tenant {}", id, tenant)))
+    }
+    
+    /// Resolve a node reference to the node's system ID, `None` if no node
+    /// has the alias
+    async fn resolve_node_ref(&self, tenant: &TenantId, node: &NodeRef) -> Result<Option<Uuid>, GraphError> {
+        match node {
+            NodeRef::Id(id) => Ok(Some(*id)),
+            NodeRef::Alias(alias) => Ok(self.resolve_aliases(tenant, std::slice::from_ref(alias)).await?.remove(alias)),
+        }
+    }
+    
+    /// Read a node by its namespace-qualified alias
+    async fn get_node_by_alias(&self, tenant: &TenantId, alias: &AliasKey) -> Result<Option<NodeRecord>, GraphError> {
+        let Some(id) = self.resolve_node_ref(tenant, &NodeRef::Alias(alias.clone())).await? else {
+            return Ok(None);
+        };
+        Ok(self.get_node(tenant, id).await?.map(|node| NodeRecord { id, node }))
+    }
+    
+    /// Delete the node with an alias and its relationships; returns the
+    /// deleted node's ID, `None` if no node has the alias
+    async fn delete_node_by_alias(&self, tenant: &TenantId, alias: &AliasKey) -> Result<Option<Uuid>, GraphError> {
+        let Some(id) = self.resolve_node_ref(tenant, &NodeRef::Alias(alias.clone())).await? else {
+            return Ok(None);
+        };
+        Ok(self.delete_node(tenant, id).await?.then_some(id))
+    }
+    
+    /// Merge properties into the node with an alias, see [`Node::merge_props`];
+    /// `None` if no node has the alias
+    async fn patch_node_by_alias(
+        &self,
+        tenant: &TenantId,
+        alias: &AliasKey,
+        props: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Option<NodeRecord>, GraphError> {
+        let Some(NodeRecord { mut node, .. }) = self.get_node_by_alias(tenant, alias).await? else {
+            return Ok(None);
+        };
+        node.merge_props(props);
+        let id = self.upsert_node(tenant, node.clone()).await?;
+        Ok(Some(NodeRecord { id, node }))
+    }
+    
+    /// Upsert an edge whose endpoints may be given by alias
+    async fn upsert_edge_by_ref(&self, tenant: &TenantId, edge: EdgeByRef) -> Result<Uuid, GraphError> {
+        let mut ids = Vec::with_capacity(2);
+        for node in [&edge.from, &edge.to] {
+            let id = self.resolve_node_ref(tenant, node).await?
+                .ok_or_else(|| GraphError::NodeNotFound(format!("Edge endpoint {:?} not found in tenant {}", node, tenant)))?;
+            ids.push(id);
+        }
+        self.upsert_edge(tenant, edge.to_time_edge(ids[0], ids[1])).await
+    }
+    
+    /// Delete an edge, if the service supports deletes
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        Err(GraphError::Unsupported(format!("Deleting edge {} of tenant {}", id, tenant)))
+    }
+    
+    /// List the tenants that have data, if the service can enumerate them
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        Err(GraphError::Unsupported("Listing tenants".to_string()))
+    }
+    
+    /// Permanently remove all of a tenant's graph data; returns the number of
+    /// records removed
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        Err(GraphError::Unsupported(format!("Clearing tenant {}", tenant)))
+    }
+    
+    /// Follow edges out from a node, if the service supports traversal
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        Err(GraphError::Unsupported(format!("Traversing from node {} of tenant {}", request.start, tenant)))
+    }
+    
+    /// Path between two nodes, if the service supports traversal
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        Err(GraphError::Unsupported(format!("Shortest paths from node {} of tenant {}", request.from, tenant)))
+    }
+    
+    /// Activity timeline of a node, if the service supports timelines
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        Err(GraphError::Unsupported(format!("Timeline of node {} of tenant {}", request.node, tenant)))
+    }
+    
+    /// History of a node property, if the service supports it
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        Err(GraphError::Unsupported(format!("History of property '{}' of node {} of tenant {}", key, node_id, tenant)))
+    }
+    
+    /// Replace the label and properties of a node by system ID, if the service supports it
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, _node: Node) -> Result<bool, GraphError> {
+        Err(GraphError::Unsupported(format!("Updating node {} of tenant {}", id, tenant)))
+    }
+    
+    /// The tenant's relationship constraints
+    async fn edge_constraints(&self, _tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        Ok(EdgeConstraints::default())
+    }
+    
+    /// Replace the tenant's relationship constraints, if the service supports them
+    async fn set_edge_constraints(&self, tenant: &TenantId, _constraints: EdgeConstraints) -> Result<(), GraphError> {
+        Err(GraphError::Unsupported(format!("Edge constraints of tenant {}", tenant)))
+    }
+    
+    /// Whether extraction and completion can currently be served. Services
+    /// that guard their connector with `GuardedConnector` report its status.
+    async fn llm_status(&self) -> CapabilityStatus {
+        CapabilityStatus::Available
+    }
+
+    /// Status and models of the LLM providers the tenant may use, or of
+    /// every provider without a tenant; empty without a connector
+    async fn llm_providers(&self, _tenant: Option<&TenantId>) -> Vec<ProviderStatus> {
+        Vec::new()
+    }
 }
 
 /// Trait for data source adapters that stream mutations into the graph
@@ -243,6 +871,36 @@ impl Default for PluginConfig {
     }
 }
 
+/// A plugin's run during a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginRun {
+    /// Plugin name
+    pub plugin: String,
+    /// Pipeline stage the plugin ran in
+    pub stage: String,
+    /// Time spent in the plugin, in microseconds
+    pub duration_us: u64,
+    /// How the plugin finished: `continue`, `halt` or `error`
+    pub outcome: String,
+}
+
+/// What the pipeline did for a request, returned to clients that ask for
+/// debug metadata so they can see where latency went
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationMetadata {
+    /// Plugins that ran, in order
+    pub plugins: Vec<PluginRun>,
+    /// Warnings recorded by plugins
+    pub warnings: Vec<String>,
+}
+
+impl OperationMetadata {
+    /// Total time spent in plugins, in microseconds
+    pub fn plugin_time_us(&self) -> u64 {
+        self.plugins.iter().map(|run| run.duration_us).sum()
+    }
+}
+
 /// Represents the shared context flowing through the pipeline
 #[derive(Debug, Clone)]
 pub struct RequestContext {
@@ -258,6 +916,8 @@ pub struct RequestContext {
     pub attributes: HashMap<String, serde_json::Value>,
     pub start_time: std::time::Instant,
     pub error: Option<String>,
+    /// Plugins run so far and their warnings
+    pub operation: OperationMetadata,
 }
 
 impl RequestContext {
@@ -275,6 +935,7 @@ impl RequestContext {
             attributes: HashMap::new(),
             start_time: std::time::Instant::now(),
             error: None,
+            operation: OperationMetadata::default(),
         }
     }
     
@@ -289,6 +950,13 @@ impl RequestContext {
     pub fn get_attribute(&self, key: &str) -> Option<&serde_json::Value> {
         self.attributes.get(key)
     }
+    
+    /// Record a warning for the operation's metadata and the client
+    pub fn warn(&mut self, warning: impl Into<String>) {
+        let warning = warning.into();
+        crate::warnings::report(warning.clone());
+        self.operation.warnings.push(warning);
+    }
 }
 
     async fn stop(&self) -> Result<(), SourceError>;
@@ -312,11 +980,53 @@ impl ExtractionEnvelope {
       "to_id_alias": "string (refers to node id_alias)",
       "type_label": "string (e.g., WORKS_FOR)",
       "props": {"key": "value", "...": "..."},
-      "valid_from": "datetime (ISO8601, optional)",
-      "valid_to": "datetime (ISO8601, optional, null for open)",
+      "valid_from": "date or datetime (ISO8601 as precise as the text: YYYY, YYYY-MM, YYYY-MM-DD or a timestamp; optional)",
+      "valid_to": "date or datetime (ISO8601 as precise as the text; optional, null for open)",
       "confidence": "float (0.0-1.0, optional)"
     }
   ]
 }"#
     }
+    
+    /// Name of the tool/function used by connectors with native structured output
+    pub const TOOL_NAME: &'static str = "record_extraction";
+    
+    /// JSON Schema of the envelope for tool calling and response schemas
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "nodes": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "id_alias": {"type": "string", "description": "Unique identifier for the node within this extraction"},
+                            "label": {"type": "string", "description": "Node type, e.g. Person or Organization"},
+                            "props": {"type": "object", "description": "Node properties"},
+                            "confidence": {"type": "number", "description": "Confidence score from 0.0 to 1.0"}
+                        },
+                        "required": ["id_alias", "label", "props"]
+                    }
+                },
+                "relations": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "from_id_alias": {"type": "string", "description": "id_alias of the source node"},
+                            "to_id_alias": {"type": "string", "description": "id_alias of the target node"},
+                            "type_label": {"type": "string", "description": "Relationship type, e.g. WORKS_FOR"},
+                            "props": {"type": "object", "description": "Relationship properties"},
+                            "valid_from": {"type": "string", "description": "ISO8601 time the relationship became valid, only as precise as the text: YYYY, YYYY-MM, YYYY-MM-DD or a timestamp"},
+                            "valid_to": {"type": "string", "description": "ISO8601 time the relationship ended, as precise as the text; omit if ongoing"},
+                            "confidence": {"type": "number", "description": "Confidence score from 0.0 to 1.0"}
+                        },
+                        "required": ["from_id_alias", "to_id_alias", "type_label", "props"]
+                    }
+                }
+            },
+            "required": ["nodes", "relations"]
+        })
+    }
 }
\ No newline at end of file
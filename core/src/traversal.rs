@@ -0,0 +1,392 @@
+//! Weighted traversal and shortest paths
+//!
+//! Edges carry an optional weight in a numeric property, `weight` unless a
+//! request names another. Edges without one weigh 1, so unweighted graphs
+//! behave as if every hop cost the same.
+//!
+//! Stores gather the edges a traversal may follow and hand them to
+//! [`traverse`] and [`shortest_path`] here as [`Hop`]s, so every store ranks
+//! and breaks ties the same way.
+
+use crate::errors::GraphError;
+use crate::types::{Path, PathNode, PathRelationship};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use uuid::Uuid;
+
+/// Property holding an edge's weight when the request names none
+pub const DEFAULT_WEIGHT_PROPERTY: &str = "weight";
+
+/// Weight of an edge without a numeric weight
+pub const DEFAULT_EDGE_WEIGHT: f64 = 1.0;
+
+/// Hops followed when the request names no depth
+pub const DEFAULT_MAX_DEPTH: u32 = 3;
+
+/// Deepest traversal served
+pub const MAX_DEPTH: u32 = 10;
+
+/// Direction in which edges are followed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraversalDirection {
+    /// From the edge's source to its target
+    #[default]
+    Outgoing,
+    /// From the edge's target to its source
+    Incoming,
+    /// Either way
+    Both,
+}
+
+/// Order in which a traversal reaches nodes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraversalOrder {
+    /// Fewest hops first
+    #[default]
+    Breadth,
+    /// Least total weight first
+    Weight,
+}
+
+/// Request to follow edges out from a node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraversalRequest {
+    /// Node to start from
+    pub start: Uuid,
+    #[serde(default)]
+    pub direction: TraversalDirection,
+    /// Follow only these relationship types; all when empty
+    #[serde(default)]
+    pub relationship_types: Vec<String>,
+    /// Most hops from the start, at most `MAX_DEPTH`
+    #[serde(default = "default_max_depth")]
+    pub max_depth: u32,
+    /// Follow only edges valid at this time
+    #[serde(default)]
+    pub valid_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub order: TraversalOrder,
+    /// Property holding edge weights, `weight` by default
+    #[serde(default)]
+    pub weight_property: Option<String>,
+    /// Return at most this many nodes, the first reached
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+impl TraversalRequest {
+    /// Breadth-first traversal of outgoing edges from a node
+    pub fn new(start: Uuid) -> Self {
+        Self {
+            start,
+            direction: TraversalDirection::default(),
+            relationship_types: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            valid_at: None,
+            order: TraversalOrder::default(),
+            weight_property: None,
+            limit: None,
+        }
+    }
+
+    /// Property holding edge weights
+    pub fn weight_property(&self) -> &str {
+        self.weight_property.as_deref().unwrap_or(DEFAULT_WEIGHT_PROPERTY)
+    }
+
+    /// Most hops from the start, capped at `MAX_DEPTH`
+    pub fn depth(&self) -> u32 {
+        self.max_depth.min(MAX_DEPTH)
+    }
+}
+
+/// Request for the path between two nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortestPathRequest {
+    pub from: Uuid,
+    pub to: Uuid,
+    #[serde(default)]
+    pub direction: TraversalDirection,
+    /// Follow only these relationship types; all when empty
+    #[serde(default)]
+    pub relationship_types: Vec<String>,
+    /// Longest path considered, at most `MAX_DEPTH`
+    #[serde(default = "default_max_depth")]
+    pub max_depth: u32,
+    /// Follow only edges valid at this time
+    #[serde(default)]
+    pub valid_at: Option<DateTime<Utc>>,
+    /// Minimize total weight instead of the number of hops
+    #[serde(default)]
+    pub weighted: bool,
+    /// Property holding edge weights, `weight` by default
+    #[serde(default)]
+    pub weight_property: Option<String>,
+}
+
+impl ShortestPathRequest {
+    /// Path of fewest outgoing hops between two nodes
+    pub fn new(from: Uuid, to: Uuid) -> Self {
+        Self {
+            from,
+            to,
+            direction: TraversalDirection::default(),
+            relationship_types: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            valid_at: None,
+            weighted: false,
+            weight_property: None,
+        }
+    }
+
+    /// Property holding edge weights
+    pub fn weight_property(&self) -> &str {
+        self.weight_property.as_deref().unwrap_or(DEFAULT_WEIGHT_PROPERTY)
+    }
+
+    /// Longest path considered, capped at `MAX_DEPTH`
+    pub fn depth(&self) -> u32 {
+        self.max_depth.min(MAX_DEPTH)
+    }
+}
+
+fn default_max_depth() -> u32 {
+    DEFAULT_MAX_DEPTH
+}
+
+/// Path from the start of a traversal, with the total weight of its edges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedPath {
+    pub path: Path,
+    pub weight: f64,
+}
+
+/// Edge a traversal can follow from a node, with the node it leads to
+#[derive(Debug, Clone)]
+pub struct Hop {
+    pub relationship: PathRelationship,
+    pub node: PathNode,
+    pub weight: f64,
+}
+
+/// Weight of an edge from its properties
+pub fn edge_weight(properties: &serde_json::Value, property: &str) -> f64 {
+    properties.get(property).and_then(serde_json::Value::as_f64).unwrap_or(DEFAULT_EDGE_WEIGHT)
+}
+
+/// Nodes reachable from `start`, each by its first path in the requested
+/// order. `hops` lists the edges the traversal may follow from a node.
+pub fn traverse(
+    request: &TraversalRequest,
+    start: PathNode,
+    hops: impl FnMut(Uuid) -> Vec<Hop>,
+) -> Result<Vec<WeightedPath>, GraphError> {
+    let limit = request.limit.map_or(usize::MAX, |limit| limit as usize);
+    let mut reached = Vec::new();
+    if limit > 0 {
+        explore(start, request.depth(), request.order == TraversalOrder::Weight, hops, |path| {
+            reached.push(path);
+            reached.len() < limit
+        })?;
+    }
+    Ok(reached)
+}
+
+/// Path from `start` to the request's target with the fewest hops, or the
+/// least total weight if the request is weighted; `None` if the target is
+/// not reachable within the request's depth
+pub fn shortest_path(
+    request: &ShortestPathRequest,
+    start: PathNode,
+    hops: impl FnMut(Uuid) -> Vec<Hop>,
+) -> Result<Option<WeightedPath>, GraphError> {
+    if start.id == request.to {
+        return Ok(Some(WeightedPath { path: Path { nodes: vec![start], relationships: Vec::new() }, weight: 0.0 }));
+    }
+
+    let mut found = None;
+    explore(start, request.depth(), request.weighted, hops, |path| {
+        if path.path.nodes.last().is_some_and(|node| node.id == request.to) {
+            found = Some(path);
+            return false;
+        }
+        true
+    })?;
+    Ok(found)
+}
+
+/// Path waiting to be extended, cheapest first, then first queued
+struct Frontier {
+    cost: f64,
+    seq: u64,
+    path: WeightedPath,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    // Reversed, as `BinaryHeap` pops the greatest
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// Extend paths from `start` cheapest first, where each hop costs 1 or,
+/// if `weighted`, its weight. `reached` sees each node once, on its
+/// cheapest path within `max_depth` hops, and stops the search by
+/// returning false.
+fn explore(
+    start: PathNode,
+    max_depth: u32,
+    weighted: bool,
+    mut hops: impl FnMut(Uuid) -> Vec<Hop>,
+    mut reached: impl FnMut(WeightedPath) -> bool,
+) -> Result<(), GraphError> {
+    // Fewest hops of any path a node was settled on. A costlier path is
+    // still extended if it is shorter, as it may reach further.
+    let mut settled: HashMap<Uuid, usize> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    let mut seq = 0;
+    queue.push(Frontier {
+        cost: 0.0,
+        seq,
+        path: WeightedPath { path: Path { nodes: vec![start], relationships: Vec::new() }, weight: 0.0 },
+    });
+
+    while let Some(Frontier { cost, path, .. }) = queue.pop() {
+        let node_id = path.path.nodes.last().map(|node| node.id).unwrap_or_default();
+        let depth = path.path.relationships.len();
+        match settled.get(&node_id) {
+            Some(&shortest) if shortest <= depth => continue,
+            None if depth > 0 && !reached(path.clone()) => return Ok(()),
+            _ => {}
+        }
+        settled.insert(node_id, depth);
+
+        if depth as u32 >= max_depth {
+            continue;
+        }
+        for hop in hops(node_id) {
+            if weighted && hop.weight < 0.0 {
+                return Err(GraphError::QueryFailed(format!(
+                    "Edge {} has weight {}; weighted traversal needs weights of 0 or more",
+                    hop.relationship.id, hop.weight
+                )));
+            }
+            if path.path.nodes.iter().any(|node| node.id == hop.node.id) {
+                continue;
+            }
+
+            let mut next = path.clone();
+            next.weight += hop.weight;
+            next.path.relationships.push(hop.relationship);
+            next.path.nodes.push(hop.node);
+            seq += 1;
+            queue.push(Frontier { cost: cost + if weighted { hop.weight } else { 1.0 }, seq, path: next });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn node(id: Uuid) -> PathNode {
+        PathNode { id, labels: vec!["Place".to_string()], properties: json!({}) }
+    }
+
+    /// Outgoing hops over `(from, to, weight)` edges
+    fn graph(edges: &[(Uuid, Uuid, f64)]) -> impl FnMut(Uuid) -> Vec<Hop> + '_ {
+        move |from| {
+            edges.iter()
+                .filter(|(start, _, _)| *start == from)
+                .map(|&(start, end, weight)| Hop {
+                    relationship: PathRelationship {
+                        id: Uuid::new_v4(),
+                        rel_type: "ROAD".to_string(),
+                        start_node_id: start,
+                        end_node_id: end,
+                        properties: json!({"weight": weight}),
+                        collapsed: None,
+                    },
+                    node: node(end),
+                    weight,
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let [a, b, c, d] = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        // a -> d directly is one hop but heavy; a -> b -> c -> d is light
+        let edges = [(a, d, 10.0), (a, b, 1.0), (b, c, 1.0), (c, d, 1.0)];
+
+        let mut request = ShortestPathRequest::new(a, d);
+        let hops = shortest_path(&request, node(a), graph(&edges)).unwrap().unwrap();
+        assert_eq!(hops.path.relationships.len(), 1);
+        assert_eq!(hops.weight, 10.0);
+
+        request.weighted = true;
+        let cheapest = shortest_path(&request, node(a), graph(&edges)).unwrap().unwrap();
+        let ids: Vec<Uuid> = cheapest.path.nodes.iter().map(|node| node.id).collect();
+        assert_eq!(ids, [a, b, c, d]);
+        assert_eq!(cheapest.weight, 3.0);
+
+        // The light path is too long
+        request.max_depth = 2;
+        let within = shortest_path(&request, node(a), graph(&edges)).unwrap().unwrap();
+        assert_eq!(within.weight, 10.0);
+
+        // Edges are followed forwards only
+        (request.from, request.to) = (d, a);
+        assert!(shortest_path(&request, node(d), graph(&edges)).unwrap().is_none());
+
+        let negative = [(a, b, -1.0)];
+        let request = ShortestPathRequest { weighted: true, ..ShortestPathRequest::new(a, b) };
+        assert!(shortest_path(&request, node(a), graph(&negative)).is_err());
+    }
+
+    #[test]
+    fn test_traverse_order() {
+        let [a, b, c, d] = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        let edges = [(a, b, 5.0), (a, c, 1.0), (c, d, 1.0)];
+        let reached = |request: &TraversalRequest| -> Vec<Uuid> {
+            traverse(request, node(a), graph(&edges)).unwrap()
+                .iter()
+                .map(|path| path.path.nodes.last().unwrap().id)
+                .collect()
+        };
+
+        let mut request = TraversalRequest::new(a);
+        assert_eq!(reached(&request), [b, c, d]);
+
+        request.order = TraversalOrder::Weight;
+        assert_eq!(reached(&request), [c, d, b]);
+
+        request.limit = Some(2);
+        assert_eq!(reached(&request), [c, d]);
+
+        request.max_depth = 1;
+        request.limit = None;
+        assert_eq!(reached(&request), [c, b]);
+    }
+}
@@ -2,10 +2,25 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use crate::errors::TenantIdError;
+use crate::sessions::SESSION_SEPARATOR;
+use crate::traversal::DEFAULT_WEIGHT_PROPERTY;
 use uuid::Uuid;
 
+/// Longest tenant ID [`TenantId::parse`] accepts
+pub const MAX_TENANT_ID_LEN: usize = 64;
+
 /// Unique identifier for a tenant in the multi-tenant system
+///
+/// Valid tenant IDs are 1 to [`MAX_TENANT_ID_LEN`] lowercase ASCII letters,
+/// digits, `_` and `-`, starting with a letter or digit, so they are safe
+/// in query parameters, file names and URLs alike. IDs starting with `_`
+/// are reserved for tenants of TelaMentis itself, such as the lineage
+/// tenant. `new` takes any string; IDs from clients go through
+/// [`TenantId::parse`], and stores reject writes to tenants whose IDs do
+/// not [`validate`](TenantId::validate).
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TenantId(pub String);
 
@@ -15,12 +30,54 @@ impl TenantId {
         Self(id.into())
     }
 
+    /// Parse a tenant ID given by a client: surrounding whitespace is
+    /// trimmed and ASCII letters lowercased, so `" Acme "` is `acme`, and
+    /// the result must be valid and not reserved
+    pub fn parse(id: &str) -> Result<Self, TenantIdError> {
+        let tenant = Self(id.trim().to_ascii_lowercase());
+        tenant.validate()?;
+        if tenant.0.starts_with('_') {
+            return Err(TenantIdError::InvalidStart('_'));
+        }
+        Ok(tenant)
+    }
+
+    /// Check the ID follows the rules [`TenantId::parse`] normalizes to,
+    /// allowing reserved IDs. The graph of a session of a valid tenant is
+    /// valid too.
+    pub fn validate(&self) -> Result<(), TenantIdError> {
+        let id = self.0.rsplit_once(SESSION_SEPARATOR)
+            .filter(|(_, session)| Uuid::parse_str(session).is_ok())
+            .map_or(self.as_str(), |(tenant, _)| tenant);
+
+        let first = id.chars().next().ok_or(TenantIdError::Empty)?;
+        let length = id.chars().count();
+        if length > MAX_TENANT_ID_LEN {
+            return Err(TenantIdError::TooLong { length, max: MAX_TENANT_ID_LEN });
+        }
+        if let Some(invalid) = id.chars().find(|c| !matches!(c, 'a'..='z' | '0'..='9' | '_' | '-')) {
+            return Err(TenantIdError::InvalidCharacter(invalid));
+        }
+        if first == '-' {
+            return Err(TenantIdError::InvalidStart(first));
+        }
+        Ok(())
+    }
+
     /// Get the inner string value
     pub fn as_str(&self) -> &str {
         &self.0
     }
 }
 
+impl std::str::FromStr for TenantId {
+    type Err = TenantIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
 impl std::fmt::Display for TenantId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -32,6 +89,9 @@ impl std::fmt::Display for TenantId {
 pub struct Node {
     /// Optional user-defined identifier for idempotent operations
     pub id_alias: Option<String>,
+    /// Optional namespace qualifying the id_alias (e.g. the source system it came from)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias_namespace: Option<String>,
     /// The type/category of the node (e.g., "Person", "Organization")
     pub label: String,
     /// Key-value properties describing the node
@@ -43,6 +103,7 @@ impl Node {
     pub fn new(label: impl Into<String>) -> Self {
         Self {
             id_alias: None,
+            alias_namespace: None,
             label: label.into(),
             props: serde_json::Value::Object(Default::default()),
         }
@@ -54,12 +115,43 @@ impl Node {
         self
     }
 
+    /// Set the namespace the id_alias belongs to
+    pub fn with_alias_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.alias_namespace = Some(namespace.into());
+        self
+    }
+
+    /// Get the namespace-qualified alias of this node, if it has an id_alias
+    pub fn alias_key(&self) -> Option<AliasKey> {
+        self.id_alias.as_ref().map(|alias| AliasKey {
+            namespace: self.alias_namespace.clone(),
+            alias: alias.clone(),
+        })
+    }
+
     /// Set properties for this node
     pub fn with_props(mut self, props: serde_json::Value) -> Self {
         self.props = props;
         self
     }
 
+    /// Merge a JSON object into the properties: keys set to null are
+    /// removed, others replace the current value
+    pub fn merge_props(&mut self, patch: serde_json::Map<String, serde_json::Value>) {
+        if !self.props.is_object() {
+            self.props = serde_json::Value::Object(Default::default());
+        }
+        if let serde_json::Value::Object(ref mut map) = self.props {
+            for (key, value) in patch {
+                if value.is_null() {
+                    map.remove(&key);
+                } else {
+                    map.insert(key, value);
+                }
+            }
+        }
+    }
+
     /// Add a single property to this node
     pub fn with_property(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
         if let serde_json::Value::Object(ref mut map) = self.props {
@@ -69,6 +161,46 @@ impl Node {
     }
 }
 
+/// A node alias qualified by an optional namespace.
+///
+/// Aliases are unique per (tenant, namespace, alias), so "123" from a CRM and
+/// "123" from a ticketing system can coexist as different nodes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AliasKey {
+    /// Namespace of the alias; `None` is the default (unqualified) namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// The alias value within the namespace
+    pub alias: String,
+}
+
+impl AliasKey {
+    /// Create an alias in the default namespace
+    pub fn new(alias: impl Into<String>) -> Self {
+        Self {
+            namespace: None,
+            alias: alias.into(),
+        }
+    }
+
+    /// Create an alias qualified by the given namespace
+    pub fn namespaced(namespace: impl Into<String>, alias: impl Into<String>) -> Self {
+        Self {
+            namespace: Some(namespace.into()),
+            alias: alias.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for AliasKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.namespace {
+            Some(namespace) => write!(f, "{}:{}", namespace, self.alias),
+            None => write!(f, "{}", self.alias),
+        }
+    }
+}
+
 /// Represents a bitemporal edge (relationship) between two nodes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeEdge<P = serde_json::Value> {
@@ -78,14 +210,23 @@ pub struct TimeEdge<P = serde_json::Value> {
     pub to_node_id: Uuid,
     /// Type of the relationship (e.g., "WORKS_FOR", "KNOWS")
     pub kind: String,
-    /// When the relationship became true in the modeled world
+    /// When the relationship became true in the modeled world; a partial
+    /// date such as "2020-03" is read as the start of its period
+    #[serde(deserialize_with = "crate::temporal::valid_bound::start")]
     pub valid_from: DateTime<Utc>,
-    /// When the relationship ceased to be true (None = still valid)
+    /// When the relationship ceased to be true (None = still valid); a
+    /// partial date is read as the end of its period
+    #[serde(default, deserialize_with = "crate::temporal::valid_bound::end_opt")]
     pub valid_to: Option<DateTime<Utc>>,
     /// When this version of the relationship was recorded in the database
     pub transaction_start_time: DateTime<Utc>,
     /// When this version was superseded/deleted (None = current version)
     pub transaction_end_time: Option<DateTime<Utc>>,
+    /// When the store drops the edge (None = never). Unlike `valid_to`, which
+    /// keeps a relationship as history, an expired edge disappears from reads
+    /// and is later removed for good.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
     /// Properties of the relationship
     pub props: P,
 }
@@ -108,6 +249,7 @@ impl<P> TimeEdge<P> {
             valid_to: None,
             transaction_start_time: now,
             transaction_end_time: None,
+            expires_at: None,
             props,
         }
     }
@@ -130,6 +272,17 @@ impl<P> TimeEdge<P> {
         self
     }
 
+    /// Set when the store drops the edge
+    pub fn with_expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Check if this edge has expired by `now`
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
     /// Check if this edge is currently valid (valid_to is None or in the future)
     pub fn is_currently_valid(&self) -> bool {
         self.valid_to.map_or(true, |end| end > Utc::now())
@@ -153,6 +306,22 @@ impl<P> TimeEdge<P> {
     }
 }
 
+impl TimeEdge<serde_json::Value> {
+    /// Set the edge's weight, kept in its `weight` property
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        if !self.props.is_object() {
+            self.props = serde_json::Value::Object(serde_json::Map::new());
+        }
+        self.props[DEFAULT_WEIGHT_PROPERTY] = weight.into();
+        self
+    }
+
+    /// The edge's weight, if its `weight` property is a number
+    pub fn weight(&self) -> Option<f64> {
+        self.props.get(DEFAULT_WEIGHT_PROPERTY).and_then(serde_json::Value::as_f64)
+    }
+}
+
 /// Query structure for graph operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GraphQuery {
@@ -165,6 +334,12 @@ pub enum GraphQuery {
     FindNodes {
         labels: Vec<String>,
         properties: HashMap<String, serde_json::Value>,
+        /// Sort keys, most significant first
+        #[serde(default)]
+        order_by: Vec<OrderBy>,
+        /// Number of results to skip, applied after sorting
+        #[serde(default)]
+        offset: Option<u32>,
         limit: Option<u32>,
     },
     /// Structured query for finding relationships
@@ -172,16 +347,186 @@ pub enum GraphQuery {
         from_node_id: Option<Uuid>,
         to_node_id: Option<Uuid>,
         relationship_types: Vec<String>,
+        /// A date such as "2020-03" means as of its last instant
+        #[serde(default, deserialize_with = "crate::temporal::valid_bound::as_of_opt")]
         valid_at: Option<DateTime<Utc>>,
+        /// Sort keys, most significant first
+        #[serde(default)]
+        order_by: Vec<OrderBy>,
+        /// Number of results to skip, applied after sorting
+        #[serde(default)]
+        offset: Option<u32>,
         limit: Option<u32>,
+        /// Return one aggregate relationship per group of matching edges
+        /// instead of every version; groups sort by their latest edge, and
+        /// offset and limit count groups
+        #[serde(default)]
+        collapse: Option<Collapse>,
     },
     /// Temporal query to get graph state as of a specific time
     AsOfQuery {
         base_query: Box<GraphQuery>,
+        #[serde(deserialize_with = "crate::temporal::valid_bound::as_of")]
         as_of_time: DateTime<Utc>,
     },
 }
 
+impl GraphQuery {
+    /// The same query with its offset and limit replaced; raw queries are
+    /// returned unchanged
+    pub fn with_page(self, offset: Option<u32>, limit: Option<u32>) -> GraphQuery {
+        match self {
+            GraphQuery::FindNodes { labels, properties, order_by, .. } => {
+                GraphQuery::FindNodes { labels, properties, order_by, offset, limit }
+            }
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, collapse, .. } => {
+                GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse }
+            }
+            GraphQuery::AsOfQuery { base_query, as_of_time } => GraphQuery::AsOfQuery {
+                base_query: Box::new(base_query.with_page(offset, limit)),
+                as_of_time,
+            },
+            raw @ GraphQuery::Raw { .. } => raw,
+        }
+    }
+}
+
+/// How a relationship query groups the edges it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Collapse {
+    /// One relationship per start node, end node and kind
+    ByKind,
+    /// One relationship per start and end node, whatever their kinds
+    ByPair,
+}
+
+/// The edges a collapsed relationship stands for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EdgeAggregate {
+    /// Number of edges collapsed
+    pub count: u64,
+    /// Earliest `valid_from` of the edges
+    pub valid_from: DateTime<Utc>,
+    /// Latest `valid_to` of the edges; `None` if one is open-ended
+    pub valid_to: Option<DateTime<Utc>>,
+    /// Kinds of the edges, sorted
+    pub kinds: Vec<String>,
+}
+
+/// How much of a query's result is returned.
+///
+/// `Count` and `Exists` let stores answer without reading the matching
+/// nodes and relationships; both ignore ordering, offset and limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMode {
+    /// The matching paths
+    #[default]
+    Full,
+    /// The number of matches
+    Count,
+    /// Whether there is any match
+    Exists,
+}
+
+/// Field that query results are sorted by
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    /// A property of the node or relationship; missing values sort last
+    Property(String),
+    /// Creation time of a node, or transaction start time of a relationship
+    CreatedAt,
+    /// Node label, or relationship type
+    Label,
+}
+
+/// Direction of a sort key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Sort key of a structured query.
+///
+/// Results with equal sort keys are ordered by system ID, so that pages
+/// fetched with `offset` and `limit` are stable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OrderBy {
+    /// Field to sort by
+    pub field: SortField,
+    /// Sort direction, ascending by default
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+impl OrderBy {
+    /// Sort ascending by a field
+    pub fn asc(field: SortField) -> Self {
+        Self { field, direction: SortDirection::Asc }
+    }
+
+    /// Sort descending by a field
+    pub fn desc(field: SortField) -> Self {
+        Self { field, direction: SortDirection::Desc }
+    }
+}
+
+/// Value of a sort field for one query result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey<'a> {
+    Property(Option<&'a serde_json::Value>),
+    Time(DateTime<Utc>),
+    Text(&'a str),
+    /// Position in creation order, for results without a creation time
+    Position(usize),
+}
+
+impl SortKey<'_> {
+    /// Compare two keys of the same field
+    pub fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SortKey::Property(a), SortKey::Property(b)) => compare_property_values(*a, *b),
+            (SortKey::Time(a), SortKey::Time(b)) => a.cmp(b),
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            (SortKey::Position(a), SortKey::Position(b)) => a.cmp(b),
+            // Keys of one field always have the same kind
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// Order property values like Cypher's ORDER BY: booleans, then numbers, then
+/// strings, with missing and null values last
+pub fn compare_property_values(a: Option<&serde_json::Value>, b: Option<&serde_json::Value>) -> Ordering {
+    use serde_json::Value;
+
+    fn rank(value: Option<&Value>) -> u8 {
+        match value {
+            Some(Value::Bool(_)) => 0,
+            Some(Value::Number(_)) => 1,
+            Some(Value::String(_)) => 2,
+            Some(Value::Array(_)) => 3,
+            Some(Value::Object(_)) => 4,
+            Some(Value::Null) | None => 5,
+        }
+    }
+
+    match (a, b) {
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(Value::Number(a)), Some(Value::Number(b))) => {
+            a.as_f64().unwrap_or_default().total_cmp(&b.as_f64().unwrap_or_default())
+        }
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(a), Some(b)) if rank(Some(a)) == rank(Some(b)) => a.to_string().cmp(&b.to_string()),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
 /// Represents a path in the graph (sequence of nodes and relationships)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Path {
@@ -215,6 +560,11 @@ pub struct PathRelationship {
     pub end_node_id: Uuid,
     /// Relationship properties (including temporal info)
     pub properties: serde_json::Value,
+    /// For a collapsed relationship, the edges it stands for. Its ID and
+    /// kind are those of the latest edge, and its properties those of all
+    /// edges merged, later edges overriding earlier ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collapsed: Option<EdgeAggregate>,
 }
 
 /// Mutation operations for graph data
@@ -228,4 +578,260 @@ pub enum GraphMutation {
     DeleteNode { id: Uuid },
     /// Delete a relationship (logical delete)
     DeleteEdge { id: Uuid },
-}
\ No newline at end of file
+}
+
+/// Reference to an existing node, either by system ID or by alias
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRef {
+    /// Node system ID
+    Id(Uuid),
+    /// Namespace-qualified alias of the node
+    Alias(AliasKey),
+}
+
+/// Direction of an edge relative to the node it is attached to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeDirection {
+    /// The upserted node is the source of the edge
+    #[default]
+    Outgoing,
+    /// The upserted node is the target of the edge
+    Incoming,
+}
+
+/// An edge to create together with a node upsert, pointing at an existing node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeSpec {
+    /// Type of the relationship (e.g., "WORKS_FOR")
+    pub kind: String,
+    /// The node on the other end of the edge
+    pub target: NodeRef,
+    /// Whether the edge points away from or towards the upserted node
+    #[serde(default)]
+    pub direction: EdgeDirection,
+    /// Start of validity; defaults to now
+    #[serde(default, deserialize_with = "crate::temporal::valid_bound::start_opt")]
+    pub valid_from: Option<DateTime<Utc>>,
+    /// End of validity (None = open-ended)
+    #[serde(default, deserialize_with = "crate::temporal::valid_bound::end_opt")]
+    pub valid_to: Option<DateTime<Utc>>,
+    /// When the store drops the edge (None = never)
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Edge properties
+    #[serde(default = "empty_props")]
+    pub props: serde_json::Value,
+}
+
+fn empty_props() -> serde_json::Value {
+    serde_json::Value::Object(Default::default())
+}
+
+impl EdgeSpec {
+    /// Create an outgoing edge spec to the given target
+    pub fn new(kind: impl Into<String>, target: NodeRef) -> Self {
+        Self {
+            kind: kind.into(),
+            target,
+            direction: EdgeDirection::Outgoing,
+            valid_from: None,
+            valid_to: None,
+            expires_at: None,
+            props: empty_props(),
+        }
+    }
+
+    /// Make the edge point towards the upserted node
+    pub fn incoming(mut self) -> Self {
+        self.direction = EdgeDirection::Incoming;
+        self
+    }
+
+    /// Set the valid_from timestamp
+    pub fn with_valid_from(mut self, valid_from: DateTime<Utc>) -> Self {
+        self.valid_from = Some(valid_from);
+        self
+    }
+
+    /// Set the valid_to timestamp
+    pub fn with_valid_to(mut self, valid_to: DateTime<Utc>) -> Self {
+        self.valid_to = Some(valid_to);
+        self
+    }
+
+    /// Set when the store drops the edge
+    pub fn with_expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Set properties for this edge
+    pub fn with_props(mut self, props: serde_json::Value) -> Self {
+        self.props = props;
+        self
+    }
+
+    /// Build the TimeEdge once both endpoints are known
+    pub fn to_time_edge(&self, node_id: Uuid, target_id: Uuid) -> TimeEdge {
+        let (from, to) = match self.direction {
+            EdgeDirection::Outgoing => (node_id, target_id),
+            EdgeDirection::Incoming => (target_id, node_id),
+        };
+
+        let mut edge = TimeEdge::new(
+            from,
+            to,
+            self.kind.clone(),
+            self.valid_from.unwrap_or_else(Utc::now),
+            self.props.clone(),
+        );
+        edge.valid_to = self.valid_to;
+        edge.expires_at = self.expires_at;
+        edge
+    }
+}
+
+/// An edge whose endpoints are given by system ID or by alias, for clients
+/// that address nodes by their aliases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeByRef {
+    /// Source node
+    pub from: NodeRef,
+    /// Target node
+    pub to: NodeRef,
+    /// Type of the relationship (e.g., "WORKS_FOR")
+    pub kind: String,
+    /// Start of validity; defaults to now
+    #[serde(default, deserialize_with = "crate::temporal::valid_bound::start_opt")]
+    pub valid_from: Option<DateTime<Utc>>,
+    /// End of validity (None = open-ended)
+    #[serde(default, deserialize_with = "crate::temporal::valid_bound::end_opt")]
+    pub valid_to: Option<DateTime<Utc>>,
+    /// When the store drops the edge (None = never)
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Edge properties
+    #[serde(default = "empty_props")]
+    pub props: serde_json::Value,
+}
+
+impl EdgeByRef {
+    /// Create an edge between two referenced nodes
+    pub fn new(from: NodeRef, to: NodeRef, kind: impl Into<String>) -> Self {
+        Self {
+            from,
+            to,
+            kind: kind.into(),
+            valid_from: None,
+            valid_to: None,
+            expires_at: None,
+            props: empty_props(),
+        }
+    }
+
+    /// Build the TimeEdge once both endpoints are resolved
+    pub fn to_time_edge(&self, from: Uuid, to: Uuid) -> TimeEdge {
+        let mut edge = TimeEdge::new(
+            from,
+            to,
+            self.kind.clone(),
+            self.valid_from.unwrap_or_else(Utc::now),
+            self.props.clone(),
+        );
+        edge.valid_to = self.valid_to;
+        edge.expires_at = self.expires_at;
+        edge
+    }
+}
+
+/// Result of upserting a node together with its edges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeWithEdges {
+    /// System ID of the upserted node
+    pub node_id: Uuid,
+    /// System IDs of the created edges, in the order of the submitted specs
+    pub edge_ids: Vec<Uuid>,
+}
+
+/// A node together with its system ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeRecord {
+    /// System ID of the node
+    pub id: Uuid,
+    /// The node itself
+    pub node: Node,
+}
+
+/// An edge together with its system ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeRecord {
+    /// System ID of the edge
+    pub id: Uuid,
+    /// The edge itself
+    pub edge: TimeEdge,
+}
+
+/// A consistent view of a tenant's graph, used for exports.
+///
+/// Contains exactly what was known at `snapshot_at` (transaction time): writes
+/// committed while the snapshot is being read are not included, so the node
+/// and edge sets always agree with each other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    /// Transaction time the snapshot reflects
+    pub snapshot_at: DateTime<Utc>,
+    /// Valid-time filter applied to edges, if any
+    pub valid_at: Option<DateTime<Utc>>,
+    /// Nodes known at `snapshot_at`
+    pub nodes: Vec<NodeRecord>,
+    /// Edges known at `snapshot_at`
+    pub edges: Vec<EdgeRecord>,
+}
+
+/// Size and activity of a tenant's graph, as counted by the store
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphSummary {
+    /// Number of nodes
+    pub node_count: u64,
+    /// Number of stored edge versions
+    pub edge_count: u64,
+    /// Node counts by label
+    pub nodes_by_label: HashMap<String, u64>,
+    /// Edge counts by kind
+    pub edges_by_kind: HashMap<String, u64>,
+    /// Time of the tenant's most recent write, if any
+    pub last_write_at: Option<DateTime<Utc>>,
+    /// Rough estimate of the storage used by the tenant's graph
+    pub storage_bytes_estimate: u64,
+}
+
+/// Labels, relationship kinds and property keys in use in a tenant's graph
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphCatalog {
+    /// Node labels, sorted by name
+    pub labels: Vec<CatalogEntry>,
+    /// Relationship kinds, sorted by name
+    pub kinds: Vec<CatalogEntry>,
+}
+
+/// A node label or relationship kind in a tenant's graph
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    /// Label or kind
+    pub name: String,
+    /// Number of nodes with the label, or edges of the kind
+    pub count: u64,
+    /// Number of those nodes or edges holding each property key
+    pub property_keys: HashMap<String, u64>,
+}
+
+/// An embedding returned by a similarity search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorMatch {
+    /// ID the embedding was stored under, usually a node ID
+    pub id: Uuid,
+    /// Distance from the query under the index's metric; smaller is closer
+    pub distance: f32,
+}
@@ -0,0 +1,329 @@
+//! Default valid times for ingested edges
+//!
+//! Edges ingested without a `valid_from` would otherwise start at the time of
+//! ingestion, which is wrong for historical documents. A [`ValidTimePolicy`]
+//! says where the default comes from instead (the message timestamp, a date
+//! property of the source document, or an unknown start), whether edges
+//! without a `valid_to` stay open-ended, and which timezone applies to
+//! timestamps without an offset. Policies are configured per tenant with
+//! [`ValidTimePolicies`]; CSV ingestion applies them to each row and
+//! [`ValidTimeConnector`] applies them to extracted relations.
+
+use crate::errors::LlmError;
+use crate::temporal::{IntervalBound, PartialDate, TimePrecision};
+use crate::traits::{CompletionRequest, CompletionResponse, ExtractionContext, ExtractionEnvelope, LlmConnector};
+use crate::types::TenantId;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Where a default `valid_from` comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum ValidFromSource {
+    /// The time of ingestion
+    Now,
+    /// When the source message was sent or the document received
+    MessageTimestamp,
+    /// A date or timestamp in a property of the source document
+    DocumentProperty {
+        /// Name of the property, e.g. "published_at"
+        property: String,
+    },
+    /// The start is not known: the edge is valid since [`unknown_valid_from`]
+    Unknown,
+}
+
+/// `valid_from` of edges whose start is not known
+pub fn unknown_valid_from() -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(1, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+/// The current time as a defaulted `valid_from`, which clients are told about
+fn defaulted_to_now() -> DateTime<Utc> {
+    crate::warnings::report("valid_from defaulted to the current time for edges without one");
+    Utc::now()
+}
+
+/// Where ingested content came from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceInfo {
+    /// When the message was sent or the document received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Properties of the source document, e.g. its publication date
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub properties: serde_json::Map<String, serde_json::Value>,
+}
+
+impl SourceInfo {
+    /// Source with a message timestamp
+    pub fn at(timestamp: DateTime<Utc>) -> Self {
+        Self {
+            timestamp: Some(timestamp),
+            ..Default::default()
+        }
+    }
+
+    /// Add a document property
+    pub fn with_property(mut self, name: impl Into<String>, value: serde_json::Value) -> Self {
+        self.properties.insert(name.into(), value);
+        self
+    }
+}
+
+/// How missing valid times of ingested edges are filled in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidTimePolicy {
+    /// Sources of `valid_from`, tried in order; the time of ingestion is used
+    /// when none of them yields a time
+    pub valid_from: Vec<ValidFromSource>,
+    /// Validity given to edges without a `valid_to`, in seconds; `None`
+    /// leaves them open-ended
+    pub default_validity_secs: Option<u64>,
+    /// UTC offset of timestamps and dates written without one, e.g. "+02:00"
+    #[serde(with = "utc_offset")]
+    pub timezone: FixedOffset,
+}
+
+impl Default for ValidTimePolicy {
+    fn default() -> Self {
+        Self {
+            valid_from: vec![ValidFromSource::Now],
+            default_validity_secs: None,
+            timezone: FixedOffset::east_opt(0).unwrap(),
+        }
+    }
+}
+
+impl ValidTimePolicy {
+    /// Create a policy trying the given sources in order
+    pub fn new(valid_from: Vec<ValidFromSource>) -> Self {
+        Self {
+            valid_from,
+            ..Default::default()
+        }
+    }
+
+    /// Give edges without a `valid_to` a bounded validity
+    pub fn with_default_validity_secs(mut self, secs: u64) -> Self {
+        self.default_validity_secs = Some(secs);
+        self
+    }
+
+    /// Set the timezone of timestamps without an offset
+    pub fn with_timezone(mut self, timezone: FixedOffset) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Interpret a timestamp without an offset in the policy's timezone
+    pub fn localize(&self, naive: NaiveDateTime) -> DateTime<Utc> {
+        match self.timezone.from_local_datetime(&naive).single() {
+            Some(local) => local.with_timezone(&Utc),
+            None => naive.and_utc(),
+        }
+    }
+
+    /// Parse an RFC 3339 timestamp, a date-time without an offset or a date.
+    /// Values without an offset are in the policy's timezone and dates start
+    /// at midnight; a year or month such as "2020-03" starts with its first
+    /// day.
+    pub fn parse_time(&self, value: &str) -> Option<DateTime<Utc>> {
+        self.parse_bound(value, IntervalBound::Start)
+    }
+
+    /// Parse like [`parse_time`](Self::parse_time), with a year, month or
+    /// day standing for the instant `bound` gives it: its start, the end of
+    /// the period for a `valid_to`, or its last instant for an "as of" time
+    pub fn parse_bound(&self, value: &str, bound: IntervalBound) -> Option<DateTime<Utc>> {
+        let value = value.trim();
+        if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+            return Some(time.with_timezone(&Utc));
+        }
+
+        let date = value.parse::<PartialDate>().ok()
+            .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| PartialDate::from_date(date, TimePrecision::Day)));
+        if let Some(date) = date {
+            return Some(self.localize(bound.of(&date).naive_utc()));
+        }
+        ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .map(|naive| self.localize(naive))
+    }
+
+    /// Default `valid_from` for an edge ingested from `source`
+    pub fn default_valid_from(&self, source: &SourceInfo) -> DateTime<Utc> {
+        self.valid_from
+            .iter()
+            .find_map(|from| match from {
+                ValidFromSource::Now => Some(defaulted_to_now()),
+                ValidFromSource::MessageTimestamp => source.timestamp,
+                ValidFromSource::DocumentProperty { property } => match source.properties.get(property)? {
+                    serde_json::Value::String(value) => self.parse_time(value),
+                    // Numbers are Unix timestamps in seconds
+                    serde_json::Value::Number(secs) => DateTime::from_timestamp(secs.as_i64()?, 0),
+                    _ => None,
+                },
+                ValidFromSource::Unknown => Some(unknown_valid_from()),
+            })
+            .unwrap_or_else(defaulted_to_now)
+    }
+
+    /// Default `valid_to` for an edge valid from `valid_from`
+    pub fn default_valid_to(&self, valid_from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let secs = i64::try_from(self.default_validity_secs?).ok()?;
+        valid_from.checked_add_signed(Duration::seconds(secs))
+    }
+
+    /// Fill in the missing valid times of extracted relations
+    pub fn apply_to_envelope(&self, envelope: &mut ExtractionEnvelope, source: &SourceInfo) {
+        for relation in &mut envelope.relations {
+            let valid_from = *relation.valid_from.get_or_insert_with(|| self.default_valid_from(source));
+            if relation.valid_to.is_none() {
+                relation.valid_to = self.default_valid_to(valid_from);
+            }
+        }
+    }
+}
+
+/// Valid-time policies by tenant
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidTimePolicies {
+    /// Policy of tenants without their own
+    pub default: ValidTimePolicy,
+    /// Policies by tenant ID
+    pub tenants: HashMap<String, ValidTimePolicy>,
+}
+
+impl ValidTimePolicies {
+    /// Policy of a tenant
+    pub fn for_tenant(&self, tenant: &TenantId) -> &ValidTimePolicy {
+        self.tenants.get(tenant.as_str()).unwrap_or(&self.default)
+    }
+}
+
+/// Connector wrapper that fills in the valid times of extracted relations
+/// from the tenant's policy and `ExtractionContext::source`
+pub struct ValidTimeConnector {
+    inner: Arc<dyn LlmConnector>,
+    policies: ValidTimePolicies,
+}
+
+impl ValidTimeConnector {
+    /// Wrap a connector with the given policies
+    pub fn new(inner: Arc<dyn LlmConnector>, policies: ValidTimePolicies) -> Self {
+        Self { inner, policies }
+    }
+}
+
+#[async_trait]
+impl LlmConnector for ValidTimeConnector {
+    async fn extract(&self, tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        let source = context.source.clone().unwrap_or_default();
+        let mut envelope = self.inner.extract(tenant, context).await?;
+
+        debug!("Applying valid-time policy of tenant {} to {} relations", tenant, envelope.relations.len());
+        self.policies.for_tenant(tenant).apply_to_envelope(&mut envelope, &source);
+        Ok(envelope)
+    }
+
+    async fn complete(&self, tenant: &TenantId, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        self.inner.complete(tenant, request).await
+    }
+}
+
+/// Parse a UTC offset such as "+02:00", "-0530", "Z" or "UTC"
+pub fn parse_utc_offset(value: &str) -> Result<FixedOffset, String> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("utc") || value.eq_ignore_ascii_case("z") {
+        return Ok(FixedOffset::east_opt(0).unwrap());
+    }
+    value.parse().map_err(|e| format!("Invalid UTC offset '{}': {}", value, e))
+}
+
+/// Serde for `FixedOffset` as an offset string
+mod utc_offset {
+    use chrono::FixedOffset;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(offset: &FixedOffset, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(offset)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FixedOffset, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        super::parse_utc_offset(&value).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::ExtractionRelation;
+
+    fn relation(valid_from: Option<DateTime<Utc>>) -> ExtractionRelation {
+        ExtractionRelation {
+            from_id_alias: "alice".to_string(),
+            to_id_alias: "acme".to_string(),
+            type_label: "WORKS_FOR".to_string(),
+            props: serde_json::json!({}),
+            valid_from,
+            valid_to: None,
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn test_default_valid_from() {
+        let policy: ValidTimePolicy = serde_json::from_value(serde_json::json!({
+            "valid_from": [
+                {"source": "document_property", "property": "published"},
+                {"source": "message_timestamp"},
+                {"source": "unknown"},
+            ],
+            "timezone": "+02:00",
+        })).unwrap();
+
+        let sent: DateTime<Utc> = "2020-05-01T08:00:00Z".parse().unwrap();
+        let document = SourceInfo::at(sent).with_property("published", serde_json::json!("2019-03-01"));
+        assert_eq!(policy.default_valid_from(&document), "2019-02-28T22:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(policy.default_valid_from(&SourceInfo::at(sent)), sent);
+        assert_eq!(policy.default_valid_from(&SourceInfo::default()), unknown_valid_from());
+
+        // Partial dates are read in the policy's timezone
+        assert_eq!(policy.parse_time("2019-03"), Some("2019-02-28T22:00:00Z".parse().unwrap()));
+        assert_eq!(policy.parse_bound("2019-03", IntervalBound::End), Some("2019-03-31T22:00:00Z".parse().unwrap()));
+
+        // Nothing matches: fall back to the time of ingestion
+        let before = Utc::now();
+        let policy = ValidTimePolicy::new(vec![ValidFromSource::MessageTimestamp]);
+        assert!(policy.default_valid_from(&SourceInfo::default()) >= before);
+    }
+
+    #[test]
+    fn test_apply_to_envelope() {
+        let explicit: DateTime<Utc> = "2021-01-01T00:00:00Z".parse().unwrap();
+        let sent: DateTime<Utc> = "2022-01-01T00:00:00Z".parse().unwrap();
+        let mut envelope = ExtractionEnvelope {
+            nodes: vec![],
+            relations: vec![relation(Some(explicit)), relation(None)],
+            metadata: None,
+        };
+
+        ValidTimePolicy::new(vec![ValidFromSource::MessageTimestamp])
+            .with_default_validity_secs(86_400)
+            .apply_to_envelope(&mut envelope, &SourceInfo::at(sent));
+
+        assert_eq!(envelope.relations[0].valid_from, Some(explicit));
+        assert_eq!(envelope.relations[0].valid_to, Some(explicit + Duration::days(1)));
+        assert_eq!(envelope.relations[1].valid_from, Some(sent));
+        assert_eq!(envelope.relations[1].valid_to, Some(sent + Duration::days(1)));
+    }
+}
@@ -0,0 +1,514 @@
+//! Disk-persisted vector index, partitioned by tenant
+//!
+//! `DiskVectorIndex` keeps one [`HnswGraph`] per tenant under
+//! `<dir>/<tenant>/`. `graph.json` holds the graph as of the last compaction
+//! and `wal.jsonl` logs every upsert and removal since. A write is appended to
+//! the log before it is applied, so a tenant is recovered by loading its graph
+//! and replaying the log. Compaction drops removed vectors, writes a fresh
+//! graph file and truncates the log; a background task compacts tenants once
+//! their log or tombstone count grows too large.
+
+use crate::errors::VectorError;
+use crate::hnsw::{HnswConfig, HnswGraph};
+use crate::traits::VectorIndex;
+use crate::types::{TenantId, VectorMatch};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+const GRAPH_FILE: &str = "graph.json";
+const LOG_FILE: &str = "wal.jsonl";
+
+/// Configuration for the disk-persisted vector index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskVectorIndexConfig {
+    /// Directory holding one subdirectory per tenant
+    pub dir: PathBuf,
+    /// Parameters of newly created tenant graphs
+    pub hnsw: HnswConfig,
+    /// Whether each write is synced to disk before it is acknowledged
+    pub sync_writes: bool,
+    /// Logged writes since the last compaction that make a tenant due for compaction
+    pub compact_after_writes: usize,
+    /// Share of removed vectors that makes a tenant due for compaction
+    pub compact_tombstone_ratio: f64,
+    /// How often the background task looks for tenants to compact, in milliseconds
+    pub compaction_interval_ms: u64,
+}
+
+impl Default for DiskVectorIndexConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("data/vectors"),
+            hnsw: HnswConfig::default(),
+            sync_writes: true,
+            compact_after_writes: 10_000,
+            compact_tombstone_ratio: 0.2,
+            compaction_interval_ms: 60_000,
+        }
+    }
+}
+
+/// A write recorded in a tenant's log
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogEntry {
+    Upsert { id: Uuid, vector: Vec<f32> },
+    Remove { id: Uuid },
+}
+
+/// One tenant's graph and the log of writes since it was last saved
+struct Partition {
+    dir: PathBuf,
+    graph: HnswGraph,
+    log: File,
+    logged: usize,
+}
+
+impl Partition {
+    /// Load a tenant's graph and replay its log, creating the directory if missing
+    fn open(dir: PathBuf, config: &HnswConfig) -> Result<Self, VectorError> {
+        fs::create_dir_all(&dir).map_err(|e| storage_error(&dir, e))?;
+
+        let graph_path = dir.join(GRAPH_FILE);
+        let mut graph = match File::open(&graph_path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))
+                .map_err(|e| VectorError::Corrupt(format!("{}: {}", graph_path.display(), e)))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => HnswGraph::new(config.clone()),
+            Err(e) => return Err(storage_error(&graph_path, e)),
+        };
+
+        let log_path = dir.join(LOG_FILE);
+        let logged = replay_log(&log_path, &mut graph)?;
+        let log = OpenOptions::new().create(true).append(true).open(&log_path)
+            .map_err(|e| storage_error(&log_path, e))?;
+
+        debug!("Opened vector partition {} with {} vectors ({} logged writes)", dir.display(), graph.len(), logged);
+        Ok(Self { dir, graph, log, logged })
+    }
+
+    fn append(&mut self, entry: &LogEntry, sync: bool) -> Result<(), VectorError> {
+        let mut line = serde_json::to_vec(entry).map_err(|e| VectorError::Storage(e.to_string()))?;
+        line.push(b'\n');
+
+        let log_path = self.dir.join(LOG_FILE);
+        self.log.write_all(&line).map_err(|e| storage_error(&log_path, e))?;
+        if sync {
+            self.log.sync_data().map_err(|e| storage_error(&log_path, e))?;
+        }
+        self.logged += 1;
+        Ok(())
+    }
+
+    fn needs_compaction(&self, config: &DiskVectorIndexConfig) -> bool {
+        let tombstones = self.graph.tombstones();
+        let total = self.graph.len() + tombstones;
+        self.logged >= config.compact_after_writes.max(1)
+            || (tombstones > 0 && tombstones as f64 >= total as f64 * config.compact_tombstone_ratio)
+    }
+
+    /// Save a rebuilt graph without removed vectors and truncate the log
+    fn compact(&mut self) -> Result<(), VectorError> {
+        let graph = self.graph.rebuild();
+
+        let tmp_path = self.dir.join(format!("{}.tmp", GRAPH_FILE));
+        let file = File::create(&tmp_path).map_err(|e| storage_error(&tmp_path, e))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &graph).map_err(|e| VectorError::Storage(e.to_string()))?;
+        let file = writer.into_inner().map_err(|e| storage_error(&tmp_path, e.into_error()))?;
+        file.sync_all().map_err(|e| storage_error(&tmp_path, e))?;
+        fs::rename(&tmp_path, self.dir.join(GRAPH_FILE)).map_err(|e| storage_error(&tmp_path, e))?;
+
+        // A crash before this point replays writes the new graph already has,
+        // which leaves it unchanged
+        let log_path = self.dir.join(LOG_FILE);
+        self.log.set_len(0).map_err(|e| storage_error(&log_path, e))?;
+        self.log.sync_all().map_err(|e| storage_error(&log_path, e))?;
+
+        debug!(
+            "Compacted vector partition {}: {} vectors, {} removed dropped",
+            self.dir.display(), graph.len(), self.graph.tombstones()
+        );
+        self.graph = graph;
+        self.logged = 0;
+        Ok(())
+    }
+}
+
+struct Shared {
+    config: DiskVectorIndexConfig,
+    partitions: Mutex<HashMap<TenantId, Arc<RwLock<Partition>>>>,
+}
+
+impl Shared {
+    /// A tenant's partition, loading it from disk on first use. Returns `None`
+    /// if the tenant has nothing stored and `create` is false.
+    async fn partition(&self, tenant: &TenantId, create: bool) -> Result<Option<Arc<RwLock<Partition>>>, VectorError> {
+        let mut partitions = self.partitions.lock().await;
+        if let Some(partition) = partitions.get(tenant) {
+            return Ok(Some(partition.clone()));
+        }
+
+        let dir = partition_dir(&self.config.dir, tenant);
+        if !create && !dir.exists() {
+            return Ok(None);
+        }
+
+        let partition = Arc::new(RwLock::new(Partition::open(dir, &self.config.hnsw)?));
+        partitions.insert(tenant.clone(), partition.clone());
+        Ok(Some(partition))
+    }
+
+    /// Compact every loaded tenant that is due, returning how many were compacted
+    async fn compact_due(&self) -> usize {
+        let partitions: Vec<_> = self.partitions.lock().await
+            .iter()
+            .map(|(tenant, partition)| (tenant.clone(), partition.clone()))
+            .collect();
+
+        let mut compacted = 0;
+        for (tenant, partition) in partitions {
+            let mut partition = partition.write().await;
+            if !partition.needs_compaction(&self.config) {
+                continue;
+            }
+            match partition.compact() {
+                Ok(()) => compacted += 1,
+                Err(e) => warn!("Failed to compact vector index for tenant {}: {}", tenant, e),
+            }
+        }
+        compacted
+    }
+}
+
+/// `VectorIndex` backed by per-tenant HNSW graphs persisted to disk.
+///
+/// Must be created inside a Tokio runtime. Call [`DiskVectorIndex::shutdown`]
+/// before dropping it to stop the background compactor cleanly.
+pub struct DiskVectorIndex {
+    shared: Arc<Shared>,
+    shutdown: Arc<Notify>,
+    compactor: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl DiskVectorIndex {
+    /// Open the index rooted at `config.dir`. Tenants are loaded on first use.
+    pub fn open(config: DiskVectorIndexConfig) -> Result<Self, VectorError> {
+        fs::create_dir_all(&config.dir).map_err(|e| storage_error(&config.dir, e))?;
+        info!("Opened vector index at {}", config.dir.display());
+
+        let shared = Arc::new(Shared {
+            config,
+            partitions: Mutex::new(HashMap::new()),
+        });
+        let shutdown = Arc::new(Notify::new());
+        let compactor = tokio::spawn(Self::run_compactor(Arc::downgrade(&shared), shutdown.clone()));
+
+        Ok(Self {
+            shared,
+            shutdown,
+            compactor: std::sync::Mutex::new(Some(compactor)),
+        })
+    }
+
+    async fn run_compactor(shared: Weak<Shared>, shutdown: Arc<Notify>) {
+        let Some(period) = shared.upgrade().map(|s| Duration::from_millis(s.config.compaction_interval_ms.max(1))) else {
+            return;
+        };
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match shared.upgrade() {
+                        Some(shared) => { shared.compact_due().await; }
+                        None => break,
+                    }
+                }
+                _ = shutdown.notified() => break,
+            }
+        }
+    }
+
+    /// Compact a tenant now, whether or not it is due. Returns false if the
+    /// tenant has nothing stored.
+    pub async fn compact(&self, tenant: &TenantId) -> Result<bool, VectorError> {
+        match self.shared.partition(tenant, false).await? {
+            Some(partition) => {
+                partition.write().await.compact()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Compact every loaded tenant that is due, returning how many were compacted
+    pub async fn compact_due(&self) -> usize {
+        self.shared.compact_due().await
+    }
+
+    /// Stop the background compactor
+    pub async fn shutdown(&self) {
+        self.shutdown.notify_one();
+
+        let compactor = self.compactor.lock().unwrap().take();
+        if let Some(compactor) = compactor {
+            let _ = compactor.await;
+        }
+    }
+}
+
+impl Drop for DiskVectorIndex {
+    fn drop(&mut self) {
+        if let Some(compactor) = self.compactor.lock().unwrap().take() {
+            compactor.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl VectorIndex for DiskVectorIndex {
+    async fn upsert_vector(&self, tenant: &TenantId, id: Uuid, vector: Vec<f32>) -> Result<(), VectorError> {
+        let Some(partition) = self.shared.partition(tenant, true).await? else {
+            return Err(VectorError::Storage(format!("No partition for tenant {}", tenant)));
+        };
+        let mut partition = partition.write().await;
+
+        // Reject bad vectors before they reach the log
+        partition.graph.validate(&vector)?;
+        let entry = LogEntry::Upsert { id, vector };
+        partition.append(&entry, self.shared.config.sync_writes)?;
+        apply(&mut partition.graph, entry)?;
+        Ok(())
+    }
+
+    async fn remove_vector(&self, tenant: &TenantId, id: Uuid) -> Result<bool, VectorError> {
+        let Some(partition) = self.shared.partition(tenant, false).await? else {
+            return Ok(false);
+        };
+        let mut partition = partition.write().await;
+
+        if !partition.graph.contains(&id) {
+            return Ok(false);
+        }
+        let entry = LogEntry::Remove { id };
+        partition.append(&entry, self.shared.config.sync_writes)?;
+        apply(&mut partition.graph, entry)
+    }
+
+    async fn search(&self, tenant: &TenantId, query: &[f32], k: usize) -> Result<Vec<VectorMatch>, VectorError> {
+        match self.shared.partition(tenant, false).await? {
+            Some(partition) => partition.read().await.graph.search(query, k),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn count(&self, tenant: &TenantId) -> Result<usize, VectorError> {
+        match self.shared.partition(tenant, false).await? {
+            Some(partition) => Ok(partition.read().await.graph.len()),
+            None => Ok(0),
+        }
+    }
+
+    async fn drop_tenant(&self, tenant: &TenantId) -> Result<bool, VectorError> {
+        let mut partitions = self.shared.partitions.lock().await;
+        // Wait for writes in flight before removing the files under them
+        let _partition = match partitions.remove(tenant) {
+            Some(partition) => Some(partition.write_owned().await),
+            None => None,
+        };
+
+        let dir = partition_dir(&self.shared.config.dir, tenant);
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => {
+                info!("Dropped vector index for tenant {}", tenant);
+                Ok(true)
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(storage_error(&dir, e)),
+        }
+    }
+}
+
+/// Apply the entries in a tenant's log to its graph, returning how many there were.
+/// An incomplete last entry, left by a crash mid-write, is discarded.
+fn replay_log(path: &Path, graph: &mut HnswGraph) -> Result<usize, VectorError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(storage_error(path, e)),
+    };
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    let mut offset = 0u64;
+    let mut count = 0;
+
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line).map_err(|e| storage_error(path, e))?;
+        if read == 0 {
+            break;
+        }
+
+        let entry = match line.strip_suffix('\n') {
+            Some(json) => serde_json::from_str::<LogEntry>(json).ok(),
+            None => None,
+        };
+        let Some(entry) = entry else {
+            if reader.read_line(&mut String::new()).map_err(|e| storage_error(path, e))? > 0 {
+                return Err(VectorError::Corrupt(format!("{}: invalid entry at byte {}", path.display(), offset)));
+            }
+            warn!("Discarding incomplete entry at the end of {}", path.display());
+            OpenOptions::new().write(true).open(path)
+                .and_then(|file| file.set_len(offset))
+                .map_err(|e| storage_error(path, e))?;
+            break;
+        };
+
+        apply(graph, entry)
+            .map_err(|e| VectorError::Corrupt(format!("{}: entry at byte {}: {}", path.display(), offset, e)))?;
+        offset += read as u64;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Apply a logged write to a graph, returning whether it changed anything
+fn apply(graph: &mut HnswGraph, entry: LogEntry) -> Result<bool, VectorError> {
+    match entry {
+        LogEntry::Upsert { id, vector } => graph.insert(id, vector).map(|()| true),
+        LogEntry::Remove { id } => Ok(graph.remove(&id)),
+    }
+}
+
+/// Directory of a tenant's partition. Tenant IDs are percent-encoded so
+/// they cannot escape the index directory.
+pub(crate) fn partition_dir(root: &Path, tenant: &TenantId) -> PathBuf {
+    let mut name = String::new();
+    for byte in tenant.as_str().bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    if name.is_empty() {
+        name.push('%');
+    }
+    root.join(name)
+}
+
+fn storage_error(path: &Path, error: std::io::Error) -> VectorError {
+    VectorError::Storage(format!("{}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> DiskVectorIndexConfig {
+        DiskVectorIndexConfig {
+            dir: std::env::temp_dir().join(format!("telamentis-vectors-{}", Uuid::new_v4())),
+            sync_writes: false,
+            ..DiskVectorIndexConfig::default()
+        }
+    }
+
+    fn vector(seed: usize) -> Vec<f32> {
+        (0..8).map(|i| ((seed * 8 + i) as f32 * 0.618).sin()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_recovery_after_restart() {
+        let config = test_config();
+        let tenant_a = TenantId::new("tenant_a");
+        let tenant_b = TenantId::new("tenant/b");
+        let ids: Vec<Uuid> = (0..50).map(|_| Uuid::new_v4()).collect();
+
+        let index = DiskVectorIndex::open(config.clone()).unwrap();
+        for (seed, id) in ids.iter().enumerate() {
+            index.upsert_vector(&tenant_a, *id, vector(seed)).await.unwrap();
+        }
+        index.upsert_vector(&tenant_b, Uuid::new_v4(), vector(0)).await.unwrap();
+        assert!(index.remove_vector(&tenant_a, ids[0]).await.unwrap());
+        assert!(!index.remove_vector(&tenant_a, ids[0]).await.unwrap());
+        assert!(matches!(
+            index.upsert_vector(&tenant_a, Uuid::new_v4(), vec![1.0]).await,
+            Err(VectorError::DimensionMismatch { expected: 8, actual: 1 })
+        ));
+        let before = index.search(&tenant_a, &vector(3), 5).await.unwrap();
+        index.shutdown().await;
+        drop(index);
+
+        let index = DiskVectorIndex::open(config.clone()).unwrap();
+        assert_eq!(index.count(&tenant_a).await.unwrap(), 49);
+        assert_eq!(index.count(&tenant_b).await.unwrap(), 1);
+        assert_eq!(index.count(&TenantId::new("other")).await.unwrap(), 0);
+        assert_eq!(index.search(&tenant_a, &vector(3), 5).await.unwrap(), before);
+        assert_eq!(before[0].id, ids[3]);
+
+        // Compacting keeps the data and empties the log
+        assert!(index.compact(&tenant_a).await.unwrap());
+        let log = partition_dir(&config.dir, &tenant_a).join(LOG_FILE);
+        assert_eq!(fs::metadata(&log).unwrap().len(), 0);
+        index.shutdown().await;
+        drop(index);
+
+        let index = DiskVectorIndex::open(config.clone()).unwrap();
+        assert_eq!(index.search(&tenant_a, &vector(3), 5).await.unwrap(), before);
+        assert!(index.drop_tenant(&tenant_b).await.unwrap());
+        assert_eq!(index.count(&tenant_b).await.unwrap(), 0);
+        index.shutdown().await;
+
+        fs::remove_dir_all(&config.dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compaction_and_torn_log() {
+        let config = DiskVectorIndexConfig {
+            compact_after_writes: 10,
+            ..test_config()
+        };
+        let tenant = TenantId::new("tenant");
+
+        let index = DiskVectorIndex::open(config.clone()).unwrap();
+        for seed in 0..5 {
+            index.upsert_vector(&tenant, Uuid::new_v4(), vector(seed)).await.unwrap();
+        }
+        assert_eq!(index.compact_due().await, 0);
+        for seed in 5..10 {
+            index.upsert_vector(&tenant, Uuid::new_v4(), vector(seed)).await.unwrap();
+        }
+        assert_eq!(index.compact_due().await, 1);
+
+        let kept = Uuid::new_v4();
+        index.upsert_vector(&tenant, kept, vector(42)).await.unwrap();
+        index.shutdown().await;
+        drop(index);
+
+        // Simulate a crash in the middle of appending an entry
+        let log = partition_dir(&config.dir, &tenant).join(LOG_FILE);
+        let complete = fs::metadata(&log).unwrap().len();
+        OpenOptions::new().append(true).open(&log).unwrap()
+            .write_all(br#"{"op":"upsert","id":"#).unwrap();
+
+        let index = DiskVectorIndex::open(config.clone()).unwrap();
+        assert_eq!(index.count(&tenant).await.unwrap(), 11);
+        assert_eq!(index.search(&tenant, &vector(42), 1).await.unwrap()[0].id, kept);
+        assert_eq!(fs::metadata(&log).unwrap().len(), complete);
+        index.shutdown().await;
+
+        fs::remove_dir_all(&config.dir).unwrap();
+    }
+}
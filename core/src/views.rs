@@ -0,0 +1,909 @@
+//! Graph views: row-level security within a tenant
+//!
+//! Some consumers should only see a slice of a tenant's graph. A
+//! [`GraphView`] defines that slice: nodes with one of its labels and all of
+//! its property values, and edges of its kinds between such nodes. Views are
+//! defined per tenant in [`GraphViews`] and attached to principals, such as
+//! API tokens by ID; presentation adapters run the requests of a principal
+//! with a view under [`with_view`], and work they spawn under
+//! [`in_current_view`].
+//!
+//! [`ViewGraphStore`] confines every call made under a view to it, and
+//! `CoreGraphService` runs its store inside one. Structured queries get the
+//! view's labels, kinds and properties added as predicates, and results
+//! holding anything outside the view are dropped; relationships are checked
+//! against their endpoints after the query, so pages of them may come back
+//! short. Nodes and edges outside the view look as if they did not exist,
+//! writes of nodes and edges outside it fail with
+//! `GraphError::TenantIsolationViolation`, and so do raw queries and
+//! operations on the whole graph, such as summaries and history purges.
+//! Mutations of edges by ID read a snapshot of the tenant to check the edge.
+
+use crate::archive::ArchiveBatch;
+use crate::constraints::EdgeConstraints;
+use crate::bulk::{until_error, EdgeStream, NodeStream};
+use crate::errors::{GraphError, ViewError};
+use crate::materialized::SnapshotInfo;
+use crate::traits::GraphStore;
+use crate::traversal::{ShortestPathRequest, TraversalRequest, WeightedPath};
+use crate::timeline::{Timeline, TimelineRequest};
+use crate::property_history::PropertyHistory;
+use crate::types::{AliasKey, EdgeSpec, GraphCatalog, GraphQuery, GraphSnapshot, GraphSummary, Node, NodeRef, NodeWithEdges, Path, PathNode, TenantId, TimeEdge};
+use async_trait::async_trait;
+use futures::StreamExt;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use tracing::info;
+use uuid::Uuid;
+
+tokio::task_local! {
+    static VIEW: Option<Arc<GraphView>>;
+}
+
+/// Run `future` confined to `view`, or unconfined if `None`
+pub async fn with_view<F: Future>(view: Option<GraphView>, future: F) -> F::Output {
+    VIEW.scope(view.map(Arc::new), future).await
+}
+
+/// View the current task is confined to
+pub fn current_view() -> Option<Arc<GraphView>> {
+    VIEW.try_with(|view| view.clone()).ok().flatten()
+}
+
+/// `future`, confined to the view of the current task. Spawned tasks do not
+/// inherit it, so futures that reach the store from another task are
+/// wrapped where they are created, e.g. `tokio::spawn(in_current_view(work))`.
+pub fn in_current_view<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    VIEW.scope(current_view(), future)
+}
+
+/// A slice of a tenant's graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphView {
+    pub name: String,
+    /// Labels of the nodes in the view; any label if empty
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Kinds of the edges in the view; any kind if empty
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    /// Property values every node in the view has
+    #[serde(default)]
+    pub properties: HashMap<String, Value>,
+}
+
+impl GraphView {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), labels: Vec::new(), kinds: Vec::new(), properties: HashMap::new() }
+    }
+
+    pub fn with_labels<S: Into<String>>(mut self, labels: impl IntoIterator<Item = S>) -> Self {
+        self.labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_kinds<S: Into<String>>(mut self, kinds: impl IntoIterator<Item = S>) -> Self {
+        self.kinds = kinds.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_property(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.properties.insert(key.into(), value);
+        self
+    }
+
+    /// Whether the node is in the view
+    pub fn contains_node(&self, node: &Node) -> bool {
+        self.allows_labels(std::slice::from_ref(&node.label)) && self.allows_props(&node.props)
+    }
+
+    /// Whether edges of the kind are in the view, given their endpoints are
+    pub fn contains_kind(&self, kind: &str) -> bool {
+        self.kinds.is_empty() || self.kinds.iter().any(|allowed| allowed == kind)
+    }
+
+    /// Whether everything in the path is in the view. Relationships whose
+    /// endpoints are not in the path are not.
+    pub fn contains_path(&self, path: &Path) -> bool {
+        path.nodes.iter().all(|node| self.contains_path_node(node))
+            && path.relationships.iter().all(|relationship| {
+                self.contains_kind(&relationship.rel_type)
+                    && [relationship.start_node_id, relationship.end_node_id].iter()
+                        .all(|id| path.nodes.iter().any(|node| node.id == *id))
+            })
+    }
+
+    fn contains_path_node(&self, node: &PathNode) -> bool {
+        self.allows_labels(&node.labels) && self.allows_props(&node.properties)
+    }
+
+    fn allows_labels(&self, labels: &[String]) -> bool {
+        self.labels.is_empty() || labels.iter().any(|label| self.labels.contains(label))
+    }
+
+    fn allows_props(&self, props: &Value) -> bool {
+        self.properties.iter().all(|(key, value)| props.get(key) == Some(value))
+    }
+
+    /// Whether the view restricts nodes, not just edge kinds
+    fn restricts_nodes(&self) -> bool {
+        !self.labels.is_empty() || !self.properties.is_empty()
+    }
+
+    /// The query with the view's predicates added; `None` if nothing in the
+    /// view can match it
+    fn restrict(&self, query: GraphQuery) -> Result<Option<GraphQuery>, GraphError> {
+        match query {
+            GraphQuery::FindNodes { labels, mut properties, order_by, offset, limit } => {
+                let Some(labels) = narrow(labels, &self.labels) else {
+                    return Ok(None);
+                };
+                for (key, value) in &self.properties {
+                    if properties.get(key).is_some_and(|requested| requested != value) {
+                        return Ok(None);
+                    }
+                    properties.insert(key.clone(), value.clone());
+                }
+                Ok(Some(GraphQuery::FindNodes { labels, properties, order_by, offset, limit }))
+            }
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse } => {
+                Ok(narrow(relationship_types, &self.kinds).map(|relationship_types| GraphQuery::FindRelationships {
+                    from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse,
+                }))
+            }
+            GraphQuery::AsOfQuery { base_query, as_of_time } => Ok(self.restrict(*base_query)?
+                .map(|base_query| GraphQuery::AsOfQuery { base_query: Box::new(base_query), as_of_time })),
+            GraphQuery::Raw { .. } => Err(self.refuse("Raw queries")),
+        }
+    }
+
+    fn visible(&self, paths: Vec<Path>) -> Vec<Path> {
+        paths.into_iter().filter(|path| self.contains_path(path)).collect()
+    }
+
+    pub(crate) fn refuse(&self, operation: &str) -> GraphError {
+        GraphError::TenantIsolationViolation(format!("{} are not available within view '{}'", operation, self.name))
+    }
+
+    fn outside(&self, what: String) -> GraphError {
+        GraphError::TenantIsolationViolation(format!("{} is outside view '{}'", what, self.name))
+    }
+
+    fn check_node(&self, node: &Node) -> Result<(), GraphError> {
+        if self.contains_node(node) { Ok(()) } else { Err(self.outside(format!("{} node", node.label))) }
+    }
+
+    fn check_kind(&self, kind: &str) -> Result<(), GraphError> {
+        if self.contains_kind(kind) { Ok(()) } else { Err(self.outside(format!("{} edge", kind))) }
+    }
+}
+
+/// Values of a query predicate narrowed to those a view allows; any value
+/// if both are empty, `None` if none is left
+fn narrow(requested: Vec<String>, allowed: &[String]) -> Option<Vec<String>> {
+    if allowed.is_empty() {
+        return Some(requested);
+    }
+    if requested.is_empty() {
+        return Some(allowed.to_vec());
+    }
+    let narrowed: Vec<String> = requested.into_iter().filter(|value| allowed.contains(value)).collect();
+    (!narrowed.is_empty()).then_some(narrowed)
+}
+
+fn finds_relationships(query: &GraphQuery) -> bool {
+    match query {
+        GraphQuery::FindRelationships { .. } => true,
+        GraphQuery::AsOfQuery { base_query, .. } => finds_relationships(base_query),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct TenantViews {
+    views: BTreeMap<String, GraphView>,
+    /// View name by principal
+    principals: HashMap<String, String>,
+}
+
+/// Views of each tenant and the principals confined to them
+#[derive(Default)]
+pub struct GraphViews {
+    tenants: RwLock<HashMap<TenantId, TenantViews>>,
+}
+
+impl GraphViews {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define a view of the tenant, replacing the view of the same name
+    pub fn define(&self, tenant: &TenantId, view: GraphView) -> Result<(), ViewError> {
+        if view.name.trim().is_empty() {
+            return Err(ViewError::Invalid("views need a name".to_string()));
+        }
+        if let Some(label) = view.labels.iter().find(|label| label.is_empty()) {
+            return Err(ViewError::Invalid(format!("view '{}' has an empty label '{}'", view.name, label)));
+        }
+        info!("Defined view '{}' of tenant {}", view.name, tenant);
+        self.tenants.write().unwrap().entry(tenant.clone()).or_default().views.insert(view.name.clone(), view);
+        Ok(())
+    }
+
+    /// The tenant's views by name
+    pub fn list(&self, tenant: &TenantId) -> Vec<GraphView> {
+        self.tenants.read().unwrap().get(tenant)
+            .map(|views| views.views.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, tenant: &TenantId, name: &str) -> Option<GraphView> {
+        self.tenants.read().unwrap().get(tenant)?.views.get(name).cloned()
+    }
+
+    /// Remove a view; fails while principals are confined to it, as removing
+    /// it would lift their confinement
+    pub fn remove(&self, tenant: &TenantId, name: &str) -> Result<GraphView, ViewError> {
+        let mut tenants = self.tenants.write().unwrap();
+        let views = tenants.get_mut(tenant).ok_or_else(|| ViewError::NotFound(name.to_string()))?;
+        let principals = principals_of(views, name);
+        if !principals.is_empty() {
+            return Err(ViewError::InUse(format!("{} is assigned to {}", name, principals.join(", "))));
+        }
+        let view = views.views.remove(name).ok_or_else(|| ViewError::NotFound(name.to_string()))?;
+        info!("Removed view '{}' of tenant {}", name, tenant);
+        Ok(view)
+    }
+
+    /// Confine a principal to a view, instead of any view it had
+    pub fn assign(&self, tenant: &TenantId, principal: &str, name: &str) -> Result<(), ViewError> {
+        let mut tenants = self.tenants.write().unwrap();
+        let views = tenants.get_mut(tenant)
+            .filter(|views| views.views.contains_key(name))
+            .ok_or_else(|| ViewError::NotFound(name.to_string()))?;
+        views.principals.insert(principal.to_string(), name.to_string());
+        info!("Confined principal {} of tenant {} to view '{}'", principal, tenant, name);
+        Ok(())
+    }
+
+    /// Lift a principal's confinement; `None` if it had no view
+    pub fn unassign(&self, tenant: &TenantId, principal: &str) -> Option<String> {
+        let name = self.tenants.write().unwrap().get_mut(tenant)?.principals.remove(principal)?;
+        info!("Lifted the confinement of principal {} of tenant {} to view '{}'", principal, tenant, name);
+        Some(name)
+    }
+
+    /// Principals confined to a view, sorted
+    pub fn principals(&self, tenant: &TenantId, name: &str) -> Vec<String> {
+        self.tenants.read().unwrap().get(tenant).map_or_else(Vec::new, |views| principals_of(views, name))
+    }
+
+    /// View a principal of the tenant is confined to
+    pub fn view_for(&self, tenant: &TenantId, principal: &str) -> Option<GraphView> {
+        let tenants = self.tenants.read().unwrap();
+        let views = tenants.get(tenant)?;
+        views.views.get(views.principals.get(principal)?).cloned()
+    }
+}
+
+fn principals_of(views: &TenantViews, name: &str) -> Vec<String> {
+    let mut principals: Vec<String> = views.principals.iter()
+        .filter(|(_, view)| view.as_str() == name)
+        .map(|(principal, _)| principal.clone())
+        .collect();
+    principals.sort();
+    principals
+}
+
+/// `GraphStore` confining each call to the view of the task making it
+pub struct ViewGraphStore {
+    inner: Arc<dyn GraphStore>,
+}
+
+impl ViewGraphStore {
+    pub fn new(inner: Arc<dyn GraphStore>) -> Self {
+        Self { inner }
+    }
+
+    /// The node, if it exists and is in the view
+    async fn visible_node(&self, tenant: &TenantId, view: &GraphView, id: Uuid) -> Result<Option<Node>, GraphError> {
+        Ok(self.inner.get_node(tenant, id).await?.filter(|node| view.contains_node(node)))
+    }
+
+    /// Fail as if the node did not exist unless it is in the view
+    async fn require_node(&self, tenant: &TenantId, view: &GraphView, id: Uuid) -> Result<(), GraphError> {
+        match self.visible_node(tenant, view, id).await? {
+            Some(_) => Ok(()),
+            None => Err(GraphError::NodeNotFound(id.to_string())),
+        }
+    }
+
+    /// Fail unless the node may be written: it is in the view, and so is the
+    /// node of the same alias it would update
+    async fn check_upsert(&self, tenant: &TenantId, view: &GraphView, node: &Node) -> Result<(), GraphError> {
+        view.check_node(node)?;
+        let Some(alias) = node.alias_key() else {
+            return Ok(());
+        };
+        match self.inner.resolve_aliases(tenant, std::slice::from_ref(&alias)).await?.get(&alias) {
+            Some(&id) if self.visible_node(tenant, view, id).await?.is_none() => Err(view.outside(format!("Node '{}'", alias.alias))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the edge, of any version, is in the view with its endpoints
+    async fn visible_edge(&self, tenant: &TenantId, view: &GraphView, id: Uuid) -> Result<bool, GraphError> {
+        let snapshot = self.inner.snapshot(tenant, None).await?;
+        let Some(record) = snapshot.edges.iter().find(|record| record.id == id) else {
+            return Ok(false);
+        };
+        let endpoints = [record.edge.from_node_id, record.edge.to_node_id];
+        Ok(view.contains_kind(&record.edge.kind)
+            && endpoints.iter().all(|id| snapshot.nodes.iter().any(|node| node.id == *id && view.contains_node(&node.node))))
+    }
+
+    async fn require_edge(&self, tenant: &TenantId, view: &GraphView, id: Uuid) -> Result<(), GraphError> {
+        if self.visible_edge(tenant, view, id).await? { Ok(()) } else { Err(GraphError::EdgeNotFound(id.to_string())) }
+    }
+
+    async fn check_edge(&self, tenant: &TenantId, view: &GraphView, edge: &TimeEdge) -> Result<(), GraphError> {
+        view.check_kind(&edge.kind)?;
+        self.require_node(tenant, view, edge.from_node_id).await?;
+        self.require_node(tenant, view, edge.to_node_id).await
+    }
+}
+
+#[async_trait]
+impl GraphStore for ViewGraphStore {
+    async fn upsert_node(&self, tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+        if let Some(view) = current_view() {
+            self.check_upsert(tenant, &view, &node).await?;
+        }
+        self.inner.upsert_node(tenant, node).await
+    }
+
+    async fn upsert_edge(&self, tenant: &TenantId, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        if let Some(view) = current_view() {
+            self.check_edge(tenant, &view, &edge).await?;
+        }
+        self.inner.upsert_edge(tenant, edge).await
+    }
+
+    async fn upsert_node_with_edges(&self, tenant: &TenantId, node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+        if let Some(view) = current_view() {
+            self.check_upsert(tenant, &view, &node).await?;
+            for spec in &edges {
+                view.check_kind(&spec.kind)?;
+                let target = match &spec.target {
+                    NodeRef::Id(id) => Some(*id),
+                    NodeRef::Alias(alias) => self.inner.resolve_aliases(tenant, std::slice::from_ref(alias)).await?.get(alias).copied(),
+                };
+                match target {
+                    Some(id) if self.visible_node(tenant, &view, id).await?.is_some() => {}
+                    _ => return Err(GraphError::NodeNotFound(format!("Edge target {:?}", spec.target))),
+                }
+            }
+        }
+        self.inner.upsert_node_with_edges(tenant, node, edges).await
+    }
+
+    async fn bulk_load_nodes(&self, tenant: &TenantId, nodes: NodeStream<'_>) -> Result<u64, GraphError> {
+        let Some(view) = current_view() else {
+            return self.inner.bulk_load_nodes(tenant, nodes).await;
+        };
+        // Each node is checked as the store reads it; one outside the view ends the load
+        let view = &view;
+        let (nodes, outside) = until_error(nodes.then(|node| async move {
+            self.check_upsert(tenant, view, &node).await.map(|()| node)
+        }));
+        let loaded = self.inner.bulk_load_nodes(tenant, nodes).await?;
+        outside.take().map_or(Ok(loaded), Err)
+    }
+
+    async fn bulk_load_edges(&self, tenant: &TenantId, edges: EdgeStream<'_>) -> Result<u64, GraphError> {
+        let Some(view) = current_view() else {
+            return self.inner.bulk_load_edges(tenant, edges).await;
+        };
+        let view = &view;
+        let (edges, outside) = until_error(edges.then(|edge| async move {
+            self.check_edge(tenant, view, &edge).await.map(|()| edge)
+        }));
+        let loaded = self.inner.bulk_load_edges(tenant, edges).await?;
+        outside.take().map_or(Ok(loaded), Err)
+    }
+
+    async fn query(&self, tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        let Some(view) = current_view() else {
+            return self.inner.query(tenant, query).await;
+        };
+        let Some(query) = view.restrict(query)? else {
+            return Ok(Vec::new());
+        };
+        Ok(view.visible(self.inner.query(tenant, query).await?))
+    }
+
+    async fn query_count(&self, tenant: &TenantId, query: GraphQuery) -> Result<u64, GraphError> {
+        let Some(view) = current_view() else {
+            return self.inner.query_count(tenant, query).await;
+        };
+        let Some(query) = view.restrict(query)? else {
+            return Ok(0);
+        };
+        if view.restricts_nodes() && finds_relationships(&query) {
+            // Endpoints are not predicates of the query, so relationships are counted once checked
+            let paths = self.inner.query(tenant, query.with_page(None, None)).await?;
+            return Ok(view.visible(paths).len() as u64);
+        }
+        self.inner.query_count(tenant, query).await
+    }
+
+    async fn query_exists(&self, tenant: &TenantId, query: GraphQuery) -> Result<bool, GraphError> {
+        match current_view() {
+            Some(view) if view.restricts_nodes() && finds_relationships(&query) => Ok(self.query_count(tenant, query).await? > 0),
+            Some(view) => match view.restrict(query)? {
+                Some(query) => self.inner.query_exists(tenant, query).await,
+                None => Ok(false),
+            },
+            None => self.inner.query_exists(tenant, query).await,
+        }
+    }
+
+    async fn traverse(&self, tenant: &TenantId, request: TraversalRequest) -> Result<Vec<WeightedPath>, GraphError> {
+        let paths = self.inner.traverse(tenant, request).await?;
+        Ok(match current_view() {
+            Some(view) => paths.into_iter().filter(|path| view.contains_path(&path.path)).collect(),
+            None => paths,
+        })
+    }
+
+    async fn shortest_path(&self, tenant: &TenantId, request: ShortestPathRequest) -> Result<Option<WeightedPath>, GraphError> {
+        let path = self.inner.shortest_path(tenant, request).await?;
+        Ok(match current_view() {
+            Some(view) => path.filter(|path| view.contains_path(&path.path)),
+            None => path,
+        })
+    }
+
+    async fn timeline(&self, tenant: &TenantId, request: TimelineRequest) -> Result<Timeline, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Timelines")),
+            None => self.inner.timeline(tenant, request).await,
+        }
+    }
+
+    async fn property_history(&self, tenant: &TenantId, node_id: Uuid, key: &str) -> Result<PropertyHistory, GraphError> {
+        if let Some(view) = current_view() {
+            self.require_node(tenant, &view, node_id).await?;
+        }
+        self.inner.property_history(tenant, node_id, key).await
+    }
+
+    async fn update_node(&self, tenant: &TenantId, id: Uuid, node: Node) -> Result<bool, GraphError> {
+        if let Some(view) = current_view() {
+            if self.visible_node(tenant, &view, id).await?.is_none() {
+                return Ok(false);
+            }
+            view.check_node(&node)?;
+        }
+        self.inner.update_node(tenant, id, node).await
+    }
+
+    async fn edge_constraints(&self, tenant: &TenantId) -> Result<EdgeConstraints, GraphError> {
+        self.inner.edge_constraints(tenant).await
+    }
+
+    async fn set_edge_constraints(&self, tenant: &TenantId, constraints: EdgeConstraints) -> Result<(), GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Edge constraint changes")),
+            None => self.inner.set_edge_constraints(tenant, constraints).await,
+        }
+    }
+
+    async fn get_node(&self, tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+        match current_view() {
+            Some(view) => self.visible_node(tenant, &view, id).await,
+            None => self.inner.get_node(tenant, id).await,
+        }
+    }
+
+    async fn get_node_by_alias(&self, tenant: &TenantId, id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+        let node = self.inner.get_node_by_alias(tenant, id_alias).await?;
+        Ok(match current_view() {
+            Some(view) => node.filter(|(_, node)| view.contains_node(node)),
+            None => node,
+        })
+    }
+
+    async fn resolve_aliases(&self, tenant: &TenantId, aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+        let resolved = self.inner.resolve_aliases(tenant, aliases).await?;
+        let Some(view) = current_view() else {
+            return Ok(resolved);
+        };
+        let mut visible = HashMap::with_capacity(resolved.len());
+        for (alias, id) in resolved {
+            if self.visible_node(tenant, &view, id).await?.is_some() {
+                visible.insert(alias, id);
+            }
+        }
+        Ok(visible)
+    }
+
+    async fn delete_node(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        if let Some(view) = current_view() {
+            if self.visible_node(tenant, &view, id).await?.is_none() {
+                return Ok(false);
+            }
+        }
+        self.inner.delete_node(tenant, id).await
+    }
+
+    async fn delete_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        if let Some(view) = current_view() {
+            if !self.visible_edge(tenant, &view, id).await? {
+                return Ok(false);
+            }
+        }
+        self.inner.delete_edge(tenant, id).await
+    }
+
+    async fn close_edge(&self, tenant: &TenantId, id: Uuid, valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+        if let Some(view) = current_view() {
+            self.require_edge(tenant, &view, id).await?;
+        }
+        self.inner.close_edge(tenant, id, valid_to).await
+    }
+
+    async fn supersede_edge(&self, tenant: &TenantId, id: Uuid, edge: TimeEdge) -> Result<Uuid, GraphError> {
+        if let Some(view) = current_view() {
+            self.require_edge(tenant, &view, id).await?;
+            self.check_edge(tenant, &view, &edge).await?;
+        }
+        self.inner.supersede_edge(tenant, id, edge).await
+    }
+
+    async fn retract_edge(&self, tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+        if let Some(view) = current_view() {
+            if !self.visible_edge(tenant, &view, id).await? {
+                return Ok(false);
+            }
+        }
+        self.inner.retract_edge(tenant, id).await
+    }
+
+    async fn get_node_history(&self, tenant: &TenantId, id: Uuid) -> Result<Vec<Node>, GraphError> {
+        let history = self.inner.get_node_history(tenant, id).await?;
+        Ok(match current_view() {
+            Some(view) => history.into_iter().filter(|node| view.contains_node(node)).collect(),
+            None => history,
+        })
+    }
+
+    async fn snapshot(&self, tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+        let mut snapshot = self.inner.snapshot(tenant, valid_at).await?;
+        if let Some(view) = current_view() {
+            snapshot.nodes.retain(|record| view.contains_node(&record.node));
+            let visible: HashSet<Uuid> = snapshot.nodes.iter().map(|record| record.id).collect();
+            snapshot.edges.retain(|record| {
+                view.contains_kind(&record.edge.kind)
+                    && visible.contains(&record.edge.from_node_id)
+                    && visible.contains(&record.edge.to_node_id)
+            });
+        }
+        Ok(snapshot)
+    }
+
+    async fn materialize_snapshot(&self, tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Materialized snapshots")),
+            None => self.inner.materialize_snapshot(tenant, name, valid_at).await,
+        }
+    }
+
+    async fn list_snapshots(&self, tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Materialized snapshots")),
+            None => self.inner.list_snapshots(tenant).await,
+        }
+    }
+
+    async fn drop_snapshot(&self, tenant: &TenantId, name: &str) -> Result<bool, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Materialized snapshots")),
+            None => self.inner.drop_snapshot(tenant, name).await,
+        }
+    }
+
+    async fn query_snapshot(&self, tenant: &TenantId, name: &str, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+        let Some(view) = current_view() else {
+            return self.inner.query_snapshot(tenant, name, query).await;
+        };
+        let Some(query) = view.restrict(query)? else {
+            return Ok(Vec::new());
+        };
+        Ok(view.visible(self.inner.query_snapshot(tenant, name, query).await?))
+    }
+
+    async fn read_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("History archives")),
+            None => self.inner.read_history(tenant, before).await,
+        }
+    }
+
+    async fn purge_history(&self, tenant: &TenantId, before: DateTime<Utc>) -> Result<u64, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("History purges")),
+            None => self.inner.purge_history(tenant, before).await,
+        }
+    }
+
+    async fn restore_history(&self, tenant: &TenantId, batch: ArchiveBatch) -> Result<u64, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("History restores")),
+            None => self.inner.restore_history(tenant, batch).await,
+        }
+    }
+
+    async fn purge_expired_edges(&self, tenant: &TenantId, now: DateTime<Utc>) -> Result<u64, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Expiry purges")),
+            None => self.inner.purge_expired_edges(tenant, now).await,
+        }
+    }
+
+    async fn summary(&self, tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Graph summaries")),
+            None => self.inner.summary(tenant).await,
+        }
+    }
+
+    async fn catalog(&self, tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Graph catalogs")),
+            None => self.inner.catalog(tenant).await,
+        }
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<TenantId>, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Tenant lists")),
+            None => self.inner.list_tenants().await,
+        }
+    }
+
+    async fn clear_tenant(&self, tenant: &TenantId) -> Result<u64, GraphError> {
+        match current_view() {
+            Some(view) => Err(view.refuse("Tenant clears")),
+            None => self.inner.clear_tenant(tenant).await,
+        }
+    }
+
+    async fn health_check(&self) -> Result<(), GraphError> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Store that keeps nodes and records the queries it runs
+    #[derive(Default)]
+    struct NodeStore {
+        nodes: Mutex<HashMap<Uuid, Node>>,
+        queries: Mutex<Vec<GraphQuery>>,
+    }
+
+    #[async_trait]
+    impl GraphStore for NodeStore {
+        async fn upsert_node(&self, _tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
+            let id = Uuid::new_v4();
+            self.nodes.lock().unwrap().insert(id, node);
+            Ok(id)
+        }
+
+        async fn upsert_edge(&self, _tenant: &TenantId, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn upsert_node_with_edges(&self, _tenant: &TenantId, _node: Node, _edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+            Ok(NodeWithEdges { node_id: Uuid::new_v4(), edge_ids: vec![] })
+        }
+
+        async fn query(&self, _tenant: &TenantId, query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            self.queries.lock().unwrap().push(query);
+            Ok(self.nodes.lock().unwrap().iter()
+                .map(|(id, node)| Path {
+                    nodes: vec![PathNode { id: *id, labels: vec![node.label.clone()], properties: node.props.clone() }],
+                    relationships: vec![],
+                })
+                .collect())
+        }
+
+        async fn get_node(&self, _tenant: &TenantId, id: Uuid) -> Result<Option<Node>, GraphError> {
+            Ok(self.nodes.lock().unwrap().get(&id).cloned())
+        }
+
+        async fn get_node_by_alias(&self, _tenant: &TenantId, _id_alias: &str) -> Result<Option<(Uuid, Node)>, GraphError> {
+            Ok(None)
+        }
+
+        async fn resolve_aliases(&self, _tenant: &TenantId, _aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+            Ok(HashMap::new())
+        }
+
+        async fn delete_node(&self, _tenant: &TenantId, id: Uuid) -> Result<bool, GraphError> {
+            Ok(self.nodes.lock().unwrap().remove(&id).is_some())
+        }
+
+        async fn delete_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn close_edge(&self, _tenant: &TenantId, _id: Uuid, _valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn supersede_edge(&self, _tenant: &TenantId, _id: Uuid, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn retract_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn get_node_history(&self, _tenant: &TenantId, _id: Uuid) -> Result<Vec<Node>, GraphError> {
+            Ok(vec![])
+        }
+
+        async fn snapshot(&self, _tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+            Ok(GraphSnapshot { snapshot_at: Utc::now(), valid_at, nodes: vec![], edges: vec![] })
+        }
+
+        async fn materialize_snapshot(&self, _tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+            Ok(SnapshotInfo { name: name.to_string(), valid_at, snapshot_at: Utc::now(), node_count: 0, edge_count: 0 })
+        }
+
+        async fn list_snapshots(&self, _tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn drop_snapshot(&self, _tenant: &TenantId, _name: &str) -> Result<bool, GraphError> {
+            Ok(true)
+        }
+
+        async fn query_snapshot(&self, _tenant: &TenantId, _name: &str, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            Ok(Vec::new())
+        }
+
+        async fn read_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<ArchiveBatch, GraphError> {
+            Ok(ArchiveBatch::default())
+        }
+
+        async fn purge_history(&self, _tenant: &TenantId, _before: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn restore_history(&self, _tenant: &TenantId, _batch: ArchiveBatch) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn purge_expired_edges(&self, _tenant: &TenantId, _now: DateTime<Utc>) -> Result<u64, GraphError> {
+            Ok(0)
+        }
+
+        async fn summary(&self, _tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+            Ok(GraphSummary::default())
+        }
+
+        async fn catalog(&self, _tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+            Ok(GraphCatalog::default())
+        }
+
+        async fn health_check(&self) -> Result<(), GraphError> {
+            Ok(())
+        }
+    }
+
+    fn public_view() -> GraphView {
+        GraphView::new("public").with_labels(["Document"]).with_kinds(["CITES"]).with_property("visibility", json!("Public"))
+    }
+
+    fn document(visibility: &str) -> Node {
+        Node::new("Document").with_props(json!({"visibility": visibility}))
+    }
+
+    #[test]
+    fn test_restrict_adds_view_predicates() {
+        let view = public_view();
+        let query = GraphQuery::FindNodes { labels: vec![], properties: HashMap::new(), order_by: vec![], offset: None, limit: Some(5) };
+        let Some(GraphQuery::FindNodes { labels, properties, limit, .. }) = view.restrict(query).unwrap() else { panic!("not restricted") };
+        assert_eq!((labels, properties.get("visibility"), limit), (vec!["Document".to_string()], Some(&json!("Public")), Some(5)));
+
+        let private = GraphQuery::FindNodes {
+            labels: vec!["Document".to_string()],
+            properties: HashMap::from([("visibility".to_string(), json!("Private"))]),
+            order_by: vec![], offset: None, limit: None,
+        };
+        assert!(view.restrict(private).unwrap().is_none());
+        let person = GraphQuery::FindNodes { labels: vec!["Person".to_string()], properties: HashMap::new(), order_by: vec![], offset: None, limit: None };
+        assert!(view.restrict(person).unwrap().is_none());
+        let raw = GraphQuery::Raw { query: "MATCH (n) RETURN n".to_string(), params: HashMap::new() };
+        assert!(matches!(view.restrict(raw), Err(GraphError::TenantIsolationViolation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_store_confines_calls_to_view() {
+        let inner = Arc::new(NodeStore::default());
+        let store = ViewGraphStore::new(inner.clone());
+        let tenant = TenantId::new("acme");
+        let public = store.upsert_node(&tenant, document("Public")).await.unwrap();
+        let private = store.upsert_node(&tenant, document("Private")).await.unwrap();
+
+        with_view(Some(public_view()), async {
+            assert!(store.get_node(&tenant, public).await.unwrap().is_some());
+            assert!(store.get_node(&tenant, private).await.unwrap().is_none());
+            assert!(!store.delete_node(&tenant, private).await.unwrap());
+
+            let query = GraphQuery::FindNodes { labels: vec![], properties: HashMap::new(), order_by: vec![], offset: None, limit: None };
+            let paths = store.query(&tenant, query).await.unwrap();
+            assert_eq!(paths.iter().map(|path| path.nodes[0].id).collect::<Vec<_>>(), vec![public]);
+            assert!(matches!(&inner.queries.lock().unwrap()[0], GraphQuery::FindNodes { properties, .. } if properties.contains_key("visibility")));
+
+            let err = store.upsert_node(&tenant, document("Private")).await.unwrap_err();
+            assert!(matches!(err, GraphError::TenantIsolationViolation(_)));
+            let edge = TimeEdge::new(public, private, "CITES", Utc::now(), json!({}));
+            assert!(matches!(store.upsert_edge(&tenant, edge).await, Err(GraphError::NodeNotFound(_))));
+            assert!(matches!(store.summary(&tenant).await, Err(GraphError::TenantIsolationViolation(_))));
+        }).await;
+
+        // Without a view everything is visible
+        assert!(store.get_node(&tenant, private).await.unwrap().is_some());
+        with_view(None, async {
+            assert!(store.delete_node(&tenant, private).await.unwrap());
+        }).await;
+    }
+
+    #[tokio::test]
+    async fn test_spawned_work_keeps_view() {
+        let inner = Arc::new(NodeStore::default());
+        let store = Arc::new(ViewGraphStore::new(inner));
+        let tenant = TenantId::new("acme");
+        let private = store.upsert_node(&tenant, document("Private")).await.unwrap();
+
+        let get = |store: Arc<ViewGraphStore>, tenant: TenantId| async move { store.get_node(&tenant, private).await.unwrap() };
+        let (unconfined, confined) = with_view(Some(public_view()), async {
+            let unconfined = tokio::spawn(get(store.clone(), tenant.clone()));
+            let confined = tokio::spawn(in_current_view(get(store.clone(), tenant.clone())));
+            (unconfined.await.unwrap(), confined.await.unwrap())
+        }).await;
+        assert!(unconfined.is_some());
+        assert!(confined.is_none());
+    }
+
+    #[test]
+    fn test_views_assigned_to_principals() {
+        let views = GraphViews::new();
+        let tenant = TenantId::new("acme");
+        assert!(matches!(views.assign(&tenant, "token-1", "public"), Err(ViewError::NotFound(_))));
+        assert!(matches!(views.define(&tenant, GraphView::new(" ")), Err(ViewError::Invalid(_))));
+
+        views.define(&tenant, public_view()).unwrap();
+        views.assign(&tenant, "token-1", "public").unwrap();
+        assert_eq!(views.view_for(&tenant, "token-1"), Some(public_view()));
+        assert!(views.view_for(&TenantId::new("other"), "token-1").is_none());
+        assert_eq!(views.principals(&tenant, "public"), vec!["token-1".to_string()]);
+        assert!(matches!(views.remove(&tenant, "public"), Err(ViewError::InUse(_))));
+
+        assert_eq!(views.unassign(&tenant, "token-1").as_deref(), Some("public"));
+        assert_eq!(views.remove(&tenant, "public").unwrap().name, "public");
+        assert!(views.list(&tenant).is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+//! Non-fatal issues reported back to the client of a request
+//!
+//! Validation and pipeline plugins sometimes let a request through after
+//! changing it: a reserved property is stripped, an inverted interval is
+//! repaired, a missing valid time is filled in. Presentation adapters run
+//! each request inside [`collect_warnings`], and such code calls [`report`]
+//! so the response can tell the client instead of only the server log.
+//! Outside of a collecting request, reports are only logged.
+
+use std::cell::RefCell;
+use std::future::Future;
+use tracing::debug;
+
+/// Most distinct warnings kept for one request
+pub const MAX_WARNINGS: usize = 50;
+
+tokio::task_local! {
+    static WARNINGS: RefCell<Warnings>;
+}
+
+#[derive(Debug, Default)]
+struct Warnings {
+    messages: Vec<String>,
+    omitted: usize,
+}
+
+/// Run `future`, returning its output with the warnings it reported, in the
+/// order first reported and without repeats
+pub async fn collect_warnings<F: Future>(future: F) -> (F::Output, Vec<String>) {
+    WARNINGS.scope(RefCell::new(Warnings::default()), async {
+        let output = future.await;
+        (output, take_warnings())
+    }).await
+}
+
+/// Report a warning to the client of the current request
+pub fn report(message: impl Into<String>) {
+    let message = message.into();
+    let collected = WARNINGS.try_with(|warnings| {
+        let mut warnings = warnings.borrow_mut();
+        if warnings.messages.contains(&message) {
+            return;
+        }
+        if warnings.messages.len() < MAX_WARNINGS {
+            warnings.messages.push(message.clone());
+        } else {
+            warnings.omitted += 1;
+        }
+    });
+    if collected.is_err() {
+        debug!("Warning outside of a request: {}", message);
+    }
+}
+
+/// Take the warnings reported so far in the current request; empty outside of one
+pub fn take_warnings() -> Vec<String> {
+    WARNINGS.try_with(|warnings| {
+        let Warnings { mut messages, omitted } = warnings.take();
+        if omitted > 0 {
+            messages.push(format!("{} more warning(s) omitted", omitted));
+        }
+        messages
+    }).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collect_warnings() {
+        let ((), warnings) = collect_warnings(async {
+            report("valid_from defaulted");
+            report("property stripped");
+            report("valid_from defaulted");
+        }).await;
+        assert_eq!(warnings, vec!["valid_from defaulted", "property stripped"]);
+
+        // Outside of a request nothing is collected
+        report("ignored");
+        assert!(take_warnings().is_empty());
+
+        let ((), warnings) = collect_warnings(async {
+            for i in 0..MAX_WARNINGS + 3 {
+                report(format!("warning {}", i));
+            }
+        }).await;
+        assert_eq!(warnings.len(), MAX_WARNINGS + 1);
+        assert_eq!(warnings.last().unwrap(), "3 more warning(s) omitted");
+    }
+}
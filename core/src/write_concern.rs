@@ -0,0 +1,72 @@
+//! Per-request write concern
+//!
+//! Callers choose how far a write must get before it is acknowledged:
+//! `buffered` once the write-behind buffer has accepted it, `committed` (the
+//! default) once the store has committed it and `replicated` once the
+//! store's read replicas can serve it too. Presentation adapters run a
+//! request's writes inside [`with_write_concern`], and stores and store
+//! layers read it with [`current_write_concern`]: `BatchingGraphStore`
+//! returns buffered writes without waiting for the flush and the Neo4j store
+//! waits for its replicas. A store without a write buffer treats `buffered`
+//! as `committed`, and one without replicas treats `replicated` as
+//! `committed`.
+//!
+//! Buffered writes are acknowledged before they have an ID, so their
+//! upserts return the nil UUID.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+
+tokio::task_local! {
+    static WRITE_CONCERN: WriteConcern;
+}
+
+/// How far a write must get before it is acknowledged
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteConcern {
+    /// Accepted into the write-behind buffer; may be lost if the process stops
+    Buffered,
+    /// Committed by the store
+    #[default]
+    Committed,
+    /// Committed and readable from the store's replicas
+    Replicated,
+}
+
+impl fmt::Display for WriteConcern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteConcern::Buffered => write!(f, "buffered"),
+            WriteConcern::Committed => write!(f, "committed"),
+            WriteConcern::Replicated => write!(f, "replicated"),
+        }
+    }
+}
+
+/// Run `future` with the given write concern for the writes it makes
+pub async fn with_write_concern<F: Future>(concern: WriteConcern, future: F) -> F::Output {
+    WRITE_CONCERN.scope(concern, future).await
+}
+
+/// Write concern of the current request; `committed` outside of [`with_write_concern`]
+pub fn current_write_concern() -> WriteConcern {
+    WRITE_CONCERN.try_with(|concern| *concern).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_concern_scope() {
+        assert_eq!(current_write_concern(), WriteConcern::Committed);
+        let concern = with_write_concern(WriteConcern::Buffered, async { current_write_concern() }).await;
+        assert_eq!(concern, WriteConcern::Buffered);
+        assert_eq!(current_write_concern(), WriteConcern::Committed);
+
+        let parsed: WriteConcern = serde_json::from_str("\"replicated\"").unwrap();
+        assert_eq!(parsed, WriteConcern::Replicated);
+    }
+}
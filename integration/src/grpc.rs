@@ -0,0 +1,280 @@
+//! Client for the gRPC adapter
+
+use crate::{free_local_addr, spawn_adapter, wait_until_ready, ClientError, ErrorKind, GraphClient};
+use async_trait::async_trait;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use telamentis_presentation_grpc::telamentis::{
+    order_by::Field as ProtoSortField, query_request::Query as ProtoQuery,
+    tela_mentis_client::TelaMentisClient, AsOfQuery, ExtractRequest, ExtractResponse, FindNodesQuery,
+    FindRelationshipsQuery, HealthCheckRequest, LlmMessage as ProtoLlmMessage, Node as ProtoNode,
+    OrderBy as ProtoOrderBy, Path as ProtoPath, QueryRequest, RawQuery, TimeEdge as ProtoTimeEdge,
+    UpsertEdgeRequest, UpsertNodeRequest,
+};
+use telamentis_presentation_grpc::telamentis::v2::{
+    tela_mentis_client::TelaMentisClient as TelaMentisV2Client, BatchQuery as ProtoBatchQuery, QueryBatchRequest,
+};
+use telamentis_presentation_grpc::{GrpcAdapter, GrpcConfig};
+use tonic::transport::Channel;
+use tonic::{Code, Status};
+
+/// gRPC client for an adapter started by [`serve`]
+pub struct GrpcClient {
+    client: TelaMentisClient<Channel>,
+    /// Client of the v2 service on the same channel, for RPCs only it has
+    v2: TelaMentisV2Client<Channel>,
+}
+
+/// Start the adapter on an ephemeral port in front of `service`
+pub async fn serve(service: Arc<dyn GraphService>) -> Result<GrpcClient, ClientError> {
+    let addr = free_local_addr();
+    let config = GrpcConfig {
+        bind_address: addr,
+        ..Default::default()
+    };
+    spawn_adapter(GrpcAdapter::new(config), service);
+
+    // Connect lazily so the first health check can wait for the server
+    let channel = Channel::from_shared(format!("http://{}", addr))
+        .map_err(ClientError::protocol)?
+        .connect_lazy();
+    let client = GrpcClient {
+        client: TelaMentisClient::new(channel.clone()),
+        v2: TelaMentisV2Client::new(channel),
+    };
+    wait_until_ready(&client).await?;
+    Ok(client)
+}
+
+fn status_to_error(status: Status) -> ClientError {
+    let kind = match status.code() {
+        Code::NotFound => ErrorKind::NotFound,
+        Code::InvalidArgument => ErrorKind::InvalidArgument,
+        Code::PermissionDenied => ErrorKind::PermissionDenied,
+        Code::Unavailable => ErrorKind::Unavailable,
+        _ => ErrorKind::Other,
+    };
+    ClientError::new(kind, status.message())
+}
+
+fn parse_json(text: &str) -> Result<serde_json::Value, ClientError> {
+    serde_json::from_str(text).map_err(ClientError::protocol)
+}
+
+fn parse_uuid(text: &str) -> Result<Uuid, ClientError> {
+    Uuid::parse_str(text).map_err(ClientError::protocol)
+}
+
+fn parse_time(text: &str) -> Result<DateTime<Utc>, ClientError> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(ClientError::protocol)
+}
+
+fn order_by_to_proto(order_by: &[OrderBy]) -> Vec<ProtoOrderBy> {
+    order_by.iter()
+        .map(|key| ProtoOrderBy {
+            field: Some(match &key.field {
+                SortField::Property(name) => ProtoSortField::Property(name.clone()),
+                SortField::CreatedAt => ProtoSortField::CreatedAt(true),
+                SortField::Label => ProtoSortField::Label(true),
+            }),
+            descending: key.direction == SortDirection::Desc,
+        })
+        .collect()
+}
+
+fn query_to_proto(tenant: &str, query: GraphQuery) -> QueryRequest {
+    let query = match query {
+        GraphQuery::Raw { query, params } => ProtoQuery::RawQuery(RawQuery {
+            query_string: query,
+            params_json: serde_json::to_string(&params).unwrap_or_default(),
+        }),
+        GraphQuery::FindNodes { labels, properties, order_by, offset, limit } => ProtoQuery::FindNodesQuery(FindNodesQuery {
+            labels,
+            properties_json: serde_json::to_string(&properties).unwrap_or_default(),
+            limit: limit.map(|l| l as i32),
+            order_by: order_by_to_proto(&order_by),
+            offset: offset.map(|o| o as i32),
+        }),
+        GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse } => {
+            ProtoQuery::FindRelationshipsQuery(FindRelationshipsQuery {
+                from_node_id: from_node_id.map(|id| id.to_string()),
+                to_node_id: to_node_id.map(|id| id.to_string()),
+                relationship_types,
+                valid_at: valid_at.map(|time| time.to_rfc3339()),
+                limit: limit.map(|l| l as i32),
+                order_by: order_by_to_proto(&order_by),
+                offset: offset.map(|o| o as i32),
+                collapse: collapse.map(|collapse| collapse.as_str().to_string()),
+            })
+        }
+        GraphQuery::AsOfQuery { base_query, as_of_time } => ProtoQuery::AsOfQuery(Box::new(AsOfQuery {
+            base_query: Some(Box::new(query_to_proto(tenant, *base_query))),
+            as_of_time: as_of_time.to_rfc3339(),
+        })),
+    };
+
+    QueryRequest {
+        tenant_id: tenant.to_string(),
+        query: Some(query),
+        snapshot: None,
+    }
+}
+
+fn path_from_proto(path: ProtoPath) -> Result<Path, ClientError> {
+    Ok(Path {
+        nodes: path.nodes.into_iter()
+            .map(|node| Ok(PathNode {
+                id: parse_uuid(&node.id)?,
+                labels: node.labels,
+                properties: parse_json(&node.properties_json)?,
+            }))
+            .collect::<Result<_, ClientError>>()?,
+        relationships: path.relationships.into_iter()
+            .map(|rel| Ok(PathRelationship {
+                id: parse_uuid(&rel.id)?,
+                rel_type: rel.rel_type,
+                start_node_id: parse_uuid(&rel.start_node_id)?,
+                end_node_id: parse_uuid(&rel.end_node_id)?,
+                properties: parse_json(&rel.properties_json)?,
+                collapsed: rel.collapsed_json.as_deref()
+                    .map(|json| serde_json::from_str(json).map_err(ClientError::protocol))
+                    .transpose()?,
+            }))
+            .collect::<Result<_, ClientError>>()?,
+    })
+}
+
+fn envelope_from_proto(response: ExtractResponse) -> Result<ExtractionEnvelope, ClientError> {
+    Ok(ExtractionEnvelope {
+        nodes: response.nodes.into_iter()
+            .map(|node| Ok(ExtractionNode {
+                id_alias: node.id_alias,
+                label: node.label,
+                props: parse_json(&node.props_json)?,
+                confidence: node.confidence,
+            }))
+            .collect::<Result<_, ClientError>>()?,
+        relations: response.relations.into_iter()
+            .map(|rel| Ok(ExtractionRelation {
+                from_id_alias: rel.from_id_alias,
+                to_id_alias: rel.to_id_alias,
+                type_label: rel.type_label,
+                props: parse_json(&rel.props_json)?,
+                valid_from: rel.valid_from.as_deref().map(parse_time).transpose()?,
+                valid_to: rel.valid_to.as_deref().map(parse_time).transpose()?,
+                confidence: rel.confidence,
+            }))
+            .collect::<Result<_, ClientError>>()?,
+        metadata: response.metadata.map(|metadata| ExtractionMetadata {
+            provider: metadata.provider,
+            model_name: metadata.model_name,
+            latency_ms: metadata.latency_ms.map(|ms| ms as u64),
+            input_tokens: metadata.input_tokens.map(|t| t as u32),
+            output_tokens: metadata.output_tokens.map(|t| t as u32),
+            cost_usd: metadata.cost_usd,
+            warnings: metadata.warnings,
+            model_selection: None,
+            citations: Vec::new(),
+        }),
+    })
+}
+
+#[async_trait]
+impl GraphClient for GrpcClient {
+    fn name(&self) -> &'static str {
+        "grpc"
+    }
+
+    async fn health(&self) -> Result<(), ClientError> {
+        self.client.clone().health_check(HealthCheckRequest {}).await
+            .map(|_| ())
+            .map_err(status_to_error)
+    }
+
+    async fn upsert_node(&self, tenant: &str, node: Node) -> Result<Uuid, ClientError> {
+        let request = UpsertNodeRequest {
+            tenant_id: tenant.to_string(),
+            node: Some(ProtoNode {
+                id_alias: node.id_alias,
+                label: node.label,
+                props_json: node.props.to_string(),
+                alias_namespace: node.alias_namespace,
+            }),
+            write_concern: None,
+        };
+        let response = self.client.clone().upsert_node(request).await.map_err(status_to_error)?;
+        parse_uuid(&response.into_inner().node_id)
+    }
+
+    async fn upsert_edge(&self, tenant: &str, edge: TimeEdge) -> Result<Uuid, ClientError> {
+        let request = UpsertEdgeRequest {
+            tenant_id: tenant.to_string(),
+            edge: Some(ProtoTimeEdge {
+                from_node_id: edge.from_node_id.to_string(),
+                to_node_id: edge.to_node_id.to_string(),
+                kind: edge.kind,
+                valid_from: edge.valid_from.to_rfc3339(),
+                valid_to: edge.valid_to.map(|time| time.to_rfc3339()),
+                transaction_start_time: edge.transaction_start_time.to_rfc3339(),
+                transaction_end_time: edge.transaction_end_time.map(|time| time.to_rfc3339()),
+                props_json: edge.props.to_string(),
+                expires_at: edge.expires_at.map(|time| time.to_rfc3339()),
+            }),
+            write_concern: None,
+        };
+        let response = self.client.clone().upsert_edge(request).await.map_err(status_to_error)?;
+        parse_uuid(&response.into_inner().edge_id)
+    }
+
+    async fn query(&self, tenant: &str, query: GraphQuery) -> Result<Vec<Path>, ClientError> {
+        let response = self.client.clone().execute_query(query_to_proto(tenant, query)).await.map_err(status_to_error)?;
+        response.into_inner().paths.into_iter().map(path_from_proto).collect()
+    }
+
+    async fn batch_query(&self, tenant: &str, request: BatchQueryRequest) -> Result<BatchQueryResponse, ClientError> {
+        let request = QueryBatchRequest {
+            tenant_id: tenant.to_string(),
+            queries: request.queries.into_iter()
+                .map(|query| ProtoBatchQuery {
+                    query: Some(query_to_proto(tenant, query.query)),
+                    mode: serde_json::to_value(query.mode).ok().and_then(|mode| mode.as_str().map(str::to_string)),
+                })
+                .collect(),
+            timeout_ms: request.timeout_ms,
+        };
+        let response = self.v2.clone().execute_query_batch(request).await.map_err(status_to_error)?.into_inner();
+        Ok(BatchQueryResponse {
+            results: response.results.into_iter()
+                .map(|result| Ok(BatchQueryResult {
+                    paths: result.paths.into_iter().map(path_from_proto).collect::<Result<_, ClientError>>()?,
+                    count: result.count,
+                    exists: result.exists,
+                    error: result.error,
+                    execution_time_ms: result.execution_time_ms as u64,
+                }))
+                .collect::<Result<_, ClientError>>()?,
+            execution_time_ms: response.execution_time_ms as u64,
+        })
+    }
+
+    async fn extract(&self, tenant: &str, context: ExtractionContext) -> Result<ExtractionEnvelope, ClientError> {
+        let request = ExtractRequest {
+            tenant_id: tenant.to_string(),
+            messages: context.messages.into_iter()
+                .map(|message| ProtoLlmMessage { role: message.role, content: message.content })
+                .collect(),
+            system_prompt: context.system_prompt,
+            desired_schema: context.desired_schema,
+            max_tokens: context.max_tokens.map(|t| t as i32),
+            temperature: context.temperature,
+            source_timestamp: None,
+            source_properties_json: None,
+            provider: context.provider,
+            model: context.model,
+        };
+        let response = self.client.clone().extract_knowledge(request).await.map_err(status_to_error)?;
+        envelope_from_proto(response.into_inner())
+    }
+}
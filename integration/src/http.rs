@@ -0,0 +1,117 @@
+//! Client for the REST API of the FastAPI bridge
+
+use crate::{free_local_addr, spawn_adapter, wait_until_ready, ClientError, ErrorKind, GraphClient};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use telamentis_fastapi_bridge::{FastApiBridge, FastApiBridgeConfig};
+
+/// Response wrapper of the REST API
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    data: Option<T>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeResponse {
+    node_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+struct EdgeResponse {
+    edge_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryResponse {
+    paths: Vec<Path>,
+}
+
+/// REST client for a bridge started by [`serve`]
+pub struct HttpClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+/// Start the bridge on an ephemeral port in front of `service`
+pub async fn serve(service: Arc<dyn GraphService>) -> Result<HttpClient, ClientError> {
+    let addr = free_local_addr();
+    let config = FastApiBridgeConfig {
+        bind_address: addr,
+        ..Default::default()
+    };
+    spawn_adapter(FastApiBridge::new(config), service);
+
+    let client = HttpClient {
+        base_url: format!("http://{}", addr),
+        http: reqwest::Client::new(),
+    };
+    wait_until_ready(&client).await?;
+    Ok(client)
+}
+
+impl HttpClient {
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T, ClientError> {
+        let response = self.http.post(format!("{}/v1{}", self.base_url, path))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| ClientError::new(ErrorKind::Unavailable, e.to_string()))?;
+        read_response(response).await
+    }
+}
+
+/// The data of a successful response, or the error it reports
+async fn read_response<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, ClientError> {
+    let status = response.status();
+    let body: ApiResponse<T> = response.json().await.map_err(ClientError::protocol)?;
+    if !status.is_success() {
+        return Err(ClientError::new(
+            ErrorKind::from_status(status.as_u16()),
+            body.error.unwrap_or_else(|| status.to_string()),
+        ));
+    }
+    body.data.ok_or_else(|| ClientError::protocol("response has no data"))
+}
+
+#[async_trait]
+impl GraphClient for HttpClient {
+    fn name(&self) -> &'static str {
+        "rest"
+    }
+
+    async fn health(&self) -> Result<(), ClientError> {
+        let response = self.http.get(format!("{}/health", self.base_url))
+            .send()
+            .await
+            .map_err(|e| ClientError::new(ErrorKind::Unavailable, e.to_string()))?;
+        read_response::<serde_json::Value>(response).await.map(|_| ())
+    }
+
+    async fn upsert_node(&self, tenant: &str, node: Node) -> Result<Uuid, ClientError> {
+        let response: NodeResponse = self.post(&format!("/graph/{}/nodes", tenant), &json!({ "node": node })).await?;
+        Ok(response.node_id)
+    }
+
+    async fn upsert_edge(&self, tenant: &str, edge: TimeEdge) -> Result<Uuid, ClientError> {
+        let response: EdgeResponse = self.post(&format!("/graph/{}/edges", tenant), &json!({ "edge": edge })).await?;
+        Ok(response.edge_id)
+    }
+
+    async fn query(&self, tenant: &str, query: GraphQuery) -> Result<Vec<Path>, ClientError> {
+        let response: QueryResponse = self.post(&format!("/graph/{}/query", tenant), &json!({ "query": query })).await?;
+        Ok(response.paths)
+    }
+
+    async fn batch_query(&self, tenant: &str, request: BatchQueryRequest) -> Result<BatchQueryResponse, ClientError> {
+        self.post(&format!("/graph/{}/query/batch", tenant), &request).await
+    }
+
+    async fn extract(&self, tenant: &str, context: ExtractionContext) -> Result<ExtractionEnvelope, ClientError> {
+        self.post(&format!("/llm/{}/extract", tenant), &context).await
+    }
+}
@@ -0,0 +1,200 @@
+//! End-to-end harness for the presentation adapters
+//!
+//! Each adapter is booted on an ephemeral port, or a socket in a temporary
+//! directory, in front of a [`CoreGraphService`] over an `InMemoryStore` and
+//! the scripted [`MockLlm`]. A [`GraphClient`] per adapter speaks its wire
+//! protocol, so the scenarios in [`scenarios`] run unchanged against REST,
+//! gRPC and UDS and catch adapters that disagree on behavior or on how
+//! errors are reported.
+
+use async_trait::async_trait;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use telamentis_adapter_in_memory::InMemoryStore;
+use telamentis_core::prelude::*;
+
+pub mod grpc;
+pub mod http;
+pub mod scenarios;
+pub mod uds;
+
+/// Provider name the mock connector answers to
+pub const MOCK_PROVIDER: &str = "mock";
+
+/// How long an adapter has to start accepting requests
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Kind of failure reported by an adapter, independent of its protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    NotFound,
+    InvalidArgument,
+    PermissionDenied,
+    Unavailable,
+    Other,
+}
+
+impl ErrorKind {
+    /// Kind of an HTTP-style status code, as returned by REST and UDS
+    pub fn from_status(code: u16) -> Self {
+        match code {
+            404 => ErrorKind::NotFound,
+            400 | 422 => ErrorKind::InvalidArgument,
+            403 => ErrorKind::PermissionDenied,
+            503 => ErrorKind::Unavailable,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// A request that failed, or a response the client could not understand
+#[derive(Debug, Clone)]
+pub struct ClientError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl ClientError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+
+    /// A response that did not match the protocol
+    pub fn protocol(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorKind::Other, format!("Unexpected response: {}", message))
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// The operations the scenarios need, over one adapter's wire protocol
+#[async_trait]
+pub trait GraphClient: Send + Sync {
+    /// Adapter name, for assertion messages
+    fn name(&self) -> &'static str;
+
+    async fn health(&self) -> Result<(), ClientError>;
+
+    async fn upsert_node(&self, tenant: &str, node: Node) -> Result<Uuid, ClientError>;
+
+    async fn upsert_edge(&self, tenant: &str, edge: TimeEdge) -> Result<Uuid, ClientError>;
+
+    async fn query(&self, tenant: &str, query: GraphQuery) -> Result<Vec<Path>, ClientError>;
+
+    async fn batch_query(&self, tenant: &str, request: BatchQueryRequest) -> Result<BatchQueryResponse, ClientError>;
+
+    async fn extract(&self, tenant: &str, context: ExtractionContext) -> Result<ExtractionEnvelope, ClientError>;
+}
+
+/// Connector that extracts one relation per line of the form
+/// `<from> <TYPE> <to>`, such as `alice WORKS_FOR acme`. Requests naming a
+/// provider other than [`MOCK_PROVIDER`] are refused.
+pub struct MockLlm;
+
+#[async_trait]
+impl LlmConnector for MockLlm {
+    async fn extract(&self, _tenant: &TenantId, context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
+        if let Some(provider) = context.provider.as_deref().filter(|p| *p != MOCK_PROVIDER) {
+            return Err(LlmError::ProviderNotAllowed(format!("Provider '{}' is not allowed", provider)));
+        }
+
+        let mut envelope = ExtractionEnvelope {
+            nodes: Vec::new(),
+            relations: Vec::new(),
+            metadata: Some(ExtractionMetadata {
+                provider: MOCK_PROVIDER.to_string(),
+                model_name: MOCK_PROVIDER.to_string(),
+                ..Default::default()
+            }),
+        };
+        let lines = context.messages.iter().flat_map(|message| message.content.lines());
+        for line in lines {
+            let [from, kind, to] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                continue;
+            };
+            for alias in [from, to] {
+                if !envelope.nodes.iter().any(|node| node.id_alias == alias) {
+                    envelope.nodes.push(ExtractionNode {
+                        id_alias: alias.to_string(),
+                        label: "Entity".to_string(),
+                        props: json!({ "name": alias }),
+                        confidence: Some(1.0),
+                    });
+                }
+            }
+            envelope.relations.push(ExtractionRelation {
+                from_id_alias: from.to_string(),
+                to_id_alias: to.to_string(),
+                type_label: kind.to_string(),
+                props: json!({}),
+                valid_from: None,
+                valid_to: None,
+                confidence: Some(1.0),
+            });
+        }
+        Ok(envelope)
+    }
+}
+
+/// A fresh service over an empty in-memory store, extracting with [`MockLlm`]
+pub fn core_service() -> Arc<dyn GraphService> {
+    let store = Arc::new(InMemoryStore::new());
+    Arc::new(CoreGraphService::new(store).with_connector(Arc::new(MockLlm), AvailabilityConfig::default()))
+}
+
+/// A loopback address that was free a moment ago
+pub fn free_local_addr() -> SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("no free local port")
+}
+
+/// Start `adapter` in the background. Adapters that serve in `start` keep
+/// running until the test's runtime shuts down; a failure to start shows up
+/// as [`wait_until_ready`] timing out.
+pub fn spawn_adapter(adapter: impl PresentationAdapter + 'static, service: Arc<dyn GraphService>) {
+    tokio::spawn(async move {
+        if let Err(e) = adapter.start(service).await {
+            eprintln!("Adapter stopped with an error: {}", e);
+        }
+    });
+}
+
+/// Wait until `client` gets a healthy answer from its adapter
+pub async fn wait_until_ready(client: &dyn GraphClient) -> Result<(), ClientError> {
+    let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        match client.health().await {
+            Ok(()) => return Ok(()),
+            Err(e) if tokio::time::Instant::now() >= deadline => {
+                return Err(ClientError::new(e.kind, format!("{} did not start: {}", client.name(), e.message)));
+            }
+            Err(_) => tokio::time::sleep(Duration::from_millis(50)).await,
+        }
+    }
+}
+
+/// Extraction context for the given text
+pub fn extraction_context(text: &str, provider: Option<&str>) -> ExtractionContext {
+    ExtractionContext {
+        messages: vec![LlmMessage::new("user", text)],
+        system_prompt: None,
+        desired_schema: None,
+        max_tokens: None,
+        temperature: None,
+        examples: None,
+        model: None,
+        provider: provider.map(str::to_string),
+        source: None,
+        seed: None,
+        graph_context: None,
+    }
+}
@@ -0,0 +1,178 @@
+//! Scenarios shared by every adapter
+//!
+//! Each scenario works in its own tenant, so scenarios can run against the
+//! same adapter in any order.
+
+use crate::{extraction_context, ErrorKind, GraphClient};
+use chrono::TimeZone;
+use serde_json::json;
+use telamentis_core::prelude::*;
+
+/// Run every scenario against `client`
+pub async fn run_all(client: &dyn GraphClient) {
+    crud(client).await;
+    temporal(client).await;
+    batch_queries(client).await;
+    extraction(client).await;
+    error_mapping(client).await;
+}
+
+fn tenant(scenario: &str) -> String {
+    format!("{}_{}", scenario, Uuid::new_v4().simple())
+}
+
+fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+}
+
+fn find_nodes(label: &str) -> GraphQuery {
+    Query::nodes().label(label).build()
+}
+
+fn find_relationships(from: Uuid, valid_at: Option<DateTime<Utc>>) -> GraphQuery {
+    Query::relationships().from(from).valid_at(valid_at).build()
+}
+
+fn person(alias: &str) -> Node {
+    Node::new("Person").with_id_alias(alias).with_props(json!({ "name": alias }))
+}
+
+/// Node and edge upserts, alias idempotency, queries and tenant isolation
+pub async fn crud(client: &dyn GraphClient) {
+    let name = client.name();
+    let tenant = tenant("crud");
+
+    let alice = client.upsert_node(&tenant, person("alice")).await
+        .unwrap_or_else(|e| panic!("{}: upsert alice: {}", name, e));
+    let again = client.upsert_node(&tenant, person("alice").with_property("age", json!(30))).await
+        .unwrap_or_else(|e| panic!("{}: upsert alice again: {}", name, e));
+    assert_eq!(alice, again, "{}: upserting by alias must keep the node id", name);
+
+    let bob = client.upsert_node(&tenant, person("bob")).await
+        .unwrap_or_else(|e| panic!("{}: upsert bob: {}", name, e));
+    let edge = TimeEdge::new(alice, bob, "KNOWS", date(2020, 1, 1), json!({ "since": 2020 }));
+    let edge_id = client.upsert_edge(&tenant, edge).await
+        .unwrap_or_else(|e| panic!("{}: upsert edge: {}", name, e));
+
+    let people = client.query(&tenant, find_nodes("Person")).await
+        .unwrap_or_else(|e| panic!("{}: find nodes: {}", name, e));
+    assert_eq!(people.len(), 2, "{}: expected both people, got {:?}", name, people);
+    let stored = people.iter()
+        .flat_map(|path| &path.nodes)
+        .find(|node| node.id == alice)
+        .unwrap_or_else(|| panic!("{}: alice missing from {:?}", name, people));
+    assert_eq!(stored.properties["age"], json!(30), "{}: second upsert must update properties", name);
+
+    let relationships = client.query(&tenant, find_relationships(alice, None)).await
+        .unwrap_or_else(|e| panic!("{}: find relationships: {}", name, e));
+    let found: Vec<_> = relationships.iter().flat_map(|path| &path.relationships).collect();
+    assert_eq!(found.len(), 1, "{}: expected one relationship, got {:?}", name, found);
+    assert_eq!(found[0].id, edge_id, "{}", name);
+    assert_eq!(found[0].rel_type, "KNOWS", "{}", name);
+    assert_eq!((found[0].start_node_id, found[0].end_node_id), (alice, bob), "{}", name);
+
+    let other = client.query(&self::tenant("crud_other"), find_nodes("Person")).await
+        .unwrap_or_else(|e| panic!("{}: find nodes in other tenant: {}", name, e));
+    assert!(other.is_empty(), "{}: tenants must not see each other's nodes, got {:?}", name, other);
+}
+
+/// Edges are returned only inside their valid time range
+pub async fn temporal(client: &dyn GraphClient) {
+    let name = client.name();
+    let tenant = tenant("temporal");
+
+    let alice = client.upsert_node(&tenant, person("alice")).await
+        .unwrap_or_else(|e| panic!("{}: upsert alice: {}", name, e));
+    let acme = client.upsert_node(&tenant, Node::new("Company").with_id_alias("acme")).await
+        .unwrap_or_else(|e| panic!("{}: upsert acme: {}", name, e));
+    let globex = client.upsert_node(&tenant, Node::new("Company").with_id_alias("globex")).await
+        .unwrap_or_else(|e| panic!("{}: upsert globex: {}", name, e));
+
+    let past = TimeEdge::new(alice, acme, "WORKS_FOR", date(2020, 1, 1), json!({}))
+        .with_valid_to(date(2021, 1, 1));
+    let past = client.upsert_edge(&tenant, past).await
+        .unwrap_or_else(|e| panic!("{}: upsert past edge: {}", name, e));
+    let current = TimeEdge::new(alice, globex, "WORKS_FOR", date(2021, 1, 1), json!({}));
+    let current = client.upsert_edge(&tenant, current).await
+        .unwrap_or_else(|e| panic!("{}: upsert current edge: {}", name, e));
+
+    for (valid_at, expected) in [(date(2020, 6, 1), past), (Utc::now(), current)] {
+        let paths = client.query(&tenant, find_relationships(alice, Some(valid_at))).await
+            .unwrap_or_else(|e| panic!("{}: find relationships at {}: {}", name, valid_at, e));
+        let ids: Vec<Uuid> = paths.iter().flat_map(|path| &path.relationships).map(|rel| rel.id).collect();
+        assert_eq!(ids, vec![expected], "{}: relationships valid at {}", name, valid_at);
+    }
+
+    let before = client.query(&tenant, find_relationships(alice, Some(date(2019, 1, 1)))).await
+        .unwrap_or_else(|e| panic!("{}: find relationships before: {}", name, e));
+    assert!(before.iter().all(|path| path.relationships.is_empty()), "{}: no edge was valid in 2019", name);
+}
+
+/// A batch returns a result per query in order, failing queries on their own
+pub async fn batch_queries(client: &dyn GraphClient) {
+    let name = client.name();
+    let tenant = tenant("batch");
+
+    for alias in ["alice", "bob"] {
+        client.upsert_node(&tenant, person(alias)).await
+            .unwrap_or_else(|e| panic!("{}: upsert {}: {}", name, alias, e));
+    }
+
+    let request = BatchQueryRequest {
+        queries: vec![
+            BatchQuery::new(find_nodes("Person")),
+            BatchQuery::new(GraphQuery::Raw { query: "MATCH (n) RETURN n".to_string(), params: Default::default() }),
+            BatchQuery::new(find_nodes("Person")).with_mode(QueryMode::Count),
+            BatchQuery::new(find_nodes("Company")).with_mode(QueryMode::Exists),
+        ],
+        timeout_ms: None,
+    };
+    let response = client.batch_query(&tenant, request).await
+        .unwrap_or_else(|e| panic!("{}: batch query: {}", name, e));
+    let results = &response.results;
+    assert_eq!(results.len(), 4, "{}: expected a result per query, got {:?}", name, results);
+    assert_eq!(results[0].paths.len(), 2, "{}: {:?}", name, results[0]);
+    assert!(results[1].error.is_some(), "{}: raw queries must fail on the in-memory store", name);
+    assert_eq!(results[2].count, Some(2), "{}: {:?}", name, results[2]);
+    assert_eq!(results[3].exists, Some(false), "{}: {:?}", name, results[3]);
+
+    let error = client.batch_query(&tenant, BatchQueryRequest::default()).await
+        .expect_err(&format!("{}: an empty batch must fail", name));
+    assert_eq!(error.kind, ErrorKind::InvalidArgument, "{}: {}", name, error);
+}
+
+/// Extraction reaches the connector and returns its envelope
+pub async fn extraction(client: &dyn GraphClient) {
+    let name = client.name();
+    let tenant = tenant("extraction");
+
+    let context = extraction_context("alice WORKS_FOR acme\nbob WORKS_FOR acme", Some(crate::MOCK_PROVIDER));
+    let envelope = client.extract(&tenant, context).await
+        .unwrap_or_else(|e| panic!("{}: extract: {}", name, e));
+
+    let mut aliases: Vec<_> = envelope.nodes.iter().map(|node| node.id_alias.as_str()).collect();
+    aliases.sort_unstable();
+    assert_eq!(aliases, ["acme", "alice", "bob"], "{}", name);
+    assert_eq!(envelope.relations.len(), 2, "{}", name);
+    assert!(envelope.relations.iter().all(|rel| rel.type_label == "WORKS_FOR" && rel.to_id_alias == "acme"), "{}", name);
+    let provider = envelope.metadata.map(|metadata| metadata.provider);
+    assert_eq!(provider.as_deref(), Some(crate::MOCK_PROVIDER), "{}", name);
+}
+
+/// Core errors reach the client as the matching protocol error
+pub async fn error_mapping(client: &dyn GraphClient) {
+    let name = client.name();
+    let tenant = tenant("errors");
+
+    let alice = client.upsert_node(&tenant, person("alice")).await
+        .unwrap_or_else(|e| panic!("{}: upsert alice: {}", name, e));
+    let dangling = TimeEdge::new(alice, Uuid::new_v4(), "KNOWS", date(2020, 1, 1), json!({}));
+    let error = client.upsert_edge(&tenant, dangling).await
+        .expect_err(&format!("{}: edge to a missing node must fail", name));
+    assert_eq!(error.kind, ErrorKind::NotFound, "{}: {}", name, error);
+
+    let context = extraction_context("alice WORKS_FOR acme", Some("forbidden"));
+    let error = client.extract(&tenant, context).await
+        .expect_err(&format!("{}: extraction with a refused provider must fail", name));
+    assert_eq!(error.kind, ErrorKind::PermissionDenied, "{}: {}", name, error);
+}
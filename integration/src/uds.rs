@@ -0,0 +1,142 @@
+//! Client for the Unix Domain Socket adapter
+
+use crate::{spawn_adapter, wait_until_ready, ClientError, ErrorKind, GraphClient};
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use telamentis_presentation_uds::protocol::{self, Request, Response};
+use telamentis_presentation_uds::{ClientCodec, UdsAdapter, UdsConfig};
+use tempfile::TempDir;
+use tokio::net::UnixStream;
+use tokio::sync::Mutex;
+use tokio_util::codec::Framed;
+
+/// UDS client for an adapter started by [`serve`]
+pub struct UdsClient {
+    socket_path: PathBuf,
+    max_message_size: usize,
+    /// Connection, opened on first use
+    connection: Mutex<Option<Framed<UnixStream, ClientCodec>>>,
+    /// Keeps the socket's directory until the client is dropped
+    _dir: TempDir,
+}
+
+/// Start the adapter on a socket in a temporary directory in front of `service`
+pub async fn serve(service: Arc<dyn GraphService>) -> Result<UdsClient, ClientError> {
+    let dir = tempfile::tempdir().map_err(|e| ClientError::new(ErrorKind::Other, e.to_string()))?;
+    let config = UdsConfig {
+        socket_path: dir.path().join("telamentis.sock"),
+        ..Default::default()
+    };
+    let client = UdsClient {
+        socket_path: config.socket_path.clone(),
+        max_message_size: config.max_message_size,
+        connection: Mutex::new(None),
+        _dir: dir,
+    };
+    spawn_adapter(UdsAdapter::new(config), service);
+
+    wait_until_ready(&client).await?;
+    Ok(client)
+}
+
+/// Convert between core and protocol types, which mirror each other field
+/// for field
+fn convert<A: Serialize, B: DeserializeOwned>(value: A) -> Result<B, ClientError> {
+    serde_json::to_value(value)
+        .and_then(serde_json::from_value)
+        .map_err(ClientError::protocol)
+}
+
+impl UdsClient {
+    async fn call(&self, request: Request) -> Result<Response, ClientError> {
+        let mut connection = self.connection.lock().await;
+        if connection.is_none() {
+            let stream = UnixStream::connect(&self.socket_path).await
+                .map_err(|e| ClientError::new(ErrorKind::Unavailable, e.to_string()))?;
+            *connection = Some(Framed::new(stream, ClientCodec::new(self.max_message_size)));
+        }
+        let framed = connection.as_mut().expect("connection was just opened");
+
+        let sent = framed.send(request).await;
+        let received = match sent {
+            Ok(()) => framed.next().await,
+            Err(e) => Some(Err(e)),
+        };
+        match received {
+            Some(Ok(Response::Error(error))) => Err(ClientError::new(ErrorKind::from_status(error.code), error.message)),
+            Some(Ok(response)) => Ok(response),
+            Some(Err(e)) => {
+                *connection = None;
+                Err(ClientError::new(ErrorKind::Unavailable, e.to_string()))
+            }
+            None => {
+                *connection = None;
+                Err(ClientError::new(ErrorKind::Unavailable, "Connection closed"))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl GraphClient for UdsClient {
+    fn name(&self) -> &'static str {
+        "uds"
+    }
+
+    async fn health(&self) -> Result<(), ClientError> {
+        match self.call(Request::HealthCheck).await? {
+            Response::HealthCheck { .. } => Ok(()),
+            other => Err(ClientError::protocol(format!("{:?}", other))),
+        }
+    }
+
+    async fn upsert_node(&self, tenant: &str, node: Node) -> Result<Uuid, ClientError> {
+        let node = protocol::Node {
+            id_alias: node.id_alias,
+            alias_namespace: node.alias_namespace,
+            label: node.label,
+            props: node.props,
+        };
+        match self.call(Request::UpsertNode { tenant_id: tenant.to_string(), node, write_concern: WriteConcern::Committed }).await? {
+            Response::UpsertNode { node_id, .. } => Ok(node_id),
+            other => Err(ClientError::protocol(format!("{:?}", other))),
+        }
+    }
+
+    async fn upsert_edge(&self, tenant: &str, edge: TimeEdge) -> Result<Uuid, ClientError> {
+        let edge = convert(edge)?;
+        match self.call(Request::UpsertEdge { tenant_id: tenant.to_string(), edge, write_concern: WriteConcern::Committed }).await? {
+            Response::UpsertEdge { edge_id, .. } => Ok(edge_id),
+            other => Err(ClientError::protocol(format!("{:?}", other))),
+        }
+    }
+
+    async fn query(&self, tenant: &str, query: GraphQuery) -> Result<Vec<Path>, ClientError> {
+        let query = convert(query)?;
+        match self.call(Request::ExecuteQuery { tenant_id: tenant.to_string(), query, mode: QueryMode::Full }).await? {
+            Response::ExecuteQuery { paths, .. } => convert(paths),
+            other => Err(ClientError::protocol(format!("{:?}", other))),
+        }
+    }
+
+    async fn batch_query(&self, tenant: &str, request: BatchQueryRequest) -> Result<BatchQueryResponse, ClientError> {
+        let queries = convert(request.queries)?;
+        match self.call(Request::ExecuteQueryBatch { tenant_id: tenant.to_string(), queries, timeout_ms: request.timeout_ms }).await? {
+            Response::ExecuteQueryBatch { results, execution_time_ms } => Ok(BatchQueryResponse { results: convert(results)?, execution_time_ms }),
+            other => Err(ClientError::protocol(format!("{:?}", other))),
+        }
+    }
+
+    async fn extract(&self, tenant: &str, context: ExtractionContext) -> Result<ExtractionEnvelope, ClientError> {
+        let context = convert(context)?;
+        match self.call(Request::ExtractKnowledge { tenant_id: tenant.to_string(), context }).await? {
+            Response::ExtractKnowledge { envelope } => convert(envelope),
+            other => Err(ClientError::protocol(format!("{:?}", other))),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! Runs the shared scenarios against every presentation adapter
+
+use telamentis_integration_tests::{core_service, grpc, http, scenarios, uds};
+
+#[tokio::test]
+async fn rest_adapter() {
+    let client = http::serve(core_service()).await.unwrap();
+    scenarios::run_all(&client).await;
+}
+
+#[tokio::test]
+async fn grpc_adapter() {
+    let client = grpc::serve(core_service()).await.unwrap();
+    scenarios::run_all(&client).await;
+}
+
+#[tokio::test]
+async fn uds_adapter() {
+    let client = uds::serve(core_service()).await.unwrap();
+    scenarios::run_all(&client).await;
+}
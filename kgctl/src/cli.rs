@@ -1,7 +1,10 @@
 //! CLI argument definitions
 
+use crate::completion;
 use clap::{Parser, Subcommand, Args};
+use clap_complete::ArgValueCompleter;
 use std::path::PathBuf;
+use telamentis_core::rdf::RdfSyntax;
 
 #[derive(Parser)]
 #[command(name = "kgctl")]
@@ -17,9 +20,13 @@ pub struct Cli {
     pub endpoint: Option<String>,
 
     /// Default tenant ID
-    #[arg(short, long, global = true)]
+    #[arg(short, long, global = true, add = ArgValueCompleter::new(completion::tenants))]
     pub tenant: Option<String>,
 
+    /// Context from the configuration file to use
+    #[arg(long, global = true, add = ArgValueCompleter::new(completion::contexts))]
+    pub context: Option<String>,
+
     /// Increase verbosity (-v, -vv, -vvv)
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     pub verbose: u8,
@@ -58,8 +65,158 @@ pub enum Commands {
         #[command(subcommand)]
         command: QueryCommands,
     },
+    /// Edge correction operations
+    Edge {
+        #[command(subcommand)]
+        command: EdgeCommands,
+    },
+    /// Materialized snapshot operations
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+    /// Archival of closed history to object storage
+    Archive {
+        #[command(subcommand)]
+        command: ArchiveCommands,
+    },
+    /// Tenant API token management
+    Token {
+        #[command(subcommand)]
+        command: TokenCommands,
+    },
+    /// Re-execute a captured request against a target environment
+    Replay {
+        /// ID of the captured request, as returned in `X-Request-Id`
+        request_id: String,
+        /// Tenant ID the request was captured for
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Endpoint to replay against; defaults to the configured endpoint
+        #[arg(long)]
+        target: Option<String>,
+        /// Tenant to replay as, if different from the captured tenant
+        #[arg(long, add = ArgValueCompleter::new(completion::tenants))]
+        target_tenant: Option<String>,
+        /// Show the captured request without sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Few-shot extraction example management
+    Examples {
+        #[command(subcommand)]
+        command: ExamplesCommands,
+    },
+    /// Dead-letter queue of failed ingestion records
+    Dlq {
+        #[command(subcommand)]
+        command: DlqCommands,
+    },
+    /// Activity of an entity over time: its edges created and closed, bucketed
+    Timeline {
+        /// Alias of the node
+        alias: String,
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Namespace of the alias
+        #[arg(long)]
+        namespace: Option<String>,
+        /// Width of the buckets
+        #[arg(short, long, value_enum, default_value = "day")]
+        interval: TimelineInterval,
+        /// Start of the range; the last 30 buckets if omitted
+        #[arg(long)]
+        from: Option<String>,
+        /// End of the range; now if omitted
+        #[arg(long)]
+        to: Option<String>,
+        /// Count only edges of this relationship type (repeatable)
+        #[arg(long = "type")]
+        types: Vec<String>,
+        /// List the edges created and closed in each bucket
+        #[arg(long)]
+        events: bool,
+    },
+    /// How properties of an entity changed over time
+    History {
+        #[command(subcommand)]
+        command: HistoryCommands,
+    },
+    /// Long-running queries, exports and extractions in flight
+    Operations {
+        #[command(subcommand)]
+        command: OperationsCommands,
+    },
+    /// Exchange a tenant's changes with another instance
+    Sync {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Context of the instance to sync with
+        #[arg(long, add = ArgValueCompleter::new(completion::contexts))]
+        peer: String,
+        /// Only apply the peer's changes here
+        #[arg(long, conflicts_with = "push_only")]
+        pull_only: bool,
+        /// Only apply this instance's changes to the peer
+        #[arg(long)]
+        push_only: bool,
+    },
+    /// Documentation of a tenant's schema
+    Schema {
+        #[command(subcommand)]
+        command: SchemaCommands,
+    },
+    /// Schema migrations of the graph store
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommands,
+    },
+    /// Reproducible graph states from fixture files, on servers in dev mode
+    Seed {
+        #[command(subcommand)]
+        command: SeedCommands,
+    },
+    /// Reports computed from exports
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+    /// Operator controls of the server, authorized by the admin secret
+    Admin {
+        #[command(subcommand)]
+        command: AdminCommands,
+    },
     /// Health check
     Health,
+    /// Check the configuration and connections to the API and store
+    Doctor,
+    /// Configuration contexts
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Print a shell completion script
+    Completion {
+        /// Shell to complete for
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// List the contexts in the configuration file
+    List,
+    /// Make a context the current one
+    UseContext {
+        /// Context name
+        #[arg(add = ArgValueCompleter::new(completion::contexts))]
+        name: String,
+    },
+    /// Show the current context
+    CurrentContext,
 }
 
 #[derive(Subcommand)]
@@ -83,11 +240,25 @@ pub enum TenantCommands {
     /// Describe a specific tenant
     Describe {
         /// Tenant ID
+        #[arg(add = ArgValueCompleter::new(completion::tenants))]
+        tenant_id: String,
+    },
+    /// Show node and edge counts of a tenant's graph
+    Stats {
+        /// Tenant ID
+        #[arg(add = ArgValueCompleter::new(completion::tenants))]
+        tenant_id: String,
+    },
+    /// List the labels and relationship kinds in a tenant's graph
+    Catalog {
+        /// Tenant ID
+        #[arg(add = ArgValueCompleter::new(completion::tenants))]
         tenant_id: String,
     },
     /// Delete a tenant
     Delete {
         /// Tenant ID
+        #[arg(add = ArgValueCompleter::new(completion::tenants))]
         tenant_id: String,
         /// Force deletion without confirmation
         #[arg(long)]
@@ -103,7 +274,7 @@ pub enum IngestCommands {
         #[arg(short, long, required = true)]
         file: Vec<PathBuf>,
         /// Tenant ID
-        #[arg(short, long)]
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
         tenant: Option<String>,
         /// Data type: node or relationship
         #[arg(short = 'T', long, value_enum, default_value = "node")]
@@ -117,6 +288,9 @@ pub enum IngestCommands {
         /// ID column name or index (for nodes)
         #[arg(long)]
         id_col: Option<String>,
+        /// Namespace for id aliases, e.g. the source system (avoids collisions across sources)
+        #[arg(long)]
+        alias_namespace: Option<String>,
         /// Label column name or index (for nodes)
         #[arg(long)]
         label_col: Option<String>,
@@ -150,6 +324,38 @@ pub enum IngestCommands {
         /// Batch size for bulk operations
         #[arg(long, default_value = "100")]
         batch_size: usize,
+        /// Pin the type of a property column, as COLUMN=TYPE with TYPE one of
+        /// string, integer, float, boolean, json or timestamp; overrides the
+        /// tenant's ingestion schema (repeatable)
+        #[arg(long = "column-type", value_name = "COLUMN=TYPE")]
+        column_types: Vec<String>,
+        /// Only infer types that read back as written: `01234` and `yes`
+        /// stay strings
+        #[arg(long)]
+        strict: bool,
+        /// Map columns with the tenant's server-side ingestion template of
+        /// this name instead of the column flags
+        #[arg(long, conflicts_with_all = ["data_type", "id_col", "alias_namespace", "label_col", "label", "props_cols", "from_col", "to_col", "rel_type_val", "rel_type_col", "valid_from_col", "valid_to_col", "date_format", "column_types"])]
+        template: Option<String>,
+    },
+    /// Restore a JSONL export, checking its signed manifest before loading
+    /// anything
+    Restore {
+        /// Export data file
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Manifest file (defaults to <input>.manifest.json)
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// File containing the export key (overrides export_keys in config)
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+        /// Batch size for bulk operations
+        #[arg(long, default_value = "100")]
+        batch_size: usize,
     },
 }
 
@@ -158,9 +364,10 @@ pub enum ExportCommands {
     /// Export tenant data
     Export {
         /// Tenant ID
-        #[arg(short, long)]
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
         tenant: Option<String>,
-        /// Output file path (stdout if not specified)
+        /// Output file path (stdout if not specified); a directory for
+        /// arrow and parquet
         #[arg(short, long)]
         output: Option<PathBuf>,
         /// Export format
@@ -172,9 +379,37 @@ pub enum ExportCommands {
         /// Include edges
         #[arg(long, default_value = "true")]
         include_edges: bool,
-        /// Export as of specific time (ISO8601)
+        /// Export as of specific time (ISO8601 timestamp, or a date such as 2020-03)
         #[arg(long)]
         temporal_as_of: Option<String>,
+        /// Describe edges as RDF statements with their times and properties
+        /// (turtle and ntriples; overrides `rdf.reify_edges` in config)
+        #[arg(long)]
+        reify_edges: bool,
+        /// Encrypt the export with the tenant's export key
+        #[arg(long)]
+        encrypt: bool,
+        /// File containing the export key (overrides export_keys in config)
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+    },
+    /// Verify a signed export and decrypt it if needed
+    Verify {
+        /// Export data file
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Manifest file (defaults to <input>.manifest.json)
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+        /// Tenant ID (defaults to the tenant in the manifest)
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// File containing the export key (overrides export_keys in config)
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+        /// Where to write the verified plaintext (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 }
 
@@ -183,7 +418,7 @@ pub enum QueryCommands {
     /// Execute a raw query
     Raw {
         /// Tenant ID
-        #[arg(short, long)]
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
         tenant: Option<String>,
         /// Query string
         query: String,
@@ -194,7 +429,7 @@ pub enum QueryCommands {
     /// Find nodes
     Nodes {
         /// Tenant ID
-        #[arg(short, long)]
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
         tenant: Option<String>,
         /// Node labels to search for
         #[arg(short, long)]
@@ -202,14 +437,23 @@ pub enum QueryCommands {
         /// Property filters (key=value)
         #[arg(short, long)]
         properties: Vec<String>,
+        /// Sort keys (created_at, label or a property name, with optional :asc or :desc)
+        #[arg(long)]
+        order_by: Vec<String>,
+        /// Number of results to skip
+        #[arg(long)]
+        offset: Option<u32>,
         /// Maximum results
         #[arg(short, long)]
         limit: Option<u32>,
+        /// Query this materialized snapshot instead of the live graph
+        #[arg(long)]
+        snapshot: Option<String>,
     },
     /// Find relationships
     Relationships {
         /// Tenant ID
-        #[arg(short, long)]
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
         tenant: Option<String>,
         /// From node ID
         #[arg(long)]
@@ -220,13 +464,436 @@ pub enum QueryCommands {
         /// Relationship types
         #[arg(short, long)]
         types: Vec<String>,
-        /// Valid at time (ISO8601)
+        /// Valid at time (ISO8601 timestamp, or a date such as 2020-03)
         #[arg(long)]
         valid_at: Option<String>,
+        /// Sort keys (created_at, label or a property name, with optional :asc or :desc)
+        #[arg(long)]
+        order_by: Vec<String>,
+        /// Number of results to skip
+        #[arg(long)]
+        offset: Option<u32>,
         /// Maximum results
         #[arg(short, long)]
         limit: Option<u32>,
+        /// Query this materialized snapshot instead of the live graph
+        #[arg(long)]
+        snapshot: Option<String>,
+    },
+    /// Run a query defined in a YAML file
+    File {
+        /// Query file
+        path: PathBuf,
+        /// Tenant ID (overrides the tenant in the file)
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Parameter values (name=value)
+        #[arg(short, long = "param", value_name = "NAME=VALUE")]
+        params: Vec<String>,
+        /// Query this materialized snapshot instead of the live graph (overrides the file)
+        #[arg(long)]
+        snapshot: Option<String>,
+        /// Print the resolved query without running it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Run several queries in one request, from a YAML or JSON file
+    Batch {
+        /// Batch file
+        path: PathBuf,
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Deadline for the whole batch in milliseconds (overrides the file)
+        #[arg(long)]
+        timeout_ms: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EdgeCommands {
+    /// End an edge in valid time, e.g. when a fact stopped being true
+    Close {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Edge ID
+        edge_id: String,
+        /// When the fact stopped being true (ISO8601, or a date such as 2020-03 for the end of that month)
+        #[arg(long)]
+        valid_to: String,
+    },
+    /// Replace an edge with a corrected one
+    Supersede {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Edge ID
+        edge_id: String,
+        /// From node ID of the corrected edge
+        #[arg(long)]
+        from: String,
+        /// To node ID of the corrected edge
+        #[arg(long)]
+        to: String,
+        /// Relationship type of the corrected edge
+        #[arg(short, long)]
+        kind: String,
+        /// Valid from time of the corrected edge (ISO8601 or date)
+        #[arg(long)]
+        valid_from: String,
+        /// Valid to time of the corrected edge (ISO8601, or a date such as 2020-03 for the end of that month)
+        #[arg(long)]
+        valid_to: Option<String>,
+        /// Properties of the corrected edge (JSON object)
+        #[arg(short, long)]
+        props: Option<String>,
+    },
+    /// Retract an edge that was never true
+    Retract {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Edge ID
+        edge_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SchemaCommands {
+    /// Document the labels, relationship kinds and properties in use
+    Docs {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Output file path (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Document format
+        #[arg(short = 'F', long, value_enum, default_value = "markdown")]
+        format: DocFormat,
+        /// Nodes per label and relationships per kind to sample
+        #[arg(long)]
+        sample: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HistoryCommands {
+    /// Values a property of a node took, with when each held
+    Prop {
+        /// Alias of the node
+        alias: String,
+        /// Property key
+        key: String,
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Namespace of the alias
+        #[arg(long)]
+        namespace: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// Materialize the graph as it was valid at a point in time
+    Create {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Snapshot name
+        name: String,
+        /// Valid time to freeze the graph at (ISO8601 timestamp, or a date such as 2020-03)
+        #[arg(long)]
+        valid_at: String,
+    },
+    /// List a tenant's snapshots
+    List {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+    },
+    /// Drop a snapshot
+    Drop {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Snapshot name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ArchiveCommands {
+    /// Archive history that ended before a cutoff
+    Run {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Archive history that ended before this time (ISO8601)
+        #[arg(long)]
+        before: String,
+        /// Remove the archived history from the store
+        #[arg(long)]
+        purge: bool,
+    },
+    /// List a tenant's archive segments
+    List {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+    },
+    /// Restore archived history that ended within a time range
+    Restore {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Start of the range (ISO8601, inclusive)
+        #[arg(long)]
+        from: String,
+        /// End of the range (ISO8601, exclusive)
+        #[arg(long)]
+        to: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TokenCommands {
+    /// Create a token; its secret is shown once
+    Create {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Token name, such as the client that will use it
+        name: String,
+        /// What the token may do within the tenant
+        #[arg(long, value_enum, default_value = "read")]
+        scope: TokenScope,
+        /// Hours until the token expires; it does not expire if omitted
+        #[arg(long)]
+        ttl_hours: Option<i64>,
+    },
+    /// List a tenant's tokens
+    List {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+    },
+    /// Revoke a token immediately
+    Revoke {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Token ID
+        token_id: String,
+    },
+    /// Replace a token with a new secret of the same name and scope
+    Rotate {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Token ID
+        token_id: String,
+        /// Hours the old secret keeps working
+        #[arg(long, default_value_t = 1)]
+        overlap_hours: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExamplesCommands {
+    /// List a tenant's extraction examples
+    List {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+    },
+    /// Add extraction examples from a JSON file (one example or an array)
+    Add {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// JSON file with `input` and `expected` fields
+        file: PathBuf,
+    },
+    /// Replace all of a tenant's extraction examples with those in a JSON file
+    Set {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// JSON file with an array of examples
+        file: PathBuf,
+    },
+    /// Remove an extraction example
+    Remove {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Example ID
+        example_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DlqCommands {
+    /// List a tenant's failed ingestion records
+    List {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+    },
+    /// Show a failed record with its error and context
+    Show {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Dead letter ID
+        entry_id: String,
+    },
+    /// Write failed records again; those that succeed leave the queue
+    Retry {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Dead letter IDs; all of the tenant's if omitted
+        entry_ids: Vec<String>,
+    },
+    /// Drop failed records without retrying them
+    Purge {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Dead letter IDs; all of the tenant's if omitted
+        entry_ids: Vec<String>,
+        /// Purge without confirmation
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OperationsCommands {
+    /// List a tenant's operations in flight, oldest first
+    List {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+    },
+    /// Cancel an operation; its request fails and its store or LLM call is abandoned
+    Cancel {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Operation ID
+        operation_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SeedCommands {
+    /// Write a fixture's nodes and edges
+    Apply {
+        /// Fixture file, YAML or JSON
+        file: PathBuf,
+        /// Clear the fixture's tenants first
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Clear every tenant of a fixture
+    Teardown {
+        /// Fixture file, YAML or JSON
+        file: PathBuf,
+        /// Tear down without confirmation
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Nodes and edges added, removed and changed between two JSONL exports
+    Drift {
+        /// Tenant ID
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+        /// Earlier export, from `kgctl export --format jsonl`
+        #[arg(long)]
+        baseline: PathBuf,
+        /// Later export of the same tenant
+        #[arg(long)]
+        current: PathBuf,
+        /// Also write the report as JSON to this file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AdminCommands {
+    /// Show whether the server drains, and its jobs and LLM providers
+    Status,
+    /// List the plugins of the request pipeline
+    Plugins,
+    /// Run a pipeline plugin again
+    EnablePlugin {
+        /// Plugin name
+        name: String,
+    },
+    /// Stop running a pipeline plugin, in every tenant's pipeline
+    DisablePlugin {
+        /// Plugin name
+        name: String,
+    },
+    /// Switch a tenant's default LLM provider
+    SetProvider {
+        /// Tenant ID
+        #[arg(add = ArgValueCompleter::new(completion::tenants))]
+        tenant_id: String,
+        /// Provider to use; the server's default if omitted
+        provider: Option<String>,
+    },
+    /// Drop cached query results
+    FlushCache {
+        /// Tenant ID; every tenant if omitted
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+    },
+    /// Run a maintenance job, such as retention
+    RunJob {
+        /// Job name, as listed by `kgctl admin status`
+        name: String,
+        /// Tenant ID; every tenant if omitted
+        #[arg(short, long, add = ArgValueCompleter::new(completion::tenants))]
+        tenant: Option<String>,
+    },
+    /// Show how far a maintenance job, such as a backfill, got in each tenant
+    JobProgress {
+        /// Job name, as listed by `kgctl admin status`
+        name: String,
+    },
+    /// Refuse new requests and wait for those in flight, before shutdown
+    Drain {
+        /// Longest to wait for requests in flight, in seconds
+        #[arg(long, default_value = "30")]
+        timeout: u64,
     },
+    /// Accept requests again after a drain
+    Resume,
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommands {
+    /// Show the applied and pending migrations
+    Status,
+    /// Apply pending migrations in order
+    Apply {
+        /// Version to migrate to; the latest if omitted
+        #[arg(long)]
+        target: Option<u32>,
+    },
+    /// Compare the indexes in the database with those the migrations create
+    Verify,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -236,12 +903,39 @@ pub enum IsolationModel {
     Label,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum TokenScope {
+    Read,
+    Write,
+    Admin,
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub enum DataType {
     Node,
     Relationship,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum TimelineInterval {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub enum OutputFormat {
     Table,
@@ -256,6 +950,38 @@ pub enum ExportFormat {
     Jsonl,
     Cypher,
     Csv,
+    /// Arrow IPC streams, one per table
+    Arrow,
+    /// Parquet files, one per table
+    Parquet,
+    /// RDF Turtle
+    Turtle,
+    /// RDF N-Triples
+    #[value(name = "ntriples")]
+    NTriples,
+}
+
+impl OutputFormat {
+    /// Whether output is meant for people rather than scripts
+    pub fn is_table(&self) -> bool {
+        matches!(self, OutputFormat::Table)
+    }
+}
+
+impl ExportFormat {
+    /// Whether the format is written as a nodes table and an edges table
+    pub fn is_columnar(&self) -> bool {
+        matches!(self, ExportFormat::Arrow | ExportFormat::Parquet)
+    }
+
+    /// RDF serialization of the format, if it is one
+    pub fn rdf_syntax(&self) -> Option<RdfSyntax> {
+        match self {
+            ExportFormat::Turtle => Some(RdfSyntax::Turtle),
+            ExportFormat::NTriples => Some(RdfSyntax::NTriples),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for IsolationModel {
@@ -286,6 +1012,10 @@ impl std::fmt::Display for ExportFormat {
             ExportFormat::Jsonl => write!(f, "jsonl"),
             ExportFormat::Cypher => write!(f, "cypher"),
             ExportFormat::Csv => write!(f, "csv"),
+            ExportFormat::Arrow => write!(f, "arrow"),
+            ExportFormat::Parquet => write!(f, "parquet"),
+            ExportFormat::Turtle => write!(f, "turtle"),
+            ExportFormat::NTriples => write!(f, "ntriples"),
         }
     }
 }
\ No newline at end of file
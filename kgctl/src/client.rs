@@ -1,9 +1,11 @@
 //! HTTP client for TelaMentis API
 
 use crate::config::KgctlConfig;
+use colored::*;
 use reqwest::{Client, Response};
 use serde::{Deserialize, Serialize};
 use telamentis_core::errors::CoreError;
+use telamentis_core::types::{EdgeSpec, Node, NodeWithEdges, TenantId};
 use tracing::{debug, error};
 
 /// API client for TelaMentis
@@ -15,9 +17,10 @@ pub struct TelaMentisClient {
 impl TelaMentisClient {
     /// Create a new API client
     pub fn new(config: KgctlConfig) -> Result<Self, CoreError> {
-        let client = Client::builder()
+        let builder = Client::builder()
             .timeout(std::time::Duration::from_secs(config.timeout))
-            .default_headers(config.auth_headers())
+            .default_headers(config.auth_headers());
+        let client = config.apply_network(builder)?
             .build()
             .map_err(|e| CoreError::Internal(format!("Failed to create HTTP client: {}", e)))?;
 
@@ -49,6 +52,27 @@ impl TelaMentisClient {
             .map_err(|e| CoreError::Internal(format!("HTTP POST failed: {}", e)))
     }
 
+    /// Make a POST request with a newline-delimited JSON body, one item per line
+    pub async fn post_ndjson<T: Serialize>(&self, path: &str, items: &[T]) -> Result<Response, CoreError> {
+        let url = self.config.api_url(path);
+        debug!("POST {} ({} lines)", url, items.len());
+
+        let mut body = String::new();
+        for item in items {
+            let line = serde_json::to_string(item)
+                .map_err(|e| CoreError::Internal(format!("Failed to serialize line: {}", e)))?;
+            body.push_str(&line);
+            body.push('\n');
+        }
+        self.client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| CoreError::Internal(format!("HTTP POST failed: {}", e)))
+    }
+
     /// Make a PUT request with JSON body
     pub async fn put<T: Serialize>(&self, path: &str, body: &T) -> Result<Response, CoreError> {
         let url = self.config.api_url(path);
@@ -74,6 +98,21 @@ impl TelaMentisClient {
             .map_err(|e| CoreError::Internal(format!("HTTP DELETE failed: {}", e)))
     }
 
+    /// Make a request with any method and an optional JSON body
+    pub async fn send(&self, method: reqwest::Method, path: &str, body: Option<&serde_json::Value>) -> Result<Response, CoreError> {
+        let url = self.config.api_url(path);
+        debug!("{} {}", method, url);
+
+        let mut request = self.client.request(method.clone(), &url);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| CoreError::Internal(format!("HTTP {} failed: {}", method, e)))
+    }
+
     /// Handle API response, checking status and parsing JSON
     pub async fn handle_response<T: for<'de> Deserialize<'de>>(
         &self,
@@ -82,9 +121,16 @@ impl TelaMentisClient {
         let status = response.status();
         
         if status.is_success() {
-            response
-                .json::<T>()
+            let body: serde_json::Value = response
+                .json()
                 .await
+                .map_err(|e| CoreError::Internal(format!("Failed to parse JSON response: {}", e)))?;
+            // Warnings go to stderr so that stdout only carries the result
+            let warnings = body.get("warnings").and_then(|w| w.as_array()).into_iter().flatten();
+            for warning in warnings.filter_map(|w| w.as_str()) {
+                eprintln!("{} {}", "Warning:".yellow(), warning);
+            }
+            serde_json::from_value(body)
                 .map_err(|e| CoreError::Internal(format!("Failed to parse JSON response: {}", e)))
         } else {
             let error_text = response
@@ -105,6 +151,22 @@ impl TelaMentisClient {
         }
     }
 
+    /// Upsert a node together with edges to existing nodes in a single request
+    pub async fn upsert_node_with_edges(
+        &self,
+        tenant: &TenantId,
+        node: &Node,
+        edges: &[EdgeSpec],
+    ) -> Result<NodeWithEdges, CoreError> {
+        let body = serde_json::json!({
+            "node": node,
+            "edges": edges,
+        });
+        
+        let response = self.post(&format!("/graph/{}/nodes/with-edges", tenant.as_str()), &body).await?;
+        self.handle_response(response).await
+    }
+
     /// Get the configuration
     pub fn config(&self) -> &KgctlConfig {
         &self.config
@@ -112,7 +174,7 @@ impl TelaMentisClient {
 }
 
 /// Health check response
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
     pub version: Option<String>,
@@ -0,0 +1,184 @@
+//! Admin command implementations
+//!
+//! The admin API takes the deployment's admin secret as bearer token, so
+//! these commands are run with a context whose `auth_token` is that secret.
+
+use crate::cli::AdminCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use telamentis_core::admin::{AdminStatus, DrainReport, JobProgress, JobReport};
+use telamentis_core::errors::CoreError;
+use telamentis_core::pipeline::PluginState;
+use telamentis_core::traits::ProviderStatus;
+use telamentis_core::types::TenantId;
+use tracing::{info, warn};
+
+/// Tenants whose query caches were flushed
+#[derive(Debug, Serialize, Deserialize)]
+struct FlushCachesResponse {
+    tenants: Vec<TenantId>,
+}
+
+/// Handle admin commands
+pub async fn handle_admin_command(command: AdminCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        AdminCommands::Status => show_status(&client, config).await,
+        AdminCommands::Plugins => {
+            let response = client.get("/admin/plugins").await?;
+            let plugins: Vec<PluginState> = client.handle_response(response).await?;
+            display_plugins(&plugins, config)
+        }
+        AdminCommands::EnablePlugin { name } => set_plugin_enabled(&client, &name, true, config).await,
+        AdminCommands::DisablePlugin { name } => set_plugin_enabled(&client, &name, false, config).await,
+        AdminCommands::SetProvider { tenant_id, provider } => {
+            set_provider(&client, &tenant_id, provider.as_deref(), config).await
+        }
+        AdminCommands::FlushCache { tenant } => flush_caches(&client, tenant.as_deref(), config).await,
+        AdminCommands::RunJob { name, tenant } => run_job(&client, &name, tenant.as_deref(), config).await,
+        AdminCommands::JobProgress { name } => job_progress(&client, &name, config).await,
+        AdminCommands::Drain { timeout } => drain(&client, timeout, config).await,
+        AdminCommands::Resume => {
+            let response = client.delete("/admin/drain").await?;
+            let status: AdminStatus = client.handle_response(response).await?;
+            output::display_outcome(&status, &config.default_format, || {
+                println!("{}", "✓ Accepting requests again".green());
+            })
+        }
+    }
+}
+
+/// Show whether the server drains, and its jobs and LLM providers
+async fn show_status(client: &TelaMentisClient, config: &KgctlConfig) -> Result<(), CoreError> {
+    let response = client.get("/admin/status").await?;
+    let status: AdminStatus = client.handle_response(response).await?;
+
+    output::display_outcome(&status, &config.default_format, || {
+        if status.draining {
+            println!("State: {}", "draining".yellow());
+        } else {
+            println!("State: {}", "serving".green());
+        }
+        println!("Requests in flight: {}", status.in_flight);
+        println!("Maintenance jobs: {}", list_or_none(&status.jobs));
+        println!("LLM providers: {}", list_or_none(&status.providers));
+        for (provider, lane) in &status.llm_queues {
+            println!(
+                "  {}: {} in flight, {} interactive and {} background waiting, {} requests and {} tokens in the last minute",
+                provider, lane.in_flight, lane.waiting_interactive, lane.waiting_background,
+                lane.requests_last_minute, lane.tokens_last_minute
+            );
+        }
+        for (tenant, queries) in &status.query_queues {
+            println!(
+                "  tenant {}: {} queries running, {} waiting, {} refused, {}ms longest wait",
+                tenant, queries.in_flight, queries.queued, queries.rejected, queries.max_wait_ms
+            );
+        }
+    })
+}
+
+/// Enable or disable a plugin and show the plugins as they now are
+async fn set_plugin_enabled(client: &TelaMentisClient, name: &str, enabled: bool, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("{} plugin: {}", if enabled { "Enabling" } else { "Disabling" }, name);
+
+    let response = client.put(&format!("/admin/plugins/{}", name), &json!({ "enabled": enabled })).await?;
+    let plugins: Vec<PluginState> = client.handle_response(response).await?;
+    display_plugins(&plugins, config)
+}
+
+fn display_plugins(plugins: &[PluginState], config: &KgctlConfig) -> Result<(), CoreError> {
+    output::display_outcome(&plugins, &config.default_format, || {
+        for plugin in plugins {
+            let state = if plugin.enabled { "enabled".green() } else { "disabled".red() };
+            println!("{:<12} {:<32} {}", plugin.stage.to_string(), plugin.name, state);
+        }
+    })
+}
+
+/// Switch a tenant's default LLM provider
+async fn set_provider(client: &TelaMentisClient, tenant_id: &str, provider: Option<&str>, config: &KgctlConfig) -> Result<(), CoreError> {
+    let response = client.put(&format!("/admin/tenants/{}/provider", tenant_id), &json!({ "provider": provider })).await?;
+    let providers: Vec<ProviderStatus> = client.handle_response(response).await?;
+
+    output::display_outcome(&providers, &config.default_format, || {
+        let default = providers.iter().find(|status| status.is_default)
+            .map_or("none", |status| status.provider.as_str());
+        println!("{}", format!("✓ Default LLM provider of tenant '{}' is now {}", tenant_id, default).green());
+    })
+}
+
+/// Drop cached query results of a tenant, or of every tenant
+async fn flush_caches(client: &TelaMentisClient, tenant_id: Option<&str>, config: &KgctlConfig) -> Result<(), CoreError> {
+    let response = client.post("/admin/caches/flush", &json!({ "tenant_id": tenant_id })).await?;
+    let flushed: FlushCachesResponse = client.handle_response(response).await?;
+
+    output::display_outcome(&flushed, &config.default_format, || {
+        println!("{}", format!("✓ Flushed query caches of {} tenant(s)", flushed.tenants.len()).green());
+    })
+}
+
+/// Run a maintenance job over a tenant, or over every tenant
+async fn run_job(client: &TelaMentisClient, name: &str, tenant_id: Option<&str>, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Running maintenance job: {}", name);
+
+    let response = client.post(&format!("/admin/jobs/{}", name), &json!({ "tenant_id": tenant_id })).await?;
+    let reports: Vec<JobReport> = client.handle_response(response).await?;
+
+    output::display_outcome(&reports, &config.default_format, || {
+        println!("{}", format!("✓ Ran {} over {} tenant(s)", name, reports.len()).green());
+        for report in &reports {
+            let seconds = (report.finished_at - report.started_at).num_milliseconds() as f64 / 1000.0;
+            println!("  {}: {} record(s) in {:.1}s", report.tenant, report.affected, seconds);
+        }
+    })
+}
+
+/// Show how far a maintenance job got in each tenant it ran for
+async fn job_progress(client: &TelaMentisClient, name: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    let response = client.get(&format!("/admin/jobs/{}", name)).await?;
+    let progress: Vec<JobProgress> = client.handle_response(response).await?;
+
+    output::display_outcome(&progress, &config.default_format, || {
+        if progress.is_empty() {
+            println!("{}", format!("No progress reported by {}", name).yellow());
+        }
+        for tenant in &progress {
+            let total = tenant.total.map_or_else(|| "?".to_string(), |total| total.to_string());
+            let state = if tenant.finished { "finished".green() } else { "not finished".yellow() };
+            println!(
+                "  {}: {}/{} record(s) visited, {} changed, {} failed, {}",
+                tenant.tenant, tenant.processed, total, tenant.changed, tenant.failed, state
+            );
+            if let Some(error) = &tenant.last_error {
+                println!("    {}", format!("last error: {}", error).red());
+            }
+        }
+    })
+}
+
+/// Drain the server and report whether every request in flight finished
+async fn drain(client: &TelaMentisClient, timeout: u64, config: &KgctlConfig) -> Result<(), CoreError> {
+    warn!("Draining the server, waiting up to {}s", timeout);
+
+    let response = client.post("/admin/drain", &json!({ "timeout_secs": timeout })).await?;
+    let report: DrainReport = client.handle_response(response).await?;
+
+    output::display_outcome(&report, &config.default_format, || {
+        if report.drained {
+            println!("{}", "✓ Drained: no requests in flight, new ones are refused".green());
+        } else {
+            println!("{}", format!("Timed out with {} request(s) still in flight; new ones are refused", report.in_flight).yellow());
+        }
+        println!("Resume with `kgctl admin resume`");
+    })
+}
+
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() { "none".to_string() } else { items.join(", ") }
+}
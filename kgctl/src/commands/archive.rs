@@ -0,0 +1,113 @@
+//! Archive command implementations
+
+use crate::cli::ArchiveCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde::Deserialize;
+use serde_json::json;
+use telamentis_core::archive::{ArchiveManifest, ArchiveSegment, RestoreReport};
+use telamentis_core::errors::CoreError;
+use tracing::info;
+
+/// Result of an archive run
+#[derive(Debug, Deserialize)]
+struct RunArchiveResult {
+    segment: Option<ArchiveSegment>,
+}
+
+/// Handle archive commands
+pub async fn handle_archive_command(command: ArchiveCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        ArchiveCommands::Run { tenant, before, purge } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            let before = parse_datetime(&before)?;
+            run_archive(&client, &tenant_id, before, purge, config).await
+        }
+        ArchiveCommands::List { tenant } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            list_segments(&client, &tenant_id, config).await
+        }
+        ArchiveCommands::Restore { tenant, from, to } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            let from = parse_datetime(&from)?;
+            let to = parse_datetime(&to)?;
+            restore_archive(&client, &tenant_id, from, to, config).await
+        }
+    }
+}
+
+/// Archive history that ended before `before`, purging it if asked to
+async fn run_archive(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    before: DateTime<Utc>,
+    purge: bool,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Archiving history before {} for tenant: {}", before, tenant_id);
+
+    let response = client.post(&archive_path(tenant_id), &json!({ "before": before, "purge": purge })).await?;
+    let result: RunArchiveResult = client.handle_response(response).await?;
+
+    let outcome = json!({ "segment": result.segment, "purged": purge && result.segment.is_some() });
+    output::display_outcome(&outcome, &config.default_format, || match &result.segment {
+        Some(segment) => {
+            let verb = if purge { "Moved" } else { "Copied" };
+            println!("{}", format!("✓ {} history to archive segment {}", verb, segment.id).green());
+            println!("  Nodes: {}, Edges: {}", segment.node_count, segment.edge_count);
+            println!("  Ended between {} and {}", segment.from.to_rfc3339(), segment.to.to_rfc3339());
+        }
+        None => println!("No history before {} to archive", before.to_rfc3339()),
+    })
+}
+
+/// List a tenant's archive segments
+async fn list_segments(client: &TelaMentisClient, tenant_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Listing archive segments for tenant: {}", tenant_id);
+
+    let response = client.get(&archive_path(tenant_id)).await?;
+    let manifest: ArchiveManifest = client.handle_response(response).await?;
+
+    if manifest.segments.is_empty() && config.default_format.is_table() {
+        println!("No archived history for tenant '{}'", tenant_id);
+        return Ok(());
+    }
+
+    output::display_archive_segments(&manifest.segments, &config.default_format)
+}
+
+/// Restore archived history that ended within `[from, to)`
+async fn restore_archive(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Restoring archived history from {} to {} for tenant: {}", from, to, tenant_id);
+
+    let path = format!("{}/restore", archive_path(tenant_id));
+    let response = client.post(&path, &json!({ "from": from, "to": to })).await?;
+    let report: RestoreReport = client.handle_response(response).await?;
+
+    output::display_outcome(&report, &config.default_format, || {
+        println!("{}", format!("✓ Restored {} of {} archived records", report.restored, report.records).green());
+        println!("  Segments read: {}", report.segments);
+    })
+}
+
+fn archive_path(tenant_id: &str) -> String {
+    format!("/archive/{}", tenant_id)
+}
+
+/// Parse datetime from string
+fn parse_datetime(datetime_str: &str) -> Result<DateTime<Utc>, CoreError> {
+    DateTime::parse_from_rfc3339(datetime_str)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| CoreError::Internal(format!("Invalid datetime '{}': {}", datetime_str, e)))
+}
@@ -0,0 +1,94 @@
+//! Configuration context command implementations
+
+use crate::cli::ConfigCommands;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use serde_json::json;
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+use telamentis_core::errors::CoreError;
+use tracing::info;
+
+/// Handle configuration context commands
+pub fn handle_config_command(
+    command: ConfigCommands,
+    context_override: Option<&str>,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    match command {
+        ConfigCommands::List => {
+            let current = context_override.or(config.current_context.as_deref());
+            output::display_contexts(&config.contexts, current, &config.default_format)
+        }
+        ConfigCommands::UseContext { name } => {
+            if !config.contexts.contains_key(&name) {
+                return Err(CoreError::Configuration(format!(
+                    "No context named '{}'. See `kgctl config list`", name
+                )));
+            }
+            let path = config.writable_config_file();
+            set_current_context(&path, &name)?;
+            info!("Set current context to '{}' in {}", name, path.display());
+            output::display_outcome(&json!({ "current_context": name }), &config.default_format, || {
+                println!("{}", format!("✓ Switched to context \"{}\"", name).green());
+            })
+        }
+        ConfigCommands::CurrentContext => {
+            match context_override.or(config.current_context.as_deref()) {
+                Some(name) => {
+                    println!("{}", name);
+                    Ok(())
+                }
+                None => Err(CoreError::Configuration("No current context is set".to_string())),
+            }
+        }
+    }
+}
+
+/// Set `current_context` in a config file, keeping its other settings
+fn set_current_context(path: &Path, name: &str) -> Result<(), CoreError> {
+    let mut document = if path.exists() {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| CoreError::Configuration(format!("Failed to read {}: {}", path.display(), e)))?;
+        serde_yaml::from_str(&content)
+            .map_err(|e| CoreError::Configuration(format!("Failed to parse {}: {}", path.display(), e)))?
+    } else {
+        Value::Null
+    };
+    if document.is_null() {
+        document = Value::Mapping(Mapping::new());
+    }
+
+    let settings = document.as_mapping_mut().ok_or_else(|| CoreError::Configuration(format!(
+        "{} does not hold a YAML mapping", path.display()
+    )))?;
+    settings.insert(Value::from("current_context"), Value::from(name));
+
+    let content = serde_yaml::to_string(&document)
+        .map_err(|e| CoreError::Internal(format!("Failed to serialize configuration: {}", e)))?;
+    std::fs::write(path, content)
+        .map_err(|e| CoreError::Configuration(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_current_context_keeps_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kgctl.yaml");
+        std::fs::write(&path, "endpoint: http://localhost:8000\ncontexts:\n  prod:\n    endpoint: https://prod.example.com\n").unwrap();
+
+        set_current_context(&path, "prod").unwrap();
+        let document: Value = serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(document["current_context"], Value::from("prod"));
+        assert_eq!(document["contexts"]["prod"]["endpoint"], Value::from("https://prod.example.com"));
+
+        // A missing file is created
+        let new_path = dir.path().join("new.yaml");
+        set_current_context(&new_path, "staging").unwrap();
+        assert!(std::fs::read_to_string(&new_path).unwrap().contains("current_context: staging"));
+    }
+}
@@ -0,0 +1,135 @@
+//! Dead-letter queue command implementations
+
+use crate::cli::DlqCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use serde_json::json;
+use std::io::{self, Write};
+use telamentis_core::dead_letter::{DeadLetter, DeadLetterRecord, RetryReport};
+use telamentis_core::errors::CoreError;
+use tracing::{info, warn};
+
+/// Handle dead-letter queue commands
+pub async fn handle_dlq_command(command: DlqCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        DlqCommands::List { tenant } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            list_dead_letters(&client, &tenant_id, config).await
+        }
+        DlqCommands::Show { tenant, entry_id } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            show_dead_letter(&client, &tenant_id, &entry_id, config).await
+        }
+        DlqCommands::Retry { tenant, entry_ids } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            retry_dead_letters(&client, &tenant_id, &entry_ids, config).await
+        }
+        DlqCommands::Purge { tenant, entry_ids, force } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            purge_dead_letters(&client, &tenant_id, &entry_ids, force, config).await
+        }
+    }
+}
+
+/// List a tenant's failed ingestion records
+async fn list_dead_letters(client: &TelaMentisClient, tenant_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Listing dead letters for tenant: {}", tenant_id);
+
+    let response = client.get(&dead_letters_path(tenant_id)).await?;
+    let entries: Vec<DeadLetter> = client.handle_response(response).await?;
+
+    if entries.is_empty() && config.default_format.is_table() {
+        println!("No dead letters for tenant '{}'", tenant_id);
+        return Ok(());
+    }
+
+    output::display_dead_letters(&entries, &config.default_format)
+}
+
+/// Show a failed record with its error and context
+async fn show_dead_letter(client: &TelaMentisClient, tenant_id: &str, entry_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    let response = client.get(&format!("{}/{}", dead_letters_path(tenant_id), entry_id)).await?;
+    let entry: DeadLetter = client.handle_response(response).await?;
+
+    output::display_outcome(&entry, &config.default_format, || {
+        println!("{}", format!("Dead letter {}", entry.id).bold());
+        println!("  Source: {}", entry.source);
+        println!("  Error: {}", entry.error.red());
+        println!("  Attempts: {}", entry.attempts);
+        println!("  First failed: {}", entry.first_failed_at.to_rfc3339());
+        println!("  Last failed: {}", entry.last_failed_at.to_rfc3339());
+        for (key, value) in &entry.context {
+            println!("  {}: {}", key, value);
+        }
+        let record = match &entry.record {
+            DeadLetterRecord::Node(node) => serde_json::to_string_pretty(node),
+            DeadLetterRecord::Edge(edge) => serde_json::to_string_pretty(edge),
+        };
+        println!("  Record:\n{}", record.unwrap_or_default());
+    })
+}
+
+/// Retry the given records, or all of the tenant's
+async fn retry_dead_letters(client: &TelaMentisClient, tenant_id: &str, entry_ids: &[String], config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Retrying dead letters for tenant: {}", tenant_id);
+
+    let request = if entry_ids.is_empty() { json!({}) } else { json!({ "ids": entry_ids }) };
+    let response = client.post(&format!("{}/retry", dead_letters_path(tenant_id)), &request).await?;
+    let report: RetryReport = client.handle_response(response).await?;
+
+    output::display_outcome(&report, &config.default_format, || {
+        println!("{}", format!("✓ Retried {} dead letter(s): {} succeeded", report.succeeded.len() + report.failed.len(), report.succeeded.len()).green());
+        if !report.failed.is_empty() {
+            println!("{}", format!("{} failed again and stay queued; see `kgctl dlq list`", report.failed.len()).yellow());
+        }
+    })
+}
+
+/// Drop the given records, or all of the tenant's
+async fn purge_dead_letters(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    entry_ids: &[String],
+    force: bool,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    if !force && entry_ids.is_empty() {
+        // Prompt on stderr so that stdout only carries the result
+        eprint!("Drop all dead letters of tenant '{}' without retrying them? [y/N]: ", tenant_id);
+        io::stderr().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            eprintln!("Purge cancelled");
+            return Ok(());
+        }
+    }
+
+    warn!("Purging dead letters of tenant: {}", tenant_id);
+
+    let removed = if entry_ids.is_empty() {
+        let response = client.delete(&dead_letters_path(tenant_id)).await?;
+        client.handle_response::<usize>(response).await?
+    } else {
+        for entry_id in entry_ids {
+            let response = client.delete(&format!("{}/{}", dead_letters_path(tenant_id), entry_id)).await?;
+            client.handle_response::<serde_json::Value>(response).await?;
+        }
+        entry_ids.len()
+    };
+
+    output::display_outcome(&json!({ "removed": removed }), &config.default_format, || {
+        println!("{}", format!("✓ Purged {} dead letter(s) of tenant '{}'", removed, tenant_id).green());
+    })
+}
+
+fn dead_letters_path(tenant_id: &str) -> String {
+    format!("/dead-letters/{}", tenant_id)
+}
@@ -0,0 +1,129 @@
+//! Doctor command implementation
+
+use crate::client::{HealthResponse, TelaMentisClient};
+use crate::config::KgctlConfig;
+use crate::output;
+use crate::secure_export::ExportKey;
+use colored::*;
+use std::sync::Arc;
+use telamentis_adapter_neo4j::Neo4jMigrator;
+use telamentis_core::doctor::{CheckOutcome, CheckStatus, Doctor};
+use telamentis_core::errors::CoreError;
+use telamentis_core::traits::SchemaMigrator;
+
+/// Handle the doctor command: validate the configuration, then connect to
+/// the API and the configured store and report what passed and failed
+pub async fn handle_doctor_command(config: &KgctlConfig) -> Result<(), CoreError> {
+    let shared = Arc::new(config.clone());
+
+    let doctor = Doctor::new()
+        .with_timeout(config.timeout * 1000)
+        .with_check("config", {
+            let config = shared.clone();
+            move || {
+                let config = config.clone();
+                async move { check_config(&config) }
+            }
+        })
+        .with_check("api", {
+            let config = shared.clone();
+            move || {
+                let config = config.clone();
+                async move { check_api(&config).await }
+            }
+        })
+        .with_check("neo4j", {
+            let config = shared.clone();
+            move || {
+                let config = config.clone();
+                async move { check_neo4j(&config).await }
+            }
+        });
+    let report = doctor.run().await;
+
+    output::display_outcome(&report, &config.default_format, || {
+        for check in &report.checks {
+            let mark = match check.status {
+                CheckStatus::Pass => "✓".green(),
+                CheckStatus::Warn => "!".yellow(),
+                CheckStatus::Fail => "✗".red(),
+                CheckStatus::Skip => "-".dimmed(),
+            };
+            println!("{} {:<8} {} ({} ms)", mark, check.name, check.message, check.elapsed_ms);
+        }
+    })?;
+
+    match report.failures().count() {
+        0 => Ok(()),
+        failed => Err(CoreError::Configuration(format!("{} doctor check(s) failed", failed))),
+    }
+}
+
+/// Settings that can be checked without connecting anywhere
+fn check_config(config: &KgctlConfig) -> CheckOutcome {
+    if let Err(e) = reqwest::Url::parse(&config.endpoint) {
+        return CheckOutcome::fail(format!("Endpoint {} is not a URL: {}", config.endpoint, e));
+    }
+    if let Err(e) = config.apply_network(reqwest::Client::builder()).and_then(|builder| {
+        builder.build().map_err(|e| CoreError::Configuration(e.to_string()))
+    }) {
+        return CheckOutcome::fail(format!("TLS or proxy settings are invalid: {}", e));
+    }
+    let bad_keys: Vec<_> = config.export_keys.iter()
+        .filter(|(_, key)| ExportKey::from_base64(key).is_err())
+        .map(|(tenant, _)| tenant.as_str())
+        .collect();
+    if !bad_keys.is_empty() {
+        return CheckOutcome::fail(format!("Invalid export keys for tenants: {}", bad_keys.join(", ")));
+    }
+
+    let source = config.config_file.as_ref().map_or("defaults".to_string(), |path| path.display().to_string());
+    match &config.current_context {
+        Some(context) => CheckOutcome::pass(format!("Loaded from {}, context {}", source, context)),
+        None => CheckOutcome::pass(format!("Loaded from {}", source)),
+    }
+}
+
+async fn check_api(config: &KgctlConfig) -> CheckOutcome {
+    let health: Result<HealthResponse, CoreError> = async {
+        let client = TelaMentisClient::new(config.clone())?;
+        let response = client.get("/health").await?;
+        client.handle_response(response).await
+    }.await;
+
+    match health {
+        Ok(health) if health.status == "healthy" => CheckOutcome::pass(format!(
+            "{} is healthy{}", config.endpoint, health.version.map(|v| format!(" (version {})", v)).unwrap_or_default()
+        )),
+        Ok(health) => CheckOutcome::warn(format!("{} reports status {}", config.endpoint, health.status)),
+        Err(e) => CheckOutcome::fail(format!("{}: {}", config.endpoint, e)),
+    }
+}
+
+/// Connect to the store `kgctl migrate` uses and check its indexes
+async fn check_neo4j(config: &KgctlConfig) -> CheckOutcome {
+    let Some(store) = &config.neo4j else {
+        return CheckOutcome::skip("No neo4j section in the config");
+    };
+    let migrator = match Neo4jMigrator::connect(store).await {
+        Ok(migrator) => migrator,
+        Err(e) => return CheckOutcome::fail(format!("{}: {}", store.uri, e)),
+    };
+
+    match migrator.schema_status().await {
+        Ok(status) if status.is_current() => match migrator.verify_indexes().await {
+            Ok(report) if report.is_ok() => {
+                CheckOutcome::pass(format!("{} is reachable; schema is at version {}", store.uri, status.current_version))
+            }
+            Ok(report) => CheckOutcome::fail(format!(
+                "Indexes of {} differ from the schema: {}; see `kgctl migrate verify`", store.uri, report.summary()
+            )),
+            Err(e) => CheckOutcome::fail(format!("{}: {}", store.uri, e)),
+        },
+        Ok(status) => CheckOutcome::fail(format!(
+            "Schema of {} is at version {} of {}; run `kgctl migrate apply`",
+            store.uri, status.current_version, status.latest_version
+        )),
+        Err(e) => CheckOutcome::fail(format!("{}: {}", store.uri, e)),
+    }
+}
@@ -0,0 +1,141 @@
+//! Edge correction command implementations
+
+use crate::cli::EdgeCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use telamentis_core::errors::CoreError;
+use telamentis_core::temporal::IntervalBound;
+use telamentis_core::types::{TenantId, TimeEdge};
+use telamentis_core::valid_time::ValidTimePolicy;
+use tracing::info;
+use uuid::Uuid;
+
+/// New version of a closed or superseded edge
+#[derive(Debug, Serialize, Deserialize)]
+struct EdgeVersion {
+    edge_id: Uuid,
+    previous_edge_id: Uuid,
+}
+
+/// Handle edge correction commands
+pub async fn handle_edge_command(command: EdgeCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        EdgeCommands::Close { tenant, edge_id, valid_to } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            let valid_time = config.valid_time.for_tenant(&TenantId::new(&tenant_id));
+            let valid_to = parse_time(&valid_to, valid_time, IntervalBound::End)?;
+            close_edge(&client, &tenant_id, &edge_id, valid_to, config).await
+        }
+        EdgeCommands::Supersede { tenant, edge_id, from, to, kind, valid_from, valid_to, props } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            let valid_time = config.valid_time.for_tenant(&TenantId::new(&tenant_id));
+
+            let mut edge = TimeEdge::new(
+                parse_uuid(&from)?,
+                parse_uuid(&to)?,
+                kind,
+                parse_time(&valid_from, valid_time, IntervalBound::Start)?,
+                parse_props(props.as_deref())?,
+            );
+            if let Some(valid_to) = valid_to {
+                edge = edge.with_valid_to(parse_time(&valid_to, valid_time, IntervalBound::End)?);
+            }
+
+            supersede_edge(&client, &tenant_id, &edge_id, edge, config).await
+        }
+        EdgeCommands::Retract { tenant, edge_id } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            retract_edge(&client, &tenant_id, &edge_id, config).await
+        }
+    }
+}
+
+/// Close an edge at `valid_to`
+async fn close_edge(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    edge_id: &str,
+    valid_to: DateTime<Utc>,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Closing edge {} for tenant: {}", edge_id, tenant_id);
+
+    let response = client.post(&format!("{}/close", edge_path(tenant_id, edge_id)), &json!({ "valid_to": valid_to })).await?;
+    let version: EdgeVersion = client.handle_response(response).await?;
+
+    output::display_outcome(&version, &config.default_format, || {
+        println!("{}", format!("✓ Closed edge {} at {}", version.previous_edge_id, valid_to.to_rfc3339()).green());
+        println!("  Current version: {}", version.edge_id);
+    })
+}
+
+/// Replace an edge with a corrected one
+async fn supersede_edge(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    edge_id: &str,
+    edge: TimeEdge,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Superseding edge {} for tenant: {}", edge_id, tenant_id);
+
+    let response = client.post(&format!("{}/supersede", edge_path(tenant_id, edge_id)), &json!({ "edge": edge })).await?;
+    let version: EdgeVersion = client.handle_response(response).await?;
+
+    output::display_outcome(&version, &config.default_format, || {
+        println!("{}", format!("✓ Superseded edge {}", version.previous_edge_id).green());
+        println!("  Current version: {}", version.edge_id);
+    })
+}
+
+/// Retract an edge that was never true
+async fn retract_edge(client: &TelaMentisClient, tenant_id: &str, edge_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Retracting edge {} for tenant: {}", edge_id, tenant_id);
+
+    let response = client.post(&format!("{}/retract", edge_path(tenant_id, edge_id)), &json!({})).await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(CoreError::Internal(format!("Failed to retract edge: {}", error_text)));
+    }
+
+    output::display_outcome(&json!({ "retracted_edge_id": edge_id }), &config.default_format, || {
+        println!("{}", format!("✓ Retracted edge {}", edge_id).green());
+    })
+}
+
+fn edge_path(tenant_id: &str, edge_id: &str) -> String {
+    format!("/graph/{}/edges/{}", tenant_id, edge_id)
+}
+
+/// Parse UUID from string
+fn parse_uuid(uuid_str: &str) -> Result<Uuid, CoreError> {
+    Uuid::parse_str(uuid_str)
+        .map_err(|e| CoreError::Internal(format!("Invalid UUID '{}': {}", uuid_str, e)))
+}
+
+/// Parse a timestamp or date; values without an offset are in the tenant's
+/// timezone, and a year, month or day stands for the instant `bound` gives it
+fn parse_time(value: &str, valid_time: &ValidTimePolicy, bound: IntervalBound) -> Result<DateTime<Utc>, CoreError> {
+    valid_time.parse_bound(value, bound)
+        .ok_or_else(|| CoreError::Internal(format!("Invalid datetime '{}'", value)))
+}
+
+/// Parse edge properties from a JSON object
+fn parse_props(props: Option<&str>) -> Result<Value, CoreError> {
+    match props {
+        Some(props) => match serde_json::from_str(props) {
+            Ok(Value::Object(map)) => Ok(Value::Object(map)),
+            Ok(_) => Err(CoreError::Internal("Edge properties must be a JSON object".to_string())),
+            Err(e) => Err(CoreError::Internal(format!("Invalid edge properties: {}", e))),
+        },
+        None => Ok(json!({})),
+    }
+}
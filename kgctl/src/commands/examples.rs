@@ -0,0 +1,148 @@
+//! Few-shot extraction example command implementations
+
+use crate::cli::ExamplesCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+use telamentis_core::errors::CoreError;
+use telamentis_core::examples::ExtractionExample;
+use tracing::info;
+
+/// Handle example management commands
+pub async fn handle_examples_command(command: ExamplesCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        ExamplesCommands::List { tenant } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            list_examples(&client, &tenant_id, config).await
+        }
+        ExamplesCommands::Add { tenant, file } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            add_examples(&client, &tenant_id, &file, config).await
+        }
+        ExamplesCommands::Set { tenant, file } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            set_examples(&client, &tenant_id, &file, config).await
+        }
+        ExamplesCommands::Remove { tenant, example_id } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            remove_example(&client, &tenant_id, &example_id, config).await
+        }
+    }
+}
+
+/// List a tenant's examples
+async fn list_examples(client: &TelaMentisClient, tenant_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Listing extraction examples for tenant: {}", tenant_id);
+
+    let response = client.get(&examples_path(tenant_id)).await?;
+    let examples: Vec<ExtractionExample> = client.handle_response(response).await?;
+
+    if examples.is_empty() && config.default_format.is_table() {
+        println!("No extraction examples for tenant '{}'", tenant_id);
+        return Ok(());
+    }
+
+    output::display_examples(&examples, &config.default_format)
+}
+
+/// Add the examples in a file
+async fn add_examples(client: &TelaMentisClient, tenant_id: &str, file: &Path, config: &KgctlConfig) -> Result<(), CoreError> {
+    let examples = read_examples(file)?;
+    info!("Adding {} extraction examples for tenant: {}", examples.len(), tenant_id);
+
+    let mut added = Vec::with_capacity(examples.len());
+    for example in &examples {
+        let response = client.post(&examples_path(tenant_id), example).await?;
+        let example: ExtractionExample = client.handle_response(response).await?;
+        if config.default_format.is_table() {
+            println!("{}", format!("✓ Added example {}", example.id).green());
+        }
+        added.push(example);
+    }
+
+    if config.default_format.is_table() {
+        return Ok(());
+    }
+    output::display_examples(&added, &config.default_format)
+}
+
+/// Replace a tenant's examples with those in a file
+async fn set_examples(client: &TelaMentisClient, tenant_id: &str, file: &Path, config: &KgctlConfig) -> Result<(), CoreError> {
+    let examples = read_examples(file)?;
+    info!("Replacing extraction examples for tenant: {}", tenant_id);
+
+    let response = client.put(&examples_path(tenant_id), &examples).await?;
+    let stored: Vec<ExtractionExample> = client.handle_response(response).await?;
+
+    if !config.default_format.is_table() {
+        return output::display_examples(&stored, &config.default_format);
+    }
+    println!("{}", format!("✓ Tenant '{}' now has {} extraction examples", tenant_id, stored.len()).green().bold());
+    Ok(())
+}
+
+/// Remove a single example
+async fn remove_example(client: &TelaMentisClient, tenant_id: &str, example_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Removing extraction example {} for tenant: {}", example_id, tenant_id);
+
+    let response = client.delete(&format!("{}/{}", examples_path(tenant_id), example_id)).await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(CoreError::Internal(format!("Failed to remove example: {}", error_text)));
+    }
+
+    output::display_outcome(&json!({ "removed_example_id": example_id }), &config.default_format, || {
+        println!("{}", format!("✓ Removed example {}", example_id).green());
+    })
+}
+
+fn examples_path(tenant_id: &str) -> String {
+    format!("/llm/{}/examples", tenant_id)
+}
+
+/// Read one example or an array of examples from a JSON file
+fn read_examples(file: &Path) -> Result<Vec<ExtractionExample>, CoreError> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ExampleFile {
+        Many(Vec<ExtractionExample>),
+        One(Box<ExtractionExample>),
+    }
+
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| CoreError::Internal(format!("Failed to read {}: {}", file.display(), e)))?;
+
+    match serde_json::from_str(&content) {
+        Ok(ExampleFile::Many(examples)) => Ok(examples),
+        Ok(ExampleFile::One(example)) => Ok(vec![*example]),
+        Err(e) => Err(CoreError::Internal(format!("Invalid examples file {}: {}", file.display(), e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_read_examples_accepts_single_or_array() {
+        let example = r#"{"input": "Alice works at Acme", "expected": {"nodes": [], "relations": [], "metadata": null}}"#;
+
+        let mut single = tempfile::NamedTempFile::new().unwrap();
+        write!(single, "{}", example).unwrap();
+        assert_eq!(read_examples(single.path()).unwrap().len(), 1);
+
+        let mut array = tempfile::NamedTempFile::new().unwrap();
+        write!(array, "[{}, {}]", example, example).unwrap();
+        let examples = read_examples(array.path()).unwrap();
+        assert_eq!(examples.len(), 2);
+        assert_ne!(examples[0].id, examples[1].id);
+    }
+}
@@ -3,14 +3,18 @@
 use crate::cli::{ExportCommands, ExportFormat};
 use crate::client::TelaMentisClient;
 use crate::config::KgctlConfig;
-use chrono::{DateTime, Utc};
+use crate::output;
+use crate::secure_export::{manifest_path, ExportKey, ExportManifest};
+use chrono::{DateTime, SecondsFormat, Utc};
 use colored::*;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::fs::File;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use telamentis_core::errors::CoreError;
-use telamentis_core::types::TenantId;
+use telamentis_core::temporal::{IntervalBound, TemporalUtils};
+use telamentis_core::types::{GraphSnapshot, TenantId};
 use tracing::{debug, info};
 
 /// Export data structure
@@ -24,6 +28,11 @@ pub struct ExportData {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExportNode {
     pub id: String,
+    /// Alias of the node, which identifies it across exports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_alias: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias_namespace: Option<String>,
     pub labels: Vec<String>,
     pub properties: serde_json::Value,
 }
@@ -34,6 +43,11 @@ pub struct ExportEdge {
     pub from_node: String,
     pub to_node: String,
     pub edge_type: String,
+    /// Valid time of the edge; exports made before it was recorded have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<DateTime<Utc>>,
     pub properties: serde_json::Value,
 }
 
@@ -44,6 +58,9 @@ pub struct ExportMetadata {
     pub node_count: usize,
     pub edge_count: usize,
     pub temporal_as_of: Option<String>,
+    /// Transaction time of the snapshot the export was read from
+    #[serde(default)]
+    pub snapshot_at: Option<String>,
 }
 
 /// Handle data export commands
@@ -56,8 +73,12 @@ pub async fn handle_export_command(command: ExportCommands, config: &KgctlConfig
             include_nodes,
             include_edges,
             temporal_as_of,
+            reify_edges,
+            encrypt,
+            key_file,
         } => {
             let tenant_id = config.get_tenant(&tenant)?;
+            let key = config.export_key(&tenant_id, key_file.as_deref())?;
             export_data(
                 config,
                 &tenant_id,
@@ -66,12 +87,32 @@ pub async fn handle_export_command(command: ExportCommands, config: &KgctlConfig
                 include_nodes,
                 include_edges,
                 temporal_as_of.as_deref(),
+                reify_edges,
+                key.as_ref(),
+                encrypt,
             ).await
         }
+        ExportCommands::Verify {
+            input,
+            manifest,
+            tenant,
+            key_file,
+            output,
+        } => {
+            verify_export(
+                config,
+                &input,
+                manifest.as_deref(),
+                tenant,
+                key_file.as_deref(),
+                output.as_deref(),
+            )
+        }
     }
 }
 
 /// Export data for a tenant
+#[allow(clippy::too_many_arguments)]
 async fn export_data(
     config: &KgctlConfig,
     tenant_id: &str,
@@ -80,9 +121,25 @@ async fn export_data(
     include_nodes: bool,
     include_edges: bool,
     temporal_as_of: Option<&str>,
+    reify_edges: bool,
+    key: Option<&ExportKey>,
+    encrypt: bool,
 ) -> Result<(), CoreError> {
     info!("Exporting data for tenant: {}", tenant_id);
     
+    if encrypt {
+        if key.is_none() {
+            return Err(CoreError::Configuration(format!(
+                "No export key for tenant '{}'. Use --key-file or set export_keys in config", tenant_id
+            )));
+        }
+        if output_path.is_none() {
+            return Err(CoreError::Configuration(
+                "Encrypted exports must be written to a file. Use --output".to_string()
+            ));
+        }
+    }
+    
     let client = TelaMentisClient::new(config.clone())?;
     let tenant = TenantId::new(tenant_id);
     
@@ -93,81 +150,394 @@ async fn export_data(
         None
     };
     
+    if format.is_columnar() {
+        if encrypt {
+            return Err(CoreError::Configuration(format!("{} exports cannot be encrypted", format)));
+        }
+        let dir = output_path.ok_or_else(|| CoreError::Configuration(format!(
+            "{} exports are written to a directory. Use --output", format
+        )))?;
+        let tables: Vec<&str> = [("nodes", include_nodes), ("edges", include_edges)]
+            .into_iter()
+            .filter_map(|(table, included)| included.then_some(table))
+            .collect();
+        let exported = export_columnar(&client, &tenant, dir, &format, &tables, as_of_time, key).await?;
+        return output::display_outcome(&exported, &config.default_format, || {
+            for table in &exported {
+                println!("{}", format!("✓ Exported {} {} to: {}", table.rows, table.table, table.path.display()).green());
+            }
+        });
+    }
+    
     // Fetch data from API
-    let export_data = fetch_export_data(
-        &client,
-        &tenant,
-        include_nodes,
-        include_edges,
-        as_of_time,
-    ).await?;
+    let mut snapshot = fetch_snapshot(&client, &tenant, as_of_time).await?;
+    if !include_nodes {
+        snapshot.nodes.clear();
+    }
+    if !include_edges {
+        snapshot.edges.clear();
+    }
     
-    // Format and output data
-    let formatted_output = format_export_data(&export_data, &format)?;
+    // Format and output data; RDF is encoded from the snapshot, which has
+    // the edge times that export records leave out
+    let rdf = format.rdf_syntax().map(|syntax| {
+        let mut mapping = config.rdf.clone();
+        mapping.reify_edges |= reify_edges;
+        mapping.encode(&tenant, &snapshot, syntax)
+    });
+    let export_data = snapshot_to_export_data(&tenant, snapshot, include_nodes, include_edges);
+    let formatted_output = match rdf {
+        Some(rdf) => rdf,
+        None => format_export_data(&export_data, &format)?,
+    };
     
     match output_path {
         Some(path) => {
-            write_to_file(&formatted_output, path)?;
-            println!("{}", format!("✓ Data exported to: {}", path.display()).green().bold());
+            let manifest = match key {
+                Some(key) => {
+                    write_signed_export(key, &export_data, &format, &formatted_output, path, encrypt)?;
+                    Some(manifest_path(path))
+                }
+                None => {
+                    write_to_file(&formatted_output, path)?;
+                    None
+                }
+            };
+            let outcome = json!({
+                "tenant_id": tenant_id,
+                "format": format.to_string(),
+                "path": path,
+                "manifest": manifest,
+                "encrypted": encrypt,
+                "node_count": export_data.node_count,
+                "edge_count": export_data.edge_count,
+            });
+            output::display_outcome(&outcome, &config.default_format, || {
+                if let Some(manifest) = &manifest {
+                    println!("{}", format!("✓ Signed manifest written to: {}", manifest.display()).green());
+                }
+                println!("{}", format!("✓ Data exported to: {}", path.display()).green().bold());
+                println!("Exported {} nodes and {} edges", export_data.node_count, export_data.edge_count);
+            })
         }
         None => {
+            // The export itself is the output; keep the summary off stdout
             print!("{}", formatted_output);
+            eprintln!("Exported {} nodes and {} edges", export_data.node_count, export_data.edge_count);
+            Ok(())
         }
     }
-    
-    println!("Exported {} nodes and {} edges", export_data.node_count, export_data.edge_count);
-    
-    Ok(())
 }
 
-/// Fetch data from the TelaMentis API
-async fn fetch_export_data(
+/// A table written by a columnar export
+#[derive(Debug, Serialize)]
+struct ExportedTable {
+    table: String,
+    path: PathBuf,
+    rows: usize,
+    manifest: Option<PathBuf>,
+}
+
+/// Export tables in a columnar format, streaming each from the server into
+/// `{dir}/{table}.{ext}`; each file gets its own signed manifest if the
+/// tenant has an export key
+async fn export_columnar(
     client: &TelaMentisClient,
     tenant: &TenantId,
-    include_nodes: bool,
-    include_edges: bool,
+    dir: &Path,
+    format: &ExportFormat,
+    tables: &[&str],
     as_of_time: Option<DateTime<Utc>>,
-) -> Result<ExportData, CoreError> {
-    let mut nodes = Vec::new();
-    let mut edges = Vec::new();
+    key: Option<&ExportKey>,
+) -> Result<Vec<ExportedTable>, CoreError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| CoreError::Internal(format!("Failed to create directory {}: {}", dir.display(), e)))?;
+    
+    let extension = match format {
+        ExportFormat::Arrow => "arrows",
+        _ => "parquet",
+    };
     
-    // Build query parameters
-    let mut query_params = Vec::new();
-    if let Some(timestamp) = as_of_time {
-        query_params.push(format!("as_of={}", timestamp.to_rfc3339()));
+    let mut exported = Vec::with_capacity(tables.len());
+    for table in tables {
+        debug!("Exporting {} table for tenant: {}", table, tenant);
+        let mut response = client.get(&columnar_export_path(tenant, format, table, as_of_time)).await?;
+        if !response.status().is_success() {
+            return Err(client.handle_response::<serde_json::Value>(response).await.err()
+                .unwrap_or_else(|| CoreError::Internal(format!("Export of {} failed", table))));
+        }
+        
+        let header = |name: &str| response.headers().get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let snapshot_at = header("x-snapshot-at");
+        let rows: usize = header("x-row-count").and_then(|rows| rows.parse().ok()).unwrap_or_default();
+        
+        let path = dir.join(format!("{}.{}", table, extension));
+        let mut file = File::create(&path)
+            .map_err(|e| CoreError::Internal(format!("Failed to create file {}: {}", path.display(), e)))?;
+        while let Some(chunk) = response.chunk().await
+            .map_err(|e| CoreError::Internal(format!("Failed to read {} export: {}", table, e)))?
+        {
+            file.write_all(&chunk)
+                .map_err(|e| CoreError::Internal(format!("Failed to write to file {}: {}", path.display(), e)))?;
+        }
+        
+        let mut manifest_file = None;
+        if let Some(key) = key {
+            let data = std::fs::read(&path)
+                .map_err(|e| CoreError::Internal(format!("Failed to read export {}: {}", path.display(), e)))?;
+            let (node_count, edge_count) = if *table == "nodes" { (rows, 0) } else { (0, rows) };
+            let mut manifest = ExportManifest::new(
+                tenant.as_str(), format.to_string(), snapshot_at, node_count, edge_count, false, &data,
+            );
+            key.sign(&mut manifest)?;
+            let manifest_json = serde_json::to_string_pretty(&manifest)
+                .map_err(|e| CoreError::Internal(format!("Failed to serialize manifest: {}", e)))?;
+            write_to_file(&manifest_json, &manifest_path(&path))?;
+            manifest_file = Some(manifest_path(&path));
+        }
+        
+        exported.push(ExportedTable { table: table.to_string(), path, rows, manifest: manifest_file });
     }
     
-    let query_string = if query_params.is_empty() {
-        String::new()
+    Ok(exported)
+}
+
+/// API path of one table of a columnar export
+fn columnar_export_path(
+    tenant: &TenantId,
+    format: &ExportFormat,
+    table: &str,
+    as_of_time: Option<DateTime<Utc>>,
+) -> String {
+    let mut path = format!("/graph/{}/export?format={}&table={}", tenant.as_str(), format, table);
+    if let Some(timestamp) = as_of_time {
+        path.push_str(&format!("&valid_at={}", timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)));
+    }
+    path
+}
+
+/// Write export data, encrypted if requested, together with a signed manifest
+fn write_signed_export(
+    key: &ExportKey,
+    data: &ExportData,
+    format: &ExportFormat,
+    formatted_output: &str,
+    path: &Path,
+    encrypt: bool,
+) -> Result<(), CoreError> {
+    let tenant_id = &data.metadata.tenant_id;
+    let bytes = if encrypt {
+        key.encrypt(tenant_id, formatted_output.as_bytes())?
     } else {
-        format!("?{}", query_params.join("&"))
+        formatted_output.as_bytes().to_vec()
+    };
+    
+    let mut manifest = ExportManifest::new(
+        tenant_id.as_str(),
+        format.to_string(),
+        data.metadata.snapshot_at.clone(),
+        data.metadata.node_count,
+        data.metadata.edge_count,
+        encrypt,
+        &bytes,
+    );
+    key.sign(&mut manifest)?;
+    
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| CoreError::Internal(format!("Failed to serialize manifest: {}", e)))?;
+    
+    write_bytes_to_file(&bytes, path)?;
+    write_to_file(&manifest_json, &manifest_path(path))
+}
+
+/// Verify a signed export and write out its plaintext
+fn verify_export(
+    config: &KgctlConfig,
+    input: &Path,
+    manifest: Option<&Path>,
+    tenant: Option<String>,
+    key_file: Option<&Path>,
+    output: Option<&Path>,
+) -> Result<(), CoreError> {
+    let manifest_file = manifest.map(Path::to_path_buf).unwrap_or_else(|| manifest_path(input));
+    let manifest = read_manifest(&manifest_file)?;
+    
+    let tenant_id = tenant.unwrap_or_else(|| manifest.tenant_id.clone());
+    let key = config.export_key(&tenant_id, key_file)?
+        .ok_or_else(|| CoreError::Configuration(format!(
+            "No export key for tenant '{}'. Use --key-file or set export_keys in config", tenant_id
+        )))?;
+    
+    let plaintext = read_verified_export(&key, &tenant_id, &manifest, input)?;
+    
+    let summary = format!(
+        "✓ Export verified: {} nodes and {} edges for tenant {}",
+        manifest.node_count, manifest.edge_count, manifest.tenant_id
+    );
+    match output {
+        Some(path) => write_bytes_to_file(&plaintext, path)?,
+        None if manifest.encrypted => {
+            io::stdout().write_all(&plaintext)
+                .map_err(|e| CoreError::Internal(format!("Failed to write to stdout: {}", e)))?;
+            // The plaintext is the output; keep the summary off stdout
+            eprintln!("{}", summary.green().bold());
+            return Ok(());
+        }
+        None => {}
+    }
+    
+    let outcome = json!({
+        "verified": true,
+        "tenant_id": manifest.tenant_id,
+        "node_count": manifest.node_count,
+        "edge_count": manifest.edge_count,
+        "encrypted": manifest.encrypted,
+    });
+    output::display_outcome(&outcome, &config.default_format, || {
+        println!("{}", summary.green().bold());
+    })
+}
+
+/// Read an export to load into a tenant. An export with a manifest must
+/// match it under the tenant's key; one without is only accepted for tenants
+/// without an export key, since their keyed exports are always signed.
+pub(crate) fn read_export_to_restore(
+    config: &KgctlConfig,
+    input: &Path,
+    manifest: Option<&Path>,
+    tenant_id: &str,
+    key_file: Option<&Path>,
+) -> Result<(Option<ExportManifest>, Vec<u8>), CoreError> {
+    let manifest = match manifest {
+        Some(path) => Some(read_manifest(path)?),
+        None => {
+            let path = manifest_path(input);
+            path.exists().then(|| read_manifest(&path)).transpose()?
+        }
     };
     
-    // Fetch nodes if requested
-    if include_nodes {
-        debug!("Fetching nodes for tenant: {}", tenant);
-        let response = client.get(&format!("/graph/{}/nodes{}", tenant.as_str(), query_string)).await?;
-        nodes = client.handle_response(response).await?;
+    match (manifest, config.export_key(tenant_id, key_file)?) {
+        (Some(manifest), Some(key)) => {
+            let data = read_verified_export(&key, tenant_id, &manifest, input)?;
+            Ok((Some(manifest), data))
+        }
+        (Some(_), None) => Err(CoreError::Configuration(format!(
+            "No export key for tenant '{}' to verify the export with. Use --key-file or set export_keys in config", tenant_id
+        ))),
+        (None, Some(_)) => Err(CoreError::Configuration(format!(
+            "Export {} has no manifest; exports of tenant '{}' must be signed", input.display(), tenant_id
+        ))),
+        (None, None) => std::fs::read(input)
+            .map(|data| (None, data))
+            .map_err(|e| CoreError::Internal(format!("Failed to read export {}: {}", input.display(), e))),
     }
+}
+
+/// Read an export manifest from disk
+fn read_manifest(path: &Path) -> Result<ExportManifest, CoreError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| CoreError::Internal(format!("Failed to read manifest {}: {}", path.display(), e)))?;
     
-    // Fetch edges if requested
-    if include_edges {
-        debug!("Fetching edges for tenant: {}", tenant);
-        let response = client.get(&format!("/graph/{}/edges{}", tenant.as_str(), query_string)).await?;
-        edges = client.handle_response(response).await?;
+    serde_json::from_str(&content)
+        .map_err(|e| CoreError::Internal(format!("Invalid manifest {}: {}", path.display(), e)))
+}
+
+/// Check an export against its manifest and return the plaintext data
+fn read_verified_export(
+    key: &ExportKey,
+    tenant_id: &str,
+    manifest: &ExportManifest,
+    input: &Path,
+) -> Result<Vec<u8>, CoreError> {
+    if manifest.tenant_id != tenant_id {
+        return Err(CoreError::Tenant(format!(
+            "Export belongs to tenant '{}', not '{}'", manifest.tenant_id, tenant_id
+        )));
     }
     
-    Ok(ExportData {
+    let data = std::fs::read(input)
+        .map_err(|e| CoreError::Internal(format!("Failed to read export {}: {}", input.display(), e)))?;
+    
+    key.verify(manifest, &data)?;
+    debug!("Export {} matches its signed manifest", input.display());
+    
+    if manifest.encrypted {
+        key.decrypt(tenant_id, &data)
+    } else {
+        Ok(data)
+    }
+}
+
+/// Fetch a consistent snapshot from the TelaMentis API
+async fn fetch_snapshot(
+    client: &TelaMentisClient,
+    tenant: &TenantId,
+    as_of_time: Option<DateTime<Utc>>,
+) -> Result<GraphSnapshot, CoreError> {
+    // Nodes and edges come from a single snapshot so they are consistent with
+    // each other even while writes continue
+    let query_string = match as_of_time {
+        // UTC with a `Z` suffix keeps the timestamp free of characters needing escaping
+        Some(timestamp) => format!("?valid_at={}", timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        None => String::new(),
+    };
+    
+    debug!("Fetching snapshot for tenant: {}", tenant);
+    let response = client.get(&format!("/graph/{}/export{}", tenant.as_str(), query_string)).await?;
+    client.handle_response(response).await
+}
+
+/// Convert a graph snapshot into export records
+fn snapshot_to_export_data(
+    tenant: &TenantId,
+    snapshot: GraphSnapshot,
+    include_nodes: bool,
+    include_edges: bool,
+) -> ExportData {
+    let nodes: Vec<ExportNode> = if include_nodes {
+        snapshot.nodes.into_iter()
+            .map(|record| ExportNode {
+                id: record.id.to_string(),
+                id_alias: record.node.id_alias,
+                alias_namespace: record.node.alias_namespace,
+                labels: vec![record.node.label],
+                properties: record.node.props,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    
+    let edges: Vec<ExportEdge> = if include_edges {
+        snapshot.edges.into_iter()
+            .map(|record| ExportEdge {
+                id: record.id.to_string(),
+                from_node: record.edge.from_node_id.to_string(),
+                to_node: record.edge.to_node_id.to_string(),
+                edge_type: record.edge.kind,
+                valid_from: Some(record.edge.valid_from),
+                valid_to: record.edge.valid_to,
+                properties: record.edge.props,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    
+    ExportData {
         metadata: ExportMetadata {
             tenant_id: tenant.to_string(),
             export_timestamp: Utc::now().to_rfc3339(),
             node_count: nodes.len(),
             edge_count: edges.len(),
-            temporal_as_of: as_of_time.map(|t| t.to_rfc3339()),
+            temporal_as_of: snapshot.valid_at.map(|t| t.to_rfc3339()),
+            snapshot_at: Some(snapshot.snapshot_at.to_rfc3339()),
         },
         nodes,
         edges,
-    })
+    }
 }
 
 /// Format export data according to the specified format
@@ -177,6 +547,12 @@ fn format_export_data(data: &ExportData, format: &ExportFormat) -> Result<String
         ExportFormat::Jsonl => format_as_jsonl(data),
         ExportFormat::Cypher => format_as_cypher(data),
         ExportFormat::Csv => format_as_csv(data),
+        ExportFormat::Arrow | ExportFormat::Parquet => Err(CoreError::Internal(format!(
+            "{} exports are streamed from the server", format
+        ))),
+        ExportFormat::Turtle | ExportFormat::NTriples => Err(CoreError::Internal(format!(
+            "{} exports are encoded from the snapshot", format
+        ))),
     }
 }
 
@@ -258,6 +634,30 @@ fn format_as_jsonl(data: &ExportData) -> Result<String, CoreError> {
     Ok(output)
 }
 
+/// Parse an export written as JSONL: its metadata, then a line per node and
+/// a line per edge
+pub(crate) fn parse_jsonl(content: &str) -> Result<ExportData, CoreError> {
+    let mut lines = content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let parse_error = |number: usize, e: serde_json::Error| {
+        CoreError::Internal(format!("Invalid export on line {}: {}", number + 1, e))
+    };
+    
+    let (number, metadata) = lines.next()
+        .ok_or_else(|| CoreError::Internal("Export is empty".to_string()))?;
+    let metadata = serde_json::from_str(metadata).map_err(|e| parse_error(number, e))?;
+    
+    let mut data = ExportData { nodes: Vec::new(), edges: Vec::new(), metadata };
+    for (number, line) in lines {
+        let value: serde_json::Value = serde_json::from_str(line).map_err(|e| parse_error(number, e))?;
+        if value.get("from_node").is_some() {
+            data.edges.push(serde_json::from_value(value).map_err(|e| parse_error(number, e))?);
+        } else {
+            data.nodes.push(serde_json::from_value(value).map_err(|e| parse_error(number, e))?);
+        }
+    }
+    Ok(data)
+}
+
 /// Format data as Cypher statements
 fn format_as_cypher(data: &ExportData) -> Result<String, CoreError> {
     let mut output = String::new();
@@ -350,17 +750,21 @@ fn format_as_csv(data: &ExportData) -> Result<String, CoreError> {
 
 /// Parse temporal constraint string
 fn parse_temporal_constraint(time_str: &str) -> Result<DateTime<Utc>, CoreError> {
-    DateTime::parse_from_rfc3339(time_str)
-        .map(|dt| dt.with_timezone(&Utc))
-        .map_err(|e| CoreError::Internal(format!("Invalid temporal constraint '{}': {}", time_str, e)))
+    TemporalUtils::parse_time(time_str, IntervalBound::AsOf)
+        .map_err(|e| CoreError::Internal(format!("Invalid temporal constraint: {}", e)))
 }
 
 /// Write output to file
 fn write_to_file(content: &str, path: &Path) -> Result<(), CoreError> {
+    write_bytes_to_file(content.as_bytes(), path)
+}
+
+/// Write raw bytes to file
+fn write_bytes_to_file(content: &[u8], path: &Path) -> Result<(), CoreError> {
     let mut file = File::create(path)
         .map_err(|e| CoreError::Internal(format!("Failed to create file {}: {}", path.display(), e)))?;
     
-    file.write_all(content.as_bytes())
+    file.write_all(content)
         .map_err(|e| CoreError::Internal(format!("Failed to write to file {}: {}", path.display(), e)))?;
     
     Ok(())
@@ -416,6 +820,138 @@ mod tests {
         assert_eq!(sanitize_cypher_identifier("with spaces"), "with_spaces");
     }
 
+    #[test]
+    fn test_snapshot_to_export_data() {
+        use telamentis_core::types::{EdgeRecord, Node, NodeRecord, TimeEdge};
+        use uuid::Uuid;
+
+        let alice = Uuid::new_v4();
+        let acme = Uuid::new_v4();
+        let snapshot = GraphSnapshot {
+            snapshot_at: Utc::now(),
+            valid_at: None,
+            nodes: vec![
+                NodeRecord { id: alice, node: Node::new("Person") },
+                NodeRecord { id: acme, node: Node::new("Company") },
+            ],
+            edges: vec![EdgeRecord {
+                id: Uuid::new_v4(),
+                edge: TimeEdge::new(alice, acme, "WORKS_FOR", Utc::now(), serde_json::json!({})),
+            }],
+        };
+
+        let tenant = TenantId::new("tenant");
+        let data = snapshot_to_export_data(&tenant, snapshot.clone(), true, true);
+        assert_eq!(data.metadata.node_count, data.nodes.len());
+        assert_eq!(data.metadata.edge_count, 1);
+        assert_eq!(data.edges[0].from_node, alice.to_string());
+        assert!(data.metadata.snapshot_at.is_some());
+
+        let data = snapshot_to_export_data(&tenant, snapshot, true, false);
+        assert_eq!(data.metadata.edge_count, 0);
+        assert!(data.edges.is_empty());
+    }
+
+    #[test]
+    fn test_signed_export_roundtrip() {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+        let key = ExportKey::from_base64(&BASE64.encode([3u8; 32])).unwrap();
+        let tenant = TenantId::new("tenant");
+        let snapshot = GraphSnapshot {
+            snapshot_at: Utc::now(),
+            valid_at: None,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        };
+        let data = snapshot_to_export_data(&tenant, snapshot, true, true);
+        let formatted = format_as_jsonl(&data).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.jsonl");
+        write_signed_export(&key, &data, &ExportFormat::Jsonl, &formatted, &path, true).unwrap();
+
+        let manifest = read_manifest(&manifest_path(&path)).unwrap();
+        assert!(manifest.encrypted);
+        assert_ne!(std::fs::read(&path).unwrap(), formatted.as_bytes());
+
+        let plaintext = read_verified_export(&key, "tenant", &manifest, &path).unwrap();
+        assert_eq!(plaintext, formatted.as_bytes());
+        assert!(read_verified_export(&key, "other", &manifest, &path).is_err());
+
+        std::fs::write(&path, b"tampered").unwrap();
+        assert!(read_verified_export(&key, "tenant", &manifest, &path).is_err());
+    }
+
+    #[test]
+    fn test_read_export_to_restore() {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+        use telamentis_core::types::{EdgeRecord, Node, NodeRecord, TimeEdge};
+        use uuid::Uuid;
+
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("export.key");
+        std::fs::write(&key_file, BASE64.encode([3u8; 32])).unwrap();
+        let key = ExportKey::from_file(&key_file).unwrap();
+        let config = KgctlConfig::default();
+
+        let tenant = TenantId::new("tenant");
+        let alice = Uuid::new_v4();
+        let bob = Uuid::new_v4();
+        let mut knows = TimeEdge::new(alice, bob, "KNOWS", parse_temporal_constraint("2020-01-01T00:00:00Z").unwrap(), serde_json::json!({}));
+        knows.valid_to = Some(parse_temporal_constraint("2024-01-01T00:00:00Z").unwrap());
+        let snapshot = GraphSnapshot {
+            snapshot_at: Utc::now(),
+            valid_at: None,
+            nodes: vec![
+                NodeRecord { id: alice, node: Node::new("Person").with_id_alias("alice") },
+                NodeRecord { id: bob, node: Node::new("Person") },
+            ],
+            edges: vec![EdgeRecord { id: Uuid::new_v4(), edge: knows.clone() }],
+        };
+        let data = snapshot_to_export_data(&tenant, snapshot, true, true);
+        let formatted = format_as_jsonl(&data).unwrap();
+
+        let path = dir.path().join("export.jsonl");
+        write_signed_export(&key, &data, &ExportFormat::Jsonl, &formatted, &path, true).unwrap();
+        let (manifest, plaintext) = read_export_to_restore(&config, &path, None, "tenant", Some(&key_file)).unwrap();
+        assert_eq!(manifest.unwrap().format, "jsonl");
+
+        let restored = parse_jsonl(std::str::from_utf8(&plaintext).unwrap()).unwrap();
+        assert_eq!(restored.metadata.tenant_id, "tenant");
+        assert_eq!(restored.nodes.len(), 2);
+        assert_eq!(restored.nodes[0].id_alias.as_deref(), Some("alice"));
+        assert_eq!(restored.edges[0].from_node, alice.to_string());
+        assert_eq!(restored.edges[0].valid_from, Some(knows.valid_from));
+        assert_eq!(restored.edges[0].valid_to, knows.valid_to);
+
+        // Without a key the signed export can't be checked, and a keyed
+        // tenant's export must come with its manifest
+        assert!(read_export_to_restore(&config, &path, None, "tenant", None).is_err());
+        assert!(read_export_to_restore(&config, &path, None, "other", Some(&key_file)).is_err());
+        std::fs::remove_file(manifest_path(&path)).unwrap();
+        assert!(read_export_to_restore(&config, &path, None, "tenant", Some(&key_file)).is_err());
+
+        std::fs::write(&path, &formatted).unwrap();
+        let (manifest, plaintext) = read_export_to_restore(&config, &path, None, "tenant", None).unwrap();
+        assert!(manifest.is_none());
+        assert_eq!(plaintext, formatted.as_bytes());
+    }
+
+    #[test]
+    fn test_columnar_export_path() {
+        let tenant = TenantId::new("acme");
+        assert_eq!(
+            columnar_export_path(&tenant, &ExportFormat::Parquet, "nodes", None),
+            "/graph/acme/export?format=parquet&table=nodes"
+        );
+        let as_of = parse_temporal_constraint("2024-01-15T10:30:00Z").unwrap();
+        assert_eq!(
+            columnar_export_path(&tenant, &ExportFormat::Arrow, "edges", Some(as_of)),
+            "/graph/acme/export?format=arrow&table=edges&valid_at=2024-01-15T10:30:00.000Z"
+        );
+    }
+
     #[test]
     fn test_parse_temporal_constraint() {
         let result = parse_temporal_constraint("2024-01-15T10:30:00Z");
@@ -14,15 +14,15 @@ pub async fn handle_health_command(config: &KgctlConfig) -> Result<(), CoreError
     info!("Checking TelaMentis health at {}", config.endpoint);
     
     match check_health(&client).await {
-        Ok(health) => {
+        Ok(health) => output::display_outcome(&health, &config.default_format, || {
             println!("{}", "✓ TelaMentis is healthy".green().bold());
             println!("Status: {}", health.status.green());
-            if let Some(version) = health.version {
+            if let Some(version) = &health.version {
                 println!("Version: {}", version);
             }
             println!("Timestamp: {}", health.timestamp);
-            Ok(())
-        }
+        }),
+        Err(e) if !config.default_format.is_table() => Err(e),
         Err(e) => {
             println!("{}", "✗ TelaMentis health check failed".red().bold());
             println!("Error: {}", e.to_string().red());
@@ -0,0 +1,71 @@
+//! History command implementations
+
+use crate::cli::HistoryCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use chrono::{DateTime, Utc};
+use colored::*;
+use telamentis_core::errors::CoreError;
+use telamentis_core::property_history::PropertyHistory;
+use tracing::info;
+
+/// Handle history commands
+pub async fn handle_history_command(command: HistoryCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        HistoryCommands::Prop { alias, key, tenant, namespace } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            property_history(&client, config, &tenant_id, &alias, &key, namespace).await
+        }
+    }
+}
+
+/// Show the values a property of an aliased node took, oldest first
+async fn property_history(
+    client: &TelaMentisClient,
+    config: &KgctlConfig,
+    tenant_id: &str,
+    alias: &str,
+    key: &str,
+    namespace: Option<String>,
+) -> Result<(), CoreError> {
+    info!("Fetching history of property {} of alias {} for tenant: {}", key, alias, tenant_id);
+
+    let params: Vec<(&str, String)> = namespace.into_iter().map(|namespace| ("namespace", namespace)).collect();
+    let url = reqwest::Url::parse_with_params("http://localhost/", &params)
+        .map_err(|e| CoreError::Internal(format!("Failed to build history URL: {}", e)))?;
+    let mut path = format!("/graph/{}/aliases/{}/properties/{}/history", tenant_id, alias, key);
+    if let Some(query) = url.query().filter(|query| !query.is_empty()) {
+        path.push('?');
+        path.push_str(query);
+    }
+    let response = client.get(&path).await?;
+    let history: PropertyHistory = client.handle_response(response).await?;
+
+    output::display_outcome(&history, &config.default_format, || {
+        println!("History of '{}' of '{}' ({})", key, alias, history.node_id);
+        if history.values.is_empty() {
+            println!("{}", "The property was never set".yellow());
+        }
+        for value in &history.values {
+            let held = format!("{} to {}", time(value.valid_from), value.valid_to.map(time).unwrap_or_else(|| "now".to_string()));
+            let recorded = match value.transaction_end_time {
+                Some(end) => format!("recorded {}, retracted {}", time(value.transaction_start_time), time(end)).red(),
+                None => format!("recorded {}", time(value.transaction_start_time)).normal(),
+            };
+            let current = value.valid_to.is_none() && value.transaction_end_time.is_none();
+            println!(
+                "{:<37}  {}  ({})",
+                held,
+                if current { value.value.to_string().green() } else { value.value.to_string().normal() },
+                recorded,
+            );
+        }
+    })
+}
+
+fn time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
@@ -2,15 +2,18 @@
 
 use crate::cli::{IngestCommands, DataType};
 use crate::client::TelaMentisClient;
+use crate::commands::export::{parse_jsonl, read_export_to_restore};
 use crate::config::KgctlConfig;
+use crate::output;
 use chrono::{DateTime, Utc};
 use colored::*;
 use csv::ReaderBuilder;
-use serde_json::{Map, Value};
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
 use std::fs::File;
 use std::path::Path;
 use telamentis_core::errors::CoreError;
+use telamentis_core::ingest_template::{infer_value, parse_timestamp};
 use telamentis_core::prelude::*;
 use tracing::{debug, info, warn};
 
@@ -24,6 +27,7 @@ pub async fn handle_ingest_command(command: IngestCommands, config: &KgctlConfig
             delimiter, 
             header, 
             id_col, 
+            alias_namespace,
             label_col, 
             label, 
             props_cols,
@@ -35,9 +39,26 @@ pub async fn handle_ingest_command(command: IngestCommands, config: &KgctlConfig
             valid_to_col,
             date_format,
             batch_size,
+            column_types,
+            strict,
+            template,
         } => {
             let tenant_id = config.get_tenant(&tenant)?;
             
+            if let Some(template) = template {
+                let client = TelaMentisClient::new(config.clone())?;
+                let path = format!("/graph/{}/ingest-templates/{}", tenant_id, template);
+                let mut template: IngestTemplate = client.handle_response(client.get(&path).await?).await?;
+                template.strict |= strict;
+                let schema = fetch_schema(&client, &tenant_id).await?;
+                for file_path in file {
+                    ingest_csv_with_template(config, &client, &file_path, &tenant_id, &template, schema.as_ref(), delimiter, header, batch_size).await?;
+                }
+                return Ok(());
+            }
+            
+            let pinned = parse_column_types(&column_types)?;
+            
             for file_path in file {
                 ingest_csv_file(
                     config,
@@ -47,6 +68,7 @@ pub async fn handle_ingest_command(command: IngestCommands, config: &KgctlConfig
                     delimiter,
                     header,
                     &id_col,
+                    &alias_namespace,
                     &label_col,
                     &label,
                     &props_cols,
@@ -57,15 +79,107 @@ pub async fn handle_ingest_command(command: IngestCommands, config: &KgctlConfig
                     &valid_from_col,
                     &valid_to_col,
                     &date_format,
+                    &pinned,
+                    strict,
                     batch_size,
                 ).await?;
             }
             
             Ok(())
         }
+        IngestCommands::Restore { input, manifest, tenant, key_file, batch_size } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            restore_export(config, &input, manifest.as_deref(), &tenant_id, key_file.as_deref(), batch_size).await
+        }
     }
 }
 
+/// Restore a JSONL export into a tenant once it matches its signed manifest.
+/// Nodes get new IDs, which the edges are remapped to. Edges keep their valid
+/// times; those from exports without them are valid from the snapshot time.
+async fn restore_export(
+    config: &KgctlConfig,
+    input: &Path,
+    manifest: Option<&Path>,
+    tenant_id: &str,
+    key_file: Option<&Path>,
+    batch_size: usize,
+) -> Result<(), CoreError> {
+    let (manifest, data) = read_export_to_restore(config, input, manifest, tenant_id, key_file)?;
+    if let Some(format) = manifest.as_ref().map(|manifest| manifest.format.as_str()).filter(|format| *format != "jsonl") {
+        return Err(CoreError::Configuration(format!("Only JSONL exports can be restored, not {}", format)));
+    }
+    let content = String::from_utf8(data)
+        .map_err(|_| CoreError::Internal(format!("Export {} is not UTF-8 text", input.display())))?;
+    let export = parse_jsonl(&content)?;
+    
+    let client = TelaMentisClient::new(config.clone())?;
+    let tenant = TenantId::new(tenant_id);
+    let snapshot_at = export.metadata.snapshot_at.as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map_or_else(Utc::now, |at| at.with_timezone(&Utc));
+    
+    let mut node_ids = HashMap::new();
+    for exported in &export.nodes {
+        let mut node = Node::new(exported.labels.first().map_or("Node", String::as_str))
+            .with_props(exported.properties.clone());
+        node.id_alias = exported.id_alias.clone();
+        node.alias_namespace = exported.alias_namespace.clone();
+        
+        let response = client.post(&format!("/graph/{}/nodes", tenant.as_str()), &json!({ "node": node })).await?;
+        let response: Value = client.handle_response(response).await?;
+        let node_id = response.pointer("/data/node_id")
+            .and_then(Value::as_str)
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .ok_or_else(|| CoreError::Internal(format!("No ID returned for restored node {}", exported.id)))?;
+        node_ids.insert(exported.id.as_str(), node_id);
+    }
+    
+    let mut skipped = 0;
+    let mut edges = Vec::new();
+    for exported in &export.edges {
+        let (Some(from), Some(to)) = (node_ids.get(exported.from_node.as_str()), node_ids.get(exported.to_node.as_str())) else {
+            warn!("Skipping edge {}: its nodes are not in the export", exported.id);
+            skipped += 1;
+            continue;
+        };
+        let mut edge = TimeEdge::new(
+            *from, *to, exported.edge_type.clone(),
+            exported.valid_from.unwrap_or(snapshot_at), exported.properties.clone(),
+        );
+        edge.valid_to = exported.valid_to;
+        edges.push(edge);
+    }
+    
+    let mut edge_count = 0;
+    for batch in edges.chunks(batch_size.max(1)) {
+        let response = client.post(&format!("/graph/{}/edges/batch", tenant.as_str()), &json!({ "edges": batch })).await?;
+        let response: Value = client.handle_response(response).await?;
+        edge_count += response.pointer("/data/edge_ids").and_then(Value::as_array).map_or(0, Vec::len);
+    }
+    debug!("Restored {} of {} edges from {}", edge_count, export.edges.len(), input.display());
+    
+    let outcome = json!({
+        "tenant_id": tenant.as_str(),
+        "verified": manifest.is_some(),
+        "nodes": node_ids.len(),
+        "edges": edge_count,
+        "skipped_edges": skipped,
+    });
+    output::display_outcome(&outcome, &config.default_format, || {
+        if manifest.is_some() {
+            println!("{}", "✓ Export matches its signed manifest".green());
+        }
+        println!("{}", format!(
+            "✓ Restored {} nodes and {} of {} edges into tenant {}",
+            node_ids.len(), edge_count, export.edges.len(), tenant
+        ).green().bold());
+        if skipped > 0 {
+            println!("{}", format!("  Skipped {} edges whose nodes are not in the export", skipped).yellow());
+        }
+    })
+}
+
 /// Ingest data from a CSV file
 #[allow(clippy::too_many_arguments)]
 async fn ingest_csv_file(
@@ -76,6 +190,7 @@ async fn ingest_csv_file(
     delimiter: char,
     has_header: bool,
     id_col: &Option<String>,
+    alias_namespace: &Option<String>,
     label_col: &Option<String>,
     default_label: &Option<String>,
     props_cols: &Option<String>,
@@ -86,6 +201,8 @@ async fn ingest_csv_file(
     valid_from_col: &Option<String>,
     valid_to_col: &Option<String>,
     date_format: &str,
+    pinned: &HashMap<String, Coercion>,
+    strict: bool,
     batch_size: usize,
 ) -> Result<(), CoreError> {
     info!("Ingesting {} from: {}", 
@@ -104,6 +221,14 @@ async fn ingest_csv_file(
     
     let client = TelaMentisClient::new(config.clone())?;
     let tenant = TenantId::new(tenant_id);
+    let valid_time = config.valid_time.for_tenant(&tenant);
+    let types = PropertyTypes {
+        kind: match data_type { DataType::Node => TemplateKind::Node, DataType::Relationship => TemplateKind::Relationship },
+        schema: fetch_schema(&client, tenant_id).await?,
+        pinned: pinned.clone(),
+        strict,
+        date_format: date_format.to_string(),
+    };
     
     // Get headers for column mapping
     let headers: Vec<String> = if has_header {
@@ -123,6 +248,7 @@ async fn ingest_csv_file(
     
     // Process rows in batches
     let mut batch = Vec::new();
+    let mut batch_rows = Vec::new();
     let mut row_count = 0;
     let mut success_count = 0;
     let mut error_count = 0;
@@ -139,11 +265,17 @@ async fn ingest_csv_file(
                     &record,
                     &headers,
                     id_col,
+                    alias_namespace,
                     label_col,
                     default_label,
                     props_cols,
+                    &types,
+                    valid_time,
                 ) {
-                    Ok(node) => batch.push(node),
+                    Ok(node) => {
+                        batch.push(json!(node));
+                        batch_rows.push(row_count);
+                    }
                     Err(e) => {
                         warn!("Skipping row {}: {}", row_count, e);
                         error_count += 1;
@@ -155,6 +287,7 @@ async fn ingest_csv_file(
                 match process_relationship_record(
                     &record,
                     &headers,
+                    alias_namespace,
                     from_col,
                     to_col,
                     rel_type_val,
@@ -163,8 +296,13 @@ async fn ingest_csv_file(
                     valid_from_col,
                     valid_to_col,
                     date_format,
+                    &types,
+                    valid_time,
                 ) {
-                    Ok(edge) => batch.push(edge),
+                    Ok(edge) => {
+                        batch.push(json!(edge));
+                        batch_rows.push(row_count);
+                    }
                     Err(e) => {
                         warn!("Skipping row {}: {}", row_count, e);
                         error_count += 1;
@@ -176,11 +314,13 @@ async fn ingest_csv_file(
         
         // Process batch when it reaches the target size
         if batch.len() >= batch_size {
-            let batch_success = process_batch(&client, &tenant, &batch, &data_type).await?;
+            let batch_success = process_batch(&client, &tenant, &batch, &batch_rows, &data_type).await?;
             success_count += batch_success;
+            error_count += batch.len() - batch_success;
             batch.clear();
+            batch_rows.clear();
             
-            if row_count % 1000 == 0 {
+            if row_count % 1000 == 0 && config.default_format.is_table() {
                 println!("Processed {} rows ({} successful, {} errors)", row_count, success_count, error_count);
             }
         }
@@ -188,26 +328,208 @@ async fn ingest_csv_file(
     
     // Process remaining items in the final batch
     if !batch.is_empty() {
-        let batch_success = process_batch(&client, &tenant, &batch, &data_type).await?;
+        let batch_success = process_batch(&client, &tenant, &batch, &batch_rows, &data_type).await?;
         success_count += batch_success;
+        error_count += batch.len() - batch_success;
     }
     
-    println!("{}", format!(
-        "✓ Ingestion completed: {} total rows, {} successful, {} errors",
-        row_count, success_count, error_count
-    ).green().bold());
+    let outcome = json!({
+        "tenant_id": tenant.as_str(),
+        "rows": row_count,
+        "successful": success_count,
+        "errors": error_count,
+    });
+    output::display_outcome(&outcome, &config.default_format, || {
+        println!("{}", format!(
+            "✓ Ingestion completed: {} total rows, {} successful, {} errors",
+            row_count, success_count, error_count
+        ).green().bold());
+    })
+}
+
+/// Ingest a CSV file, mapping its rows with a server-side ingestion template
+#[allow(clippy::too_many_arguments)]
+async fn ingest_csv_with_template(
+    config: &KgctlConfig,
+    client: &TelaMentisClient,
+    file_path: &Path,
+    tenant_id: &str,
+    template: &IngestTemplate,
+    schema: Option<&IngestSchema>,
+    delimiter: char,
+    has_header: bool,
+    batch_size: usize,
+) -> Result<(), CoreError> {
+    info!("Ingesting {} with template '{}'", file_path.display(), template.name);
+    
+    let file = File::open(file_path)
+        .map_err(|e| CoreError::Internal(format!("Failed to open file {}: {}", file_path.display(), e)))?;
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .has_headers(has_header)
+        .from_reader(file);
     
-    Ok(())
+    let tenant = TenantId::new(tenant_id);
+    let valid_time = config.valid_time.for_tenant(&tenant);
+    let headers: Vec<String> = {
+        let headers = reader.headers().map_err(|e| CoreError::Internal(format!("Failed to read headers: {}", e)))?;
+        if has_header {
+            headers.iter().map(|h| h.to_string()).collect()
+        } else {
+            (0..headers.len()).map(|i| i.to_string()).collect()
+        }
+    };
+    let data_type = match template.kind {
+        TemplateKind::Node => DataType::Node,
+        TemplateKind::Relationship => DataType::Relationship,
+    };
+    
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    // Rows of the pending batch; a template maps every row to the same kind of record
+    let mut rows = Vec::new();
+    let mut row_count = 0;
+    let mut success_count = 0;
+    let mut error_count = 0;
+    
+    for result in reader.records() {
+        let record = result
+            .map_err(|e| CoreError::Internal(format!("Failed to read CSV record: {}", e)))?;
+        row_count += 1;
+        
+        let row: Vec<&str> = record.iter().collect();
+        match template.map_row_with_schema(&headers, &row, valid_time, schema) {
+            Ok(MappedRecord::Node(node)) => nodes.push(node),
+            Ok(MappedRecord::Edge(edge)) => edges.push(edge),
+            Err(e) => {
+                warn!("Skipping row {}: {}", row_count, e);
+                error_count += 1;
+                continue;
+            }
+        }
+        rows.push(row_count);
+        
+        if nodes.len() >= batch_size {
+            let loaded = process_batch(client, &tenant, &nodes, &rows, &data_type).await?;
+            success_count += loaded;
+            error_count += nodes.len() - loaded;
+            nodes.clear();
+            rows.clear();
+        }
+        if edges.len() >= batch_size {
+            let loaded = process_batch(client, &tenant, &edges, &rows, &data_type).await?;
+            success_count += loaded;
+            error_count += edges.len() - loaded;
+            edges.clear();
+            rows.clear();
+        }
+    }
+    
+    if !nodes.is_empty() {
+        let loaded = process_batch(client, &tenant, &nodes, &rows, &data_type).await?;
+        success_count += loaded;
+        error_count += nodes.len() - loaded;
+    }
+    if !edges.is_empty() {
+        let loaded = process_batch(client, &tenant, &edges, &rows, &data_type).await?;
+        success_count += loaded;
+        error_count += edges.len() - loaded;
+    }
+    
+    let outcome = json!({
+        "tenant_id": tenant.as_str(),
+        "template": template.name,
+        "rows": row_count,
+        "successful": success_count,
+        "errors": error_count,
+    });
+    output::display_outcome(&outcome, &config.default_format, || {
+        println!("{}", format!(
+            "✓ Ingestion with template '{}' completed: {} total rows, {} successful, {} errors",
+            template.name, row_count, success_count, error_count
+        ).green().bold());
+    })
+}
+
+/// How the property cells of rows are typed
+struct PropertyTypes {
+    kind: TemplateKind,
+    /// The tenant's ingestion schema, if it has one
+    schema: Option<IngestSchema>,
+    /// Types pinned by column name, over the schema
+    pinned: HashMap<String, Coercion>,
+    strict: bool,
+    date_format: String,
+}
+
+impl PropertyTypes {
+    /// Type a cell of a property column of a row of label or kind `name`
+    fn value(&self, name: &str, column: &str, cell: &str, valid_time: &ValidTimePolicy) -> Result<Value, String> {
+        let coercion = self.pinned.get(column).copied()
+            .or_else(|| self.schema.as_ref()?.property_type(self.kind, name, column));
+        match coercion {
+            Some(coercion) => coercion.apply(cell, &self.date_format, valid_time)
+                .map_err(|e| format!("Column '{}': {}", column, e)),
+            None => Ok(infer_value(cell, self.strict)),
+        }
+    }
+
+    /// Type the property cells of a row, reporting every cell that does not fit
+    fn properties<'a>(&self, name: &str, cells: impl Iterator<Item = (&'a String, &'a str)>, valid_time: &ValidTimePolicy) -> Result<Map<String, Value>, CoreError> {
+        let mut props = Map::new();
+        let mut errors = Vec::new();
+        for (column, cell) in cells {
+            match self.value(name, column, cell, valid_time) {
+                Ok(value) => {
+                    props.insert(column.clone(), value);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(props)
+        } else {
+            Err(CoreError::Internal(errors.join("; ")))
+        }
+    }
+}
+
+/// Parse `--column-type` values of the form COLUMN=TYPE
+fn parse_column_types(column_types: &[String]) -> Result<HashMap<String, Coercion>, CoreError> {
+    column_types.iter()
+        .map(|pin| {
+            let (column, kind) = pin.split_once('=')
+                .ok_or_else(|| CoreError::Configuration(format!("Column type '{}' is not of the form COLUMN=TYPE", pin)))?;
+            let coercion: Coercion = serde_json::from_value(Value::String(kind.trim().to_ascii_lowercase()))
+                .map_err(|_| CoreError::Configuration(format!(
+                    "Unknown column type '{}'; expected string, integer, float, boolean, json, timestamp, date or duration", kind
+                )))?;
+            Ok((column.trim().to_string(), coercion))
+        })
+        .collect()
+}
+
+/// The tenant's ingestion schema, if it has one
+async fn fetch_schema(client: &TelaMentisClient, tenant_id: &str) -> Result<Option<IngestSchema>, CoreError> {
+    let response = client.get(&format!("/graph/{}/ingest-schema", tenant_id)).await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    client.handle_response(response).await.map(Some)
 }
 
 /// Process a CSV record into a Node
+#[allow(clippy::too_many_arguments)]
 fn process_node_record(
     record: &csv::StringRecord,
     headers: &[String],
     id_col: &Option<String>,
+    alias_namespace: &Option<String>,
     label_col: &Option<String>,
     default_label: &Option<String>,
     props_cols: &Option<String>,
+    types: &PropertyTypes,
+    valid_time: &ValidTimePolicy,
 ) -> Result<Node, CoreError> {
     let mut node = Node::new("DefaultNode");
     
@@ -229,31 +551,21 @@ fn process_node_record(
         if let Some(id_value) = record.get(id_idx) {
             if !id_value.is_empty() {
                 node.id_alias = Some(id_value.to_string());
+                node.alias_namespace = alias_namespace.clone();
             }
         }
     }
     
     // Set properties
     let prop_indices = get_property_indices(headers, props_cols)?;
-    let mut props = Map::new();
-    
-    for (idx, header) in headers.iter().enumerate() {
+    let cells = headers.iter().enumerate()
         // Skip system columns
-        if Some(header) == id_col.as_ref() || Some(header) == label_col.as_ref() {
-            continue;
-        }
-        
+        .filter(|(_, header)| Some(*header) != id_col.as_ref() && Some(*header) != label_col.as_ref())
         // Include column if in props_cols or if props_cols is None (include all)
-        if prop_indices.is_empty() || prop_indices.contains(&idx) {
-            if let Some(value) = record.get(idx) {
-                if !value.is_empty() {
-                    props.insert(header.clone(), parse_csv_value(value));
-                }
-            }
-        }
-    }
+        .filter(|(idx, _)| prop_indices.is_empty() || prop_indices.contains(idx))
+        .filter_map(|(idx, header)| record.get(idx).filter(|value| !value.is_empty()).map(|value| (header, value)));
     
-    node.props = Value::Object(props);
+    node.props = Value::Object(types.properties(&node.label, cells, valid_time)?);
     Ok(node)
 }
 
@@ -262,6 +574,7 @@ fn process_node_record(
 fn process_relationship_record(
     record: &csv::StringRecord,
     headers: &[String],
+    alias_namespace: &Option<String>,
     from_col: &Option<String>,
     to_col: &Option<String>,
     rel_type_val: &Option<String>,
@@ -270,6 +583,8 @@ fn process_relationship_record(
     valid_from_col: &Option<String>,
     valid_to_col: &Option<String>,
     date_format: &str,
+    types: &PropertyTypes,
+    valid_time: &ValidTimePolicy,
 ) -> Result<TimeEdge, CoreError> {
     // Get from and to node references
     let from_id_alias = if let Some(col) = from_col {
@@ -302,14 +617,19 @@ fn process_relationship_record(
         return Err(CoreError::Internal("Either rel_type_val or rel_type_col is required".to_string()));
     };
     
-    // Get valid_from timestamp
-    let valid_from = if let Some(col) = valid_from_col {
-        let idx = find_column_index(headers, col)?;
-        let value = record.get(idx)
-            .ok_or_else(|| CoreError::Internal("Missing valid_from_col value".to_string()))?;
-        parse_datetime(value, date_format)?
+    // Get valid_from timestamp, falling back to the tenant's valid-time policy
+    let valid_from = match valid_from_col {
+        Some(col) => {
+            let idx = find_column_index(headers, col)?;
+            record.get(idx)
+                .ok_or_else(|| CoreError::Internal("Missing valid_from_col value".to_string()))?
+        }
+        None => "",
+    };
+    let valid_from = if !valid_from.is_empty() {
+        parse_datetime(valid_from, date_format, valid_time, IntervalBound::Start)?
     } else {
-        Utc::now() // Default to current time
+        valid_time.default_valid_from(&row_source(record, headers))
     };
     
     // Get valid_to timestamp (optional)
@@ -317,7 +637,7 @@ fn process_relationship_record(
         let idx = find_column_index(headers, col)?;
         if let Some(value) = record.get(idx) {
             if !value.is_empty() {
-                Some(parse_datetime(value, date_format)?)
+                Some(parse_datetime(value, date_format, valid_time, IntervalBound::End)?)
             } else {
                 None
             }
@@ -327,35 +647,27 @@ fn process_relationship_record(
     } else {
         None
     };
+    let valid_to = valid_to.or_else(|| valid_time.default_valid_to(valid_from));
     
     // Get properties
     let prop_indices = get_property_indices(headers, props_cols)?;
-    let mut props = Map::new();
-    
-    for (idx, header) in headers.iter().enumerate() {
+    let system_columns = [from_col, to_col, rel_type_col, valid_from_col, valid_to_col];
+    let cells = headers.iter().enumerate()
         // Skip system columns
-        if Some(header) == from_col.as_ref() 
-            || Some(header) == to_col.as_ref()
-            || Some(header) == rel_type_col.as_ref()
-            || Some(header) == valid_from_col.as_ref()
-            || Some(header) == valid_to_col.as_ref() {
-            continue;
-        }
-        
+        .filter(|(_, header)| !system_columns.iter().any(|col| Some(*header) == col.as_ref()))
         // Include column if in props_cols or if props_cols is None (include all)
-        if prop_indices.is_empty() || prop_indices.contains(&idx) {
-            if let Some(value) = record.get(idx) {
-                if !value.is_empty() {
-                    props.insert(header.clone(), parse_csv_value(value));
-                }
-            }
-        }
-    }
+        .filter(|(idx, _)| prop_indices.is_empty() || prop_indices.contains(idx))
+        .filter_map(|(idx, header)| record.get(idx).filter(|value| !value.is_empty()).map(|value| (header, value)));
+    let mut props = types.properties(&rel_type, cells, valid_time)?;
     
     // For CSV ingestion, we need to resolve node IDs later
     // For now, we'll store the id_aliases in the props and handle resolution in the API
     props.insert("_from_id_alias".to_string(), Value::String(from_id_alias));
     props.insert("_to_id_alias".to_string(), Value::String(to_id_alias));
+    if let Some(namespace) = alias_namespace {
+        props.insert("_from_alias_namespace".to_string(), Value::String(namespace.clone()));
+        props.insert("_to_alias_namespace".to_string(), Value::String(namespace.clone()));
+    }
     
     Ok(TimeEdge {
         from_node_id: Uuid::nil(), // Will be resolved by API
@@ -367,6 +679,17 @@ fn process_relationship_record(
     })
 }
 
+/// The row as the source document of its edge, for valid-time policies
+fn row_source(record: &csv::StringRecord, headers: &[String]) -> SourceInfo {
+    let properties = headers.iter()
+        .zip(record.iter())
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(header, value)| (header.clone(), Value::String(value.to_string())))
+        .collect();
+    
+    SourceInfo { timestamp: None, properties }
+}
+
 /// Find the index of a column by name or numeric index
 fn find_column_index(headers: &[String], column: &str) -> Result<usize, CoreError> {
     // Try parsing as numeric index first
@@ -397,65 +720,43 @@ fn get_property_indices(headers: &[String], props_cols: &Option<String>) -> Resu
     }
 }
 
-/// Parse a CSV value into appropriate JSON type
-fn parse_csv_value(value: &str) -> Value {
-    // Try parsing as number
-    if let Ok(int_val) = value.parse::<i64>() {
-        return Value::Number(serde_json::Number::from(int_val));
-    }
-    
-    if let Ok(float_val) = value.parse::<f64>() {
-        if let Some(num) = serde_json::Number::from_f64(float_val) {
-            return Value::Number(num);
-        }
-    }
-    
-    // Try parsing as boolean
-    match value.to_lowercase().as_str() {
-        "true" | "yes" | "1" => return Value::Bool(true),
-        "false" | "no" | "0" => return Value::Bool(false),
-        _ => {}
-    }
-    
-    // Default to string
-    Value::String(value.to_string())
-}
-
-/// Parse datetime from string
-fn parse_datetime(value: &str, format: &str) -> Result<DateTime<Utc>, CoreError> {
-    // Try ISO8601 first
-    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
-        return Ok(dt.with_timezone(&Utc));
-    }
-    
-    // Try custom format
-    chrono::NaiveDateTime::parse_from_str(value, format)
-        .map(|dt| dt.and_utc())
-        .map_err(|e| CoreError::Internal(format!("Failed to parse datetime '{}' with format '{}': {}", value, format, e)))
+/// Parse datetime from string; values without an offset are in the policy's
+/// timezone, and a year, month or day stands for the instant `bound` gives it
+fn parse_datetime(value: &str, format: &str, valid_time: &ValidTimePolicy, bound: IntervalBound) -> Result<DateTime<Utc>, CoreError> {
+    parse_timestamp(value, format, valid_time, bound).map_err(CoreError::Internal)
 }
 
-/// Process a batch of items
+/// Bulk load a batch of items as newline-delimited JSON, returning how many
+/// the server loaded. A batch the store rejects is reported with its CSV
+/// `rows` and counted as not loaded, since bulk loads are not dead-lettered.
 async fn process_batch<T: serde::Serialize>(
     client: &TelaMentisClient,
     tenant: &TenantId,
     batch: &[T],
+    rows: &[usize],
     data_type: &DataType,
 ) -> Result<usize, CoreError> {
     let endpoint = match data_type {
-        DataType::Node => format!("/graph/{}/nodes/batch", tenant.as_str()),
-        DataType::Relationship => format!("/graph/{}/edges/batch", tenant.as_str()),
+        DataType::Node => format!("/graph/{}/nodes/bulk", tenant.as_str()),
+        DataType::Relationship => format!("/graph/{}/edges/bulk", tenant.as_str()),
     };
     
-    debug!("Processing batch of {} items to {}", batch.len(), endpoint);
+    debug!("Bulk loading batch of {} items to {}", batch.len(), endpoint);
     
-    let response = client.post(&endpoint, batch).await?;
-    
-    if response.status().is_success() {
-        Ok(batch.len()) // Assume all succeeded for now
-    } else {
-        let error_text = response.text().await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(CoreError::Internal(format!("Batch processing failed: {}", error_text)))
+    let response = client.post_ndjson(&endpoint, batch).await?;
+    let status = response.status();
+    match client.handle_response::<Value>(response).await {
+        Ok(body) => body.pointer("/data/loaded")
+            .and_then(Value::as_u64)
+            .map(|loaded| loaded as usize)
+            .ok_or_else(|| CoreError::Internal("Bulk load response has no loaded count".to_string())),
+        // Other batches would fail the same way
+        Err(e) if matches!(status.as_u16(), 401 | 404) => Err(e),
+        Err(e) => {
+            let (first, last) = (rows.first().copied().unwrap_or_default(), rows.last().copied().unwrap_or_default());
+            warn!("Rows {}-{} not loaded: {}", first, last, e);
+            Ok(0)
+        }
     }
 }
 
@@ -475,26 +776,67 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_csv_value() {
-        assert_eq!(parse_csv_value("123"), Value::Number(serde_json::Number::from(123)));
-        assert_eq!(parse_csv_value("123.45"), Value::Number(serde_json::Number::from_f64(123.45).unwrap()));
-        assert_eq!(parse_csv_value("true"), Value::Bool(true));
-        assert_eq!(parse_csv_value("false"), Value::Bool(false));
-        assert_eq!(parse_csv_value("hello"), Value::String("hello".to_string()));
+    fn test_property_types() {
+        let policy = ValidTimePolicy::default();
+        let mut types = PropertyTypes {
+            kind: TemplateKind::Node,
+            schema: None,
+            pinned: HashMap::new(),
+            strict: false,
+            date_format: "%Y-%m-%d %H:%M:%S".to_string(),
+        };
+        let value = |types: &PropertyTypes, column: &str, cell: &str| types.value("Person", column, cell, &policy);
+
+        assert_eq!(value(&types, "n", "123").unwrap(), Value::Number(serde_json::Number::from(123)));
+        assert_eq!(value(&types, "n", "123.45").unwrap(), Value::Number(serde_json::Number::from_f64(123.45).unwrap()));
+        assert_eq!(value(&types, "n", "true").unwrap(), Value::Bool(true));
+        assert_eq!(value(&types, "n", "false").unwrap(), Value::Bool(false));
+        assert_eq!(value(&types, "n", "hello").unwrap(), Value::String("hello".to_string()));
+        assert_eq!(value(&types, "zip", "01234").unwrap(), json!(1234));
+
+        // The schema types its columns, and pinned types win over it
+        types.schema = Some(serde_json::from_value(json!({"labels": {"Person": {"zip": "string", "age": "integer"}}})).unwrap());
+        types.pinned = parse_column_types(&["age = float".to_string()]).unwrap();
+        assert_eq!(value(&types, "zip", "01234").unwrap(), json!("01234"));
+        assert_eq!(value(&types, "age", "42").unwrap(), json!(42.0));
+        assert!(value(&types, "age", "old").unwrap_err().contains("'age'"));
+
+        types.strict = true;
+        assert_eq!(value(&types, "code", "007").unwrap(), json!("007"));
+        assert!(parse_column_types(&["age".to_string()]).is_err());
+        assert!(parse_column_types(&["age=datetime".to_string()]).is_err());
+
+        // Dates keep their precision
+        types.pinned = parse_column_types(&["joined=date".to_string()]).unwrap();
+        assert_eq!(value(&types, "joined", "2020-03").unwrap(), json!("2020-03"));
+        assert!(value(&types, "joined", "March 2020").is_err());
     }
 
     #[test]
     fn test_parse_datetime() {
+        let utc = ValidTimePolicy::default();
+        
         // ISO8601 format
-        let result = parse_datetime("2024-01-15T10:30:00Z", "%Y-%m-%d %H:%M:%S");
+        let result = parse_datetime("2024-01-15T10:30:00Z", "%Y-%m-%d %H:%M:%S", &utc, IntervalBound::Start);
         assert!(result.is_ok());
         
         // Custom format
-        let result = parse_datetime("2024-01-15 10:30:00", "%Y-%m-%d %H:%M:%S");
+        let result = parse_datetime("2024-01-15 10:30:00", "%Y-%m-%d %H:%M:%S", &utc, IntervalBound::Start);
         assert!(result.is_ok());
         
         // Invalid format
-        let result = parse_datetime("invalid", "%Y-%m-%d %H:%M:%S");
+        let result = parse_datetime("invalid", "%Y-%m-%d %H:%M:%S", &utc, IntervalBound::Start);
         assert!(result.is_err());
+        
+        // Values without an offset are in the policy's timezone
+        let berlin = ValidTimePolicy::default().with_timezone(parse_utc_offset("+01:00").unwrap());
+        let result = parse_datetime("2024-01-15 10:30:00", "%Y-%m-%d %H:%M:%S", &berlin, IntervalBound::Start).unwrap();
+        assert_eq!(result.to_rfc3339(), "2024-01-15T09:30:00+00:00");
+        let result = parse_datetime("2024-01-15", "%Y-%m-%d %H:%M:%S", &berlin, IntervalBound::Start).unwrap();
+        assert_eq!(result.to_rfc3339(), "2024-01-14T23:00:00+00:00");
+
+        // A month-precision end keeps the whole month valid
+        let result = parse_datetime("2021-06", "%Y-%m-%d %H:%M:%S", &utc, IntervalBound::End).unwrap();
+        assert_eq!(result.to_rfc3339(), "2021-07-01T00:00:00+00:00");
     }
 }
\ No newline at end of file
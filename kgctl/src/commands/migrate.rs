@@ -0,0 +1,84 @@
+//! Schema migration command implementations
+
+use crate::cli::MigrateCommands;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use telamentis_adapter_neo4j::{IndexReport, Neo4jMigrator};
+use telamentis_core::errors::CoreError;
+use telamentis_core::migrations::SchemaStatus;
+use telamentis_core::traits::SchemaMigrator;
+use tracing::info;
+
+/// Handle schema migration commands, which connect to the store directly so
+/// that it can be migrated before the API is started against it
+pub async fn handle_migrate_command(command: MigrateCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let store = config.neo4j.as_ref().ok_or_else(|| CoreError::Configuration(
+        "No store configured for migrations. Set neo4j.uri in the config".to_string()
+    ))?;
+    let migrator = Neo4jMigrator::connect(store).await?;
+
+    match command {
+        MigrateCommands::Status => {
+            let status = migrator.schema_status().await?;
+            display_status(&status, config)
+        }
+        MigrateCommands::Apply { target } => {
+            info!("Migrating schema of {} to version {}", store.uri, target.map_or("latest".to_string(), |t| t.to_string()));
+            let before = migrator.schema_status().await?.current_version;
+            let status = migrator.migrate(target).await?;
+
+            output::display_outcome(&status, &config.default_format, || {
+                if status.current_version == before {
+                    println!("Schema is already at version {}", status.current_version);
+                } else {
+                    println!("{}", format!("✓ Migrated schema from version {} to {}", before, status.current_version).green());
+                }
+            })
+        }
+        MigrateCommands::Verify => {
+            let report = migrator.verify_indexes().await?;
+            display_index_report(&report, config)?;
+            match report.missing.len() + report.mismatched.len() {
+                0 => Ok(()),
+                differing => Err(CoreError::Configuration(format!("{} index(es) differ from the schema", differing))),
+            }
+        }
+    }
+}
+
+/// Show the missing and mismatched indexes
+fn display_index_report(report: &IndexReport, config: &KgctlConfig) -> Result<(), CoreError> {
+    output::display_outcome(report, &config.default_format, || {
+        if report.is_ok() {
+            println!("{}", format!("✓ All {} expected indexes are present", report.expected).green());
+            return;
+        }
+        for index in &report.missing {
+            println!("  {}  {}", "missing".red(), index);
+        }
+        for mismatch in &report.mismatched {
+            println!("  {}  {}", "mismatched".yellow(), mismatch.expected);
+            println!("              found {} ({})", mismatch.found, mismatch.state);
+        }
+        println!("{}", "Run `kgctl migrate apply` with a user allowed to create indexes, or have an administrator create them".yellow());
+    })
+}
+
+/// Show the applied and pending migrations
+fn display_status(status: &SchemaStatus, config: &KgctlConfig) -> Result<(), CoreError> {
+    output::display_outcome(status, &config.default_format, || {
+        println!("{}", format!("Schema version {} of {}", status.current_version, status.latest_version).bold());
+        for migration in &status.migrations {
+            let state = if migration.version <= status.current_version {
+                migration.applied_at.map_or("applied".to_string(), |time| format!("applied {}", time.to_rfc3339())).green()
+            } else {
+                "pending".yellow()
+            };
+            println!("  {:>3}  {}  ({})", migration.version, migration.description, state);
+        }
+        if !status.is_current() {
+            println!("{}", "Run `kgctl migrate apply` to apply the pending migrations".yellow());
+        }
+    })
+}
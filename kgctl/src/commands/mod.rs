@@ -4,4 +4,22 @@ pub mod tenant;
 pub mod ingest;
 pub mod export;
 pub mod query;
-pub mod health;
\ No newline at end of file
+pub mod edge;
+pub mod snapshot;
+pub mod archive;
+pub mod token;
+pub mod replay;
+pub mod examples;
+pub mod dlq;
+pub mod sync;
+pub mod timeline;
+pub mod history;
+pub mod operations;
+pub mod schema;
+pub mod migrate;
+pub mod admin;
+pub mod seed;
+pub mod report;
+pub mod health;
+pub mod doctor;
+pub mod config;
\ No newline at end of file
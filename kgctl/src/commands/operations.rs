@@ -0,0 +1,59 @@
+//! Operations command implementations
+
+use crate::cli::OperationsCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use telamentis_core::errors::CoreError;
+use telamentis_core::operations::OperationInfo;
+use tracing::{info, warn};
+
+/// Handle operations commands
+pub async fn handle_operations_command(command: OperationsCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        OperationsCommands::List { tenant } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            list_operations(&client, &tenant_id, config).await
+        }
+        OperationsCommands::Cancel { tenant, operation_id } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            cancel_operation(&client, &tenant_id, &operation_id, config).await
+        }
+    }
+}
+
+/// List a tenant's operations in flight
+async fn list_operations(client: &TelaMentisClient, tenant_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Listing operations for tenant: {}", tenant_id);
+
+    let response = client.get(&format!("/operations/{}", tenant_id)).await?;
+    let operations: Vec<OperationInfo> = client.handle_response(response).await?;
+
+    if operations.is_empty() && config.default_format.is_table() {
+        println!("No operations in flight for tenant '{}'", tenant_id);
+        return Ok(());
+    }
+
+    output::display_outcome(&operations, &config.default_format, || {
+        for operation in &operations {
+            let state = if operation.cancelled { "cancelling".yellow() } else { "running".green() };
+            let seconds = (chrono::Utc::now() - operation.started_at).num_milliseconds() as f64 / 1000.0;
+            println!("{}  {:<10} {:<10} {:.1}s", operation.id, operation.kind.to_string(), state, seconds);
+        }
+    })
+}
+
+/// Cancel an operation in flight
+async fn cancel_operation(client: &TelaMentisClient, tenant_id: &str, operation_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    warn!("Cancelling operation {} of tenant {}", operation_id, tenant_id);
+
+    let response = client.delete(&format!("/operations/{}/{}", tenant_id, operation_id)).await?;
+    let operation: OperationInfo = client.handle_response(response).await?;
+
+    output::display_outcome(&operation, &config.default_format, || {
+        println!("{}", format!("✓ Cancelled {} operation {}", operation.kind, operation.id).green());
+    })
+}
@@ -4,15 +4,26 @@ use crate::cli::QueryCommands;
 use crate::client::TelaMentisClient;
 use crate::config::KgctlConfig;
 use crate::output;
+use crate::query_file::QueryFile;
 use chrono::{DateTime, Utc};
 use colored::*;
-use serde_json::{Map, Value};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
+use telamentis_core::batch_query::{BatchQueryRequest, BatchQueryResponse};
 use telamentis_core::errors::CoreError;
-use telamentis_core::types::{GraphQuery, Path, TenantId};
+use telamentis_core::query::{NodeQuery, Query, RelationshipQuery};
+use telamentis_core::temporal::{IntervalBound, TemporalUtils};
+use telamentis_core::types::{GraphQuery, OrderBy, Path, SortField, TenantId};
 use tracing::{debug, info};
 use uuid::Uuid;
 
+/// Paths returned by a snapshot query
+#[derive(Debug, Deserialize)]
+struct SnapshotQueryResult {
+    paths: Vec<Path>,
+}
+
 /// Handle query commands
 pub async fn handle_query_command(command: QueryCommands, config: &KgctlConfig) -> Result<(), CoreError> {
     match command {
@@ -20,13 +31,22 @@ pub async fn handle_query_command(command: QueryCommands, config: &KgctlConfig)
             let tenant_id = config.get_tenant(&tenant)?;
             execute_raw_query(config, &tenant_id, &query, params.as_deref()).await
         }
-        QueryCommands::Nodes { tenant, labels, properties, limit } => {
+        QueryCommands::Nodes { tenant, labels, properties, order_by, offset, limit, snapshot } => {
             let tenant_id = config.get_tenant(&tenant)?;
-            find_nodes(config, &tenant_id, labels, properties, limit).await
+            let order_by = parse_order_by(&order_by)?;
+            find_nodes(config, &tenant_id, labels, properties, order_by, offset, limit, snapshot.as_deref()).await
+        }
+        QueryCommands::Relationships { tenant, from, to, types, valid_at, order_by, offset, limit, snapshot } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            let order_by = parse_order_by(&order_by)?;
+            find_relationships(config, &tenant_id, from, to, types, valid_at, order_by, offset, limit, snapshot.as_deref()).await
+        }
+        QueryCommands::File { path, tenant, params, snapshot, dry_run } => {
+            run_query_file(config, &path, tenant, &params, snapshot, dry_run).await
         }
-        QueryCommands::Relationships { tenant, from, to, types, valid_at, limit } => {
+        QueryCommands::Batch { path, tenant, timeout_ms } => {
             let tenant_id = config.get_tenant(&tenant)?;
-            find_relationships(config, &tenant_id, from, to, types, valid_at, limit).await
+            run_query_batch(config, &tenant_id, &path, timeout_ms).await
         }
     }
 }
@@ -53,10 +73,9 @@ async fn execute_raw_query(
     };
     
     // Build query object
-    let graph_query = GraphQuery::Raw {
-        query: query.to_string(),
-        params,
-    };
+    let graph_query = params.into_iter()
+        .fold(Query::raw(query), |raw, (name, value)| raw.param(name, value))
+        .build();
     
     // Execute query
     let response = client.post(&format!("/graph/{}/query", tenant.as_str()), &graph_query).await?;
@@ -65,18 +84,24 @@ async fn execute_raw_query(
     // Display results
     output::display_query_results(&paths, &config.default_format)?;
     
-    println!("{}", format!("Query returned {} result(s)", paths.len()).green());
+    if config.default_format.is_table() {
+        println!("{}", format!("Query returned {} result(s)", paths.len()).green());
+    }
     
     Ok(())
 }
 
 /// Find nodes with specified criteria
+#[allow(clippy::too_many_arguments)]
 async fn find_nodes(
     config: &KgctlConfig,
     tenant_id: &str,
     labels: Vec<String>,
     property_filters: Vec<String>,
+    order_by: Vec<OrderBy>,
+    offset: Option<u32>,
     limit: Option<u32>,
+    snapshot: Option<&str>,
 ) -> Result<(), CoreError> {
     info!("Finding nodes for tenant: {}", tenant_id);
     debug!("Labels: {:?}, Properties: {:?}", labels, property_filters);
@@ -88,25 +113,29 @@ async fn find_nodes(
     let properties = parse_property_filters(&property_filters)?;
     
     // Build query object
-    let graph_query = GraphQuery::FindNodes {
-        labels,
-        properties,
-        limit,
-    };
+    let graph_query = properties.into_iter()
+        .fold(Query::nodes().labels(labels), |query, (key, value)| query.prop_eq(key, value));
+    let graph_query = order_by.into_iter()
+        .fold(graph_query, NodeQuery::order_by)
+        .offset(offset)
+        .limit(limit)
+        .build();
     
     // Execute query
-    let response = client.post(&format!("/graph/{}/query", tenant.as_str()), &graph_query).await?;
-    let paths: Vec<Path> = client.handle_response(response).await?;
+    let paths = run_query(&client, &tenant, snapshot, &graph_query).await?;
     
     // Display results
     output::display_query_results(&paths, &config.default_format)?;
     
-    println!("{}", format!("Found {} node(s)", paths.len()).green());
+    if config.default_format.is_table() {
+        println!("{}", format!("Found {} node(s)", paths.len()).green());
+    }
     
     Ok(())
 }
 
 /// Find relationships with specified criteria
+#[allow(clippy::too_many_arguments)]
 async fn find_relationships(
     config: &KgctlConfig,
     tenant_id: &str,
@@ -114,7 +143,10 @@ async fn find_relationships(
     to_node: Option<String>,
     relationship_types: Vec<String>,
     valid_at: Option<String>,
+    order_by: Vec<OrderBy>,
+    offset: Option<u32>,
     limit: Option<u32>,
+    snapshot: Option<&str>,
 ) -> Result<(), CoreError> {
     info!("Finding relationships for tenant: {}", tenant_id);
     debug!("From: {:?}, To: {:?}, Types: {:?}", from_node, to_node, relationship_types);
@@ -143,26 +175,143 @@ async fn find_relationships(
     };
     
     // Build query object
-    let graph_query = GraphQuery::FindRelationships {
-        from_node_id,
-        to_node_id,
-        relationship_types,
-        valid_at: valid_at_time,
-        limit,
-    };
+    let graph_query = order_by.into_iter()
+        .fold(Query::relationships().rel_types(relationship_types), RelationshipQuery::order_by)
+        .from(from_node_id)
+        .to(to_node_id)
+        .valid_at(valid_at_time)
+        .offset(offset)
+        .limit(limit)
+        .build();
     
     // Execute query
-    let response = client.post(&format!("/graph/{}/query", tenant.as_str()), &graph_query).await?;
-    let paths: Vec<Path> = client.handle_response(response).await?;
+    let paths = run_query(&client, &tenant, snapshot, &graph_query).await?;
     
     // Display results
     output::display_query_results(&paths, &config.default_format)?;
     
-    println!("{}", format!("Found {} relationship(s)", paths.len()).green());
+    if config.default_format.is_table() {
+        println!("{}", format!("Found {} relationship(s)", paths.len()).green());
+    }
+    
+    Ok(())
+}
+
+/// Run a query defined in a file, filling in its parameters
+async fn run_query_file(
+    config: &KgctlConfig,
+    path: &std::path::Path,
+    tenant: Option<String>,
+    params: &[String],
+    snapshot: Option<String>,
+    dry_run: bool,
+) -> Result<(), CoreError> {
+    let file = QueryFile::load(path)?;
+    let args = params.iter()
+        .map(|param| match param.split_once('=') {
+            Some((name, value)) => Ok((name.trim().to_string(), parse_filter_value(value.trim()))),
+            None => Err(CoreError::Internal(format!("Invalid parameter '{}'. Expected 'name=value'", param))),
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    let graph_query = file.resolve(&args)?;
+    
+    if dry_run {
+        return output::display_outcome(&graph_query, &config.default_format, || {
+            if let Some(description) = &file.description {
+                println!("{}", description.bold());
+            }
+            println!("{}", serde_json::to_string_pretty(&graph_query).unwrap_or_default());
+        });
+    }
+    
+    let tenant_id = config.get_tenant(&tenant.or(file.tenant))?;
+    let snapshot = snapshot.or(file.snapshot);
+    info!("Running query file {} for tenant: {}", path.display(), tenant_id);
+    
+    let client = TelaMentisClient::new(config.clone())?;
+    let paths = run_query(&client, &TenantId::new(&tenant_id), snapshot.as_deref(), &graph_query).await?;
+    
+    output::display_query_results(&paths, &config.default_format)?;
+    
+    if config.default_format.is_table() {
+        println!("{}", format!("Query returned {} result(s)", paths.len()).green());
+    }
+    
+    Ok(())
+}
+
+/// Read a batch file: a `queries` list with an optional `timeout_ms`
+fn load_batch_file(path: &std::path::Path) -> Result<BatchQueryRequest, CoreError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| CoreError::Configuration(format!("Failed to read batch file {}: {}", path.display(), e)))?;
+    // Through JSON, so that queries are written as maps rather than YAML tags
+    serde_yaml::from_str::<Value>(&content)
+        .map_err(|e| e.to_string())
+        .and_then(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+        .map_err(|e| CoreError::Configuration(format!("Invalid batch file {}: {}", path.display(), e)))
+}
+
+/// Run the queries of a batch file in one request
+async fn run_query_batch(
+    config: &KgctlConfig,
+    tenant_id: &str,
+    path: &std::path::Path,
+    timeout_ms: Option<u64>,
+) -> Result<(), CoreError> {
+    let mut request = load_batch_file(path)?;
+    request.timeout_ms = timeout_ms.or(request.timeout_ms);
+    info!("Running {} batched queries for tenant: {}", request.queries.len(), tenant_id);
+    
+    let client = TelaMentisClient::new(config.clone())?;
+    let response = client.post(&format!("/graph/{}/query/batch", tenant_id), &request).await?;
+    let response: BatchQueryResponse = client.handle_response(response).await?;
+    
+    if !config.default_format.is_table() {
+        return output::display_outcome(&response, &config.default_format, || {});
+    }
+    
+    for (index, result) in response.results.iter().enumerate() {
+        println!("{}", format!("Query {} ({}ms)", index + 1, result.execution_time_ms).bold());
+        match (&result.error, result.count, result.exists) {
+            (Some(error), _, _) => println!("{}", format!("Failed: {}", error).red()),
+            (None, Some(count), _) => println!("{} match(es)", count),
+            (None, None, Some(exists)) => println!("{}", if exists { "Matches found" } else { "No matches" }),
+            (None, None, None) => output::display_query_results(&result.paths, &config.default_format)?,
+        }
+        println!();
+    }
+    
+    let failed = response.results.iter().filter(|result| !result.is_ok()).count();
+    let summary = format!("Ran {} queries in {}ms, {} failed", response.results.len(), response.execution_time_ms, failed);
+    println!("{}", if failed == 0 { summary.green() } else { summary.yellow() });
     
     Ok(())
 }
 
+/// Run a structured query against the live graph or a materialized snapshot
+async fn run_query(
+    client: &TelaMentisClient,
+    tenant: &TenantId,
+    snapshot: Option<&str>,
+    graph_query: &GraphQuery,
+) -> Result<Vec<Path>, CoreError> {
+    match snapshot {
+        Some(name) => {
+            debug!("Querying snapshot: {}", name);
+            let response = client.post(
+                &format!("/graph/{}/snapshots/{}/query", tenant.as_str(), name),
+                &json!({ "query": graph_query }),
+            ).await?;
+            let result: SnapshotQueryResult = client.handle_response(response).await?;
+            Ok(result.paths)
+        }
+        None => {
+            let response = client.post(&format!("/graph/{}/query", tenant.as_str()), graph_query).await?;
+            client.handle_response(response).await
+        }
+    }
+}
+
 /// Parse property filters from key=value strings
 fn parse_property_filters(filters: &[String]) -> Result<HashMap<String, Value>, CoreError> {
     let mut properties = HashMap::new();
@@ -213,17 +362,41 @@ fn parse_filter_value(value_str: &str) -> Value {
     Value::String(value_str.to_string())
 }
 
+/// Parse sort keys from field[:asc|desc] strings
+fn parse_order_by(keys: &[String]) -> Result<Vec<OrderBy>, CoreError> {
+    keys.iter()
+        .map(|key| {
+            let (name, direction) = match key.rsplit_once(':') {
+                Some((name, direction)) => (name, Some(direction)),
+                None => (key.as_str(), None),
+            };
+
+            let field = match name.trim() {
+                "created_at" => SortField::CreatedAt,
+                "label" => SortField::Label,
+                "" => return Err(CoreError::Internal(format!("Invalid sort key '{}'", key))),
+                property => SortField::Property(property.to_string()),
+            };
+
+            match direction.map(|d| d.trim().to_lowercase()).as_deref() {
+                None | Some("asc") => Ok(OrderBy::asc(field)),
+                Some("desc") => Ok(OrderBy::desc(field)),
+                Some(other) => Err(CoreError::Internal(format!("Invalid sort direction '{}'. Expected 'asc' or 'desc'", other))),
+            }
+        })
+        .collect()
+}
+
 /// Parse UUID from string
 fn parse_uuid(uuid_str: &str) -> Result<Uuid, CoreError> {
     Uuid::parse_str(uuid_str)
         .map_err(|e| CoreError::Internal(format!("Invalid UUID '{}': {}", uuid_str, e)))
 }
 
-/// Parse datetime from string
+/// Parse an "as of" time; a date such as "2020-03" means its last instant
 fn parse_datetime(datetime_str: &str) -> Result<DateTime<Utc>, CoreError> {
-    DateTime::parse_from_rfc3339(datetime_str)
-        .map(|dt| dt.with_timezone(&Utc))
-        .map_err(|e| CoreError::Internal(format!("Invalid datetime '{}': {}", datetime_str, e)))
+    TemporalUtils::parse_time(datetime_str, IntervalBound::AsOf)
+        .map_err(|e| CoreError::Internal(format!("Invalid datetime: {}", e)))
 }
 
 #[cfg(test)]
@@ -255,6 +428,43 @@ mod tests {
         assert_eq!(parse_filter_value("hello"), Value::String("hello".to_string()));
     }
 
+    #[test]
+    fn test_parse_order_by() {
+        let keys = vec!["name".to_string(), "created_at:desc".to_string(), "label:asc".to_string()];
+        let result = parse_order_by(&keys).unwrap();
+
+        assert_eq!(result, vec![
+            OrderBy::asc(SortField::Property("name".to_string())),
+            OrderBy::desc(SortField::CreatedAt),
+            OrderBy::asc(SortField::Label),
+        ]);
+        assert!(parse_order_by(&["name:sideways".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_load_batch_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dashboard.yaml");
+        std::fs::write(&path, r#"
+timeout_ms: 5000
+queries:
+  - query:
+      FindNodes: {labels: [Person], properties: {}, limit: 10}
+  - query:
+      FindRelationships: {from_node_id: null, to_node_id: null, relationship_types: [WORKS_FOR], valid_at: null, limit: null}
+    mode: count
+"#).unwrap();
+
+        let request = load_batch_file(&path).unwrap();
+        assert_eq!(request.timeout_ms, Some(5000));
+        assert_eq!(request.queries.len(), 2);
+        assert_eq!(request.queries[0].mode, telamentis_core::types::QueryMode::Full);
+        assert_eq!(request.queries[1].mode, telamentis_core::types::QueryMode::Count);
+
+        std::fs::write(&path, "queries: [{mode: count}]").unwrap();
+        assert!(load_batch_file(&path).is_err());
+    }
+
     #[test]
     fn test_parse_uuid() {
         let valid_uuid = "550e8400-e29b-41d4-a716-446655440000";
@@ -271,5 +481,8 @@ mod tests {
         
         let invalid_datetime = "not-a-datetime";
         assert!(parse_datetime(invalid_datetime).is_err());
+
+        // As of a month is as of its last instant
+        assert_eq!(parse_datetime("2020-03").unwrap().to_rfc3339(), "2020-03-31T23:59:59.999999999+00:00");
     }
 }
\ No newline at end of file
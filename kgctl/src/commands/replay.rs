@@ -0,0 +1,139 @@
+//! Replay command implementation
+
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use reqwest::Method;
+use serde_json::{json, Value};
+use telamentis_core::capture::CapturedRequest;
+use telamentis_core::errors::CoreError;
+use tracing::info;
+
+/// Handle the replay command: fetch a captured request from the configured
+/// server and re-execute it against the target environment
+pub async fn handle_replay_command(
+    request_id: &str,
+    tenant: Option<String>,
+    target: Option<String>,
+    target_tenant: Option<String>,
+    dry_run: bool,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    let tenant_id = config.get_tenant(&tenant)?;
+    let client = TelaMentisClient::new(config.clone())?;
+
+    info!("Fetching captured request {} for tenant: {}", request_id, tenant_id);
+    let response = client.get(&format!("/captures/{}/{}", tenant_id, request_id)).await?;
+    let captured: CapturedRequest = client.handle_response(response).await?;
+
+    let path = replay_path(&captured.path, target_tenant.as_deref())?;
+    let method = Method::from_bytes(captured.method.as_bytes())
+        .map_err(|_| CoreError::Internal(format!("Invalid captured method '{}'", captured.method)))?;
+
+    let mut target_config = config.clone();
+    if let Some(target) = target {
+        target_config.endpoint = target;
+    }
+
+    let target_url = format!("{}{}", target_config.endpoint, path);
+    let mut outcome = json!({
+        "request_id": captured.request_id,
+        "captured_at": captured.captured_at,
+        "method": method.as_str(),
+        "path": captured.path,
+        "target": target_url,
+        "captured_status": captured.status,
+    });
+
+    if dry_run {
+        outcome["input"] = captured.input.clone().unwrap_or(Value::Null);
+        return output::display_outcome(&outcome, &config.default_format, || {
+            println!("Request {} captured at {}", captured.request_id, captured.captured_at.to_rfc3339());
+            println!("  {} {} → {}", method, captured.path, target_url);
+            if let Some(input) = &captured.input {
+                println!("{}", serde_json::to_string_pretty(input).unwrap_or_default());
+            }
+        });
+    }
+    if config.default_format.is_table() {
+        println!("Request {} captured at {}", captured.request_id, captured.captured_at.to_rfc3339());
+        println!("  {} {} → {}", method, captured.path, target_url);
+    }
+
+    let target_client = TelaMentisClient::new(target_config)?;
+    let response = target_client.send(method, &path, captured.input.as_ref()).await?;
+    let status = response.status().as_u16();
+    let replayed: Option<Value> = response.json().await.ok();
+    let matches = captured.status == Some(status) && same_output(captured.output.as_ref(), replayed.as_ref());
+
+    outcome["status"] = json!(status);
+    outcome["matches"] = json!(matches);
+    outcome["output"] = replayed.clone().unwrap_or(Value::Null);
+    output::display_outcome(&outcome, &config.default_format, || {
+        let captured_status = captured.status
+            .map(|status| status.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        println!("  Status: {} (captured: {})", status, captured_status);
+
+        if matches {
+            println!("{}", "✓ Replayed response matches the captured response".green());
+        } else {
+            println!("{}", "✗ Replayed response differs from the captured response".yellow());
+            if let Some(replayed) = &replayed {
+                println!("{}", serde_json::to_string_pretty(replayed).unwrap_or_default());
+            }
+        }
+    })
+}
+
+/// Client path for a captured path, without the API version prefix and with
+/// the tenant segment replaced if replaying as another tenant
+fn replay_path(captured_path: &str, target_tenant: Option<&str>) -> Result<String, CoreError> {
+    let path = captured_path.strip_prefix("/v1")
+        .ok_or_else(|| CoreError::Internal(format!("Cannot replay request to '{}'", captured_path)))?;
+
+    let Some(target_tenant) = target_tenant else {
+        return Ok(path.to_string());
+    };
+
+    // Captured paths have the form /{area}/{tenant}/...
+    let mut segments: Vec<&str> = path.splitn(4, '/').collect();
+    if segments.len() < 3 {
+        return Err(CoreError::Internal(format!("No tenant in captured path '{}'", captured_path)));
+    }
+    segments[2] = target_tenant;
+    Ok(segments.join("/"))
+}
+
+/// Compare responses, ignoring the top-level `timestamp` every response carries
+fn same_output(captured: Option<&Value>, replayed: Option<&Value>) -> bool {
+    let strip = |value: Option<&Value>| value.cloned().map(|mut value| {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("timestamp");
+        }
+        value
+    });
+    strip(captured) == strip(replayed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_path() {
+        assert_eq!(replay_path("/v1/graph/acme/nodes", None).unwrap(), "/graph/acme/nodes");
+        assert_eq!(replay_path("/v1/graph/acme/nodes?limit=5", Some("staging")).unwrap(), "/graph/staging/nodes?limit=5");
+        assert_eq!(replay_path("/v1/llm/acme", Some("staging")).unwrap(), "/llm/staging");
+        assert!(replay_path("/health", None).is_err());
+    }
+
+    #[test]
+    fn test_same_output_ignores_timestamp() {
+        let captured = json!({"success": true, "data": 1, "timestamp": "2024-01-01T00:00:00Z"});
+        let replayed = json!({"success": true, "data": 1, "timestamp": "2024-06-01T00:00:00Z"});
+        assert!(same_output(Some(&captured), Some(&replayed)));
+        assert!(!same_output(Some(&captured), Some(&json!({"success": false}))));
+    }
+}
@@ -0,0 +1,373 @@
+//! Report command implementations
+//!
+//! Drift reports compare two JSONL exports of a tenant, for example before
+//! and after a migration or a re-extraction run. System IDs change when a
+//! graph is rebuilt, so nodes are matched by alias, falling back to their ID
+//! when they have none, and edges by their endpoints and relationship type.
+
+use crate::cli::ReportCommands;
+use crate::commands::export::{ExportEdge, ExportMetadata, ExportNode};
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use telamentis_core::errors::CoreError;
+use tracing::info;
+
+/// Handle report commands
+pub fn handle_report_command(command: ReportCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    match command {
+        ReportCommands::Drift { tenant, baseline, current, output } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            info!("Comparing exports {} and {} of tenant {}", baseline.display(), current.display(), tenant_id);
+
+            let before = read_export(&baseline, &tenant_id)?;
+            let after = read_export(&current, &tenant_id)?;
+            let report = drift(&tenant_id, &baseline, &current, &before, &after);
+
+            if let Some(path) = &output {
+                let json = serde_json::to_string_pretty(&report)
+                    .map_err(|e| CoreError::Internal(format!("Failed to serialize report: {}", e)))?;
+                std::fs::write(path, json)
+                    .map_err(|e| CoreError::Internal(format!("Failed to write report {}: {}", path.display(), e)))?;
+            }
+            output::display_outcome(&report, &config.default_format, || display_drift(&report, output.as_deref()))
+        }
+    }
+}
+
+/// A JSONL export read back
+#[derive(Debug, Default)]
+struct Export {
+    nodes: Vec<ExportNode>,
+    edges: Vec<ExportEdge>,
+}
+
+/// Read a JSONL export of the tenant: a metadata line, then node and edge lines
+fn read_export(path: &Path, tenant_id: &str) -> Result<Export, CoreError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| CoreError::Internal(format!("Failed to read export {}: {}", path.display(), e)))?;
+    let export = parse_export(&content, tenant_id)
+        .map_err(|e| CoreError::Configuration(format!("Invalid export {}: {}", path.display(), e)))?;
+    info!("Read {} nodes and {} edges from {}", export.nodes.len(), export.edges.len(), path.display());
+    Ok(export)
+}
+
+fn parse_export(content: &str, tenant_id: &str) -> Result<Export, String> {
+    let mut export = Export::default();
+    for (number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let invalid = |e: serde_json::Error| format!("line {}: {}", number + 1, e);
+        let value: Value = serde_json::from_str(line).map_err(invalid)?;
+        if value.get("edge_type").is_some() {
+            export.edges.push(serde_json::from_value(value).map_err(invalid)?);
+        } else if value.get("labels").is_some() {
+            export.nodes.push(serde_json::from_value(value).map_err(invalid)?);
+        } else {
+            let metadata: ExportMetadata = serde_json::from_value(value).map_err(invalid)?;
+            if metadata.tenant_id != tenant_id {
+                return Err(format!("it belongs to tenant '{}', not '{}'", metadata.tenant_id, tenant_id));
+            }
+        }
+    }
+    Ok(export)
+}
+
+/// Node and edge counts of the two exports and how they differ
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DriftSummary {
+    pub baseline_nodes: usize,
+    pub current_nodes: usize,
+    pub nodes_added: usize,
+    pub nodes_removed: usize,
+    pub nodes_changed: usize,
+    pub nodes_unchanged: usize,
+    pub baseline_edges: usize,
+    pub current_edges: usize,
+    pub edges_added: usize,
+    pub edges_removed: usize,
+    pub edges_changed: usize,
+    pub edges_unchanged: usize,
+}
+
+/// A property whose value differs, by its dotted path; `None` where the
+/// property is missing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PropertyDiff {
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// A node or edge present in both exports that differs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedEntity {
+    pub key: String,
+    /// Labels before and after, if they differ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<(Vec<String>, Vec<String>)>,
+    pub properties: Vec<PropertyDiff>,
+}
+
+/// Keys of nodes or edges only in one export, and of those that changed
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct EntityDrift {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedEntity>,
+}
+
+/// What changed between two exports of a tenant
+#[derive(Debug, Serialize)]
+pub struct DriftReport {
+    pub tenant_id: String,
+    pub baseline: PathBuf,
+    pub current: PathBuf,
+    pub summary: DriftSummary,
+    pub nodes: EntityDrift,
+    pub edges: EntityDrift,
+}
+
+/// Key of a node across exports: its alias, or its system ID without one
+fn node_key(node: &ExportNode) -> String {
+    match (&node.alias_namespace, &node.id_alias) {
+        (Some(namespace), Some(alias)) => format!("{}:{}", namespace, alias),
+        (None, Some(alias)) => alias.clone(),
+        _ => format!("id:{}", node.id),
+    }
+}
+
+/// Nodes of an export by key
+fn keyed_nodes(export: &Export) -> BTreeMap<String, &ExportNode> {
+    export.nodes.iter().map(|node| (node_key(node), node)).collect()
+}
+
+/// Edges of an export by key. Edges with the same endpoints and type are told
+/// apart by a `#n` suffix, in the order of their properties, so identical
+/// duplicates pair up.
+fn keyed_edges(export: &Export) -> BTreeMap<String, &ExportEdge> {
+    let node_keys: HashMap<&str, String> = export.nodes.iter().map(|node| (node.id.as_str(), node_key(node))).collect();
+    let endpoint = |id: &str| node_keys.get(id).cloned().unwrap_or_else(|| format!("id:{}", id));
+
+    let mut groups: BTreeMap<String, Vec<&ExportEdge>> = BTreeMap::new();
+    for edge in &export.edges {
+        let key = format!("{} -[{}]-> {}", endpoint(&edge.from_node), edge.edge_type, endpoint(&edge.to_node));
+        groups.entry(key).or_default().push(edge);
+    }
+
+    let mut keyed = BTreeMap::new();
+    for (key, mut edges) in groups {
+        if edges.len() == 1 {
+            keyed.insert(key, edges[0]);
+            continue;
+        }
+        edges.sort_by_cached_key(|edge| edge.properties.to_string());
+        for (index, edge) in edges.into_iter().enumerate() {
+            keyed.insert(format!("{} #{}", key, index + 1), edge);
+        }
+    }
+    keyed
+}
+
+/// Differences between two property values, descending into objects
+fn diff_properties(path: &str, before: Option<&Value>, after: Option<&Value>, diffs: &mut Vec<PropertyDiff>) {
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                diff_properties(&path, before.get(key), after.get(key), diffs);
+            }
+        }
+        (before, after) if before != after => diffs.push(PropertyDiff {
+            path: path.to_string(),
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// Compare entities of two exports by key; returns the drift and the number
+/// of unchanged entities
+fn compare<T>(
+    before: &BTreeMap<String, &T>,
+    after: &BTreeMap<String, &T>,
+    changes: impl Fn(&T, &T) -> Option<ChangedEntity>,
+) -> (EntityDrift, usize) {
+    let mut drift = EntityDrift {
+        added: after.keys().filter(|key| !before.contains_key(*key)).cloned().collect(),
+        removed: before.keys().filter(|key| !after.contains_key(*key)).cloned().collect(),
+        changed: Vec::new(),
+    };
+    let mut unchanged = 0;
+    for (key, old) in before {
+        let Some(new) = after.get(key) else { continue };
+        match changes(old, new) {
+            Some(mut changed) => {
+                changed.key = key.clone();
+                drift.changed.push(changed);
+            }
+            None => unchanged += 1,
+        }
+    }
+    (drift, unchanged)
+}
+
+fn drift(tenant_id: &str, baseline: &Path, current: &Path, before: &Export, after: &Export) -> DriftReport {
+    let (nodes, nodes_unchanged) = compare(&keyed_nodes(before), &keyed_nodes(after), |old, new| {
+        let mut properties = Vec::new();
+        diff_properties("", Some(&old.properties), Some(&new.properties), &mut properties);
+        let labels = (old.labels != new.labels).then(|| (old.labels.clone(), new.labels.clone()));
+        (labels.is_some() || !properties.is_empty()).then(|| ChangedEntity { key: String::new(), labels, properties })
+    });
+    let (edges, edges_unchanged) = compare(&keyed_edges(before), &keyed_edges(after), |old, new| {
+        let mut properties = Vec::new();
+        diff_properties("", Some(&old.properties), Some(&new.properties), &mut properties);
+        (!properties.is_empty()).then(|| ChangedEntity { key: String::new(), labels: None, properties })
+    });
+
+    DriftReport {
+        tenant_id: tenant_id.to_string(),
+        baseline: baseline.to_path_buf(),
+        current: current.to_path_buf(),
+        summary: DriftSummary {
+            baseline_nodes: before.nodes.len(),
+            current_nodes: after.nodes.len(),
+            nodes_added: nodes.added.len(),
+            nodes_removed: nodes.removed.len(),
+            nodes_changed: nodes.changed.len(),
+            nodes_unchanged,
+            baseline_edges: before.edges.len(),
+            current_edges: after.edges.len(),
+            edges_added: edges.added.len(),
+            edges_removed: edges.removed.len(),
+            edges_changed: edges.changed.len(),
+            edges_unchanged,
+        },
+        nodes,
+        edges,
+    }
+}
+
+fn display_value(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "(none)".to_string(),
+    }
+}
+
+fn display_entities(kind: &str, drift: &EntityDrift) {
+    for key in &drift.added {
+        println!("  {} {} {}", "+".green(), kind, key);
+    }
+    for key in &drift.removed {
+        println!("  {} {} {}", "-".red(), kind, key);
+    }
+    for changed in &drift.changed {
+        println!("  {} {} {}", "~".yellow(), kind, changed.key);
+        if let Some((before, after)) = &changed.labels {
+            println!("      labels: {} → {}", before.join(":"), after.join(":"));
+        }
+        for property in &changed.properties {
+            println!("      {}: {} → {}", property.path, display_value(&property.before), display_value(&property.after));
+        }
+    }
+}
+
+fn display_drift(report: &DriftReport, output: Option<&Path>) {
+    let summary = &report.summary;
+    println!(
+        "Drift of tenant '{}' from {} to {}",
+        report.tenant_id, report.baseline.display(), report.current.display()
+    );
+    println!(
+        "Nodes: {} → {} ({} added, {} removed, {} changed, {} unchanged)",
+        summary.baseline_nodes, summary.current_nodes,
+        summary.nodes_added, summary.nodes_removed, summary.nodes_changed, summary.nodes_unchanged
+    );
+    println!(
+        "Edges: {} → {} ({} added, {} removed, {} changed, {} unchanged)",
+        summary.baseline_edges, summary.current_edges,
+        summary.edges_added, summary.edges_removed, summary.edges_changed, summary.edges_unchanged
+    );
+
+    display_entities("node", &report.nodes);
+    display_entities("edge", &report.edges);
+    if let Some(path) = output {
+        println!("{}", format!("✓ Report written to: {}", path.display()).green());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn export(lines: &[Value]) -> Export {
+        let mut content = json!({
+            "tenant_id": "acme", "export_timestamp": "2024-01-01T00:00:00Z",
+            "node_count": 0, "edge_count": 0, "temporal_as_of": null
+        }).to_string();
+        for line in lines {
+            content.push('\n');
+            content.push_str(&line.to_string());
+        }
+        parse_export(&content, "acme").unwrap()
+    }
+
+    #[test]
+    fn test_drift() {
+        let before = export(&[
+            json!({"id": "1", "id_alias": "alice", "labels": ["Person"], "properties": {"name": "Alice", "address": {"city": "Paris"}}}),
+            json!({"id": "2", "id_alias": "bob", "labels": ["Person"], "properties": {}}),
+            json!({"id": "3", "labels": ["Note"], "properties": {}}),
+            json!({"id": "e1", "from_node": "1", "to_node": "2", "edge_type": "KNOWS", "properties": {"since": 2020}}),
+        ]);
+        // Rebuilt graph: new system IDs, Bob gone, Carol new, Alice moved
+        let after = export(&[
+            json!({"id": "10", "id_alias": "alice", "labels": ["Person"], "properties": {"name": "Alice", "address": {"city": "Lyon"}}}),
+            json!({"id": "11", "id_alias": "carol", "alias_namespace": "crm", "labels": ["Person"], "properties": {}}),
+            json!({"id": "3", "labels": ["Note"], "properties": {}}),
+            json!({"id": "e2", "from_node": "10", "to_node": "11", "edge_type": "KNOWS", "properties": {}}),
+        ]);
+
+        let report = drift("acme", Path::new("before.jsonl"), Path::new("after.jsonl"), &before, &after);
+        assert_eq!(report.nodes.added, vec!["crm:carol"]);
+        assert_eq!(report.nodes.removed, vec!["bob"]);
+        assert_eq!(report.nodes.changed[0].properties, vec![PropertyDiff {
+            path: "address.city".to_string(),
+            before: Some(json!("Paris")),
+            after: Some(json!("Lyon")),
+        }]);
+        assert_eq!(report.edges.added, vec!["alice -[KNOWS]-> crm:carol"]);
+        assert_eq!(report.edges.removed, vec!["alice -[KNOWS]-> bob"]);
+        assert_eq!(report.summary.nodes_unchanged, 1);
+        assert_eq!(report.summary.edges_changed, 0);
+    }
+
+    #[test]
+    fn test_duplicate_edges_pair_up() {
+        let nodes = [
+            json!({"id": "1", "id_alias": "alice", "labels": ["Person"], "properties": {}}),
+            json!({"id": "2", "id_alias": "acme", "labels": ["Company"], "properties": {}}),
+        ];
+        let edge = |id: &str, role: &str| json!({"id": id, "from_node": "1", "to_node": "2", "edge_type": "WORKS_FOR", "properties": {"role": role}});
+        let before = export(&[nodes[0].clone(), nodes[1].clone(), edge("a", "dev"), edge("b", "lead")]);
+        let after = export(&[nodes[0].clone(), nodes[1].clone(), edge("c", "lead"), edge("d", "dev")]);
+
+        let report = drift("acme", Path::new("a"), Path::new("b"), &before, &after);
+        assert_eq!(report.summary.edges_unchanged, 2);
+        assert!(report.edges.changed.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_other_tenants() {
+        let content = json!({"tenant_id": "globex", "export_timestamp": "", "node_count": 0, "edge_count": 0, "temporal_as_of": null}).to_string();
+        assert!(parse_export(&content, "acme").is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Schema documentation command implementations
+
+use crate::cli::{DocFormat, SchemaCommands};
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use colored::*;
+use std::path::Path;
+use telamentis_core::errors::CoreError;
+use tracing::info;
+
+/// Handle schema commands
+pub async fn handle_schema_command(command: SchemaCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        SchemaCommands::Docs { tenant, output, format, sample } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            schema_docs(&client, &tenant_id, output.as_deref(), format, sample).await
+        }
+    }
+}
+
+/// Write the schema document of a tenant to a file or stdout
+async fn schema_docs(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    output: Option<&Path>,
+    format: DocFormat,
+    sample: Option<u32>,
+) -> Result<(), CoreError> {
+    info!("Documenting schema of tenant: {}", tenant_id);
+
+    let format = match format {
+        DocFormat::Markdown => "markdown",
+        DocFormat::Html => "html",
+    };
+    let mut path = format!("/graph/{}/schema/docs?format={}", tenant_id, format);
+    if let Some(sample) = sample {
+        path.push_str(&format!("&sample={}", sample));
+    }
+    let response = client.get(&path).await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(CoreError::Internal(format!("Failed to document schema: {}", error_text)));
+    }
+    let doc = response.text().await
+        .map_err(|e| CoreError::Internal(format!("Failed to read schema document: {}", e)))?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, doc)
+                .map_err(|e| CoreError::Internal(format!("Failed to write {}: {}", path.display(), e)))?;
+            eprintln!("{}", format!("✓ Wrote schema of tenant '{}' to {}", tenant_id, path.display()).green());
+        }
+        None => print!("{}", doc),
+    }
+    Ok(())
+}
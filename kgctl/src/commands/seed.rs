@@ -0,0 +1,75 @@
+//! Seed command implementations
+//!
+//! Seeding goes through the admin API, so these commands are run with a
+//! context whose `auth_token` is the admin secret, against a server started
+//! in dev mode.
+
+use crate::cli::SeedCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use serde_json::json;
+use std::io::{self, Write};
+use std::path::Path;
+use telamentis_core::errors::CoreError;
+use telamentis_core::fixtures::{Fixture, SeedReport};
+use tracing::{info, warn};
+
+/// Handle seed commands
+pub async fn handle_seed_command(command: SeedCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        SeedCommands::Apply { file, reset } => {
+            let fixture = load_fixture(&file)?;
+            info!("Seeding {} tenant(s) from {}", fixture.tenants.len(), file.display());
+
+            let response = client.post("/admin/seed", &json!({ "fixture": fixture, "reset": reset })).await?;
+            let report: SeedReport = client.handle_response(response).await?;
+            output::display_outcome(&report, &config.default_format, || {
+                for (tenant, seeded) in &report.tenants {
+                    let removed = if reset { format!(" after clearing {} record(s)", seeded.removed) } else { String::new() };
+                    println!("{}", format!("✓ Seeded tenant '{}' with {} node(s) and {} edge(s){}", tenant, seeded.nodes, seeded.edges, removed).green());
+                }
+            })
+        }
+        SeedCommands::Teardown { file, force } => {
+            let fixture = load_fixture(&file)?;
+            let tenants: Vec<_> = fixture.tenants.keys().cloned().collect();
+            if !force {
+                // Prompt on stderr so that stdout only carries the result
+                eprint!("Remove all data of tenant(s) {}? [y/N]: ", tenants.join(", "));
+                io::stderr().flush().unwrap();
+
+                let mut input = String::new();
+                io::stdin().read_line(&mut input).unwrap();
+
+                let input = input.trim().to_lowercase();
+                if input != "y" && input != "yes" {
+                    eprintln!("Teardown cancelled");
+                    return Ok(());
+                }
+            }
+            warn!("Tearing down fixture tenants: {}", tenants.join(", "));
+
+            let response = client.post("/admin/seed/teardown", &fixture).await?;
+            let report: SeedReport = client.handle_response(response).await?;
+            output::display_outcome(&report, &config.default_format, || {
+                for (tenant, removed) in &report.tenants {
+                    println!("{}", format!("✓ Cleared tenant '{}': {} record(s) removed", tenant, removed.removed).green());
+                }
+            })
+        }
+    }
+}
+
+/// Read a fixture file; YAML, which JSON files are too
+fn load_fixture(path: &Path) -> Result<Fixture, CoreError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| CoreError::Configuration(format!("Failed to read fixture {}: {}", path.display(), e)))?;
+    let fixture: Fixture = serde_yaml::from_str(&content)
+        .map_err(|e| CoreError::Configuration(format!("Invalid fixture {}: {}", path.display(), e)))?;
+    fixture.validate()?;
+    Ok(fixture)
+}
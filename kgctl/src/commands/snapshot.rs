@@ -0,0 +1,96 @@
+//! Materialized snapshot command implementations
+
+use crate::cli::SnapshotCommands;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde_json::json;
+use telamentis_core::errors::CoreError;
+use telamentis_core::materialized::SnapshotInfo;
+use telamentis_core::temporal::{IntervalBound, TemporalUtils};
+use tracing::info;
+
+/// Handle snapshot commands
+pub async fn handle_snapshot_command(command: SnapshotCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        SnapshotCommands::Create { tenant, name, valid_at } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            let valid_at = parse_datetime(&valid_at)?;
+            create_snapshot(&client, &tenant_id, &name, valid_at, config).await
+        }
+        SnapshotCommands::List { tenant } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            list_snapshots(&client, &tenant_id, config).await
+        }
+        SnapshotCommands::Drop { tenant, name } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            drop_snapshot(&client, &tenant_id, &name, config).await
+        }
+    }
+}
+
+/// Materialize the graph valid at `valid_at` under `name`
+async fn create_snapshot(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    name: &str,
+    valid_at: DateTime<Utc>,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Materializing snapshot '{}' for tenant: {}", name, tenant_id);
+
+    let response = client.post(&snapshots_path(tenant_id), &json!({ "name": name, "valid_at": valid_at })).await?;
+    let snapshot: SnapshotInfo = client.handle_response(response).await?;
+
+    if !config.default_format.is_table() {
+        return output::display_snapshots(std::slice::from_ref(&snapshot), &config.default_format);
+    }
+    println!("{}", format!("✓ Materialized snapshot '{}' at {}", snapshot.name, snapshot.valid_at.to_rfc3339()).green());
+    println!("  Nodes: {}, Edges: {}", snapshot.node_count, snapshot.edge_count);
+    Ok(())
+}
+
+/// List a tenant's snapshots
+async fn list_snapshots(client: &TelaMentisClient, tenant_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Listing snapshots for tenant: {}", tenant_id);
+
+    let response = client.get(&snapshots_path(tenant_id)).await?;
+    let snapshots: Vec<SnapshotInfo> = client.handle_response(response).await?;
+
+    if snapshots.is_empty() && config.default_format.is_table() {
+        println!("No snapshots for tenant '{}'", tenant_id);
+        return Ok(());
+    }
+
+    output::display_snapshots(&snapshots, &config.default_format)
+}
+
+/// Drop a snapshot
+async fn drop_snapshot(client: &TelaMentisClient, tenant_id: &str, name: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Dropping snapshot '{}' for tenant: {}", name, tenant_id);
+
+    let response = client.delete(&format!("{}/{}", snapshots_path(tenant_id), name)).await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(CoreError::Internal(format!("Failed to drop snapshot: {}", error_text)));
+    }
+
+    output::display_outcome(&json!({ "dropped_snapshot": name }), &config.default_format, || {
+        println!("{}", format!("✓ Dropped snapshot '{}'", name).green());
+    })
+}
+
+fn snapshots_path(tenant_id: &str) -> String {
+    format!("/graph/{}/snapshots", tenant_id)
+}
+
+/// Parse an "as of" time; a date such as "2020-03" means its last instant
+fn parse_datetime(datetime_str: &str) -> Result<DateTime<Utc>, CoreError> {
+    TemporalUtils::parse_time(datetime_str, IntervalBound::AsOf)
+        .map_err(|e| CoreError::Internal(format!("Invalid datetime: {}", e)))
+}
@@ -0,0 +1,115 @@
+//! Sync command implementation
+
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use serde::Serialize;
+use telamentis_core::errors::CoreError;
+use telamentis_core::sync::{ApplyReport, ChangeBatch, SyncFailure, SyncStatus};
+use tracing::info;
+
+/// Outcome of syncing in one direction
+#[derive(Debug, Default, Serialize)]
+struct SyncPass {
+    from: String,
+    to: String,
+    batches: usize,
+    applied: usize,
+    skipped: usize,
+    /// Whether the source had already dropped changes the target had not seen
+    truncated: bool,
+    failed: Option<SyncFailure>,
+}
+
+#[derive(Debug, Serialize)]
+struct SyncOutcome {
+    tenant: String,
+    peer: String,
+    pulled: Option<SyncPass>,
+    pushed: Option<SyncPass>,
+}
+
+/// Handle the sync command: pull the peer's changes to the configured
+/// instance, then push the configured instance's changes to the peer
+pub async fn handle_sync_command(
+    tenant: Option<String>,
+    peer: &str,
+    pull_only: bool,
+    push_only: bool,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    let tenant_id = config.get_tenant(&tenant)?;
+    let local = TelaMentisClient::new(config.clone())?;
+    let remote = TelaMentisClient::new(config.clone().with_context(Some(peer))?)?;
+
+    let mut outcome = SyncOutcome { tenant: tenant_id.clone(), peer: peer.to_string(), pulled: None, pushed: None };
+    if !push_only {
+        info!("Pulling changes of tenant {} from {}", tenant_id, peer);
+        outcome.pulled = Some(sync_pass(&remote, &local, &tenant_id).await?);
+    }
+    if !pull_only && outcome.pulled.as_ref().and_then(|pass| pass.failed.as_ref()).is_none() {
+        info!("Pushing changes of tenant {} to {}", tenant_id, peer);
+        outcome.pushed = Some(sync_pass(&local, &remote, &tenant_id).await?);
+    }
+
+    output::display_outcome(&outcome, &config.default_format, || {
+        for (direction, pass) in [("Pulled", &outcome.pulled), ("Pushed", &outcome.pushed)] {
+            let Some(pass) = pass else { continue };
+            println!("{}", format!("✓ {} {} change(s) from {} to {}", direction, pass.applied, pass.from, pass.to).green());
+            if pass.skipped > 0 {
+                println!("  {} change(s) lost to newer versions", pass.skipped);
+            }
+            if pass.truncated {
+                println!("{}", format!("  {} dropped changes before they were synced; some writes are missing", pass.from).yellow());
+            }
+            if let Some(failure) = &pass.failed {
+                println!("{}", format!("  Stopped at change {}: {}", failure.cursor, failure.error).red());
+            }
+        }
+    })
+}
+
+/// Apply the source's changes to the target in batches, starting from the
+/// cursor the target last applied
+async fn sync_pass(source: &TelaMentisClient, target: &TelaMentisClient, tenant_id: &str) -> Result<SyncPass, CoreError> {
+    let source_status = status(source, tenant_id).await?;
+    let target_status = status(target, tenant_id).await?;
+    let mut since = target_status.peers.get(&source_status.instance_id).copied().unwrap_or(0);
+
+    let mut pass = SyncPass {
+        from: source_status.instance_id,
+        to: target_status.instance_id.clone(),
+        ..Default::default()
+    };
+    loop {
+        let response = source.get(&changes_path(tenant_id, since, &target_status.instance_id)?).await?;
+        let batch: ChangeBatch = source.handle_response(response).await?;
+        let has_more = batch.has_more;
+        pass.truncated |= batch.truncated;
+
+        let response = target.post(&format!("/sync/{}/apply", tenant_id), &batch).await?;
+        let report: ApplyReport = target.handle_response(response).await?;
+        pass.batches += 1;
+        pass.applied += report.applied;
+        pass.skipped += report.skipped.len();
+        if report.failed.is_some() || !has_more {
+            pass.failed = report.failed;
+            return Ok(pass);
+        }
+        since = report.cursor;
+    }
+}
+
+async fn status(client: &TelaMentisClient, tenant_id: &str) -> Result<SyncStatus, CoreError> {
+    let response = client.get(&format!("/sync/{}", tenant_id)).await?;
+    client.handle_response(response).await
+}
+
+/// Path of the changes after `since`, leaving out those the target sent
+fn changes_path(tenant_id: &str, since: u64, exclude_origin: &str) -> Result<String, CoreError> {
+    let since = since.to_string();
+    let url = reqwest::Url::parse_with_params("http://localhost/", [("since", since.as_str()), ("exclude_origin", exclude_origin)])
+        .map_err(|e| CoreError::Internal(format!("Failed to build sync URL: {}", e)))?;
+    Ok(format!("/sync/{}/changes?{}", tenant_id, url.query().unwrap_or_default()))
+}
@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
 use telamentis_core::errors::CoreError;
 use telamentis_core::tenant::{TenantInfo, TenantStatus};
-use telamentis_core::types::TenantId;
+use telamentis_core::types::{GraphCatalog, GraphSummary, TenantId};
 use tracing::{info, warn};
 
 /// Handle tenant management commands
@@ -18,7 +18,7 @@ pub async fn handle_tenant_command(command: TenantCommands, config: &KgctlConfig
     
     match command {
         TenantCommands::Create { tenant_id, name, description, isolation } => {
-            create_tenant(&client, &tenant_id, name, description, isolation).await
+            create_tenant(&client, &tenant_id, name, description, isolation, config).await
         }
         TenantCommands::List => {
             list_tenants(&client, config).await
@@ -26,8 +26,14 @@ pub async fn handle_tenant_command(command: TenantCommands, config: &KgctlConfig
         TenantCommands::Describe { tenant_id } => {
             describe_tenant(&client, &tenant_id, config).await
         }
+        TenantCommands::Stats { tenant_id } => {
+            tenant_stats(&client, &tenant_id, config).await
+        }
+        TenantCommands::Catalog { tenant_id } => {
+            tenant_catalog(&client, &tenant_id, config).await
+        }
         TenantCommands::Delete { tenant_id, force } => {
-            delete_tenant(&client, &tenant_id, force).await
+            delete_tenant(&client, &tenant_id, force, config).await
         }
     }
 }
@@ -39,6 +45,7 @@ async fn create_tenant(
     name: Option<String>,
     description: Option<String>,
     isolation: IsolationModel,
+    config: &KgctlConfig,
 ) -> Result<(), CoreError> {
     info!("Creating tenant: {}", tenant_id);
     
@@ -48,7 +55,8 @@ async fn create_tenant(
         IsolationModel::Label => telamentis_core::tenant::IsolationModel::Label,
     };
     
-    let tenant_info = TenantInfo::new(TenantId::new(tenant_id))
+    let id = TenantId::parse(tenant_id).map_err(|e| CoreError::Tenant(format!("Invalid tenant ID '{}': {}", tenant_id, e)))?;
+    let tenant_info = TenantInfo::new(id)
         .with_isolation_model(isolation_model)
         .activate();
     
@@ -65,9 +73,12 @@ async fn create_tenant(
     };
     
     let response = client.post("/tenants", &tenant_info).await?;
-    let _created_tenant: TenantInfo = client.handle_response(response).await?;
+    let created_tenant: TenantInfo = client.handle_response(response).await?;
     
-    println!("{}", format!("✓ Tenant '{}' created successfully", tenant_id).green().bold());
+    if !config.default_format.is_table() {
+        return output::display_tenant_details(&created_tenant, &config.default_format);
+    }
+    println!("{}", format!("✓ Tenant '{}' created successfully", created_tenant.id).green().bold());
     println!("Isolation model: {}", isolation);
     
     Ok(())
@@ -80,7 +91,7 @@ async fn list_tenants(client: &TelaMentisClient, config: &KgctlConfig) -> Result
     let response = client.get("/tenants").await?;
     let tenants: Vec<TenantInfo> = client.handle_response(response).await?;
     
-    if tenants.is_empty() {
+    if tenants.is_empty() && config.default_format.is_table() {
         println!("No tenants found");
         return Ok(());
     }
@@ -104,22 +115,54 @@ async fn describe_tenant(
     Ok(())
 }
 
+/// Show the size and activity of a tenant's graph
+async fn tenant_stats(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Reading graph summary for tenant: {}", tenant_id);
+    
+    let response = client.get(&format!("/graph/{}/summary", tenant_id)).await?;
+    let summary: GraphSummary = client.handle_response(response).await?;
+    
+    output::display_graph_summary(tenant_id, &summary, &config.default_format)?;
+    Ok(())
+}
+
+/// Show the labels, relationship kinds and property keys of a tenant's graph
+async fn tenant_catalog(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Reading graph catalog for tenant: {}", tenant_id);
+    
+    let response = client.get(&format!("/graph/{}/catalog", tenant_id)).await?;
+    let catalog: GraphCatalog = client.handle_response(response).await?;
+    
+    output::display_graph_catalog(tenant_id, &catalog, &config.default_format)?;
+    Ok(())
+}
+
 /// Delete a tenant
 async fn delete_tenant(
     client: &TelaMentisClient,
     tenant_id: &str,
     force: bool,
+    config: &KgctlConfig,
 ) -> Result<(), CoreError> {
     if !force {
-        print!("Are you sure you want to delete tenant '{}'? This action cannot be undone. [y/N]: ", tenant_id);
-        io::stdout().flush().unwrap();
+        // Prompt on stderr so that stdout only carries the result
+        eprint!("Are you sure you want to delete tenant '{}'? This action cannot be undone. [y/N]: ", tenant_id);
+        io::stderr().flush().unwrap();
         
         let mut input = String::new();
         io::stdin().read_line(&mut input).unwrap();
         
         let input = input.trim().to_lowercase();
         if input != "y" && input != "yes" {
-            println!("Deletion cancelled");
+            eprintln!("Deletion cancelled");
             return Ok(());
         }
     }
@@ -128,14 +171,14 @@ async fn delete_tenant(
     
     let response = client.delete(&format!("/tenants/{}", tenant_id)).await?;
     
-    if response.status().is_success() {
-        println!("{}", format!("✓ Tenant '{}' deleted successfully", tenant_id).green().bold());
-    } else {
+    if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
         return Err(CoreError::Internal(format!("Failed to delete tenant: {}", error_text)));
     }
     
-    Ok(())
+    output::display_outcome(&serde_json::json!({ "deleted_tenant_id": tenant_id }), &config.default_format, || {
+        println!("{}", format!("✓ Tenant '{}' deleted successfully", tenant_id).green().bold());
+    })
 }
 
 #[cfg(test)]
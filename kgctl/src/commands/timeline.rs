@@ -0,0 +1,94 @@
+//! Timeline command implementation
+
+use crate::cli::TimelineInterval;
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use chrono::{DateTime, SecondsFormat, Utc};
+use colored::*;
+use telamentis_core::errors::CoreError;
+use telamentis_core::temporal::IntervalBound;
+use telamentis_core::timeline::{EdgeChange, Timeline};
+use telamentis_core::types::TenantId;
+use telamentis_core::valid_time::ValidTimePolicy;
+use tracing::info;
+
+/// Handle the timeline command: show the edges of an aliased node created
+/// and closed in each bucket of a range
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_timeline_command(
+    alias: &str,
+    tenant: Option<String>,
+    namespace: Option<String>,
+    interval: TimelineInterval,
+    from: Option<String>,
+    to: Option<String>,
+    types: Vec<String>,
+    events: bool,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    let tenant_id = config.get_tenant(&tenant)?;
+    let valid_time = config.valid_time.for_tenant(&TenantId::new(&tenant_id));
+    let client = TelaMentisClient::new(config.clone())?;
+
+    let interval = match interval {
+        TimelineInterval::Day => "day",
+        TimelineInterval::Week => "week",
+        TimelineInterval::Month => "month",
+    };
+    let mut params = vec![("interval", interval.to_string())];
+    if let Some(namespace) = namespace {
+        params.push(("namespace", namespace));
+    }
+    for (name, value, bound) in [("from", from, IntervalBound::Start), ("to", to, IntervalBound::End)] {
+        if let Some(value) = value {
+            params.push((name, parse_time(&value, valid_time, bound)?.to_rfc3339_opts(SecondsFormat::Millis, true)));
+        }
+    }
+    if !types.is_empty() {
+        params.push(("types", types.join(",")));
+    }
+
+    info!("Fetching {} timeline of alias {} for tenant: {}", interval, alias, tenant_id);
+    let url = reqwest::Url::parse_with_params("http://localhost/", &params)
+        .map_err(|e| CoreError::Internal(format!("Failed to build timeline URL: {}", e)))?;
+    let path = format!("/graph/{}/aliases/{}/timeline?{}", tenant_id, alias, url.query().unwrap_or_default());
+    let response = client.get(&path).await?;
+    let timeline: Timeline = client.handle_response(response).await?;
+
+    output::display_outcome(&timeline, &config.default_format, || {
+        println!(
+            "Timeline of '{}' ({}) by {}, {} to {}",
+            alias, timeline.node_id, timeline.interval,
+            timeline.from.format("%Y-%m-%d"), timeline.to.format("%Y-%m-%d %H:%M")
+        );
+        for bucket in &timeline.buckets {
+            let created = format!("+{}", bucket.created);
+            let closed = format!("-{}", bucket.closed);
+            println!(
+                "{}  {:>6} {:>6}",
+                bucket.start.format("%Y-%m-%d"),
+                if bucket.created > 0 { created.green() } else { created.normal() },
+                if bucket.closed > 0 { closed.red() } else { closed.normal() },
+            );
+            if events {
+                for event in &bucket.events {
+                    let change = match event.change {
+                        EdgeChange::Created => "created".green(),
+                        EdgeChange::Closed => "closed".red(),
+                    };
+                    println!(
+                        "    {} {:<7} {} {} -> {} ({})",
+                        event.at.format("%Y-%m-%d %H:%M"), change, event.kind,
+                        event.from_node_id, event.to_node_id, event.edge_id
+                    );
+                }
+            }
+        }
+    })
+}
+
+fn parse_time(value: &str, valid_time: &ValidTimePolicy, bound: IntervalBound) -> Result<DateTime<Utc>, CoreError> {
+    valid_time.parse_bound(value, bound)
+        .ok_or_else(|| CoreError::Internal(format!("Invalid datetime '{}'", value)))
+}
@@ -0,0 +1,126 @@
+//! API token command implementations
+
+use crate::cli::{TokenCommands, TokenScope};
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use crate::output;
+use colored::*;
+use serde_json::json;
+use telamentis_core::auth::{ApiToken, IssuedToken};
+use telamentis_core::errors::CoreError;
+use tracing::info;
+
+/// Handle token commands
+pub async fn handle_token_command(command: TokenCommands, config: &KgctlConfig) -> Result<(), CoreError> {
+    let client = TelaMentisClient::new(config.clone())?;
+
+    match command {
+        TokenCommands::Create { tenant, name, scope, ttl_hours } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            create_token(&client, &tenant_id, &name, scope, ttl_hours, config).await
+        }
+        TokenCommands::List { tenant } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            list_tokens(&client, &tenant_id, config).await
+        }
+        TokenCommands::Revoke { tenant, token_id } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            revoke_token(&client, &tenant_id, &token_id, config).await
+        }
+        TokenCommands::Rotate { tenant, token_id, overlap_hours } => {
+            let tenant_id = config.get_tenant(&tenant)?;
+            rotate_token(&client, &tenant_id, &token_id, overlap_hours, config).await
+        }
+    }
+}
+
+/// Create a token and show its secret
+async fn create_token(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    name: &str,
+    scope: TokenScope,
+    ttl_hours: Option<i64>,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Creating token '{}' for tenant: {}", name, tenant_id);
+
+    let request = json!({
+        "name": name,
+        "scope": core_scope(scope),
+        "ttl_secs": ttl_hours.map(|hours| hours * 3600),
+    });
+    let response = client.post(&tokens_path(tenant_id), &request).await?;
+    let issued: IssuedToken = client.handle_response(response).await?;
+
+    display_issued(&issued, &format!("✓ Created {} token '{}' ({})", issued.token.scope, issued.token.name, issued.token.id), config)
+}
+
+/// List a tenant's tokens
+async fn list_tokens(client: &TelaMentisClient, tenant_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Listing tokens for tenant: {}", tenant_id);
+
+    let response = client.get(&tokens_path(tenant_id)).await?;
+    let tokens: Vec<ApiToken> = client.handle_response(response).await?;
+
+    if tokens.is_empty() && config.default_format.is_table() {
+        println!("No tokens for tenant '{}'", tenant_id);
+        return Ok(());
+    }
+
+    output::display_tokens(&tokens, &config.default_format)
+}
+
+/// Revoke a token
+async fn revoke_token(client: &TelaMentisClient, tenant_id: &str, token_id: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    info!("Revoking token {} of tenant: {}", token_id, tenant_id);
+
+    let response = client.delete(&format!("{}/{}", tokens_path(tenant_id), token_id)).await?;
+    let token: ApiToken = client.handle_response(response).await?;
+
+    output::display_outcome(&token, &config.default_format, || {
+        println!("{}", format!("✓ Revoked token '{}' ({})", token.name, token.id).green());
+    })
+}
+
+/// Rotate a token and show the new secret
+async fn rotate_token(
+    client: &TelaMentisClient,
+    tenant_id: &str,
+    token_id: &str,
+    overlap_hours: i64,
+    config: &KgctlConfig,
+) -> Result<(), CoreError> {
+    info!("Rotating token {} of tenant: {}", token_id, tenant_id);
+
+    let path = format!("{}/{}/rotate", tokens_path(tenant_id), token_id);
+    let response = client.post(&path, &json!({ "overlap_secs": overlap_hours * 3600 })).await?;
+    let issued: IssuedToken = client.handle_response(response).await?;
+
+    let message = format!("✓ Rotated token {} to {}; the old secret works for {} more hour(s)", token_id, issued.token.id, overlap_hours);
+    display_issued(&issued, &message, config)
+}
+
+/// Show a new token with its secret, which the server will not return again
+fn display_issued(issued: &IssuedToken, message: &str, config: &KgctlConfig) -> Result<(), CoreError> {
+    output::display_outcome(issued, &config.default_format, || {
+        println!("{}", message.green());
+        println!("  Secret: {}", issued.secret.bold());
+        if let Some(expires_at) = issued.token.expires_at {
+            println!("  Expires: {}", expires_at.to_rfc3339());
+        }
+        println!("{}", "Store the secret now; it cannot be shown again.".yellow());
+    })
+}
+
+fn core_scope(scope: TokenScope) -> telamentis_core::auth::TokenScope {
+    match scope {
+        TokenScope::Read => telamentis_core::auth::TokenScope::Read,
+        TokenScope::Write => telamentis_core::auth::TokenScope::Write,
+        TokenScope::Admin => telamentis_core::auth::TokenScope::Admin,
+    }
+}
+
+fn tokens_path(tenant_id: &str) -> String {
+    format!("/tenants/{}/tokens", tenant_id)
+}
@@ -0,0 +1,106 @@
+//! Shell completion
+//!
+//! Completion runs through `kgctl` itself: the registration script printed
+//! by `kgctl completion <shell>` calls back into the binary with `COMPLETE`
+//! set, so values such as tenant IDs can be looked up when the user presses
+//! tab. Lookups use the configuration file and current context, never fail
+//! loudly, and give up quickly when the API is unreachable.
+
+use crate::cli::{Cli, CompletionShell};
+use crate::client::TelaMentisClient;
+use crate::config::KgctlConfig;
+use clap::CommandFactory;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, EnvCompleter, Fish, Zsh};
+use clap_complete::CompleteEnv;
+use std::ffi::OsStr;
+use std::future::Future;
+use std::io::Write;
+use telamentis_core::errors::CoreError;
+use telamentis_core::tenant::TenantInfo;
+
+/// Environment variable the registration scripts set when asking for completions
+const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Longest wait for the API while completing, in seconds
+const LOOKUP_TIMEOUT_SECS: u64 = 2;
+
+/// Answer a completion request and exit, if this run is one
+pub fn complete_if_requested() {
+    CompleteEnv::with_factory(Cli::command).var(COMPLETE_VAR).complete();
+}
+
+/// Print the script that registers completion for `shell`
+pub fn write_registration(shell: CompletionShell, out: &mut dyn Write) -> Result<(), CoreError> {
+    let bin = std::env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| "kgctl".to_string());
+    let completer: &dyn EnvCompleter = match shell {
+        CompletionShell::Bash => &Bash,
+        CompletionShell::Zsh => &Zsh,
+        CompletionShell::Fish => &Fish,
+    };
+    completer.write_registration(COMPLETE_VAR, "kgctl", "kgctl", &bin, out)
+        .map_err(|e| CoreError::Internal(format!("Failed to write completion script: {}", e)))
+}
+
+/// Complete tenant IDs from the API
+pub fn tenants(current: &OsStr) -> Vec<CompletionCandidate> {
+    let prefix = current.to_string_lossy();
+    lookup(list_tenants())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|tenant| tenant.id.as_str().starts_with(prefix.as_ref()))
+        .map(|tenant| {
+            let candidate = CompletionCandidate::new(tenant.id.as_str());
+            match tenant.name {
+                Some(name) => candidate.help(Some(name.into())),
+                None => candidate,
+            }
+        })
+        .collect()
+}
+
+async fn list_tenants() -> Result<Vec<TenantInfo>, CoreError> {
+    let mut config = KgctlConfig::load(&None).await?.with_context(None)?;
+    config.timeout = config.timeout.min(LOOKUP_TIMEOUT_SECS);
+
+    let client = TelaMentisClient::new(config)?;
+    let response = client.get("/tenants").await?;
+    client.handle_response(response).await
+}
+
+/// Complete context names from the configuration file
+pub fn contexts(current: &OsStr) -> Vec<CompletionCandidate> {
+    let prefix = current.to_string_lossy();
+    let Some(config) = lookup(async { KgctlConfig::load(&None).await }) else {
+        return Vec::new();
+    };
+    config.contexts
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(prefix.as_ref()))
+        .map(|(name, context)| {
+            let candidate = CompletionCandidate::new(name);
+            match context.endpoint {
+                Some(endpoint) => candidate.help(Some(endpoint.into())),
+                None => candidate,
+            }
+        })
+        .collect()
+}
+
+/// Run a lookup on its own runtime, since completers are called from within
+/// kgctl's; any failure just means no candidates
+fn lookup<T, F>(future: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, CoreError>> + Send + 'static,
+{
+    std::thread::spawn(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().ok()?;
+        runtime.block_on(future).ok()
+    })
+    .join()
+    .ok()
+    .flatten()
+}
@@ -1,10 +1,82 @@
 //! Configuration management for kgctl
 
 use crate::cli::{Cli, OutputFormat};
+use crate::secure_export::ExportKey;
 use figment::{Figment, providers::{Format, Yaml, Env}};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use telamentis_adapter_neo4j::Neo4jConfig;
 use telamentis_core::errors::CoreError;
+use telamentis_core::http::{self, PoolConfig, ProxyConfig};
+use telamentis_core::rdf::RdfMapping;
+use telamentis_core::valid_time::ValidTimePolicies;
+
+/// Config file written when none exists yet
+const DEFAULT_CONFIG_FILE: &str = "kgctl.yaml";
+
+/// TLS settings for connecting to the API
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// PEM bundle of CA certificates to trust in addition to the system roots
+    pub ca_cert: Option<PathBuf>,
+    /// PEM file of a client certificate, for mutual TLS
+    pub client_cert: Option<PathBuf>,
+    /// PEM file of the client certificate's PKCS#8 key
+    pub client_key: Option<PathBuf>,
+    /// Accept any server certificate; for development only
+    pub insecure_skip_verify: bool,
+}
+
+impl TlsConfig {
+    /// Apply the settings to an HTTP client builder
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, CoreError> {
+        if let Some(path) = &self.ca_cert {
+            for certificate in http::read_root_certificates(path).map_err(CoreError::Configuration)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                let identity = reqwest::Identity::from_pkcs8_pem(&read_pem(cert)?, &read_pem(key)?)
+                    .map_err(|e| CoreError::Configuration(format!("Invalid client certificate {}: {}", cert.display(), e)))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(CoreError::Configuration(
+                    "client_cert and client_key must be set together".to_string()
+                ));
+            }
+        }
+
+        Ok(builder.danger_accept_invalid_certs(self.insecure_skip_verify))
+    }
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>, CoreError> {
+    std::fs::read(path)
+        .map_err(|e| CoreError::Configuration(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+/// A named environment, like a kubectl context; unset fields keep the
+/// top-level values
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// TelaMentis API endpoint
+    pub endpoint: Option<String>,
+    /// Default tenant ID
+    pub default_tenant: Option<String>,
+    /// Authentication token
+    pub auth_token: Option<String>,
+    /// TLS settings, replacing the top-level ones
+    pub tls: Option<TlsConfig>,
+    /// Outbound proxy, replacing the top-level one
+    pub proxy: Option<ProxyConfig>,
+}
 
 /// Configuration for kgctl CLI
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +93,36 @@ pub struct KgctlConfig {
     pub timeout: u64,
     /// Date format string for parsing
     pub default_date_format: String,
+    /// Base64-encoded 32-byte export keys by tenant ID
+    #[serde(default)]
+    pub export_keys: HashMap<String, String>,
+    /// Per-tenant defaults for the valid times of ingested edges
+    #[serde(default)]
+    pub valid_time: ValidTimePolicies,
+    /// IRIs of labels, kinds and properties in Turtle and N-Triples exports
+    #[serde(default)]
+    pub rdf: RdfMapping,
+    /// TLS settings for connecting to the API
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Outbound proxy for reaching the API
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    /// Connection pool tuning
+    #[serde(default)]
+    pub pool: PoolConfig,
+    /// Neo4j database that `kgctl migrate` connects to directly
+    #[serde(default)]
+    pub neo4j: Option<Neo4jConfig>,
+    /// Named contexts
+    #[serde(default)]
+    pub contexts: BTreeMap<String, ContextConfig>,
+    /// Context used when `--context` is not given
+    #[serde(default)]
+    pub current_context: Option<String>,
+    /// File the configuration was read from
+    #[serde(skip)]
+    pub config_file: Option<PathBuf>,
 }
 
 impl Default for KgctlConfig {
@@ -32,6 +134,16 @@ impl Default for KgctlConfig {
             auth_token: None,
             timeout: 30,
             default_date_format: "%Y-%m-%d %H:%M:%S".to_string(),
+            export_keys: HashMap::new(),
+            valid_time: ValidTimePolicies::default(),
+            rdf: RdfMapping::default(),
+            tls: TlsConfig::default(),
+            proxy: None,
+            pool: PoolConfig::default(),
+            neo4j: None,
+            contexts: BTreeMap::new(),
+            current_context: None,
+            config_file: None,
         }
     }
 }
@@ -40,6 +152,7 @@ impl KgctlConfig {
     /// Load configuration from file and environment
     pub async fn load(config_path: &Option<std::path::PathBuf>) -> Result<Self, CoreError> {
         let mut figment = Figment::new();
+        let mut config_file = None;
 
         // Load from default config file if it exists
         let default_config_paths = [
@@ -52,6 +165,7 @@ impl KgctlConfig {
         for path in &default_config_paths {
             if Path::new(path).exists() {
                 figment = figment.merge(Yaml::file(path));
+                config_file = Some(PathBuf::from(path));
                 break;
             }
         }
@@ -60,6 +174,7 @@ impl KgctlConfig {
         if let Some(path) = config_path {
             if path.exists() {
                 figment = figment.merge(Yaml::file(path));
+                config_file = Some(path.clone());
             } else {
                 return Err(CoreError::Configuration(format!(
                     "Configuration file not found: {}",
@@ -72,8 +187,43 @@ impl KgctlConfig {
         figment = figment.merge(Env::prefixed("KGCTL_"));
 
         // Extract the configuration
-        figment.extract()
-            .map_err(|e| CoreError::Configuration(format!("Failed to parse configuration: {}", e)))
+        let mut config: Self = figment.extract()
+            .map_err(|e| CoreError::Configuration(format!("Failed to parse configuration: {}", e)))?;
+        config.config_file = config_file;
+        Ok(config)
+    }
+
+    /// Apply a context: the named one, or else the current context if set
+    pub fn with_context(mut self, name: Option<&str>) -> Result<Self, CoreError> {
+        let Some(name) = name.map(str::to_string).or_else(|| self.current_context.clone()) else {
+            return Ok(self);
+        };
+        let context = self.contexts.get(&name).cloned().ok_or_else(|| CoreError::Configuration(format!(
+            "No context named '{}'. See `kgctl config list`", name
+        )))?;
+
+        if let Some(endpoint) = context.endpoint {
+            self.endpoint = endpoint;
+        }
+        if let Some(tenant) = context.default_tenant {
+            self.default_tenant = Some(tenant);
+        }
+        if let Some(token) = context.auth_token {
+            self.auth_token = Some(token);
+        }
+        if let Some(tls) = context.tls {
+            self.tls = tls;
+        }
+        if let Some(proxy) = context.proxy {
+            self.proxy = Some(proxy);
+        }
+        self.current_context = Some(name);
+        Ok(self)
+    }
+
+    /// File that `kgctl config use-context` writes to
+    pub fn writable_config_file(&self) -> PathBuf {
+        self.config_file.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE))
     }
 
     /// Apply CLI argument overrides to the configuration
@@ -104,11 +254,32 @@ impl KgctlConfig {
             ))
     }
 
+    /// Get the export key for a tenant, preferring an explicit key file
+    pub fn export_key(&self, tenant_id: &str, key_file: Option<&Path>) -> Result<Option<ExportKey>, CoreError> {
+        if let Some(path) = key_file {
+            return ExportKey::from_file(path).map(Some);
+        }
+
+        self.export_keys
+            .get(tenant_id)
+            .map(|encoded| ExportKey::from_base64(encoded))
+            .transpose()
+    }
+
     /// Get the base URL for API calls
     pub fn api_url(&self, path: &str) -> String {
         format!("{}/v1{}", self.endpoint.trim_end_matches('/'), path)
     }
 
+    /// Apply the TLS, proxy and pool settings to an HTTP client builder
+    pub fn apply_network(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, CoreError> {
+        builder = self.tls.apply(builder)?;
+        if let Some(proxy) = &self.proxy {
+            builder = proxy.apply(builder).map_err(CoreError::Configuration)?;
+        }
+        Ok(self.pool.apply(builder))
+    }
+
     /// Get authentication headers if configured
     pub fn auth_headers(&self) -> reqwest::header::HeaderMap {
         let mut headers = reqwest::header::HeaderMap::new();
@@ -162,6 +333,44 @@ mod tests {
         assert_eq!(config.api_url("/tenants"), "http://example.com/v1/tenants");
     }
 
+    #[test]
+    fn test_with_context() {
+        let staging = ContextConfig {
+            endpoint: Some("https://staging.example.com".to_string()),
+            default_tenant: Some("staging_tenant".to_string()),
+            ..Default::default()
+        };
+        let prod = ContextConfig {
+            endpoint: Some("https://prod.example.com".to_string()),
+            auth_token: Some("secret".to_string()),
+            tls: Some(TlsConfig { ca_cert: Some("ca.pem".into()), ..Default::default() }),
+            proxy: Some(ProxyConfig::new("http://proxy.prod.example.com:3128")),
+            ..Default::default()
+        };
+        let config = KgctlConfig {
+            default_tenant: Some("default".to_string()),
+            contexts: BTreeMap::from([("staging".to_string(), staging), ("prod".to_string(), prod)]),
+            current_context: Some("staging".to_string()),
+            ..Default::default()
+        };
+
+        let current = config.clone().with_context(None).unwrap();
+        assert_eq!(current.endpoint, "https://staging.example.com");
+        assert_eq!(current.default_tenant.as_deref(), Some("staging_tenant"));
+
+        // Unset fields keep the top-level values
+        let prod = config.clone().with_context(Some("prod")).unwrap();
+        assert_eq!(prod.endpoint, "https://prod.example.com");
+        assert_eq!(prod.default_tenant.as_deref(), Some("default"));
+        assert_eq!(prod.auth_token.as_deref(), Some("secret"));
+        assert_eq!(prod.tls.ca_cert, Some(PathBuf::from("ca.pem")));
+        assert_eq!(prod.proxy.map(|proxy| proxy.url).as_deref(), Some("http://proxy.prod.example.com:3128"));
+        assert_eq!(prod.current_context.as_deref(), Some("prod"));
+
+        assert!(config.with_context(Some("missing")).is_err());
+        assert_eq!(KgctlConfig::default().with_context(None).unwrap().endpoint, "http://localhost:8000");
+    }
+
     #[test]
     fn test_get_tenant() {
         let config = KgctlConfig {
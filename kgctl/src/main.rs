@@ -9,13 +9,17 @@ mod cli;
 mod commands;
 mod config;
 mod output;
+mod query_file;
 mod client;
+mod completion;
+mod secure_export;
 
 use cli::*;
 use config::KgctlConfig;
 
 #[tokio::main]
 async fn main() {
+    completion::complete_if_requested();
     let args = Cli::parse();
 
     // Initialize logging
@@ -40,7 +44,18 @@ async fn main() {
         }
     };
 
-    // Override config with CLI args
+    // Apply the context, then CLI args. Config commands see the file as it
+    // is, so a broken current context can still be switched away from.
+    let config = match &args.command {
+        Commands::Config { .. } | Commands::Completion { .. } => config,
+        _ => match config.with_context(args.context.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Failed to apply context: {}", e);
+                process::exit(1);
+            }
+        },
+    };
     let config = config.with_overrides(&args);
 
     info!("Starting kgctl with endpoint: {}", config.endpoint);
@@ -59,9 +74,66 @@ async fn main() {
         Commands::Query { command } => {
             commands::query::handle_query_command(command, &config).await
         }
+        Commands::Edge { command } => {
+            commands::edge::handle_edge_command(command, &config).await
+        }
+        Commands::Snapshot { command } => {
+            commands::snapshot::handle_snapshot_command(command, &config).await
+        }
+        Commands::Archive { command } => {
+            commands::archive::handle_archive_command(command, &config).await
+        }
+        Commands::Token { command } => {
+            commands::token::handle_token_command(command, &config).await
+        }
+        Commands::Replay { request_id, tenant, target, target_tenant, dry_run } => {
+            commands::replay::handle_replay_command(&request_id, tenant, target, target_tenant, dry_run, &config).await
+        }
+        Commands::Examples { command } => {
+            commands::examples::handle_examples_command(command, &config).await
+        }
+        Commands::Dlq { command } => {
+            commands::dlq::handle_dlq_command(command, &config).await
+        }
+        Commands::Timeline { alias, tenant, namespace, interval, from, to, types, events } => {
+            commands::timeline::handle_timeline_command(&alias, tenant, namespace, interval, from, to, types, events, &config).await
+        }
+        Commands::History { command } => {
+            commands::history::handle_history_command(command, &config).await
+        }
+        Commands::Operations { command } => {
+            commands::operations::handle_operations_command(command, &config).await
+        }
+        Commands::Sync { tenant, peer, pull_only, push_only } => {
+            commands::sync::handle_sync_command(tenant, &peer, pull_only, push_only, &config).await
+        }
+        Commands::Schema { command } => {
+            commands::schema::handle_schema_command(command, &config).await
+        }
+        Commands::Migrate { command } => {
+            commands::migrate::handle_migrate_command(command, &config).await
+        }
+        Commands::Seed { command } => {
+            commands::seed::handle_seed_command(command, &config).await
+        }
+        Commands::Report { command } => {
+            commands::report::handle_report_command(command, &config)
+        }
+        Commands::Admin { command } => {
+            commands::admin::handle_admin_command(command, &config).await
+        }
         Commands::Health => {
             commands::health::handle_health_command(&config).await
         }
+        Commands::Doctor => {
+            commands::doctor::handle_doctor_command(&config).await
+        }
+        Commands::Config { command } => {
+            commands::config::handle_config_command(command, args.context.as_deref(), &config)
+        }
+        Commands::Completion { shell } => {
+            completion::write_registration(shell, &mut std::io::stdout())
+        }
     };
 
     match result {
@@ -1,12 +1,20 @@
 //! Output formatting utilities for kgctl
 
 use crate::cli::OutputFormat;
+use crate::config::ContextConfig;
 use colored::*;
+use serde::Serialize;
 use serde_json::Value;
 use tabled::{Table, Tabled};
+use telamentis_core::archive::ArchiveSegment;
+use telamentis_core::auth::ApiToken;
+use telamentis_core::dead_letter::{DeadLetter, DeadLetterRecord};
 use telamentis_core::errors::CoreError;
+use telamentis_core::examples::ExtractionExample;
+use telamentis_core::materialized::SnapshotInfo;
 use telamentis_core::tenant::TenantInfo;
-use telamentis_core::types::Path;
+use std::collections::{BTreeMap, HashMap};
+use telamentis_core::types::{CatalogEntry, GraphCatalog, GraphSummary, Path};
 
 /// Display a list of tenants
 pub fn display_tenants(tenants: &[TenantInfo], format: &OutputFormat) -> Result<(), CoreError> {
@@ -31,17 +39,7 @@ pub fn display_tenants(tenants: &[TenantInfo], format: &OutputFormat) -> Result<
             let table = Table::new(table_data);
             println!("{}", table);
         }
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(tenants)
-                .map_err(|e| CoreError::Internal(format!("Failed to serialize to JSON: {}", e)))?;
-            println!("{}", json);
-        }
-        OutputFormat::Yaml => {
-            // For simplicity, we'll use JSON format for YAML (could use serde_yaml crate)
-            let json = serde_json::to_string_pretty(tenants)
-                .map_err(|e| CoreError::Internal(format!("Failed to serialize to YAML: {}", e)))?;
-            println!("{}", json);
-        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(tenants, format)?,
         OutputFormat::Csv => {
             println!("id,name,status,isolation,created");
             for tenant in tenants {
@@ -76,17 +74,7 @@ pub fn display_tenant_details(tenant: &TenantInfo, format: &OutputFormat) -> Res
                 println!("{:<15} {}", "Metadata:".bold(), serde_json::to_string_pretty(&tenant.metadata).unwrap_or_else(|_| "{}".to_string()));
             }
         }
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(tenant)
-                .map_err(|e| CoreError::Internal(format!("Failed to serialize to JSON: {}", e)))?;
-            println!("{}", json);
-        }
-        OutputFormat::Yaml => {
-            // For simplicity, using JSON format
-            let json = serde_json::to_string_pretty(tenant)
-                .map_err(|e| CoreError::Internal(format!("Failed to serialize to YAML: {}", e)))?;
-            println!("{}", json);
-        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(tenant, format)?,
         OutputFormat::Csv => {
             println!("field,value");
             println!("id,{}", tenant.id);
@@ -101,6 +89,118 @@ pub fn display_tenant_details(tenant: &TenantInfo, format: &OutputFormat) -> Res
     Ok(())
 }
 
+/// Display the node and edge counts of a tenant's graph
+pub fn display_graph_summary(tenant_id: &str, summary: &GraphSummary, format: &OutputFormat) -> Result<(), CoreError> {
+    let last_write = summary.last_write_at
+        .map_or_else(|| "-".to_string(), |t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string());
+
+    match format {
+        OutputFormat::Table => {
+            println!("{}", format!("Graph Summary: {}", tenant_id).bold().blue());
+            println!("{:<15} {}", "Nodes:".bold(), summary.node_count);
+            println!("{:<15} {}", "Edges:".bold(), summary.edge_count);
+            println!("{:<15} {}", "Last write:".bold(), last_write);
+            println!("{:<15} {}", "Storage (est):".bold(), format_bytes(summary.storage_bytes_estimate));
+
+            for (title, counts) in [("Nodes by label", &summary.nodes_by_label), ("Edges by kind", &summary.edges_by_kind)] {
+                if counts.is_empty() {
+                    continue;
+                }
+                println!();
+                println!("{}", title.bold());
+                let table_data: Vec<CountTableRow> = sorted_counts(counts)
+                    .into_iter()
+                    .map(|(name, count)| CountTableRow { name: name.to_string(), count })
+                    .collect();
+                println!("{}", Table::new(table_data));
+            }
+        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(summary, format)?,
+        OutputFormat::Csv => {
+            println!("field,value");
+            println!("nodes,{}", summary.node_count);
+            println!("edges,{}", summary.edge_count);
+            println!("last_write,{}", last_write);
+            println!("storage_bytes_estimate,{}", summary.storage_bytes_estimate);
+            for (label, count) in sorted_counts(&summary.nodes_by_label) {
+                println!("{},{}", escape_csv(&format!("label:{}", label)), count);
+            }
+            for (kind, count) in sorted_counts(&summary.edges_by_kind) {
+                println!("{},{}", escape_csv(&format!("kind:{}", kind)), count);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Display the labels and relationship kinds of a tenant's graph
+pub fn display_graph_catalog(tenant_id: &str, catalog: &GraphCatalog, format: &OutputFormat) -> Result<(), CoreError> {
+    match format {
+        OutputFormat::Table => {
+            println!("{}", format!("Graph Catalog: {}", tenant_id).bold().blue());
+            for (title, entries) in [("Labels", &catalog.labels), ("Relationship kinds", &catalog.kinds)] {
+                println!();
+                println!("{}", title.bold());
+                if entries.is_empty() {
+                    println!("None");
+                    continue;
+                }
+                let table_data: Vec<CatalogTableRow> = entries
+                    .iter()
+                    .map(|e| CatalogTableRow {
+                        name: e.name.clone(),
+                        count: e.count,
+                        property_keys: format_property_keys(e),
+                    })
+                    .collect();
+                println!("{}", Table::new(table_data));
+            }
+        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(catalog, format)?,
+        OutputFormat::Csv => {
+            println!("type,name,count,property_keys");
+            for (kind, entries) in [("label", &catalog.labels), ("kind", &catalog.kinds)] {
+                for entry in entries {
+                    println!("{},{},{},{}", kind, escape_csv(&entry.name), entry.count, escape_csv(&format_property_keys(entry)));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Property keys of a catalog entry with their counts, most common first
+fn format_property_keys(entry: &CatalogEntry) -> String {
+    sorted_counts(&entry.property_keys)
+        .into_iter()
+        .map(|(key, count)| format!("{} ({})", key, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Counts sorted from largest to smallest, then by name
+fn sorted_counts(counts: &HashMap<String, u64>) -> Vec<(&str, u64)> {
+    let mut sorted: Vec<_> = counts.iter().map(|(name, &count)| (name.as_str(), count)).collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    sorted
+}
+
+/// Format a byte count with a binary unit
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
 /// Display query results
 pub fn display_query_results(paths: &[Path], format: &OutputFormat) -> Result<(), CoreError> {
     match format {
@@ -152,17 +252,7 @@ pub fn display_query_results(paths: &[Path], format: &OutputFormat) -> Result<()
                 println!("{}", table);
             }
         }
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(paths)
-                .map_err(|e| CoreError::Internal(format!("Failed to serialize to JSON: {}", e)))?;
-            println!("{}", json);
-        }
-        OutputFormat::Yaml => {
-            // For simplicity, using JSON format
-            let json = serde_json::to_string_pretty(paths)
-                .map_err(|e| CoreError::Internal(format!("Failed to serialize to YAML: {}", e)))?;
-            println!("{}", json);
-        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(paths, format)?,
         OutputFormat::Csv => {
             // Output nodes first
             println!("# Nodes");
@@ -197,6 +287,341 @@ pub fn display_query_results(paths: &[Path], format: &OutputFormat) -> Result<()
     Ok(())
 }
 
+/// Display a tenant's few-shot extraction examples
+pub fn display_examples(examples: &[ExtractionExample], format: &OutputFormat) -> Result<(), CoreError> {
+    match format {
+        OutputFormat::Table => {
+            let table_data: Vec<ExampleTableRow> = examples
+                .iter()
+                .map(|e| ExampleTableRow {
+                    id: e.id.to_string(),
+                    input: truncate(&e.input, 60),
+                    nodes: e.expected.nodes.len(),
+                    relations: e.expected.relations.len(),
+                    description: e.description.clone().unwrap_or_else(|| "-".to_string()),
+                })
+                .collect();
+
+            let table = Table::new(table_data);
+            println!("{}", table);
+        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(examples, format)?,
+        OutputFormat::Csv => {
+            println!("id,input,nodes,relations,description");
+            for example in examples {
+                println!(
+                    "{},{},{},{},{}",
+                    example.id,
+                    escape_csv(&example.input),
+                    example.expected.nodes.len(),
+                    example.expected.relations.len(),
+                    escape_csv(example.description.as_deref().unwrap_or("-"))
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Display a tenant's failed ingestion records
+pub fn display_dead_letters(entries: &[DeadLetter], format: &OutputFormat) -> Result<(), CoreError> {
+    match format {
+        OutputFormat::Table => {
+            let table_data: Vec<DeadLetterTableRow> = entries
+                .iter()
+                .map(|e| DeadLetterTableRow {
+                    id: e.id.to_string(),
+                    record: dead_letter_record(&e.record),
+                    source: e.source.clone(),
+                    error: truncate(&e.error, 60),
+                    attempts: e.attempts,
+                    last_failed_at: e.last_failed_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                })
+                .collect();
+
+            let table = Table::new(table_data);
+            println!("{}", table);
+        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(entries, format)?,
+        OutputFormat::Csv => {
+            println!("id,record,source,error,attempts,first_failed_at,last_failed_at");
+            for entry in entries {
+                println!(
+                    "{},{},{},{},{},{},{}",
+                    entry.id,
+                    escape_csv(&dead_letter_record(&entry.record)),
+                    escape_csv(&entry.source),
+                    escape_csv(&entry.error),
+                    entry.attempts,
+                    entry.first_failed_at.to_rfc3339(),
+                    entry.last_failed_at.to_rfc3339()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Short description of a dead-lettered record, e.g. `node Person (alice)`
+fn dead_letter_record(record: &DeadLetterRecord) -> String {
+    match record {
+        DeadLetterRecord::Node(node) => match &node.id_alias {
+            Some(alias) => format!("node {} ({})", node.label, alias),
+            None => format!("node {}", node.label),
+        },
+        DeadLetterRecord::Edge(edge) => format!("edge {}", edge.kind),
+    }
+}
+
+/// Display a tenant's materialized snapshots
+pub fn display_snapshots(snapshots: &[SnapshotInfo], format: &OutputFormat) -> Result<(), CoreError> {
+    match format {
+        OutputFormat::Table => {
+            let table_data: Vec<SnapshotTableRow> = snapshots
+                .iter()
+                .map(|s| SnapshotTableRow {
+                    name: s.name.clone(),
+                    valid_at: s.valid_at.to_rfc3339(),
+                    snapshot_at: s.snapshot_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    nodes: s.node_count,
+                    edges: s.edge_count,
+                })
+                .collect();
+
+            let table = Table::new(table_data);
+            println!("{}", table);
+        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(snapshots, format)?,
+        OutputFormat::Csv => {
+            println!("name,valid_at,snapshot_at,nodes,edges");
+            for snapshot in snapshots {
+                println!(
+                    "{},{},{},{},{}",
+                    escape_csv(&snapshot.name),
+                    snapshot.valid_at.to_rfc3339(),
+                    snapshot.snapshot_at.to_rfc3339(),
+                    snapshot.node_count,
+                    snapshot.edge_count
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Display a tenant's archive segments
+pub fn display_archive_segments(segments: &[ArchiveSegment], format: &OutputFormat) -> Result<(), CoreError> {
+    match format {
+        OutputFormat::Table => {
+            let table_data: Vec<ArchiveSegmentTableRow> = segments
+                .iter()
+                .map(|s| ArchiveSegmentTableRow {
+                    id: s.id.to_string(),
+                    from: s.from.to_rfc3339(),
+                    to: s.to.to_rfc3339(),
+                    nodes: s.node_count,
+                    edges: s.edge_count,
+                    created_at: s.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                })
+                .collect();
+
+            let table = Table::new(table_data);
+            println!("{}", table);
+        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(segments, format)?,
+        OutputFormat::Csv => {
+            println!("id,from,to,nodes,edges,created_at");
+            for segment in segments {
+                println!(
+                    "{},{},{},{},{},{}",
+                    segment.id,
+                    segment.from.to_rfc3339(),
+                    segment.to.to_rfc3339(),
+                    segment.node_count,
+                    segment.edge_count,
+                    segment.created_at.to_rfc3339()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Display a tenant's API tokens. Secrets are never part of a listing.
+pub fn display_tokens(tokens: &[ApiToken], format: &OutputFormat) -> Result<(), CoreError> {
+    match format {
+        OutputFormat::Table => {
+            let table_data: Vec<TokenTableRow> = tokens
+                .iter()
+                .map(|t| TokenTableRow {
+                    id: t.id.to_string(),
+                    name: t.name.clone(),
+                    scope: t.scope.to_string(),
+                    prefix: t.prefix.clone(),
+                    status: token_status(t).to_string(),
+                    created_at: t.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    expires_at: t.expires_at.map_or_else(|| "never".to_string(), |e| e.format("%Y-%m-%d %H:%M:%S").to_string()),
+                })
+                .collect();
+
+            let table = Table::new(table_data);
+            println!("{}", table);
+        }
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(tokens, format)?,
+        OutputFormat::Csv => {
+            println!("id,name,scope,prefix,status,created_at,expires_at");
+            for token in tokens {
+                println!(
+                    "{},{},{},{},{},{},{}",
+                    token.id,
+                    escape_csv(&token.name),
+                    token.scope,
+                    token.prefix,
+                    token_status(token),
+                    token.created_at.to_rfc3339(),
+                    token.expires_at.map(|e| e.to_rfc3339()).unwrap_or_default()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a token is active, rotated but still in its overlap, or no longer accepted
+fn token_status(token: &ApiToken) -> &'static str {
+    if token.revoked_at.is_some() {
+        "revoked"
+    } else if !token.is_active_at(chrono::Utc::now()) {
+        "expired"
+    } else if token.replaced_by.is_some() {
+        "rotated"
+    } else {
+        "active"
+    }
+}
+
+/// Display configured contexts, marking the current one. Auth tokens are not shown.
+pub fn display_contexts(
+    contexts: &BTreeMap<String, ContextConfig>,
+    current: Option<&str>,
+    format: &OutputFormat,
+) -> Result<(), CoreError> {
+    match format {
+        OutputFormat::Table => {
+            let table_data: Vec<ContextTableRow> = contexts
+                .iter()
+                .map(|(name, context)| ContextTableRow {
+                    current: if current == Some(name.as_str()) { "*".to_string() } else { String::new() },
+                    name: name.clone(),
+                    endpoint: context.endpoint.clone().unwrap_or_else(|| "-".to_string()),
+                    tenant: context.default_tenant.clone().unwrap_or_else(|| "-".to_string()),
+                })
+                .collect();
+
+            let table = Table::new(table_data);
+            println!("{}", table);
+        }
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let entries: Vec<Value> = contexts
+                .iter()
+                .map(|(name, context)| serde_json::json!({
+                    "name": name,
+                    "current": current == Some(name.as_str()),
+                    "endpoint": context.endpoint,
+                    "default_tenant": context.default_tenant,
+                }))
+                .collect();
+            print_serialized(&entries, format)?;
+        }
+        OutputFormat::Csv => {
+            println!("name,current,endpoint,default_tenant");
+            for (name, context) in contexts {
+                println!(
+                    "{},{},{},{}",
+                    escape_csv(name),
+                    current == Some(name.as_str()),
+                    escape_csv(context.endpoint.as_deref().unwrap_or("")),
+                    escape_csv(context.default_tenant.as_deref().unwrap_or(""))
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Display the outcome of a command: `text` prints it for people with table
+/// output, other formats print `outcome` itself so scripts can read it
+pub fn display_outcome<T: Serialize>(outcome: &T, format: &OutputFormat, text: impl FnOnce()) -> Result<(), CoreError> {
+    match format {
+        OutputFormat::Table => text(),
+        OutputFormat::Json | OutputFormat::Yaml => print_serialized(outcome, format)?,
+        OutputFormat::Csv => {
+            let value = serde_json::to_value(outcome)
+                .map_err(|e| CoreError::Internal(format!("Failed to serialize outcome: {}", e)))?;
+            print!("{}", to_csv(&value));
+        }
+    }
+    Ok(())
+}
+
+/// Print a value as JSON or YAML
+fn print_serialized<T: Serialize + ?Sized>(value: &T, format: &OutputFormat) -> Result<(), CoreError> {
+    let text = match format {
+        OutputFormat::Yaml => serde_yaml::to_string(value)
+            .map_err(|e| CoreError::Internal(format!("Failed to serialize to YAML: {}", e)))?,
+        _ => serde_json::to_string_pretty(value)
+            .map_err(|e| CoreError::Internal(format!("Failed to serialize to JSON: {}", e)))?,
+    };
+    println!("{}", text.trim_end());
+    Ok(())
+}
+
+/// CSV of an object (one row) or a list of objects (one row each), with a
+/// header taken from the first object's fields
+fn to_csv(value: &Value) -> String {
+    let rows: Vec<&Value> = match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    let columns: Vec<String> = match rows.first() {
+        Some(Value::Object(fields)) => fields.keys().cloned().collect(),
+        Some(_) => vec!["value".to_string()],
+        None => return String::new(),
+    };
+
+    let mut csv = columns.join(",") + "\n";
+    for row in rows {
+        let cells: Vec<String> = match row {
+            Value::Object(fields) => columns
+                .iter()
+                .map(|column| escape_csv(&csv_cell(fields.get(column).unwrap_or(&Value::Null))))
+                .collect(),
+            other => vec![escape_csv(&csv_cell(other))],
+        };
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(_) | Value::Object(_) => format_properties(value),
+        other => other.to_string(),
+    }
+}
+
+/// Shorten text to a single line of at most `max_chars` characters
+fn truncate(text: &str, max_chars: usize) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= max_chars {
+        return line;
+    }
+    format!("{}...", line.chars().take(max_chars.saturating_sub(3)).collect::<String>())
+}
+
 /// Format status with color
 fn format_status(status: &telamentis_core::tenant::TenantStatus) -> String {
     match status {
@@ -244,6 +669,102 @@ struct TenantTableRow {
     created: String,
 }
 
+/// Table row for context display
+#[derive(Tabled)]
+struct ContextTableRow {
+    #[tabled(rename = "Current")]
+    current: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Endpoint")]
+    endpoint: String,
+    #[tabled(rename = "Tenant")]
+    tenant: String,
+}
+
+/// Table row for extraction example display
+#[derive(Tabled)]
+struct ExampleTableRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Input")]
+    input: String,
+    #[tabled(rename = "Nodes")]
+    nodes: usize,
+    #[tabled(rename = "Relations")]
+    relations: usize,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+/// Table row for dead letter display
+#[derive(Tabled)]
+struct DeadLetterTableRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Record")]
+    record: String,
+    #[tabled(rename = "Source")]
+    source: String,
+    #[tabled(rename = "Error")]
+    error: String,
+    #[tabled(rename = "Attempts")]
+    attempts: u32,
+    #[tabled(rename = "Last Failed")]
+    last_failed_at: String,
+}
+
+/// Table row for snapshot display
+#[derive(Tabled)]
+struct SnapshotTableRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Valid At")]
+    valid_at: String,
+    #[tabled(rename = "Materialized")]
+    snapshot_at: String,
+    #[tabled(rename = "Nodes")]
+    nodes: u64,
+    #[tabled(rename = "Edges")]
+    edges: u64,
+}
+
+/// Table row for archive segment display
+#[derive(Tabled)]
+struct ArchiveSegmentTableRow {
+    #[tabled(rename = "Segment")]
+    id: String,
+    #[tabled(rename = "From")]
+    from: String,
+    #[tabled(rename = "To")]
+    to: String,
+    #[tabled(rename = "Nodes")]
+    nodes: u64,
+    #[tabled(rename = "Edges")]
+    edges: u64,
+    #[tabled(rename = "Written")]
+    created_at: String,
+}
+
+/// Table row for API token display
+#[derive(Tabled)]
+struct TokenTableRow {
+    #[tabled(rename = "Token")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Scope")]
+    scope: String,
+    #[tabled(rename = "Prefix")]
+    prefix: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Created")]
+    created_at: String,
+    #[tabled(rename = "Expires")]
+    expires_at: String,
+}
+
 /// Table row for node display
 #[derive(Tabled)]
 struct NodeTableRow {
@@ -270,6 +791,26 @@ struct RelationshipTableRow {
     properties: String,
 }
 
+/// Table row for catalog entries
+#[derive(Tabled)]
+struct CatalogTableRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Count")]
+    count: u64,
+    #[tabled(rename = "Property keys")]
+    property_keys: String,
+}
+
+/// Table row for label and kind counts
+#[derive(Tabled)]
+struct CountTableRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Count")]
+    count: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,6 +826,13 @@ mod tests {
         assert!(formatted.contains("30"));
     }
 
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+    }
+
     #[test]
     fn test_escape_csv() {
         assert_eq!(escape_csv("simple"), "simple");
@@ -292,4 +840,14 @@ mod tests {
         assert_eq!(escape_csv("with\"quote"), "\"with\"\"quote\"");
         assert_eq!(escape_csv("with\nnewline"), "\"with\nnewline\"");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_to_csv() {
+        let outcome = serde_json::json!({"tenant": "acme", "rows": 3, "errors": null});
+        assert_eq!(to_csv(&outcome), "errors,rows,tenant\n,3,acme\n");
+
+        let list = serde_json::json!([{"id": "a", "tags": ["x", "y"]}, {"id": "b,c"}]);
+        assert_eq!(to_csv(&list), "id,tags\na,\"[\"\"x\"\",\"\"y\"\"]\"\n\"b,c\",\n");
+        assert_eq!(to_csv(&serde_json::json!([])), "");
+    }
+}
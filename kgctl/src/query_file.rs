@@ -0,0 +1,261 @@
+//! Query definitions kept in files
+//!
+//! A query file is YAML (or JSON) describing one structured `GraphQuery`
+//! with `${name}` placeholders, so that queries can be versioned and
+//! reviewed like code:
+//!
+//! ```yaml
+//! description: Who worked where at the start of a month
+//! params:
+//!   month:
+//!     description: Month, as YYYY-MM
+//!   kind:
+//!     default: WORKS_FOR
+//! query:
+//!   FindRelationships:
+//!     relationship_types: ["${kind}"]
+//!     valid_at: "${month}-01T00:00:00Z"
+//! ```
+//!
+//! Instead of `query`, a file may give `ref`, the path of another query
+//! file relative to this one, and bind some of its parameters in `params`.
+//! A string that is exactly one placeholder takes the parameter's value
+//! with its type (`"${limit}"` becomes a number for `limit=10`); elsewhere
+//! the value is spliced into the string.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use telamentis_core::errors::CoreError;
+use telamentis_core::types::GraphQuery;
+
+/// Most `ref`s followed before giving up on a file
+const MAX_REF_DEPTH: usize = 8;
+
+/// A parameter declared by a query file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QueryParam {
+    /// What the parameter means
+    pub description: Option<String>,
+    /// Value used when none is given; parameters without one are required
+    pub default: Option<Value>,
+}
+
+/// Parameters as written in a file: declarations, or plain values when
+/// binding the parameters of a referenced file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum ParamEntry {
+    Declared(QueryParam),
+    Value(Value),
+}
+
+/// Contents of a query file
+#[derive(Debug, Clone, Deserialize)]
+struct RawQueryFile {
+    description: Option<String>,
+    tenant: Option<String>,
+    snapshot: Option<String>,
+    #[serde(default)]
+    params: BTreeMap<String, ParamEntry>,
+    query: Option<Value>,
+    #[serde(rename = "ref")]
+    reference: Option<PathBuf>,
+}
+
+/// A query file with any `ref`s resolved
+#[derive(Debug, Clone)]
+pub struct QueryFile {
+    /// What the query is for
+    pub description: Option<String>,
+    /// Tenant to query when `--tenant` is not given
+    pub tenant: Option<String>,
+    /// Materialized snapshot to query instead of the live graph
+    pub snapshot: Option<String>,
+    /// Declared parameters, with defaults bound by referring files
+    pub params: BTreeMap<String, QueryParam>,
+    /// The query, with placeholders still in place
+    pub query: Value,
+}
+
+impl QueryFile {
+    /// Read a query file, following `ref`s
+    pub fn load(path: &Path) -> Result<Self, CoreError> {
+        Self::load_at_depth(path, 0)
+    }
+
+    fn load_at_depth(path: &Path, depth: usize) -> Result<Self, CoreError> {
+        if depth > MAX_REF_DEPTH {
+            return Err(CoreError::Configuration(format!(
+                "Query file {} follows more than {} refs", path.display(), MAX_REF_DEPTH
+            )));
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| CoreError::Configuration(format!("Failed to read query file {}: {}", path.display(), e)))?;
+        let raw: RawQueryFile = serde_yaml::from_str(&content)
+            .map_err(|e| CoreError::Configuration(format!("Invalid query file {}: {}", path.display(), e)))?;
+
+        match (raw.query, raw.reference) {
+            (Some(query), None) => {
+                let params = raw.params.into_iter()
+                    .map(|(name, entry)| match entry {
+                        ParamEntry::Declared(param) => (name, param),
+                        ParamEntry::Value(value) => (name, QueryParam { description: None, default: Some(value) }),
+                    })
+                    .collect();
+                Ok(Self { description: raw.description, tenant: raw.tenant, snapshot: raw.snapshot, params, query })
+            }
+            (None, Some(reference)) => {
+                let target = path.parent().unwrap_or(Path::new(".")).join(reference);
+                let mut file = Self::load_at_depth(&target, depth + 1)?;
+                for (name, entry) in raw.params {
+                    let param = file.params.get_mut(&name).ok_or_else(|| CoreError::Configuration(format!(
+                        "{} binds '{}', which {} does not declare", path.display(), name, target.display()
+                    )))?;
+                    param.default = Some(match entry {
+                        ParamEntry::Value(value) => value,
+                        ParamEntry::Declared(declared) => declared.default.unwrap_or(Value::Null),
+                    });
+                }
+                file.description = raw.description.or(file.description);
+                file.tenant = raw.tenant.or(file.tenant);
+                file.snapshot = raw.snapshot.or(file.snapshot);
+                Ok(file)
+            }
+            _ => Err(CoreError::Configuration(format!(
+                "Query file {} must give exactly one of 'query' and 'ref'", path.display()
+            ))),
+        }
+    }
+
+    /// The query with every placeholder replaced by a `--param` value or
+    /// the parameter's default
+    pub fn resolve(&self, args: &HashMap<String, Value>) -> Result<GraphQuery, CoreError> {
+        if let Some(unknown) = args.keys().find(|name| !self.params.contains_key(*name)) {
+            return Err(CoreError::Configuration(format!("Unknown parameter '{}'", unknown)));
+        }
+        let mut values = HashMap::new();
+        for (name, param) in &self.params {
+            let value = args.get(name).or(param.default.as_ref()).ok_or_else(|| {
+                CoreError::Configuration(format!("Missing parameter '{}'. Use --param {}=<value>", name, name))
+            })?;
+            values.insert(name.as_str(), value);
+        }
+
+        // Splicing text into a raw query would be an injection; raw queries
+        // take their parameters through `params` instead
+        if let Some(text) = self.query.pointer("/Raw/query").and_then(Value::as_str) {
+            if text.contains("${") {
+                return Err(CoreError::Configuration(
+                    "Raw query text cannot contain placeholders; pass them through the query's params".to_string()
+                ));
+            }
+        }
+
+        let query = substitute(&self.query, &values)?;
+        serde_json::from_value(query)
+            .map_err(|e| CoreError::Configuration(format!("Invalid query: {}", e)))
+    }
+}
+
+/// Replace placeholders throughout a value
+fn substitute(value: &Value, values: &HashMap<&str, &Value>) -> Result<Value, CoreError> {
+    Ok(match value {
+        Value::String(text) => substitute_str(text, values)?,
+        Value::Array(items) => Value::Array(
+            items.iter().map(|item| substitute(item, values)).collect::<Result<_, _>>()?
+        ),
+        Value::Object(fields) => Value::Object(
+            fields.iter()
+                .map(|(key, field)| Ok((key.clone(), substitute(field, values)?)))
+                .collect::<Result<_, CoreError>>()?
+        ),
+        other => other.clone(),
+    })
+}
+
+fn substitute_str(text: &str, values: &HashMap<&str, &Value>) -> Result<Value, CoreError> {
+    let lookup = |name: &str| values.get(name).copied().ok_or_else(|| {
+        CoreError::Configuration(format!("Placeholder '${{{}}}' names no declared parameter", name))
+    });
+
+    // A lone placeholder keeps the value's type
+    if let Some(name) = text.strip_prefix("${").and_then(|rest| rest.strip_suffix('}')) {
+        if !name.contains('}') {
+            return lookup(name).cloned();
+        }
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| {
+            CoreError::Configuration(format!("Unterminated placeholder in '{}'", text))
+        })?;
+        match lookup(&rest[start + 2..start + end])? {
+            Value::String(value) => result.push_str(value),
+            value => result.push_str(&value.to_string()),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    Ok(Value::String(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_with_ref() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("employment.yaml"), r#"
+description: Employment at the start of a month
+params:
+  month: {description: "Month, as YYYY-MM"}
+  kind: {default: WORKS_FOR}
+  limit: {default: 50}
+query:
+  FindRelationships:
+    from_node_id: null
+    to_node_id: null
+    relationship_types: ["${kind}"]
+    valid_at: "${month}-01T00:00:00Z"
+    limit: "${limit}"
+"#).unwrap();
+        std::fs::write(dir.path().join("may.yaml"), "ref: employment.yaml\nparams:\n  month: \"2024-05\"\n").unwrap();
+
+        let file = QueryFile::load(&dir.path().join("employment.yaml")).unwrap();
+        assert!(matches!(file.resolve(&HashMap::new()), Err(CoreError::Configuration(_))));
+
+        let file = QueryFile::load(&dir.path().join("may.yaml")).unwrap();
+        assert_eq!(file.description.as_deref(), Some("Employment at the start of a month"));
+        let args = HashMap::from([("limit".to_string(), json!(10))]);
+        let GraphQuery::FindRelationships { relationship_types, valid_at, limit, .. } = file.resolve(&args).unwrap() else {
+            panic!("expected a relationship query");
+        };
+        assert_eq!(relationship_types, vec!["WORKS_FOR".to_string()]);
+        assert_eq!(valid_at.unwrap().to_rfc3339(), "2024-05-01T00:00:00+00:00");
+        assert_eq!(limit, Some(10));
+
+        let unknown = HashMap::from([("year".to_string(), json!(2024))]);
+        assert!(file.resolve(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_raw_query_text_is_not_spliced() {
+        let file = QueryFile {
+            description: None,
+            tenant: None,
+            snapshot: None,
+            params: BTreeMap::from([("name".to_string(), QueryParam::default())]),
+            query: json!({"Raw": {"query": "MATCH (p {name: '${name}'}) RETURN p", "params": {}}}),
+        };
+        let args = HashMap::from([("name".to_string(), json!("x' OR 1=1"))]);
+        assert!(file.resolve(&args).is_err());
+    }
+}
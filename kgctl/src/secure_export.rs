@@ -0,0 +1,26 @@
+//! Signed and encrypted export files
+//!
+//! Keys and manifests are those of the server's exports, from
+//! `telamentis_core::secure_export`; kgctl writes each manifest next to the
+//! data file it describes.
+
+use std::path::{Path, PathBuf};
+
+pub use telamentis_core::secure_export::{ExportKey, ExportManifest};
+
+/// Path of the manifest that accompanies an export data file
+pub fn manifest_path(data_path: &Path) -> PathBuf {
+    let mut name = data_path.as_os_str().to_owned();
+    name.push(".manifest.json");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_path() {
+        assert_eq!(manifest_path(Path::new("out.jsonl")), PathBuf::from("out.jsonl.manifest.json"));
+    }
+}
@@ -0,0 +1,451 @@
+//! Sandboxed pipeline plugins for TelaMentis
+//!
+//! Operators can deploy custom validation and transformation logic without
+//! rebuilding the server: a plugin is a WebAssembly component implementing
+//! the `pipeline-plugin` world of `wit/pipeline-plugin.wit`, which mirrors
+//! `PipelinePlugin`. A [`WasmPluginHost`] compiles plugin components and
+//! registers them in a `PluginRegistry` under their configured names, so
+//! pipelines, including tenants' own `TenantPipelines`, enable them like any
+//! compiled-in plugin.
+//!
+//! Plugins run in a sandbox. The world has no imports, so a plugin sees only
+//! the request context it is called with; changes it makes to the request
+//! ID, tenant, method or path are ignored. Each call gets a fuel budget of
+//! WebAssembly instructions and each instance a memory limit, from the
+//! plugin's [`WasmLimits`]. A call that traps, for example by running out of
+//! fuel, halts its request with an error, and the plugin is instantiated and
+//! initialized afresh for the next call.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use telamentis_core::prelude::*;
+use tracing::{debug, info, warn};
+use wasmtime::component::{Component, Instance, InstancePre, Linker, Val};
+use wasmtime::{Engine, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Resources a plugin may use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WasmLimits {
+    /// WebAssembly instructions, roughly, that one call may execute
+    pub fuel_per_call: u64,
+    /// Linear memory an instance may grow to, in bytes
+    pub max_memory_bytes: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel_per_call: 10_000_000,
+            max_memory_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// A plugin component to load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmPluginSpec {
+    /// Name the plugin is registered under, and that pipelines refer to
+    pub name: String,
+    /// Path of the component, a `.wasm` file
+    pub path: PathBuf,
+    #[serde(default)]
+    pub limits: WasmLimits,
+}
+
+/// Plugin components of a deployment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WasmPluginsConfig {
+    pub plugins: Vec<WasmPluginSpec>,
+}
+
+/// The request context as plugins see it, in JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PluginContext {
+    request_id: Uuid,
+    tenant_id: Option<TenantId>,
+    method: String,
+    path: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    raw_request: Option<serde_json::Value>,
+    #[serde(default)]
+    core_operation_input: Option<serde_json::Value>,
+    #[serde(default)]
+    core_operation_output: Option<serde_json::Value>,
+    #[serde(default)]
+    final_response: Option<serde_json::Value>,
+    #[serde(default)]
+    attributes: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl PluginContext {
+    fn from_request(ctx: &RequestContext) -> Self {
+        Self {
+            request_id: ctx.request_id,
+            tenant_id: ctx.tenant_id.clone(),
+            method: ctx.method.clone(),
+            path: ctx.path.clone(),
+            headers: ctx.headers.clone(),
+            raw_request: ctx.raw_request.clone(),
+            core_operation_input: ctx.core_operation_input.clone(),
+            core_operation_output: ctx.core_operation_output.clone(),
+            final_response: ctx.final_response.clone(),
+            attributes: ctx.attributes.clone(),
+            error: ctx.error.clone(),
+        }
+    }
+
+    /// Copy what a plugin may change back into the request
+    fn apply(self, ctx: &mut RequestContext) {
+        ctx.headers = self.headers;
+        ctx.raw_request = self.raw_request;
+        ctx.core_operation_input = self.core_operation_input;
+        ctx.core_operation_output = self.core_operation_output;
+        ctx.final_response = self.final_response;
+        ctx.attributes = self.attributes;
+        ctx.error = self.error;
+    }
+}
+
+/// What a call of the plugin asked for
+enum Outcome {
+    Continue(String),
+    Halt,
+    HaltWithError(String),
+}
+
+/// Compiles plugin components and registers them
+pub struct WasmPluginHost {
+    engine: Engine,
+}
+
+impl WasmPluginHost {
+    pub fn new() -> Result<Self, PipelineError> {
+        let mut config = wasmtime::Config::new();
+        config.wasm_component_model(true);
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)
+            .map_err(|e| PipelineError::ConfigurationError(format!("Failed to set up the WebAssembly engine: {}", e)))?;
+        Ok(Self { engine })
+    }
+
+    /// Compile the component of a spec
+    pub fn load(&self, spec: &WasmPluginSpec) -> Result<WasmPluginModule, PipelineError> {
+        let bytes = std::fs::read(&spec.path)
+            .map_err(|e| PipelineError::PluginInitFailed(format!("{}: cannot read {}: {}", spec.name, spec.path.display(), e)))?;
+        self.load_bytes(&spec.name, &bytes, spec.limits.clone())
+    }
+
+    /// Compile a component from its bytes
+    pub fn load_bytes(&self, name: &str, bytes: &[u8], limits: WasmLimits) -> Result<WasmPluginModule, PipelineError> {
+        let component = Component::new(&self.engine, bytes)
+            .map_err(|e| PipelineError::PluginInitFailed(format!("{}: invalid component: {}", name, e)))?;
+        // An empty linker rejects components that import anything
+        let pre = Linker::new(&self.engine).instantiate_pre(&component)
+            .map_err(|e| PipelineError::PluginInitFailed(format!("{}: plugins cannot have imports: {}", name, e)))?;
+
+        info!("Loaded WebAssembly plugin {}", name);
+        Ok(WasmPluginModule {
+            // Plugin names are static; each loaded plugin leaks its name once
+            name: Box::leak(name.to_string().into_boxed_str()),
+            engine: self.engine.clone(),
+            pre,
+            limits,
+        })
+    }
+
+    /// Load every configured plugin into `registry`
+    pub fn register_all(&self, registry: &mut PluginRegistry, config: &WasmPluginsConfig) -> Result<(), PipelineError> {
+        for spec in &config.plugins {
+            self.load(spec)?.register(registry);
+        }
+        Ok(())
+    }
+}
+
+/// A compiled plugin component
+#[derive(Clone)]
+pub struct WasmPluginModule {
+    name: &'static str,
+    engine: Engine,
+    pre: InstancePre<StoreLimits>,
+    limits: WasmLimits,
+}
+
+impl WasmPluginModule {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// A plugin instance, instantiated when initialized
+    pub fn plugin(&self) -> WasmPlugin {
+        WasmPlugin {
+            module: self.clone(),
+            config: String::new(),
+            running: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Register the plugin under its name
+    pub fn register(&self, registry: &mut PluginRegistry) {
+        let module = self.clone();
+        registry.register(self.name, move || Box::new(module.plugin()));
+    }
+
+    /// Instantiate the component and initialize it with `config`
+    fn instantiate(&self, config: &str) -> Result<Running, String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.max_memory_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        let instance = self.pre.instantiate(&mut store).map_err(|e| e.to_string())?;
+
+        let mut running = Running { store, instance, fuel_per_call: self.limits.fuel_per_call };
+        match running.invoke("init", &[Val::String(config.to_string())])? {
+            Val::Result(Ok(_)) => Ok(running),
+            Val::Result(Err(Some(message))) => match *message {
+                Val::String(message) => Err(message),
+                other => Err(format!("unexpected init error {:?}", other)),
+            },
+            other => Err(format!("init returned {:?} instead of a result", other)),
+        }
+    }
+}
+
+/// An initialized plugin instance
+struct Running {
+    store: Store<StoreLimits>,
+    instance: Instance,
+    fuel_per_call: u64,
+}
+
+impl Running {
+    /// Call an export with a fresh fuel budget, returning its result if it
+    /// has one
+    fn invoke(&mut self, export: &str, params: &[Val]) -> Result<Val, String> {
+        let func = self.instance.get_func(&mut self.store, export)
+            .ok_or_else(|| format!("component does not export '{}'", export))?;
+        self.store.set_fuel(self.fuel_per_call).map_err(|e| e.to_string())?;
+
+        let mut results = vec![Val::Bool(false); func.results(&self.store).len()];
+        func.call(&mut self.store, params, &mut results).map_err(|e| format!("{} trapped: {:#}", export, e))?;
+        func.post_return(&mut self.store).map_err(|e| e.to_string())?;
+        Ok(results.pop().unwrap_or(Val::Bool(true)))
+    }
+
+    fn call(&mut self, context: String) -> Result<Outcome, String> {
+        match self.invoke("call", &[Val::String(context)])? {
+            Val::Variant(case, payload) => match (case.as_str(), payload.map(|payload| *payload)) {
+                ("continue", Some(Val::String(context))) => Ok(Outcome::Continue(context)),
+                ("halt", None) => Ok(Outcome::Halt),
+                ("halt-with-error", Some(Val::String(message))) => Ok(Outcome::HaltWithError(message)),
+                (case, _) => Err(format!("call returned unknown outcome '{}'", case)),
+            },
+            other => Err(format!("call returned {:?} instead of an outcome", other)),
+        }
+    }
+}
+
+/// A pipeline plugin running in a WebAssembly component
+pub struct WasmPlugin {
+    module: WasmPluginModule,
+    config: String,
+    /// The instance, until a call traps
+    running: Arc<Mutex<Option<Running>>>,
+}
+
+impl WasmPlugin {
+    fn failed(&self, message: String) -> PluginOutcome {
+        warn!("WebAssembly plugin {} failed: {}", self.module.name, message);
+        PluginOutcome::HaltWithError(Box::new(PipelineError::PluginExecutionFailed(format!("{}: {}", self.module.name, message))))
+    }
+}
+
+#[async_trait]
+impl PipelinePlugin for WasmPlugin {
+    fn name(&self) -> &'static str {
+        self.module.name
+    }
+
+    async fn init(&mut self, config: PluginConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.config = serde_json::to_string(&config.config)?;
+        let running = self.module.instantiate(&self.config)
+            .map_err(|e| PipelineError::PluginInitFailed(format!("{}: {}", self.module.name, e)))?;
+        *self.running.lock().unwrap() = Some(running);
+        Ok(())
+    }
+
+    async fn call(&self, ctx: &mut RequestContext) -> PluginOutcome {
+        let context = match serde_json::to_string(&PluginContext::from_request(ctx)) {
+            Ok(context) => context,
+            Err(e) => return self.failed(e.to_string()),
+        };
+
+        let (module, config, running) = (self.module.clone(), self.config.clone(), self.running.clone());
+        let called = tokio::task::spawn_blocking(move || {
+            let mut running = running.lock().unwrap();
+            if running.is_none() {
+                debug!("Instantiating WebAssembly plugin {} again", module.name);
+                *running = Some(module.instantiate(&config)?);
+            }
+            let result = running.as_mut().unwrap().call(context);
+            if result.is_err() {
+                // A trapped instance cannot be entered again
+                *running = None;
+            }
+            result
+        }).await;
+
+        match called {
+            Ok(Ok(Outcome::Continue(context))) => match serde_json::from_str::<PluginContext>(&context) {
+                Ok(context) => {
+                    context.apply(ctx);
+                    PluginOutcome::Continue
+                }
+                Err(e) => self.failed(format!("returned an invalid context: {}", e)),
+            },
+            Ok(Ok(Outcome::Halt)) => PluginOutcome::Halt,
+            Ok(Ok(Outcome::HaltWithError(message))) => {
+                PluginOutcome::HaltWithError(Box::new(PipelineError::PipelineHalted(format!("{}: {}", self.module.name, message))))
+            }
+            Ok(Err(message)) => self.failed(message),
+            Err(e) => self.failed(e.to_string()),
+        }
+    }
+
+    async fn teardown(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let running = self.running.lock().unwrap().take();
+        if let Some(mut running) = running {
+            running.invoke("teardown", &[])
+                .map_err(|e| PipelineError::PluginExecutionFailed(format!("{}: {}", self.module.name, e)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A plugin component whose `call` runs `body` with the context at
+    /// (`$ptr`, `$len`), writing its outcome at offset 32. "blocked" is at
+    /// offset 64.
+    fn component(init_ok: bool, body: &str) -> String {
+        format!(r#"
+            (component
+                (core module $m
+                    (memory (export "memory") 1)
+                    (global $heap (mut i32) (i32.const 1024))
+                    (data (i32.const 64) "blocked")
+                    (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+                        (local $ptr i32)
+                        (local.set $ptr (i32.and (i32.add (global.get $heap) (i32.const 7)) (i32.const -8)))
+                        (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+                        (local.get $ptr))
+                    (func (export "init") (param i32 i32) (result i32)
+                        (i32.store8 (i32.const 16) (i32.const {init}))
+                        (i32.store (i32.const 20) (i32.const 64))
+                        (i32.store (i32.const 24) (i32.const 7))
+                        (i32.const 16))
+                    (func (export "call") (param $ptr i32) (param $len i32) (result i32)
+                        {body})
+                    (func (export "teardown")))
+                (core instance $i (instantiate $m))
+                (type $outcome' (variant (case "continue" string) (case "halt") (case "halt-with-error" string)))
+                (export $outcome "outcome" (type $outcome'))
+                (func (export "init") (param "config" string) (result (result (error string)))
+                    (canon lift (core func $i "init") (memory $i "memory") (realloc (func $i "cabi_realloc"))))
+                (func (export "call") (param "context" string) (result $outcome)
+                    (canon lift (core func $i "call") (memory $i "memory") (realloc (func $i "cabi_realloc"))))
+                (func (export "teardown")
+                    (canon lift (core func $i "teardown"))))
+        "#, init = if init_ok { 0 } else { 1 }, body = body)
+    }
+
+    const ECHO: &str = "
+        (i32.store8 (i32.const 32) (i32.const 0))
+        (i32.store (i32.const 36) (local.get $ptr))
+        (i32.store (i32.const 40) (local.get $len))
+        (i32.const 32)";
+
+    const BLOCK: &str = "
+        (i32.store8 (i32.const 32) (i32.const 2))
+        (i32.store (i32.const 36) (i32.const 64))
+        (i32.store (i32.const 40) (i32.const 7))
+        (i32.const 32)";
+
+    async fn plugin(wat: &str, limits: WasmLimits) -> Result<WasmPlugin, Box<dyn std::error::Error + Send + Sync>> {
+        let host = WasmPluginHost::new().unwrap();
+        let mut plugin = host.load_bytes("Test", wat.as_bytes(), limits)?.plugin();
+        plugin.init(PluginConfig::default()).await?;
+        Ok(plugin)
+    }
+
+    fn request() -> RequestContext {
+        let mut ctx = RequestContext::new("POST".to_string(), "/v1/graph/acme/nodes".to_string());
+        ctx.tenant_id = Some(TenantId::new("acme"));
+        ctx.set_attribute("source", serde_json::json!("crm"));
+        ctx
+    }
+
+    #[tokio::test]
+    async fn test_plugin_outcomes() {
+        let echo = plugin(&component(true, ECHO), WasmLimits::default()).await.unwrap();
+        let mut ctx = request();
+        assert!(matches!(echo.call(&mut ctx).await, PluginOutcome::Continue));
+        assert_eq!(ctx.attributes["source"], "crm");
+        assert_eq!(ctx.tenant_id, Some(TenantId::new("acme")));
+        echo.teardown().await.unwrap();
+
+        let block = plugin(&component(true, BLOCK), WasmLimits::default()).await.unwrap();
+        match block.call(&mut request()).await {
+            PluginOutcome::HaltWithError(e) => assert!(e.to_string().contains("blocked")),
+            other => panic!("expected an error, got {:?}", other),
+        }
+
+        let error = plugin(&component(false, ECHO), WasmLimits::default()).await.err().unwrap();
+        assert!(error.to_string().contains("blocked"));
+    }
+
+    #[tokio::test]
+    async fn test_fuel_limit() {
+        let spin = "(loop $spin (br $spin)) (i32.const 0)";
+        let limits = WasmLimits { fuel_per_call: 10_000, ..Default::default() };
+        let plugin = plugin(&component(true, spin), limits).await.unwrap();
+
+        // The trap halts the request; the next call gets a new instance
+        for _ in 0..2 {
+            assert!(matches!(plugin.call(&mut request()).await, PluginOutcome::HaltWithError(_)));
+        }
+    }
+
+    #[test]
+    fn test_rejects_imports() {
+        let host = WasmPluginHost::new().unwrap();
+        let importing = r#"(component (import "log" (func (param "message" string))))"#;
+        assert!(host.load_bytes("Importing", importing.as_bytes(), WasmLimits::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registers_plugins() {
+        let host = WasmPluginHost::new().unwrap();
+        let mut registry = PluginRegistry::new();
+        host.load_bytes("Echo", component(true, ECHO).as_bytes(), WasmLimits::default()).unwrap().register(&mut registry);
+
+        let config: PipelineConfig = serde_json::from_value(serde_json::json!({
+            "stages": {"pre-operation": [{"name": "Echo"}]}
+        })).unwrap();
+        let plugins = registry.build(&config).await.unwrap();
+        assert_eq!(plugins[&PipelineStage::PreOperation][0].name(), "Echo");
+    }
+}
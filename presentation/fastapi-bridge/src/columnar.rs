@@ -0,0 +1,519 @@
+//! Arrow IPC, Parquet, NDJSON and CSV encoding of graph exports
+//!
+//! A snapshot is exported one table at a time, nodes or edges, so that each
+//! response is a single file DataFrame libraries can load directly. Rows are
+//! encoded and sent in record batches of `BATCH_ROWS` (`TEXT_BATCH_ROWS` for
+//! NDJSON and CSV), so the encoded file is never held in memory as a whole,
+//! and encoding stops when the client disconnects. IDs and labels are strings,
+//! properties are JSON text and times are UTC timestamps in microseconds, as
+//! in the archive; NDJSON and CSV give times in RFC 3339, and NDJSON gives
+//! properties as objects.
+
+use arrow_array::{Array, ArrayRef, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use telamentis_core::prelude::*;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::warn;
+
+/// Rows per record batch, and per Parquet row group
+pub const BATCH_ROWS: usize = 65_536;
+
+/// Rows per chunk of an NDJSON or CSV export, which clients can read as it arrives
+pub const TEXT_BATCH_ROWS: usize = 1_024;
+
+/// Response header carrying the transaction time of the exported snapshot
+pub const SNAPSHOT_AT_HEADER: &str = "x-snapshot-at";
+
+/// Response header carrying the number of rows in the exported table
+pub const ROW_COUNT_HEADER: &str = "x-row-count";
+
+/// Response header carrying the signed manifest of an export, as JSON
+pub const EXPORT_MANIFEST_HEADER: &str = "x-export-manifest";
+
+/// Encoding of a snapshot export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    /// The whole snapshot as one JSON document
+    #[default]
+    Json,
+    /// Arrow IPC stream of one table
+    Arrow,
+    /// Parquet file of one table
+    Parquet,
+    /// RDF Turtle of the whole snapshot
+    Turtle,
+    /// RDF N-Triples of the whole snapshot
+    NTriples,
+    /// Newline-delimited JSON of one table, an object per row
+    Ndjson,
+    /// CSV of one table, with a header row
+    Csv,
+}
+
+impl SnapshotFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Json => "application/json",
+            SnapshotFormat::Arrow => "application/vnd.apache.arrow.stream",
+            SnapshotFormat::Parquet => "application/vnd.apache.parquet",
+            SnapshotFormat::Turtle => RdfSyntax::Turtle.content_type(),
+            SnapshotFormat::NTriples => RdfSyntax::NTriples.content_type(),
+            SnapshotFormat::Ndjson => "application/x-ndjson",
+            SnapshotFormat::Csv => "text/csv",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Json => "json",
+            SnapshotFormat::Arrow => "arrows",
+            SnapshotFormat::Parquet => "parquet",
+            SnapshotFormat::Turtle => RdfSyntax::Turtle.extension(),
+            SnapshotFormat::NTriples => RdfSyntax::NTriples.extension(),
+            SnapshotFormat::Ndjson => "ndjson",
+            SnapshotFormat::Csv => "csv",
+        }
+    }
+
+    /// Rows encoded and sent at a time
+    pub fn batch_rows(&self) -> usize {
+        match self {
+            SnapshotFormat::Ndjson | SnapshotFormat::Csv => TEXT_BATCH_ROWS,
+            _ => BATCH_ROWS,
+        }
+    }
+
+    /// RDF serialization of the format, if it is one
+    pub fn rdf_syntax(&self) -> Option<RdfSyntax> {
+        match self {
+            SnapshotFormat::Turtle => Some(RdfSyntax::Turtle),
+            SnapshotFormat::NTriples => Some(RdfSyntax::NTriples),
+            _ => None,
+        }
+    }
+}
+
+/// Table of a columnar export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportTable {
+    Nodes,
+    Edges,
+}
+
+impl ExportTable {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportTable::Nodes => "nodes",
+            ExportTable::Edges => "edges",
+        }
+    }
+
+    /// Rows the table has in a snapshot
+    pub fn rows(&self, snapshot: &GraphSnapshot) -> usize {
+        match self {
+            ExportTable::Nodes => snapshot.nodes.len(),
+            ExportTable::Edges => snapshot.edges.len(),
+        }
+    }
+}
+
+fn timestamp(name: &str, nullable: bool) -> Field {
+    Field::new(name, DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), nullable)
+}
+
+/// Schema of a table, with the snapshot it was read from as metadata
+fn schema(table: ExportTable, tenant: &TenantId, snapshot: &GraphSnapshot) -> SchemaRef {
+    let fields = match table {
+        ExportTable::Nodes => vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("label", DataType::Utf8, false),
+            Field::new("id_alias", DataType::Utf8, true),
+            Field::new("alias_namespace", DataType::Utf8, true),
+            Field::new("props", DataType::Utf8, false),
+        ],
+        ExportTable::Edges => vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("from_node_id", DataType::Utf8, false),
+            Field::new("to_node_id", DataType::Utf8, false),
+            Field::new("kind", DataType::Utf8, false),
+            timestamp("valid_from", false),
+            timestamp("valid_to", true),
+            timestamp("transaction_start_time", false),
+            timestamp("transaction_end_time", true),
+            timestamp("expires_at", true),
+            Field::new("props", DataType::Utf8, false),
+        ],
+    };
+
+    let mut metadata = HashMap::from([
+        ("tenant_id".to_string(), tenant.to_string()),
+        ("snapshot_at".to_string(), snapshot.snapshot_at.to_rfc3339()),
+    ]);
+    if let Some(valid_at) = snapshot.valid_at {
+        metadata.insert("valid_at".to_string(), valid_at.to_rfc3339());
+    }
+    Arc::new(Schema::new(fields).with_metadata(metadata))
+}
+
+fn strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+fn times(values: impl Iterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+    let micros = values.map(|t| t.map(|t| t.timestamp_micros())).collect::<TimestampMicrosecondArray>();
+    Arc::new(micros.with_timezone("UTC"))
+}
+
+fn json(value: &serde_json::Value) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("Failed to encode properties: {}", e))
+}
+
+fn node_columns(nodes: &[NodeRecord]) -> Result<Vec<ArrayRef>, String> {
+    let ids: Vec<String> = nodes.iter().map(|n| n.id.to_string()).collect();
+    let props = nodes.iter().map(|n| json(&n.node.props)).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(vec![
+        strings(ids.iter().map(|id| Some(id.as_str()))),
+        strings(nodes.iter().map(|n| Some(n.node.label.as_str()))),
+        strings(nodes.iter().map(|n| n.node.id_alias.as_deref())),
+        strings(nodes.iter().map(|n| n.node.alias_namespace.as_deref())),
+        strings(props.iter().map(|p| Some(p.as_str()))),
+    ])
+}
+
+fn edge_columns(edges: &[EdgeRecord]) -> Result<Vec<ArrayRef>, String> {
+    let ids: Vec<[String; 3]> = edges.iter()
+        .map(|e| [e.id.to_string(), e.edge.from_node_id.to_string(), e.edge.to_node_id.to_string()])
+        .collect();
+    let props = edges.iter().map(|e| json(&e.edge.props)).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(vec![
+        strings(ids.iter().map(|ids| Some(ids[0].as_str()))),
+        strings(ids.iter().map(|ids| Some(ids[1].as_str()))),
+        strings(ids.iter().map(|ids| Some(ids[2].as_str()))),
+        strings(edges.iter().map(|e| Some(e.edge.kind.as_str()))),
+        times(edges.iter().map(|e| Some(e.edge.valid_from))),
+        times(edges.iter().map(|e| e.edge.valid_to)),
+        times(edges.iter().map(|e| Some(e.edge.transaction_start_time))),
+        times(edges.iter().map(|e| e.edge.transaction_end_time)),
+        times(edges.iter().map(|e| e.edge.expires_at)),
+        strings(props.iter().map(|p| Some(p.as_str()))),
+    ])
+}
+
+/// Output buffer shared with a writer, drained after every batch
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Bytes {
+        Bytes::from(std::mem::take(&mut *self.0.lock().unwrap()))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum TableWriter {
+    Arrow(StreamWriter<SharedBuffer>),
+    Parquet(ArrowWriter<SharedBuffer>),
+    Ndjson(SharedBuffer),
+    Csv(csv::Writer<SharedBuffer>),
+}
+
+impl TableWriter {
+    fn new(format: SnapshotFormat, schema: SchemaRef, buffer: SharedBuffer) -> Result<Self, String> {
+        match format {
+            SnapshotFormat::Arrow => StreamWriter::try_new(buffer, &schema)
+                .map(TableWriter::Arrow)
+                .map_err(|e| format!("Failed to create Arrow writer: {}", e)),
+            SnapshotFormat::Parquet => {
+                let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+                ArrowWriter::try_new(buffer, schema, Some(properties))
+                    .map(TableWriter::Parquet)
+                    .map_err(|e| format!("Failed to create Parquet writer: {}", e))
+            }
+            SnapshotFormat::Ndjson => Ok(TableWriter::Ndjson(buffer)),
+            SnapshotFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(buffer);
+                writer.write_record(schema.fields().iter().map(|field| field.name()))
+                    .map_err(|e| format!("Failed to write CSV header: {}", e))?;
+                Ok(TableWriter::Csv(writer))
+            }
+            SnapshotFormat::Json | SnapshotFormat::Turtle | SnapshotFormat::NTriples => {
+                Err(format!("{:?} exports are not tabular", format))
+            }
+        }
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        match self {
+            TableWriter::Arrow(writer) => writer.write(batch)
+                .map_err(|e| format!("Failed to write Arrow batch: {}", e)),
+            // Each batch becomes a row group, so its bytes can be sent now
+            TableWriter::Parquet(writer) => writer.write(batch)
+                .and_then(|_| writer.flush())
+                .map_err(|e| format!("Failed to write Parquet row group: {}", e)),
+            TableWriter::Ndjson(buffer) => {
+                let fields = batch.schema().fields().clone();
+                for row in 0..batch.num_rows() {
+                    let object: serde_json::Map<_, _> = fields.iter().zip(batch.columns())
+                        .map(|(field, column)| {
+                            let value = match cell(column, row) {
+                                Some(text) if field.name() == "props" => serde_json::from_str(&text)
+                                    .map_err(|e| format!("Failed to decode properties: {}", e))?,
+                                Some(text) => serde_json::Value::String(text),
+                                None => serde_json::Value::Null,
+                            };
+                            Ok((field.name().clone(), value))
+                        })
+                        .collect::<Result<_, String>>()?;
+                    serde_json::to_writer(&mut *buffer, &object)
+                        .map_err(|e| format!("Failed to write NDJSON row: {}", e))?;
+                    buffer.write_all(b"\n").map_err(|e| format!("Failed to write NDJSON row: {}", e))?;
+                }
+                Ok(())
+            }
+            TableWriter::Csv(writer) => {
+                for row in 0..batch.num_rows() {
+                    writer.write_record(batch.columns().iter().map(|column| cell(column, row).unwrap_or_default()))
+                        .map_err(|e| format!("Failed to write CSV row: {}", e))?;
+                }
+                writer.flush().map_err(|e| format!("Failed to write CSV row: {}", e))
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            TableWriter::Arrow(mut writer) => writer.finish()
+                .map_err(|e| format!("Failed to finish Arrow stream: {}", e)),
+            TableWriter::Parquet(writer) => writer.close()
+                .map(|_| ())
+                .map_err(|e| format!("Failed to finish Parquet file: {}", e)),
+            TableWriter::Ndjson(_) => Ok(()),
+            TableWriter::Csv(mut writer) => writer.flush()
+                .map_err(|e| format!("Failed to finish CSV file: {}", e)),
+        }
+    }
+}
+
+/// A value of a string or timestamp column as text; times in RFC 3339
+fn cell(column: &ArrayRef, row: usize) -> Option<String> {
+    if column.is_null(row) {
+        return None;
+    }
+    if let Some(strings) = column.as_any().downcast_ref::<StringArray>() {
+        return Some(strings.value(row).to_string());
+    }
+    column.as_any().downcast_ref::<TimestampMicrosecondArray>()
+        .and_then(|times| DateTime::<Utc>::from_timestamp_micros(times.value(row)))
+        .map(|time| time.to_rfc3339())
+}
+
+/// Encode a table of a snapshot, passing the output to `send` a batch at a
+/// time. Stops early, without error, if `send` returns false.
+pub fn encode(
+    snapshot: &GraphSnapshot,
+    tenant: &TenantId,
+    table: ExportTable,
+    format: SnapshotFormat,
+    batch_rows: usize,
+    mut send: impl FnMut(Bytes) -> bool,
+) -> Result<(), String> {
+    let schema = schema(table, tenant, snapshot);
+    let buffer = SharedBuffer::default();
+    let mut writer = TableWriter::new(format, schema.clone(), buffer.clone())?;
+
+    let rows = table.rows(snapshot);
+    for start in (0..rows).step_by(batch_rows.max(1)) {
+        let end = (start + batch_rows.max(1)).min(rows);
+        let columns = match table {
+            ExportTable::Nodes => node_columns(&snapshot.nodes[start..end])?,
+            ExportTable::Edges => edge_columns(&snapshot.edges[start..end])?,
+        };
+        let batch = RecordBatch::try_new(schema.clone(), columns)
+            .map_err(|e| format!("Failed to build record batch: {}", e))?;
+        writer.write(&batch)?;
+        if !send(buffer.take()) {
+            return Ok(());
+        }
+    }
+
+    writer.finish()?;
+    send(buffer.take());
+    Ok(())
+}
+
+/// Encode a table of a snapshot on a blocking thread, as a stream of body
+/// chunks; encoding stops once the stream is dropped, e.g. because the
+/// client disconnected
+pub fn encode_stream(
+    snapshot: GraphSnapshot,
+    tenant: TenantId,
+    table: ExportTable,
+    format: SnapshotFormat,
+) -> ReceiverStream<io::Result<Bytes>> {
+    let (tx, rx) = mpsc::channel(2);
+
+    tokio::task::spawn_blocking(move || {
+        let result = encode(&snapshot, &tenant, table, format, format.batch_rows(), |chunk| {
+            chunk.is_empty() || tx.blocking_send(Ok(chunk)).is_ok()
+        });
+        if let Err(e) = result {
+            warn!("Export of {} for tenant {} failed: {}", table.name(), tenant, e);
+            let _ = tx.blocking_send(Err(io::Error::other(e)));
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+/// Encode a table of a snapshot whole on a blocking thread, for exports
+/// that are hashed and signed before they are sent
+pub async fn encode_bytes(
+    snapshot: GraphSnapshot,
+    tenant: TenantId,
+    table: ExportTable,
+    format: SnapshotFormat,
+) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut data = Vec::new();
+        encode(&snapshot, &tenant, table, format, format.batch_rows(), |chunk| {
+            data.extend_from_slice(&chunk);
+            true
+        })?;
+        Ok(data)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use arrow_ipc::reader::StreamReader;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use uuid::Uuid;
+
+    fn snapshot() -> GraphSnapshot {
+        let nodes: Vec<NodeRecord> = (0..5)
+            .map(|i| NodeRecord { id: Uuid::new_v4(), node: Node::new("Person").with_id_alias(format!("p{}", i)) })
+            .collect();
+        let mut edge = TimeEdge::new(nodes[0].id, nodes[1].id, "KNOWS", Utc::now(), serde_json::json!({"since": 2020}));
+        edge.valid_to = Some(Utc::now());
+
+        GraphSnapshot {
+            snapshot_at: Utc::now(),
+            valid_at: None,
+            nodes,
+            edges: vec![EdgeRecord { id: Uuid::new_v4(), edge }],
+        }
+    }
+
+    fn encoded(snapshot: &GraphSnapshot, table: ExportTable, format: SnapshotFormat) -> (Vec<u8>, usize) {
+        let mut output = Vec::new();
+        let mut chunks = 0;
+        encode(snapshot, &TenantId::new("acme"), table, format, 2, |chunk| {
+            output.extend_from_slice(&chunk);
+            chunks += 1;
+            true
+        }).unwrap();
+        (output, chunks)
+    }
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let snapshot = snapshot();
+        let (output, chunks) = encoded(&snapshot, ExportTable::Nodes, SnapshotFormat::Parquet);
+        // Three batches of at most two rows, then the footer
+        assert_eq!(chunks, 4);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(output)).unwrap();
+        assert_eq!(reader.schema().metadata()["tenant_id"], "acme");
+        let batches = reader.build().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 5);
+    }
+
+    #[test]
+    fn test_arrow_round_trip() {
+        let snapshot = snapshot();
+        let (output, _) = encoded(&snapshot, ExportTable::Edges, SnapshotFormat::Arrow);
+
+        let reader = StreamReader::try_new(io::Cursor::new(output), None).unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 1);
+        let kinds = batch.column_by_name("kind").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(kinds.value(0), "KNOWS");
+        let valid_to = batch.column_by_name("valid_to").unwrap();
+        assert_eq!(valid_to.null_count(), 0);
+    }
+
+    #[test]
+    fn test_text_formats() {
+        let snapshot = snapshot();
+        let (output, _) = encoded(&snapshot, ExportTable::Edges, SnapshotFormat::Ndjson);
+        let rows: Vec<serde_json::Value> = String::from_utf8(output).unwrap().lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["kind"], "KNOWS");
+        assert_eq!(rows[0]["props"]["since"], 2020);
+        assert!(rows[0]["transaction_end_time"].is_null());
+        assert!(rows[0]["valid_from"].as_str().unwrap().parse::<DateTime<Utc>>().is_ok());
+
+        let (output, chunks) = encoded(&snapshot, ExportTable::Nodes, SnapshotFormat::Csv);
+        // Three batches of at most two rows, the header with the first, then
+        // an empty chunk as there is no footer
+        assert_eq!(chunks, 4);
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        assert_eq!(reader.headers().unwrap(), vec!["id", "label", "id_alias", "alias_namespace", "props"]);
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 5);
+        assert_eq!(&records[4][2], "p4");
+    }
+
+    #[test]
+    fn test_stops_when_receiver_is_gone() {
+        let snapshot = snapshot();
+        let mut chunks = 0;
+        encode(&snapshot, &TenantId::new("acme"), ExportTable::Nodes, SnapshotFormat::Ndjson, 1, |_| {
+            chunks += 1;
+            false
+        }).unwrap();
+        assert_eq!(chunks, 1);
+    }
+
+    #[tokio::test]
+    async fn test_encode_bytes() {
+        let snapshot = snapshot();
+        let (expected, _) = encoded(&snapshot, ExportTable::Nodes, SnapshotFormat::Csv);
+        let data = encode_bytes(snapshot, TenantId::new("acme"), ExportTable::Nodes, SnapshotFormat::Csv).await.unwrap();
+        assert_eq!(data, expected);
+    }
+}
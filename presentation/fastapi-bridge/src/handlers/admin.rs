@@ -0,0 +1,228 @@
+//! Admin API handlers: plugin and connector lifecycle, caches, maintenance
+//! jobs, draining and, in dev mode, seeding fixtures
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+use tracing::info;
+
+/// Longest a drain waits for requests in flight by default, in seconds
+const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
+
+/// Request to enable or disable a plugin
+#[derive(Debug, Deserialize)]
+pub struct SetPluginEnabledRequest {
+    pub enabled: bool,
+}
+
+/// Request to switch a tenant's default LLM provider
+#[derive(Debug, Deserialize)]
+pub struct SetDefaultProviderRequest {
+    /// Provider to use; the registry's default if omitted
+    pub provider: Option<String>,
+}
+
+/// Request naming the tenants to act on
+#[derive(Debug, Default, Deserialize)]
+pub struct TenantsRequest {
+    /// Tenant to act on; every tenant in the store if omitted
+    pub tenant_id: Option<String>,
+}
+
+/// Tenants whose query caches were flushed
+#[derive(Debug, Serialize)]
+pub struct FlushCachesResponse {
+    pub tenants: Vec<TenantId>,
+}
+
+/// Request to drain the server
+#[derive(Debug, Default, Deserialize)]
+pub struct DrainRequest {
+    /// Longest to wait for requests in flight, in seconds
+    pub timeout_secs: Option<u64>,
+}
+
+/// Request to seed the graph from a fixture
+#[derive(Debug, Deserialize)]
+pub struct SeedRequest {
+    pub fixture: Fixture,
+    /// Clear the fixture's tenants before seeding them
+    #[serde(default)]
+    pub reset: bool,
+}
+
+/// Whether the server drains, its requests in flight, jobs and providers
+pub async fn status(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<AdminStatus>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let admin = admin_controls(&state)?;
+    Ok(Json(ApiResponse::success(admin.status())))
+}
+
+/// Plugins of the default pipeline and whether they run
+pub async fn list_plugins(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<PluginState>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    admin_controls(&state)?;
+    Ok(Json(ApiResponse::success(state.pipeline.plugin_states())))
+}
+
+/// Enable or disable a plugin in every pipeline
+pub async fn set_plugin_enabled(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<SetPluginEnabledRequest>,
+) -> Result<Json<ApiResponse<Vec<PluginState>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    admin_controls(&state)?;
+
+    match state.pipeline.set_plugin_enabled(&name, request.enabled) {
+        Ok(()) => Ok(Json(ApiResponse::success(state.pipeline.plugin_states()))),
+        Err(e @ PipelineError::PluginNotFound(_)) => Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(e.to_string())))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Switch a tenant's default LLM provider
+pub async fn set_default_provider(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<SetDefaultProviderRequest>,
+) -> Result<Json<ApiResponse<Vec<ProviderStatus>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let admin = admin_controls(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    admin.set_default_provider(&tenant, request.provider.as_deref()).map_err(handle_core_error)?;
+    Ok(Json(ApiResponse::success(state.core_service.llm_providers(Some(&tenant)).await)))
+}
+
+/// Drop cached query results of a tenant, or of every tenant
+pub async fn flush_caches(
+    State(state): State<AppState>,
+    request: Option<Json<TenantsRequest>>,
+) -> Result<Json<ApiResponse<FlushCachesResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let admin = admin_controls(&state)?;
+    let tenants = target_tenants(&state, request).await?;
+
+    if !admin.flush_caches(&tenants) {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("No query cache is configured"))));
+    }
+    Ok(Json(ApiResponse::success(FlushCachesResponse { tenants })))
+}
+
+/// Run a maintenance job over a tenant, or over every tenant
+pub async fn run_job(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    request: Option<Json<TenantsRequest>>,
+) -> Result<Json<ApiResponse<Vec<JobReport>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let admin = admin_controls(&state)?;
+    if admin.job(&name).is_none() {
+        return Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(format!("Unknown maintenance job '{}'", name)))));
+    }
+    let tenants = target_tenants(&state, request).await?;
+    info!("Running maintenance job {} over {} tenant(s)", name, tenants.len());
+
+    match admin.run_job(&name, &tenants).await {
+        Ok(reports) => Ok(Json(ApiResponse::success(reports))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Progress of a maintenance job over the tenants it ran for, such as a
+/// backfill stopped at its checkpoint
+pub async fn job_progress(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<Vec<JobProgress>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let admin = admin_controls(&state)?;
+    if admin.job(&name).is_none() {
+        return Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(format!("Unknown maintenance job '{}'", name)))));
+    }
+
+    match admin.job_progress(&name) {
+        Ok(progress) => Ok(Json(ApiResponse::success(progress))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Refuse new requests and wait for those in flight, before shutdown
+pub async fn drain(
+    State(state): State<AppState>,
+    request: Option<Json<DrainRequest>>,
+) -> Result<Json<ApiResponse<DrainReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let admin = admin_controls(&state)?;
+    let timeout_secs = request.and_then(|Json(request)| request.timeout_secs).unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECS);
+
+    let report = admin.drain_state().drain(Duration::from_secs(timeout_secs)).await;
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// Accept requests again after a drain
+pub async fn resume(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<AdminStatus>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let admin = admin_controls(&state)?;
+    admin.drain_state().resume();
+    Ok(Json(ApiResponse::success(admin.status())))
+}
+
+/// Seed the graph from a fixture; served only in dev mode
+pub async fn seed(
+    State(state): State<AppState>,
+    Json(request): Json<SeedRequest>,
+) -> Result<Json<ApiResponse<SeedReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    admin_controls(&state)?;
+    require_dev_mode(&state)?;
+    info!("Seeding {} tenant(s) from a fixture (reset: {})", request.fixture.tenants.len(), request.reset);
+
+    match state.fixtures.apply(&request.fixture, request.reset).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Clear the tenants of a fixture; served only in dev mode
+pub async fn teardown_seed(
+    State(state): State<AppState>,
+    Json(fixture): Json<Fixture>,
+) -> Result<Json<ApiResponse<SeedReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    admin_controls(&state)?;
+    require_dev_mode(&state)?;
+    info!("Tearing down the fixture of {} tenant(s)", fixture.tenants.len());
+
+    match state.fixtures.teardown(&fixture).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+fn require_dev_mode(state: &AppState) -> Result<(), (StatusCode, Json<ApiResponse<()>>)> {
+    if state.config.dev_mode {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, Json(ApiResponse::<()>::error("Seeding is only served in dev mode"))))
+    }
+}
+
+/// The tenant a request names, or every tenant in the store
+async fn target_tenants(
+    state: &AppState,
+    request: Option<Json<TenantsRequest>>,
+) -> Result<Vec<TenantId>, (StatusCode, Json<ApiResponse<()>>)> {
+    match request.and_then(|Json(request)| request.tenant_id) {
+        Some(tenant_id) => Ok(vec![TenantId::new(tenant_id)]),
+        None => state.core_service.list_tenants().await.map_err(|e| handle_core_error(e.into())),
+    }
+}
+
+fn admin_controls(state: &AppState) -> Result<Arc<AdminControls>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.admin.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("The admin API is not enabled"))))
+}
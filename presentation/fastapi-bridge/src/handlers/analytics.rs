@@ -0,0 +1,57 @@
+//! Read-only SQL analytics handlers
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+use tracing::{debug, info};
+
+/// Request to run an analytics query
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQueryRequest {
+    /// A single read-only SQL statement over the `nodes` and `edges` tables
+    pub sql: String,
+    /// Most rows to return, up to the configured maximum
+    pub limit: Option<usize>,
+}
+
+/// Run a read-only SQL query over the tenant's analytics tables
+pub async fn run_query(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<AnalyticsQueryRequest>,
+) -> Result<Json<ApiResponse<AnalyticsResult>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let analytics = analytics_job(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    debug!("Running analytics query for tenant {}: {}", tenant, request.sql);
+
+    match analytics.query(&tenant, &request.sql, request.limit).await {
+        Ok(result) => Ok(Json(ApiResponse::success(result))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Reload the tenant's analytics tables from the graph store now
+pub async fn sync_tables(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<AnalyticsSync>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let analytics = analytics_job(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    info!("Syncing analytics tables for tenant {}", tenant);
+
+    match analytics.sync(&tenant).await {
+        Ok(sync) => Ok(Json(ApiResponse::success(sync))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+fn analytics_job(state: &AppState) -> Result<Arc<AnalyticsJob>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.analytics.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("Analytics is not configured"))))
+}
@@ -0,0 +1,95 @@
+//! Archival handlers for closed graph history
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+use tracing::info;
+
+/// Request to archive history that ended before a cutoff
+#[derive(Debug, Deserialize)]
+pub struct RunArchiveRequest {
+    pub before: DateTime<Utc>,
+    /// Remove the archived history from the store once it is written
+    #[serde(default)]
+    pub purge: bool,
+}
+
+/// Segment written by an archive run, if there was history to archive
+#[derive(Debug, Serialize)]
+pub struct RunArchiveResponse {
+    pub segment: Option<ArchiveSegment>,
+}
+
+/// Request to rehydrate history that ended within `[from, to)`
+#[derive(Debug, Deserialize)]
+pub struct RestoreArchiveRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// List the tenant's archive segments
+pub async fn get_manifest(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<ArchiveManifest>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let archive = archive_job(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    match archive.manifest(&tenant).await {
+        Ok(manifest) => Ok(Json(ApiResponse::success(manifest))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Archive history that ended before a cutoff, optionally purging it
+pub async fn run_archive(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<RunArchiveRequest>,
+) -> Result<Json<ApiResponse<RunArchiveResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let archive = archive_job(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    info!("Archiving history before {} for tenant {} (purge: {})", request.before, tenant, request.purge);
+
+    let result = if request.purge {
+        archive.retain(&tenant, request.before).await
+    } else {
+        archive.export(&tenant, request.before).await
+    };
+
+    match result {
+        Ok(segment) => Ok(Json(ApiResponse::success(RunArchiveResponse { segment }))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Write archived history back into the store
+pub async fn restore_archive(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<RestoreArchiveRequest>,
+) -> Result<Json<ApiResponse<RestoreReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let archive = archive_job(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    if request.from >= request.to {
+        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("'from' must be before 'to'"))));
+    }
+
+    info!("Restoring archived history from {} to {} for tenant {}", request.from, request.to, tenant);
+    match archive.restore(&tenant, request.from, request.to).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+fn archive_job(state: &AppState) -> Result<Arc<ArchiveJob>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.archive.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("No archive is configured"))))
+}
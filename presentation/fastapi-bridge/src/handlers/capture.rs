@@ -0,0 +1,94 @@
+//! Request capture handlers
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+use tracing::info;
+
+/// Request to capture a share of a tenant's requests
+#[derive(Debug, Deserialize)]
+pub struct EnableCaptureRequest {
+    /// Share of requests captured, from 0 to 1
+    pub sample_rate: f64,
+}
+
+/// A tenant's capture setting and captured requests
+#[derive(Debug, Serialize)]
+pub struct ListCapturesResponse {
+    pub sample_rate: Option<f64>,
+    pub captures: Vec<CapturedRequest>,
+}
+
+/// List a tenant's captured requests, oldest first
+pub async fn list_captures(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<ListCapturesResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let capture = request_capture(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    match capture.list(&tenant).await {
+        Ok(captures) => Ok(Json(ApiResponse::success(ListCapturesResponse {
+            sample_rate: capture.sample_rate(&tenant),
+            captures,
+        }))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Get a captured request for replay
+pub async fn get_capture(
+    State(state): State<AppState>,
+    Path((tenant_id, request_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<CapturedRequest>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let capture = request_capture(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    let request_id = Uuid::parse_str(&request_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid request ID format"))))?;
+
+    match capture.get(&tenant, request_id).await {
+        Ok(Some(captured)) => Ok(Json(ApiResponse::success(captured))),
+        Ok(None) => Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error("Captured request not found")))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Start capturing a share of a tenant's requests
+pub async fn enable_capture(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<EnableCaptureRequest>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let capture = request_capture(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    capture.enable(&tenant, request.sample_rate).map_err(handle_core_error)?;
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Stop capturing a tenant's requests; captured requests are kept
+pub async fn disable_capture(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let capture = request_capture(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    if !capture.disable(&tenant) {
+        return Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error("Capture is not enabled for this tenant"))));
+    }
+
+    info!("Stopped capturing requests for tenant {}", tenant);
+    Ok(Json(ApiResponse::success(())))
+}
+
+fn request_capture(state: &AppState) -> Result<Arc<RequestCapture>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.capture.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("Request capture is not configured"))))
+}
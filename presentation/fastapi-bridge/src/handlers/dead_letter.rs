@@ -0,0 +1,110 @@
+//! Dead-letter queue handlers
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{ApiResponse, AppState};
+use tracing::warn;
+
+/// Request to retry dead letters
+#[derive(Debug, Default, Deserialize)]
+pub struct RetryDeadLettersRequest {
+    /// Entries to retry; all of the tenant's if omitted
+    pub ids: Option<Vec<Uuid>>,
+}
+
+/// List a tenant's failed ingestion records, oldest first
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<DeadLetter>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let queue = dead_letter_queue(&state)?;
+    Ok(Json(ApiResponse::success(queue.list(&TenantId::new(tenant_id)))))
+}
+
+/// Get a failed ingestion record
+pub async fn get_dead_letter(
+    State(state): State<AppState>,
+    Path((tenant_id, entry_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<DeadLetter>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let queue = dead_letter_queue(&state)?;
+    let entry_id = parse_entry_id(&entry_id)?;
+
+    match queue.get(&TenantId::new(tenant_id), entry_id) {
+        Some(entry) => Ok(Json(ApiResponse::success(entry))),
+        None => Err(entry_not_found()),
+    }
+}
+
+/// Write failed records again; those that succeed leave the queue
+pub async fn retry_dead_letters(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    request: Option<Json<RetryDeadLettersRequest>>,
+) -> Result<Json<ApiResponse<RetryReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let queue = dead_letter_queue(&state)?;
+    let ids = request.and_then(|Json(request)| request.ids);
+
+    let report = queue.retry(&TenantId::new(tenant_id), ids.as_deref(), state.core_service.as_ref()).await;
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// Drop a failed ingestion record without retrying it
+pub async fn delete_dead_letter(
+    State(state): State<AppState>,
+    Path((tenant_id, entry_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let queue = dead_letter_queue(&state)?;
+    let entry_id = parse_entry_id(&entry_id)?;
+
+    if !queue.remove(&TenantId::new(tenant_id), entry_id) {
+        return Err(entry_not_found());
+    }
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Drop all of a tenant's failed ingestion records; returns how many there were
+pub async fn purge_dead_letters(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<usize>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let queue = dead_letter_queue(&state)?;
+    Ok(Json(ApiResponse::success(queue.purge(&TenantId::new(tenant_id)))))
+}
+
+/// Queue a record that an ingestion handler failed to write, if the
+/// dead-letter queue is enabled
+pub(crate) fn dead_letter(
+    state: &AppState,
+    tenant: &TenantId,
+    record: DeadLetterRecord,
+    error: impl ToString,
+    source: &str,
+    context: BTreeMap<String, String>,
+) {
+    let error = error.to_string();
+    warn!("Failed to ingest {} record for tenant {}: {}", source, tenant, error);
+    if let Some(queue) = &state.dead_letters {
+        queue.record(tenant, record, error, source, context);
+    }
+}
+
+fn parse_entry_id(entry_id: &str) -> Result<Uuid, (StatusCode, Json<ApiResponse<()>>)> {
+    Uuid::parse_str(entry_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid dead letter ID format"))))
+}
+
+fn entry_not_found() -> (StatusCode, Json<ApiResponse<()>>) {
+    (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error("Dead letter not found")))
+}
+
+fn dead_letter_queue(state: &AppState) -> Result<Arc<DeadLetterQueue>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.dead_letters.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("Dead-letter queue is not enabled"))))
+}
@@ -0,0 +1,88 @@
+//! LLM exchange log handlers
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+use tracing::info;
+
+/// A tenant's logging settings and recorded exchanges
+#[derive(Debug, Serialize)]
+pub struct ListExchangesResponse {
+    pub settings: Option<TenantExchangeLogging>,
+    pub exchanges: Vec<LlmExchange>,
+}
+
+/// List a tenant's recorded LLM exchanges, oldest first
+pub async fn list_exchanges(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<ListExchangesResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let log = exchange_log(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    match log.list(&tenant).await {
+        Ok(exchanges) => Ok(Json(ApiResponse::success(ListExchangesResponse {
+            settings: log.settings(&tenant),
+            exchanges,
+        }))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Get a recorded LLM exchange
+pub async fn get_exchange(
+    State(state): State<AppState>,
+    Path((tenant_id, exchange_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<LlmExchange>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let log = exchange_log(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    let exchange_id = Uuid::parse_str(&exchange_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid exchange ID format"))))?;
+
+    match log.get(&tenant, exchange_id).await {
+        Ok(Some(exchange)) => Ok(Json(ApiResponse::success(exchange))),
+        Ok(None) => Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error("Exchange not found")))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// Start logging a share of a tenant's LLM exchanges, optionally with its own
+/// redacted fields
+pub async fn enable_exchange_log(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<TenantExchangeLogging>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let log = exchange_log(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    log.enable(&tenant, request).map_err(handle_core_error)?;
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Stop logging a tenant's LLM exchanges; recorded exchanges are kept
+pub async fn disable_exchange_log(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let log = exchange_log(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    if !log.disable(&tenant) {
+        return Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error("Exchange logging is not enabled for this tenant"))));
+    }
+
+    info!("Stopped logging LLM exchanges for tenant {}", tenant);
+    Ok(Json(ApiResponse::success(())))
+}
+
+fn exchange_log(state: &AppState) -> Result<Arc<ExchangeLog>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.exchange_log.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("LLM exchange logging is not configured"))))
+}
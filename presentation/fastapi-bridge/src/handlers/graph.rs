@@ -1,89 +1,262 @@
 //! Graph operation handlers
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
-use serde::{Deserialize, Serialize};
+use bytes::BytesMut;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
 use telamentis_core::prelude::*;
+use telamentis_core::ingest_template::resolve_edge_aliases;
+use telamentis_core::query_cache::without_cache;
+use telamentis_core::types::Path as GraphPath;
 use uuid::Uuid;
-use crate::{handle_core_error, ApiResponse, AppState};
+use crate::handlers::dead_letter::dead_letter;
+use crate::columnar::{self, ExportTable, SnapshotFormat, EXPORT_MANIFEST_HEADER, ROW_COUNT_HEADER, SNAPSHOT_AT_HEADER};
+use crate::{handle_core_error, ApiResponse, AppState, FilterParams, PaginatedResponse, PaginationInfo, PaginationParams};
 use tracing::{debug, info, warn};
 
+/// Media type of JSON-LD responses
+const JSON_LD: &str = "application/ld+json";
+
+/// Edges of a bulk load whose alias references are resolved in one lookup
+const BULK_ALIAS_CHUNK_SIZE: usize = 1_000;
+
 /// Request to upsert a single node
 #[derive(Debug, Deserialize)]
 pub struct UpsertNodeRequest {
     pub node: Node,
+    #[serde(default)]
+    pub write_concern: WriteConcern,
 }
 
 /// Response from upserting a node
 #[derive(Debug, Serialize)]
 pub struct UpsertNodeResponse {
+    /// Nil for a buffered write
     pub node_id: Uuid,
     pub created: bool,
+    pub write_concern: WriteConcern,
+}
+
+/// Request to upsert a node together with edges to existing nodes
+#[derive(Debug, Deserialize)]
+pub struct UpsertNodeWithEdgesRequest {
+    pub node: Node,
+    #[serde(default)]
+    pub edges: Vec<EdgeSpec>,
+    #[serde(default)]
+    pub write_concern: WriteConcern,
 }
 
 /// Request to upsert a single edge
 #[derive(Debug, Deserialize)]
 pub struct UpsertEdgeRequest {
     pub edge: TimeEdge,
+    #[serde(default)]
+    pub write_concern: WriteConcern,
 }
 
 /// Response from upserting an edge
 #[derive(Debug, Serialize)]
 pub struct UpsertEdgeResponse {
+    /// Nil for a buffered write
     pub edge_id: Uuid,
     pub created: bool,
+    pub write_concern: WriteConcern,
+}
+
+/// Query parameters qualifying a node alias
+#[derive(Debug, Default, Deserialize)]
+pub struct AliasParams {
+    /// Namespace of the alias; the default namespace if omitted
+    pub namespace: Option<String>,
+}
+
+/// Query parameters of a node's activity timeline
+#[derive(Debug, Default, Deserialize)]
+pub struct TimelineParams {
+    /// Namespace of the alias; the default namespace if omitted
+    pub namespace: Option<String>,
+    /// Width of the buckets: `day` (default), `week` or `month`
+    #[serde(default)]
+    pub interval: TimelineInterval,
+    /// Start of the range; the last 30 buckets if omitted
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range; now if omitted
+    pub to: Option<DateTime<Utc>>,
+    /// Comma-separated relationship types to count; all if omitted
+    pub types: Option<String>,
+}
+
+/// Request to merge properties into a node addressed by alias
+#[derive(Debug, Deserialize)]
+pub struct PatchNodeRequest {
+    /// Properties to set; a null value removes the property
+    pub props: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub write_concern: WriteConcern,
+}
+
+/// Request to upsert an edge whose endpoints may be given by alias
+#[derive(Debug, Deserialize)]
+pub struct UpsertEdgeByRefRequest {
+    pub edge: EdgeByRef,
+    #[serde(default)]
+    pub write_concern: WriteConcern,
+}
+
+/// Request to close an edge in valid time
+#[derive(Debug, Deserialize)]
+pub struct CloseEdgeRequest {
+    pub valid_to: DateTime<Utc>,
+}
+
+/// Request to replace an edge with a corrected one
+#[derive(Debug, Deserialize)]
+pub struct SupersedeEdgeRequest {
+    pub edge: TimeEdge,
+}
+
+/// Response from closing or superseding an edge
+#[derive(Debug, Serialize)]
+pub struct EdgeVersionResponse {
+    /// ID of the new current version
+    pub edge_id: Uuid,
+    /// ID of the version it replaced, kept for audit
+    pub previous_edge_id: Uuid,
 }
 
 /// Batch upsert request for nodes
 #[derive(Debug, Deserialize)]
 pub struct BatchUpsertNodesRequest {
     pub nodes: Vec<Node>,
+    #[serde(default)]
+    pub write_concern: WriteConcern,
 }
 
 /// Batch upsert response for nodes
 #[derive(Debug, Serialize)]
 pub struct BatchUpsertNodesResponse {
+    /// Nil for buffered writes
     pub node_ids: Vec<Uuid>,
     pub created_count: usize,
     pub updated_count: usize,
+    pub write_concern: WriteConcern,
 }
 
 /// Batch upsert request for edges
 #[derive(Debug, Deserialize)]
 pub struct BatchUpsertEdgesRequest {
     pub edges: Vec<TimeEdge>,
+    #[serde(default)]
+    pub write_concern: WriteConcern,
 }
 
 /// Batch upsert response for edges
 #[derive(Debug, Serialize)]
 pub struct BatchUpsertEdgesResponse {
+    /// Nil for buffered writes
     pub edge_ids: Vec<Uuid>,
     pub created_count: usize,
     pub updated_count: usize,
+    pub write_concern: WriteConcern,
+}
+
+/// Response to a bulk load
+#[derive(Debug, Serialize)]
+pub struct BulkLoadResponse {
+    pub loaded: u64,
+}
+
+/// Query parameters for a snapshot export
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    /// Only export edges valid at this time; a date such as "2020-03" means
+    /// as of its last instant
+    #[serde(default, deserialize_with = "telamentis_core::temporal::valid_bound::as_of_opt")]
+    pub valid_at: Option<DateTime<Utc>>,
+    /// Encoding of the export, JSON by default
+    #[serde(default)]
+    pub format: SnapshotFormat,
+    /// Table to export, required for Arrow, Parquet, NDJSON and CSV
+    pub table: Option<ExportTable>,
+    /// Describe edges as RDF statements with their times and properties,
+    /// overriding the configured RDF mapping
+    pub reify_edges: Option<bool>,
+    /// Encrypt the export with the tenant's export key
+    #[serde(default)]
+    pub encrypt: bool,
+}
+
+/// Query parameters for a schema document
+#[derive(Debug, Deserialize)]
+pub struct SchemaDocParams {
+    /// Markdown by default
+    #[serde(default)]
+    pub format: SchemaDocFormat,
+    /// Nodes per label and relationships per kind to sample
+    pub sample: Option<u32>,
 }
 
 /// Query execution request
 #[derive(Debug, Deserialize)]
 pub struct QueryRequest {
     pub query: GraphQuery,
+    /// Return the matching paths, only their number, or only whether there are any
+    #[serde(default)]
+    pub mode: QueryMode,
 }
 
 /// Query execution response
 #[derive(Debug, Serialize)]
 pub struct QueryResponse {
+    /// Matching paths; empty unless the mode is `full`
     pub paths: Vec<Path>,
+    /// Number of matches, in `count` mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    /// Whether anything matched, in `exists` mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    pub execution_time_ms: u64,
+}
+
+/// Traversal response
+#[derive(Debug, Serialize)]
+pub struct TraversalResponse {
+    /// Path to each node reached, in the order reached
+    pub paths: Vec<WeightedPath>,
     pub execution_time_ms: u64,
 }
 
+/// Shortest path response
+#[derive(Debug, Serialize)]
+pub struct ShortestPathResponse {
+    /// The path, or null if the nodes are not connected within the depth
+    pub path: Option<WeightedPath>,
+    pub execution_time_ms: u64,
+}
+
+/// Request to materialize a named snapshot
+#[derive(Debug, Deserialize)]
+pub struct MaterializeSnapshotRequest {
+    pub name: String,
+    #[serde(deserialize_with = "telamentis_core::temporal::valid_bound::as_of")]
+    pub valid_at: DateTime<Utc>,
+}
+
 /// Upsert a single node
 pub async fn upsert_node(
     State(state): State<AppState>,
     Path(tenant_id): Path<String>,
+    headers: HeaderMap,
     Json(request): Json<UpsertNodeRequest>,
-) -> Result<Json<ApiResponse<UpsertNodeResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<(StatusCode, Json<ApiResponse<UpsertNodeResponse>>), (StatusCode, Json<ApiResponse<()>>)> {
     // Create request context for pipeline
     let mut ctx = RequestContext::new("POST".to_string(), format!("/graph/{}/nodes", tenant_id));
     ctx.tenant_id = Some(TenantId::new(&tenant_id));
@@ -95,7 +268,7 @@ pub async fn upsert_node(
     }
     
     // Execute pipeline
-    match state.pipeline.execute(ctx).await {
+    let operation = match state.pipeline.execute(ctx).await {
         Ok(processed_ctx) => {
             if let Some(error) = processed_ctx.error {
                 return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::error(error))));
@@ -103,27 +276,71 @@ pub async fn upsert_node(
             
             // Continue with core operation
             debug!("Upserting node for tenant: {}", tenant_id);
+            processed_ctx.operation
         }
         Err(e) => {
             return Err(handle_core_error(e));
         }
-    }
+    };
     
     let tenant = TenantId::new(tenant_id);
     
-    match state.core_service.upsert_node(&tenant, request.node).await {
+    let write_concern = request.write_concern;
+    match with_write_concern(write_concern, state.core_service.upsert_node(&tenant, request.node)).await {
         Ok(node_id) => {
             let response = UpsertNodeResponse {
                 node_id,
                 created: true, // Simplified - in reality we'd track if it was created or updated
+                write_concern,
             };
-            info!("Upserted node {} for tenant {}", node_id, tenant);
-            Ok(Json(ApiResponse::success(response)))
+            info!("Upserted node {} for tenant {} ({})", node_id, tenant, write_concern);
+            Ok((write_status(write_concern), Json(ApiResponse::success(response).with_operation(state.debug_operation(&headers, operation)))))
         }
         Err(e) => Err(handle_core_error(e))
     }
 }
 
+/// Upsert a node and its edges to existing nodes atomically
+pub async fn upsert_node_with_edges(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<UpsertNodeWithEdgesRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<NodeWithEdges>>), (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Upserting node with {} edges for tenant: {}", request.edges.len(), tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    
+    let write_concern = request.write_concern;
+    match with_write_concern(write_concern, state.core_service.upsert_node_with_edges(&tenant, request.node, request.edges)).await {
+        Ok(result) => {
+            info!("Upserted node {} with {} edges for tenant {} ({})", result.node_id, result.edge_ids.len(), tenant, write_concern);
+            Ok((write_status(write_concern), Json(ApiResponse::success(result))))
+        }
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Apply an extraction envelope made by the client, with the alias
+/// resolution, confidence filtering, contradiction handling and provenance
+/// of server-side ingestion
+pub async fn apply_envelope(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(mut request): Json<ApplyEnvelopeRequest>,
+) -> Result<Json<ApiResponse<EnvelopeReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Applying envelope of {} nodes and {} relations for tenant: {}",
+        request.envelope.nodes.len(), request.envelope.relations.len(), tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let source = request.source.clone().unwrap_or_default();
+    state.config.valid_time.for_tenant(&tenant).apply_to_envelope(&mut request.envelope, &source);
+    
+    match state.envelopes.apply(&tenant, request).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
 /// Extract tenant ID from URL path
 fn extract_tenant_from_path(path: &str) -> Option<String> {
     // Simple regex-like extraction for paths like "/graph/{tenant_id}/..."
@@ -144,22 +361,23 @@ pub async fn batch_upsert_nodes(
     State(state): State<AppState>,
     Path(tenant_id): Path<String>,
     Json(request): Json<BatchUpsertNodesRequest>,
-) -> Result<Json<ApiResponse<BatchUpsertNodesResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<(StatusCode, Json<ApiResponse<BatchUpsertNodesResponse>>), (StatusCode, Json<ApiResponse<()>>)> {
     info!("Batch upserting {} nodes for tenant: {}", request.nodes.len(), tenant_id);
     
     let tenant = TenantId::new(tenant_id);
+    let write_concern = request.write_concern;
     let mut node_ids = Vec::new();
     let mut created_count = 0;
     let mut error_count = 0;
     
-    for node in request.nodes {
-        match state.core_service.upsert_node(&tenant, node).await {
+    for (index, node) in request.nodes.into_iter().enumerate() {
+        match with_write_concern(write_concern, state.core_service.upsert_node(&tenant, node.clone())).await {
             Ok(node_id) => {
                 node_ids.push(node_id);
                 created_count += 1;
             }
             Err(e) => {
-                warn!("Failed to upsert node: {}", e);
+                dead_letter(&state, &tenant, DeadLetterRecord::Node(node), e, "http", batch_context(index));
                 error_count += 1;
             }
         }
@@ -169,17 +387,122 @@ pub async fn batch_upsert_nodes(
         node_ids,
         created_count,
         updated_count: 0, // Simplified
+        write_concern,
     };
     
     info!("Batch upserted {} nodes ({} errors) for tenant {}", created_count, error_count, tenant);
-    Ok(Json(ApiResponse::success(response)))
+    Ok((write_status(write_concern), Json(ApiResponse::success(response))))
+}
+
+/// Bulk load nodes sent as newline-delimited JSON, one node per line. The
+/// store commits them in chunks as the body arrives; a malformed line ends
+/// the load with `400`, leaving the nodes before it loaded.
+pub async fn bulk_load_nodes(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    body: Body,
+) -> Result<Json<ApiResponse<BulkLoadResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    let (nodes, bad_line) = until_error(ndjson_lines::<Node>(body));
+
+    let loaded = state.core_service.bulk_load_nodes(&tenant, nodes).await
+        .map_err(|e| handle_core_error(e.into()))?;
+    if let Some(message) = bad_line.take() {
+        let message = format!("{} ({} nodes loaded before it)", message, loaded);
+        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(message))));
+    }
+
+    info!("Bulk loaded {} nodes for tenant {}", loaded, tenant);
+    Ok(Json(ApiResponse::success(BulkLoadResponse { loaded })))
+}
+
+/// Bulk load edges sent as newline-delimited JSON, one edge per line, as
+/// [`bulk_load_nodes`] does nodes. Edges may refer to their nodes by alias,
+/// as in batch upserts; aliases are resolved a chunk of edges at a time.
+pub async fn bulk_load_edges(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    body: Body,
+) -> Result<Json<ApiResponse<BulkLoadResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    let (edges, bad_line) = until_error(ndjson_lines::<TimeEdge>(body));
+
+    let (service, resolving) = (state.core_service.clone(), tenant.clone());
+    let (resolved, unresolved) = until_error(edges.chunks(BULK_ALIAS_CHUNK_SIZE).then(move |mut chunk| {
+        let (service, tenant) = (service.clone(), resolving.clone());
+        async move { resolve_edge_aliases(service.as_ref(), &tenant, &mut chunk).await.map(|()| chunk) }
+    }));
+    let edges = resolved.flat_map(stream::iter).boxed();
+
+    let loaded = state.core_service.bulk_load_edges(&tenant, edges).await
+        .map_err(|e| handle_core_error(e.into()))?;
+    if let Some(e) = unresolved.take() {
+        return Err(handle_core_error(e.into()));
+    }
+    if let Some(message) = bad_line.take() {
+        let message = format!("{} ({} edges loaded before it)", message, loaded);
+        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(message))));
+    }
+
+    info!("Bulk loaded {} edges for tenant {}", loaded, tenant);
+    Ok(Json(ApiResponse::success(BulkLoadResponse { loaded })))
+}
+
+/// Items of a newline-delimited JSON body, parsed as its chunks arrive;
+/// blank lines are skipped and the last line needs no newline
+fn ndjson_lines<T: DeserializeOwned + Send + 'static>(body: Body) -> BoxStream<'static, Result<T, String>> {
+    let start = (body.into_data_stream(), BytesMut::new(), 0usize, false);
+    stream::unfold(start, |(mut data, mut buffer, mut line, mut ended)| async move {
+        loop {
+            if let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+                let raw = buffer.split_to(end + 1);
+                line += 1;
+                if raw.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                let item = serde_json::from_slice(&raw)
+                    .map_err(|e| format!("Invalid JSON on line {}: {}", line, e));
+                return Some((item, (data, buffer, line, ended)));
+            }
+            if ended {
+                if buffer.iter().all(u8::is_ascii_whitespace) {
+                    return None;
+                }
+                buffer.extend_from_slice(b"\n");
+                continue;
+            }
+            match data.next().await {
+                Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    let error = Err(format!("Failed to read request body: {}", e));
+                    return Some((error, (data, BytesMut::new(), line, true)));
+                }
+                None => ended = true,
+            }
+        }
+    })
+    .boxed()
 }
 
-/// Get a node by ID
+/// `202 Accepted` for buffered writes, which are not yet in the store
+fn write_status(write_concern: WriteConcern) -> StatusCode {
+    match write_concern {
+        WriteConcern::Buffered => StatusCode::ACCEPTED,
+        WriteConcern::Committed | WriteConcern::Replicated => StatusCode::OK,
+    }
+}
+
+/// Dead-letter context of the record at `index` of a batch request
+fn batch_context(index: usize) -> BTreeMap<String, String> {
+    BTreeMap::from([("batch_index".to_string(), index.to_string())])
+}
+
+/// Get a node by ID; as JSON-LD if the request accepts `application/ld+json`
 pub async fn get_node(
     State(state): State<AppState>,
     Path((tenant_id, node_id)): Path<(String, String)>,
-) -> Result<Json<ApiResponse<Node>>, (StatusCode, Json<ApiResponse<()>>)> {
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
     debug!("Getting node {} for tenant: {}", node_id, tenant_id);
     
     let tenant = TenantId::new(tenant_id);
@@ -192,7 +515,10 @@ pub async fn get_node(
         .with_id_alias(&node_id)
         .with_property("mock", serde_json::Value::Bool(true));
     
-    Ok(Json(ApiResponse::success(node)))
+    if accepts_json_ld(&headers) {
+        return Ok(json_ld(state.config.rdf.for_tenant(&tenant).node_json_ld(&tenant, uuid, &node)));
+    }
+    Ok(Json(ApiResponse::success(node)).into_response())
 }
 
 /// Delete a node
@@ -211,24 +537,163 @@ pub async fn delete_node(
     Ok(Json(ApiResponse::success(())))
 }
 
+/// Get a node by its alias
+pub async fn get_node_by_alias(
+    State(state): State<AppState>,
+    Path((tenant_id, alias)): Path<(String, String)>,
+    Query(params): Query<AliasParams>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Getting node by alias {} for tenant: {}", alias, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let alias = alias_key(params, alias);
+    
+    let record = state.core_service.get_node_by_alias(&tenant, &alias).await
+        .map_err(|e| handle_core_error(e.into()))?
+        .ok_or_else(|| alias_not_found(&alias))?;
+    
+    if accepts_json_ld(&headers) {
+        return Ok(json_ld(state.config.rdf.for_tenant(&tenant).node_json_ld(&tenant, record.id, &record.node)));
+    }
+    Ok(Json(ApiResponse::success(record)).into_response())
+}
+
+/// Delete a node and its relationships by the node's alias
+pub async fn delete_node_by_alias(
+    State(state): State<AppState>,
+    Path((tenant_id, alias)): Path<(String, String)>,
+    Query(params): Query<AliasParams>,
+) -> Result<Json<ApiResponse<Uuid>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!("Deleting node by alias {} for tenant: {}", alias, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let alias = alias_key(params, alias);
+    
+    let node_id = state.core_service.delete_node_by_alias(&tenant, &alias).await
+        .map_err(|e| handle_core_error(e.into()))?
+        .ok_or_else(|| alias_not_found(&alias))?;
+    info!("Deleted node {} ({}) for tenant {}", node_id, alias, tenant);
+    Ok(Json(ApiResponse::success(node_id)))
+}
+
+/// Merge properties into a node addressed by its alias
+pub async fn patch_node_by_alias(
+    State(state): State<AppState>,
+    Path((tenant_id, alias)): Path<(String, String)>,
+    Query(params): Query<AliasParams>,
+    Json(request): Json<PatchNodeRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<NodeRecord>>), (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Patching node by alias {} for tenant: {}", alias, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let alias = alias_key(params, alias);
+    
+    let write_concern = request.write_concern;
+    let record = with_write_concern(write_concern, state.core_service.patch_node_by_alias(&tenant, &alias, request.props)).await
+        .map_err(|e| handle_core_error(e.into()))?
+        .ok_or_else(|| alias_not_found(&alias))?;
+    info!("Patched node {} ({}) for tenant {} ({})", record.id, alias, tenant, write_concern);
+    Ok((write_status(write_concern), Json(ApiResponse::success(record))))
+}
+
+/// Upsert an edge whose endpoints may be given by alias
+pub async fn upsert_edge_by_ref(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<UpsertEdgeByRefRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<UpsertEdgeResponse>>), (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Upserting edge by reference for tenant: {}", tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    
+    let write_concern = request.write_concern;
+    match with_write_concern(write_concern, state.core_service.upsert_edge_by_ref(&tenant, request.edge)).await {
+        Ok(edge_id) => {
+            info!("Upserted edge {} for tenant {} ({})", edge_id, tenant, write_concern);
+            Ok((write_status(write_concern), Json(ApiResponse::success(UpsertEdgeResponse { edge_id, created: true, write_concern }))))
+        }
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Activity timeline of the node with an alias: its edges that became valid
+/// or were closed, bucketed by day, week or month
+pub async fn node_timeline(
+    State(state): State<AppState>,
+    Path((tenant_id, alias)): Path<(String, String)>,
+    Query(params): Query<TimelineParams>,
+) -> Result<Json<ApiResponse<Timeline>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Building {} timeline of alias {} for tenant: {}", params.interval, alias, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let alias = AliasKey { namespace: params.namespace, alias };
+    let node = state.core_service.resolve_node_ref(&tenant, &NodeRef::Alias(alias.clone())).await
+        .map_err(|e| handle_core_error(e.into()))?
+        .ok_or_else(|| alias_not_found(&alias))?;
+    
+    let mut request = TimelineRequest::new(node, params.interval);
+    request.from = params.from;
+    request.to = params.to;
+    request.relationship_types = params.types
+        .map(|types| types.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+    
+    match state.core_service.timeline(&tenant, request).await {
+        Ok(timeline) => Ok(Json(ApiResponse::success(timeline))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Values a property of the node with an alias took over time, with the
+/// valid and transaction time of each
+pub async fn property_history(
+    State(state): State<AppState>,
+    Path((tenant_id, alias, key)): Path<(String, String, String)>,
+    Query(params): Query<AliasParams>,
+) -> Result<Json<ApiResponse<PropertyHistory>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Getting history of property {} of alias {} for tenant: {}", key, alias, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let alias = alias_key(params, alias);
+    let node = state.core_service.resolve_node_ref(&tenant, &NodeRef::Alias(alias.clone())).await
+        .map_err(|e| handle_core_error(e.into()))?
+        .ok_or_else(|| alias_not_found(&alias))?;
+    
+    match state.core_service.property_history(&tenant, node, &key).await {
+        Ok(history) => Ok(Json(ApiResponse::success(history))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+fn alias_key(params: AliasParams, alias: String) -> AliasKey {
+    AliasKey { namespace: params.namespace, alias }
+}
+
+fn alias_not_found(alias: &AliasKey) -> (StatusCode, Json<ApiResponse<()>>) {
+    (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(format!("No node has the alias '{}'", alias))))
+}
+
 /// Upsert a single edge
 pub async fn upsert_edge(
     State(state): State<AppState>,
     Path(tenant_id): Path<String>,
     Json(request): Json<UpsertEdgeRequest>,
-) -> Result<Json<ApiResponse<UpsertEdgeResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<(StatusCode, Json<ApiResponse<UpsertEdgeResponse>>), (StatusCode, Json<ApiResponse<()>>)> {
     debug!("Upserting edge for tenant: {}", tenant_id);
     
     let tenant = TenantId::new(tenant_id);
     
-    match state.core_service.upsert_edge(&tenant, request.edge).await {
+    let write_concern = request.write_concern;
+    match with_write_concern(write_concern, state.core_service.upsert_edge(&tenant, request.edge)).await {
         Ok(edge_id) => {
             let response = UpsertEdgeResponse {
                 edge_id,
                 created: true,
+                write_concern,
             };
-            info!("Upserted edge {} for tenant {}", edge_id, tenant);
-            Ok(Json(ApiResponse::success(response)))
+            info!("Upserted edge {} for tenant {} ({})", edge_id, tenant, write_concern);
+            Ok((write_status(write_concern), Json(ApiResponse::success(response))))
         }
         Err(e) => Err(handle_core_error(e))
     }
@@ -239,22 +704,29 @@ pub async fn batch_upsert_edges(
     State(state): State<AppState>,
     Path(tenant_id): Path<String>,
     Json(request): Json<BatchUpsertEdgesRequest>,
-) -> Result<Json<ApiResponse<BatchUpsertEdgesResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<(StatusCode, Json<ApiResponse<BatchUpsertEdgesResponse>>), (StatusCode, Json<ApiResponse<()>>)> {
     info!("Batch upserting {} edges for tenant: {}", request.edges.len(), tenant_id);
     
     let tenant = TenantId::new(tenant_id);
+    let write_concern = request.write_concern;
+    let submitted = request.edges;
+    let mut edges = submitted.clone();
     let mut edge_ids = Vec::new();
     let mut created_count = 0;
     let mut error_count = 0;
     
-    for edge in request.edges {
-        match state.core_service.upsert_edge(&tenant, edge).await {
+    resolve_edge_aliases(state.core_service.as_ref(), &tenant, &mut edges).await
+        .map_err(|e| handle_core_error(e.into()))?;
+    
+    // Failed edges are queued as submitted, so that alias references resolve on retry
+    for (index, (edge, original)) in edges.into_iter().zip(submitted).enumerate() {
+        match with_write_concern(write_concern, state.core_service.upsert_edge(&tenant, edge)).await {
             Ok(edge_id) => {
                 edge_ids.push(edge_id);
                 created_count += 1;
             }
             Err(e) => {
-                warn!("Failed to upsert edge: {}", e);
+                dead_letter(&state, &tenant, DeadLetterRecord::Edge(original), e, "http", batch_context(index));
                 error_count += 1;
             }
         }
@@ -264,10 +736,11 @@ pub async fn batch_upsert_edges(
         edge_ids,
         created_count,
         updated_count: 0,
+        write_concern,
     };
     
     info!("Batch upserted {} edges ({} errors) for tenant {}", created_count, error_count, tenant);
-    Ok(Json(ApiResponse::success(response)))
+    Ok((write_status(write_concern), Json(ApiResponse::success(response))))
 }
 
 /// Delete an edge
@@ -286,31 +759,546 @@ pub async fn delete_edge(
     Ok(Json(ApiResponse::success(())))
 }
 
-/// Execute a graph query
+/// Close an edge in valid time
+pub async fn close_edge(
+    State(state): State<AppState>,
+    Path((tenant_id, edge_id)): Path<(String, String)>,
+    Json(request): Json<CloseEdgeRequest>,
+) -> Result<Json<ApiResponse<EdgeVersionResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!("Closing edge {} for tenant: {}", edge_id, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let uuid = Uuid::parse_str(&edge_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid edge ID format"))))?;
+    
+    match state.core_service.close_edge(&tenant, uuid, request.valid_to).await {
+        Ok(new_id) => {
+            info!("Closed edge {} for tenant {} as {}", uuid, tenant, new_id);
+            Ok(Json(ApiResponse::success(EdgeVersionResponse {
+                edge_id: new_id,
+                previous_edge_id: uuid,
+            })))
+        }
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Replace an edge with a corrected one
+pub async fn supersede_edge(
+    State(state): State<AppState>,
+    Path((tenant_id, edge_id)): Path<(String, String)>,
+    Json(request): Json<SupersedeEdgeRequest>,
+) -> Result<Json<ApiResponse<EdgeVersionResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!("Superseding edge {} for tenant: {}", edge_id, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let uuid = Uuid::parse_str(&edge_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid edge ID format"))))?;
+    
+    match state.core_service.supersede_edge(&tenant, uuid, request.edge).await {
+        Ok(new_id) => {
+            info!("Superseded edge {} for tenant {} with {}", uuid, tenant, new_id);
+            Ok(Json(ApiResponse::success(EdgeVersionResponse {
+                edge_id: new_id,
+                previous_edge_id: uuid,
+            })))
+        }
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Retract an edge that was never true
+pub async fn retract_edge(
+    State(state): State<AppState>,
+    Path((tenant_id, edge_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!("Retracting edge {} for tenant: {}", edge_id, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let uuid = Uuid::parse_str(&edge_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid edge ID format"))))?;
+    
+    match state.core_service.retract_edge(&tenant, uuid).await {
+        Ok(true) => {
+            info!("Retracted edge {} for tenant {}", uuid, tenant);
+            Ok(Json(ApiResponse::success(())))
+        }
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error(format!("No current version of edge {}", uuid))),
+        )),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Execute a graph query. `Cache-Control: no-cache` skips the query result
+/// cache, and results are JSON-LD if the request accepts `application/ld+json`.
 pub async fn execute_query(
     State(state): State<AppState>,
     Path(tenant_id): Path<String>,
+    headers: HeaderMap,
     Json(request): Json<QueryRequest>,
-) -> Result<Json<ApiResponse<QueryResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
     debug!("Executing query for tenant: {}", tenant_id);
     
     let tenant = TenantId::new(tenant_id);
     let start_time = std::time::Instant::now();
     
-    match state.core_service.query(&tenant, request.query).await {
-        Ok(paths) => {
+    let no_cache = no_cache_requested(&headers);
+    let run = async {
+        let mut response = QueryResponse { paths: Vec::new(), count: None, exists: None, execution_time_ms: 0 };
+        match request.mode {
+            QueryMode::Full => response.paths = state.core_service.query(&tenant, request.query).await?,
+            QueryMode::Count => response.count = Some(state.core_service.query_count(&tenant, request.query).await?),
+            QueryMode::Exists => response.exists = Some(state.core_service.query_exists(&tenant, request.query).await?),
+        }
+        Ok::<_, GraphError>(response)
+    };
+    let result = if no_cache { without_cache(run).await } else { run.await };
+    
+    match result {
+        Ok(mut response) => {
             let execution_time = start_time.elapsed();
-            let response = QueryResponse {
-                paths,
-                execution_time_ms: execution_time.as_millis() as u64,
-            };
+            response.execution_time_ms = execution_time.as_millis() as u64;
             info!("Query executed for tenant {} in {}ms", tenant, execution_time.as_millis());
-            Ok(Json(ApiResponse::success(response)))
+            if request.mode == QueryMode::Full && accepts_json_ld(&headers) {
+                return Ok(json_ld(state.config.rdf.for_tenant(&tenant).paths_json_ld(&tenant, &response.paths)));
+            }
+            Ok(Json(ApiResponse::success(response)).into_response())
         }
         Err(e) => Err(handle_core_error(e))
     }
 }
 
+/// Run several queries concurrently, returning their results in order
+pub async fn execute_query_batch(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<BatchQueryRequest>,
+) -> Result<Json<ApiResponse<BatchQueryResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Executing a batch of {} queries for tenant: {}", request.queries.len(), tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let run = run_batch(state.core_service.clone(), &tenant, request);
+    let result = if no_cache_requested(&headers) { without_cache(run).await } else { run.await };
+    
+    match result {
+        Ok(response) => {
+            let failed = response.results.iter().filter(|result| !result.is_ok()).count();
+            info!("Batch of {} queries executed for tenant {} in {}ms, {} failed", response.results.len(), tenant, response.execution_time_ms, failed);
+            Ok(Json(ApiResponse::success(response)))
+        }
+        // The batch as a whole only fails when it is empty or too large
+        Err(e) => Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(e.to_string()))))
+    }
+}
+
+/// Follow edges out from a node, by fewest hops or least total weight
+pub async fn traverse(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<TraversalRequest>,
+) -> Result<Json<ApiResponse<TraversalResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Traversing from node {} for tenant: {}", request.start, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let start_time = std::time::Instant::now();
+    
+    match state.core_service.traverse(&tenant, request).await {
+        Ok(paths) => Ok(Json(ApiResponse::success(TraversalResponse {
+            paths,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        }))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Path between two nodes with the fewest hops, or the least total weight
+pub async fn shortest_path(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<ShortestPathRequest>,
+) -> Result<Json<ApiResponse<ShortestPathResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Finding path from {} to {} for tenant: {}", request.from, request.to, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let start_time = std::time::Instant::now();
+    
+    match state.core_service.shortest_path(&tenant, request).await {
+        Ok(path) => Ok(Json(ApiResponse::success(ShortestPathResponse {
+            path,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        }))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// List the nodes matching the filters, a page at a time (v2)
+pub async fn list_nodes(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Query(filters): Query<FilterParams>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<ApiResponse<PaginatedResponse<GraphPath>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let query = filters.node_query()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(e))))?;
+    query_page(&state, TenantId::new(tenant_id), query, &pagination).await
+}
+
+/// List the relationships matching the filters, a page at a time (v2)
+pub async fn list_relationships(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Query(filters): Query<FilterParams>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<Json<ApiResponse<PaginatedResponse<GraphPath>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let query = filters.relationship_query()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(e))))?;
+    query_page(&state, TenantId::new(tenant_id), query, &pagination).await
+}
+
+async fn query_page(
+    state: &AppState,
+    tenant: TenantId,
+    query: GraphQuery,
+    pagination: &PaginationParams,
+) -> Result<Json<ApiResponse<PaginatedResponse<GraphPath>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let page = pagination.page_request();
+    let query = page.apply(query)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(e))))?;
+
+    let mut paths = state.core_service.query(&tenant, query).await.map_err(|e| handle_core_error(e.into()))?;
+    let has_next = page.finish(&mut paths);
+    debug!("Listed {} results at offset {} for tenant {}", paths.len(), page.offset, tenant);
+
+    Ok(Json(ApiResponse::success(PaginatedResponse {
+        data: paths,
+        pagination: PaginationInfo::new(&page, has_next),
+    })))
+}
+
+/// Export a consistent snapshot of a tenant's graph, as JSON, as RDF or as
+/// one table in Arrow IPC, Parquet, NDJSON or CSV, streamed as it is encoded
+pub async fn export_snapshot(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Exporting snapshot for tenant: {}", tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let table = match (params.format, params.table) {
+        (SnapshotFormat::Json | SnapshotFormat::Turtle | SnapshotFormat::NTriples, _) => None,
+        (_, Some(table)) => Some(table),
+        (_, None) => {
+            return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Tabular exports need a table: nodes or edges"))));
+        }
+    };
+    
+    let key = state.export_keys.as_ref().and_then(|keys| keys.get(&tenant));
+    if params.encrypt && key.is_none() {
+        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Tenant has no export key to encrypt with"))));
+    }
+    
+    let snapshot = state.core_service.snapshot(&tenant, params.valid_at).await
+        .map_err(|e| handle_core_error(e.into()))?;
+    info!("Exported {} nodes and {} edges for tenant {} as of {}",
+        snapshot.nodes.len(), snapshot.edges.len(), tenant, snapshot.snapshot_at);
+    
+    if let Some(key) = key {
+        return signed_export(key, &state, tenant, snapshot, table, &params).await;
+    }
+    
+    if let Some(syntax) = params.format.rdf_syntax() {
+        let mut mapping = state.config.rdf.for_tenant(&tenant).clone();
+        mapping.reify_edges = params.reify_edges.unwrap_or(mapping.reify_edges);
+        let headers = [
+            (header::CONTENT_TYPE, syntax.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.{}\"", tenant, syntax.extension())),
+            (HeaderName::from_static(SNAPSHOT_AT_HEADER), snapshot.snapshot_at.to_rfc3339()),
+        ];
+        return Ok((headers, mapping.encode(&tenant, &snapshot, syntax)).into_response());
+    }
+    
+    let Some(table) = table else {
+        return Ok(Json(ApiResponse::success(snapshot)).into_response());
+    };
+    
+    let filename = format!("{}-{}.{}", tenant, table.name(), params.format.extension());
+    let headers = [
+        (header::CONTENT_TYPE, params.format.content_type().to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        (HeaderName::from_static(SNAPSHOT_AT_HEADER), snapshot.snapshot_at.to_rfc3339()),
+        (HeaderName::from_static(ROW_COUNT_HEADER), table.rows(&snapshot).to_string()),
+    ];
+    let chunks = columnar::encode_stream(snapshot, tenant, table, params.format);
+    Ok((headers, Body::from_stream(chunks)).into_response())
+}
+
+/// Export of a tenant with an export key: the encoded snapshot is held whole
+/// so it can be hashed, and encrypted on request, and its signed manifest is
+/// sent in the `x-export-manifest` header
+async fn signed_export(
+    key: &ExportKey,
+    state: &AppState,
+    tenant: TenantId,
+    snapshot: GraphSnapshot,
+    table: Option<ExportTable>,
+    params: &ExportParams,
+) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
+    let snapshot_at = snapshot.snapshot_at.to_rfc3339();
+    let (node_count, edge_count) = (snapshot.nodes.len(), snapshot.edges.len());
+    let row_count = table.map(|table| table.rows(&snapshot));
+    
+    let (content_type, filename, data) = match (params.format.rdf_syntax(), table) {
+        (Some(syntax), _) => {
+            let mut mapping = state.config.rdf.for_tenant(&tenant).clone();
+            mapping.reify_edges = params.reify_edges.unwrap_or(mapping.reify_edges);
+            let data = mapping.encode(&tenant, &snapshot, syntax).into_bytes();
+            (syntax.content_type(), format!("{}.{}", tenant, syntax.extension()), data)
+        }
+        (None, None) => {
+            let data = serde_json::to_vec(&ApiResponse::success(&snapshot))
+                .map_err(|e| handle_core_error(CoreError::Internal(format!("Failed to serialize snapshot: {}", e))))?;
+            (params.format.content_type(), format!("{}.json", tenant), data)
+        }
+        (None, Some(table)) => {
+            let filename = format!("{}-{}.{}", tenant, table.name(), params.format.extension());
+            let data = columnar::encode_bytes(snapshot, tenant.clone(), table, params.format).await
+                .map_err(|e| handle_core_error(CoreError::Internal(format!("Failed to encode export: {}", e))))?;
+            (params.format.content_type(), filename, data)
+        }
+    };
+    
+    let (content_type, filename, data) = if params.encrypt {
+        let data = key.encrypt(tenant.as_str(), &data).map_err(handle_core_error)?;
+        ("application/octet-stream", format!("{}.enc", filename), data)
+    } else {
+        (content_type, filename, data)
+    };
+    let mut manifest = ExportManifest::new(
+        tenant.as_str(), params.format.extension(), Some(snapshot_at.clone()),
+        node_count, edge_count, params.encrypt, &data,
+    );
+    key.sign(&mut manifest).map_err(handle_core_error)?;
+    let manifest = serde_json::to_string(&manifest)
+        .map_err(|e| handle_core_error(CoreError::Internal(format!("Failed to serialize manifest: {}", e))))?;
+    
+    let mut headers = vec![
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        (HeaderName::from_static(SNAPSHOT_AT_HEADER), snapshot_at),
+        (HeaderName::from_static(EXPORT_MANIFEST_HEADER), manifest),
+    ];
+    if let Some(rows) = row_count {
+        headers.push((HeaderName::from_static(ROW_COUNT_HEADER), rows.to_string()));
+    }
+    let mut response = data.into_response();
+    for (name, value) in headers {
+        let value = value.parse()
+            .map_err(|_| handle_core_error(CoreError::Internal(format!("Invalid {} header", name))))?;
+        response.headers_mut().insert(name, value);
+    }
+    Ok(response)
+}
+
+/// Node and edge counts of a tenant's graph, for dashboards
+pub async fn graph_summary(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<GraphSummary>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Summarizing graph for tenant: {}", tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    
+    match state.core_service.summary(&tenant).await {
+        Ok(summary) => Ok(Json(ApiResponse::success(summary))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// JSON-LD context of a tenant's node and query responses, for consumers
+/// that reference it rather than reading it inline
+pub async fn json_ld_context(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Response {
+    let context = state.config.rdf.for_tenant(&TenantId::new(tenant_id)).json_ld_context();
+    json_ld(serde_json::json!({ "@context": context }))
+}
+
+/// Labels and relationship kinds of a tenant's graph with their property keys
+pub async fn graph_catalog(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<GraphCatalog>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Reading catalog for tenant: {}", tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    
+    match state.core_service.catalog(&tenant).await {
+        Ok(catalog) => Ok(Json(ApiResponse::success(catalog))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Human-readable document of a tenant's schema, as Markdown or HTML
+pub async fn schema_docs(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Query(params): Query<SchemaDocParams>,
+) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Documenting schema of tenant: {}", tenant_id);
+
+    let tenant = TenantId::new(tenant_id);
+    let schema = state.ingest_templates.schema(&tenant).await;
+    let sample = params.sample.unwrap_or(DEFAULT_SCHEMA_DOC_SAMPLE);
+    let doc = generate_schema_doc(state.core_service.as_ref(), &tenant, schema.as_ref(), sample).await
+        .map_err(|e| handle_core_error(e.into()))?;
+
+    Ok(([(header::CONTENT_TYPE, params.format.content_type())], doc.render(params.format)).into_response())
+}
+
+/// Relationship constraints of a tenant, checked at every edge upsert
+pub async fn get_edge_constraints(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<EdgeConstraints>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Reading edge constraints for tenant: {}", tenant_id);
+
+    let tenant = TenantId::new(tenant_id);
+
+    match state.core_service.edge_constraints(&tenant).await {
+        Ok(constraints) => Ok(Json(ApiResponse::success(constraints))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Replace the relationship constraints of a tenant; edges already stored
+/// are not checked
+pub async fn put_edge_constraints(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(constraints): Json<EdgeConstraints>,
+) -> Result<Json<ApiResponse<EdgeConstraints>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!("Setting {} exclusive relationship kinds for tenant: {}", constraints.exclusive.len(), tenant_id);
+
+    let tenant = TenantId::new(tenant_id);
+
+    match state.core_service.set_edge_constraints(&tenant, constraints.clone()).await {
+        Ok(()) => Ok(Json(ApiResponse::success(constraints))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Materialize a named snapshot of the graph valid at a time
+pub async fn materialize_snapshot(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<MaterializeSnapshotRequest>,
+) -> Result<Json<ApiResponse<SnapshotInfo>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!("Materializing snapshot '{}' for tenant: {}", request.name, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    
+    match state.core_service.materialize_snapshot(&tenant, &request.name, request.valid_at).await {
+        Ok(info) => {
+            info!("Materialized snapshot '{}' for tenant {}: {} nodes, {} edges", info.name, tenant, info.node_count, info.edge_count);
+            Ok(Json(ApiResponse::success(info)))
+        }
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// List a tenant's materialized snapshots
+pub async fn list_snapshots(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<SnapshotInfo>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Listing snapshots for tenant: {}", tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    
+    match state.core_service.list_snapshots(&tenant).await {
+        Ok(snapshots) => Ok(Json(ApiResponse::success(snapshots))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Drop a materialized snapshot
+pub async fn drop_snapshot(
+    State(state): State<AppState>,
+    Path((tenant_id, name)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    info!("Dropping snapshot '{}' for tenant: {}", name, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    
+    match state.core_service.drop_snapshot(&tenant, &name).await {
+        Ok(true) => Ok(Json(ApiResponse::success(()))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error(format!("Snapshot '{}' not found", name))),
+        )),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Execute a query against a materialized snapshot
+pub async fn query_snapshot(
+    State(state): State<AppState>,
+    Path((tenant_id, name)): Path<(String, String)>,
+    Json(request): Json<QueryRequest>,
+) -> Result<Json<ApiResponse<QueryResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    debug!("Querying snapshot '{}' for tenant: {}", name, tenant_id);
+    
+    let tenant = TenantId::new(tenant_id);
+    let start_time = std::time::Instant::now();
+    
+    // Snapshots are read whole anyway, so other modes are answered from the paths
+    let query = match request.mode {
+        QueryMode::Full => request.query,
+        QueryMode::Count => request.query.with_page(None, None),
+        QueryMode::Exists => request.query.with_page(None, Some(1)),
+    };
+    match state.core_service.query_snapshot(&tenant, &name, query).await {
+        Ok(paths) => {
+            let execution_time = start_time.elapsed();
+            let mut response = QueryResponse { paths: Vec::new(), count: None, exists: None, execution_time_ms: execution_time.as_millis() as u64 };
+            match request.mode {
+                QueryMode::Full => response.paths = paths,
+                QueryMode::Count => response.count = Some(paths.len() as u64),
+                QueryMode::Exists => response.exists = Some(!paths.is_empty()),
+            }
+            Ok(Json(ApiResponse::success(response)))
+        }
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Whether the request asks to bypass the query cache with `Cache-Control: no-cache`
+fn no_cache_requested(headers: &HeaderMap) -> bool {
+    headers.get(header::CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|directive| directive.trim().eq_ignore_ascii_case("no-cache")))
+}
+
+/// Whether the request asks for JSON-LD rather than the API's own JSON
+fn accepts_json_ld(headers: &HeaderMap) -> bool {
+    headers.get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|media_type| {
+            media_type.split(';').next().is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(JSON_LD))
+        }))
+}
+
+/// A JSON-LD document, sent without the `ApiResponse` envelope so that its
+/// `@context` applies to the whole body
+fn json_ld(document: serde_json::Value) -> Response {
+    ([(header::CONTENT_TYPE, JSON_LD)], Json(document)).into_response()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -322,9 +1310,27 @@ mod tests {
             .with_id_alias("test_123")
             .with_property("name", json!("Test"));
         
-        let request = UpsertNodeRequest { node };
+        let request = UpsertNodeRequest { node, write_concern: WriteConcern::Buffered };
         assert_eq!(request.node.label, "TestNode");
         assert_eq!(request.node.id_alias, Some("test_123".to_string()));
+        assert_eq!(write_status(request.write_concern), StatusCode::ACCEPTED);
+    }
+
+    #[test]
+    fn test_upsert_node_with_edges_request() {
+        let request: UpsertNodeWithEdgesRequest = serde_json::from_value(json!({
+            "node": {"id_alias": "alice", "label": "Person", "props": {}},
+            "edges": [
+                {"kind": "WORKS_FOR", "target": {"alias": {"namespace": "crm", "alias": "acme"}}},
+                {"kind": "KNOWS", "target": {"id": Uuid::nil()}, "direction": "incoming"}
+            ]
+        })).unwrap();
+        
+        assert_eq!(request.edges.len(), 2);
+        assert_eq!(request.edges[0].target, NodeRef::Alias(AliasKey::namespaced("crm", "acme")));
+        assert_eq!(request.edges[0].direction, EdgeDirection::Outgoing);
+        assert_eq!(request.edges[1].direction, EdgeDirection::Incoming);
+        assert_eq!(request.write_concern, WriteConcern::Committed);
     }
 
     #[test]
@@ -334,7 +1340,34 @@ mod tests {
             Node::new("Node2"),
         ];
         
-        let request = BatchUpsertNodesRequest { nodes };
+        let request = BatchUpsertNodesRequest { nodes, write_concern: WriteConcern::default() };
         assert_eq!(request.nodes.len(), 2);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_ndjson_lines_across_chunks() {
+        // Lines split across chunks, a blank line, and no final newline
+        let chunks = vec![
+            Ok::<_, std::io::Error>("{\"label\": \"A\", \"props\": {}}\n{\"label\"".to_string()),
+            Ok(": \"B\", \"props\": {}}\n\n{\"label\": \"C\", \"props\": {}}".to_string()),
+        ];
+        let nodes: Vec<Result<Node, String>> = ndjson_lines(Body::from_stream(stream::iter(chunks))).collect().await;
+        let labels: Vec<_> = nodes.into_iter().map(|node| node.unwrap().label).collect();
+        assert_eq!(labels, vec!["A", "B", "C"]);
+
+        let body = Body::from("{\"label\": \"A\", \"props\": {}}\nnot json\n");
+        let nodes: Vec<Result<Node, String>> = ndjson_lines(body).collect().await;
+        assert!(nodes[0].is_ok());
+        assert!(nodes[1].as_ref().unwrap_err().starts_with("Invalid JSON on line 2"));
+    }
+
+    #[test]
+    fn test_accepts_json_ld() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_json_ld(&headers));
+        headers.insert(header::ACCEPT, "application/json, application/ld+json; profile=\"http://www.w3.org/ns/json-ld#compacted\"".parse().unwrap());
+        assert!(accepts_json_ld(&headers));
+        headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+        assert!(!accepts_json_ld(&headers));
+    }
+}
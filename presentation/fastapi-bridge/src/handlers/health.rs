@@ -2,14 +2,19 @@
 
 use axum::{extract::State, http::StatusCode, response::Json};
 use serde::Serialize;
+use telamentis_core::prelude::*;
 use crate::{ApiResponse, AppState};
 
 /// Health check response
 #[derive(Debug, Serialize)]
 pub struct HealthStatus {
+    /// "healthy", or "degraded" when graph operations work but the LLM does not
     pub status: String,
     pub version: String,
     pub timestamp: String,
+    pub llm: CapabilityStatus,
+    /// Status of each LLM provider, probed for this request
+    pub providers: Vec<ProviderStatus>,
 }
 
 /// Health check endpoint
@@ -17,10 +22,14 @@ pub async fn health_check(State(state): State<AppState>) -> Result<Json<ApiRespo
     // Check core service health
     match state.core_service.health_check().await {
         Ok(_) => {
+            let llm = state.core_service.llm_status().await;
+            let providers = state.core_service.llm_providers(None).await;
             let health = HealthStatus {
-                status: "healthy".to_string(),
+                status: if llm.is_available() { "healthy" } else { "degraded" }.to_string(),
                 version: env!("CARGO_PKG_VERSION").to_string(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
+                llm,
+                providers,
             };
             Ok(Json(ApiResponse::success(health)))
         }
@@ -41,6 +50,8 @@ mod tests {
             status: "healthy".to_string(),
             version: "0.1.0".to_string(),
             timestamp: chrono::Utc::now().to_rfc3339(),
+            llm: CapabilityStatus::Available,
+            providers: Vec::new(),
         };
         
         assert_eq!(health.status, "healthy");
@@ -0,0 +1,254 @@
+//! Ingestion template, schema and CSV import handlers
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use telamentis_core::ingest_template::resolve_edge_aliases;
+use telamentis_core::prelude::*;
+use crate::handlers::dead_letter::dead_letter;
+use crate::{handle_core_error, ApiResponse, AppState};
+use tracing::{info, warn};
+
+/// Row errors reported in an import response; further errors are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Query parameters of a CSV import
+#[derive(Debug, Deserialize)]
+pub struct ImportCsvParams {
+    /// Name of the tenant's ingestion template to map rows with
+    pub template: String,
+    /// Field delimiter
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Whether the first row names the columns; templates refer to columns
+    /// by index otherwise
+    #[serde(default = "default_has_header")]
+    pub has_header: bool,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_has_header() -> bool {
+    true
+}
+
+/// A row that could not be imported
+#[derive(Debug, Serialize)]
+pub struct ImportRowError {
+    /// One-based row number, not counting the header
+    pub row: usize,
+    pub error: String,
+}
+
+/// Outcome of a CSV import
+#[derive(Debug, Serialize)]
+pub struct ImportCsvResponse {
+    pub rows: usize,
+    pub imported: usize,
+    pub failed: usize,
+    /// The first failed rows and why they failed
+    pub errors: Vec<ImportRowError>,
+}
+
+impl ImportCsvResponse {
+    fn fail(&mut self, row: usize, error: impl Into<String>) {
+        self.failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(ImportRowError { row, error: error.into() });
+        }
+    }
+}
+
+/// List a tenant's ingestion templates
+pub async fn list_templates(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Json<ApiResponse<Vec<IngestTemplate>>> {
+    Json(ApiResponse::success(state.ingest_templates.list(&TenantId::new(tenant_id)).await))
+}
+
+/// Get an ingestion template
+pub async fn get_template(
+    State(state): State<AppState>,
+    Path((tenant_id, name)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<IngestTemplate>>, (StatusCode, Json<ApiResponse<()>>)> {
+    match state.ingest_templates.get(&TenantId::new(tenant_id), &name).await {
+        Some(template) => Ok(Json(ApiResponse::success(template))),
+        None => Err(template_not_found(&name)),
+    }
+}
+
+/// Create or replace an ingestion template; the name in the path wins over
+/// the one in the body
+pub async fn put_template(
+    State(state): State<AppState>,
+    Path((tenant_id, name)): Path<(String, String)>,
+    Json(mut template): Json<IngestTemplate>,
+) -> Result<Json<ApiResponse<IngestTemplate>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    template.name = name;
+
+    match state.ingest_templates.put(&tenant, template.clone()).await {
+        Ok(replaced) => {
+            info!("{} ingestion template '{}' for tenant {}", if replaced { "Replaced" } else { "Created" }, template.name, tenant);
+            Ok(Json(ApiResponse::success(template)))
+        }
+        Err(e) => Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(format!("Invalid template: {}", e))))),
+    }
+}
+
+/// Delete an ingestion template
+pub async fn delete_template(
+    State(state): State<AppState>,
+    Path((tenant_id, name)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if !state.ingest_templates.remove(&TenantId::new(tenant_id), &name).await {
+        return Err(template_not_found(&name));
+    }
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Get a tenant's ingestion schema
+pub async fn get_schema(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<IngestSchema>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    match state.ingest_templates.schema(&tenant).await {
+        Some(schema) => Ok(Json(ApiResponse::success(schema))),
+        None => Err(schema_not_found(&tenant)),
+    }
+}
+
+/// Set or replace a tenant's ingestion schema
+pub async fn put_schema(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(schema): Json<IngestSchema>,
+) -> Result<Json<ApiResponse<IngestSchema>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    match state.ingest_templates.put_schema(&tenant, schema.clone()).await {
+        Ok(replaced) => {
+            info!("{} ingestion schema for tenant {}", if replaced { "Replaced" } else { "Created" }, tenant);
+            Ok(Json(ApiResponse::success(schema)))
+        }
+        Err(e) => Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(format!("Invalid schema: {}", e))))),
+    }
+}
+
+/// Delete a tenant's ingestion schema
+pub async fn delete_schema(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    if !state.ingest_templates.remove_schema(&tenant).await {
+        return Err(schema_not_found(&tenant));
+    }
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Import a CSV body, mapping each row with one of the tenant's templates
+/// and typing properties by the tenant's schema, if it has one. Rows that
+/// cannot be mapped or written are reported and skipped; records that fail
+/// to be written are also dead-lettered.
+pub async fn import_csv(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Query(params): Query<ImportCsvParams>,
+    body: String,
+) -> Result<Json<ApiResponse<ImportCsvResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    let template = state.ingest_templates.get(&tenant, &params.template).await
+        .ok_or_else(|| template_not_found(&params.template))?;
+    let schema = state.ingest_templates.schema(&tenant).await;
+    let valid_time = state.config.valid_time.for_tenant(&tenant);
+    let bad_request = |e: csv::Error| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(format!("Invalid CSV: {}", e))));
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(params.delimiter as u8)
+        .has_headers(params.has_header)
+        .flexible(true)
+        .from_reader(body.as_bytes());
+    let headers: Vec<String> = if params.has_header {
+        reader.headers().map_err(bad_request)?.iter().map(str::to_string).collect()
+    } else {
+        (0..reader.headers().map_err(bad_request)?.len()).map(|i| i.to_string()).collect()
+    };
+
+    let mut response = ImportCsvResponse { rows: 0, imported: 0, failed: 0, errors: Vec::new() };
+    let mut edges = Vec::new();
+    for record in reader.records() {
+        response.rows += 1;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                response.fail(response.rows, e.to_string());
+                continue;
+            }
+        };
+        let row: Vec<&str> = record.iter().collect();
+
+        match template.map_row_with_schema(&headers, &row, valid_time, schema.as_ref()) {
+            Ok(MappedRecord::Node(node)) => match state.core_service.upsert_node(&tenant, node.clone()).await {
+                Ok(_) => response.imported += 1,
+                Err(e) => {
+                    response.fail(response.rows, e.to_string());
+                    dead_letter(&state, &tenant, DeadLetterRecord::Node(node), e, "csv-import", row_context(&template, response.rows));
+                }
+            },
+            Ok(MappedRecord::Edge(edge)) => edges.push((response.rows, edge)),
+            Err(e) => response.fail(response.rows, e),
+        }
+    }
+
+    if !edges.is_empty() {
+        let (rows, mapped): (Vec<usize>, Vec<TimeEdge>) = edges.into_iter().unzip();
+        let mut resolved = mapped.clone();
+        resolve_edge_aliases(state.core_service.as_ref(), &tenant, &mut resolved).await
+            .map_err(|e| handle_core_error(e.into()))?;
+
+        for ((row, edge), original) in rows.into_iter().zip(resolved).zip(mapped) {
+            let result = if edge.from_node_id.is_nil() || edge.to_node_id.is_nil() {
+                Err("Unknown node alias".to_string())
+            } else {
+                state.core_service.upsert_edge(&tenant, edge).await.map_err(|e| e.to_string())
+            };
+            match result {
+                Ok(_) => response.imported += 1,
+                Err(e) => {
+                    response.fail(row, e.clone());
+                    dead_letter(&state, &tenant, DeadLetterRecord::Edge(original), e, "csv-import", row_context(&template, row));
+                }
+            }
+        }
+    }
+
+    if response.failed > 0 {
+        warn!("Import with template '{}' for tenant {} skipped {} of {} rows", template.name, tenant, response.failed, response.rows);
+    }
+    info!("Imported {} rows with template '{}' for tenant {}", response.imported, template.name, tenant);
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Dead-letter context of an import row
+fn row_context(template: &IngestTemplate, row: usize) -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("template".to_string(), template.name.clone()),
+        ("row".to_string(), row.to_string()),
+    ])
+}
+
+fn template_not_found(name: &str) -> (StatusCode, Json<ApiResponse<()>>) {
+    (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(format!("Ingestion template not found: {}", name))))
+}
+
+fn schema_not_found(tenant: &TenantId) -> (StatusCode, Json<ApiResponse<()>>) {
+    (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(format!("Tenant {} has no ingestion schema", tenant))))
+}
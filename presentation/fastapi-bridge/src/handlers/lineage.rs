@@ -0,0 +1,79 @@
+//! Extraction lineage handlers
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+use tracing::info;
+
+/// List a tenant's extraction runs, latest first, optionally only those of a
+/// model or prompt version
+pub async fn list_runs(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Query(filter): Query<RunFilter>,
+) -> Result<Json<ApiResponse<Vec<ExtractionRun>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    match state.lineage.runs(&TenantId::new(tenant_id), &filter).await {
+        Ok(runs) => Ok(Json(ApiResponse::success(runs))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Get an extraction run with the nodes and edges it wrote
+pub async fn get_run(
+    State(state): State<AppState>,
+    Path((tenant_id, run_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    let run_id = parse_run_id(&run_id)?;
+
+    let run = state.lineage.run(&tenant, run_id).await.map_err(|e| handle_core_error(e.into()))?;
+    let facts = state.lineage.facts(&tenant, run_id).await.map_err(|e| handle_core_error(e.into()))?;
+    match (run, facts) {
+        (Some(run), Some(facts)) => Ok(Json(ApiResponse::success(serde_json::json!({ "run": run, "facts": facts })))),
+        _ => Err(run_not_found()),
+    }
+}
+
+/// Delete the nodes and edges an extraction run created
+pub async fn purge_run(
+    State(state): State<AppState>,
+    Path((tenant_id, run_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<PurgeReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    let run_id = parse_run_id(&run_id)?;
+
+    match state.lineage.purge(&tenant, run_id).await {
+        Ok(report) => {
+            info!("Purged extraction run {} of tenant {}", run_id, tenant);
+            Ok(Json(ApiResponse::success(report)))
+        }
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+/// Extraction runs that wrote or closed a node or edge, latest first
+pub async fn fact_producers(
+    State(state): State<AppState>,
+    Path((tenant_id, fact_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<Vec<ExtractionRun>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let fact_id = Uuid::parse_str(&fact_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid node or edge ID format"))))?;
+
+    match state.lineage.producers(&TenantId::new(tenant_id), fact_id).await {
+        Ok(runs) => Ok(Json(ApiResponse::success(runs))),
+        Err(e) => Err(handle_core_error(e.into()))
+    }
+}
+
+fn parse_run_id(run_id: &str) -> Result<Uuid, (StatusCode, Json<ApiResponse<()>>)> {
+    Uuid::parse_str(run_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid extraction run ID format"))))
+}
+
+fn run_not_found() -> (StatusCode, Json<ApiResponse<()>>) {
+    (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error("Extraction run not found")))
+}
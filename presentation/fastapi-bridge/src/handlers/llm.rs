@@ -2,28 +2,41 @@
 
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::Json,
 };
 use telamentis_core::prelude::*;
-use crate::{handle_core_error, ApiResponse, AppState};
+use crate::{handle_core_error, ApiResponse, AppState, PRIORITY_HEADER};
 use tracing::{debug, info};
 
-/// Extract knowledge using LLM
+/// Extract knowledge using LLM. With `X-TelaMentis-Priority: background`,
+/// e.g. for re-extraction, the LLM call yields to interactive requests.
 pub async fn extract_knowledge(
     State(state): State<AppState>,
     Path(tenant_id): Path<String>,
+    headers: HeaderMap,
     Json(context): Json<ExtractionContext>,
 ) -> Result<Json<ApiResponse<ExtractionEnvelope>>, (StatusCode, Json<ApiResponse<()>>)> {
     debug!("Extracting knowledge for tenant: {}", tenant_id);
     
     let tenant = TenantId::new(tenant_id);
+    let source = context.source.clone().unwrap_or_default();
     
-    match state.core_service.extract_knowledge(&tenant, context).await {
-        Ok(envelope) => {
+    // Screen the input before it reaches the LLM
+    let (mut context, operation) = state.pipeline.prepare_extraction(&tenant, context).await
+        .map_err(handle_core_error)?;
+    state.examples.apply(&tenant, &mut context).await;
+    state.graph_context.apply(&tenant, &mut context).await;
+    
+    match with_priority(request_priority(&headers), state.core_service.extract_knowledge(&tenant, context)).await {
+        Ok(mut envelope) => {
+            state.config.valid_time.for_tenant(&tenant).apply_to_envelope(&mut envelope, &source);
+            if !operation.warnings.is_empty() {
+                envelope.metadata.get_or_insert_with(ExtractionMetadata::default).warnings.extend(operation.warnings.clone());
+            }
             info!("Extracted {} nodes and {} relations for tenant {}", 
                 envelope.nodes.len(), envelope.relations.len(), tenant);
-            Ok(Json(ApiResponse::success(envelope)))
+            Ok(Json(ApiResponse::success(envelope).with_operation(state.debug_operation(&headers, operation))))
         }
         Err(e) => Err(handle_core_error(CoreError::Llm(e)))
     }
@@ -51,6 +64,8 @@ pub async fn complete_text(
             output_tokens: Some(10),
             cost_usd: Some(0.001),
             warnings: Vec::new(),
+            model_selection: None,
+            citations: Vec::new(),
         }),
     };
     
@@ -58,6 +73,75 @@ pub async fn complete_text(
     Ok(Json(ApiResponse::success(response)))
 }
 
+/// Status and models of the LLM providers the tenant may use
+pub async fn list_providers(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ProviderStatus>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    Ok(Json(ApiResponse::success(state.core_service.llm_providers(Some(&tenant)).await)))
+}
+
+/// List the tenant's few-shot extraction examples
+pub async fn list_examples(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ExtractionExample>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    Ok(Json(ApiResponse::success(state.examples.list(&tenant).await)))
+}
+
+/// Add a few-shot extraction example
+pub async fn add_example(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(example): Json<ExtractionExample>,
+) -> Result<Json<ApiResponse<ExtractionExample>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    
+    state.examples.add(&tenant, example.clone()).await;
+    info!("Added extraction example {} for tenant {}", example.id, tenant);
+    Ok(Json(ApiResponse::success(example)))
+}
+
+/// Replace all of the tenant's few-shot extraction examples
+pub async fn replace_examples(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(examples): Json<Vec<ExtractionExample>>,
+) -> Result<Json<ApiResponse<Vec<ExtractionExample>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    
+    state.examples.replace(&tenant, examples.clone()).await;
+    info!("Replaced extraction examples for tenant {} ({} examples)", tenant, examples.len());
+    Ok(Json(ApiResponse::success(examples)))
+}
+
+/// Delete a few-shot extraction example
+pub async fn delete_example(
+    State(state): State<AppState>,
+    Path((tenant_id, example_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tenant = TenantId::new(tenant_id);
+    let id = Uuid::parse_str(&example_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid example ID format"))))?;
+    
+    if !state.examples.remove(&tenant, id).await {
+        return Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error("Example not found"))));
+    }
+    
+    info!("Deleted extraction example {} for tenant {}", id, tenant);
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Priority of a request's LLM calls, interactive unless the header says otherwise
+fn request_priority(headers: &HeaderMap) -> RequestPriority {
+    let background = headers.get(PRIORITY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("background"));
+    if background { RequestPriority::Background } else { RequestPriority::Interactive }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,11 +152,18 @@ mod tests {
             messages: vec![LlmMessage {
                 role: "user".to_string(),
                 content: "Alice works at Acme Corp".to_string(),
+                parts: Vec::new(),
             }],
             system_prompt: Some("Extract entities".to_string()),
             desired_schema: None,
             max_tokens: Some(1000),
             temperature: Some(0.1),
+            examples: None,
+            model: None,
+            provider: None,
+            source: None,
+            seed: None,
+            graph_context: None,
         };
         
         assert_eq!(context.messages.len(), 1);
@@ -83,12 +174,15 @@ mod tests {
     fn test_completion_request() {
         let request = CompletionRequest {
             prompt: "Complete this sentence".to_string(),
+            messages: Vec::new(),
+            system: None,
             max_tokens: Some(100),
             temperature: Some(0.7),
             params: serde_json::json!({}),
+            seed: None,
         };
         
         assert_eq!(request.prompt, "Complete this sentence");
         assert_eq!(request.max_tokens, Some(100));
     }
-}
\ No newline at end of file
+}
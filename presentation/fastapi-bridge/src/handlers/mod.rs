@@ -3,4 +3,19 @@
 pub mod health;
 pub mod tenant;
 pub mod graph;
-pub mod llm;
\ No newline at end of file
+pub mod ingest;
+pub mod llm;
+pub mod vector;
+pub mod archive;
+pub mod capture;
+pub mod exchange_log;
+pub mod analytics;
+pub mod token;
+pub mod session;
+pub mod dead_letter;
+pub mod sync;
+pub mod admin;
+pub mod operation;
+pub mod progress;
+pub mod lineage;
+pub mod views;
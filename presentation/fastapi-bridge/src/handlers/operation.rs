@@ -0,0 +1,50 @@
+//! Handlers of long-running operations in flight: queries, exports and
+//! extractions, which report their ID in the operation header
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use telamentis_core::prelude::*;
+use crate::{ApiResponse, AppState};
+
+/// List a tenant's operations in flight, oldest first
+pub async fn list_operations(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Json<ApiResponse<Vec<OperationInfo>>> {
+    Json(ApiResponse::success(state.operations.list(&TenantId::new(tenant_id))))
+}
+
+/// Get an operation in flight
+pub async fn get_operation(
+    State(state): State<AppState>,
+    Path((tenant_id, operation_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<OperationInfo>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let operation_id = parse_operation_id(&operation_id)?;
+    state.operations.get(&TenantId::new(tenant_id), operation_id)
+        .map(|operation| Json(ApiResponse::success(operation)))
+        .ok_or_else(|| operation_not_found(operation_id))
+}
+
+/// Cancel an operation in flight; its request fails with status 499
+pub async fn cancel_operation(
+    State(state): State<AppState>,
+    Path((tenant_id, operation_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<OperationInfo>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let operation_id = parse_operation_id(&operation_id)?;
+    state.operations.cancel(&TenantId::new(tenant_id), operation_id)
+        .map(|operation| Json(ApiResponse::success(operation)))
+        .ok_or_else(|| operation_not_found(operation_id))
+}
+
+pub(crate) fn parse_operation_id(operation_id: &str) -> Result<Uuid, (StatusCode, Json<ApiResponse<()>>)> {
+    Uuid::parse_str(operation_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid operation ID format"))))
+}
+
+fn operation_not_found(operation_id: Uuid) -> (StatusCode, Json<ApiResponse<()>>) {
+    let message = format!("No operation {} in flight; it may have finished already", operation_id);
+    (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(message)))
+}
@@ -0,0 +1,317 @@
+//! WebSocket of a tenant's long-running operations: queries stream their
+//! results a page at a time and extractions the chunks extracted, as the
+//! numbered events of the bridge's `ProgressHub`
+//!
+//! Clients send JSON messages tagged by `type`: `query` and `extract` start
+//! an operation, `resume` replays the events of one after the last the
+//! client saw, e.g. after reconnecting, and `cancel` cancels one. Operations
+//! keep running when the socket closes. The server sends each event as a
+//! `ProgressUpdate`, `started` when an operation starts, `error` for
+//! messages it cannot act on, and a `heartbeat` every `heartbeat_secs`.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use telamentis_core::prelude::*;
+use telamentis_core::progress::ProgressSubscription;
+use tokio::sync::{broadcast, mpsc};
+use crate::AppState;
+use tracing::{debug, info, warn};
+
+/// Rows a query sends per partial result unless the client asks otherwise
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Message from a client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Run a query, sending its results `page_size` rows at a time
+    Query {
+        query: GraphQuery,
+        #[serde(default)]
+        page_size: Option<u32>,
+        /// ID to run the operation under; a new one if omitted
+        #[serde(default)]
+        operation_id: Option<Uuid>,
+    },
+    /// Extract knowledge, reporting the chunks extracted
+    Extract {
+        context: ExtractionContext,
+        #[serde(default)]
+        operation_id: Option<Uuid>,
+    },
+    /// Send the events of an operation after `after`, then those to come
+    Resume {
+        operation_id: Uuid,
+        #[serde(default)]
+        after: Option<u64>,
+    },
+    /// Cancel an operation in flight
+    Cancel { operation_id: Uuid },
+}
+
+/// Message to a client other than an operation's events
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Started { operation: Uuid, kind: OperationKind },
+    Heartbeat { at: DateTime<Utc> },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        operation: Option<Uuid>,
+        error: String,
+    },
+}
+
+/// Upgrade to a WebSocket running and reporting the tenant's operations
+pub async fn progress_socket(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let tenant = TenantId::new(tenant_id);
+    // The socket is served in a task of its own, within the request's view
+    let view = current_view().map(|view| view.as_ref().clone());
+    ws.on_upgrade(move |socket| with_view(view, serve_socket(socket, state, tenant)))
+}
+
+async fn serve_socket(mut socket: WebSocket, state: AppState, tenant: TenantId) {
+    debug!("Progress socket opened for tenant {}", tenant);
+    let (outgoing, mut to_send) = mpsc::channel::<String>(64);
+    let mut heartbeat = tokio::time::interval(Duration::from_secs(state.config.heartbeat_secs.max(1)));
+
+    loop {
+        tokio::select! {
+            received = socket.recv() => match received {
+                Some(Ok(Message::Text(text))) => handle_message(&state, &tenant, &text, &outgoing),
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    debug!("Progress socket of tenant {} failed: {}", tenant, e);
+                    break;
+                }
+            },
+            Some(text) = to_send.recv() => {
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if socket.send(Message::Text(encode(&ServerMessage::Heartbeat { at: Utc::now() }))).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    debug!("Progress socket closed for tenant {}", tenant);
+}
+
+fn handle_message(state: &AppState, tenant: &TenantId, text: &str, outgoing: &mpsc::Sender<String>) {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => return reply_error(outgoing, None, format!("Invalid message: {}", e)),
+    };
+
+    match message {
+        ClientMessage::Query { query, page_size, operation_id } => {
+            let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+            let service = state.core_service.clone();
+            let owner = tenant.clone();
+            start(state, tenant, OperationKind::Query, operation_id, outgoing, |tracker| async move {
+                stream_query(service, &owner, query, page_size, tracker).await
+            });
+        }
+        ClientMessage::Extract { context, operation_id } => {
+            let state_for_work = state.clone();
+            let owner = tenant.clone();
+            start(state, tenant, OperationKind::Extraction, operation_id, outgoing, |_| async move {
+                extract(&state_for_work, &owner, context).await
+            });
+        }
+        ClientMessage::Resume { operation_id, after } => match state.progress.subscribe(tenant, operation_id, after) {
+            Some(subscription) => forward(subscription, outgoing.clone()),
+            None => reply_error(outgoing, Some(operation_id), format!("No progress kept for operation {}", operation_id)),
+        },
+        ClientMessage::Cancel { operation_id } => {
+            if state.operations.cancel(tenant, operation_id).is_none() {
+                reply_error(outgoing, Some(operation_id), format!("No operation {} in flight; it may have finished already", operation_id));
+            }
+        }
+    }
+}
+
+/// Start an operation whose events the socket receives, running `work`
+/// with its tracker until it completes, fails or is cancelled
+fn start<W, F>(
+    state: &AppState,
+    tenant: &TenantId,
+    kind: OperationKind,
+    id: Option<Uuid>,
+    outgoing: &mpsc::Sender<String>,
+    work: W,
+) where
+    W: FnOnce(ProgressTracker) -> F,
+    F: std::future::Future<Output = Result<serde_json::Value, CoreError>> + Send + 'static,
+{
+    let operation = match state.operations.start(tenant, kind, id) {
+        Ok(operation) => operation,
+        Err(e) => return reply_error(outgoing, id, e.to_string()),
+    };
+    let id = operation.id();
+    let tracker = state.progress.track(tenant, id);
+    let subscription = state.progress.subscribe(tenant, id, None).expect("tracked just now");
+    reply(outgoing, &ServerMessage::Started { operation: id, kind });
+    forward(subscription, outgoing.clone());
+
+    let work = work(tracker.clone());
+    // Spawned work stays within the socket's view
+    tokio::spawn(in_current_view(async move {
+        let work = with_progress(tracker.clone(), work);
+        match operation.run(work).await.and_then(|result| result) {
+            Ok(result) => tracker.complete(result),
+            Err(e) => {
+                info!("{} operation {} failed: {}", kind, id, e);
+                tracker.fail(e);
+            }
+        }
+    }));
+}
+
+/// Send missed and upcoming events of an operation to the socket until the
+/// operation finishes or the socket closes
+fn forward(subscription: ProgressSubscription, outgoing: mpsc::Sender<String>) {
+    let ProgressSubscription { missed, mut live, .. } = subscription;
+    tokio::spawn(async move {
+        let mut last = 0;
+        for update in missed {
+            last = update.seq;
+            if outgoing.send(encode(&update)).await.is_err() {
+                return;
+            }
+        }
+        loop {
+            match live.recv().await {
+                // Replayed already
+                Ok(update) if update.seq <= last => {}
+                Ok(update) => {
+                    if outgoing.send(encode(&update)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Progress socket fell {} events behind; the client can resume", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    });
+}
+
+/// Run a query a page at a time, reporting each page as partial results
+async fn stream_query(
+    service: Arc<dyn GraphService>,
+    tenant: &TenantId,
+    query: GraphQuery,
+    page_size: u32,
+    tracker: ProgressTracker,
+) -> Result<serde_json::Value, CoreError> {
+    let Some((start, limit)) = requested_page(&query) else {
+        // Raw queries cannot be paged
+        let paths = service.query(tenant, query).await?;
+        let rows = paths.len() as u64;
+        tracker.partial(serde_json::to_value(paths).unwrap_or_default());
+        tracker.update(|progress| progress.processed = rows);
+        return Ok(serde_json::json!({ "rows": rows }));
+    };
+
+    let matching = service.query_count(tenant, query.clone()).await?.saturating_sub(start as u64);
+    let total = limit.map_or(matching, |limit| matching.min(limit as u64));
+    tracker.update(|progress| progress.total = Some(total));
+
+    let mut rows = 0;
+    while rows < total {
+        let wanted = (total - rows).min(page_size as u64) as u32;
+        let page = query.clone().with_page(Some(start + rows as u32), Some(wanted));
+        let paths = service.query(tenant, page).await?;
+        let read = paths.len() as u64;
+        rows += read;
+        tracker.partial(serde_json::to_value(paths).unwrap_or_default());
+        tracker.update(|progress| progress.processed = rows);
+        if read < wanted as u64 {
+            break;
+        }
+    }
+    Ok(serde_json::json!({ "rows": rows }))
+}
+
+/// Offset and limit of a structured query; `None` for raw queries
+fn requested_page(query: &GraphQuery) -> Option<(u32, Option<u32>)> {
+    match query {
+        GraphQuery::FindNodes { offset, limit, .. } | GraphQuery::FindRelationships { offset, limit, .. } => {
+            Some((offset.unwrap_or(0), *limit))
+        }
+        GraphQuery::AsOfQuery { base_query, .. } => requested_page(base_query),
+        GraphQuery::Raw { .. } => None,
+    }
+}
+
+/// Extract knowledge like `POST /llm/{tenant_id}/extract`
+async fn extract(state: &AppState, tenant: &TenantId, context: ExtractionContext) -> Result<serde_json::Value, CoreError> {
+    let source = context.source.clone().unwrap_or_default();
+    let (mut context, operation) = state.pipeline.prepare_extraction(tenant, context).await?;
+    state.examples.apply(tenant, &mut context).await;
+    state.graph_context.apply(tenant, &mut context).await;
+
+    let mut envelope = state.core_service.extract_knowledge(tenant, context).await?;
+    state.config.valid_time.for_tenant(tenant).apply_to_envelope(&mut envelope, &source);
+    if !operation.warnings.is_empty() {
+        envelope.metadata.get_or_insert_with(ExtractionMetadata::default).warnings.extend(operation.warnings);
+    }
+    info!("Extracted {} nodes and {} relations for tenant {}", envelope.nodes.len(), envelope.relations.len(), tenant);
+    serde_json::to_value(envelope).map_err(|e| CoreError::Internal(format!("Failed to encode the envelope: {}", e)))
+}
+
+fn reply(outgoing: &mpsc::Sender<String>, message: &ServerMessage) {
+    // A full queue means the client stopped reading; it can resume
+    let _ = outgoing.try_send(encode(message));
+}
+
+fn reply_error(outgoing: &mpsc::Sender<String>, operation: Option<Uuid>, error: String) {
+    reply(outgoing, &ServerMessage::Error { operation, error });
+}
+
+fn encode(message: &impl Serialize) -> String {
+    serde_json::to_string(message).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_messages() {
+        let message: ClientMessage = serde_json::from_str(r#"{
+            "type": "query",
+            "query": {"FindNodes": {"labels": ["Person"], "properties": {}, "offset": 10, "limit": 25}},
+            "page_size": 5
+        }"#).unwrap();
+        let ClientMessage::Query { query, page_size, operation_id } = message else { panic!("not a query") };
+        assert_eq!((requested_page(&query), page_size, operation_id), (Some((10, Some(25))), Some(5), None));
+
+        let message: ClientMessage = serde_json::from_str(
+            r#"{"type": "resume", "operation_id": "6f1c2a7e-8a57-4d43-9b8e-2d9a7c1b3e00", "after": 4}"#
+        ).unwrap();
+        assert!(matches!(message, ClientMessage::Resume { after: Some(4), .. }));
+
+        let heartbeat = encode(&ServerMessage::Heartbeat { at: Utc::now() });
+        assert!(heartbeat.starts_with(r#"{"type":"heartbeat""#));
+    }
+}
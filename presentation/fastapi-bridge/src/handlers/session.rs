@@ -0,0 +1,110 @@
+//! Ephemeral session graph handlers
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+
+/// Request to start a session
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateSessionRequest {
+    /// Inactivity after which the session expires, in seconds; the
+    /// configured default if omitted
+    pub ttl_secs: Option<u64>,
+}
+
+/// List a tenant's live sessions
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<EphemeralSession>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sessions = session_graphs(&state)?;
+    Ok(Json(ApiResponse::success(sessions.list(&TenantId::new(tenant_id)))))
+}
+
+/// Start a session; its graph is served under `/graph/{graph}`
+pub async fn create_session(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    request: Option<Json<CreateSessionRequest>>,
+) -> Result<Json<ApiResponse<EphemeralSession>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sessions = session_graphs(&state)?;
+    let ttl_secs = request.and_then(|Json(request)| request.ttl_secs);
+
+    match sessions.create(&TenantId::new(tenant_id), ttl_secs) {
+        Ok(session) => Ok(Json(ApiResponse::success(session))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Get a live session
+pub async fn get_session(
+    State(state): State<AppState>,
+    Path((tenant_id, session_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<EphemeralSession>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sessions = session_graphs(&state)?;
+    let session_id = parse_session_id(&session_id)?;
+
+    match sessions.get(&TenantId::new(tenant_id), session_id) {
+        Ok(session) => Ok(Json(ApiResponse::success(session))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Keep a session alive without using its graph
+pub async fn touch_session(
+    State(state): State<AppState>,
+    Path((tenant_id, session_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<EphemeralSession>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sessions = session_graphs(&state)?;
+    let session_id = parse_session_id(&session_id)?;
+
+    match sessions.touch(&TenantId::new(tenant_id), session_id) {
+        Ok(session) => Ok(Json(ApiResponse::success(session))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Copy selected nodes and edges of a session into the tenant's graph
+pub async fn promote_session(
+    State(state): State<AppState>,
+    Path((tenant_id, session_id)): Path<(String, String)>,
+    Json(request): Json<PromoteRequest>,
+) -> Result<Json<ApiResponse<PromotionReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sessions = session_graphs(&state)?;
+    let session_id = parse_session_id(&session_id)?;
+
+    match sessions.promote(&TenantId::new(tenant_id), session_id, &request).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+/// End a session now, removing its graph; returns the number of records removed
+pub async fn end_session(
+    State(state): State<AppState>,
+    Path((tenant_id, session_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<u64>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sessions = session_graphs(&state)?;
+    let session_id = parse_session_id(&session_id)?;
+
+    match sessions.end(&TenantId::new(tenant_id), session_id).await {
+        Ok(removed) => Ok(Json(ApiResponse::success(removed))),
+        Err(e) => Err(handle_core_error(e)),
+    }
+}
+
+fn parse_session_id(session_id: &str) -> Result<Uuid, (StatusCode, Json<ApiResponse<()>>)> {
+    Uuid::parse_str(session_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid session ID format"))))
+}
+
+fn session_graphs(state: &AppState) -> Result<Arc<SessionGraphs>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.sessions.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("Session graphs are not enabled"))))
+}
@@ -0,0 +1,63 @@
+//! Graph sync handlers
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{ApiResponse, AppState};
+
+/// Query parameters for pulling changes
+#[derive(Debug, Deserialize)]
+pub struct ChangesParams {
+    /// Cursor of the last change already seen; 0 for all
+    #[serde(default)]
+    pub since: u64,
+    /// Changes to return at most
+    pub limit: Option<usize>,
+    /// Leave out changes that originated on this instance
+    pub exclude_origin: Option<String>,
+}
+
+/// Sync cursor of a tenant and how far it has applied each peer's changes
+pub async fn sync_status(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<SyncStatus>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sync = sync_engine(&state)?;
+    Ok(Json(ApiResponse::success(sync.status(&TenantId::new(tenant_id)))))
+}
+
+/// Changes to a tenant's graph after a cursor, for a peer to apply
+pub async fn list_changes(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Query(params): Query<ChangesParams>,
+) -> Result<Json<ApiResponse<ChangeBatch>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sync = sync_engine(&state)?;
+    let batch = sync.changes(&TenantId::new(tenant_id), params.since, params.limit, params.exclude_origin.as_deref());
+    Ok(Json(ApiResponse::success(batch)))
+}
+
+/// Apply changes pulled from a peer
+pub async fn apply_changes(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(batch): Json<ChangeBatch>,
+) -> Result<Json<ApiResponse<ApplyReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let sync = sync_engine(&state)?;
+    if batch.instance_id == sync.instance_id() {
+        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Cannot apply changes from this instance to itself"))));
+    }
+
+    let report = sync.apply(state.core_service.as_ref(), &TenantId::new(tenant_id), batch).await;
+    Ok(Json(ApiResponse::success(report)))
+}
+
+fn sync_engine(state: &AppState) -> Result<Arc<SyncEngine>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.sync.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("Sync is not enabled"))))
+}
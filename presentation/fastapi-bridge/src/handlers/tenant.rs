@@ -27,9 +27,11 @@ pub async fn list_tenants(
 /// Create a new tenant
 pub async fn create_tenant(
     State(state): State<AppState>,
-    Json(tenant_info): Json<TenantInfo>,
+    Json(mut tenant_info): Json<TenantInfo>,
 ) -> Result<Json<ApiResponse<TenantInfo>>, (StatusCode, Json<ApiResponse<()>>)> {
     info!("Creating tenant: {}", tenant_info.id);
+    tenant_info.id = TenantId::parse(tenant_info.id.as_str())
+        .map_err(|e| handle_core_error(GraphError::from(e).into()))?;
     
     // Note: In a real implementation, this would use a TenantManager
     // For now, we'll just return the tenant info as-is
@@ -83,10 +85,12 @@ pub async fn delete_tenant(
 ) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
     info!("Deleting tenant: {}", tenant_id);
     
-    // Note: In a real implementation, this would use a TenantManager
-    // and actually delete the tenant and all its data
+    // Note: In a real implementation, this would also remove the tenant
+    // from a TenantManager; here we remove all of its graph data
+    let removed = state.core_service.clear_tenant(&TenantId::new(&tenant_id)).await
+        .map_err(|e| handle_core_error(e.into()))?;
     
-    info!("Deleted tenant: {}", tenant_id);
+    info!("Deleted tenant: {} ({} records removed)", tenant_id, removed);
     Ok(Json(ApiResponse::success(())))
 }
 
@@ -0,0 +1,147 @@
+//! API token handlers of the tenant admin API
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+
+/// How long a rotated token keeps working if the request does not say
+const DEFAULT_ROTATION_OVERLAP_SECS: i64 = 3600;
+
+/// Request to create a token
+#[derive(Debug, Deserialize)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    pub scope: TokenScope,
+    /// Lifetime in seconds; the token does not expire if omitted
+    pub ttl_secs: Option<i64>,
+    /// Graph view to confine the token to
+    #[serde(default)]
+    pub view: Option<String>,
+}
+
+/// Request to rotate a token
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateTokenRequest {
+    /// How long the old secret keeps working, in seconds
+    pub overlap_secs: Option<i64>,
+}
+
+/// List a tenant's tokens, including revoked and expired ones
+pub async fn list_tokens(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ApiToken>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tokens = token_manager(&state)?;
+
+    match tokens.list(&TenantId::new(tenant_id)).await {
+        Ok(tokens) => Ok(Json(ApiResponse::success(tokens))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Create a token; the response is the only time its secret is returned
+pub async fn create_token(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<CreateTokenRequest>,
+) -> Result<Json<ApiResponse<IssuedToken>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tokens = token_manager(&state)?;
+    if request.ttl_secs.is_some_and(|ttl| ttl <= 0) {
+        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("'ttl_secs' must be positive"))));
+    }
+
+    let tenant = TenantId::new(tenant_id);
+    // Tokens created within a view are confined to it too
+    let view = match current_view() {
+        Some(current) if request.view.as_ref().is_some_and(|view| *view != current.name) => {
+            let message = format!("Requests confined to view '{}' can only create tokens confined to it", current.name);
+            return Err((StatusCode::FORBIDDEN, Json(ApiResponse::<()>::error(message))));
+        }
+        Some(current) => Some(current.name.clone()),
+        None => request.view,
+    };
+    let views = match &view {
+        Some(name) => {
+            let views = state.views.clone()
+                .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("Graph views are not enabled"))))?;
+            if views.get(&tenant, name).is_none() {
+                return Err(handle_core_error(ViewError::NotFound(name.clone()).into()));
+            }
+            Some(views)
+        }
+        None => None,
+    };
+
+    let ttl = request.ttl_secs.map(chrono::Duration::seconds);
+    let issued = tokens.create(&tenant, &request.name, request.scope, ttl).await
+        .map_err(|e| handle_core_error(e.into()))?;
+    if let (Some(views), Some(view)) = (views, view) {
+        if let Err(e) = views.assign(&tenant, &issued.token.id.to_string(), &view) {
+            // The view was removed meanwhile; the token must not work unconfined
+            let _ = tokens.revoke(&tenant, issued.token.id).await;
+            return Err(handle_core_error(e.into()));
+        }
+    }
+    Ok(Json(ApiResponse::success(issued)))
+}
+
+/// Revoke a token immediately, lifting its confinement to a view
+pub async fn revoke_token(
+    State(state): State<AppState>,
+    Path((tenant_id, token_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<ApiToken>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tokens = token_manager(&state)?;
+    let token_id = parse_token_id(&token_id)?;
+
+    let tenant = TenantId::new(tenant_id);
+    match tokens.revoke(&tenant, token_id).await {
+        Ok(token) => {
+            if let Some(views) = &state.views {
+                views.unassign(&tenant, &token_id.to_string());
+            }
+            Ok(Json(ApiResponse::success(token)))
+        }
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Issue a successor of a token, keeping the old secret valid for an overlap
+pub async fn rotate_token(
+    State(state): State<AppState>,
+    Path((tenant_id, token_id)): Path<(String, String)>,
+    request: Option<Json<RotateTokenRequest>>,
+) -> Result<Json<ApiResponse<IssuedToken>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let tokens = token_manager(&state)?;
+    let token_id = parse_token_id(&token_id)?;
+    let overlap_secs = request.and_then(|Json(request)| request.overlap_secs).unwrap_or(DEFAULT_ROTATION_OVERLAP_SECS);
+    if overlap_secs < 0 {
+        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("'overlap_secs' must not be negative"))));
+    }
+
+    let tenant = TenantId::new(tenant_id);
+    let issued = tokens.rotate(&tenant, token_id, chrono::Duration::seconds(overlap_secs)).await
+        .map_err(|e| handle_core_error(e.into()))?;
+    // The successor is confined to the view of the token it replaces
+    if let Some(views) = &state.views {
+        if let Some(view) = views.view_for(&tenant, &token_id.to_string()) {
+            views.assign(&tenant, &issued.token.id.to_string(), &view.name).map_err(|e| handle_core_error(e.into()))?;
+        }
+    }
+    Ok(Json(ApiResponse::success(issued)))
+}
+
+fn parse_token_id(token_id: &str) -> Result<Uuid, (StatusCode, Json<ApiResponse<()>>)> {
+    Uuid::parse_str(token_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid token ID format"))))
+}
+
+fn token_manager(state: &AppState) -> Result<Arc<ApiTokenManager>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.tokens.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("API tokens are not enabled"))))
+}
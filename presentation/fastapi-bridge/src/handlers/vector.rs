@@ -0,0 +1,96 @@
+//! Vector search handlers
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+use tracing::{debug, info};
+
+/// Default number of matches returned by a search
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// Request to store an embedding
+#[derive(Debug, Deserialize)]
+pub struct UpsertVectorRequest {
+    pub vector: Vec<f32>,
+}
+
+/// Request to find the nearest embeddings
+#[derive(Debug, Deserialize)]
+pub struct SearchVectorsRequest {
+    pub vector: Vec<f32>,
+    pub k: Option<usize>,
+}
+
+/// Nearest embeddings, closest first
+#[derive(Debug, Serialize)]
+pub struct SearchVectorsResponse {
+    pub matches: Vec<VectorMatch>,
+}
+
+/// Store the embedding for an ID, usually a node ID
+pub async fn upsert_vector(
+    State(state): State<AppState>,
+    Path((tenant_id, id)): Path<(String, String)>,
+    Json(request): Json<UpsertVectorRequest>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let vectors = vector_index(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    let id = parse_id(&id)?;
+
+    vectors.upsert_vector(&tenant, id, request.vector).await
+        .map_err(|e| handle_core_error(e.into()))?;
+
+    debug!("Stored embedding {} for tenant {}", id, tenant);
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Remove the embedding for an ID
+pub async fn delete_vector(
+    State(state): State<AppState>,
+    Path((tenant_id, id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let vectors = vector_index(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    let id = parse_id(&id)?;
+
+    match vectors.remove_vector(&tenant, id).await {
+        Ok(true) => {
+            info!("Removed embedding {} for tenant {}", id, tenant);
+            Ok(Json(ApiResponse::success(())))
+        }
+        Ok(false) => Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error("Embedding not found")))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Find the embeddings nearest to a query vector
+pub async fn search_vectors(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<SearchVectorsRequest>,
+) -> Result<Json<ApiResponse<SearchVectorsResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let vectors = vector_index(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    let k = request.k.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    match vectors.search(&tenant, &request.vector, k).await {
+        Ok(matches) => Ok(Json(ApiResponse::success(SearchVectorsResponse { matches }))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+fn vector_index(state: &AppState) -> Result<Arc<dyn VectorIndex>, (StatusCode, Json<ApiResponse<()>>)> {
+    state.vectors.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("No vector index is configured"))))
+}
+
+fn parse_id(id: &str) -> Result<Uuid, (StatusCode, Json<ApiResponse<()>>)> {
+    Uuid::parse_str(id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("Invalid ID format"))))
+}
@@ -0,0 +1,128 @@
+//! Graph view handlers of the tenant admin API. Principals are token IDs;
+//! requests confined to a view cannot manage views.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use telamentis_core::prelude::*;
+use crate::{handle_core_error, ApiResponse, AppState};
+
+/// Request to define a view; its name is that of the path
+#[derive(Debug, Default, Deserialize)]
+pub struct DefineViewRequest {
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    #[serde(default)]
+    pub properties: HashMap<String, serde_json::Value>,
+}
+
+/// A view and the principals confined to it
+#[derive(Debug, Serialize)]
+pub struct ViewInfo {
+    #[serde(flatten)]
+    pub view: GraphView,
+    pub principals: Vec<String>,
+}
+
+/// List a tenant's views
+pub async fn list_views(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ViewInfo>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let views = graph_views(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    let infos = views.list(&tenant).into_iter()
+        .map(|view| ViewInfo { principals: views.principals(&tenant, &view.name), view })
+        .collect();
+    Ok(Json(ApiResponse::success(infos)))
+}
+
+/// Get a view and the principals confined to it
+pub async fn get_view(
+    State(state): State<AppState>,
+    Path((tenant_id, name)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<ViewInfo>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let views = graph_views(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    match views.get(&tenant, &name) {
+        Some(view) => Ok(Json(ApiResponse::success(ViewInfo { principals: views.principals(&tenant, &name), view }))),
+        None => Err(handle_core_error(ViewError::NotFound(name).into())),
+    }
+}
+
+/// Define a view, replacing the one of the same name; principals confined
+/// to it see the new definition from their next request
+pub async fn define_view(
+    State(state): State<AppState>,
+    Path((tenant_id, name)): Path<(String, String)>,
+    Json(request): Json<DefineViewRequest>,
+) -> Result<Json<ApiResponse<GraphView>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let views = graph_views(&state)?;
+    let view = GraphView { name, labels: request.labels, kinds: request.kinds, properties: request.properties };
+
+    match views.define(&TenantId::new(tenant_id), view.clone()) {
+        Ok(()) => Ok(Json(ApiResponse::success(view))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Remove a view no principal is confined to
+pub async fn remove_view(
+    State(state): State<AppState>,
+    Path((tenant_id, name)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<GraphView>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let views = graph_views(&state)?;
+
+    match views.remove(&TenantId::new(tenant_id), &name) {
+        Ok(view) => Ok(Json(ApiResponse::success(view))),
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Confine a principal to a view, instead of any view it had
+pub async fn assign_view(
+    State(state): State<AppState>,
+    Path((tenant_id, name, principal)): Path<(String, String, String)>,
+) -> Result<Json<ApiResponse<ViewInfo>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let views = graph_views(&state)?;
+    let tenant = TenantId::new(tenant_id);
+
+    match views.assign(&tenant, &principal, &name) {
+        Ok(()) => get_view(State(state), Path((tenant.to_string(), name))).await,
+        Err(e) => Err(handle_core_error(e.into())),
+    }
+}
+
+/// Lift a principal's confinement to a view
+pub async fn unassign_view(
+    State(state): State<AppState>,
+    Path((tenant_id, name, principal)): Path<(String, String, String)>,
+) -> Result<Json<ApiResponse<ViewInfo>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let views = graph_views(&state)?;
+    let tenant = TenantId::new(tenant_id);
+    if views.view_for(&tenant, &principal).is_none_or(|view| view.name != name) {
+        let message = format!("Principal {} is not confined to view {}", principal, name);
+        return Err((StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(message))));
+    }
+
+    views.unassign(&tenant, &principal);
+    get_view(State(state), Path((tenant.to_string(), name))).await
+}
+
+/// The bridge's views, unless views are not enabled or the request is
+/// confined to one, which must not lift its own confinement
+fn graph_views(state: &AppState) -> Result<Arc<GraphViews>, (StatusCode, Json<ApiResponse<()>>)> {
+    if let Some(view) = current_view() {
+        let message = format!("Requests confined to view '{}' cannot manage views", view.name);
+        return Err((StatusCode::FORBIDDEN, Json(ApiResponse::<()>::error(message))));
+    }
+    state.views.clone()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("Graph views are not enabled"))))
+}
@@ -6,9 +6,9 @@
 use async_trait::async_trait;
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::Json,
-    routing::{get, post, put, delete},
+    routing::{get, post, put, patch, delete},
     Router,
 };
 use serde::{Deserialize, Serialize};
@@ -16,11 +16,13 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use telamentis_core::prelude::*;
-use telamentis_core::pipeline::{PipelineRunner, PipelineStage, RequestLoggingPlugin, TenantValidationPlugin, AuditTrailPlugin};
+use telamentis_core::pipeline::{PipelineRunner, PipelineStage, PluginRegistry, RequestLoggingPlugin, TenantPipelines, TenantValidationPlugin, AuditTrailPlugin};
+use telamentis_core::warnings::take_warnings;
 use tower::ServiceBuilder;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use tracing::{debug, error, info, warn};
 
+mod columnar;
 mod handlers;
 mod middleware;
 mod models;
@@ -36,6 +38,30 @@ pub struct FastApiBridgeConfig {
     pub enable_cors: bool,
     /// Request timeout in seconds
     pub request_timeout: u64,
+    /// Per-tenant defaults for the valid times of extracted relations
+    pub valid_time: ValidTimePolicies,
+    /// Per-tenant summaries of the graph added to extraction prompts
+    pub graph_context: GraphContextPolicies,
+    /// Per-tenant handling of envelopes applied with `apply-envelope`
+    pub envelopes: EnvelopePolicies,
+    /// Return pipeline metadata to requests that send `X-TelaMentis-Debug`
+    pub debug_metadata: bool,
+    /// Verify HMAC request signatures of the configured tenants
+    pub request_signing: Option<SigningConfig>,
+    /// Per-tenant IRIs of labels, kinds and properties in RDF exports and
+    /// JSON-LD responses
+    pub rdf: RdfMappings,
+    /// Run the doctor before serving and refuse to start if a check fails
+    pub self_test: bool,
+    /// Pipelines of tenants that do not use the default plugins
+    pub pipelines: TenantPipelines,
+    /// Serve development-only admin routes, such as seeding fixtures. Never
+    /// enable in production: seeding clears tenants.
+    pub dev_mode: bool,
+    /// Sampling and destination of the access log
+    pub access_log: AccessLogConfig,
+    /// Seconds between heartbeats on progress WebSockets
+    pub heartbeat_secs: u64,
 }
 
 impl Default for FastApiBridgeConfig {
@@ -44,14 +70,57 @@ impl Default for FastApiBridgeConfig {
             bind_address: "0.0.0.0:3000".parse().unwrap(),
             enable_cors: true,
             request_timeout: 30,
+            valid_time: ValidTimePolicies::default(),
+            graph_context: GraphContextPolicies::default(),
+            envelopes: EnvelopePolicies::default(),
+            debug_metadata: false,
+            request_signing: None,
+            rdf: RdfMappings::default(),
+            self_test: false,
+            pipelines: TenantPipelines::default(),
+            dev_mode: false,
+            access_log: AccessLogConfig::default(),
+            heartbeat_secs: 15,
         }
     }
 }
 
+/// Request header asking for pipeline metadata in the response
+pub const DEBUG_HEADER: &str = "x-telamentis-debug";
+
+/// Request header queueing a request's LLM calls as `background` work
+pub const PRIORITY_HEADER: &str = "x-telamentis-priority";
+
+/// Request header choosing the ID of a cancellable operation, and response
+/// header carrying it
+pub const OPERATION_HEADER: &str = "x-telamentis-operation";
+
+/// Status of cancelled operations, nginx's "client closed request"
+const CLIENT_CLOSED_REQUEST: u16 = 499;
+
 /// FastAPI bridge presentation adapter
 pub struct FastApiBridge {
     config: FastApiBridgeConfig,
+    export_keys: Option<Arc<ExportKeys>>,
     pipeline: Arc<PipelineRunner>,
+    examples: Arc<FewShotStore>,
+    ingest_templates: Arc<IngestTemplateStore>,
+    vectors: Option<Arc<dyn VectorIndex>>,
+    embeddings: Option<Arc<EmbeddingOrchestrator>>,
+    archive: Option<Arc<ArchiveJob>>,
+    capture: Option<Arc<RequestCapture>>,
+    analytics: Option<Arc<AnalyticsJob>>,
+    tokens: Option<Arc<ApiTokenManager>>,
+    exchange_log: Option<Arc<ExchangeLog>>,
+    sessions: Option<Arc<SessionGraphs>>,
+    dead_letters: Option<Arc<DeadLetterQueue>>,
+    sync: Option<Arc<SyncEngine>>,
+    doctor: Option<Arc<Doctor>>,
+    plugins: Arc<PluginRegistry>,
+    admin: Option<Arc<AdminControls>>,
+    operations: Arc<OperationRegistry>,
+    progress: Arc<ProgressHub>,
+    views: Option<Arc<GraphViews>>,
 }
 
 impl FastApiBridge {
@@ -62,11 +131,34 @@ impl FastApiBridge {
         // Register built-in plugins
         pipeline.register_plugin(PipelineStage::PreOperation, Arc::new(RequestLoggingPlugin::new()));
         pipeline.register_plugin(PipelineStage::PreOperation, Arc::new(TenantValidationPlugin::new()));
+        pipeline.register_plugin(PipelineStage::PreExtraction, Arc::new(ExtractionSafetyPlugin::new()));
         pipeline.register_plugin(PipelineStage::PostOperation, Arc::new(AuditTrailPlugin::new()));
+        if let Some(signing) = &config.request_signing {
+            pipeline.register_plugin(PipelineStage::Verification, Arc::new(RequestSigningPlugin::new(signing.clone())));
+        }
         
         Self { 
             config,
+            export_keys: None,
             pipeline: Arc::new(pipeline),
+            examples: Arc::new(FewShotStore::new()),
+            ingest_templates: Arc::new(IngestTemplateStore::new()),
+            vectors: None,
+            embeddings: None,
+            archive: None,
+            capture: None,
+            analytics: None,
+            tokens: None,
+            exchange_log: None,
+            sessions: None,
+            dead_letters: None,
+            sync: None,
+            doctor: None,
+            plugins: Arc::new(PluginRegistry::with_builtins()),
+            admin: None,
+            operations: Arc::new(OperationRegistry::new()),
+            progress: Arc::new(ProgressHub::default()),
+            views: None,
         }
     }
     
@@ -74,52 +166,251 @@ impl FastApiBridge {
     pub fn new_with_pipeline(config: FastApiBridgeConfig, pipeline: PipelineRunner) -> Self {
         Self {
             config,
+            export_keys: None,
             pipeline: Arc::new(pipeline),
+            examples: Arc::new(FewShotStore::new()),
+            ingest_templates: Arc::new(IngestTemplateStore::new()),
+            vectors: None,
+            embeddings: None,
+            archive: None,
+            capture: None,
+            analytics: None,
+            tokens: None,
+            exchange_log: None,
+            sessions: None,
+            dead_letters: None,
+            sync: None,
+            doctor: None,
+            plugins: Arc::new(PluginRegistry::with_builtins()),
+            admin: None,
+            operations: Arc::new(OperationRegistry::new()),
+            progress: Arc::new(ProgressHub::default()),
+            views: None,
+        }
+    }
+
+    /// Per-tenant few-shot examples used for extraction requests
+    pub fn example_store(&self) -> Arc<FewShotStore> {
+        self.examples.clone()
+    }
+
+    /// Per-tenant ingestion templates used by the CSV import endpoint
+    pub fn ingest_template_store(&self) -> Arc<IngestTemplateStore> {
+        self.ingest_templates.clone()
+    }
+
+    /// Serve similarity search from the given vector index
+    pub fn with_vector_index(mut self, vectors: Arc<dyn VectorIndex>) -> Self {
+        self.vectors = Some(vectors);
+        self
+    }
+
+    /// Embed the nodes of applied extraction envelopes with the given
+    /// orchestrator, e.g. one storing embeddings in the served vector index
+    pub fn with_embeddings(mut self, embeddings: Arc<EmbeddingOrchestrator>) -> Self {
+        self.embeddings = Some(embeddings);
+        self
+    }
+
+    /// Serve archival and restore of closed history through the given job
+    pub fn with_archive(mut self, archive: Arc<ArchiveJob>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Capture sampled requests of opted-in tenants for replay
+    pub fn with_request_capture(mut self, capture: Arc<RequestCapture>) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Serve the exchange log that the LLM connectors record to
+    pub fn with_exchange_log(mut self, exchange_log: Arc<ExchangeLog>) -> Self {
+        self.exchange_log = Some(exchange_log);
+        self
+    }
+
+    /// Serve read-only SQL analytics through the given job
+    pub fn with_analytics(mut self, analytics: Arc<AnalyticsJob>) -> Self {
+        self.analytics = Some(analytics);
+        self
+    }
+
+    /// Serve ephemeral session graphs, restarting a session's TTL whenever
+    /// its graph is used
+    pub fn with_session_graphs(mut self, sessions: Arc<SessionGraphs>) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Queue records that batch ingestion and CSV imports fail to write,
+    /// and serve the queue for operators to retry or purge
+    pub fn with_dead_letter_queue(mut self, dead_letters: Arc<DeadLetterQueue>) -> Self {
+        self.dead_letters = Some(dead_letters);
+        self
+    }
+
+    /// Serve the change log of the given sync engine and apply changes
+    /// from peers through it; the core service's store must log its writes
+    /// to the same engine, see `SyncLayer`
+    pub fn with_sync(mut self, sync: Arc<SyncEngine>) -> Self {
+        self.sync = Some(sync);
+        self
+    }
+
+    /// Checks of the store, connectors and other dependencies, run by
+    /// `doctor` and, with `self_test`, before serving
+    pub fn with_doctor(mut self, doctor: Doctor) -> Self {
+        self.doctor = Some(Arc::new(doctor));
+        self
+    }
+
+    /// Compose the configured tenant pipelines from these plugins instead of the built-in ones
+    pub fn with_plugin_registry(mut self, plugins: PluginRegistry) -> Self {
+        self.plugins = Arc::new(plugins);
+        self
+    }
+
+    /// Check the bridge's own configuration, then run the doctor's checks.
+    /// A server's doctor mode prints this report instead of serving.
+    pub async fn doctor(&self) -> DoctorReport {
+        let bind_address = self.config.bind_address;
+        let mut report = Doctor::new()
+            .with_check("bind_address", move || async move {
+                match std::net::TcpListener::bind(bind_address) {
+                    Ok(_) => CheckOutcome::pass(format!("{} is free", bind_address)),
+                    Err(e) => CheckOutcome::fail(format!("Cannot bind to {}: {}", bind_address, e)),
+                }
+            })
+            .run()
+            .await;
+        if let Some(doctor) = &self.doctor {
+            report.extend(doctor.run().await);
+        }
+        report
+    }
+
+    /// Require API tokens on tenant routes and serve token management
+    pub fn with_api_tokens(mut self, tokens: Arc<ApiTokenManager>) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Serve the admin API under `/admin`, authorized by the admin secret,
+    /// and refuse other requests while an operator drains the server
+    pub fn with_admin(mut self, admin: Arc<AdminControls>) -> Self {
+        self.admin = Some(admin);
+        self
+    }
+
+    /// Track cancellable operations in the given registry instead of one of
+    /// the bridge's own, e.g. to count cancellations in telemetry
+    pub fn with_operations(mut self, operations: Arc<OperationRegistry>) -> Self {
+        self.operations = operations;
+        self
+    }
+
+    /// Keep the progress of operations run over WebSockets in the given hub
+    /// instead of one of the bridge's own, e.g. to keep events longer
+    pub fn with_progress(mut self, progress: Arc<ProgressHub>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Confine requests authorized by tokens to the graph views assigned to
+    /// them, and serve view management; needs API tokens
+    pub fn with_views(mut self, views: Arc<GraphViews>) -> Self {
+        self.views = Some(views);
+        self
+    }
+
+    fn envelope_applier(&self, core_service: Arc<dyn GraphService>, lineage: Arc<ExtractionLineage>) -> EnvelopeApplier {
+        let applier = EnvelopeApplier::new(core_service, self.config.envelopes.clone()).with_lineage(lineage);
+        match &self.embeddings {
+            Some(embeddings) => applier.with_embeddings(embeddings.clone()),
+            None => applier,
         }
     }
 
+    /// Sign the snapshot exports of the tenants with keys, and encrypt them
+    /// on request, so exports can be checked before they are restored
+    pub fn with_export_keys(mut self, keys: Arc<ExportKeys>) -> Self {
+        self.export_keys = Some(keys);
+        self
+    }
+
     /// Build the Axum router with all routes
-    fn build_router(&self, core_service: Arc<dyn GraphService>) -> Router {
+    fn build_router(&self, core_service: Arc<dyn GraphService>, access_log: Arc<AccessLog>) -> Router {
+        let lineage = Arc::new(ExtractionLineage::new(core_service.clone()));
         let app_state = AppState {
+            graph_context: Arc::new(GraphContextBuilder::new(core_service.clone(), self.config.graph_context.clone())),
+            envelopes: Arc::new(self.envelope_applier(core_service.clone(), lineage.clone())),
+            lineage,
+            fixtures: Arc::new(FixtureLoader::new(core_service.clone())),
             core_service,
             config: self.config.clone(),
+            export_keys: self.export_keys.clone(),
             pipeline: self.pipeline.clone(),
+            examples: self.examples.clone(),
+            ingest_templates: self.ingest_templates.clone(),
+            vectors: self.vectors.clone(),
+            archive: self.archive.clone(),
+            capture: self.capture.clone(),
+            analytics: self.analytics.clone(),
+            tokens: self.tokens.clone(),
+            exchange_log: self.exchange_log.clone(),
+            sessions: self.sessions.clone(),
+            dead_letters: self.dead_letters.clone(),
+            sync: self.sync.clone(),
+            admin: self.admin.clone(),
+            operations: self.operations.clone(),
+            progress: self.progress.clone(),
+            views: self.views.clone(),
         };
 
         let mut router = Router::new()
-            // Health check
             .route("/health", get(handlers::health::health_check))
-            .route("/v1/health", get(handlers::health::health_check))
-            
-            // Tenant management
-            .route("/v1/tenants", get(handlers::tenant::list_tenants))
-            .route("/v1/tenants", post(handlers::tenant::create_tenant))
-            .route("/v1/tenants/:tenant_id", get(handlers::tenant::get_tenant))
-            .route("/v1/tenants/:tenant_id", put(handlers::tenant::update_tenant))
-            .route("/v1/tenants/:tenant_id", delete(handlers::tenant::delete_tenant))
-            
-            // Graph operations
-            .route("/v1/graph/:tenant_id/nodes", post(handlers::graph::upsert_node))
-            .route("/v1/graph/:tenant_id/nodes/batch", post(handlers::graph::batch_upsert_nodes))
-            .route("/v1/graph/:tenant_id/nodes/:node_id", get(handlers::graph::get_node))
-            .route("/v1/graph/:tenant_id/nodes/:node_id", delete(handlers::graph::delete_node))
-            
-            .route("/v1/graph/:tenant_id/edges", post(handlers::graph::upsert_edge))
-            .route("/v1/graph/:tenant_id/edges/batch", post(handlers::graph::batch_upsert_edges))
-            .route("/v1/graph/:tenant_id/edges/:edge_id", delete(handlers::graph::delete_edge))
-            
-            .route("/v1/graph/:tenant_id/query", post(handlers::graph::execute_query))
-            
-            // LLM operations
-            .route("/v1/llm/:tenant_id/extract", post(handlers::llm::extract_knowledge))
-            .route("/v1/llm/:tenant_id/complete", post(handlers::llm::complete_text))
-            
+            .nest("/v1", v1_routes())
+            .nest("/v2", v2_routes())
             .with_state(app_state);
 
         // Add middleware
         let service_builder = ServiceBuilder::new()
             .layer(TraceLayer::new_for_http());
 
+        router = router.layer(axum::middleware::from_fn(middleware::collect_request_warnings));
+        router = router.layer(axum::middleware::from_fn_with_state(self.operations.clone(), middleware::track_operations));
+
+        if let Some(sessions) = &self.sessions {
+            router = router.layer(axum::middleware::from_fn_with_state(sessions.clone(), middleware::touch_sessions));
+        }
+
+        if let Some(capture) = &self.capture {
+            router = router.layer(axum::middleware::from_fn_with_state(capture.clone(), middleware::capture_requests));
+        }
+
+        // Inside tokens, which tell the principal
+        if let Some(views) = &self.views {
+            router = router.layer(axum::middleware::from_fn_with_state(views.clone(), middleware::apply_views));
+        }
+
+        // Outside capture, so that rejected requests are not captured
+        if let Some(tokens) = &self.tokens {
+            router = router.layer(axum::middleware::from_fn_with_state(tokens.clone(), middleware::require_token));
+        }
+
+        if let Some(admin) = &self.admin {
+            router = router.layer(axum::middleware::from_fn_with_state(admin.clone(), middleware::require_admin));
+            router = router.layer(axum::middleware::from_fn_with_state(admin.drain_state(), middleware::track_requests));
+        }
+
+        if self.pipeline.plugin_count(&PipelineStage::Verification) > 0 {
+            router = router.layer(axum::middleware::from_fn_with_state(self.pipeline.clone(), middleware::verify_requests));
+        }
+
+        // Outside authorization, so that denied requests are logged with their status
+        router = router.layer(axum::middleware::from_fn_with_state(access_log, middleware::log_access));
+
         if self.config.enable_cors {
             router = router.layer(CorsLayer::permissive());
         }
@@ -128,12 +419,191 @@ impl FastApiBridge {
     }
 }
 
+/// Routes of API v1.
+///
+/// v1 is stable: existing routes keep their request and response shapes.
+/// Capabilities that need new shapes are added to v2 instead.
+fn v1_routes() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(handlers::health::health_check))
+        
+        // Tenant management
+        .route("/tenants", get(handlers::tenant::list_tenants))
+        .route("/tenants", post(handlers::tenant::create_tenant))
+        .route("/tenants/:tenant_id", get(handlers::tenant::get_tenant))
+        .route("/tenants/:tenant_id", put(handlers::tenant::update_tenant))
+        .route("/tenants/:tenant_id", delete(handlers::tenant::delete_tenant))
+        .route("/tenants/:tenant_id/tokens", get(handlers::token::list_tokens))
+        .route("/tenants/:tenant_id/tokens", post(handlers::token::create_token))
+        .route("/tenants/:tenant_id/tokens/:token_id", delete(handlers::token::revoke_token))
+        .route("/tenants/:tenant_id/tokens/:token_id/rotate", post(handlers::token::rotate_token))
+        .route("/tenants/:tenant_id/views", get(handlers::views::list_views))
+        .route("/tenants/:tenant_id/views/:name", get(handlers::views::get_view))
+        .route("/tenants/:tenant_id/views/:name", put(handlers::views::define_view))
+        .route("/tenants/:tenant_id/views/:name", delete(handlers::views::remove_view))
+        .route("/tenants/:tenant_id/views/:name/principals/:principal", put(handlers::views::assign_view))
+        .route("/tenants/:tenant_id/views/:name/principals/:principal", delete(handlers::views::unassign_view))
+        
+        // Graph operations
+        .route("/graph/:tenant_id/nodes", post(handlers::graph::upsert_node))
+        .route("/graph/:tenant_id/nodes/batch", post(handlers::graph::batch_upsert_nodes))
+        .route("/graph/:tenant_id/nodes/bulk", post(handlers::graph::bulk_load_nodes))
+        .route("/graph/:tenant_id/nodes/with-edges", post(handlers::graph::upsert_node_with_edges))
+        .route("/graph/:tenant_id/apply-envelope", post(handlers::graph::apply_envelope))
+        .route("/graph/:tenant_id/extraction-runs", get(handlers::lineage::list_runs))
+        .route("/graph/:tenant_id/extraction-runs/:run_id", get(handlers::lineage::get_run))
+        .route("/graph/:tenant_id/extraction-runs/:run_id", delete(handlers::lineage::purge_run))
+        .route("/graph/:tenant_id/lineage/:fact_id", get(handlers::lineage::fact_producers))
+        .route("/graph/:tenant_id/nodes/:node_id", get(handlers::graph::get_node))
+        .route("/graph/:tenant_id/nodes/:node_id", delete(handlers::graph::delete_node))
+        .route("/graph/:tenant_id/aliases/:alias", get(handlers::graph::get_node_by_alias))
+        .route("/graph/:tenant_id/aliases/:alias", patch(handlers::graph::patch_node_by_alias))
+        .route("/graph/:tenant_id/aliases/:alias", delete(handlers::graph::delete_node_by_alias))
+        .route("/graph/:tenant_id/aliases/:alias/timeline", get(handlers::graph::node_timeline))
+        .route("/graph/:tenant_id/aliases/:alias/properties/:key/history", get(handlers::graph::property_history))
+        
+        .route("/graph/:tenant_id/edges", post(handlers::graph::upsert_edge))
+        .route("/graph/:tenant_id/edges/batch", post(handlers::graph::batch_upsert_edges))
+        .route("/graph/:tenant_id/edges/bulk", post(handlers::graph::bulk_load_edges))
+        .route("/graph/:tenant_id/edges/by-alias", post(handlers::graph::upsert_edge_by_ref))
+        .route("/graph/:tenant_id/edges/:edge_id", delete(handlers::graph::delete_edge))
+        .route("/graph/:tenant_id/edges/:edge_id/close", post(handlers::graph::close_edge))
+        .route("/graph/:tenant_id/edges/:edge_id/supersede", post(handlers::graph::supersede_edge))
+        .route("/graph/:tenant_id/edges/:edge_id/retract", post(handlers::graph::retract_edge))
+        
+        .route("/graph/:tenant_id/query", post(handlers::graph::execute_query))
+        .route("/graph/:tenant_id/query/batch", post(handlers::graph::execute_query_batch))
+        .route("/graph/:tenant_id/traverse", post(handlers::graph::traverse))
+        .route("/graph/:tenant_id/shortest-path", post(handlers::graph::shortest_path))
+        .route("/graph/:tenant_id/context", get(handlers::graph::json_ld_context))
+        .route("/graph/:tenant_id/export", get(handlers::graph::export_snapshot))
+        .route("/graph/:tenant_id/summary", get(handlers::graph::graph_summary))
+        .route("/graph/:tenant_id/catalog", get(handlers::graph::graph_catalog))
+        .route("/graph/:tenant_id/schema/docs", get(handlers::graph::schema_docs))
+        .route("/graph/:tenant_id/constraints", get(handlers::graph::get_edge_constraints))
+        .route("/graph/:tenant_id/constraints", put(handlers::graph::put_edge_constraints))
+        .route("/graph/:tenant_id/snapshots", get(handlers::graph::list_snapshots))
+        .route("/graph/:tenant_id/snapshots", post(handlers::graph::materialize_snapshot))
+        .route("/graph/:tenant_id/snapshots/:name", delete(handlers::graph::drop_snapshot))
+        .route("/graph/:tenant_id/snapshots/:name/query", post(handlers::graph::query_snapshot))
+        .route("/graph/:tenant_id/ingest-templates", get(handlers::ingest::list_templates))
+        .route("/graph/:tenant_id/ingest-templates/:name", get(handlers::ingest::get_template))
+        .route("/graph/:tenant_id/ingest-templates/:name", put(handlers::ingest::put_template))
+        .route("/graph/:tenant_id/ingest-templates/:name", delete(handlers::ingest::delete_template))
+        .route("/graph/:tenant_id/ingest-schema", get(handlers::ingest::get_schema))
+        .route("/graph/:tenant_id/ingest-schema", put(handlers::ingest::put_schema))
+        .route("/graph/:tenant_id/ingest-schema", delete(handlers::ingest::delete_schema))
+        .route("/graph/:tenant_id/import/csv", post(handlers::ingest::import_csv))
+        
+        // LLM operations
+        .route("/llm/:tenant_id/extract", post(handlers::llm::extract_knowledge))
+        .route("/llm/:tenant_id/complete", post(handlers::llm::complete_text))
+        .route("/llm/:tenant_id/providers", get(handlers::llm::list_providers))
+        .route("/llm/:tenant_id/examples", get(handlers::llm::list_examples))
+        .route("/llm/:tenant_id/examples", post(handlers::llm::add_example))
+        .route("/llm/:tenant_id/examples", put(handlers::llm::replace_examples))
+        .route("/llm/:tenant_id/examples/:example_id", delete(handlers::llm::delete_example))
+        
+        // Vector search
+        .route("/vectors/:tenant_id/search", post(handlers::vector::search_vectors))
+        .route("/vectors/:tenant_id/:id", put(handlers::vector::upsert_vector))
+        .route("/vectors/:tenant_id/:id", delete(handlers::vector::delete_vector))
+        
+        // Archival of closed history
+        .route("/archive/:tenant_id", get(handlers::archive::get_manifest))
+        .route("/archive/:tenant_id", post(handlers::archive::run_archive))
+        .route("/archive/:tenant_id/restore", post(handlers::archive::restore_archive))
+        
+        // Request capture for replay
+        .route("/captures/:tenant_id", get(handlers::capture::list_captures))
+        .route("/captures/:tenant_id", put(handlers::capture::enable_capture))
+        .route("/captures/:tenant_id", delete(handlers::capture::disable_capture))
+        .route("/captures/:tenant_id/:request_id", get(handlers::capture::get_capture))
+
+        // Raw LLM exchanges for debugging extraction
+        .route("/llm-exchanges/:tenant_id", get(handlers::exchange_log::list_exchanges))
+        .route("/llm-exchanges/:tenant_id", put(handlers::exchange_log::enable_exchange_log))
+        .route("/llm-exchanges/:tenant_id", delete(handlers::exchange_log::disable_exchange_log))
+        .route("/llm-exchanges/:tenant_id/:exchange_id", get(handlers::exchange_log::get_exchange))
+
+        // Ephemeral session graphs
+        .route("/sessions/:tenant_id", get(handlers::session::list_sessions))
+        .route("/sessions/:tenant_id", post(handlers::session::create_session))
+        .route("/sessions/:tenant_id/:session_id", get(handlers::session::get_session))
+        .route("/sessions/:tenant_id/:session_id", delete(handlers::session::end_session))
+        .route("/sessions/:tenant_id/:session_id/touch", post(handlers::session::touch_session))
+        .route("/sessions/:tenant_id/:session_id/promote", post(handlers::session::promote_session))
+
+        // Failed ingestion records
+        .route("/dead-letters/:tenant_id", get(handlers::dead_letter::list_dead_letters))
+        .route("/dead-letters/:tenant_id", delete(handlers::dead_letter::purge_dead_letters))
+        .route("/dead-letters/:tenant_id/retry", post(handlers::dead_letter::retry_dead_letters))
+        .route("/dead-letters/:tenant_id/:entry_id", get(handlers::dead_letter::get_dead_letter))
+        .route("/dead-letters/:tenant_id/:entry_id", delete(handlers::dead_letter::delete_dead_letter))
+
+        // Sync with other instances
+        .route("/sync/:tenant_id", get(handlers::sync::sync_status))
+        .route("/sync/:tenant_id/changes", get(handlers::sync::list_changes))
+        .route("/sync/:tenant_id/apply", post(handlers::sync::apply_changes))
+        
+        // Long-running operations in flight
+        .route("/operations/:tenant_id", get(handlers::operation::list_operations))
+        .route("/operations/:tenant_id/:operation_id", get(handlers::operation::get_operation))
+        .route("/operations/:tenant_id/:operation_id", delete(handlers::operation::cancel_operation))
+        .route("/operations/:tenant_id/ws", get(handlers::progress::progress_socket))
+        
+        // Read-only SQL analytics
+        .route("/analytics/:tenant_id/query", post(handlers::analytics::run_query))
+        .route("/analytics/:tenant_id/sync", post(handlers::analytics::sync_tables))
+
+        // Operator controls, authorized by the admin secret
+        .route("/admin/status", get(handlers::admin::status))
+        .route("/admin/plugins", get(handlers::admin::list_plugins))
+        .route("/admin/plugins/:name", put(handlers::admin::set_plugin_enabled))
+        .route("/admin/tenants/:tenant_id/provider", put(handlers::admin::set_default_provider))
+        .route("/admin/caches/flush", post(handlers::admin::flush_caches))
+        .route("/admin/jobs/:name", post(handlers::admin::run_job))
+        .route("/admin/jobs/:name", get(handlers::admin::job_progress))
+        .route("/admin/drain", post(handlers::admin::drain))
+        .route("/admin/drain", delete(handlers::admin::resume))
+        .route("/admin/seed", post(handlers::admin::seed))
+        .route("/admin/seed/teardown", post(handlers::admin::teardown_seed))
+}
+
+/// Routes of API v2: every v1 route, served by the same handlers, plus
+/// paginated and filtered listings
+fn v2_routes() -> Router<AppState> {
+    v1_routes()
+        .route("/graph/:tenant_id/nodes", get(handlers::graph::list_nodes))
+        .route("/graph/:tenant_id/edges", get(handlers::graph::list_relationships))
+}
+
 #[async_trait]
 impl PresentationAdapter for FastApiBridge {
     async fn start(&self, core_service: Arc<dyn GraphService>) -> Result<(), PresentationError> {
         info!("Starting FastAPI bridge server on {}", self.config.bind_address);
 
-        let router = self.build_router(core_service);
+        if self.config.self_test {
+            let report = self.doctor().await;
+            for check in &report.checks {
+                match check.status {
+                    CheckStatus::Fail => error!("Self-test {} failed: {}", check.name, check.message),
+                    CheckStatus::Warn => warn!("Self-test {}: {}", check.name, check.message),
+                    _ => info!("Self-test {}: {}", check.name, check.message),
+                }
+            }
+            if !report.passed() {
+                let failed: Vec<_> = report.failures().map(|c| c.name.as_str()).collect();
+                return Err(PresentationError::StartupFailed(format!("Self-test failed: {}", failed.join(", "))));
+            }
+        }
+
+        self.pipeline.compose_tenants(&self.config.pipelines, &self.plugins).await
+            .map_err(|e| PresentationError::StartupFailed(format!("Failed to compose tenant pipelines: {}", e)))?;
+
+        let access_log = AccessLog::new(self.config.access_log.clone())
+            .map_err(|e| PresentationError::StartupFailed(format!("Failed to open access log: {}", e)))?;
+        let router = self.build_router(core_service, Arc::new(access_log));
 
         let listener = tokio::net::TcpListener::bind(&self.config.bind_address)
             .await
@@ -161,7 +631,27 @@ impl PresentationAdapter for FastApiBridge {
 pub struct AppState {
     pub core_service: Arc<dyn GraphService>,
     pub config: FastApiBridgeConfig,
+    pub export_keys: Option<Arc<ExportKeys>>,
     pub pipeline: Arc<PipelineRunner>,
+    pub examples: Arc<FewShotStore>,
+    pub graph_context: Arc<GraphContextBuilder>,
+    pub envelopes: Arc<EnvelopeApplier>,
+    pub lineage: Arc<ExtractionLineage>,
+    pub fixtures: Arc<FixtureLoader>,
+    pub ingest_templates: Arc<IngestTemplateStore>,
+    pub vectors: Option<Arc<dyn VectorIndex>>,
+    pub archive: Option<Arc<ArchiveJob>>,
+    pub capture: Option<Arc<RequestCapture>>,
+    pub analytics: Option<Arc<AnalyticsJob>>,
+    pub tokens: Option<Arc<ApiTokenManager>>,
+    pub exchange_log: Option<Arc<ExchangeLog>>,
+    pub sessions: Option<Arc<SessionGraphs>>,
+    pub dead_letters: Option<Arc<DeadLetterQueue>>,
+    pub sync: Option<Arc<SyncEngine>>,
+    pub admin: Option<Arc<AdminControls>>,
+    pub operations: Arc<OperationRegistry>,
+    pub progress: Arc<ProgressHub>,
+    pub views: Option<Arc<GraphViews>>,
 }
 
 /// Standard API response wrapper
@@ -170,16 +660,30 @@ pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// Code of the error and whether to retry, for errors from the core
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_details: Option<ErrorDetails>,
     pub timestamp: String,
+    /// Pipeline metadata, for requests that asked for it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<OperationMetadata>,
+    /// Non-fatal issues with the request, e.g. properties that were removed
+    /// or times that were defaulted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 impl<T> ApiResponse<T> {
+    /// A successful response, with the warnings the request reported so far
     pub fn success(data: T) -> Self {
         Self {
             success: true,
             data: Some(data),
             error: None,
+            error_details: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            operation: None,
+            warnings: take_warnings(),
         }
     }
 
@@ -188,31 +692,88 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(message.into()),
+            error_details: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            operation: None,
+            warnings: take_warnings(),
         }
     }
+
+    /// Attach the classification of the error the response reports
+    pub fn with_error_details(mut self, details: ErrorDetails) -> Self {
+        self.error_details = Some(details);
+        self
+    }
+
+    /// Attach pipeline metadata if the request asked for it
+    pub fn with_operation(mut self, operation: Option<OperationMetadata>) -> Self {
+        self.operation = operation;
+        self
+    }
+}
+
+impl AppState {
+    /// `operation` if debug metadata is enabled and the request asked for it
+    pub fn debug_operation(&self, headers: &HeaderMap, operation: OperationMetadata) -> Option<OperationMetadata> {
+        let requested = headers.get(DEBUG_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true"));
+        (self.config.debug_metadata && requested).then_some(operation)
+    }
 }
 
 /// Convert core errors to HTTP status codes and responses
 pub fn handle_core_error(error: CoreError) -> (StatusCode, Json<ApiResponse<()>>) {
+    let details = error.details();
     let (status, message) = match error {
         CoreError::Tenant(msg) => (StatusCode::BAD_REQUEST, format!("Tenant error: {}", msg)),
+        CoreError::Cancelled(msg) => (StatusCode::from_u16(CLIENT_CLOSED_REQUEST).unwrap(), msg),
         CoreError::Storage(GraphError::NodeNotFound(msg)) => (StatusCode::NOT_FOUND, format!("Node not found: {}", msg)),
         CoreError::Storage(GraphError::EdgeNotFound(msg)) => (StatusCode::NOT_FOUND, format!("Edge not found: {}", msg)),
+        CoreError::Storage(GraphError::SnapshotNotFound(msg)) => (StatusCode::NOT_FOUND, format!("Snapshot not found: {}", msg)),
         CoreError::Storage(GraphError::ConstraintViolation(msg)) => (StatusCode::CONFLICT, format!("Constraint violation: {}", msg)),
         CoreError::Storage(GraphError::TenantIsolationViolation(msg)) => (StatusCode::FORBIDDEN, format!("Access denied: {}", msg)),
+        CoreError::Storage(GraphError::ReservedProperty(msg)) => (StatusCode::BAD_REQUEST, format!("Reserved property: {}", msg)),
+        CoreError::Storage(GraphError::InvalidTenant(e)) => (StatusCode::BAD_REQUEST, format!("Invalid tenant ID: {}", e)),
+        CoreError::Storage(GraphError::Temporal(e)) => (StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid temporal data: {}", e)),
+        CoreError::Storage(GraphError::Unsupported(msg)) => (StatusCode::NOT_IMPLEMENTED, format!("Not supported by this store: {}", msg)),
+        CoreError::Storage(GraphError::Overloaded(msg)) => (StatusCode::TOO_MANY_REQUESTS, format!("Too many queries: {}", msg)),
         CoreError::Storage(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()),
         CoreError::Llm(LlmError::BudgetExceeded) => (StatusCode::TOO_MANY_REQUESTS, "LLM budget exceeded".to_string()),
         CoreError::Llm(LlmError::Timeout) => (StatusCode::REQUEST_TIMEOUT, "LLM request timeout".to_string()),
+        CoreError::Llm(LlmError::RateLimited(_)) => (StatusCode::TOO_MANY_REQUESTS, "LLM provider rate limit reached".to_string()),
+        CoreError::Llm(LlmError::UnsafeInput(msg)) => (StatusCode::UNPROCESSABLE_ENTITY, format!("Unsafe extraction input: {}", msg)),
+        CoreError::Llm(LlmError::ContextLengthExceeded(msg)) => (StatusCode::PAYLOAD_TOO_LARGE, format!("Extraction input too large: {}", msg)),
+        CoreError::Llm(LlmError::CapabilityUnavailable(msg)) => (StatusCode::SERVICE_UNAVAILABLE, msg),
+        CoreError::Llm(LlmError::ProviderNotAllowed(msg)) => (StatusCode::FORBIDDEN, msg),
+        CoreError::Llm(LlmError::ContentBlocked(msg)) => (StatusCode::UNPROCESSABLE_ENTITY, format!("Content blocked by the LLM provider: {}", msg)),
         CoreError::Llm(_) => (StatusCode::BAD_GATEWAY, "LLM service error".to_string()),
+        CoreError::Pipeline(PipelineError::PipelineHalted(msg)) => (StatusCode::UNPROCESSABLE_ENTITY, format!("Request rejected: {}", msg)),
+        CoreError::Pipeline(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Pipeline error: {}", e)),
+        CoreError::Vector(e @ (VectorError::DimensionMismatch { .. } | VectorError::InvalidVector(_))) => (StatusCode::BAD_REQUEST, e.to_string()),
+        CoreError::Vector(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Vector index error: {}", e)),
+        CoreError::Archive(e @ ArchiveError::Storage(_)) => (StatusCode::BAD_GATEWAY, e.to_string()),
+        CoreError::Archive(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Archive error: {}", e)),
+        CoreError::Analytics(e @ AnalyticsError::InvalidQuery(_)) => (StatusCode::BAD_REQUEST, e.to_string()),
+        CoreError::Analytics(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Analytics error: {}", e)),
+        CoreError::Auth(e @ (AuthError::InvalidToken | AuthError::TokenExpired | AuthError::TokenRevoked | AuthError::InvalidSignature(_))) => (StatusCode::UNAUTHORIZED, e.to_string()),
+        CoreError::Auth(e @ (AuthError::WrongTenant(_) | AuthError::InsufficientScope { .. })) => (StatusCode::FORBIDDEN, e.to_string()),
+        CoreError::Auth(e @ AuthError::TokenNotFound(_)) => (StatusCode::NOT_FOUND, e.to_string()),
+        CoreError::Auth(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Authentication error: {}", e)),
+        CoreError::Session(e @ SessionError::NotFound(_)) => (StatusCode::NOT_FOUND, e.to_string()),
+        CoreError::Session(e @ SessionError::LimitReached(_)) => (StatusCode::TOO_MANY_REQUESTS, e.to_string()),
+        CoreError::Session(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+        CoreError::View(e @ ViewError::NotFound(_)) => (StatusCode::NOT_FOUND, e.to_string()),
+        CoreError::View(e @ ViewError::InUse(_)) => (StatusCode::CONFLICT, e.to_string()),
+        CoreError::View(e) => (StatusCode::BAD_REQUEST, e.to_string()),
         CoreError::Configuration(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Configuration error: {}", msg)),
         CoreError::Serialization(_) => (StatusCode::BAD_REQUEST, "Invalid request format".to_string()),
         CoreError::Temporal(msg) => (StatusCode::BAD_REQUEST, format!("Temporal query error: {}", msg)),
         CoreError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Internal error: {}", msg)),
     };
 
-    error!("API error: {} - {}", status, message);
-    (status, Json(ApiResponse::error(message)))
+    error!("API error: {} - {} ({})", status, message, details.code);
+    (status, Json(ApiResponse::error(message).with_error_details(details)))
 }
 
 #[cfg(test)]
@@ -235,6 +796,19 @@ mod tests {
         assert!(response.error.is_none());
     }
 
+    #[tokio::test]
+    async fn test_api_response_warnings() {
+        let (response, leftover) = collect_warnings(async {
+            telamentis_core::warnings::report("valid_from defaulted");
+            ApiResponse::success(1)
+        }).await;
+        assert_eq!(response.warnings, vec!["valid_from defaulted"]);
+        assert!(leftover.is_empty());
+
+        let json = serde_json::to_value(ApiResponse::success(1)).unwrap();
+        assert!(json.get("warnings").is_none());
+    }
+
     #[test]
     fn test_api_response_error() {
         let response = ApiResponse::<()>::error("test error");
@@ -242,4 +816,32 @@ mod tests {
         assert!(response.data.is_none());
         assert_eq!(response.error, Some("test error".to_string()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_core_error_details() {
+        let (status, Json(response)) = handle_core_error(GraphError::Overloaded("busy".to_string()).into());
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["error_details"], serde_json::json!({"code": "overloaded", "kind": "retryable", "retry_after_ms": 1000}));
+
+        let (_, Json(response)) = handle_core_error(GraphError::NodeNotFound("alice".to_string()).into());
+        let details = response.error_details.unwrap();
+        assert_eq!((details.code.as_str(), details.kind, details.retry_after_ms), ("node_not_found", ErrorKind::Permanent, None));
+    }
+
+    #[tokio::test]
+    async fn test_doctor_reports_bind_conflict() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = FastApiBridgeConfig {
+            bind_address: taken.local_addr().unwrap(),
+            ..Default::default()
+        };
+        let bridge = FastApiBridge::new(config)
+            .with_doctor(Doctor::new().with_check("config", || async { CheckOutcome::pass("ok") }));
+
+        let report = bridge.doctor().await;
+        let statuses: Vec<_> = report.checks.iter().map(|c| (c.name.as_str(), c.status)).collect();
+        assert_eq!(statuses, vec![("bind_address", CheckStatus::Fail), ("config", CheckStatus::Pass)]);
+        assert!(!report.passed());
+    }
+}
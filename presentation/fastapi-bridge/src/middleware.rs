@@ -1,14 +1,33 @@
 //! Middleware for the FastAPI bridge
 
 use axum::{
-    extract::Request,
-    http::{HeaderMap, StatusCode},
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Json, Response},
 };
+use futures::{stream, StreamExt};
+use std::io;
+use std::sync::Arc;
 use std::time::Instant;
+use telamentis_core::access_log::{attribute_principal, attributed};
+use telamentis_core::capture::{RequestCapture, STATUS_ATTRIBUTE};
+use telamentis_core::sessions::session_of;
+use telamentis_core::pipeline::PipelineRunner;
+use telamentis_core::signing::{body_digest, BodyHasher, BODY_SHA256_ATTRIBUTE, CONTENT_SHA256_HEADER};
+use telamentis_core::prelude::*;
 use tracing::{debug, info, warn};
 
+use crate::{handle_core_error, ApiResponse, OPERATION_HEADER};
+
+/// API areas whose requests are captured; each is `/{version}/{area}/{tenant_id}/...`
+const CAPTURED_AREAS: &[&str] = &["graph", "llm", "vectors"];
+
+/// Largest request body read for signature verification, in bytes, when the
+/// request does not claim its digest
+const MAX_VERIFIED_BODY_BYTES: usize = 64 * 1024 * 1024;
+
 /// Request logging middleware
 pub async fn request_logging(request: Request, next: Next) -> Response {
     let method = request.method().clone();
@@ -33,6 +52,15 @@ pub async fn request_logging(request: Request, next: Next) -> Response {
     response
 }
 
+/// Collect the warnings a request reports, for its `ApiResponse`
+pub async fn collect_request_warnings(request: Request, next: Next) -> Response {
+    let (response, leftover) = collect_warnings(next.run(request)).await;
+    if !leftover.is_empty() {
+        debug!("Warnings reported after the response was built: {}", leftover.join("; "));
+    }
+    response
+}
+
 /// Request timeout middleware
 pub async fn request_timeout(request: Request, next: Next) -> Result<Response, StatusCode> {
     let timeout_duration = std::time::Duration::from_secs(30); // 30 second timeout
@@ -67,6 +95,338 @@ pub fn extract_tenant_id(headers: &HeaderMap, path: &str) -> Option<String> {
     None
 }
 
+/// Capture sampled requests of tenants with capture enabled. Captured
+/// responses carry the request ID in `X-Request-Id`, so users can report it.
+pub async fn capture_requests(State(capture): State<Arc<RequestCapture>>, request: Request, next: Next) -> Response {
+    let Some(tenant) = captured_tenant(request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    let path = request.uri().path_and_query().map_or_else(|| request.uri().path().to_string(), |p| p.to_string());
+    let mut ctx = RequestContext::new(request.method().to_string(), path);
+
+    // Bodies of unknown or excessive length are passed through uncaptured
+    let limit = capture.max_body_bytes();
+    let content_length = request.headers().get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    let has_body = !matches!(*request.method(), Method::GET | Method::DELETE);
+    let body_fits = content_length.map_or(!has_body, |length| length <= limit);
+    if !body_fits || !capture.should_capture(&tenant, ctx.request_id) {
+        return next.run(request).await;
+    }
+    ctx.tenant_id = Some(tenant);
+
+    let (parts, body) = request.into_parts();
+    let input = match axum::body::to_bytes(body, limit).await {
+        Ok(input) => input,
+        Err(e) => {
+            warn!("Failed to read request body for capture: {}", e);
+            return Response::builder().status(StatusCode::BAD_REQUEST).body(Body::empty()).unwrap();
+        }
+    };
+    ctx.core_operation_input = serde_json::from_slice(&input).ok();
+
+    let response = next.run(Request::from_parts(parts, Body::from(input))).await;
+
+    let (mut parts, body) = response.into_parts();
+    let output = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("Failed to read response body for capture: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    if output.len() <= limit {
+        ctx.core_operation_output = serde_json::from_slice(&output).ok();
+    }
+    ctx.set_attribute(STATUS_ATTRIBUTE, serde_json::json!(parts.status.as_u16()));
+    if !parts.status.is_success() {
+        ctx.error = Some(parts.status.to_string());
+    }
+
+    match capture.record(&ctx).await {
+        Ok(_) => {
+            if let Ok(value) = HeaderValue::from_str(&ctx.request_id.to_string()) {
+                parts.headers.insert("X-Request-Id", value);
+            }
+        }
+        Err(e) => warn!("Failed to capture request {}: {}", ctx.request_id, e),
+    }
+
+    Response::from_parts(parts, Body::from(output))
+}
+
+/// Tenant of a request in one of the captured API areas
+fn captured_tenant(path: &str) -> Option<TenantId> {
+    path_tenant(path).filter(|(area, _)| CAPTURED_AREAS.contains(area)).map(|(_, tenant)| tenant)
+}
+
+/// API area and tenant of a `/{version}/{area}/{tenant_id}/...` path
+fn path_tenant(path: &str) -> Option<(&str, TenantId)> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    if !matches!(segments.next(), Some("v1" | "v2")) {
+        return None;
+    }
+    let area = segments.next()?;
+    segments.next().filter(|tenant| !tenant.is_empty()).map(|tenant| (area, TenantId::new(tenant)))
+}
+
+/// Restart the TTL of the session whose graph a request uses, rejecting
+/// requests to graphs of sessions that have ended or expired
+pub async fn touch_sessions(State(sessions): State<Arc<SessionGraphs>>, request: Request, next: Next) -> Response {
+    let session = path_tenant(request.uri().path())
+        .filter(|(area, _)| *area == "graph")
+        .and_then(|(_, graph)| session_of(&graph));
+    if let Some((tenant, session_id)) = session {
+        if let Err(e) = sessions.touch(&tenant, session_id) {
+            return handle_core_error(e.into()).into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Run the pipeline's verification stage over each request, with its headers
+/// and the digest of its body, rejecting the requests it fails. A body whose
+/// digest is claimed in `CONTENT_SHA256_HEADER` is verified against that
+/// claim and streams to the handler, which gets an error at the end of the
+/// body if it does not match; other bodies are read to digest them.
+pub async fn verify_requests(State(pipeline): State<Arc<PipelineRunner>>, request: Request, next: Next) -> Response {
+    let path = request.uri().path_and_query().map_or_else(|| request.uri().path().to_string(), |p| p.to_string());
+    let mut ctx = RequestContext::new(request.method().to_string(), path);
+    ctx.tenant_id = path_tenant(request.uri().path()).map(|(_, tenant)| tenant);
+    ctx.headers = request.headers().iter()
+        .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+
+    let (parts, body) = request.into_parts();
+    let claimed = parts.headers.get(CONTENT_SHA256_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_ascii_lowercase);
+    let body = match claimed {
+        Some(claimed) => {
+            ctx.set_attribute(BODY_SHA256_ATTRIBUTE, serde_json::json!(claimed));
+            digest_checked(body, claimed)
+        }
+        None => match axum::body::to_bytes(body, MAX_VERIFIED_BODY_BYTES).await {
+            Ok(body) => {
+                ctx.set_attribute(BODY_SHA256_ATTRIBUTE, serde_json::json!(body_digest(&body)));
+                Body::from(body)
+            }
+            Err(e) => {
+                warn!("Failed to read request body for verification: {}", e);
+                return Response::builder().status(StatusCode::PAYLOAD_TOO_LARGE).body(Body::empty()).unwrap();
+            }
+        },
+    };
+
+    match pipeline.verify_request(ctx).await {
+        Ok(_) => next.run(Request::from_parts(parts, body)).await,
+        Err(e) => handle_core_error(e).into_response(),
+    }
+}
+
+/// `body`, hashed as its chunks pass, ending in an error instead if its
+/// digest is not `claimed`
+fn digest_checked(body: Body, claimed: String) -> Body {
+    let start = (body.into_data_stream(), Some(BodyHasher::default()), claimed);
+    Body::from_stream(stream::unfold(start, |(mut data, hasher, claimed)| async move {
+        let mut hasher = hasher?;
+        match data.next().await {
+            Some(Ok(chunk)) => {
+                hasher.update(&chunk);
+                Some((Ok(chunk), (data, Some(hasher), claimed)))
+            }
+            Some(Err(e)) => Some((Err(io::Error::other(e)), (data, None, claimed))),
+            None if hasher.finish() == claimed => None,
+            None => {
+                let error = io::Error::new(io::ErrorKind::InvalidData, "Body does not match its digest");
+                Some((Err(error), (data, None, claimed)))
+            }
+        }
+    }))
+}
+
+/// Reject requests to tenant routes that lack a bearer token granting the
+/// route's scope on that tenant. Routes outside a tenant are not checked.
+/// The token is added to the request's extensions.
+pub async fn require_token(State(tokens): State<Arc<ApiTokenManager>>, mut request: Request, next: Next) -> Response {
+    let Some((tenant, required)) = required_scope(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    let secret = request.headers().get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or_default();
+    match tokens.authorize(secret, &tenant, required).await {
+        Ok(token) => {
+            debug!("Request authorized by token {} ({})", token.id, token.scope);
+            attribute_principal(token.id.to_string());
+            request.extensions_mut().insert(token);
+            next.run(request).await
+        }
+        Err(e) => handle_core_error(e.into()).into_response(),
+    }
+}
+
+/// Run requests authorized by a token confined to a graph view within that
+/// view; other requests are not confined
+pub async fn apply_views(State(views): State<Arc<GraphViews>>, request: Request, next: Next) -> Response {
+    let view = request.extensions().get::<ApiToken>()
+        .and_then(|token| views.view_for(&token.tenant, &token.id.to_string()));
+    match view {
+        Some(view) => {
+            debug!("Request confined to view '{}'", view.name);
+            with_view(Some(view), next.run(request)).await
+        }
+        None => next.run(request).await,
+    }
+}
+
+/// Reject admin API requests that lack the admin secret as bearer token.
+/// Tenant tokens, even admin ones, do not grant access.
+pub async fn require_admin(State(admin): State<Arc<AdminControls>>, request: Request, next: Next) -> Response {
+    if !is_admin_path(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let secret = request.headers().get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or_default();
+    match admin.authorize(secret) {
+        Ok(()) => {
+            attribute_principal("admin");
+            next.run(request).await
+        }
+        Err(e) => handle_core_error(e.into()).into_response(),
+    }
+}
+
+/// Record each request in the access log, attributed to the tenant of its
+/// path and the token that authorized it. Requests other than reads,
+/// queries, exports and extractions count as writes.
+pub async fn log_access(State(access_log): State<Arc<AccessLog>>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let bytes_in = content_length(request.headers());
+    let start_time = Instant::now();
+
+    let (response, attribution) = attributed(next.run(request)).await;
+
+    let mut entry = AccessLogEntry::new("http", method.as_str(), response.status().as_u16(), start_time.elapsed());
+    entry.write = !matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) && operation_kind(&method, &path).is_none();
+    entry.tenant = attribution.tenant.or_else(|| path_tenant(&path).map(|(_, tenant)| tenant));
+    entry.principal = attribution.principal;
+    entry.path = Some(path);
+    entry.bytes_in = bytes_in;
+    entry.bytes_out = content_length(response.headers()).or_else(|| response.body().size_hint().exact());
+    access_log.record(entry);
+    response
+}
+
+/// Length of a body as announced in its headers
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(header::CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+/// Count requests in flight for a drain, refusing them while the server
+/// drains. Admin and health requests are let through uncounted.
+pub async fn track_requests(State(drain): State<Arc<DrainState>>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if is_admin_path(path) || matches!(path, "/health" | "/v1/health" | "/v2/health") {
+        return next.run(request).await;
+    }
+
+    match drain.enter() {
+        Some(_in_flight) => next.run(request).await,
+        None => {
+            let mut response = (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::error("Server is draining"))).into_response();
+            response.headers_mut().insert(header::CONNECTION, HeaderValue::from_static("close"));
+            response
+        }
+    }
+}
+
+/// Run queries, exports and extractions as operations that can be
+/// cancelled, under the ID the request chose in the operation header or a
+/// new one. Every response of such a request carries the ID in that header.
+pub async fn track_operations(State(operations): State<Arc<OperationRegistry>>, request: Request, next: Next) -> Response {
+    let Some((tenant, kind)) = operation_kind(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    let requested = match request.headers().get(OPERATION_HEADER).map(|value| value.to_str().ok().and_then(|id| Uuid::parse_str(id).ok())) {
+        None => None,
+        Some(Some(id)) => Some(id),
+        Some(None) => {
+            let message = format!("Invalid {} header, expected a UUID", OPERATION_HEADER);
+            return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(message))).into_response();
+        }
+    };
+    let operation = match operations.start(&tenant, kind, requested) {
+        Ok(operation) => operation,
+        Err(e) => return handle_core_error(e).into_response(),
+    };
+
+    let id = HeaderValue::from_str(&operation.id().to_string()).unwrap();
+    let mut response = match operation.run(next.run(request)).await {
+        Ok(response) => response,
+        Err(e) => handle_core_error(e).into_response(),
+    };
+    response.headers_mut().insert(OPERATION_HEADER, id);
+    response
+}
+
+/// Tenant and kind of the cancellable operation a request runs: queries,
+/// traversals and path searches, exports and extractions. Operations on
+/// session graphs belong to the tenant that owns the session.
+fn operation_kind(method: &Method, path: &str) -> Option<(TenantId, OperationKind)> {
+    let (area, tenant) = path_tenant(path)?;
+    let tenant = session_of(&tenant).map_or(tenant, |(owner, _)| owner);
+    let last = path.trim_start_matches('/').split('/').skip(3).last();
+
+    let kind = match (area, last) {
+        ("graph" | "analytics", Some("query" | "traverse" | "shortest-path")) if *method == Method::POST => OperationKind::Query,
+        ("graph", Some("batch")) if *method == Method::POST && path.ends_with("/query/batch") => OperationKind::Query,
+        ("graph", Some("export")) if *method == Method::GET => OperationKind::Export,
+        ("llm", Some("extract")) if *method == Method::POST => OperationKind::Extraction,
+        _ => return None,
+    };
+    Some((tenant, kind))
+}
+
+/// Whether a path is in the admin API, `/{version}/admin/...`
+fn is_admin_path(path: &str) -> bool {
+    let mut segments = path.trim_start_matches('/').split('/');
+    matches!(segments.next(), Some("v1" | "v2")) && segments.next() == Some("admin")
+}
+
+/// Tenant of a request and the token scope it needs: admin for tenant
+/// management, archives, captures and LLM exchanges; read for lookups, including queries
+/// sent as POST; write for everything else. Session graphs belong to the
+/// tenant that owns the session.
+fn required_scope(method: &Method, path: &str) -> Option<(TenantId, TokenScope)> {
+    let (area, tenant) = path_tenant(path)?;
+    let tenant = session_of(&tenant).map_or(tenant, |(owner, _)| owner);
+    let last = path.trim_start_matches('/').split('/').skip(3).last();
+
+    let scope = match area {
+        "tenants" | "archive" | "captures" | "llm-exchanges" | "dead-letters" | "sync" => TokenScope::Admin,
+        "graph" | "llm" | "vectors" | "analytics" | "sessions" | "operations" => {
+            // The progress socket also runs extractions
+            let is_socket = area == "operations" && last == Some("ws");
+            let is_lookup = (*method == Method::GET && !is_socket) || matches!(last, Some("query" | "search"));
+            if is_lookup { TokenScope::Read } else { TokenScope::Write }
+        }
+        _ => return None,
+    };
+    Some((tenant, scope))
+}
+
 /// Rate limiting middleware (simplified implementation)
 pub async fn rate_limiting(request: Request, next: Next) -> Result<Response, StatusCode> {
     // In a real implementation, this would use a proper rate limiting algorithm
@@ -111,6 +471,120 @@ mod tests {
         assert_eq!(tenant_id, Some("my_tenant".to_string()));
     }
 
+    #[test]
+    fn test_captured_tenant() {
+        assert_eq!(captured_tenant("/v1/graph/my_tenant/nodes"), Some(TenantId::new("my_tenant")));
+        assert_eq!(captured_tenant("/v1/llm/my_tenant/extract"), Some(TenantId::new("my_tenant")));
+        assert_eq!(captured_tenant("/v2/graph/my_tenant/nodes"), Some(TenantId::new("my_tenant")));
+        assert_eq!(captured_tenant("/v1/captures/my_tenant"), None);
+        assert_eq!(captured_tenant("/v1/health"), None);
+    }
+
+    #[test]
+    fn test_required_scope() {
+        let tenant = || TenantId::new("my_tenant");
+        assert_eq!(required_scope(&Method::GET, "/v1/graph/my_tenant/summary"), Some((tenant(), TokenScope::Read)));
+        assert_eq!(required_scope(&Method::POST, "/v1/graph/my_tenant/query"), Some((tenant(), TokenScope::Read)));
+        assert_eq!(required_scope(&Method::POST, "/v2/graph/my_tenant/nodes"), Some((tenant(), TokenScope::Write)));
+        assert_eq!(required_scope(&Method::DELETE, "/v1/vectors/my_tenant/abc"), Some((tenant(), TokenScope::Write)));
+        assert_eq!(required_scope(&Method::GET, "/v1/tenants/my_tenant/tokens"), Some((tenant(), TokenScope::Admin)));
+        assert_eq!(required_scope(&Method::PUT, "/v1/tenants/my_tenant/views/public"), Some((tenant(), TokenScope::Admin)));
+        assert_eq!(required_scope(&Method::GET, "/v1/archive/my_tenant"), Some((tenant(), TokenScope::Admin)));
+        assert_eq!(required_scope(&Method::GET, "/v1/llm-exchanges/my_tenant"), Some((tenant(), TokenScope::Admin)));
+        assert_eq!(required_scope(&Method::POST, "/v1/dead-letters/my_tenant/retry"), Some((tenant(), TokenScope::Admin)));
+        assert_eq!(required_scope(&Method::GET, "/v1/sync/my_tenant/changes"), Some((tenant(), TokenScope::Admin)));
+        assert_eq!(required_scope(&Method::POST, "/v1/sessions/my_tenant"), Some((tenant(), TokenScope::Write)));
+        assert_eq!(required_scope(&Method::GET, "/v1/operations/my_tenant/ws"), Some((tenant(), TokenScope::Write)));
+        let session_graph = format!("/v1/graph/my_tenant~session~{}/query", Uuid::nil());
+        assert_eq!(required_scope(&Method::POST, &session_graph), Some((tenant(), TokenScope::Read)));
+        assert_eq!(required_scope(&Method::GET, "/v1/tenants"), None);
+        assert_eq!(required_scope(&Method::GET, "/health"), None);
+    }
+
+    #[test]
+    fn test_operation_kind() {
+        let tenant = || TenantId::new("my_tenant");
+        assert_eq!(operation_kind(&Method::POST, "/v1/graph/my_tenant/query"), Some((tenant(), OperationKind::Query)));
+        assert_eq!(operation_kind(&Method::POST, "/v2/graph/my_tenant/snapshots/q1/query"), Some((tenant(), OperationKind::Query)));
+        assert_eq!(operation_kind(&Method::POST, "/v1/graph/my_tenant/query/batch"), Some((tenant(), OperationKind::Query)));
+        assert_eq!(operation_kind(&Method::POST, "/v1/graph/my_tenant/edges/batch"), None);
+        assert_eq!(operation_kind(&Method::GET, "/v1/graph/my_tenant/export"), Some((tenant(), OperationKind::Export)));
+        assert_eq!(operation_kind(&Method::POST, "/v1/llm/my_tenant/extract"), Some((tenant(), OperationKind::Extraction)));
+        assert_eq!(operation_kind(&Method::POST, "/v1/graph/my_tenant/nodes"), None);
+        assert_eq!(operation_kind(&Method::DELETE, "/v1/operations/my_tenant/query"), None);
+        assert_eq!(required_scope(&Method::GET, "/v1/operations/my_tenant"), Some((tenant(), TokenScope::Read)));
+        assert_eq!(required_scope(&Method::DELETE, &format!("/v1/operations/my_tenant/{}", Uuid::nil())), Some((tenant(), TokenScope::Write)));
+    }
+
+    #[test]
+    fn test_admin_paths() {
+        assert!(is_admin_path("/v1/admin/status"));
+        assert!(is_admin_path("/v2/admin/tenants/acme/provider"));
+        assert!(!is_admin_path("/v1/tenants/admin"));
+        assert!(!is_admin_path("/admin/status"));
+        assert_eq!(required_scope(&Method::POST, "/v1/admin/drain"), None);
+    }
+
+    #[tokio::test]
+    async fn test_signed_bulk_load() {
+        use axum::{routing::post, Router};
+        use std::collections::HashMap;
+        use telamentis_core::signing::{signature_headers, RequestSigningPlugin, SigningConfig, TenantSigning};
+        use tower::Service;
+
+        let signing = SigningConfig {
+            tenants: HashMap::from([(TenantId::new("acme"), TenantSigning { secret: "s3cret".to_string(), required: true })]),
+            ..Default::default()
+        };
+        let mut pipeline = PipelineRunner::new();
+        pipeline.register_plugin(PipelineStage::Verification, Arc::new(RequestSigningPlugin::new(signing)));
+        // Reads the body a chunk at a time, as bulk loads do
+        let app = Router::new()
+            .route("/v1/graph/:tenant_id/nodes/bulk", post(|body: Body| async move {
+                let mut data = body.into_data_stream();
+                let mut lines = 0;
+                while let Some(chunk) = data.next().await {
+                    match chunk {
+                        Ok(chunk) => lines += chunk.iter().filter(|byte| **byte == b'\n').count(),
+                        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()),
+                    }
+                }
+                (StatusCode::OK, lines.to_string())
+            }))
+            .layer(axum::middleware::from_fn_with_state(Arc::new(pipeline), verify_requests));
+
+        let path = "/v1/graph/acme/nodes/bulk";
+        let line = "{\"label\":\"Person\",\"props\":{}}\n";
+        let send = |body: String, signed_body: &str, timestamp: i64| {
+            let mut request = Request::post(path);
+            for (name, value) in signature_headers("s3cret", timestamp, "POST", path, signed_body.as_bytes()) {
+                request = request.header(name, value);
+            }
+            let chunks = body.into_bytes().chunks(1024).map(|chunk| Ok::<_, io::Error>(chunk.to_vec())).collect::<Vec<_>>();
+            app.clone().call(request.body(Body::from_stream(stream::iter(chunks))).unwrap())
+        };
+        let read = |response: Response| async move {
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let body = line.repeat(1000);
+        let (status, lines) = read(send(body.clone(), &body, now).await.unwrap()).await;
+        assert_eq!((status, lines.as_str()), (StatusCode::OK, "1000"));
+
+        // A body that differs from the signed one fails at its end
+        let tampered = body.replacen("Person", "Admin!", 1);
+        let (status, error) = read(send(tampered, &body, now - 1).await.unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error.contains("does not match its digest"), "{}", error);
+
+        // So does one whose signature does not cover its digest
+        let (status, _) = read(send(body.clone(), &line.repeat(999), now - 2).await.unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn test_extract_tenant_id_not_found() {
         let headers = HeaderMap::new();
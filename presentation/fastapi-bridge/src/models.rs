@@ -1,6 +1,7 @@
 //! Data models for the FastAPI bridge
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use telamentis_core::prelude::*;
 
 /// Re-export core types for convenience
@@ -50,17 +51,35 @@ pub struct PaginatedResponse<T> {
     pub pagination: PaginationInfo,
 }
 
+impl PaginationParams {
+    /// The page these parameters ask for
+    pub fn page_request(&self) -> PageRequest {
+        PageRequest::new(self.page, self.limit, self.offset)
+    }
+}
+
 /// Pagination metadata
 #[derive(Debug, Serialize)]
 pub struct PaginationInfo {
     pub page: u32,
     pub limit: u32,
-    pub total: u64,
-    pub pages: u32,
+    pub offset: u32,
     pub has_next: bool,
     pub has_prev: bool,
 }
 
+impl PaginationInfo {
+    pub fn new(request: &PageRequest, has_next: bool) -> Self {
+        Self {
+            page: request.page(),
+            limit: request.limit,
+            offset: request.offset,
+            has_next,
+            has_prev: request.offset > 0,
+        }
+    }
+}
+
 /// Query parameters for filtering
 #[derive(Debug, Deserialize)]
 pub struct FilterParams {
@@ -68,7 +87,76 @@ pub struct FilterParams {
     pub properties: Option<String>,    // JSON object as string
     pub created_after: Option<String>, // ISO8601 datetime
     pub created_before: Option<String>, // ISO8601 datetime
-    pub valid_at: Option<String>,      // ISO8601 datetime for temporal queries
+    pub valid_at: Option<String>,      // ISO8601 datetime or date for temporal queries
+    pub collapse: Option<String>,      // by_kind or by_pair
+}
+
+impl FilterParams {
+    /// Query for the nodes matching these filters
+    pub fn node_query(&self) -> Result<GraphQuery, String> {
+        self.unsupported(&[
+            ("created_after", &self.created_after),
+            ("created_before", &self.created_before),
+            ("valid_at", &self.valid_at),
+            ("collapse", &self.collapse),
+        ])?;
+        Ok(GraphQuery::FindNodes {
+            labels: self.labels(),
+            properties: self.properties()?,
+            order_by: Vec::new(),
+            offset: None,
+            limit: None,
+        })
+    }
+
+    /// Query for the relationships matching these filters; `labels` are
+    /// relationship types
+    pub fn relationship_query(&self) -> Result<GraphQuery, String> {
+        self.unsupported(&[
+            ("properties", &self.properties),
+            ("created_after", &self.created_after),
+            ("created_before", &self.created_before),
+        ])?;
+        let valid_at = self.valid_at.as_deref()
+            .map(|value| TemporalUtils::parse_time(value, IntervalBound::AsOf)
+                .map_err(|e| format!("Invalid valid_at: {}", e)))
+            .transpose()?;
+        let collapse = self.collapse.as_deref()
+            .map(|value| serde_json::from_value::<Collapse>(serde_json::Value::String(value.to_string()))
+                .map_err(|_| format!("Invalid collapse: {} (expected by_kind or by_pair)", value)))
+            .transpose()?;
+        Ok(GraphQuery::FindRelationships {
+            from_node_id: None,
+            to_node_id: None,
+            relationship_types: self.labels(),
+            valid_at,
+            order_by: Vec::new(),
+            offset: None,
+            limit: None,
+            collapse,
+        })
+    }
+
+    fn labels(&self) -> Vec<String> {
+        self.labels.as_deref()
+            .map(|labels| labels.split(',').map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    fn properties(&self) -> Result<HashMap<String, serde_json::Value>, String> {
+        match &self.properties {
+            Some(properties) => serde_json::from_str(properties)
+                .map_err(|e| format!("Invalid properties filter: {}", e)),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    fn unsupported(&self, filters: &[(&str, &Option<String>)]) -> Result<(), String> {
+        match filters.iter().find(|(_, value)| value.is_some()) {
+            Some((name, _)) => Err(format!("Filter '{}' is not supported here", name)),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Bulk operation result
@@ -188,6 +276,34 @@ mod tests {
         assert!(params.offset.is_none());
     }
 
+    #[test]
+    fn test_filter_params_queries() {
+        let filters = FilterParams {
+            labels: Some("Person, Company".to_string()),
+            properties: Some(r#"{"active": true}"#.to_string()),
+            created_after: None,
+            created_before: None,
+            valid_at: None,
+            collapse: None,
+        };
+        let GraphQuery::FindNodes { labels, properties, .. } = filters.node_query().unwrap() else {
+            panic!("expected a node query");
+        };
+        assert_eq!(labels, vec!["Person", "Company"]);
+        assert_eq!(properties.get("active"), Some(&serde_json::json!(true)));
+
+        // Relationships cannot be filtered by property
+        assert!(filters.relationship_query().is_err());
+
+        let filters = FilterParams { properties: None, collapse: Some("by_pair".to_string()), ..filters };
+        let GraphQuery::FindRelationships { collapse, .. } = filters.relationship_query().unwrap() else {
+            panic!("expected a relationship query");
+        };
+        assert_eq!(collapse, Some(Collapse::ByPair));
+        assert!(filters.node_query().is_err());
+        assert!(FilterParams { collapse: Some("by_label".to_string()), ..filters }.relationship_query().is_err());
+    }
+
     #[test]
     fn test_bulk_operation_result() {
         let result = BulkOperationResult {
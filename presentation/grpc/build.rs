@@ -1,4 +1,7 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("proto/telamentis.proto")?;
+    tonic_build::configure().compile(
+        &["proto/telamentis.proto", "proto/telamentis_v2.proto"],
+        &["proto"],
+    )?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,125 @@
+//! Access log of gRPC calls
+//!
+//! A tower layer around all services records each call once its response
+//! headers are ready. Calls that fail before streaming carry their status in
+//! the headers; any other call is logged as successful.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use telamentis_core::access_log::attributed;
+use telamentis_core::prelude::*;
+use tonic::codegen::http;
+use tonic::Code;
+
+/// RPCs that only read, by prefix of their name; every other RPC is a write
+const READ_RPC_PREFIXES: &[&str] = &["Execute", "Get", "List", "HealthCheck", "ExtractKnowledge", "CompleteText"];
+
+/// HTTP status equivalent to a gRPC status code
+fn http_status(code: Code) -> u16 {
+    match code {
+        Code::Ok => 200,
+        Code::Cancelled => 499,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => 400,
+        Code::Unauthenticated => 401,
+        Code::PermissionDenied => 403,
+        Code::NotFound => 404,
+        Code::AlreadyExists | Code::Aborted => 409,
+        Code::ResourceExhausted => 429,
+        Code::Unimplemented => 501,
+        Code::Unavailable => 503,
+        Code::DeadlineExceeded => 504,
+        Code::Unknown | Code::Internal | Code::DataLoss => 500,
+    }
+}
+
+/// Length of a message as announced in its headers
+fn content_length(headers: &http::HeaderMap) -> Option<u64> {
+    headers.get(http::header::CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+/// Layer recording every call in the access log
+#[derive(Clone)]
+pub(crate) struct AccessLogLayer {
+    access_log: Arc<AccessLog>,
+}
+
+impl AccessLogLayer {
+    pub(crate) fn new(access_log: Arc<AccessLog>) -> Self {
+        Self { access_log }
+    }
+}
+
+impl<S> tower::Layer<S> for AccessLogLayer {
+    type Service = AccessLogged<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AccessLogged { inner, access_log: self.access_log.clone() }
+    }
+}
+
+/// Service recording its calls in the access log
+#[derive(Clone)]
+pub(crate) struct AccessLogged<S> {
+    inner: S,
+    access_log: Arc<AccessLog>,
+}
+
+impl<S, B, ResBody> tower::Service<http::Request<B>> for AccessLogged<S>
+where
+    S: tower::Service<http::Request<B>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let path = request.uri().path().to_string();
+        let bytes_in = content_length(request.headers());
+        let access_log = self.access_log.clone();
+        let start_time = Instant::now();
+        let call = self.inner.call(request);
+
+        Box::pin(async move {
+            let (response, attribution) = attributed(call).await;
+
+            let status = match &response {
+                Ok(response) => response.headers().get("grpc-status")
+                    .and_then(|code| code.to_str().ok()?.parse::<i32>().ok())
+                    .map_or(200, |code| http_status(Code::from(code))),
+                Err(_) => 500,
+            };
+            let rpc = path.rsplit('/').next().unwrap_or_default().to_string();
+            let mut entry = AccessLogEntry::new("grpc", rpc.as_str(), status, start_time.elapsed());
+            entry.write = !READ_RPC_PREFIXES.iter().any(|prefix| rpc.starts_with(prefix));
+            entry.path = Some(path);
+            entry.tenant = attribution.tenant;
+            entry.principal = attribution.principal;
+            entry.bytes_in = bytes_in;
+            entry.bytes_out = response.as_ref().ok().and_then(|response| content_length(response.headers()));
+            access_log.record(entry);
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_status() {
+        assert_eq!(http_status(Code::Ok), 200);
+        assert_eq!(http_status(Code::NotFound), 404);
+        assert_eq!(http_status(Code::PermissionDenied), 403);
+        assert_eq!(http_status(Code::Unavailable), 503);
+    }
+}
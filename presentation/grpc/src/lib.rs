@@ -2,15 +2,28 @@
 
 use async_trait::async_trait;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
+use telamentis_core::access_log::{attribute_principal, attribute_tenant};
 use telamentis_core::prelude::*;
-use telamentis_core::pipeline::{PipelineRunner, PipelineStage, RequestLoggingPlugin, TenantValidationPlugin, AuditTrailPlugin};
-use tonic::{transport::Server, Request, Response, Status};
+use telamentis_core::pipeline::{PipelineRunner, PipelineStage, PluginRegistry, RequestLoggingPlugin, TenantPipelines, TenantValidationPlugin, AuditTrailPlugin};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{service::interceptor::InterceptedService, transport::Server, Request, Response, Status, Streaming};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-mod telamentis {
+mod access_log;
+
+use access_log::AccessLogLayer;
+
+/// Generated protobuf messages, servers and clients
+pub mod telamentis {
     tonic::include_proto!("telamentis");
+
+    pub mod v2 {
+        tonic::include_proto!("telamentis.v2");
+    }
 }
 
 use telamentis::{
@@ -19,15 +32,23 @@ use telamentis::{
     GetNodeRequest, GetNodeResponse,
     DeleteNodeRequest, DeleteNodeResponse,
     BatchUpsertNodesRequest, BatchUpsertNodesResponse,
+    UpsertNodeWithEdgesRequest, UpsertNodeWithEdgesResponse,
     UpsertEdgeRequest, UpsertEdgeResponse,
     DeleteEdgeRequest, DeleteEdgeResponse,
+    CloseEdgeRequest, SupersedeEdgeRequest, EdgeVersionResponse,
+    RetractEdgeRequest, RetractEdgeResponse,
     BatchUpsertEdgesRequest, BatchUpsertEdgesResponse,
     QueryRequest, QueryResponse,
+    CatalogRequest, CatalogResponse,
+    MaterializeSnapshotRequest, ListSnapshotsRequest, ListSnapshotsResponse,
+    DropSnapshotRequest, DropSnapshotResponse,
     ExtractRequest, ExtractResponse,
     CompleteRequest, CompleteResponse,
     HealthCheckRequest, HealthCheckResponse,
     Node as ProtoNode,
     TimeEdge as ProtoTimeEdge,
+    EdgeSpec as ProtoEdgeSpec,
+    edge_spec::Target as ProtoEdgeTarget,
     Path as ProtoPath,
     PathNode as ProtoPathNode,
     PathRelationship as ProtoPathRelationship,
@@ -35,9 +56,50 @@ use telamentis::{
     ExtractionNode as ProtoExtractionNode,
     ExtractionRelation as ProtoExtractionRelation,
     ExtractionMetadata as ProtoExtractionMetadata,
+    OrderBy as ProtoOrderBy,
+    CatalogEntry as ProtoCatalogEntry,
+    SnapshotInfo as ProtoSnapshotInfo,
+    order_by::Field as ProtoSortField,
     RawQuery, FindNodesQuery, FindRelationshipsQuery, AsOfQuery,
+    tela_mentis_admin_server::{TelaMentisAdmin, TelaMentisAdminServer},
+    AdminStatusRequest, AdminStatusResponse,
+    ListPluginsRequest, ListPluginsResponse, SetPluginEnabledRequest,
+    SetDefaultProviderRequest, SetDefaultProviderResponse,
+    FlushCachesRequest, FlushCachesResponse,
+    RunJobRequest, RunJobResponse,
+    DrainRequest, DrainResponse, ResumeRequest,
+    PluginState as ProtoPluginState,
+    JobReport as ProtoJobReport,
 };
 
+use telamentis::v2::{
+    tela_mentis_server::{TelaMentis as TelaMentisV2, TelaMentisServer as TelaMentisV2Server},
+    QueryPageRequest, QueryPageResponse,
+    QueryBatchRequest, QueryBatchResponse,
+    BatchQuery as ProtoBatchQuery,
+    BatchQueryResult as ProtoBatchQueryResult,
+    NodeAliasRequest, NodeByAliasResponse, DeleteNodeByAliasResponse, PatchNodeByAliasRequest,
+    UpsertEdgeByAliasRequest,
+    EdgeByAlias as ProtoEdgeByAlias,
+    edge_by_alias::From as ProtoEdgeFrom,
+    edge_by_alias::To as ProtoEdgeTo,
+    PageInfo as ProtoPageInfo,
+    MutationRecord, MutationAck as ProtoMutationAck, MutationStatus as ProtoMutationStatus,
+    mutation_record::Mutation as ProtoMutation,
+    ListOperationsRequest, ListOperationsResponse, CancelOperationRequest,
+    OperationInfo as ProtoOperationInfo,
+};
+
+/// Metadata key of the ID of a cancellable call, chosen by the client or
+/// reported in the response
+const OPERATION_METADATA: &str = "x-telamentis-operation";
+
+/// Metadata keys of the classification of a failed call's error: its code,
+/// whether it is retryable, and how long to wait before retrying
+const ERROR_CODE_METADATA: &str = "x-telamentis-error-code";
+const ERROR_KIND_METADATA: &str = "x-telamentis-error-kind";
+const RETRY_AFTER_METADATA: &str = "x-telamentis-retry-after-ms";
+
 /// gRPC server configuration
 #[derive(Debug, Clone)]
 pub struct GrpcConfig {
@@ -45,6 +107,16 @@ pub struct GrpcConfig {
     pub bind_address: SocketAddr,
     /// Request timeout in seconds
     pub request_timeout: u64,
+    /// Per-tenant defaults for the valid times of extracted relations
+    pub valid_time: ValidTimePolicies,
+    /// Per-tenant summaries of the graph added to extraction prompts
+    pub graph_context: GraphContextPolicies,
+    /// Backpressure and ack cadence of `StreamMutations`
+    pub mutation_stream: MutationApplierConfig,
+    /// Pipelines of tenants that do not use the default plugins
+    pub pipelines: TenantPipelines,
+    /// Sampling and destination of the access log
+    pub access_log: AccessLogConfig,
 }
 
 impl Default for GrpcConfig {
@@ -52,6 +124,11 @@ impl Default for GrpcConfig {
         Self {
             bind_address: "0.0.0.0:50051".parse().unwrap(),
             request_timeout: 30,
+            valid_time: ValidTimePolicies::default(),
+            graph_context: GraphContextPolicies::default(),
+            mutation_stream: MutationApplierConfig::default(),
+            pipelines: TenantPipelines::default(),
+            access_log: AccessLogConfig::default(),
         }
     }
 }
@@ -60,6 +137,10 @@ impl Default for GrpcConfig {
 pub struct GrpcAdapter {
     config: GrpcConfig,
     pipeline: Arc<PipelineRunner>,
+    examples: Arc<FewShotStore>,
+    plugins: Arc<PluginRegistry>,
+    admin: Option<Arc<AdminControls>>,
+    operations: Arc<OperationRegistry>,
 }
 
 impl GrpcAdapter {
@@ -70,11 +151,16 @@ impl GrpcAdapter {
         // Register built-in plugins
         pipeline.register_plugin(PipelineStage::PreOperation, Arc::new(RequestLoggingPlugin::new()));
         pipeline.register_plugin(PipelineStage::PreOperation, Arc::new(TenantValidationPlugin::new()));
+        pipeline.register_plugin(PipelineStage::PreExtraction, Arc::new(ExtractionSafetyPlugin::new()));
         pipeline.register_plugin(PipelineStage::PostOperation, Arc::new(AuditTrailPlugin::new()));
         
         Self { 
             config, 
             pipeline: Arc::new(pipeline),
+            examples: Arc::new(FewShotStore::new()),
+            plugins: Arc::new(PluginRegistry::with_builtins()),
+            admin: None,
+            operations: Arc::new(OperationRegistry::new()),
         }
     }
     
@@ -83,8 +169,38 @@ impl GrpcAdapter {
         Self {
             config,
             pipeline: Arc::new(pipeline),
+            examples: Arc::new(FewShotStore::new()),
+            plugins: Arc::new(PluginRegistry::with_builtins()),
+            admin: None,
+            operations: Arc::new(OperationRegistry::new()),
         }
     }
+    
+    /// Use a shared few-shot example store, e.g. the one managed over HTTP
+    pub fn with_example_store(mut self, examples: Arc<FewShotStore>) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    /// Compose the configured tenant pipelines from these plugins instead of the built-in ones
+    pub fn with_plugin_registry(mut self, plugins: PluginRegistry) -> Self {
+        self.plugins = Arc::new(plugins);
+        self
+    }
+
+    /// Track cancellable calls in the given registry, e.g. the one of the
+    /// HTTP bridge, so that either can cancel them
+    pub fn with_operations(mut self, operations: Arc<OperationRegistry>) -> Self {
+        self.operations = operations;
+        self
+    }
+
+    /// Serve the `TelaMentisAdmin` service, authorized by the admin secret,
+    /// and refuse other calls while an operator drains the server
+    pub fn with_admin(mut self, admin: Arc<AdminControls>) -> Self {
+        self.admin = Some(admin);
+        self
+    }
 }
 
 /// Convert from protobuf Node to core Node
@@ -99,6 +215,10 @@ fn proto_to_core_node(proto: &ProtoNode) -> Result<Node, tonic::Status> {
         node = node.with_id_alias(id_alias);
     }
 
+    if let Some(namespace) = &proto.alias_namespace {
+        node = node.with_alias_namespace(namespace);
+    }
+
     Ok(node)
 }
 
@@ -111,9 +231,258 @@ fn core_to_proto_node(core: &Node) -> Result<ProtoNode, tonic::Status> {
         id_alias: core.id_alias.clone(),
         label: core.label.clone(),
         props_json,
+        alias_namespace: core.alias_namespace.clone(),
     })
 }
 
+/// Convert from core OrderBy to protobuf OrderBy
+fn core_to_proto_catalog_entry(core: &CatalogEntry) -> ProtoCatalogEntry {
+    ProtoCatalogEntry {
+        name: core.name.clone(),
+        count: core.count as i64,
+        property_keys: core.property_keys.iter()
+            .map(|(key, count)| (key.clone(), *count as i64))
+            .collect(),
+    }
+}
+
+fn core_to_proto_snapshot_info(core: &SnapshotInfo) -> ProtoSnapshotInfo {
+    ProtoSnapshotInfo {
+        name: core.name.clone(),
+        valid_at: core.valid_at.to_rfc3339(),
+        snapshot_at: core.snapshot_at.to_rfc3339(),
+        node_count: core.node_count as i64,
+        edge_count: core.edge_count as i64,
+    }
+}
+
+fn core_to_proto_order_by(core: &OrderBy) -> ProtoOrderBy {
+    let field = match &core.field {
+        SortField::Property(name) => ProtoSortField::Property(name.clone()),
+        SortField::CreatedAt => ProtoSortField::CreatedAt(true),
+        SortField::Label => ProtoSortField::Label(true),
+    };
+
+    ProtoOrderBy {
+        field: Some(field),
+        descending: core.direction == SortDirection::Desc,
+    }
+}
+
+/// Convert from protobuf OrderBy to core OrderBy
+fn proto_to_core_order_by(proto: &ProtoOrderBy) -> Result<OrderBy, tonic::Status> {
+    let field = match &proto.field {
+        Some(ProtoSortField::Property(name)) => SortField::Property(name.clone()),
+        Some(ProtoSortField::CreatedAt(_)) => SortField::CreatedAt,
+        Some(ProtoSortField::Label(_)) => SortField::Label,
+        None => return Err(Status::invalid_argument("Sort key is missing a field")),
+    };
+
+    Ok(if proto.descending { OrderBy::desc(field) } else { OrderBy::asc(field) })
+}
+
+/// Parse a relationship query's collapse
+fn proto_to_core_collapse(proto: &str) -> Result<Collapse, tonic::Status> {
+    serde_json::from_value(serde_json::Value::String(proto.to_string()))
+        .map_err(|_| Status::invalid_argument(format!("Invalid collapse: {}", proto)))
+}
+
+/// Parse a request's write concern, `committed` if unset
+fn proto_to_core_write_concern(proto: Option<&str>) -> Result<WriteConcern, tonic::Status> {
+    match proto {
+        None | Some("") => Ok(WriteConcern::default()),
+        Some(value) => serde_json::from_value(serde_json::Value::String(value.to_string()))
+            .map_err(|_| Status::invalid_argument(format!("Invalid write concern: {}", value))),
+    }
+}
+
+/// Convert from a protobuf batch query to a core batch query
+fn proto_to_core_batch_query(proto: &ProtoBatchQuery) -> Result<BatchQuery, tonic::Status> {
+    let query = proto.query.as_ref()
+        .ok_or_else(|| Status::invalid_argument("Batch query is missing its query"))?;
+    if query.snapshot.is_some() {
+        return Err(Status::invalid_argument("Batch queries cannot query a snapshot"));
+    }
+    let mode = match proto.mode.as_deref() {
+        None | Some("") => QueryMode::default(),
+        Some(value) => serde_json::from_value(serde_json::Value::String(value.to_string()))
+            .map_err(|_| Status::invalid_argument(format!("Invalid query mode: {}", value)))?,
+    };
+
+    Ok(BatchQuery::new(proto_to_core_query(query)?).with_mode(mode))
+}
+
+/// Convert from a core batch query result to protobuf
+fn core_to_proto_batch_query_result(core: &BatchQueryResult) -> Result<ProtoBatchQueryResult, tonic::Status> {
+    Ok(ProtoBatchQueryResult {
+        paths: core.paths.iter().map(core_to_proto_path).collect::<Result<Vec<_>, _>>()?,
+        count: core.count,
+        exists: core.exists,
+        error: core.error.clone(),
+        execution_time_ms: core.execution_time_ms as i64,
+    })
+}
+
+/// ID of a write for a response; buffered writes have none yet
+fn proto_write_id(id: Uuid) -> String {
+    if id.is_nil() { String::new() } else { id.to_string() }
+}
+
+/// Convert from protobuf EdgeSpec to core EdgeSpec
+/// Parse a valid time sent as RFC 3339 or as a date such as "2020-03", which
+/// stands for the instant `bound` gives it
+fn parse_valid_time(value: &str, bound: IntervalBound, field: &str) -> Result<chrono::DateTime<chrono::Utc>, tonic::Status> {
+    TemporalUtils::parse_time(value, bound)
+        .map_err(|e| Status::invalid_argument(format!("Invalid {}: {}", field, e)))
+}
+
+fn proto_to_core_edge_spec(proto: &ProtoEdgeSpec) -> Result<EdgeSpec, tonic::Status> {
+    let target = match &proto.target {
+        Some(ProtoEdgeTarget::TargetNodeId(id)) => NodeRef::Id(
+            Uuid::parse_str(id)
+                .map_err(|e| Status::invalid_argument(format!("Invalid target_node_id: {}", e)))?
+        ),
+        Some(ProtoEdgeTarget::TargetIdAlias(alias)) => NodeRef::Alias(AliasKey {
+            namespace: proto.target_alias_namespace.clone(),
+            alias: alias.clone(),
+        }),
+        None => return Err(Status::invalid_argument("Edge spec is missing a target")),
+    };
+
+    let props = if proto.props_json.is_empty() {
+        serde_json::json!({})
+    } else {
+        serde_json::from_str(&proto.props_json)
+            .map_err(|e| Status::invalid_argument(format!("Invalid JSON for props: {}", e)))?
+    };
+
+    let mut spec = EdgeSpec::new(&proto.kind, target).with_props(props);
+
+    if proto.incoming {
+        spec = spec.incoming();
+    }
+
+    if let Some(vf) = &proto.valid_from {
+        spec = spec.with_valid_from(parse_valid_time(vf, IntervalBound::Start, "valid_from")?);
+    }
+
+    if let Some(vt) = &proto.valid_to {
+        spec = spec.with_valid_to(parse_valid_time(vt, IntervalBound::End, "valid_to")?);
+    }
+
+    if let Some(ea) = &proto.expires_at {
+        spec = spec.with_expires_at(
+            chrono::DateTime::parse_from_rfc3339(ea)
+                .map_err(|e| Status::invalid_argument(format!("Invalid expires_at: {}", e)))?
+                .with_timezone(&chrono::Utc)
+        );
+    }
+
+    Ok(spec)
+}
+
+fn proto_to_core_edge_by_ref(proto: &ProtoEdgeByAlias) -> Result<EdgeByRef, tonic::Status> {
+    let from = match &proto.from {
+        Some(ProtoEdgeFrom::FromNodeId(id)) => NodeRef::Id(
+            Uuid::parse_str(id)
+                .map_err(|e| Status::invalid_argument(format!("Invalid from_node_id: {}", e)))?
+        ),
+        Some(ProtoEdgeFrom::FromIdAlias(alias)) => NodeRef::Alias(AliasKey {
+            namespace: proto.from_alias_namespace.clone(),
+            alias: alias.clone(),
+        }),
+        None => return Err(Status::invalid_argument("Edge is missing its source")),
+    };
+    let to = match &proto.to {
+        Some(ProtoEdgeTo::ToNodeId(id)) => NodeRef::Id(
+            Uuid::parse_str(id)
+                .map_err(|e| Status::invalid_argument(format!("Invalid to_node_id: {}", e)))?
+        ),
+        Some(ProtoEdgeTo::ToIdAlias(alias)) => NodeRef::Alias(AliasKey {
+            namespace: proto.to_alias_namespace.clone(),
+            alias: alias.clone(),
+        }),
+        None => return Err(Status::invalid_argument("Edge is missing its target")),
+    };
+
+    let mut edge = EdgeByRef::new(from, to, &proto.kind);
+    if !proto.props_json.is_empty() {
+        edge.props = serde_json::from_str(&proto.props_json)
+            .map_err(|e| Status::invalid_argument(format!("Invalid JSON for props: {}", e)))?;
+    }
+    if let Some(vf) = &proto.valid_from {
+        edge.valid_from = Some(parse_valid_time(vf, IntervalBound::Start, "valid_from")?);
+    }
+    if let Some(vt) = &proto.valid_to {
+        edge.valid_to = Some(parse_valid_time(vt, IntervalBound::End, "valid_to")?);
+    }
+    if let Some(ea) = &proto.expires_at {
+        edge.expires_at = Some(
+            chrono::DateTime::parse_from_rfc3339(ea)
+                .map_err(|e| Status::invalid_argument(format!("Invalid expires_at: {}", e)))?
+                .with_timezone(&chrono::Utc)
+        );
+    }
+
+    Ok(edge)
+}
+
+fn proto_to_alias_key(alias: &str, namespace: Option<&str>) -> AliasKey {
+    AliasKey {
+        namespace: namespace.map(str::to_string),
+        alias: alias.to_string(),
+    }
+}
+
+fn core_to_proto_node_by_alias(record: Option<NodeRecord>) -> Result<NodeByAliasResponse, Status> {
+    Ok(match record {
+        Some(record) => NodeByAliasResponse {
+            found: true,
+            node_id: record.id.to_string(),
+            node: Some(core_to_proto_node(&record.node)?),
+        },
+        None => NodeByAliasResponse { found: false, node_id: String::new(), node: None },
+    })
+}
+
+/// Convert a streamed protobuf record to a core mutation
+fn proto_to_core_mutation(proto: &MutationRecord) -> Result<GraphMutation, tonic::Status> {
+    if proto.tenant_id.is_empty() {
+        return Err(Status::invalid_argument("Missing tenant_id"));
+    }
+    let parse_id = |id: &str| Uuid::parse_str(id)
+        .map_err(|e| Status::invalid_argument(format!("Invalid ID: {}", e)));
+
+    match proto.mutation.as_ref().ok_or_else(|| Status::invalid_argument("Missing mutation"))? {
+        ProtoMutation::UpsertNode(node) => Ok(GraphMutation::UpsertNode(proto_to_core_node(node)?)),
+        ProtoMutation::UpsertEdge(edge) => Ok(GraphMutation::UpsertEdge(proto_to_core_edge(edge)?)),
+        ProtoMutation::DeleteNodeId(id) => Ok(GraphMutation::DeleteNode { id: parse_id(id)? }),
+        ProtoMutation::DeleteEdgeId(id) => Ok(GraphMutation::DeleteEdge { id: parse_id(id)? }),
+    }
+}
+
+/// Convert from core MutationAck to protobuf MutationAck
+fn core_to_proto_mutation_ack(core: MutationAck) -> ProtoMutationAck {
+    let statuses = core.results.into_iter().map(|result| {
+        let mut status = ProtoMutationStatus { sequence: result.sequence, ok: true, ..Default::default() };
+        match result.status {
+            MutationStatus::Applied { id } => status.id = id.to_string(),
+            MutationStatus::Deleted { deleted } => status.deleted = deleted,
+            MutationStatus::Failed { error } => {
+                status.ok = false;
+                status.error = error;
+            }
+        }
+        status
+    }).collect();
+
+    ProtoMutationAck {
+        statuses,
+        applied: core.applied,
+        failed: core.failed,
+    }
+}
+
 /// Convert from protobuf TimeEdge to core TimeEdge
 fn proto_to_core_edge(proto: &ProtoTimeEdge) -> Result<TimeEdge, tonic::Status> {
     let from_node_id = Uuid::parse_str(&proto.from_node_id)
@@ -121,16 +490,10 @@ fn proto_to_core_edge(proto: &ProtoTimeEdge) -> Result<TimeEdge, tonic::Status>
     let to_node_id = Uuid::parse_str(&proto.to_node_id)
         .map_err(|e| Status::invalid_argument(format!("Invalid to_node_id: {}", e)))?;
     
-    let valid_from = chrono::DateTime::parse_from_rfc3339(&proto.valid_from)
-        .map_err(|e| Status::invalid_argument(format!("Invalid valid_from: {}", e)))?
-        .with_timezone(&chrono::Utc);
+    let valid_from = parse_valid_time(&proto.valid_from, IntervalBound::Start, "valid_from")?;
     
     let valid_to = if let Some(vt) = &proto.valid_to {
-        Some(
-            chrono::DateTime::parse_from_rfc3339(vt)
-                .map_err(|e| Status::invalid_argument(format!("Invalid valid_to: {}", e)))?
-                .with_timezone(&chrono::Utc)
-        )
+        Some(parse_valid_time(vt, IntervalBound::End, "valid_to")?)
     } else {
         None
     };
@@ -163,6 +526,14 @@ fn proto_to_core_edge(proto: &ProtoTimeEdge) -> Result<TimeEdge, tonic::Status>
         edge = edge.with_transaction_end_time(tet);
     }
 
+    if let Some(ea) = &proto.expires_at {
+        edge = edge.with_expires_at(
+            chrono::DateTime::parse_from_rfc3339(ea)
+                .map_err(|e| Status::invalid_argument(format!("Invalid expires_at: {}", e)))?
+                .with_timezone(&chrono::Utc)
+        );
+    }
+
     Ok(edge)
 }
 
@@ -180,6 +551,7 @@ fn core_to_proto_edge(core: &TimeEdge) -> Result<ProtoTimeEdge, tonic::Status> {
         transaction_start_time: core.transaction_start_time.to_rfc3339(),
         transaction_end_time: core.transaction_end_time.map(|dt| dt.to_rfc3339()),
         props_json,
+        expires_at: core.expires_at.map(|dt| dt.to_rfc3339()),
     })
 }
 
@@ -188,6 +560,7 @@ fn proto_to_core_message(proto: &ProtoLlmMessage) -> LlmMessage {
     LlmMessage {
         role: proto.role.clone(),
         content: proto.content.clone(),
+        parts: Vec::new(),
     }
 }
 
@@ -208,6 +581,7 @@ fn core_to_proto_query(query: &GraphQuery) -> Result<QueryRequest, tonic::Status
 
             Ok(QueryRequest {
                 tenant_id: "".to_string(), // Will be set by caller
+                snapshot: None,
                 query: Some(telamentis::query_request::Query::RawQuery(
                     RawQuery {
                         query_string: query.clone(),
@@ -216,24 +590,28 @@ fn core_to_proto_query(query: &GraphQuery) -> Result<QueryRequest, tonic::Status
                 )),
             })
         },
-        GraphQuery::FindNodes { labels, properties, limit } => {
+        GraphQuery::FindNodes { labels, properties, order_by, offset, limit } => {
             let properties_json = serde_json::to_string(properties)
                 .map_err(|e| Status::internal(format!("Failed to serialize properties: {}", e)))?;
 
             Ok(QueryRequest {
                 tenant_id: "".to_string(), // Will be set by caller
+                snapshot: None,
                 query: Some(telamentis::query_request::Query::FindNodesQuery(
                     FindNodesQuery {
                         labels: labels.clone(),
                         properties_json,
                         limit: limit.map(|l| l as i32),
+                        order_by: order_by.iter().map(core_to_proto_order_by).collect(),
+                        offset: offset.map(|o| o as i32),
                     }
                 )),
             })
         },
-        GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, limit } => {
+        GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse } => {
             Ok(QueryRequest {
                 tenant_id: "".to_string(), // Will be set by caller
+                snapshot: None,
                 query: Some(telamentis::query_request::Query::FindRelationshipsQuery(
                     FindRelationshipsQuery {
                         from_node_id: from_node_id.map(|id| id.to_string()),
@@ -241,6 +619,9 @@ fn core_to_proto_query(query: &GraphQuery) -> Result<QueryRequest, tonic::Status
                         relationship_types: relationship_types.clone(),
                         valid_at: valid_at.map(|dt| dt.to_rfc3339()),
                         limit: limit.map(|l| l as i32),
+                        order_by: order_by.iter().map(core_to_proto_order_by).collect(),
+                        offset: offset.map(|o| o as i32),
+                        collapse: collapse.map(|collapse| collapse.as_str().to_string()),
                     }
                 )),
             })
@@ -250,6 +631,7 @@ fn core_to_proto_query(query: &GraphQuery) -> Result<QueryRequest, tonic::Status
             
             Ok(QueryRequest {
                 tenant_id: "".to_string(), // Will be set by caller
+                snapshot: None,
                 query: Some(telamentis::query_request::Query::AsOfQuery(
                     Box::new(AsOfQuery {
                         base_query: Some(Box::new(base_proto_query)),
@@ -281,6 +663,8 @@ fn proto_to_core_query(proto: &QueryRequest) -> Result<GraphQuery, tonic::Status
             Ok(GraphQuery::FindNodes {
                 labels: find_nodes.labels.clone(),
                 properties,
+                order_by: find_nodes.order_by.iter().map(proto_to_core_order_by).collect::<Result<_, _>>()?,
+                offset: find_nodes.offset.map(|o| o as u32),
                 limit: find_nodes.limit.map(|l| l as u32),
             })
         },
@@ -300,9 +684,7 @@ fn proto_to_core_query(proto: &QueryRequest) -> Result<GraphQuery, tonic::Status
             };
             
             let valid_at = if let Some(time_str) = &find_rels.valid_at {
-                Some(chrono::DateTime::parse_from_rfc3339(time_str)
-                    .map_err(|e| Status::invalid_argument(format!("Invalid valid_at timestamp: {}", e)))?
-                    .with_timezone(&chrono::Utc))
+                Some(parse_valid_time(time_str, IntervalBound::AsOf, "valid_at")?)
             } else {
                 None
             };
@@ -312,16 +694,17 @@ fn proto_to_core_query(proto: &QueryRequest) -> Result<GraphQuery, tonic::Status
                 to_node_id,
                 relationship_types: find_rels.relationship_types.clone(),
                 valid_at,
+                order_by: find_rels.order_by.iter().map(proto_to_core_order_by).collect::<Result<_, _>>()?,
+                offset: find_rels.offset.map(|o| o as u32),
                 limit: find_rels.limit.map(|l| l as u32),
+                collapse: find_rels.collapse.as_deref().map(proto_to_core_collapse).transpose()?,
             })
         },
         Some(telamentis::query_request::Query::AsOfQuery(as_of)) => {
             if let Some(base) = &as_of.base_query {
                 let core_base_query = proto_to_core_query(base)?;
                 
-                let as_of_time = chrono::DateTime::parse_from_rfc3339(&as_of.as_of_time)
-                    .map_err(|e| Status::invalid_argument(format!("Invalid as_of_time timestamp: {}", e)))?
-                    .with_timezone(&chrono::Utc);
+                let as_of_time = parse_valid_time(&as_of.as_of_time, IntervalBound::AsOf, "as_of_time")?;
                 
                 Ok(GraphQuery::AsOfQuery {
                     base_query: Box::new(core_base_query),
@@ -354,6 +737,10 @@ fn core_to_proto_path(core: &Path) -> Result<ProtoPath, tonic::Status> {
     for rel in &core.relationships {
         let properties_json = serde_json::to_string(&rel.properties)
             .map_err(|e| Status::internal(format!("Failed to serialize properties: {}", e)))?;
+        let collapsed_json = rel.collapsed.as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| Status::internal(format!("Failed to serialize collapsed relationship: {}", e)))?;
             
         relationships.push(ProtoPathRelationship {
             id: rel.id.to_string(),
@@ -361,6 +748,7 @@ fn core_to_proto_path(core: &Path) -> Result<ProtoPath, tonic::Status> {
             start_node_id: rel.start_node_id.to_string(),
             end_node_id: rel.end_node_id.to_string(),
             properties_json,
+            collapsed_json,
         });
     }
     
@@ -417,46 +805,149 @@ fn core_to_proto_extraction(core: &ExtractionEnvelope) -> Result<ExtractResponse
 }
 
 /// Convert from protobuf ExtractRequest to core ExtractionContext
-fn proto_to_core_extraction_context(proto: &ExtractRequest) -> ExtractionContext {
+fn proto_to_core_extraction_context(proto: &ExtractRequest) -> Result<ExtractionContext, tonic::Status> {
     let messages = proto.messages.iter()
         .map(proto_to_core_message)
         .collect();
+
+    let timestamp = proto.source_timestamp.as_ref()
+        .map(|t| chrono::DateTime::parse_from_rfc3339(t)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .map_err(|e| Status::invalid_argument(format!("Invalid source_timestamp: {}", e))))
+        .transpose()?;
+    let properties = proto.source_properties_json.as_ref()
+        .map(|json| serde_json::from_str(json)
+            .map_err(|e| Status::invalid_argument(format!("Invalid JSON for source properties: {}", e))))
+        .transpose()?
+        .unwrap_or_default();
+    let source = (timestamp.is_some() || proto.source_properties_json.is_some())
+        .then_some(SourceInfo { timestamp, properties });
     
-    ExtractionContext {
+    Ok(ExtractionContext {
         messages,
         system_prompt: proto.system_prompt.clone(),
         desired_schema: proto.desired_schema.clone(),
         max_tokens: proto.max_tokens.map(|t| t as u32),
         temperature: proto.temperature,
+        examples: None,
+        model: proto.model.clone(),
+        provider: proto.provider.clone(),
+        source,
+        seed: None,
+        graph_context: None,
+    })
+}
+
+/// Convert an operation in flight to protobuf
+fn core_to_proto_operation(operation: &OperationInfo) -> ProtoOperationInfo {
+    ProtoOperationInfo {
+        operation_id: operation.id.to_string(),
+        tenant_id: operation.tenant.to_string(),
+        kind: operation.kind.to_string(),
+        started_at: operation.started_at.to_rfc3339(),
+        cancelled: operation.cancelled,
     }
 }
 
+/// Tenant a call is made for, attributed to the call in the access log
+fn request_tenant(tenant_id: &str) -> TenantId {
+    let tenant = TenantId::new(tenant_id);
+    attribute_tenant(&tenant);
+    tenant
+}
+
 /// Convert from core CoreError to gRPC Status
 fn core_error_to_status(error: CoreError) -> Status {
-    match error {
+    let (code, kind, retry_after) = (error.code(), error.kind(), error.retry_after());
+    let mut status = match error {
         CoreError::Storage(GraphError::NodeNotFound(msg)) => Status::not_found(msg),
         CoreError::Storage(GraphError::EdgeNotFound(msg)) => Status::not_found(msg),
+        CoreError::Storage(GraphError::SnapshotNotFound(msg)) => Status::not_found(msg),
         CoreError::Storage(GraphError::ConstraintViolation(msg)) => Status::failed_precondition(msg),
         CoreError::Storage(GraphError::TenantIsolationViolation(msg)) => Status::permission_denied(msg),
+        CoreError::Storage(GraphError::ReservedProperty(msg)) => Status::invalid_argument(msg),
+        CoreError::Storage(GraphError::InvalidTenant(e)) => Status::invalid_argument(e.to_string()),
+        CoreError::Storage(GraphError::Temporal(e)) => Status::invalid_argument(e.to_string()),
+        CoreError::Storage(GraphError::Unsupported(msg)) => Status::unimplemented(msg),
         CoreError::Storage(GraphError::ConnectionFailed(msg)) => Status::unavailable(msg),
         CoreError::Storage(GraphError::Timeout(msg)) => Status::deadline_exceeded(msg),
+        CoreError::Storage(GraphError::Overloaded(msg)) => Status::resource_exhausted(msg),
         CoreError::Storage(_) => Status::internal("Database error"),
         CoreError::Llm(LlmError::BudgetExceeded) => Status::resource_exhausted("LLM budget exceeded"),
         CoreError::Llm(LlmError::Timeout) => Status::deadline_exceeded("LLM request timeout"),
+        CoreError::Llm(LlmError::RateLimited(_)) => Status::resource_exhausted("LLM provider rate limit reached"),
+        CoreError::Llm(LlmError::UnsafeInput(msg)) => Status::invalid_argument(msg),
+        CoreError::Llm(LlmError::ContextLengthExceeded(msg)) => Status::invalid_argument(msg),
+        CoreError::Llm(LlmError::CapabilityUnavailable(msg)) => Status::unavailable(msg),
+        CoreError::Llm(LlmError::ProviderNotAllowed(msg)) => Status::permission_denied(msg),
+        CoreError::Llm(LlmError::ContentBlocked(msg)) => Status::failed_precondition(msg),
         CoreError::Llm(_) => Status::unavailable("LLM service error"),
         CoreError::Tenant(msg) => Status::invalid_argument(format!("Tenant error: {}", msg)),
+        CoreError::Cancelled(msg) => Status::cancelled(msg),
+        CoreError::Pipeline(PipelineError::PipelineHalted(msg)) => Status::failed_precondition(msg),
         CoreError::Pipeline(err) => Status::internal(format!("Pipeline error: {}", err)),
+        CoreError::Vector(err @ (VectorError::DimensionMismatch { .. } | VectorError::InvalidVector(_))) => Status::invalid_argument(err.to_string()),
+        CoreError::Vector(err) => Status::internal(format!("Vector index error: {}", err)),
+        CoreError::Archive(err @ ArchiveError::Storage(_)) => Status::unavailable(err.to_string()),
+        CoreError::Archive(err) => Status::internal(format!("Archive error: {}", err)),
+        CoreError::Analytics(err @ AnalyticsError::InvalidQuery(_)) => Status::invalid_argument(err.to_string()),
+        CoreError::Analytics(err) => Status::internal(format!("Analytics error: {}", err)),
+        CoreError::Auth(err @ (AuthError::InvalidToken | AuthError::TokenExpired | AuthError::TokenRevoked | AuthError::InvalidSignature(_))) => Status::unauthenticated(err.to_string()),
+        CoreError::Auth(err @ (AuthError::WrongTenant(_) | AuthError::InsufficientScope { .. })) => Status::permission_denied(err.to_string()),
+        CoreError::Auth(err @ AuthError::TokenNotFound(_)) => Status::not_found(err.to_string()),
+        CoreError::Auth(err) => Status::internal(format!("Authentication error: {}", err)),
+        CoreError::Session(err @ SessionError::NotFound(_)) => Status::not_found(err.to_string()),
+        CoreError::Session(err @ SessionError::LimitReached(_)) => Status::resource_exhausted(err.to_string()),
+        CoreError::Session(err) => Status::invalid_argument(err.to_string()),
+        CoreError::View(err @ ViewError::NotFound(_)) => Status::not_found(err.to_string()),
+        CoreError::View(err @ ViewError::InUse(_)) => Status::failed_precondition(err.to_string()),
+        CoreError::View(err) => Status::invalid_argument(err.to_string()),
         CoreError::Temporal(msg) => Status::invalid_argument(format!("Temporal query error: {}", msg)),
         CoreError::Configuration(msg) => Status::internal(format!("Configuration error: {}", msg)),
         CoreError::Serialization(err) => Status::invalid_argument(format!("Serialization error: {}", err)),
         CoreError::Internal(msg) => Status::internal(msg),
+    };
+
+    let metadata = status.metadata_mut();
+    metadata.insert(ERROR_CODE_METADATA, tonic::metadata::MetadataValue::from_static(code));
+    metadata.insert(ERROR_KIND_METADATA, tonic::metadata::MetadataValue::from_static(kind.as_str()));
+    if let Some(retry_after) = retry_after {
+        metadata.insert(RETRY_AFTER_METADATA, (retry_after.as_millis() as u64).into());
     }
+    status
 }
 
 /// gRPC service implementation
 struct TelaMentisService {
     core_service: Arc<dyn GraphService>,
     pipeline: Arc<PipelineRunner>,
+    examples: Arc<FewShotStore>,
+    graph_context: GraphContextBuilder,
+    valid_time: ValidTimePolicies,
+    operations: Arc<OperationRegistry>,
+}
+
+impl TelaMentisService {
+    /// Start tracking a cancellable call of the tenant, under the ID the
+    /// client chose in the operation metadata or a new one
+    fn start_operation<T>(&self, request: &Request<T>, tenant: &TenantId, kind: OperationKind) -> Result<Operation, Status> {
+        let requested = request.metadata().get(OPERATION_METADATA)
+            .map(|value| value.to_str().ok().and_then(|id| Uuid::parse_str(id).ok())
+                .ok_or_else(|| Status::invalid_argument(format!("Invalid {} metadata, expected a UUID", OPERATION_METADATA))))
+            .transpose()?;
+        self.operations.start(tenant, kind, requested).map_err(core_error_to_status)
+    }
+}
+
+/// Run a cancellable call, reporting its operation ID in the response metadata
+async fn run_operation<T>(
+    operation: Operation,
+    call: impl std::future::Future<Output = Result<Response<T>, Status>>,
+) -> Result<Response<T>, Status> {
+    let id = operation.id();
+    let mut response = operation.run(call).await.map_err(core_error_to_status)??;
+    response.metadata_mut().insert(OPERATION_METADATA, id.to_string().parse().unwrap());
+    Ok(response)
 }
 
 #[tonic::async_trait]
@@ -466,7 +957,7 @@ impl TelaMentis for TelaMentisService {
         request: Request<UpsertNodeRequest>
     ) -> Result<Response<UpsertNodeResponse>, Status> {
         let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&req.tenant_id);
         
         // Create request context for pipeline
         let mut ctx = RequestContext::new("POST".to_string(), format!("/graph/{}/nodes", tenant));
@@ -481,13 +972,15 @@ impl TelaMentis for TelaMentisService {
                 
                 // Convert protobuf node to core node
                 let node = proto_to_core_node(req.node.as_ref().ok_or_else(|| Status::invalid_argument("Missing node"))?)?;
+                let write_concern = proto_to_core_write_concern(req.write_concern.as_deref())?;
                 
                 // Upsert node
-                match self.core_service.upsert_node(&tenant, node).await {
+                match with_write_concern(write_concern, self.core_service.upsert_node(&tenant, node)).await {
                     Ok(node_id) => {
                         Ok(Response::new(UpsertNodeResponse {
-                            node_id: node_id.to_string(),
+                            node_id: proto_write_id(node_id),
                             created: true,
+                            write_concern: write_concern.to_string(),
                         }))
                     }
                     Err(e) => Err(core_error_to_status(e)),
@@ -502,7 +995,7 @@ impl TelaMentis for TelaMentisService {
         request: Request<GetNodeRequest>
     ) -> Result<Response<GetNodeResponse>, Status> {
         let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&req.tenant_id);
         let node_id = Uuid::parse_str(&req.node_id)
             .map_err(|e| Status::invalid_argument(format!("Invalid node ID: {}", e)))?;
         
@@ -528,7 +1021,7 @@ impl TelaMentis for TelaMentisService {
         request: Request<DeleteNodeRequest>
     ) -> Result<Response<DeleteNodeResponse>, Status> {
         let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&req.tenant_id);
         let node_id = Uuid::parse_str(&req.node_id)
             .map_err(|e| Status::invalid_argument(format!("Invalid node ID: {}", e)))?;
         
@@ -545,49 +1038,81 @@ impl TelaMentis for TelaMentisService {
         request: Request<BatchUpsertNodesRequest>
     ) -> Result<Response<BatchUpsertNodesResponse>, Status> {
         let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&req.tenant_id);
+        let write_concern = proto_to_core_write_concern(req.write_concern.as_deref())?;
         let mut node_ids = Vec::new();
         let mut created_count = 0;
         let mut updated_count = 0;
         
-        for proto_node in req.nodes {
-            match proto_to_core_node(&proto_node).and_then(|node| {
-                match self.core_service.upsert_node(&tenant, node) {
-                    Ok(id) => {
-                        created_count += 1;
-                        Ok(id)
+        with_write_concern(write_concern, async {
+            for proto_node in req.nodes {
+                match proto_to_core_node(&proto_node).and_then(|node| {
+                    match self.core_service.upsert_node(&tenant, node) {
+                        Ok(id) => {
+                            created_count += 1;
+                            Ok(id)
+                        }
+                        Err(e) => Err(Status::from_error(Box::new(e))),
                     }
-                    Err(e) => Err(Status::from_error(Box::new(e))),
+                }) {
+                    Ok(id) => node_ids.push(proto_write_id(id)),
+                    Err(e) => return Err(e),
                 }
-            }) {
-                Ok(id) => node_ids.push(id.to_string()),
-                Err(e) => return Err(e),
             }
-        }
+            Ok(())
+        }).await?;
         
         Ok(Response::new(BatchUpsertNodesResponse {
             node_ids,
             created_count: created_count as i32,
             updated_count: updated_count as i32,
+            write_concern: write_concern.to_string(),
         }))
     }
 
+    async fn upsert_node_with_edges(
+        &self,
+        request: Request<UpsertNodeWithEdgesRequest>
+    ) -> Result<Response<UpsertNodeWithEdgesResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        
+        let node = proto_to_core_node(req.node.as_ref().ok_or_else(|| Status::invalid_argument("Missing node"))?)?;
+        let edges = req.edges.iter()
+            .map(proto_to_core_edge_spec)
+            .collect::<Result<Vec<_>, _>>()?;
+        let write_concern = proto_to_core_write_concern(req.write_concern.as_deref())?;
+        
+        match with_write_concern(write_concern, self.core_service.upsert_node_with_edges(&tenant, node, edges)).await {
+            Ok(result) => {
+                Ok(Response::new(UpsertNodeWithEdgesResponse {
+                    node_id: proto_write_id(result.node_id),
+                    edge_ids: result.edge_ids.into_iter().map(proto_write_id).collect(),
+                    write_concern: write_concern.to_string(),
+                }))
+            }
+            Err(e) => Err(core_error_to_status(e.into())),
+        }
+    }
+
     async fn upsert_edge(
         &self,
         request: Request<UpsertEdgeRequest>
     ) -> Result<Response<UpsertEdgeResponse>, Status> {
         let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&req.tenant_id);
         
         // Convert protobuf edge to core edge
         let edge = proto_to_core_edge(req.edge.as_ref().ok_or_else(|| Status::invalid_argument("Missing edge"))?)?;
+        let write_concern = proto_to_core_write_concern(req.write_concern.as_deref())?;
         
         // Upsert edge
-        match self.core_service.upsert_edge(&tenant, edge).await {
+        match with_write_concern(write_concern, self.core_service.upsert_edge(&tenant, edge)).await {
             Ok(edge_id) => {
                 Ok(Response::new(UpsertEdgeResponse {
-                    edge_id: edge_id.to_string(),
+                    edge_id: proto_write_id(edge_id),
                     created: true,
+                    write_concern: write_concern.to_string(),
                 }))
             }
             Err(e) => Err(core_error_to_status(e)),
@@ -599,7 +1124,7 @@ impl TelaMentis for TelaMentisService {
         request: Request<DeleteEdgeRequest>
     ) -> Result<Response<DeleteEdgeResponse>, Status> {
         let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&req.tenant_id);
         let edge_id = Uuid::parse_str(&req.edge_id)
             .map_err(|e| Status::invalid_argument(format!("Invalid edge ID: {}", e)))?;
         
@@ -611,35 +1136,99 @@ impl TelaMentis for TelaMentisService {
         }
     }
 
+    async fn close_edge(
+        &self,
+        request: Request<CloseEdgeRequest>
+    ) -> Result<Response<EdgeVersionResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let edge_id = Uuid::parse_str(&req.edge_id)
+            .map_err(|e| Status::invalid_argument(format!("Invalid edge ID: {}", e)))?;
+        let valid_to = parse_valid_time(&req.valid_to, IntervalBound::End, "valid_to")?;
+        
+        match self.core_service.close_edge(&tenant, edge_id, valid_to).await {
+            Ok(new_id) => {
+                Ok(Response::new(EdgeVersionResponse {
+                    edge_id: new_id.to_string(),
+                    previous_edge_id: edge_id.to_string(),
+                }))
+            }
+            Err(e) => Err(core_error_to_status(e)),
+        }
+    }
+
+    async fn supersede_edge(
+        &self,
+        request: Request<SupersedeEdgeRequest>
+    ) -> Result<Response<EdgeVersionResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let edge_id = Uuid::parse_str(&req.edge_id)
+            .map_err(|e| Status::invalid_argument(format!("Invalid edge ID: {}", e)))?;
+        let edge = proto_to_core_edge(req.edge.as_ref().ok_or_else(|| Status::invalid_argument("Missing edge"))?)?;
+        
+        match self.core_service.supersede_edge(&tenant, edge_id, edge).await {
+            Ok(new_id) => {
+                Ok(Response::new(EdgeVersionResponse {
+                    edge_id: new_id.to_string(),
+                    previous_edge_id: edge_id.to_string(),
+                }))
+            }
+            Err(e) => Err(core_error_to_status(e)),
+        }
+    }
+
+    async fn retract_edge(
+        &self,
+        request: Request<RetractEdgeRequest>
+    ) -> Result<Response<RetractEdgeResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let edge_id = Uuid::parse_str(&req.edge_id)
+            .map_err(|e| Status::invalid_argument(format!("Invalid edge ID: {}", e)))?;
+        
+        match self.core_service.retract_edge(&tenant, edge_id).await {
+            Ok(retracted) => {
+                Ok(Response::new(RetractEdgeResponse { retracted }))
+            }
+            Err(e) => Err(core_error_to_status(e)),
+        }
+    }
+
     async fn batch_upsert_edges(
         &self,
         request: Request<BatchUpsertEdgesRequest>
     ) -> Result<Response<BatchUpsertEdgesResponse>, Status> {
         let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&req.tenant_id);
+        let write_concern = proto_to_core_write_concern(req.write_concern.as_deref())?;
         let mut edge_ids = Vec::new();
         let mut created_count = 0;
         let mut updated_count = 0;
         
-        for proto_edge in req.edges {
-            match proto_to_core_edge(&proto_edge).and_then(|edge| {
-                match self.core_service.upsert_edge(&tenant, edge) {
-                    Ok(id) => {
-                        created_count += 1;
-                        Ok(id)
+        with_write_concern(write_concern, async {
+            for proto_edge in req.edges {
+                match proto_to_core_edge(&proto_edge).and_then(|edge| {
+                    match self.core_service.upsert_edge(&tenant, edge) {
+                        Ok(id) => {
+                            created_count += 1;
+                            Ok(id)
+                        }
+                        Err(e) => Err(Status::from_error(Box::new(e))),
                     }
-                    Err(e) => Err(Status::from_error(Box::new(e))),
+                }) {
+                    Ok(id) => edge_ids.push(proto_write_id(id)),
+                    Err(e) => return Err(e),
                 }
-            }) {
-                Ok(id) => edge_ids.push(id.to_string()),
-                Err(e) => return Err(e),
             }
-        }
+            Ok(())
+        }).await?;
         
         Ok(Response::new(BatchUpsertEdgesResponse {
             edge_ids,
             created_count: created_count as i32,
             updated_count: updated_count as i32,
+            write_concern: write_concern.to_string(),
         }))
     }
 
@@ -647,28 +1236,93 @@ impl TelaMentis for TelaMentisService {
         &self,
         request: Request<QueryRequest>
     ) -> Result<Response<QueryResponse>, Status> {
-        let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
-        let start_time = std::time::Instant::now();
+        let tenant = request_tenant(&request.get_ref().tenant_id);
+        let operation = self.start_operation(&request, &tenant, OperationKind::Query)?;
+        run_operation(operation, async move {
+            let req = request.into_inner();
+            let start_time = std::time::Instant::now();
         
-        // Convert protobuf query to core query
-        let core_query = proto_to_core_query(&req)?;
+            // Convert protobuf query to core query
+            let core_query = proto_to_core_query(&req)?;
         
-        // Execute query
-        match self.core_service.query(&tenant, core_query).await {
-            Ok(paths) => {
-                let execution_time = start_time.elapsed();
+            // Execute query, against a materialized snapshot if one is named
+            let result = match &req.snapshot {
+                Some(name) => self.core_service.query_snapshot(&tenant, name, core_query).await,
+                None => self.core_service.query(&tenant, core_query).await,
+            };
+            match result {
+                Ok(paths) => {
+                    let execution_time = start_time.elapsed();
                 
-                // Convert core paths to protobuf paths
-                let proto_paths = paths.iter()
-                    .map(core_to_proto_path)
-                    .collect::<Result<Vec<_>, _>>()?;
+                    // Convert core paths to protobuf paths
+                    let proto_paths = paths.iter()
+                        .map(core_to_proto_path)
+                        .collect::<Result<Vec<_>, _>>()?;
                 
-                Ok(Response::new(QueryResponse {
-                    paths: proto_paths,
-                    execution_time_ms: execution_time.as_millis() as i64,
-                }))
+                    Ok(Response::new(QueryResponse {
+                        paths: proto_paths,
+                        execution_time_ms: execution_time.as_millis() as i64,
+                    }))
+                }
+                Err(e) => Err(core_error_to_status(e)),
             }
+        }).await
+    }
+
+    async fn get_catalog(
+        &self,
+        request: Request<CatalogRequest>
+    ) -> Result<Response<CatalogResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+
+        match self.core_service.catalog(&tenant).await {
+            Ok(catalog) => Ok(Response::new(CatalogResponse {
+                labels: catalog.labels.iter().map(core_to_proto_catalog_entry).collect(),
+                kinds: catalog.kinds.iter().map(core_to_proto_catalog_entry).collect(),
+            })),
+            Err(e) => Err(core_error_to_status(e)),
+        }
+    }
+
+    async fn materialize_snapshot(
+        &self,
+        request: Request<MaterializeSnapshotRequest>
+    ) -> Result<Response<ProtoSnapshotInfo>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let valid_at = parse_valid_time(&req.valid_at, IntervalBound::AsOf, "valid_at")?;
+
+        match self.core_service.materialize_snapshot(&tenant, &req.name, valid_at).await {
+            Ok(info) => Ok(Response::new(core_to_proto_snapshot_info(&info))),
+            Err(e) => Err(core_error_to_status(e)),
+        }
+    }
+
+    async fn list_snapshots(
+        &self,
+        request: Request<ListSnapshotsRequest>
+    ) -> Result<Response<ListSnapshotsResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+
+        match self.core_service.list_snapshots(&tenant).await {
+            Ok(snapshots) => Ok(Response::new(ListSnapshotsResponse {
+                snapshots: snapshots.iter().map(core_to_proto_snapshot_info).collect(),
+            })),
+            Err(e) => Err(core_error_to_status(e)),
+        }
+    }
+
+    async fn drop_snapshot(
+        &self,
+        request: Request<DropSnapshotRequest>
+    ) -> Result<Response<DropSnapshotResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+
+        match self.core_service.drop_snapshot(&tenant, &req.name).await {
+            Ok(dropped) => Ok(Response::new(DropSnapshotResponse { dropped })),
             Err(e) => Err(core_error_to_status(e)),
         }
     }
@@ -677,21 +1331,35 @@ impl TelaMentis for TelaMentisService {
         &self,
         request: Request<ExtractRequest>
     ) -> Result<Response<ExtractResponse>, Status> {
-        let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&request.get_ref().tenant_id);
+        let operation = self.start_operation(&request, &tenant, OperationKind::Extraction)?;
+        run_operation(operation, async move {
+            let req = request.into_inner();
         
-        // Convert protobuf request to core request
-        let context = proto_to_core_extraction_context(&req);
+            // Convert protobuf request to core request
+            let context = proto_to_core_extraction_context(&req)?;
+            let source = context.source.clone().unwrap_or_default();
         
-        // Extract knowledge
-        match self.core_service.extract_knowledge(&tenant, context).await {
-            Ok(envelope) => {
-                // Convert core envelope to protobuf response
-                let response = core_to_proto_extraction(&envelope)?;
-                Ok(Response::new(response))
+            // Screen the input before it reaches the LLM
+            let (mut context, operation) = self.pipeline.prepare_extraction(&tenant, context).await
+                .map_err(core_error_to_status)?;
+            self.examples.apply(&tenant, &mut context).await;
+            self.graph_context.apply(&tenant, &mut context).await;
+        
+            // Extract knowledge
+            match self.core_service.extract_knowledge(&tenant, context).await {
+                Ok(mut envelope) => {
+                    self.valid_time.for_tenant(&tenant).apply_to_envelope(&mut envelope, &source);
+                    if !operation.warnings.is_empty() {
+                        envelope.metadata.get_or_insert_with(ExtractionMetadata::default).warnings.extend(operation.warnings);
+                    }
+                    // Convert core envelope to protobuf response
+                    let response = core_to_proto_extraction(&envelope)?;
+                    Ok(Response::new(response))
+                }
+                Err(e) => Err(core_error_to_status(CoreError::Llm(e))),
             }
-            Err(e) => Err(core_error_to_status(e)),
-        }
+        }).await
     }
 
     async fn complete_text(
@@ -699,7 +1367,7 @@ impl TelaMentis for TelaMentisService {
         request: Request<CompleteRequest>
     ) -> Result<Response<CompleteResponse>, Status> {
         let req = request.into_inner();
-        let tenant = TenantId::new(&req.tenant_id);
+        let tenant = request_tenant(&req.tenant_id);
         
         // Parse params JSON
         let params = serde_json::from_str(&req.params_json)
@@ -708,9 +1376,12 @@ impl TelaMentis for TelaMentisService {
         // Create completion request
         let completion_request = CompletionRequest {
             prompt: req.prompt,
+            messages: req.messages.iter().map(proto_to_core_message).collect(),
+            system: req.system,
             max_tokens: req.max_tokens.map(|t| t as u32),
             temperature: req.temperature,
             params,
+            seed: None,
         };
         
         // Complete text
@@ -743,8 +1414,12 @@ impl TelaMentis for TelaMentisService {
         match self.core_service.health_check().await {
             Ok(_) => {
                 let now = chrono::Utc::now();
+                let status = match self.core_service.llm_status().await {
+                    CapabilityStatus::Available => "healthy",
+                    CapabilityStatus::Unavailable { .. } => "degraded",
+                };
                 Ok(Response::new(HealthCheckResponse {
-                    status: "healthy".to_string(),
+                    status: status.to_string(),
                     version: env!("CARGO_PKG_VERSION").to_string(),
                     timestamp: now.to_rfc3339(),
                 }))
@@ -757,20 +1432,539 @@ impl TelaMentis for TelaMentisService {
     }
 }
 
+/// gRPC service for API v2: serves the v1 RPCs through the v1 service and
+/// adds the v2 ones
+struct TelaMentisServiceV2 {
+    v1: Arc<TelaMentisService>,
+    mutations: MutationApplier,
+}
+
+#[tonic::async_trait]
+impl TelaMentisV2 for TelaMentisServiceV2 {
+    type StreamMutationsStream = Pin<Box<dyn Stream<Item = Result<ProtoMutationAck, Status>> + Send>>;
+
+    async fn upsert_node(
+        &self,
+        request: Request<UpsertNodeRequest>
+    ) -> Result<Response<UpsertNodeResponse>, Status> {
+        TelaMentis::upsert_node(self.v1.as_ref(), request).await
+    }
+
+    async fn get_node(
+        &self,
+        request: Request<GetNodeRequest>
+    ) -> Result<Response<GetNodeResponse>, Status> {
+        TelaMentis::get_node(self.v1.as_ref(), request).await
+    }
+
+    async fn delete_node(
+        &self,
+        request: Request<DeleteNodeRequest>
+    ) -> Result<Response<DeleteNodeResponse>, Status> {
+        TelaMentis::delete_node(self.v1.as_ref(), request).await
+    }
+
+    async fn batch_upsert_nodes(
+        &self,
+        request: Request<BatchUpsertNodesRequest>
+    ) -> Result<Response<BatchUpsertNodesResponse>, Status> {
+        TelaMentis::batch_upsert_nodes(self.v1.as_ref(), request).await
+    }
+
+    async fn get_node_by_alias(
+        &self,
+        request: Request<NodeAliasRequest>
+    ) -> Result<Response<NodeByAliasResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let alias = proto_to_alias_key(&req.alias, req.alias_namespace.as_deref());
+
+        let record = self.v1.core_service.get_node_by_alias(&tenant, &alias).await
+            .map_err(|e| core_error_to_status(e.into()))?;
+        Ok(Response::new(core_to_proto_node_by_alias(record)?))
+    }
+
+    async fn delete_node_by_alias(
+        &self,
+        request: Request<NodeAliasRequest>
+    ) -> Result<Response<DeleteNodeByAliasResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let alias = proto_to_alias_key(&req.alias, req.alias_namespace.as_deref());
+
+        let deleted = self.v1.core_service.delete_node_by_alias(&tenant, &alias).await
+            .map_err(|e| core_error_to_status(e.into()))?;
+        Ok(Response::new(DeleteNodeByAliasResponse {
+            deleted: deleted.is_some(),
+            node_id: deleted.map(|id| id.to_string()).unwrap_or_default(),
+        }))
+    }
+
+    async fn patch_node_by_alias(
+        &self,
+        request: Request<PatchNodeByAliasRequest>
+    ) -> Result<Response<NodeByAliasResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let alias = proto_to_alias_key(&req.alias, req.alias_namespace.as_deref());
+        let props = serde_json::from_str(&req.props_json)
+            .map_err(|e| Status::invalid_argument(format!("props_json must be a JSON object: {}", e)))?;
+        let write_concern = proto_to_core_write_concern(req.write_concern.as_deref())?;
+
+        let record = with_write_concern(write_concern, self.v1.core_service.patch_node_by_alias(&tenant, &alias, props)).await
+            .map_err(|e| core_error_to_status(e.into()))?;
+        Ok(Response::new(core_to_proto_node_by_alias(record)?))
+    }
+
+    async fn upsert_node_with_edges(
+        &self,
+        request: Request<UpsertNodeWithEdgesRequest>
+    ) -> Result<Response<UpsertNodeWithEdgesResponse>, Status> {
+        TelaMentis::upsert_node_with_edges(self.v1.as_ref(), request).await
+    }
+
+    async fn upsert_edge(
+        &self,
+        request: Request<UpsertEdgeRequest>
+    ) -> Result<Response<UpsertEdgeResponse>, Status> {
+        TelaMentis::upsert_edge(self.v1.as_ref(), request).await
+    }
+
+    async fn delete_edge(
+        &self,
+        request: Request<DeleteEdgeRequest>
+    ) -> Result<Response<DeleteEdgeResponse>, Status> {
+        TelaMentis::delete_edge(self.v1.as_ref(), request).await
+    }
+
+    async fn close_edge(
+        &self,
+        request: Request<CloseEdgeRequest>
+    ) -> Result<Response<EdgeVersionResponse>, Status> {
+        TelaMentis::close_edge(self.v1.as_ref(), request).await
+    }
+
+    async fn supersede_edge(
+        &self,
+        request: Request<SupersedeEdgeRequest>
+    ) -> Result<Response<EdgeVersionResponse>, Status> {
+        TelaMentis::supersede_edge(self.v1.as_ref(), request).await
+    }
+
+    async fn retract_edge(
+        &self,
+        request: Request<RetractEdgeRequest>
+    ) -> Result<Response<RetractEdgeResponse>, Status> {
+        TelaMentis::retract_edge(self.v1.as_ref(), request).await
+    }
+
+    async fn batch_upsert_edges(
+        &self,
+        request: Request<BatchUpsertEdgesRequest>
+    ) -> Result<Response<BatchUpsertEdgesResponse>, Status> {
+        TelaMentis::batch_upsert_edges(self.v1.as_ref(), request).await
+    }
+
+    async fn upsert_edge_by_alias(
+        &self,
+        request: Request<UpsertEdgeByAliasRequest>
+    ) -> Result<Response<UpsertEdgeResponse>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let edge = proto_to_core_edge_by_ref(req.edge.as_ref().ok_or_else(|| Status::invalid_argument("Missing edge"))?)?;
+        let write_concern = proto_to_core_write_concern(req.write_concern.as_deref())?;
+
+        let edge_id = with_write_concern(write_concern, self.v1.core_service.upsert_edge_by_ref(&tenant, edge)).await
+            .map_err(|e| core_error_to_status(e.into()))?;
+        Ok(Response::new(UpsertEdgeResponse {
+            edge_id: proto_write_id(edge_id),
+            created: true,
+            write_concern: write_concern.to_string(),
+        }))
+    }
+
+    async fn stream_mutations(
+        &self,
+        request: Request<Streaming<MutationRecord>>
+    ) -> Result<Response<Self::StreamMutationsStream>, Status> {
+        let mut records = request.into_inner();
+        let (sink, acks) = self.mutations.start();
+
+        // Records are only read as fast as the applier takes them, so HTTP/2
+        // flow control holds back clients that outrun it
+        tokio::spawn(async move {
+            loop {
+                let record = match records.message().await {
+                    Ok(Some(record)) => record,
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Mutation stream ended with an error: {}", e);
+                        break;
+                    }
+                };
+                let queued = match proto_to_core_mutation(&record) {
+                    Ok(mutation) => sink.send(record.sequence, TenantId::new(&record.tenant_id), mutation).await,
+                    Err(status) => sink.reject(record.sequence, status.message()).await,
+                };
+                if queued.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let acks = ReceiverStream::new(acks).map(|ack| Ok(core_to_proto_mutation_ack(ack)));
+        Ok(Response::new(Box::pin(acks)))
+    }
+
+    async fn execute_query(
+        &self,
+        request: Request<QueryRequest>
+    ) -> Result<Response<QueryResponse>, Status> {
+        TelaMentis::execute_query(self.v1.as_ref(), request).await
+    }
+
+    async fn execute_query_page(
+        &self,
+        request: Request<QueryPageRequest>
+    ) -> Result<Response<QueryPageResponse>, Status> {
+        let tenant = request.get_ref().query.as_ref()
+            .map(|query| request_tenant(&query.tenant_id))
+            .ok_or_else(|| Status::invalid_argument("Query is required"))?;
+        let operation = self.v1.start_operation(&request, &tenant, OperationKind::Query)?;
+        run_operation(operation, async move {
+            let req = request.into_inner();
+            let query = req.query
+                .ok_or_else(|| Status::invalid_argument("Query is required"))?;
+            let start_time = std::time::Instant::now();
+
+            let page = req.page.unwrap_or_default();
+            let page = PageRequest::new(page.page, page.limit, page.offset);
+            let core_query = page.apply(proto_to_core_query(&query)?)
+                .map_err(Status::invalid_argument)?;
+
+            let result = match &query.snapshot {
+                Some(name) => self.v1.core_service.query_snapshot(&tenant, name, core_query).await,
+                None => self.v1.core_service.query(&tenant, core_query).await,
+            };
+            let mut paths = result.map_err(core_error_to_status)?;
+            let has_next = page.finish(&mut paths);
+
+            let proto_paths = paths.iter()
+                .map(core_to_proto_path)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(Response::new(QueryPageResponse {
+                paths: proto_paths,
+                page: Some(ProtoPageInfo {
+                    page: page.page(),
+                    limit: page.limit,
+                    offset: page.offset,
+                    has_next,
+                    has_prev: page.offset > 0,
+                }),
+                execution_time_ms: start_time.elapsed().as_millis() as i64,
+            }))
+        }).await
+    }
+
+    async fn execute_query_batch(
+        &self,
+        request: Request<QueryBatchRequest>
+    ) -> Result<Response<QueryBatchResponse>, Status> {
+        let tenant = request_tenant(&request.get_ref().tenant_id);
+        let operation = self.v1.start_operation(&request, &tenant, OperationKind::Query)?;
+        run_operation(operation, async move {
+            let req = request.into_inner();
+            let batch = BatchQueryRequest {
+                queries: req.queries.iter()
+                    .map(proto_to_core_batch_query)
+                    .collect::<Result<Vec<_>, _>>()?,
+                timeout_ms: req.timeout_ms,
+            };
+
+            // The batch as a whole only fails when it is empty or too large
+            let response = run_batch(self.v1.core_service.clone(), &tenant, batch).await
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+            Ok(Response::new(QueryBatchResponse {
+                results: response.results.iter()
+                    .map(core_to_proto_batch_query_result)
+                    .collect::<Result<Vec<_>, _>>()?,
+                execution_time_ms: response.execution_time_ms as i64,
+            }))
+        }).await
+    }
+
+    async fn get_catalog(
+        &self,
+        request: Request<CatalogRequest>
+    ) -> Result<Response<CatalogResponse>, Status> {
+        TelaMentis::get_catalog(self.v1.as_ref(), request).await
+    }
+
+    async fn materialize_snapshot(
+        &self,
+        request: Request<MaterializeSnapshotRequest>
+    ) -> Result<Response<ProtoSnapshotInfo>, Status> {
+        TelaMentis::materialize_snapshot(self.v1.as_ref(), request).await
+    }
+
+    async fn list_snapshots(
+        &self,
+        request: Request<ListSnapshotsRequest>
+    ) -> Result<Response<ListSnapshotsResponse>, Status> {
+        TelaMentis::list_snapshots(self.v1.as_ref(), request).await
+    }
+
+    async fn drop_snapshot(
+        &self,
+        request: Request<DropSnapshotRequest>
+    ) -> Result<Response<DropSnapshotResponse>, Status> {
+        TelaMentis::drop_snapshot(self.v1.as_ref(), request).await
+    }
+
+    async fn extract_knowledge(
+        &self,
+        request: Request<ExtractRequest>
+    ) -> Result<Response<ExtractResponse>, Status> {
+        TelaMentis::extract_knowledge(self.v1.as_ref(), request).await
+    }
+
+    async fn complete_text(
+        &self,
+        request: Request<CompleteRequest>
+    ) -> Result<Response<CompleteResponse>, Status> {
+        TelaMentis::complete_text(self.v1.as_ref(), request).await
+    }
+
+    async fn health_check(
+        &self,
+        request: Request<HealthCheckRequest>
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        TelaMentis::health_check(self.v1.as_ref(), request).await
+    }
+
+    async fn list_operations(
+        &self,
+        request: Request<ListOperationsRequest>
+    ) -> Result<Response<ListOperationsResponse>, Status> {
+        let tenant = request_tenant(&request.into_inner().tenant_id);
+        let operations = self.v1.operations.list(&tenant);
+        Ok(Response::new(ListOperationsResponse {
+            operations: operations.iter().map(core_to_proto_operation).collect(),
+        }))
+    }
+
+    async fn cancel_operation(
+        &self,
+        request: Request<CancelOperationRequest>
+    ) -> Result<Response<ProtoOperationInfo>, Status> {
+        let req = request.into_inner();
+        let tenant = request_tenant(&req.tenant_id);
+        let operation_id = Uuid::parse_str(&req.operation_id)
+            .map_err(|e| Status::invalid_argument(format!("Invalid operation ID: {}", e)))?;
+
+        match self.v1.operations.cancel(&tenant, operation_id) {
+            Some(operation) => Ok(Response::new(core_to_proto_operation(&operation))),
+            None => Err(Status::not_found(format!("No operation {} in flight; it may have finished already", operation_id))),
+        }
+    }
+}
+
+
+/// gRPC service of operator controls
+struct TelaMentisAdminService {
+    core_service: Arc<dyn GraphService>,
+    pipeline: Arc<PipelineRunner>,
+    admin: Arc<AdminControls>,
+}
+
+impl TelaMentisAdminService {
+    /// Check the admin secret a call carries in `authorization`
+    fn authorize<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let secret = request.metadata().get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .unwrap_or_default();
+        self.admin.authorize(secret).map_err(|e| core_error_to_status(e.into()))?;
+        attribute_principal("admin");
+        Ok(())
+    }
+
+    /// The tenant a call names, or every tenant in the store
+    async fn target_tenants(&self, tenant_id: Option<String>) -> Result<Vec<TenantId>, Status> {
+        match tenant_id {
+            Some(tenant_id) => Ok(vec![request_tenant(&tenant_id)]),
+            None => self.core_service.list_tenants().await.map_err(|e| core_error_to_status(e.into())),
+        }
+    }
+
+    fn status(&self) -> AdminStatusResponse {
+        let status = self.admin.status();
+        AdminStatusResponse {
+            draining: status.draining,
+            in_flight: status.in_flight as u64,
+            jobs: status.jobs,
+            providers: status.providers,
+        }
+    }
+
+    fn plugins(&self) -> ListPluginsResponse {
+        let plugins = self.pipeline.plugin_states().into_iter()
+            .map(|state| ProtoPluginState { stage: state.stage.to_string(), name: state.name, enabled: state.enabled })
+            .collect();
+        ListPluginsResponse { plugins }
+    }
+}
+
+#[tonic::async_trait]
+impl TelaMentisAdmin for TelaMentisAdminService {
+    async fn get_status(
+        &self,
+        request: Request<AdminStatusRequest>
+    ) -> Result<Response<AdminStatusResponse>, Status> {
+        self.authorize(&request)?;
+        Ok(Response::new(self.status()))
+    }
+
+    async fn list_plugins(
+        &self,
+        request: Request<ListPluginsRequest>
+    ) -> Result<Response<ListPluginsResponse>, Status> {
+        self.authorize(&request)?;
+        Ok(Response::new(self.plugins()))
+    }
+
+    async fn set_plugin_enabled(
+        &self,
+        request: Request<SetPluginEnabledRequest>
+    ) -> Result<Response<ListPluginsResponse>, Status> {
+        self.authorize(&request)?;
+        let req = request.into_inner();
+
+        match self.pipeline.set_plugin_enabled(&req.name, req.enabled) {
+            Ok(()) => Ok(Response::new(self.plugins())),
+            Err(e @ PipelineError::PluginNotFound(_)) => Err(Status::not_found(e.to_string())),
+            Err(e) => Err(core_error_to_status(e.into())),
+        }
+    }
+
+    async fn set_default_provider(
+        &self,
+        request: Request<SetDefaultProviderRequest>
+    ) -> Result<Response<SetDefaultProviderResponse>, Status> {
+        self.authorize(&request)?;
+        let req = request.into_inner();
+
+        self.admin.set_default_provider(&request_tenant(&req.tenant_id), req.provider.as_deref())
+            .map_err(core_error_to_status)?;
+        Ok(Response::new(SetDefaultProviderResponse {}))
+    }
+
+    async fn flush_caches(
+        &self,
+        request: Request<FlushCachesRequest>
+    ) -> Result<Response<FlushCachesResponse>, Status> {
+        self.authorize(&request)?;
+        let tenants = self.target_tenants(request.into_inner().tenant_id).await?;
+
+        if !self.admin.flush_caches(&tenants) {
+            return Err(Status::unavailable("No query cache is configured"));
+        }
+        Ok(Response::new(FlushCachesResponse {
+            tenant_ids: tenants.iter().map(|tenant| tenant.to_string()).collect(),
+        }))
+    }
+
+    async fn run_job(
+        &self,
+        request: Request<RunJobRequest>
+    ) -> Result<Response<RunJobResponse>, Status> {
+        self.authorize(&request)?;
+        let req = request.into_inner();
+        if self.admin.job(&req.name).is_none() {
+            return Err(Status::not_found(format!("Unknown maintenance job '{}'", req.name)));
+        }
+        let tenants = self.target_tenants(req.tenant_id).await?;
+
+        let reports = self.admin.run_job(&req.name, &tenants).await.map_err(core_error_to_status)?;
+        Ok(Response::new(RunJobResponse {
+            reports: reports.into_iter().map(|report| ProtoJobReport {
+                job: report.job,
+                tenant_id: report.tenant.to_string(),
+                affected: report.affected,
+                started_at: report.started_at.to_rfc3339(),
+                finished_at: report.finished_at.to_rfc3339(),
+            }).collect(),
+        }))
+    }
+
+    async fn drain(
+        &self,
+        request: Request<DrainRequest>
+    ) -> Result<Response<DrainResponse>, Status> {
+        self.authorize(&request)?;
+        let timeout_secs = request.into_inner().timeout_secs.unwrap_or(30);
+
+        let report = self.admin.drain_state().drain(std::time::Duration::from_secs(timeout_secs)).await;
+        Ok(Response::new(DrainResponse { drained: report.drained, in_flight: report.in_flight as u64 }))
+    }
+
+    async fn resume(
+        &self,
+        request: Request<ResumeRequest>
+    ) -> Result<Response<AdminStatusResponse>, Status> {
+        self.authorize(&request)?;
+        self.admin.drain_state().resume();
+        Ok(Response::new(self.status()))
+    }
+}
+
+/// Interceptor refusing calls while the server drains. Only requests over
+/// HTTP are counted in flight; calls already running are not waited for.
+fn refuse_while_draining(drain: Option<Arc<DrainState>>) -> impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone {
+    move |request| match &drain {
+        Some(drain) if drain.is_draining() => Err(Status::unavailable("Server is draining")),
+        _ => Ok(request),
+    }
+}
+
 #[async_trait]
 impl PresentationAdapter for GrpcAdapter {
     async fn start(&self, core_service: Arc<dyn GraphService>) -> Result<(), PresentationError> {
         info!("Starting gRPC server on {}", self.config.bind_address);
         
-        let service = TelaMentisService {
+        self.pipeline.compose_tenants(&self.config.pipelines, &self.plugins).await
+            .map_err(|e| PresentationError::StartupFailed(format!("Failed to compose tenant pipelines: {}", e)))?;
+        
+        let access_log = AccessLog::new(self.config.access_log.clone())
+            .map_err(|e| PresentationError::StartupFailed(format!("Failed to open access log: {}", e)))?;
+        let mutations = MutationApplier::new(core_service.clone(), self.config.mutation_stream.clone());
+        let admin = self.admin.clone().map(|admin| TelaMentisAdminServer::new(TelaMentisAdminService {
+            core_service: core_service.clone(),
+            pipeline: self.pipeline.clone(),
+            admin,
+        }));
+        let draining = refuse_while_draining(self.admin.as_ref().map(|admin| admin.drain_state()));
+        let service = Arc::new(TelaMentisService {
+            graph_context: GraphContextBuilder::new(core_service.clone(), self.config.graph_context.clone()),
             core_service,
             pipeline: self.pipeline.clone(),
-        };
+            examples: self.examples.clone(),
+            valid_time: self.config.valid_time.clone(),
+            operations: self.operations.clone(),
+        });
         
-        let server = TelaMentisServer::new(service);
+        // v1 and v2 are served side by side
+        let server = InterceptedService::new(TelaMentisServer::from_arc(service.clone()), draining.clone());
+        let server_v2 = InterceptedService::new(TelaMentisV2Server::new(TelaMentisServiceV2 { v1: service, mutations }), draining);
         
         Server::builder()
+            .layer(AccessLogLayer::new(Arc::new(access_log)))
             .add_service(server)
+            .add_service(server_v2)
+            .add_optional_service(admin)
             .serve(self.config.bind_address)
             .await
             .map_err(|e| PresentationError::StartupFailed(format!("gRPC server error: {}", e)))?;
@@ -798,12 +1992,27 @@ mod tests {
         assert_eq!(config.request_timeout, 30);
     }
 
+    #[test]
+    fn test_error_metadata() {
+        let status = core_error_to_status(LlmError::RateLimited("slow down".to_string()).into());
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        let metadata = status.metadata();
+        assert_eq!(metadata.get(ERROR_CODE_METADATA).unwrap(), "llm_rate_limited");
+        assert_eq!(metadata.get(ERROR_KIND_METADATA).unwrap(), "retryable");
+        assert_eq!(metadata.get(RETRY_AFTER_METADATA).unwrap(), "10000");
+
+        let status = core_error_to_status(GraphError::ReservedProperty("_tenant_id".to_string()).into());
+        assert_eq!(status.metadata().get(ERROR_KIND_METADATA).unwrap(), "permanent");
+        assert!(status.metadata().get(RETRY_AFTER_METADATA).is_none());
+    }
+
     #[test]
     fn test_proto_to_core_node() {
         let proto_node = ProtoNode {
             id_alias: Some("test".to_string()),
             label: "Person".to_string(),
             props_json: r#"{"name":"Alice","age":30}"#.to_string(),
+            alias_namespace: None,
         };
         
         let result = proto_to_core_node(&proto_node);
@@ -816,6 +2025,77 @@ mod tests {
         assert_eq!(node.props.get("age").unwrap().as_i64().unwrap(), 30);
     }
 
+    #[test]
+    fn test_proto_to_core_edge_spec() {
+        let proto_spec = ProtoEdgeSpec {
+            kind: "WORKS_FOR".to_string(),
+            target: Some(ProtoEdgeTarget::TargetIdAlias("acme".to_string())),
+            target_alias_namespace: Some("crm".to_string()),
+            incoming: true,
+            valid_from: Some("2024-01-01T00:00:00Z".to_string()),
+            valid_to: None,
+            props_json: String::new(),
+            expires_at: None,
+        };
+        
+        let spec = proto_to_core_edge_spec(&proto_spec).unwrap();
+        assert_eq!(spec.kind, "WORKS_FOR");
+        assert_eq!(spec.target, NodeRef::Alias(AliasKey::namespaced("crm", "acme")));
+        assert_eq!(spec.direction, EdgeDirection::Incoming);
+        assert!(spec.valid_from.is_some());
+        
+        let missing_target = ProtoEdgeSpec { target: None, ..proto_spec };
+        assert!(proto_to_core_edge_spec(&missing_target).is_err());
+    }
+
+    #[test]
+    fn test_proto_to_core_mutation() {
+        let mut record = MutationRecord {
+            sequence: 7,
+            tenant_id: "acme".to_string(),
+            mutation: Some(ProtoMutation::DeleteEdgeId(Uuid::nil().to_string())),
+        };
+        assert!(matches!(proto_to_core_mutation(&record).unwrap(), GraphMutation::DeleteEdge { id } if id.is_nil()));
+        
+        record.mutation = Some(ProtoMutation::DeleteNodeId("not-a-uuid".to_string()));
+        assert!(proto_to_core_mutation(&record).is_err());
+        
+        record.mutation = None;
+        assert!(proto_to_core_mutation(&record).is_err());
+        
+        let ack = core_to_proto_mutation_ack(MutationAck {
+            results: vec![MutationResult { sequence: 7, status: MutationStatus::Failed { error: "Missing mutation".to_string() } }],
+            applied: 3,
+            failed: 1,
+        });
+        assert_eq!((ack.statuses[0].sequence, ack.statuses[0].ok, ack.applied, ack.failed), (7, false, 3, 1));
+    }
+
+    #[test]
+    fn test_proto_to_core_batch_query() {
+        let query = QueryRequest {
+            tenant_id: String::new(),
+            query: Some(telamentis::query_request::Query::FindNodesQuery(FindNodesQuery {
+                labels: vec!["Person".to_string()],
+                properties_json: "{}".to_string(),
+                limit: None,
+                order_by: Vec::new(),
+                offset: None,
+            })),
+            snapshot: None,
+        };
+        let proto = ProtoBatchQuery { query: Some(query.clone()), mode: Some("count".to_string()) };
+        
+        let batch_query = proto_to_core_batch_query(&proto).unwrap();
+        assert_eq!(batch_query.mode, QueryMode::Count);
+        assert!(matches!(batch_query.query, GraphQuery::FindNodes { ref labels, .. } if labels == &["Person"]));
+        
+        assert!(proto_to_core_batch_query(&ProtoBatchQuery { mode: Some("all".to_string()), ..proto.clone() }).is_err());
+        assert!(proto_to_core_batch_query(&ProtoBatchQuery { query: None, ..proto.clone() }).is_err());
+        let snapshot = QueryRequest { snapshot: Some("q1".to_string()), ..query };
+        assert!(proto_to_core_batch_query(&ProtoBatchQuery { query: Some(snapshot), ..proto }).is_err());
+    }
+
     #[test]
     fn test_core_to_proto_node() {
         let core_node = Node::new("Person")
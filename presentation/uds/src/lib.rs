@@ -1,25 +1,29 @@
 //! Unix Domain Socket presentation adapter for TelaMentis
 //! 
 //! This adapter provides an ultra-low-latency IPC mechanism for
-//! communicating with TelaMentis from the same host.
+//! communicating with TelaMentis from the same host. Clients can negotiate
+//! a [`shared_memory`] data plane for large messages.
 
 use async_trait::async_trait;
 use bytes::{BytesMut, Buf, BufMut};
 use serde::{Serialize, Deserialize};
+use std::os::unix::fs::FileTypeExt;
 use std::path::PathBuf;
 use std::sync::Arc;
 use telamentis_core::prelude::*;
-use telamentis_core::pipeline::{PipelineRunner, PipelineStage, RequestLoggingPlugin, TenantValidationPlugin, AuditTrailPlugin};
-use tokio::net::{UnixListener, UnixStream};
+use telamentis_core::pipeline::{PipelineRunner, PipelineStage, PluginRegistry, RequestLoggingPlugin, TenantPipelines, TenantValidationPlugin, AuditTrailPlugin};
+use tokio::net::UnixListener;
 use tokio_util::codec::{Decoder, Encoder, Framed};
 use tracing::{debug, error, info, warn};
-use futures::StreamExt;
+use futures::{SinkExt, StreamExt};
 
-mod protocol;
+pub mod protocol;
+pub mod shared_memory;
 mod service;
 
 use protocol::{Request, Response, ApiError};
 use service::UdsService;
+use shared_memory::{Descriptors, Segment, SegmentStream, SegmentTransfer, SharedMemoryConfig};
 
 /// UDS adapter configuration
 #[derive(Debug, Clone)]
@@ -30,6 +34,12 @@ pub struct UdsConfig {
     pub max_message_size: usize,
     /// Request timeout in milliseconds
     pub request_timeout_ms: u64,
+    /// Pipelines of tenants that do not use the default plugins
+    pub pipelines: TenantPipelines,
+    /// Sampling and destination of the access log
+    pub access_log: AccessLogConfig,
+    /// Shared-memory transfer of large messages, for clients that ask
+    pub shared_memory: SharedMemoryConfig,
 }
 
 impl Default for UdsConfig {
@@ -38,6 +48,34 @@ impl Default for UdsConfig {
             socket_path: PathBuf::from("/tmp/telamentis.sock"),
             max_message_size: 10 * 1024 * 1024, // 10 MiB
             request_timeout_ms: 30_000,
+            pipelines: TenantPipelines::default(),
+            access_log: AccessLogConfig::default(),
+            shared_memory: SharedMemoryConfig::default(),
+        }
+    }
+}
+
+impl UdsConfig {
+    /// Check that the socket path can be bound: its directory exists, and
+    /// nothing but a stale socket is in the way
+    pub fn check_socket_path(&self) -> CheckOutcome {
+        let path = &self.socket_path;
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() && !dir.is_dir() => {
+                return CheckOutcome::fail(format!("Directory {} does not exist", dir.display()));
+            }
+            _ => {}
+        }
+
+        let Ok(metadata) = std::fs::symlink_metadata(path) else {
+            return CheckOutcome::pass(format!("{} is free", path.display()));
+        };
+        if !metadata.file_type().is_socket() {
+            return CheckOutcome::fail(format!("{} exists and is not a socket", path.display()));
+        }
+        match std::os::unix::net::UnixStream::connect(path) {
+            Ok(_) => CheckOutcome::fail(format!("Another server is listening on {}", path.display())),
+            Err(_) => CheckOutcome::warn(format!("Stale socket {} will be replaced", path.display())),
         }
     }
 }
@@ -46,6 +84,8 @@ impl Default for UdsConfig {
 pub struct UdsAdapter {
     config: UdsConfig,
     pipeline: Arc<PipelineRunner>,
+    dead_letters: Option<Arc<DeadLetterQueue>>,
+    plugins: Arc<PluginRegistry>,
     shutdown_signal: Option<tokio::sync::oneshot::Sender<()>>,
 }
 
@@ -62,6 +102,8 @@ impl UdsAdapter {
         Self { 
             config,
             pipeline: Arc::new(pipeline),
+            dead_letters: None,
+            plugins: Arc::new(PluginRegistry::with_builtins()),
             shutdown_signal: None,
         }
     }
@@ -71,19 +113,52 @@ impl UdsAdapter {
         Self {
             config,
             pipeline: Arc::new(pipeline),
+            dead_letters: None,
+            plugins: Arc::new(PluginRegistry::with_builtins()),
             shutdown_signal: None,
         }
     }
+    
+    /// Queue records that batch requests fail to write
+    pub fn with_dead_letter_queue(mut self, dead_letters: Arc<DeadLetterQueue>) -> Self {
+        self.dead_letters = Some(dead_letters);
+        self
+    }
+
+    /// Compose the configured tenant pipelines from these plugins instead of the built-in ones
+    pub fn with_plugin_registry(mut self, plugins: PluginRegistry) -> Self {
+        self.plugins = Arc::new(plugins);
+        self
+    }
 }
 
 /// Message codec for framed UDS communication
 pub struct MessageCodec {
     max_message_size: usize,
+    segments: Option<SegmentTransfer>,
+    request_bytes: usize,
+    response_bytes: usize,
 }
 
 impl MessageCodec {
     pub fn new(max_message_size: usize) -> Self {
-        Self { max_message_size }
+        Self { max_message_size, segments: None, request_bytes: 0, response_bytes: 0 }
+    }
+
+    /// Send and accept large messages as shared-memory segments
+    pub fn enable_shared_memory(&mut self, segments: SegmentTransfer) {
+        self.segments = Some(segments);
+    }
+
+    /// Size of the last request decoded, including its length marker and
+    /// any segment it came in
+    pub fn request_bytes(&self) -> usize {
+        self.request_bytes
+    }
+
+    /// Size of the last response encoded, including its length marker
+    pub fn response_bytes(&self) -> usize {
+        self.response_bytes
     }
 }
 
@@ -91,18 +166,7 @@ impl Encoder<Response> for MessageCodec {
     type Error = std::io::Error;
 
     fn encode(&mut self, item: Response, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let bytes = bincode::serialize(&item)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        
-        if bytes.len() > self.max_message_size {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Message size exceeds limit: {} > {}", bytes.len(), self.max_message_size)
-            ));
-        }
-        
-        dst.put_u32_le(bytes.len() as u32);
-        dst.put_slice(&bytes);
+        self.response_bytes = encode_frame(&item, dst, self.max_message_size, self.segments.as_ref())?;
         Ok(())
     }
 }
@@ -112,38 +176,159 @@ impl Decoder for MessageCodec {
     type Error = std::io::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if src.len() < 4 {
-            // Not enough data to read length marker
-            return Ok(None);
-        }
-        
-        let mut size_bytes = [0u8; 4];
-        size_bytes.copy_from_slice(&src[..4]);
-        let size = u32::from_le_bytes(size_bytes) as usize;
-        
-        if size > self.max_message_size {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Message size exceeds limit: {} > {}", size, self.max_message_size)
-            ));
-        }
-        
-        if src.len() < 4 + size {
-            // The full message hasn't arrived yet
-            return Ok(None);
-        }
-        
-        // Discard the length marker
-        src.advance(4);
-        
-        // Extract the message
-        let message_bytes = src.split_to(size);
-        
-        match bincode::deserialize(&message_bytes) {
-            Ok(message) => Ok(Some(message)),
-            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
-        }
+        let decoded = decode_frame(src, self.max_message_size, self.segments.as_ref())?;
+        Ok(decoded.map(|(request, bytes)| {
+            self.request_bytes = bytes;
+            request
+        }))
+    }
+}
+
+/// Client side of the message codec: sends requests and reads responses
+pub struct ClientCodec {
+    max_message_size: usize,
+    segments: Option<SegmentTransfer>,
+}
+
+impl ClientCodec {
+    pub fn new(max_message_size: usize) -> Self {
+        Self { max_message_size, segments: None }
+    }
+
+    /// Send and accept large messages as shared-memory segments, once the
+    /// server agreed to with [`Response::SharedMemory`]. The connection must
+    /// be a [`SegmentStream`], whose descriptors these are.
+    pub fn enable_shared_memory(&mut self, descriptors: Descriptors, min_segment_bytes: usize, max_segment_bytes: usize) {
+        self.segments = Some(SegmentTransfer { descriptors, min_segment_bytes, max_segment_bytes });
+    }
+}
+
+impl Encoder<Request> for ClientCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: Request, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        encode_frame(&item, dst, self.max_message_size, self.segments.as_ref()).map(|_| ())
+    }
+}
+
+impl Decoder for ClientCodec {
+    type Item = Response;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(decode_frame(src, self.max_message_size, self.segments.as_ref())?.map(|(response, _)| response))
+    }
+}
+
+/// Length markers with this bit set start a segment frame: the message is in
+/// the shared-memory segment passed with the frame, and the frame holds only
+/// its little-endian 64-bit length. Message size limits keep the bit clear
+/// in the markers of other frames.
+const SEGMENT_FRAME: u32 = 1 << 31;
+
+/// Write a message as a little-endian length followed by its JSON encoding,
+/// or as a segment frame if shared memory is in use and the message is large
+/// enough. Messages carry arbitrary JSON properties, which a
+/// non-self-describing format such as bincode cannot decode.
+///
+/// Returns the number of bytes transferred, segment included.
+fn encode_frame<T: Serialize>(
+    item: &T,
+    dst: &mut BytesMut,
+    max_message_size: usize,
+    segments: Option<&SegmentTransfer>,
+) -> Result<usize, std::io::Error> {
+    let bytes = serde_json::to_vec(item)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    
+    if let Some(segments) = segments.filter(|segments| segments.sends(bytes.len())) {
+        segments.descriptors.send(shared_memory::write_segment(&bytes)?);
+        dst.put_u32_le(SEGMENT_FRAME | 8);
+        dst.put_u64_le(bytes.len() as u64);
+        return Ok(12 + bytes.len());
+    }
+    
+    if bytes.len() > max_message_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Message size exceeds limit: {} > {}", bytes.len(), max_message_size)
+        ));
+    }
+    
+    dst.put_u32_le(bytes.len() as u32);
+    dst.put_slice(&bytes);
+    Ok(4 + bytes.len())
+}
+
+/// Read one message written by `encode_frame`, if it has fully arrived, with
+/// the number of bytes it took
+fn decode_frame<T: serde::de::DeserializeOwned>(
+    src: &mut BytesMut,
+    max_message_size: usize,
+    segments: Option<&SegmentTransfer>,
+) -> Result<Option<(T, usize)>, std::io::Error> {
+    if src.len() < 4 {
+        // Not enough data to read length marker
+        return Ok(None);
+    }
+    
+    let mut size_bytes = [0u8; 4];
+    size_bytes.copy_from_slice(&src[..4]);
+    let marker = u32::from_le_bytes(size_bytes);
+    if marker & SEGMENT_FRAME != 0 {
+        return decode_segment_frame(src, segments);
+    }
+    let size = marker as usize;
+    
+    if size > max_message_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Message size exceeds limit: {} > {}", size, max_message_size)
+        ));
+    }
+    
+    if src.len() < 4 + size {
+        // The full message hasn't arrived yet
+        return Ok(None);
+    }
+    
+    // Discard the length marker
+    src.advance(4);
+    
+    // Extract the message
+    let message_bytes = src.split_to(size);
+    
+    serde_json::from_slice(&message_bytes)
+        .map(|message| Some((message, 4 + size)))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Read a message from the segment passed with a segment frame, decoding it
+/// straight from the mapping
+fn decode_segment_frame<T: serde::de::DeserializeOwned>(
+    src: &mut BytesMut,
+    segments: Option<&SegmentTransfer>,
+) -> Result<Option<(T, usize)>, std::io::Error> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+    let Some(segments) = segments else {
+        return Err(invalid("Segment frame on a connection without shared memory".to_string()));
+    };
+    if src.len() < 12 {
+        return Ok(None);
+    }
+    
+    src.advance(4);
+    let len = src.get_u64_le() as usize;
+    if len > segments.max_segment_bytes {
+        return Err(invalid(format!("Segment size exceeds limit: {} > {}", len, segments.max_segment_bytes)));
     }
+    let fd = segments.descriptors.take()
+        .ok_or_else(|| invalid("Segment frame arrived without its descriptor".to_string()))?;
+    let segment = Segment::map(fd, len)?;
+    
+    serde_json::from_slice(&segment)
+        .map(|message| Some((message, 12 + len)))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
 }
 
 #[async_trait]
@@ -151,7 +336,14 @@ impl PresentationAdapter for UdsAdapter {
     async fn start(&self, core_service: Arc<dyn GraphService>) -> Result<(), PresentationError> {
         info!("Starting UDS server on {}", self.config.socket_path.display());
         
-        // Remove socket file if it already exists
+        // Remove a stale socket file, but never take over a live one
+        let socket_check = self.config.check_socket_path();
+        if socket_check.status == CheckStatus::Fail {
+            return Err(PresentationError::StartupFailed(socket_check.message));
+        }
+        
+        self.pipeline.compose_tenants(&self.config.pipelines, &self.plugins).await
+            .map_err(|e| PresentationError::StartupFailed(format!("Failed to compose tenant pipelines: {}", e)))?;
         if self.config.socket_path.exists() {
             std::fs::remove_file(&self.config.socket_path)
                 .map_err(|e| PresentationError::StartupFailed(format!("Failed to remove existing socket: {}", e)))?;
@@ -163,15 +355,20 @@ impl PresentationAdapter for UdsAdapter {
         
         // Create shutdown channel
         let (tx, rx) = tokio::sync::oneshot::channel();
+
+        let access_log = AccessLog::new(self.config.access_log.clone())
+            .map_err(|e| PresentationError::StartupFailed(format!("Failed to open access log: {}", e)))?;
+        let access_log = Arc::new(access_log);
         
         // Clone necessary data for the server task
         let config = self.config.clone();
         let pipeline = self.pipeline.clone();
+        let dead_letters = self.dead_letters.clone();
         let socket_path = self.config.socket_path.clone();
         
         // Spawn server task
         tokio::spawn(async move {
-            let service = UdsService::new(core_service, pipeline);
+            let service = UdsService::new(core_service, pipeline).with_dead_letter_queue(dead_letters);
             
             loop {
                 tokio::select! {
@@ -186,10 +383,14 @@ impl PresentationAdapter for UdsAdapter {
                                 let service = service.clone();
                                 let codec = MessageCodec::new(config.max_message_size);
                                 let timeout = config.request_timeout_ms;
+                                let shared_memory = config.shared_memory.clone();
+                                let access_log = access_log.clone();
+                                // Clients are attributed to the user they run as
+                                let principal = stream.peer_cred().ok().map(|cred| format!("uid:{}", cred.uid()));
                                 
                                 tokio::spawn(async move {
-                                    let framed = Framed::new(stream, codec);
-                                    Self::handle_connection(service, framed, timeout).await;
+                                    let framed = Framed::new(SegmentStream::new(stream), codec);
+                                    Self::handle_connection(service, framed, timeout, shared_memory, access_log, principal).await;
                                 });
                             }
                             Err(e) => {
@@ -228,12 +429,42 @@ impl UdsAdapter {
     /// Handle a client connection
     async fn handle_connection(
         service: UdsService,
-        mut framed: Framed<UnixStream, MessageCodec>,
+        mut framed: Framed<SegmentStream, MessageCodec>,
         timeout_ms: u64,
+        shared_memory: SharedMemoryConfig,
+        access_log: Arc<AccessLog>,
+        principal: Option<String>,
     ) {
         while let Some(msg_result) = framed.next().await {
             match msg_result {
+                Ok(Request::NegotiateSharedMemory { max_segment_bytes }) => {
+                    let enabled = shared_memory.enabled && shared_memory::supported();
+                    let max_segment_bytes = max_segment_bytes.min(shared_memory.max_segment_bytes);
+                    let response = Response::SharedMemory {
+                        enabled,
+                        min_segment_bytes: shared_memory.min_segment_bytes,
+                        max_segment_bytes,
+                    };
+                    if let Err(e) = framed.send(response).await {
+                        error!("Failed to send response: {}", e);
+                        break;
+                    }
+                    if enabled {
+                        debug!("UDS client negotiated shared memory for messages up to {} bytes", max_segment_bytes);
+                        let descriptors = framed.get_ref().descriptors();
+                        framed.codec_mut().enable_shared_memory(SegmentTransfer {
+                            descriptors,
+                            min_segment_bytes: shared_memory.min_segment_bytes,
+                            max_segment_bytes,
+                        });
+                    }
+                }
                 Ok(request) => {
+                    let start_time = std::time::Instant::now();
+                    let (name, write) = (request.name(), request.is_write());
+                    let tenant = request.tenant_id().map(TenantId::new);
+                    let bytes_in = framed.codec().request_bytes() as u64;
+
                     let response = tokio::time::timeout(
                         std::time::Duration::from_millis(timeout_ms),
                         service.handle_request(request)
@@ -251,7 +482,20 @@ impl UdsAdapter {
                         }),
                     };
                     
-                    if let Err(e) = framed.send(result).await {
+                    let status = match &result {
+                        Response::Error(error) => error.code,
+                        _ => 200,
+                    };
+                    let sent = framed.send(result).await;
+                    let mut entry = AccessLogEntry::new("uds", name, status, start_time.elapsed());
+                    entry.tenant = tenant;
+                    entry.principal = principal.clone();
+                    entry.write = write;
+                    entry.bytes_in = Some(bytes_in);
+                    entry.bytes_out = sent.is_ok().then(|| framed.codec().response_bytes() as u64);
+                    access_log.record(entry);
+
+                    if let Err(e) = sent {
                         error!("Failed to send response: {}", e);
                         break;
                     }
@@ -290,6 +534,22 @@ mod tests {
         assert_eq!(config.request_timeout_ms, 30_000);
     }
     
+    #[test]
+    fn test_check_socket_path() {
+        let dir = tempdir().unwrap();
+        let config = UdsConfig { socket_path: dir.path().join("tm.sock"), ..Default::default() };
+        assert_eq!(config.check_socket_path().status, CheckStatus::Pass);
+        
+        let listener = std::os::unix::net::UnixListener::bind(&config.socket_path).unwrap();
+        assert_eq!(config.check_socket_path().status, CheckStatus::Fail);
+        
+        drop(listener);
+        assert_eq!(config.check_socket_path().status, CheckStatus::Warn);
+        
+        let missing = UdsConfig { socket_path: dir.path().join("missing/tm.sock"), ..Default::default() };
+        assert_eq!(missing.check_socket_path().status, CheckStatus::Fail);
+    }
+    
     #[tokio::test]
     async fn test_message_codec() {
         let mut codec = MessageCodec::new(1024 * 1024);
@@ -301,4 +561,31 @@ mod tests {
         // Size should be larger than 0
         assert!(buf.len() > 4);
     }
+    
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_segment_frames() {
+        let (left, right) = tokio::net::UnixStream::pair().unwrap();
+        let mut client = Framed::new(SegmentStream::new(left), ClientCodec::new(1024));
+        let mut server = Framed::new(SegmentStream::new(right), MessageCodec::new(1024));
+        let large = Request::GetNodeByAlias { tenant_id: "acme".to_string(), alias: "a".repeat(4096), namespace: None };
+        
+        // Too large for the socket until shared memory is enabled
+        assert!(client.send(large.clone()).await.is_err());
+        
+        let descriptors = client.get_ref().descriptors();
+        client.codec_mut().enable_shared_memory(descriptors, 2048, 1 << 20);
+        let descriptors = server.get_ref().descriptors();
+        server.codec_mut().enable_shared_memory(SegmentTransfer { descriptors, min_segment_bytes: 2048, max_segment_bytes: 1 << 20 });
+        
+        client.send(large).await.unwrap();
+        client.send(Request::HealthCheck).await.unwrap();
+        match server.next().await.unwrap().unwrap() {
+            Request::GetNodeByAlias { alias, .. } => assert_eq!(alias.len(), 4096),
+            other => panic!("unexpected request {:?}", other),
+        }
+        assert!(server.codec().request_bytes() > 4096);
+        assert!(matches!(server.next().await.unwrap().unwrap(), Request::HealthCheck));
+        assert_eq!(server.codec().request_bytes(), 4 + "\"HealthCheck\"".len());
+    }
 }
\ No newline at end of file
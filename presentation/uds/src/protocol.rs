@@ -3,6 +3,9 @@
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
+use telamentis_core::types::{Collapse, EdgeAggregate, OrderBy, QueryMode};
+use telamentis_core::valid_time::SourceInfo;
+use telamentis_core::write_concern::WriteConcern;
 use uuid::Uuid;
 
 /// API request
@@ -12,6 +15,8 @@ pub enum Request {
     UpsertNode {
         tenant_id: String,
         node: Node,
+        #[serde(default)]
+        write_concern: WriteConcern,
     },
     GetNode {
         tenant_id: String,
@@ -24,26 +29,78 @@ pub enum Request {
     BatchUpsertNodes {
         tenant_id: String,
         nodes: Vec<Node>,
+        #[serde(default)]
+        write_concern: WriteConcern,
+    },
+    UpsertNodeWithEdges {
+        tenant_id: String,
+        node: Node,
+        edges: Vec<EdgeSpec>,
+        #[serde(default)]
+        write_concern: WriteConcern,
+    },
+    GetNodeByAlias {
+        tenant_id: String,
+        alias: String,
+        #[serde(default)]
+        namespace: Option<String>,
+    },
+    DeleteNodeByAlias {
+        tenant_id: String,
+        alias: String,
+        #[serde(default)]
+        namespace: Option<String>,
+    },
+    /// Merge properties into a node; a null value removes the property
+    PatchNodeByAlias {
+        tenant_id: String,
+        alias: String,
+        #[serde(default)]
+        namespace: Option<String>,
+        props: serde_json::Map<String, serde_json::Value>,
+        #[serde(default)]
+        write_concern: WriteConcern,
     },
     
     /// Edge operations
     UpsertEdge {
         tenant_id: String,
         edge: TimeEdge,
+        #[serde(default)]
+        write_concern: WriteConcern,
     },
     DeleteEdge {
         tenant_id: String,
         edge_id: Uuid,
     },
+    UpsertEdgeByRef {
+        tenant_id: String,
+        edge: EdgeByRef,
+        #[serde(default)]
+        write_concern: WriteConcern,
+    },
     BatchUpsertEdges {
         tenant_id: String,
         edges: Vec<TimeEdge>,
+        #[serde(default)]
+        write_concern: WriteConcern,
     },
     
     /// Query operations
     ExecuteQuery {
         tenant_id: String,
         query: GraphQuery,
+        /// Return the matching paths, only their number, or only whether there are any
+        #[serde(default)]
+        mode: QueryMode,
+    },
+    /// Several queries run concurrently; each succeeds or fails on its own
+    ExecuteQueryBatch {
+        tenant_id: String,
+        queries: Vec<BatchQuery>,
+        /// Deadline for the whole batch in milliseconds, 30000 if unset
+        #[serde(default)]
+        timeout_ms: Option<u64>,
     },
     
     /// LLM operations
@@ -58,6 +115,77 @@ pub enum Request {
     
     /// Health check
     HealthCheck,
+    
+    /// Ask to send large messages on this connection as shared-memory
+    /// segments, up to the given size. Needs a connection that passes
+    /// descriptors, such as a [`SegmentStream`](crate::shared_memory::SegmentStream).
+    NegotiateSharedMemory {
+        max_segment_bytes: usize,
+    },
+}
+
+impl Request {
+    /// Name of the request, as in its JSON encoding
+    pub fn name(&self) -> &'static str {
+        match self {
+            Request::UpsertNode { .. } => "UpsertNode",
+            Request::GetNode { .. } => "GetNode",
+            Request::DeleteNode { .. } => "DeleteNode",
+            Request::BatchUpsertNodes { .. } => "BatchUpsertNodes",
+            Request::UpsertNodeWithEdges { .. } => "UpsertNodeWithEdges",
+            Request::GetNodeByAlias { .. } => "GetNodeByAlias",
+            Request::DeleteNodeByAlias { .. } => "DeleteNodeByAlias",
+            Request::PatchNodeByAlias { .. } => "PatchNodeByAlias",
+            Request::UpsertEdge { .. } => "UpsertEdge",
+            Request::DeleteEdge { .. } => "DeleteEdge",
+            Request::UpsertEdgeByRef { .. } => "UpsertEdgeByRef",
+            Request::BatchUpsertEdges { .. } => "BatchUpsertEdges",
+            Request::ExecuteQuery { .. } => "ExecuteQuery",
+            Request::ExecuteQueryBatch { .. } => "ExecuteQueryBatch",
+            Request::ExtractKnowledge { .. } => "ExtractKnowledge",
+            Request::CompleteText { .. } => "CompleteText",
+            Request::HealthCheck => "HealthCheck",
+            Request::NegotiateSharedMemory { .. } => "NegotiateSharedMemory",
+        }
+    }
+
+    /// Tenant the request is made for
+    pub fn tenant_id(&self) -> Option<&str> {
+        match self {
+            Request::UpsertNode { tenant_id, .. }
+            | Request::GetNode { tenant_id, .. }
+            | Request::DeleteNode { tenant_id, .. }
+            | Request::BatchUpsertNodes { tenant_id, .. }
+            | Request::UpsertNodeWithEdges { tenant_id, .. }
+            | Request::GetNodeByAlias { tenant_id, .. }
+            | Request::DeleteNodeByAlias { tenant_id, .. }
+            | Request::PatchNodeByAlias { tenant_id, .. }
+            | Request::UpsertEdge { tenant_id, .. }
+            | Request::DeleteEdge { tenant_id, .. }
+            | Request::UpsertEdgeByRef { tenant_id, .. }
+            | Request::BatchUpsertEdges { tenant_id, .. }
+            | Request::ExecuteQuery { tenant_id, .. }
+            | Request::ExecuteQueryBatch { tenant_id, .. }
+            | Request::ExtractKnowledge { tenant_id, .. }
+            | Request::CompleteText { tenant_id, .. } => Some(tenant_id),
+            Request::HealthCheck | Request::NegotiateSharedMemory { .. } => None,
+        }
+    }
+
+    /// Whether the request changes the graph
+    pub fn is_write(&self) -> bool {
+        !matches!(
+            self,
+            Request::GetNode { .. }
+                | Request::GetNodeByAlias { .. }
+                | Request::ExecuteQuery { .. }
+                | Request::ExecuteQueryBatch { .. }
+                | Request::ExtractKnowledge { .. }
+                | Request::CompleteText { .. }
+                | Request::HealthCheck
+                | Request::NegotiateSharedMemory { .. }
+        )
+    }
 }
 
 /// API response
@@ -67,6 +195,8 @@ pub enum Response {
     UpsertNode {
         node_id: Uuid,
         created: bool,
+        /// Write concern the write was acknowledged at; IDs are nil for `buffered`
+        write_concern: WriteConcern,
     },
     GetNode {
         node: Option<Node>,
@@ -78,12 +208,32 @@ pub enum Response {
         node_ids: Vec<Uuid>,
         created_count: usize,
         updated_count: usize,
+        /// Write concern the write was acknowledged at; IDs are nil for `buffered`
+        write_concern: WriteConcern,
+    },
+    UpsertNodeWithEdges {
+        node_id: Uuid,
+        edge_ids: Vec<Uuid>,
+        /// Write concern the write was acknowledged at; IDs are nil for `buffered`
+        write_concern: WriteConcern,
+    },
+    /// A node addressed by alias, for get and patch requests; empty if no
+    /// node has the alias
+    NodeByAlias {
+        node_id: Option<Uuid>,
+        node: Option<Node>,
+    },
+    DeleteNodeByAlias {
+        /// ID of the deleted node, if one had the alias
+        node_id: Option<Uuid>,
     },
     
     /// Edge operations
     UpsertEdge {
         edge_id: Uuid,
         created: bool,
+        /// Write concern the write was acknowledged at; IDs are nil for `buffered`
+        write_concern: WriteConcern,
     },
     DeleteEdge {
         deleted: bool,
@@ -92,11 +242,25 @@ pub enum Response {
         edge_ids: Vec<Uuid>,
         created_count: usize,
         updated_count: usize,
+        /// Write concern the write was acknowledged at; IDs are nil for `buffered`
+        write_concern: WriteConcern,
     },
     
     /// Query operations
     ExecuteQuery {
+        /// Matching paths; empty unless the mode is `full`
         paths: Vec<Path>,
+        /// Number of matches, in `count` mode
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        count: Option<u64>,
+        /// Whether anything matched, in `exists` mode
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        exists: Option<bool>,
+        execution_time_ms: u64,
+    },
+    ExecuteQueryBatch {
+        /// Results in the order of the queries
+        results: Vec<BatchQueryResult>,
         execution_time_ms: u64,
     },
     
@@ -114,6 +278,15 @@ pub enum Response {
         status: String,
     },
     
+    /// Whether the server sends large messages as shared-memory segments
+    /// from now on, and accepts them. Messages of `min_segment_bytes` up to
+    /// `max_segment_bytes` go as segments; others through the socket.
+    SharedMemory {
+        enabled: bool,
+        min_segment_bytes: usize,
+        max_segment_bytes: usize,
+    },
+    
     /// Error
     Error(ApiError),
 }
@@ -129,6 +302,7 @@ pub struct ApiError {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
     pub id_alias: Option<String>,
+    pub alias_namespace: Option<String>,
     pub label: String,
     pub props: serde_json::Value,
 }
@@ -139,10 +313,57 @@ pub struct TimeEdge {
     pub from_node_id: Uuid,
     pub to_node_id: Uuid,
     pub kind: String,
+    /// A date such as "2020-03" is read as the start of its period
+    #[serde(deserialize_with = "telamentis_core::temporal::valid_bound::start")]
     pub valid_from: DateTime<Utc>,
+    /// A date is read as the end of its period
+    #[serde(default, deserialize_with = "telamentis_core::temporal::valid_bound::end_opt")]
     pub valid_to: Option<DateTime<Utc>>,
     pub transaction_start_time: DateTime<Utc>,
     pub transaction_end_time: Option<DateTime<Utc>>,
+    /// When the store drops the edge (None = never)
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub props: serde_json::Value,
+}
+
+/// Reference to an existing node by ID or alias
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum NodeRef {
+    Id(Uuid),
+    Alias {
+        namespace: Option<String>,
+        alias: String,
+    },
+}
+
+/// Edge to create alongside a node upsert
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeSpec {
+    pub kind: String,
+    pub target: NodeRef,
+    pub incoming: bool,
+    #[serde(default, deserialize_with = "telamentis_core::temporal::valid_bound::start_opt")]
+    pub valid_from: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "telamentis_core::temporal::valid_bound::end_opt")]
+    pub valid_to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub props: serde_json::Value,
+}
+
+/// Edge whose endpoints are given by ID or alias
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeByRef {
+    pub from: NodeRef,
+    pub to: NodeRef,
+    pub kind: String,
+    #[serde(default, deserialize_with = "telamentis_core::temporal::valid_bound::start_opt")]
+    pub valid_from: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "telamentis_core::temporal::valid_bound::end_opt")]
+    pub valid_to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
     pub props: serde_json::Value,
 }
 
@@ -169,6 +390,8 @@ pub struct PathRelationship {
     pub start_node_id: Uuid,
     pub end_node_id: Uuid,
     pub properties: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collapsed: Option<EdgeAggregate>,
 }
 
 /// Graph query
@@ -181,21 +404,57 @@ pub enum GraphQuery {
     FindNodes {
         labels: Vec<String>,
         properties: HashMap<String, serde_json::Value>,
+        #[serde(default)]
+        order_by: Vec<OrderBy>,
+        #[serde(default)]
+        offset: Option<u32>,
         limit: Option<u32>,
     },
     FindRelationships {
         from_node_id: Option<Uuid>,
         to_node_id: Option<Uuid>,
         relationship_types: Vec<String>,
+        /// A date such as "2020-03" means as of its last instant
+        #[serde(default, deserialize_with = "telamentis_core::temporal::valid_bound::as_of_opt")]
         valid_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        order_by: Vec<OrderBy>,
+        #[serde(default)]
+        offset: Option<u32>,
         limit: Option<u32>,
+        #[serde(default)]
+        collapse: Option<Collapse>,
     },
     AsOfQuery {
         base_query: Box<GraphQuery>,
+        #[serde(deserialize_with = "telamentis_core::temporal::valid_bound::as_of")]
         as_of_time: DateTime<Utc>,
     },
 }
 
+/// A query of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchQuery {
+    pub query: GraphQuery,
+    #[serde(default)]
+    pub mode: QueryMode,
+}
+
+/// Outcome of a query of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchQueryResult {
+    /// Matching paths; empty unless the mode is `full`
+    pub paths: Vec<Path>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    /// Why the query failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub execution_time_ms: u64,
+}
+
 /// LLM message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmMessage {
@@ -211,6 +470,14 @@ pub struct ExtractionContext {
     pub desired_schema: Option<String>,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
+    #[serde(default)]
+    pub source: Option<SourceInfo>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 /// Extraction node
@@ -257,8 +524,16 @@ pub struct ExtractionEnvelope {
 /// Completion request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompletionRequest {
+    #[serde(default)]
     pub prompt: String,
+    /// Conversation history, sent before the prompt
+    #[serde(default)]
+    pub messages: Vec<LlmMessage>,
+    #[serde(default)]
+    pub system: Option<String>,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
     pub params: serde_json::Value,
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
\ No newline at end of file
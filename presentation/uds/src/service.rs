@@ -1,6 +1,7 @@
 //! UDS service implementation
 
 use crate::protocol::{Request, Response, ApiError, GraphQuery as ProtoGraphQuery};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use telamentis_core::prelude::*;
 use telamentis_core::pipeline::PipelineRunner;
@@ -11,6 +12,7 @@ use tracing::{debug, error, info};
 pub struct UdsService {
     core_service: Arc<dyn GraphService>,
     pipeline: Arc<PipelineRunner>,
+    dead_letters: Option<Arc<DeadLetterQueue>>,
 }
 
 impl UdsService {
@@ -19,14 +21,20 @@ impl UdsService {
         core_service: Arc<dyn GraphService>,
         pipeline: Arc<PipelineRunner>,
     ) -> Self {
-        Self { core_service, pipeline }
+        Self { core_service, pipeline, dead_letters: None }
+    }
+    
+    /// Queue records that batch requests fail to write
+    pub fn with_dead_letter_queue(mut self, dead_letters: Option<Arc<DeadLetterQueue>>) -> Self {
+        self.dead_letters = dead_letters;
+        self
     }
     
     /// Handle an incoming request
     pub async fn handle_request(&self, request: Request) -> Result<Response, CoreError> {
         match request {
-            Request::UpsertNode { tenant_id, node } => {
-                self.handle_upsert_node(tenant_id, node).await
+            Request::UpsertNode { tenant_id, node, write_concern } => {
+                with_write_concern(write_concern, self.handle_upsert_node(tenant_id, node)).await
             },
             Request::GetNode { tenant_id, node_id } => {
                 self.handle_get_node(tenant_id, node_id).await
@@ -34,20 +42,38 @@ impl UdsService {
             Request::DeleteNode { tenant_id, node_id } => {
                 self.handle_delete_node(tenant_id, node_id).await
             },
-            Request::BatchUpsertNodes { tenant_id, nodes } => {
-                self.handle_batch_upsert_nodes(tenant_id, nodes).await
+            Request::BatchUpsertNodes { tenant_id, nodes, write_concern } => {
+                with_write_concern(write_concern, self.handle_batch_upsert_nodes(tenant_id, nodes)).await
+            },
+            Request::UpsertNodeWithEdges { tenant_id, node, edges, write_concern } => {
+                with_write_concern(write_concern, self.handle_upsert_node_with_edges(tenant_id, node, edges)).await
+            },
+            Request::GetNodeByAlias { tenant_id, alias, namespace } => {
+                self.handle_get_node_by_alias(tenant_id, AliasKey { namespace, alias }).await
+            },
+            Request::DeleteNodeByAlias { tenant_id, alias, namespace } => {
+                self.handle_delete_node_by_alias(tenant_id, AliasKey { namespace, alias }).await
+            },
+            Request::PatchNodeByAlias { tenant_id, alias, namespace, props, write_concern } => {
+                with_write_concern(write_concern, self.handle_patch_node_by_alias(tenant_id, AliasKey { namespace, alias }, props)).await
             },
-            Request::UpsertEdge { tenant_id, edge } => {
-                self.handle_upsert_edge(tenant_id, edge).await
+            Request::UpsertEdge { tenant_id, edge, write_concern } => {
+                with_write_concern(write_concern, self.handle_upsert_edge(tenant_id, edge)).await
             },
             Request::DeleteEdge { tenant_id, edge_id } => {
                 self.handle_delete_edge(tenant_id, edge_id).await
             },
-            Request::BatchUpsertEdges { tenant_id, edges } => {
-                self.handle_batch_upsert_edges(tenant_id, edges).await
+            Request::UpsertEdgeByRef { tenant_id, edge, write_concern } => {
+                with_write_concern(write_concern, self.handle_upsert_edge_by_ref(tenant_id, edge)).await
             },
-            Request::ExecuteQuery { tenant_id, query } => {
-                self.handle_execute_query(tenant_id, query).await
+            Request::BatchUpsertEdges { tenant_id, edges, write_concern } => {
+                with_write_concern(write_concern, self.handle_batch_upsert_edges(tenant_id, edges)).await
+            },
+            Request::ExecuteQuery { tenant_id, query, mode } => {
+                self.handle_execute_query(tenant_id, query, mode).await
+            },
+            Request::ExecuteQueryBatch { tenant_id, queries, timeout_ms } => {
+                self.handle_execute_query_batch(tenant_id, queries, timeout_ms).await
             },
             Request::ExtractKnowledge { tenant_id, context } => {
                 self.handle_extract_knowledge(tenant_id, context).await
@@ -58,6 +84,12 @@ impl UdsService {
             Request::HealthCheck => {
                 self.handle_health_check().await
             },
+            // Negotiated by the connection, which the service does not see
+            Request::NegotiateSharedMemory { .. } => Ok(Response::SharedMemory {
+                enabled: false,
+                min_segment_bytes: 0,
+                max_segment_bytes: 0,
+            }),
         }
     }
     
@@ -81,6 +113,7 @@ impl UdsService {
         // Convert protocol node to core node
         let core_node = Node {
             id_alias: node.id_alias,
+            alias_namespace: node.alias_namespace,
             label: node.label,
             props: node.props,
         };
@@ -90,9 +123,10 @@ impl UdsService {
             Ok(node_id) => Ok(Response::UpsertNode {
                 node_id,
                 created: true,
+                write_concern: current_write_concern(),
             }),
             Err(e) => Ok(Response::Error(ApiError {
-                code: 500,
+                code: graph_error_code(&e),
                 message: format!("Failed to upsert node: {}", e),
             })),
         }
@@ -106,6 +140,7 @@ impl UdsService {
             Ok(Some(node)) => {
                 let proto_node = crate::protocol::Node {
                     id_alias: node.id_alias,
+                    alias_namespace: node.alias_namespace,
                     label: node.label,
                     props: node.props,
                 };
@@ -114,7 +149,7 @@ impl UdsService {
             },
             Ok(None) => Ok(Response::GetNode { node: None }),
             Err(e) => Ok(Response::Error(ApiError {
-                code: 500,
+                code: graph_error_code(&e),
                 message: format!("Failed to get node: {}", e),
             })),
         }
@@ -127,12 +162,56 @@ impl UdsService {
         match self.core_service.delete_node(&tenant, node_id).await {
             Ok(deleted) => Ok(Response::DeleteNode { deleted }),
             Err(e) => Ok(Response::Error(ApiError {
-                code: 500,
+                code: graph_error_code(&e),
                 message: format!("Failed to delete node: {}", e),
             })),
         }
     }
     
+    /// Handle get node by alias request
+    async fn handle_get_node_by_alias(&self, tenant_id: String, alias: AliasKey) -> Result<Response, CoreError> {
+        let tenant = TenantId::new(tenant_id);
+        
+        match self.core_service.get_node_by_alias(&tenant, &alias).await {
+            Ok(record) => Ok(node_by_alias(record)),
+            Err(e) => Ok(Response::Error(ApiError {
+                code: graph_error_code(&e),
+                message: format!("Failed to get node {}: {}", alias, e),
+            })),
+        }
+    }
+    
+    /// Handle delete node by alias request
+    async fn handle_delete_node_by_alias(&self, tenant_id: String, alias: AliasKey) -> Result<Response, CoreError> {
+        let tenant = TenantId::new(tenant_id);
+        
+        match self.core_service.delete_node_by_alias(&tenant, &alias).await {
+            Ok(node_id) => Ok(Response::DeleteNodeByAlias { node_id }),
+            Err(e) => Ok(Response::Error(ApiError {
+                code: graph_error_code(&e),
+                message: format!("Failed to delete node {}: {}", alias, e),
+            })),
+        }
+    }
+    
+    /// Handle patch node by alias request
+    async fn handle_patch_node_by_alias(
+        &self,
+        tenant_id: String,
+        alias: AliasKey,
+        props: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Response, CoreError> {
+        let tenant = TenantId::new(tenant_id);
+        
+        match self.core_service.patch_node_by_alias(&tenant, &alias, props).await {
+            Ok(record) => Ok(node_by_alias(record)),
+            Err(e) => Ok(Response::Error(ApiError {
+                code: graph_error_code(&e),
+                message: format!("Failed to patch node {}: {}", alias, e),
+            })),
+        }
+    }
+    
     /// Handle batch upsert nodes request
     async fn handle_batch_upsert_nodes(&self, tenant_id: String, nodes: Vec<crate::protocol::Node>) -> Result<Response, CoreError> {
         let tenant = TenantId::new(tenant_id);
@@ -140,21 +219,22 @@ impl UdsService {
         let mut created_count = 0;
         let mut updated_count = 0;
         
-        for node in nodes {
+        for (index, node) in nodes.into_iter().enumerate() {
             let core_node = Node {
                 id_alias: node.id_alias,
+                alias_namespace: node.alias_namespace,
                 label: node.label,
                 props: node.props,
             };
             
-            match self.core_service.upsert_node(&tenant, core_node).await {
+            match self.core_service.upsert_node(&tenant, core_node.clone()).await {
                 Ok(id) => {
                     node_ids.push(id);
                     created_count += 1;
                 },
                 Err(e) => {
-                    error!("Failed to upsert node: {}", e);
                     // Continue with other nodes
+                    self.dead_letter(&tenant, DeadLetterRecord::Node(core_node), e, index);
                 }
             }
         }
@@ -163,9 +243,78 @@ impl UdsService {
             node_ids,
             created_count,
             updated_count,
+            write_concern: current_write_concern(),
         })
     }
     
+    /// Handle upsert node with edges request
+    async fn handle_upsert_node_with_edges(
+        &self,
+        tenant_id: String,
+        node: crate::protocol::Node,
+        edges: Vec<crate::protocol::EdgeSpec>,
+    ) -> Result<Response, CoreError> {
+        let tenant = TenantId::new(tenant_id);
+        
+        let core_node = Node {
+            id_alias: node.id_alias,
+            alias_namespace: node.alias_namespace,
+            label: node.label,
+            props: node.props,
+        };
+        
+        let core_edges = edges.into_iter().map(|spec| {
+            EdgeSpec {
+                kind: spec.kind,
+                target: core_node_ref(spec.target),
+                direction: if spec.incoming { EdgeDirection::Incoming } else { EdgeDirection::Outgoing },
+                valid_from: spec.valid_from,
+                valid_to: spec.valid_to,
+                expires_at: spec.expires_at,
+                props: spec.props,
+            }
+        }).collect();
+        
+        match self.core_service.upsert_node_with_edges(&tenant, core_node, core_edges).await {
+            Ok(result) => Ok(Response::UpsertNodeWithEdges {
+                node_id: result.node_id,
+                edge_ids: result.edge_ids,
+                write_concern: current_write_concern(),
+            }),
+            Err(e) => Ok(Response::Error(ApiError {
+                code: graph_error_code(&e),
+                message: format!("Failed to upsert node with edges: {}", e),
+            })),
+        }
+    }
+    
+    /// Handle upsert edge by reference request
+    async fn handle_upsert_edge_by_ref(&self, tenant_id: String, edge: crate::protocol::EdgeByRef) -> Result<Response, CoreError> {
+        let tenant = TenantId::new(tenant_id);
+        
+        let core_edge = EdgeByRef {
+            from: core_node_ref(edge.from),
+            to: core_node_ref(edge.to),
+            kind: edge.kind,
+            valid_from: edge.valid_from,
+            valid_to: edge.valid_to,
+            expires_at: edge.expires_at,
+            props: edge.props,
+        };
+        
+        match self.core_service.upsert_edge_by_ref(&tenant, core_edge).await {
+            Ok(edge_id) => Ok(Response::UpsertEdge {
+                edge_id,
+                created: true,
+                write_concern: current_write_concern(),
+            }),
+            Err(e) => Ok(Response::Error(ApiError {
+                code: graph_error_code(&e),
+                message: format!("Failed to upsert edge: {}", e),
+            })),
+        }
+    }
+    
     /// Handle upsert edge request
     async fn handle_upsert_edge(&self, tenant_id: String, edge: crate::protocol::TimeEdge) -> Result<Response, CoreError> {
         let tenant = TenantId::new(tenant_id);
@@ -179,6 +328,7 @@ impl UdsService {
             valid_to: edge.valid_to,
             transaction_start_time: edge.transaction_start_time,
             transaction_end_time: edge.transaction_end_time,
+            expires_at: edge.expires_at,
             props: edge.props,
         };
         
@@ -187,9 +337,10 @@ impl UdsService {
             Ok(edge_id) => Ok(Response::UpsertEdge {
                 edge_id,
                 created: true,
+                write_concern: current_write_concern(),
             }),
             Err(e) => Ok(Response::Error(ApiError {
-                code: 500,
+                code: graph_error_code(&e),
                 message: format!("Failed to upsert edge: {}", e),
             })),
         }
@@ -202,7 +353,7 @@ impl UdsService {
         match self.core_service.delete_edge(&tenant, edge_id).await {
             Ok(deleted) => Ok(Response::DeleteEdge { deleted }),
             Err(e) => Ok(Response::Error(ApiError {
-                code: 500,
+                code: graph_error_code(&e),
                 message: format!("Failed to delete edge: {}", e),
             })),
         }
@@ -215,7 +366,7 @@ impl UdsService {
         let mut created_count = 0;
         let mut updated_count = 0;
         
-        for edge in edges {
+        for (index, edge) in edges.into_iter().enumerate() {
             let core_edge = TimeEdge {
                 from_node_id: edge.from_node_id,
                 to_node_id: edge.to_node_id,
@@ -224,17 +375,18 @@ impl UdsService {
                 valid_to: edge.valid_to,
                 transaction_start_time: edge.transaction_start_time,
                 transaction_end_time: edge.transaction_end_time,
+                expires_at: edge.expires_at,
                 props: edge.props,
             };
             
-            match self.core_service.upsert_edge(&tenant, core_edge).await {
+            match self.core_service.upsert_edge(&tenant, core_edge.clone()).await {
                 Ok(id) => {
                     edge_ids.push(id);
                     created_count += 1;
                 },
                 Err(e) => {
-                    error!("Failed to upsert edge: {}", e);
                     // Continue with other edges
+                    self.dead_letter(&tenant, DeadLetterRecord::Edge(core_edge), e, index);
                 }
             }
         }
@@ -243,75 +395,88 @@ impl UdsService {
             edge_ids,
             created_count,
             updated_count,
+            write_concern: current_write_concern(),
         })
     }
     
+    /// Log a record a batch request failed to write and queue it, if the
+    /// dead-letter queue is enabled
+    fn dead_letter(&self, tenant: &TenantId, record: DeadLetterRecord, error: GraphError, index: usize) {
+        error!("Failed to upsert batch record {} for tenant {}: {}", index, tenant, error);
+        if let Some(queue) = &self.dead_letters {
+            let context = BTreeMap::from([("batch_index".to_string(), index.to_string())]);
+            queue.record(tenant, record, error, "uds", context);
+        }
+    }
+    
     /// Handle execute query request
-    async fn handle_execute_query(&self, tenant_id: String, query: ProtoGraphQuery) -> Result<Response, CoreError> {
+    async fn handle_execute_query(&self, tenant_id: String, query: ProtoGraphQuery, mode: QueryMode) -> Result<Response, CoreError> {
         let tenant = TenantId::new(tenant_id);
         let start_time = std::time::Instant::now();
         
         // Convert protocol query to core query
-        let core_query = match query {
-            ProtoGraphQuery::Raw { query, params } => {
-                GraphQuery::Raw { query, params }
-            },
-            ProtoGraphQuery::FindNodes { labels, properties, limit } => {
-                GraphQuery::FindNodes { labels, properties, limit }
-            },
-            ProtoGraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, limit } => {
-                GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, limit }
-            },
-            ProtoGraphQuery::AsOfQuery { base_query, as_of_time } => {
-                GraphQuery::AsOfQuery { base_query: Box::new(*base_query), as_of_time }
-            },
+        let core_query = proto_to_core_query(query);
+        
+        // Execute core operation; count and exists modes skip reading the matches
+        let result = match mode {
+            QueryMode::Full => self.core_service.query(&tenant, core_query).await
+                .map(|paths| (paths, None, None)),
+            QueryMode::Count => self.core_service.query_count(&tenant, core_query).await
+                .map(|count| (Vec::new(), Some(count), None)),
+            QueryMode::Exists => self.core_service.query_exists(&tenant, core_query).await
+                .map(|exists| (Vec::new(), None, Some(exists))),
         };
         
-        // Execute core operation
-        match self.core_service.query(&tenant, core_query).await {
-            Ok(paths) => {
+        match result {
+            Ok((paths, count, exists)) => {
                 let execution_time = start_time.elapsed();
                 
                 // Convert core paths to protocol paths
-                let proto_paths = paths.iter().map(|p| {
-                    // Convert nodes
-                    let nodes = p.nodes.iter().map(|n| {
-                        crate::protocol::PathNode {
-                            id: n.id,
-                            labels: n.labels.clone(),
-                            properties: n.properties.clone(),
-                        }
-                    }).collect();
-                    
-                    // Convert relationships
-                    let relationships = p.relationships.iter().map(|r| {
-                        crate::protocol::PathRelationship {
-                            id: r.id,
-                            rel_type: r.rel_type.clone(),
-                            start_node_id: r.start_node_id,
-                            end_node_id: r.end_node_id,
-                            properties: r.properties.clone(),
-                        }
-                    }).collect();
-                    
-                    crate::protocol::Path {
-                        nodes,
-                        relationships,
-                    }
-                }).collect();
+                let proto_paths = paths.iter().map(core_to_proto_path).collect();
                 
                 Ok(Response::ExecuteQuery {
                     paths: proto_paths,
+                    count,
+                    exists,
                     execution_time_ms: execution_time.as_millis() as u64,
                 })
             },
             Err(e) => Ok(Response::Error(ApiError {
-                code: 500,
+                code: graph_error_code(&e),
                 message: format!("Failed to execute query: {}", e),
             })),
         }
     }
     
+    /// Handle execute query batch request
+    async fn handle_execute_query_batch(&self, tenant_id: String, queries: Vec<crate::protocol::BatchQuery>, timeout_ms: Option<u64>) -> Result<Response, CoreError> {
+        let tenant = TenantId::new(tenant_id);
+        let request = BatchQueryRequest {
+            queries: queries.into_iter()
+                .map(|query| BatchQuery::new(proto_to_core_query(query.query)).with_mode(query.mode))
+                .collect(),
+            timeout_ms,
+        };
+        
+        match run_batch(self.core_service.clone(), &tenant, request).await {
+            Ok(response) => Ok(Response::ExecuteQueryBatch {
+                results: response.results.into_iter().map(|result| crate::protocol::BatchQueryResult {
+                    paths: result.paths.iter().map(core_to_proto_path).collect(),
+                    count: result.count,
+                    exists: result.exists,
+                    error: result.error,
+                    execution_time_ms: result.execution_time_ms,
+                }).collect(),
+                execution_time_ms: response.execution_time_ms,
+            }),
+            // The batch as a whole only fails when it is empty or too large
+            Err(e) => Ok(Response::Error(ApiError {
+                code: 400,
+                message: format!("Failed to execute query batch: {}", e),
+            })),
+        }
+    }
+    
     /// Handle extract knowledge request
     async fn handle_extract_knowledge(&self, tenant_id: String, context: crate::protocol::ExtractionContext) -> Result<Response, CoreError> {
         let tenant = TenantId::new(tenant_id);
@@ -322,12 +487,19 @@ impl UdsService {
                 LlmMessage {
                     role: m.role,
                     content: m.content,
+                    parts: Vec::new(),
                 }
             }).collect(),
             system_prompt: context.system_prompt,
             desired_schema: context.desired_schema,
             max_tokens: context.max_tokens,
             temperature: context.temperature,
+            examples: None,
+            model: context.model,
+            provider: context.provider,
+            source: context.source,
+            seed: context.seed,
+            graph_context: None,
         };
         
         // Execute core operation
@@ -376,7 +548,12 @@ impl UdsService {
                 })
             },
             Err(e) => Ok(Response::Error(ApiError {
-                code: 500,
+                code: match e {
+                    LlmError::CapabilityUnavailable(_) => 503,
+                    LlmError::ProviderNotAllowed(_) => 403,
+                    LlmError::ContentBlocked(_) => 422,
+                    _ => 500,
+                },
                 message: format!("Failed to extract knowledge: {}", e),
             })),
         }
@@ -389,9 +566,12 @@ impl UdsService {
         // Convert protocol request to core request
         let core_request = CompletionRequest {
             prompt: request.prompt,
+            messages: request.messages.into_iter().map(|m| LlmMessage::new(m.role, m.content)).collect(),
+            system: request.system,
             max_tokens: request.max_tokens,
             temperature: request.temperature,
             params: request.params,
+            seed: request.seed,
         };
         
         // Execute core operation
@@ -425,9 +605,13 @@ impl UdsService {
     /// Handle health check request
     async fn handle_health_check(&self) -> Result<Response, CoreError> {
         match self.core_service.health_check().await {
-            Ok(_) => Ok(Response::HealthCheck {
-                status: "healthy".to_string(),
-            }),
+            Ok(_) => {
+                let status = match self.core_service.llm_status().await {
+                    CapabilityStatus::Available => "healthy",
+                    CapabilityStatus::Unavailable { .. } => "degraded",
+                };
+                Ok(Response::HealthCheck { status: status.to_string() })
+            }
             Err(e) => Ok(Response::Error(ApiError {
                 code: 500,
                 message: format!("Health check failed: {}", e),
@@ -436,11 +620,93 @@ impl UdsService {
     }
 }
 
+/// Status code for a storage error, matching the HTTP status the REST API
+/// returns for it
+fn core_node_ref(node: crate::protocol::NodeRef) -> NodeRef {
+    match node {
+        crate::protocol::NodeRef::Id(id) => NodeRef::Id(id),
+        crate::protocol::NodeRef::Alias { namespace, alias } => NodeRef::Alias(AliasKey { namespace, alias }),
+    }
+}
+
+fn node_by_alias(record: Option<NodeRecord>) -> Response {
+    match record {
+        Some(NodeRecord { id, node }) => Response::NodeByAlias {
+            node_id: Some(id),
+            node: Some(crate::protocol::Node {
+                id_alias: node.id_alias,
+                alias_namespace: node.alias_namespace,
+                label: node.label,
+                props: node.props,
+            }),
+        },
+        None => Response::NodeByAlias { node_id: None, node: None },
+    }
+}
+
+/// Convert a protocol query to a core query
+fn proto_to_core_query(query: ProtoGraphQuery) -> GraphQuery {
+    match query {
+        ProtoGraphQuery::Raw { query, params } => {
+            GraphQuery::Raw { query, params }
+        },
+        ProtoGraphQuery::FindNodes { labels, properties, order_by, offset, limit } => {
+            GraphQuery::FindNodes { labels, properties, order_by, offset, limit }
+        },
+        ProtoGraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse } => {
+            GraphQuery::FindRelationships { from_node_id, to_node_id, relationship_types, valid_at, order_by, offset, limit, collapse }
+        },
+        ProtoGraphQuery::AsOfQuery { base_query, as_of_time } => {
+            GraphQuery::AsOfQuery { base_query: Box::new(proto_to_core_query(*base_query)), as_of_time }
+        },
+    }
+}
+
+/// Convert a core path to a protocol path
+fn core_to_proto_path(path: &Path) -> crate::protocol::Path {
+    let nodes = path.nodes.iter().map(|n| {
+        crate::protocol::PathNode {
+            id: n.id,
+            labels: n.labels.clone(),
+            properties: n.properties.clone(),
+        }
+    }).collect();
+    
+    let relationships = path.relationships.iter().map(|r| {
+        crate::protocol::PathRelationship {
+            id: r.id,
+            rel_type: r.rel_type.clone(),
+            start_node_id: r.start_node_id,
+            end_node_id: r.end_node_id,
+            properties: r.properties.clone(),
+            collapsed: r.collapsed.clone(),
+        }
+    }).collect();
+    
+    crate::protocol::Path {
+        nodes,
+        relationships,
+    }
+}
+
+fn graph_error_code(error: &GraphError) -> u16 {
+    match error {
+        GraphError::NodeNotFound(_) | GraphError::EdgeNotFound(_) | GraphError::SnapshotNotFound(_) => 404,
+        GraphError::ConstraintViolation(_) => 409,
+        GraphError::TenantIsolationViolation(_) => 403,
+        GraphError::ReservedProperty(_) | GraphError::InvalidTenant(_) => 400,
+        GraphError::Temporal(_) => 422,
+        GraphError::Overloaded(_) => 429,
+        _ => 500,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use telamentis_core::traits::*;
     use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::collections::HashMap;
     
     #[test]
     fn test_service_creation() {
@@ -449,6 +715,70 @@ mod tests {
         let service = UdsService::new(core_service, Arc::new(pipeline));
     }
     
+    #[tokio::test]
+    async fn test_upsert_node_with_edges_request() {
+        let core_service = Arc::new(MockGraphService::new());
+        let service = UdsService::new(core_service.clone(), Arc::new(PipelineRunner::new()));
+        
+        let request = Request::UpsertNodeWithEdges {
+            tenant_id: "tenant".to_string(),
+            node: crate::protocol::Node {
+                id_alias: Some("alice".to_string()),
+                alias_namespace: None,
+                label: "Person".to_string(),
+                props: serde_json::json!({}),
+            },
+            edges: vec![crate::protocol::EdgeSpec {
+                kind: "WORKS_FOR".to_string(),
+                target: crate::protocol::NodeRef::Alias {
+                    namespace: Some("crm".to_string()),
+                    alias: "acme".to_string(),
+                },
+                incoming: false,
+                valid_from: None,
+                valid_to: None,
+                expires_at: None,
+                props: serde_json::json!({}),
+            }],
+            write_concern: WriteConcern::Committed,
+        };
+        
+        match service.handle_request(request).await.unwrap() {
+            Response::UpsertNodeWithEdges { edge_ids, .. } => assert_eq!(edge_ids.len(), 1),
+            other => panic!("Unexpected response: {:?}", other),
+        }
+        assert_eq!(core_service.call_count(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_batch_failures_are_dead_lettered() {
+        let queue = Arc::new(DeadLetterQueue::default());
+        let service = UdsService::new(Arc::new(MockGraphService::new()), Arc::new(PipelineRunner::new()))
+            .with_dead_letter_queue(Some(queue.clone()));
+        let node = |label: &str| crate::protocol::Node {
+            id_alias: None,
+            alias_namespace: None,
+            label: label.to_string(),
+            props: serde_json::json!({}),
+        };
+        
+        let request = Request::BatchUpsertNodes {
+            tenant_id: "tenant".to_string(),
+            nodes: vec![node("Person"), node("")],
+            write_concern: WriteConcern::Committed,
+        };
+        match service.handle_request(request).await.unwrap() {
+            Response::BatchUpsertNodes { node_ids, .. } => assert_eq!(node_ids.len(), 1),
+            other => panic!("Unexpected response: {:?}", other),
+        }
+        
+        let entries = queue.list(&TenantId::new("tenant"));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].source, "uds");
+        assert_eq!(entries[0].context["batch_index"], "1");
+        assert!(matches!(&entries[0].record, DeadLetterRecord::Node(node) if node.label.is_empty()));
+    }
+    
     // Mock implementation of GraphService for testing
     struct MockGraphService {
         call_count: AtomicUsize,
@@ -468,8 +798,11 @@ mod tests {
     
     #[async_trait]
     impl GraphService for MockGraphService {
-        async fn upsert_node(&self, _tenant: &TenantId, _node: Node) -> Result<Uuid, GraphError> {
+        async fn upsert_node(&self, _tenant: &TenantId, node: Node) -> Result<Uuid, GraphError> {
             self.call_count.fetch_add(1, Ordering::Relaxed);
+            if node.label.is_empty() {
+                return Err(GraphError::ConstraintViolation("Node label is required".to_string()));
+            }
             Ok(Uuid::new_v4())
         }
         
@@ -478,11 +811,85 @@ mod tests {
             Ok(Uuid::new_v4())
         }
         
+        async fn close_edge(&self, _tenant: &TenantId, _id: Uuid, _valid_to: DateTime<Utc>) -> Result<Uuid, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(Uuid::new_v4())
+        }
+        
+        async fn supersede_edge(&self, _tenant: &TenantId, _id: Uuid, _edge: TimeEdge) -> Result<Uuid, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(Uuid::new_v4())
+        }
+        
+        async fn retract_edge(&self, _tenant: &TenantId, _id: Uuid) -> Result<bool, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(true)
+        }
+        
+        async fn upsert_node_with_edges(&self, _tenant: &TenantId, _node: Node, edges: Vec<EdgeSpec>) -> Result<NodeWithEdges, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(NodeWithEdges {
+                node_id: Uuid::new_v4(),
+                edge_ids: edges.iter().map(|_| Uuid::new_v4()).collect(),
+            })
+        }
+        
         async fn query(&self, _tenant: &TenantId, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
             self.call_count.fetch_add(1, Ordering::Relaxed);
             Ok(Vec::new())
         }
         
+        async fn resolve_aliases(&self, _tenant: &TenantId, _aliases: &[AliasKey]) -> Result<HashMap<AliasKey, Uuid>, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(HashMap::new())
+        }
+        
+        async fn snapshot(&self, _tenant: &TenantId, valid_at: Option<DateTime<Utc>>) -> Result<GraphSnapshot, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(GraphSnapshot {
+                snapshot_at: Utc::now(),
+                valid_at,
+                nodes: Vec::new(),
+                edges: Vec::new(),
+            })
+        }
+        
+        async fn materialize_snapshot(&self, _tenant: &TenantId, name: &str, valid_at: DateTime<Utc>) -> Result<SnapshotInfo, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(SnapshotInfo {
+                name: name.to_string(),
+                valid_at,
+                snapshot_at: Utc::now(),
+                node_count: 0,
+                edge_count: 0,
+            })
+        }
+        
+        async fn list_snapshots(&self, _tenant: &TenantId) -> Result<Vec<SnapshotInfo>, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(Vec::new())
+        }
+        
+        async fn drop_snapshot(&self, _tenant: &TenantId, _name: &str) -> Result<bool, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(true)
+        }
+        
+        async fn query_snapshot(&self, _tenant: &TenantId, _name: &str, _query: GraphQuery) -> Result<Vec<Path>, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(Vec::new())
+        }
+        
+        async fn summary(&self, _tenant: &TenantId) -> Result<GraphSummary, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(GraphSummary::default())
+        }
+        
+        async fn catalog(&self, _tenant: &TenantId) -> Result<GraphCatalog, GraphError> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            Ok(GraphCatalog::default())
+        }
+        
         async fn extract_knowledge(&self, _tenant: &TenantId, _context: ExtractionContext) -> Result<ExtractionEnvelope, LlmError> {
             self.call_count.fetch_add(1, Ordering::Relaxed);
             Ok(ExtractionEnvelope {
@@ -0,0 +1,411 @@
+//! Shared-memory data plane for large UDS messages
+//!
+//! Clients on the same host can negotiate shared memory on a connection with
+//! [`Request::NegotiateSharedMemory`](crate::protocol::Request). From then on,
+//! either side sends a message of at least `min_segment_bytes` as a sealed
+//! memfd segment instead of through the socket: the frame only carries the
+//! segment's length, and the segment's descriptor is passed alongside it
+//! with `SCM_RIGHTS`. The receiver maps the segment read-only and decodes the
+//! message straight from the mapping. Smaller messages, and all messages on
+//! connections that did not negotiate, use the framed codec as before.
+//!
+//! Segments are sealed against writes and resizing before they are sent, and
+//! unsealed segments are refused, so a peer can neither change a message
+//! while it is decoded nor truncate the mapping under the reader.
+
+use std::collections::VecDeque;
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, Interest, ReadBuf};
+use tokio::net::UnixStream;
+
+/// Descriptors sent or accepted with one `sendmsg` or `recvmsg`
+const MAX_DESCRIPTORS_PER_MESSAGE: usize = 16;
+
+/// Descriptors received ahead of their frames before the connection is
+/// considered misbehaving
+const MAX_PENDING_DESCRIPTORS: usize = 64;
+
+/// Shared-memory settings of the UDS adapter
+#[derive(Debug, Clone)]
+pub struct SharedMemoryConfig {
+    /// Accept clients' requests to negotiate shared memory
+    pub enabled: bool,
+    /// Messages smaller than this are sent through the socket
+    pub min_segment_bytes: usize,
+    /// Largest message sent as a segment. Unlike `max_message_size`, this
+    /// bounds memory mapped rather than read from the socket.
+    pub max_segment_bytes: usize,
+}
+
+impl Default for SharedMemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_segment_bytes: 64 * 1024, // 64 KiB
+            max_segment_bytes: 256 * 1024 * 1024, // 256 MiB
+        }
+    }
+}
+
+/// Whether this platform can send messages as shared-memory segments
+pub fn supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+/// Descriptors passed with the frames of one connection, shared by its
+/// stream and codec
+#[derive(Debug, Clone, Default)]
+pub struct Descriptors {
+    queues: Arc<Mutex<DescriptorQueues>>,
+}
+
+#[derive(Debug, Default)]
+struct DescriptorQueues {
+    /// Received, in order, for the codec to take as it decodes frames
+    received: VecDeque<OwnedFd>,
+    /// Queued by the codec, sent with the next bytes written
+    outgoing: VecDeque<OwnedFd>,
+}
+
+impl Descriptors {
+    /// Send a descriptor with the next bytes written to the connection
+    pub fn send(&self, fd: OwnedFd) {
+        self.queues.lock().unwrap().outgoing.push_back(fd);
+    }
+
+    /// The oldest received descriptor not yet taken
+    pub fn take(&self) -> Option<OwnedFd> {
+        self.queues.lock().unwrap().received.pop_front()
+    }
+}
+
+/// Agreed use of shared memory on a connection
+#[derive(Debug, Clone)]
+pub struct SegmentTransfer {
+    pub descriptors: Descriptors,
+    pub min_segment_bytes: usize,
+    pub max_segment_bytes: usize,
+}
+
+impl SegmentTransfer {
+    /// Whether a message of this size goes as a segment
+    pub fn sends(&self, len: usize) -> bool {
+        len >= self.min_segment_bytes && len <= self.max_segment_bytes
+    }
+}
+
+/// Unix stream that passes descriptors along with the bytes it carries.
+/// Received descriptors are queued in [`Descriptors`] in the order they
+/// arrive; those queued for sending go with the next write, so a descriptor
+/// always arrives no later than the frame that refers to it.
+pub struct SegmentStream {
+    stream: UnixStream,
+    descriptors: Descriptors,
+}
+
+impl SegmentStream {
+    pub fn new(stream: UnixStream) -> Self {
+        Self { stream, descriptors: Descriptors::default() }
+    }
+
+    /// Descriptors of this connection, for its codec
+    pub fn descriptors(&self) -> Descriptors {
+        self.descriptors.clone()
+    }
+
+    pub fn get_ref(&self) -> &UnixStream {
+        &self.stream
+    }
+}
+
+impl AsyncRead for SegmentStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.stream.poll_read_ready(cx))?;
+            let fd = this.stream.as_raw_fd();
+            let unfilled = buf.initialize_unfilled();
+            let mut fds = Vec::new();
+            match this.stream.try_io(Interest::READABLE, || recv_with_descriptors(fd, unfilled, &mut fds)) {
+                Ok(read) => {
+                    buf.advance(read);
+                    if !fds.is_empty() {
+                        let mut queues = this.descriptors.queues.lock().unwrap();
+                        queues.received.extend(fds);
+                        if queues.received.len() > MAX_PENDING_DESCRIPTORS {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("More than {} descriptors received without frames", MAX_PENDING_DESCRIPTORS),
+                            )));
+                        }
+                    }
+                    return Poll::Ready(Ok(()));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
+
+impl AsyncWrite for SegmentStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.stream.poll_write_ready(cx))?;
+            let fd = this.stream.as_raw_fd();
+            let mut queues = this.descriptors.queues.lock().unwrap();
+            let fds: Vec<RawFd> = queues.outgoing.iter()
+                .take(MAX_DESCRIPTORS_PER_MESSAGE)
+                .map(|fd| fd.as_raw_fd())
+                .collect();
+            match this.stream.try_io(Interest::WRITABLE, || send_with_descriptors(fd, data, &fds)) {
+                Ok(written) => {
+                    // The peer holds its own copies now
+                    queues.outgoing.drain(..fds.len());
+                    return Poll::Ready(Ok(written));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+/// Control message buffer for `MAX_DESCRIPTORS_PER_MESSAGE` descriptors,
+/// aligned for `cmsghdr`
+#[repr(C, align(8))]
+struct ControlBuffer([u8; 128]);
+
+fn control_space(fds: usize) -> usize {
+    // SAFETY: CMSG_SPACE only computes a size
+    unsafe { libc::CMSG_SPACE((fds * std::mem::size_of::<RawFd>()) as u32) as usize }
+}
+
+/// `sendmsg` with `fds` attached as `SCM_RIGHTS`
+fn send_with_descriptors(socket: RawFd, data: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+    let mut iov = libc::iovec { iov_base: data.as_ptr() as *mut _, iov_len: data.len() };
+    let mut control = ControlBuffer([0; 128]);
+    // SAFETY: the message header points at `iov` and `control`, which
+    // outlive the call, and the control buffer has room for the descriptors
+    unsafe {
+        let mut message: libc::msghdr = std::mem::zeroed();
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        if !fds.is_empty() {
+            let space = control_space(fds.len());
+            debug_assert!(space <= control.0.len());
+            message.msg_control = control.0.as_mut_ptr() as *mut _;
+            message.msg_controllen = space as _;
+            let header = libc::CMSG_FIRSTHDR(&message);
+            (*header).cmsg_level = libc::SOL_SOCKET;
+            (*header).cmsg_type = libc::SCM_RIGHTS;
+            (*header).cmsg_len = libc::CMSG_LEN(std::mem::size_of_val(fds) as u32) as _;
+            std::ptr::copy_nonoverlapping(fds.as_ptr(), libc::CMSG_DATA(header) as *mut RawFd, fds.len());
+        }
+        let sent = libc::sendmsg(socket, &message, libc::MSG_NOSIGNAL);
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(sent as usize)
+    }
+}
+
+/// `recvmsg`, collecting descriptors passed as `SCM_RIGHTS` into `fds`
+fn recv_with_descriptors(socket: RawFd, data: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+    use std::os::fd::FromRawFd;
+
+    let mut iov = libc::iovec { iov_base: data.as_mut_ptr() as *mut _, iov_len: data.len() };
+    let mut control = ControlBuffer([0; 128]);
+    // SAFETY: the message header points at `iov` and `control`, which
+    // outlive the call; descriptors are read from control messages the
+    // kernel wrote, and each is owned by us once received
+    unsafe {
+        let mut message: libc::msghdr = std::mem::zeroed();
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.0.as_mut_ptr() as *mut _;
+        message.msg_controllen = control_space(MAX_DESCRIPTORS_PER_MESSAGE) as _;
+        #[cfg(target_os = "linux")]
+        let flags = libc::MSG_CMSG_CLOEXEC;
+        #[cfg(not(target_os = "linux"))]
+        let flags = 0;
+        let received = libc::recvmsg(socket, &mut message, flags);
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut header = libc::CMSG_FIRSTHDR(&message);
+        while !header.is_null() {
+            if (*header).cmsg_level == libc::SOL_SOCKET && (*header).cmsg_type == libc::SCM_RIGHTS {
+                let len = (*header).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                let data = libc::CMSG_DATA(header) as *const RawFd;
+                for i in 0..len / std::mem::size_of::<RawFd>() {
+                    fds.push(OwnedFd::from_raw_fd(std::ptr::read_unaligned(data.add(i))));
+                }
+            }
+            header = libc::CMSG_NXTHDR(&message, header);
+        }
+        if message.msg_flags & libc::MSG_CTRUNC != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Peer passed more descriptors than accepted"));
+        }
+        Ok(received as usize)
+    }
+}
+
+/// Write a message to a new sealed segment
+#[cfg(target_os = "linux")]
+pub fn write_segment(bytes: &[u8]) -> io::Result<OwnedFd> {
+    use std::io::Write;
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: the name is a NUL-terminated string; the returned descriptor
+    // is checked and then owned
+    let fd = unsafe {
+        let fd = libc::memfd_create(c"telamentis-segment".as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        OwnedFd::from_raw_fd(fd)
+    };
+    let mut file = std::fs::File::from(fd);
+    file.write_all(bytes)?;
+
+    let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+    // SAFETY: fcntl on a descriptor we own
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_ADD_SEALS, seals) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(file.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn write_segment(_bytes: &[u8]) -> io::Result<OwnedFd> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Shared-memory segments need memfd"))
+}
+
+/// A received segment, mapped read-only
+pub struct Segment {
+    address: *mut libc::c_void,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only and sealed, so it can be read from any
+// thread
+unsafe impl Send for Segment {}
+unsafe impl Sync for Segment {}
+
+impl Segment {
+    /// Map the first `len` bytes of a received segment, which must be
+    /// sealed against writes and shrinking
+    #[cfg(target_os = "linux")]
+    pub fn map(fd: OwnedFd, len: usize) -> io::Result<Self> {
+        if len == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Empty segment"));
+        }
+        let required = libc::F_SEAL_SHRINK | libc::F_SEAL_WRITE;
+        // SAFETY: fcntl and fstat on a descriptor we own, into a zeroed stat
+        let size = unsafe {
+            let seals = libc::fcntl(fd.as_raw_fd(), libc::F_GET_SEALS);
+            if seals < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if seals & required != required {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Segment is not sealed"));
+            }
+            let mut stat: libc::stat = std::mem::zeroed();
+            if libc::fstat(fd.as_raw_fd(), &mut stat) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            stat.st_size as usize
+        };
+        if size < len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Segment has {} bytes, frame announced {}", size, len),
+            ));
+        }
+
+        // SAFETY: a read-only shared mapping of a sealed segment at least
+        // `len` bytes long, unmapped on drop; the mapping outlives `fd`
+        let address = unsafe {
+            libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_SHARED, fd.as_raw_fd(), 0)
+        };
+        if address == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { address, len })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn map(_fd: OwnedFd, _len: usize) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Shared-memory segments need memfd"))
+    }
+}
+
+impl std::ops::Deref for Segment {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: the mapping is `len` readable bytes until dropped, and
+        // sealed against changes
+        unsafe { std::slice::from_raw_parts(self.address as *const u8, self.len) }
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        // SAFETY: unmaps exactly the mapping made in `map`
+        unsafe {
+            libc::munmap(self.address, self.len);
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_segment_round_trip() {
+        let fd = write_segment(b"{\"HealthCheck\":null}").unwrap();
+        let segment = Segment::map(fd, 20).unwrap();
+        assert_eq!(&segment[..], b"{\"HealthCheck\":null}");
+
+        let fd = write_segment(b"short").unwrap();
+        assert!(Segment::map(fd, 100).is_err());
+
+        // A segment its sender can still change is refused
+        let unsealed: OwnedFd = tempfile::tempfile().unwrap().into();
+        assert!(Segment::map(unsealed, 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_descriptors_arrive_with_bytes() {
+        let (left, right) = UnixStream::pair().unwrap();
+        let (mut sender, mut receiver) = (SegmentStream::new(left), SegmentStream::new(right));
+
+        sender.descriptors().send(write_segment(b"payload").unwrap());
+        sender.write_all(b"frame").await.unwrap();
+        let mut frame = [0u8; 5];
+        receiver.read_exact(&mut frame).await.unwrap();
+        assert_eq!(&frame, b"frame");
+
+        let segment = Segment::map(receiver.descriptors().take().unwrap(), 7).unwrap();
+        assert_eq!(&segment[..], b"payload");
+        assert!(receiver.descriptors().take().is_none());
+    }
+}